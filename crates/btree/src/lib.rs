@@ -2,6 +2,15 @@
 //!
 //! This crate provides a page-based B+Tree structure that integrates with
 //! the database's buffer pool for efficient key-based lookups.
+//!
+//! Nodes can be compressed as they are written (see
+//! [`BTreeIndex::with_compression`] and [`common::compression`]); a
+//! compressed node is decompressed when read whatever the setting.
+//!
+//! The index of an encrypted database (see [`BTreeIndex::open_with_key`])
+//! seals every node with the database key, bound to the index and page, and
+//! stores it as `ENCRYPTED_MAGIC || sealed length || sealed node` at the
+//! start of its page. Encrypted nodes are not compressed.
 
 mod node;
 mod page;
@@ -16,6 +25,8 @@ use std::path::Path;
 use bincode::config::{self, Config};
 use bincode::serde::{decode_from_slice, encode_to_vec};
 use catalog::IndexId;
+use common::compression::{self, Compression};
+use common::crypto::{EncryptionKey, SEAL_OVERHEAD};
 use common::{DbError, DbResult, PageId, RecordId};
 use storage::PAGE_SIZE;
 use types::Value;
@@ -24,6 +35,13 @@ fn bincode_config() -> impl Config {
     config::legacy()
 }
 
+/// Marks the start of an encrypted node. Read as a node's variant tag it
+/// names no variant.
+const ENCRYPTED_MAGIC: [u8; 4] = [0xFF, 0xFF, b'E', b'N'];
+
+/// Bytes of an encrypted page before the sealed node: magic and length.
+const ENCRYPTED_HEADER: usize = ENCRYPTED_MAGIC.len() + 4;
+
 /// A persistent B+Tree index that stores key-value pairs on disk.
 ///
 /// Keys are `Vec<Value>` (supporting composite keys) and values are `RecordId`
@@ -38,11 +56,25 @@ pub struct BTreeIndex {
     file: File,
     /// Number of pages currently allocated
     num_pages: u64,
+    /// How nodes are compressed when written
+    compression: Compression,
+    /// Key sealing every node, if the database is encrypted
+    key: Option<EncryptionKey>,
 }
 
 impl BTreeIndex {
     /// Create a new B+Tree index file at the given path.
     pub fn create(path: &Path, index_id: IndexId) -> DbResult<Self> {
+        Self::create_with_key(path, index_id, None)
+    }
+
+    /// Create a new B+Tree index file at the given path whose nodes are
+    /// encrypted with `key`, if given.
+    pub fn create_with_key(
+        path: &Path,
+        index_id: IndexId,
+        key: Option<&EncryptionKey>,
+    ) -> DbResult<Self> {
         let file = OpenOptions::new()
             .read(true)
             .write(true)
@@ -55,6 +87,8 @@ impl BTreeIndex {
             root_page_id: PageId(0),
             file,
             num_pages: 0,
+            compression: Compression::None,
+            key: key.cloned(),
         };
 
         // Allocate the root page as an empty leaf
@@ -68,6 +102,17 @@ impl BTreeIndex {
 
     /// Open an existing B+Tree index file.
     pub fn open(path: &Path, index_id: IndexId) -> DbResult<Self> {
+        Self::open_with_key(path, index_id, None)
+    }
+
+    /// Open an existing B+Tree index file of a database encrypted with
+    /// `key`, if given. Reading a node fails if its encryption does not
+    /// match `key`.
+    pub fn open_with_key(
+        path: &Path,
+        index_id: IndexId,
+        key: Option<&EncryptionKey>,
+    ) -> DbResult<Self> {
         if !path.exists() {
             return Err(DbError::Storage(format!(
                 "index file does not exist: {}",
@@ -94,26 +139,112 @@ impl BTreeIndex {
             root_page_id: PageId(0),
             file,
             num_pages,
+            compression: Compression::None,
+            key: key.cloned(),
         })
     }
 
+    /// Compress nodes with `compression` as they are written, unless they are
+    /// encrypted. Nodes already in the file are read whether or not they
+    /// were compressed.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Create a B+Tree index file at the given path holding `entries`, which
+    /// must be sorted by key.
+    ///
+    /// The tree is built bottom-up from full leaves, which is much faster
+    /// than inserting the entries one by one. Runs of equal keys are kept in
+    /// one leaf where they fit.
+    pub fn bulk_load(
+        path: &Path,
+        index_id: IndexId,
+        entries: Vec<(Vec<Value>, RecordId)>,
+    ) -> DbResult<Self> {
+        Self::bulk_load_with(path, index_id, entries, Compression::None, None)
+    }
+
+    /// [`BTreeIndex::bulk_load`], writing nodes compressed with
+    /// `compression` or encrypted with `key`.
+    pub fn bulk_load_with(
+        path: &Path,
+        index_id: IndexId,
+        entries: Vec<(Vec<Value>, RecordId)>,
+        compression: Compression,
+        key: Option<&EncryptionKey>,
+    ) -> DbResult<Self> {
+        if entries.windows(2).any(|pair| pair[0].0 > pair[1].0) {
+            return Err(DbError::Storage(
+                "bulk load entries must be sorted by key".into(),
+            ));
+        }
+
+        let mut index = Self::create_with_key(path, index_id, key)?.with_compression(compression);
+        if entries.len() <= Self::max_leaf_entries() {
+            let root = BTreeNode::Leaf {
+                entries,
+                next_leaf: None,
+            };
+            index.write_node(index.root_page_id, &root)?;
+            return Ok(index);
+        }
+
+        // Lowest level first: (first key, page) of every node on the level
+        let leaves = Self::leaf_groups(entries);
+        let pages = (0..leaves.len())
+            .map(|_| index.allocate_page())
+            .collect::<DbResult<Vec<_>>>()?;
+        let mut level = Vec::with_capacity(leaves.len());
+        for (i, entries) in leaves.into_iter().enumerate() {
+            let first_key = entries[0].0.clone();
+            let leaf = BTreeNode::Leaf {
+                entries,
+                next_leaf: pages.get(i + 1).copied(),
+            };
+            index.write_node(pages[i], &leaf)?;
+            level.push((first_key, pages[i]));
+        }
+
+        let max_children = Self::max_internal_keys() + 1;
+        while level.len() > max_children {
+            let mut parents = Vec::with_capacity(level.len().div_ceil(max_children));
+            for group in level.chunks(max_children) {
+                let page = index.allocate_page()?;
+                index.write_node(page, &Self::internal_over(group))?;
+                parents.push((group[0].0.clone(), page));
+            }
+            level = parents;
+        }
+        index.write_node(index.root_page_id, &Self::internal_over(&level))?;
+        Ok(index)
+    }
+
     /// Search for all RecordIds matching the given key.
     pub fn search(&mut self, key: &[Value]) -> DbResult<Vec<RecordId>> {
-        let leaf_page_id = self.find_leaf(key)?;
-        let leaf = self.read_node(leaf_page_id)?;
-
-        match leaf {
-            BTreeNode::Leaf { entries, .. } => {
-                let mut results = Vec::new();
-                for (k, rid) in &entries {
-                    if k == key {
-                        results.push(*rid);
+        let mut leaf_page_id = self.find_leaf(key)?;
+        let mut results = Vec::new();
+
+        loop {
+            match self.read_node(leaf_page_id)? {
+                BTreeNode::Leaf { entries, next_leaf } => {
+                    for (k, rid) in &entries {
+                        if k.as_slice() > key {
+                            return Ok(results);
+                        }
+                        if k == key {
+                            results.push(*rid);
+                        }
+                    }
+                    match next_leaf {
+                        Some(next) => leaf_page_id = next,
+                        None => return Ok(results),
                     }
                 }
-                Ok(results)
-            }
-            BTreeNode::Internal { .. } => {
-                Err(DbError::Storage("find_leaf returned non-leaf node".into()))
+                BTreeNode::Internal { .. } => {
+                    return Err(DbError::Storage("find_leaf returned non-leaf node".into()));
+                }
             }
         }
     }
@@ -171,14 +302,16 @@ impl BTreeIndex {
         let result = self.insert_recursive(self.root_page_id, key, rid)?;
 
         if let Some((new_key, new_child_page)) = result {
-            // Root was split, create new root
-            let new_root_page = self.allocate_page()?;
+            // Root was split: move its left half to a new page so the new
+            // root stays at page 0, where `open` expects it
+            let left = self.read_node(self.root_page_id)?;
+            let left_page = self.allocate_page()?;
+            self.write_node(left_page, &left)?;
             let new_root = BTreeNode::Internal {
                 keys: vec![new_key],
-                children: vec![self.root_page_id, new_child_page],
+                children: vec![left_page, new_child_page],
             };
-            self.write_node(new_root_page, &new_root)?;
-            self.root_page_id = new_root_page;
+            self.write_node(self.root_page_id, &new_root)?;
         }
 
         Ok(())
@@ -186,23 +319,26 @@ impl BTreeIndex {
 
     /// Delete a key-value pair from the index.
     pub fn delete(&mut self, key: &[Value], rid: RecordId) -> DbResult<bool> {
-        let leaf_page_id = self.find_leaf(key)?;
-        let mut leaf = self.read_node(leaf_page_id)?;
-
-        match &mut leaf {
-            BTreeNode::Leaf { entries, .. } => {
-                let original_len = entries.len();
-                entries.retain(|(k, r)| !(k == key && r == &rid));
-                let deleted = entries.len() < original_len;
+        let mut leaf_page_id = self.find_leaf(key)?;
 
-                if deleted {
-                    self.write_node(leaf_page_id, &leaf)?;
+        loop {
+            let mut leaf = self.read_node(leaf_page_id)?;
+            match &mut leaf {
+                BTreeNode::Leaf { entries, next_leaf } => {
+                    if let Some(pos) = entries.iter().position(|(k, r)| k == key && r == &rid) {
+                        entries.remove(pos);
+                        self.write_node(leaf_page_id, &leaf)?;
+                        return Ok(true);
+                    }
+                    let past_key = entries.last().is_some_and(|(k, _)| k.as_slice() > key);
+                    match next_leaf {
+                        Some(next) if !past_key => leaf_page_id = *next,
+                        _ => return Ok(false),
+                    }
+                }
+                BTreeNode::Internal { .. } => {
+                    return Err(DbError::Storage("find_leaf returned non-leaf node".into()));
                 }
-
-                Ok(deleted)
-            }
-            BTreeNode::Internal { .. } => {
-                Err(DbError::Storage("find_leaf returned non-leaf node".into()))
             }
         }
     }
@@ -256,22 +392,20 @@ impl BTreeIndex {
 
     // ---- Private helpers ----
 
-    /// Find the leaf page that should contain the given key.
+    /// Find the leftmost leaf that may contain the given key.
+    ///
+    /// Separators equal to the key lead left, because a run of equal keys
+    /// may begin in the left subtree and continue through later leaves.
     fn find_leaf(&mut self, key: &[Value]) -> DbResult<PageId> {
         let mut current = self.root_page_id;
 
         loop {
-            let node = self.read_node(current)?;
-
-            match node {
+            match self.read_node(current)? {
                 BTreeNode::Internal { keys, children } => {
-                    // Binary search for the child to follow
-                    let idx = keys.partition_point(|k| k.as_slice() <= key);
+                    let idx = keys.partition_point(|k| k.as_slice() < key);
                     current = children[idx];
                 }
-                BTreeNode::Leaf { .. } => {
-                    return Ok(current);
-                }
+                BTreeNode::Leaf { .. } => return Ok(current),
             }
         }
     }
@@ -412,6 +546,36 @@ impl BTreeIndex {
         Ok((left, split_key, right))
     }
 
+    /// Split sorted entries into full leaves, ending a leaf early rather than
+    /// splitting a run of equal keys that fits in one leaf.
+    fn leaf_groups(entries: Vec<(Vec<Value>, RecordId)>) -> Vec<Vec<(Vec<Value>, RecordId)>> {
+        let max = Self::max_leaf_entries();
+        let mut groups = Vec::new();
+        let mut rest = entries;
+        while rest.len() > max {
+            let mut cut = max;
+            if rest[cut].0 == rest[cut - 1].0 {
+                let run_start = rest[..cut].partition_point(|(k, _)| *k < rest[cut].0);
+                if run_start > 0 {
+                    cut = run_start;
+                }
+            }
+            let tail = rest.split_off(cut);
+            groups.push(rest);
+            rest = tail;
+        }
+        groups.push(rest);
+        groups
+    }
+
+    /// Internal node over `children`, given as (first key, page) pairs.
+    fn internal_over(children: &[(Vec<Value>, PageId)]) -> BTreeNode {
+        BTreeNode::Internal {
+            keys: children[1..].iter().map(|(key, _)| key.clone()).collect(),
+            children: children.iter().map(|(_, page)| *page).collect(),
+        }
+    }
+
     fn max_leaf_entries() -> usize {
         // Conservative estimate: ~100 entries per leaf
         // Each entry is roughly key + RecordId
@@ -435,12 +599,48 @@ impl BTreeIndex {
         Ok(page_id)
     }
 
+    /// Associated data binding a sealed node to its index and page.
+    fn aad(&self, page_id: PageId) -> [u8; 16] {
+        let mut aad = [0u8; 16];
+        aad[..8].copy_from_slice(&self.index_id.0.to_le_bytes());
+        aad[8..].copy_from_slice(&page_id.0.to_le_bytes());
+        aad
+    }
+
     fn read_node(&mut self, page_id: PageId) -> DbResult<BTreeNode> {
         let offset = page_id.0 * PAGE_SIZE as u64;
         self.file.seek(SeekFrom::Start(offset))?;
 
         let mut buffer = vec![0u8; PAGE_SIZE];
         self.file.read_exact(&mut buffer)?;
+        let encrypted = buffer.starts_with(&ENCRYPTED_MAGIC);
+        let buffer = match &self.key {
+            Some(key) if encrypted => {
+                let len = u32::from_le_bytes(
+                    buffer[ENCRYPTED_MAGIC.len()..ENCRYPTED_HEADER]
+                        .try_into()
+                        .expect("four bytes"),
+                ) as usize;
+                let Some(sealed) = buffer.get(ENCRYPTED_HEADER..ENCRYPTED_HEADER + len) else {
+                    return Err(DbError::Corruption(format!(
+                        "encrypted btree node {} claims {len} bytes, more than its page holds",
+                        page_id.0
+                    )));
+                };
+                key.open(&self.aad(page_id), sealed)?
+            }
+            Some(_) => {
+                return Err(DbError::Storage(
+                    "index is not encrypted but an encryption key was given".into(),
+                ));
+            }
+            None if encrypted => {
+                return Err(DbError::Storage(
+                    "index is encrypted but no encryption key was given".into(),
+                ));
+            }
+            None => compression::decompress_page(buffer)?,
+        };
 
         let (node, _): (BTreeNode, usize) = decode_from_slice(&buffer, bincode_config())
             .map_err(|e| DbError::Storage(format!("failed to decode btree node: {e}")))?;
@@ -452,20 +652,38 @@ impl BTreeIndex {
         let bytes = encode_to_vec(node, bincode_config())
             .map_err(|e| DbError::Storage(format!("failed to encode btree node: {e}")))?;
 
-        if bytes.len() > PAGE_SIZE {
+        let capacity = match self.key {
+            Some(_) => PAGE_SIZE - ENCRYPTED_HEADER - SEAL_OVERHEAD,
+            None => PAGE_SIZE,
+        };
+        if bytes.len() > capacity {
             return Err(DbError::Storage(format!(
                 "btree node too large: {} bytes (max {})",
                 bytes.len(),
-                PAGE_SIZE
+                capacity
             )));
         }
 
         let mut buffer = vec![0u8; PAGE_SIZE];
-        buffer[..bytes.len()].copy_from_slice(&bytes);
-
+        let frame = match &self.key {
+            Some(key) => {
+                let sealed = key.seal(&self.aad(page_id), &bytes)?;
+                buffer[..ENCRYPTED_MAGIC.len()].copy_from_slice(&ENCRYPTED_MAGIC);
+                buffer[ENCRYPTED_MAGIC.len()..ENCRYPTED_HEADER]
+                    .copy_from_slice(&(sealed.len() as u32).to_le_bytes());
+                buffer[ENCRYPTED_HEADER..ENCRYPTED_HEADER + sealed.len()].copy_from_slice(&sealed);
+                None
+            }
+            None => {
+                buffer[..bytes.len()].copy_from_slice(&bytes);
+                // Pages are allocated whole, so a compressed node leaves the
+                // rest of its page as it was
+                compression::compress_page(self.compression, &buffer)?
+            }
+        };
         let offset = page_id.0 * PAGE_SIZE as u64;
         self.file.seek(SeekFrom::Start(offset))?;
-        self.file.write_all(&buffer)?;
+        self.file.write_all(frame.as_deref().unwrap_or(&buffer))?;
 
         Ok(())
     }
@@ -296,3 +296,218 @@ fn many_inserts_trigger_splits() {
     let all = index.scan_all().unwrap();
     assert_eq!(all.len(), count as usize);
 }
+
+#[test]
+fn splits_survive_reopen() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("test.idx");
+
+    let count = 500;
+    {
+        let mut index = BTreeIndex::create(&path, IndexId(1)).unwrap();
+        for i in 0..count {
+            let rid = RecordId {
+                page_id: PageId(i / 100),
+                slot: (i % 100) as u16,
+            };
+            index.insert(vec![Value::Int(i as i64)], rid).unwrap();
+        }
+        index.flush().unwrap();
+    }
+
+    // Root splits keep the root at page 0, where open looks for it
+    let mut index = BTreeIndex::open(&path, IndexId(1)).unwrap();
+    for i in 0..count {
+        let results = index.search(&[Value::Int(i as i64)]).unwrap();
+        assert_eq!(results.len(), 1, "key {} not found", i);
+    }
+    assert_eq!(index.scan_all().unwrap().len(), count as usize);
+}
+
+#[test]
+fn bulk_load_builds_a_searchable_tree() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("test.idx");
+
+    // Enough entries for three levels, with a run of equal keys that
+    // straddles a full leaf
+    let entries: Vec<_> = (0..30_000u64)
+        .map(|i| {
+            let key = if (150..180).contains(&i) { 150 } else { i };
+            (
+                vec![Value::Int(key as i64)],
+                RecordId {
+                    page_id: PageId(i / 100),
+                    slot: (i % 100) as u16,
+                },
+            )
+        })
+        .collect();
+    let mut index = BTreeIndex::bulk_load(&path, IndexId(1), entries.clone()).unwrap();
+
+    assert_eq!(index.search(&[Value::Int(150)]).unwrap().len(), 30);
+    assert_eq!(index.search(&[Value::Int(29_999)]).unwrap().len(), 1);
+    assert_eq!(
+        index
+            .range_scan(Some(&[Value::Int(1_000)]), Some(&[Value::Int(1_009)]))
+            .unwrap()
+            .len(),
+        10
+    );
+
+    // Inserts after a bulk load still split correctly, and the tree reopens
+    index
+        .insert(
+            vec![Value::Int(-1)],
+            RecordId {
+                page_id: PageId(999),
+                slot: 0,
+            },
+        )
+        .unwrap();
+    drop(index);
+    let mut index = BTreeIndex::open(&path, IndexId(1)).unwrap();
+    let all = index.scan_all().unwrap();
+    assert_eq!(all.len(), entries.len() + 1);
+    assert_eq!(all[0].0, vec![Value::Int(-1)]);
+    assert_eq!(&all[1..], entries.as_slice());
+}
+
+#[test]
+fn bulk_load_small_and_unsorted_inputs() {
+    let dir = tempdir().unwrap();
+    let rid = RecordId {
+        page_id: PageId(0),
+        slot: 0,
+    };
+
+    let mut index =
+        BTreeIndex::bulk_load(&dir.path().join("empty.idx"), IndexId(1), vec![]).unwrap();
+    assert!(index.scan_all().unwrap().is_empty());
+
+    let mut index = BTreeIndex::bulk_load(
+        &dir.path().join("one.idx"),
+        IndexId(1),
+        vec![(vec![Value::Text("a".into())], rid)],
+    )
+    .unwrap();
+    assert_eq!(index.search(&[Value::Text("a".into())]).unwrap(), vec![rid]);
+
+    let err = BTreeIndex::bulk_load(
+        &dir.path().join("unsorted.idx"),
+        IndexId(1),
+        vec![(vec![Value::Int(2)], rid), (vec![Value::Int(1)], rid)],
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("sorted"));
+}
+
+#[test]
+fn compressed_nodes_survive_reopen() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("test.idx");
+
+    let entries: Vec<_> = (0..2_000u64)
+        .map(|i| {
+            (
+                vec![Value::Text(format!("c{i:05}"))],
+                RecordId {
+                    page_id: PageId(i / 100),
+                    slot: (i % 100) as u16,
+                },
+            )
+        })
+        .collect();
+    let index =
+        BTreeIndex::bulk_load_with(&path, IndexId(1), entries.clone(), Compression::Lz4, None)
+            .unwrap();
+    drop(index);
+    let bytes = std::fs::read(&path).unwrap();
+    assert!(compression::is_compressed(&bytes[..PAGE_SIZE]));
+
+    // Nodes written afterwards may be compressed or not; both read back
+    let rid = RecordId {
+        page_id: PageId(999),
+        slot: 0,
+    };
+    let mut index = BTreeIndex::open(&path, IndexId(1)).unwrap();
+    index.insert(vec![Value::Text("a".into())], rid).unwrap();
+    drop(index);
+    let mut index = BTreeIndex::open(&path, IndexId(1))
+        .unwrap()
+        .with_compression(Compression::Zstd);
+    index.insert(vec![Value::Text("z".into())], rid).unwrap();
+
+    let all = index.scan_all().unwrap();
+    assert_eq!(all.len(), entries.len() + 2);
+    assert_eq!(&all[1..=entries.len()], entries.as_slice());
+    assert_eq!(
+        index.search(&[Value::Text("c01234".into())]).unwrap().len(),
+        1
+    );
+}
+
+#[test]
+fn encrypted_nodes_need_the_key() {
+    use common::crypto::{self, EncryptionKey};
+
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("test.idx");
+    let key = EncryptionKey::from_bytes(&[5u8; crypto::KEY_LEN]).unwrap();
+
+    let entries: Vec<_> = (0..1_000u64)
+        .map(|i| {
+            (
+                vec![Value::Text(format!("secret{i:04}"))],
+                RecordId {
+                    page_id: PageId(i / 100),
+                    slot: (i % 100) as u16,
+                },
+            )
+        })
+        .collect();
+    let index = BTreeIndex::bulk_load_with(
+        &path,
+        IndexId(7),
+        entries.clone(),
+        Compression::Zstd,
+        Some(&key),
+    )
+    .unwrap();
+    drop(index);
+    let bytes = std::fs::read(&path).unwrap();
+    assert!(!bytes.windows(6).any(|w| w == b"secret"));
+    let node = &bytes[ENCRYPTED_HEADER..];
+    assert_eq!(crypto::sealed_key_id(node), Some(key.id()));
+
+    let mut index = BTreeIndex::open_with_key(&path, IndexId(7), Some(&key)).unwrap();
+    let rid = RecordId {
+        page_id: PageId(999),
+        slot: 0,
+    };
+    index
+        .insert(vec![Value::Text("secret9999".into())], rid)
+        .unwrap();
+    assert_eq!(index.scan_all().unwrap().len(), entries.len() + 1);
+    assert_eq!(
+        index.search(&[Value::Text("secret0123".into())]).unwrap(),
+        vec![entries[123].1]
+    );
+
+    // Without the key, or with another one, nodes cannot be read
+    let mut index = BTreeIndex::open(&path, IndexId(7)).unwrap();
+    assert!(index.search(&[Value::Text("secret0001".into())]).is_err());
+    let other = EncryptionKey::from_bytes(&[6u8; crypto::KEY_LEN]).unwrap();
+    let mut index = BTreeIndex::open_with_key(&path, IndexId(7), Some(&other)).unwrap();
+    assert!(index.search(&[Value::Text("secret0001".into())]).is_err());
+    // A rotated key still reads nodes sealed with the key it replaced
+    let rotated = other.with_previous(&key);
+    let mut index = BTreeIndex::open_with_key(&path, IndexId(7), Some(&rotated)).unwrap();
+    assert_eq!(
+        index
+            .search(&[Value::Text("secret0001".into())])
+            .unwrap()
+            .len(),
+        1
+    );
+}
@@ -1,9 +1,83 @@
 //! Buffer pool manager for page-level caching and I/O.
 //!
 //! The buffer pool sits between the storage layer and the executor, providing:
-//! - LRU-based in-memory page cache
+//! - In-memory page cache with a choice of replacement policies (see
+//!   [`ReplacementPolicy`])
 //! - Lazy loading and eviction with automatic dirty page flushing
-//! - File-per-table storage with sequential page IDs
+//! - File-per-table storage with sequential page IDs, keeping the most
+//!   recently used table files open
+//! - Page pinning, which keeps a page cached until it is unpinned, by hand
+//!   or when the [`PageReadGuard`] or [`PageWriteGuard`] holding it is
+//!   dropped
+//! - Concurrent access from many threads, with the cache split into shards
+//!   locked separately and each cached page latched on its own (see
+//!   [Concurrency](#concurrency))
+//! - Sharing one pool between threads and heap files (see [`SharedPager`])
+//! - Page checksums, stamped on every page written and verified on every
+//!   page loaded that has one (see [`storage::checksum`])
+//! - Page compression, for tables that ask for it with
+//!   [`FilePager::set_compression`] (see [`common::compression`])
+//!
+//! # Concurrency
+//!
+//! Every [`Pager`] method takes `&self`, so one [`FilePager`] can serve many
+//! threads at once. Cached pages are split into shards by table and page ID,
+//! each behind its own lock, with adjacent pages of a table in different
+//! shards. A shard is locked only while a page is looked up, loaded or
+//! evicted. A fetched page is then pinned and read or written in place
+//! through a guard holding the page's own latch, a read-write lock, so any
+//! number of guards can read a page at once while a write guard has it to
+//! itself. Guards on different pages never wait for each other, whether or
+//! not their pages share a shard.
+//!
+//! A thread may hold several guards at once and fetch more pages while it
+//! holds them. Threads that latch several pages must latch them in a
+//! consistent order, as with any locks, and a thread must drop its write
+//! guards before flushing, which reads every dirty page. No page latch is
+//! waited for with a shard locked, so a held guard never blocks loads or
+//! evictions of other pages.
+//!
+//! Each shard caches its share of the pool's pages, and replaces pages
+//! among its own, so eviction ranks pages within a shard rather than
+//! across the pool. A new pool has a shard for every [`PAGES_PER_SHARD`]
+//! pages, up to [`MAX_SHARDS`], so a small pool has a single shard.
+//!
+//! # Exhaustion
+//!
+//! Eviction skips pinned pages, including every page a guard holds. When
+//! every cached page of a shard is pinned, loading another page into it
+//! waits up to the pager's pin wait (see [`FilePager::with_pin_wait`]) for
+//! another thread to unpin one, by dropping a guard or through a
+//! [`PagePins`] handle, then fails with [`DbError::BufferPoolExhausted`].
+//! The shard is unlocked while it waits, so other threads keep using its
+//! cached pages.
+//!
+//! # Eviction
+//!
+//! Eviction replaces the unpinned page the shard's [`Replacer`] ranks first:
+//! by default the least recently used one, or with
+//! [`FilePager::with_replacement_policy`] a scan-resistant policy that
+//! evicts pages used once before pages used again (see [`replacer`]).
+//! [`EvictionPolicy::CleanFirst`] replaces clean pages before dirty ones,
+//! so loading a page writes one only when every unpinned page is dirty.
+//! Before a dirty page is written, on eviction, flush or
+//! [`FilePager::write_through`], the pager syncs the write-ahead log through
+//! its [`LogSync`] (see [`FilePager::with_log_sync`]), so no page reaches
+//! disk ahead of the log records describing it. Pages carry no LSNs, so
+//! the whole log is synced. Evictions are counted in [`EvictionStats`].
+//!
+//! Pages fetched at [`Priority::Background`] (see [`Pager::set_priority`])
+//! are ranked for eviction as if used once, and fetching one that is
+//! already cached leaves its rank alone, so a background scan evicts its
+//! own pages before anyone else's. The priority belongs to the thread that
+//! set it, so statements running at once on different threads each fetch
+//! at their own.
+//!
+//! # Testing
+//!
+//! Page reads and writes consult a [`FaultInjector`] and pin waits read a
+//! [`Clock`] (see [`common::hooks`]), so tests can fail chosen writes and
+//! expire waits without sleeping.
 //!
 //! # Example
 //!
@@ -11,15 +85,15 @@
 //! use buffer::{Pager, FilePager};
 //! use common::{TableId, PageId};
 //!
-//! let mut pager = FilePager::new("/tmp/db", 100);
+//! let pager = FilePager::new("/tmp/db", 100);
 //! let table = TableId(1);
 //!
 //! // Allocate a new page
 //! let page_id = pager.allocate_page(table).unwrap();
 //!
-//! // Fetch and modify
+//! // Fetch for writing, which marks the page dirty
 //! {
-//!     let page = pager.fetch_page(table, page_id).unwrap();
+//!     let mut page = pager.fetch_page_mut(table, page_id).unwrap();
 //!     page.data[0] = 42;
 //! }
 //!
@@ -27,19 +101,36 @@
 //! pager.flush().unwrap();
 //! ```
 
+pub mod replacer;
+mod shared;
 #[cfg(test)]
 mod tests;
 
-use common::{DbError, DbResult, PageId, TableId};
-use hashbrown::HashMap;
+use common::compression::{self, Compression};
+use common::hooks::{Clock, FaultInjector, IoOp, no_faults, system_clock};
+use common::{DbError, DbResult, PageId, Priority, TableId};
+use hashbrown::{HashMap, HashSet};
 use lru::LruCache;
+use parking_lot::{ArcRwLockReadGuard, ArcRwLockWriteGuard, RawRwLock, RwLock};
+use replacer::{PageKey, Replacer};
 use std::{
-    fs::OpenOptions,
+    cell::Cell,
+    fmt,
+    fs::{File, OpenOptions},
     io::{Read, Seek, SeekFrom, Write},
     num::NonZeroUsize,
-    path::PathBuf,
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Condvar, Mutex, MutexGuard,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
 };
-use storage::{PAGE_SIZE, Page};
+use storage::{PAGE_SIZE, Page, checksum};
+
+pub use replacer::ReplacementPolicy;
+pub use shared::SharedPager;
 
 /// Abstraction for fetching, allocating, and flushing pages.
 ///
@@ -47,36 +138,277 @@ use storage::{PAGE_SIZE, Page};
 /// - Loading pages from persistent storage into memory
 /// - Evicting pages when the cache is full
 /// - Tracking dirty pages and flushing them to disk
+///
+/// Methods take `&self`, so implementors synchronize internally and can be
+/// shared between threads.
 pub trait Pager {
-    /// Fetch a page from the buffer pool or load it from disk.
+    /// Fetch a page from the buffer pool or load it from disk, for reading.
     ///
-    /// Returns a mutable reference to the page in the cache.
-    /// Marks the page as recently used in the LRU policy.
-    fn fetch_page(&mut self, table: TableId, pid: PageId) -> DbResult<&mut Page>;
+    /// Records the use with the replacement policy. The page is pinned
+    /// until the guard is dropped, and may be evicted after that (see
+    /// [`FilePager::pin_page`] for a page that stays cached).
+    fn fetch_page(&self, table: TableId, pid: PageId) -> DbResult<PageReadGuard<'_>>;
+
+    /// Fetch a page like [`Pager::fetch_page`], for writing.
+    ///
+    /// Marks the page as dirty, so changes made through the returned
+    /// guard are written on eviction or flush.
+    fn fetch_page_mut(&self, table: TableId, pid: PageId) -> DbResult<PageWriteGuard<'_>>;
 
     /// Allocate a new page for the given table.
     ///
     /// Assigns the next sequential `PageId` and returns it.
     /// The new page is initialized with zeros and marked as dirty.
-    fn allocate_page(&mut self, table: TableId) -> DbResult<PageId>;
+    fn allocate_page(&self, table: TableId) -> DbResult<PageId>;
 
     /// Flush all dirty pages to disk.
     ///
     /// After flushing, all pages are marked as clean.
-    fn flush(&mut self) -> DbResult<()>;
+    fn flush(&self) -> DbResult<()>;
+
+    /// Serve the following fetches made on the calling thread on behalf of
+    /// work of `priority`.
+    ///
+    /// Pagers that cache pages should keep pages used only by background
+    /// work from pushing out those other work needs, without changing the
+    /// priority other threads fetch at. Ignored by default.
+    fn set_priority(&self, _priority: Priority) {}
+}
+
+/// How long a load waits for a pinned page to be released by default.
+pub const DEFAULT_PIN_WAIT: Duration = Duration::from_millis(500);
+
+/// How many table files a pager keeps open by default.
+pub const DEFAULT_MAX_OPEN_FILES: usize = 64;
+
+/// Pages per shard of a new pager's cache, until it has [`MAX_SHARDS`].
+pub const PAGES_PER_SHARD: usize = 64;
+
+/// Most shards a new pager splits its cache into.
+pub const MAX_SHARDS: usize = 16;
+
+/// Whether eviction prefers clean pages when the cache is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// The unpinned page the replacement policy ranks first.
+    #[default]
+    Lru,
+    /// The clean unpinned page the replacement policy ranks first, or the
+    /// first dirty one if every unpinned page is dirty.
+    CleanFirst,
+}
+
+/// Makes the write-ahead log durable before a dirty page is written.
+pub trait LogSync: fmt::Debug + Send + Sync {
+    /// Sync every record appended to the log. An error fails the write, and
+    /// the page stays cached and dirty.
+    fn sync_log(&self) -> DbResult<()>;
+}
+
+/// Pages evicted from a buffer pool.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EvictionStats {
+    /// Clean pages, dropped without a write.
+    pub clean: u64,
+    /// Dirty pages, written before being dropped.
+    pub dirty: u64,
+}
+
+thread_local! {
+    /// Whether the current thread fetches pages for background work.
+    static BACKGROUND: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether the current thread fetches pages for background work (see
+/// [`Pager::set_priority`]).
+fn fetching_for_background() -> bool {
+    BACKGROUND.with(Cell::get)
+}
+
+/// Lock `mutex`, ignoring poisoning: every critical section leaves its data
+/// consistent before it can panic.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Pins held on each cached page.
+type PinCounts = HashMap<PageKey, usize>;
+
+/// A cached page behind its latch, shared by the shard caching it and the
+/// guards reading or writing it.
+type Frame = Arc<RwLock<Page>>;
+
+/// Pin counts of the pages in a buffer pool.
+///
+/// Clones share the same counts, so a thread that does not hold the pager
+/// can still unpin pages, waking a load that is waiting for a victim.
+#[derive(Clone, Debug, Default)]
+pub struct PagePins {
+    inner: Arc<(Mutex<PinCounts>, Condvar)>,
+}
+
+impl PagePins {
+    fn counts(&self) -> MutexGuard<'_, PinCounts> {
+        lock(&self.inner.0)
+    }
+
+    fn pin(&self, table: TableId, pid: PageId) {
+        *self.counts().entry((table, pid)).or_insert(0) += 1;
+    }
+
+    /// Release one pin on a page.
+    ///
+    /// Fails if the page is not pinned.
+    pub fn unpin(&self, table: TableId, pid: PageId) -> DbResult<()> {
+        let mut counts = self.counts();
+        let count = counts.get_mut(&(table, pid)).ok_or_else(|| {
+            DbError::Storage(format!("page {} of table {} is not pinned", pid.0, table.0))
+        })?;
+        *count -= 1;
+        if *count == 0 {
+            counts.remove(&(table, pid));
+            self.inner.1.notify_all();
+        }
+        Ok(())
+    }
+
+    /// Number of pins held on a page.
+    pub fn pin_count(&self, table: TableId, pid: PageId) -> usize {
+        self.counts().get(&(table, pid)).copied().unwrap_or(0)
+    }
+}
+
+/// One lock's worth of a pool's cached pages.
+#[derive(Debug)]
+struct Shard {
+    /// Pages this shard caches at most
+    capacity: usize,
+    cache: HashMap<PageKey, Frame>,
+    /// Ranks the cached pages for eviction
+    replacer: Box<dyn Replacer>,
+    /// Cached pages changed since they were last written.
+    dirty: HashSet<PageKey>,
+    eviction_stats: EvictionStats,
+}
+
+impl Shard {
+    fn new(capacity: usize, policy: ReplacementPolicy) -> Self {
+        Self {
+            capacity,
+            cache: HashMap::new(),
+            replacer: policy.replacer(),
+            dirty: HashSet::new(),
+            eviction_stats: EvictionStats::default(),
+        }
+    }
+}
+
+/// Split `max_pages` between `shards` shards, each caching at least one.
+fn shard_capacities(max_pages: usize, shards: usize) -> impl Iterator<Item = usize> {
+    (0..shards).map(move |i| (max_pages / shards + usize::from(i < max_pages % shards)).max(1))
+}
+
+/// Whether [`FilePager::make_room`] left room in a shard.
+enum Room<'a> {
+    /// The shard can cache another page.
+    Made,
+    /// Every page of the full shard is pinned. Holds the pins, locked since
+    /// they were checked, to wait for one to be released.
+    Pinned(MutexGuard<'a, PinCounts>),
+}
+
+/// A page fetched for reading, holding the page's latch for reading and a
+/// pin on it until dropped.
+#[derive(Debug)]
+pub struct PageReadGuard<'a> {
+    /// Taken when the guard is dropped, to release the latch before the pin
+    page: Option<ArcRwLockReadGuard<RawRwLock, Page>>,
+    key: PageKey,
+    pins: &'a PagePins,
 }
 
-/// File-backed buffer pool with LRU eviction.
+impl Deref for PageReadGuard<'_> {
+    type Target = Page;
+
+    fn deref(&self) -> &Page {
+        self.page.as_ref().expect("latched until dropped")
+    }
+}
+
+impl Drop for PageReadGuard<'_> {
+    fn drop(&mut self) {
+        // Unlatched first, so an unpinned page is never latched
+        self.page = None;
+        // The guard holds one of the page's pins, so this cannot fail
+        let _ = self.pins.unpin(self.key.0, self.key.1);
+    }
+}
+
+/// A page fetched for writing, and marked dirty, holding the page's latch
+/// for writing and a pin on it until dropped.
+#[derive(Debug)]
+pub struct PageWriteGuard<'a> {
+    /// Taken when the guard is dropped, to release the latch before the pin
+    page: Option<ArcRwLockWriteGuard<RawRwLock, Page>>,
+    key: PageKey,
+    pins: &'a PagePins,
+}
+
+impl Deref for PageWriteGuard<'_> {
+    type Target = Page;
+
+    fn deref(&self) -> &Page {
+        self.page.as_ref().expect("latched until dropped")
+    }
+}
+
+impl DerefMut for PageWriteGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Page {
+        self.page.as_mut().expect("latched until dropped")
+    }
+}
+
+impl Drop for PageWriteGuard<'_> {
+    fn drop(&mut self) {
+        // Unlatched first, so an unpinned page is never latched
+        self.page = None;
+        // The guard holds one of the page's pins, so this cannot fail
+        let _ = self.pins.unpin(self.key.0, self.key.1);
+    }
+}
+
+/// File-backed buffer pool.
 ///
 /// Uses a file-per-table storage model with sequential page IDs.
-/// Pages are evicted using an LRU (Least Recently Used) policy.
+/// Pages are evicted as a [`Replacer`] ranks them, least recently used
+/// first unless another [`ReplacementPolicy`] is chosen.
 /// Dirty pages are automatically flushed to disk on eviction or explicit flush.
+/// Pinned pages are never evicted. Table files stay open between reads and
+/// writes, up to a limit past which the least recently used one is closed.
+///
+/// The pool synchronizes internally, so it can be shared between threads
+/// (see [Concurrency](crate#concurrency)). Shared state is locked in a fixed
+/// order, page latches before page counts before shards before everything
+/// else, so no two calls wait for each other.
 #[derive(Debug)]
 pub struct FilePager {
     base_dir: PathBuf,
-    max_pages: usize,
-    cache: LruCache<(TableId, PageId), Page>,
-    dirty: HashMap<(TableId, PageId), bool>,
+    max_pages: AtomicUsize,
+    shards: Box<[Mutex<Shard>]>,
+    replacement_policy: ReplacementPolicy,
+    files: Mutex<LruCache<TableId, Arc<Mutex<File>>>>,
+    /// Files of tables stored somewhere other than `table_{id}.tbl`
+    paths: Mutex<HashMap<TableId, PathBuf>>,
+    /// How the pages of tables that compress them are written
+    compression: Mutex<HashMap<TableId, Compression>>,
+    /// Pages in each table, counting allocated pages not yet written.
+    page_counts: Mutex<HashMap<TableId, u64>>,
+    pins: PagePins,
+    pin_wait: Duration,
+    eviction_policy: EvictionPolicy,
+    log_sync: Option<Arc<dyn LogSync>>,
+    clock: Arc<dyn Clock>,
+    faults: Arc<dyn FaultInjector>,
 }
 
 impl FilePager {
@@ -87,34 +419,407 @@ impl FilePager {
     /// * `base_dir` - Directory for table files (format: `table_{id}.tbl`)
     /// * `max_pages` - Maximum number of pages to cache in memory
     ///
+    /// The cache has a shard for every [`PAGES_PER_SHARD`] pages, up to
+    /// [`MAX_SHARDS`]; see [`FilePager::with_shards`] to choose.
+    ///
     /// # Panics
     ///
     /// Panics if `max_pages` is 0.
     pub fn new(base_dir: impl Into<PathBuf>, max_pages: usize) -> Self {
         assert!(max_pages > 0, "max_pages must be > 0");
-        Self {
+        let pager = Self {
             base_dir: base_dir.into(),
-            max_pages,
-            cache: LruCache::new(NonZeroUsize::new(max_pages).unwrap()),
-            dirty: HashMap::new(),
+            max_pages: AtomicUsize::new(max_pages),
+            shards: Box::new([]),
+            replacement_policy: ReplacementPolicy::default(),
+            files: Mutex::new(LruCache::new(
+                NonZeroUsize::new(DEFAULT_MAX_OPEN_FILES).unwrap(),
+            )),
+            paths: Mutex::new(HashMap::new()),
+            compression: Mutex::new(HashMap::new()),
+            page_counts: Mutex::new(HashMap::new()),
+            pins: PagePins::default(),
+            pin_wait: DEFAULT_PIN_WAIT,
+            eviction_policy: EvictionPolicy::default(),
+            log_sync: None,
+            clock: system_clock(),
+            faults: no_faults(),
+        };
+        pager.with_shards((max_pages / PAGES_PER_SHARD).clamp(1, MAX_SHARDS))
+    }
+
+    /// Split the cache into `shards` shards, each caching an equal share
+    /// of the pool's pages, and at least one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is 0.
+    pub fn with_shards(mut self, shards: usize) -> Self {
+        assert!(shards > 0, "shards must be > 0");
+        self.shards = shard_capacities(self.max_pages(), shards)
+            .map(|capacity| Mutex::new(Shard::new(capacity, self.replacement_policy)))
+            .collect();
+        self
+    }
+
+    /// Set how long a load waits for a page to be unpinned when every
+    /// cached page is pinned. Zero fails immediately.
+    pub fn with_pin_wait(mut self, wait: Duration) -> Self {
+        self.pin_wait = wait;
+        self
+    }
+
+    /// Keep at most `max_files` table files open at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_files` is 0.
+    pub fn with_max_open_files(mut self, max_files: usize) -> Self {
+        let max_files = NonZeroUsize::new(max_files).expect("max_files must be > 0");
+        self.files
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .resize(max_files);
+        self
+    }
+
+    /// Rank pages for eviction with one of the built-in replacement
+    /// policies, one replacer per shard.
+    pub fn with_replacement_policy(mut self, policy: ReplacementPolicy) -> Self {
+        self.replacement_policy = policy;
+        for shard in &mut self.shards {
+            shard.get_mut().unwrap_or_else(|e| e.into_inner()).replacer = policy.replacer();
         }
+        self
+    }
+
+    /// Choose whether eviction prefers clean pages.
+    pub fn with_eviction_policy(mut self, policy: EvictionPolicy) -> Self {
+        self.eviction_policy = policy;
+        self
+    }
+
+    /// Sync the write-ahead log through `log` before writing a dirty page.
+    pub fn with_log_sync(mut self, log: Arc<dyn LogSync>) -> Self {
+        self.log_sync = Some(log);
+        self
+    }
+
+    /// Time pin waits with `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Consult `faults` before each page read and write.
+    pub fn with_faults(mut self, faults: Arc<dyn FaultInjector>) -> Self {
+        self.faults = faults;
+        self
+    }
+
+    /// Fetch a page and pin it, so it stays cached until unpinned.
+    ///
+    /// Each pin must be released with [`FilePager::unpin_page`] or through
+    /// the [`PagePins`] handle.
+    pub fn pin_page(&self, table: TableId, pid: PageId) -> DbResult<()> {
+        self.pin_frame((table, pid))?;
+        Ok(())
+    }
+
+    /// Fetch a page pinned until the returned guard is dropped, as
+    /// [`Pager::fetch_page`] does.
+    pub fn pin_for_read(&self, table: TableId, pid: PageId) -> DbResult<PageReadGuard<'_>> {
+        let key = (table, pid);
+        // Latched with no shard locked, so waiting for a writer of the page
+        // holds up no other page
+        let page = self.pin_frame(key)?.read_arc();
+        Ok(PageReadGuard {
+            page: Some(page),
+            key,
+            pins: &self.pins,
+        })
+    }
+
+    /// Fetch a page for writing, pinned until the returned guard is
+    /// dropped, as [`Pager::fetch_page_mut`] does.
+    pub fn pin_for_write(&self, table: TableId, pid: PageId) -> DbResult<PageWriteGuard<'_>> {
+        let key = (table, pid);
+        let page = self.pin_frame(key)?.write_arc();
+        // Marked once latched, so a flush that takes the mark first copies
+        // the page only after this guard's changes
+        self.shard(key).dirty.insert(key);
+        Ok(PageWriteGuard {
+            page: Some(page),
+            key,
+            pins: &self.pins,
+        })
+    }
+
+    /// Release one pin on a page.
+    pub fn unpin_page(&self, table: TableId, pid: PageId) -> DbResult<()> {
+        self.pins.unpin(table, pid)
+    }
+
+    /// A handle on this pool's pin counts, for unpinning from other threads.
+    pub fn pins(&self) -> PagePins {
+        self.pins.clone()
+    }
+
+    /// Pages evicted so far.
+    pub fn eviction_stats(&self) -> EvictionStats {
+        self.shards
+            .iter()
+            .fold(EvictionStats::default(), |sum, shard| {
+                let stats = lock(shard).eviction_stats;
+                EvictionStats {
+                    clean: sum.clean + stats.clean,
+                    dirty: sum.dirty + stats.dirty,
+                }
+            })
+    }
+
+    /// Maximum number of pages cached at once.
+    pub fn max_pages(&self) -> usize {
+        self.max_pages.load(Ordering::Relaxed)
+    }
+
+    /// Number of shards the cache is split into.
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Cache at most `max_pages` pages from now on, split between the
+    /// shards as before.
+    ///
+    /// Shrinking evicts pages as a full cache would, writing dirty ones
+    /// first. If a page cannot be evicted, for example because every page
+    /// is pinned, the size is left unchanged and the error is returned at
+    /// once, without waiting for pages to be unpinned.
+    pub fn set_max_pages(&self, max_pages: usize) -> DbResult<()> {
+        if max_pages == 0 {
+            return Err(DbError::Storage("max_pages must be > 0".into()));
+        }
+        let mut shards = self.lock_shards();
+        let previous: Vec<_> = shards.iter().map(|shard| shard.capacity).collect();
+        let capacities = shard_capacities(max_pages, shards.len());
+        let shrunk = shards
+            .iter_mut()
+            .zip(capacities)
+            .try_for_each(|(shard, capacity)| {
+                shard.capacity = capacity;
+                while shard.cache.len() > capacity {
+                    if let Room::Pinned(_) = self.make_room(shard)? {
+                        return Err(exhausted(shard.capacity, Duration::ZERO));
+                    }
+                }
+                Ok(())
+            });
+        if let Err(e) = shrunk {
+            for (shard, capacity) in shards.iter_mut().zip(previous) {
+                shard.capacity = capacity;
+            }
+            return Err(e);
+        }
+        self.max_pages.store(max_pages, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Keep a table's pages in the file at `path` rather than in
+    /// `table_{id}.tbl`.
+    ///
+    /// Attaching a table to a different file than before forgets the pages
+    /// of the old one, as [`FilePager::forget_table`] does.
+    pub fn attach_file(&self, table: TableId, path: &Path) -> DbResult<()> {
+        if self.attached(table, path) {
+            return Ok(());
+        }
+        self.forget_table(table)?;
+        lock(&self.paths).insert(table, path.to_path_buf());
+        Ok(())
+    }
+
+    /// Whether `table` is kept in the file at `path`.
+    fn attached(&self, table: TableId, path: &Path) -> bool {
+        lock(&self.paths)
+            .get(&table)
+            .is_some_and(|attached| attached == path)
+    }
+
+    /// Compress `table`'s pages with `compression` as they are written.
+    /// Compressed pages are decompressed as they are loaded whatever the
+    /// table's setting.
+    pub fn set_compression(&self, table: TableId, compression: Compression) {
+        let mut tables = lock(&self.compression);
+        match compression {
+            Compression::None => tables.remove(&table),
+            _ => tables.insert(table, compression),
+        };
+    }
+
+    /// Drop a table's cached pages without writing them, close its file and
+    /// forget its page count and compression, for example because the file
+    /// was replaced or removed. Fails, forgetting nothing, if one of the pages is pinned.
+    pub fn forget_table(&self, table: TableId) -> DbResult<()> {
+        self.forget(|forgotten| forgotten == table)
+    }
+
+    /// Forget every table, as [`FilePager::forget_table`] does, for
+    /// example because the data directory was restored from a backup.
+    /// Fails, forgetting nothing, if a page is pinned.
+    pub fn forget_all(&self) -> DbResult<()> {
+        self.forget(|_| true)
+    }
+
+    /// Forget the tables `forgotten` accepts.
+    fn forget(&self, forgotten: impl Fn(TableId) -> bool) -> DbResult<()> {
+        // Pages cannot be pinned while every shard is locked
+        let mut shards = self.lock_shards();
+        let pinned = self.pins.counts();
+        if let Some((table, pid)) = pinned.keys().find(|(table, _)| forgotten(*table)) {
+            return Err(DbError::Storage(format!(
+                "page {} of table {} is pinned",
+                pid.0, table.0
+            )));
+        }
+        drop(pinned);
+        for shard in &mut shards {
+            let cached: Vec<_> = shard
+                .cache
+                .keys()
+                .copied()
+                .filter(|(table, _)| forgotten(*table))
+                .collect();
+            for key in cached {
+                shard.cache.remove(&key);
+                shard.replacer.remove(&key);
+                shard.dirty.remove(&key);
+            }
+        }
+        drop(shards);
+
+        let mut files = lock(&self.files);
+        let open: Vec<_> = files
+            .iter()
+            .map(|(table, _)| *table)
+            .filter(|table| forgotten(*table))
+            .collect();
+        for table in open {
+            files.pop(&table);
+        }
+        drop(files);
+        lock(&self.page_counts).retain(|table, _| !forgotten(*table));
+        lock(&self.paths).retain(|table, _| !forgotten(*table));
+        lock(&self.compression).retain(|table, _| !forgotten(*table));
+        Ok(())
+    }
+
+    /// Cache `page` of `table` as a dirty page and write it to its file at
+    /// once, as eviction would, syncing the log first but not the file. The
+    /// cached copy is then clean.
+    ///
+    /// Unlike a change through [`Pager::fetch_page_mut`], the page is on
+    /// disk when this returns, so callers control the order in which pages
+    /// reach the file. If the log sync or the write fails, the file and the
+    /// cached copy, if any, are unchanged.
+    pub fn write_through(&self, table: TableId, page: &Page) -> DbResult<()> {
+        self.page_count(table)?;
+        let key = (table, PageId(page.id));
+        // Pinned until written, and latched, so no guard sees the page
+        // before it is on disk
+        let mut shard = self.shard_with_room(key)?;
+        let (mut latched, previous) = match shard.cache.get(&key) {
+            Some(frame) => {
+                let frame = frame.clone();
+                self.pins.pin(table, key.1);
+                drop(shard);
+                let mut latched = frame.write_arc();
+                let previous = std::mem::replace(&mut *latched, page.clone());
+                (latched, Some(previous))
+            }
+            None => {
+                let frame = Arc::new(RwLock::new(page.clone()));
+                let latched = frame.write_arc();
+                shard.cache.insert(key, frame);
+                self.pins.pin(table, key.1);
+                drop(shard);
+                (latched, None)
+            }
+        };
+
+        let written = self.sync_log().and_then(|()| {
+            let file = self.open_table_file(table)?;
+            self.write_run(table, &lock(&file), &[&latched])
+        });
+        let mut shard = self.shard(key);
+        match (&written, previous) {
+            (Ok(()), _) => {
+                shard.dirty.remove(&key);
+                shard.replacer.record_access(key, fetching_for_background());
+            }
+            (Err(_), Some(previous)) => *latched = previous,
+            (Err(_), None) => {
+                shard.cache.remove(&key);
+                shard.replacer.remove(&key);
+                shard.dirty.remove(&key);
+            }
+        }
+        drop((shard, latched));
+        // Pinned above, so this cannot fail
+        let _ = self.pins.unpin(table, key.1);
+        written?;
+        let mut counts = lock(&self.page_counts);
+        let count = counts.entry(table).or_insert(0);
+        *count = (*count).max(page.id + 1);
+        Ok(())
+    }
+
+    /// Sync a table's file to disk. Cached dirty pages are not written.
+    pub fn sync_table(&self, table: TableId) -> DbResult<()> {
+        let file = self.open_table_file(table)?;
+        lock(&file)
+            .sync_all()
+            .map_err(|e| DbError::from_write(e, DbError::Storage, "Failed to sync table file"))
     }
 
     /// Get the file path for a table.
     fn table_path(&self, table: TableId) -> PathBuf {
-        self.base_dir.join(format!("table_{}.tbl", table.0))
+        match lock(&self.paths).get(&table) {
+            Some(path) => path.clone(),
+            None => self.base_dir.join(format!("table_{}.tbl", table.0)),
+        }
     }
 
-    /// Load a page from disk, or create a new zero-initialized page if it doesn't exist.
-    fn load_page(&self, table: TableId, pid: PageId) -> DbResult<Page> {
+    /// Get a table's open file, opening it, and creating it if it doesn't
+    /// exist, when it is not already open.
+    ///
+    /// Opening a file past the limit closes the least recently used one.
+    /// Each file has its own lock, held from a seek to the read or write
+    /// after it.
+    fn open_table_file(&self, table: TableId) -> DbResult<Arc<Mutex<File>>> {
         let path = self.table_path(table);
-        let mut file = OpenOptions::new()
+        let mut files = lock(&self.files);
+        if let Some(file) = files.get(&table) {
+            return Ok(file.clone());
+        }
+        let file = OpenOptions::new()
             .read(true)
             .write(true)
             .create(true)
             .truncate(false)
-            .open(&path)
+            .open(path)
             .map_err(|e| DbError::Storage(format!("Failed to open table file: {}", e)))?;
+        let file = Arc::new(Mutex::new(file));
+        files.push(table, file.clone());
+        Ok(file)
+    }
+
+    /// Load a page from disk, or create a new zero-initialized page if it doesn't exist.
+    fn load_page(&self, table: TableId, pid: PageId) -> DbResult<Page> {
+        self.faults
+            .check(IoOp::PageRead)
+            .map_err(|e| DbError::Storage(format!("Failed to read page: {}", e)))?;
+        let file = self.open_table_file(table)?;
+        let mut file = lock(&file);
 
         let offset = pid.0 * PAGE_SIZE as u64;
         file.seek(SeekFrom::Start(offset))
@@ -124,6 +829,7 @@ impl FilePager {
         let n = file
             .read(&mut buf)
             .map_err(|e| DbError::Storage(format!("Failed to read page: {}", e)))?;
+        drop(file);
 
         if n == 0 {
             // Page doesn't exist yet, return zero-initialized page
@@ -135,6 +841,8 @@ impl FilePager {
                 PAGE_SIZE, n
             )))
         } else {
+            let buf = compression::decompress_page(buf)?;
+            checksum::verify(&buf, pid.0, &self.table_path(table))?;
             Ok(Page {
                 id: pid.0,
                 data: buf,
@@ -142,112 +850,345 @@ impl FilePager {
         }
     }
 
-    /// Write a page to disk.
-    fn write_page(&self, table: TableId, page: &Page) -> DbResult<()> {
-        let path = self.table_path(table);
-        let mut file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(false)
-            .open(&path)
-            .map_err(|e| DbError::Storage(format!("Failed to open table file: {}", e)))?;
+    /// Write pages with consecutive IDs to a table's file with one write,
+    /// or with one write each if the table compresses its pages.
+    fn write_run(&self, table: TableId, mut file: &File, pages: &[&Page]) -> DbResult<()> {
+        self.faults
+            .check(IoOp::PageWrite)
+            .map_err(|e| DbError::from_write(e, DbError::Storage, "Failed to write page"))?;
+        let Some(first) = pages.first() else {
+            return Ok(());
+        };
 
-        let offset = page.id * PAGE_SIZE as u64;
+        let offset = first.id * PAGE_SIZE as u64;
         file.seek(SeekFrom::Start(offset))
             .map_err(|e| DbError::Storage(format!("Failed to seek to page: {}", e)))?;
 
-        file.write_all(&page.data)
-            .map_err(|e| DbError::Storage(format!("Failed to write page: {}", e)))?;
+        let mut buf = Vec::with_capacity(pages.len() * PAGE_SIZE);
+        for page in pages {
+            buf.extend_from_slice(&page.data);
+        }
+        for data in buf.chunks_mut(PAGE_SIZE) {
+            checksum::stamp(data);
+        }
+        let Some(compression) = lock(&self.compression).get(&table).copied() else {
+            file.write_all(&buf)
+                .map_err(|e| DbError::from_write(e, DbError::Storage, "Failed to write page"))?;
+            return Ok(());
+        };
+
+        // A compressed page fills only the start of its slot, and the rest
+        // is left unwritten
+        for (page, data) in pages.iter().zip(buf.chunks(PAGE_SIZE)) {
+            let frame = compression::compress_page(compression, data)?;
+            file.seek(SeekFrom::Start(page.id * PAGE_SIZE as u64))
+                .and_then(|_| file.write_all(frame.as_deref().unwrap_or(data)))
+                .map_err(|e| DbError::from_write(e, DbError::Storage, "Failed to write page"))?;
+        }
+        let end = (first.id + pages.len() as u64) * PAGE_SIZE as u64;
+        let len = file
+            .metadata()
+            .map_err(|e| DbError::Storage(format!("Failed to read file metadata: {}", e)))?
+            .len();
+        if len < end {
+            file.set_len(end).map_err(|e| {
+                DbError::from_write(e, DbError::Storage, "Failed to extend table file")
+            })?;
+        }
 
         Ok(())
     }
 
-    /// Evict the least recently used page if the cache is full.
-    ///
-    /// If the evicted page is dirty, it is flushed to disk first.
-    fn evict_if_needed(&mut self) -> DbResult<()> {
-        if self.cache.len() < self.max_pages {
-            return Ok(());
+    /// Number of pages in a table, including allocated pages that have not
+    /// been written yet. The first call for a table reads its file's size.
+    pub fn page_count(&self, table: TableId) -> DbResult<u64> {
+        self.count_pages(&mut lock(&self.page_counts), table)
+    }
+
+    /// [`FilePager::page_count`] with the page counts locked.
+    fn count_pages(&self, counts: &mut HashMap<TableId, u64>, table: TableId) -> DbResult<u64> {
+        if let Some(&count) = counts.get(&table) {
+            return Ok(count);
         }
+        let file = self.open_table_file(table)?;
+        let len = lock(&file)
+            .metadata()
+            .map_err(|e| DbError::Storage(format!("Failed to read file metadata: {}", e)))?
+            .len();
+        let count = len / PAGE_SIZE as u64;
+        counts.insert(table, count);
+        Ok(count)
+    }
+
+    /// Index of the shard caching `key`.
+    fn shard_index(&self, (table, pid): PageKey) -> usize {
+        // Adjacent pages of a table fall in different shards, so a scan
+        // spreads over all of them
+        let hash = table
+            .0
+            .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            .wrapping_add(pid.0);
+        (hash % self.shards.len() as u64) as usize
+    }
+
+    /// Lock the shard caching `key`.
+    fn shard(&self, key: PageKey) -> MutexGuard<'_, Shard> {
+        lock(&self.shards[self.shard_index(key)])
+    }
+
+    /// Lock every shard, in order.
+    fn lock_shards(&self) -> Vec<MutexGuard<'_, Shard>> {
+        self.shards.iter().map(lock).collect()
+    }
+
+    /// Make sure a page is cached, loading it from disk if it is not,
+    /// record its use with the replacer and pin it. Returns the page's
+    /// frame, with its shard unlocked again.
+    fn pin_frame(&self, key: PageKey) -> DbResult<Frame> {
+        let mut shard = self.shard_with_room(key)?;
+        let frame = match shard.cache.get(&key) {
+            Some(frame) => frame.clone(),
+            None => {
+                // Page not in cache - load from disk
+                let frame = Arc::new(RwLock::new(self.load_page(key.0, key.1)?));
+                shard.cache.insert(key, frame.clone());
+                frame
+            }
+        };
+        shard.replacer.record_access(key, fetching_for_background());
+        // Pinned with the shard locked, so the page cannot be evicted first
+        self.pins.pin(key.0, key.1);
+        Ok(frame)
+    }
 
-        if let Some(((table, pid), page)) = self.cache.pop_lru()
-            && self.dirty.remove(&(table, pid)).is_some()
-        {
-            self.write_page(table, &page)?;
+    /// Lock the shard caching `key`, with the page cached or room made for
+    /// it.
+    ///
+    /// If every page of the shard is pinned, unlocks it and waits up to the
+    /// pin wait for one to be unpinned.
+    fn shard_with_room(&self, key: PageKey) -> DbResult<MutexGuard<'_, Shard>> {
+        let mut deadline = None;
+        loop {
+            let mut shard = self.shard(key);
+            if shard.cache.contains_key(&key) {
+                return Ok(shard);
+            }
+            let Room::Pinned(pinned) = self.make_room(&mut shard)? else {
+                return Ok(shard);
+            };
+            let capacity = shard.capacity;
+            drop(shard);
+            let deadline = *deadline.get_or_insert_with(|| self.clock.now() + self.pin_wait);
+            self.wait_for_unpin(pinned, capacity, deadline)?;
         }
+    }
 
+    /// Wait until a page is unpinned or `deadline` passes, with `pinned`
+    /// locked since every page of a shard of `capacity` pages was found
+    /// pinned. Fails once the deadline has passed.
+    fn wait_for_unpin(
+        &self,
+        pinned: MutexGuard<'_, PinCounts>,
+        capacity: usize,
+        deadline: Instant,
+    ) -> DbResult<()> {
+        let now = self.clock.now();
+        if now >= deadline {
+            return Err(exhausted(capacity, self.pin_wait));
+        }
+        let _ = self
+            .pins
+            .inner
+            .1
+            .wait_timeout(pinned, self.clock.block_for(deadline - now))
+            .unwrap_or_else(|e| e.into_inner());
         Ok(())
     }
-}
 
-impl Pager for FilePager {
-    fn fetch_page(&mut self, table: TableId, pid: PageId) -> DbResult<&mut Page> {
-        // Check if page is already in cache
-        if self.cache.contains(&(table, pid)) {
-            // LruCache::get_mut updates LRU order
-            return Ok(self.cache.get_mut(&(table, pid)).unwrap());
+    /// Evict an unpinned page of `shard`, chosen by the eviction policy, if
+    /// the shard is full.
+    ///
+    /// If the evicted page is dirty, it is written first (see
+    /// [`FilePager::write_dirty`]); if that fails, the page stays cached.
+    fn make_room(&self, shard: &mut Shard) -> DbResult<Room<'_>> {
+        if shard.cache.len() < shard.capacity {
+            return Ok(Room::Made);
         }
 
-        // Page not in cache - load from disk
-        let page = self.load_page(table, pid)?;
+        let pinned = self.pins.counts();
+        let Shard {
+            replacer, dirty, ..
+        } = &mut *shard;
+        let clean = match self.eviction_policy {
+            EvictionPolicy::Lru => None,
+            EvictionPolicy::CleanFirst => {
+                replacer.victim(&mut |key| !pinned.contains_key(key) && !dirty.contains(key))
+            }
+        };
+        let Some(victim) = clean.or_else(|| replacer.victim(&mut |key| !pinned.contains_key(key)))
+        else {
+            return Ok(Room::Pinned(pinned));
+        };
+        drop(pinned);
 
-        // Evict LRU page if cache is full
-        self.evict_if_needed()?;
+        if shard.dirty.contains(&victim) {
+            self.write_dirty(shard, victim)?;
+            shard.eviction_stats.dirty += 1;
+        } else {
+            shard.eviction_stats.clean += 1;
+        }
+        shard.cache.remove(&victim);
+        shard.replacer.remove(&victim);
 
-        // Insert into cache
-        self.cache.push((table, pid), page);
+        Ok(Room::Made)
+    }
 
-        Ok(self.cache.get_mut(&(table, pid)).unwrap())
+    /// Sync the log, then write the dirty cached page `key` of `shard` to
+    /// its file and mark it clean. If either fails, the page stays dirty.
+    ///
+    /// The page must be unpinned, so no guard holds its latch.
+    fn write_dirty(&self, shard: &mut Shard, key: PageKey) -> DbResult<()> {
+        self.sync_log()?;
+        let file = self.open_table_file(key.0)?;
+        self.write_run(key.0, &lock(&file), &[&shard.cache[&key].read()])?;
+        shard.dirty.remove(&key);
+        Ok(())
     }
 
-    fn allocate_page(&mut self, table: TableId) -> DbResult<PageId> {
-        let path = self.table_path(table);
+    /// Sync the log, then write `pages`, sorted by table and page ID, for
+    /// [`Pager::flush`], counting in `written` the pages of each table once
+    /// its file is synced.
+    fn write_pages(&self, pages: &[(PageKey, Page)], written: &mut usize) -> DbResult<()> {
+        if !pages.is_empty() {
+            self.sync_log()?;
+        }
+        for table_pages in pages.chunk_by(|a, b| a.0.0 == b.0.0) {
+            let table = table_pages[0].0.0;
+            let file = self.open_table_file(table)?;
+            let file = lock(&file);
+            for run in table_pages.chunk_by(|a, b| a.0.1.0 + 1 == b.0.1.0) {
+                let run: Vec<&Page> = run.iter().map(|(_, page)| page).collect();
+                self.write_run(table, &file, &run)?;
+            }
+            file.sync_data().map_err(|e| {
+                DbError::from_write(e, DbError::Storage, "Failed to sync table file")
+            })?;
+            *written += table_pages.len();
+        }
+        Ok(())
+    }
 
-        // Determine next page ID from file size
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(false)
-            .open(&path)
-            .map_err(|e| DbError::Storage(format!("Failed to open table file: {}", e)))?;
+    /// Sync the write-ahead log, if the pager has one.
+    fn sync_log(&self) -> DbResult<()> {
+        match &self.log_sync {
+            Some(log) => log.sync_log(),
+            None => Ok(()),
+        }
+    }
+}
 
-        let len = file
-            .metadata()
-            .map_err(|e| DbError::Storage(format!("Failed to read file metadata: {}", e)))?
-            .len();
+/// Error returned when every page of a shard of `capacity` pages stayed
+/// pinned for `waited`.
+fn exhausted(capacity: usize, waited: Duration) -> DbError {
+    DbError::BufferPoolExhausted(format!(
+        "all {capacity} pages are pinned after waiting {waited:?}"
+    ))
+}
 
-        let pid = PageId(len / PAGE_SIZE as u64);
+impl Pager for FilePager {
+    fn fetch_page(&self, table: TableId, pid: PageId) -> DbResult<PageReadGuard<'_>> {
+        self.pin_for_read(table, pid)
+    }
 
-        // Create new zero-initialized page
-        let page = Page::new(pid.0);
+    fn fetch_page_mut(&self, table: TableId, pid: PageId) -> DbResult<PageWriteGuard<'_>> {
+        self.pin_for_write(table, pid)
+    }
 
-        // Write page to disk immediately to extend the file
-        self.write_page(table, &page)?;
+    /// The page is only cached; the file grows when the page is first
+    /// written, on eviction or flush.
+    fn allocate_page(&self, table: TableId) -> DbResult<PageId> {
+        let mut deadline = None;
+        loop {
+            // Held until the page is cached, so no other call allocates the
+            // same ID
+            let mut counts = lock(&self.page_counts);
+            let pid = PageId(self.count_pages(&mut counts, table)?);
+            let key = (table, pid);
 
-        // Evict LRU page if cache is full
-        self.evict_if_needed()?;
+            let mut shard = self.shard(key);
+            if let Room::Pinned(pinned) = self.make_room(&mut shard)? {
+                // Wait with nothing else locked, then take the next ID again
+                let capacity = shard.capacity;
+                drop((shard, counts));
+                let deadline = *deadline.get_or_insert_with(|| self.clock.now() + self.pin_wait);
+                self.wait_for_unpin(pinned, capacity, deadline)?;
+                continue;
+            }
 
-        // Insert into cache and mark as dirty (so future modifications are tracked)
-        self.cache.push((table, pid), page);
-        self.dirty.insert((table, pid), true);
+            // Insert into cache and mark as dirty, so it is written before
+            // it can be evicted
+            shard
+                .cache
+                .insert(key, Arc::new(RwLock::new(Page::new(pid.0))));
+            shard.replacer.record_access(key, false);
+            shard.dirty.insert(key);
+            counts.insert(table, pid.0 + 1);
 
-        Ok(pid)
+            return Ok(pid);
+        }
     }
 
-    fn flush(&mut self) -> DbResult<()> {
-        // Collect all dirty page keys
-        let dirty_keys: Vec<_> = self.dirty.keys().copied().collect();
+    /// Syncs the log, then writes each table's dirty pages in page order,
+    /// each run of adjacent pages with a single write, and syncs the
+    /// table's file once.
+    ///
+    /// Each shard is locked only to take its dirty pages, which are marked
+    /// clean and pinned until written, then each page is latched only to
+    /// copy it, so guards can be fetched and pages changed meanwhile; a page
+    /// changed after it was taken is dirty again. If a table's file fails
+    /// to be written or synced, its pages and those of the tables after it
+    /// are marked dirty again.
+    fn flush(&self) -> DbResult<()> {
+        let mut taken: Vec<(PageKey, Frame)> = Vec::new();
+        for shard in self.shards.iter() {
+            let mut shard = lock(shard);
+            let Shard { cache, dirty, .. } = &mut *shard;
+            for key in dirty.drain() {
+                if let Some(frame) = cache.get(&key) {
+                    self.pins.pin(key.0, key.1);
+                    taken.push((key, frame.clone()));
+                }
+            }
+        }
+        taken.sort_unstable_by_key(|((table, pid), _)| (table.0, pid.0));
 
-        // Flush each dirty page
-        for (table, pid) in dirty_keys {
-            if let Some(page) = self.cache.peek(&(table, pid)) {
-                self.write_page(table, page)?;
-                self.dirty.remove(&(table, pid));
+        // Copied before any file is locked, since a guard holding a latch
+        // may be reading from a file
+        let pages: Vec<(PageKey, Page)> = taken
+            .iter()
+            .map(|(key, frame)| (*key, frame.read().clone()))
+            .collect();
+        drop(taken);
+        let mut written = 0;
+        let result = self.write_pages(&pages, &mut written);
+        if result.is_err() {
+            for &(key, _) in &pages[written..] {
+                let mut shard = self.shard(key);
+                if shard.cache.contains_key(&key) {
+                    shard.dirty.insert(key);
+                }
             }
         }
+        for &(key, _) in &pages {
+            // Pinned above, so this cannot fail
+            let _ = self.pins.unpin(key.0, key.1);
+        }
+        result
+    }
 
-        Ok(())
+    /// Sets the priority of the calling thread, whichever pool it fetches
+    /// from.
+    fn set_priority(&self, priority: Priority) {
+        BACKGROUND.with(|background| background.set(priority == Priority::Background));
     }
 }
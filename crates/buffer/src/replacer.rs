@@ -0,0 +1,286 @@
+//! Page replacement policies.
+//!
+//! A [`Replacer`] ranks the pages of a [`FilePager`](crate::FilePager) for
+//! eviction. The pager tells it about every page used and every page that
+//! leaves the cache, and asks it for a victim when the cache is full; the
+//! pager decides which pages may be evicted (pinned pages may not).
+//!
+//! [`LruReplacer`] evicts the least recently used page, so a scan of as many
+//! pages as the cache holds replaces everything cached. The other policies
+//! resist scans by evicting pages used once before pages used again:
+//!
+//! - [`ClockReplacer`] approximates LRU with one reference bit per page. A
+//!   page used again outlasts the scan's pages until the hand has passed it
+//!   once, so it survives a scan of up to a cacheful of pages.
+//! - [`LruKReplacer`] evicts the page whose K-th most recent use is oldest, so
+//!   a scan's pages replace each other however long it runs.
+//! - [`TwoQReplacer`] keeps pages used once in a FIFO queue apart from those
+//!   used again, and likewise confines a scan to the queue.
+//!
+//! Pages used by [`Priority::Background`](common::Priority::Background) work
+//! are always evicted as if used once, and using a cached page for
+//! background work leaves its rank alone.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::num::NonZeroUsize;
+
+use common::{PageId, TableId};
+use hashbrown::HashMap;
+use lru::LruCache;
+
+/// A cached page: its table and page ID.
+pub type PageKey = (TableId, PageId);
+
+/// Chooses which cached page eviction replaces.
+pub trait Replacer: fmt::Debug + Send {
+    /// A page was cached, or a cached page was used again, for background
+    /// work if `background`.
+    fn record_access(&mut self, key: PageKey, background: bool);
+
+    /// A page left the cache.
+    fn remove(&mut self, key: &PageKey);
+
+    /// The page to evict next among those `evictable` accepts, if any. The
+    /// page stays tracked until it is [removed](Replacer::remove), since
+    /// evicting it can fail.
+    fn victim(&mut self, evictable: &mut dyn FnMut(&PageKey) -> bool) -> Option<PageKey>;
+}
+
+/// A built-in [`Replacer`], chosen with
+/// [`FilePager::with_replacement_policy`](crate::FilePager::with_replacement_policy).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReplacementPolicy {
+    /// [`LruReplacer`].
+    #[default]
+    Lru,
+    /// [`ClockReplacer`].
+    Clock,
+    /// [`LruKReplacer`] with this K.
+    LruK(NonZeroUsize),
+    /// [`TwoQReplacer`].
+    TwoQ,
+}
+
+impl ReplacementPolicy {
+    /// A new replacer of this policy, tracking no pages.
+    pub fn replacer(self) -> Box<dyn Replacer> {
+        match self {
+            Self::Lru => Box::new(LruReplacer::default()),
+            Self::Clock => Box::new(ClockReplacer::default()),
+            Self::LruK(k) => Box::new(LruKReplacer::new(k)),
+            Self::TwoQ => Box::new(TwoQReplacer::default()),
+        }
+    }
+}
+
+/// Evicts the least recently used page.
+///
+/// Background pages are ranked as least recently used when cached.
+#[derive(Debug)]
+pub struct LruReplacer {
+    pages: LruCache<PageKey, ()>,
+}
+
+impl Default for LruReplacer {
+    fn default() -> Self {
+        Self {
+            pages: LruCache::unbounded(),
+        }
+    }
+}
+
+impl Replacer for LruReplacer {
+    fn record_access(&mut self, key: PageKey, background: bool) {
+        if !background {
+            self.pages.push(key, ());
+        } else if !self.pages.contains(&key) {
+            self.pages.push(key, ());
+            self.pages.demote(&key);
+        }
+    }
+
+    fn remove(&mut self, key: &PageKey) {
+        self.pages.pop(key);
+    }
+
+    fn victim(&mut self, evictable: &mut dyn FnMut(&PageKey) -> bool) -> Option<PageKey> {
+        // `iter` runs from most to least recently used
+        self.pages
+            .iter()
+            .rev()
+            .map(|(key, _)| *key)
+            .find(|key| evictable(key))
+    }
+}
+
+/// Evicts the first page without its reference bit that a hand sweeping
+/// the cached pages finds, clearing the bits it passes.
+///
+/// A page's bit is set when it is used again, not when it is cached, so a
+/// page used once is evicted the first time the hand reaches it.
+#[derive(Debug, Default)]
+pub struct ClockReplacer {
+    /// Pages in sweep order, starting at the hand.
+    ring: VecDeque<PageKey>,
+    referenced: HashMap<PageKey, bool>,
+}
+
+impl Replacer for ClockReplacer {
+    fn record_access(&mut self, key: PageKey, background: bool) {
+        match self.referenced.get_mut(&key) {
+            Some(referenced) => *referenced |= !background,
+            None => {
+                // Behind the hand, so it is the last page the sweep reaches
+                self.ring.push_back(key);
+                self.referenced.insert(key, false);
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &PageKey) {
+        if self.referenced.remove(key).is_some()
+            && let Some(position) = self.ring.iter().position(|cached| cached == key)
+        {
+            self.ring.remove(position);
+        }
+    }
+
+    fn victim(&mut self, evictable: &mut dyn FnMut(&PageKey) -> bool) -> Option<PageKey> {
+        // Two turns clear every bit, so a page that can be evicted is found
+        for _ in 0..2 * self.ring.len() {
+            let key = *self.ring.front()?;
+            if evictable(&key) {
+                let referenced = self.referenced.get_mut(&key).expect("tracked page");
+                if !*referenced {
+                    return Some(key);
+                }
+                *referenced = false;
+            }
+            self.ring.rotate_left(1);
+        }
+        None
+    }
+}
+
+/// Evicts the page whose K-th most recent use is oldest, or, before that,
+/// the least recently used of the pages used fewer than K times.
+///
+/// Uses are only counted while a page is cached.
+#[derive(Debug)]
+pub struct LruKReplacer {
+    k: usize,
+    /// Ticks of each page's last K uses, oldest first.
+    history: HashMap<PageKey, VecDeque<u64>>,
+    tick: u64,
+}
+
+impl LruKReplacer {
+    pub fn new(k: NonZeroUsize) -> Self {
+        Self {
+            k: k.get(),
+            history: HashMap::new(),
+            tick: 0,
+        }
+    }
+}
+
+impl Replacer for LruKReplacer {
+    fn record_access(&mut self, key: PageKey, background: bool) {
+        self.tick += 1;
+        let uses = self.history.entry(key).or_default();
+        if background {
+            // Cached as used once, longer ago than any other use
+            if uses.is_empty() {
+                uses.push_back(0);
+            }
+            return;
+        }
+        uses.push_back(self.tick);
+        if uses.len() > self.k {
+            uses.pop_front();
+        }
+    }
+
+    fn remove(&mut self, key: &PageKey) {
+        self.history.remove(key);
+    }
+
+    fn victim(&mut self, evictable: &mut dyn FnMut(&PageKey) -> bool) -> Option<PageKey> {
+        // Fewer than K uses is an infinite backward K-distance, ranked first
+        self.history
+            .iter()
+            .filter(|(key, _)| evictable(key))
+            .min_by_key(|(_, uses)| {
+                if uses.len() >= self.k {
+                    (true, uses[0])
+                } else {
+                    (false, *uses.back().unwrap_or(&0))
+                }
+            })
+            .map(|(key, _)| *key)
+    }
+}
+
+/// Keeps pages used once in a FIFO queue and pages used again in an LRU
+/// list, the simplified form of the 2Q algorithm.
+///
+/// The queue is evicted from first while it holds more than a quarter of
+/// the cached pages, so a scan churns through it without reaching the list.
+#[derive(Debug)]
+pub struct TwoQReplacer {
+    /// Pages used once, oldest first.
+    once: VecDeque<PageKey>,
+    /// Pages used again.
+    again: LruCache<PageKey, ()>,
+}
+
+impl Default for TwoQReplacer {
+    fn default() -> Self {
+        Self {
+            once: VecDeque::new(),
+            again: LruCache::unbounded(),
+        }
+    }
+}
+
+impl Replacer for TwoQReplacer {
+    fn record_access(&mut self, key: PageKey, background: bool) {
+        if self.again.contains(&key) {
+            if !background {
+                self.again.promote(&key);
+            }
+            return;
+        }
+        match self.once.iter().position(|queued| *queued == key) {
+            Some(position) if !background => {
+                self.once.remove(position);
+                self.again.push(key, ());
+            }
+            Some(_) => {}
+            None => self.once.push_back(key),
+        }
+    }
+
+    fn remove(&mut self, key: &PageKey) {
+        if self.again.pop(key).is_none()
+            && let Some(position) = self.once.iter().position(|queued| queued == key)
+        {
+            self.once.remove(position);
+        }
+    }
+
+    fn victim(&mut self, evictable: &mut dyn FnMut(&PageKey) -> bool) -> Option<PageKey> {
+        if self.once.len() * 4 > self.once.len() + self.again.len()
+            && let Some(key) = self.once.iter().copied().find(|key| evictable(key))
+        {
+            return Some(key);
+        }
+        self.again
+            .iter()
+            .rev()
+            .map(|(key, _)| *key)
+            .find(|key| evictable(key))
+            .or_else(|| self.once.iter().copied().find(|key| evictable(key)))
+    }
+}
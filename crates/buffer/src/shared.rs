@@ -0,0 +1,144 @@
+//! A buffer pool shared between threads and heap files.
+//!
+//! [`SharedPager`] is a cloneable handle on one [`FilePager`]. Each clone is
+//! a [`Pager`] of its own, and heap files opened through a
+//! [`storage::HeapEngine`] given the handle with
+//! [`storage::HeapEngine::with_page_io`] read their pages through the same
+//! cache, so a page read by one statement stays in memory for the next.
+//! Handles on different threads use pages of the pool concurrently (see
+//! [Concurrency](crate#concurrency)).
+//!
+//! Heap pages are written through: a write caches the page as dirty and
+//! writes it as eviction would, syncing the log through the pool's
+//! [`LogSync`](crate::LogSync) first, so it reaches the table's file before
+//! it returns (see [`storage::page_io`]) and leaves the cached copy clean.
+//! Reads and writes are checked by the pool's [`FaultInjector`], not the
+//! heap file's. Heap files with an encryption key are read and written
+//! directly, since the pool keeps pages unencrypted in their files.
+//!
+//! [`FaultInjector`]: common::hooks::FaultInjector
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use common::compression::Compression;
+use common::crypto::EncryptionKey;
+use common::{DbResult, PageId, Priority, TableId};
+use storage::{FileIo, Page, PageIo, PageIoSource};
+
+use crate::{FilePager, PageReadGuard, PageWriteGuard, Pager};
+
+/// Cloneable handle on a buffer pool (see the [module docs](self)).
+///
+/// As a [`Pager`], a handle fetches pages from the pool in place, so a page
+/// written through one handle is seen by every other.
+#[derive(Clone, Debug)]
+pub struct SharedPager {
+    pool: Arc<FilePager>,
+}
+
+impl SharedPager {
+    pub fn new(pager: FilePager) -> Self {
+        Self {
+            pool: Arc::new(pager),
+        }
+    }
+
+    /// The pool, for example to resize it or read its statistics.
+    pub fn pool(&self) -> &FilePager {
+        &self.pool
+    }
+}
+
+impl Pager for SharedPager {
+    fn fetch_page(&self, table: TableId, pid: PageId) -> DbResult<PageReadGuard<'_>> {
+        self.pool.fetch_page(table, pid)
+    }
+
+    fn fetch_page_mut(&self, table: TableId, pid: PageId) -> DbResult<PageWriteGuard<'_>> {
+        self.pool.fetch_page_mut(table, pid)
+    }
+
+    fn allocate_page(&self, table: TableId) -> DbResult<PageId> {
+        self.pool.allocate_page(table)
+    }
+
+    fn flush(&self) -> DbResult<()> {
+        self.pool.flush()
+    }
+
+    fn set_priority(&self, priority: Priority) {
+        self.pool.set_priority(priority);
+    }
+}
+
+impl PageIoSource for SharedPager {
+    fn open(
+        &self,
+        path: &Path,
+        table_id: u64,
+        key: Option<&EncryptionKey>,
+    ) -> DbResult<Box<dyn PageIo>> {
+        if key.is_some() {
+            return Ok(Box::new(FileIo::open(path, table_id, key)?));
+        }
+        Ok(Box::new(PooledIo {
+            pool: self.pool.clone(),
+            table: TableId(table_id),
+            path: path.to_path_buf(),
+            compression: Compression::None,
+        }))
+    }
+
+    fn forget(&self, path: &Path, table_id: u64) -> DbResult<()> {
+        if self.pool.attached(TableId(table_id), path) {
+            self.pool.forget_table(TableId(table_id))?;
+        }
+        Ok(())
+    }
+}
+
+/// Page I/O of one heap file through a shared pool.
+#[derive(Debug)]
+struct PooledIo {
+    pool: Arc<FilePager>,
+    table: TableId,
+    path: PathBuf,
+    compression: Compression,
+}
+
+impl PooledIo {
+    /// The pool, with the table attached to this file and compressed as
+    /// this file asks.
+    fn pool(&self) -> DbResult<&FilePager> {
+        self.pool.attach_file(self.table, &self.path)?;
+        self.pool.set_compression(self.table, self.compression);
+        Ok(&self.pool)
+    }
+}
+
+impl PageIo for PooledIo {
+    fn num_pages(&self) -> DbResult<u64> {
+        self.pool()?.page_count(self.table)
+    }
+
+    fn read_page(&mut self, id: u64) -> DbResult<Page> {
+        Ok(self.pool()?.fetch_page(self.table, PageId(id))?.clone())
+    }
+
+    fn write_page(&mut self, page: &Page) -> DbResult<()> {
+        self.pool()?.write_through(self.table, page)
+    }
+
+    fn sync(&mut self) -> DbResult<()> {
+        self.pool()?.sync_table(self.table)
+    }
+
+    fn reopen(&mut self) -> DbResult<()> {
+        self.pool()?.forget_table(self.table)
+    }
+
+    fn set_compression(&mut self, compression: Compression) {
+        self.compression = compression;
+    }
+}
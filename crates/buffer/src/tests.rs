@@ -1,23 +1,58 @@
 use super::*;
+use common::hooks::{FaultPlan, ManualClock};
+use std::fs;
+use std::time::Instant;
 use tempfile::tempdir;
 
+/// Whether `pager` caches a page.
+fn cached(pager: &FilePager, key: PageKey) -> bool {
+    lock(&pager.shards[pager.shard_index(key)])
+        .cache
+        .contains_key(&key)
+}
+
+/// Whether `pager` caches a page changed since it was last written.
+fn dirty(pager: &FilePager, key: PageKey) -> bool {
+    lock(&pager.shards[pager.shard_index(key)])
+        .dirty
+        .contains(&key)
+}
+
+/// Number of pages `pager` caches.
+fn cached_pages(pager: &FilePager) -> usize {
+    pager
+        .lock_shards()
+        .iter()
+        .map(|shard| shard.cache.len())
+        .sum()
+}
+
+/// Number of cached pages changed since they were last written.
+fn dirty_pages(pager: &FilePager) -> usize {
+    pager
+        .lock_shards()
+        .iter()
+        .map(|shard| shard.dirty.len())
+        .sum()
+}
+
 #[test]
 fn allocate_and_fetch_persist_pages() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 2);
+    let pager = FilePager::new(dir.path(), 2);
     let table = TableId(1);
 
     // Allocate and modify a page
     let pid = pager.allocate_page(table).unwrap();
     {
-        let page = pager.fetch_page(table, pid).unwrap();
+        let mut page = pager.fetch_page_mut(table, pid).unwrap();
         page.data[0..4].copy_from_slice(&[1, 2, 3, 4]);
     }
 
     pager.flush().unwrap();
 
     // New pager should read the same page
-    let mut pager2 = FilePager::new(dir.path(), 2);
+    let pager2 = FilePager::new(dir.path(), 2);
     let page2 = pager2.fetch_page(table, pid).unwrap();
     assert_eq!(&page2.data[0..4], &[1, 2, 3, 4]);
 }
@@ -25,18 +60,18 @@ fn allocate_and_fetch_persist_pages() {
 #[test]
 fn lru_eviction_flushes_dirty_pages() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 1);
+    let pager = FilePager::new(dir.path(), 1);
     let table = TableId(1);
 
     let pid1 = pager.allocate_page(table).unwrap();
-    pager.fetch_page(table, pid1).unwrap().data[0] = 99;
+    pager.fetch_page_mut(table, pid1).unwrap().data[0] = 99;
 
     // Allocate another, should evict the first and flush it
     let _pid2 = pager.allocate_page(table).unwrap();
     pager.flush().unwrap();
 
     // Verify persisted data
-    let mut pager2 = FilePager::new(dir.path(), 2);
+    let pager2 = FilePager::new(dir.path(), 2);
     let p = pager2.fetch_page(table, pid1).unwrap();
     assert_eq!(p.data[0], 99);
 }
@@ -44,7 +79,7 @@ fn lru_eviction_flushes_dirty_pages() {
 #[test]
 fn allocate_sequential_page_ids() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 10);
+    let pager = FilePager::new(dir.path(), 10);
     let table = TableId(1);
 
     let pid0 = pager.allocate_page(table).unwrap();
@@ -59,7 +94,7 @@ fn allocate_sequential_page_ids() {
 #[test]
 fn fetch_page_updates_lru_order() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 2);
+    let pager = FilePager::new(dir.path(), 2);
     let table = TableId(1);
 
     let pid0 = pager.allocate_page(table).unwrap();
@@ -72,7 +107,7 @@ fn fetch_page_updates_lru_order() {
     let pid2 = pager.allocate_page(table).unwrap();
 
     // pid0 should still be in cache (no disk read needed)
-    pager.fetch_page(table, pid0).unwrap().data[0] = 77;
+    pager.fetch_page_mut(table, pid0).unwrap().data[0] = 77;
 
     // pid1 should have been evicted (requires disk read)
     pager.fetch_page(table, pid1).unwrap();
@@ -81,30 +116,166 @@ fn fetch_page_updates_lru_order() {
     assert_eq!(pid2, PageId(2));
 }
 
+#[test]
+fn background_fetches_are_evicted_first() {
+    let dir = tempdir().unwrap();
+    let pager = FilePager::new(dir.path(), 3);
+    let table = TableId(1);
+    let pids: Vec<_> = (0..5)
+        .map(|_| pager.allocate_page(table).unwrap())
+        .collect();
+    pager.flush().unwrap();
+
+    pager.fetch_page(table, pids[0]).unwrap();
+    pager.fetch_page(table, pids[1]).unwrap();
+
+    // A background scan cycles its pages through a single slot
+    pager.set_priority(Priority::Background);
+    for &pid in &pids[2..] {
+        pager.fetch_page(table, pid).unwrap();
+    }
+    // ...and does not promote pages it finds cached
+    pager.fetch_page(table, pids[0]).unwrap();
+    assert!(cached(&pager, (table, pids[0])));
+    assert!(cached(&pager, (table, pids[1])));
+    assert!(cached(&pager, (table, pids[4])));
+
+    pager.set_priority(Priority::Normal);
+    pager.fetch_page(table, pids[2]).unwrap();
+    assert!(!cached(&pager, (table, pids[4])));
+    assert!(cached(&pager, (table, pids[0])));
+}
+
+#[test]
+fn priority_is_set_per_thread() {
+    let dir = tempdir().unwrap();
+    let pager = FilePager::new(dir.path(), 3);
+    let table = TableId(1);
+    let pids: Vec<_> = (0..5)
+        .map(|_| pager.allocate_page(table).unwrap())
+        .collect();
+    pager.flush().unwrap();
+
+    let background_set = std::sync::Barrier::new(2);
+    let normal_done = std::sync::Barrier::new(2);
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            pager.set_priority(Priority::Background);
+            background_set.wait();
+            normal_done.wait();
+            for &pid in &pids[2..] {
+                pager.fetch_page(table, pid).unwrap();
+            }
+        });
+        s.spawn(|| {
+            // Fetches at normal priority while the other thread is at
+            // background priority
+            background_set.wait();
+            pager.fetch_page(table, pids[0]).unwrap();
+            pager.fetch_page(table, pids[1]).unwrap();
+            normal_done.wait();
+        });
+    });
+
+    // The background scan evicted only its own pages
+    assert!(cached(&pager, (table, pids[0])));
+    assert!(cached(&pager, (table, pids[1])));
+    assert!(cached(&pager, (table, pids[4])));
+}
+
+#[test]
+fn scan_resistant_policies_keep_pages_used_again() {
+    let dir = tempdir().unwrap();
+    let table = TableId(1);
+    {
+        let pager = FilePager::new(dir.path(), 4);
+        for _ in 0..12 {
+            pager.allocate_page(table).unwrap();
+        }
+        pager.flush().unwrap();
+    }
+
+    let lru_k = ReplacementPolicy::LruK(NonZeroUsize::new(2).unwrap());
+    for (policy, scanned, kept) in [
+        (ReplacementPolicy::Lru, 4, false),
+        (ReplacementPolicy::Clock, 4, true),
+        (lru_k, 10, true),
+        (ReplacementPolicy::TwoQ, 10, true),
+    ] {
+        let pager = FilePager::new(dir.path(), 4).with_replacement_policy(policy);
+        // The working set, used twice
+        for pid in [0, 1, 0, 1] {
+            pager.fetch_page(table, PageId(pid)).unwrap();
+        }
+
+        for pid in 2..2 + scanned {
+            pager.fetch_page(table, PageId(pid)).unwrap();
+        }
+        for pid in [0, 1] {
+            let cached = cached(&pager, (table, PageId(pid)));
+            assert_eq!(cached, kept, "{policy:?}");
+        }
+        assert_eq!(cached_pages(&pager), 4, "{policy:?}");
+    }
+}
+
+#[test]
+fn replacers_skip_pages_that_cannot_be_evicted() {
+    let table = TableId(1);
+    let lru_k = ReplacementPolicy::LruK(NonZeroUsize::new(2).unwrap());
+    for policy in [
+        ReplacementPolicy::Lru,
+        ReplacementPolicy::Clock,
+        lru_k,
+        ReplacementPolicy::TwoQ,
+    ] {
+        let mut replacer = policy.replacer();
+        for pid in [0, 1, 2, 1] {
+            replacer.record_access((table, PageId(pid)), false);
+        }
+        assert_eq!(
+            replacer.victim(&mut |_| true),
+            Some((table, PageId(0))),
+            "{policy:?}"
+        );
+        let victim = replacer.victim(&mut |(_, pid)| pid.0 != 0);
+        assert_eq!(victim, Some((table, PageId(2))), "{policy:?}");
+
+        replacer.remove(&(table, PageId(2)));
+        replacer.remove(&(table, PageId(0)));
+        assert_eq!(
+            replacer.victim(&mut |_| true),
+            Some((table, PageId(1))),
+            "{policy:?}"
+        );
+        assert_eq!(replacer.victim(&mut |_| false), None, "{policy:?}");
+    }
+}
+
 #[test]
 fn dirty_tracking_only_writes_modified_pages() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 3);
+    let pager = FilePager::new(dir.path(), 3);
     let table = TableId(1);
 
     let pid0 = pager.allocate_page(table).unwrap();
     let _pid1 = pager.allocate_page(table).unwrap();
 
     // Modify only pid0
-    pager.fetch_page(table, pid0).unwrap().data[0] = 42;
+    pager.fetch_page_mut(table, pid0).unwrap().data[0] = 42;
 
     // Flush should only write pid0 (pid1 is not dirty after allocation flush)
     pager.flush().unwrap();
 
     // Verify persistence
-    let mut pager2 = FilePager::new(dir.path(), 2);
+    let pager2 = FilePager::new(dir.path(), 2);
     assert_eq!(pager2.fetch_page(table, pid0).unwrap().data[0], 42);
 }
 
 #[test]
 fn multiple_tables_isolated() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 10);
+    let pager = FilePager::new(dir.path(), 10);
     let table1 = TableId(1);
     let table2 = TableId(2);
 
@@ -117,13 +288,13 @@ fn multiple_tables_isolated() {
     assert_eq!(pid1_t2, PageId(0));
 
     // Modify each page differently
-    pager.fetch_page(table1, pid1_t1).unwrap().data[0] = 10;
-    pager.fetch_page(table2, pid1_t2).unwrap().data[0] = 20;
+    pager.fetch_page_mut(table1, pid1_t1).unwrap().data[0] = 10;
+    pager.fetch_page_mut(table2, pid1_t2).unwrap().data[0] = 20;
 
     pager.flush().unwrap();
 
     // Verify isolation
-    let mut pager2 = FilePager::new(dir.path(), 10);
+    let pager2 = FilePager::new(dir.path(), 10);
     assert_eq!(pager2.fetch_page(table1, pid1_t1).unwrap().data[0], 10);
     assert_eq!(pager2.fetch_page(table2, pid1_t2).unwrap().data[0], 20);
 }
@@ -131,7 +302,7 @@ fn multiple_tables_isolated() {
 #[test]
 fn fetch_nonexistent_page_returns_initialized_page() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 10);
+    let pager = FilePager::new(dir.path(), 10);
     let table = TableId(1);
 
     // Fetch a page that doesn't exist yet (beyond allocated pages)
@@ -147,15 +318,15 @@ fn fetch_nonexistent_page_returns_initialized_page() {
 #[test]
 fn eviction_writes_dirty_pages_before_removal() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 2);
+    let pager = FilePager::new(dir.path(), 2);
     let table = TableId(1);
 
     let pid0 = pager.allocate_page(table).unwrap();
     let pid1 = pager.allocate_page(table).unwrap();
 
     // Modify both pages
-    pager.fetch_page(table, pid0).unwrap().data[0] = 11;
-    pager.fetch_page(table, pid1).unwrap().data[1] = 22;
+    pager.fetch_page_mut(table, pid0).unwrap().data[0] = 11;
+    pager.fetch_page_mut(table, pid1).unwrap().data[1] = 22;
 
     // Allocate a third page, forcing eviction of pid0 (LRU)
     let _pid2 = pager.allocate_page(table).unwrap();
@@ -164,7 +335,7 @@ fn eviction_writes_dirty_pages_before_removal() {
     pager.flush().unwrap();
 
     // Verify both modifications persisted
-    let mut pager2 = FilePager::new(dir.path(), 3);
+    let pager2 = FilePager::new(dir.path(), 3);
     assert_eq!(pager2.fetch_page(table, pid0).unwrap().data[0], 11);
     assert_eq!(pager2.fetch_page(table, pid1).unwrap().data[1], 22);
 }
@@ -172,14 +343,14 @@ fn eviction_writes_dirty_pages_before_removal() {
 #[test]
 fn large_page_modifications_persist() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 5);
+    let pager = FilePager::new(dir.path(), 5);
     let table = TableId(1);
 
     let pid = pager.allocate_page(table).unwrap();
 
     // Fill entire page with pattern
     {
-        let page = pager.fetch_page(table, pid).unwrap();
+        let mut page = pager.fetch_page_mut(table, pid).unwrap();
         for i in 0..PAGE_SIZE {
             page.data[i] = (i % 256) as u8;
         }
@@ -188,7 +359,7 @@ fn large_page_modifications_persist() {
     pager.flush().unwrap();
 
     // Verify entire page pattern
-    let mut pager2 = FilePager::new(dir.path(), 5);
+    let pager2 = FilePager::new(dir.path(), 5);
     let page2 = pager2.fetch_page(table, pid).unwrap();
     for i in 0..PAGE_SIZE {
         assert_eq!(page2.data[i], (i % 256) as u8, "Mismatch at offset {}", i);
@@ -205,18 +376,18 @@ fn new_pager_panics_with_zero_capacity() {
 #[test]
 fn flush_empty_pager_succeeds() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 10);
+    let pager = FilePager::new(dir.path(), 10);
     pager.flush().unwrap();
 }
 
 #[test]
 fn refetch_after_eviction_reloads_from_disk() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 1);
+    let pager = FilePager::new(dir.path(), 1);
     let table = TableId(1);
 
     let pid0 = pager.allocate_page(table).unwrap();
-    pager.fetch_page(table, pid0).unwrap().data[0] = 55;
+    pager.fetch_page_mut(table, pid0).unwrap().data[0] = 55;
 
     // Allocate another page, evicting pid0
     let _pid1 = pager.allocate_page(table).unwrap();
@@ -229,20 +400,21 @@ fn refetch_after_eviction_reloads_from_disk() {
 #[test]
 fn cache_hit_does_not_reload() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 10);
+    let pager = FilePager::new(dir.path(), 10);
     let table = TableId(1);
 
     let pid = pager.allocate_page(table).unwrap();
 
     // First fetch (cache miss, loads from disk)
-    pager.fetch_page(table, pid).unwrap().data[0] = 100;
+    pager.fetch_page_mut(table, pid).unwrap().data[0] = 100;
 
     // Second fetch should be cache hit (no disk I/O)
-    let page = pager.fetch_page(table, pid).unwrap();
+    let mut page = pager.fetch_page_mut(table, pid).unwrap();
     assert_eq!(page.data[0], 100);
 
     // Modify again
     page.data[1] = 200;
+    drop(page);
 
     // Third fetch still cache hit
     let page2 = pager.fetch_page(table, pid).unwrap();
@@ -253,7 +425,7 @@ fn cache_hit_does_not_reload() {
 #[test]
 fn multiple_evictions_in_sequence() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 2);
+    let pager = FilePager::new(dir.path(), 2);
     let table = TableId(1);
 
     // Fill cache
@@ -261,20 +433,20 @@ fn multiple_evictions_in_sequence() {
     let pid1 = pager.allocate_page(table).unwrap();
 
     // Modify both
-    pager.fetch_page(table, pid0).unwrap().data[0] = 10;
-    pager.fetch_page(table, pid1).unwrap().data[0] = 20;
+    pager.fetch_page_mut(table, pid0).unwrap().data[0] = 10;
+    pager.fetch_page_mut(table, pid1).unwrap().data[0] = 20;
 
     // Allocate more pages, causing multiple evictions
     let pid2 = pager.allocate_page(table).unwrap();
-    pager.fetch_page(table, pid2).unwrap().data[0] = 30;
+    pager.fetch_page_mut(table, pid2).unwrap().data[0] = 30;
 
     let pid3 = pager.allocate_page(table).unwrap();
-    pager.fetch_page(table, pid3).unwrap().data[0] = 40;
+    pager.fetch_page_mut(table, pid3).unwrap().data[0] = 40;
 
     // Flush and verify all persisted
     pager.flush().unwrap();
 
-    let mut pager2 = FilePager::new(dir.path(), 4);
+    let pager2 = FilePager::new(dir.path(), 4);
     assert_eq!(pager2.fetch_page(table, pid0).unwrap().data[0], 10);
     assert_eq!(pager2.fetch_page(table, pid1).unwrap().data[0], 20);
     assert_eq!(pager2.fetch_page(table, pid2).unwrap().data[0], 30);
@@ -284,7 +456,7 @@ fn multiple_evictions_in_sequence() {
 #[test]
 fn flush_with_no_dirty_pages() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 5);
+    let pager = FilePager::new(dir.path(), 5);
     let table = TableId(1);
 
     // Allocate pages
@@ -304,7 +476,7 @@ fn flush_with_no_dirty_pages() {
 #[test]
 fn concurrent_operations_on_different_tables() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 5);
+    let pager = FilePager::new(dir.path(), 5);
     let table1 = TableId(1);
     let table2 = TableId(2);
     let table3 = TableId(3);
@@ -317,16 +489,16 @@ fn concurrent_operations_on_different_tables() {
     let t2p1 = pager.allocate_page(table2).unwrap();
 
     // Modify pages
-    pager.fetch_page(table1, t1p0).unwrap().data[0] = 1;
-    pager.fetch_page(table2, t2p0).unwrap().data[0] = 2;
-    pager.fetch_page(table1, t1p1).unwrap().data[0] = 3;
-    pager.fetch_page(table3, t3p0).unwrap().data[0] = 4;
-    pager.fetch_page(table2, t2p1).unwrap().data[0] = 5;
+    pager.fetch_page_mut(table1, t1p0).unwrap().data[0] = 1;
+    pager.fetch_page_mut(table2, t2p0).unwrap().data[0] = 2;
+    pager.fetch_page_mut(table1, t1p1).unwrap().data[0] = 3;
+    pager.fetch_page_mut(table3, t3p0).unwrap().data[0] = 4;
+    pager.fetch_page_mut(table2, t2p1).unwrap().data[0] = 5;
 
     pager.flush().unwrap();
 
     // Verify isolation
-    let mut pager2 = FilePager::new(dir.path(), 10);
+    let pager2 = FilePager::new(dir.path(), 10);
     assert_eq!(pager2.fetch_page(table1, t1p0).unwrap().data[0], 1);
     assert_eq!(pager2.fetch_page(table2, t2p0).unwrap().data[0], 2);
     assert_eq!(pager2.fetch_page(table1, t1p1).unwrap().data[0], 3);
@@ -337,7 +509,7 @@ fn concurrent_operations_on_different_tables() {
 #[test]
 fn lru_ordering_with_mixed_operations() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 3);
+    let pager = FilePager::new(dir.path(), 3);
     let table = TableId(1);
 
     let pid0 = pager.allocate_page(table).unwrap();
@@ -347,12 +519,12 @@ fn lru_ordering_with_mixed_operations() {
     // LRU order: pid0 (oldest), pid1, pid2 (newest)
 
     // Access pid0 to make it most recently used
-    pager.fetch_page(table, pid0).unwrap().data[0] = 100;
+    pager.fetch_page_mut(table, pid0).unwrap().data[0] = 100;
     // LRU order: pid1 (oldest), pid2, pid0 (newest)
 
     // Allocate new page, should evict pid1
     let pid3 = pager.allocate_page(table).unwrap();
-    pager.fetch_page(table, pid3).unwrap().data[0] = 200;
+    pager.fetch_page_mut(table, pid3).unwrap().data[0] = 200;
 
     // pid0 should still be in cache (was most recently used)
     assert_eq!(pager.fetch_page(table, pid0).unwrap().data[0], 100);
@@ -364,7 +536,7 @@ fn lru_ordering_with_mixed_operations() {
 #[test]
 fn allocate_many_pages_sequential() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 10);
+    let pager = FilePager::new(dir.path(), 10);
     let table = TableId(1);
 
     let mut page_ids = Vec::new();
@@ -377,7 +549,7 @@ fn allocate_many_pages_sequential() {
     pager.flush().unwrap();
 
     // Verify all pages exist and are sequential
-    let mut pager2 = FilePager::new(dir.path(), 25);
+    let pager2 = FilePager::new(dir.path(), 25);
     for (i, &pid) in page_ids.iter().enumerate() {
         let page = pager2.fetch_page(table, pid).unwrap();
         assert_eq!(page.id, i as u64);
@@ -387,25 +559,25 @@ fn allocate_many_pages_sequential() {
 #[test]
 fn page_modifications_across_fetch_calls() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 5);
+    let pager = FilePager::new(dir.path(), 5);
     let table = TableId(1);
 
     let pid = pager.allocate_page(table).unwrap();
 
     // Multiple fetch and modify operations
     {
-        let page = pager.fetch_page(table, pid).unwrap();
+        let mut page = pager.fetch_page_mut(table, pid).unwrap();
         page.data[0] = 1;
     }
 
     {
-        let page = pager.fetch_page(table, pid).unwrap();
+        let mut page = pager.fetch_page_mut(table, pid).unwrap();
         assert_eq!(page.data[0], 1);
         page.data[1] = 2;
     }
 
     {
-        let page = pager.fetch_page(table, pid).unwrap();
+        let mut page = pager.fetch_page_mut(table, pid).unwrap();
         assert_eq!(page.data[0], 1);
         assert_eq!(page.data[1], 2);
         page.data[2] = 3;
@@ -413,7 +585,7 @@ fn page_modifications_across_fetch_calls() {
 
     pager.flush().unwrap();
 
-    let mut pager2 = FilePager::new(dir.path(), 5);
+    let pager2 = FilePager::new(dir.path(), 5);
     let page = pager2.fetch_page(table, pid).unwrap();
     assert_eq!(page.data[0], 1);
     assert_eq!(page.data[1], 2);
@@ -423,7 +595,7 @@ fn page_modifications_across_fetch_calls() {
 #[test]
 fn eviction_of_clean_page() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 2);
+    let pager = FilePager::new(dir.path(), 2);
     let table = TableId(1);
 
     let pid0 = pager.allocate_page(table).unwrap();
@@ -442,7 +614,7 @@ fn eviction_of_clean_page() {
 #[test]
 fn table_path_format() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 5);
+    let pager = FilePager::new(dir.path(), 5);
 
     // Allocate page to trigger file creation
     pager.allocate_page(TableId(123)).unwrap();
@@ -456,17 +628,803 @@ fn table_path_format() {
 #[test]
 fn multiple_flushes_idempotent() {
     let dir = tempdir().unwrap();
-    let mut pager = FilePager::new(dir.path(), 5);
+    let pager = FilePager::new(dir.path(), 5);
     let table = TableId(1);
 
     let pid = pager.allocate_page(table).unwrap();
-    pager.fetch_page(table, pid).unwrap().data[0] = 99;
+    pager.fetch_page_mut(table, pid).unwrap().data[0] = 99;
 
     // Multiple flushes should be safe
     pager.flush().unwrap();
     pager.flush().unwrap();
     pager.flush().unwrap();
 
-    let mut pager2 = FilePager::new(dir.path(), 5);
+    let pager2 = FilePager::new(dir.path(), 5);
     assert_eq!(pager2.fetch_page(table, pid).unwrap().data[0], 99);
 }
+
+#[test]
+fn eviction_skips_pinned_pages() {
+    let dir = tempdir().unwrap();
+    let pager = FilePager::new(dir.path(), 2);
+    let table = TableId(1);
+
+    let pid0 = pager.allocate_page(table).unwrap();
+    let pid1 = pager.allocate_page(table).unwrap();
+    pager.pin_page(table, pid0).unwrap();
+    pager.fetch_page_mut(table, pid0).unwrap().data[0] = 7;
+    // Touch pid1 so the pinned page is least recently used
+    pager.fetch_page(table, pid1).unwrap();
+
+    let _pid2 = pager.allocate_page(table).unwrap();
+
+    // pid0 was kept cached, pid1 was evicted
+    assert_eq!(cached_pages(&pager), 2);
+    assert!(cached(&pager, (table, pid0)));
+    assert!(!cached(&pager, (table, pid1)));
+    assert_eq!(pager.pins().pin_count(table, pid0), 1);
+}
+
+#[test]
+fn fully_pinned_pool_fails_after_wait() {
+    let dir = tempdir().unwrap();
+    let pager = FilePager::new(dir.path(), 2).with_pin_wait(Duration::from_millis(20));
+    let table = TableId(1);
+
+    let pid0 = pager.allocate_page(table).unwrap();
+    let pid1 = pager.allocate_page(table).unwrap();
+    pager.pin_page(table, pid0).unwrap();
+    pager.pin_page(table, pid1).unwrap();
+
+    let started = Instant::now();
+    let err = pager.fetch_page(table, PageId(5)).unwrap_err();
+    assert!(started.elapsed() >= Duration::from_millis(20));
+    assert!(matches!(err, DbError::BufferPoolExhausted(_)), "{err}");
+
+    // Releasing a pin makes room again
+    pager.unpin_page(table, pid1).unwrap();
+    pager.fetch_page(table, PageId(5)).unwrap();
+    assert!(!cached(&pager, (table, pid1)));
+}
+
+#[test]
+fn shrinking_the_pool_writes_evicted_dirty_pages() {
+    let dir = tempdir().unwrap();
+    let pager = FilePager::new(dir.path(), 4).with_pin_wait(Duration::ZERO);
+    let table = TableId(1);
+
+    for value in 0..4 {
+        let pid = pager.allocate_page(table).unwrap();
+        pager.fetch_page_mut(table, pid).unwrap().data[0] = value;
+    }
+    pager.pin_page(table, PageId(2)).unwrap();
+    pager.pin_page(table, PageId(3)).unwrap();
+
+    // Two pinned pages do not fit in one
+    let err = pager.set_max_pages(1).unwrap_err();
+    assert!(matches!(err, DbError::BufferPoolExhausted(_)), "{err}");
+    assert_eq!(pager.max_pages(), 4);
+    pager.set_max_pages(2).unwrap();
+    assert_eq!(pager.max_pages(), 2);
+    assert_eq!(cached_pages(&pager), 2);
+
+    let reopened = FilePager::new(dir.path(), 4);
+    assert_eq!(reopened.fetch_page(table, PageId(0)).unwrap().data[0], 0);
+    assert_eq!(reopened.fetch_page(table, PageId(1)).unwrap().data[0], 1);
+
+    pager.set_max_pages(8).unwrap();
+    assert!(pager.set_max_pages(0).is_err());
+    assert_eq!(pager.max_pages(), 8);
+}
+
+#[test]
+fn waiting_load_proceeds_when_another_thread_unpins() {
+    let dir = tempdir().unwrap();
+    let pager = FilePager::new(dir.path(), 1).with_pin_wait(Duration::from_secs(10));
+    let table = TableId(1);
+
+    let pid0 = pager.allocate_page(table).unwrap();
+    pager.pin_page(table, pid0).unwrap();
+
+    let pins = pager.pins();
+    let releaser = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        pins.unpin(table, pid0).unwrap();
+    });
+
+    let pid1 = pager.allocate_page(table).unwrap();
+    releaser.join().unwrap();
+    assert!(cached(&pager, (table, pid1)));
+    assert_eq!(pager.pins().pin_count(table, pid0), 0);
+}
+
+#[test]
+fn page_guards_unpin_when_dropped() {
+    let dir = tempdir().unwrap();
+    let pager = FilePager::new(dir.path(), 1).with_pin_wait(Duration::ZERO);
+    let table = TableId(1);
+    let pid0 = pager.allocate_page(table).unwrap();
+    let pins = pager.pins();
+
+    {
+        let mut page = pager.pin_for_write(table, pid0).unwrap();
+        page.data[0] = 42;
+        assert_eq!(pins.pin_count(table, pid0), 1);
+    }
+    assert_eq!(pins.pin_count(table, pid0), 0);
+    assert!(dirty(&pager, (table, pid0)));
+
+    // Unpinned, the dirty page is written when evicted
+    pager.fetch_page(table, PageId(1)).unwrap();
+    assert!(!cached(&pager, (table, pid0)));
+    {
+        let page = pager.pin_for_read(table, pid0).unwrap();
+        assert_eq!(page.data[0], 42);
+        assert_eq!(pins.pin_count(table, pid0), 1);
+    }
+
+    // Pins taken by hand and by a guard are counted together
+    pager.pin_page(table, pid0).unwrap();
+    drop(pager.pin_for_read(table, pid0).unwrap());
+    assert_eq!(pins.pin_count(table, pid0), 1);
+    let err = pager.fetch_page(table, PageId(1)).unwrap_err();
+    assert!(matches!(err, DbError::BufferPoolExhausted(_)), "{err}");
+}
+
+#[test]
+fn unpin_requires_a_pin() {
+    let dir = tempdir().unwrap();
+    let pager = FilePager::new(dir.path(), 2);
+    let table = TableId(1);
+
+    let pid = pager.allocate_page(table).unwrap();
+    pager.pin_page(table, pid).unwrap();
+    pager.pin_page(table, pid).unwrap();
+    assert_eq!(pager.pins().pin_count(table, pid), 2);
+
+    pager.unpin_page(table, pid).unwrap();
+    pager.unpin_page(table, pid).unwrap();
+    assert!(pager.unpin_page(table, pid).is_err());
+}
+
+#[test]
+fn pin_wait_follows_the_injected_clock() {
+    let dir = tempdir().unwrap();
+    let clock = Arc::new(ManualClock::new());
+    let pager = FilePager::new(dir.path(), 1)
+        .with_pin_wait(Duration::from_secs(60))
+        .with_clock(clock.clone());
+    let table = TableId(1);
+
+    let pid0 = pager.allocate_page(table).unwrap();
+    pager.pin_page(table, pid0).unwrap();
+
+    // Fails at once, with the clock moved past the deadline
+    let err = pager.fetch_page(table, PageId(1)).unwrap_err();
+    assert!(matches!(err, DbError::BufferPoolExhausted(_)), "{err}");
+    assert_eq!(clock.elapsed(), Duration::from_secs(60));
+}
+
+#[test]
+fn failed_page_write_keeps_the_page_dirty() {
+    let dir = tempdir().unwrap();
+    // Allocating does not write, so the 1st write is the first flush
+    let faults = Arc::new(FaultPlan::new().fail_nth(IoOp::PageWrite, 1));
+    let pager = FilePager::new(dir.path(), 4).with_faults(faults.clone());
+    let table = TableId(1);
+
+    let pid0 = pager.allocate_page(table).unwrap();
+    let pid1 = pager.allocate_page(table).unwrap();
+    pager.fetch_page_mut(table, pid0).unwrap().data[0] = 1;
+    pager.fetch_page_mut(table, pid1).unwrap().data[0] = 2;
+
+    assert!(pager.flush().is_err());
+    assert_eq!(faults.count(IoOp::PageWrite), 1);
+    assert_ne!(dirty_pages(&pager), 0);
+
+    pager.flush().unwrap();
+    assert_eq!(dirty_pages(&pager), 0);
+    let reopened = FilePager::new(dir.path(), 4);
+    assert_eq!(reopened.fetch_page(table, pid0).unwrap().data[0], 1);
+    assert_eq!(reopened.fetch_page(table, pid1).unwrap().data[0], 2);
+}
+
+#[test]
+fn failed_page_read_is_not_cached() {
+    let dir = tempdir().unwrap();
+    let faults = Arc::new(FaultPlan::new().fail_nth(IoOp::PageRead, 1));
+    let pager = FilePager::new(dir.path(), 2).with_faults(faults);
+    let table = TableId(1);
+
+    let err = pager.fetch_page(table, PageId(0)).unwrap_err();
+    assert!(err.to_string().contains("injected fault"), "{err}");
+    assert_eq!(cached_pages(&pager), 0);
+    pager.fetch_page(table, PageId(0)).unwrap();
+}
+
+#[test]
+fn flush_writes_each_run_of_adjacent_pages_at_once() {
+    let dir = tempdir().unwrap();
+    let faults = Arc::new(FaultPlan::new());
+    let pager = FilePager::new(dir.path(), 8).with_faults(faults.clone());
+    let (t1, t2) = (TableId(1), TableId(2));
+    for _ in 0..4 {
+        pager.allocate_page(t1).unwrap();
+    }
+    pager.allocate_page(t2).unwrap();
+    assert_eq!(faults.count(IoOp::PageWrite), 0);
+
+    // Pages 0-3 of one table and page 0 of the other
+    pager.flush().unwrap();
+    assert_eq!(faults.count(IoOp::PageWrite), 2);
+
+    for (table, pid, byte) in [(t1, 3, 4), (t2, 0, 9), (t1, 0, 1), (t1, 1, 2)] {
+        pager.fetch_page_mut(table, PageId(pid)).unwrap().data[0] = byte;
+    }
+    // Pages 0-1 and 3 of one table and page 0 of the other
+    pager.flush().unwrap();
+    assert_eq!(faults.count(IoOp::PageWrite), 5);
+    assert_eq!(dirty_pages(&pager), 0);
+
+    let reopened = FilePager::new(dir.path(), 8);
+    for (table, pid, byte) in [(t1, 0, 1), (t1, 1, 2), (t1, 2, 0), (t1, 3, 4), (t2, 0, 9)] {
+        assert_eq!(
+            reopened.fetch_page(table, PageId(pid)).unwrap().data[0],
+            byte
+        );
+    }
+}
+
+#[test]
+fn table_files_stay_open_up_to_the_limit() {
+    let dir = tempdir().unwrap();
+    let pager = FilePager::new(dir.path(), 8).with_max_open_files(2);
+    let tables = [TableId(1), TableId(2), TableId(3)];
+
+    for (i, table) in tables.into_iter().enumerate() {
+        let pid = pager.allocate_page(table).unwrap();
+        pager.fetch_page_mut(table, pid).unwrap().data[0] = i as u8 + 1;
+        let file = pager.open_table_file(table).unwrap();
+        assert_eq!(Arc::strong_count(&file), 2, "the pager keeps the file open");
+    }
+    assert_eq!(lock(&pager.files).len(), 2);
+    assert!(!lock(&pager.files).contains(&tables[0]));
+
+    // Dirty pages of a closed file are written through a reopened one
+    pager.flush().unwrap();
+    let pager = FilePager::new(dir.path(), 8).with_max_open_files(2);
+    for (i, table) in tables.into_iter().enumerate() {
+        assert_eq!(
+            pager.fetch_page(table, PageId(0)).unwrap().data[0],
+            i as u8 + 1
+        );
+    }
+    assert_eq!(pager.allocate_page(tables[0]).unwrap(), PageId(1));
+    assert_eq!(lock(&pager.files).len(), 2);
+}
+
+#[test]
+fn allocated_pages_extend_the_file_when_written() {
+    let dir = tempdir().unwrap();
+    let faults = Arc::new(FaultPlan::new());
+    let pager = FilePager::new(dir.path(), 2).with_faults(faults.clone());
+    let table = TableId(1);
+    let file_len = || fs::metadata(dir.path().join("table_1.tbl")).unwrap().len();
+
+    let pids: Vec<_> = (0..3)
+        .map(|_| pager.allocate_page(table).unwrap())
+        .collect();
+    assert_eq!(pids, vec![PageId(0), PageId(1), PageId(2)]);
+    // Page 0 was evicted to make room for page 2
+    assert_eq!(faults.count(IoOp::PageWrite), 1);
+    assert_eq!(file_len(), PAGE_SIZE as u64);
+
+    pager.fetch_page_mut(table, PageId(2)).unwrap().data[0] = 7;
+    pager.flush().unwrap();
+    assert_eq!(file_len(), 3 * PAGE_SIZE as u64);
+
+    let reopened = FilePager::new(dir.path(), 2);
+    assert_eq!(reopened.allocate_page(table).unwrap(), PageId(3));
+    assert_eq!(reopened.fetch_page(table, PageId(2)).unwrap().data[0], 7);
+}
+
+#[test]
+fn pages_fetched_for_writing_are_flushed_again() {
+    let dir = tempdir().unwrap();
+    let faults = Arc::new(FaultPlan::new());
+    let pager = FilePager::new(dir.path(), 4).with_faults(faults.clone());
+    let table = TableId(1);
+    let pid = pager.allocate_page(table).unwrap();
+    pager.flush().unwrap();
+    assert_eq!(faults.count(IoOp::PageWrite), 1);
+
+    // Reading leaves the page clean
+    pager.fetch_page(table, pid).unwrap();
+    pager.flush().unwrap();
+    assert_eq!(faults.count(IoOp::PageWrite), 1);
+
+    pager.fetch_page_mut(table, pid).unwrap().data[0] = 3;
+    assert!(dirty(&pager, (table, pid)));
+    pager.flush().unwrap();
+    assert_eq!(faults.count(IoOp::PageWrite), 2);
+
+    let reopened = FilePager::new(dir.path(), 4);
+    assert_eq!(reopened.fetch_page(table, pid).unwrap().data[0], 3);
+}
+
+#[test]
+fn modified_pages_are_written_when_evicted() {
+    let dir = tempdir().unwrap();
+    let table = TableId(1);
+    {
+        let pager = FilePager::new(dir.path(), 4);
+        for _ in 0..2 {
+            pager.allocate_page(table).unwrap();
+        }
+        pager.flush().unwrap();
+    }
+
+    let faults = Arc::new(FaultPlan::new());
+    let pager = FilePager::new(dir.path(), 2).with_faults(faults.clone());
+    pager.fetch_page_mut(table, PageId(0)).unwrap().data[0] = 8;
+    pager.pin_page(table, PageId(1)).unwrap();
+    pager.fetch_page_mut(table, PageId(1)).unwrap().data[0] = 9;
+    pager.unpin_page(table, PageId(1)).unwrap();
+
+    // Loading two more pages evicts both modified pages
+    pager.fetch_page(table, PageId(2)).unwrap();
+    pager.fetch_page(table, PageId(3)).unwrap();
+    assert_eq!(faults.count(IoOp::PageWrite), 2);
+    assert_eq!(dirty_pages(&pager), 0);
+
+    // Evicting pages that were only read writes nothing
+    pager.fetch_page(table, PageId(0)).unwrap();
+    pager.fetch_page(table, PageId(1)).unwrap();
+    assert_eq!(faults.count(IoOp::PageWrite), 2);
+    assert_eq!(pager.fetch_page(table, PageId(0)).unwrap().data[0], 8);
+    assert_eq!(pager.fetch_page(table, PageId(1)).unwrap().data[0], 9);
+}
+
+/// Records how many pages had been written each time the log was synced.
+#[derive(Debug)]
+struct RecordingLog {
+    faults: Arc<FaultPlan>,
+    writes_at_sync: Mutex<Vec<u64>>,
+    fail: bool,
+}
+
+impl LogSync for RecordingLog {
+    fn sync_log(&self) -> DbResult<()> {
+        self.writes_at_sync
+            .lock()
+            .unwrap()
+            .push(self.faults.count(IoOp::PageWrite));
+        if self.fail {
+            return Err(DbError::Wal("log sync failed".into()));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn log_is_synced_before_a_dirty_page_is_evicted() {
+    let dir = tempdir().unwrap();
+    let faults = Arc::new(FaultPlan::new());
+    let log = Arc::new(RecordingLog {
+        faults: faults.clone(),
+        writes_at_sync: Mutex::new(Vec::new()),
+        fail: false,
+    });
+    let pager = FilePager::new(dir.path(), 1)
+        .with_faults(faults.clone())
+        .with_log_sync(log.clone());
+    let table = TableId(1);
+
+    // Evicting the allocated page syncs the log, then writes the page
+    pager.allocate_page(table).unwrap();
+    pager.fetch_page(table, PageId(1)).unwrap();
+    assert_eq!(*log.writes_at_sync.lock().unwrap(), vec![0]);
+    assert_eq!(faults.count(IoOp::PageWrite), 1);
+
+    // A clean page is dropped without either
+    pager.fetch_page(table, PageId(0)).unwrap();
+    assert_eq!(log.writes_at_sync.lock().unwrap().len(), 1);
+    assert_eq!(faults.count(IoOp::PageWrite), 1);
+    assert_eq!(pager.eviction_stats(), EvictionStats { clean: 1, dirty: 1 });
+}
+
+#[test]
+fn failed_log_sync_keeps_the_page_cached() {
+    let dir = tempdir().unwrap();
+    let faults = Arc::new(FaultPlan::new());
+    let log = Arc::new(RecordingLog {
+        faults: faults.clone(),
+        writes_at_sync: Mutex::new(Vec::new()),
+        fail: true,
+    });
+    let pager = FilePager::new(dir.path(), 1)
+        .with_faults(faults.clone())
+        .with_log_sync(log);
+    let table = TableId(1);
+    let pid = pager.allocate_page(table).unwrap();
+    pager.fetch_page_mut(table, pid).unwrap().data[0] = 5;
+
+    let err = pager.fetch_page(table, PageId(1)).unwrap_err();
+    assert!(err.to_string().contains("log sync failed"), "{err}");
+    assert_eq!(faults.count(IoOp::PageWrite), 0);
+    assert!(dirty(&pager, (table, pid)));
+    assert_eq!(pager.fetch_page(table, pid).unwrap().data[0], 5);
+    assert_eq!(pager.eviction_stats(), EvictionStats::default());
+}
+
+#[test]
+fn clean_first_policy_evicts_clean_pages_before_dirty_ones() {
+    let dir = tempdir().unwrap();
+    let table = TableId(1);
+    {
+        let pager = FilePager::new(dir.path(), 4);
+        for _ in 0..3 {
+            pager.allocate_page(table).unwrap();
+        }
+        pager.flush().unwrap();
+    }
+
+    for (policy, written, evicted) in [
+        (EvictionPolicy::Lru, 1, PageId(0)),
+        (EvictionPolicy::CleanFirst, 0, PageId(1)),
+    ] {
+        let faults = Arc::new(FaultPlan::new());
+        let pager = FilePager::new(dir.path(), 2)
+            .with_faults(faults.clone())
+            .with_eviction_policy(policy);
+        // The dirty page is the least recently used
+        pager.fetch_page_mut(table, PageId(0)).unwrap();
+        pager.fetch_page(table, PageId(1)).unwrap();
+
+        pager.fetch_page(table, PageId(2)).unwrap();
+        assert_eq!(faults.count(IoOp::PageWrite), written, "{policy:?}");
+        assert!(!cached(&pager, (table, evicted)), "{policy:?}");
+        assert_eq!(pager.eviction_stats().dirty, written, "{policy:?}");
+
+        // With every unpinned page dirty, the dirty page is evicted anyway
+        for pid in [PageId(0), PageId(1), PageId(2)] {
+            if pid != evicted {
+                pager.fetch_page_mut(table, pid).unwrap();
+            }
+        }
+        pager.fetch_page(table, evicted).unwrap();
+        assert_eq!(pager.eviction_stats().dirty, written + 1, "{policy:?}");
+    }
+}
+
+fn text_row(text: &str) -> common::Row {
+    common::Row::new(vec![common::prelude::Value::Text(text.into())])
+}
+
+#[test]
+fn heap_files_read_pages_through_the_shared_pool() {
+    use storage::{HeapEngine, HeapFile, HeapTable, TableEngine};
+
+    let dir = tempdir().unwrap();
+    let faults = Arc::new(FaultPlan::new());
+    let pager = SharedPager::new(FilePager::new(dir.path(), 8).with_faults(faults.clone()));
+    let engine = HeapEngine::default().with_page_io(Arc::new(pager.clone()));
+
+    let mut table = engine.open(dir.path(), "users", 7, None).unwrap();
+    let rids: Vec<_> = ["a", "b", "c"]
+        .iter()
+        .map(|text| table.insert(&text_row(text)).unwrap())
+        .collect();
+    // Each write reached the file at once
+    assert_eq!(faults.count(IoOp::PageWrite), 3);
+    let mut direct = HeapFile::open(&dir.path().join("users.heap"), 7).unwrap();
+    assert_eq!(direct.get(rids[2]).unwrap().values, text_row("c").values);
+
+    // Written pages stay cached, so another handle reads without I/O
+    let reads = faults.count(IoOp::PageRead);
+    let mut other = engine.open(dir.path(), "users", 7, None).unwrap();
+    for rid in &rids {
+        other.get(*rid).unwrap();
+    }
+    assert_eq!(faults.count(IoOp::PageRead), reads);
+    assert_eq!(dirty_pages(pager.pool()), 0);
+
+    // Dropping the table forgets its pages
+    engine.drop_table(dir.path(), "users", 7).unwrap();
+    assert_eq!(cached_pages(pager.pool()), 0);
+    let mut table = engine.open(dir.path(), "users", 7, None).unwrap();
+    assert!(storage::Scan::new(table.as_mut()).next().is_none());
+}
+
+#[test]
+fn loading_a_damaged_page_fails_with_corruption() {
+    let dir = tempdir().unwrap();
+    let table = TableId(1);
+    let pager = FilePager::new(dir.path(), 4);
+    let pid = pager.allocate_page(table).unwrap();
+    pager.fetch_page_mut(table, pid).unwrap().data[100] = 7;
+    pager.flush().unwrap();
+
+    let path = dir.path().join("table_1.tbl");
+    let mut bytes = fs::read(&path).unwrap();
+    let stored = &bytes[checksum::CHECKSUM_BYTES];
+    assert_ne!(stored, [0; 4], "flushed pages carry a checksum");
+    bytes[100] = 8;
+    fs::write(&path, &bytes).unwrap();
+
+    let pager = FilePager::new(dir.path(), 4);
+    let err = pager.fetch_page(table, pid).unwrap_err();
+    assert!(matches!(err, DbError::Corruption(_)), "{err}");
+}
+
+#[test]
+fn compressed_tables_are_written_as_frames_and_loaded_back() {
+    use storage::{HeapEngine, HeapFile, HeapTable, TableEngine};
+
+    let dir = tempdir().unwrap();
+    let table = TableId(1);
+    let pager = FilePager::new(dir.path(), 4);
+    pager.set_compression(table, Compression::Zstd);
+    for _ in 0..3 {
+        let pid = pager.allocate_page(table).unwrap();
+        pager.fetch_page_mut(table, pid).unwrap().data[100..200].fill(7);
+    }
+    pager.flush().unwrap();
+
+    let bytes = fs::read(dir.path().join("table_1.tbl")).unwrap();
+    assert_eq!(bytes.len(), 3 * PAGE_SIZE);
+    assert!(bytes.chunks(PAGE_SIZE).all(compression::is_compressed));
+    let pager = FilePager::new(dir.path(), 4);
+    for pid in 0..3 {
+        assert_eq!(pager.fetch_page(table, PageId(pid)).unwrap().data[150], 7);
+    }
+
+    // Heap files opened through a shared pool pass their setting on
+    let pager = SharedPager::new(FilePager::new(dir.path(), 8));
+    let engine = HeapEngine::default().with_page_io(Arc::new(pager.clone()));
+    let mut heap = engine.open(dir.path(), "notes", 2, None).unwrap();
+    heap.set_compression(Compression::Lz4);
+    let rid = heap.insert(&text_row(&"abc".repeat(200))).unwrap();
+    let bytes = fs::read(dir.path().join("notes.heap")).unwrap();
+    assert!(compression::is_compressed(&bytes));
+    let mut direct = HeapFile::open(&dir.path().join("notes.heap"), 2).unwrap();
+    assert_eq!(
+        direct.get(rid).unwrap().values,
+        text_row(&"abc".repeat(200)).values
+    );
+}
+
+#[test]
+fn attaching_a_table_to_another_file_forgets_its_pages() {
+    let dir = tempdir().unwrap();
+    let table = TableId(1);
+    let pager = FilePager::new(dir.path(), 4);
+    let mut page = Page::new(0);
+    page.data[100] = 7;
+    pager
+        .attach_file(table, &dir.path().join("a.heap"))
+        .unwrap();
+    pager.write_through(table, &page).unwrap();
+    assert_eq!(pager.fetch_page(table, PageId(0)).unwrap().data[100], 7);
+
+    pager
+        .attach_file(table, &dir.path().join("b.heap"))
+        .unwrap();
+    assert_eq!(pager.page_count(table).unwrap(), 0);
+    assert_eq!(pager.fetch_page(table, PageId(0)).unwrap().data[100], 0);
+
+    // A pinned page keeps the table attached
+    pager.pin_page(table, PageId(0)).unwrap();
+    let err = pager
+        .attach_file(table, &dir.path().join("a.heap"))
+        .unwrap_err();
+    assert!(err.to_string().contains("pinned"), "{err}");
+}
+
+#[test]
+fn shared_handles_see_each_others_writes_at_once() {
+    let dir = tempdir().unwrap();
+    let table = TableId(1);
+    let writer = SharedPager::new(FilePager::new(dir.path(), 4));
+    let reader = writer.clone();
+    let pid = writer.allocate_page(table).unwrap();
+
+    writer.fetch_page_mut(table, pid).unwrap().data[0] = 42;
+    assert_eq!(reader.fetch_page(table, pid).unwrap().data[0], 42);
+    assert_eq!(reader.pool().pins().pin_count(table, pid), 0);
+
+    drop(writer);
+    reader.flush().unwrap();
+    let reopened = FilePager::new(dir.path(), 4);
+    assert_eq!(reopened.fetch_page(table, pid).unwrap().data[0], 42);
+}
+
+#[test]
+fn pages_of_different_shards_are_used_at_once() {
+    use std::sync::mpsc;
+
+    let dir = tempdir().unwrap();
+    let table = TableId(1);
+    let pager = SharedPager::new(FilePager::new(dir.path(), 8).with_shards(4));
+    assert_eq!(pager.pool().shard_count(), 4);
+    let pid0 = pager.allocate_page(table).unwrap();
+    let pid1 = pager.allocate_page(table).unwrap();
+    assert_ne!(
+        pager.pool().shard_index((table, pid0)),
+        pager.pool().shard_index((table, pid1))
+    );
+
+    // A page held by one thread does not hold up a page of another shard
+    let guard = pager.fetch_page_mut(table, pid0).unwrap();
+    let (done, finished) = mpsc::channel();
+    let other = pager.clone();
+    let reader = std::thread::spawn(move || {
+        other.fetch_page_mut(table, pid1).unwrap().data[0] = 2;
+        done.send(()).unwrap();
+    });
+    finished.recv_timeout(Duration::from_secs(10)).unwrap();
+    drop(guard);
+    reader.join().unwrap();
+    assert_eq!(pager.fetch_page(table, pid1).unwrap().data[0], 2);
+}
+
+#[test]
+fn guards_on_pages_of_one_shard_are_held_at_once() {
+    use std::sync::mpsc;
+
+    let dir = tempdir().unwrap();
+    let table = TableId(1);
+    let pager = SharedPager::new(FilePager::new(dir.path(), 8).with_shards(1));
+    let pids: Vec<_> = (0..4)
+        .map(|_| pager.allocate_page(table).unwrap())
+        .collect();
+
+    // One thread holds two guards on pages of the only shard
+    let mut first = pager.fetch_page_mut(table, pids[0]).unwrap();
+    let second = pager.fetch_page(table, pids[1]).unwrap();
+    first.data[0] = 1;
+    assert_eq!(pager.pool().pins().pin_count(table, pids[0]), 1);
+
+    // Another thread reads one of them and writes a third page meanwhile,
+    // and waits only for the write guard
+    let (done, finished) = mpsc::channel();
+    let other = pager.clone();
+    let (read, written) = (pids[1], pids[2]);
+    let worker = std::thread::spawn(move || {
+        assert_eq!(other.fetch_page(table, read).unwrap().data[0], 0);
+        other.fetch_page_mut(table, written).unwrap().data[0] = 3;
+        done.send(()).unwrap();
+    });
+    finished.recv_timeout(Duration::from_secs(10)).unwrap();
+    worker.join().unwrap();
+    drop((first, second));
+
+    assert_eq!(pager.fetch_page(table, pids[0]).unwrap().data[0], 1);
+    assert_eq!(pager.fetch_page(table, pids[2]).unwrap().data[0], 3);
+    assert_eq!(pager.pool().pins().pin_count(table, pids[0]), 0);
+}
+
+#[test]
+fn a_writer_waits_only_for_guards_on_its_page() {
+    use std::sync::mpsc;
+
+    let dir = tempdir().unwrap();
+    let table = TableId(1);
+    let pager = SharedPager::new(FilePager::new(dir.path(), 8).with_shards(1));
+    let pid0 = pager.allocate_page(table).unwrap();
+    let pid1 = pager.allocate_page(table).unwrap();
+
+    let reader = pager.fetch_page(table, pid0).unwrap();
+    let (done, finished) = mpsc::channel();
+    let other = pager.clone();
+    let writer = std::thread::spawn(move || {
+        other.fetch_page_mut(table, pid0).unwrap().data[0] = 5;
+        done.send(()).unwrap();
+    });
+    // The writer waits for the reader, while the shard stays usable
+    assert!(finished.recv_timeout(Duration::from_millis(50)).is_err());
+    pager.fetch_page_mut(table, pid1).unwrap().data[0] = 6;
+    drop(reader);
+    finished.recv_timeout(Duration::from_secs(10)).unwrap();
+    writer.join().unwrap();
+    assert_eq!(pager.fetch_page(table, pid0).unwrap().data[0], 5);
+}
+
+#[test]
+fn threads_share_one_pool() {
+    let dir = tempdir().unwrap();
+    let pager = SharedPager::new(FilePager::new(dir.path(), 16).with_shards(4));
+
+    // More pages than the pool holds, so threads evict each other's
+    let workers: Vec<_> = (0..4)
+        .map(|worker| {
+            let pager = pager.clone();
+            std::thread::spawn(move || {
+                let table = TableId(worker);
+                let pids: Vec<_> = (0..8)
+                    .map(|_| pager.allocate_page(table).unwrap())
+                    .collect();
+                for round in 0..20u8 {
+                    for pid in &pids {
+                        pager.fetch_page_mut(table, *pid).unwrap().data[0] = round;
+                        let page = pager.fetch_page(table, *pid).unwrap();
+                        assert_eq!(page.data[0], round);
+                    }
+                }
+                pids
+            })
+        })
+        .collect();
+    for (worker, handle) in workers.into_iter().enumerate() {
+        let pids = handle.join().unwrap();
+        assert_eq!(pids, (0..8).map(PageId).collect::<Vec<_>>());
+        for pid in pids {
+            let page = pager.fetch_page(TableId(worker as u64), pid).unwrap();
+            assert_eq!(page.data[0], 19);
+        }
+    }
+    pager.flush().unwrap();
+    assert_eq!(dirty_pages(pager.pool()), 0);
+}
+
+#[test]
+fn a_load_waiting_for_a_pin_leaves_its_shard_unlocked() {
+    use std::sync::mpsc;
+
+    let dir = tempdir().unwrap();
+    let pager =
+        SharedPager::new(FilePager::new(dir.path(), 1).with_pin_wait(Duration::from_secs(10)));
+    let table = TableId(1);
+    let pid0 = pager.allocate_page(table).unwrap();
+    pager.pool().pin_page(table, pid0).unwrap();
+
+    let loader = pager.clone();
+    let waiting = std::thread::spawn(move || loader.fetch_page(table, PageId(1)).map(drop));
+    std::thread::sleep(Duration::from_millis(20));
+
+    // The pinned page stays readable while the load waits for it
+    let (done, read) = mpsc::channel();
+    let reader = pager.clone();
+    std::thread::spawn(move || {
+        drop(reader.fetch_page(table, pid0).unwrap());
+        done.send(()).unwrap();
+    });
+    read.recv_timeout(Duration::from_secs(5)).unwrap();
+
+    pager.pool().unpin_page(table, pid0).unwrap();
+    waiting.join().unwrap().unwrap();
+    assert!(cached(pager.pool(), (table, PageId(1))));
+}
+
+#[test]
+fn heap_page_writes_sync_the_log_first() {
+    use storage::{HeapEngine, TableEngine};
+
+    let dir = tempdir().unwrap();
+    let faults = Arc::new(FaultPlan::new());
+    let open = |fail: bool| {
+        let log = Arc::new(RecordingLog {
+            faults: faults.clone(),
+            writes_at_sync: Mutex::new(Vec::new()),
+            fail,
+        });
+        let pool = FilePager::new(dir.path(), 8)
+            .with_faults(faults.clone())
+            .with_log_sync(log.clone());
+        let engine = HeapEngine::default().with_page_io(Arc::new(SharedPager::new(pool)));
+        (engine.open(dir.path(), "users", 7, None).unwrap(), log)
+    };
+
+    let (mut table, log) = open(false);
+    table.insert(&text_row("a")).unwrap();
+    table.insert(&text_row("b")).unwrap();
+    assert_eq!(*log.writes_at_sync.lock().unwrap(), vec![0, 1]);
+    assert_eq!(faults.count(IoOp::PageWrite), 2);
+
+    // A failed sync writes nothing
+    let (mut table, _) = open(true);
+    let err = table.insert(&text_row("c")).unwrap_err();
+    assert!(err.to_string().contains("log sync failed"), "{err}");
+    assert_eq!(faults.count(IoOp::PageWrite), 2);
+    assert_eq!(storage::Scan::new(table.as_mut()).count(), 2);
+}
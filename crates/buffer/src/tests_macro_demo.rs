@@ -35,7 +35,7 @@ mod tests {
         let table = TableId(1);
 
         let pid1 = pager.allocate_page(table).unwrap();
-        pager.fetch_page(table, pid1).unwrap().data[0] = 99;
+        pager.fetch_page_mut(table, pid1).unwrap().data[0] = 99;
 
         // Allocate another, should evict the first
         let _pid2 = pager.allocate_page(table).unwrap();
@@ -53,7 +53,7 @@ mod tests {
         test_pager!(pager, table, capacity: 1); // One line setup!
 
         let pid1 = pager.allocate_page(table).unwrap();
-        pager.fetch_page(table, pid1).unwrap().data[0] = 99;
+        pager.fetch_page_mut(table, pid1).unwrap().data[0] = 99;
 
         // Allocate another, should evict the first
         let _pid2 = pager.allocate_page(table).unwrap();
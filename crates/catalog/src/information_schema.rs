@@ -0,0 +1,79 @@
+//! Read-only views of catalog state, queried as `information_schema.<view>`.
+//!
+//! Views have no storage: their rows are built from the catalog each time
+//! they are scanned, so they show the counters as of the scan.
+
+use crate::{Catalog, Column};
+use types::{SqlType, Value};
+
+/// Schema the views are named under.
+pub const SCHEMA: &str = "information_schema";
+
+/// A view of catalog state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SystemView {
+    /// Rows written to each table (see [`crate::TableActivity`]).
+    TableActivity,
+}
+
+impl SystemView {
+    /// The view named `name`, which must be qualified with [`SCHEMA`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (schema, view) = name.split_once('.')?;
+        if !schema.eq_ignore_ascii_case(SCHEMA) {
+            return None;
+        }
+        match view.to_ascii_lowercase().as_str() {
+            "table_activity" => Some(SystemView::TableActivity),
+            _ => None,
+        }
+    }
+
+    /// Qualified name of the view.
+    pub fn name(self) -> &'static str {
+        match self {
+            SystemView::TableActivity => "information_schema.table_activity",
+        }
+    }
+
+    /// Columns of the view's rows.
+    pub fn columns(self) -> Vec<Column> {
+        match self {
+            SystemView::TableActivity => vec![
+                Column::new("table_name", SqlType::Text),
+                Column::new("inserts", SqlType::Int),
+                Column::new("updates", SqlType::Int),
+                Column::new("deletes", SqlType::Int),
+                Column::new("live_rows", SqlType::Int),
+                Column::new("modifications_since_analyze", SqlType::Int),
+                Column::new("dead_rows", SqlType::Int),
+            ],
+        }
+    }
+
+    /// The view's rows, one per table in name order.
+    pub fn rows(self, catalog: &Catalog) -> Vec<Vec<Value>> {
+        let count = |n: u64| Value::Int(i64::try_from(n).unwrap_or(i64::MAX));
+        match self {
+            SystemView::TableActivity => {
+                let mut tables: Vec<_> = catalog.tables().collect();
+                tables.sort_by(|a, b| a.name.cmp(&b.name));
+                tables
+                    .into_iter()
+                    .map(|table| {
+                        let activity = table.activity();
+                        vec![
+                            Value::Text(table.name.clone()),
+                            count(activity.inserts),
+                            count(activity.updates),
+                            count(activity.deletes),
+                            count(activity.live_rows),
+                            count(activity.modifications_since_analyze),
+                            count(activity.dead_rows),
+                        ]
+                    })
+                    .collect()
+            }
+        }
+    }
+}
@@ -1,12 +1,24 @@
-use std::{fs, path::Path};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    ops::{Bound, RangeBounds},
+    path::Path,
+    sync::atomic::{AtomicI64, AtomicU64, Ordering},
+};
 
 use ahash::RandomState;
+use common::compression::Compression;
+use common::crypto::{self, EncryptionKey};
 use common::{ColumnId, DbError, DbResult, TableId};
 use hashbrown::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
-use types::SqlType;
+use types::{SqlType, TextLength, Value};
 use uuid::Uuid;
 
+pub mod information_schema;
+
+pub use information_schema::SystemView;
+
 type Map<K, V> = HashMap<K, V, RandomState>;
 type Set<T> = HashSet<T, RandomState>;
 
@@ -20,6 +32,15 @@ pub struct Catalog {
     tables: Vec<TableMeta>,
     next_table_id: u64,
     next_index_id: u64,
+    /// Advanced once per schema change; see [`Catalog::epoch`].
+    #[serde(default)]
+    epoch: u64,
+    /// Counters for auto-increment columns, by sequence name.
+    #[serde(default)]
+    sequences: BTreeMap<String, Sequence>,
+    /// Stored queries, by view name.
+    #[serde(default)]
+    views: BTreeMap<String, ViewMeta>,
     #[serde(skip)]
     #[serde(default)]
     table_name_index: Map<String, usize>,
@@ -29,11 +50,40 @@ pub struct Catalog {
     #[serde(skip)]
     #[serde(default)]
     index_name_index: Map<String, TableId>,
+    /// Master key for data at rest; see [`Catalog::load_with_key`].
+    #[serde(skip)]
+    #[serde(default)]
+    encryption: Option<EncryptionKey>,
 }
 
+/// Associated data for the encrypted catalog file.
+const CATALOG_AAD: &[u8] = b"catalog";
+
 const RESERVED_TABLE_NAMES: &[&str] = &["_catalog", "sqlite_master"];
 const RESERVED_INDEX_NAMES: &[&str] = &["_primary"];
 
+/// Name of the implicit column that holds each row's version for optimistic
+/// concurrency control. Only present on tables created with row versioning.
+pub const ROW_VERSION_COLUMN: &str = "_version";
+
+/// Name of the sequence backing auto-increment column `column` of `table`.
+pub fn sequence_name(table: &str, column: &str) -> String {
+    format!("{table}_{column}_seq")
+}
+
+/// Advance the row version stored at ordinal `col` after an update.
+/// A NULL or missing version restarts at 1.
+pub fn bump_row_version(values: &mut Vec<Value>, col: ColumnId) {
+    let col = col as usize;
+    if values.len() <= col {
+        values.resize(col + 1, Value::Null);
+    }
+    values[col] = match values[col] {
+        Value::Int(version) => Value::Int(version + 1),
+        _ => Value::Int(1),
+    };
+}
+
 #[bon::bon]
 impl Catalog {
     /// Create an empty catalog.
@@ -42,9 +92,13 @@ impl Catalog {
             tables: Vec::new(),
             next_table_id: 1,
             next_index_id: 1,
+            epoch: 0,
+            sequences: BTreeMap::new(),
+            views: BTreeMap::new(),
             table_name_index: Map::default(),
             table_id_index: Map::default(),
             index_name_index: Map::default(),
+            encryption: None,
         };
         catalog.rebuild_indexes();
         catalog
@@ -52,24 +106,103 @@ impl Catalog {
 
     /// Load a catalog from disk, returning an empty catalog if the file does not exist.
     pub fn load(path: &Path) -> DbResult<Self> {
+        Self::load_with_key(path, None)
+    }
+
+    /// Load a catalog for a database encrypted at rest with `key`.
+    ///
+    /// The catalog file itself is encrypted, and the key is kept on the
+    /// catalog (never serialized) so every component that opens a table's
+    /// files through the catalog uses it; see [`Catalog::encryption_key`].
+    /// Loading fails if the file's encryption does not match `key`.
+    pub fn load_with_key(path: &Path, key: Option<EncryptionKey>) -> DbResult<Self> {
         if !path.exists() {
-            return Ok(Self::new());
+            let mut catalog = Self::new();
+            catalog.encryption = key;
+            return Ok(catalog);
         }
-        let data = fs::read_to_string(path)?;
-        let mut catalog: Catalog = serde_json::from_str(&data)
+        let bytes = crypto::open_file(key.as_ref(), CATALOG_AAD, fs::read(path)?)
+            .map_err(|err| DbError::Catalog(format!("cannot read catalog file: {err}")))?;
+        let mut catalog: Catalog = serde_json::from_slice(&bytes)
             .map_err(|err| DbError::Catalog(format!("invalid catalog file: {err}")))?;
+        catalog.encryption = key;
         catalog.rebuild_indexes();
         Ok(catalog)
     }
 
-    /// Persist the catalog contents as pretty JSON.
+    /// Persist the catalog contents as pretty JSON, encrypted if the catalog
+    /// was loaded with a key. Temporary tables and their sequences are left
+    /// out.
+    ///
+    /// The file is written beside `path` and renamed over it, so a reader
+    /// never sees a partly written catalog, even while a background save
+    /// outlives the database that started it.
     pub fn save(&self, path: &Path) -> DbResult<()> {
-        let data = serde_json::to_string_pretty(self)
-            .map_err(|err| DbError::Catalog(format!("serialize failed: {err}")))?;
-        fs::write(path, data)?;
+        let data = if self.tables.iter().any(|table| table.temporary) {
+            let mut persistent = self.clone();
+            for table in self.tables.iter().filter(|table| table.temporary) {
+                for column in table.columns() {
+                    if let Some(sequence) = &column.sequence {
+                        persistent.sequences.remove(sequence);
+                    }
+                }
+            }
+            persistent.tables.retain(|table| !table.temporary);
+            serde_json::to_string_pretty(&persistent)
+        } else {
+            serde_json::to_string_pretty(self)
+        }
+        .map_err(|err| DbError::Catalog(format!("serialize failed: {err}")))?;
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        fs::write(
+            &tmp,
+            crypto::seal_file(self.encryption_key(), CATALOG_AAD, data.into_bytes())?,
+        )?;
+        fs::rename(&tmp, path)?;
         Ok(())
     }
 
+    /// Master key used to encrypt this database's files, if encryption is on.
+    pub fn encryption_key(&self) -> Option<&EncryptionKey> {
+        self.encryption.as_ref()
+    }
+
+    /// Schema version of this catalog.
+    ///
+    /// The epoch advances once per schema change (see
+    /// [`Catalog::bump_epoch`]), and the value is persisted, so anything
+    /// derived from the catalog (planned statements, cached schemas) can be
+    /// tagged with the epoch it was built against and discarded once the
+    /// epoch moves on.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Advance the epoch after a schema change and return the new one.
+    ///
+    /// Mutations do not advance it themselves, since one change (a DDL
+    /// statement) may take several, and some (statistics, activity counters)
+    /// change nothing a plan depends on.
+    pub fn bump_epoch(&mut self) -> u64 {
+        self.epoch += 1;
+        self.epoch
+    }
+
+    /// Move the epoch forward to `epoch`, as a replica does when it applies
+    /// another node's schema change. Returns whether it moved.
+    pub fn advance_epoch_to(&mut self, epoch: u64) -> bool {
+        let advanced = epoch > self.epoch;
+        self.epoch = self.epoch.max(epoch);
+        advanced
+    }
+
+    /// Move the epoch past `previous`, e.g. when a catalog is replaced by a
+    /// freshly loaded one that must not appear older than what it replaces.
+    pub fn advance_epoch_past(&mut self, previous: u64) {
+        self.epoch = self.epoch.max(previous) + 1;
+    }
+
     /// Returns an immutable reference to a table by name.
     pub fn table(&self, name: &str) -> DbResult<&TableMeta> {
         let idx = self
@@ -105,7 +238,11 @@ impl Catalog {
         if self.table_name_index.contains_key(name) {
             return Err(DbError::Catalog(format!("table '{name}' already exists")));
         }
+        if self.views.contains_key(name) {
+            return Err(DbError::Catalog(format!("view '{name}' already exists")));
+        }
         let schema = TableSchema::try_new(columns)?;
+        self.create_sequences(&schema)?;
         let table_id = TableId(self.next_table_id);
         self.next_table_id += 1;
         let mut table = TableMeta::new(table_id, name.to_string(), schema);
@@ -119,6 +256,53 @@ impl Catalog {
         Ok(table_id)
     }
 
+    /// Partition a newly created table by the values of `column`.
+    ///
+    /// Each partition is given a storage ID from the table ID sequence. The
+    /// table must not hold rows yet, since they would not be moved.
+    pub fn partition_table(
+        &mut self,
+        table_name: &str,
+        method: PartitionMethod,
+        column: &str,
+        partitions: Vec<(String, PartitionBound)>,
+    ) -> DbResult<()> {
+        let table = self.table(table_name)?;
+        if table.partitioning.is_some() {
+            return Err(DbError::Catalog(format!(
+                "table '{table_name}' is already partitioned"
+            )));
+        }
+        let column_id = table.schema.column_index(column).ok_or_else(|| {
+            DbError::Catalog(format!(
+                "unknown partition key column '{column}' in table '{table_name}'"
+            ))
+        })?;
+        let key_type = table.schema.columns[column_id as usize].ty.clone();
+
+        let mut next_id = self.next_table_id;
+        let partitioning = Partitioning {
+            method,
+            column: column_id,
+            partitions: partitions
+                .into_iter()
+                .map(|(name, bound)| {
+                    next_id += 1;
+                    Partition {
+                        name,
+                        storage_id: TableId(next_id - 1),
+                        bound: bound.coerce_to(&key_type),
+                    }
+                })
+                .collect(),
+        };
+        partitioning.validate(table_name, &key_type)?;
+
+        self.next_table_id = next_id;
+        self.table_mut(table_name)?.partitioning = Some(partitioning);
+        Ok(())
+    }
+
     /// Remove a table and its associated indexes.
     pub fn drop_table(&mut self, name: &str) -> DbResult<()> {
         let idx = self
@@ -126,7 +310,8 @@ impl Catalog {
             .get(name)
             .copied()
             .ok_or_else(|| DbError::Catalog(format!("unknown table '{name}'")))?;
-        self.tables.remove(idx);
+        let table = self.tables.remove(idx);
+        self.drop_sequences(&table.schema);
         self.rebuild_indexes();
         Ok(())
     }
@@ -138,7 +323,8 @@ impl Catalog {
             .get(&table_id)
             .copied()
             .ok_or_else(|| DbError::Catalog(format!("unknown table id {}", table_id.0)))?;
-        self.tables.remove(idx);
+        let table = self.tables.remove(idx);
+        self.drop_sequences(&table.schema);
         self.rebuild_indexes();
         Ok(())
     }
@@ -155,6 +341,9 @@ impl Catalog {
         if self.table_name_index.contains_key(name) {
             return Err(DbError::Catalog(format!("table '{name}' already exists")));
         }
+        if self.views.contains_key(name) {
+            return Err(DbError::Catalog(format!("view '{name}' already exists")));
+        }
         if self.table_id_index.contains_key(&table_id) {
             return Err(DbError::Catalog(format!(
                 "table id {} already exists",
@@ -178,6 +367,8 @@ impl Catalog {
             table.set_primary_key(pk_ordinals)?;
         }
 
+        self.create_sequences(&table.schema)?;
+
         // Update next_table_id if needed
         if table_id.0 >= self.next_table_id {
             self.next_table_id = table_id.0 + 1;
@@ -197,6 +388,7 @@ impl Catalog {
     ///     .index_name("idx_users_email")
     ///     .columns(&["email"])
     ///     .kind(IndexKind::BTree)
+    ///     .unique(true)
     ///     .call()?;
     /// ```
     #[builder]
@@ -206,6 +398,7 @@ impl Catalog {
         index_name: &str,
         columns: &[&str],
         kind: IndexKind,
+        #[builder(default)] unique: bool,
     ) -> DbResult<IndexId> {
         Self::validate_index_name(index_name)?;
         if self.index_name_index.contains_key(index_name) {
@@ -256,6 +449,7 @@ impl Catalog {
                 columns: resolved,
                 kind,
                 storage: StorageDescriptor::new(),
+                unique,
             })?;
             id
         };
@@ -308,6 +502,122 @@ impl Catalog {
             .ok_or_else(|| DbError::Catalog(format!("unknown table '{name}'")))
     }
 
+    /// Store statistics gathered by `ANALYZE` for a table; see
+    /// [`TableMeta::set_statistics`].
+    ///
+    /// Statistics are estimates rather than schema, so unlike
+    /// [`Catalog::table_mut`] this does not bump the epoch. Fails if the
+    /// table's columns changed since the statistics were gathered.
+    pub fn set_table_statistics(
+        &mut self,
+        table_id: TableId,
+        statistics: TableStatistics,
+        modifications_seen: u64,
+    ) -> DbResult<()> {
+        let idx = self
+            .table_id_index
+            .get(&table_id)
+            .copied()
+            .ok_or_else(|| DbError::Catalog(format!("unknown table id {}", table_id.0)))?;
+        let table = &mut self.tables[idx];
+        if statistics.columns.len() != table.schema.columns().len() {
+            return Err(DbError::Catalog(format!(
+                "statistics for table '{}' do not match its columns",
+                table.name
+            )));
+        }
+        table.set_statistics(statistics, modifications_seen);
+        Ok(())
+    }
+
+    /// Returns the sequence with the given name.
+    pub fn sequence(&self, name: &str) -> DbResult<&Sequence> {
+        self.sequences
+            .get(name)
+            .ok_or_else(|| DbError::Catalog(format!("unknown sequence '{name}'")))
+    }
+
+    /// Remove a sequence, e.g. when the column it fills is dropped.
+    pub fn drop_sequence(&mut self, name: &str) -> DbResult<()> {
+        self.sequences
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| DbError::Catalog(format!("unknown sequence '{name}'")))
+    }
+
+    /// Returns the view with the given name.
+    pub fn view(&self, name: &str) -> DbResult<&ViewMeta> {
+        self.views
+            .get(name)
+            .ok_or_else(|| DbError::Catalog(format!("unknown view '{name}'")))
+    }
+
+    /// Iterate over all views, in name order.
+    pub fn views(&self) -> impl Iterator<Item = &ViewMeta> {
+        self.views.values()
+    }
+
+    /// Define a view over the SELECT in `query`.
+    ///
+    /// Views share the table namespace. The query is not checked here; it is
+    /// planned each time the view is referenced.
+    pub fn create_view(&mut self, name: &str, query: &str) -> DbResult<()> {
+        Self::validate_table_name(name)?;
+        if self.table_name_index.contains_key(name) {
+            return Err(DbError::Catalog(format!("table '{name}' already exists")));
+        }
+        if self.views.contains_key(name) {
+            return Err(DbError::Catalog(format!("view '{name}' already exists")));
+        }
+        self.views.insert(
+            name.to_string(),
+            ViewMeta {
+                name: name.to_string(),
+                query: query.to_string(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Remove a view.
+    pub fn drop_view(&mut self, name: &str) -> DbResult<()> {
+        self.views
+            .remove(name)
+            .ok_or_else(|| DbError::Catalog(format!("unknown view '{name}'")))?;
+        Ok(())
+    }
+
+    /// Create the sequences referenced by a new table's columns.
+    ///
+    /// Checks every name before creating any, so a failure leaves the
+    /// catalog unchanged.
+    fn create_sequences(&mut self, schema: &TableSchema) -> DbResult<()> {
+        let names: Vec<&String> = schema
+            .columns()
+            .iter()
+            .filter_map(|column| column.sequence.as_ref())
+            .collect();
+        for (i, name) in names.iter().enumerate() {
+            if self.sequences.contains_key(*name) || names[..i].contains(name) {
+                return Err(DbError::Catalog(format!(
+                    "sequence '{name}' already exists"
+                )));
+            }
+        }
+        for name in names {
+            self.sequences.insert(name.clone(), Sequence::default());
+        }
+        Ok(())
+    }
+
+    fn drop_sequences(&mut self, schema: &TableSchema) {
+        for column in schema.columns() {
+            if let Some(name) = &column.sequence {
+                self.sequences.remove(name);
+            }
+        }
+    }
+
     fn rebuild_indexes(&mut self) {
         self.table_name_index.clear();
         self.table_id_index.clear();
@@ -366,10 +676,42 @@ pub struct TableMeta {
     pub name: String,
     pub schema: TableSchema,
     pub storage: StorageDescriptor,
+    /// Storage engine holding the table's rows.
+    #[serde(default)]
+    pub engine: EngineKind,
     /// Primary key columns (ordinals). None if table has no PRIMARY KEY constraint.
     /// Empty Vec is invalid; use None for no constraint.
     pub primary_key: Option<Vec<ColumnId>>,
     pub indexes: Vec<IndexMeta>,
+    /// How rows are split across partitions, if the table is partitioned.
+    #[serde(default)]
+    pub partitioning: Option<Partitioning>,
+    /// Whether statements touching this table are written to the audit log.
+    #[serde(default)]
+    pub audit: bool,
+    /// Percentage of each heap page that inserts may fill, leaving the rest
+    /// for rows to grow into on update. `None` fills pages completely.
+    #[serde(default)]
+    pub fillfactor: Option<u8>,
+    /// How the table's heap pages and B+Tree index pages are compressed
+    /// when written.
+    #[serde(default)]
+    pub compression: Compression,
+    /// Whether the table lasts only until the database is closed. Temporary
+    /// tables are left out when the catalog is saved, so they never appear
+    /// in the catalog file.
+    #[serde(default)]
+    pub temporary: bool,
+    /// Statistics from the last `ANALYZE`, if the table has been analyzed
+    /// since its columns last changed.
+    #[serde(default)]
+    pub statistics: Option<TableStatistics>,
+    /// Rows inserted, updated or deleted since the last `ANALYZE`.
+    #[serde(default)]
+    modifications: ModificationCounter,
+    /// Rows written since the table was created.
+    #[serde(default)]
+    activity: ActivityCounters,
     #[serde(skip)]
     #[serde(default)]
     index_name_lookup: Map<String, usize>,
@@ -385,8 +727,17 @@ impl TableMeta {
             name,
             schema,
             storage: StorageDescriptor::new(),
+            engine: EngineKind::default(),
             primary_key: None,
             indexes: Vec::new(),
+            partitioning: None,
+            audit: false,
+            fillfactor: None,
+            compression: Compression::None,
+            temporary: false,
+            statistics: None,
+            modifications: ModificationCounter::default(),
+            activity: ActivityCounters::default(),
             index_name_lookup: Map::default(),
             index_id_lookup: Map::default(),
         };
@@ -499,6 +850,241 @@ impl TableMeta {
         self.schema.columns()
     }
 
+    /// Append a column to the table, returning its ordinal.
+    ///
+    /// Existing rows are not rewritten; readers fill the new column with its
+    /// default via [`TableSchema::fill_missing_columns`].
+    pub fn add_column(&mut self, column: Column) -> DbResult<ColumnId> {
+        let mut columns = self.schema.columns.clone();
+        columns.push(column);
+        self.schema = TableSchema::try_new(columns)?;
+        self.statistics = None;
+        Ok((self.schema.columns.len() - 1) as ColumnId)
+    }
+
+    /// Remove a column, returning the ordinal it had.
+    ///
+    /// Primary key and indexed columns cannot be dropped. Key and index
+    /// ordinals after the removed column shift down by one; callers must
+    /// rewrite stored rows to match.
+    pub fn drop_column(&mut self, name: &str) -> DbResult<ColumnId> {
+        let ordinal = self.schema.column_index(name).ok_or_else(|| {
+            DbError::Catalog(format!("unknown column '{name}' in table '{}'", self.name))
+        })?;
+        if self
+            .primary_key
+            .as_ref()
+            .is_some_and(|pk| pk.contains(&ordinal))
+        {
+            return Err(DbError::Catalog(format!(
+                "cannot drop primary key column '{name}'"
+            )));
+        }
+        if let Some(index) = self.indexes.iter().find(|i| i.columns.contains(&ordinal)) {
+            return Err(DbError::Catalog(format!(
+                "cannot drop column '{name}': it is used by index '{}'",
+                index.name
+            )));
+        }
+        if self
+            .partitioning
+            .as_ref()
+            .is_some_and(|partitioning| partitioning.column == ordinal)
+        {
+            return Err(DbError::Catalog(format!(
+                "cannot drop partition key column '{name}'"
+            )));
+        }
+
+        let mut columns = self.schema.columns.clone();
+        columns.remove(ordinal as usize);
+        self.schema = TableSchema::try_new(columns)?;
+        self.statistics = None;
+
+        let shift = |col: &mut ColumnId| {
+            if *col > ordinal {
+                *col -= 1;
+            }
+        };
+        if let Some(pk) = self.primary_key.as_mut() {
+            pk.iter_mut().for_each(shift);
+        }
+        for index in &mut self.indexes {
+            index.columns.iter_mut().for_each(shift);
+        }
+        if let Some(partitioning) = self.partitioning.as_mut() {
+            shift(&mut partitioning.column);
+        }
+        Ok(ordinal)
+    }
+
+    /// Count rows written by a DML statement against the table.
+    ///
+    /// Takes `&self` so writers holding the catalog read-only can record
+    /// their changes; the counts reach disk the next time the catalog is
+    /// saved.
+    pub fn record_modifications(&self, kind: Modification, rows: u64) {
+        self.modifications.0.fetch_add(rows, Ordering::Relaxed);
+        let activity = &self.activity;
+        match kind {
+            Modification::Insert => {
+                activity.inserts.fetch_add(rows, Ordering::Relaxed);
+                activity.live_rows.fetch_add(rows, Ordering::Relaxed);
+            }
+            Modification::Update => {
+                activity.updates.fetch_add(rows, Ordering::Relaxed);
+                activity.dead_rows.fetch_add(rows, Ordering::Relaxed);
+            }
+            Modification::Delete => {
+                activity.deletes.fetch_add(rows, Ordering::Relaxed);
+                activity.dead_rows.fetch_add(rows, Ordering::Relaxed);
+                let _ =
+                    activity
+                        .live_rows
+                        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |live| {
+                            Some(live.saturating_sub(rows))
+                        });
+            }
+        }
+    }
+
+    /// Rows inserted, updated or deleted since the last `ANALYZE`.
+    pub fn modifications_since_analyze(&self) -> u64 {
+        self.modifications.0.load(Ordering::Relaxed)
+    }
+
+    /// The table's write counters.
+    pub fn activity(&self) -> TableActivity {
+        let activity = &self.activity;
+        TableActivity {
+            inserts: activity.inserts.load(Ordering::Relaxed),
+            updates: activity.updates.load(Ordering::Relaxed),
+            deletes: activity.deletes.load(Ordering::Relaxed),
+            live_rows: activity.live_rows.load(Ordering::Relaxed),
+            modifications_since_analyze: self.modifications_since_analyze(),
+            dead_rows: activity.dead_rows.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Note that `VACUUM` reclaimed the space of the table's dead rows.
+    pub fn record_vacuum(&self) {
+        self.activity.dead_rows.store(0, Ordering::Relaxed);
+    }
+
+    /// Store freshly gathered statistics.
+    ///
+    /// `modifications_seen` is the value of
+    /// [`modifications_since_analyze`](Self::modifications_since_analyze)
+    /// when the rows were read; changes recorded after that still count
+    /// against the new statistics.
+    pub fn set_statistics(&mut self, statistics: TableStatistics, modifications_seen: u64) {
+        *self.activity.live_rows.get_mut() = statistics.row_count;
+        self.statistics = Some(statistics);
+        let counter = self.modifications.0.get_mut();
+        *counter = counter.saturating_sub(modifications_seen);
+    }
+
+    /// Rename a column in place; stored rows are unaffected.
+    pub fn rename_column(&mut self, old: &str, new: &str) -> DbResult<()> {
+        let ordinal = self.schema.column_index(old).ok_or_else(|| {
+            DbError::Catalog(format!("unknown column '{old}' in table '{}'", self.name))
+        })?;
+        let mut columns = self.schema.columns.clone();
+        columns[ordinal as usize].name = new.to_string();
+        self.schema = TableSchema::try_new(columns)?;
+        Ok(())
+    }
+
+    /// Check a full row against the table's NOT NULL constraints.
+    ///
+    /// Values missing from the end of `values` count as NULL.
+    pub fn check_not_null(&self, values: &[Value]) -> DbResult<()> {
+        for (idx, column) in self.schema.columns.iter().enumerate() {
+            if column.not_null && matches!(values.get(idx), None | Some(Value::Null)) {
+                return Err(DbError::Constraint(format!(
+                    "NULL value in column '{}' of table '{}' violates NOT NULL constraint",
+                    column.name, self.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Bring a full row's values to their columns' types (see
+    /// [`Value::coerce_to`]). Floats, decimals, dates, timestamps and bytes
+    /// are rejected outside columns of their own type, DATE and TIMESTAMP
+    /// columns reject text that is not a date or timestamp, and DECIMAL
+    /// columns reject numbers with more digits before the point than they
+    /// allow. Text longer than a `VARCHAR(n)` or `CHAR(n)` column allows is
+    /// rejected, and `CHAR(n)` pads shorter text (see [`TextLength::fit`]).
+    pub fn coerce_types(&self, values: &mut [Value]) -> DbResult<()> {
+        for (column, value) in self.schema.columns.iter().zip(values.iter_mut()) {
+            *value = std::mem::replace(value, Value::Null).coerce_to(&column.ty);
+            let fits = match value {
+                Value::Null => true,
+                Value::Float(_) => column.ty == SqlType::Float,
+                Value::Date(_) => column.ty == SqlType::Date,
+                Value::Timestamp(_) => column.ty == SqlType::Timestamp,
+                Value::Bytes(_) => column.ty == SqlType::Bytes,
+                Value::Decimal(d) => matches!(
+                    column.ty,
+                    SqlType::Decimal { precision, scale }
+                        if d.scale() == scale && d.precision() <= precision
+                ),
+                _ => !matches!(
+                    column.ty,
+                    SqlType::Date | SqlType::Timestamp | SqlType::Decimal { .. } | SqlType::Bytes
+                ),
+            };
+            if !fits {
+                return Err(DbError::Constraint(format!(
+                    "column '{}' of table '{}' has type {} and cannot hold {}",
+                    column.name,
+                    self.name,
+                    column.ty,
+                    value.describe()
+                )));
+            }
+            if let (Some(length), Value::Text(text)) = (column.length, &mut *value) {
+                *text = length.fit(text).ok_or_else(|| {
+                    DbError::Constraint(format!(
+                        "value too long for column '{}' of table '{}' of type {length}: \
+                         {text:?} has {} characters",
+                        column.name,
+                        self.name,
+                        text.chars().count()
+                    ))
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Names and IDs under which the table's rows are stored: one pair per
+    /// partition of a partitioned table, otherwise the table's own.
+    ///
+    /// Partition `p` of table `t` is stored under the name `t.p`.
+    pub fn storage_units(&self) -> Vec<(String, TableId)> {
+        match &self.partitioning {
+            Some(partitioning) => partitioning
+                .partitions
+                .iter()
+                .map(|partition| {
+                    (
+                        format!("{}.{}", self.name, partition.name),
+                        partition.storage_id,
+                    )
+                })
+                .collect(),
+            None => vec![(self.name.clone(), self.id)],
+        }
+    }
+
+    /// Ordinal of the implicit row version column, if the table has one.
+    pub fn row_version_column(&self) -> Option<ColumnId> {
+        self.schema.column_index(ROW_VERSION_COLUMN)
+    }
+
     fn rebuild_index_lookup(&mut self) {
         self.index_name_lookup.clear();
         self.index_id_lookup.clear();
@@ -566,6 +1152,14 @@ impl TableSchema {
     pub fn columns(&self) -> &[Column] {
         self.columns.as_slice()
     }
+
+    /// Pad a stored row written before trailing columns were added, using
+    /// each missing column's default.
+    pub fn fill_missing_columns(&self, values: &mut Vec<Value>) {
+        if let Some(missing) = self.columns.get(values.len()..) {
+            values.extend(missing.iter().map(Column::default_value));
+        }
+    }
 }
 
 /// Describes a logical column within a table schema.
@@ -573,6 +1167,21 @@ impl TableSchema {
 pub struct Column {
     pub name: String,
     pub ty: SqlType,
+    /// Value used when an INSERT does not supply this column.
+    /// `None` means the column defaults to NULL.
+    #[serde(default)]
+    pub default: Option<Value>,
+    /// Whether the column rejects NULL values.
+    #[serde(default)]
+    pub not_null: bool,
+    /// Sequence that fills the column when an INSERT omits it or supplies
+    /// NULL (an auto-increment column).
+    #[serde(default)]
+    pub sequence: Option<String>,
+    /// Length of a `VARCHAR(n)` or `CHAR(n)` column, whose type is
+    /// [`SqlType::Text`]. `None` for text of any length and other types.
+    #[serde(default)]
+    pub length: Option<TextLength>,
 }
 
 impl Column {
@@ -580,10 +1189,314 @@ impl Column {
         Self {
             name: name.into(),
             ty,
+            default: None,
+            not_null: false,
+            sequence: None,
+            length: None,
+        }
+    }
+
+    /// Reject NULL values in this column.
+    pub fn with_not_null(mut self) -> Self {
+        self.not_null = true;
+        self
+    }
+
+    /// Set the value used when an INSERT omits this column.
+    pub fn with_default(mut self, default: Value) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Fill this column from the named sequence when an INSERT omits it.
+    pub fn with_sequence(mut self, name: impl Into<String>) -> Self {
+        self.sequence = Some(name.into());
+        self
+    }
+
+    /// Limit this text column to `length` characters.
+    pub fn with_length(mut self, length: TextLength) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    /// The column's type as declared, e.g. `VARCHAR(20)` rather than `TEXT`.
+    pub fn type_name(&self) -> String {
+        match self.length {
+            Some(length) => length.to_string(),
+            None => self.ty.to_string(),
+        }
+    }
+
+    /// Value for this column when an INSERT does not supply one.
+    pub fn default_value(&self) -> Value {
+        self.default.clone().unwrap_or(Value::Null)
+    }
+}
+
+/// Buckets in the histogram `ANALYZE` builds for each column.
+pub const HISTOGRAM_BUCKETS: usize = 10;
+
+/// Planner statistics for a table, gathered by `ANALYZE`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TableStatistics {
+    /// Rows in the table when it was analyzed.
+    pub row_count: u64,
+    /// Pages holding the table's rows when it was analyzed.
+    #[serde(default)]
+    pub page_count: u64,
+    /// One entry per column, in schema order.
+    pub columns: Vec<ColumnStatistics>,
+    /// Sizes of the stored rows and how full their pages are, for storage
+    /// that keeps rows in pages.
+    #[serde(default)]
+    pub storage: Option<StorageStatistics>,
+}
+
+/// How a table's rows fill its pages, gathered by `ANALYZE`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StorageStatistics {
+    /// Pages holding rows.
+    pub pages: u64,
+    /// Bytes of those pages taken by page headers, slots and rows, including
+    /// the space of deleted rows not yet reclaimed.
+    pub used_bytes: u64,
+    /// Bytes in a page.
+    pub page_size: u64,
+    /// Rows stored in the pages.
+    pub rows: u64,
+    /// Bytes taken by the stored rows, excluding their slots.
+    pub row_bytes: u64,
+    /// Smallest stored row, in bytes.
+    pub min_row_bytes: u64,
+    /// Largest stored row, in bytes.
+    pub max_row_bytes: u64,
+    /// Bounds of an equi-depth histogram of stored row sizes, like
+    /// [`ColumnStatistics::histogram`].
+    pub row_size_histogram: Vec<u64>,
+}
+
+impl StorageStatistics {
+    /// Statistics of `pages` pages of `page_size` bytes, of which
+    /// `used_bytes` are in use, holding rows of `row_sizes` bytes.
+    pub fn new(pages: u64, used_bytes: u64, page_size: u64, mut row_sizes: Vec<u64>) -> Self {
+        row_sizes.sort_unstable();
+        Self {
+            pages,
+            used_bytes,
+            page_size,
+            rows: row_sizes.len() as u64,
+            row_bytes: row_sizes.iter().sum(),
+            min_row_bytes: row_sizes.first().copied().unwrap_or(0),
+            max_row_bytes: row_sizes.last().copied().unwrap_or(0),
+            row_size_histogram: histogram_bounds(&row_sizes),
+        }
+    }
+
+    /// Fraction of the pages' bytes in use, or 0 without pages.
+    pub fn fill_factor(&self) -> f64 {
+        match self.pages * self.page_size {
+            0 => 0.0,
+            capacity => self.used_bytes as f64 / capacity as f64,
+        }
+    }
+
+    /// Mean size of a stored row in bytes, or 0 without rows.
+    pub fn avg_row_bytes(&self) -> f64 {
+        match self.rows {
+            0 => 0.0,
+            rows => self.row_bytes as f64 / rows as f64,
+        }
+    }
+}
+
+/// Planner statistics for one column.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ColumnStatistics {
+    /// Distinct non-NULL values.
+    pub distinct_count: u64,
+    /// NULL values.
+    pub null_count: u64,
+    /// Smallest non-NULL value.
+    #[serde(default)]
+    pub min: Option<Value>,
+    /// Largest non-NULL value.
+    #[serde(default)]
+    pub max: Option<Value>,
+    /// Bounds of an equi-depth histogram of the non-NULL values: each of the
+    /// (up to [`HISTOGRAM_BUCKETS`]) buckets between adjacent bounds holds
+    /// about the same number of values. Empty if every value is NULL.
+    #[serde(default)]
+    pub histogram: Vec<Value>,
+}
+
+impl TableStatistics {
+    /// Gather statistics over every row of a table with `column_count`
+    /// columns. Missing trailing values count as NULL.
+    pub fn from_rows<'a>(column_count: usize, rows: impl IntoIterator<Item = &'a [Value]>) -> Self {
+        let mut values: Vec<Vec<&Value>> = vec![Vec::new(); column_count];
+        let mut columns = vec![ColumnStatistics::default(); column_count];
+        let mut row_count = 0;
+        for row in rows {
+            row_count += 1;
+            for (col, stats) in columns.iter_mut().enumerate() {
+                match row.get(col) {
+                    None | Some(Value::Null) => stats.null_count += 1,
+                    Some(value) => values[col].push(value),
+                }
+            }
         }
+        for (stats, values) in columns.iter_mut().zip(&mut values) {
+            values.sort_unstable();
+            stats.distinct_count = values.iter().collect::<Set<_>>().len() as u64;
+            stats.min = values.first().map(|v| (*v).clone());
+            stats.max = values.last().map(|v| (*v).clone());
+            stats.histogram = histogram_bounds(values).into_iter().cloned().collect();
+        }
+        Self {
+            row_count,
+            page_count: 0,
+            columns,
+            storage: None,
+        }
+    }
+
+    /// Record that the table's rows filled `pages` pages.
+    pub fn with_page_count(mut self, pages: u64) -> Self {
+        self.page_count = pages;
+        self
+    }
+
+    /// Record how the table's rows fill their pages.
+    pub fn with_storage(mut self, storage: StorageStatistics) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+}
+
+/// Bounds splitting sorted `values` into [`HISTOGRAM_BUCKETS`] buckets of
+/// about equal size, or fewer if there are fewer values.
+fn histogram_bounds<T: Clone>(values: &[T]) -> Vec<T> {
+    let Some(last) = values.len().checked_sub(1) else {
+        return Vec::new();
+    };
+    let buckets = HISTOGRAM_BUCKETS.min(last.max(1));
+    (0..=buckets)
+        .map(|i| values[i * last / buckets].clone())
+        .collect()
+}
+
+/// Shared-reference counter for [`TableMeta::record_modifications`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+struct ModificationCounter(AtomicU64);
+
+impl Clone for ModificationCounter {
+    fn clone(&self) -> Self {
+        Self(AtomicU64::new(self.0.load(Ordering::Relaxed)))
     }
 }
 
+/// Kind of row write counted by [`TableMeta::record_modifications`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Modification {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// Rows written to a table since it was created (see
+/// [`TableMeta::activity`]).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TableActivity {
+    /// Rows inserted.
+    pub inserts: u64,
+    /// Rows updated.
+    pub updates: u64,
+    /// Rows deleted.
+    pub deletes: u64,
+    /// Estimated rows in the table: the count from the last `ANALYZE`,
+    /// adjusted by the rows inserted and deleted since.
+    pub live_rows: u64,
+    /// Rows inserted, updated or deleted since the last `ANALYZE`.
+    pub modifications_since_analyze: u64,
+    /// Rows updated or deleted since the last `VACUUM`: at most the number
+    /// of dead rows taking up space in the table's pages.
+    pub dead_rows: u64,
+}
+
+/// Shared-reference counters behind [`TableActivity`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ActivityCounters {
+    inserts: AtomicU64,
+    updates: AtomicU64,
+    deletes: AtomicU64,
+    live_rows: AtomicU64,
+    #[serde(default)]
+    dead_rows: AtomicU64,
+}
+
+impl Clone for ActivityCounters {
+    fn clone(&self) -> Self {
+        let copy = |counter: &AtomicU64| AtomicU64::new(counter.load(Ordering::Relaxed));
+        Self {
+            inserts: copy(&self.inserts),
+            updates: copy(&self.updates),
+            deletes: copy(&self.deletes),
+            live_rows: copy(&self.live_rows),
+            dead_rows: copy(&self.dead_rows),
+        }
+    }
+}
+
+/// Persistent counter that hands out values for an auto-increment column,
+/// starting at 1.
+///
+/// Values are taken through a shared reference, so an executor holding the
+/// catalog read-only can allocate them. The counter reaches disk the next
+/// time the catalog is saved; values taken but not saved before a crash are
+/// handed out again, so callers must skip values that are already in use.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Sequence {
+    next_value: AtomicI64,
+}
+
+impl Sequence {
+    /// Take the next value.
+    pub fn next_value(&self) -> i64 {
+        self.next_value.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// The value the next call to [`Sequence::next_value`] will return.
+    pub fn peek(&self) -> i64 {
+        self.next_value.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for Sequence {
+    fn default() -> Self {
+        Self {
+            next_value: AtomicI64::new(1),
+        }
+    }
+}
+
+impl Clone for Sequence {
+    fn clone(&self) -> Self {
+        Self {
+            next_value: AtomicI64::new(self.peek()),
+        }
+    }
+}
+
+/// A named query that can be selected from like a table.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ViewMeta {
+    pub name: String,
+    /// The view's SELECT statement as SQL text.
+    pub query: String,
+}
+
 /// Metadata describing a table index.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct IndexMeta {
@@ -592,6 +1505,9 @@ pub struct IndexMeta {
     pub columns: Vec<ColumnId>,
     pub kind: IndexKind,
     pub storage: StorageDescriptor,
+    /// Whether no two rows may share a key. Keys containing NULL are exempt.
+    #[serde(default)]
+    pub unique: bool,
 }
 
 /// Supported index implementations.
@@ -606,14 +1522,236 @@ pub enum IndexKind {
 impl IndexKind {
     fn supports_type(&self, ty: &SqlType) -> bool {
         match self {
-            IndexKind::BTree => matches!(ty, SqlType::Int | SqlType::Text | SqlType::Bool),
-            IndexKind::Hash => matches!(ty, SqlType::Int | SqlType::Text | SqlType::Bool),
+            IndexKind::BTree | IndexKind::Hash => matches!(
+                ty,
+                SqlType::Int
+                    | SqlType::Text
+                    | SqlType::Bool
+                    | SqlType::Float
+                    | SqlType::Date
+                    | SqlType::Timestamp
+                    | SqlType::Decimal { .. }
+            ),
             IndexKind::Bitmap => matches!(ty, SqlType::Bool),
             IndexKind::Trie => matches!(ty, SqlType::Text),
         }
     }
 }
 
+/// Storage engines a table can be created on.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum EngineKind {
+    /// Slotted-page heap files, one per table.
+    #[default]
+    Heap,
+    /// Rows kept in memory and lost when the database closes.
+    Memory,
+    /// Log-structured merge tree, for write-heavy tables.
+    Lsm,
+    /// Column by column, for analytics tables that scan a few columns.
+    Columnar,
+}
+
+impl EngineKind {
+    /// Resolve the name used in `CREATE TABLE ... ENGINE = <name>` or
+    /// `USING <name>`.
+    pub fn from_name(name: &str) -> DbResult<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "heap" => Ok(EngineKind::Heap),
+            "memory" => Ok(EngineKind::Memory),
+            "lsm" => Ok(EngineKind::Lsm),
+            "columnar" => Ok(EngineKind::Columnar),
+            other => Err(DbError::Catalog(format!(
+                "unknown storage engine '{other}'; supported: heap, memory, lsm, columnar"
+            ))),
+        }
+    }
+}
+
+/// How a partitioned table assigns rows to its partitions.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PartitionMethod {
+    /// Each partition holds keys below its upper bound that no earlier
+    /// partition holds.
+    Range,
+    /// Each partition holds the keys it lists.
+    List,
+}
+
+/// The keys a partition holds.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum PartitionBound {
+    /// `VALUES LESS THAN (v)`, or `VALUES LESS THAN (MAXVALUE)` for `None`.
+    LessThan(Option<Value>),
+    /// `VALUES IN (v, ...)`.
+    In(Vec<Value>),
+}
+
+impl PartitionBound {
+    /// Convert the bound's values for a key column of type `ty` (see
+    /// [`Value::coerce_to`]), so that `'2024-01-01'` bounds a DATE key.
+    fn coerce_to(self, ty: &SqlType) -> Self {
+        match self {
+            PartitionBound::LessThan(upper) => {
+                PartitionBound::LessThan(upper.map(|value| value.coerce_to(ty)))
+            }
+            PartitionBound::In(values) => PartitionBound::In(
+                values
+                    .into_iter()
+                    .map(|value| value.coerce_to(ty))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// One partition of a partitioned table.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Partition {
+    pub name: String,
+    /// Identifies the partition's storage; drawn from the table ID sequence
+    /// so that it is distinct from every table's.
+    pub storage_id: TableId,
+    pub bound: PartitionBound,
+}
+
+/// Declarative partitioning of a table by the value of one column.
+///
+/// Rows are stored per partition. NULL keys sort lowest, so they belong to the
+/// first range partition; a list partition only holds NULLs if it lists NULL.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Partitioning {
+    pub method: PartitionMethod,
+    pub column: ColumnId,
+    pub partitions: Vec<Partition>,
+}
+
+impl Partitioning {
+    /// Position of the partition that holds rows whose key is `key`, or
+    /// `None` if no partition accepts it.
+    pub fn partition_for(&self, key: &Value) -> Option<usize> {
+        self.partitions
+            .iter()
+            .position(|partition| match &partition.bound {
+                PartitionBound::LessThan(None) => true,
+                PartitionBound::LessThan(Some(upper)) => key < upper,
+                PartitionBound::In(values) => values.contains(key),
+            })
+    }
+
+    /// Positions of the partitions that may hold non-NULL keys between `low`
+    /// and `high`.
+    pub fn overlapping(&self, low: Bound<&Value>, high: Bound<&Value>) -> Vec<usize> {
+        let mut lower: Option<&Value> = None;
+        let mut matching = Vec::new();
+        for (position, partition) in self.partitions.iter().enumerate() {
+            let overlaps = match &partition.bound {
+                PartitionBound::LessThan(upper) => {
+                    let above_lower = lower.is_none_or(|lower| match high {
+                        Bound::Included(high) => high >= lower,
+                        Bound::Excluded(high) => high > lower,
+                        Bound::Unbounded => true,
+                    });
+                    let below_upper = upper.as_ref().is_none_or(|upper| match low {
+                        Bound::Included(low) | Bound::Excluded(low) => low < upper,
+                        Bound::Unbounded => true,
+                    });
+                    lower = upper.as_ref();
+                    above_lower && below_upper
+                }
+                PartitionBound::In(values) => values
+                    .iter()
+                    .any(|value| *value != Value::Null && (low, high).contains(value)),
+            };
+            if overlaps {
+                matching.push(position);
+            }
+        }
+        matching
+    }
+
+    /// Check that the bounds suit the method and the key column's type, and
+    /// that no key belongs to two partitions.
+    fn validate(&self, table: &str, key_type: &SqlType) -> DbResult<()> {
+        let invalid = |msg: String| {
+            Err(DbError::Catalog(format!(
+                "invalid partitioning of table '{table}': {msg}"
+            )))
+        };
+        if self.partitions.is_empty() {
+            return invalid("at least one partition is required".into());
+        }
+        let fits_key = |value: &Value| match value {
+            Value::Int(_) => *key_type == SqlType::Int,
+            Value::Text(_) => *key_type == SqlType::Text,
+            Value::Bool(_) => *key_type == SqlType::Bool,
+            Value::Float(_) => *key_type == SqlType::Float,
+            Value::Date(_) => *key_type == SqlType::Date,
+            Value::Timestamp(_) => *key_type == SqlType::Timestamp,
+            Value::Decimal(d) => {
+                matches!(key_type, SqlType::Decimal { scale, .. } if d.scale() == *scale)
+            }
+            Value::Null | Value::Bytes(_) => false,
+        };
+
+        let mut names = BTreeSet::new();
+        let mut listed = BTreeSet::new();
+        let mut previous_upper: Option<&Value> = None;
+        for (position, partition) in self.partitions.iter().enumerate() {
+            if !names.insert(partition.name.as_str()) {
+                return invalid(format!("duplicate partition '{}'", partition.name));
+            }
+            match (&self.method, &partition.bound) {
+                (PartitionMethod::Range, PartitionBound::LessThan(upper)) => {
+                    if let Some(upper) = upper {
+                        if !fits_key(upper) {
+                            return invalid(format!(
+                                "bound {upper:?} of partition '{}' does not match the key type",
+                                partition.name
+                            ));
+                        }
+                        if previous_upper.is_some_and(|previous| upper <= previous) {
+                            return invalid(format!(
+                                "range bounds must increase, but partition '{}' does not",
+                                partition.name
+                            ));
+                        }
+                    }
+                    previous_upper = upper.as_ref();
+                    if upper.is_none() && position + 1 < self.partitions.len() {
+                        return invalid("only the last partition may use MAXVALUE".into());
+                    }
+                }
+                (PartitionMethod::List, PartitionBound::In(values)) => {
+                    if values.is_empty() {
+                        return invalid(format!("partition '{}' lists no values", partition.name));
+                    }
+                    for value in values {
+                        if *value != Value::Null && !fits_key(value) {
+                            return invalid(format!(
+                                "value {value:?} of partition '{}' does not match the key type",
+                                partition.name
+                            ));
+                        }
+                        if !listed.insert(value) {
+                            return invalid(format!(
+                                "value {value:?} is listed by more than one partition"
+                            ));
+                        }
+                    }
+                }
+                (PartitionMethod::Range, PartitionBound::In(_)) => {
+                    return invalid("RANGE partitions take VALUES LESS THAN".into());
+                }
+                (PartitionMethod::List, PartitionBound::LessThan(_)) => {
+                    return invalid("LIST partitions take VALUES IN".into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Lightweight description for external inspection calls.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TableSummary {
@@ -672,9 +1810,399 @@ mod tests {
             columns,
             kind: IndexKind::BTree,
             storage: StorageDescriptor::new(),
+            unique: false,
         }
     }
 
+    #[test]
+    fn sequences_persist_and_drop_with_their_table() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("catalog.json");
+        let name = sequence_name("orders", "id");
+        let mut catalog = Catalog::new();
+        let columns = vec![Column::new("id", SqlType::Int).with_sequence(&name)];
+        catalog
+            .create_table("orders", columns.clone(), None)
+            .unwrap();
+        assert_eq!(catalog.sequence(&name).unwrap().next_value(), 1);
+        assert_eq!(catalog.sequence(&name).unwrap().next_value(), 2);
+        catalog.save(&path).unwrap();
+
+        let mut loaded = Catalog::load(&path).unwrap();
+        assert_eq!(loaded.sequence(&name).unwrap().peek(), 3);
+        let err = loaded.create_table("copy", columns, None).unwrap_err();
+        assert!(format!("{err}").contains("already exists"));
+
+        loaded.drop_table("orders").unwrap();
+        assert!(loaded.sequence(&name).is_err());
+    }
+
+    #[test]
+    fn temporary_tables_are_not_saved() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("catalog.json");
+        let name = sequence_name("scratch", "id");
+        let mut catalog = Catalog::new();
+        catalog
+            .create_table("users", sample_columns(), None)
+            .unwrap();
+        let columns = vec![Column::new("id", SqlType::Int).with_sequence(&name)];
+        catalog.create_table("scratch", columns, None).unwrap();
+        catalog.table_mut("scratch").unwrap().temporary = true;
+        catalog.save(&path).unwrap();
+
+        // The catalog in memory keeps the table
+        assert!(catalog.table("scratch").is_ok());
+        assert!(catalog.sequence(&name).is_ok());
+
+        let loaded = Catalog::load(&path).unwrap();
+        assert!(loaded.table("users").is_ok());
+        assert!(loaded.table("scratch").is_err());
+        assert!(loaded.sequence(&name).is_err());
+    }
+
+    #[test]
+    fn views_persist_and_share_the_table_namespace() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("catalog.json");
+        let mut catalog = Catalog::new();
+        catalog
+            .create_table("users", sample_columns(), None)
+            .unwrap();
+        catalog
+            .create_view("adults", "SELECT id, name FROM users WHERE age >= 18")
+            .unwrap();
+
+        let err = catalog
+            .create_view("users", "SELECT id FROM users")
+            .unwrap_err();
+        assert_eq!(format!("{err}"), "catalog: table 'users' already exists");
+        let err = catalog
+            .create_table("adults", sample_columns(), None)
+            .unwrap_err();
+        assert_eq!(format!("{err}"), "catalog: view 'adults' already exists");
+        catalog.save(&path).unwrap();
+
+        let mut loaded = Catalog::load(&path).unwrap();
+        assert_eq!(
+            loaded.view("adults").unwrap().query,
+            "SELECT id, name FROM users WHERE age >= 18"
+        );
+        assert_eq!(loaded.views().count(), 1);
+        loaded.drop_view("adults").unwrap();
+        assert!(loaded.view("adults").is_err());
+        let err = loaded.drop_view("adults").unwrap_err();
+        assert_eq!(format!("{err}"), "catalog: unknown view 'adults'");
+    }
+
+    #[test]
+    fn statistics_track_modifications_since_analyze() {
+        let mut catalog = Catalog::new();
+        let table_id = catalog
+            .create_table("people", sample_columns(), None)
+            .unwrap();
+        let rows = [
+            vec![Value::Int(1), Value::Text("a".into()), Value::Int(30)],
+            vec![Value::Int(2), Value::Text("a".into()), Value::Null],
+        ];
+        let stats = TableStatistics::from_rows(4, rows.iter().map(Vec::as_slice));
+        assert_eq!(stats.row_count, 2);
+        assert_eq!(
+            stats.columns[1],
+            ColumnStatistics {
+                distinct_count: 1,
+                null_count: 0,
+                min: Some(Value::Text("a".into())),
+                max: Some(Value::Text("a".into())),
+                histogram: vec![Value::Text("a".into()), Value::Text("a".into())],
+            }
+        );
+        assert_eq!(stats.columns[2].null_count, 1);
+        assert_eq!(stats.columns[3].null_count, 2);
+
+        let table = catalog.table("people").unwrap();
+        table.record_modifications(Modification::Insert, 5);
+        let epoch = catalog.epoch();
+        // Rows written while the table was being analyzed still count
+        catalog
+            .set_table_statistics(table_id, stats.clone(), 3)
+            .unwrap();
+        let table = catalog.table("people").unwrap();
+        assert_eq!(table.statistics.as_ref(), Some(&stats));
+        assert_eq!(table.modifications_since_analyze(), 2);
+        assert_eq!(catalog.epoch(), epoch);
+
+        let stale = TableStatistics::from_rows(2, std::iter::empty());
+        assert!(catalog.set_table_statistics(table_id, stale, 0).is_err());
+        catalog
+            .table_mut("people")
+            .unwrap()
+            .add_column(Column::new("extra", SqlType::Int))
+            .unwrap();
+        assert!(catalog.table("people").unwrap().statistics.is_none());
+    }
+
+    #[test]
+    fn activity_counts_writes_by_kind_and_survives_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("catalog.json");
+        let mut catalog = Catalog::new();
+        let table_id = catalog
+            .create_table("people", sample_columns(), None)
+            .unwrap();
+        let table = catalog.table("people").unwrap();
+        table.record_modifications(Modification::Insert, 10);
+        table.record_modifications(Modification::Update, 4);
+        table.record_modifications(Modification::Delete, 3);
+        assert_eq!(
+            table.activity(),
+            TableActivity {
+                inserts: 10,
+                updates: 4,
+                deletes: 3,
+                live_rows: 7,
+                modifications_since_analyze: 17,
+                dead_rows: 7,
+            }
+        );
+
+        // Analyzing replaces the live row estimate with the counted rows
+        let rows = [vec![Value::Int(1), Value::Null, Value::Null, Value::Null]];
+        let stats = TableStatistics::from_rows(4, rows.iter().map(Vec::as_slice));
+        catalog.set_table_statistics(table_id, stats, 17).unwrap();
+        catalog.save(&path).unwrap();
+
+        let loaded = Catalog::load(&path).unwrap();
+        let activity = loaded.table("people").unwrap().activity();
+        assert_eq!(activity.inserts, 10);
+        assert_eq!(activity.live_rows, 1);
+        assert_eq!(activity.modifications_since_analyze, 0);
+        assert_eq!(activity.dead_rows, 7);
+
+        // Vacuuming only clears the dead rows
+        loaded.table("people").unwrap().record_vacuum();
+        let activity = loaded.table("people").unwrap().activity();
+        assert_eq!((activity.inserts, activity.dead_rows), (10, 0));
+    }
+
+    #[test]
+    fn statistics_bound_each_column_with_a_histogram() {
+        let rows: Vec<Vec<Value>> = (1..=100)
+            .rev()
+            .map(|i| vec![Value::Int(i), Value::Int(i % 4)])
+            .collect();
+        let stats =
+            TableStatistics::from_rows(2, rows.iter().map(Vec::as_slice)).with_page_count(3);
+        assert_eq!((stats.row_count, stats.page_count), (100, 3));
+
+        let ids = &stats.columns[0];
+        assert_eq!(
+            (ids.min.clone(), ids.max.clone()),
+            (Some(Value::Int(1)), Some(Value::Int(100)))
+        );
+        assert_eq!(ids.histogram.len(), HISTOGRAM_BUCKETS + 1);
+        assert_eq!(ids.histogram[0], Value::Int(1));
+        assert_eq!(ids.histogram[5], Value::Int(50));
+        assert_eq!(ids.histogram[HISTOGRAM_BUCKETS], Value::Int(100));
+        assert_eq!(stats.columns[1].distinct_count, 4);
+
+        let single = TableStatistics::from_rows(1, [[Value::Int(7)].as_slice()]);
+        assert_eq!(
+            single.columns[0].histogram,
+            vec![Value::Int(7), Value::Int(7)]
+        );
+        let empty = TableStatistics::from_rows(1, [[Value::Null].as_slice()]);
+        assert!(empty.columns[0].histogram.is_empty());
+        assert_eq!(empty.columns[0].min, None);
+    }
+
+    #[test]
+    fn storage_statistics_summarize_row_sizes_and_fill() {
+        let sizes = vec![40, 10, 30, 20];
+        let storage = StorageStatistics::new(2, 4096, 4096, sizes);
+        assert_eq!((storage.rows, storage.row_bytes), (4, 100));
+        assert_eq!((storage.min_row_bytes, storage.max_row_bytes), (10, 40));
+        assert_eq!(storage.row_size_histogram, vec![10, 20, 30, 40]);
+        assert_eq!(storage.fill_factor(), 0.5);
+        assert_eq!(storage.avg_row_bytes(), 25.0);
+
+        let empty = StorageStatistics::new(0, 0, 4096, Vec::new());
+        assert_eq!((empty.fill_factor(), empty.avg_row_bytes()), (0.0, 0.0));
+        assert!(empty.row_size_histogram.is_empty());
+    }
+
+    #[test]
+    fn row_version_column_lookup() {
+        let mut catalog = Catalog::new();
+        catalog
+            .create_table("plain", sample_columns(), None)
+            .unwrap();
+        let mut columns = sample_columns();
+        columns.push(Column::new(ROW_VERSION_COLUMN, SqlType::Int));
+        catalog.create_table("versioned", columns, None).unwrap();
+
+        assert_eq!(catalog.table("plain").unwrap().row_version_column(), None);
+        assert_eq!(
+            catalog.table("versioned").unwrap().row_version_column(),
+            Some(4)
+        );
+        let mut row = vec![Value::Int(1), Value::Int(3)];
+        bump_row_version(&mut row, 1);
+        assert_eq!(row[1], Value::Int(4));
+        bump_row_version(&mut row, 2);
+        assert_eq!(row[2], Value::Int(1));
+    }
+
+    #[test]
+    fn check_not_null_rejects_missing_and_null_values() {
+        let mut catalog = Catalog::new();
+        let columns = vec![
+            Column::new("id", SqlType::Int).with_not_null(),
+            Column::new("name", SqlType::Text),
+            Column::new("age", SqlType::Int).with_not_null(),
+        ];
+        catalog.create_table("users", columns, None).unwrap();
+        let table = catalog.table("users").unwrap();
+
+        table
+            .check_not_null(&[Value::Int(1), Value::Null, Value::Int(3)])
+            .unwrap();
+        let err = table
+            .check_not_null(&[Value::Int(1), Value::Null, Value::Null])
+            .unwrap_err();
+        assert!(matches!(err, DbError::Constraint(ref msg) if msg.contains("'age'")));
+        assert!(table.check_not_null(&[Value::Int(1)]).is_err());
+    }
+
+    #[test]
+    fn coerce_types_checks_text_lengths() {
+        let mut catalog = Catalog::new();
+        let columns = vec![
+            Column::new("code", SqlType::Text).with_length(TextLength::Fixed(3)),
+            Column::new("name", SqlType::Text).with_length(TextLength::Varying(4)),
+        ];
+        catalog.create_table("items", columns, None).unwrap();
+        let table = catalog.table("items").unwrap();
+        assert_eq!(table.columns()[1].type_name(), "VARCHAR(4)");
+
+        let mut row = vec![Value::Text("ab".into()), Value::Text("abcd ".into())];
+        table.coerce_types(&mut row).unwrap();
+        assert_eq!(
+            row,
+            vec![Value::Text("ab ".into()), Value::Text("abcd".into())]
+        );
+        let mut row = vec![Value::Null, Value::Text("abcde".into())];
+        let err = table.coerce_types(&mut row).unwrap_err();
+        assert!(
+            matches!(err, DbError::Constraint(ref msg) if msg.contains("type VARCHAR(4)")),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn encrypted_catalog_round_trips_only_with_key() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("catalog.json");
+        let key = EncryptionKey::from_bytes(&[5u8; crypto::KEY_LEN]).unwrap();
+
+        let mut catalog = Catalog::load_with_key(&path, Some(key.clone())).unwrap();
+        catalog
+            .create_table("patients", sample_columns(), None)
+            .unwrap();
+        catalog.save(&path).unwrap();
+
+        let raw = fs::read(&path).unwrap();
+        assert!(!raw.windows(8).any(|w| w == b"patients"));
+        assert!(Catalog::load(&path).is_err());
+
+        let loaded = Catalog::load_with_key(&path, Some(key)).unwrap();
+        assert!(loaded.table("patients").is_ok());
+        assert!(loaded.encryption_key().is_some());
+    }
+
+    #[test]
+    fn epoch_advances_once_per_bump() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("catalog.json");
+        let mut catalog = Catalog::new();
+        assert_eq!(catalog.epoch(), 0);
+
+        // A statement's mutations share one bump
+        catalog
+            .create_table("users", sample_columns(), None)
+            .unwrap();
+        catalog
+            .table_mut("users")
+            .unwrap()
+            .rename_column("age", "years")
+            .unwrap();
+        assert_eq!(catalog.epoch(), 0);
+        assert_eq!(catalog.bump_epoch(), 1);
+
+        catalog.save(&path).unwrap();
+        let mut loaded = Catalog::load(&path).unwrap();
+        assert_eq!(loaded.epoch(), 1);
+
+        let mut empty = Catalog::new();
+        empty.advance_epoch_past(loaded.epoch());
+        assert!(empty.epoch() > loaded.epoch());
+
+        assert!(loaded.advance_epoch_to(5));
+        assert!(!loaded.advance_epoch_to(3));
+        assert_eq!(loaded.epoch(), 5);
+    }
+
+    #[test]
+    fn alter_table_columns() {
+        let mut catalog = Catalog::new();
+        catalog
+            .create_table("users", sample_columns(), Some(vec![0]))
+            .unwrap();
+        catalog
+            .create_index()
+            .table_name("users")
+            .index_name("idx_active")
+            .columns(&["active"])
+            .kind(IndexKind::BTree)
+            .call()
+            .unwrap();
+
+        let table = catalog.table_mut("users").unwrap();
+        let ordinal = table
+            .add_column(Column::new("score", SqlType::Int).with_default(Value::Int(0)))
+            .unwrap();
+        assert_eq!(ordinal, 4);
+        assert!(
+            table
+                .add_column(Column::new("name", SqlType::Text))
+                .is_err()
+        );
+
+        let mut row = vec![Value::Int(1), Value::Text("a".into())];
+        table.schema.fill_missing_columns(&mut row);
+        assert_eq!(
+            row,
+            vec![
+                Value::Int(1),
+                Value::Text("a".into()),
+                Value::Null,
+                Value::Null,
+                Value::Int(0)
+            ]
+        );
+
+        assert!(table.drop_column("id").is_err());
+        assert!(table.drop_column("active").is_err());
+        assert_eq!(table.drop_column("name").unwrap(), 1);
+        assert_eq!(table.index("idx_active").unwrap().columns, vec![2]);
+        assert_eq!(table.schema.column_index("score"), Some(3));
+
+        table.rename_column("age", "years").unwrap();
+        assert_eq!(table.schema.column_index("years"), Some(1));
+        assert!(table.rename_column("years", "score").is_err());
+        assert!(table.rename_column("missing", "x").is_err());
+    }
+
     #[test]
     fn create_and_lookup_table() {
         let mut catalog = Catalog::new();
@@ -749,6 +2277,59 @@ mod tests {
         assert!(!catalog.table("users").unwrap().has_index("idx_users_name"));
     }
 
+    #[test]
+    fn table_engine_persists_and_defaults_to_heap() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("catalog.json");
+        let mut catalog = Catalog::new();
+        catalog
+            .create_table("cache", sample_columns(), None)
+            .unwrap();
+        catalog
+            .create_table("users", sample_columns(), None)
+            .unwrap();
+        catalog.table_mut("cache").unwrap().engine = EngineKind::from_name("MEMORY").unwrap();
+        catalog.save(&path).unwrap();
+
+        let loaded = Catalog::load(&path).unwrap();
+        assert_eq!(loaded.table("cache").unwrap().engine, EngineKind::Memory);
+        assert_eq!(loaded.table("users").unwrap().engine, EngineKind::Heap);
+        assert!(EngineKind::from_name("rocksdb").is_err());
+    }
+
+    #[test]
+    fn unique_flag_persists_with_index() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("catalog.json");
+        let mut catalog = Catalog::new();
+        catalog
+            .create_table("users", sample_columns(), None)
+            .unwrap();
+        catalog
+            .create_index()
+            .table_name("users")
+            .index_name("idx_users_name")
+            .columns(&["name"])
+            .kind(IndexKind::Hash)
+            .unique(true)
+            .call()
+            .unwrap();
+        catalog
+            .create_index()
+            .table_name("users")
+            .index_name("idx_users_id")
+            .columns(&["id"])
+            .kind(IndexKind::BTree)
+            .call()
+            .unwrap();
+        catalog.save(&path).unwrap();
+
+        let loaded = Catalog::load(&path).unwrap();
+        let table = loaded.table("users").unwrap();
+        assert!(table.index("idx_users_name").unwrap().unique);
+        assert!(!table.index("idx_users_id").unwrap().unique);
+    }
+
     #[test]
     fn index_creation_validates_columns() {
         let mut catalog = Catalog::new();
@@ -1237,4 +2818,150 @@ mod tests {
         let table = loaded.table("users").unwrap();
         assert_eq!(table.primary_key, Some(vec![0, 1]));
     }
+
+    #[test]
+    fn range_partitions_route_keys_and_overlap_bounds() {
+        let mut catalog = Catalog::new();
+        catalog
+            .create_table("events", sample_columns(), Some(vec![0]))
+            .unwrap();
+        catalog
+            .partition_table(
+                "events",
+                PartitionMethod::Range,
+                "age",
+                vec![
+                    (
+                        "young".into(),
+                        PartitionBound::LessThan(Some(Value::Int(18))),
+                    ),
+                    (
+                        "adult".into(),
+                        PartitionBound::LessThan(Some(Value::Int(65))),
+                    ),
+                    ("rest".into(), PartitionBound::LessThan(None)),
+                ],
+            )
+            .unwrap();
+
+        let table = catalog.table("events").unwrap();
+        let partitioning = table.partitioning.as_ref().unwrap();
+        assert_eq!(partitioning.column, 2);
+        assert_eq!(partitioning.partition_for(&Value::Int(17)), Some(0));
+        assert_eq!(partitioning.partition_for(&Value::Int(18)), Some(1));
+        assert_eq!(partitioning.partition_for(&Value::Int(90)), Some(2));
+        assert_eq!(partitioning.partition_for(&Value::Null), Some(0));
+
+        let eighteen = Value::Int(18);
+        let seventy = Value::Int(70);
+        assert_eq!(
+            partitioning.overlapping(Bound::Included(&eighteen), Bound::Excluded(&seventy)),
+            vec![1, 2]
+        );
+        assert_eq!(
+            partitioning.overlapping(Bound::Unbounded, Bound::Excluded(&eighteen)),
+            vec![0]
+        );
+
+        let storage: Vec<String> = table
+            .storage_units()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(storage, vec!["events.young", "events.adult", "events.rest"]);
+
+        let err = catalog
+            .table_mut("events")
+            .unwrap()
+            .drop_column("age")
+            .unwrap_err();
+        assert!(format!("{err}").contains("partition key"));
+    }
+
+    #[test]
+    fn list_partitions_route_listed_values_only() {
+        let mut catalog = Catalog::new();
+        catalog
+            .create_table("users", sample_columns(), None)
+            .unwrap();
+        catalog
+            .partition_table(
+                "users",
+                PartitionMethod::List,
+                "name",
+                vec![
+                    (
+                        "a".into(),
+                        PartitionBound::In(vec![Value::Text("ann".into())]),
+                    ),
+                    (
+                        "b".into(),
+                        PartitionBound::In(vec![Value::Text("bob".into()), Value::Null]),
+                    ),
+                ],
+            )
+            .unwrap();
+
+        let partitioning = catalog
+            .table("users")
+            .unwrap()
+            .partitioning
+            .clone()
+            .unwrap();
+        assert_eq!(
+            partitioning.partition_for(&Value::Text("bob".into())),
+            Some(1)
+        );
+        assert_eq!(partitioning.partition_for(&Value::Null), Some(1));
+        assert_eq!(partitioning.partition_for(&Value::Text("cy".into())), None);
+        let ann = Value::Text("ann".into());
+        assert_eq!(
+            partitioning.overlapping(Bound::Included(&ann), Bound::Included(&ann)),
+            vec![0]
+        );
+    }
+
+    #[test]
+    fn partition_table_validates_bounds() {
+        let attempt = |method, column: &str, partitions: Vec<(&str, PartitionBound)>| {
+            let mut catalog = Catalog::new();
+            catalog.create_table("t", sample_columns(), None).unwrap();
+            let partitions = partitions
+                .into_iter()
+                .map(|(name, bound)| (name.to_string(), bound))
+                .collect();
+            catalog
+                .partition_table("t", method, column, partitions)
+                .map_err(|e| e.to_string())
+        };
+        let less_than = |v: i64| PartitionBound::LessThan(Some(Value::Int(v)));
+
+        let err = attempt(
+            PartitionMethod::Range,
+            "age",
+            vec![("a", less_than(20)), ("b", less_than(10))],
+        )
+        .unwrap_err();
+        assert!(err.contains("must increase"), "{err}");
+
+        let err = attempt(
+            PartitionMethod::Range,
+            "age",
+            vec![("a", less_than(1)), ("a", less_than(2))],
+        )
+        .unwrap_err();
+        assert!(err.contains("duplicate partition"), "{err}");
+
+        let err = attempt(PartitionMethod::Range, "name", vec![("a", less_than(1))]).unwrap_err();
+        assert!(err.contains("key type"), "{err}");
+
+        let err = attempt(PartitionMethod::List, "age", vec![("a", less_than(1))]).unwrap_err();
+        assert!(err.contains("VALUES IN"), "{err}");
+
+        let err =
+            attempt(PartitionMethod::Range, "missing", vec![("a", less_than(1))]).unwrap_err();
+        assert!(err.contains("missing"), "{err}");
+
+        assert!(attempt(PartitionMethod::Range, "age", vec![("a", less_than(1))]).is_ok());
+    }
 }
@@ -60,12 +60,13 @@ async fn handle_client(mut socket: TcpStream, db: Arc<Database>) -> Result<()> {
             ClientRequest::Execute { sql } => {
                 let result = db.execute(&sql).await;
                 let response = match result {
-                    Ok(database::QueryResult::Rows { schema, rows }) => {
+                    Ok(database::QueryResult::Rows { schema, rows, .. }) => {
                         ServerResponse::Rows { schema, rows }
                     }
-                    Ok(database::QueryResult::Count { affected }) => {
-                        ServerResponse::Count { affected }
-                    }
+                    Ok(
+                        database::QueryResult::Count { affected, .. }
+                        | database::QueryResult::Inserted { affected, .. },
+                    ) => ServerResponse::Count { affected },
                     Ok(database::QueryResult::Empty) => ServerResponse::Empty,
                     Err(e) => ServerResponse::Error {
                         code: protocol::ErrorCode::ExecutionError,
@@ -0,0 +1,99 @@
+//! Allocation counting for `PROFILE`.
+//!
+//! A binary that installs [`CountingAllocator`] as its global allocator has
+//! every heap allocation counted on the thread that made it, so a profile can
+//! tell how many allocations each operator made by reading the counters
+//! before and after calling it. Without it [`thread_allocations`] returns
+//! `None` and profiles leave allocations out.
+//!
+//! ```ignore
+//! #[global_allocator]
+//! static ALLOCATOR: common::alloc::CountingAllocator = common::alloc::CountingAllocator;
+//! ```
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// The system allocator, counting the allocations of each thread.
+pub struct CountingAllocator;
+
+/// Allocations made by a thread since it started.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Allocations {
+    /// Allocations and reallocations made.
+    pub count: u64,
+    /// Bytes requested by them.
+    pub bytes: u64,
+}
+
+impl Allocations {
+    /// The allocations made between `earlier` and `self`.
+    pub fn since(self, earlier: Allocations) -> Allocations {
+        Allocations {
+            count: self.count.saturating_sub(earlier.count),
+            bytes: self.bytes.saturating_sub(earlier.bytes),
+        }
+    }
+}
+
+/// Whether [`CountingAllocator`] has made an allocation in this process.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static COUNT: Cell<u64> = const { Cell::new(0) };
+    static BYTES: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Allocations made by the current thread, or `None` if
+/// [`CountingAllocator`] is not the global allocator.
+pub fn thread_allocations() -> Option<Allocations> {
+    if !INSTALLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    Some(Allocations {
+        count: COUNT.try_with(Cell::get).unwrap_or(0),
+        bytes: BYTES.try_with(Cell::get).unwrap_or(0),
+    })
+}
+
+fn record(size: usize) {
+    if !INSTALLED.load(Ordering::Relaxed) {
+        INSTALLED.store(true, Ordering::Relaxed);
+    }
+    // The counters have no destructor, so they can be read while a thread
+    // exits; `try_with` covers the rest of its teardown
+    let _ = COUNT.try_with(|count| count.set(count.get() + 1));
+    let _ = BYTES.try_with(|bytes| bytes.set(bytes.get() + size as u64));
+}
+
+// SAFETY: every call is passed on unchanged to the system allocator, which
+// upholds the `GlobalAlloc` contract; counting does not allocate.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        // SAFETY: the caller upholds `alloc`'s contract for `layout`
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        // SAFETY: the caller upholds `alloc_zeroed`'s contract for `layout`
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(new_size);
+        // SAFETY: the caller upholds `realloc`'s contract for `ptr`, `layout`
+        // and `new_size`
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: the caller upholds `dealloc`'s contract for `ptr` and
+        // `layout`
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
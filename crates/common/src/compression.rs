@@ -0,0 +1,150 @@
+//! Page compression.
+//!
+//! A table created `WITH (compression = 'lz4')` or `'zstd'` has its pages
+//! compressed as they are written and decompressed as they are read. A
+//! compressed page is stored as a frame, `MAGIC || codec || page length ||
+//! compressed length || compressed bytes`, at the start of the page's slot
+//! in the file. The magic cannot begin an uncompressed heap page or B+Tree
+//! node, so every page says for itself whether it is compressed: changing a
+//! table's setting only affects pages written afterwards, and pages that do
+//! not shrink are simply stored as they are.
+//!
+//! Pages keep their page-sized slots, so a page is still found at its ID
+//! times the page size. The bytes after a frame are never written, and the
+//! file system need not allocate them.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{DbError, DbResult};
+
+/// Marks the start of a compressed page. Bytes 2..4 read as a heap page's
+/// free space offset would be far past the end of the page, and bytes 0..4
+/// read as a B+Tree node's variant tag name no variant.
+const MAGIC: [u8; 4] = [0xFF, 0xFF, b'C', b'Z'];
+
+/// Bytes of a frame before the compressed data.
+pub const FRAME_HEADER: usize = MAGIC.len() + 1 + 4 + 4;
+
+/// Compression level used for Zstandard; its default trades speed for
+/// ratio about evenly.
+const ZSTD_LEVEL: i32 = 3;
+
+/// How a table's pages are compressed.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// Pages are stored as they are.
+    #[default]
+    None,
+    /// LZ4: fast, with a modest ratio.
+    Lz4,
+    /// Zstandard: slower, with a better ratio.
+    Zstd,
+}
+
+impl Compression {
+    /// Resolve the name used in `CREATE TABLE ... WITH (compression = <name>)`.
+    pub fn from_name(name: &str) -> DbResult<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Ok(Compression::None),
+            "lz4" => Ok(Compression::Lz4),
+            "zstd" => Ok(Compression::Zstd),
+            other => Err(DbError::Storage(format!(
+                "unknown compression '{other}'; supported: none, lz4, zstd"
+            ))),
+        }
+    }
+
+    fn tag(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Lz4 => 1,
+            Compression::Zstd => 2,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(Compression::None),
+            1 => Some(Compression::Lz4),
+            2 => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Compression::None => "none",
+            Compression::Lz4 => "lz4",
+            Compression::Zstd => "zstd",
+        })
+    }
+}
+
+/// Compress `page` into a frame, or return `None` if `compression` is
+/// [`Compression::None`] or the frame would be no smaller than the page.
+pub fn compress_page(compression: Compression, page: &[u8]) -> DbResult<Option<Vec<u8>>> {
+    let packed = match compression {
+        Compression::None => return Ok(None),
+        Compression::Lz4 => lz4_flex::block::compress(page),
+        Compression::Zstd => zstd::bulk::compress(page, ZSTD_LEVEL)
+            .map_err(|e| DbError::Storage(format!("zstd compression failed: {e}")))?,
+    };
+    if FRAME_HEADER + packed.len() >= page.len() {
+        return Ok(None);
+    }
+    let mut frame = Vec::with_capacity(FRAME_HEADER + packed.len());
+    frame.extend_from_slice(&MAGIC);
+    frame.push(compression.tag());
+    frame.extend_from_slice(&(page.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&(packed.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&packed);
+    Ok(Some(frame))
+}
+
+/// Whether `stored` begins with a compressed page's frame.
+pub fn is_compressed(stored: &[u8]) -> bool {
+    stored.starts_with(&MAGIC)
+}
+
+/// The page stored in `stored`, a page's slot as read from its file:
+/// decompressed if it holds a frame, `stored` itself otherwise. Bytes after
+/// a frame are ignored.
+pub fn decompress_page(stored: Vec<u8>) -> DbResult<Vec<u8>> {
+    if !is_compressed(&stored) {
+        return Ok(stored);
+    }
+    let damaged = |what: String| DbError::Corruption(format!("compressed page {what}"));
+    if stored.len() < FRAME_HEADER {
+        return Err(damaged("frame is truncated".into()));
+    }
+    let read_u32 =
+        |at: usize| u32::from_le_bytes(stored[at..at + 4].try_into().expect("four bytes")) as usize;
+    let codec = stored[MAGIC.len()];
+    let page_len = read_u32(MAGIC.len() + 1);
+    let packed_len = read_u32(MAGIC.len() + 5);
+    let Some(packed) = stored.get(FRAME_HEADER..FRAME_HEADER + packed_len) else {
+        return Err(damaged(format!(
+            "claims {packed_len} bytes, more than its slot holds"
+        )));
+    };
+    let page = match Compression::from_tag(codec) {
+        Some(Compression::Lz4) => lz4_flex::block::decompress(packed, page_len)
+            .map_err(|e| damaged(format!("failed to decompress: {e}")))?,
+        Some(Compression::Zstd) => zstd::bulk::decompress(packed, page_len)
+            .map_err(|e| damaged(format!("failed to decompress: {e}")))?,
+        Some(Compression::None) | None => {
+            return Err(damaged(format!("names unknown codec {codec}")));
+        }
+    };
+    if page.len() != page_len {
+        return Err(damaged(format!(
+            "decompressed to {} bytes, expected {page_len}",
+            page.len()
+        )));
+    }
+    Ok(page)
+}
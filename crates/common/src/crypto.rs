@@ -0,0 +1,226 @@
+//! At-rest encryption primitives.
+//!
+//! Data files are encrypted with AES-256-GCM under a database master key.
+//! Every sealed buffer is laid out as `key id || nonce || ciphertext || tag`,
+//! so it is [`SEAL_OVERHEAD`] bytes longer than the plaintext. Callers bind
+//! each buffer to its location with associated data (for example the table
+//! and page ID of a heap page) so that ciphertext cannot be moved between
+//! pages or files without failing authentication.
+//!
+//! The key id names the key a buffer was sealed with (see
+//! [`EncryptionKey::id`]), which is what makes keys rotatable: a key given
+//! the keys it replaces with [`EncryptionKey::with_previous`] seals with
+//! itself but opens buffers sealed with any of them. Data is re-encrypted
+//! under the new key as it is rewritten, and an old key can be dropped once
+//! nothing sealed with it is left.
+//!
+//! Whole files (the catalog, the data directory manifest) use
+//! [`seal_file`] and [`open_file`], which add a magic header so an encrypted
+//! file is never mistaken for a plaintext one and vice versa.
+
+use std::{fmt, sync::Arc};
+
+use ring::{
+    aead::{self, Aad, LessSafeKey, Nonce, UnboundKey},
+    digest,
+    rand::{SecureRandom, SystemRandom},
+};
+
+use crate::{DbError, DbResult};
+
+/// Length of a master key in bytes.
+pub const KEY_LEN: usize = 32;
+
+/// Length of the key id that starts every sealed buffer.
+pub const KEY_ID_LEN: usize = 4;
+
+/// Bytes added to every sealed buffer (key id, nonce and authentication tag).
+pub const SEAL_OVERHEAD: usize = KEY_ID_LEN + aead::NONCE_LEN + aead::MAX_TAG_LEN;
+
+/// Prefix hashed with a key's bytes to derive its id.
+const KEY_ID_CONTEXT: &[u8] = b"sql-database key id";
+
+/// Header that marks a file written by [`seal_file`].
+const FILE_MAGIC: &[u8; 8] = b"SQLDBENC";
+
+/// Database master key used to encrypt data at rest, along with the keys it
+/// replaced, if any.
+///
+/// Cloning is cheap; clones share the expanded key schedules. The key bytes
+/// are never printed by `Debug`.
+#[derive(Clone)]
+pub struct EncryptionKey {
+    current: Arc<KeyMaterial>,
+    /// Keys that can still open what they sealed, newest first.
+    previous: Vec<Arc<KeyMaterial>>,
+}
+
+struct KeyMaterial {
+    id: u32,
+    key: LessSafeKey,
+}
+
+impl EncryptionKey {
+    /// Build a key from exactly [`KEY_LEN`] raw bytes.
+    pub fn from_bytes(bytes: &[u8]) -> DbResult<Self> {
+        if bytes.len() != KEY_LEN {
+            return Err(DbError::Storage(format!(
+                "encryption key must be {KEY_LEN} bytes, got {}",
+                bytes.len()
+            )));
+        }
+        let unbound = UnboundKey::new(&aead::AES_256_GCM, bytes)
+            .map_err(|_| DbError::Storage("invalid encryption key".into()))?;
+        let mut context = digest::Context::new(&digest::SHA256);
+        context.update(KEY_ID_CONTEXT);
+        context.update(bytes);
+        let digest = context.finish();
+        let id = u32::from_le_bytes(digest.as_ref()[..KEY_ID_LEN].try_into().expect("4 bytes"));
+        Ok(Self {
+            current: Arc::new(KeyMaterial {
+                id,
+                key: LessSafeKey::new(unbound),
+            }),
+            previous: Vec::new(),
+        })
+    }
+
+    /// Build a key from its hex encoding (64 hex digits, surrounding
+    /// whitespace ignored).
+    pub fn from_hex(hex: &str) -> DbResult<Self> {
+        let hex = hex.trim();
+        if hex.len() != KEY_LEN * 2 || !hex.is_ascii() {
+            return Err(DbError::Storage(format!(
+                "encryption key must be {} hex digits",
+                KEY_LEN * 2
+            )));
+        }
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| DbError::Storage("encryption key is not valid hex".into()))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Id of the key, written at the start of every buffer it seals.
+    ///
+    /// Ids are derived from the key bytes, so the same key always has the
+    /// same id.
+    pub fn id(&self) -> u32 {
+        self.current.id
+    }
+
+    /// Rotate from `previous` to this key: seal with this key, and open
+    /// buffers sealed with it, `previous`, or any key `previous` replaced.
+    pub fn with_previous(mut self, previous: &EncryptionKey) -> Self {
+        for material in std::iter::once(&previous.current).chain(&previous.previous) {
+            if material.id != self.current.id
+                && !self.previous.iter().any(|known| known.id == material.id)
+            {
+                self.previous.push(material.clone());
+            }
+        }
+        self
+    }
+
+    /// Encrypt `plaintext` with this key, binding it to `aad`.
+    ///
+    /// The result is exactly `plaintext.len() + SEAL_OVERHEAD` bytes.
+    pub fn seal(&self, aad: &[u8], plaintext: &[u8]) -> DbResult<Vec<u8>> {
+        let mut nonce = [0u8; aead::NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| DbError::Storage("failed to generate nonce".into()))?;
+
+        let mut out = Vec::with_capacity(plaintext.len() + SEAL_OVERHEAD);
+        out.extend_from_slice(&self.current.id.to_le_bytes());
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(plaintext);
+        let tag = self
+            .current
+            .key
+            .seal_in_place_separate_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(aad),
+                &mut out[KEY_ID_LEN + aead::NONCE_LEN..],
+            )
+            .map_err(|_| DbError::Storage("encryption failed".into()))?;
+        out.extend_from_slice(tag.as_ref());
+        Ok(out)
+    }
+
+    /// Decrypt a buffer produced by [`EncryptionKey::seal`] with the same `aad`,
+    /// using whichever of this key and the keys it replaced sealed it.
+    ///
+    /// Fails if the buffer was tampered with, moved, or sealed under a
+    /// key that is not among them.
+    pub fn open(&self, aad: &[u8], sealed: &[u8]) -> DbResult<Vec<u8>> {
+        let Some(id) = sealed_key_id(sealed).filter(|_| sealed.len() >= SEAL_OVERHEAD) else {
+            return Err(DbError::Storage("encrypted data is truncated".into()));
+        };
+        let Some(material) = std::iter::once(&self.current)
+            .chain(&self.previous)
+            .find(|material| material.id == id)
+        else {
+            return Err(DbError::Storage(format!(
+                "decryption failed: data is sealed with key {id:08x}, which was not given"
+            )));
+        };
+        let (nonce, ciphertext) = sealed[KEY_ID_LEN..].split_at(aead::NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| DbError::Storage("invalid nonce".into()))?;
+        let mut buf = ciphertext.to_vec();
+        let len = material
+            .key
+            .open_in_place(nonce, Aad::from(aad), &mut buf)
+            .map_err(|_| DbError::Storage("decryption failed: wrong key or corrupted data".into()))?
+            .len();
+        buf.truncate(len);
+        Ok(buf)
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EncryptionKey({:08x}, ..)", self.id())
+    }
+}
+
+/// Id of the key that sealed `sealed` (see [`EncryptionKey::id`]), or `None`
+/// if it is too short to hold one.
+pub fn sealed_key_id(sealed: &[u8]) -> Option<u32> {
+    let id = sealed.get(..KEY_ID_LEN)?;
+    Some(u32::from_le_bytes(id.try_into().expect("4 bytes")))
+}
+
+/// Prepare file contents for writing: sealed with a magic header when a key
+/// is given, unchanged otherwise.
+pub fn seal_file(key: Option<&EncryptionKey>, aad: &[u8], data: Vec<u8>) -> DbResult<Vec<u8>> {
+    let Some(key) = key else {
+        return Ok(data);
+    };
+    let sealed = key.seal(aad, &data)?;
+    let mut out = Vec::with_capacity(FILE_MAGIC.len() + sealed.len());
+    out.extend_from_slice(FILE_MAGIC);
+    out.extend_from_slice(&sealed);
+    Ok(out)
+}
+
+/// Recover file contents written by [`seal_file`].
+///
+/// An encrypted file cannot be read without a key, and a plaintext file is
+/// rejected when a key is given, so a database is never silently half
+/// encrypted.
+pub fn open_file(key: Option<&EncryptionKey>, aad: &[u8], bytes: Vec<u8>) -> DbResult<Vec<u8>> {
+    match (key, bytes.strip_prefix(FILE_MAGIC.as_slice())) {
+        (Some(key), Some(sealed)) => key.open(aad, sealed),
+        (None, None) => Ok(bytes),
+        (None, Some(_)) => Err(DbError::Storage(
+            "file is encrypted but no encryption key was given".into(),
+        )),
+        (Some(_), None) => Err(DbError::Storage(
+            "file is not encrypted but an encryption key was given".into(),
+        )),
+    }
+}
@@ -0,0 +1,243 @@
+//! Injectable time and I/O hooks for deterministic tests.
+//!
+//! The WAL, the buffer pool and heap files ask a [`FaultInjector`] before
+//! each disk operation, and the buffer pool reads time from a [`Clock`].
+//! Production code uses [`NoFaults`] and [`SystemClock`]; tests swap in a
+//! [`FaultPlan`] to fail, say, the third page write, and a [`ManualClock`] so
+//! that timeouts expire without sleeping.
+//!
+//! A failed operation has no effect: the hook runs before any bytes are
+//! written, so a [`FaultPlan::crash_at`] leaves the files exactly as they
+//! were at the boundary of the failed operation.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, io,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// A disk operation that can be made to fail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IoOp {
+    /// Writing a record to the WAL.
+    WalAppend,
+    /// Fsyncing the WAL.
+    WalSync,
+    /// Reading a page from a table file.
+    PageRead,
+    /// Writing a page, or a run of adjacent pages, to a table file.
+    PageWrite,
+}
+
+impl fmt::Display for IoOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IoOp::WalAppend => "WAL append",
+            IoOp::WalSync => "WAL sync",
+            IoOp::PageRead => "page read",
+            IoOp::PageWrite => "page write",
+        })
+    }
+}
+
+/// Decides whether disk operations go ahead.
+pub trait FaultInjector: fmt::Debug + Send + Sync {
+    /// Called before `op` is performed. An error is returned to the caller
+    /// in place of the operation's result.
+    fn check(&self, op: IoOp) -> io::Result<()>;
+}
+
+/// Lets every operation go ahead.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoFaults;
+
+impl FaultInjector for NoFaults {
+    fn check(&self, _op: IoOp) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The injector used when none is configured.
+pub fn no_faults() -> Arc<dyn FaultInjector> {
+    Arc::new(NoFaults)
+}
+
+/// Fails chosen operations, counting each kind of operation from 1.
+///
+/// # Example
+/// ```
+/// use common::hooks::{FaultInjector, FaultPlan, IoOp};
+///
+/// let plan = FaultPlan::new().fail_nth(IoOp::PageWrite, 2);
+/// assert!(plan.check(IoOp::PageWrite).is_ok());
+/// assert!(plan.check(IoOp::PageWrite).is_err());
+/// assert!(plan.check(IoOp::PageWrite).is_ok());
+/// assert_eq!(plan.count(IoOp::PageWrite), 3);
+/// ```
+#[derive(Debug, Default)]
+pub struct FaultPlan {
+    state: Mutex<PlanState>,
+}
+
+#[derive(Debug, Default)]
+struct PlanState {
+    counts: HashMap<IoOp, u64>,
+    failures: HashSet<(IoOp, u64)>,
+    crash: Option<(IoOp, u64)>,
+    crashed: bool,
+    disk_full: bool,
+}
+
+impl FaultPlan {
+    /// A plan that fails nothing until told to.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, PlanState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Fail the `n`th `op`. Later operations go ahead.
+    pub fn fail_nth(self, op: IoOp, n: u64) -> Self {
+        self.state().failures.insert((op, n));
+        self
+    }
+
+    /// Fail the `n`th `op` and every operation of any kind after it, as if
+    /// the process died just before performing it.
+    pub fn crash_at(self, op: IoOp, n: u64) -> Self {
+        self.state().crash = Some((op, n));
+        self
+    }
+
+    /// Fail every WAL append and page write from now on as a full disk
+    /// would, with [`io::ErrorKind::StorageFull`]. Reads and syncs go ahead.
+    pub fn fill_disk(&self) {
+        self.state().disk_full = true;
+    }
+
+    /// Undo [`FaultPlan::fill_disk`].
+    pub fn free_disk(&self) {
+        self.state().disk_full = false;
+    }
+
+    /// Number of times `op` was attempted, including failed attempts.
+    pub fn count(&self, op: IoOp) -> u64 {
+        self.state().counts.get(&op).copied().unwrap_or(0)
+    }
+
+    /// Whether the crash set by [`FaultPlan::crash_at`] has happened.
+    pub fn crashed(&self) -> bool {
+        self.state().crashed
+    }
+}
+
+impl FaultInjector for FaultPlan {
+    fn check(&self, op: IoOp) -> io::Result<()> {
+        let mut state = self.state();
+        let count = state.counts.entry(op).or_insert(0);
+        *count += 1;
+        let n = *count;
+        if state.crash == Some((op, n)) {
+            state.crashed = true;
+        }
+        if state.crashed {
+            return Err(io::Error::other(format!(
+                "injected crash: {op} #{n} not performed"
+            )));
+        }
+        if state.disk_full && matches!(op, IoOp::WalAppend | IoOp::PageWrite) {
+            return Err(io::Error::new(
+                io::ErrorKind::StorageFull,
+                format!("injected fault: {op} #{n} failed, disk full"),
+            ));
+        }
+        if state.failures.contains(&(op, n)) {
+            return Err(io::Error::other(format!(
+                "injected fault: {op} #{n} failed"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Source of time for timeouts.
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// The current time.
+    fn now(&self) -> Instant;
+
+    /// How long to actually block when about to wait up to `timeout` for
+    /// another thread.
+    fn block_for(&self, timeout: Duration) -> Duration;
+}
+
+/// Real time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn block_for(&self, timeout: Duration) -> Duration {
+        timeout
+    }
+}
+
+/// The clock used when none is configured.
+pub fn system_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+/// A clock that only moves when told to.
+///
+/// Instead of blocking, a wait advances the clock by its whole timeout, so
+/// the timeout has expired by the time the waiter looks again.
+#[derive(Debug)]
+pub struct ManualClock {
+    start: Instant,
+    elapsed: Mutex<Duration>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+        }
+    }
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn elapsed_mut(&self) -> MutexGuard<'_, Duration> {
+        self.elapsed.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Move the clock forward.
+    pub fn advance(&self, by: Duration) {
+        *self.elapsed_mut() += by;
+    }
+
+    /// Time the clock has moved since it was created.
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed_mut()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn block_for(&self, timeout: Duration) -> Duration {
+        self.advance(timeout);
+        Duration::ZERO
+    }
+}
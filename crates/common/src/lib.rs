@@ -1,10 +1,14 @@
 #[cfg(test)]
 mod tests;
 
+pub mod alloc;
+pub mod compression;
+pub mod crypto;
+pub mod hooks;
 pub mod pretty;
 
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, io, path::PathBuf, time::Duration};
+use std::{collections::HashMap, fmt, io, path::PathBuf, time::Duration};
 use thiserror::Error;
 use types::Value;
 
@@ -124,6 +128,18 @@ pub enum DbError {
     Wal(String),
     #[error("constraint violation: {0}")]
     Constraint(String),
+    #[error("resource limit exceeded: {0}")]
+    ResourceExhausted(String),
+    #[error("buffer pool exhausted: {0}")]
+    BufferPoolExhausted(String),
+    /// Data read back from disk is not what was written, e.g. a page whose
+    /// checksum does not match its contents.
+    #[error("corruption: {0}")]
+    Corruption(String),
+    /// A write ran out of disk space, or the database is read-only until
+    /// space is freed.
+    #[error("disk full: {0}")]
+    DiskFull(String),
     #[error(transparent)]
     Io(#[from] io::Error),
 }
@@ -131,6 +147,45 @@ pub enum DbError {
 /// Result alias that carries a `DbError`.
 pub type DbResult<T> = Result<T, DbError>;
 
+impl DbError {
+    /// The error for a failed disk write: [`DbError::DiskFull`] if the disk
+    /// is out of space, `wrap("{message}: {err}")` otherwise.
+    ///
+    /// # Example
+    /// ```
+    /// use common::DbError;
+    /// use std::io;
+    ///
+    /// let err = io::Error::from(io::ErrorKind::StorageFull);
+    /// let err = DbError::from_write(err, DbError::Wal, "Failed to write record");
+    /// assert!(matches!(err, DbError::DiskFull(_)));
+    /// ```
+    pub fn from_write(err: io::Error, wrap: fn(String) -> DbError, message: &str) -> DbError {
+        if is_disk_full(&err) {
+            DbError::DiskFull(format!("{message}: {err}"))
+        } else {
+            wrap(format!("{message}: {err}"))
+        }
+    }
+
+    /// Whether this error was caused by the disk running out of space.
+    pub fn is_disk_full(&self) -> bool {
+        match self {
+            DbError::DiskFull(_) => true,
+            DbError::Io(err) => is_disk_full(err),
+            _ => false,
+        }
+    }
+}
+
+/// Whether an I/O error means the disk, or the user's quota on it, is full.
+pub fn is_disk_full(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::StorageFull | io::ErrorKind::QuotaExceeded
+    )
+}
+
 /// Runtime configuration for the database components.
 ///
 /// # Example
@@ -172,6 +227,81 @@ impl Default for Config {
     }
 }
 
+/// Per-session resource caps. `None` leaves a resource unlimited.
+///
+/// Row, memory and temporary disk caps apply to each statement; the
+/// concurrency cap applies to all statements a session has in flight at
+/// once. Work memory is not a cap: a statement whose sorts and joins buffer
+/// more than it moves the excess to temporary files instead of failing.
+///
+/// # Example
+/// ```
+/// use common::ResourceLimits;
+///
+/// let limits = ResourceLimits::builder()
+///     .max_rows_scanned(1_000_000)
+///     .max_concurrent_statements(4)
+///     .build();
+/// assert_eq!(limits.max_memory_bytes, None);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, bon::Builder)]
+pub struct ResourceLimits {
+    /// Rows a statement may read from tables, across all of its scans.
+    pub max_rows_scanned: Option<u64>,
+    /// Bytes a statement may buffer in memory for sorts and join inputs.
+    pub max_memory_bytes: Option<u64>,
+    /// Bytes a statement's sorts and joins may buffer between them before
+    /// they spill rows to temporary files.
+    pub work_memory_bytes: Option<u64>,
+    /// Bytes a statement may write to temporary files once it runs out of
+    /// work memory.
+    pub max_temp_bytes: Option<u64>,
+    /// Statements a session may have executing or queued at once.
+    pub max_concurrent_statements: Option<usize>,
+}
+
+/// How urgently a session's statements should run, set with
+/// `SET statement_priority = high | normal | background`.
+///
+/// High-priority statements are admitted ahead of normal ones, and normal
+/// ones ahead of background work such as bulk scans and garbage collection.
+/// Background statements also scan serially and keep the pages they read
+/// from crowding other statements' pages out of the buffer pool.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Background,
+}
+
+impl Priority {
+    /// Every priority, most urgent first.
+    pub const ALL: [Priority; 3] = [Priority::High, Priority::Normal, Priority::Background];
+
+    /// The priority named `name`, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|priority| priority.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Name of the priority as written in `SET statement_priority`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Normal => "normal",
+            Priority::Background => "background",
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 /// Execution statistics collected during query execution for EXPLAIN ANALYZE.
 ///
 /// # Examples
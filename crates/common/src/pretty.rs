@@ -1,6 +1,6 @@
 use crate::{RecordBatch, RecordId, Row};
 use tabled::{Table, Tabled, builder::Builder, settings};
-use types::Value;
+use types::{Value, binary, temporal};
 
 /// Predefined output styles that map to `tabled` styles.
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
@@ -96,6 +96,11 @@ pub fn format_value(value: &Value) -> String {
         Value::Text(text) => format!("'{}'", text),
         Value::Bool(b) => b.to_string(),
         Value::Null => "NULL".into(),
+        Value::Float(f) => format!("{f:?}"),
+        Value::Date(d) => temporal::format_date(*d),
+        Value::Timestamp(t) => temporal::format_timestamp(*t),
+        Value::Decimal(d) => d.to_string(),
+        Value::Bytes(b) => binary::format_hex(b),
     }
 }
 
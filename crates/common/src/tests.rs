@@ -31,3 +31,155 @@ fn io_error_converts() {
     let db_err: DbError = e.into();
     assert!(matches!(db_err, DbError::Io(_)));
 }
+
+#[test]
+fn encryption_round_trips_and_binds_associated_data() {
+    let key = crypto::EncryptionKey::from_bytes(&[7u8; crypto::KEY_LEN]).unwrap();
+    let sealed = key.seal(b"page:1", b"secret row").unwrap();
+    assert_eq!(sealed.len(), b"secret row".len() + crypto::SEAL_OVERHEAD);
+    assert!(!sealed.windows(6).any(|w| w == b"secret"));
+    assert_eq!(key.open(b"page:1", &sealed).unwrap(), b"secret row");
+    assert!(key.open(b"page:2", &sealed).is_err());
+
+    let other = crypto::EncryptionKey::from_hex(&"ab".repeat(crypto::KEY_LEN)).unwrap();
+    assert!(other.open(b"page:1", &sealed).is_err());
+    assert!(crypto::EncryptionKey::from_hex("abc").is_err());
+}
+
+#[test]
+fn rotated_keys_open_data_sealed_under_earlier_keys() {
+    let old = crypto::EncryptionKey::from_bytes(&[3u8; crypto::KEY_LEN]).unwrap();
+    let new = crypto::EncryptionKey::from_bytes(&[4u8; crypto::KEY_LEN]).unwrap();
+    assert_ne!(old.id(), new.id());
+
+    let before = old.seal(b"aad", b"old row").unwrap();
+    assert_eq!(crypto::sealed_key_id(&before), Some(old.id()));
+
+    let rotated = new.clone().with_previous(&old);
+    assert_eq!(rotated.id(), new.id());
+    assert_eq!(rotated.open(b"aad", &before).unwrap(), b"old row");
+    let after = rotated.seal(b"aad", b"new row").unwrap();
+    assert_eq!(crypto::sealed_key_id(&after), Some(new.id()));
+
+    let err = new.open(b"aad", &before).unwrap_err();
+    assert!(format!("{err}").contains(&format!("{:08x}", old.id())));
+    assert!(old.open(b"aad", &after).is_err());
+}
+
+#[test]
+fn sealed_files_require_matching_key_presence() {
+    let key = crypto::EncryptionKey::from_bytes(&[1u8; crypto::KEY_LEN]).unwrap();
+    let sealed = crypto::seal_file(Some(&key), b"f", b"data".to_vec()).unwrap();
+    assert_eq!(
+        crypto::open_file(Some(&key), b"f", sealed.clone()).unwrap(),
+        b"data"
+    );
+    assert!(crypto::open_file(None, b"f", sealed).is_err());
+    assert!(crypto::open_file(Some(&key), b"f", b"data".to_vec()).is_err());
+    assert_eq!(
+        crypto::open_file(None, b"f", b"data".to_vec()).unwrap(),
+        b"data"
+    );
+}
+
+#[test]
+fn compressed_pages_round_trip_and_describe_themselves() {
+    use compression::{Compression, compress_page, decompress_page, is_compressed};
+
+    let mut page = vec![0u8; 4096];
+    for (i, chunk) in page[64..].chunks_mut(32).enumerate() {
+        let text = format!("row {i:04} some text");
+        chunk[..text.len()].copy_from_slice(text.as_bytes());
+    }
+    for codec in [Compression::Lz4, Compression::Zstd] {
+        let frame = compress_page(codec, &page)
+            .unwrap()
+            .expect("text compresses");
+        assert!(
+            frame.len() < page.len() / 2,
+            "{codec}: {} bytes",
+            frame.len()
+        );
+        assert!(is_compressed(&frame));
+
+        // A slot is read whole; the bytes after the frame are ignored
+        let mut slot = frame.clone();
+        slot.resize(page.len(), 0xAA);
+        assert_eq!(decompress_page(slot).unwrap(), page);
+
+        let mut damaged = frame;
+        damaged.truncate(damaged.len() / 2);
+        assert!(matches!(
+            decompress_page(damaged),
+            Err(DbError::Corruption(_))
+        ));
+    }
+
+    // Uncompressed pages pass through, and noise is not worth compressing
+    assert!(!is_compressed(&page));
+    assert_eq!(decompress_page(page.clone()).unwrap(), page);
+    assert_eq!(compress_page(Compression::None, &page).unwrap(), None);
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let noise: Vec<u8> = (0..4096)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect();
+    assert_eq!(compress_page(Compression::Lz4, &noise).unwrap(), None);
+
+    assert_eq!(Compression::from_name("ZSTD").unwrap(), Compression::Zstd);
+    assert!(Compression::from_name("gzip").is_err());
+}
+
+#[test]
+fn fault_plan_crash_fails_everything_after_it() {
+    use hooks::{FaultInjector, FaultPlan, IoOp};
+
+    let plan = FaultPlan::new().crash_at(IoOp::PageWrite, 2);
+    assert!(plan.check(IoOp::WalAppend).is_ok());
+    assert!(plan.check(IoOp::PageWrite).is_ok());
+    assert!(!plan.crashed());
+    assert!(plan.check(IoOp::PageWrite).is_err());
+    assert!(plan.crashed());
+    assert!(plan.check(IoOp::WalAppend).is_err());
+    assert_eq!(plan.count(IoOp::WalAppend), 2);
+}
+
+#[test]
+fn full_disk_fails_writes_until_freed() {
+    use hooks::{FaultInjector, FaultPlan, IoOp};
+
+    let plan = FaultPlan::new();
+    plan.fill_disk();
+    let err = plan.check(IoOp::PageWrite).unwrap_err();
+    assert!(is_disk_full(&err));
+    assert!(DbError::from_write(err, DbError::Storage, "write failed").is_disk_full());
+    assert!(plan.check(IoOp::WalAppend).is_err());
+    assert!(plan.check(IoOp::PageRead).is_ok());
+    plan.free_disk();
+    assert!(plan.check(IoOp::PageWrite).is_ok());
+
+    let err = DbError::from_write(
+        io::Error::other("bad sector"),
+        DbError::Storage,
+        "write failed",
+    );
+    assert!(matches!(err, DbError::Storage(_)));
+    assert!(!err.is_disk_full());
+}
+
+#[test]
+fn manual_clock_only_moves_when_told() {
+    use hooks::{Clock, ManualClock};
+    use std::time::Duration;
+
+    let clock = ManualClock::new();
+    let start = clock.now();
+    assert_eq!(clock.now(), start);
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(clock.block_for(Duration::from_secs(2)), Duration::ZERO);
+    assert_eq!(clock.now() - start, Duration::from_secs(7));
+}
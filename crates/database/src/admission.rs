@@ -0,0 +1,211 @@
+//! Admission control for statements.
+//!
+//! [`Database::with_max_executing_statements`](crate::Database::with_max_executing_statements)
+//! caps how many statements execute at once across all sessions. Statements
+//! over the cap wait until one finishes, so a burst of traffic queues up
+//! instead of piling onto the WAL lock. Unlike the per-session cap in
+//! [`sessions`](crate::sessions), nothing is rejected.
+//!
+//! Each freed slot goes to the longest-waiting statement of the most urgent
+//! [`Priority`] waiting: high-priority statements are admitted ahead of
+//! normal ones, and background statements only when nothing else waits.
+//! Without a cap every statement is admitted at once, whatever its priority.
+//!
+//! Maintenance waits its turn too: `ANALYZE`, `VACUUM` and the re-analysis
+//! and vacuums started automatically after writes are admitted at background
+//! priority, and fetch pages at it, so they do not hold up foreground
+//! statements.
+//!
+//! How many statements waited and for how long is reported by
+//! [`Database::admission_stats`](crate::Database::admission_stats).
+
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use common::Priority;
+use tokio::sync::oneshot;
+
+/// Counters describing how statements were admitted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AdmissionStats {
+    /// Statements admitted so far
+    pub admitted: u64,
+    /// Admitted statements that had to wait in the queue
+    pub queued: u64,
+    /// Statements executing now
+    pub executing: usize,
+    /// Statements waiting in the queue now
+    pub waiting: usize,
+    /// Time admitted statements spent in the queue, in total
+    pub total_queue_time: Duration,
+    /// Longest time any statement spent in the queue
+    pub max_queue_time: Duration,
+}
+
+impl AdmissionStats {
+    /// Average time a statement that had to wait spent in the queue.
+    pub fn mean_queue_time(&self) -> Duration {
+        if self.queued == 0 {
+            return Duration::ZERO;
+        }
+        self.total_queue_time.div_f64(self.queued as f64)
+    }
+}
+
+/// Statements waiting for one of a fixed number of slots, queued by
+/// priority and then in arrival order.
+#[derive(Debug)]
+pub struct AdmissionQueue {
+    slots: Mutex<Slots>,
+    stats: Mutex<AdmissionStats>,
+}
+
+#[derive(Debug)]
+struct Slots {
+    /// Slots free to take; unlimited if `None`
+    free: Option<usize>,
+    /// Statements waiting for a slot, most urgent priority first
+    waiting: [VecDeque<oneshot::Sender<()>>; Priority::ALL.len()],
+}
+
+impl Default for AdmissionQueue {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl AdmissionQueue {
+    /// Let at most `max_executing` statements execute at once, or any number
+    /// if `None`. A cap of zero is treated as one.
+    pub fn new(max_executing: Option<usize>) -> Self {
+        Self {
+            slots: Mutex::new(Slots {
+                free: max_executing.map(|max| max.max(1)),
+                waiting: Default::default(),
+            }),
+            stats: Mutex::default(),
+        }
+    }
+
+    /// Wait for a slot, behind every statement of the same or a more urgent
+    /// priority that asked before.
+    ///
+    /// The statement holds the slot until the returned admission is dropped.
+    pub async fn admit(&self, priority: Priority) -> Admission<'_> {
+        let granted = {
+            let mut slots = self.slots();
+            let Slots { free, waiting } = &mut *slots;
+            match free {
+                None => None,
+                // A freed slot goes straight to a waiter, so one is only
+                // free when nothing waits
+                Some(free) if *free > 0 => {
+                    *free -= 1;
+                    None
+                }
+                Some(_) => {
+                    let (grant, granted) = oneshot::channel();
+                    waiting[priority as usize].push_back(grant);
+                    Some(granted)
+                }
+            }
+        };
+        if let Some(granted) = granted {
+            self.wait(granted).await;
+        }
+        self.update(|stats| {
+            stats.admitted += 1;
+            stats.executing += 1;
+        });
+        Admission { queue: self }
+    }
+
+    /// Wait for a slot to be handed over, recording how long it took.
+    async fn wait(&self, granted: oneshot::Receiver<()>) {
+        self.update(|stats| stats.waiting += 1);
+        let start = Instant::now();
+        // Leave the counts right, and pass the slot on, if the statement is
+        // cancelled while it waits
+        let mut waiting = Waiting {
+            queue: self,
+            granted,
+            admitted: false,
+        };
+        (&mut waiting.granted)
+            .await
+            .expect("waiters are only dropped by the queue when granted");
+        waiting.admitted = true;
+        let waited = start.elapsed();
+        self.update(|stats| {
+            stats.queued += 1;
+            stats.total_queue_time += waited;
+            stats.max_queue_time = stats.max_queue_time.max(waited);
+        });
+    }
+
+    /// Hand a finished statement's slot to the next waiter, or free it.
+    fn release(&self) {
+        let mut slots = self.slots();
+        let Slots { free, waiting } = &mut *slots;
+        let Some(free) = free else {
+            return;
+        };
+        for queue in waiting.iter_mut() {
+            while let Some(grant) = queue.pop_front() {
+                // Fails if the waiter was cancelled; try the next one
+                if grant.send(()).is_ok() {
+                    return;
+                }
+            }
+        }
+        *free += 1;
+    }
+
+    /// Current admission counters.
+    pub fn stats(&self) -> AdmissionStats {
+        *self.stats.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn slots(&self) -> MutexGuard<'_, Slots> {
+        self.slots.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn update(&self, f: impl FnOnce(&mut AdmissionStats)) {
+        f(&mut self.stats.lock().unwrap_or_else(|e| e.into_inner()));
+    }
+}
+
+/// Counts a statement as waiting until dropped.
+struct Waiting<'a> {
+    queue: &'a AdmissionQueue,
+    granted: oneshot::Receiver<()>,
+    admitted: bool,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.queue.update(|stats| stats.waiting -= 1);
+        if !self.admitted {
+            // A slot handed over before the statement was cancelled would
+            // otherwise be lost
+            self.granted.close();
+            if self.granted.try_recv().is_ok() {
+                self.queue.release();
+            }
+        }
+    }
+}
+
+/// A statement admitted by [`AdmissionQueue::admit`].
+#[derive(Debug)]
+pub struct Admission<'a> {
+    queue: &'a AdmissionQueue,
+}
+
+impl Drop for Admission<'_> {
+    fn drop(&mut self) {
+        self.queue.update(|stats| stats.executing -= 1);
+        self.queue.release();
+    }
+}
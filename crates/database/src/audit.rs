@@ -0,0 +1,163 @@
+//! Audit logging of data access.
+//!
+//! Tables created `WITH (audit = true)`, or switched on later with
+//! [`Database::set_table_audit`](crate::Database::set_table_audit), have every
+//! statement that touches them recorded in an append-only log in the data
+//! directory. Each record says who ran the statement, its SQL text, every
+//! table it referenced, and how many rows it returned or affected; failed
+//! statements are recorded with their error, so denied or invalid access
+//! attempts are visible too.
+//!
+//! The log is one JSON object per line. In an encrypted database each line
+//! is instead the record sealed with the database key, base64-encoded, so
+//! the SQL text of audited statements is not readable on disk. The log is
+//! never truncated by the database, including by
+//! [`Database::reset`](crate::Database::reset).
+//!
+//! "Who" is the principal passed to
+//! [`Database::execute_as`](crate::Database::execute_as); the server uses the
+//! client's address. There is no authentication, so the principal is a label,
+//! not a verified identity. Each node audits the statements it receives;
+//! records are not replicated through Raft.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use common::crypto::EncryptionKey;
+use serde::{Deserialize, Serialize};
+
+use crate::QueryResult;
+
+/// Principal recorded for statements run through [`Database::execute`](crate::Database::execute).
+pub const LOCAL_PRINCIPAL: &str = "local";
+
+/// Associated data sealed audit records are bound to.
+const RECORD_AAD: &[u8] = b"audit record";
+
+/// One audited statement.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// When the statement finished, in milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    /// Who executed the statement.
+    pub principal: String,
+    /// The SQL text as submitted.
+    pub statement: String,
+    /// Every table the statement referenced, audited or not.
+    pub tables: Vec<String>,
+    /// Rows returned or affected; `None` for DDL and failed statements.
+    pub rows: Option<u64>,
+    /// Error message if the statement failed.
+    pub error: Option<String>,
+}
+
+impl AuditRecord {
+    /// Build a record for a statement that has finished executing.
+    pub fn new(
+        principal: &str,
+        statement: &str,
+        tables: Vec<String>,
+        result: &Result<QueryResult>,
+    ) -> Self {
+        let (rows, error) = match result {
+            Ok(QueryResult::Rows { rows, .. }) => (Some(rows.len() as u64), None),
+            Ok(QueryResult::Count { affected, .. } | QueryResult::Inserted { affected, .. }) => {
+                (Some(*affected), None)
+            }
+            Ok(QueryResult::Empty) => (None, None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        Self {
+            timestamp_ms,
+            principal: principal.to_string(),
+            statement: statement.to_string(),
+            tables,
+            rows,
+            error,
+        }
+    }
+}
+
+/// Append-only audit log file.
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    /// Key records are sealed with, in an encrypted database
+    key: Option<EncryptionKey>,
+    /// Serializes appends so concurrent records never interleave.
+    write: Mutex<()>,
+}
+
+impl AuditLog {
+    /// Audit log stored at `path`. The file is created on the first append.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            key: None,
+            write: Mutex::new(()),
+        }
+    }
+
+    /// Seal records with `key`, if given, instead of writing them as plain
+    /// JSON.
+    pub fn with_key(mut self, key: Option<EncryptionKey>) -> Self {
+        self.key = key;
+        self
+    }
+
+    /// Location of the log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a record and sync it to disk.
+    pub fn append(&self, record: &AuditRecord) -> Result<()> {
+        let mut line = serde_json::to_string(record).context("failed to encode audit record")?;
+        if let Some(key) = &self.key {
+            line = STANDARD.encode(key.seal(RECORD_AAD, line.as_bytes())?);
+        }
+        line.push('\n');
+
+        let _guard = self.write.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("failed to open audit log {}", self.path.display()))?;
+        file.write_all(line.as_bytes())
+            .and_then(|()| file.sync_data())
+            .with_context(|| format!("failed to write audit log {}", self.path.display()))
+    }
+
+    /// Read every record in the log, oldest first.
+    pub fn read(&self) -> Result<Vec<AuditRecord>> {
+        let data = match fs::read_to_string(&self.path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to read audit log {}", self.path.display()))
+            }
+        };
+        data.lines()
+            .map(|line| match &self.key {
+                None => serde_json::from_str(line).context("invalid audit record"),
+                Some(key) => {
+                    let sealed = STANDARD.decode(line).context("invalid audit record")?;
+                    serde_json::from_slice(&key.open(RECORD_AAD, &sealed)?)
+                        .context("invalid audit record")
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,91 @@
+//! Batching for [`Database::insert_rows`].
+//!
+//! Rows are taken from the caller's stream until they would fill about
+//! [`BATCH_PAGES`] heap pages, and each batch is inserted as one multi-row
+//! `INSERT`: constraints are checked, each index is opened and flushed once,
+//! and the rows are logged to the WAL with a single sync. The next batch is
+//! only pulled from the stream once the previous one is written, so a fast
+//! producer waits for the database instead of buffering rows in memory.
+//!
+//! [`Database::insert_rows`]: crate::Database::insert_rows
+
+use std::time::Duration;
+
+use common::Row;
+use futures::{Stream, StreamExt};
+use parser::{quote_ident, Statement};
+use storage::PAGE_SIZE;
+use types::Value;
+
+/// Heap pages' worth of rows inserted per batch.
+pub const BATCH_PAGES: usize = 8;
+
+/// Progress of [`Database::insert_rows`], yielded after each batch.
+///
+/// [`Database::insert_rows`]: crate::Database::insert_rows
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InsertProgress {
+    /// Rows inserted so far
+    pub inserted: u64,
+    /// Batches inserted so far
+    pub batches: u64,
+    /// Values generated for auto-increment columns by this batch
+    pub generated_ids: Vec<i64>,
+    /// Time since the first batch started
+    pub elapsed: Duration,
+}
+
+/// Approximate encoded size of `row` in bytes.
+fn row_size(row: &Row) -> usize {
+    row.values
+        .iter()
+        .map(|value| match value {
+            Value::Null => 1,
+            Value::Bool(_) => 1,
+            Value::Date(_) => 4,
+            Value::Int(_) | Value::Float(_) | Value::Timestamp(_) => 8,
+            Value::Decimal(_) => 16,
+            Value::Text(text) => text.len() + 4,
+            Value::Bytes(bytes) => bytes.len() + 4,
+        })
+        .sum()
+}
+
+/// Take rows from `rows` until they fill [`BATCH_PAGES`] pages or the stream
+/// ends. Returns an empty batch once the stream is exhausted.
+pub(crate) async fn next_batch<S>(rows: &mut S) -> Vec<Row>
+where
+    S: Stream<Item = Row> + Unpin,
+{
+    let budget = BATCH_PAGES * PAGE_SIZE;
+    let mut batch = Vec::new();
+    let mut bytes = 0;
+    while bytes < budget {
+        let Some(row) = rows.next().await else {
+            break;
+        };
+        bytes += row_size(&row);
+        batch.push(row);
+    }
+    batch
+}
+
+/// The `INSERT` for a batch, with the text it is audited and logged under.
+pub(crate) fn insert_statement(table: &str, batch: Vec<Row>) -> (String, Statement) {
+    let sql = format!(
+        "INSERT INTO {} /* {} rows from insert_rows */",
+        quote_ident(table),
+        batch.len()
+    );
+    let rows = batch
+        .into_iter()
+        .map(|row| row.values.into_iter().map(expr::Expr::Literal).collect())
+        .collect();
+    let stmt = Statement::Insert {
+        table: table.to_string(),
+        columns: Vec::new(),
+        rows,
+        query: None,
+    };
+    (sql, stmt)
+}
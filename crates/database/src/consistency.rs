@@ -0,0 +1,85 @@
+//! Table content checksums for cross-node consistency checks.
+//!
+//! Every node of a Raft cluster should hold the same rows once it has
+//! applied the same log entries, but replicated writes are applied straight
+//! to table storage rather than through the executor, so a bug on that path
+//! can leave nodes silently different. Each node checksums its tables and
+//! the admin endpoints (see [`raft::admin`]) compare the results.
+//!
+//! Rows are hashed in primary key order, with ties and tables without a
+//! primary key falling back to the full row, so the checksum does not depend
+//! on where each node happened to store a row. Record IDs are not hashed.
+
+use std::{ops::DerefMut, path::Path, sync::Arc};
+
+use anyhow::Result;
+use buffer::SharedPager;
+use catalog::{Catalog, TableMeta};
+use common::Row;
+use executor::{execute_query, EngineRegistry, ExecutionContext};
+use planner::PhysicalPlan;
+use raft::TableChecksum;
+use tokio::sync::Mutex;
+use wal::Wal;
+
+/// Checksum the rows of every table in the catalog, in table name order.
+pub fn table_checksums(
+    catalog: &Catalog,
+    pager: &SharedPager,
+    wal: &Mutex<Wal>,
+    engines: &Arc<EngineRegistry>,
+    data_dir: &Path,
+) -> Result<Vec<TableChecksum>> {
+    let mut tables: Vec<&TableMeta> = catalog.tables().collect();
+    tables.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut pager = pager.clone();
+    let mut wal_lock = wal.blocking_lock();
+    let mut ctx = ExecutionContext::new(
+        catalog,
+        &mut pager,
+        wal_lock.deref_mut(),
+        data_dir.to_path_buf(),
+    )
+    .with_engines(engines.clone());
+
+    tables
+        .into_iter()
+        .map(|table| {
+            let plan = PhysicalPlan::SeqScan {
+                table_id: table.id,
+                schema: table.columns().iter().map(|c| c.name.clone()).collect(),
+                projection: None,
+            };
+            let rows = execute_query(plan, &mut ctx).map_err(anyhow::Error::from)?;
+            checksum_rows(table, rows)
+        })
+        .collect()
+}
+
+/// Checksum `rows` of `table` in primary key order.
+pub fn checksum_rows(table: &TableMeta, mut rows: Vec<Row>) -> Result<TableChecksum> {
+    let key: Vec<usize> = table
+        .primary_key
+        .iter()
+        .flatten()
+        .map(|&column| column as usize)
+        .collect();
+    rows.sort_by(|a, b| {
+        let key_a = key.iter().map(|&i| a.values.get(i));
+        let key_b = key.iter().map(|&i| b.values.get(i));
+        key_a.cmp(key_b).then_with(|| a.values.cmp(&b.values))
+    });
+
+    let mut hasher = crc32fast::Hasher::new();
+    for row in &rows {
+        let bytes = serde_json::to_vec(&row.values)?;
+        hasher.update(&(bytes.len() as u64).to_le_bytes());
+        hasher.update(&bytes);
+    }
+    Ok(TableChecksum {
+        table: table.name.clone(),
+        rows: rows.len() as u64,
+        checksum: hasher.finalize(),
+    })
+}
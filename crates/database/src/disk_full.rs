@@ -0,0 +1,118 @@
+//! Read-only mode while the disk is full.
+//!
+//! A write that runs out of disk space fails with [`DbError::DiskFull`],
+//! whether it was a WAL append, a page write or an index write. The
+//! statement that hit it fails as usual, and the database then turns
+//! read-only: queries keep working, but INSERT, UPDATE, DELETE and DDL are
+//! rejected with [`DbError::DiskFull`] before they touch any file, instead
+//! of each failing part-way through with its own storage error. So are
+//! EXPLAIN ANALYZE and PROFILE of a write, which run it.
+//!
+//! There is no background task watching the disk. Instead, each rejected
+//! write first probes for space by writing [`PROBE_BYTES`] to a scratch file
+//! in the data directory, syncing and removing it. Once that succeeds the
+//! database is writable again and the statement runs, so freeing space is
+//! all it takes to recover. The probe asks the database's
+//! [`FaultInjector`] before writing, as a page write, so tests can fill and
+//! free the disk with [`FaultPlan::fill_disk`].
+//!
+//! [`FaultPlan::fill_disk`]: common::hooks::FaultPlan::fill_disk
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use anyhow::Result;
+use common::hooks::{FaultInjector, IoOp};
+use common::DbError;
+
+/// Bytes the probe writes before a rejected write: room for a statement's
+/// WAL records and a few pages.
+pub const PROBE_BYTES: usize = 64 * storage::PAGE_SIZE;
+
+/// Name of the probe's scratch file in the data directory.
+const PROBE_FILE: &str = ".space_probe";
+
+/// Whether `err` was caused by the disk running out of space.
+pub fn is_disk_full(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause
+            .downcast_ref::<DbError>()
+            .is_some_and(DbError::is_disk_full)
+            || cause
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(common::is_disk_full)
+    })
+}
+
+/// Tracks whether the database is read-only (see the [module docs](self)).
+#[derive(Debug)]
+pub(crate) struct DiskFullGuard {
+    data_dir: Arc<PathBuf>,
+    faults: Arc<dyn FaultInjector>,
+    /// The error that made the database read-only, while it is
+    full: Mutex<Option<String>>,
+}
+
+impl DiskFullGuard {
+    pub(crate) fn new(data_dir: Arc<PathBuf>, faults: Arc<dyn FaultInjector>) -> Self {
+        Self {
+            data_dir,
+            faults,
+            full: Mutex::new(None),
+        }
+    }
+
+    fn full(&self) -> MutexGuard<'_, Option<String>> {
+        self.full.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn is_read_only(&self) -> bool {
+        self.full().is_some()
+    }
+
+    /// Allow a write statement to run, probing for free space first if the
+    /// database is read-only.
+    ///
+    /// Blocks on the probe's I/O; call it from a blocking thread.
+    pub(crate) fn check_writable(&self) -> Result<()> {
+        let mut full = self.full();
+        let Some(reason) = full.as_ref() else {
+            return Ok(());
+        };
+        if self.probe().is_err() {
+            return Err(DbError::DiskFull(format!(
+                "the database is read-only until disk space is freed ({reason})"
+            ))
+            .into());
+        }
+        *full = None;
+        Ok(())
+    }
+
+    /// Turn the database read-only if a statement failed for lack of space.
+    pub(crate) fn observe<T>(&self, result: &Result<T>) {
+        if let Err(err) = result {
+            if is_disk_full(err) {
+                *self.full() = Some(err.to_string());
+            }
+        }
+    }
+
+    /// Write, sync and remove [`PROBE_BYTES`] of scratch data.
+    fn probe(&self) -> std::io::Result<()> {
+        let path = self.data_dir.join(PROBE_FILE);
+        let written = self.faults.check(IoOp::PageWrite).and_then(|()| {
+            let mut file = File::create(&path)?;
+            file.write_all(&vec![0; PROBE_BYTES])?;
+            file.sync_all()
+        });
+        let removed = fs::remove_file(&path);
+        written?;
+        match removed {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
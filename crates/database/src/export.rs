@@ -0,0 +1,174 @@
+//! Streaming export of query results for `COPY ... TO`.
+//!
+//! Rows are written to the file as the executor produces them, so exporting
+//! a large table never holds more than one row in memory.
+//!
+//! CSV output starts with a header line of column names. Text is quoted when
+//! it contains a delimiter, quote or line break, and the empty string is
+//! written as `""` so that it can be told apart from NULL, which is written
+//! as an empty field. JSON output is an array with one object per line, with
+//! keys in column order.
+//!
+//! Exports are written only into the [`EXPORT_DIR`] directory of the data
+//! directory, and never replace an existing file, so that a client cannot
+//! overwrite the database's own files or write anywhere else the server
+//! can.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use common::Row;
+use parser::CopyFormat;
+use types::{binary, temporal, Value};
+
+/// Directory of the data directory that exports are written into.
+pub const EXPORT_DIR: &str = "exports";
+
+/// Create the file `COPY ... TO '<path>'` writes into, returning its full
+/// path.
+///
+/// `path` must be relative and made only of plain names, and is resolved
+/// against [`EXPORT_DIR`], which is created if needed. Fails if the file
+/// already exists.
+pub fn create_file(data_dir: &Path, path: &str) -> io::Result<(PathBuf, File)> {
+    let relative = Path::new(path);
+    let plain = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    if !plain || path.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("export path '{path}' must be a relative path without '.' or '..'"),
+        ));
+    }
+    let path = data_dir.join(EXPORT_DIR).join(relative);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match OpenOptions::new().write(true).create_new(true).open(&path) {
+        Ok(file) => Ok((path, file)),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", path.display()),
+        )),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes rows in a [`CopyFormat`] to an underlying writer.
+pub struct RowWriter<W: Write> {
+    out: W,
+    format: CopyFormat,
+    schema: Vec<String>,
+    rows: u64,
+}
+
+impl<W: Write> RowWriter<W> {
+    /// Start an export of rows with columns `schema`, writing any header.
+    pub fn new(mut out: W, format: CopyFormat, schema: Vec<String>) -> io::Result<Self> {
+        match format {
+            CopyFormat::Csv => {
+                write_csv_line(&mut out, schema.iter().map(|name| csv_field(name)))?;
+            }
+            CopyFormat::Json => out.write_all(b"[")?,
+        }
+        Ok(Self {
+            out,
+            format,
+            schema,
+            rows: 0,
+        })
+    }
+
+    /// Append one row.
+    pub fn write_row(&mut self, row: &Row) -> io::Result<()> {
+        if row.values.len() != self.schema.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "row has {} values but the export has {} columns",
+                    row.values.len(),
+                    self.schema.len()
+                ),
+            ));
+        }
+        match self.format {
+            CopyFormat::Csv => write_csv_line(
+                &mut self.out,
+                row.values.iter().map(|value| match value {
+                    Value::Null => String::new(),
+                    Value::Int(n) => n.to_string(),
+                    Value::Text(text) => csv_field(text),
+                    Value::Bool(b) => b.to_string(),
+                    Value::Float(f) => format!("{f:?}"),
+                    Value::Date(d) => temporal::format_date(*d),
+                    Value::Timestamp(t) => temporal::format_timestamp(*t),
+                    Value::Decimal(d) => d.to_string(),
+                    Value::Bytes(b) => binary::format_hex(b),
+                }),
+            )?,
+            CopyFormat::Json => {
+                let separator: &[u8] = if self.rows == 0 { b"\n{" } else { b",\n{" };
+                self.out.write_all(separator)?;
+                for (i, (name, value)) in self.schema.iter().zip(&row.values).enumerate() {
+                    if i > 0 {
+                        self.out.write_all(b",")?;
+                    }
+                    serde_json::to_writer(&mut self.out, name)?;
+                    self.out.write_all(b":")?;
+                    serde_json::to_writer(&mut self.out, &json_value(value))?;
+                }
+                self.out.write_all(b"}")?;
+            }
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Finish the export and flush it, returning the number of rows written.
+    pub fn finish(mut self) -> io::Result<u64> {
+        if self.format == CopyFormat::Json {
+            self.out.write_all(b"\n]\n")?;
+        }
+        self.out.flush()?;
+        Ok(self.rows)
+    }
+}
+
+fn write_csv_line(out: &mut impl Write, fields: impl Iterator<Item = String>) -> io::Result<()> {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        out.write_all(field.as_bytes())?;
+    }
+    out.write_all(b"\n")
+}
+
+fn csv_field(text: &str) -> String {
+    if text.is_empty() || text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn json_value(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Int(n) => serde_json::Value::from(*n),
+        Value::Text(text) => serde_json::Value::from(text.as_str()),
+        Value::Bool(b) => serde_json::Value::from(*b),
+        // JSON has no NaN or infinities; they are written as null
+        Value::Float(f) => serde_json::Value::from(*f),
+        Value::Date(d) => serde_json::Value::from(temporal::format_date(*d)),
+        Value::Timestamp(t) => serde_json::Value::from(temporal::format_timestamp(*t)),
+        // As a string, so that readers parsing numbers as doubles keep every
+        // digit
+        Value::Decimal(d) => serde_json::Value::from(d.to_string()),
+        Value::Bytes(b) => serde_json::Value::from(binary::format_hex(b)),
+        Value::Null => serde_json::Value::Null,
+    }
+}
@@ -0,0 +1,189 @@
+//! Garbage collection of orphaned files in the data directory.
+//!
+//! A crash can leave behind files that no table refers to: the storage of a
+//! table dropped just before the crash, an index whose CREATE INDEX never
+//! reached the catalog, or a half-written temporary file. Such files are
+//! collected when the database opens and by `ADMIN GC`.
+//!
+//! Table and index files (`<table>.heap`, `table_<id>.tbl`,
+//! `<table>.pk_idx`, `index_<id>.idx`, and `<table>.lsm` and
+//! `<table>.columnar` directories) are moved into the [`QUARANTINE_DIR`]
+//! directory rather than deleted, in case they hold rows worth recovering.
+//! Temporary (`.tmp`) files are deleted, except the spill files of
+//! statements this process is running. Nothing else in the data directory,
+//! such as the catalog, the WAL or the Raft log, is touched.
+//!
+//! Collection takes two steps so the directory can be listed without
+//! blocking statements: [`scan`] takes no lock, and [`collect_garbage`]
+//! then runs under the catalog lock and skips files that are referenced or
+//! already gone by then.
+
+use std::{
+    collections::HashSet,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use catalog::{Catalog, EngineKind};
+
+/// Directory in the data directory that orphaned files are moved into.
+pub const QUARANTINE_DIR: &str = "orphaned";
+
+/// What happened to a collected file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GcAction {
+    /// Deleted: a temporary file.
+    Removed,
+    /// Moved into [`QUARANTINE_DIR`].
+    Quarantined,
+}
+
+impl fmt::Display for GcAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GcAction::Removed => "removed",
+            GcAction::Quarantined => "quarantined",
+        })
+    }
+}
+
+/// A file collected from the data directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Collected {
+    /// File name within the data directory.
+    pub file: String,
+    pub action: GcAction,
+}
+
+/// Files in the data directory that may be garbage, found by [`scan`].
+#[derive(Debug)]
+pub struct Scan {
+    candidates: Vec<Collected>,
+}
+
+/// List the files in `data_dir` that the collector manages, without
+/// deciding yet whether anything refers to them.
+pub fn scan(data_dir: &Path) -> Result<Scan> {
+    let entries = fs::read_dir(data_dir)
+        .with_context(|| format!("failed to read data directory {}", data_dir.display()))?;
+    let mut candidates = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        let is_dir = entry.file_type()?.is_dir();
+        if let Some(action) = classify(&name, is_dir) {
+            candidates.push(Collected { file: name, action });
+        }
+    }
+    candidates.sort_by(|a, b| a.file.cmp(&b.file));
+    Ok(Scan { candidates })
+}
+
+/// Quarantine or remove the files of `scan` that no table in `catalog`
+/// refers to, returning them sorted by name.
+///
+/// The caller must keep the catalog from changing while this runs, so that
+/// files of tables being created are not mistaken for orphans. Files
+/// created since the scan are left for the next collection.
+pub fn collect_garbage(catalog: &Catalog, data_dir: &Path, scan: Scan) -> Result<Vec<Collected>> {
+    let referenced = referenced_files(catalog);
+    let mut collected = Vec::new();
+    for candidate in scan.candidates {
+        let name = &candidate.file;
+        if candidate.action == GcAction::Quarantined && referenced.contains(name) {
+            continue;
+        }
+
+        let path = data_dir.join(name);
+        let result = match candidate.action {
+            GcAction::Removed => fs::remove_file(&path)
+                .with_context(|| format!("failed to remove {}", path.display())),
+            GcAction::Quarantined => {
+                let target = quarantine_path(data_dir, name)?;
+                fs::rename(&path, &target).with_context(|| {
+                    format!("failed to move {} to {}", path.display(), target.display())
+                })
+            }
+        };
+        match result {
+            Ok(()) => collected.push(candidate),
+            // Renamed into place or removed by its owner since the scan
+            Err(_) if !path.exists() => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(collected)
+}
+
+/// Names of the files that the tables in `catalog` may keep.
+fn referenced_files(catalog: &Catalog) -> HashSet<String> {
+    let mut files = HashSet::new();
+    for table in catalog.tables() {
+        for (storage, id) in table.storage_units() {
+            // Pages the buffer pool writes for a table it has no file for
+            files.insert(format!("table_{}.tbl", id.0));
+            match table.engine {
+                EngineKind::Heap => {
+                    files.insert(format!("{storage}.heap"));
+                }
+                EngineKind::Lsm => {
+                    files.insert(format!("{storage}.lsm"));
+                }
+                EngineKind::Columnar => {
+                    files.insert(format!("{storage}.columnar"));
+                }
+                EngineKind::Memory => {}
+            }
+        }
+        if table.primary_key.is_some() {
+            files.insert(format!("{}.pk_idx", table.name));
+        }
+        for index in &table.indexes {
+            files.insert(format!("index_{}.idx", index.id.0));
+        }
+    }
+    files
+}
+
+/// What to do with `name` if nothing refers to it, or `None` if it is not a
+/// file the collector manages.
+fn classify(name: &str, is_dir: bool) -> Option<GcAction> {
+    let (stem, ext) = name.rsplit_once('.')?;
+    let index_file = stem
+        .strip_prefix("index_")
+        .is_some_and(|id| id.parse::<u64>().is_ok());
+    match (ext, is_dir) {
+        ("lsm" | "columnar", true) | ("heap" | "tbl" | "pk_idx", false) => {
+            Some(GcAction::Quarantined)
+        }
+        ("idx", false) if index_file => Some(GcAction::Quarantined),
+        ("tmp", false) if is_live_spill(stem) => None,
+        ("tmp", false) => Some(GcAction::Removed),
+        _ => None,
+    }
+}
+
+/// Whether `stem` names a spill file of this process, which a running
+/// statement may still be using. Spill files are removed when dropped, so
+/// those of other processes were left by a crash.
+fn is_live_spill(stem: &str) -> bool {
+    stem.strip_prefix("spill-")
+        .and_then(|rest| rest.split_once('-'))
+        .is_some_and(|(pid, _)| pid == std::process::id().to_string())
+}
+
+/// A path in the quarantine directory for `name` that is not taken yet.
+fn quarantine_path(data_dir: &Path, name: &str) -> Result<PathBuf> {
+    let dir = data_dir.join(QUARANTINE_DIR);
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let mut target = dir.join(name);
+    let mut n = 1;
+    while target.exists() {
+        target = dir.join(format!("{name}.{n}"));
+        n += 1;
+    }
+    Ok(target)
+}
@@ -0,0 +1,194 @@
+//! Parallel scans for building indexes over existing rows.
+//!
+//! `CREATE INDEX` on a populated table reads every row once. The pages of
+//! each run are handed out in chunks to worker threads, each with its own
+//! handle on the table's storage; every worker sorts the entries it found,
+//! and the sorted runs are merged into one list that a B+Tree can be bulk
+//! loaded from.
+//!
+//! Each run's length is read from the storage before the walk starts, and
+//! every page of it is read exactly once.
+
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+};
+
+use anyhow::{anyhow, Result};
+use catalog::{IndexId, IndexKind, TableMeta};
+use common::{compression::Compression, crypto::EncryptionKey, PageId, RecordId};
+use executor::EngineRegistry;
+use storage::HeapTable;
+use types::Value;
+
+/// Most worker threads one index build uses.
+pub const MAX_BUILD_THREADS: usize = 8;
+
+/// Pages a worker claims at a time.
+const CHUNK_PAGES: u64 = 16;
+
+/// An index key and the row it points at.
+pub type IndexEntry = (Vec<Value>, RecordId);
+
+/// Worker threads to build an index with on this machine.
+pub fn build_threads() -> usize {
+    thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(MAX_BUILD_THREADS)
+}
+
+/// Collect the `columns` of every row of `table` as index entries, scanning
+/// with `threads` workers. Entries are sorted by key, and rows with equal
+/// keys by record ID.
+pub fn collect_entries(
+    engines: &EngineRegistry,
+    data_dir: &Path,
+    table: &TableMeta,
+    key: Option<&EncryptionKey>,
+    columns: &[usize],
+    threads: usize,
+) -> Result<Vec<IndexEntry>> {
+    let mut heap = engines
+        .open(data_dir, table, key)
+        .map_err(|e| anyhow!("failed to open table storage: {}", e))?;
+    let runs = heap
+        .page_runs()
+        .into_iter()
+        .map(|start| Ok((start, heap.run_pages(start)?)))
+        .collect::<common::DbResult<Vec<_>>>()
+        .map_err(|e| anyhow!("failed to read table storage: {}", e))?;
+    drop(heap);
+
+    let mut sorted_runs = Vec::new();
+    for (start, pages) in runs {
+        let next = AtomicU64::new(start.0);
+        let end = start.0 + pages;
+        let worker = || -> Result<Vec<IndexEntry>> {
+            let mut heap = engines
+                .open(data_dir, table, key)
+                .map_err(|e| anyhow!("failed to open table storage: {}", e))?;
+            let mut entries = Vec::new();
+            loop {
+                let first = next.fetch_add(CHUNK_PAGES, Ordering::SeqCst);
+                if first >= end {
+                    break;
+                }
+                for page in first..end.min(first + CHUNK_PAGES) {
+                    scan_page(heap.as_mut(), table, columns, PageId(page), &mut entries)?;
+                }
+            }
+            entries.sort_by(entry_order);
+            Ok(entries)
+        };
+
+        let found = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads.max(1)).map(|_| scope.spawn(worker)).collect();
+            workers
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|_| Err(anyhow!("index build worker panicked")))
+                })
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        sorted_runs.extend(found);
+    }
+    Ok(merge(sorted_runs))
+}
+
+/// Write `entries`, sorted as [`collect_entries`] returns them, to a new
+/// index file of `kind` at `path`, replacing any file already there. B+Tree
+/// nodes are compressed with `compression`, the table's setting, or sealed
+/// with `key` when the database is encrypted.
+pub fn write_index(
+    kind: &IndexKind,
+    path: &Path,
+    index_id: IndexId,
+    compression: Compression,
+    key: Option<&EncryptionKey>,
+    entries: Vec<IndexEntry>,
+) -> Result<()> {
+    match kind {
+        IndexKind::BTree => {
+            btree::BTreeIndex::bulk_load_with(path, index_id, entries, compression, key)
+                .and_then(|mut btree| btree.flush())
+                .map_err(|e| anyhow!("failed to build B+Tree index: {}", e))?;
+        }
+        IndexKind::Hash => {
+            let mut hash = hash::HashIndex::create_with_key(path, index_id, key)
+                .map_err(|e| anyhow!("failed to create Hash index: {}", e))?;
+            for (key, rid) in entries {
+                hash.insert(key, rid)
+                    .map_err(|e| anyhow!("failed to insert into Hash: {}", e))?;
+            }
+            hash.flush()
+                .map_err(|e| anyhow!("failed to flush Hash index: {}", e))?;
+        }
+        _ => return Err(anyhow!("unsupported index type")),
+    }
+    Ok(())
+}
+
+/// Add an entry for every row on `page` to `entries`.
+fn scan_page(
+    heap: &mut dyn HeapTable,
+    table: &TableMeta,
+    columns: &[usize],
+    page: PageId,
+    entries: &mut Vec<IndexEntry>,
+) -> Result<()> {
+    let rows = heap
+        .scan_page(page, None)
+        .map_err(|e| anyhow!("failed to read table storage: {}", e))?;
+    for mut row in rows.unwrap_or_default() {
+        let rid = row
+            .rid()
+            .ok_or_else(|| anyhow!("scanned row has no record ID"))?;
+        table.schema.fill_missing_columns(&mut row.values);
+        let key = columns
+            .iter()
+            .filter_map(|&ord| row.values.get(ord).cloned())
+            .collect();
+        entries.push((key, rid));
+    }
+    Ok(())
+}
+
+fn entry_order(a: &IndexEntry, b: &IndexEntry) -> std::cmp::Ordering {
+    a.0.cmp(&b.0)
+        .then_with(|| rid_order(a.1).cmp(&rid_order(b.1)))
+}
+
+fn rid_order(rid: RecordId) -> (u64, u16) {
+    (rid.page_id.0, rid.slot)
+}
+
+/// Merge runs that are each sorted by [`entry_order`].
+fn merge(runs: Vec<Vec<IndexEntry>>) -> Vec<IndexEntry> {
+    let mut merged = Vec::with_capacity(runs.iter().map(Vec::len).sum());
+    let mut runs: Vec<_> = runs.into_iter().map(Vec::into_iter).collect();
+    let mut heads = BinaryHeap::new();
+    for (run, entries) in runs.iter_mut().enumerate() {
+        if let Some((key, rid)) = entries.next() {
+            heads.push(Reverse((key, rid_order(rid), run)));
+        }
+    }
+    while let Some(Reverse((key, (page, slot), run))) = heads.pop() {
+        merged.push((
+            key,
+            RecordId {
+                page_id: PageId(page),
+                slot,
+            },
+        ));
+        if let Some((key, rid)) = runs[run].next() {
+            heads.push(Reverse((key, rid_order(rid), run)));
+        }
+    }
+    merged
+}
@@ -0,0 +1,88 @@
+//! Transaction isolation levels.
+//!
+//! `SET TRANSACTION ISOLATION LEVEL ...` selects the level for the next
+//! transaction, and `SET SESSION CHARACTERISTICS AS TRANSACTION ...` changes
+//! the default for every later transaction. A statement run on its own is
+//! its own transaction, so the level chosen with `SET TRANSACTION` applies
+//! to the statement that follows it, or to the next
+//! [`Database::begin_as`](crate::Database::begin_as).
+//!
+//! Both belong to the session that runs them (see [`sessions`](crate::sessions)):
+//! other clients of the same database keep their own levels.
+//!
+//! A single statement is isolated by locks, not snapshots: the engine keeps
+//! a single version of each row. A statement plans under a shared lock on
+//! the catalog, then holds the exclusive WAL lock from before its first
+//! read until its last write is logged, and DDL takes the catalog lock
+//! exclusively. Every lock a statement takes is held until it finishes, so
+//! statements run one at a time and each sees all of the statements before
+//! it and none of the ones after. That is serializable, whichever level was
+//! chosen.
+//!
+//! A [`Transaction`](crate::Transaction) of several statements does not
+//! hold those locks between its statements, and the level decides what it
+//! may see. The table gives the anomalies each level permits and the ones a
+//! transaction can show:
+//!
+//! | level           | dirty read | non-repeatable read | phantom | write skew | transactions |
+//! |-----------------|------------|---------------------|---------|------------|--------------|
+//! | read committed  | no         | allowed             | allowed | allowed    | all three    |
+//! | repeatable read | no         | no                  | allowed | allowed    | write skew   |
+//! | serializable    | no         | no                  | no      | no         | none         |
+//!
+//! `READ UNCOMMITTED` is accepted and treated as read committed. A weaker
+//! level never takes fewer locks; it only records what the statement would
+//! tolerate.
+//!
+//! # Serializable
+//!
+//! Rows have a single version, so a transaction's `SELECT`s read the live
+//! tables rather than a snapshot. Repeatable read and serializable
+//! transactions instead fail as soon as they read or write a table that a
+//! transaction committed since they began has written, and serializable
+//! ones also track read-write antidependencies between tables, aborting one
+//! transaction of any that could form a cycle; see the [`ssi`](crate::ssi)
+//! module. Either way a transaction may fail with a
+//! [`SerializationFailure`](crate::SerializationFailure) and should be run
+//! again from the start.
+//!
+//! [`Database::execute_versioned_update`](crate::Database::execute_versioned_update)
+//! is the other tool for read-modify-write cycles that span several
+//! statements outside a transaction.
+
+pub use parser::IsolationLevel;
+
+/// Isolation state of one session.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IsolationSettings {
+    /// Default level for every transaction.
+    session: IsolationLevel,
+    /// Level chosen with `SET TRANSACTION` for the next transaction only.
+    next: Option<IsolationLevel>,
+}
+
+impl IsolationSettings {
+    /// Apply a `SET TRANSACTION` (or `SET SESSION CHARACTERISTICS`) statement.
+    pub fn set(&mut self, isolation: IsolationLevel, session: bool) {
+        if session {
+            self.session = isolation;
+        } else {
+            self.next = Some(isolation);
+        }
+    }
+
+    /// The level the next transaction will run under.
+    pub fn current(&self) -> IsolationLevel {
+        self.next.unwrap_or(self.session)
+    }
+
+    /// The session default.
+    pub fn session(&self) -> IsolationLevel {
+        self.session
+    }
+
+    /// Start a transaction, consuming any one-shot level.
+    pub fn begin(&mut self) -> IsolationLevel {
+        self.next.take().unwrap_or(self.session)
+    }
+}
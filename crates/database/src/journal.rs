@@ -0,0 +1,87 @@
+//! The undo journal that keeps a transaction's commit all or nothing across
+//! a crash.
+//!
+//! A [`Transaction`](crate::Transaction) applies its queued writes one
+//! statement at a time, and each statement writes its pages in place and
+//! commits its own WAL records. The WAL is redo-only, so a crash between two
+//! of the statements would leave the earlier ones applied. To avoid that,
+//! the undo records of the statements applied so far are saved to
+//! [`JOURNAL_FILE`] before the next one runs, and the file is removed once
+//! the last one has applied: removing it is the transaction's commit record.
+//!
+//! A database that opens with a journal left behind rolls its records back,
+//! newest first, before serving statements (see [`crate::recovery`]). The
+//! journal is saved between statements, so a crash while one statement is
+//! applying can still leave that statement's own row changes, as it can for
+//! a statement run outside a transaction.
+//!
+//! Row changes to tables on engines that keep no rows across a restart are
+//! not rolled back, and Raft nodes keep no journal: their rollback is
+//! replicated like any other write.
+
+use std::{
+    fs::{self, File},
+    io::{ErrorKind, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use bincode::serde::{decode_from_slice, encode_to_vec};
+use common::crypto::{self, EncryptionKey};
+use executor::UndoRecord;
+
+/// Journal of the transaction being committed, in the data directory.
+pub const JOURNAL_FILE: &str = "transaction.undo";
+
+/// Associated data for an encrypted journal.
+const JOURNAL_AAD: &[u8] = b"transaction.undo";
+
+/// Save `undo`, the row changes applied so far, replacing the journal.
+///
+/// The journal is synced before it replaces the previous one, so it is
+/// never seen half written.
+pub(crate) fn save(
+    data_dir: &Path,
+    key: Option<&EncryptionKey>,
+    undo: &[UndoRecord],
+) -> Result<()> {
+    let bytes = encode_to_vec(undo, bincode::config::legacy())?;
+    let bytes = crypto::seal_file(key, JOURNAL_AAD, bytes)?;
+    let path = data_dir.join(JOURNAL_FILE);
+    let tmp = path.with_extension("undo.tmp");
+    let mut file =
+        File::create(&tmp).with_context(|| format!("failed to write {}", tmp.display()))?;
+    file.write_all(&bytes)
+        .and_then(|()| file.sync_all())
+        .with_context(|| format!("failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, &path).with_context(|| format!("failed to replace {}", path.display()))
+}
+
+/// The row changes of a transaction interrupted by a crash, if it left a
+/// journal.
+pub(crate) fn load(
+    data_dir: &Path,
+    key: Option<&EncryptionKey>,
+) -> Result<Option<Vec<UndoRecord>>> {
+    let path = data_dir.join(JOURNAL_FILE);
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let bytes = crypto::open_file(key, JOURNAL_AAD, bytes)?;
+    let (undo, _) = decode_from_slice(&bytes, bincode::config::legacy())
+        .with_context(|| format!("failed to decode {}", path.display()))?;
+    Ok(Some(undo))
+}
+
+/// Remove the journal, committing the transaction it belongs to.
+pub(crate) fn remove(data_dir: &Path) -> Result<()> {
+    let path = data_dir.join(JOURNAL_FILE);
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => {
+            Err(e).with_context(|| format!("failed to remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
@@ -1,47 +1,207 @@
 use anyhow::{Context, Result};
-use buffer::FilePager;
-use catalog::{Catalog, Column, IndexKind};
-use executor::{build_executor, execute_dml, execute_query, ExecutionContext};
+use buffer::{FilePager, SharedPager};
+use catalog::{
+    bump_row_version, Catalog, Column, IndexKind, Modification, PartitionBound, PartitionMethod,
+    StorageStatistics, TableMeta, TableStatistics, ROW_VERSION_COLUMN,
+};
+use common::compression::Compression;
+use common::hooks::{self, FaultInjector};
+use common::{DbError, DbResult, RecordId, TableId};
+use executor::{
+    build_executor, build_profiled_executor, execute_dml, execute_query, EngineRegistry,
+    ExecutionContext, PrimaryKeyIndex, UndoRecord,
+};
+use expr::random::{with_generator, Generator};
+use futures::Stream;
 use openraft::storage::{Adaptor, RaftLogStorage, RaftStateMachine};
 use openraft::Raft;
-use parser::{parse_sql, Statement};
+use parser::{parse_sql, CopyFormat, Statement};
 use planner::{PhysicalPlan, Planner, PlanningContext, ResolvedExpr};
 use raft::{
-    ActivitySender, ApplyHandler, ClusterConfig, Command, CommandResponse, HttpNetworkFactory,
-    MemRaftStore, NetworkFactory, PersistentRaftStore, RaftHttpState, ServerHandle, TypeConfig,
+    ActivitySender, AdminConfig, ApplyHandler, CheckpointHandler, ChecksumHandler, ClusterConfig,
+    Command, CommandResponse, HttpNetworkFactory, MemRaftStore, NetworkFactory,
+    PersistentRaftStore, RaftHttpState, ServerHandle, SettingsHandler, TypeConfig,
 };
 
 // Re-export activity types for external use (e.g., server TUI)
-pub use raft::{activity_channel, ActivityReceiver, RaftActivityEvent};
+pub use raft::{activity_channel, ActivityReceiver, RaftActivityEvent, TableChecksum};
 
 // Re-export RaftNode type for external use (e.g., server TUI metrics polling)
+use admission::AdmissionQueue;
+use disk_full::DiskFullGuard;
 pub use raft::RaftNode;
+use recovery::SelfCheck;
+use sessions::{SessionRandom, SessionRegistry};
+use settings::GlobalSettings;
 use std::{
-    collections::BTreeMap,
-    fs,
+    collections::{BTreeMap, HashMap, HashSet},
+    fs, io,
     ops::DerefMut,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
-use storage::HeapTable;
-use tokio::sync::{Mutex, RwLock};
+use storage::{ColumnarEngine, HeapEngine, HeapTable, LsmEngine, MemoryEngine};
+use tokio::sync::{watch, Mutex, RwLock};
 use types::Value;
 use wal::{Wal, WalRecord};
 
+pub mod admission;
+pub mod audit;
+pub mod bulk;
+pub mod consistency;
+pub mod disk_full;
+pub mod export;
+pub mod gc;
+pub mod index_build;
+pub mod isolation;
+pub mod journal;
+pub mod manifest;
+pub mod recovery;
+pub mod replication;
+pub mod retry;
+pub mod routing;
+pub mod schema;
+pub mod sessions;
+pub mod settings;
+pub mod snapshot;
+pub mod ssi;
+pub mod statistics;
+pub mod transaction;
+pub mod vacuum;
+
+pub use admission::AdmissionStats;
+pub use audit::{AuditLog, AuditRecord, LOCAL_PRINCIPAL};
+pub use bulk::InsertProgress;
+pub use catalog::EngineKind;
+pub use common::crypto::EncryptionKey;
+pub use common::{Priority, ResourceLimits};
+pub use disk_full::is_disk_full;
+pub use export::RowWriter;
+pub use gc::{Collected, GcAction};
+pub use isolation::{IsolationLevel, IsolationSettings};
+pub use manifest::Manifest;
+pub use recovery::RecoveryReport;
+pub use replication::{Change, ChangeBatch, Publication, Subscription};
+pub use retry::{is_retryable, RetryPolicy};
+pub use routing::{route, ClusterView, NotLeaderError, ReadConsistency, Route, StatementClass};
+pub use schema::{SchemaBundle, SchemaChange};
+pub use settings::{Settings, SlowQuery};
+pub use snapshot::TableSnapshot;
+pub use ssi::{ConflictTracker, SerializationFailure};
+pub use statistics::AutoAnalyze;
+pub use transaction::Transaction;
+pub use vacuum::{AutoVacuum, VacuumOutcome};
+pub use wal::Durability;
+
+/// File in the data directory that holds the audit log.
+const AUDIT_LOG_FILE: &str = "audit.log";
+
+/// File in the data directory that lists the files expected next to it.
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Rows written between saves of the tables' activity counters. Counts since
+/// the last save are lost if the process exits without dropping the database.
+const ACTIVITY_SAVE_ROWS: u64 = 1000;
+
 /// Result type for database operations that may include query results.
+///
+/// Queries and DML carry an [`ExecutionInfo`] when they ran through the
+/// executor on this node; other statements returning rows or counts, such as
+/// `SHOW` commands or DML replicated through Raft, leave it `None`.
 #[derive(Debug)]
 pub enum QueryResult {
     /// Query returned rows
     Rows {
         schema: Vec<String>,
         rows: Vec<common::Row>,
+        info: Option<ExecutionInfo>,
     },
     /// DML operation affected N rows
-    Count { affected: u64 },
+    Count {
+        affected: u64,
+        info: Option<ExecutionInfo>,
+    },
+    /// INSERT that generated values for auto-increment columns, returned in
+    /// insertion order. Inserts that generate nothing return [`QueryResult::Count`].
+    Inserted {
+        affected: u64,
+        generated_ids: Vec<i64>,
+        info: Option<ExecutionInfo>,
+    },
     /// DDL or other operation with no result
     Empty,
 }
 
+impl QueryResult {
+    /// How the statement ran, if it ran through the executor.
+    pub fn info(&self) -> Option<&ExecutionInfo> {
+        match self {
+            QueryResult::Rows { info, .. }
+            | QueryResult::Count { info, .. }
+            | QueryResult::Inserted { info, .. } => info.as_ref(),
+            QueryResult::Empty => None,
+        }
+    }
+}
+
+/// How a query or DML statement ran, measured as it runs so that showing it
+/// does not take an `EXPLAIN ANALYZE`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionInfo {
+    /// Rows read from tables, counted as for
+    /// [`ResourceLimits::max_rows_scanned`](common::ResourceLimits).
+    pub rows_scanned: u64,
+    /// Rows the planner expected a query to return, from table statistics.
+    /// `None` for DML and for queries reading a table that was never
+    /// analyzed.
+    pub estimated_rows: Option<u64>,
+    /// Rows the query returned or the DML affected.
+    pub actual_rows: u64,
+    /// Time spent planning and running the statement, including waiting for
+    /// the storage locks.
+    pub elapsed: Duration,
+}
+
+impl std::fmt::Display for ExecutionInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "time={} scanned={} rows={}",
+            common::ExecutionStats::format_duration(self.elapsed),
+            self.rows_scanned,
+            self.actual_rows
+        )?;
+        if let Some(estimated) = self.estimated_rows {
+            write!(f, " estimated={estimated}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned by [`Database::execute_versioned_update`] when no row
+/// matched the expected version: another writer updated or deleted the row
+/// after it was read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionConflict {
+    pub table: String,
+}
+
+impl std::fmt::Display for VersionConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "version conflict on table '{}': row was modified concurrently",
+            self.table
+        )
+    }
+}
+
+impl std::error::Error for VersionConflict {}
+
 /// Configuration for Raft consensus mode.
 #[derive(Clone, Debug, Default)]
 pub struct RaftConfig {
@@ -60,6 +220,11 @@ pub struct RaftConfig {
     pub persistent_storage: bool,
     /// Optional sender for Raft activity events (for TUI monitoring).
     pub activity_tx: Option<ActivitySender>,
+    /// Bearer token for the `/admin/*` HTTP endpoints.
+    /// Admin endpoints are disabled when unset (multi-node mode only).
+    pub admin_token: Option<String>,
+    /// How fresh reads must be; see [`ReadConsistency`].
+    pub read_consistency: ReadConsistency,
 }
 
 impl RaftConfig {
@@ -72,6 +237,8 @@ impl RaftConfig {
             peers: Vec::new(),
             persistent_storage: false,
             activity_tx: None,
+            admin_token: None,
+            read_consistency: ReadConsistency::Local,
         }
     }
 
@@ -84,6 +251,8 @@ impl RaftConfig {
             peers: Vec::new(),
             persistent_storage: true,
             activity_tx: None,
+            admin_token: None,
+            read_consistency: ReadConsistency::Local,
         }
     }
 
@@ -99,6 +268,18 @@ impl RaftConfig {
         self
     }
 
+    /// Enable the admin HTTP endpoints, protected by the given bearer token.
+    pub fn with_admin_token(mut self, token: impl Into<String>) -> Self {
+        self.admin_token = Some(token.into());
+        self
+    }
+
+    /// Set the consistency level for reads.
+    pub fn with_read_consistency(mut self, consistency: ReadConsistency) -> Self {
+        self.read_consistency = consistency;
+        self
+    }
+
     /// Create a new Raft config for multi-node cluster (in-memory storage).
     ///
     /// # Arguments
@@ -117,6 +298,8 @@ impl RaftConfig {
             peers,
             persistent_storage: false,
             activity_tx: None,
+            admin_token: None,
+            read_consistency: ReadConsistency::Local,
         }
     }
 
@@ -133,6 +316,8 @@ impl RaftConfig {
             peers,
             persistent_storage: true,
             activity_tx: None,
+            admin_token: None,
+            read_consistency: ReadConsistency::Local,
         }
     }
 
@@ -151,9 +336,10 @@ pub struct Database {
     data_dir: Arc<PathBuf>,
     catalog_path: Arc<PathBuf>,
     wal_path: Arc<PathBuf>,
-    buffer_pages: usize,
+    /// Settings changed with SET GLOBAL, including the buffer pool's size
+    settings: Arc<GlobalSettings>,
     catalog: Arc<RwLock<Catalog>>,
-    pager: Arc<Mutex<FilePager>>,
+    pager: SharedPager,
     wal: Arc<Mutex<Wal>>,
     /// Raft consensus node (None if Raft is disabled)
     raft: Option<Arc<RaftNode>>,
@@ -162,13 +348,59 @@ pub struct Database {
     http_server: Option<ServerHandle>,
     /// Node ID for Raft
     node_id: u64,
+    /// Consistency level applied when routing reads
+    read_consistency: ReadConsistency,
+    /// Raft addresses of known cluster members, for leader hints
+    member_addrs: BTreeMap<u64, String>,
+    /// Tables read and written by concurrent transactions
+    conflicts: ConflictTracker,
+    /// Held while a transaction commits and while one reads, so no read
+    /// sees part of a commit
+    commit_lock: Mutex<()>,
+    /// Retry policy for transient failures (None disables retries)
+    retry_policy: Option<RetryPolicy>,
+    /// Latest catalog epoch, published after every schema change
+    catalog_epoch: Arc<watch::Sender<u64>>,
+    /// Master key for data at rest (None stores files in plaintext)
+    encryption: Option<EncryptionKey>,
+    /// Append-only log of statements touching audited tables
+    audit_log: AuditLog,
+    /// Caps applied to every session
+    resource_limits: ResourceLimits,
+    /// Worker threads each sequential scan may use (0 scans serially)
+    max_parallel_workers: usize,
+    /// Engine for tables created without `ENGINE = <name>`
+    default_engine: EngineKind,
+    /// Statements in flight per session
+    sessions: SessionRegistry,
+    /// Queue of statements waiting to execute
+    admission: Arc<AdmissionQueue>,
+    /// When to re-analyze tables after writes (None disables it)
+    auto_analyze: Option<AutoAnalyze>,
+    /// Tables with a background re-analysis in progress
+    analyzing: Arc<std::sync::Mutex<HashSet<TableId>>>,
+    /// When to vacuum tables after writes (None disables it)
+    auto_vacuum: Option<AutoVacuum>,
+    /// Tables with a background vacuum in progress
+    vacuuming: Arc<std::sync::Mutex<HashSet<TableId>>>,
+    /// Rows written since the activity counters were last saved
+    unsaved_writes: AtomicU64,
+    /// Storage engines serving this database's tables
+    engines: Arc<EngineRegistry>,
+    /// Consulted before every WAL, pager and heap file operation
+    faults: Arc<dyn FaultInjector>,
+    /// Read-only mode entered when a write runs out of disk space
+    disk_full: Arc<DiskFullGuard>,
+    /// What the self-check found when the database was opened
+    recovery: RecoveryReport,
 }
 
 impl Database {
     /// Create a new async database instance.
     ///
     /// Creates the data directory if it doesn't exist, loads the catalog,
-    /// initializes the pager, and opens the WAL.
+    /// initializes the pager, and opens the WAL, checking the data directory
+    /// on the way (see [`Database::recovery_report`]).
     /// All I/O operations are performed in spawn_blocking.
     pub async fn new(
         data_dir: &Path,
@@ -186,12 +418,113 @@ impl Database {
         wal_file: &str,
         buffer_pages: usize,
         raft_config: Option<RaftConfig>,
+    ) -> Result<Self> {
+        Self::with_encryption(
+            data_dir,
+            catalog_file,
+            wal_file,
+            buffer_pages,
+            raft_config,
+            None,
+        )
+        .await
+    }
+
+    /// Create a new async database instance whose files are encrypted at rest.
+    ///
+    /// With a key, heap pages, B-tree nodes, hash index pages, primary key
+    /// indexes, the catalog, WAL records, audit log records, and the rows
+    /// that sorts and joins spill to disk are encrypted with AES-256-GCM.
+    /// The key must be supplied on every open: an encrypted database cannot
+    /// be opened without it, and an existing plaintext database cannot be
+    /// opened with one.
+    ///
+    /// Everything sealed records the id of its key, so keys can be rotated:
+    /// open with the new key and [`EncryptionKey::with_previous`] the old one.
+    /// New writes use the new key while older pages stay readable, and
+    /// `VACUUM` rewrites a table and its indexes under the new key.
+    ///
+    /// The Raft log is not encrypted yet and still contains replicated rows
+    /// in plaintext.
+    pub async fn with_encryption(
+        data_dir: &Path,
+        catalog_file: &str,
+        wal_file: &str,
+        buffer_pages: usize,
+        raft_config: Option<RaftConfig>,
+        encryption: Option<EncryptionKey>,
+    ) -> Result<Self> {
+        Self::open(
+            data_dir,
+            catalog_file,
+            wal_file,
+            buffer_pages,
+            raft_config,
+            encryption,
+            hooks::no_faults(),
+        )
+        .await
+    }
+
+    /// Create a new async database instance whose WAL appends and syncs, and
+    /// page reads and writes, first ask `faults` whether to go ahead.
+    ///
+    /// For crash testing (see [`common::hooks`]): a failed operation leaves
+    /// the files as they were before it, so dropping the database after a
+    /// [`FaultPlan::crash_at`](common::hooks::FaultPlan::crash_at) and
+    /// opening the directory again simulates a restart after a crash.
+    pub async fn with_faults(
+        data_dir: &Path,
+        catalog_file: &str,
+        wal_file: &str,
+        buffer_pages: usize,
+        faults: Arc<dyn FaultInjector>,
+    ) -> Result<Self> {
+        Self::open(
+            data_dir,
+            catalog_file,
+            wal_file,
+            buffer_pages,
+            None,
+            None,
+            faults,
+        )
+        .await
+    }
+
+    async fn open(
+        data_dir: &Path,
+        catalog_file: &str,
+        wal_file: &str,
+        buffer_pages: usize,
+        raft_config: Option<RaftConfig>,
+        encryption: Option<EncryptionKey>,
+        faults: Arc<dyn FaultInjector>,
     ) -> Result<Self> {
         let data_dir_owned = data_dir.to_path_buf();
         let catalog_file_owned = catalog_file.to_string();
         let wal_file_owned = wal_file.to_string();
-
-        let (catalog, pager, wal, catalog_path, wal_path) =
+        let key = encryption.clone();
+        let pager =
+            SharedPager::new(FilePager::new(data_dir, buffer_pages).with_faults(faults.clone()));
+        let engines = Arc::new(
+            EngineRegistry::default()
+                .with_engine(
+                    EngineKind::Heap,
+                    Arc::new(
+                        HeapEngine::with_faults(faults.clone())
+                            .with_page_io(Arc::new(pager.clone())),
+                    ),
+                )
+                .with_engine(EngineKind::Memory, Arc::new(MemoryEngine::default()))
+                .with_engine(EngineKind::Lsm, Arc::new(LsmEngine::default()))
+                .with_engine(EngineKind::Columnar, Arc::new(ColumnarEngine::default())),
+        );
+        let open_engines = engines.clone();
+        let open_faults = faults.clone();
+        let open_pager = pager.clone();
+
+        let (catalog, wal, catalog_path, wal_path, recovery) =
             tokio::task::spawn_blocking(move || {
                 fs::create_dir_all(&data_dir_owned).with_context(|| {
                     format!(
@@ -200,39 +533,134 @@ impl Database {
                     )
                 })?;
 
+                let manifest_path = data_dir_owned.join(MANIFEST_FILE);
+                if let Some(manifest) = Manifest::load(&manifest_path, key.as_ref())? {
+                    manifest.verify(&data_dir_owned)?;
+                }
+
                 let catalog_path = data_dir_owned.join(&catalog_file_owned);
                 let wal_path = data_dir_owned.join(&wal_file_owned);
-                let catalog = Catalog::load(&catalog_path).map_err(anyhow::Error::from)?;
-                let pager = FilePager::new(&data_dir_owned, buffer_pages);
-                let wal = Wal::open(&wal_path).map_err(anyhow::Error::from)?;
+                let catalog = Catalog::load_with_key(&catalog_path, key.clone())
+                    .map_err(anyhow::Error::from)?;
+                let mut check = SelfCheck {
+                    report: RecoveryReport::default(),
+                    data_dir: &data_dir_owned,
+                    key: key.as_ref(),
+                };
+                check.catalog(&catalog_path);
+                let scan = gc::scan(&data_dir_owned)?;
+                check.collected(&gc::collect_garbage(&catalog, &data_dir_owned, scan)?);
+                reset_volatile_indexes(&catalog, &open_engines, &data_dir_owned)?;
+                check.wal(&wal_path);
+                check.tables(&catalog, &open_engines);
+                let mut wal = Wal::open_with_key(&wal_path, key.as_ref())
+                    .map_err(anyhow::Error::from)?
+                    .with_faults(open_faults);
+                check.transaction(&catalog, &open_pager, &mut wal, &open_engines);
+                let recovery = check.report;
+                let files = manifest::expected_files(
+                    &catalog,
+                    &open_engines,
+                    &data_dir_owned,
+                    &catalog_path,
+                    &wal_path,
+                );
+                Manifest::capture(&data_dir_owned, &files, false)?
+                    .save(&manifest_path, key.as_ref())?;
 
-                Ok::<_, anyhow::Error>((catalog, pager, wal, catalog_path, wal_path))
+                Ok::<_, anyhow::Error>((catalog, wal, catalog_path, wal_path, recovery))
             })
             .await??;
 
         let data_dir_arc = Arc::new(data_dir.to_path_buf());
+        let catalog_path = Arc::new(catalog_path);
+        let catalog_epoch = Arc::new(watch::channel(catalog.epoch()).0);
         let catalog_arc = Arc::new(RwLock::new(catalog));
+        let wal_arc = Arc::new(Mutex::new(wal));
+        let settings = Arc::new(GlobalSettings::new(
+            Settings {
+                durability: Durability::default(),
+                slow_query_threshold: None,
+                buffer_pages,
+            },
+            pager.clone(),
+            wal_arc.clone(),
+        ));
 
         // Initialize Raft if configured
+        let mut read_consistency = ReadConsistency::default();
+        let mut member_addrs = BTreeMap::new();
         let (raft, http_server, node_id) = if let Some(config) = raft_config.filter(|c| c.enabled) {
-            let (raft_node, server) =
-                Self::init_raft(&config, catalog_arc.clone(), data_dir_arc.clone()).await?;
+            read_consistency = config.read_consistency;
+            member_addrs.extend(config.peers.iter().cloned());
+            if let Some(ref addr) = config.listen_addr {
+                member_addrs.insert(config.node_id, addr.clone());
+            }
+            let checkpoint = Self::create_checkpoint_handler(pager.clone(), wal_arc.clone());
+            let checksums = Self::create_checksum_handler(
+                catalog_arc.clone(),
+                pager.clone(),
+                wal_arc.clone(),
+                engines.clone(),
+                data_dir_arc.clone(),
+            );
+            let apply_handler = Self::create_apply_handler(
+                catalog_arc.clone(),
+                catalog_epoch.clone(),
+                data_dir_arc.clone(),
+                catalog_path.clone(),
+                engines.clone(),
+            );
+            let (raft_node, server) = Self::init_raft(
+                &config,
+                catalog_arc.clone(),
+                data_dir_arc.clone(),
+                apply_handler,
+                checkpoint,
+                checksums,
+                Self::create_settings_handler(settings.clone()),
+            )
+            .await?;
             (Some(raft_node), server, config.node_id)
         } else {
             (None, None, 1)
         };
 
+        let disk_full = Arc::new(DiskFullGuard::new(data_dir_arc.clone(), faults.clone()));
+        let audit_log = AuditLog::new(data_dir.join(AUDIT_LOG_FILE)).with_key(encryption.clone());
         Ok(Self {
             data_dir: data_dir_arc,
-            catalog_path: Arc::new(catalog_path),
+            catalog_path,
             wal_path: Arc::new(wal_path),
-            buffer_pages,
+            settings,
             catalog: catalog_arc,
-            pager: Arc::new(Mutex::new(pager)),
-            wal: Arc::new(Mutex::new(wal)),
+            pager,
+            wal: wal_arc,
             raft,
             http_server,
             node_id,
+            read_consistency,
+            member_addrs,
+            conflicts: ConflictTracker::default(),
+            commit_lock: Mutex::new(()),
+            retry_policy: None,
+            catalog_epoch,
+            encryption,
+            audit_log,
+            resource_limits: ResourceLimits::default(),
+            max_parallel_workers: 0,
+            default_engine: EngineKind::default(),
+            sessions: SessionRegistry::default(),
+            admission: Arc::default(),
+            auto_analyze: Some(AutoAnalyze::default()),
+            analyzing: Arc::default(),
+            auto_vacuum: None,
+            vacuuming: Arc::default(),
+            unsaved_writes: AtomicU64::new(0),
+            engines,
+            faults,
+            disk_full,
+            recovery,
         })
     }
 
@@ -245,11 +673,25 @@ impl Database {
         config: &RaftConfig,
         catalog: Arc<RwLock<Catalog>>,
         data_dir: Arc<PathBuf>,
+        apply_handler: ApplyHandler,
+        checkpoint: CheckpointHandler,
+        checksums: ChecksumHandler,
+        settings: SettingsHandler,
     ) -> Result<(Arc<RaftNode>, Option<ServerHandle>)> {
         let node_id = config.node_id;
 
-        // Create apply handler that applies commands to actual storage
-        let apply_handler = Self::create_apply_handler(catalog, data_dir.clone());
+        // Admin endpoints share the Raft HTTP listener (multi-node only)
+        let admin = config.admin_token.as_ref().map(|token| {
+            let peers = config
+                .peers
+                .iter()
+                .map(|(peer_id, peer_addr)| (*peer_id, peer_url(peer_addr)))
+                .collect();
+            AdminConfig::new(token.clone(), catalog.clone(), checkpoint)
+                .with_checksums(checksums)
+                .with_settings(settings)
+                .with_peers(peers)
+        });
 
         // Create Raft config
         let raft_config = Arc::new(openraft::Config {
@@ -287,6 +729,7 @@ impl Database {
                     log_store,
                     state_machine,
                     is_restart,
+                    admin,
                 )
                 .await
             } else {
@@ -311,8 +754,15 @@ impl Database {
             let (log_store, state_machine) = Adaptor::<TypeConfig, Arc<MemRaftStore>>::new(store);
 
             if config.is_multi_node() {
-                Self::init_raft_multi_node(config, raft_config, log_store, state_machine, false)
-                    .await
+                Self::init_raft_multi_node(
+                    config,
+                    raft_config,
+                    log_store,
+                    state_machine,
+                    false,
+                    admin,
+                )
+                .await
             } else {
                 Self::init_raft_single_node(node_id, raft_config, log_store, state_machine, false)
                     .await
@@ -365,6 +815,7 @@ impl Database {
         log_store: impl RaftLogStorage<TypeConfig> + 'static,
         state_machine: impl RaftStateMachine<TypeConfig> + 'static,
         is_restart: bool,
+        admin: Option<AdminConfig>,
     ) -> Result<(Arc<RaftNode>, Option<ServerHandle>)> {
         let node_id = config.node_id;
         let listen_addr = config
@@ -376,13 +827,7 @@ impl Database {
         let mut cluster_config = ClusterConfig::new();
         cluster_config.add_node(node_id, format!("http://{}", listen_addr));
         for (peer_id, peer_addr) in &config.peers {
-            // Ensure peer addresses have http:// prefix
-            let addr = if peer_addr.starts_with("http://") || peer_addr.starts_with("https://") {
-                peer_addr.clone()
-            } else {
-                format!("http://{}", peer_addr)
-            };
-            cluster_config.add_node(*peer_id, addr);
+            cluster_config.add_node(*peer_id, peer_url(peer_addr));
         }
 
         // Create HTTP network factory
@@ -395,7 +840,10 @@ impl Database {
 
         // Start HTTP server for Raft RPCs
         let raft_arc = Arc::new(raft);
-        let http_state = RaftHttpState::new(raft_arc.clone());
+        let mut http_state = RaftHttpState::new(raft_arc.clone());
+        if let Some(admin) = admin {
+            http_state = http_state.with_admin(admin);
+        }
         let addr: std::net::SocketAddr = listen_addr
             .parse()
             .map_err(|e| anyhow::anyhow!("invalid listen address '{}': {}", listen_addr, e))?;
@@ -470,33 +918,122 @@ impl Database {
         }
     }
 
-    /// Check if this node is the leader, and return an error if not.
+    /// Isolation level `principal`'s next statement will run under.
+    ///
+    /// See the [`isolation`] module for how each level behaves.
+    pub fn isolation_level(&self, principal: &str) -> IsolationLevel {
+        self.sessions.isolation(principal).current()
+    }
+
+    /// `principal`'s session default isolation level.
+    pub fn session_isolation_level(&self, principal: &str) -> IsolationLevel {
+        self.sessions.isolation(principal).session()
+    }
+
+    /// Start a multi-statement transaction in the local session, as
+    /// [`Database::begin_as`] does.
+    pub fn begin(&self) -> Transaction<'_> {
+        self.begin_as(LOCAL_PRINCIPAL)
+    }
+
+    /// Start a multi-statement transaction in `principal`'s session, at the
+    /// level its next statement would run under, consuming a one-shot
+    /// `SET TRANSACTION`.
+    ///
+    /// See [`Transaction`] for what it can run, and the [`ssi`] module for
+    /// how concurrent transactions are kept apart.
+    pub fn begin_as(&self, principal: &str) -> Transaction<'_> {
+        let isolation = self.sessions.begin(principal);
+        Transaction::new(self, principal, isolation)
+    }
+
+    /// Transactions the conflict tracker holds: those running, and those
+    /// finished that running ones are still checked against.
+    pub fn tracked_transactions(&self) -> usize {
+        self.conflicts.tracked()
+    }
+
+    /// Fix the sequence `RANDOM()` produces for the rest of `principal`'s
+    /// session, as `SET random_seed = <seed>` does, or make it unpredictable
+    /// again with `None`, as `SET random_seed = DEFAULT` does.
     ///
-    /// Returns the current leader's node ID in the error message if known.
-    fn require_leader(&self) -> Result<()> {
-        if !self.is_raft_enabled() {
-            return Ok(()); // Non-Raft mode is always allowed
+    /// Statements run after the same seed produce the same values, so a
+    /// script that sets the seed first generates the same data every run.
+    /// Other sessions are unaffected.
+    pub fn set_random_seed(&self, principal: &str, seed: Option<i64>) {
+        self.sessions.set_random_seed(principal, seed);
+    }
+
+    /// The generator the running statement's session draws `RANDOM()` from.
+    fn session_random(&self) -> SessionRandom {
+        self.sessions.random(&statement_session())
+    }
+
+    /// Change a setting of this node for every session, as
+    /// `SET GLOBAL <name> = <value>` does, or restore the value the node was
+    /// opened with for `None`. See [`settings`] for the settings.
+    pub async fn set_global(&self, name: &str, value: Option<&str>) -> Result<()> {
+        let settings = self.settings.clone();
+        let name = name.to_string();
+        let value = value.map(str::to_string);
+        tokio::task::spawn_blocking(move || settings.set(&name, value.as_deref())).await?
+    }
+
+    /// The current values of the settings `SET GLOBAL` changes.
+    pub fn settings(&self) -> Settings {
+        self.settings.get()
+    }
+
+    /// The latest statements, oldest first, that took at least the
+    /// `slow_query_threshold_ms` setting, up to
+    /// [`settings::SLOW_QUERY_LOG_LEN`].
+    pub fn slow_queries(&self) -> Vec<SlowQuery> {
+        self.settings.slow_queries()
+    }
+
+    /// This node's current view of the cluster, used for statement routing.
+    fn cluster_view(&self) -> ClusterView {
+        match self.raft {
+            Some(ref raft) => ClusterView {
+                raft_enabled: true,
+                node_id: self.node_id,
+                leader: raft.metrics().borrow().current_leader,
+            },
+            None => ClusterView::standalone(self.node_id),
         }
+    }
+
+    /// Decide where a statement would be executed if it arrived now.
+    pub fn route_statement(&self, stmt: &Statement) -> Route {
+        route(
+            StatementClass::of(stmt),
+            self.read_consistency,
+            &self.cluster_view(),
+        )
+    }
+
+    /// Build the error returned when a statement must run on the leader.
+    fn not_leader_error(&self, class: StatementClass, leader: Option<u64>) -> anyhow::Error {
+        anyhow::Error::new(NotLeaderError {
+            node_id: self.node_id,
+            class,
+            leader,
+            leader_addr: leader.and_then(|id| self.member_addrs.get(&id).cloned()),
+        })
+    }
 
+    /// Confirm leadership with a quorum and wait for the state machine to
+    /// apply everything committed before the read.
+    async fn ensure_linearizable(&self) -> Result<()> {
         if let Some(ref raft) = self.raft {
-            let metrics = raft.metrics().borrow().clone();
-            if metrics.current_leader == Some(self.node_id) {
-                return Ok(());
+            if let Err(e) = raft.ensure_linearizable().await {
+                let leader = raft.metrics().borrow().current_leader;
+                return Err(self
+                    .not_leader_error(StatementClass::Read, leader)
+                    .context(e.to_string()));
             }
-
-            // Not the leader - return error with leader info if known
-            let leader_info = metrics
-                .current_leader
-                .map(|id| format!(" (current leader: node {})", id))
-                .unwrap_or_default();
-            Err(anyhow::anyhow!(
-                "not the leader: this node is {}, cannot accept writes{}",
-                self.node_id,
-                leader_info
-            ))
-        } else {
-            Ok(())
         }
+        Ok(())
     }
 
     /// Check if Raft consensus is enabled.
@@ -504,158 +1041,1282 @@ impl Database {
         self.raft.is_some()
     }
 
-    /// Execute a SQL statement and return results.
+    /// Execute SQL and return results.
     ///
     /// This is the main entry point for SQL execution.
     /// Handles DDL (CREATE/DROP TABLE/INDEX) and delegates DML/queries to executor.
+    /// Several `;`-separated statements run as with [`Database::execute_script`],
+    /// and the last statement's result is returned.
     pub async fn execute(&self, sql: &str) -> Result<QueryResult> {
-        let statements = parse_sql(sql).map_err(anyhow::Error::from)?;
-
-        if statements.is_empty() {
-            return Ok(QueryResult::Empty);
-        }
-
-        if statements.len() > 1 {
-            anyhow::bail!("multiple statements not supported yet");
-        }
-
-        let stmt = statements.into_iter().next().unwrap();
-        self.execute_statement(stmt).await
+        self.execute_as(LOCAL_PRINCIPAL, sql).await
     }
 
-    /// Execute a single parsed statement.
-    async fn execute_statement(&self, stmt: Statement) -> Result<QueryResult> {
-        match stmt {
-            Statement::CreateTable {
-                name,
-                columns,
-                primary_key,
-            } => self.execute_create_table(name, columns, primary_key).await,
+    /// Execute SQL on behalf of `principal`.
+    ///
+    /// Behaves like [`Database::execute`], and records `principal` as the
+    /// statement's author if it touches an audited table (see [`audit`]).
+    pub async fn execute_as(&self, principal: &str, sql: &str) -> Result<QueryResult> {
+        let mut results = self.execute_script_as(principal, sql).await?;
+        Ok(results.pop().unwrap_or(QueryResult::Empty))
+    }
 
-            Statement::DropTable { name } => self.execute_drop_table(name).await,
+    /// Execute `;`-separated statements in order and return each one's
+    /// result.
+    ///
+    /// Every statement is parsed before any runs, so a syntax error anywhere
+    /// runs nothing. Execution stops at the first failing statement; the
+    /// statements before it keep their effects. With more than one statement,
+    /// errors name the statement that failed.
+    pub async fn execute_script(&self, sql: &str) -> Result<Vec<QueryResult>> {
+        self.execute_script_as(LOCAL_PRINCIPAL, sql).await
+    }
 
-            Statement::CreateIndex {
-                name,
-                table,
-                column,
-                index_type,
-            } => {
-                self.execute_create_index(name, table, column, index_type)
-                    .await
+    /// Execute statements on behalf of `principal`, as with
+    /// [`Database::execute_script`]. Each statement is audited with its own
+    /// text.
+    pub async fn execute_script_as(&self, principal: &str, sql: &str) -> Result<Vec<QueryResult>> {
+        let texts = parser::split_statements(sql);
+        let total = texts.len();
+        let numbered = |i: usize, e: anyhow::Error| {
+            if total == 1 {
+                return e;
             }
+            let message = format!("statement {} of {} failed: {}", i + 1, total, e);
+            e.context(message)
+        };
 
-            Statement::DropIndex { name } => self.execute_drop_index(name).await,
-
-            Statement::Explain { query, analyze } => self.execute_explain(*query, analyze).await,
+        let mut statements = Vec::with_capacity(total);
+        for (i, text) in texts.into_iter().enumerate() {
+            let parsed = parse_sql(text).map_err(|e| numbered(i, e.into()))?;
+            statements.extend(parsed.into_iter().map(|stmt| (i, text, stmt)));
+        }
 
-            other => self.execute_query_or_dml(other).await,
+        let mut results = Vec::with_capacity(statements.len());
+        for (i, text, stmt) in statements {
+            let result = self.execute_in_session(principal, text, stmt).await;
+            results.push(result.map_err(|e| numbered(i, e))?);
         }
+        Ok(results)
     }
 
-    /// Execute CREATE TABLE statement.
-    async fn execute_create_table(
-        &self,
-        name: String,
-        columns: Vec<parser::ColumnDef>,
-        primary_key: Option<Vec<String>>,
-    ) -> Result<QueryResult> {
-        // CPU-bound work: map columns and validate primary key
-        let catalog_columns: Vec<Column> = columns
-            .iter()
-            .map(|col| {
-                let ty = map_sql_type(&col.ty)?;
-                Ok(Column::new(col.name.clone(), ty))
-            })
-            .collect::<Result<Vec<_>>>()?;
+    /// Insert every row of `rows` into `table`, yielding progress after each
+    /// batch.
+    ///
+    /// Rows must have a value for every column in table order; NULL in an
+    /// auto-increment column takes the next value of its sequence. Rows are
+    /// inserted in batches of about [`bulk::BATCH_PAGES`] heap pages, each
+    /// running as one `INSERT` with its constraints, index maintenance, WAL
+    /// sync and audit record (see [`bulk`]). Nothing is inserted until the
+    /// returned stream is polled, and the next batch is only read from
+    /// `rows` after the previous one is written. A failing batch inserts
+    /// nothing, yields its error and ends the stream; earlier batches keep
+    /// their rows.
+    pub fn insert_rows<'a, S>(
+        &'a self,
+        table: &'a str,
+        rows: S,
+    ) -> impl Stream<Item = Result<InsertProgress>> + 'a
+    where
+        S: Stream<Item = common::Row> + 'a,
+    {
+        self.insert_rows_as(LOCAL_PRINCIPAL, table, rows)
+    }
 
-        let primary_key_ordinals = if let Some(pk_names) = primary_key {
-            let mut ordinals = Vec::new();
-            for pk_name in &pk_names {
-                let ordinal = columns
-                    .iter()
-                    .position(|col| col.name.eq_ignore_ascii_case(pk_name))
-                    .ok_or_else(|| {
-                        anyhow::anyhow!(
-                            "PRIMARY KEY column '{}' not found in table columns",
-                            pk_name
-                        )
-                    })? as u16;
-                ordinals.push(ordinal);
-            }
-            Some(ordinals)
-        } else {
-            None
+    /// Insert rows on behalf of `principal`, as with
+    /// [`Database::insert_rows`].
+    pub fn insert_rows_as<'a, S>(
+        &'a self,
+        principal: &'a str,
+        table: &'a str,
+        rows: S,
+    ) -> impl Stream<Item = Result<InsertProgress>> + 'a
+    where
+        S: Stream<Item = common::Row> + 'a,
+    {
+        let started = Instant::now();
+        let progress = InsertProgress {
+            inserted: 0,
+            batches: 0,
+            generated_ids: Vec::new(),
+            elapsed: Duration::ZERO,
         };
+        futures::stream::unfold(Some((Box::pin(rows), progress)), move |state| async move {
+            let (mut rows, mut progress) = state?;
+            let batch = bulk::next_batch(&mut rows).await;
+            if batch.is_empty() {
+                return None;
+            }
+            let (sql, stmt) = bulk::insert_statement(table, batch);
+            let (affected, generated_ids) =
+                match self.execute_in_session(principal, &sql, stmt).await {
+                    Ok(QueryResult::Count { affected, .. }) => (affected, Vec::new()),
+                    Ok(QueryResult::Inserted {
+                        affected,
+                        generated_ids,
+                        ..
+                    }) => (affected, generated_ids),
+                    Ok(other) => {
+                        let err = anyhow::anyhow!("unexpected INSERT result: {:?}", other);
+                        return Some((Err(err), None));
+                    }
+                    Err(e) => return Some((Err(e), None)),
+                };
+            progress.inserted += affected;
+            progress.batches += 1;
+            progress.generated_ids = generated_ids;
+            progress.elapsed = started.elapsed();
+            Some((Ok(progress.clone()), Some((rows, progress))))
+        })
+    }
 
-        // Clone Arc references for spawn_blocking
-        let catalog = self.catalog.clone();
-        let catalog_path = self.catalog_path.clone();
-        let wal = self.wal.clone();
+    /// Retry statements that fail with a transient error.
+    ///
+    /// Retries are off by default. See the [`retry`] module for which
+    /// failures are retried and why that is safe.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
 
-        tokio::task::spawn_blocking(move || {
-            // Acquire write lock on catalog (exclusive access)
-            let mut catalog_lock = catalog.blocking_write();
+    /// Cap the resources each session may use.
+    ///
+    /// Limits are off by default. See the [`sessions`] module for how each
+    /// limit is enforced.
+    pub fn with_resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.resource_limits = limits;
+        self
+    }
 
-            let table_id = catalog_lock
-                .create_table(&name, catalog_columns, primary_key_ordinals)
-                .map_err(anyhow::Error::from)?;
+    /// Let each sequential scan split the table's pages across up to
+    /// `workers` threads.
+    ///
+    /// Off (0) by default. Parallel scans return rows in no particular
+    /// order, so queries that need one should say `ORDER BY`.
+    pub fn with_max_parallel_workers(mut self, workers: usize) -> Self {
+        self.max_parallel_workers = workers;
+        self
+    }
 
-            // Persist catalog to disk (blocking I/O)
-            catalog_lock
-                .save(&catalog_path)
-                .map_err(anyhow::Error::from)?;
+    /// Store the rows of tables created without `ENGINE = <name>` in
+    /// `engine`.
+    ///
+    /// The heap by default. Temporary tables always use the memory engine.
+    pub fn with_default_engine(mut self, engine: EngineKind) -> Self {
+        self.default_engine = engine;
+        self
+    }
 
-            drop(catalog_lock); // Release catalog lock
+    /// Choose when tables are re-analyzed after writes, or pass `None` to
+    /// only analyze on `ANALYZE TABLE`.
+    ///
+    /// On by default with [`AutoAnalyze::default`]. See the [`statistics`]
+    /// module.
+    pub fn with_auto_analyze(mut self, policy: Option<AutoAnalyze>) -> Self {
+        self.auto_analyze = policy;
+        self
+    }
 
-            // Log WAL (exclusive access, blocking I/O)
-            let mut wal_lock = wal.blocking_lock();
-            wal_lock
-                .append(&WalRecord::CreateTable {
-                    name: name.clone(),
-                    table: table_id,
-                })
-                .and_then(|_| wal_lock.sync())
-                .map_err(anyhow::Error::from)?;
+    /// Vacuum tables in the background once enough of their rows were
+    /// updated or deleted, or pass `None` to only vacuum on `VACUUM`.
+    ///
+    /// Off by default. See the [`vacuum`] module.
+    pub fn with_auto_vacuum(mut self, policy: Option<AutoVacuum>) -> Self {
+        self.auto_vacuum = policy;
+        self
+    }
 
-            Ok(QueryResult::Empty)
-        })
-        .await?
+    /// Let at most `max` statements execute at once across all sessions;
+    /// the rest wait their turn in arrival order.
+    ///
+    /// Unlimited by default. See the [`admission`] module.
+    pub fn with_max_executing_statements(mut self, max: usize) -> Self {
+        self.admission = Arc::new(AdmissionQueue::new(Some(max)));
+        self
     }
 
-    /// Execute DROP TABLE statement.
-    async fn execute_drop_table(&self, name: String) -> Result<QueryResult> {
-        let catalog = self.catalog.clone();
-        let catalog_path = self.catalog_path.clone();
-        let data_dir = self.data_dir.clone();
-        let wal = self.wal.clone();
+    /// Statements `principal` currently has in flight.
+    pub fn active_statements(&self, principal: &str) -> usize {
+        self.sessions.active(principal)
+    }
 
-        tokio::task::spawn_blocking(move || {
-            // Acquire write lock on catalog
-            let mut catalog_lock = catalog.blocking_write();
+    /// How many statements have been admitted and how long they queued.
+    pub fn admission_stats(&self) -> AdmissionStats {
+        self.admission.stats()
+    }
 
-            let table_id = catalog_lock.table(&name).map_err(anyhow::Error::from)?.id;
-            catalog_lock
-                .drop_table(&name)
-                .map_err(anyhow::Error::from)?;
+    /// Run `principal`'s statements at `priority` from now on, as
+    /// `SET statement_priority` does.
+    pub fn set_priority(&self, principal: &str, priority: Priority) {
+        self.sessions.set_priority(principal, priority);
+    }
 
-            // Persist catalog
-            catalog_lock
-                .save(&catalog_path)
-                .map_err(anyhow::Error::from)?;
+    /// Priority `principal`'s statements run at.
+    pub fn priority(&self, principal: &str) -> Priority {
+        self.sessions.priority(principal)
+    }
 
-            drop(catalog_lock);
+    /// Forget the settings of `principal`'s session, e.g. once its client
+    /// disconnects.
+    pub fn end_session(&self, principal: &str) {
+        self.sessions.end(principal);
+    }
 
-            // Remove heap file (blocking I/O)
-            let path = data_dir.join(format!("{name}.heap"));
-            if path.exists() {
-                fs::remove_file(&path)
-                    .with_context(|| format!("failed to remove heap file {}", path.display()))?;
-            }
+    /// Execute an optimistic-locking UPDATE.
+    ///
+    /// The statement must target a table created with
+    /// `WITH (row_version = true)` and filter on the implicit `_version`
+    /// column, e.g. `UPDATE accounts SET balance = 5 WHERE id = 1 AND _version = 3`.
+    /// A successful update advances `_version`. Returns the number of updated
+    /// rows, or a [`VersionConflict`] error if no row matched.
+    pub async fn execute_versioned_update(&self, sql: &str) -> Result<u64> {
+        let mut statements = parse_sql(sql).map_err(anyhow::Error::from)?;
+        if statements.len() != 1 {
+            anyhow::bail!("versioned update expects exactly one statement");
+        }
+        let stmt = statements.remove(0);
 
-            // Log WAL
-            let mut wal_lock = wal.blocking_lock();
-            wal_lock
-                .append(&WalRecord::DropTable { table: table_id })
+        let Statement::Update {
+            table, selection, ..
+        } = &stmt
+        else {
+            anyhow::bail!("versioned update expects an UPDATE statement");
+        };
+        let table = table.clone();
+        if self
+            .catalog
+            .read()
+            .await
+            .table(&table)
+            .map_err(anyhow::Error::from)?
+            .row_version_column()
+            .is_none()
+        {
+            anyhow::bail!(
+                "table '{}' has no row version; create it WITH (row_version = true)",
+                table
+            );
+        }
+        if !selection
+            .as_ref()
+            .is_some_and(|e| references_column(e, ROW_VERSION_COLUMN))
+        {
+            anyhow::bail!(
+                "versioned update must filter on {} in its WHERE clause",
+                ROW_VERSION_COLUMN
+            );
+        }
+
+        match self.execute_in_session(LOCAL_PRINCIPAL, sql, stmt).await? {
+            QueryResult::Count { affected: 0, .. } => {
+                Err(anyhow::Error::new(VersionConflict { table }))
+            }
+            QueryResult::Count { affected, .. } => Ok(affected),
+            other => anyhow::bail!("unexpected UPDATE result: {:?}", other),
+        }
+    }
+
+    /// Execute a statement on behalf of a session and record it in the audit
+    /// log if it touches an audited table.
+    ///
+    /// Statements over the session's concurrency limit are rejected before
+    /// they run (see [`sessions`]); the rest then wait for admission at the
+    /// session's priority (see [`admission`]), except `ANALYZE` and `VACUUM`,
+    /// which are maintenance and run at background priority whatever the
+    /// session's. `SET statement_priority` sets
+    /// that priority without waiting. Audited tables are resolved before
+    /// execution, so dropping an audited table is recorded. If the record
+    /// cannot be written the statement's effects stand, but the caller gets
+    /// the audit error instead of the result.
+    ///
+    /// The tables it writes are recorded as written by a transaction that
+    /// commits at once, so running [`Transaction`]s see the conflict.
+    async fn execute_in_session(
+        &self,
+        principal: &str,
+        sql: &str,
+        stmt: Statement,
+    ) -> Result<QueryResult> {
+        match (
+            executed_dml(&stmt).and_then(modification),
+            StatementClass::of(&stmt),
+        ) {
+            (Some((table, _)), _) => self.conflicts.record_write(table),
+            (_, StatementClass::Ddl) => {
+                for table in stmt.tables() {
+                    self.conflicts.record_write(table);
+                }
+            }
+            _ => {}
+        }
+        self.run_in_session(principal, sql, stmt).await
+    }
+
+    /// Execute a statement as [`Database::execute_in_session`] does, without
+    /// recording its writes as conflicts.
+    async fn run_in_session(
+        &self,
+        principal: &str,
+        sql: &str,
+        stmt: Statement,
+    ) -> Result<QueryResult> {
+        if let Statement::SetPriority { priority } = stmt {
+            self.sessions.set_priority(principal, priority);
+            return Ok(QueryResult::Empty);
+        }
+        let _slot = self.sessions.enter(principal, &self.resource_limits)?;
+        let priority = match stmt {
+            Statement::Analyze { .. } | Statement::Vacuum { .. } => Priority::Background,
+            _ => self.sessions.priority(principal),
+        };
+        let _admission = self.admission.admit(priority).await;
+        let writes = matches!(
+            StatementClass::of(&stmt),
+            StatementClass::Write | StatementClass::Ddl
+        );
+        if writes && self.disk_full.is_read_only() {
+            let guard = self.disk_full.clone();
+            tokio::task::spawn_blocking(move || guard.check_writable()).await??;
+        }
+        let tables: Vec<String> = stmt.tables().into_iter().map(str::to_string).collect();
+        let audited = matches!(stmt, Statement::CreateTable { audit: true, .. }) || {
+            let catalog = self.catalog.read().await;
+            tables
+                .iter()
+                .any(|name| catalog.table(name).is_ok_and(|table| table.audit))
+        };
+
+        let written = modification(&stmt).map(|(table, kind)| (table.to_string(), kind));
+
+        let started = Instant::now();
+        let result = STATEMENT_SESSION
+            .scope(
+                principal.to_string(),
+                STATEMENT_PRIORITY.scope(priority, self.execute_with_retry(stmt)),
+            )
+            .await;
+        self.settings
+            .record_statement(principal, sql, started.elapsed());
+        self.disk_full.observe(&result);
+        if let (
+            Some((table, kind)),
+            Ok(QueryResult::Count { affected, .. } | QueryResult::Inserted { affected, .. }),
+        ) = (&written, &result)
+        {
+            self.record_modifications(table, *kind, *affected).await;
+        }
+        if audited {
+            let record = AuditRecord::new(principal, sql, tables, &result);
+            self.audit_log.append(&record)?;
+        }
+        result
+    }
+
+    /// Count rows written to `table`, saving the counters every
+    /// [`ACTIVITY_SAVE_ROWS`] rows, and re-analyze or vacuum the table in
+    /// the background once its churn passes the [`AutoAnalyze`] or
+    /// [`AutoVacuum`] threshold. Background work waits for admission at
+    /// background priority, like any other statement.
+    async fn record_modifications(&self, table: &str, kind: Modification, rows: u64) {
+        let catalog_lock = self.catalog.read().await;
+        let Ok(meta) = catalog_lock.table(table) else {
+            return;
+        };
+        meta.record_modifications(kind, rows);
+        let due = self.auto_analyze.is_some_and(|policy| policy.is_due(meta));
+        let vacuum_due = self.auto_vacuum.is_some_and(|policy| policy.is_due(meta));
+        let table_id = meta.id;
+        let table = meta.name.clone();
+        drop(catalog_lock);
+
+        let unsaved = self.unsaved_writes.fetch_add(rows, Ordering::Relaxed) + rows;
+        if unsaved >= ACTIVITY_SAVE_ROWS {
+            self.save_activity().await;
+        }
+        if vacuum_due {
+            self.spawn_vacuum(table_id, table.clone());
+        }
+
+        let mut analyzing = self.analyzing.lock().unwrap_or_else(|e| e.into_inner());
+        if !due || !analyzing.insert(table_id) {
+            return;
+        }
+        drop(analyzing);
+
+        let catalog = self.catalog.clone();
+        let pager = self.pager.clone();
+        let wal = self.wal.clone();
+        let data_dir = self.data_dir.clone();
+        let catalog_path = self.catalog_path.clone();
+        let engines = self.engines.clone();
+        let analyzing = self.analyzing.clone();
+        let admission = self.admission.clone();
+        tokio::spawn(async move {
+            let _admission = admission.admit(Priority::Background).await;
+            // A failure (e.g. the table was dropped) is not reported; the
+            // table is due again on its next write.
+            let _ = tokio::task::spawn_blocking(move || {
+                analyze_table(
+                    &catalog,
+                    &pager,
+                    &wal,
+                    &engines,
+                    &data_dir,
+                    &catalog_path,
+                    &table,
+                )
+            })
+            .await;
+            analyzing
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&table_id);
+        });
+    }
+
+    /// Vacuum `table` on a blocking thread, once admitted at background
+    /// priority, unless it is already being vacuumed.
+    fn spawn_vacuum(&self, table_id: TableId, table: String) {
+        let mut vacuuming = self.vacuuming.lock().unwrap_or_else(|e| e.into_inner());
+        if !vacuuming.insert(table_id) {
+            return;
+        }
+        drop(vacuuming);
+
+        let catalog = self.catalog.clone();
+        let pager = self.pager.clone();
+        let wal = self.wal.clone();
+        let data_dir = self.data_dir.clone();
+        let catalog_path = self.catalog_path.clone();
+        let engines = self.engines.clone();
+        let vacuuming = self.vacuuming.clone();
+        let admission = self.admission.clone();
+        tokio::spawn(async move {
+            let _admission = admission.admit(Priority::Background).await;
+            // As with re-analysis, a failure leaves the table due on its next
+            // write
+            let _ = tokio::task::spawn_blocking(move || {
+                vacuum::vacuum_table(
+                    &catalog,
+                    &pager,
+                    &wal,
+                    &engines,
+                    &data_dir,
+                    &catalog_path,
+                    &table,
+                )
+            })
+            .await;
+            vacuuming
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&table_id);
+        });
+    }
+
+    /// Save the catalog so that its tables' activity counters survive a
+    /// restart. The statements were committed, so a failed save is not
+    /// reported; the rows are counted towards the next attempt.
+    async fn save_activity(&self) {
+        let unsaved = self.unsaved_writes.swap(0, Ordering::Relaxed);
+        if unsaved == 0 {
+            return;
+        }
+        let catalog = self.catalog.clone();
+        let catalog_path = self.catalog_path.clone();
+        let saved =
+            tokio::task::spawn_blocking(move || catalog.blocking_read().save(&catalog_path)).await;
+        if !matches!(saved, Ok(Ok(()))) {
+            self.unsaved_writes.fetch_add(unsaved, Ordering::Relaxed);
+        }
+    }
+
+    /// Turn audit logging on or off for an existing table.
+    pub async fn set_table_audit(&self, table: &str, enabled: bool) -> Result<()> {
+        let catalog = self.catalog.clone();
+        let catalog_path = self.catalog_path.clone();
+        let table = table.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let mut catalog_lock = catalog.blocking_write();
+            catalog_lock
+                .table_mut(&table)
+                .map_err(anyhow::Error::from)?
+                .audit = enabled;
+            catalog_lock
+                .save(&catalog_path)
+                .map_err(anyhow::Error::from)
+        })
+        .await??;
+
+        self.advance_catalog_epoch().await
+    }
+
+    /// The audit log for this database's data directory.
+    pub fn audit_log(&self) -> &AuditLog {
+        &self.audit_log
+    }
+
+    /// Execute a statement, retrying transient failures if a
+    /// [`RetryPolicy`] is configured.
+    async fn execute_with_retry(&self, stmt: Statement) -> Result<QueryResult> {
+        match self.retry_policy {
+            Some(policy) => policy.run(|| self.execute_statement(stmt.clone())).await,
+            None => self.execute_statement(stmt).await,
+        }
+    }
+
+    /// Execute a single parsed statement.
+    ///
+    /// The statement is first routed (see [`routing`]): writes are replicated
+    /// through Raft, and statements this node cannot serve are rejected with a
+    /// [`NotLeaderError`].
+    async fn execute_statement(&self, stmt: Statement) -> Result<QueryResult> {
+        if let Statement::SetTransaction { isolation, session } = stmt {
+            self.sessions
+                .set_isolation(&statement_session(), isolation, session);
+            return Ok(QueryResult::Empty);
+        }
+        if let Statement::SetRandomSeed { seed } = stmt {
+            self.sessions.set_random_seed(&statement_session(), seed);
+            return Ok(QueryResult::Empty);
+        }
+        if let Statement::SetGlobal { name, value } = stmt {
+            self.set_global(&name, value.as_deref()).await?;
+            return Ok(QueryResult::Empty);
+        }
+        // Statements run serially, so every level is satisfied; beginning the
+        // statement's transaction only consumes a one-shot SET TRANSACTION.
+        self.sessions.begin(&statement_session());
+
+        let class = StatementClass::of(&stmt);
+        let route = route(class, self.read_consistency, &self.cluster_view());
+        if let Route::Redirect { leader } = route {
+            return Err(self.not_leader_error(class, leader));
+        }
+        match route {
+            Route::Local | Route::Redirect { .. } => {}
+            // EXPLAIN ANALYZE and PROFILE refuse DML with Raft themselves
+            Route::Replicate
+                if matches!(stmt, Statement::Explain { .. } | Statement::Profile { .. }) => {}
+            Route::LinearizableRead => self.ensure_linearizable().await?,
+            Route::Replicate => {
+                let stmt = self.insert_query_rows(stmt).await?;
+                return self.execute_dml_via_raft(stmt).await;
+            }
+        }
+
+        let result = match stmt {
+            Statement::CreateTable {
+                name,
+                columns,
+                primary_key,
+                row_version,
+                audit,
+                fillfactor,
+                compression,
+                engine,
+                partition_by,
+                temporary,
+            } => {
+                self.execute_create_table(
+                    name,
+                    columns,
+                    primary_key,
+                    row_version,
+                    audit,
+                    fillfactor,
+                    compression,
+                    engine,
+                    partition_by,
+                    temporary,
+                )
+                .await
+            }
+
+            Statement::CreateTableAs {
+                name,
+                temporary,
+                query,
+            } => self.execute_create_table_as(name, temporary, *query).await,
+
+            Statement::DropTable { name } => self.execute_drop_table(name).await,
+
+            Statement::AlterTable { name, action } => self.execute_alter_table(name, action).await,
+
+            Statement::CreateIndex {
+                name,
+                table,
+                column,
+                index_type,
+                unique,
+            } => {
+                self.execute_create_index(name, table, column, index_type, unique)
+                    .await
+            }
+
+            Statement::DropIndex { name } => self.execute_drop_index(name).await,
+
+            Statement::CreateView { name, query } => self.execute_create_view(name, query).await,
+
+            Statement::DropView { name } => self.execute_drop_view(name).await,
+
+            Statement::Analyze { table } => self.execute_analyze(table).await,
+
+            Statement::Vacuum { table } => self.execute_vacuum(table).await,
+
+            Statement::AdminGc => self.execute_admin_gc().await,
+
+            Statement::ShowTableStats { table } => self.execute_show_table_stats(table).await,
+
+            Statement::Explain { query, analyze } => self.execute_explain(*query, analyze).await,
+
+            Statement::Profile { query } => self.execute_profile(*query).await,
+
+            Statement::CopyTo {
+                query,
+                path,
+                format,
+            } => self.execute_copy_to(*query, path, format).await,
+
+            other => self.execute_query_or_dml(other).await,
+        };
+
+        // Failed DDL may still have touched the catalog and files, so advance
+        // the epoch and record them either way.
+        if class == StatementClass::Ddl {
+            let advanced = self.advance_catalog_epoch().await;
+            let recorded = self.record_manifest().await;
+            return result.and_then(|result| advanced.and(recorded).map(|()| result));
+        }
+        result
+    }
+
+    /// Run the query of an `INSERT ... SELECT` and return an `INSERT` of its
+    /// rows as VALUES. Other statements are returned as they are.
+    ///
+    /// Used for writes replicated through Raft: the query runs once, on this
+    /// node, so Raft replicates the values it produced here, those of
+    /// `RANDOM()` included. Locally the query runs with the insert instead
+    /// (see [`insert_query_values`]).
+    async fn insert_query_rows(&self, stmt: Statement) -> Result<Statement> {
+        let Statement::Insert {
+            table,
+            columns,
+            query: Some(query),
+            ..
+        } = stmt
+        else {
+            return Ok(stmt);
+        };
+        let QueryResult::Rows { rows, .. } = self.execute_query_or_dml(*query).await? else {
+            anyhow::bail!("INSERT ... SELECT needs a query that returns rows");
+        };
+        Ok(Statement::Insert {
+            table,
+            columns,
+            rows: rows
+                .into_iter()
+                .map(|row| row.values.into_iter().map(expr::Expr::Literal).collect())
+                .collect(),
+            query: None,
+        })
+    }
+
+    /// Rewrite the data directory manifest after files were added or removed.
+    async fn record_manifest(&self) -> Result<()> {
+        let catalog = self.catalog.clone();
+        let engines = self.engines.clone();
+        let data_dir = self.data_dir.clone();
+        let catalog_path = self.catalog_path.clone();
+        let wal_path = self.wal_path.clone();
+        let key = self.encryption.clone();
+
+        tokio::task::spawn_blocking(move || {
+            // Hold the catalog so ADMIN GC cannot collect the manifest's temp file
+            let catalog_lock = catalog.blocking_read();
+            let files = manifest::expected_files(
+                &catalog_lock,
+                &engines,
+                &data_dir,
+                &catalog_path,
+                &wal_path,
+            );
+            Manifest::capture(&data_dir, &files, false)?
+                .save(&data_dir.join(MANIFEST_FILE), key.as_ref())
+        })
+        .await?
+    }
+
+    /// Current catalog epoch; it advances on every schema change.
+    pub fn catalog_epoch(&self) -> u64 {
+        *self.catalog_epoch.borrow()
+    }
+
+    /// Subscribe to catalog changes.
+    ///
+    /// The receiver is notified with the new [`catalog_epoch`](Self::catalog_epoch)
+    /// after each DDL statement commits on this node, and on a Raft follower
+    /// when it applies the epoch the leader replicated after its own DDL.
+    /// Anything cached against the catalog (planned statements, schemas held
+    /// by sessions) should be tagged with the epoch it was built at and
+    /// dropped when a newer one arrives. Planning itself always reads the
+    /// catalog under its lock, so statements never observe a half-applied
+    /// schema change.
+    pub fn watch_catalog(&self) -> watch::Receiver<u64> {
+        self.catalog_epoch.subscribe()
+    }
+
+    /// Advance the catalog epoch once for a schema change, save it, and
+    /// publish it. The leader of a Raft cluster also replicates the new
+    /// epoch, so followers drop what they cached against the old schema.
+    async fn advance_catalog_epoch(&self) -> Result<()> {
+        let catalog = self.catalog.clone();
+        let catalog_path = self.catalog_path.clone();
+        let epoch = tokio::task::spawn_blocking(move || {
+            let mut catalog_lock = catalog.blocking_write();
+            let epoch = catalog_lock.bump_epoch();
+            catalog_lock
+                .save(&catalog_path)
+                .map(|()| epoch)
+                .map_err(anyhow::Error::from)
+        })
+        .await??;
+        self.publish_catalog_epoch().await;
+
+        if self.raft.is_some() && self.is_leader() {
+            self.raft_write(Command::AdvanceCatalogEpoch { epoch })
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn publish_catalog_epoch(&self) {
+        let epoch = self.catalog.read().await.epoch();
+        self.catalog_epoch.send_if_modified(|current| {
+            let changed = *current != epoch;
+            *current = epoch;
+            changed
+        });
+    }
+
+    /// Execute CREATE TABLE statement.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_create_table(
+        &self,
+        name: String,
+        columns: Vec<parser::ColumnDef>,
+        primary_key: Option<Vec<String>>,
+        row_version: bool,
+        audit: bool,
+        fillfactor: Option<u8>,
+        compression: Compression,
+        engine: Option<String>,
+        partition_by: Option<parser::PartitionBy>,
+        temporary: bool,
+    ) -> Result<QueryResult> {
+        let engine = match engine {
+            Some(name) => EngineKind::from_name(&name).map_err(anyhow::Error::from)?,
+            // Temporary tables never outlive the database, so their rows
+            // need not either
+            None if temporary => EngineKind::Memory,
+            None => self.default_engine,
+        };
+        self.engines.engine(engine).map_err(anyhow::Error::from)?;
+        if temporary && engine != EngineKind::Memory {
+            anyhow::bail!("temporary tables can only use the memory engine");
+        }
+        if temporary && partition_by.is_some() {
+            anyhow::bail!("temporary tables cannot be partitioned");
+        }
+
+        // CPU-bound work: map columns and validate primary key
+        let mut catalog_columns: Vec<Column> = columns
+            .iter()
+            .map(|col| map_column_def(&name, col))
+            .collect::<Result<Vec<_>>>()?;
+        if row_version {
+            catalog_columns.push(Column::new(ROW_VERSION_COLUMN, types::SqlType::Int));
+        }
+
+        let primary_key_ordinals = if let Some(pk_names) = primary_key {
+            let mut ordinals = Vec::new();
+            for pk_name in &pk_names {
+                let ordinal = columns
+                    .iter()
+                    .position(|col| col.name.eq_ignore_ascii_case(pk_name))
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "PRIMARY KEY column '{}' not found in table columns",
+                            pk_name
+                        )
+                    })? as u16;
+                ordinals.push(ordinal);
+            }
+            Some(ordinals)
+        } else {
+            None
+        };
+
+        // PRIMARY KEY implies NOT NULL
+        for &ordinal in primary_key_ordinals.iter().flatten() {
+            catalog_columns[ordinal as usize].not_null = true;
+        }
+
+        let partitioning = partition_by.map(|partition_by| {
+            let method = match partition_by.method {
+                parser::PartitionMethod::Range => PartitionMethod::Range,
+                parser::PartitionMethod::List => PartitionMethod::List,
+            };
+            let partitions: Vec<(String, PartitionBound)> = partition_by
+                .partitions
+                .into_iter()
+                .map(|partition| {
+                    let bound = match partition.values {
+                        parser::PartitionValues::LessThan(upper) => PartitionBound::LessThan(upper),
+                        parser::PartitionValues::In(values) => PartitionBound::In(values),
+                    };
+                    (partition.name, bound)
+                })
+                .collect();
+            (method, partition_by.column, partitions)
+        });
+
+        // Clone Arc references for spawn_blocking
+        let catalog = self.catalog.clone();
+        let catalog_path = self.catalog_path.clone();
+        let data_dir = self.data_dir.clone();
+        let wal = self.wal.clone();
+        let engines = self.engines.clone();
+
+        tokio::task::spawn_blocking(move || {
+            // Acquire write lock on catalog (exclusive access)
+            let mut catalog_lock = catalog.blocking_write();
+
+            let table_id = catalog_lock
+                .create_table(&name, catalog_columns, primary_key_ordinals.clone())
+                .map_err(anyhow::Error::from)?;
+            let table = catalog_lock.table_mut(&name).map_err(anyhow::Error::from)?;
+            table.audit = audit;
+            table.fillfactor = fillfactor;
+            table.compression = compression;
+            table.engine = engine;
+            table.temporary = temporary;
+            if let Some((method, column, partitions)) = partitioning {
+                if let Err(e) = catalog_lock.partition_table(&name, method, &column, partitions) {
+                    // Leave no table behind whose partitions could not be declared
+                    let _ = catalog_lock.drop_table(&name);
+                    return Err(e.into());
+                }
+            }
+
+            // Start the primary key index empty, replacing any left over
+            // from an earlier table of the same name
+            let table = catalog_lock.table(&name).map_err(anyhow::Error::from)?;
+            if let Some(pk_columns) = primary_key_ordinals {
+                if engines.is_durable(table) {
+                    let path = data_dir.join(format!("{name}.pk_idx"));
+                    PrimaryKeyIndex::create(&path, pk_columns, catalog_lock.encryption_key())
+                        .with_context(|| format!("failed to create {}", path.display()))?;
+                }
+            }
+
+            // Persist catalog to disk (blocking I/O)
+            catalog_lock
+                .save(&catalog_path)
+                .map_err(anyhow::Error::from)?;
+
+            drop(catalog_lock); // Release catalog lock
+            if temporary {
+                // Nothing of a temporary table is recovered, so nothing is logged
+                return Ok(QueryResult::Empty);
+            }
+
+            // Log WAL (exclusive access, blocking I/O)
+            let mut wal_lock = wal.blocking_lock();
+            wal_lock
+                .append(&WalRecord::CreateTable {
+                    name: name.clone(),
+                    table: table_id,
+                })
+                .and_then(|_| wal_lock.sync())
+                .map_err(anyhow::Error::from)?;
+
+            Ok(QueryResult::Empty)
+        })
+        .await?
+    }
+
+    /// Execute CREATE [TEMPORARY] TABLE ... AS: run the query, create a table
+    /// with its output columns and insert its rows. If the rows cannot be
+    /// inserted, the table is dropped again.
+    async fn execute_create_table_as(
+        &self,
+        name: String,
+        temporary: bool,
+        query: Statement,
+    ) -> Result<QueryResult> {
+        let QueryResult::Rows { schema, rows, .. } = self.execute_query_or_dml(query).await? else {
+            anyhow::bail!("CREATE TABLE ... AS needs a query that returns rows");
+        };
+        let columns = schema
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                // Qualified names such as `u.id` name the column `id`
+                let name = name.rsplit('.').next().unwrap_or(name);
+                let ty =
+                    created_column_type(name, rows.iter().filter_map(|row| row.values.get(i)))?;
+                Ok(parser::ColumnDef {
+                    name: name.to_string(),
+                    ty: ty.to_string(),
+                    default: None,
+                    not_null: false,
+                    auto_increment: false,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.execute_create_table(
+            name.clone(),
+            columns,
+            None,
+            false,
+            false,
+            None,
+            Compression::None,
+            None,
+            None,
+            temporary,
+        )
+        .await?;
+
+        let catalog = self.catalog.clone();
+        let pager = self.pager.clone();
+        let wal = self.wal.clone();
+        let data_dir = self.data_dir.clone();
+        let limits = self.resource_limits;
+        let priority = statement_priority();
+        let engines = self.engines.clone();
+        let table = name.clone();
+        let inserted = tokio::task::spawn_blocking(move || {
+            let catalog_lock = catalog.blocking_read();
+            let table_id = catalog_lock.table(&table).map_err(anyhow::Error::from)?.id;
+            let plan = PhysicalPlan::Insert {
+                table_id,
+                rows: rows
+                    .into_iter()
+                    .map(|row| row.values.into_iter().map(ResolvedExpr::Literal).collect())
+                    .collect(),
+            };
+
+            let mut pager = pager.clone();
+            let mut wal_lock = wal.blocking_lock();
+            let mut ctx = ExecutionContext::new(
+                &catalog_lock,
+                &mut pager,
+                wal_lock.deref_mut(),
+                data_dir.as_ref().clone(),
+            )
+            .with_resource_limits(limits)
+            .with_priority(priority)
+            .with_engines(engines);
+            execute_dml(plan, &mut ctx).map_err(anyhow::Error::from)
+        })
+        .await?;
+
+        match inserted {
+            Ok(affected) => Ok(QueryResult::Count {
+                affected,
+                info: None,
+            }),
+            Err(e) => {
+                let _ = self.execute_drop_table(name).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Execute ANALYZE TABLE statement.
+    async fn execute_analyze(&self, table: String) -> Result<QueryResult> {
+        let catalog = self.catalog.clone();
+        let pager = self.pager.clone();
+        let wal = self.wal.clone();
+        let engines = self.engines.clone();
+        let data_dir = self.data_dir.clone();
+        let catalog_path = self.catalog_path.clone();
+
+        tokio::task::spawn_blocking(move || {
+            analyze_table(
+                &catalog,
+                &pager,
+                &wal,
+                &engines,
+                &data_dir,
+                &catalog_path,
+                &table,
+            )?;
+            Ok(QueryResult::Empty)
+        })
+        .await?
+    }
+
+    /// Execute VACUUM statement.
+    ///
+    /// Vacuums the named table, or every table one at a time, and returns
+    /// one row per table with the rows moved and the pages holding rows
+    /// before and after (see [`vacuum`]).
+    async fn execute_vacuum(&self, table: Option<String>) -> Result<QueryResult> {
+        let tables = match table {
+            Some(table) => vec![table],
+            None => {
+                let catalog = self.catalog.read().await;
+                let mut names: Vec<String> =
+                    catalog.tables().map(|table| table.name.clone()).collect();
+                names.sort();
+                names
+            }
+        };
+
+        let count = |n: u64| Value::Int(i64::try_from(n).unwrap_or(i64::MAX));
+        let mut rows = Vec::with_capacity(tables.len());
+        for table in tables {
+            let catalog = self.catalog.clone();
+            let pager = self.pager.clone();
+            let wal = self.wal.clone();
+            let engines = self.engines.clone();
+            let data_dir = self.data_dir.clone();
+            let catalog_path = self.catalog_path.clone();
+            let name = table.clone();
+            let outcome = tokio::task::spawn_blocking(move || {
+                vacuum::vacuum_table(
+                    &catalog,
+                    &pager,
+                    &wal,
+                    &engines,
+                    &data_dir,
+                    &catalog_path,
+                    &name,
+                )
+            })
+            .await??;
+            rows.push(common::Row::new(vec![
+                Value::Text(table),
+                count(outcome.rows_moved),
+                count(outcome.pages_before),
+                count(outcome.pages_after),
+            ]));
+        }
+        Ok(QueryResult::Rows {
+            schema: vec![
+                "table_name".into(),
+                "rows_moved".into(),
+                "pages_before".into(),
+                "pages_after".into(),
+            ],
+            rows,
+            info: None,
+        })
+    }
+
+    /// Execute SHOW TABLE STATS statement.
+    ///
+    /// Returns one row per statistic gathered by the table's last ANALYZE.
+    /// Row sizes and page fill are listed only for tables that keep their
+    /// rows in pages.
+    async fn execute_show_table_stats(&self, table: String) -> Result<QueryResult> {
+        let catalog = self.catalog.read().await;
+        let meta = catalog.table(&table).map_err(anyhow::Error::from)?;
+        let Some(statistics) = &meta.statistics else {
+            anyhow::bail!("table '{table}' has not been analyzed; run ANALYZE TABLE {table}");
+        };
+
+        let count = |n: u64| Value::Int(n as i64);
+        let mut stats = vec![
+            ("row_count", count(statistics.row_count)),
+            ("page_count", count(statistics.page_count)),
+        ];
+        if let Some(storage) = &statistics.storage {
+            let histogram = storage
+                .row_size_histogram
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            stats.extend([
+                ("fill_factor", Value::Float(storage.fill_factor())),
+                ("avg_row_bytes", Value::Float(storage.avg_row_bytes())),
+                ("min_row_bytes", count(storage.min_row_bytes)),
+                ("max_row_bytes", count(storage.max_row_bytes)),
+                ("row_size_histogram", Value::Text(format!("[{histogram}]"))),
+            ]);
+        }
+        Ok(QueryResult::Rows {
+            schema: vec!["statistic".into(), "value".into()],
+            rows: stats
+                .into_iter()
+                .map(|(name, value)| common::Row::new(vec![Value::Text(name.into()), value]))
+                .collect(),
+            info: None,
+        })
+    }
+
+    /// Execute ADMIN GC statement.
+    ///
+    /// Returns one row per collected file with the file name and whether it
+    /// was removed or quarantined (see [`gc`]).
+    async fn execute_admin_gc(&self) -> Result<QueryResult> {
+        let catalog = self.catalog.clone();
+        let data_dir = self.data_dir.clone();
+
+        tokio::task::spawn_blocking(move || {
+            // List the directory first, then hold the write lock only while
+            // deciding, so no table gains files in the meantime
+            let scan = gc::scan(&data_dir)?;
+            let catalog_lock = catalog.blocking_write();
+            let collected = gc::collect_garbage(&catalog_lock, &data_dir, scan)?;
+            let rows = collected
+                .into_iter()
+                .map(|c| {
+                    common::Row::new(vec![Value::Text(c.file), Value::Text(c.action.to_string())])
+                })
+                .collect();
+            Ok(QueryResult::Rows {
+                schema: vec!["file".into(), "action".into()],
+                rows,
+                info: None,
+            })
+        })
+        .await?
+    }
+
+    /// Execute ALTER TABLE statement.
+    ///
+    /// ADD COLUMN and RENAME COLUMN only change the catalog: rows written
+    /// before a column was added are padded with its default when read. DROP
+    /// COLUMN rewrites every stored row without the dropped value, and the
+    /// persisted primary key index is discarded so it is rebuilt against the
+    /// shifted key ordinals.
+    async fn execute_alter_table(
+        &self,
+        name: String,
+        action: parser::AlterTableAction,
+    ) -> Result<QueryResult> {
+        let catalog = self.catalog.clone();
+        let catalog_path = self.catalog_path.clone();
+        let data_dir = self.data_dir.clone();
+        let engines = self.engines.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut catalog_lock = catalog.blocking_write();
+            let key = catalog_lock.encryption_key().cloned();
+            let table = catalog_lock.table_mut(&name).map_err(anyhow::Error::from)?;
+
+            match action {
+                parser::AlterTableAction::AddColumn(col) => {
+                    if col.auto_increment {
+                        anyhow::bail!(
+                            "cannot add auto-increment column '{}'; declare it in CREATE TABLE",
+                            col.name
+                        );
+                    }
+                    if col.not_null && col.default.is_none() {
+                        anyhow::bail!(
+                            "cannot add NOT NULL column '{}' without a DEFAULT",
+                            col.name
+                        );
+                    }
+                    table
+                        .add_column(map_column_def(&name, &col)?)
+                        .map_err(anyhow::Error::from)?;
+                }
+                parser::AlterTableAction::RenameColumn { old, new } => {
+                    ensure_not_reserved(&old)?;
+                    ensure_not_reserved(&new)?;
+                    table
+                        .rename_column(&old, &new)
+                        .map_err(anyhow::Error::from)?;
+                }
+                parser::AlterTableAction::DropColumn { name: column } => {
+                    ensure_not_reserved(&column)?;
+                    let sequence = table
+                        .schema
+                        .column_index(&column)
+                        .and_then(|ordinal| table.columns()[ordinal as usize].sequence.clone());
+                    // Rows are read with the layout they were stored with,
+                    // and are rewritten without it
+                    let stored = table.clone();
+                    let ordinal = table.drop_column(&column).map_err(anyhow::Error::from)? as usize;
+                    if let Some(sequence) = sequence {
+                        catalog_lock
+                            .drop_sequence(&sequence)
+                            .map_err(anyhow::Error::from)?;
+                    }
+                    let mut rows = engines
+                        .open(&data_dir, &stored, key.as_ref())
+                        .map_err(|e| anyhow::anyhow!("failed to open table storage: {}", e))?;
+                    drop_stored_column(rows.as_mut(), ordinal)?;
+                    let pk_index_path = data_dir.join(format!("{name}.pk_idx"));
+                    if pk_index_path.exists() {
+                        fs::remove_file(&pk_index_path).with_context(|| {
+                            format!("failed to remove {}", pk_index_path.display())
+                        })?;
+                    }
+                }
+            }
+
+            catalog_lock
+                .save(&catalog_path)
+                .map_err(anyhow::Error::from)?;
+
+            Ok(QueryResult::Empty)
+        })
+        .await?
+    }
+
+    /// Execute DROP TABLE statement.
+    async fn execute_drop_table(&self, name: String) -> Result<QueryResult> {
+        let catalog = self.catalog.clone();
+        let catalog_path = self.catalog_path.clone();
+        let data_dir = self.data_dir.clone();
+        let wal = self.wal.clone();
+        let engines = self.engines.clone();
+
+        tokio::task::spawn_blocking(move || {
+            // Acquire write lock on catalog
+            let mut catalog_lock = catalog.blocking_write();
+
+            let table = catalog_lock
+                .table(&name)
+                .map_err(anyhow::Error::from)?
+                .clone();
+            catalog_lock
+                .drop_table(&name)
+                .map_err(anyhow::Error::from)?;
+
+            // Persist catalog
+            catalog_lock
+                .save(&catalog_path)
+                .map_err(anyhow::Error::from)?;
+
+            drop(catalog_lock);
+
+            // Remove the table's rows and primary key index (blocking I/O)
+            engines
+                .drop_table(&data_dir, &table)
+                .with_context(|| format!("failed to remove storage of table {name}"))?;
+            let pk_index_path = data_dir.join(format!("{name}.pk_idx"));
+            if pk_index_path.exists() {
+                fs::remove_file(&pk_index_path)
+                    .with_context(|| format!("failed to remove {}", pk_index_path.display()))?;
+            }
+
+            if table.temporary {
+                return Ok(QueryResult::Empty);
+            }
+
+            // Log WAL
+            let mut wal_lock = wal.blocking_lock();
+            wal_lock
+                .append(&WalRecord::DropTable { table: table.id })
                 .and_then(|_| wal_lock.sync())
                 .map_err(anyhow::Error::from)?;
 
@@ -667,17 +2328,21 @@ impl Database {
     /// Execute CREATE INDEX statement.
     ///
     /// Creates the index metadata in the catalog and builds the index
-    /// (BTree or Hash) by scanning all existing rows in the table.
+    /// (BTree or Hash) by scanning all existing rows in the table. A UNIQUE
+    /// index is not created if the existing rows already contain a duplicate
+    /// key.
     async fn execute_create_index(
         &self,
         name: String,
         table: String,
         column: String,
         index_type: parser::IndexType,
+        unique: bool,
     ) -> Result<QueryResult> {
         let catalog = self.catalog.clone();
         let catalog_path = self.catalog_path.clone();
         let data_dir = self.data_dir.clone();
+        let engines = self.engines.clone();
 
         tokio::task::spawn_blocking(move || {
             let mut catalog_lock = catalog.blocking_write();
@@ -695,112 +2360,56 @@ impl Database {
                 .index_name(&name)
                 .columns(&[column.as_str()])
                 .kind(catalog_kind.clone())
+                .unique(unique)
                 .call()
                 .map_err(anyhow::Error::from)?;
 
             // Get table metadata for building the index
             let table_meta = catalog_lock.table(&table).map_err(anyhow::Error::from)?;
-            let table_id = table_meta.id;
             let index_meta = table_meta.index(&name).map_err(anyhow::Error::from)?;
             let column_ordinals: Vec<usize> =
                 index_meta.columns.iter().map(|c| *c as usize).collect();
-
-            // Build the index file based on type
-            let index_path = data_dir.join(format!("index_{}.idx", index_id.0));
-
-            // Create a helper enum to manage both index types
-            enum IndexWriter {
-                BTree(btree::BTreeIndex),
-                Hash(hash::HashIndex),
-            }
-
-            let mut writer = match catalog_kind {
-                IndexKind::BTree => IndexWriter::BTree(
-                    btree::BTreeIndex::create(&index_path, index_id)
-                        .map_err(|e| anyhow::anyhow!("failed to create B+Tree index: {}", e))?,
-                ),
-                IndexKind::Hash => IndexWriter::Hash(
-                    hash::HashIndex::create(&index_path, index_id)
-                        .map_err(|e| anyhow::anyhow!("failed to create Hash index: {}", e))?,
-                ),
-                _ => {
-                    return Err(anyhow::anyhow!("unsupported index type"));
-                }
-            };
-
-            // Scan existing rows and insert into the index
-            let heap_path = data_dir.join(format!("{}.heap", table));
-            if heap_path.exists() {
-                let mut heap_file = storage::HeapFile::open(&heap_path, table_id.0)
-                    .map_err(|e| anyhow::anyhow!("failed to open heap file: {}", e))?;
-
-                // Iterate through all pages and slots
-                let mut page_id = 0u64;
-                loop {
-                    let mut found_in_page = false;
-                    for slot in 0..100u16 {
-                        let rid = common::RecordId {
-                            page_id: common::PageId(page_id),
-                            slot,
-                        };
-
-                        match heap_file.get(rid) {
-                            Ok(row) => {
-                                found_in_page = true;
-                                // Extract key columns from the row
-                                let key: Vec<types::Value> = column_ordinals
-                                    .iter()
-                                    .filter_map(|&ord| row.values.get(ord).cloned())
-                                    .collect();
-
-                                match &mut writer {
-                                    IndexWriter::BTree(btree) => {
-                                        btree.insert(key, rid).map_err(|e| {
-                                            anyhow::anyhow!("failed to insert into B+Tree: {}", e)
-                                        })?;
-                                    }
-                                    IndexWriter::Hash(hash) => {
-                                        hash.insert(key, rid).map_err(|e| {
-                                            anyhow::anyhow!("failed to insert into Hash: {}", e)
-                                        })?;
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                // Check if this is an empty slot or end of pages
-                                let msg = e.to_string();
-                                if msg.contains("page") || msg.contains("beyond") {
-                                    break;
-                                }
-                                // Empty slot, continue to next slot
-                            }
-                        }
-                    }
-
-                    if !found_in_page {
-                        break;
-                    }
-                    page_id += 1;
-
-                    // Safety limit
-                    if page_id > 100_000 {
-                        break;
-                    }
+            let compression = table_meta.compression;
+
+            // Scan existing rows in parallel, sorted by key
+            let entries = index_build::collect_entries(
+                &engines,
+                &data_dir,
+                table_meta,
+                catalog_lock.encryption_key(),
+                &column_ordinals,
+                index_build::build_threads(),
+            )?;
+
+            if unique {
+                let duplicate = entries.windows(2).find(|pair| {
+                    pair[0].0 == pair[1].0
+                        && !pair[0].0.iter().any(|v| matches!(v, types::Value::Null))
+                });
+                if let Some(pair) = duplicate {
+                    let key = pair[0].0.clone();
+                    // Leave no trace of the index that could not be built
+                    catalog_lock
+                        .drop_index(&table, &name)
+                        .map_err(anyhow::Error::from)?;
+                    return Err(anyhow::anyhow!(
+                        "cannot create unique index '{}': duplicate key value {:?}",
+                        name,
+                        key
+                    ));
                 }
             }
 
-            // Flush the index
-            match &mut writer {
-                IndexWriter::BTree(btree) => {
-                    btree
-                        .flush()
-                        .map_err(|e| anyhow::anyhow!("failed to flush B+Tree index: {}", e))?;
-                }
-                IndexWriter::Hash(hash) => {
-                    hash.flush()
-                        .map_err(|e| anyhow::anyhow!("failed to flush Hash index: {}", e))?;
-                }
-            }
+            // Build the index file based on type
+            let index_path = data_dir.join(format!("index_{}.idx", index_id.0));
+            index_build::write_index(
+                &catalog_kind,
+                &index_path,
+                index_id,
+                compression,
+                catalog_lock.encryption_key(),
+                entries,
+            )?;
 
             catalog_lock
                 .save(&catalog_path)
@@ -856,43 +2465,126 @@ impl Database {
         .await?
     }
 
+    /// Execute CREATE VIEW, checking that the view's query plans against the
+    /// current schema before storing it.
+    async fn execute_create_view(&self, name: String, query: String) -> Result<QueryResult> {
+        let catalog = self.catalog.clone();
+        let catalog_path = self.catalog_path.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut catalog_lock = catalog.blocking_write();
+
+            let stmt = parse_sql(&query)
+                .map_err(anyhow::Error::from)?
+                .pop()
+                .ok_or_else(|| anyhow::anyhow!("view '{}' has no query", name))?;
+            let mut planning_ctx = PlanningContext::new(&catalog_lock);
+            Planner::plan(stmt, &mut planning_ctx)
+                .map_err(|e| anyhow::anyhow!("invalid query for view '{}': {}", name, e))?;
+
+            catalog_lock
+                .create_view(&name, &query)
+                .map_err(anyhow::Error::from)?;
+            catalog_lock
+                .save(&catalog_path)
+                .map_err(anyhow::Error::from)?;
+
+            Ok(QueryResult::Empty)
+        })
+        .await?
+    }
+
+    /// Execute DROP VIEW.
+    async fn execute_drop_view(&self, name: String) -> Result<QueryResult> {
+        let catalog = self.catalog.clone();
+        let catalog_path = self.catalog_path.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut catalog_lock = catalog.blocking_write();
+            catalog_lock.drop_view(&name).map_err(anyhow::Error::from)?;
+            catalog_lock
+                .save(&catalog_path)
+                .map_err(anyhow::Error::from)?;
+            Ok(QueryResult::Empty)
+        })
+        .await?
+    }
+
     /// Execute EXPLAIN or EXPLAIN ANALYZE statement.
+    ///
+    /// For INSERT, UPDATE and DELETE the plan also lists the indexes and
+    /// constraints the write touches and the path it would take through
+    /// Raft. EXPLAIN ANALYZE runs the write, so it is refused for DML on Raft
+    /// nodes, where running it here would skip replication; elsewhere the
+    /// rows it writes count towards the table's activity.
     async fn execute_explain(&self, query: Statement, analyze: bool) -> Result<QueryResult> {
+        let written = modification(&query).map(|(table, kind)| (table.to_string(), kind));
+        let is_dml = written.is_some();
+        if analyze && is_dml && self.raft.is_some() {
+            anyhow::bail!("EXPLAIN ANALYZE of INSERT, UPDATE or DELETE is not supported with Raft");
+        }
+        let replication = is_dml.then(|| self.describe_replication(&query));
         let catalog = self.catalog.clone();
         let pager = self.pager.clone();
         let wal = self.wal.clone();
         let data_dir = self.data_dir.clone();
+        let limits = self.resource_limits;
+        let priority = statement_priority();
+        let max_parallel_workers = self.max_parallel_workers;
+        let engines = self.engines.clone();
+        let random = self.session_random();
 
-        tokio::task::spawn_blocking(move || {
+        let (result, affected) = tokio::task::spawn_blocking(move || {
             let catalog_lock = catalog.blocking_read();
             let mut planning_ctx = PlanningContext::new(&catalog_lock);
             let plan = Planner::plan(query, &mut planning_ctx).map_err(anyhow::Error::from)?;
+            let dml_description = match &plan {
+                PhysicalPlan::Insert { table_id, .. }
+                | PhysicalPlan::Update { table_id, .. }
+                | PhysicalPlan::Delete { table_id, .. } => {
+                    let table = catalog_lock
+                        .table_by_id(*table_id)
+                        .map_err(anyhow::Error::from)?;
+                    planner::explain_dml(&plan, table)
+                }
+                _ => None,
+            };
+            let mut description =
+                dml_description.unwrap_or_else(|| planner::explain_physical(&plan));
+            if let Some(replication) = &replication {
+                description.push_str("\n  Replication: ");
+                description.push_str(replication);
+            }
 
             if analyze {
                 // EXPLAIN ANALYZE: Execute the query and collect statistics
-                let plan_description = planner::explain_physical(&plan);
+                let plan_description = description;
 
-                let mut pager_lock = pager.blocking_lock();
+                let mut pager = pager.clone();
                 let mut wal_lock = wal.blocking_lock();
                 let mut ctx = ExecutionContext::new(
                     &catalog_lock,
-                    pager_lock.deref_mut(),
+                    &mut pager,
                     wal_lock.deref_mut(),
                     data_dir.as_ref().clone(),
-                );
+                )
+                .with_resource_limits(limits)
+                .with_priority(priority)
+                .with_max_parallel_workers(scan_workers(&plan, max_parallel_workers))
+                .with_engines(engines);
 
                 let mut executor = build_executor(plan).map_err(anyhow::Error::from)?;
-                executor.open(&mut ctx).map_err(anyhow::Error::from)?;
-
-                let mut row_count = 0;
-                while executor
-                    .next(&mut ctx)
-                    .map_err(anyhow::Error::from)?
-                    .is_some()
-                {
-                    row_count += 1;
-                }
-                executor.close(&mut ctx).map_err(anyhow::Error::from)?;
+                let (row_count, affected) = with_session_random(&random, || -> Result<_> {
+                    executor.open(&mut ctx).map_err(anyhow::Error::from)?;
+                    let mut row_count = 0;
+                    let mut affected = None;
+                    while let Some(row) = executor.next(&mut ctx).map_err(anyhow::Error::from)? {
+                        affected = dml_count(&row);
+                        row_count += 1;
+                    }
+                    executor.close(&mut ctx).map_err(anyhow::Error::from)?;
+                    Ok((row_count, affected))
+                })?;
 
                 // Format the output
                 let mut output = String::new();
@@ -905,68 +2597,289 @@ impl Database {
                 ));
                 output.push_str(&format!("\nTotal rows: {}", row_count));
 
-                Ok(QueryResult::Rows {
+                let result = QueryResult::Rows {
                     schema: vec!["Explain".to_string()],
                     rows: vec![common::Row::new(vec![Value::Text(output)])],
-                })
+                    info: None,
+                };
+                Ok((result, affected))
             } else {
                 // EXPLAIN: Just show the plan
-                let description = planner::explain_physical(&plan);
-                Ok(QueryResult::Rows {
+                let result = QueryResult::Rows {
                     schema: vec!["Explain".to_string()],
                     rows: vec![common::Row::new(vec![Value::Text(description)])],
-                })
+                    info: None,
+                };
+                Ok::<_, anyhow::Error>((result, None))
             }
         })
-        .await?
+        .await??;
+
+        if let (Some((table, kind)), Some(affected)) = (written, affected) {
+            self.record_modifications(&table, kind, affected).await;
+        }
+        Ok(result)
     }
 
-    /// Execute a query or DML statement (SELECT, INSERT, UPDATE, DELETE).
-    async fn execute_query_or_dml(&self, stmt: Statement) -> Result<QueryResult> {
-        // If Raft is enabled and this is a DML statement, route through Raft
-        if self.is_raft_enabled() && is_dml_statement(&stmt) {
-            // Check that we're the leader before accepting writes
-            self.require_leader()?;
-            return self.execute_dml_via_raft(stmt).await;
+    /// Execute PROFILE: run the statement with every operator profiled and
+    /// report the time, rows, allocations and storage wait of each, from the
+    /// root of the plan down.
+    ///
+    /// Allocations are reported only when the process counts them (see
+    /// [`common::alloc`]). Like EXPLAIN ANALYZE, PROFILE runs writes, so it
+    /// is refused for DML on Raft nodes.
+    async fn execute_profile(&self, query: Statement) -> Result<QueryResult> {
+        let written = modification(&query).map(|(table, kind)| (table.to_string(), kind));
+        if written.is_some() && self.raft.is_some() {
+            anyhow::bail!("PROFILE of INSERT, UPDATE or DELETE is not supported with Raft");
+        }
+        let catalog = self.catalog.clone();
+        let pager = self.pager.clone();
+        let wal = self.wal.clone();
+        let data_dir = self.data_dir.clone();
+        let limits = self.resource_limits;
+        let priority = statement_priority();
+        let max_parallel_workers = self.max_parallel_workers;
+        let engines = self.engines.clone();
+        let random = self.session_random();
+
+        let (result, affected) = tokio::task::spawn_blocking(move || {
+            let catalog_lock = catalog.blocking_read();
+            let mut planning_ctx = PlanningContext::new(&catalog_lock);
+            let plan = Planner::plan(query, &mut planning_ctx).map_err(anyhow::Error::from)?;
+
+            let mut pager = pager.clone();
+            let mut wal_lock = wal.blocking_lock();
+            let mut ctx = ExecutionContext::new(
+                &catalog_lock,
+                &mut pager,
+                wal_lock.deref_mut(),
+                data_dir.as_ref().clone(),
+            )
+            .with_resource_limits(limits)
+            .with_priority(priority)
+            .with_max_parallel_workers(scan_workers(&plan, max_parallel_workers))
+            .with_engines(engines)
+            .with_storage_timing();
+
+            let (mut executor, profile) =
+                build_profiled_executor(plan).map_err(anyhow::Error::from)?;
+            let (row_count, affected) = with_session_random(&random, || -> Result<_> {
+                executor.open(&mut ctx).map_err(anyhow::Error::from)?;
+                let mut row_count = 0;
+                let mut affected = None;
+                while let Some(row) = executor.next(&mut ctx).map_err(anyhow::Error::from)? {
+                    affected = dml_count(&row);
+                    row_count += 1;
+                }
+                executor.close(&mut ctx).map_err(anyhow::Error::from)?;
+                Ok((row_count, affected))
+            })?;
+
+            let report = profile
+                .report()
+                .ok_or_else(|| anyhow::anyhow!("the statement has no operators to profile"))?;
+            let total = report.counters.total_time();
+            let mut output = format!(
+                "PROFILE: total={} rows={}",
+                executor::profile::format_time(total),
+                row_count
+            );
+            if common::alloc::thread_allocations().is_none() {
+                output.push_str(" (allocations not counted)");
+            }
+            output.push('\n');
+            output.push_str(&report.render(total));
+
+            let result = QueryResult::Rows {
+                schema: vec!["Profile".to_string()],
+                rows: vec![common::Row::new(vec![Value::Text(
+                    output.trim_end().to_string(),
+                )])],
+                info: None,
+            };
+            Ok::<_, anyhow::Error>((result, affected))
+        })
+        .await??;
+
+        if let (Some((table, kind)), Some(affected)) = (written, affected) {
+            self.record_modifications(&table, kind, affected).await;
+        }
+        Ok(result)
+    }
+
+    /// Describe how this node would carry out a write, for EXPLAIN.
+    fn describe_replication(&self, stmt: &Statement) -> String {
+        match self.route_statement(stmt) {
+            Route::Local | Route::LinearizableRead => {
+                "none, applied locally and logged to the WAL".to_string()
+            }
+            Route::Replicate => "Raft log, one entry per row, applied to table storage on \
+                 every node without index maintenance or checks beyond NOT NULL"
+                .to_string(),
+            Route::Redirect {
+                leader: Some(leader),
+            } => format!("rejected on this follower, send to leader node {}", leader),
+            Route::Redirect { leader: None } => "rejected, no Raft leader is known".to_string(),
         }
+    }
 
-        // Otherwise use the standard synchronous executor path
+    /// Run a query and stream its rows into a file (see [`export`]).
+    ///
+    /// The file is created in the data directory's [`export::EXPORT_DIR`],
+    /// and must not exist yet. If the export fails, the partly written file
+    /// is removed.
+    async fn execute_copy_to(
+        &self,
+        query: Statement,
+        path: String,
+        format: CopyFormat,
+    ) -> Result<QueryResult> {
         let catalog = self.catalog.clone();
         let pager = self.pager.clone();
         let wal = self.wal.clone();
         let data_dir = self.data_dir.clone();
+        let limits = self.resource_limits;
+        let priority = statement_priority();
+        let max_parallel_workers = self.max_parallel_workers;
+        let engines = self.engines.clone();
+        let random = self.session_random();
 
         tokio::task::spawn_blocking(move || {
-            // Acquire read lock on catalog (shared access for queries/DML)
             let catalog_lock = catalog.blocking_read();
             let mut planning_ctx = PlanningContext::new(&catalog_lock);
-            let plan = Planner::plan(stmt, &mut planning_ctx).map_err(anyhow::Error::from)?;
+            let plan = Planner::plan(query, &mut planning_ctx).map_err(anyhow::Error::from)?;
+            let schema = infer_schema(&plan);
 
-            // Acquire exclusive locks on pager and WAL
-            let mut pager_lock = pager.blocking_lock();
+            let mut pager = pager.clone();
             let mut wal_lock = wal.blocking_lock();
             let mut ctx = ExecutionContext::new(
                 &catalog_lock,
-                pager_lock.deref_mut(),
+                &mut pager,
                 wal_lock.deref_mut(),
                 data_dir.as_ref().clone(),
-            );
+            )
+            .with_resource_limits(limits)
+            .with_priority(priority)
+            .with_max_parallel_workers(max_parallel_workers)
+            .with_engines(engines);
+
+            let (path, file) = export::create_file(&data_dir, &path)
+                .with_context(|| format!("failed to export to '{path}'"))?;
+            let export = || -> Result<u64> {
+                let mut writer = RowWriter::new(io::BufWriter::new(file), format, schema)?;
+                let mut executor = build_executor(plan).map_err(anyhow::Error::from)?;
+                executor.open(&mut ctx).map_err(anyhow::Error::from)?;
+                while let Some(row) = executor.next(&mut ctx).map_err(anyhow::Error::from)? {
+                    writer.write_row(&row)?;
+                }
+                executor.close(&mut ctx).map_err(anyhow::Error::from)?;
+                Ok(writer.finish()?)
+            };
+            match with_session_random(&random, export) {
+                Ok(affected) => Ok(QueryResult::Count {
+                    affected,
+                    info: None,
+                }),
+                Err(e) => {
+                    let _ = fs::remove_file(&path);
+                    Err(e.context(format!("failed to export to {}", path.display())))
+                }
+            }
+        })
+        .await?
+    }
+
+    /// Execute a query or DML statement (SELECT, INSERT, UPDATE, DELETE)
+    /// against local storage using the synchronous executor.
+    async fn execute_query_or_dml(&self, stmt: Statement) -> Result<QueryResult> {
+        let catalog = self.catalog.clone();
+        let pager = self.pager.clone();
+        let wal = self.wal.clone();
+        let data_dir = self.data_dir.clone();
+        let catalog_path = self.catalog_path.clone();
+        let limits = self.resource_limits;
+        let priority = statement_priority();
+        let max_parallel_workers = self.max_parallel_workers;
+        let engines = self.engines.clone();
+        let random = self.session_random();
+        let keeps_undo_log = transaction::keeps_undo_log();
+
+        let (result, undo) = tokio::task::spawn_blocking(move || {
+            let start = Instant::now();
+            // Acquire read lock on catalog (shared access for queries/DML)
+            let catalog_lock = catalog.blocking_read();
+
+            // Acquire an exclusive lock on the WAL
+            let mut pager = pager.clone();
+            let mut wal_lock = wal.blocking_lock();
+            let mut ctx = ExecutionContext::new(
+                &catalog_lock,
+                &mut pager,
+                wal_lock.deref_mut(),
+                data_dir.as_ref().clone(),
+            )
+            .with_resource_limits(limits)
+            .with_priority(priority)
+            .with_engines(engines);
+            if keeps_undo_log {
+                ctx = ctx.with_undo_log();
+            }
 
-            match plan {
+            // The query of an INSERT ... SELECT runs under the same locks as
+            // the insert, so no other write lands between them
+            let stmt = with_session_random(&random, || {
+                insert_query_values(stmt, &catalog_lock, &mut ctx)
+            })?;
+            let mut planning_ctx = PlanningContext::new(&catalog_lock);
+            let plan = Planner::plan(stmt, &mut planning_ctx).map_err(anyhow::Error::from)?;
+            let mut ctx = ctx.with_max_parallel_workers(scan_workers(&plan, max_parallel_workers));
+
+            let info = |ctx: &ExecutionContext, estimated_rows, actual_rows| {
+                Some(ExecutionInfo {
+                    rows_scanned: ctx.resource_usage().rows_scanned,
+                    estimated_rows,
+                    actual_rows,
+                    elapsed: start.elapsed(),
+                })
+            };
+            let result = with_session_random(&random, || match plan {
                 PhysicalPlan::Insert { .. }
                 | PhysicalPlan::Update { .. }
                 | PhysicalPlan::Delete { .. } => {
                     let count = execute_dml(plan, &mut ctx).map_err(anyhow::Error::from)?;
-                    Ok(QueryResult::Count { affected: count })
+                    let generated_ids = ctx.generated_ids().to_vec();
+                    if generated_ids.is_empty() {
+                        return Ok(QueryResult::Count {
+                            affected: count,
+                            info: info(&ctx, None, count),
+                        });
+                    }
+                    // Persist the advanced sequences
+                    catalog_lock
+                        .save(&catalog_path)
+                        .map_err(anyhow::Error::from)?;
+                    Ok(QueryResult::Inserted {
+                        affected: count,
+                        generated_ids,
+                        info: info(&ctx, None, count),
+                    })
                 }
                 ref query_plan => {
                     let schema = infer_schema(query_plan);
+                    let estimated_rows = planner::estimated_rows(query_plan, &catalog_lock);
                     let rows = execute_query(plan, &mut ctx).map_err(anyhow::Error::from)?;
-                    Ok(QueryResult::Rows { schema, rows })
+                    let info = info(&ctx, estimated_rows, rows.len() as u64);
+                    Ok(QueryResult::Rows { schema, rows, info })
                 }
-            }
+            });
+            // Kept whether or not the statement failed, since a failed
+            // write may have changed rows before it stopped
+            Ok::<_, anyhow::Error>((result, ctx.take_undo_log()))
         })
-        .await?
+        .await??;
+        transaction::record_undo(undo);
+        result
     }
 
     /// Reset the database by removing all data files and reinitializing.
@@ -977,7 +2890,9 @@ impl Database {
         let catalog = self.catalog.clone();
         let pager = self.pager.clone();
         let wal = self.wal.clone();
-        let buffer_pages = self.buffer_pages;
+        let settings = self.settings.get();
+        let key = self.encryption.clone();
+        let faults = self.faults.clone();
 
         tokio::task::spawn_blocking(move || {
             // Remove all table files (.tbl) and heap files (.heap)
@@ -1005,7 +2920,8 @@ impl Database {
             {
                 let mut wal_lock = wal.blocking_lock();
                 // Close the WAL by dropping the old one
-                *wal_lock = Wal::open(&**wal_path).map_err(anyhow::Error::from)?;
+                *wal_lock =
+                    Wal::open_with_key(&**wal_path, key.as_ref()).map_err(anyhow::Error::from)?;
             }
             if wal_path.exists() {
                 fs::remove_file(&**wal_path)
@@ -1015,39 +2931,319 @@ impl Database {
             // Reinitialize catalog
             {
                 let mut catalog_lock = catalog.blocking_write();
-                *catalog_lock = Catalog::load(&catalog_path).map_err(anyhow::Error::from)?;
+                let previous_epoch = catalog_lock.epoch();
+                *catalog_lock = Catalog::load_with_key(&catalog_path, key.clone())
+                    .map_err(anyhow::Error::from)?;
+                catalog_lock.advance_epoch_past(previous_epoch);
             }
 
             // Reinitialize pager (clear buffer pool)
             {
-                let mut pager_lock = pager.blocking_lock();
-                *pager_lock = FilePager::new(&**data_dir, buffer_pages);
+                let pool = pager.pool();
+                pool.forget_all().map_err(anyhow::Error::from)?;
+                pool.set_max_pages(settings.buffer_pages)
+                    .map_err(anyhow::Error::from)?;
             }
 
             // Reinitialize WAL
             {
                 let mut wal_lock = wal.blocking_lock();
-                *wal_lock = Wal::open(&**wal_path).map_err(anyhow::Error::from)?;
+                *wal_lock = Wal::open_with_key(&**wal_path, key.as_ref())
+                    .map_err(anyhow::Error::from)?
+                    .with_faults(faults);
+                wal_lock.set_durability(settings.durability);
+            }
+
+            Ok::<_, anyhow::Error>(())
+        })
+        .await??;
+
+        self.publish_catalog_epoch().await;
+        self.record_manifest().await
+    }
+
+    /// Get a clone of the catalog Arc for async access.
+    ///
+    /// Use this to read catalog metadata in async contexts.
+    /// For synchronous access within spawn_blocking, use catalog.blocking_read().
+    pub fn catalog(&self) -> Arc<RwLock<Catalog>> {
+        self.catalog.clone()
+    }
+
+    /// Get the data directory path.
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// What the self-check run while opening the database found and
+    /// repaired (see [`recovery`]).
+    pub fn recovery_report(&self) -> &RecoveryReport {
+        &self.recovery
+    }
+
+    /// Whether a write ran out of disk space and writes are being rejected
+    /// until space is freed (see [`disk_full`]).
+    pub fn is_read_only(&self) -> bool {
+        self.disk_full.is_read_only()
+    }
+
+    /// The schema of the database, without its data, as a bundle that can
+    /// be imported into another database (see [`schema`]).
+    pub async fn export_schema(&self) -> SchemaBundle {
+        SchemaBundle::from_catalog(&*self.catalog.read().await)
+    }
+
+    /// The changes [`Database::import_schema`] would make for `bundle`,
+    /// without making them.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the bundle's schema cannot be reached with DDL (see
+    /// [`schema`]).
+    pub async fn plan_schema_import(&self, bundle: &SchemaBundle) -> Result<Vec<SchemaChange>> {
+        bundle.diff(&self.export_schema().await)
+    }
+
+    /// Change the schema of the database to `bundle`'s, returning the
+    /// changes made.
+    ///
+    /// The changes run in order as one script (see
+    /// [`Database::execute_script`]), so a failing change stops the import
+    /// and the changes before it keep their effects. Data in dropped tables
+    /// and columns is lost.
+    ///
+    /// # Errors
+    ///
+    /// Fails without changing anything if the bundle's schema cannot be
+    /// reached with DDL, and otherwise if a change fails.
+    pub async fn import_schema(&self, bundle: &SchemaBundle) -> Result<Vec<SchemaChange>> {
+        let changes = self.plan_schema_import(bundle).await?;
+        let script: Vec<String> = changes.iter().map(SchemaChange::to_sql).collect();
+        self.execute_script(&script.join(";\n")).await?;
+        Ok(changes)
+    }
+
+    /// Checksum the rows of every table, as served by `/admin/checksums`.
+    ///
+    /// Two nodes that have applied the same Raft log entries should return
+    /// the same checksums (see [`consistency`]).
+    pub async fn table_checksums(&self) -> Result<Vec<TableChecksum>> {
+        let catalog = self.catalog.clone();
+        let pager = self.pager.clone();
+        let wal = self.wal.clone();
+        let engines = self.engines.clone();
+        let data_dir = self.data_dir.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let catalog_lock = catalog.blocking_read();
+            consistency::table_checksums(&catalog_lock, &pager, &wal, &engines, &data_dir)
+        })
+        .await?
+    }
+
+    /// Read the changes to `publication`'s tables committed after position
+    /// `from` of the WAL, for a subscriber to apply with
+    /// [`Database::apply_changes`] (see [`replication`]).
+    ///
+    /// # Errors
+    ///
+    /// Fails on Raft nodes, if a published table is missing or cannot be
+    /// published, and if `from` is no longer a position in the WAL.
+    pub async fn read_changes(&self, publication: &Publication, from: u64) -> Result<ChangeBatch> {
+        if self.raft.is_some() {
+            anyhow::bail!("Raft nodes do not log replicated writes and cannot publish changes");
+        }
+        let catalog = self.catalog.clone();
+        let wal = self.wal.clone();
+        let publication = publication.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let catalog_lock = catalog.blocking_read();
+            let wal_lock = wal.blocking_lock();
+            replication::read_changes(&catalog_lock, &wal_lock, &publication, from)
+        })
+        .await?
+    }
+
+    /// Copy the rows of table `name` as of one point in time.
+    ///
+    /// Writes wait while the table is scanned, so the copy holds each write
+    /// committed before it whole and nothing of later ones; see
+    /// [`snapshot`] for bootstrapping a subscription from it.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the table does not exist or cannot be read.
+    pub async fn snapshot_table(&self, name: &str) -> Result<TableSnapshot> {
+        let catalog = self.catalog.clone();
+        let pager = self.pager.clone();
+        let wal = self.wal.clone();
+        let data_dir = self.data_dir.clone();
+        let engines = self.engines.clone();
+        let name = name.to_lowercase();
+
+        tokio::task::spawn_blocking(move || {
+            let catalog_lock = catalog.blocking_read();
+            snapshot::snapshot_table(&catalog_lock, &pager, &wal, &engines, &data_dir, &name)
+        })
+        .await?
+    }
+
+    /// Apply a batch of changes read from a publisher with
+    /// [`Database::read_changes`], returning how many were applied.
+    ///
+    /// Each change is applied as its own write. If one fails, the changes
+    /// before it stay applied and `subscription` is saved at the failed
+    /// change, so that the next batch read from its position retries it.
+    pub async fn apply_changes(
+        &self,
+        subscription: &mut Subscription,
+        batch: ChangeBatch,
+    ) -> Result<u64> {
+        let interrupted = subscription.begin(&batch)?;
+        let mut applied = 0;
+        for (end, change) in batch.changes {
+            let replayed = interrupted.is_some_and(|until| end <= until);
+            let planned = {
+                let catalog = self.catalog.read().await;
+                subscription.plan(&catalog, &change, replayed)
+            };
+            let result = match planned {
+                Ok(planned) => self
+                    .execute_replicated(planned.plans)
+                    .await
+                    .map(|_| planned.key),
+                Err(e) => Err(e),
+            };
+            let key = match result {
+                Ok(key) => key,
+                Err(e) => {
+                    subscription.save()?;
+                    return Err(e);
+                }
+            };
+            let kind = match &change {
+                Change::Insert { .. } => Modification::Insert,
+                Change::Update { .. } => Modification::Update,
+                Change::Delete { .. } => Modification::Delete,
+            };
+            self.record_modifications(change.table(), kind, 1).await;
+            subscription.applied(change, end, key);
+            applied += 1;
+        }
+        subscription.finish(batch.end)?;
+        Ok(applied)
+    }
+
+    /// Reverse the row changes in `undo`, newest first, as
+    /// [`executor::roll_back`] does.
+    ///
+    /// On a Raft leader the reversing writes are replicated like any other.
+    pub(crate) async fn roll_back(&self, undo: Vec<UndoRecord>) -> Result<()> {
+        if undo.is_empty() {
+            return Ok(());
+        }
+        if self.raft.is_some() {
+            return self.roll_back_via_raft(undo).await;
+        }
+        let catalog = self.catalog.clone();
+        let pager = self.pager.clone();
+        let wal = self.wal.clone();
+        let data_dir = self.data_dir.clone();
+        let engines = self.engines.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let catalog_lock = catalog.blocking_read();
+            let mut pager = pager.clone();
+            let mut wal_lock = wal.blocking_lock();
+            let mut ctx = ExecutionContext::new(
+                &catalog_lock,
+                &mut pager,
+                wal_lock.deref_mut(),
+                data_dir.as_ref().clone(),
+            )
+            .with_engines(engines);
+            executor::roll_back(&mut ctx, undo).map_err(anyhow::Error::from)
+        })
+        .await?
+    }
+
+    /// Reverse the row changes in `undo` with commands replicated through
+    /// Raft.
+    async fn roll_back_via_raft(&self, undo: Vec<UndoRecord>) -> Result<()> {
+        // Rows inserted again land at new record ids
+        let mut moved: HashMap<(TableId, RecordId), RecordId> = HashMap::new();
+        for record in undo.into_iter().rev() {
+            let current = |table, rid| *moved.get(&(table, rid)).unwrap_or(&rid);
+            let (cmd, reinserted) = match record {
+                UndoRecord::Insert { table, rid } => (
+                    Command::Delete {
+                        table_id: table,
+                        rid: current(table, rid),
+                    },
+                    None,
+                ),
+                UndoRecord::Update {
+                    table,
+                    rid,
+                    old_row,
+                    ..
+                } => (
+                    Command::Update {
+                        table_id: table,
+                        rid: current(table, rid),
+                        new_row: old_row,
+                    },
+                    None,
+                ),
+                UndoRecord::Delete { table, rid, row } => (
+                    Command::Insert {
+                        table_id: table,
+                        row,
+                    },
+                    Some((table, rid)),
+                ),
+            };
+            match self.raft_write(cmd).await? {
+                CommandResponse::Error { message } => return Err(anyhow::anyhow!("{}", message)),
+                CommandResponse::Insert { rid } => {
+                    if let Some(old) = reinserted {
+                        moved.insert(old, rid);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Execute the DML plans that apply a replicated change.
+    async fn execute_replicated(&self, plans: Vec<PhysicalPlan>) -> Result<()> {
+        let catalog = self.catalog.clone();
+        let pager = self.pager.clone();
+        let wal = self.wal.clone();
+        let data_dir = self.data_dir.clone();
+        let engines = self.engines.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let catalog_lock = catalog.blocking_read();
+            let mut pager = pager.clone();
+            let mut wal_lock = wal.blocking_lock();
+            let mut ctx = ExecutionContext::new(
+                &catalog_lock,
+                &mut pager,
+                wal_lock.deref_mut(),
+                data_dir.as_ref().clone(),
+            )
+            .with_engines(engines);
+            for plan in plans {
+                execute_dml(plan, &mut ctx).map_err(anyhow::Error::from)?;
             }
-
             Ok(())
         })
         .await?
     }
 
-    /// Get a clone of the catalog Arc for async access.
-    ///
-    /// Use this to read catalog metadata in async contexts.
-    /// For synchronous access within spawn_blocking, use catalog.blocking_read().
-    pub fn catalog(&self) -> Arc<RwLock<Catalog>> {
-        self.catalog.clone()
-    }
-
-    /// Get the data directory path.
-    pub fn data_dir(&self) -> &Path {
-        &self.data_dir
-    }
-
     /// Get the Raft node, if Raft is enabled.
     pub fn raft_node(&self) -> Option<&Arc<RaftNode>> {
         self.raft.as_ref()
@@ -1078,31 +3274,69 @@ impl Database {
 
     /// Execute a DML statement through Raft consensus.
     ///
-    /// For INSERT: Converts directly to a Command and writes through Raft.
+    /// For INSERT: Converts each VALUES row to a Command and writes it through Raft.
     /// For UPDATE/DELETE: First scans to find matching rows, then sends individual
     /// commands for each row through Raft.
     async fn execute_dml_via_raft(&self, stmt: Statement) -> Result<QueryResult> {
         match stmt {
-            Statement::Insert { table, values } => {
-                let cmd = self.insert_to_command(&table, &values).await?;
-                let response = self.raft_write(cmd).await?;
-                match response {
-                    CommandResponse::Insert { .. } => Ok(QueryResult::Count { affected: 1 }),
-                    CommandResponse::Error { message } => Err(anyhow::anyhow!("{}", message)),
-                    _ => Ok(QueryResult::Empty),
+            Statement::Insert {
+                table,
+                columns,
+                rows,
+                ..
+            } => {
+                let (commands, generated_ids) =
+                    self.insert_to_commands(&table, &columns, rows).await?;
+                let mut affected = 0;
+                for cmd in commands {
+                    let Command::Insert { table_id, .. } = cmd else {
+                        unreachable!("INSERT is replicated as insert commands")
+                    };
+                    match self.raft_write(cmd).await? {
+                        CommandResponse::Insert { rid } => {
+                            transaction::record_undo([UndoRecord::Insert {
+                                table: table_id,
+                                rid,
+                            }]);
+                            affected += 1
+                        }
+                        CommandResponse::Error { message } => {
+                            return Err(anyhow::anyhow!("{}", message))
+                        }
+                        _ => {}
+                    }
+                }
+                if generated_ids.is_empty() {
+                    Ok(QueryResult::Count {
+                        affected,
+                        info: None,
+                    })
+                } else {
+                    Ok(QueryResult::Inserted {
+                        affected,
+                        generated_ids,
+                        info: None,
+                    })
                 }
             }
+            Statement::Update { from, .. } if !from.is_empty() => Err(anyhow::anyhow!(
+                "UPDATE ... FROM not supported through Raft"
+            )),
+            Statement::Delete { using, .. } if !using.is_empty() => Err(anyhow::anyhow!(
+                "DELETE ... USING not supported through Raft"
+            )),
             Statement::Update {
                 table,
                 assignments,
                 selection,
+                ..
             } => {
                 self.execute_update_via_raft(table, assignments, selection)
                     .await
             }
-            Statement::Delete { table, selection } => {
-                self.execute_delete_via_raft(table, selection).await
-            }
+            Statement::Delete {
+                table, selection, ..
+            } => self.execute_delete_via_raft(table, selection).await,
             _ => Err(anyhow::anyhow!(
                 "statement not supported through Raft: {:?}",
                 stmt
@@ -1118,48 +3352,66 @@ impl Database {
         selection: Option<expr::Expr>,
     ) -> Result<QueryResult> {
         // Get table metadata
-        let (table_id, schema_names) = {
-            let catalog_lock = self.catalog.read().await;
-            let table_meta = catalog_lock
-                .table(&table)
-                .map_err(|e| anyhow::anyhow!("table lookup failed: {}", e))?;
-            let schema_names: Vec<String> = table_meta
-                .schema
-                .columns()
-                .iter()
-                .map(|c| c.name.clone())
-                .collect();
-            (table_meta.id, schema_names)
-        };
+        let table_meta = self
+            .catalog
+            .read()
+            .await
+            .table(&table)
+            .map_err(|e| anyhow::anyhow!("table lookup failed: {}", e))?
+            .clone();
+        let table_id = table_meta.id;
+        let version_col = table_meta.row_version_column();
+        let schema_names: Vec<String> = table_meta
+            .schema
+            .columns()
+            .iter()
+            .map(|c| c.name.clone())
+            .collect();
 
         // Resolve assignments: column name -> (column_id, new_value)
-        let resolved_assignments: Vec<(u16, Value)> = assignments
-            .iter()
-            .map(|(col_name, expr)| {
-                let col_idx = schema_names
-                    .iter()
-                    .position(|n| n == col_name)
-                    .ok_or_else(|| anyhow::anyhow!("column '{}' not found", col_name))?
-                    as u16;
-                let value = eval_literal_expr(expr)?;
-                Ok((col_idx, value))
-            })
-            .collect::<Result<Vec<_>>>()?;
+        let random = self.session_random();
+        let resolved_assignments: Vec<(u16, Value)> = with_session_random(&random, || {
+            assignments
+                .iter()
+                .map(|(col_name, expr)| {
+                    let col_idx = schema_names
+                        .iter()
+                        .position(|n| n == col_name)
+                        .ok_or_else(|| anyhow::anyhow!("column '{}' not found", col_name))?
+                        as u16;
+                    let value = eval_literal_expr(expr)?;
+                    Ok((col_idx, value))
+                })
+                .collect::<Result<Vec<_>>>()
+        })?;
 
         // Find matching rows by executing a scan
         let matching_rows = self
             .find_matching_rows(table_id, &schema_names, selection)
             .await?;
 
-        // For each matching row, send an UPDATE command through Raft
-        let mut affected = 0u64;
+        // Build and validate every new row before replicating any of them
+        let mut updates = Vec::with_capacity(matching_rows.len());
         for (rid, old_row) in matching_rows {
-            // Build new row by applying assignments
             let mut new_values = old_row.values.clone();
             for (col_idx, value) in &resolved_assignments {
                 new_values[*col_idx as usize] = value.clone();
             }
+            if let Some(v) = version_col {
+                if !resolved_assignments.iter().any(|(col, _)| *col == v) {
+                    bump_row_version(&mut new_values, v);
+                }
+            }
+            table_meta
+                .coerce_types(&mut new_values)
+                .and_then(|()| table_meta.check_not_null(&new_values))
+                .map_err(anyhow::Error::from)?;
+            updates.push((rid, old_row.values, new_values));
+        }
 
+        // Send an UPDATE command through Raft for each matching row
+        let mut affected = 0u64;
+        for (rid, old_row, new_values) in updates {
             let cmd = Command::Update {
                 table_id,
                 rid,
@@ -1168,13 +3420,24 @@ impl Database {
 
             let response = self.raft_write(cmd).await?;
             match response {
-                CommandResponse::Update { rows_affected } => affected += rows_affected,
+                CommandResponse::Update { rows_affected } => {
+                    transaction::record_undo([UndoRecord::Update {
+                        table: table_id,
+                        rid,
+                        old_rid: rid,
+                        old_row,
+                    }]);
+                    affected += rows_affected
+                }
                 CommandResponse::Error { message } => return Err(anyhow::anyhow!("{}", message)),
                 _ => {}
             }
         }
 
-        Ok(QueryResult::Count { affected })
+        Ok(QueryResult::Count {
+            affected,
+            info: None,
+        })
     }
 
     /// Execute DELETE through Raft by scanning for matching rows first.
@@ -1205,18 +3468,28 @@ impl Database {
 
         // For each matching row, send a DELETE command through Raft
         let mut affected = 0u64;
-        for (rid, _row) in matching_rows {
+        for (rid, row) in matching_rows {
             let cmd = Command::Delete { table_id, rid };
 
             let response = self.raft_write(cmd).await?;
             match response {
-                CommandResponse::Delete { rows_affected } => affected += rows_affected,
+                CommandResponse::Delete { rows_affected } => {
+                    transaction::record_undo([UndoRecord::Delete {
+                        table: table_id,
+                        rid,
+                        row: row.values,
+                    }]);
+                    affected += rows_affected
+                }
                 CommandResponse::Error { message } => return Err(anyhow::anyhow!("{}", message)),
                 _ => {}
             }
         }
 
-        Ok(QueryResult::Count { affected })
+        Ok(QueryResult::Count {
+            affected,
+            info: None,
+        })
     }
 
     /// Find rows matching an optional predicate, returning their RIDs and data.
@@ -1231,17 +3504,25 @@ impl Database {
         let wal = self.wal.clone();
         let data_dir = self.data_dir.clone();
         let schema_names = schema_names.to_vec();
+        let limits = self.resource_limits;
+        let priority = statement_priority();
+        let max_parallel_workers = self.max_parallel_workers;
+        let engines = self.engines.clone();
 
         tokio::task::spawn_blocking(move || {
             let catalog_lock = catalog.blocking_read();
-            let mut pager_lock = pager.blocking_lock();
+            let mut pager = pager.clone();
             let mut wal_lock = wal.blocking_lock();
             let mut ctx = ExecutionContext::new(
                 &catalog_lock,
-                pager_lock.deref_mut(),
+                &mut pager,
                 wal_lock.deref_mut(),
                 data_dir.as_ref().clone(),
-            );
+            )
+            .with_resource_limits(limits)
+            .with_priority(priority)
+            .with_max_parallel_workers(max_parallel_workers)
+            .with_engines(engines);
 
             // Build a scan plan with optional filter
             let plan = if let Some(pred) = selection {
@@ -1251,6 +3532,7 @@ impl Database {
                     input: Box::new(PhysicalPlan::SeqScan {
                         table_id,
                         schema: schema_names.clone(),
+                        projection: None,
                     }),
                     predicate: resolved_pred,
                 }
@@ -1258,6 +3540,7 @@ impl Database {
                 PhysicalPlan::SeqScan {
                     table_id,
                     schema: schema_names.clone(),
+                    projection: None,
                 }
             };
 
@@ -1278,25 +3561,120 @@ impl Database {
         .await?
     }
 
-    /// Convert an INSERT statement to a Raft Command.
+    /// Convert an INSERT statement to Raft Commands, one per row.
     ///
     /// This resolves table names to IDs and evaluates value expressions.
-    async fn insert_to_command(&self, table: &str, values: &[expr::Expr]) -> Result<Command> {
+    /// All rows are evaluated before any command is proposed, so a bad row
+    /// rejects the whole statement.
+    ///
+    /// NULL values in auto-increment columns are taken from the leader's
+    /// sequences and returned alongside the commands. Unlike local inserts,
+    /// generated values are not checked against existing keys; a collision
+    /// fails when the command is applied.
+    async fn insert_to_commands(
+        &self,
+        table: &str,
+        columns: &[String],
+        rows: Vec<Vec<expr::Expr>>,
+    ) -> Result<(Vec<Command>, Vec<i64>)> {
         let catalog_lock = self.catalog.read().await;
         let table_meta = catalog_lock
             .table(table)
             .map_err(|e| anyhow::anyhow!("table lookup failed: {}", e))?;
         let table_id = table_meta.id;
 
+        // Fill omitted columns the same way the local planner does
+        let rows =
+            Planner::expand_insert_rows(table_meta, columns, rows).map_err(anyhow::Error::from)?;
+
+        let sequences = table_meta
+            .columns()
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, column)| Some((idx, column.sequence.as_deref()?)))
+            .map(|(idx, name)| Ok((idx, catalog_lock.sequence(name)?)))
+            .collect::<Result<Vec<_>, common::DbError>>()?;
+
         // Evaluate value expressions (they should all be literals for now)
-        let row_values: Vec<Value> = values
+        let random = self.session_random();
+        let mut generated_ids = Vec::new();
+        let commands = rows
             .iter()
-            .map(eval_literal_expr)
+            .map(|values| {
+                let mut row = with_session_random(&random, || {
+                    values
+                        .iter()
+                        .map(eval_literal_expr)
+                        .collect::<Result<Vec<_>>>()
+                })?;
+                for &(idx, sequence) in &sequences {
+                    if row.get(idx) == Some(&Value::Null) {
+                        let id = sequence.next_value();
+                        row[idx] = Value::Int(id);
+                        generated_ids.push(id);
+                    }
+                }
+                table_meta
+                    .coerce_types(&mut row)
+                    .and_then(|()| table_meta.check_not_null(&row))
+                    .map_err(anyhow::Error::from)?;
+                Ok(Command::Insert { table_id, row })
+            })
             .collect::<Result<Vec<_>>>()?;
 
-        Ok(Command::Insert {
-            table_id,
-            row: row_values,
+        if !generated_ids.is_empty() {
+            // Persist the advanced sequences before the rows are proposed
+            tokio::task::block_in_place(|| catalog_lock.save(&self.catalog_path))
+                .map_err(anyhow::Error::from)?;
+        }
+        Ok((commands, generated_ids))
+    }
+
+    /// Create the checkpoint handler for the admin HTTP endpoints.
+    ///
+    /// Flushes dirty buffer pool pages, then syncs and truncates the WAL
+    /// since every logged change is now durable in the heap files.
+    /// Runs on a blocking thread, so it uses the blocking lock variants.
+    fn create_checkpoint_handler(pager: SharedPager, wal: Arc<Mutex<Wal>>) -> CheckpointHandler {
+        Arc::new(move || {
+            use buffer::Pager;
+
+            pager.flush().map_err(|e| e.to_string())?;
+            let mut wal = wal.blocking_lock();
+            wal.sync().map_err(|e| e.to_string())?;
+            wal.truncate().map_err(|e| e.to_string())
+        })
+    }
+
+    /// Create the checksum handler for the admin HTTP endpoints.
+    ///
+    /// Runs on a blocking thread, so it uses the blocking lock variants.
+    fn create_checksum_handler(
+        catalog: Arc<RwLock<Catalog>>,
+        pager: SharedPager,
+        wal: Arc<Mutex<Wal>>,
+        engines: Arc<EngineRegistry>,
+        data_dir: Arc<PathBuf>,
+    ) -> ChecksumHandler {
+        Arc::new(move || {
+            let catalog_lock = catalog.blocking_read();
+            consistency::table_checksums(&catalog_lock, &pager, &wal, &engines, &data_dir)
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    /// Create the settings handler for the admin HTTP endpoints.
+    ///
+    /// Applies each change in turn, stopping at the first that fails, and
+    /// returns the settings after them. Runs on a blocking thread.
+    fn create_settings_handler(settings: Arc<GlobalSettings>) -> SettingsHandler {
+        Arc::new(move |changes| {
+            for (name, value) in changes {
+                settings
+                    .set(name, value.as_deref())
+                    .map_err(|e| e.to_string())?;
+            }
+            Ok(settings.get().to_map())
         })
     }
 
@@ -1306,45 +3684,20 @@ impl Database {
     /// the command to actual database storage.
     ///
     /// Note: This uses block_in_place to allow blocking catalog access from async context.
-    fn create_apply_handler(catalog: Arc<RwLock<Catalog>>, data_dir: Arc<PathBuf>) -> ApplyHandler {
+    fn create_apply_handler(
+        catalog: Arc<RwLock<Catalog>>,
+        catalog_epoch: Arc<watch::Sender<u64>>,
+        data_dir: Arc<PathBuf>,
+        catalog_path: Arc<PathBuf>,
+        engines: Arc<EngineRegistry>,
+    ) -> ApplyHandler {
         Arc::new(move |cmd: &Command| {
             // Use block_in_place to safely call blocking operations
             // from within an async runtime context
             tokio::task::block_in_place(|| match cmd {
-                Command::Insert { table_id, row } => {
-                    // Get table metadata
-                    let catalog_lock = catalog.blocking_read();
-                    let table_meta = match catalog_lock.table_by_id(*table_id) {
-                        Ok(t) => t,
-                        Err(e) => {
-                            return CommandResponse::error(format!("table lookup failed: {}", e))
-                        }
-                    };
-
-                    // Open heap file and insert
-                    let file_path = data_dir.join(format!("{}.heap", table_meta.name));
-                    let mut heap_file = match storage::HeapFile::open(&file_path, table_id.0) {
-                        Ok(h) => h,
-                        Err(e) => {
-                            return CommandResponse::error(format!(
-                                "failed to open heap file: {}",
-                                e
-                            ))
-                        }
-                    };
-
-                    let rid = match heap_file.insert(&common::Row::new(row.clone())) {
-                        Ok(r) => r,
-                        Err(e) => return CommandResponse::error(format!("insert failed: {}", e)),
-                    };
-
-                    CommandResponse::insert(rid)
-                }
-                Command::Update {
-                    table_id,
-                    rid,
-                    new_row,
-                } => {
+                Command::Insert { table_id, .. }
+                | Command::Update { table_id, .. }
+                | Command::Delete { table_id, .. } => {
                     // Get table metadata
                     let catalog_lock = catalog.blocking_read();
                     let table_meta = match catalog_lock.table_by_id(*table_id) {
@@ -1354,49 +3707,33 @@ impl Database {
                         }
                     };
 
-                    // Open heap file and update
-                    let file_path = data_dir.join(format!("{}.heap", table_meta.name));
-                    let mut heap_file = match storage::HeapFile::open(&file_path, table_id.0) {
-                        Ok(h) => h,
-                        Err(e) => {
-                            return CommandResponse::error(format!(
-                                "failed to open heap file: {}",
-                                e
-                            ))
-                        }
-                    };
-
-                    let new_row_obj = common::Row::new(new_row.clone());
-                    match heap_file.update(*rid, &new_row_obj) {
-                        Ok(_) => CommandResponse::update(1),
-                        Err(e) => CommandResponse::error(format!("update failed: {}", e)),
-                    }
-                }
-                Command::Delete { table_id, rid } => {
-                    // Get table metadata
-                    let catalog_lock = catalog.blocking_read();
-                    let table_meta = match catalog_lock.table_by_id(*table_id) {
-                        Ok(t) => t,
-                        Err(e) => {
-                            return CommandResponse::error(format!("table lookup failed: {}", e))
-                        }
-                    };
+                    // Open the table's storage and apply the change
+                    let mut heap_file =
+                        match engines.open(&data_dir, table_meta, catalog_lock.encryption_key()) {
+                            Ok(h) => h,
+                            Err(e) => {
+                                return CommandResponse::error(format!(
+                                    "failed to open table storage: {}",
+                                    e
+                                ))
+                            }
+                        };
 
-                    // Open heap file and delete
-                    let file_path = data_dir.join(format!("{}.heap", table_meta.name));
-                    let mut heap_file = match storage::HeapFile::open(&file_path, table_id.0) {
-                        Ok(h) => h,
-                        Err(e) => {
-                            return CommandResponse::error(format!(
-                                "failed to open heap file: {}",
-                                e
-                            ))
-                        }
+                    let action = match cmd {
+                        Command::Insert { .. } => "insert",
+                        Command::Update { .. } => "update",
+                        _ => "delete",
                     };
-
-                    match heap_file.delete(*rid) {
-                        Ok(_) => CommandResponse::delete(1),
-                        Err(e) => CommandResponse::error(format!("delete failed: {}", e)),
+                    match apply_row_command(
+                        &data_dir,
+                        table_meta,
+                        engines.is_durable(table_meta),
+                        catalog_lock.encryption_key(),
+                        heap_file.as_mut(),
+                        cmd,
+                    ) {
+                        Ok(response) => response,
+                        Err(e) => CommandResponse::error(format!("{} failed: {}", action, e)),
                     }
                 }
                 Command::CreateTable { .. }
@@ -1406,26 +3743,492 @@ impl Database {
                     // DDL operations are handled separately
                     CommandResponse::Ddl
                 }
+                Command::AdvanceCatalogEpoch { epoch } => {
+                    // The leader applies its own epoch, which it already has
+                    let mut catalog_lock = catalog.blocking_write();
+                    if catalog_lock.advance_epoch_to(*epoch) {
+                        if let Err(e) = catalog_lock.save(&catalog_path) {
+                            return CommandResponse::error(format!(
+                                "failed to save catalog: {}",
+                                e
+                            ));
+                        }
+                        catalog_epoch.send_replace(*epoch);
+                    }
+                    CommandResponse::Ddl
+                }
             })
         })
     }
 }
 
+impl Drop for Database {
+    /// Save unsaved table activity counters and record a clean manifest, so
+    /// the next open checks exact sizes and checksums.
+    ///
+    /// Skipped while a statement or background re-analysis or vacuum still
+    /// holds the catalog, and on Raft nodes, which can apply replicated writes
+    /// after the database is dropped.
+    fn drop(&mut self) {
+        let analyzing = self.analyzing.lock().unwrap_or_else(|e| e.into_inner());
+        let vacuuming = self.vacuuming.lock().unwrap_or_else(|e| e.into_inner());
+        if self.raft.is_some() || !analyzing.is_empty() || !vacuuming.is_empty() {
+            return;
+        }
+        let Ok(catalog) = self.catalog.try_write() else {
+            return;
+        };
+        if *self.unsaved_writes.get_mut() > 0 {
+            let _ = catalog.save(&self.catalog_path);
+        }
+        let files = manifest::expected_files(
+            &catalog,
+            &self.engines,
+            &self.data_dir,
+            &self.catalog_path,
+            &self.wal_path,
+        );
+        // A failed write leaves the previous manifest, whose checks are only looser
+        let _ = Manifest::capture(&self.data_dir, &files, true).and_then(|manifest| {
+            manifest.save(&self.data_dir.join(MANIFEST_FILE), self.encryption.as_ref())
+        });
+    }
+}
+
+/// Apply a row command from the Raft log to `heap`, keeping the table's
+/// primary key index in step.
+///
+/// Commands write straight to storage rather than through the executor, so
+/// the index is maintained here, and a change that would duplicate a key is
+/// refused. Only tables on durable engines keep an index file; statements on
+/// the others build theirs from the rows. A missing or unreadable file is
+/// rebuilt from the rows before the change is made.
+fn apply_row_command(
+    data_dir: &Path,
+    table: &TableMeta,
+    durable: bool,
+    key: Option<&EncryptionKey>,
+    heap: &mut dyn HeapTable,
+    cmd: &Command,
+) -> DbResult<CommandResponse> {
+    let path = data_dir.join(format!("{}.pk_idx", table.name));
+    let mut pk_index = match &table.primary_key {
+        Some(pk_columns) if durable => {
+            match PrimaryKeyIndex::open(&path, pk_columns.clone(), key) {
+                Ok(index) => Some(index.with_compression(table.compression)),
+                Err(_) => {
+                    let mut index = PrimaryKeyIndex::create(&path, pk_columns.clone(), key)?
+                        .with_compression(table.compression);
+                    index.fill(heap)?;
+                    Some(index)
+                }
+            }
+        }
+        _ => None,
+    };
+    let unused_key = |index: &mut PrimaryKeyIndex, key: &[Value]| -> DbResult<()> {
+        if index.contains(key)? {
+            return Err(DbError::Constraint(format!(
+                "duplicate primary key value: {:?}",
+                key
+            )));
+        }
+        Ok(())
+    };
+
+    let response = match cmd {
+        Command::Insert { row, .. } => {
+            let row = common::Row::new(row.clone());
+            let new_key = match &mut pk_index {
+                Some(index) => {
+                    let new_key = index.extract_key(&row)?;
+                    unused_key(index, &new_key)?;
+                    Some(new_key)
+                }
+                None => None,
+            };
+            let rid = heap.insert(&row)?;
+            if let (Some(index), Some(new_key)) = (&mut pk_index, new_key) {
+                index.insert(new_key, rid)?;
+            }
+            CommandResponse::insert(rid)
+        }
+        Command::Update { rid, new_row, .. } => {
+            let new_row = common::Row::new(new_row.clone());
+            let keys = match &mut pk_index {
+                Some(index) => {
+                    let old_key = index.extract_key(&heap.get(*rid)?)?;
+                    let new_key = index.extract_key(&new_row)?;
+                    if new_key != old_key {
+                        unused_key(index, &new_key)?;
+                    }
+                    Some((old_key, new_key))
+                }
+                None => None,
+            };
+            let new_rid = heap.update(*rid, &new_row)?;
+            if let (Some(index), Some((old_key, new_key))) = (&mut pk_index, keys) {
+                index.remove(&old_key)?;
+                index.insert(new_key, new_rid)?;
+            }
+            CommandResponse::update(1)
+        }
+        Command::Delete { rid, .. } => {
+            let old_key = match &mut pk_index {
+                Some(index) => Some(index.extract_key(&heap.get(*rid)?)?),
+                None => None,
+            };
+            heap.delete(*rid)?;
+            if let (Some(index), Some(old_key)) = (&mut pk_index, old_key) {
+                index.remove(&old_key)?;
+            }
+            CommandResponse::delete(1)
+        }
+        _ => CommandResponse::Ddl,
+    };
+    if let Some(index) = &mut pk_index {
+        index.persist()?;
+    }
+    Ok(response)
+}
+
+/// Base URL of a Raft peer, adding the `http://` scheme if it has none.
+fn peer_url(addr: &str) -> String {
+    if addr.starts_with("http://") || addr.starts_with("https://") {
+        addr.to_string()
+    } else {
+        format!("http://{}", addr)
+    }
+}
+
+/// Empty the index files of tables on engines that do not keep rows across
+/// restarts, so that no index entry outlives the row it points at.
+fn reset_volatile_indexes(
+    catalog: &Catalog,
+    engines: &EngineRegistry,
+    data_dir: &Path,
+) -> Result<()> {
+    let key = catalog.encryption_key();
+    for table in catalog.tables().filter(|table| !engines.is_durable(table)) {
+        for index in &table.indexes {
+            let path = data_dir.join(format!("index_{}.idx", index.id.0));
+            match index.kind {
+                IndexKind::BTree => btree::BTreeIndex::create_with_key(&path, index.id, key)
+                    .and_then(|mut btree| btree.flush())
+                    .map_err(anyhow::Error::from)?,
+                IndexKind::Hash => hash::HashIndex::create_with_key(&path, index.id, key)
+                    .and_then(|mut hash| hash.flush())
+                    .map_err(anyhow::Error::from)?,
+                IndexKind::Bitmap | IndexKind::Trie => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Remove the value at `ordinal` from every row of a table.
+///
+/// Rows only shrink, so each update stays in its slot and record IDs held by
+/// indexes remain valid. Rows too short to hold the column are left alone.
+fn drop_stored_column(heap_file: &mut dyn HeapTable, ordinal: usize) -> Result<()> {
+    let rows = storage::Scan::new(heap_file)
+        .collect::<common::DbResult<Vec<_>>>()
+        .map_err(|e| anyhow::anyhow!("failed to read rows: {}", e))?;
+    for (rid, mut row) in rows {
+        if ordinal < row.values.len() {
+            row.values.remove(ordinal);
+            heap_file
+                .update(rid, &row)
+                .map_err(|e| anyhow::anyhow!("failed to rewrite row: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Convert a parsed column definition into a catalog column.
+/// Type of a column created by CREATE TABLE ... AS to hold `values`: the
+/// type they share, widened across numbers, or TEXT if every value is NULL.
+fn created_column_type<'a>(
+    name: &str,
+    values: impl Iterator<Item = &'a Value>,
+) -> Result<types::SqlType> {
+    use types::SqlType;
+
+    let mut column: Option<SqlType> = None;
+    for value in values {
+        let ty = match value {
+            Value::Null => continue,
+            Value::Int(_) => SqlType::Int,
+            Value::Float(_) => SqlType::Float,
+            Value::Decimal(d) => SqlType::Decimal {
+                precision: types::decimal::MAX_PRECISION,
+                scale: d.scale(),
+            },
+            Value::Text(_) => SqlType::Text,
+            Value::Bool(_) => SqlType::Bool,
+            Value::Date(_) => SqlType::Date,
+            Value::Timestamp(_) => SqlType::Timestamp,
+            Value::Bytes(_) => SqlType::Bytes,
+        };
+        column = Some(match (column, ty) {
+            (None, ty) => ty,
+            (Some(a), b) if a == b => a,
+            (Some(SqlType::Float), b) | (Some(b), SqlType::Float)
+                if matches!(b, SqlType::Int | SqlType::Decimal { .. }) =>
+            {
+                SqlType::Float
+            }
+            (Some(SqlType::Decimal { scale: a, .. }), SqlType::Decimal { scale: b, .. }) => {
+                SqlType::Decimal {
+                    precision: types::decimal::MAX_PRECISION,
+                    scale: a.max(b),
+                }
+            }
+            (Some(decimal @ SqlType::Decimal { .. }), SqlType::Int)
+            | (Some(SqlType::Int), decimal @ SqlType::Decimal { .. }) => decimal,
+            (Some(a), b) => {
+                anyhow::bail!("column '{name}' of the query holds both {a} and {b} values")
+            }
+        });
+    }
+    Ok(column.unwrap_or(SqlType::Text))
+}
+
+fn map_column_def(table: &str, col: &parser::ColumnDef) -> Result<Column> {
+    ensure_not_reserved(&col.name)?;
+    let ty = map_sql_type(&col.ty)?;
+    if col.auto_increment && ty != types::SqlType::Int {
+        anyhow::bail!("auto-increment column '{}' must be INT", col.name);
+    }
+    let mut column = Column::new(col.name.clone(), ty);
+    if col.not_null {
+        column = column.with_not_null();
+    }
+    if col.auto_increment {
+        column = column.with_sequence(catalog::sequence_name(table, &col.name));
+    }
+    let length = types::TextLength::from_type_name(&col.ty);
+    if let Some(length) = length {
+        column = column.with_length(length);
+    }
+    let Some(expr) = &col.default else {
+        return Ok(column);
+    };
+    // Rows that predate an added column read its default, so it is stored
+    // as the column holds it
+    let default = match (eval_literal_expr(expr)?, length) {
+        (Value::Text(text), Some(length)) => Value::Text(length.fit(&text).ok_or_else(|| {
+            anyhow::anyhow!(
+                "default for column '{}' is too long for type {length}",
+                col.name
+            )
+        })?),
+        (default, _) => default,
+    };
+    Ok(column.with_default(default))
+}
+
+fn ensure_not_reserved(column: &str) -> Result<()> {
+    if column.eq_ignore_ascii_case(ROW_VERSION_COLUMN) {
+        anyhow::bail!("column name '{}' is reserved", ROW_VERSION_COLUMN);
+    }
+    Ok(())
+}
+
+/// Gather statistics for `table` and store them in the catalog.
+///
+/// Rows are read under the catalog read lock and the WAL lock, which are
+/// released before the catalog write lock is taken to store the
+/// result. Runs on a blocking thread.
+fn analyze_table(
+    catalog: &RwLock<Catalog>,
+    pager: &SharedPager,
+    wal: &Mutex<Wal>,
+    engines: &Arc<EngineRegistry>,
+    data_dir: &Path,
+    catalog_path: &Path,
+    table: &str,
+) -> Result<()> {
+    let (table_id, statistics, modifications_seen) = {
+        let catalog_lock = catalog.blocking_read();
+        let meta = catalog_lock.table(table).map_err(anyhow::Error::from)?;
+        let modifications_seen = meta.modifications_since_analyze();
+        let plan = PhysicalPlan::SeqScan {
+            table_id: meta.id,
+            schema: meta.columns().iter().map(|c| c.name.clone()).collect(),
+            projection: None,
+        };
+
+        let mut pager = pager.clone();
+        let mut wal_lock = wal.blocking_lock();
+        let mut ctx = ExecutionContext::new(
+            &catalog_lock,
+            &mut pager,
+            wal_lock.deref_mut(),
+            data_dir.to_path_buf(),
+        )
+        .with_engines(engines.clone())
+        .with_priority(Priority::Background);
+        let mut executor = build_executor(plan).map_err(anyhow::Error::from)?;
+        executor.open(&mut ctx).map_err(anyhow::Error::from)?;
+        let mut rows = Vec::new();
+        while let Some(row) = executor.next(&mut ctx).map_err(anyhow::Error::from)? {
+            rows.push(row);
+        }
+        let pages = executor.stats().map_or(0, |stats| stats.pages_scanned);
+        executor.close(&mut ctx).map_err(anyhow::Error::from)?;
+        let usage = ctx
+            .heap_table(meta.id)
+            .and_then(|mut heap| heap.page_usage())
+            .map_err(anyhow::Error::from)?;
+
+        let mut statistics = TableStatistics::from_rows(
+            meta.columns().len(),
+            rows.iter().map(|row| row.values.as_slice()),
+        )
+        .with_page_count(pages);
+        if let Some(usage) = usage {
+            statistics = statistics.with_storage(StorageStatistics::new(
+                usage.pages,
+                usage.used_bytes,
+                storage::PAGE_SIZE as u64,
+                usage.row_sizes,
+            ));
+        }
+        (meta.id, statistics, modifications_seen)
+    };
+
+    let mut catalog_lock = catalog.blocking_write();
+    catalog_lock
+        .set_table_statistics(table_id, statistics, modifications_seen)
+        .map_err(anyhow::Error::from)?;
+    catalog_lock.save(catalog_path).map_err(anyhow::Error::from)
+}
+
 /// Map parser SQL type string to internal SqlType.
 fn map_sql_type(raw: &str) -> Result<types::SqlType> {
-    match raw.trim().to_uppercase().as_str() {
-        "INT" | "INTEGER" => Ok(types::SqlType::Int),
-        "TEXT" | "STRING" | "VARCHAR" => Ok(types::SqlType::Text),
-        "BOOL" | "BOOLEAN" => Ok(types::SqlType::Bool),
-        other => Err(anyhow::anyhow!("unsupported SQL type '{}'", other)),
+    types::SqlType::from_name(raw)
+        .ok_or_else(|| anyhow::anyhow!("unsupported SQL type '{}'", raw.trim().to_uppercase()))
+}
+
+/// Worker threads the sequential scans of `plan` may use.
+///
+/// The INSERT, UPDATE or DELETE that running `stmt` executes, if any: the
+/// statement itself, or the one an EXPLAIN ANALYZE or PROFILE runs.
+fn executed_dml(stmt: &Statement) -> Option<&Statement> {
+    match stmt {
+        Statement::Insert { .. } | Statement::Update { .. } | Statement::Delete { .. } => {
+            Some(stmt)
+        }
+        Statement::Explain {
+            query,
+            analyze: true,
+        }
+        | Statement::Profile { query } => executed_dml(query),
+        _ => None,
+    }
+}
+
+/// The affected count in the single row a DML operator returns.
+fn dml_count(row: &common::Row) -> Option<u64> {
+    match row.values.as_slice() {
+        [Value::Int(count)] => u64::try_from(*count).ok(),
+        _ => None,
+    }
+}
+
+/// The table an INSERT, UPDATE or DELETE writes, and how.
+fn modification(stmt: &Statement) -> Option<(&str, Modification)> {
+    match stmt {
+        Statement::Insert { table, .. } => Some((table, Modification::Insert)),
+        Statement::Update { table, .. } => Some((table, Modification::Update)),
+        Statement::Delete { table, .. } => Some((table, Modification::Delete)),
+        _ => None,
+    }
+}
+
+/// Run the query of an `INSERT ... SELECT` in `ctx` and return an `INSERT`
+/// of its rows as VALUES, so the insert that follows in the same context
+/// sees exactly the rows the query read. Other statements are returned as
+/// they are.
+fn insert_query_values(
+    stmt: Statement,
+    catalog: &Catalog,
+    ctx: &mut ExecutionContext,
+) -> Result<Statement> {
+    let Statement::Insert {
+        table,
+        columns,
+        query: Some(query),
+        ..
+    } = stmt
+    else {
+        return Ok(stmt);
+    };
+    let mut planning_ctx = PlanningContext::new(catalog);
+    let plan = Planner::plan(*query, &mut planning_ctx).map_err(anyhow::Error::from)?;
+    if matches!(
+        plan,
+        PhysicalPlan::Insert { .. } | PhysicalPlan::Update { .. } | PhysicalPlan::Delete { .. }
+    ) {
+        anyhow::bail!("INSERT ... SELECT needs a query that returns rows");
+    }
+    let rows = execute_query(plan, ctx).map_err(anyhow::Error::from)?;
+    Ok(Statement::Insert {
+        table,
+        columns,
+        rows: rows
+            .into_iter()
+            .map(|row| row.values.into_iter().map(expr::Expr::Literal).collect())
+            .collect(),
+        query: None,
+    })
+}
+
+/// Scans under INSERT, UPDATE or DELETE stay serial, since the statement may
+/// write pages of the table being scanned while workers read them.
+fn scan_workers(plan: &PhysicalPlan, max_parallel_workers: usize) -> usize {
+    match plan {
+        PhysicalPlan::Insert { .. } | PhysicalPlan::Update { .. } | PhysicalPlan::Delete { .. } => {
+            0
+        }
+        _ => max_parallel_workers,
     }
 }
 
+tokio::task_local! {
+    /// Priority of the session the running statement belongs to.
+    static STATEMENT_PRIORITY: Priority;
+    /// Principal of the session the running statement belongs to.
+    static STATEMENT_SESSION: String;
+}
+
+/// Priority of the running statement; normal outside of a session.
+fn statement_priority() -> Priority {
+    STATEMENT_PRIORITY
+        .try_with(|priority| *priority)
+        .unwrap_or_default()
+}
+
+/// Session of the running statement; the local one outside of a session.
+fn statement_session() -> String {
+    STATEMENT_SESSION
+        .try_with(String::clone)
+        .unwrap_or_else(|_| LOCAL_PRINCIPAL.to_string())
+}
+
 /// Infer the output schema from a physical plan.
 fn infer_schema(plan: &PhysicalPlan) -> Vec<String> {
     match plan {
         PhysicalPlan::SeqScan { schema, .. } => schema.clone(),
+        PhysicalPlan::PartitionScan { schema, .. } => schema.clone(),
+        PhysicalPlan::SampleScan { schema, .. } => schema.clone(),
         PhysicalPlan::IndexScan { schema, .. } => schema.clone(),
+        PhysicalPlan::IndexUnion { schema, .. } => schema.clone(),
+        PhysicalPlan::SystemScan { schema, .. } => schema.clone(),
+        PhysicalPlan::SeriesScan { schema, .. } | PhysicalPlan::ValuesScan { schema, .. } => {
+            schema.clone()
+        }
         PhysicalPlan::Filter { input, .. } => infer_schema(input),
         PhysicalPlan::Project { columns, .. } => {
             columns.iter().map(|(name, _)| name.clone()).collect()
@@ -1435,7 +4238,24 @@ fn infer_schema(plan: &PhysicalPlan) -> Vec<String> {
         PhysicalPlan::Insert { .. } | PhysicalPlan::Update { .. } | PhysicalPlan::Delete { .. } => {
             vec![]
         }
-        PhysicalPlan::NestedLoopJoin { schema, .. } => schema.clone(),
+        PhysicalPlan::NestedLoopJoin { schema, .. }
+        | PhysicalPlan::HashJoin { schema, .. }
+        | PhysicalPlan::SemiJoin { schema, .. }
+        | PhysicalPlan::AntiJoin { schema, .. }
+        | PhysicalPlan::Aggregate { schema, .. } => schema.clone(),
+    }
+}
+
+/// Run `f` with `RANDOM()` continuing the session's sequence, if
+/// SET random_seed fixed one.
+fn with_session_random<T>(
+    random: &std::sync::Mutex<Option<Generator>>,
+    f: impl FnOnce() -> T,
+) -> T {
+    let mut random = random.lock().unwrap_or_else(|e| e.into_inner());
+    match random.as_mut() {
+        Some(generator) => with_generator(generator, f),
+        None => f(),
     }
 }
 
@@ -1452,6 +4272,22 @@ fn eval_literal_expr(e: &expr::Expr) -> Result<Value> {
                 (expr::UnaryOp::Not, _) => Err(anyhow::anyhow!("NOT requires boolean operand")),
             }
         }
+        expr::Expr::Function { name, args } => {
+            let func = expr::functions::lookup(name)
+                .ok_or_else(|| anyhow::anyhow!("unknown function '{}'", name))?;
+            let values = args
+                .iter()
+                .map(eval_literal_expr)
+                .collect::<Result<Vec<_>>>()?;
+            func.invoke(&values).map_err(anyhow::Error::from)
+        }
+        expr::Expr::Cast { expr, ty } => eval_literal_expr(expr)?
+            .cast(ty)
+            .map_err(|e| anyhow::anyhow!(e)),
+        expr::Expr::Binary { left, op, right } if op.is_arithmetic() => {
+            expr::arithmetic::apply(&eval_literal_expr(left)?, *op, &eval_literal_expr(right)?)
+                .map_err(anyhow::Error::from)
+        }
         _ => Err(anyhow::anyhow!(
             "only literal expressions supported in Raft mode, got {:?}",
             e
@@ -1459,12 +4295,36 @@ fn eval_literal_expr(e: &expr::Expr) -> Result<Value> {
     }
 }
 
-/// Check if a statement is a DML (INSERT/UPDATE/DELETE) operation.
-fn is_dml_statement(stmt: &Statement) -> bool {
-    matches!(
-        stmt,
-        Statement::Insert { .. } | Statement::Update { .. } | Statement::Delete { .. }
-    )
+/// Whether an expression refers to the named column anywhere.
+fn references_column(expr: &expr::Expr, column: &str) -> bool {
+    match expr {
+        expr::Expr::Column { name, .. } => name.eq_ignore_ascii_case(column),
+        expr::Expr::Literal(_) => false,
+        expr::Expr::Unary { expr, .. } => references_column(expr, column),
+        expr::Expr::Binary { left, right, .. } => {
+            references_column(left, column) || references_column(right, column)
+        }
+        expr::Expr::Function { args, .. } => args.iter().any(|a| references_column(a, column)),
+        expr::Expr::Cast { expr, .. } | expr::Expr::IsNull { expr, .. } => {
+            references_column(expr, column)
+        }
+        expr::Expr::Aggregate { arg, .. } => {
+            arg.as_deref().is_some_and(|a| references_column(a, column))
+        }
+        expr::Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => {
+            operand
+                .iter()
+                .chain(else_result)
+                .any(|e| references_column(e, column))
+                || branches.iter().any(|(when, then)| {
+                    references_column(when, column) || references_column(then, column)
+                })
+        }
+    }
 }
 
 /// Resolve a parser expression to a planner ResolvedExpr for use in scans.
@@ -1496,5 +4356,48 @@ fn resolve_expr_for_scan(expr: &expr::Expr, schema: &[String]) -> Result<Resolve
                 right: Box::new(resolved_right),
             })
         }
+        expr::Expr::Function { name, args } => {
+            let args = args
+                .iter()
+                .map(|arg| resolve_expr_for_scan(arg, schema))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(ResolvedExpr::Function {
+                name: name.clone(),
+                args,
+            })
+        }
+        expr::Expr::Cast { expr, ty } => Ok(ResolvedExpr::Cast {
+            expr: Box::new(resolve_expr_for_scan(expr, schema)?),
+            ty: ty.clone(),
+        }),
+        expr::Expr::IsNull { expr, negated } => Ok(ResolvedExpr::IsNull {
+            expr: Box::new(resolve_expr_for_scan(expr, schema)?),
+            negated: *negated,
+        }),
+        expr::Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => {
+            let resolve = |e: &expr::Expr| resolve_expr_for_scan(e, schema);
+            Ok(ResolvedExpr::Case {
+                operand: operand
+                    .as_deref()
+                    .map(|o| resolve(o).map(Box::new))
+                    .transpose()?,
+                branches: branches
+                    .iter()
+                    .map(|(when, then)| Ok((resolve(when)?, resolve(then)?)))
+                    .collect::<Result<Vec<_>>>()?,
+                else_result: else_result
+                    .as_deref()
+                    .map(|e| resolve(e).map(Box::new))
+                    .transpose()?,
+            })
+        }
+        expr::Expr::Aggregate { func, .. } => Err(anyhow::anyhow!(
+            "aggregate function {} is not allowed here",
+            func.name().to_uppercase()
+        )),
     }
 }
@@ -0,0 +1,249 @@
+//! Data directory integrity manifest.
+//!
+//! `manifest.json` in the data directory lists the files the catalog says
+//! should be there: the catalog and WAL, the storage and primary key index of
+//! every table on a durable engine, and every B-tree and hash index file. It
+//! is checked before anything else when the database opens, so a missing or
+//! truncated file is reported by name instead of surfacing later as a failed
+//! query.
+//!
+//! Files are written in place while the database runs, so the manifest
+//! written at open and after every schema change only records lower bounds:
+//! files that only grow must be at least the recorded size, and rewritten
+//! files (the catalog, the WAL, an encrypted primary key index) must exist.
+//! Dropping the [`Database`](crate::Database) records exact sizes and CRC32
+//! checksums, which the next open then requires to match. Raft nodes can
+//! still apply replicated writes after the database is dropped, so their
+//! manifests are never marked clean; the Raft log itself is not listed.
+//!
+//! The manifest names every table, so it is encrypted along with the
+//! catalog when the database has a key.
+//!
+//! A data directory without a manifest, such as one written by an older
+//! version, opens unchecked and gets one.
+
+use std::{
+    fs::{self, File},
+    io::{ErrorKind, Read},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use catalog::{Catalog, EngineKind, IndexKind};
+use common::crypto::{self, EncryptionKey};
+use executor::EngineRegistry;
+use serde::{Deserialize, Serialize};
+
+/// Associated data for an encrypted manifest.
+const MANIFEST_AAD: &[u8] = b"manifest";
+
+/// How a listed file may change while the database is open.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileKind {
+    /// Grows but never shrinks: heap files and index files.
+    Append,
+    /// Replaced or truncated: the catalog and the WAL.
+    Rewritten,
+    /// A directory whose contents the engine manages, e.g. an LSM or
+    /// columnar table.
+    Directory,
+}
+
+/// One file expected in the data directory.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Path relative to the data directory.
+    pub path: String,
+    pub kind: FileKind,
+    /// Size in bytes when the manifest was written; zero for directories.
+    pub size: u64,
+    /// CRC32 of the contents, recorded only in clean manifests.
+    pub checksum: Option<u32>,
+}
+
+/// The files of a data directory at one point in time.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Whether the database was closed right after the manifest was written,
+    /// making sizes and checksums exact.
+    pub clean: bool,
+    pub files: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    /// Record the `expected` files that exist in `data_dir`, checksumming
+    /// them if the manifest is `clean`.
+    pub fn capture(data_dir: &Path, expected: &[(PathBuf, FileKind)], clean: bool) -> Result<Self> {
+        let mut files = Vec::with_capacity(expected.len());
+        for (path, kind) in expected {
+            let metadata = match fs::metadata(path) {
+                Ok(metadata) => metadata,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => {
+                    return Err(e).with_context(|| format!("failed to stat {}", path.display()))
+                }
+            };
+            let (size, checksum) = match kind {
+                FileKind::Directory => (0, None),
+                _ if clean => (metadata.len(), Some(checksum(path)?)),
+                _ => (metadata.len(), None),
+            };
+            files.push(ManifestEntry {
+                path: path
+                    .strip_prefix(data_dir)
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .into_owned(),
+                kind: *kind,
+                size,
+                checksum,
+            });
+        }
+        Ok(Self { clean, files })
+    }
+
+    /// Load the manifest at `path`, or `None` if there is none.
+    pub fn load(path: &Path, key: Option<&EncryptionKey>) -> Result<Option<Self>> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+        };
+        let bytes = crypto::open_file(key, MANIFEST_AAD, bytes)?;
+        let manifest = serde_json::from_slice(&bytes)
+            .with_context(|| format!("failed to parse manifest {}", path.display()))?;
+        Ok(Some(manifest))
+    }
+
+    /// Write the manifest to `path`, encrypted with `key` if one is given,
+    /// replacing the previous one atomically.
+    pub fn save(&self, path: &Path, key: Option<&EncryptionKey>) -> Result<()> {
+        let bytes = crypto::seal_file(key, MANIFEST_AAD, serde_json::to_vec_pretty(self)?)?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, bytes).with_context(|| format!("failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, path).with_context(|| format!("failed to replace {}", path.display()))
+    }
+
+    /// Check that every listed file in `data_dir` is still intact.
+    ///
+    /// # Errors
+    ///
+    /// Fails with one line per missing, truncated or changed file.
+    pub fn verify(&self, data_dir: &Path) -> Result<()> {
+        let mut problems = Vec::new();
+        for entry in &self.files {
+            let path = data_dir.join(&entry.path);
+            let metadata = match fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(e) if e.kind() == ErrorKind::NotFound => {
+                    problems.push(format!("{}: missing", entry.path));
+                    continue;
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("failed to stat {}", path.display()))
+                }
+            };
+            let size = metadata.len();
+            match entry.kind {
+                FileKind::Directory => {
+                    if !metadata.is_dir() {
+                        problems.push(format!("{}: not a directory", entry.path));
+                    }
+                }
+                _ if self.clean && size != entry.size => problems.push(format!(
+                    "{}: {} bytes, expected {}",
+                    entry.path, size, entry.size
+                )),
+                _ if self.clean => {
+                    if entry
+                        .checksum
+                        .is_some_and(|expected| checksum(&path).ok() != Some(expected))
+                    {
+                        problems.push(format!("{}: checksum mismatch", entry.path));
+                    }
+                }
+                FileKind::Append if size < entry.size => problems.push(format!(
+                    "{}: truncated to {} bytes, expected at least {}",
+                    entry.path, size, entry.size
+                )),
+                FileKind::Append | FileKind::Rewritten => {}
+            }
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "data directory {} failed its integrity check:\n  {}\nrestore these files from a \
+             backup, or remove {} to open the directory without checking it",
+            data_dir.display(),
+            problems.join("\n  "),
+            data_dir.join(crate::MANIFEST_FILE).display()
+        )
+    }
+}
+
+/// The files that should be in `data_dir`: the catalog at `catalog_path`,
+/// the WAL at `wal_path`, and the files of every table in `catalog`.
+pub fn expected_files(
+    catalog: &Catalog,
+    engines: &EngineRegistry,
+    data_dir: &Path,
+    catalog_path: &Path,
+    wal_path: &Path,
+) -> Vec<(PathBuf, FileKind)> {
+    let mut files = vec![
+        (catalog_path.to_path_buf(), FileKind::Rewritten),
+        (wal_path.to_path_buf(), FileKind::Rewritten),
+    ];
+    for table in catalog.tables().filter(|table| engines.is_durable(table)) {
+        for (storage, _) in table.storage_units() {
+            match table.engine {
+                EngineKind::Heap => {
+                    files.push((data_dir.join(format!("{storage}.heap")), FileKind::Append))
+                }
+                EngineKind::Lsm => {
+                    files.push((data_dir.join(format!("{storage}.lsm")), FileKind::Directory))
+                }
+                EngineKind::Columnar => files.push((
+                    data_dir.join(format!("{storage}.columnar")),
+                    FileKind::Directory,
+                )),
+                EngineKind::Memory => {}
+            }
+        }
+        if table.primary_key.is_some() {
+            files.push((
+                data_dir.join(format!("{}.pk_idx", table.name)),
+                FileKind::Append,
+            ));
+        }
+        for index in &table.indexes {
+            if matches!(index.kind, IndexKind::BTree | IndexKind::Hash) {
+                files.push((
+                    data_dir.join(format!("index_{}.idx", index.id.0)),
+                    FileKind::Append,
+                ));
+            }
+        }
+    }
+    files
+}
+
+fn checksum(path: &Path) -> Result<u32> {
+    let mut file =
+        File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buf)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(hasher.finalize())
+}
@@ -0,0 +1,362 @@
+//! The self-check run when a database is opened.
+//!
+//! Before serving statements, a [`Database`](crate::Database) being opened
+//! checks each part of its data directory and records the result in a
+//! [`RecoveryReport`], available from
+//! [`Database::recovery_report`](crate::Database::recovery_report):
+//!
+//! - the catalog, which must parse for the database to open at all;
+//! - orphaned files collected from the data directory (see [`crate::gc`]);
+//! - the WAL, read to its end, with a record left half written by a crash
+//!   cut off (see [`Wal::repair_tail`]);
+//! - every page of every heap table, read back (which verifies its
+//!   checksum) and its header and slots checked for sanity;
+//! - the root of every primary key, B+Tree and hash index, with a
+//!   secondary index that cannot be read rebuilt from its table;
+//! - the undo journal of a transaction interrupted while committing, whose
+//!   row changes are rolled back (see [`crate::journal`]).
+//!
+//! Problems that cannot be repaired are reported rather than failing the
+//! open, so the healthy parts of a database stay available; an operator
+//! decides whether a node with a damaged table should serve traffic.
+
+use std::{fmt, path::Path, sync::Arc};
+
+use buffer::SharedPager;
+use catalog::{Catalog, EngineKind, IndexKind, IndexMeta, TableMeta};
+use common::crypto::EncryptionKey;
+use executor::{EngineRegistry, ExecutionContext, PrimaryKeyIndex, UndoRecord};
+use storage::HeapFile;
+use wal::Wal;
+
+use crate::journal::{self, JOURNAL_FILE};
+use crate::{gc::Collected, index_build};
+
+/// The part of the data directory a [`Check`] is about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Component {
+    Catalog,
+    /// A file collected as an orphan.
+    DataDirectory,
+    Wal,
+    /// A table's storage, or one partition's.
+    Table,
+    Index,
+    /// A transaction's undo journal.
+    Transaction,
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Component::Catalog => "catalog",
+            Component::DataDirectory => "data directory",
+            Component::Wal => "WAL",
+            Component::Table => "table",
+            Component::Index => "index",
+            Component::Transaction => "transaction",
+        })
+    }
+}
+
+/// What a check found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// Nothing wrong.
+    Ok,
+    /// Something was wrong and has been fixed.
+    Repaired(String),
+    /// Not checked, e.g. storage kept in memory.
+    Skipped(String),
+    /// Something is wrong and was left as found.
+    Damaged(String),
+}
+
+/// The result of checking one part of the data directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Check {
+    pub component: Component,
+    /// The table, index or file checked.
+    pub target: String,
+    pub outcome: Outcome,
+}
+
+/// What the self-check found when the database was opened (see the
+/// [module docs](self)).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RecoveryReport {
+    pub checks: Vec<Check>,
+}
+
+impl RecoveryReport {
+    fn push(&mut self, component: Component, target: impl Into<String>, outcome: Outcome) {
+        self.checks.push(Check {
+            component,
+            target: target.into(),
+            outcome,
+        });
+    }
+
+    /// Whether nothing was found damaged. Repaired problems do not count.
+    pub fn is_healthy(&self) -> bool {
+        self.damaged().next().is_none()
+    }
+
+    /// Checks that found a problem and fixed it.
+    pub fn repaired(&self) -> impl Iterator<Item = &Check> {
+        self.checks
+            .iter()
+            .filter(|check| matches!(check.outcome, Outcome::Repaired(_)))
+    }
+
+    /// Checks that found a problem and left it.
+    pub fn damaged(&self) -> impl Iterator<Item = &Check> {
+        self.checks
+            .iter()
+            .filter(|check| matches!(check.outcome, Outcome::Damaged(_)))
+    }
+}
+
+impl fmt::Display for RecoveryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            write!(f, "{} {}: ", check.component, check.target)?;
+            match &check.outcome {
+                Outcome::Ok => writeln!(f, "ok")?,
+                Outcome::Repaired(what) => writeln!(f, "repaired, {what}")?,
+                Outcome::Skipped(why) => writeln!(f, "skipped, {why}")?,
+                Outcome::Damaged(what) => writeln!(f, "DAMAGED, {what}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks the database runs while opening, in the order it runs them.
+pub(crate) struct SelfCheck<'a> {
+    pub(crate) report: RecoveryReport,
+    pub(crate) data_dir: &'a Path,
+    pub(crate) key: Option<&'a EncryptionKey>,
+}
+
+impl SelfCheck<'_> {
+    /// Record that the catalog at `path` was loaded.
+    pub(crate) fn catalog(&mut self, path: &Path) {
+        self.report
+            .push(Component::Catalog, file_name(path), Outcome::Ok);
+    }
+
+    pub(crate) fn collected(&mut self, collected: &[Collected]) {
+        for file in collected {
+            self.report.push(
+                Component::DataDirectory,
+                file.file.clone(),
+                Outcome::Repaired(format!("orphaned file {}", file.action)),
+            );
+        }
+    }
+
+    /// Check the WAL, cutting off a torn record. Run before the WAL is
+    /// opened for appending.
+    pub(crate) fn wal(&mut self, path: &Path) {
+        let outcome = match Wal::repair_tail(path, self.key) {
+            Ok(tail) if tail.trimmed == 0 => Outcome::Ok,
+            Ok(tail) => Outcome::Repaired(format!(
+                "cut off {} bytes of a torn record after {} complete records",
+                tail.trimmed, tail.records
+            )),
+            Err(e) => Outcome::Damaged(e.to_string()),
+        };
+        self.report.push(Component::Wal, file_name(path), outcome);
+    }
+
+    /// Roll back the row changes of a transaction that a crash interrupted
+    /// while it committed, then remove its journal. Run once the WAL is
+    /// open, since the rollback is logged like any other write.
+    pub(crate) fn transaction(
+        &mut self,
+        catalog: &Catalog,
+        pager: &SharedPager,
+        wal: &mut Wal,
+        engines: &Arc<EngineRegistry>,
+    ) {
+        let outcome = match journal::load(self.data_dir, self.key) {
+            Ok(None) => return,
+            Ok(Some(undo)) => {
+                let undo: Vec<UndoRecord> = undo
+                    .into_iter()
+                    .filter(|record| {
+                        let (UndoRecord::Insert { table, .. }
+                        | UndoRecord::Update { table, .. }
+                        | UndoRecord::Delete { table, .. }) = record;
+                        catalog
+                            .table_by_id(*table)
+                            .is_ok_and(|table| engines.is_durable(table))
+                    })
+                    .collect();
+                let changes = undo.len();
+                let mut pager = pager.clone();
+                let mut ctx =
+                    ExecutionContext::new(catalog, &mut pager, wal, self.data_dir.to_path_buf())
+                        .with_engines(engines.clone());
+                match executor::roll_back(&mut ctx, undo) {
+                    Ok(()) => Outcome::Repaired(format!(
+                        "rolled back {changes} row changes of a transaction interrupted while committing"
+                    )),
+                    Err(e) => Outcome::Damaged(format!(
+                        "could not roll back a transaction interrupted while committing: {e}"
+                    )),
+                }
+            }
+            Err(e) => Outcome::Damaged(e.to_string()),
+        };
+        // Rolled back or not, the journal would only be applied again
+        let outcome = match journal::remove(self.data_dir) {
+            Ok(()) => outcome,
+            Err(e) => Outcome::Damaged(e.to_string()),
+        };
+        self.report
+            .push(Component::Transaction, JOURNAL_FILE, outcome);
+    }
+
+    /// Check the storage and indexes of every table.
+    pub(crate) fn tables(&mut self, catalog: &Catalog, engines: &EngineRegistry) {
+        for table in catalog.tables() {
+            let durable = engines.is_durable(table);
+            let heap_ok = self.storage(table, durable);
+            if !durable {
+                for index in &table.indexes {
+                    self.report.push(
+                        Component::Index,
+                        index.name.clone(),
+                        Outcome::Skipped("its table is not stored on disk".into()),
+                    );
+                }
+                continue;
+            }
+            self.primary_key(table);
+            for index in &table.indexes {
+                self.index(table, index, engines, heap_ok);
+            }
+        }
+    }
+
+    /// Check every page of a table's storage units, returning whether all
+    /// of them are intact.
+    fn storage(&mut self, table: &TableMeta, durable: bool) -> bool {
+        let mut intact = true;
+        for (storage, id) in table.storage_units() {
+            let outcome = match table.engine {
+                _ if !durable => Outcome::Skipped("stored in memory".into()),
+                EngineKind::Heap => {
+                    let path = self.data_dir.join(format!("{storage}.heap"));
+                    check_heap(&path, id.0, self.key)
+                }
+                engine => Outcome::Skipped(format!("{engine:?} storage is not checked")),
+            };
+            intact &= !matches!(outcome, Outcome::Damaged(_));
+            self.report.push(Component::Table, storage, outcome);
+        }
+        intact
+    }
+
+    fn primary_key(&mut self, table: &TableMeta) {
+        let Some(pk_columns) = &table.primary_key else {
+            return;
+        };
+        let path = self.data_dir.join(format!("{}.pk_idx", table.name));
+        let outcome = match PrimaryKeyIndex::open(&path, pk_columns.clone(), self.key) {
+            Ok(_) => Outcome::Ok,
+            Err(e) => Outcome::Damaged(e.to_string()),
+        };
+        self.report.push(
+            Component::Index,
+            format!("{} primary key", table.name),
+            outcome,
+        );
+    }
+
+    /// Check that an index's root can be read, rebuilding it from its table
+    /// if not and the table is intact.
+    fn index(
+        &mut self,
+        table: &TableMeta,
+        index: &IndexMeta,
+        engines: &EngineRegistry,
+        heap_ok: bool,
+    ) {
+        let path = self.data_dir.join(format!("index_{}.idx", index.id.0));
+        let readable = match index.kind {
+            IndexKind::BTree => btree::BTreeIndex::open_with_key(&path, index.id, self.key)
+                .and_then(|mut btree| btree.search(&[]).map(drop)),
+            IndexKind::Hash => hash::HashIndex::open_with_key(&path, index.id, self.key).map(drop),
+            IndexKind::Bitmap | IndexKind::Trie => {
+                let outcome = Outcome::Skipped("kept in memory".into());
+                self.report
+                    .push(Component::Index, index.name.clone(), outcome);
+                return;
+            }
+        };
+        let outcome = match readable {
+            Ok(()) => Outcome::Ok,
+            Err(e) if !heap_ok => Outcome::Damaged(format!("{e}; its table is damaged too")),
+            Err(e) => match self.rebuild(table, index, engines, &path) {
+                Ok(()) => Outcome::Repaired(format!("rebuilt from its table ({e})")),
+                Err(rebuild) => Outcome::Damaged(format!("{e}; rebuilding failed: {rebuild}")),
+            },
+        };
+        self.report
+            .push(Component::Index, index.name.clone(), outcome);
+    }
+
+    fn rebuild(
+        &self,
+        table: &TableMeta,
+        index: &IndexMeta,
+        engines: &EngineRegistry,
+        path: &Path,
+    ) -> anyhow::Result<()> {
+        let columns: Vec<usize> = index.columns.iter().map(|c| *c as usize).collect();
+        let entries = index_build::collect_entries(
+            engines,
+            self.data_dir,
+            table,
+            self.key,
+            &columns,
+            index_build::build_threads(),
+        )?;
+        index_build::write_index(
+            &index.kind,
+            path,
+            index.id,
+            table.compression,
+            self.key,
+            entries,
+        )
+    }
+}
+
+/// Read every page of a heap file back, checking its checksum, header and
+/// slots.
+fn check_heap(path: &Path, table_id: u64, key: Option<&EncryptionKey>) -> Outcome {
+    let damaged =
+        HeapFile::open_with_key(path, table_id, key).and_then(|mut heap| heap.check_pages());
+    match damaged {
+        Ok(damaged) if damaged.is_empty() => Outcome::Ok,
+        Ok(damaged) => Outcome::Damaged(
+            damaged
+                .iter()
+                .map(|(page, problem)| format!("page {page}: {problem}"))
+                .collect::<Vec<_>>()
+                .join("; "),
+        ),
+        Err(e) => Outcome::Damaged(e.to_string()),
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
@@ -0,0 +1,520 @@
+//! Logical replication of tables from one database to another.
+//!
+//! Raft keeps the members of one cluster identical. Logical replication
+//! instead copies the changes to some tables of one database, the
+//! publisher, into another, the subscriber, which can belong to a different
+//! cluster and hold tables of its own. The subscriber pulls changes at its
+//! own pace, so it lags behind the publisher rather than slowing its writes.
+//!
+//! A [`Publication`] names the tables to publish.
+//! [`Database::read_changes`](crate::Database::read_changes) reads the
+//! committed changes to them from the publisher's WAL, starting at a position
+//! in the log, as a [`ChangeBatch`] that can be serialized and sent to
+//! another process. [`Database::apply_changes`](crate::Database::apply_changes)
+//! applies a batch on the subscriber and advances its [`Subscription`], which
+//! remembers the position to read from next and is saved in the subscriber's
+//! data directory.
+//!
+//! Changes identify rows by their record ID on the publisher. The subscriber
+//! applies them through its own executor, so its indexes and WAL are kept as
+//! for any other write, and finds the rows they change by primary key:
+//! published tables must have a primary key, and must exist on the
+//! subscriber with the same columns. Schema changes are not replicated.
+//!
+//! Only the changes still in the publisher's WAL can be read. A checkpoint
+//! truncates the log, after which a subscription must be recreated from a
+//! copy of the tables (see [`crate::snapshot`]). Tables on the memory engine are not logged, and Raft
+//! nodes do not log replicated writes, so neither can be published.
+//!
+//! The subscription is saved before and after each batch. A subscriber that
+//! stops in between reapplies the batch when it resumes, replacing the rows
+//! it inserted the first time rather than failing on their keys.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use catalog::{Catalog, EngineKind, TableMeta};
+use common::{ColumnId, RecordId};
+use expr::BinaryOp;
+use planner::{PhysicalPlan, ResolvedExpr};
+use serde::{Deserialize, Serialize};
+use types::Value;
+use wal::{Wal, WalRecord};
+
+use crate::snapshot::TableSnapshot;
+
+/// Most WAL records read into one [`ChangeBatch`].
+pub const MAX_BATCH_RECORDS: usize = 1000;
+
+/// Tables whose committed changes a publisher sends to its subscribers.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Publication {
+    name: String,
+    tables: BTreeSet<String>,
+}
+
+impl Publication {
+    /// A publication named `name` of `tables`.
+    pub fn new<T: Into<String>>(
+        name: impl Into<String>,
+        tables: impl IntoIterator<Item = T>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            tables: tables
+                .into_iter()
+                .map(|table| table.into().to_lowercase())
+                .collect(),
+        }
+    }
+
+    /// Name of the publication, which subscriptions refer to it by.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The published tables, in name order.
+    pub fn tables(&self) -> impl Iterator<Item = &str> {
+        self.tables.iter().map(String::as_str)
+    }
+}
+
+/// A committed change to a row of a published table, which is identified by
+/// its record ID on the publisher.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Change {
+    Insert {
+        table: String,
+        rid: RecordId,
+        row: Vec<Value>,
+    },
+    Update {
+        table: String,
+        rid: RecordId,
+        row: Vec<Value>,
+    },
+    Delete {
+        table: String,
+        rid: RecordId,
+    },
+}
+
+impl Change {
+    /// The table the change is to.
+    pub fn table(&self) -> &str {
+        match self {
+            Change::Insert { table, .. }
+            | Change::Update { table, .. }
+            | Change::Delete { table, .. } => table,
+        }
+    }
+}
+
+/// Changes to a publication's tables read from the publisher's WAL, in the
+/// order they were committed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChangeBatch {
+    /// Name of the publication the changes were read for.
+    pub publication: String,
+    /// Position in the WAL the batch was read from.
+    pub start: u64,
+    /// Each change with the position in the WAL just past it.
+    pub changes: Vec<(u64, Change)>,
+    /// Position in the WAL to read the next batch from.
+    pub end: u64,
+}
+
+impl ChangeBatch {
+    /// A batch that inserts the rows of `snapshot`, one of `publication`'s
+    /// tables, into a subscription at position `start` and moves it to the
+    /// position the snapshot was taken at.
+    ///
+    /// This starts a new subscription, or restarts one whose position the
+    /// publisher's WAL no longer holds, from a copy of the table; rows the
+    /// subscriber already holds with the same keys must be deleted first.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the snapshot's table is not published by `publication`.
+    pub fn from_snapshot(
+        publication: &Publication,
+        start: u64,
+        snapshot: TableSnapshot,
+    ) -> Result<Self> {
+        if !publication.tables.contains(snapshot.table()) {
+            bail!(
+                "table '{}' is not published by '{}'",
+                snapshot.table(),
+                publication.name
+            );
+        }
+        let table = snapshot.table().to_string();
+        let end = snapshot.position();
+        Ok(Self {
+            publication: publication.name.clone(),
+            start,
+            changes: snapshot
+                .into_iter()
+                .map(|(rid, row)| {
+                    let table = table.clone();
+                    (end, Change::Insert { table, rid, row })
+                })
+                .collect(),
+            end,
+        })
+    }
+
+    /// Whether the batch holds no changes.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Read the changes to `publication`'s tables committed to `wal` after
+/// position `from`, at most [`MAX_BATCH_RECORDS`] records' worth.
+///
+/// Changes to tables that have since been dropped are skipped.
+///
+/// # Errors
+///
+/// Fails if a published table does not exist or cannot be published, or if
+/// `from` is not a position in the WAL, for example because the WAL was
+/// truncated since it was read.
+pub fn read_changes(
+    catalog: &Catalog,
+    wal: &Wal,
+    publication: &Publication,
+    from: u64,
+) -> Result<ChangeBatch> {
+    for name in publication.tables() {
+        check_publishable(catalog.table(name)?)?;
+    }
+    let records = wal
+        .read_from(from)
+        .with_context(|| format!("cannot read publication '{}'", publication.name))?;
+
+    let mut batch = ChangeBatch {
+        publication: publication.name.clone(),
+        start: from,
+        changes: Vec::new(),
+        end: from,
+    };
+    for (end, record) in records.into_iter().take(MAX_BATCH_RECORDS) {
+        batch.end = end;
+        let table_id = match &record {
+            WalRecord::Insert { table, .. }
+            | WalRecord::Update { table, .. }
+            | WalRecord::Delete { table, .. } => *table,
+            WalRecord::CreateTable { .. } | WalRecord::DropTable { .. } => continue,
+        };
+        let Ok(table) = catalog.table_by_id(table_id) else {
+            continue;
+        };
+        if !publication.tables.contains(&table.name) {
+            continue;
+        }
+        let table = table.name.clone();
+        let change = match record {
+            WalRecord::Insert { rid, row, .. } => Change::Insert { table, rid, row },
+            WalRecord::Update { rid, new_row, .. } => Change::Update {
+                table,
+                rid,
+                row: new_row,
+            },
+            WalRecord::Delete { rid, .. } => Change::Delete { table, rid },
+            WalRecord::CreateTable { .. } | WalRecord::DropTable { .. } => continue,
+        };
+        batch.changes.push((end, change));
+    }
+    Ok(batch)
+}
+
+/// Check that `table` can be published.
+fn check_publishable(table: &TableMeta) -> Result<()> {
+    if table.primary_key.is_none() {
+        bail!(
+            "table '{}' has no primary key and cannot be published",
+            table.name
+        );
+    }
+    if table.engine == EngineKind::Memory {
+        bail!(
+            "table '{}' is not logged to the WAL and cannot be published",
+            table.name
+        );
+    }
+    Ok(())
+}
+
+/// A subscriber's progress through a publication's changes.
+#[derive(Debug)]
+pub struct Subscription {
+    publication: String,
+    path: PathBuf,
+    /// Position in the publisher's WAL of the next batch to apply
+    position: u64,
+    /// End of a batch that may have been applied in part
+    applying: Option<u64>,
+    /// Primary key of each replicated row, by table and record ID on the
+    /// publisher
+    keys: HashMap<(String, RecordId), Vec<Value>>,
+}
+
+/// Form in which a [`Subscription`] is saved.
+#[derive(Serialize, Deserialize)]
+struct SavedSubscription {
+    publication: String,
+    position: u64,
+    applying: Option<u64>,
+    keys: Vec<(String, RecordId, Vec<Value>)>,
+}
+
+/// The writes that apply one change on the subscriber.
+pub(crate) struct ChangePlan {
+    /// DML plans, executed in order.
+    pub plans: Vec<PhysicalPlan>,
+    /// Primary key of the row after the change, or `None` if it was deleted.
+    pub key: Option<Vec<Value>>,
+}
+
+impl Subscription {
+    /// Open the subscription to `publication` saved in `data_dir`, or start a
+    /// new one at the beginning of the publisher's WAL.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the publication's name is not made of ASCII letters, digits
+    /// and underscores, or the saved subscription cannot be read.
+    pub fn open(data_dir: &Path, publication: &str) -> Result<Self> {
+        if publication.is_empty()
+            || !publication
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            bail!("invalid publication name '{publication}'");
+        }
+        let path = data_dir.join(format!("subscription_{publication}.json"));
+        let saved: SavedSubscription = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .with_context(|| format!("invalid subscription {}", path.display()))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => SavedSubscription {
+                publication: publication.to_string(),
+                position: 0,
+                applying: None,
+                keys: Vec::new(),
+            },
+            Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+        };
+        Ok(Self {
+            publication: saved.publication,
+            path,
+            position: saved.position,
+            applying: saved.applying,
+            keys: saved
+                .keys
+                .into_iter()
+                .map(|(table, rid, key)| ((table, rid), key))
+                .collect(),
+        })
+    }
+
+    /// Name of the publication subscribed to.
+    pub fn publication(&self) -> &str {
+        &self.publication
+    }
+
+    /// Position in the publisher's WAL to read the next batch from.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Start applying `batch`, returning the end of the batch that was being
+    /// applied when the subscriber last stopped, if it did so mid-batch.
+    pub(crate) fn begin(&mut self, batch: &ChangeBatch) -> Result<Option<u64>> {
+        if batch.publication != self.publication {
+            bail!(
+                "batch of publication '{}' cannot be applied to a subscription to '{}'",
+                batch.publication,
+                self.publication
+            );
+        }
+        if batch.start != self.position {
+            bail!(
+                "batch starts at position {} but the subscription is at {}",
+                batch.start,
+                self.position
+            );
+        }
+        let interrupted = self.applying;
+        self.applying = Some(interrupted.map_or(batch.end, |end| end.max(batch.end)));
+        self.save()?;
+        Ok(interrupted)
+    }
+
+    /// Plan the writes that apply `change` to the tables in `catalog`. A
+    /// `replayed` change may have been applied already.
+    pub(crate) fn plan(
+        &self,
+        catalog: &Catalog,
+        change: &Change,
+        replayed: bool,
+    ) -> Result<ChangePlan> {
+        let table = catalog.table(change.table())?;
+        let Some(key_columns) = &table.primary_key else {
+            bail!(
+                "table '{}' has no primary key on the subscriber",
+                table.name
+            );
+        };
+        let replicated_key = |rid: &RecordId| {
+            self.keys.get(&(table.name.clone(), *rid)).with_context(|| {
+                format!(
+                    "row {}:{} of table '{}' was not replicated to this subscriber",
+                    rid.page_id.0, rid.slot, table.name
+                )
+            })
+        };
+
+        match change {
+            Change::Insert { row, .. } => {
+                check_width(table, row)?;
+                let key = key_of(key_columns, row);
+                let mut plans = Vec::new();
+                if replayed {
+                    plans.push(PhysicalPlan::Delete {
+                        table_id: table.id,
+                        predicate: Some(key_predicate(key_columns, &key)),
+                        source: None,
+                    });
+                }
+                plans.push(PhysicalPlan::Insert {
+                    table_id: table.id,
+                    rows: vec![row.iter().cloned().map(ResolvedExpr::Literal).collect()],
+                });
+                Ok(ChangePlan {
+                    plans,
+                    key: Some(key),
+                })
+            }
+            Change::Update { rid, row, .. } => {
+                check_width(table, row)?;
+                let old_key = replicated_key(rid)?;
+                let plan = PhysicalPlan::Update {
+                    table_id: table.id,
+                    // Primary keys cannot be updated, so only the other
+                    // columns can have changed
+                    assignments: row
+                        .iter()
+                        .enumerate()
+                        .map(|(column, value)| (column as ColumnId, value))
+                        .filter(|(column, _)| !key_columns.contains(column))
+                        .map(|(column, value)| (column, ResolvedExpr::Literal(value.clone())))
+                        .collect(),
+                    predicate: Some(key_predicate(key_columns, old_key)),
+                    source: None,
+                };
+                Ok(ChangePlan {
+                    plans: vec![plan],
+                    key: Some(key_of(key_columns, row)),
+                })
+            }
+            Change::Delete { rid, .. } => {
+                let old_key = replicated_key(rid)?;
+                Ok(ChangePlan {
+                    plans: vec![PhysicalPlan::Delete {
+                        table_id: table.id,
+                        predicate: Some(key_predicate(key_columns, old_key)),
+                        source: None,
+                    }],
+                    key: None,
+                })
+            }
+        }
+    }
+
+    /// Record that `change`, which ends at position `end`, was applied and
+    /// left its row with primary key `key`.
+    pub(crate) fn applied(&mut self, change: Change, end: u64, key: Option<Vec<Value>>) {
+        let (Change::Insert { table, rid, .. }
+        | Change::Update { table, rid, .. }
+        | Change::Delete { table, rid }) = change;
+        match key {
+            Some(key) => self.keys.insert((table, rid), key),
+            None => self.keys.remove(&(table, rid)),
+        };
+        self.position = end;
+    }
+
+    /// Finish applying a batch that ends at position `end`.
+    pub(crate) fn finish(&mut self, end: u64) -> Result<()> {
+        self.position = end;
+        if self.applying.is_some_and(|applying| applying <= end) {
+            self.applying = None;
+        }
+        self.save()
+    }
+
+    /// Save the subscription, replacing the previous copy in one step.
+    pub(crate) fn save(&self) -> Result<()> {
+        let saved = SavedSubscription {
+            publication: self.publication.clone(),
+            position: self.position,
+            applying: self.applying,
+            keys: self
+                .keys
+                .iter()
+                .map(|((table, rid), key)| (table.clone(), *rid, key.clone()))
+                .collect(),
+        };
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(&saved)?)
+            .with_context(|| format!("failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("failed to replace {}", self.path.display()))
+    }
+}
+
+/// Check that a replicated row fits `table`.
+fn check_width(table: &TableMeta, row: &[Value]) -> Result<()> {
+    let columns = table.columns().len();
+    if row.len() != columns {
+        bail!(
+            "table '{}' has {} columns on the subscriber but {} on the publisher",
+            table.name,
+            columns,
+            row.len()
+        );
+    }
+    Ok(())
+}
+
+/// The primary key values of `row`.
+fn key_of(key_columns: &[ColumnId], row: &[Value]) -> Vec<Value> {
+    key_columns
+        .iter()
+        .map(|&column| row[usize::from(column)].clone())
+        .collect()
+}
+
+/// A predicate matching the row whose primary key is `key`.
+fn key_predicate(key_columns: &[ColumnId], key: &[Value]) -> ResolvedExpr {
+    key_columns
+        .iter()
+        .zip(key)
+        .map(|(&column, value)| ResolvedExpr::Binary {
+            left: Box::new(ResolvedExpr::Column(column)),
+            op: BinaryOp::Eq,
+            right: Box::new(ResolvedExpr::Literal(value.clone())),
+        })
+        .reduce(|left, right| ResolvedExpr::Binary {
+            left: Box::new(left),
+            op: BinaryOp::And,
+            right: Box::new(right),
+        })
+        .unwrap_or(ResolvedExpr::Literal(Value::Bool(false)))
+}
@@ -0,0 +1,120 @@
+//! Opt-in retry of statements that failed for transient reasons.
+//!
+//! A statement is retried only when its error proves it never touched any
+//! data, so retrying cannot apply it twice. Today that is a
+//! [`NotLeaderError`]: routing rejects the statement before it runs, and
+//! during a leader change the same node may be able to serve it a moment
+//! later (an election finishes, or a linearizable read can confirm
+//! leadership again). Failures raised after execution starts, such as a Raft
+//! write that loses its leader part-way through a multi-row statement, have
+//! an unknown outcome and are returned as-is. A [`VersionConflict`] is not
+//! transient either: re-running the same UPDATE would match the same stale
+//! version.
+//!
+//! Backoff grows exponentially from [`RetryPolicy::initial_backoff`] up to
+//! [`RetryPolicy::max_backoff`], and each delay is drawn uniformly from
+//! `[0, backoff]` ("full jitter") so competing clients spread out.
+//!
+//! [`VersionConflict`]: crate::VersionConflict
+
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::routing::NotLeaderError;
+
+/// How often and how patiently to retry transient failures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one.
+    pub max_attempts: u32,
+    /// Upper bound of the delay before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound of any single delay.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy with the given number of attempts and default backoff.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    /// Set the backoff bounds.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Upper bound of the delay after the given failed attempt (1-based).
+    pub fn backoff_ceiling(&self, attempt: u32) -> Duration {
+        let factor = 1u32 << attempt.saturating_sub(1).min(16);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Jittered delay after the given failed attempt, in `[0, ceiling]`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self.backoff_ceiling(attempt);
+        let nanos = ceiling.as_nanos() as u64;
+        if nanos == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_nanos(random_u64() % (nanos + 1))
+    }
+
+    /// Run `op` until it succeeds, fails with a non-retryable error, or the
+    /// attempts are used up.
+    pub async fn run<T, F, Fut>(&self, mut op: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match op().await {
+                Err(e) if attempt < self.max_attempts && is_retryable(&e) => {
+                    tokio::time::sleep(self.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Whether an error guarantees the statement was not applied and may run
+/// again unchanged.
+pub fn is_retryable(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<NotLeaderError>().is_some()
+}
+
+/// Cheap per-call randomness for jitter; not suitable for anything else.
+fn random_u64() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    hasher.finish()
+}
@@ -0,0 +1,229 @@
+//! Statement routing for Raft mode.
+//!
+//! Every statement is classified as a read, a locking read, a write, or DDL
+//! and then routed according to this node's view of the cluster and the
+//! configured [`ReadConsistency`]:
+//!
+//! | class        | Raft disabled | leader            | follower                     |
+//! |--------------|---------------|-------------------|------------------------------|
+//! | read         | local         | local / confirmed | local, or redirect to leader |
+//! | locking read | local         | local             | redirect to leader           |
+//! | write        | local         | replicate         | redirect to leader           |
+//! | DDL          | local         | local             | local                        |
+//! | session      | local         | local             | local                        |
+//!
+//! DDL is not replicated through the log yet, so it is always applied on the
+//! node that received it.
+//!
+//! Routing decisions are made by the pure [`route`] function so they can be
+//! tested without a running cluster. A follower never proxies statements
+//! itself; it rejects them with a [`NotLeaderError`] that carries the leader's
+//! ID and Raft address so the client can retry there.
+
+use std::fmt;
+use std::str::FromStr;
+
+use parser::Statement;
+
+/// Coarse classification of a statement for routing purposes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatementClass {
+    /// SELECT, EXPLAIN, COPY ... TO and SHOW TABLE STATS: never modifies
+    /// table data.
+    Read,
+    /// SELECT ... FOR SHARE / FOR UPDATE: reads rows that the caller intends
+    /// to modify, so it must see the leader's state.
+    LockingRead,
+    /// INSERT, UPDATE, DELETE: must be replicated through Raft.
+    Write,
+    /// CREATE/DROP/ALTER TABLE, CREATE/DROP INDEX, ANALYZE, VACUUM and
+    /// ADMIN GC: applied locally on the receiving node.
+    Ddl,
+    /// SET TRANSACTION: changes this handle's settings, touches no data.
+    Session,
+}
+
+impl StatementClass {
+    /// Classify a parsed statement.
+    ///
+    /// EXPLAIN ANALYZE and PROFILE run their statement, so they are
+    /// classified as that statement: of INSERT, UPDATE or DELETE they are
+    /// writes. A plain EXPLAIN only plans, so it is a read.
+    pub fn of(stmt: &Statement) -> Self {
+        match stmt {
+            Statement::Insert { .. } | Statement::Update { .. } | Statement::Delete { .. } => {
+                StatementClass::Write
+            }
+            Statement::CreateTable { .. }
+            | Statement::CreateTableAs { .. }
+            | Statement::DropTable { .. }
+            | Statement::AlterTable { .. }
+            | Statement::CreateIndex { .. }
+            | Statement::DropIndex { .. }
+            | Statement::CreateView { .. }
+            | Statement::DropView { .. }
+            | Statement::Analyze { .. }
+            | Statement::Vacuum { .. }
+            | Statement::AdminGc => StatementClass::Ddl,
+            Statement::SetTransaction { .. }
+            | Statement::SetRandomSeed { .. }
+            | Statement::SetPriority { .. }
+            | Statement::SetGlobal { .. } => StatementClass::Session,
+            Statement::Explain {
+                query,
+                analyze: true,
+            }
+            | Statement::Profile { query } => StatementClass::of(query),
+            Statement::Select { lock: Some(_), .. } => StatementClass::LockingRead,
+            Statement::Select { .. }
+            | Statement::Explain { .. }
+            | Statement::CopyTo { .. }
+            | Statement::ShowTableStats { .. } => StatementClass::Read,
+        }
+    }
+}
+
+/// How fresh reads must be in Raft mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReadConsistency {
+    /// Serve reads from the local state machine on any node (may be stale on
+    /// followers).
+    #[default]
+    Local,
+    /// Serve reads only on the node that believes it is leader.
+    Leader,
+    /// Serve reads only on the leader, after confirming leadership with a
+    /// quorum and waiting for the state machine to catch up.
+    Linearizable,
+}
+
+impl fmt::Display for ReadConsistency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ReadConsistency::Local => "local",
+            ReadConsistency::Leader => "leader",
+            ReadConsistency::Linearizable => "linearizable",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for ReadConsistency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "local" => Ok(ReadConsistency::Local),
+            "leader" => Ok(ReadConsistency::Leader),
+            "linearizable" => Ok(ReadConsistency::Linearizable),
+            other => Err(format!(
+                "unknown read consistency '{}', expected local, leader or linearizable",
+                other
+            )),
+        }
+    }
+}
+
+/// This node's view of the cluster at the time a statement arrives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClusterView {
+    /// Whether Raft consensus is enabled.
+    pub raft_enabled: bool,
+    /// This node's ID.
+    pub node_id: u64,
+    /// The current leader, if known.
+    pub leader: Option<u64>,
+}
+
+impl ClusterView {
+    /// View for a database running without Raft.
+    pub fn standalone(node_id: u64) -> Self {
+        Self {
+            raft_enabled: false,
+            node_id,
+            leader: Some(node_id),
+        }
+    }
+
+    /// Whether this node is the current leader.
+    pub fn is_leader(&self) -> bool {
+        self.leader == Some(self.node_id)
+    }
+}
+
+/// Where a statement should be executed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Route {
+    /// Execute against local state without going through Raft.
+    Local,
+    /// Confirm leadership with a quorum, then execute against local state.
+    LinearizableRead,
+    /// Replicate through the Raft log (this node is leader).
+    Replicate,
+    /// This node cannot serve the statement; the client should retry on the
+    /// leader, if one is known.
+    Redirect { leader: Option<u64> },
+}
+
+/// Decide how to execute a statement of the given class.
+pub fn route(class: StatementClass, consistency: ReadConsistency, view: &ClusterView) -> Route {
+    if !view.raft_enabled {
+        return Route::Local;
+    }
+
+    let redirect = Route::Redirect {
+        leader: view.leader,
+    };
+
+    match class {
+        StatementClass::Ddl | StatementClass::Session => Route::Local,
+        StatementClass::Write if view.is_leader() => Route::Replicate,
+        StatementClass::Write => redirect,
+        StatementClass::LockingRead if view.is_leader() => Route::Local,
+        StatementClass::LockingRead => redirect,
+        StatementClass::Read => match consistency {
+            ReadConsistency::Local => Route::Local,
+            _ if !view.is_leader() => redirect,
+            ReadConsistency::Leader => Route::Local,
+            ReadConsistency::Linearizable => Route::LinearizableRead,
+        },
+    }
+}
+
+/// Error returned when a statement reaches a node that cannot serve it.
+///
+/// Callers can downcast an [`anyhow::Error`] to this type to find the leader.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotLeaderError {
+    /// The node that rejected the statement.
+    pub node_id: u64,
+    /// What kind of statement was rejected.
+    pub class: StatementClass,
+    /// The current leader, if known.
+    pub leader: Option<u64>,
+    /// The leader's Raft address, if known.
+    pub leader_addr: Option<String>,
+}
+
+impl fmt::Display for NotLeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self.class {
+            StatementClass::Read => "serve consistent reads",
+            StatementClass::LockingRead => "acquire row locks",
+            StatementClass::Write | StatementClass::Ddl => "accept writes",
+            StatementClass::Session => "change session settings",
+        };
+        write!(
+            f,
+            "not the leader: this node is {}, cannot {}",
+            self.node_id, action
+        )?;
+        match (self.leader, &self.leader_addr) {
+            (Some(id), Some(addr)) => write!(f, " (current leader: node {} at {})", id, addr),
+            (Some(id), None) => write!(f, " (current leader: node {})", id),
+            (None, _) => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for NotLeaderError {}
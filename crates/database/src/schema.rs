@@ -0,0 +1,535 @@
+//! Portable schema bundles for promoting a schema between environments.
+//!
+//! A [`SchemaBundle`] holds the schema of a database without its data: each
+//! table's columns, primary key, options, partitions and indexes, and every
+//! view. It refers to columns by name and leaves out table, index and file
+//! IDs, so a bundle exported from one database describes the same schema in
+//! any other. Temporary tables are left out, as are statistics.
+//!
+//! Bundles are written as versioned JSON ([`SchemaBundle::to_json`]), or as
+//! the SQL script that creates the schema in an empty database
+//! ([`SchemaBundle::to_sql`]). Importing a bundle
+//! ([`Database::import_schema`]) diffs it against the live catalog and runs
+//! the DDL that turns one into the other:
+//!
+//! 1. views that are gone or changed are dropped,
+//! 2. indexes that are gone or changed are dropped,
+//! 3. tables that are gone are dropped, and columns that are gone are
+//!    dropped from the tables that remain,
+//! 4. new tables are created, and new columns added,
+//! 5. new and changed indexes are created, then new and changed views.
+//!
+//! Changes that DDL cannot make in place are refused before anything runs:
+//! a column whose type or constraints changed, columns that would end up in
+//! a different order, and a table whose primary key, engine, options or
+//! partitions changed. Drop such a table first, or leave it out of the
+//! bundle and import twice. Indexes over several columns, and bitmap and
+//! trie indexes, cannot be created with SQL, so bundles that add them are
+//! refused too.
+//!
+//! [`Database::import_schema`]: crate::Database::import_schema
+
+use std::fmt::Write as _;
+
+use anyhow::{bail, Context, Result};
+use catalog::{
+    Catalog, EngineKind, IndexKind, PartitionBound, PartitionMethod, TableMeta, ViewMeta,
+    ROW_VERSION_COLUMN,
+};
+use common::compression::Compression;
+use parser::quote_ident;
+use serde::{Deserialize, Serialize};
+use types::{binary, decimal::MAX_PRECISION, temporal, Value};
+
+/// Format version written to new bundles. Bundles with a later version are
+/// refused.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// The schema of a database (see the [module docs](self)).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SchemaBundle {
+    pub version: u32,
+    /// Tables, in the order they were created.
+    pub tables: Vec<TableDef>,
+    /// Views, in name order.
+    pub views: Vec<ViewMeta>,
+}
+
+/// A table in a [`SchemaBundle`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TableDef {
+    pub name: String,
+    /// Columns in table order, without the row version column.
+    pub columns: Vec<ColumnDef>,
+    /// Primary key column names; empty if the table has no primary key.
+    #[serde(default)]
+    pub primary_key: Vec<String>,
+    #[serde(default)]
+    pub engine: EngineKind,
+    /// Whether the table has an implicit row version column.
+    #[serde(default)]
+    pub row_version: bool,
+    #[serde(default)]
+    pub audit: bool,
+    #[serde(default)]
+    pub fillfactor: Option<u8>,
+    #[serde(default)]
+    pub compression: Compression,
+    #[serde(default)]
+    pub partitioning: Option<PartitioningDef>,
+    #[serde(default)]
+    pub indexes: Vec<IndexDef>,
+}
+
+/// A column in a [`TableDef`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ColumnDef {
+    pub name: String,
+    /// The type as declared, e.g. `VARCHAR(20)`.
+    #[serde(rename = "type")]
+    pub ty: String,
+    #[serde(default)]
+    pub not_null: bool,
+    #[serde(default)]
+    pub default: Option<Value>,
+    /// Whether values are generated from the column's sequence.
+    #[serde(default)]
+    pub auto_increment: bool,
+}
+
+/// How a [`TableDef`] is partitioned.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PartitioningDef {
+    pub method: PartitionMethod,
+    pub column: String,
+    pub partitions: Vec<PartitionDef>,
+}
+
+/// One partition of a [`PartitioningDef`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PartitionDef {
+    pub name: String,
+    pub bound: PartitionBound,
+}
+
+/// An index in a [`TableDef`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexDef {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub kind: IndexKind,
+    #[serde(default)]
+    pub unique: bool,
+}
+
+/// One DDL statement of an import, as planned by [`SchemaBundle::diff`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum SchemaChange {
+    CreateTable(TableDef),
+    DropTable(String),
+    AddColumn { table: String, column: ColumnDef },
+    DropColumn { table: String, column: String },
+    CreateIndex { table: String, index: IndexDef },
+    DropIndex(String),
+    CreateView(ViewMeta),
+    DropView(String),
+}
+
+impl SchemaBundle {
+    /// The schema of the persistent tables and the views in `catalog`.
+    pub fn from_catalog(catalog: &Catalog) -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            tables: catalog
+                .tables()
+                .filter(|table| !table.temporary)
+                .map(TableDef::from_meta)
+                .collect(),
+            views: catalog.views().cloned().collect(),
+        }
+    }
+
+    /// Read a bundle written by [`SchemaBundle::to_json`].
+    pub fn from_json(json: &str) -> Result<Self> {
+        let bundle: Self = serde_json::from_str(json).context("invalid schema bundle")?;
+        if bundle.version > BUNDLE_VERSION {
+            bail!(
+                "schema bundle version {} is newer than the supported version {}",
+                bundle.version,
+                BUNDLE_VERSION
+            );
+        }
+        Ok(bundle)
+    }
+
+    /// The bundle as pretty JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("schema bundles serialize to JSON")
+    }
+
+    /// A script of `;`-terminated statements that creates the schema in an
+    /// empty database. Fails if the schema cannot be created with SQL.
+    pub fn to_sql(&self) -> Result<String> {
+        let empty = Self {
+            version: BUNDLE_VERSION,
+            tables: Vec::new(),
+            views: Vec::new(),
+        };
+        let mut script = String::new();
+        for change in self.diff(&empty)? {
+            writeln!(script, "{};", change.to_sql()).expect("writing to a String");
+        }
+        Ok(script)
+    }
+
+    /// The changes that turn the schema `live` into this one, in the order
+    /// they must run (see the [module docs](self)).
+    ///
+    /// Fails without planning anything if a change cannot be made with DDL.
+    pub fn diff(&self, live: &SchemaBundle) -> Result<Vec<SchemaChange>> {
+        let mut drop_views = Vec::new();
+        let mut drop_indexes = Vec::new();
+        let mut drops = Vec::new();
+        let mut creates = Vec::new();
+        let mut create_indexes = Vec::new();
+        let mut create_views = Vec::new();
+
+        for view in &live.views {
+            if !self.views.contains(view) {
+                drop_views.push(SchemaChange::DropView(view.name.clone()));
+            }
+        }
+        for view in &self.views {
+            if !live.views.contains(view) {
+                create_views.push(SchemaChange::CreateView(view.clone()));
+            }
+        }
+
+        for old in &live.tables {
+            if !self.tables.iter().any(|table| table.name == old.name) {
+                drops.push(SchemaChange::DropTable(old.name.clone()));
+            }
+        }
+        for table in &self.tables {
+            let Some(old) = live.tables.iter().find(|old| old.name == table.name) else {
+                creates.push(SchemaChange::CreateTable(without_indexes(table)));
+                for index in &table.indexes {
+                    create_indexes.push(create_index(table, index)?);
+                }
+                continue;
+            };
+            old.check_alterable_to(table)?;
+            for index in &old.indexes {
+                if !table.indexes.contains(index) {
+                    drop_indexes.push(SchemaChange::DropIndex(index.name.clone()));
+                }
+            }
+            for index in &table.indexes {
+                if !old.indexes.contains(index) {
+                    create_indexes.push(create_index(table, index)?);
+                }
+            }
+            for column in &old.columns {
+                if !table.columns.iter().any(|c| c.name == column.name) {
+                    drops.push(SchemaChange::DropColumn {
+                        table: table.name.clone(),
+                        column: column.name.clone(),
+                    });
+                }
+            }
+            for column in &table.columns {
+                if !old.columns.iter().any(|c| c.name == column.name) {
+                    creates.push(SchemaChange::AddColumn {
+                        table: table.name.clone(),
+                        column: column.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok([
+            drop_views,
+            drop_indexes,
+            drops,
+            creates,
+            create_indexes,
+            create_views,
+        ]
+        .concat())
+    }
+}
+
+impl TableDef {
+    fn from_meta(table: &TableMeta) -> Self {
+        let column_name = |ordinal: &u16| table.columns()[*ordinal as usize].name.clone();
+        Self {
+            name: table.name.clone(),
+            columns: table
+                .columns()
+                .iter()
+                .filter(|column| column.name != ROW_VERSION_COLUMN)
+                .map(|column| ColumnDef {
+                    name: column.name.clone(),
+                    ty: column.type_name(),
+                    not_null: column.not_null,
+                    default: column.default.clone(),
+                    auto_increment: column.sequence.is_some(),
+                })
+                .collect(),
+            primary_key: table
+                .primary_key
+                .iter()
+                .flatten()
+                .map(column_name)
+                .collect(),
+            engine: table.engine,
+            row_version: table.row_version_column().is_some(),
+            audit: table.audit,
+            fillfactor: table.fillfactor,
+            compression: table.compression,
+            partitioning: table
+                .partitioning
+                .as_ref()
+                .map(|partitioning| PartitioningDef {
+                    method: partitioning.method,
+                    column: column_name(&partitioning.column),
+                    partitions: partitioning
+                        .partitions
+                        .iter()
+                        .map(|partition| PartitionDef {
+                            name: partition.name.clone(),
+                            bound: partition.bound.clone(),
+                        })
+                        .collect(),
+                }),
+            indexes: table
+                .indexes
+                .iter()
+                .map(|index| IndexDef {
+                    name: index.name.clone(),
+                    columns: index.columns.iter().map(column_name).collect(),
+                    kind: index.kind.clone(),
+                    unique: index.unique,
+                })
+                .collect(),
+        }
+    }
+
+    /// Fail unless DDL can turn this table into `target` by dropping and
+    /// adding columns and indexes.
+    fn check_alterable_to(&self, target: &TableDef) -> Result<()> {
+        let name = &self.name;
+        let refuse = |what: &str| {
+            anyhow::anyhow!(
+                "cannot import table '{name}': its {what} changed; drop the table first"
+            )
+        };
+        if self.primary_key != target.primary_key {
+            return Err(refuse("primary key"));
+        }
+        if self.engine != target.engine {
+            return Err(refuse("engine"));
+        }
+        if (
+            self.row_version,
+            self.audit,
+            self.fillfactor,
+            self.compression,
+        ) != (
+            target.row_version,
+            target.audit,
+            target.fillfactor,
+            target.compression,
+        ) {
+            return Err(refuse("options"));
+        }
+        if self.partitioning != target.partitioning {
+            return Err(refuse("partitioning"));
+        }
+        for column in &target.columns {
+            if let Some(old) = self.columns.iter().find(|old| old.name == column.name) {
+                if old != column {
+                    return Err(refuse(&format!("column '{}'", column.name)));
+                }
+            }
+        }
+        // Added columns are appended, so the kept ones must come first
+        let kept: Vec<&str> = self
+            .columns
+            .iter()
+            .filter(|old| target.columns.iter().any(|c| c.name == old.name))
+            .map(|old| old.name.as_str())
+            .collect();
+        let order: Vec<&str> = target.columns.iter().map(|c| c.name.as_str()).collect();
+        if !order.starts_with(&kept) {
+            return Err(refuse("column order"));
+        }
+        Ok(())
+    }
+}
+
+fn without_indexes(table: &TableDef) -> TableDef {
+    TableDef {
+        indexes: Vec::new(),
+        ..table.clone()
+    }
+}
+
+fn create_index(table: &TableDef, index: &IndexDef) -> Result<SchemaChange> {
+    if index.columns.len() != 1 || !matches!(index.kind, IndexKind::BTree | IndexKind::Hash) {
+        bail!(
+            "cannot import index '{}' on table '{}': only single-column B-tree and hash \
+             indexes can be created with SQL",
+            index.name,
+            table.name
+        );
+    }
+    Ok(SchemaChange::CreateIndex {
+        table: table.name.clone(),
+        index: index.clone(),
+    })
+}
+
+impl SchemaChange {
+    /// The change as a SQL statement, without a trailing `;`.
+    pub fn to_sql(&self) -> String {
+        match self {
+            SchemaChange::CreateTable(table) => create_table_sql(table),
+            SchemaChange::DropTable(name) => format!("DROP TABLE {}", quote_ident(name)),
+            SchemaChange::AddColumn { table, column } => format!(
+                "ALTER TABLE {} ADD COLUMN {}",
+                quote_ident(table),
+                column_sql(column)
+            ),
+            SchemaChange::DropColumn { table, column } => format!(
+                "ALTER TABLE {} DROP COLUMN {}",
+                quote_ident(table),
+                quote_ident(column)
+            ),
+            SchemaChange::CreateIndex { table, index } => format!(
+                "CREATE {}INDEX {} ON {} USING {} ({})",
+                if index.unique { "UNIQUE " } else { "" },
+                quote_ident(&index.name),
+                quote_ident(table),
+                match index.kind {
+                    IndexKind::Hash => "HASH",
+                    _ => "BTREE",
+                },
+                idents(&index.columns)
+            ),
+            SchemaChange::DropIndex(name) => format!("DROP INDEX {}", quote_ident(name)),
+            SchemaChange::CreateView(view) => {
+                format!("CREATE VIEW {} AS {}", quote_ident(&view.name), view.query)
+            }
+            SchemaChange::DropView(name) => format!("DROP VIEW {}", quote_ident(name)),
+        }
+    }
+}
+
+fn create_table_sql(table: &TableDef) -> String {
+    let mut elements: Vec<String> = table.columns.iter().map(column_sql).collect();
+    if !table.primary_key.is_empty() {
+        elements.push(format!("PRIMARY KEY ({})", idents(&table.primary_key)));
+    }
+    let mut sql = format!(
+        "CREATE TABLE {} ({})",
+        quote_ident(&table.name),
+        elements.join(", ")
+    );
+
+    let mut options = Vec::new();
+    if table.row_version {
+        options.push("row_version = true".to_string());
+    }
+    if table.audit {
+        options.push("audit = true".to_string());
+    }
+    if let Some(fillfactor) = table.fillfactor {
+        options.push(format!("fillfactor = {fillfactor}"));
+    }
+    if table.compression != Compression::None {
+        options.push(format!("compression = '{}'", table.compression));
+    }
+    if !options.is_empty() {
+        write!(sql, " WITH ({})", options.join(", ")).expect("writing to a String");
+    }
+    let engine = match table.engine {
+        EngineKind::Heap => None,
+        EngineKind::Memory => Some("memory"),
+        EngineKind::Lsm => Some("lsm"),
+        EngineKind::Columnar => Some("columnar"),
+    };
+    if let Some(engine) = engine {
+        write!(sql, " ENGINE = {engine}").expect("writing to a String");
+    }
+
+    if let Some(partitioning) = &table.partitioning {
+        let partitions: Vec<String> = partitioning
+            .partitions
+            .iter()
+            .map(|partition| {
+                let values = match &partition.bound {
+                    PartitionBound::LessThan(Some(upper)) => {
+                        format!("LESS THAN ({})", sql_literal(upper))
+                    }
+                    PartitionBound::LessThan(None) => "LESS THAN (MAXVALUE)".to_string(),
+                    PartitionBound::In(values) => {
+                        let values: Vec<String> = values.iter().map(sql_literal).collect();
+                        format!("IN ({})", values.join(", "))
+                    }
+                };
+                format!("PARTITION {} VALUES {values}", quote_ident(&partition.name))
+            })
+            .collect();
+        let method = match partitioning.method {
+            PartitionMethod::Range => "RANGE",
+            PartitionMethod::List => "LIST",
+        };
+        write!(
+            sql,
+            " PARTITION BY {method} ({}) ({})",
+            quote_ident(&partitioning.column),
+            partitions.join(", ")
+        )
+        .expect("writing to a String");
+    }
+    sql
+}
+
+fn column_sql(column: &ColumnDef) -> String {
+    let mut sql = format!("{} {}", quote_ident(&column.name), column.ty);
+    if column.not_null {
+        sql.push_str(" NOT NULL");
+    }
+    if let Some(default) = &column.default {
+        write!(sql, " DEFAULT {}", sql_literal(default)).expect("writing to a String");
+    }
+    if column.auto_increment {
+        sql.push_str(" AUTO_INCREMENT");
+    }
+    sql
+}
+
+fn idents(names: &[String]) -> String {
+    let quoted: Vec<_> = names.iter().map(|name| quote_ident(name)).collect();
+    quoted.join(", ")
+}
+
+/// `value` as a SQL literal that reads back as the same value and type.
+fn sql_literal(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Int(n) => n.to_string(),
+        Value::Bool(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+        Value::Text(text) => format!("'{}'", text.replace('\'', "''")),
+        // Debug keeps the decimal point, so `1.0` is not read as an integer
+        Value::Float(f) if f.is_finite() => format!("{f:?}"),
+        Value::Float(f) => format!("CAST('{f}' AS DOUBLE)"),
+        Value::Date(days) => format!("DATE '{}'", temporal::format_date(*days)),
+        Value::Timestamp(micros) => {
+            format!("TIMESTAMP '{}'", temporal::format_timestamp(*micros))
+        }
+        Value::Decimal(d) => format!("CAST('{d}' AS DECIMAL({MAX_PRECISION}, {}))", d.scale()),
+        Value::Bytes(bytes) => format!("X'{}'", binary::encode_hex(bytes)),
+    }
+}
@@ -0,0 +1,191 @@
+//! Per-session resource governance.
+//!
+//! A session is identified by the principal a statement runs as (see
+//! [`Database::execute_as`](crate::Database::execute_as)); the server uses one
+//! per client connection. [`ResourceLimits`] set with
+//! [`Database::with_resource_limits`](crate::Database::with_resource_limits)
+//! apply to every session:
+//!
+//! - rows scanned and buffered memory are capped per statement by the
+//!   executor;
+//! - concurrent statements are capped here. Statements execute one at a time,
+//!   so the cap bounds how many a session can have queued; further
+//!   statements are rejected immediately rather than waiting behind them.
+//!
+//! Each session also has a [`Priority`], set with `SET statement_priority`,
+//! that orders its statements in the admission queue (see
+//! [`admission`](crate::admission)) and tells the executor whether to treat
+//! them as background work.
+//!
+//! `SET TRANSACTION` and `SET SESSION CHARACTERISTICS` choose the session's
+//! isolation levels (see [`isolation`](crate::isolation)), and
+//! `SET random_seed` fixes the sequence `RANDOM()` draws from for the
+//! session that sets it only; other sessions keep their own sequences, so a
+//! client's script generates the same values however many clients run
+//! alongside it.
+//!
+//! A statement that exceeds a limit fails with [`DbError::ResourceExhausted`].
+//! As with any other error, a DML statement stopped part way keeps the rows
+//! it already wrote; there are no transactions to roll back.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use common::{DbError, Priority, ResourceLimits};
+use expr::random::Generator;
+
+use crate::isolation::{IsolationLevel, IsolationSettings};
+
+/// The generator a session's `RANDOM()` draws from, or `None` for
+/// unpredictable values.
+pub type SessionRandom = Arc<Mutex<Option<Generator>>>;
+
+/// Statements in flight, priorities, isolation levels and random sequences,
+/// by session.
+#[derive(Debug, Default)]
+pub struct SessionRegistry {
+    active: Mutex<HashMap<String, usize>>,
+    /// Sessions whose priority is not the default
+    priorities: Mutex<HashMap<String, Priority>>,
+    /// Sessions whose isolation settings are not the default
+    isolation: Mutex<HashMap<String, IsolationSettings>>,
+    /// Sessions that fixed a seed with `SET random_seed`
+    randoms: Mutex<HashMap<String, SessionRandom>>,
+}
+
+impl SessionRegistry {
+    /// Admit a statement for `session`, or fail if the session already has
+    /// `limits.max_concurrent_statements` in flight.
+    ///
+    /// The statement counts against the session until the returned slot is
+    /// dropped.
+    pub fn enter(
+        &self,
+        session: &str,
+        limits: &ResourceLimits,
+    ) -> Result<SessionSlot<'_>, DbError> {
+        let mut active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        let count = active.entry(session.to_string()).or_default();
+        if let Some(max) = limits.max_concurrent_statements {
+            if *count >= max {
+                return Err(DbError::ResourceExhausted(format!(
+                    "session '{session}' already has {max} concurrent statements"
+                )));
+            }
+        }
+        *count += 1;
+        Ok(SessionSlot {
+            registry: self,
+            session: session.to_string(),
+        })
+    }
+
+    /// Statements `session` currently has in flight.
+    pub fn active(&self, session: &str) -> usize {
+        let active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        active.get(session).copied().unwrap_or(0)
+    }
+
+    /// Run `session`'s statements at `priority` from now on.
+    pub fn set_priority(&self, session: &str, priority: Priority) {
+        let mut priorities = self.priorities.lock().unwrap_or_else(|e| e.into_inner());
+        if priority == Priority::default() {
+            priorities.remove(session);
+        } else {
+            priorities.insert(session.to_string(), priority);
+        }
+    }
+
+    /// Priority `session`'s statements run at.
+    pub fn priority(&self, session: &str) -> Priority {
+        let priorities = self.priorities.lock().unwrap_or_else(|e| e.into_inner());
+        priorities.get(session).copied().unwrap_or_default()
+    }
+
+    /// Apply a `SET TRANSACTION` (or, with `session_default`,
+    /// `SET SESSION CHARACTERISTICS`) run by `session`.
+    pub fn set_isolation(&self, session: &str, isolation: IsolationLevel, session_default: bool) {
+        let mut settings = self.isolation.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = settings.entry(session.to_string()).or_default();
+        entry.set(isolation, session_default);
+        if *entry == IsolationSettings::default() {
+            settings.remove(session);
+        }
+    }
+
+    /// Isolation settings of `session`.
+    pub fn isolation(&self, session: &str) -> IsolationSettings {
+        let settings = self.isolation.lock().unwrap_or_else(|e| e.into_inner());
+        settings.get(session).copied().unwrap_or_default()
+    }
+
+    /// Start a transaction in `session`, consuming any one-shot level, and
+    /// return the level it runs under.
+    pub fn begin(&self, session: &str) -> IsolationLevel {
+        let mut settings = self.isolation.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(entry) = settings.get_mut(session) else {
+            return IsolationLevel::default();
+        };
+        let level = entry.begin();
+        if *entry == IsolationSettings::default() {
+            settings.remove(session);
+        }
+        level
+    }
+
+    /// Draw `session`'s `RANDOM()` values from a generator seeded with
+    /// `seed` from now on, or unpredictably again for `None`.
+    pub fn set_random_seed(&self, session: &str, seed: Option<i64>) {
+        let mut randoms = self.randoms.lock().unwrap_or_else(|e| e.into_inner());
+        match seed {
+            Some(seed) => {
+                let generator = Generator::seeded(seed as u64);
+                randoms.insert(session.to_string(), Arc::new(Mutex::new(Some(generator))));
+            }
+            None => {
+                randoms.remove(session);
+            }
+        }
+    }
+
+    /// The generator `session`'s statements draw `RANDOM()` from.
+    pub fn random(&self, session: &str) -> SessionRandom {
+        let randoms = self.randoms.lock().unwrap_or_else(|e| e.into_inner());
+        randoms.get(session).cloned().unwrap_or_default()
+    }
+
+    /// Forget the settings of a session that has ended.
+    pub fn end(&self, session: &str) {
+        let mut priorities = self.priorities.lock().unwrap_or_else(|e| e.into_inner());
+        priorities.remove(session);
+        let mut isolation = self.isolation.lock().unwrap_or_else(|e| e.into_inner());
+        isolation.remove(session);
+        let mut randoms = self.randoms.lock().unwrap_or_else(|e| e.into_inner());
+        randoms.remove(session);
+    }
+}
+
+/// A statement admitted by [`SessionRegistry::enter`].
+#[derive(Debug)]
+pub struct SessionSlot<'a> {
+    registry: &'a SessionRegistry,
+    session: String,
+}
+
+impl Drop for SessionSlot<'_> {
+    fn drop(&mut self) {
+        let mut active = self
+            .registry
+            .active
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(count) = active.get_mut(&self.session) {
+            *count -= 1;
+            if *count == 0 {
+                active.remove(&self.session);
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Node settings that can be changed without a restart.
+//!
+//! `SET GLOBAL <name> = <value>`, [`Database::set_global`] and the admin
+//! endpoint `POST /admin/settings` change a setting for every session on this
+//! node; `DEFAULT` restores the value the node was opened with. Settings are
+//! not replicated and are lost on restart.
+//!
+//! - `durability` (`full` or `relaxed`): whether each DML statement waits for
+//!   the WAL to be fsynced (see [`Durability`]). DDL always syncs.
+//! - `slow_query_threshold_ms` (milliseconds, or `off`): statements taking
+//!   at least this long are kept in the slow query log, read with
+//!   [`Database::slow_queries`].
+//! - `buffer_pages` (pages, at least 1): the buffer pool's size. Shrinking
+//!   it writes out the dirty pages it evicts, and fails if too many pages
+//!   are pinned.
+//!
+//! [`Database::set_global`]: crate::Database::set_global
+//! [`Database::slow_queries`]: crate::Database::slow_queries
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use buffer::SharedPager;
+use wal::{Durability, Wal};
+
+/// Slow statements kept in the log; older ones are dropped.
+pub const SLOW_QUERY_LOG_LEN: usize = 100;
+
+/// Values of the settings that can change at runtime.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Settings {
+    pub durability: Durability,
+    /// Statements at least this slow are logged (None logs none)
+    pub slow_query_threshold: Option<Duration>,
+    pub buffer_pages: usize,
+}
+
+impl Settings {
+    /// The settings by name, with values as `SET GLOBAL` takes them.
+    pub fn to_map(&self) -> BTreeMap<String, String> {
+        let threshold = match self.slow_query_threshold {
+            Some(threshold) => threshold.as_millis().to_string(),
+            None => "off".to_string(),
+        };
+        BTreeMap::from([
+            ("durability".to_string(), self.durability.name().to_string()),
+            ("slow_query_threshold_ms".to_string(), threshold),
+            ("buffer_pages".to_string(), self.buffer_pages.to_string()),
+        ])
+    }
+}
+
+/// A statement that took at least the slow query threshold.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlowQuery {
+    pub principal: String,
+    pub sql: String,
+    pub elapsed: Duration,
+}
+
+/// The node's current settings, applied to the pager and WAL they govern.
+pub struct GlobalSettings {
+    /// Values the node was opened with, restored by `DEFAULT`
+    initial: Settings,
+    current: Mutex<Settings>,
+    /// Held while a setting changes, so that changes apply in turn
+    changing: Mutex<()>,
+    pager: SharedPager,
+    wal: Arc<tokio::sync::Mutex<Wal>>,
+    slow_queries: Mutex<VecDeque<SlowQuery>>,
+}
+
+impl GlobalSettings {
+    /// Settings starting from `initial`, which must match the pager and WAL.
+    pub fn new(initial: Settings, pager: SharedPager, wal: Arc<tokio::sync::Mutex<Wal>>) -> Self {
+        Self {
+            current: Mutex::new(initial.clone()),
+            changing: Mutex::default(),
+            initial,
+            pager,
+            wal,
+            slow_queries: Mutex::default(),
+        }
+    }
+
+    fn current(&self) -> MutexGuard<'_, Settings> {
+        self.current.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn get(&self) -> Settings {
+        self.current().clone()
+    }
+
+    /// Change the setting `name` to `value`, or back to its initial value
+    /// with `None`.
+    ///
+    /// Takes the WAL lock with its blocking variant, so it must run on a
+    /// blocking thread.
+    pub fn set(&self, name: &str, value: Option<&str>) -> Result<()> {
+        let _changing = self.changing.lock().unwrap_or_else(|e| e.into_inner());
+        match name.to_ascii_lowercase().as_str() {
+            "durability" => {
+                let durability = match value {
+                    Some(value) => Durability::from_name(value)
+                        .ok_or_else(|| anyhow!("durability must be FULL, RELAXED or DEFAULT"))?,
+                    None => self.initial.durability,
+                };
+                self.wal.blocking_lock().set_durability(durability);
+                self.current().durability = durability;
+            }
+            "slow_query_threshold_ms" => {
+                let threshold = match value {
+                    Some(value) if value.eq_ignore_ascii_case("off") => None,
+                    Some(value) => Some(Duration::from_millis(value.parse().map_err(|_| {
+                        anyhow!("slow_query_threshold_ms must be milliseconds, OFF or DEFAULT")
+                    })?)),
+                    None => self.initial.slow_query_threshold,
+                };
+                self.current().slow_query_threshold = threshold;
+            }
+            "buffer_pages" => {
+                let pages = match value {
+                    Some(value) => value
+                        .parse()
+                        .ok()
+                        .filter(|&pages| pages > 0)
+                        .ok_or_else(|| anyhow!("buffer_pages must be a positive integer"))?,
+                    None => self.initial.buffer_pages,
+                };
+                self.pager
+                    .pool()
+                    .set_max_pages(pages)
+                    .map_err(|e| anyhow!("failed to resize the buffer pool: {}", e))?;
+                self.current().buffer_pages = pages;
+            }
+            _ => bail!("unknown setting '{}'", name),
+        }
+        Ok(())
+    }
+
+    /// Log the statement if it took at least the slow query threshold.
+    pub fn record_statement(&self, principal: &str, sql: &str, elapsed: Duration) {
+        let threshold = self.current().slow_query_threshold;
+        if threshold.is_none_or(|threshold| elapsed < threshold) {
+            return;
+        }
+        let mut log = self.slow_queries.lock().unwrap_or_else(|e| e.into_inner());
+        if log.len() == SLOW_QUERY_LOG_LEN {
+            log.pop_front();
+        }
+        log.push_back(SlowQuery {
+            principal: principal.to_string(),
+            sql: sql.to_string(),
+            elapsed,
+        });
+    }
+
+    /// Slow statements logged so far, oldest first.
+    pub fn slow_queries(&self) -> Vec<SlowQuery> {
+        let log = self.slow_queries.lock().unwrap_or_else(|e| e.into_inner());
+        log.iter().cloned().collect()
+    }
+}
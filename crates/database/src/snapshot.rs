@@ -0,0 +1,124 @@
+//! Consistent copies of a table's rows.
+//!
+//! Statements hold the WAL lock for their whole run, so a scan made under
+//! that lock sees every write committed before it and none of a write in
+//! progress. [`Database::snapshot_table`](crate::Database::snapshot_table)
+//! scans a table that way and releases the lock once its rows are copied
+//! out, so writers wait for the scan but not while an export writes the rows
+//! out or a subscriber loads them.
+//!
+//! A snapshot records the position the publisher's WAL had reached when it
+//! was taken. The changes read from that position are exactly the ones the
+//! snapshot does not hold, so a new subscription can load the snapshot with
+//! [`ChangeBatch::from_snapshot`](crate::ChangeBatch::from_snapshot) and
+//! continue from there.
+
+use std::{ops::DerefMut, path::Path, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use buffer::SharedPager;
+use catalog::Catalog;
+use common::RecordId;
+use executor::{execute_query, EngineRegistry, ExecutionContext};
+use planner::PhysicalPlan;
+use tokio::sync::Mutex;
+use types::Value;
+use wal::Wal;
+
+/// The rows of a table as of one point in time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableSnapshot {
+    table: String,
+    columns: Vec<String>,
+    position: u64,
+    rows: Vec<(RecordId, Vec<Value>)>,
+}
+
+impl TableSnapshot {
+    /// Name of the table.
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// Names of the table's columns, in the order of each row's values.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Position in the WAL when the snapshot was taken.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Number of rows in the snapshot.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether the table was empty.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// The rows, in storage order.
+    pub fn rows(&self) -> impl Iterator<Item = &[Value]> {
+        self.rows.iter().map(|(_, row)| row.as_slice())
+    }
+}
+
+impl IntoIterator for TableSnapshot {
+    type Item = (RecordId, Vec<Value>);
+    type IntoIter = std::vec::IntoIter<(RecordId, Vec<Value>)>;
+
+    /// Each row with its record ID, in storage order.
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.into_iter()
+    }
+}
+
+/// Copy the rows of table `name` while holding the WAL lock.
+pub fn snapshot_table(
+    catalog: &Catalog,
+    pager: &SharedPager,
+    wal: &Mutex<Wal>,
+    engines: &Arc<EngineRegistry>,
+    data_dir: &Path,
+    name: &str,
+) -> Result<TableSnapshot> {
+    let table = catalog.table(name)?;
+    let columns: Vec<String> = table.columns().iter().map(|c| c.name.clone()).collect();
+
+    let mut pager = pager.clone();
+    let mut wal_lock = wal.blocking_lock();
+    let position = wal_lock.end()?;
+    let mut ctx = ExecutionContext::new(
+        catalog,
+        &mut pager,
+        wal_lock.deref_mut(),
+        data_dir.to_path_buf(),
+    )
+    .with_engines(engines.clone());
+
+    let plan = PhysicalPlan::SeqScan {
+        table_id: table.id,
+        schema: columns.clone(),
+        projection: None,
+    };
+    let rows = execute_query(plan, &mut ctx)
+        .map_err(anyhow::Error::from)?
+        .into_iter()
+        .map(|row| {
+            let rid = row
+                .rid()
+                .ok_or_else(|| anyhow!("table '{}' returned a row without a record ID", name))?;
+            Ok((rid, row.values))
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(TableSnapshot {
+        table: table.name.clone(),
+        columns,
+        position,
+        rows,
+    })
+}
@@ -0,0 +1,438 @@
+//! Serializable isolation for multi-statement transactions, checked per
+//! table.
+//!
+//! A [`Transaction`](crate::Transaction) holds its writes back until it
+//! commits, but its `SELECT`s read the live tables: there is a single
+//! version of each row and no snapshot to read from. Repeatable read is kept
+//! by refusing reads that could differ from what the transaction would have
+//! seen when it began instead: a transaction that reads a table written by a
+//! transaction that committed after it began, or that commits a write to
+//! such a table, fails (the first committer wins). Every read that succeeds
+//! therefore saw its tables as they were at the start, as a snapshot would
+//! have shown them.
+//!
+//! That still allows write skew, where two transactions each read what
+//! the other writes. Serializable transactions also track read-write
+//! antidependencies: `a -> b` when `a` read a table before a concurrent `b`
+//! wrote it, so `a` must come first in any serial order. A cycle of them
+//! always passes through a pivot with an antidependency in and one out, and
+//! a transaction is aborted when it would commit as a pivot whose outgoing
+//! neighbour has already committed, or commit into a pivot that did so. The
+//! transaction aborted is always the one committing, since the others can
+//! no longer be undone; like the rest of this check it errs towards
+//! aborting, so a transaction may fail that would have been serializable.
+//!
+//! A `SELECT ... FOR SHARE` claims its tables as shared: the claim is
+//! checked against concurrent writes like a write would be, but two shared
+//! claims never conflict, so transactions that only share a table can all
+//! commit. `FOR UPDATE` claims its tables as writes.
+//!
+//! Conflicts are tracked per table rather than per row, the way PostgreSQL
+//! falls back to relation-level locks. Statements run outside a transaction
+//! take part as writers that commit the moment they start, so transactions
+//! notice them, but their reads are not tracked.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+
+use crate::IsolationLevel;
+
+/// Identifies a transaction to a [`ConflictTracker`].
+pub type TxnId = u64;
+
+/// Error returned when a transaction is aborted to keep the transactions
+/// serializable. Running it again from the start may succeed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SerializationFailure {
+    /// A transaction that committed after this one began wrote `table`,
+    /// which this one reads or writes.
+    ConcurrentWrite { table: String },
+    /// A transaction that committed after this one began claimed `table`
+    /// `FOR SHARE`, which this one writes.
+    ConcurrentShare { table: String },
+    /// Committing would complete a cycle of read-write antidependencies.
+    DangerousStructure,
+}
+
+impl std::fmt::Display for SerializationFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SerializationFailure::ConcurrentWrite { table } => write!(
+                f,
+                "could not serialize access: table '{table}' was written by a concurrent transaction"
+            ),
+            SerializationFailure::ConcurrentShare { table } => write!(
+                f,
+                "could not serialize access: table '{table}' was locked FOR SHARE by a concurrent transaction"
+            ),
+            SerializationFailure::DangerousStructure => write!(
+                f,
+                "could not serialize access: read/write dependencies among concurrent transactions"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SerializationFailure {}
+
+/// Tables read and written by running and recently committed transactions,
+/// and the antidependencies between them.
+#[derive(Debug, Default)]
+pub struct ConflictTracker {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Ticks at every begin and commit
+    clock: u64,
+    next_id: TxnId,
+    /// Running transactions, and committed ones a running one overlaps
+    txns: BTreeMap<TxnId, Txn>,
+}
+
+#[derive(Debug)]
+struct Txn {
+    isolation: IsolationLevel,
+    start: u64,
+    commit: Option<u64>,
+    reads: BTreeSet<String>,
+    /// Queued while running, applied once committed
+    writes: BTreeSet<String>,
+    /// Claimed `FOR SHARE`
+    shares: BTreeSet<String>,
+    /// Transactions that read a table before this one wrote it
+    readers: BTreeSet<TxnId>,
+    /// Transactions that wrote a table after this one read it
+    writers: BTreeSet<TxnId>,
+    /// Whether a writer had committed when this one did
+    writer_committed_first: bool,
+}
+
+impl Txn {
+    fn new(isolation: IsolationLevel, start: u64) -> Self {
+        Self {
+            isolation,
+            start,
+            commit: None,
+            reads: BTreeSet::new(),
+            writes: BTreeSet::new(),
+            shares: BTreeSet::new(),
+            readers: BTreeSet::new(),
+            writers: BTreeSet::new(),
+            writer_committed_first: false,
+        }
+    }
+
+    /// Whether tables written since the start may no longer be read or
+    /// written.
+    fn rejects_concurrent_writes(&self) -> bool {
+        self.isolation != IsolationLevel::ReadCommitted
+    }
+
+    /// Whether this transaction committed after `other` began.
+    fn committed_during(&self, other: &Txn) -> bool {
+        self.commit.is_some_and(|commit| commit > other.start)
+    }
+}
+
+impl State {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn begin(&mut self, isolation: IsolationLevel) -> TxnId {
+        let start = self.tick();
+        let id = self.next_id;
+        self.next_id += 1;
+        self.txns.insert(id, Txn::new(isolation, start));
+        id
+    }
+
+    /// The first of `tables` written by a transaction that committed after
+    /// `id` began.
+    fn stale(&self, id: TxnId, tables: &BTreeSet<String>) -> Option<String> {
+        let txn = &self.txns[&id];
+        self.txns
+            .iter()
+            .filter(|(other, committed)| **other != id && committed.committed_during(txn))
+            .find_map(|(_, committed)| committed.writes.intersection(tables).next().cloned())
+    }
+
+    /// The first of `tables` claimed `FOR SHARE` by a transaction that
+    /// committed after `id` began.
+    fn shared(&self, id: TxnId, tables: &BTreeSet<String>) -> Option<String> {
+        let txn = &self.txns[&id];
+        self.txns
+            .iter()
+            .filter(|(other, committed)| **other != id && committed.committed_during(txn))
+            .find_map(|(_, committed)| committed.shares.intersection(tables).next().cloned())
+    }
+
+    /// Whether `tables` were written, or claimed `FOR SHARE` when
+    /// `writing`, by a transaction that committed after `id` began.
+    fn conflict(
+        &self,
+        id: TxnId,
+        tables: &BTreeSet<String>,
+        writing: bool,
+    ) -> Option<SerializationFailure> {
+        if let Some(table) = self.stale(id, tables) {
+            return Some(SerializationFailure::ConcurrentWrite { table });
+        }
+        let table = self.shared(id, tables).filter(|_| writing)?;
+        Some(SerializationFailure::ConcurrentShare { table })
+    }
+
+    /// Record that `id` read `tables`, before any running transaction's
+    /// queued writes to them.
+    fn read(&mut self, id: TxnId, tables: BTreeSet<String>) {
+        let writers: Vec<TxnId> = self
+            .txns
+            .iter()
+            .filter(|(other, txn)| **other != id && txn.commit.is_none())
+            .filter(|(_, txn)| !txn.writes.is_disjoint(&tables))
+            .map(|(other, _)| *other)
+            .collect();
+        for writer in writers {
+            self.add_edge(id, writer);
+        }
+        self.txns
+            .get_mut(&id)
+            .expect("running")
+            .reads
+            .extend(tables);
+    }
+
+    /// Record that `id` writes `table`, after any concurrent transaction
+    /// that read it.
+    fn write(&mut self, id: TxnId, table: &str) {
+        let txn = &self.txns[&id];
+        let readers: Vec<TxnId> = self
+            .txns
+            .iter()
+            .filter(|(other, reader)| **other != id && reader.reads.contains(table))
+            .filter(|(_, reader)| reader.commit.is_none() || reader.committed_during(txn))
+            .map(|(other, _)| *other)
+            .collect();
+        for reader in readers {
+            self.add_edge(reader, id);
+        }
+        let txn = self.txns.get_mut(&id).expect("tracked");
+        txn.writes.insert(table.to_string());
+    }
+
+    /// Record the antidependency `reader -> writer`.
+    fn add_edge(&mut self, reader: TxnId, writer: TxnId) {
+        if let Some(txn) = self.txns.get_mut(&reader) {
+            txn.writers.insert(writer);
+        }
+        if let Some(txn) = self.txns.get_mut(&writer) {
+            txn.readers.insert(reader);
+        }
+    }
+
+    fn is_committed(&self, id: &TxnId) -> bool {
+        self.txns.get(id).is_some_and(|txn| txn.commit.is_some())
+    }
+
+    /// Whether committing `id` now would complete a dangerous structure.
+    fn is_dangerous(&self, id: TxnId) -> bool {
+        let txn = &self.txns[&id];
+        // As the pivot, with its outgoing neighbour committed first
+        if !txn.readers.is_empty() && txn.writers.iter().any(|w| self.is_committed(w)) {
+            return true;
+        }
+        // Into a committed pivot whose outgoing neighbour committed first
+        txn.writers.iter().any(|writer| {
+            self.txns
+                .get(writer)
+                .is_some_and(|pivot| pivot.commit.is_some() && pivot.writer_committed_first)
+        })
+    }
+
+    fn remove(&mut self, id: TxnId) {
+        self.txns.remove(&id);
+        for txn in self.txns.values_mut() {
+            txn.readers.remove(&id);
+            txn.writers.remove(&id);
+        }
+    }
+
+    /// Forget committed transactions no running transaction overlaps.
+    fn collect(&mut self) {
+        let oldest = self
+            .txns
+            .values()
+            .filter(|txn| txn.commit.is_none())
+            .map(|txn| txn.start)
+            .min()
+            .unwrap_or(u64::MAX);
+        let done: Vec<TxnId> = self
+            .txns
+            .iter()
+            .filter(|(_, txn)| txn.commit.is_some_and(|commit| commit <= oldest))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in done {
+            self.remove(id);
+        }
+    }
+
+    /// Abort `id` with `failure`.
+    fn fail(&mut self, id: TxnId, failure: SerializationFailure) -> SerializationFailure {
+        self.remove(id);
+        self.collect();
+        failure
+    }
+}
+
+impl ConflictTracker {
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Start a transaction at `isolation`; tables written by transactions
+    /// that commit from now on conflict with it.
+    pub fn begin(&self, isolation: IsolationLevel) -> TxnId {
+        self.state().begin(isolation)
+    }
+
+    /// Record that `id` has read `tables`.
+    ///
+    /// Call it once the read has run: if a concurrent transaction committed
+    /// a write to one of them, the read may have seen it and `id` is
+    /// aborted.
+    pub fn read<'a>(
+        &self,
+        id: TxnId,
+        tables: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), SerializationFailure> {
+        let mut state = self.state();
+        let Some(txn) = state.txns.get(&id) else {
+            return Ok(());
+        };
+        if !txn.rejects_concurrent_writes() {
+            return Ok(());
+        }
+        let tables: BTreeSet<String> = tables.into_iter().map(str::to_string).collect();
+        if let Some(table) = state.stale(id, &tables) {
+            return Err(state.fail(id, SerializationFailure::ConcurrentWrite { table }));
+        }
+        state.read(id, tables);
+        Ok(())
+    }
+
+    /// Record that `id` will write `table` when it commits.
+    ///
+    /// Fails early, aborting `id`, if a concurrent transaction has already
+    /// committed a write to it or claimed it `FOR SHARE`.
+    pub fn write(&self, id: TxnId, table: &str) -> Result<(), SerializationFailure> {
+        let mut state = self.state();
+        let Some(txn) = state.txns.get(&id) else {
+            return Ok(());
+        };
+        if txn.rejects_concurrent_writes() {
+            let tables = BTreeSet::from([table.to_string()]);
+            if let Some(failure) = state.conflict(id, &tables, true) {
+                return Err(state.fail(id, failure));
+            }
+        }
+        state.write(id, table);
+        Ok(())
+    }
+
+    /// Record that `id` holds `table` `FOR SHARE` until it commits: a
+    /// concurrent write to it conflicts, another shared claim does not.
+    ///
+    /// Fails early, aborting `id`, if a concurrent transaction has already
+    /// committed a write to it.
+    pub fn share(&self, id: TxnId, table: &str) -> Result<(), SerializationFailure> {
+        let mut state = self.state();
+        let Some(txn) = state.txns.get(&id) else {
+            return Ok(());
+        };
+        if txn.rejects_concurrent_writes() {
+            let tables = BTreeSet::from([table.to_string()]);
+            if let Some(failure) = state.conflict(id, &tables, false) {
+                return Err(state.fail(id, failure));
+            }
+        }
+        state
+            .txns
+            .get_mut(&id)
+            .expect("tracked")
+            .shares
+            .insert(table.to_string());
+        Ok(())
+    }
+
+    /// Commit `id`, whose queued writes are about to apply and read
+    /// `reads` as they do, or abort it if that would not be serializable.
+    ///
+    /// Commits must not overlap, or one could miss the other's writes.
+    pub fn commit<'a>(
+        &self,
+        id: TxnId,
+        reads: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), SerializationFailure> {
+        let mut state = self.state();
+        let Some(txn) = state.txns.get(&id) else {
+            return Ok(());
+        };
+        let isolation = txn.isolation;
+        if txn.rejects_concurrent_writes() {
+            let reads: BTreeSet<String> = reads.into_iter().map(str::to_string).collect();
+            let touched = reads.union(&txn.shares).cloned().collect();
+            let writes = txn.writes.clone();
+            let failure = state
+                .conflict(id, &touched, false)
+                .or_else(|| state.conflict(id, &writes, true));
+            if let Some(failure) = failure {
+                return Err(state.fail(id, failure));
+            }
+            state.read(id, reads);
+        }
+        if isolation == IsolationLevel::Serializable && state.is_dangerous(id) {
+            return Err(state.fail(id, SerializationFailure::DangerousStructure));
+        }
+
+        let commit = state.tick();
+        let writer_committed_first = state.txns[&id]
+            .writers
+            .iter()
+            .any(|writer| state.is_committed(writer));
+        let txn = state.txns.get_mut(&id).expect("tracked");
+        txn.commit = Some(commit);
+        txn.writer_committed_first = writer_committed_first;
+        state.collect();
+        Ok(())
+    }
+
+    /// Abort `id`, forgetting what it read and queued.
+    pub fn abort(&self, id: TxnId) {
+        let mut state = self.state();
+        state.remove(id);
+        state.collect();
+    }
+
+    /// Record a write to `table` by a statement outside any transaction,
+    /// committing as it starts.
+    pub fn record_write(&self, table: &str) {
+        let mut state = self.state();
+        if state.txns.values().all(|txn| txn.commit.is_some()) {
+            // Nothing running can conflict with it
+            return;
+        }
+        let id = state.begin(IsolationLevel::Serializable);
+        state.write(id, table);
+        let commit = state.tick();
+        state.txns.get_mut(&id).expect("tracked").commit = Some(commit);
+    }
+
+    /// Transactions running or kept for the running ones to check against.
+    pub fn tracked(&self) -> usize {
+        self.state().txns.len()
+    }
+}
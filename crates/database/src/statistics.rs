@@ -0,0 +1,56 @@
+//! Planner statistics and automatic re-analysis.
+//!
+//! `ANALYZE TABLE <table>` scans a table and stores its row and page counts
+//! and, for each column, its distinct and NULL counts, smallest and largest
+//! values and a histogram in the catalog (see [`catalog::TableStatistics`]).
+//! The planner estimates the cost of scans and joins from them.
+//!
+//! Every committed INSERT, UPDATE or DELETE adds the rows it touched to the
+//! table's modification counter, and once that churn passes the
+//! [`AutoAnalyze`] threshold the table is re-analyzed on a background task,
+//! so statistics keep up with the data without manual `ANALYZE` runs.
+//!
+//! The same writes are counted by kind, along with an estimate of each
+//! table's live rows, and shown by `information_schema.table_activity`. The
+//! counters are kept in the catalog, which is saved every thousand written
+//! rows and when the database is dropped.
+//!
+//! Statistics are estimates: storing them does not advance the catalog epoch,
+//! and rows written while a table is being scanned are counted towards its
+//! next re-analysis rather than the current one. Each node counts the writes
+//! it receives; followers in a Raft cluster do not re-analyze on replicated
+//! writes.
+
+use catalog::TableMeta;
+
+/// When to re-analyze a table automatically.
+///
+/// A table is due once the rows modified since its last analysis exceed
+/// `min_modifications + scale_factor * row_count`, where `row_count` comes
+/// from the last analysis (zero if it was never analyzed). The defaults
+/// match PostgreSQL's autovacuum analyze settings.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutoAnalyze {
+    /// Modified rows a table always tolerates before re-analysis.
+    pub min_modifications: u64,
+    /// Additional tolerated churn, as a fraction of the analyzed row count.
+    pub scale_factor: f64,
+}
+
+impl Default for AutoAnalyze {
+    fn default() -> Self {
+        Self {
+            min_modifications: 50,
+            scale_factor: 0.1,
+        }
+    }
+}
+
+impl AutoAnalyze {
+    /// Whether `table` has changed enough to need fresh statistics.
+    pub fn is_due(&self, table: &TableMeta) -> bool {
+        let rows = table.statistics.as_ref().map_or(0, |stats| stats.row_count);
+        let threshold = self.min_modifications as f64 + self.scale_factor * rows as f64;
+        table.modifications_since_analyze() as f64 > threshold
+    }
+}
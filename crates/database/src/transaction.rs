@@ -0,0 +1,274 @@
+//! Multi-statement transactions.
+//!
+//! A [`Transaction`] runs `SELECT`s as it goes and queues `INSERT`,
+//! `UPDATE` and `DELETE` statements until [`Transaction::commit`], when they
+//! apply in order. Queued writes are not visible to the transaction's own
+//! reads, so a table it has written cannot be read again before it commits.
+//! A locking `SELECT ... FOR UPDATE` counts as a write of the tables it
+//! reads, so of two transactions that lock or write the same table only the
+//! first to commit succeeds. `FOR SHARE` takes a shared claim instead: it
+//! conflicts with transactions that write or lock the table `FOR UPDATE`,
+//! but any number of transactions can share a table and all commit. The [`ssi`](crate::ssi) module
+//! decides which transactions may commit.
+//!
+//! Commit is all or nothing. While the writes apply, every row they change
+//! is kept as an [`UndoRecord`]; if one of them fails, the rows the earlier
+//! ones changed are written back, newest first, before the error is
+//! returned. The records are also saved to an undo journal between writes,
+//! so a crash part way through is rolled back when the database next opens
+//! (see [`journal`](crate::journal)).
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Result};
+use catalog::Catalog;
+use executor::UndoRecord;
+use parser::{parse_sql, RowLock, Statement};
+
+use crate::journal;
+use crate::ssi::TxnId;
+use crate::{Database, IsolationLevel, QueryResult};
+
+/// A transaction started with [`Database::begin_as`].
+///
+/// Its statements run in the session it was started in.
+///
+/// Dropping it without committing rolls it back. Once a statement fails
+/// with a [`SerializationFailure`](crate::SerializationFailure) the
+/// transaction is aborted and every later call fails; run it again from
+/// the start.
+pub struct Transaction<'a> {
+    db: &'a Database,
+    id: TxnId,
+    /// Session the statements run in
+    principal: String,
+    isolation: IsolationLevel,
+    /// Writes queued until commit, with their SQL text
+    writes: Vec<(String, Statement)>,
+    /// Committed, rolled back or aborted
+    done: bool,
+}
+
+impl<'a> Transaction<'a> {
+    pub(crate) fn new(db: &'a Database, principal: &str, isolation: IsolationLevel) -> Self {
+        Self {
+            db,
+            id: db.conflicts.begin(isolation),
+            principal: principal.to_string(),
+            isolation,
+            writes: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Level the transaction runs under.
+    pub fn isolation_level(&self) -> IsolationLevel {
+        self.isolation
+    }
+
+    /// Run one statement in the transaction.
+    ///
+    /// A `SELECT` runs at once and returns its rows; with `FOR UPDATE` it
+    /// also claims its tables as if it wrote them, with `FOR SHARE` it
+    /// claims them shared. `INSERT`,
+    /// `UPDATE` and `DELETE` are queued and return [`QueryResult::Empty`];
+    /// their results come from [`Transaction::commit`]. Other statements are
+    /// rejected.
+    pub async fn execute(&mut self, sql: &str) -> Result<QueryResult> {
+        self.ensure_running()?;
+        let mut statements = parse_sql(sql).map_err(anyhow::Error::from)?;
+        if statements.len() != 1 {
+            bail!("a transaction runs one statement at a time");
+        }
+        let stmt = statements.remove(0);
+        match &stmt {
+            Statement::Insert { table, .. }
+            | Statement::Update { table, .. }
+            | Statement::Delete { table, .. } => {
+                let table = table.clone();
+                self.abort_on_failure(self.db.conflicts.write(self.id, &table))?;
+                self.writes.push((sql.to_string(), stmt));
+                Ok(QueryResult::Empty)
+            }
+            Statement::Select { lock, .. } => {
+                let lock = *lock;
+                let tables = base_tables(&*self.db.catalog.read().await, &stmt);
+                if let Some(table) = tables.iter().find(|table| self.writes_table(table)) {
+                    bail!("table '{table}' cannot be read after it is written in the same transaction");
+                }
+                let _commit = self.db.commit_lock.lock().await;
+                let result = self.db.run_in_session(&self.principal, sql, stmt).await?;
+                self.abort_on_failure(
+                    self.db
+                        .conflicts
+                        .read(self.id, tables.iter().map(String::as_str)),
+                )?;
+                for table in &tables {
+                    match lock {
+                        Some(RowLock::Update) => {
+                            self.abort_on_failure(self.db.conflicts.write(self.id, table))?
+                        }
+                        Some(RowLock::Share) => {
+                            self.abort_on_failure(self.db.conflicts.share(self.id, table))?
+                        }
+                        None => {}
+                    }
+                }
+                Ok(result)
+            }
+            _ => bail!("only SELECT, INSERT, UPDATE and DELETE can run in a transaction"),
+        }
+    }
+
+    /// Apply the queued writes and return each one's result.
+    ///
+    /// Fails with a [`SerializationFailure`](crate::SerializationFailure),
+    /// applying nothing, if committing would not be serializable. If a write
+    /// fails as it applies, the rows the writes before it changed are
+    /// restored and its error is returned.
+    pub async fn commit(mut self) -> Result<Vec<QueryResult>> {
+        self.ensure_running()?;
+        self.done = true;
+        let _commit = self.db.commit_lock.lock().await;
+        let reads: BTreeSet<String> = {
+            let catalog = self.db.catalog.read().await;
+            self.writes
+                .iter()
+                .flat_map(|(_, stmt)| base_tables(&catalog, stmt))
+                .collect()
+        };
+        self.db
+            .conflicts
+            .commit(self.id, reads.iter().map(String::as_str))?;
+
+        let undo_log = UndoLog::default();
+        let writes = std::mem::take(&mut self.writes);
+        let applied = UNDO_LOG
+            .scope(undo_log.clone(), async {
+                let mut results = Vec::with_capacity(writes.len());
+                for (sql, stmt) in writes {
+                    self.save_journal(&undo_log).await?;
+                    results.push(self.db.run_in_session(&self.principal, &sql, stmt).await?);
+                }
+                Ok(results)
+            })
+            .await;
+        let applied = match applied {
+            Ok(results) => self.remove_journal().await.map(|()| results),
+            Err(e) => Err(e),
+        };
+        let Err(e) = applied else {
+            return applied;
+        };
+        let undo = std::mem::take(&mut *undo_log.lock().unwrap_or_else(|e| e.into_inner()));
+        if let Err(undo_error) = self.db.roll_back(undo).await {
+            // The journal stays for the next open to roll back
+            return Err(e.context(format!(
+                "the transaction's applied writes could not be rolled back: {undo_error}"
+            )));
+        }
+        self.remove_journal().await?;
+        Err(e)
+    }
+
+    /// Save the row changes applied so far to the undo journal, so a crash
+    /// before the commit completes rolls them back (see [`journal`]).
+    async fn save_journal(&self, undo_log: &UndoLog) -> Result<()> {
+        let undo = undo_log.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        if undo.is_empty() || self.db.raft.is_some() {
+            return Ok(());
+        }
+        let data_dir = self.db.data_dir.clone();
+        let key = self.db.encryption.clone();
+        tokio::task::spawn_blocking(move || journal::save(&data_dir, key.as_ref(), &undo)).await?
+    }
+
+    /// Remove the undo journal once the writes have applied or been rolled
+    /// back.
+    async fn remove_journal(&self) -> Result<()> {
+        if self.db.raft.is_some() {
+            return Ok(());
+        }
+        let data_dir = self.db.data_dir.clone();
+        tokio::task::spawn_blocking(move || journal::remove(&data_dir)).await?
+    }
+
+    /// Discard the queued writes.
+    pub fn rollback(self) {}
+
+    fn ensure_running(&self) -> Result<()> {
+        if self.done {
+            bail!("transaction was aborted; run it again from the start");
+        }
+        Ok(())
+    }
+
+    fn writes_table(&self, table: &str) -> bool {
+        self.writes
+            .iter()
+            .any(|(_, stmt)| stmt.tables().first() == Some(&table))
+    }
+
+    fn abort_on_failure<T>(&mut self, result: Result<T, crate::SerializationFailure>) -> Result<T> {
+        if result.is_err() {
+            self.done = true;
+        }
+        Ok(result?)
+    }
+}
+
+impl Drop for Transaction<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.db.conflicts.abort(self.id);
+        }
+    }
+}
+
+/// Row changes made by the writes of a committing transaction.
+type UndoLog = Arc<Mutex<Vec<UndoRecord>>>;
+
+tokio::task_local! {
+    /// Undo log of the transaction whose writes the task is applying.
+    static UNDO_LOG: UndoLog;
+}
+
+/// Whether the running statement's row changes must be kept for rollback.
+pub(crate) fn keeps_undo_log() -> bool {
+    UNDO_LOG.try_with(|_| ()).is_ok()
+}
+
+/// Keep `records` for rolling back the committing transaction, if the
+/// running statement belongs to one.
+pub(crate) fn record_undo(records: impl IntoIterator<Item = UndoRecord>) {
+    let _ = UNDO_LOG.try_with(|undo_log| {
+        undo_log
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend(records)
+    });
+}
+
+/// The tables `stmt` reads or writes, with views replaced by the tables
+/// they read.
+fn base_tables(catalog: &Catalog, stmt: &Statement) -> BTreeSet<String> {
+    let mut tables = BTreeSet::new();
+    for name in stmt.tables() {
+        match catalog
+            .view(name)
+            .ok()
+            .and_then(|view| parse_sql(&view.query).ok())
+        {
+            Some(queries) => {
+                for query in &queries {
+                    tables.extend(base_tables(catalog, query));
+                }
+            }
+            None => {
+                tables.insert(name.to_string());
+            }
+        }
+    }
+    tables
+}
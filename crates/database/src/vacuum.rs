@@ -0,0 +1,189 @@
+//! Reclaiming the space of deleted rows.
+//!
+//! Deleting a row only empties its slot, and an update that no longer fits
+//! on its page leaves the old copy's slot behind too. Later inserts reuse
+//! that space, but a heap file never shrinks, so a table that once held many
+//! more rows keeps its pages. `VACUUM [<table>]` rewrites the table's storage
+//! with only its live rows (see [`storage::HeapTable::vacuum`]), dropping
+//! pages that end up empty.
+//!
+//! Rows that move get new record IDs, so the table's primary key and
+//! secondary indexes are rebuilt from the compacted storage, and each move
+//! is logged to the WAL as a delete followed by an insert, keeping logical
+//! replication's record IDs in step. The table is held exclusively for the
+//! whole rewrite.
+//!
+//! Tables can also be vacuumed on a background task once enough rows were
+//! updated or deleted, by passing an [`AutoVacuum`] policy to
+//! [`crate::Database::with_auto_vacuum`]. Unlike auto-analyze this is off by
+//! default: a vacuum blocks every statement until it has copied the table.
+
+use std::{ops::DerefMut, path::Path, sync::Arc};
+
+use anyhow::Result;
+use buffer::SharedPager;
+use catalog::{Catalog, IndexKind, TableMeta};
+use common::Priority;
+use executor::{EngineRegistry, ExecutionContext};
+use storage::HeapTable;
+use tokio::sync::{Mutex, RwLock};
+use wal::{Wal, WalRecord};
+
+use crate::index_build;
+
+/// When to vacuum a table automatically.
+///
+/// A table is due once the rows updated or deleted since its last vacuum
+/// exceed `min_dead_rows + scale_factor * live_rows`, using the table's
+/// estimated live rows. The defaults match PostgreSQL's autovacuum settings.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutoVacuum {
+    /// Dead rows a table always tolerates before it is vacuumed.
+    pub min_dead_rows: u64,
+    /// Additional tolerated dead rows, as a fraction of the live rows.
+    pub scale_factor: f64,
+}
+
+impl Default for AutoVacuum {
+    fn default() -> Self {
+        Self {
+            min_dead_rows: 50,
+            scale_factor: 0.2,
+        }
+    }
+}
+
+impl AutoVacuum {
+    /// Whether `table` has enough dead rows to be worth compacting.
+    pub fn is_due(&self, table: &TableMeta) -> bool {
+        let activity = table.activity();
+        let threshold = self.min_dead_rows as f64 + self.scale_factor * activity.live_rows as f64;
+        activity.dead_rows as f64 > threshold
+    }
+}
+
+/// What vacuuming one table did.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VacuumOutcome {
+    /// Rows given a new record ID.
+    pub rows_moved: u64,
+    /// Pages holding rows before and after; both zero for storage that does
+    /// not keep rows in pages.
+    pub pages_before: u64,
+    pub pages_after: u64,
+}
+
+/// Compact the storage of `table`, rebuild its indexes and reset its dead
+/// row count in the catalog.
+///
+/// Runs on a blocking thread under the catalog write lock, so no statement
+/// sees the table while its rows move.
+pub(crate) fn vacuum_table(
+    catalog: &RwLock<Catalog>,
+    pager: &SharedPager,
+    wal: &Mutex<Wal>,
+    engines: &Arc<EngineRegistry>,
+    data_dir: &Path,
+    catalog_path: &Path,
+    table: &str,
+) -> Result<VacuumOutcome> {
+    let catalog_lock = catalog.blocking_write();
+    let meta = catalog_lock.table(table).map_err(anyhow::Error::from)?;
+    let mut pager = pager.clone();
+    let mut wal_lock = wal.blocking_lock();
+    let mut ctx = ExecutionContext::new(
+        &catalog_lock,
+        &mut pager,
+        wal_lock.deref_mut(),
+        data_dir.to_path_buf(),
+    )
+    .with_engines(engines.clone())
+    .with_priority(Priority::Background);
+
+    let pages = |heap: &mut dyn HeapTable| -> Result<u64> {
+        let usage = heap.page_usage().map_err(anyhow::Error::from)?;
+        Ok(usage.map_or(0, |usage| usage.pages))
+    };
+    let (pages_before, pages_after, moves) = {
+        let mut heap = ctx.heap_table(meta.id).map_err(anyhow::Error::from)?;
+        let pages_before = pages(&mut heap)?;
+        let moved = heap.vacuum().map_err(anyhow::Error::from)?;
+        let mut moves = Vec::with_capacity(moved.len());
+        for (old, new) in moved {
+            let row = heap.get(new).map_err(anyhow::Error::from)?;
+            moves.push((old, new, row.values));
+        }
+        (pages_before, pages(&mut heap)?, moves)
+    };
+    meta.record_vacuum();
+    catalog_lock
+        .save(catalog_path)
+        .map_err(anyhow::Error::from)?;
+    if moves.is_empty() {
+        return Ok(VacuumOutcome {
+            rows_moved: 0,
+            pages_before,
+            pages_after,
+        });
+    }
+
+    // All deletes come first: a moved row's new record ID may be the old one
+    // of a row that moved after it
+    let mut records: Vec<WalRecord> = moves
+        .iter()
+        .map(|(old, _, _)| WalRecord::Delete {
+            table: meta.id,
+            rid: *old,
+        })
+        .collect();
+    records.extend(moves.iter().map(|(_, new, row)| WalRecord::Insert {
+        table: meta.id,
+        row: row.clone(),
+        rid: *new,
+    }));
+    ctx.log_dml_batch(records).map_err(anyhow::Error::from)?;
+
+    // The primary key index is rebuilt from the table when its file is missing
+    let pk_path = data_dir.join(format!("{}.pk_idx", meta.name));
+    if pk_path.exists() {
+        std::fs::remove_file(&pk_path)?;
+    }
+    if ctx
+        .pk_index(meta.id)
+        .map_err(anyhow::Error::from)?
+        .is_some()
+    {
+        ctx.save_pk_index(meta.id).map_err(anyhow::Error::from)?;
+    }
+
+    for index in meta
+        .indexes
+        .iter()
+        .filter(|index| matches!(index.kind, IndexKind::BTree | IndexKind::Hash))
+    {
+        let columns: Vec<usize> = index.columns.iter().map(|c| *c as usize).collect();
+        let entries = index_build::collect_entries(
+            engines,
+            data_dir,
+            meta,
+            catalog_lock.encryption_key(),
+            &columns,
+            index_build::build_threads(),
+        )?;
+        let path = data_dir.join(format!("index_{}.idx", index.id.0));
+        index_build::write_index(
+            &index.kind,
+            &path,
+            index.id,
+            meta.compression,
+            catalog_lock.encryption_key(),
+            entries,
+        )?;
+    }
+
+    Ok(VacuumOutcome {
+        rows_moved: moves.len() as u64,
+        pages_before,
+        pages_after,
+    })
+}
@@ -0,0 +1,207 @@
+//! Integration tests for the statement queue behind
+//! `Database::with_max_executing_statements`.
+
+use anyhow::Result;
+use database::admission::AdmissionQueue;
+use database::{AdmissionStats, Database, Priority, QueryResult};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+async fn burst_of_statements_queues_instead_of_failing() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10)
+        .await?
+        .with_max_executing_statements(2);
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY, v INT)")
+        .await?;
+    let db = Arc::new(db);
+
+    let mut statements = Vec::new();
+    for i in 0..16 {
+        let db = Arc::clone(&db);
+        statements.push(tokio::spawn(async move {
+            let principal = format!("client-{i}");
+            db.execute_as(&principal, &format!("INSERT INTO t VALUES ({i}, {i})"))
+                .await
+        }));
+    }
+    for statement in statements {
+        assert!(matches!(
+            statement.await??,
+            QueryResult::Count { affected: 1, .. } | QueryResult::Inserted { affected: 1, .. }
+        ));
+    }
+
+    match db.execute("SELECT id FROM t").await? {
+        QueryResult::Rows { rows, .. } => assert_eq!(rows.len(), 16),
+        other => panic!("expected rows, got {other:?}"),
+    }
+    let stats = db.admission_stats();
+    assert_eq!(stats.admitted, 18);
+    assert_eq!((stats.executing, stats.waiting), (0, 0));
+    assert!(stats.queued <= stats.admitted);
+    assert!(stats.max_queue_time <= stats.total_queue_time);
+    Ok(())
+}
+
+#[tokio::test]
+async fn statements_are_unqueued_by_default() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY)").await?;
+    db.execute("SELECT id FROM t").await?;
+
+    let stats = db.admission_stats();
+    assert_eq!(stats.admitted, 2);
+    assert_eq!(stats.queued, 0);
+    assert_eq!(stats.mean_queue_time(), Duration::ZERO);
+    Ok(())
+}
+
+#[tokio::test]
+async fn waiting_statements_are_admitted_in_arrival_order() {
+    let queue = Arc::new(AdmissionQueue::new(Some(1)));
+    let first = queue.admit(Priority::Normal).await;
+
+    let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut waiters = Vec::new();
+    for i in 0..3 {
+        let waiter_queue = Arc::clone(&queue);
+        let order_tx = order_tx.clone();
+        waiters.push(tokio::spawn(async move {
+            let _admission = waiter_queue.admit(Priority::Normal).await;
+            order_tx.send(i).unwrap();
+        }));
+        // Let each waiter join the queue before the next one
+        while queue.stats().waiting <= i {
+            tokio::task::yield_now().await;
+        }
+    }
+    assert_eq!(queue.stats().executing, 1);
+
+    drop(first);
+    for waiter in waiters {
+        waiter.await.unwrap();
+    }
+    let order: Vec<_> = std::iter::from_fn(|| order_rx.try_recv().ok()).collect();
+    assert_eq!(order, vec![0, 1, 2]);
+
+    let stats = queue.stats();
+    assert_eq!((stats.admitted, stats.queued), (4, 3));
+    assert_eq!((stats.executing, stats.waiting), (0, 0));
+}
+
+#[tokio::test]
+async fn cancelled_statements_leave_the_queue() {
+    let queue = AdmissionQueue::new(Some(1));
+    let first = queue.admit(Priority::Normal).await;
+
+    let waited =
+        tokio::time::timeout(Duration::from_millis(10), queue.admit(Priority::Normal)).await;
+    assert!(waited.is_err());
+    let stats = queue.stats();
+    assert_eq!((stats.admitted, stats.waiting), (1, 0));
+
+    // The cancelled statement's place does not hold up the next one
+    drop(first);
+    let next = tokio::time::timeout(Duration::from_secs(5), queue.admit(Priority::Normal)).await;
+    assert!(next.is_ok());
+}
+
+#[tokio::test]
+async fn more_urgent_statements_are_admitted_first() {
+    let queue = Arc::new(AdmissionQueue::new(Some(1)));
+    let first = queue.admit(Priority::Normal).await;
+
+    let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut waiters = Vec::new();
+    let arrivals = [
+        Priority::Background,
+        Priority::Normal,
+        Priority::High,
+        Priority::Normal,
+        Priority::High,
+    ];
+    for (i, priority) in arrivals.into_iter().enumerate() {
+        let waiter_queue = Arc::clone(&queue);
+        let order_tx = order_tx.clone();
+        waiters.push(tokio::spawn(async move {
+            let _admission = waiter_queue.admit(priority).await;
+            order_tx.send((priority, i)).unwrap();
+        }));
+        while queue.stats().waiting <= i {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    drop(first);
+    for waiter in waiters {
+        waiter.await.unwrap();
+    }
+    let order: Vec<_> = std::iter::from_fn(|| order_rx.try_recv().ok()).collect();
+    assert_eq!(
+        order,
+        vec![
+            (Priority::High, 2),
+            (Priority::High, 4),
+            (Priority::Normal, 1),
+            (Priority::Normal, 3),
+            (Priority::Background, 0),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn maintenance_is_admitted_after_foreground_statements() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10)
+        .await?
+        .with_max_executing_statements(1);
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY)").await?;
+    db.execute("INSERT INTO t VALUES (1), (2), (3)").await?;
+    let db = Arc::new(db);
+
+    // The first statement takes the only slot and waits for the catalog,
+    // so the rest queue up behind it
+    let catalog = db.catalog();
+    let held = catalog.write().await;
+    let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut statements = Vec::new();
+    for (i, sql) in [
+        "SELECT id FROM t",
+        "ANALYZE TABLE t",
+        "VACUUM t",
+        "SELECT id FROM t",
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let session = Arc::clone(&db);
+        let order_tx = order_tx.clone();
+        statements.push(tokio::spawn(async move {
+            let result = session.execute(sql).await;
+            order_tx.send(sql).unwrap();
+            result
+        }));
+        let queued = |stats: AdmissionStats| stats.executing + stats.waiting;
+        while queued(db.admission_stats()) <= i {
+            tokio::task::yield_now().await;
+        }
+    }
+    drop(held);
+    for statement in statements {
+        statement.await??;
+    }
+    let order: Vec<_> = std::iter::from_fn(|| order_rx.try_recv().ok()).collect();
+    assert_eq!(
+        order,
+        vec![
+            "SELECT id FROM t",
+            "SELECT id FROM t",
+            "ANALYZE TABLE t",
+            "VACUUM t"
+        ]
+    );
+    Ok(())
+}
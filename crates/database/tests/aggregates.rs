@@ -0,0 +1,188 @@
+//! Integration tests for aggregate functions and GROUP BY.
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+use types::Value;
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+#[tokio::test]
+async fn count_star_counts_rows_and_count_column_skips_nulls() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY, v INT)")
+        .await?;
+    db.execute("INSERT INTO t VALUES (1, 10), (2, NULL), (3, 30), (4, NULL)")
+        .await?;
+
+    let rows = select_rows(
+        &db,
+        "SELECT COUNT(*), COUNT(v), SUM(v), MIN(v), MAX(v), AVG(v) FROM t",
+    )
+    .await?;
+    assert_eq!(
+        rows,
+        vec![vec![
+            Value::Int(4),
+            Value::Int(2),
+            Value::Int(40),
+            Value::Int(10),
+            Value::Int(30),
+            Value::Float(20.0),
+        ]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn aggregates_of_only_nulls_or_no_rows() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY, v INT)")
+        .await?;
+
+    // No rows: one result row, counts are 0 and the rest NULL
+    let empty = vec![vec![
+        Value::Int(0),
+        Value::Int(0),
+        Value::Null,
+        Value::Null,
+        Value::Null,
+    ]];
+    let sql = "SELECT COUNT(*), COUNT(v), SUM(v), MAX(v), AVG(v) FROM t";
+    assert_eq!(select_rows(&db, sql).await?, empty);
+
+    // Only NULLs: the same, except that COUNT(*) still counts the rows
+    db.execute("INSERT INTO t VALUES (1, NULL), (2, NULL)")
+        .await?;
+    let mut nulls = empty;
+    nulls[0][0] = Value::Int(2);
+    assert_eq!(select_rows(&db, sql).await?, nulls);
+
+    // A grouped query over no rows has no groups
+    let rows = select_rows(&db, "SELECT v, COUNT(*) FROM t WHERE id > 5 GROUP BY v").await?;
+    assert!(rows.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn group_by_puts_null_keys_in_one_group() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE emp (id INT PRIMARY KEY, dept TEXT, salary INT)")
+        .await?;
+    db.execute(
+        "INSERT INTO emp VALUES (1, 'eng', 100), (2, NULL, 50), (3, 'eng', NULL), \
+         (4, NULL, 70), (5, 'ops', NULL)",
+    )
+    .await?;
+
+    let rows = select_rows(
+        &db,
+        "SELECT dept, COUNT(*), COUNT(salary), SUM(salary) FROM emp \
+         GROUP BY dept HAVING COUNT(*) > 1 ORDER BY dept",
+    )
+    .await?;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Null, Value::Int(2), Value::Int(2), Value::Int(120)],
+            vec![
+                Value::Text("eng".into()),
+                Value::Int(2),
+                Value::Int(1),
+                Value::Int(100)
+            ],
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn ungrouped_columns_are_rejected() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE emp (id INT PRIMARY KEY, dept TEXT, salary INT)")
+        .await?;
+
+    for sql in [
+        "SELECT dept, salary, COUNT(*) FROM emp GROUP BY dept",
+        "SELECT id FROM emp HAVING COUNT(*) > 0",
+        "SELECT SUM(dept) FROM emp",
+        "SELECT id FROM emp WHERE COUNT(*) > 0",
+    ] {
+        assert!(db.execute(sql).await.is_err(), "{sql} should fail");
+    }
+    Ok(())
+}
+
+/// Reference model: COUNT(*), COUNT, SUM, MIN and MAX of `v` per key `k`,
+/// computed directly with SQL's NULL rules.
+fn reference(rows: &[(i64, Option<i64>, Option<i64>)]) -> Vec<Vec<Value>> {
+    let mut groups: BTreeMap<Option<i64>, Vec<Option<i64>>> = BTreeMap::new();
+    for (_, k, v) in rows {
+        groups.entry(*k).or_default().push(*v);
+    }
+    let opt = |v: Option<i64>| v.map_or(Value::Null, Value::Int);
+    groups
+        .into_iter()
+        .map(|(k, vs)| {
+            let present: Vec<i64> = vs.iter().flatten().copied().collect();
+            let sum = (!present.is_empty()).then(|| present.iter().sum());
+            vec![
+                opt(k),
+                Value::Int(vs.len() as i64),
+                Value::Int(present.len() as i64),
+                opt(sum),
+                opt(present.iter().min().copied()),
+                opt(present.iter().max().copied()),
+            ]
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn grouped_aggregates_match_a_reference_model() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY, k INT, v INT)")
+        .await?;
+
+    // Deterministic mix of keys and values, roughly a quarter of each NULL
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move |n: u64| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state % n
+    };
+    let rows: Vec<_> = (0..200)
+        .map(|id| {
+            let k = (next(4) != 0).then(|| next(5) as i64);
+            let v = (next(4) != 0).then(|| next(1000) as i64 - 500);
+            (id, k, v)
+        })
+        .collect();
+    for chunk in rows.chunks(50) {
+        let values: Vec<String> = chunk
+            .iter()
+            .map(|(id, k, v)| {
+                let sql = |x: &Option<i64>| x.map_or("NULL".to_string(), |x| x.to_string());
+                format!("({id}, {}, {})", sql(k), sql(v))
+            })
+            .collect();
+        db.execute(&format!("INSERT INTO t VALUES {}", values.join(", ")))
+            .await?;
+    }
+
+    // NULLS FIRST matches the reference model's ordering of None
+    let actual = select_rows(
+        &db,
+        "SELECT k, COUNT(*), COUNT(v), SUM(v), MIN(v), MAX(v) FROM t \
+         GROUP BY k ORDER BY k NULLS FIRST",
+    )
+    .await?;
+    assert_eq!(actual, reference(&rows));
+    Ok(())
+}
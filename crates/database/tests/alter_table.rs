@@ -0,0 +1,135 @@
+//! Integration tests for ALTER TABLE ADD/DROP/RENAME COLUMN.
+
+use anyhow::Result;
+use database::Database;
+use types::Value;
+
+mod helpers;
+use helpers::select_rows;
+
+async fn open(dir: &std::path::Path) -> Result<Database> {
+    Database::new(dir, "catalog.json", "test.wal", 10).await
+}
+
+#[tokio::test]
+async fn add_column_fills_existing_rows_with_default() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path()).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (1, 'alice'), (2, 'bob')")
+        .await?;
+
+    db.execute("ALTER TABLE users ADD COLUMN score INT DEFAULT 10")
+        .await?;
+    db.execute("ALTER TABLE users ADD COLUMN note TEXT").await?;
+    db.execute("INSERT INTO users VALUES (3, 'carol', 7, 'new')")
+        .await?;
+    db.execute("UPDATE users SET score = 20 WHERE id = 2")
+        .await?;
+
+    let rows = select_rows(&db, "SELECT id, score, note FROM users ORDER BY id").await?;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(1), Value::Int(10), Value::Null],
+            vec![Value::Int(2), Value::Int(20), Value::Null],
+            vec![Value::Int(3), Value::Int(7), Value::Text("new".into())],
+        ]
+    );
+
+    let rows = select_rows(&db, "SELECT id FROM users WHERE score = 10").await?;
+    assert_eq!(rows, vec![vec![Value::Int(1)]]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn index_on_added_column_covers_old_rows() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path()).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (1), (2)").await?;
+    db.execute("ALTER TABLE users ADD COLUMN active BOOL DEFAULT true")
+        .await?;
+    db.execute("CREATE INDEX idx_active ON users (active)")
+        .await?;
+
+    let rows = select_rows(&db, "SELECT id FROM users WHERE active = true").await?;
+    assert_eq!(rows, vec![vec![Value::Int(1)], vec![Value::Int(2)]]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn drop_column_rewrites_rows_and_survives_restart() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = open(temp_dir.path()).await?;
+        db.execute("CREATE TABLE items (sku TEXT, id INT PRIMARY KEY, qty INT)")
+            .await?;
+        db.execute("INSERT INTO items VALUES ('a', 1, 5), ('b', 2, 6)")
+            .await?;
+        db.execute("ALTER TABLE items DROP COLUMN sku").await?;
+
+        let rows = select_rows(&db, "SELECT * FROM items").await?;
+        assert_eq!(
+            rows,
+            vec![
+                vec![Value::Int(1), Value::Int(5)],
+                vec![Value::Int(2), Value::Int(6)],
+            ]
+        );
+
+        let err = db
+            .execute("INSERT INTO items VALUES (1, 9)")
+            .await
+            .expect_err("primary key still enforced");
+        assert!(err.to_string().contains("duplicate"), "{err}");
+    }
+
+    let db = open(temp_dir.path()).await?;
+    db.execute("INSERT INTO items VALUES (3, 7)").await?;
+    let rows = select_rows(&db, "SELECT id, qty FROM items WHERE id = 3").await?;
+    assert_eq!(rows, vec![vec![Value::Int(3), Value::Int(7)]]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn rename_column_keeps_data() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path()).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (1, 'alice')").await?;
+    db.execute("ALTER TABLE users RENAME COLUMN name TO full_name")
+        .await?;
+
+    let rows = select_rows(&db, "SELECT full_name FROM users").await?;
+    assert_eq!(rows, vec![vec![Value::Text("alice".into())]]);
+    assert!(db.execute("SELECT name FROM users").await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn alter_table_rejects_invalid_changes() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path()).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT) WITH (row_version = true)")
+        .await?;
+
+    for (sql, expected) in [
+        ("ALTER TABLE users DROP COLUMN id", "primary key"),
+        ("ALTER TABLE users DROP COLUMN missing", "unknown column"),
+        ("ALTER TABLE users ADD COLUMN name TEXT", "duplicate column"),
+        ("ALTER TABLE users DROP COLUMN _version", "reserved"),
+        (
+            "ALTER TABLE users RENAME COLUMN name TO _version",
+            "reserved",
+        ),
+        ("ALTER TABLE missing ADD COLUMN x INT", "unknown table"),
+    ] {
+        let err = db.execute(sql).await.expect_err(sql);
+        assert!(err.to_string().contains(expected), "{sql}: {err}");
+    }
+    Ok(())
+}
@@ -0,0 +1,134 @@
+//! Integration tests for audit logging of data access.
+
+use anyhow::Result;
+use database::{AuditRecord, Database, EncryptionKey, LOCAL_PRINCIPAL};
+
+mod helpers;
+use helpers::create_db;
+
+fn statements(records: &[AuditRecord]) -> Vec<&str> {
+    records.iter().map(|r| r.statement.as_str()).collect()
+}
+
+#[tokio::test]
+async fn audited_tables_record_principal_tables_and_counts() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute_as(
+        "alice",
+        "CREATE TABLE payroll (id INT PRIMARY KEY, salary INT) WITH (audit = true)",
+    )
+    .await?;
+    db.execute("CREATE TABLE staff (id INT, name TEXT)").await?;
+    db.execute_as("alice", "INSERT INTO payroll VALUES (1, 100), (2, 200)")
+        .await?;
+    db.execute("INSERT INTO staff VALUES (1, 'bob')").await?;
+    db.execute_as(
+        "10.0.0.7:5123",
+        "SELECT s.name, p.salary FROM staff s JOIN payroll p ON s.id = p.id",
+    )
+    .await?;
+    db.execute("SELECT * FROM staff").await?;
+    assert!(db
+        .execute_as("mallory", "SELECT nope FROM payroll")
+        .await
+        .is_err());
+
+    let records = db.audit_log().read()?;
+    assert_eq!(
+        statements(&records),
+        vec![
+            "CREATE TABLE payroll (id INT PRIMARY KEY, salary INT) WITH (audit = true)",
+            "INSERT INTO payroll VALUES (1, 100), (2, 200)",
+            "SELECT s.name, p.salary FROM staff s JOIN payroll p ON s.id = p.id",
+            "SELECT nope FROM payroll",
+        ]
+    );
+
+    assert_eq!(records[0].principal, "alice");
+    assert_eq!(records[0].rows, None);
+    assert_eq!(records[1].rows, Some(2));
+    assert_eq!(records[2].principal, "10.0.0.7:5123");
+    assert_eq!(records[2].tables, vec!["staff", "payroll"]);
+    assert_eq!(records[2].rows, Some(1));
+    assert_eq!(records[3].principal, "mallory");
+    assert!(records[3].error.is_some());
+    assert!(records.iter().all(|r| r.timestamp_ms > 0));
+    Ok(())
+}
+
+#[tokio::test]
+async fn audit_can_be_toggled_per_table_and_survives_restart() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = create_db(temp_dir.path()).await?;
+        db.execute("CREATE TABLE t (id INT)").await?;
+        db.execute("INSERT INTO t VALUES (1)").await?;
+        db.set_table_audit("t", true).await?;
+        db.execute("DELETE FROM t WHERE id = 1").await?;
+        assert!(db.set_table_audit("missing", true).await.is_err());
+    }
+
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("INSERT INTO t VALUES (2)").await?;
+    db.set_table_audit("t", false).await?;
+    db.execute("INSERT INTO t VALUES (3)").await?;
+    db.set_table_audit("t", true).await?;
+    db.execute("DROP TABLE t").await?;
+
+    let records = db.audit_log().read()?;
+    assert_eq!(
+        statements(&records),
+        vec![
+            "DELETE FROM t WHERE id = 1",
+            "INSERT INTO t VALUES (2)",
+            "DROP TABLE t"
+        ]
+    );
+    assert!(records.iter().all(|r| r.principal == LOCAL_PRINCIPAL));
+    assert_eq!(records[0].rows, Some(1));
+
+    // Reset clears data but never the audit log
+    db.reset().await?;
+    assert_eq!(db.audit_log().read()?.len(), 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn encrypted_databases_seal_audit_records() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let key = EncryptionKey::from_hex(&"3c".repeat(32))?;
+    let open = || {
+        Database::with_encryption(
+            temp_dir.path(),
+            "catalog.json",
+            "test.wal",
+            10,
+            None,
+            Some(key.clone()),
+        )
+    };
+    let create = "CREATE TABLE vault (id INT, secret TEXT) WITH (audit = true)";
+    let insert = "INSERT INTO vault VALUES (1, 'launch-code-0000')";
+    let path = {
+        let db = open().await?;
+        db.execute(create).await?;
+        db.execute(insert).await?;
+        db.audit_log().path().to_path_buf()
+    };
+
+    let raw = std::fs::read(path)?;
+    for needle in ["launch-code", "vault", "INSERT"] {
+        assert!(
+            !raw.windows(needle.len()).any(|w| w == needle.as_bytes()),
+            "audit log contains plaintext {needle:?}"
+        );
+    }
+
+    let db = open().await?;
+    let records = db.audit_log().read()?;
+    assert_eq!(statements(&records), vec![create, insert]);
+    assert_eq!(records[1].rows, Some(1));
+    Ok(())
+}
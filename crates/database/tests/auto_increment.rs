@@ -0,0 +1,85 @@
+//! Integration tests for auto-increment primary keys.
+
+use anyhow::Result;
+use database::{Database, QueryResult};
+use types::Value;
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+async fn generated_ids(db: &Database, sql: &str) -> Result<Vec<i64>> {
+    match db.execute(sql).await? {
+        QueryResult::Inserted { generated_ids, .. } => Ok(generated_ids),
+        other => panic!("expected generated ids, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn serial_primary_key_is_generated_and_returned() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE users (id SERIAL PRIMARY KEY, name TEXT)")
+        .await?;
+
+    assert_eq!(
+        generated_ids(&db, "INSERT INTO users (name) VALUES ('a'), ('b')").await?,
+        vec![1, 2]
+    );
+    // NULL also asks for a generated value
+    assert_eq!(
+        generated_ids(&db, "INSERT INTO users VALUES (NULL, 'c')").await?,
+        vec![3]
+    );
+    // Explicit keys are kept, and later generated keys skip them
+    assert!(matches!(
+        db.execute("INSERT INTO users VALUES (4, 'd')").await?,
+        QueryResult::Count { affected: 1, .. }
+    ));
+    assert_eq!(
+        generated_ids(&db, "INSERT INTO users (name) VALUES ('e')").await?,
+        vec![5]
+    );
+
+    let rows = select_rows(&db, "SELECT id, name FROM users ORDER BY id").await?;
+    let ids: Vec<Value> = rows.into_iter().map(|row| row[0].clone()).collect();
+    assert_eq!(ids, (1..=5).map(Value::Int).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[tokio::test]
+async fn sequence_survives_restart() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = create_db(temp_dir.path()).await?;
+        db.execute("CREATE TABLE events (id INT AUTO_INCREMENT PRIMARY KEY, kind TEXT)")
+            .await?;
+        generated_ids(&db, "INSERT INTO events (kind) VALUES ('a'), ('b')").await?;
+    }
+
+    let db = create_db(temp_dir.path()).await?;
+    assert_eq!(
+        generated_ids(&db, "INSERT INTO events (kind) VALUES ('c')").await?,
+        vec![3]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn auto_increment_requires_int_in_create_table() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    let err = db
+        .execute("CREATE TABLE t (id TEXT AUTO_INCREMENT PRIMARY KEY)")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("must be INT"), "{err}");
+
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY)").await?;
+    let err = db
+        .execute("ALTER TABLE t ADD COLUMN seq SERIAL")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("auto-increment"), "{err}");
+    Ok(())
+}
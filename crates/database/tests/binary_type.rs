@@ -0,0 +1,183 @@
+//! Integration tests for BYTEA columns and values larger than a page.
+
+use anyhow::Result;
+use types::Value;
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+fn bytes(b: &[u8]) -> Value {
+    Value::Bytes(b.to_vec())
+}
+
+fn hex(b: &[u8]) -> String {
+    b.iter().map(|byte| format!("{byte:02X}")).collect()
+}
+
+#[tokio::test]
+async fn bytea_columns_read_hex_and_text() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute("CREATE TABLE files (id INT PRIMARY KEY, body BYTEA)")
+        .await?;
+    db.execute("INSERT INTO files VALUES (1, X'CAFE'), (2, '\\xbeef'), (3, 'hi'), (4, NULL)")
+        .await?;
+
+    assert_eq!(
+        select_rows(&db, "SELECT body FROM files ORDER BY id").await?,
+        vec![
+            vec![bytes(&[0xca, 0xfe])],
+            vec![bytes(&[0xbe, 0xef])],
+            vec![bytes(b"hi")],
+            vec![Value::Null]
+        ]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM files WHERE body = X'BEEF'").await?,
+        vec![vec![Value::Int(2)]]
+    );
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT CAST(body AS TEXT), LENGTH(body) FROM files WHERE id = 1"
+        )
+        .await?,
+        vec![vec![Value::Text("\\xcafe".into()), Value::Int(2)]]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM files WHERE id < 4 ORDER BY body DESC").await?,
+        vec![
+            vec![Value::Int(1)],
+            vec![Value::Int(2)],
+            vec![Value::Int(3)]
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn encode_and_decode_convert_base64_and_hex() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute("CREATE TABLE files (id INT PRIMARY KEY, body BYTEA)")
+        .await?;
+    db.execute("INSERT INTO files VALUES (1, DECODE('aGVsbG8=', 'base64'))")
+        .await?;
+
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT body, ENCODE(body, 'base64'), ENCODE(body, 'hex') FROM files"
+        )
+        .await?,
+        vec![vec![
+            bytes(b"hello"),
+            Value::Text("aGVsbG8=".into()),
+            Value::Text("68656c6c6f".into())
+        ]]
+    );
+
+    db.execute("UPDATE files SET body = DECODE('00ff', 'hex') WHERE id = 1")
+        .await?;
+    assert_eq!(
+        select_rows(&db, "SELECT body FROM files").await?,
+        vec![vec![bytes(&[0x00, 0xff])]]
+    );
+
+    let err = db
+        .execute("SELECT DECODE('@@', 'base64') FROM files")
+        .await
+        .expect_err("not base64");
+    assert!(format!("{err:#}").contains("invalid base64"), "{err:#}");
+    let err = db
+        .execute("SELECT ENCODE(body, 'octal') FROM files")
+        .await
+        .expect_err("unknown format");
+    assert!(format!("{err:#}").contains("'hex' or 'base64'"), "{err:#}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn bytea_columns_reject_other_values() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute("CREATE TABLE files (id INT PRIMARY KEY, body BYTEA, name TEXT)")
+        .await?;
+    let err = db
+        .execute("INSERT INTO files VALUES (1, 12, 'a')")
+        .await
+        .expect_err("an int is not bytes");
+    assert!(
+        format!("{err:#}").contains("has type BYTEA and cannot hold"),
+        "{err:#}"
+    );
+    assert!(db
+        .execute("INSERT INTO files VALUES (1, '\\xzz', 'a')")
+        .await
+        .is_err());
+    assert!(db
+        .execute("INSERT INTO files VALUES (1, X'00', X'00')")
+        .await
+        .is_err());
+    assert!(db
+        .execute("CREATE INDEX idx_body ON files (body)")
+        .await
+        .is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn values_larger_than_a_page_are_stored_and_retrieved() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let blob: Vec<u8> = (0..20_000).map(|i| (i % 253) as u8).collect();
+    let essay = "All work and no play. ".repeat(500);
+    {
+        let db = create_db(temp_dir.path()).await?;
+        db.execute("CREATE TABLE docs (id INT PRIMARY KEY, title TEXT, body BYTEA, notes TEXT)")
+            .await?;
+        for id in 1..=5 {
+            db.execute(&format!(
+                "INSERT INTO docs VALUES ({id}, 'doc {id}', X'{}', '{essay}')",
+                hex(&blob)
+            ))
+            .await?;
+        }
+
+        assert_eq!(
+            select_rows(&db, "SELECT body, notes FROM docs WHERE id = 3").await?,
+            vec![vec![Value::Bytes(blob.clone()), Value::Text(essay.clone())]]
+        );
+        // Scans and index builds pass over the pages holding the values
+        db.execute("CREATE INDEX idx_title ON docs (title)").await?;
+        assert_eq!(
+            select_rows(&db, "SELECT id FROM docs WHERE title = 'doc 5'").await?,
+            vec![vec![Value::Int(5)]]
+        );
+        db.execute("UPDATE docs SET body = X'01' WHERE id = 2")
+            .await?;
+        db.execute("DELETE FROM docs WHERE id = 4").await?;
+    }
+
+    let db = create_db(temp_dir.path()).await?;
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT id, LENGTH(body), LENGTH(notes) FROM docs ORDER BY id"
+        )
+        .await?,
+        vec![
+            vec![Value::Int(1), Value::Int(20_000), Value::Int(11_000)],
+            vec![Value::Int(2), Value::Int(1), Value::Int(11_000)],
+            vec![Value::Int(3), Value::Int(20_000), Value::Int(11_000)],
+            vec![Value::Int(5), Value::Int(20_000), Value::Int(11_000)],
+        ]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT body FROM docs WHERE id = 5").await?,
+        vec![vec![Value::Bytes(blob)]]
+    );
+    Ok(())
+}
@@ -0,0 +1,105 @@
+//! Integration tests for `Database::insert_rows`.
+
+use anyhow::Result;
+use common::Row;
+use database::{bulk::BATCH_PAGES, Database, QueryResult};
+use futures::{stream, StreamExt, TryStreamExt};
+use types::Value;
+
+async fn open(dir: &std::path::Path) -> Result<Database> {
+    Database::new(dir, "catalog.json", "test.wal", 10).await
+}
+
+async fn count(db: &Database, sql: &str) -> Result<usize> {
+    match db.execute(sql).await? {
+        QueryResult::Rows { rows, .. } => Ok(rows.len()),
+        other => panic!("expected rows, got {other:?}"),
+    }
+}
+
+fn text_rows(n: i64) -> impl futures::Stream<Item = Row> {
+    stream::iter((1..=n).map(|i| {
+        Row::new(vec![
+            Value::Int(i),
+            Value::Text(format!("row {i}")),
+            Value::Text("x".repeat(100)),
+        ])
+    }))
+}
+
+#[tokio::test]
+async fn rows_are_inserted_in_batches_with_progress() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = open(temp_dir.path()).await?;
+        db.execute("CREATE TABLE t (id INT PRIMARY KEY, v TEXT, pad TEXT)")
+            .await?;
+        db.execute("CREATE INDEX t_v ON t (v)").await?;
+
+        let progress: Vec<_> = db.insert_rows("t", text_rows(2000)).try_collect().await?;
+        assert!(progress.len() > 1, "expected several batches");
+        assert!(progress.len() < 2000 / BATCH_PAGES);
+        for (i, step) in progress.iter().enumerate() {
+            assert_eq!(step.batches, i as u64 + 1);
+        }
+        assert!(progress.windows(2).all(|w| w[0].inserted < w[1].inserted));
+        assert_eq!(progress.last().unwrap().inserted, 2000);
+
+        assert_eq!(count(&db, "SELECT id FROM t").await?, 2000);
+        assert_eq!(
+            count(&db, "SELECT id FROM t WHERE v = 'row 1234'").await?,
+            1
+        );
+    }
+
+    let db = open(temp_dir.path()).await?;
+    assert_eq!(count(&db, "SELECT id FROM t").await?, 2000);
+    Ok(())
+}
+
+#[tokio::test]
+async fn constraint_violation_stops_at_the_failing_batch() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path()).await?;
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY, v TEXT, pad TEXT)")
+        .await?;
+
+    // The duplicate key is far enough in that earlier batches succeed.
+    let rows = text_rows(1000).chain(text_rows(1));
+    let results: Vec<_> = db.insert_rows("t", rows).collect().await;
+    let (last, written) = results.split_last().unwrap();
+    assert!(last.is_err(), "expected the duplicate key to fail");
+    let inserted = written.last().unwrap().as_ref().unwrap().inserted;
+    assert!(inserted > 0 && inserted <= 1000);
+    assert_eq!(count(&db, "SELECT id FROM t").await?, inserted as usize);
+    Ok(())
+}
+
+#[tokio::test]
+async fn generated_ids_are_reported_per_batch() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path()).await?;
+    db.execute("CREATE TABLE t (id INT AUTO_INCREMENT PRIMARY KEY, v INT)")
+        .await?;
+
+    let rows = stream::iter((0..3).map(|i| Row::new(vec![Value::Null, Value::Int(i)])));
+    let progress: Vec<_> = db.insert_rows("t", rows).try_collect().await?;
+    assert_eq!(progress.len(), 1);
+    assert_eq!(progress[0].generated_ids, vec![1, 2, 3]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn nothing_is_inserted_until_polled() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path()).await?;
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY, v TEXT, pad TEXT)")
+        .await?;
+
+    let mut progress = Box::pin(db.insert_rows("t", text_rows(10)));
+    assert_eq!(count(&db, "SELECT id FROM t").await?, 0);
+    assert_eq!(progress.next().await.transpose()?.unwrap().inserted, 10);
+    assert!(progress.next().await.is_none());
+    assert_eq!(count(&db, "SELECT id FROM t").await?, 10);
+    Ok(())
+}
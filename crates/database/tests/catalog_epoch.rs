@@ -0,0 +1,52 @@
+//! Tests for catalog epochs and the schema change notification hook.
+
+use anyhow::Result;
+use database::Database;
+
+async fn open(dir: &std::path::Path) -> Result<Database> {
+    Database::new(dir, "catalog.json", "test.wal", 10).await
+}
+
+#[tokio::test]
+async fn ddl_publishes_a_new_epoch() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path()).await?;
+    let mut rx = db.watch_catalog();
+    let initial = db.catalog_epoch();
+
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY)").await?;
+    assert!(rx.has_changed()?);
+    let created = *rx.borrow_and_update();
+    assert!(created > initial);
+    assert_eq!(db.catalog_epoch(), created);
+
+    db.execute("INSERT INTO t VALUES (1)").await?;
+    db.execute("SELECT * FROM t").await?;
+    assert!(!rx.has_changed()?);
+
+    // One statement advances the epoch once, however many changes it makes
+    db.execute("ALTER TABLE t ADD COLUMN name TEXT").await?;
+    assert!(rx.has_changed()?);
+    assert_eq!(*rx.borrow_and_update(), created + 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn epoch_survives_restart_and_never_goes_backwards() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let before_restart = {
+        let db = open(temp_dir.path()).await?;
+        db.execute("CREATE TABLE t (id INT)").await?;
+        db.execute("CREATE INDEX idx_t ON t (id)").await?;
+        db.catalog_epoch()
+    };
+
+    let db = open(temp_dir.path()).await?;
+    assert_eq!(db.catalog_epoch(), before_restart);
+
+    let mut rx = db.watch_catalog();
+    db.reset().await?;
+    assert!(rx.has_changed()?);
+    assert!(*rx.borrow_and_update() > before_restart);
+    Ok(())
+}
@@ -0,0 +1,95 @@
+//! Integration tests for tables created `WITH (compression = ...)`.
+
+use anyhow::Result;
+use common::compression::{self, Compression};
+use std::path::Path;
+use storage::PAGE_SIZE;
+use types::Value;
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+/// How many of a file's page slots hold a compressed page.
+fn compressed_slots(path: &Path) -> Result<(usize, usize)> {
+    let bytes = std::fs::read(path)?;
+    let slots = bytes.chunks(PAGE_SIZE);
+    Ok((
+        slots
+            .clone()
+            .filter(|slot| compression::is_compressed(slot))
+            .count(),
+        slots.count(),
+    ))
+}
+
+#[tokio::test]
+async fn compressed_tables_survive_a_restart() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = create_db(temp_dir.path()).await?;
+        db.execute(
+            "CREATE TABLE notes (id INT PRIMARY KEY, tag TEXT, body TEXT) \
+             WITH (compression = 'zstd')",
+        )
+        .await?;
+        db.execute("CREATE INDEX idx_notes_tag ON notes (tag)")
+            .await?;
+        for id in 0..200 {
+            db.execute(&format!(
+                "INSERT INTO notes VALUES ({id}, 't{}', 'note {id}: the quick brown fox jumps over the lazy dog')",
+                id % 10
+            ))
+            .await?;
+        }
+        let catalog = db.catalog();
+        let catalog = catalog.read().await;
+        assert_eq!(catalog.table("notes")?.compression, Compression::Zstd);
+    }
+
+    let (compressed, slots) = compressed_slots(&temp_dir.path().join("notes.heap"))?;
+    assert!(slots > 1);
+    assert_eq!(compressed, slots);
+    let index = std::fs::read_dir(temp_dir.path())?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?
+        .into_iter()
+        .find(|path| path.extension().is_some_and(|ext| ext == "idx"))
+        .expect("index file");
+    for index in [index, temp_dir.path().join("notes.pk_idx")] {
+        let (compressed, _) = compressed_slots(&index)?;
+        assert!(compressed > 0, "{}", index.display());
+    }
+
+    let db = create_db(temp_dir.path()).await?;
+    assert!(
+        db.recovery_report().is_healthy(),
+        "{}",
+        db.recovery_report()
+    );
+    assert_eq!(select_rows(&db, "SELECT id FROM notes").await?.len(), 200);
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM notes WHERE tag = 't7'")
+            .await?
+            .len(),
+        20
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM notes WHERE id = 199").await?,
+        vec![vec![Value::Int(199)]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn compression_is_part_of_the_exported_schema() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE logs (id INT, line TEXT) WITH (compression = lz4)")
+        .await?;
+    db.execute("CREATE TABLE plain (id INT)").await?;
+
+    let sql = db.export_schema().await.to_sql()?;
+    assert!(sql.contains("WITH (compression = 'lz4')"), "{sql}");
+    assert_eq!(sql.matches("compression").count(), 1, "{sql}");
+    Ok(())
+}
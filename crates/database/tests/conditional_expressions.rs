@@ -0,0 +1,170 @@
+//! Integration tests for COALESCE, NULLIF, CASE and IS [NOT] NULL expressions.
+
+use anyhow::Result;
+use database::{Database, QueryResult};
+use types::Value;
+
+mod helpers;
+use helpers::select_rows;
+
+fn text(s: &str) -> Value {
+    Value::Text(s.into())
+}
+
+async fn users(dir: &std::path::Path) -> Result<Database> {
+    let db = Database::new(dir, "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT, nick TEXT, age INT)")
+        .await?;
+    db.execute(
+        "INSERT INTO users VALUES (1, 'alice', NULL, 34), (2, 'bob', 'bobby', 12), \
+         (3, 'carol', '', NULL)",
+    )
+    .await?;
+    Ok(db)
+}
+
+#[tokio::test]
+async fn coalesce_and_nullif_replace_nulls() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = users(temp_dir.path()).await?;
+
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT COALESCE(NULLIF(nick, ''), name), COALESCE(age, 0) FROM users ORDER BY id"
+        )
+        .await?,
+        vec![
+            vec![text("alice"), Value::Int(34)],
+            vec![text("bobby"), Value::Int(12)],
+            vec![text("carol"), Value::Int(0)],
+        ]
+    );
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT id FROM users WHERE COALESCE(age, 100) > 30 ORDER BY id"
+        )
+        .await?,
+        vec![vec![Value::Int(1)], vec![Value::Int(3)]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn case_picks_the_first_matching_branch() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = users(temp_dir.path()).await?;
+
+    let result = db
+        .execute(
+            "SELECT name, CASE WHEN age < 18 THEN 'minor' WHEN age >= 18 THEN 'adult' \
+             ELSE 'unknown' END AS bracket FROM users ORDER BY id",
+        )
+        .await?;
+    match result {
+        QueryResult::Rows { schema, rows, .. } => {
+            assert_eq!(schema, vec!["name", "bracket"]);
+            let rows: Vec<_> = rows.into_iter().map(|row| row.values).collect();
+            assert_eq!(
+                rows,
+                vec![
+                    vec![text("alice"), text("adult")],
+                    vec![text("bob"), text("minor")],
+                    vec![text("carol"), text("unknown")],
+                ]
+            );
+        }
+        other => panic!("expected rows, got {other:?}"),
+    }
+
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT CASE id WHEN 1 THEN 'one' WHEN 2 THEN 'two' END FROM users ORDER BY id"
+        )
+        .await?,
+        vec![vec![text("one")], vec![text("two")], vec![Value::Null]]
+    );
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT id FROM users WHERE CASE WHEN nick = '' THEN true ELSE age > 30 END \
+             ORDER BY id"
+        )
+        .await?,
+        vec![vec![Value::Int(1)], vec![Value::Int(3)]]
+    );
+
+    // CASE computes new values in UPDATE
+    db.execute("UPDATE users SET age = CASE WHEN age < 18 THEN 18 ELSE age END")
+        .await?;
+    assert_eq!(
+        select_rows(&db, "SELECT age FROM users ORDER BY id").await?,
+        vec![
+            vec![Value::Int(34)],
+            vec![Value::Int(18)],
+            vec![Value::Null]
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn is_null_tests_for_missing_values() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = users(temp_dir.path()).await?;
+
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM users WHERE nick IS NULL").await?,
+        vec![vec![Value::Int(1)]]
+    );
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT id FROM users WHERE age IS NOT NULL ORDER BY id"
+        )
+        .await?,
+        vec![vec![Value::Int(1)], vec![Value::Int(2)]]
+    );
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT id FROM users WHERE NOT nick IS NULL ORDER BY id"
+        )
+        .await?,
+        vec![vec![Value::Int(2)], vec![Value::Int(3)]]
+    );
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT CASE WHEN age IS NULL THEN 'unknown' ELSE 'known' END, \
+             NULL IS NULL FROM users ORDER BY id"
+        )
+        .await?,
+        vec![
+            vec![text("known"), Value::Bool(true)],
+            vec![text("known"), Value::Bool(true)],
+            vec![text("unknown"), Value::Bool(true)],
+        ]
+    );
+
+    let plan = select_rows(&db, "EXPLAIN SELECT id FROM users WHERE nick IS NOT NULL").await?;
+    assert!(format!("{plan:?}").contains("nick IS NOT NULL"), "{plan:?}");
+
+    // IS NULL selects the rows UPDATE and DELETE change too
+    db.execute("UPDATE users SET nick = name WHERE nick IS NULL")
+        .await?;
+    match db.execute("DELETE FROM users WHERE age IS NULL").await? {
+        QueryResult::Count { affected, .. } => assert_eq!(affected, 1),
+        other => panic!("expected a count, got {other:?}"),
+    }
+    assert_eq!(
+        select_rows(&db, "SELECT id, nick FROM users ORDER BY id").await?,
+        vec![
+            vec![Value::Int(1), text("alice")],
+            vec![Value::Int(2), text("bobby")],
+        ]
+    );
+    Ok(())
+}
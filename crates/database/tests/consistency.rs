@@ -0,0 +1,83 @@
+//! Integration tests for per-table content checksums.
+
+use anyhow::Result;
+use database::{Database, RaftConfig, TableChecksum};
+use raft::compare_checksums;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+async fn create_db(dir: &Path, raft: Option<RaftConfig>) -> Result<Database> {
+    Database::with_raft_config(dir, "catalog.json", "test.wal", 10, raft).await
+}
+
+async fn load(db: &Database, inserts: &[&str]) -> Result<Vec<TableChecksum>> {
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT)")
+        .await?;
+    db.execute("CREATE TABLE tags (tag TEXT)").await?;
+    for sql in inserts {
+        db.execute(sql).await?;
+    }
+    db.table_checksums().await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn checksums_ignore_row_placement() -> Result<()> {
+    let local_dir = tempfile::tempdir()?;
+    let raft_dir = tempfile::tempdir()?;
+    let local = create_db(local_dir.path(), None).await?;
+    let replicated = create_db(raft_dir.path(), Some(RaftConfig::single_node(1))).await?;
+
+    // Same rows, written in a different order and through different paths
+    let local_sums = load(
+        &local,
+        &[
+            "INSERT INTO users VALUES (1, 'alice'), (2, 'bob'), (3, 'carol')",
+            "INSERT INTO tags VALUES ('x'), ('y')",
+            "DELETE FROM users WHERE id = 2",
+        ],
+    )
+    .await?;
+    let replicated_sums = load(
+        &replicated,
+        &[
+            "INSERT INTO tags VALUES ('y'), ('x')",
+            "INSERT INTO users VALUES (3, 'carol'), (1, 'alice')",
+        ],
+    )
+    .await?;
+
+    assert_eq!(
+        local_sums
+            .iter()
+            .map(|t| t.table.as_str())
+            .collect::<Vec<_>>(),
+        vec!["tags", "users"]
+    );
+    assert_eq!(local_sums[1].rows, 2);
+    assert_eq!(local_sums, replicated_sums);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn diverging_rows_are_reported() -> Result<()> {
+    let dir_a = tempfile::tempdir()?;
+    let dir_b = tempfile::tempdir()?;
+    let a = create_db(dir_a.path(), None).await?;
+    let b = create_db(dir_b.path(), None).await?;
+
+    let sums_a = load(&a, &["INSERT INTO users VALUES (1, 'alice')"]).await?;
+    let sums_b = load(&b, &["INSERT INTO users VALUES (1, 'alicia')"]).await?;
+
+    let divergent = compare_checksums(&BTreeMap::from([(1, sums_a), (2, sums_b)]));
+    assert_eq!(divergent.len(), 1);
+    assert_eq!(divergent[0].table, "users");
+    assert_eq!(divergent[0].nodes[&1].as_ref().unwrap().rows, 1);
+    assert_ne!(divergent[0].nodes[&1], divergent[0].nodes[&2]);
+
+    a.execute("UPDATE users SET name = 'alicia' WHERE id = 1")
+        .await?;
+    let sums_a = a.table_checksums().await?;
+    let sums_b = b.table_checksums().await?;
+    assert!(compare_checksums(&BTreeMap::from([(1, sums_a), (2, sums_b)])).is_empty());
+    Ok(())
+}
@@ -0,0 +1,165 @@
+//! Integration tests for COPY ... TO exports.
+
+use anyhow::Result;
+use database::{export::EXPORT_DIR, Database, QueryResult, ResourceLimits};
+use std::fs;
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+async fn create_users(db: &Database) -> Result<()> {
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT, active BOOL)")
+        .await?;
+    db.execute(
+        "INSERT INTO users VALUES (1, 'Alice', true), (2, 'Smith, \"Bob\"', false), \
+         (3, NULL, true), (4, '', NULL)",
+    )
+    .await?;
+    Ok(())
+}
+
+async fn copy(db: &Database, sql: &str) -> Result<u64> {
+    match db.execute(sql).await? {
+        QueryResult::Count { affected, .. } => Ok(affected),
+        other => panic!("expected count, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn copy_query_to_csv() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    create_users(&db).await?;
+
+    let sql =
+        "COPY (SELECT id, name AS who, active FROM users ORDER BY id) TO 'out.csv' FORMAT CSV";
+    assert_eq!(copy(&db, sql).await?, 4);
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join(EXPORT_DIR).join("out.csv"))?,
+        "id,who,active\n\
+         1,Alice,true\n\
+         2,\"Smith, \"\"Bob\"\"\",false\n\
+         3,,true\n\
+         4,\"\",\n"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn copy_table_to_json() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    create_users(&db).await?;
+
+    // Paths are resolved against the export directory
+    assert_eq!(
+        copy(&db, "COPY users (name, id) TO 'users.json' FORMAT JSON").await?,
+        4
+    );
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(
+        temp_dir.path().join(EXPORT_DIR).join("users.json"),
+    )?)?;
+    let mut rows = json.as_array().expect("array of rows").clone();
+    rows.sort_by_key(|row| row["id"].as_i64());
+    assert_eq!(
+        rows,
+        vec![
+            serde_json::json!({"name": "Alice", "id": 1}),
+            serde_json::json!({"name": "Smith, \"Bob\"", "id": 2}),
+            serde_json::json!({"name": null, "id": 3}),
+            serde_json::json!({"name": "", "id": 4}),
+        ]
+    );
+
+    db.execute("CREATE TABLE empty (id INT)").await?;
+    assert_eq!(
+        copy(&db, "COPY empty TO 'empty.json' FORMAT JSON").await?,
+        0
+    );
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(
+        temp_dir.path().join(EXPORT_DIR).join("empty.json"),
+    )?)?;
+    assert_eq!(json, serde_json::json!([]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn failed_copy_leaves_no_file() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path())
+        .await?
+        .with_resource_limits(ResourceLimits {
+            max_rows_scanned: Some(2),
+            ..Default::default()
+        });
+    create_users(&db).await?;
+
+    let err = db
+        .execute("COPY users TO 'users.csv'")
+        .await
+        .expect_err("export should exceed the scan limit");
+    assert!(format!("{err:#}").contains("users.csv"), "{err:#}");
+    assert!(!temp_dir.path().join(EXPORT_DIR).join("users.csv").exists());
+
+    assert!(db.execute("COPY missing TO 'missing.csv'").await.is_err());
+    assert!(!temp_dir
+        .path()
+        .join(EXPORT_DIR)
+        .join("missing.csv")
+        .exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn copy_cannot_write_outside_the_export_directory() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let outside = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    create_users(&db).await?;
+
+    // A database file's name only names a file in the export directory
+    assert_eq!(copy(&db, "COPY users TO 'catalog.json'").await?, 4);
+    assert!(temp_dir
+        .path()
+        .join(EXPORT_DIR)
+        .join("catalog.json")
+        .exists());
+
+    let target = outside.path().join("x");
+    for path in [
+        "../x".to_string(),
+        "../catalog.json".to_string(),
+        "a/../../x".to_string(),
+        "./x".to_string(),
+        target.display().to_string(),
+    ] {
+        let err = db
+            .execute(&format!("COPY users TO '{path}'"))
+            .await
+            .expect_err("path outside the export directory");
+        assert!(format!("{err:#}").contains("relative path"), "{err:#}");
+    }
+    assert!(!target.exists());
+    assert!(!temp_dir.path().join("x").exists());
+
+    // Existing exports are not overwritten
+    fs::write(temp_dir.path().join(EXPORT_DIR).join("kept.csv"), "kept")?;
+    let err = db
+        .execute("COPY users TO 'kept.csv'")
+        .await
+        .expect_err("the file exists");
+    assert!(format!("{err:#}").contains("already exists"), "{err:#}");
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join(EXPORT_DIR).join("kept.csv"))?,
+        "kept"
+    );
+
+    // The catalog is intact
+    drop(db);
+    let db = create_db(temp_dir.path()).await?;
+    assert_eq!(
+        select_rows(&db, "SELECT COUNT(*) FROM users").await?.len(),
+        1
+    );
+    Ok(())
+}
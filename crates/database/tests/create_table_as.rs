@@ -0,0 +1,124 @@
+//! Integration tests for CREATE TABLE ... AS and temporary tables.
+
+use anyhow::Result;
+use database::{Database, QueryResult};
+use types::{SqlType, Value};
+
+mod helpers;
+use helpers::select_rows;
+
+async fn open(dir: &std::path::Path) -> Result<Database> {
+    Database::new(dir, "catalog.json", "test.wal", 10).await
+}
+
+async fn create_users(db: &Database) -> Result<()> {
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT, age INT)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (1, 'ada', 36), (2, 'bob', 12), (3, 'cy', 52)")
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn create_table_as_copies_the_query_rows() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path()).await?;
+    create_users(&db).await?;
+
+    let result = db
+        .execute("CREATE TABLE adults AS SELECT u.id, u.name, u.age * 1.5 AS score FROM users u WHERE u.age >= 18")
+        .await?;
+    assert!(matches!(result, QueryResult::Count { affected: 2, .. }));
+    {
+        let catalog = db.catalog();
+        let catalog = catalog.read().await;
+        let table = catalog.table("adults")?;
+        let types: Vec<(&str, &SqlType)> = table
+            .columns()
+            .iter()
+            .map(|c| (c.name.as_str(), &c.ty))
+            .collect();
+        assert_eq!(
+            types[..2],
+            [("id", &SqlType::Int), ("name", &SqlType::Text)]
+        );
+        assert_eq!(types[2].0, "score");
+        assert!(!table.temporary);
+    }
+
+    // The copy is a table of its own, and survives a restart
+    db.execute("DELETE FROM users").await?;
+    drop(db);
+    let db = open(temp_dir.path()).await?;
+    assert_eq!(
+        select_rows(&db, "SELECT id, name FROM adults ORDER BY id").await?,
+        vec![
+            vec![Value::Int(1), Value::Text("ada".into())],
+            vec![Value::Int(3), Value::Text("cy".into())],
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn temporary_tables_last_until_the_database_closes() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path()).await?;
+    create_users(&db).await?;
+
+    db.execute("CREATE TEMP TABLE minors AS SELECT id, name FROM users WHERE age < 18")
+        .await?;
+    db.execute("CREATE TEMPORARY TABLE notes (user_id INT, note TEXT)")
+        .await?;
+    db.execute("INSERT INTO notes VALUES (2, 'needs consent')")
+        .await?;
+
+    // Staged results join like any other table
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT m.name, n.note FROM minors m JOIN notes n ON m.id = n.user_id"
+        )
+        .await?,
+        vec![vec![
+            Value::Text("bob".into()),
+            Value::Text("needs consent".into())
+        ]]
+    );
+
+    // They never reach the catalog file
+    let saved = std::fs::read_to_string(temp_dir.path().join("catalog.json"))?;
+    assert!(saved.contains("users"));
+    assert!(!saved.contains("minors"));
+    assert!(!saved.contains("notes"));
+
+    drop(db);
+    let db = open(temp_dir.path()).await?;
+    assert!(db.execute("SELECT * FROM minors").await.is_err());
+    assert!(db.execute("SELECT * FROM notes").await.is_err());
+    assert_eq!(select_rows(&db, "SELECT id FROM users").await?.len(), 3);
+
+    // The names are free again
+    db.execute("CREATE TEMP TABLE minors (id INT)").await?;
+    db.execute("DROP TABLE minors").await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn temporary_tables_reject_durable_storage() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path()).await?;
+
+    let err = db
+        .execute("CREATE TEMP TABLE t (id INT) ENGINE = heap")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("memory engine"), "{err}");
+
+    let err = db
+        .execute("CREATE TABLE t (id INT) AS SELECT 1 AS id FROM users")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("column lists"), "{err}");
+    Ok(())
+}
@@ -0,0 +1,192 @@
+//! Integration tests for DECIMAL / NUMERIC columns and arithmetic.
+
+use anyhow::Result;
+use types::{Decimal, Value};
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+fn dec(s: &str) -> Value {
+    Value::Decimal(Decimal::parse(s).unwrap())
+}
+
+#[tokio::test]
+async fn decimal_columns_round_to_their_scale() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, price DECIMAL(10, 2), qty INT)")
+        .await?;
+    db.execute("INSERT INTO items VALUES (1, 19.99, 3), (2, 5, 10), (3, '0.125', 8)")
+        .await?;
+
+    assert_eq!(
+        select_rows(&db, "SELECT price FROM items ORDER BY id").await?,
+        vec![vec![dec("19.99")], vec![dec("5.00")], vec![dec("0.13")]]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM items WHERE price > 5 ORDER BY id").await?,
+        vec![vec![Value::Int(1)]]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM items WHERE price = 0.13").await?,
+        vec![vec![Value::Int(3)]]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM items ORDER BY price DESC").await?,
+        vec![
+            vec![Value::Int(1)],
+            vec![Value::Int(2)],
+            vec![Value::Int(3)]
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn arithmetic_on_decimals_is_exact() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, price NUMERIC(10, 2), qty INT)")
+        .await?;
+    db.execute("INSERT INTO items VALUES (1, 0.10, 3), (2, 0.20, 1)")
+        .await?;
+
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT price * qty, price + 0.05, qty / 2 FROM items ORDER BY id"
+        )
+        .await?,
+        vec![
+            vec![
+                dec("0.30"),
+                Value::Float(0.15000000000000002),
+                Value::Int(1)
+            ],
+            vec![dec("0.20"), Value::Float(0.25), Value::Int(0)],
+        ]
+    );
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT id FROM items WHERE price * qty = CAST('0.3' AS DECIMAL(4, 1))"
+        )
+        .await?,
+        vec![vec![Value::Int(1)]]
+    );
+
+    db.execute("UPDATE items SET price = price * 3 WHERE id = 2")
+        .await?;
+    assert_eq!(
+        select_rows(&db, "SELECT price FROM items WHERE id = 2").await?,
+        vec![vec![dec("0.60")]]
+    );
+
+    let err = db
+        .execute("SELECT price / (qty - 3) FROM items WHERE id = 1")
+        .await
+        .expect_err("division by zero");
+    assert!(format!("{err:#}").contains("division by zero"), "{err:#}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn precision_is_checked_on_insert_and_update() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY, amount DECIMAL(5, 2))")
+        .await?;
+    db.execute("INSERT INTO t VALUES (1, 999.99)").await?;
+
+    let err = db
+        .execute("INSERT INTO t VALUES (2, 1000)")
+        .await
+        .expect_err("1000.00 has 6 digits");
+    assert!(
+        format!("{err:#}").contains("has type DECIMAL(5,2) and cannot hold"),
+        "{err:#}"
+    );
+    assert!(db
+        .execute("INSERT INTO t VALUES (2, 'lots')")
+        .await
+        .is_err());
+    assert!(db
+        .execute("UPDATE t SET amount = amount + 1 WHERE id = 1")
+        .await
+        .is_err());
+    // An INT column does not take a decimal
+    db.execute("CREATE TABLE n (id INT PRIMARY KEY)").await?;
+    assert!(db
+        .execute("INSERT INTO n VALUES (CAST(2 AS DECIMAL(3, 1)))")
+        .await
+        .is_err());
+
+    assert_eq!(
+        select_rows(&db, "SELECT amount FROM t").await?,
+        vec![vec![dec("999.99")]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn indexes_find_decimal_keys() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, price DECIMAL(8, 2), cost DECIMAL(8, 3))")
+        .await?;
+    db.execute("CREATE INDEX idx_price ON items (price)")
+        .await?;
+    db.execute("CREATE INDEX idx_cost ON items USING HASH (cost)")
+        .await?;
+    db.execute("INSERT INTO items VALUES (1, 3, 1.5), (2, 3.5, 2), (3, 123456.78, 2)")
+        .await?;
+
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM items WHERE price = 3").await?,
+        vec![vec![Value::Int(1)]]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM items WHERE price >= 3.5 ORDER BY id").await?,
+        vec![vec![Value::Int(2)], vec![Value::Int(3)]]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM items WHERE price < 1000000").await?,
+        vec![
+            vec![Value::Int(1)],
+            vec![Value::Int(2)],
+            vec![Value::Int(3)]
+        ]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM items WHERE cost = 2 ORDER BY id").await?,
+        vec![vec![Value::Int(2)], vec![Value::Int(3)]]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM items WHERE cost = 1.50").await?,
+        vec![vec![Value::Int(1)]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn decimals_survive_reopen() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = create_db(temp_dir.path()).await?;
+        db.execute("CREATE TABLE t (id INT PRIMARY KEY, x DECIMAL(38, 10))")
+            .await?;
+        db.execute("INSERT INTO t VALUES (1, '1234567890123456789012345678.0123456789')")
+            .await?;
+    }
+
+    let db = create_db(temp_dir.path()).await?;
+    assert_eq!(
+        select_rows(&db, "SELECT x FROM t").await?,
+        vec![vec![dec("1234567890123456789012345678.0123456789")]]
+    );
+    Ok(())
+}
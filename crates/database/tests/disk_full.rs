@@ -0,0 +1,135 @@
+//! Integration tests for read-only mode while the disk is full.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use common::hooks::FaultPlan;
+use common::DbError;
+use database::{is_disk_full, Database, QueryResult};
+use types::Value;
+
+async fn create_db(dir: &std::path::Path, faults: Arc<FaultPlan>) -> Result<Database> {
+    let db = Database::with_faults(dir, "catalog.json", "test.wal", 10, faults).await?;
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, label TEXT)")
+        .await?;
+    db.execute("INSERT INTO items VALUES (1, 'a'), (2, 'b')")
+        .await?;
+    Ok(db)
+}
+
+async fn ids(db: &Database) -> Result<Vec<Value>> {
+    match db.execute("SELECT id FROM items ORDER BY id").await? {
+        QueryResult::Rows { rows, .. } => {
+            Ok(rows.into_iter().map(|r| r.values[0].clone()).collect())
+        }
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn full_disk_makes_the_database_read_only() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let faults = Arc::new(FaultPlan::new());
+    let db = create_db(temp_dir.path(), faults.clone()).await?;
+    assert!(!db.is_read_only());
+
+    faults.fill_disk();
+    let err = db
+        .execute("INSERT INTO items VALUES (3, 'c')")
+        .await
+        .unwrap_err();
+    assert!(is_disk_full(&err), "{err:#}");
+    assert!(db.is_read_only());
+
+    // Later writes and DDL are rejected up front with a specific error
+    for sql in [
+        "INSERT INTO items VALUES (4, 'd')",
+        "DELETE FROM items",
+        "CREATE TABLE more (id INT)",
+    ] {
+        let err = db.execute(sql).await.unwrap_err();
+        assert!(
+            matches!(err.downcast_ref::<DbError>(), Some(DbError::DiskFull(msg)) if msg.contains("read-only")),
+            "{sql}: {err:#}"
+        );
+    }
+
+    // Reads keep working
+    assert_eq!(ids(&db).await?, vec![Value::Int(1), Value::Int(2)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn full_disk_rejects_writes_run_by_explain_analyze_and_profile() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let faults = Arc::new(FaultPlan::new());
+    let db = create_db(temp_dir.path(), faults.clone()).await?;
+
+    faults.fill_disk();
+    assert!(db
+        .execute("INSERT INTO items VALUES (3, 'c')")
+        .await
+        .is_err());
+    assert!(db.is_read_only());
+
+    for sql in [
+        "EXPLAIN ANALYZE DELETE FROM items WHERE id = 2",
+        "EXPLAIN ANALYZE INSERT INTO items VALUES (4, 'd')",
+        "PROFILE INSERT INTO items VALUES (5, 'e')",
+        "PROFILE UPDATE items SET label = 'z'",
+    ] {
+        let err = db.execute(sql).await.unwrap_err();
+        assert!(
+            matches!(err.downcast_ref::<DbError>(), Some(DbError::DiskFull(msg)) if msg.contains("read-only")),
+            "{sql}: {err:#}"
+        );
+    }
+
+    // Those that only read keep working
+    for sql in [
+        "EXPLAIN DELETE FROM items",
+        "EXPLAIN ANALYZE SELECT * FROM items",
+        "PROFILE SELECT * FROM items",
+    ] {
+        db.execute(sql).await?;
+    }
+    assert_eq!(ids(&db).await?, vec![Value::Int(1), Value::Int(2)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn freeing_space_makes_the_database_writable_again() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let faults = Arc::new(FaultPlan::new());
+    let db = create_db(temp_dir.path(), faults.clone()).await?;
+
+    faults.fill_disk();
+    assert!(db
+        .execute("INSERT INTO items VALUES (3, 'c')")
+        .await
+        .is_err());
+    assert!(db.is_read_only());
+
+    faults.free_disk();
+    db.execute("INSERT INTO items VALUES (3, 'c')").await?;
+    assert!(!db.is_read_only());
+    assert_eq!(
+        ids(&db).await?,
+        vec![Value::Int(1), Value::Int(2), Value::Int(3)]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn other_write_failures_leave_the_database_writable() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path(), Arc::new(FaultPlan::new())).await?;
+
+    let err = db
+        .execute("INSERT INTO items VALUES (1, 'dup')")
+        .await
+        .unwrap_err();
+    assert!(!is_disk_full(&err));
+    assert!(!db.is_read_only());
+    Ok(())
+}
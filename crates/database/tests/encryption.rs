@@ -0,0 +1,210 @@
+//! Integration tests for encryption at rest.
+
+use std::path::Path;
+
+use anyhow::Result;
+use database::{Database, EncryptionKey};
+use types::Value;
+
+mod helpers;
+use helpers::select_rows;
+
+const SECRET: &str = "hunter2-credit-card";
+
+async fn open(dir: &Path, key: Option<EncryptionKey>) -> Result<Database> {
+    Database::with_encryption(dir, "catalog.json", "test.wal", 10, None, key).await
+}
+
+fn key() -> EncryptionKey {
+    EncryptionKey::from_hex(&"0f".repeat(32)).unwrap()
+}
+
+fn assert_no_plaintext(dir: &Path, needle: &str) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(
+            !bytes.windows(needle.len()).any(|w| w == needle.as_bytes()),
+            "{} contains plaintext {needle:?}",
+            path.display()
+        );
+    }
+}
+
+#[tokio::test]
+async fn encrypted_database_round_trips_without_leaking_plaintext() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = open(temp_dir.path(), Some(key())).await?;
+        db.execute("CREATE TABLE vault (id INT PRIMARY KEY, secret TEXT)")
+            .await?;
+        db.execute(&format!("INSERT INTO vault VALUES (1, '{SECRET}')"))
+            .await?;
+        db.execute("UPDATE vault SET secret = 'rotated' WHERE id = 1")
+            .await?;
+        db.execute(&format!("INSERT INTO vault VALUES (2, '{SECRET}')"))
+            .await?;
+    }
+
+    assert!(temp_dir.path().join("vault.heap").exists());
+    assert!(temp_dir.path().join("vault.pk_idx").exists());
+    assert_no_plaintext(temp_dir.path(), SECRET);
+    assert_no_plaintext(temp_dir.path(), "vault");
+
+    let db = open(temp_dir.path(), Some(key())).await?;
+    let rows = select_rows(&db, "SELECT id, secret FROM vault ORDER BY id").await?;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(1), Value::Text("rotated".into())],
+            vec![Value::Int(2), Value::Text(SECRET.into())],
+        ]
+    );
+
+    // The persisted primary key index is readable under the key
+    assert!(db
+        .execute("INSERT INTO vault VALUES (2, 'dup')")
+        .await
+        .is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn encrypted_database_requires_the_right_key() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = open(temp_dir.path(), Some(key())).await?;
+        db.execute("CREATE TABLE t (id INT)").await?;
+    }
+
+    assert!(open(temp_dir.path(), None).await.is_err());
+    let wrong = EncryptionKey::from_hex(&"aa".repeat(32))?;
+    assert!(open(temp_dir.path(), Some(wrong)).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn plaintext_database_rejects_a_key() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = open(temp_dir.path(), None).await?;
+        db.execute("CREATE TABLE t (id INT)").await?;
+    }
+
+    let err = open(temp_dir.path(), Some(key())).await.err().unwrap();
+    assert!(err.to_string().contains("not encrypted"), "{err}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn btree_indexes_are_encrypted() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = open(temp_dir.path(), Some(key())).await?;
+        db.execute("CREATE TABLE vault (id INT PRIMARY KEY, secret TEXT)")
+            .await?;
+        db.execute(&format!("INSERT INTO vault VALUES (1, '{SECRET}')"))
+            .await?;
+        db.execute("CREATE INDEX idx_secret ON vault (secret)")
+            .await?;
+        db.execute(&format!("INSERT INTO vault VALUES (2, '{SECRET}')"))
+            .await?;
+    }
+
+    assert!(std::fs::read_dir(temp_dir.path())?
+        .any(|entry| entry.unwrap().path().extension() == Some("idx".as_ref())));
+    assert_no_plaintext(temp_dir.path(), SECRET);
+
+    let db = open(temp_dir.path(), Some(key())).await?;
+    let rows = select_rows(
+        &db,
+        &format!("SELECT id FROM vault WHERE secret = '{SECRET}' ORDER BY id"),
+    )
+    .await?;
+    assert_eq!(rows, vec![vec![Value::Int(1)], vec![Value::Int(2)]]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn hash_indexes_are_encrypted() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = open(temp_dir.path(), Some(key())).await?;
+        db.execute("CREATE TABLE vault (id INT PRIMARY KEY, secret TEXT)")
+            .await?;
+        db.execute(&format!("INSERT INTO vault VALUES (1, '{SECRET}')"))
+            .await?;
+        db.execute("CREATE INDEX idx_secret ON vault USING HASH (secret)")
+            .await?;
+        db.execute(&format!("INSERT INTO vault VALUES (2, '{SECRET}')"))
+            .await?;
+    }
+
+    let index_id = {
+        let db = open(temp_dir.path(), Some(key())).await?;
+        let catalog = db.catalog();
+        let catalog = catalog.read().await;
+        catalog.table("vault")?.indexes[0].id
+    };
+    let index_file = temp_dir.path().join(format!("index_{}.idx", index_id.0));
+    let bytes = std::fs::read(&index_file)?;
+    assert!(
+        !bytes.windows(SECRET.len()).any(|w| w == SECRET.as_bytes()),
+        "{} contains plaintext",
+        index_file.display()
+    );
+
+    let db = open(temp_dir.path(), Some(key())).await?;
+    let rows = select_rows(
+        &db,
+        &format!("SELECT id FROM vault WHERE secret = '{SECRET}' ORDER BY id"),
+    )
+    .await?;
+    assert_eq!(rows, vec![vec![Value::Int(1)], vec![Value::Int(2)]]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn rotated_key_reads_data_sealed_under_the_previous_key() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let new = EncryptionKey::from_hex(&"5a".repeat(32))?;
+    {
+        let db = open(temp_dir.path(), Some(key())).await?;
+        db.execute("CREATE TABLE vault (id INT PRIMARY KEY, secret TEXT)")
+            .await?;
+        db.execute("CREATE INDEX idx_secret ON vault (secret)")
+            .await?;
+        db.execute("INSERT INTO vault VALUES (1, 'old')").await?;
+    }
+
+    // Only the new key: everything on disk is still sealed under the old one
+    let err = open(temp_dir.path(), Some(new.clone()))
+        .await
+        .err()
+        .unwrap();
+    assert!(
+        err.to_string().contains(&format!("{:08x}", key().id())),
+        "{err}"
+    );
+
+    let rotated = new.with_previous(&key());
+    {
+        let db = open(temp_dir.path(), Some(rotated.clone())).await?;
+        db.execute("INSERT INTO vault VALUES (2, 'new')").await?;
+        let rows = select_rows(&db, "SELECT id FROM vault WHERE secret = 'old'").await?;
+        assert_eq!(rows, vec![vec![Value::Int(1)]]);
+    }
+
+    let db = open(temp_dir.path(), Some(rotated)).await?;
+    let rows = select_rows(&db, "SELECT id, secret FROM vault ORDER BY id").await?;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(1), Value::Text("old".into())],
+            vec![Value::Int(2), Value::Text("new".into())],
+        ]
+    );
+    let rows = select_rows(&db, "SELECT id FROM vault WHERE secret = 'new'").await?;
+    assert_eq!(rows, vec![vec![Value::Int(2)]]);
+    Ok(())
+}
@@ -0,0 +1,96 @@
+//! Integration tests for the execution metadata attached to query results.
+
+use anyhow::Result;
+use database::{Database, ExecutionInfo, QueryResult};
+
+async fn create_db(dir: &std::path::Path) -> Result<Database> {
+    let db = Database::new(dir, "catalog.json", "test.wal", 10).await?;
+    let db = db.with_auto_analyze(None);
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, color TEXT)")
+        .await?;
+    let values: Vec<String> = (1..=20)
+        .map(|id| format!("({id}, '{}')", if id % 4 == 0 { "red" } else { "blue" }))
+        .collect();
+    db.execute(&format!("INSERT INTO items VALUES {}", values.join(", ")))
+        .await?;
+    Ok(db)
+}
+
+fn info(result: &QueryResult) -> ExecutionInfo {
+    result
+        .info()
+        .cloned()
+        .unwrap_or_else(|| panic!("expected execution info, got {result:?}"))
+}
+
+#[tokio::test]
+async fn queries_report_rows_scanned_and_returned() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = create_db(dir.path()).await?;
+
+    let result = db
+        .execute("SELECT id FROM items WHERE color = 'red'")
+        .await?;
+    let info = info(&result);
+    assert_eq!(info.rows_scanned, 20);
+    assert_eq!(info.actual_rows, 5);
+    assert_eq!(info.estimated_rows, None, "table was never analyzed");
+    assert!(info.elapsed > std::time::Duration::ZERO);
+    Ok(())
+}
+
+#[tokio::test]
+async fn analyzed_tables_give_an_estimate() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = create_db(dir.path()).await?;
+    db.execute("ANALYZE TABLE items").await?;
+
+    let info = info(&db.execute("SELECT * FROM items").await?);
+    assert_eq!(info.estimated_rows, Some(20));
+    assert_eq!(info.actual_rows, 20);
+    Ok(())
+}
+
+#[tokio::test]
+async fn dml_reports_rows_affected() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = create_db(dir.path()).await?;
+
+    let result = db
+        .execute("UPDATE items SET color = 'green' WHERE color = 'blue'")
+        .await?;
+    assert!(matches!(result, QueryResult::Count { affected: 15, .. }));
+    let info = info(&result);
+    assert_eq!(info.actual_rows, 15);
+    assert_eq!(info.rows_scanned, 20);
+    assert_eq!(info.estimated_rows, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn other_statements_carry_no_info() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = create_db(dir.path()).await?;
+
+    let explain = db.execute("EXPLAIN SELECT * FROM items").await?;
+    assert!(explain.info().is_none());
+    let ddl = db
+        .execute("CREATE INDEX idx_color ON items (color)")
+        .await?;
+    assert!(ddl.info().is_none());
+    Ok(())
+}
+
+#[test]
+fn display_lists_each_measure() {
+    let info = ExecutionInfo {
+        rows_scanned: 20,
+        estimated_rows: Some(7),
+        actual_rows: 5,
+        elapsed: std::time::Duration::from_micros(1500),
+    };
+    assert_eq!(
+        info.to_string(),
+        "time=1.50ms scanned=20 rows=5 estimated=7"
+    );
+}
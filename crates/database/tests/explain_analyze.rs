@@ -1,7 +1,7 @@
 //! Integration tests for EXPLAIN and EXPLAIN ANALYZE functionality.
 
 use anyhow::Result;
-use database::{Database, QueryResult};
+use database::{Database, QueryResult, RaftConfig};
 
 #[tokio::test]
 async fn explain_analyze_select_query() -> Result<()> {
@@ -23,7 +23,7 @@ async fn explain_analyze_select_query() -> Result<()> {
 
     // Verify we get rows back (the explain output)
     match result {
-        QueryResult::Rows { schema, rows } => {
+        QueryResult::Rows { schema, rows, .. } => {
             assert_eq!(schema, vec!["Explain"]);
             assert!(!rows.is_empty());
 
@@ -55,7 +55,7 @@ async fn explain_select_query_without_execution() -> Result<()> {
 
     // Verify we get rows back
     match result {
-        QueryResult::Rows { schema, rows } => {
+        QueryResult::Rows { schema, rows, .. } => {
             assert_eq!(schema, vec!["Explain"]);
             assert!(!rows.is_empty());
 
@@ -108,6 +108,40 @@ async fn explain_analyze_insert_query() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn explain_analyze_dml_counts_towards_table_activity() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE products (id INT PRIMARY KEY, name TEXT)")
+        .await?;
+
+    db.execute("EXPLAIN ANALYZE INSERT INTO products VALUES (1, 'a'), (2, 'b'), (3, 'c')")
+        .await?;
+    db.execute("EXPLAIN ANALYZE DELETE FROM products WHERE id = 2")
+        .await?;
+    // A plain EXPLAIN writes nothing
+    db.execute("EXPLAIN DELETE FROM products").await?;
+
+    let QueryResult::Rows { rows, .. } = db
+        .execute(
+            "SELECT inserts, deletes, live_rows FROM information_schema.table_activity \
+             WHERE table_name = 'products'",
+        )
+        .await?
+    else {
+        panic!("expected rows");
+    };
+    assert_eq!(
+        rows[0].values,
+        vec![
+            types::Value::Int(3),
+            types::Value::Int(1),
+            types::Value::Int(2)
+        ]
+    );
+    Ok(())
+}
+
 #[tokio::test]
 async fn explain_analyze_with_filter_shows_stats() -> Result<()> {
     let temp_dir = tempfile::tempdir()?;
@@ -140,3 +174,80 @@ async fn explain_analyze_with_filter_shows_stats() -> Result<()> {
 
     Ok(())
 }
+
+async fn explain_text(db: &Database, sql: &str) -> Result<String> {
+    match db.execute(sql).await? {
+        QueryResult::Rows { rows, .. } => match &rows[0].values[0] {
+            types::Value::Text(text) => Ok(text.clone()),
+            other => panic!("expected text, got {other:?}"),
+        },
+        other => panic!("expected rows, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn explain_dml_describes_the_write() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT NOT NULL, age INT)")
+        .await?;
+    db.execute("CREATE INDEX idx_age ON users (age)").await?;
+    db.execute("INSERT INTO users VALUES (1, 'Alice', 30)")
+        .await?;
+
+    let text = explain_text(&db, "EXPLAIN DELETE FROM users WHERE age > 20").await?;
+    assert!(text.starts_with("Delete table=users"), "{text}");
+    assert!(text.contains("Scan: Filter"), "{text}");
+    assert!(
+        text.contains("Index maintenance: primary key (id), btree idx_age (age)"),
+        "{text}"
+    );
+    assert!(
+        text.contains("Replication: none, applied locally and logged to the WAL"),
+        "{text}"
+    );
+
+    let text = explain_text(&db, "EXPLAIN INSERT INTO users VALUES (2, 'Bob', 25)").await?;
+    assert!(
+        text.contains("Constraint checks: NOT NULL (id, name), primary key (id) unique"),
+        "{text}"
+    );
+
+    // Plain EXPLAIN does not run the write
+    let text = explain_text(&db, "EXPLAIN UPDATE users SET age = 31").await?;
+    assert!(text.contains("Set: age"), "{text}");
+    match db.execute("SELECT age FROM users").await? {
+        QueryResult::Rows { rows, .. } => assert_eq!(rows[0].values[0], types::Value::Int(30)),
+        other => panic!("expected rows, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn explain_dml_shows_raft_replication() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::with_raft_config(
+        temp_dir.path(),
+        "catalog.json",
+        "test.wal",
+        10,
+        Some(RaftConfig::single_node(1)),
+    )
+    .await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT)")
+        .await?;
+
+    let text = explain_text(&db, "EXPLAIN INSERT INTO users VALUES (1, 'Alice')").await?;
+    assert!(
+        text.contains("Replication: Raft log, one entry per row"),
+        "{text}"
+    );
+
+    // EXPLAIN ANALYZE would apply the write without replicating it
+    let err = db
+        .execute("EXPLAIN ANALYZE DELETE FROM users")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("not supported with Raft"), "{err}");
+    Ok(())
+}
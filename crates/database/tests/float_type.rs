@@ -0,0 +1,136 @@
+//! Integration tests for FLOAT / DOUBLE columns.
+
+use anyhow::Result;
+use types::Value;
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+#[tokio::test]
+async fn float_columns_store_compare_and_sort_by_value() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, price FLOAT, weight DOUBLE PRECISION)")
+        .await?;
+    db.execute("INSERT INTO items VALUES (1, 9.5, 1e3), (2, 3, -0.25), (3, -2.75, 0.5)")
+        .await?;
+
+    // Integer literals are widened when stored in a FLOAT column
+    assert_eq!(
+        select_rows(&db, "SELECT price FROM items WHERE id = 2").await?,
+        vec![vec![Value::Float(3.0)]]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT weight FROM items WHERE id = 1").await?,
+        vec![vec![Value::Float(1000.0)]]
+    );
+
+    // Comparisons against ints and floats use the numeric value
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM items WHERE price > 3 ORDER BY id").await?,
+        vec![vec![Value::Int(1)]]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM items WHERE price <= 3.0 ORDER BY id").await?,
+        vec![vec![Value::Int(2)], vec![Value::Int(3)]]
+    );
+
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM items ORDER BY price DESC").await?,
+        vec![
+            vec![Value::Int(1)],
+            vec![Value::Int(2)],
+            vec![Value::Int(3)]
+        ]
+    );
+
+    db.execute("UPDATE items SET price = 4 WHERE id = 3")
+        .await?;
+    assert_eq!(
+        select_rows(&db, "SELECT price FROM items WHERE id = 3").await?,
+        vec![vec![Value::Float(4.0)]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn indexes_find_floats_by_int_keys() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, price FLOAT, cost REAL)")
+        .await?;
+    db.execute("CREATE INDEX idx_price ON items (price)")
+        .await?;
+    db.execute("CREATE INDEX idx_cost ON items USING HASH (cost)")
+        .await?;
+    db.execute("INSERT INTO items VALUES (1, 3, 1.5), (2, 3.5, 2), (3, 10, 2)")
+        .await?;
+
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM items WHERE price = 3").await?,
+        vec![vec![Value::Int(1)]]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM items WHERE price >= 3.5 ORDER BY id").await?,
+        vec![vec![Value::Int(2)], vec![Value::Int(3)]]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM items WHERE cost = 2.0 ORDER BY id").await?,
+        vec![vec![Value::Int(2)], vec![Value::Int(3)]]
+    );
+    // A whole float finds an INT primary key
+    assert_eq!(
+        select_rows(&db, "SELECT price FROM items WHERE id = 1.0").await?,
+        vec![vec![Value::Float(3.0)]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn int_columns_reject_floats() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY, n INT)")
+        .await?;
+    let err = db
+        .execute("INSERT INTO t VALUES (1, 2.5)")
+        .await
+        .expect_err("INT column cannot hold 2.5");
+    assert!(
+        format!("{err:#}").contains("cannot hold the float 2.5"),
+        "{err:#}"
+    );
+
+    db.execute("INSERT INTO t VALUES (1, 2)").await?;
+    assert!(db
+        .execute("UPDATE t SET n = 0.5 WHERE id = 1")
+        .await
+        .is_err());
+    assert_eq!(
+        select_rows(&db, "SELECT n FROM t").await?,
+        vec![vec![Value::Int(2)]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn floats_survive_reopen() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = create_db(temp_dir.path()).await?;
+        db.execute("CREATE TABLE t (id INT PRIMARY KEY, x FLOAT)")
+            .await?;
+        db.execute("INSERT INTO t VALUES (1, 0.1), (2, -1e-5)")
+            .await?;
+    }
+
+    let db = create_db(temp_dir.path()).await?;
+    assert_eq!(
+        select_rows(&db, "SELECT x FROM t ORDER BY id").await?,
+        vec![vec![Value::Float(0.1)], vec![Value::Float(-1e-5)]]
+    );
+    Ok(())
+}
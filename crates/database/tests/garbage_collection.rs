@@ -0,0 +1,111 @@
+//! Integration tests for orphaned file collection.
+
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+use types::Value;
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+fn plant_orphans(dir: &Path) -> Result<()> {
+    fs::write(dir.join("ghost.heap"), b"rows")?;
+    fs::write(dir.join("ghost.pk_idx"), b"keys")?;
+    fs::write(dir.join("index_99.idx"), b"entries")?;
+    fs::write(dir.join("sort.tmp"), b"scratch")?;
+    fs::write(dir.join("notes.txt"), b"keep me")?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_gc_quarantines_orphans_and_removes_temp_files() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT)")
+        .await?;
+    db.execute("CREATE INDEX idx_users_name ON users (name)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (1, 'alice')").await?;
+    plant_orphans(temp_dir.path())?;
+
+    let rows = select_rows(&db, "ADMIN GC").await?;
+    let text = |s: &str| Value::Text(s.into());
+    assert_eq!(
+        rows,
+        vec![
+            vec![text("ghost.heap"), text("quarantined")],
+            vec![text("ghost.pk_idx"), text("quarantined")],
+            vec![text("index_99.idx"), text("quarantined")],
+            vec![text("sort.tmp"), text("removed")],
+        ]
+    );
+
+    let quarantine = temp_dir.path().join("orphaned");
+    assert_eq!(fs::read(quarantine.join("ghost.heap"))?, b"rows");
+    assert!(!temp_dir.path().join("sort.tmp").exists());
+    assert!(temp_dir.path().join("notes.txt").exists());
+    assert!(temp_dir.path().join("users.heap").exists());
+    assert_eq!(
+        select_rows(&db, "SELECT name FROM users WHERE name = 'alice'").await?,
+        vec![vec![text("alice")]]
+    );
+
+    // Nothing left to collect
+    assert!(select_rows(&db, "ADMIN GC").await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_gc_keeps_pager_files_and_live_spill_files() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY)")
+        .await?;
+    let dir = temp_dir.path();
+    // The first table gets ID 1
+    fs::write(dir.join("table_1.tbl"), b"pages")?;
+    fs::write(dir.join("table_99.tbl"), b"pages")?;
+    let live_spill = format!("spill-{}-0.tmp", std::process::id());
+    fs::write(dir.join(&live_spill), b"rows")?;
+    fs::write(dir.join("spill-0-0.tmp"), b"rows")?;
+
+    let rows = select_rows(&db, "ADMIN GC").await?;
+    let text = |s: &str| Value::Text(s.into());
+    assert_eq!(
+        rows,
+        vec![
+            vec![text("spill-0-0.tmp"), text("removed")],
+            vec![text("table_99.tbl"), text("quarantined")],
+        ]
+    );
+    assert!(dir.join("table_1.tbl").exists());
+    assert!(dir.join(&live_spill).exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn orphans_are_collected_on_open() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = create_db(temp_dir.path()).await?;
+        db.execute("CREATE TABLE users (id INT PRIMARY KEY)")
+            .await?;
+        db.execute("INSERT INTO users VALUES (1)").await?;
+    }
+    plant_orphans(temp_dir.path())?;
+    // A second orphan with a name already in quarantine is kept under a new name
+    fs::create_dir_all(temp_dir.path().join("orphaned"))?;
+    fs::write(temp_dir.path().join("orphaned/ghost.heap"), b"older")?;
+
+    let db = create_db(temp_dir.path()).await?;
+    let quarantine = temp_dir.path().join("orphaned");
+    assert_eq!(fs::read(quarantine.join("ghost.heap"))?, b"older");
+    assert_eq!(fs::read(quarantine.join("ghost.heap.1"))?, b"rows");
+    assert!(!temp_dir.path().join("ghost.heap").exists());
+    assert!(!temp_dir.path().join("sort.tmp").exists());
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM users").await?,
+        vec![vec![Value::Int(1)]]
+    );
+    Ok(())
+}
@@ -0,0 +1,103 @@
+//! Integration tests for the generate_series table function.
+
+use anyhow::Result;
+use database::{Database, QueryResult};
+use types::Value;
+
+async fn ints(db: &Database, sql: &str) -> Result<Vec<i64>> {
+    match db.execute(sql).await? {
+        QueryResult::Rows { rows, .. } => Ok(rows
+            .iter()
+            .map(|row| match row.values[0] {
+                Value::Int(n) => n,
+                ref other => panic!("expected an integer, got {other:?}"),
+            })
+            .collect()),
+        other => panic!("expected rows, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn series_count_up_down_and_by_steps() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+
+    match db.execute("SELECT * FROM generate_series(1, 3)").await? {
+        QueryResult::Rows { schema, rows, .. } => {
+            assert_eq!(schema, vec!["generate_series"]);
+            assert_eq!(rows.len(), 3);
+        }
+        other => panic!("expected rows, got {other:?}"),
+    }
+    assert_eq!(
+        ints(&db, "SELECT * FROM generate_series(0, 10, 4)").await?,
+        vec![0, 4, 8]
+    );
+    assert_eq!(
+        ints(&db, "SELECT * FROM generate_series(3, 1, -1)").await?,
+        vec![3, 2, 1]
+    );
+    assert!(ints(&db, "SELECT * FROM generate_series(5, 1)")
+        .await?
+        .is_empty());
+    assert!(ints(&db, "SELECT * FROM generate_series(1, NULL)")
+        .await?
+        .is_empty());
+    assert_eq!(
+        ints(
+            &db,
+            "SELECT * FROM generate_series(9223372036854775806, 9223372036854775807)"
+        )
+        .await?,
+        vec![i64::MAX - 1, i64::MAX]
+    );
+
+    let err = db
+        .execute("SELECT * FROM generate_series(1, 10, 0)")
+        .await
+        .expect_err("a zero step should fail");
+    assert!(
+        format!("{err:#}").contains("step cannot be zero"),
+        "{err:#}"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn series_filter_sort_and_limit_like_a_table() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+
+    assert_eq!(
+        ints(
+            &db,
+            "SELECT n.generate_series * 2 AS doubled FROM generate_series(1, 1000000) AS n \
+             WHERE n.generate_series > 999997 ORDER BY doubled DESC LIMIT 2"
+        )
+        .await?,
+        vec![2_000_000, 1_999_998]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn series_join_tables_to_fill_in_keys() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE readings (day INT PRIMARY KEY, value INT)")
+        .await?;
+    db.execute("INSERT INTO readings VALUES (1, 10), (2, 20), (4, 40), (9, 90)")
+        .await?;
+
+    // The days of the first week that have a reading
+    assert_eq!(
+        ints(
+            &db,
+            "SELECT d.generate_series FROM generate_series(1, 7) d \
+             JOIN readings r ON r.day = d.generate_series ORDER BY d.generate_series"
+        )
+        .await?,
+        vec![1, 2, 4]
+    );
+    Ok(())
+}
@@ -0,0 +1,22 @@
+//! Helpers shared by the integration tests.
+//!
+//! Each test file is its own crate and uses only some of these.
+#![allow(dead_code)]
+
+use anyhow::Result;
+use database::{Database, QueryResult};
+use std::path::Path;
+use types::Value;
+
+/// Open the database in `dir`, creating it if it is empty.
+pub async fn create_db(dir: &Path) -> Result<Database> {
+    Database::new(dir, "catalog.json", "test.wal", 10).await
+}
+
+/// Run a query and return the values of its rows.
+pub async fn select_rows(db: &Database, sql: &str) -> Result<Vec<Vec<Value>>> {
+    match db.execute(sql).await? {
+        QueryResult::Rows { rows, .. } => Ok(rows.into_iter().map(|r| r.values).collect()),
+        other => panic!("expected rows, got {other:?}"),
+    }
+}
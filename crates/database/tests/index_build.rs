@@ -0,0 +1,118 @@
+//! Integration tests for building indexes over populated tables.
+
+use anyhow::Result;
+use catalog::{Catalog, Column};
+use common::{RecordId, Row};
+use database::{index_build, Database};
+use executor::EngineRegistry;
+use types::{SqlType, Value};
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+async fn insert_rows(db: &Database, rows: impl Iterator<Item = (i64, i64)>) -> Result<()> {
+    let rows: Vec<_> = rows.collect();
+    for batch in rows.chunks(200) {
+        let values: Vec<String> = batch
+            .iter()
+            .map(|(id, grp)| format!("({id}, {grp})"))
+            .collect();
+        db.execute(&format!("INSERT INTO items VALUES {}", values.join(", ")))
+            .await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn index_built_over_many_pages_finds_every_row() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, grp INT)")
+        .await?;
+    insert_rows(&db, (0..3_000).map(|id| (id, id % 7))).await?;
+    db.execute("DELETE FROM items WHERE id = 700").await?;
+
+    db.execute("CREATE INDEX idx_items_grp ON items (grp)")
+        .await?;
+    db.execute("CREATE INDEX idx_items_hash ON items USING HASH (grp)")
+        .await?;
+
+    let mut ids: Vec<i64> = select_rows(&db, "SELECT id FROM items WHERE grp = 0")
+        .await?
+        .into_iter()
+        .map(|row| match row[0] {
+            Value::Int(id) => id,
+            ref other => panic!("expected an id, got {other:?}"),
+        })
+        .collect();
+    ids.sort();
+    let expected: Vec<i64> = (0..3_000).filter(|id| id % 7 == 0 && *id != 700).collect();
+    assert_eq!(ids, expected);
+
+    let count = select_rows(&db, "SELECT id FROM items WHERE grp >= 5")
+        .await?
+        .len();
+    assert_eq!(count, (0..3_000).filter(|id| id % 7 >= 5).count());
+    Ok(())
+}
+
+#[tokio::test]
+async fn unique_index_build_finds_duplicates_across_pages() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, grp INT)")
+        .await?;
+    insert_rows(&db, (0..2_000).map(|id| (id, id))).await?;
+    insert_rows(&db, std::iter::once((2_000, 3))).await?;
+
+    let err = db
+        .execute("CREATE UNIQUE INDEX idx_items_grp ON items (grp)")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("duplicate key value"), "{err}");
+
+    db.execute("DELETE FROM items WHERE id = 2000").await?;
+    db.execute("CREATE UNIQUE INDEX idx_items_grp ON items (grp)")
+        .await?;
+    Ok(())
+}
+
+#[test]
+fn parallel_and_single_threaded_scans_agree() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let mut catalog = Catalog::new();
+    catalog.create_table(
+        "items",
+        vec![
+            Column::new("id", SqlType::Int),
+            Column::new("grp", SqlType::Int),
+        ],
+        None,
+    )?;
+    let table = catalog.table("items")?;
+    let engines = EngineRegistry::default();
+    let mut heap = engines.open(temp_dir.path(), table, None)?;
+    let rids: Vec<RecordId> = (0..5_000)
+        .map(|id| heap.insert(&Row::new(vec![Value::Int(id), Value::Int(id % 13)])))
+        .collect::<Result<_, _>>()?;
+    // Empty slots early in each page, where scans probe past them
+    let deleted: Vec<RecordId> = rids
+        .iter()
+        .copied()
+        .filter(|rid| rid.slot < 50 && rid.slot % 3 == 0)
+        .collect();
+    for rid in &deleted {
+        heap.delete(*rid)?;
+    }
+    drop(heap);
+
+    let scan = |threads| {
+        index_build::collect_entries(&engines, temp_dir.path(), table, None, &[1], threads)
+    };
+    let single = scan(1)?;
+    assert_eq!(single.len(), rids.len() - deleted.len());
+    assert!(single.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    assert_eq!(scan(4)?, single);
+    assert_eq!(scan(index_build::MAX_BUILD_THREADS)?, single);
+    Ok(())
+}
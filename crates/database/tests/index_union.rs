@@ -0,0 +1,52 @@
+//! Integration tests for answering OR predicates from several index probes.
+
+use anyhow::Result;
+use database::Database;
+use types::Value;
+
+mod helpers;
+use helpers::select_rows;
+
+#[tokio::test]
+async fn index_union_returns_each_matching_row_once() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    // Statistics would show that scanning this small table costs less
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10)
+        .await?
+        .with_auto_analyze(None);
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, grp INT, tag TEXT)")
+        .await?;
+    let values: Vec<String> = (0..60)
+        .map(|id| format!("({id}, {}, 'tag{}')", id % 6, id % 5))
+        .collect();
+    db.execute(&format!("INSERT INTO items VALUES {}", values.join(", ")))
+        .await?;
+    db.execute("CREATE INDEX idx_items_grp ON items (grp)")
+        .await?;
+    db.execute("CREATE INDEX idx_items_tag ON items USING HASH (tag)")
+        .await?;
+
+    let sql = "SELECT id FROM items WHERE grp = 1 OR tag = 'tag1' OR grp = 1 ORDER BY id";
+    let plan = select_rows(&db, &format!("EXPLAIN {sql}")).await?;
+    assert!(format!("{plan:?}").contains("IndexUnion"), "{plan:?}");
+
+    let ids = select_rows(&db, sql).await?;
+    let expected: Vec<Vec<Value>> = (0..60)
+        .filter(|id| id % 6 == 1 || id % 5 == 1)
+        .map(|id| vec![Value::Int(id)])
+        .collect();
+    assert_eq!(ids, expected);
+
+    // The filter above the probes still applies the rest of the predicate
+    let ids = select_rows(
+        &db,
+        "SELECT id FROM items WHERE (grp = 2 OR tag = 'tag3') AND id < 20 ORDER BY id",
+    )
+    .await?;
+    let expected: Vec<Vec<Value>> = (0..20)
+        .filter(|id| id % 6 == 2 || id % 5 == 3)
+        .map(|id| vec![Value::Int(id)])
+        .collect();
+    assert_eq!(ids, expected);
+    Ok(())
+}
@@ -0,0 +1,81 @@
+//! Integration tests for INSERT with an explicit column list and DEFAULT values.
+
+use anyhow::Result;
+use database::{Database, RaftConfig};
+use types::Value;
+
+mod helpers;
+use helpers::select_rows;
+
+async fn check_column_list_inserts(db: &Database) -> Result<()> {
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, label TEXT, qty INT DEFAULT 1)")
+        .await?;
+    db.execute("INSERT INTO items (qty, id) VALUES (5, 1)")
+        .await?;
+    db.execute("INSERT INTO items (id, label) VALUES (2, 'two'), (3, 'three')")
+        .await?;
+
+    let rows = select_rows(db, "SELECT id, label, qty FROM items").await?;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(1), Value::Null, Value::Int(5)],
+            vec![Value::Int(2), Value::Text("two".into()), Value::Int(1)],
+            vec![Value::Int(3), Value::Text("three".into()), Value::Int(1)],
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn insert_with_column_list_uses_defaults() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    check_column_list_inserts(&db).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn insert_with_column_list_through_raft() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::with_raft_config(
+        temp_dir.path(),
+        "catalog.json",
+        "test.wal",
+        10,
+        Some(RaftConfig::single_node(1)),
+    )
+    .await?;
+    check_column_list_inserts(&db).await
+}
+
+#[tokio::test]
+async fn defaults_survive_restart() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+        db.execute("CREATE TABLE t (id INT, status TEXT DEFAULT 'new')")
+            .await?;
+    }
+
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("INSERT INTO t (id) VALUES (7)").await?;
+    let rows = select_rows(&db, "SELECT status FROM t").await?;
+    assert_eq!(rows, vec![vec![Value::Text("new".into())]]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn unknown_insert_column_is_rejected() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE t (id INT)").await?;
+    let err = db
+        .execute("INSERT INTO t (missing) VALUES (1)")
+        .await
+        .expect_err("unknown column should fail");
+    assert!(
+        err.to_string().contains("unknown column 'missing'"),
+        "{err}"
+    );
+    Ok(())
+}
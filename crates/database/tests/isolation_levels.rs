@@ -0,0 +1,164 @@
+//! Integration tests for SET TRANSACTION ISOLATION LEVEL.
+
+use anyhow::Result;
+use database::{Database, IsolationLevel, QueryResult, LOCAL_PRINCIPAL};
+use std::sync::Arc;
+use types::Value;
+
+#[tokio::test]
+async fn set_transaction_applies_to_the_next_statement_only() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY)").await?;
+    assert_eq!(
+        db.isolation_level(LOCAL_PRINCIPAL),
+        IsolationLevel::Serializable
+    );
+
+    let result = db
+        .execute("SET TRANSACTION ISOLATION LEVEL READ COMMITTED")
+        .await?;
+    assert!(matches!(result, QueryResult::Empty));
+    assert_eq!(
+        db.isolation_level(LOCAL_PRINCIPAL),
+        IsolationLevel::ReadCommitted
+    );
+    assert_eq!(
+        db.session_isolation_level(LOCAL_PRINCIPAL),
+        IsolationLevel::Serializable
+    );
+
+    db.execute("INSERT INTO t VALUES (1)").await?;
+    assert_eq!(
+        db.isolation_level(LOCAL_PRINCIPAL),
+        IsolationLevel::Serializable
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn session_characteristics_change_the_default() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY)").await?;
+
+    db.execute("SET SESSION CHARACTERISTICS AS TRANSACTION ISOLATION LEVEL REPEATABLE READ")
+        .await?;
+    db.execute("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
+        .await?;
+    assert_eq!(
+        db.isolation_level(LOCAL_PRINCIPAL),
+        IsolationLevel::Serializable
+    );
+
+    db.execute("SELECT * FROM t").await?;
+    assert_eq!(
+        db.isolation_level(LOCAL_PRINCIPAL),
+        IsolationLevel::RepeatableRead
+    );
+    assert_eq!(
+        db.session_isolation_level(LOCAL_PRINCIPAL),
+        IsolationLevel::RepeatableRead
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn levels_belong_to_the_session_that_sets_them() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY)").await?;
+
+    db.execute_as("a", "SET TRANSACTION ISOLATION LEVEL READ COMMITTED")
+        .await?;
+    db.execute_as(
+        "b",
+        "SET SESSION CHARACTERISTICS AS TRANSACTION ISOLATION LEVEL REPEATABLE READ",
+    )
+    .await?;
+    assert_eq!(db.isolation_level("a"), IsolationLevel::ReadCommitted);
+    assert_eq!(
+        db.session_isolation_level("a"),
+        IsolationLevel::Serializable
+    );
+    assert_eq!(db.isolation_level("b"), IsolationLevel::RepeatableRead);
+    assert_eq!(
+        db.isolation_level(LOCAL_PRINCIPAL),
+        IsolationLevel::Serializable
+    );
+
+    // Statements and transactions of other sessions leave a's one-shot
+    // level for a's next transaction
+    db.execute_as("b", "INSERT INTO t VALUES (1)").await?;
+    db.execute("INSERT INTO t VALUES (2)").await?;
+    let other = db.begin_as("b");
+    assert_eq!(other.isolation_level(), IsolationLevel::RepeatableRead);
+    assert_eq!(db.begin().isolation_level(), IsolationLevel::Serializable);
+    assert_eq!(db.isolation_level("a"), IsolationLevel::ReadCommitted);
+    assert_eq!(
+        db.begin_as("a").isolation_level(),
+        IsolationLevel::ReadCommitted
+    );
+    assert_eq!(db.isolation_level("a"), IsolationLevel::Serializable);
+    assert_eq!(db.isolation_level("b"), IsolationLevel::RepeatableRead);
+
+    db.end_session("b");
+    assert_eq!(db.isolation_level("b"), IsolationLevel::Serializable);
+    Ok(())
+}
+
+/// Sum of the `balance` column of `accounts`.
+async fn total_balance(db: &Database) -> Result<Value> {
+    match db.execute("SELECT SUM(balance) FROM accounts").await? {
+        QueryResult::Rows { mut rows, .. } => Ok(rows.remove(0).values.remove(0)),
+        other => panic!("expected rows, got {other:?}"),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn weaker_levels_still_run_statements_one_at_a_time() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE accounts (id INT PRIMARY KEY, balance INT)")
+        .await?;
+    db.execute("INSERT INTO accounts VALUES (1, 100), (2, 100)")
+        .await?;
+    db.execute("SET SESSION CHARACTERISTICS AS TRANSACTION ISOLATION LEVEL READ COMMITTED")
+        .await?;
+    let db = Arc::new(db);
+
+    // Each transfer writes both rows in one statement. Statements hold their
+    // locks until they finish, so no reader sees one row written without
+    // the other, and no transfer loses another's write.
+    let mut transfers = Vec::new();
+    for _ in 0..8 {
+        let db = Arc::clone(&db);
+        transfers.push(tokio::spawn(async move {
+            for _ in 0..10 {
+                db.execute(
+                    "UPDATE accounts SET balance = balance + CASE WHEN id = 1 THEN -1 ELSE 1 END",
+                )
+                .await?;
+            }
+            anyhow::Ok(())
+        }));
+    }
+    for _ in 0..40 {
+        assert_eq!(total_balance(&db).await?, Value::Int(200));
+    }
+    for transfer in transfers {
+        transfer.await??;
+    }
+
+    match db
+        .execute("SELECT balance FROM accounts ORDER BY id")
+        .await?
+    {
+        QueryResult::Rows { rows, .. } => {
+            let balances: Vec<_> = rows.into_iter().map(|row| row.values[0].clone()).collect();
+            assert_eq!(balances, vec![Value::Int(20), Value::Int(180)]);
+        }
+        other => panic!("expected rows, got {other:?}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,134 @@
+//! Integration tests for JOIN ON conditions beyond a single equality.
+
+use anyhow::Result;
+use database::Database;
+use types::Value;
+
+mod helpers;
+use helpers::select_rows;
+
+async fn setup(dir: &std::path::Path) -> Result<Database> {
+    let db = Database::new(dir, "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, credit INT)")
+        .await?;
+    db.execute("CREATE TABLE orders (id INT PRIMARY KEY, user_id INT, total INT)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (1, 50), (2, 100), (3, 0)")
+        .await?;
+    db.execute(
+        "INSERT INTO orders VALUES (10, 1, 40), (11, 1, 60), (12, 2, 150), \
+         (13, NULL, 500), (14, 3, 5)",
+    )
+    .await?;
+    Ok(db)
+}
+
+fn ints(rows: &[&[i64]]) -> Vec<Vec<Value>> {
+    rows.iter()
+        .map(|row| row.iter().map(|v| Value::Int(*v)).collect())
+        .collect()
+}
+
+#[tokio::test]
+async fn equality_with_a_range_term() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = setup(temp_dir.path()).await?;
+
+    // Orders over their user's credit
+    let rows = select_rows(
+        &db,
+        "SELECT o.id, u.id FROM users u JOIN orders o \
+         ON o.user_id = u.id AND o.total > u.credit ORDER BY o.id",
+    )
+    .await?;
+    assert_eq!(rows, ints(&[&[11, 1], &[12, 2], &[14, 3]]));
+
+    let rows = select_rows(
+        &db,
+        "SELECT o.id FROM users u JOIN orders o \
+         ON u.id = o.user_id AND o.total >= 10 AND o.total <= 100 AND u.credit <> 100 \
+         ORDER BY o.id",
+    )
+    .await?;
+    assert_eq!(rows, ints(&[&[10], &[11]]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn conditions_without_an_equality() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = setup(temp_dir.path()).await?;
+
+    let rows = select_rows(
+        &db,
+        "SELECT o.id, u.id FROM users u JOIN orders o \
+         ON o.id = u.id + 10 OR u.credit > 60 ORDER BY o.id, u.id",
+    )
+    .await?;
+    assert_eq!(
+        rows,
+        ints(&[
+            &[10, 2],
+            &[11, 1],
+            &[11, 2],
+            &[12, 2],
+            &[13, 2],
+            &[13, 3],
+            &[14, 2]
+        ])
+    );
+
+    let rows = select_rows(
+        &db,
+        "SELECT o.id, u.id FROM users u JOIN orders o \
+         ON o.total < u.credit - 45 ORDER BY o.id, u.id",
+    )
+    .await?;
+    assert_eq!(rows, ints(&[&[10, 2], &[14, 2]]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn expression_keys_and_null_keys() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = setup(temp_dir.path()).await?;
+
+    let rows = select_rows(
+        &db,
+        "SELECT o.id, u.id FROM users u JOIN orders o \
+         ON o.user_id = u.id + 1 ORDER BY o.id",
+    )
+    .await?;
+    assert_eq!(rows, ints(&[&[12, 1], &[14, 2]]));
+
+    // The order with no user matches no user, not even through a NULL
+    db.execute("CREATE TABLE notes (id INT PRIMARY KEY, user_id INT)")
+        .await?;
+    db.execute("INSERT INTO notes VALUES (1, NULL), (2, 1)")
+        .await?;
+    let rows = select_rows(
+        &db,
+        "SELECT o.id, n.id FROM orders o JOIN notes n ON o.user_id = n.user_id ORDER BY o.id",
+    )
+    .await?;
+    assert_eq!(rows, ints(&[&[10, 2], &[11, 2]]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn keys_of_different_numeric_types() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = setup(temp_dir.path()).await?;
+    db.execute("CREATE TABLE prices (id INT PRIMARY KEY, total DECIMAL(6, 2))")
+        .await?;
+    db.execute("INSERT INTO prices VALUES (1, 40.00), (2, 60.50), (3, 150)")
+        .await?;
+
+    let rows = select_rows(
+        &db,
+        "SELECT o.id, p.id FROM orders o JOIN prices p ON o.total = p.total ORDER BY o.id",
+    )
+    .await?;
+    assert_eq!(rows, ints(&[&[10, 1], &[12, 3]]));
+    Ok(())
+}
@@ -0,0 +1,104 @@
+//! Integration tests for ORDER BY on table-qualified columns of joins.
+
+use anyhow::Result;
+use database::Database;
+use types::Value;
+
+mod helpers;
+use helpers::select_rows;
+
+async fn setup(dir: &std::path::Path) -> Result<Database> {
+    let db = Database::new(dir, "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT)")
+        .await?;
+    db.execute("CREATE TABLE orders (id INT PRIMARY KEY, user_id INT, name TEXT)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (1, 'carol'), (2, 'alice'), (3, 'bob')")
+        .await?;
+    db.execute("INSERT INTO orders VALUES (10, 1, 'pen'), (11, 2, 'ink'), (12, 3, 'cap')")
+        .await?;
+    Ok(db)
+}
+
+fn text(values: &[&str]) -> Vec<Vec<Value>> {
+    values
+        .iter()
+        .map(|v| vec![Value::Text((*v).into())])
+        .collect()
+}
+
+#[tokio::test]
+async fn order_by_table_qualified_column() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = setup(temp_dir.path()).await?;
+
+    // Both tables have a `name`; the qualifier picks which one sorts
+    let rows = select_rows(
+        &db,
+        "SELECT users.name FROM users JOIN orders ON users.id = orders.user_id \
+         ORDER BY users.name",
+    )
+    .await?;
+    assert_eq!(rows, text(&["alice", "bob", "carol"]));
+
+    let rows = select_rows(
+        &db,
+        "SELECT users.name FROM users JOIN orders ON users.id = orders.user_id \
+         ORDER BY orders.name DESC",
+    )
+    .await?;
+    assert_eq!(rows, text(&["carol", "alice", "bob"]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn order_by_alias_qualified_column_not_selected() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = setup(temp_dir.path()).await?;
+
+    let rows = select_rows(
+        &db,
+        "SELECT o.name FROM users u JOIN orders o ON u.id = o.user_id \
+         ORDER BY U.NAME DESC, o.id",
+    )
+    .await?;
+    assert_eq!(rows, text(&["pen", "cap", "ink"]));
+
+    let rows = select_rows(
+        &db,
+        "SELECT u.name AS buyer, o.name AS item FROM users u \
+         JOIN orders o ON u.id = o.user_id ORDER BY o.name",
+    )
+    .await?;
+    assert_eq!(
+        rows[0],
+        vec![Value::Text("bob".into()), Value::Text("cap".into())]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn order_by_unknown_or_ambiguous_column_is_rejected() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = setup(temp_dir.path()).await?;
+
+    let join = "SELECT u.id FROM users u JOIN orders o ON u.id = o.user_id";
+    let err = db
+        .execute(&format!("{join} ORDER BY u.missing"))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("'u.missing'"), "{err}");
+
+    // Once aliased, a table is only known by its alias
+    assert!(db
+        .execute(&format!("{join} ORDER BY users.name"))
+        .await
+        .is_err());
+
+    let err = db
+        .execute(&format!("{join} ORDER BY name"))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("ambiguous"), "{err}");
+    Ok(())
+}
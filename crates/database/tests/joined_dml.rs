@@ -0,0 +1,126 @@
+//! Integration tests for `UPDATE ... FROM` and `DELETE ... USING`.
+
+use anyhow::Result;
+use database::{Database, QueryResult};
+use types::Value;
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+async fn affected(db: &Database, sql: &str) -> Result<u64> {
+    match db.execute(sql).await? {
+        QueryResult::Count { affected, .. } => Ok(affected),
+        other => panic!("expected a count, got {:?}", other),
+    }
+}
+
+async fn create_accounts(db: &Database) -> Result<()> {
+    db.execute("CREATE TABLE accounts (id INT PRIMARY KEY, owner TEXT, balance INT)")
+        .await?;
+    db.execute("CREATE INDEX idx_owner ON accounts (owner)")
+        .await?;
+    db.execute("CREATE TABLE transfers (id INT PRIMARY KEY, account INT, amount INT)")
+        .await?;
+    db.execute("INSERT INTO accounts VALUES (1, 'ada', 100), (2, 'bob', 50), (3, 'cy', 0)")
+        .await?;
+    db.execute("INSERT INTO transfers VALUES (10, 1, 25), (11, 2, 5), (12, 2, 7)")
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn update_from_sets_values_from_the_matched_rows() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    create_accounts(&db).await?;
+
+    // Account 2 matches two transfers but is updated once
+    let count = affected(
+        &db,
+        "UPDATE accounts a SET balance = a.balance + t.amount, owner = CONCAT(owner, '!') \
+         FROM transfers t WHERE a.id = t.account",
+    )
+    .await?;
+    assert_eq!(count, 2);
+    assert_eq!(
+        select_rows(&db, "SELECT id, owner, balance FROM accounts ORDER BY id").await?,
+        vec![
+            vec![Value::Int(1), Value::Text("ada!".into()), Value::Int(125)],
+            vec![Value::Int(2), Value::Text("bob!".into()), Value::Int(55)],
+            vec![Value::Int(3), Value::Text("cy".into()), Value::Int(0)],
+        ]
+    );
+    // The index follows the new values
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM accounts WHERE owner = 'bob!'").await?,
+        vec![vec![Value::Int(2)]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn delete_using_removes_the_matched_rows() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = create_db(temp_dir.path()).await?;
+        create_accounts(&db).await?;
+
+        let count = affected(
+            &db,
+            "DELETE FROM accounts USING transfers \
+             WHERE accounts.id = transfers.account AND transfers.amount < 10",
+        )
+        .await?;
+        assert_eq!(count, 1);
+        let count = affected(
+            &db,
+            "DELETE FROM transfers t USING accounts a WHERE t.account = a.id AND a.owner = 'ada'",
+        )
+        .await?;
+        assert_eq!(count, 1);
+    }
+
+    let db = create_db(temp_dir.path()).await?;
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM accounts ORDER BY id").await?,
+        vec![vec![Value::Int(1)], vec![Value::Int(3)]]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM transfers ORDER BY id").await?,
+        vec![vec![Value::Int(11)], vec![Value::Int(12)]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn joined_dml_rejects_ambiguous_columns() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    create_accounts(&db).await?;
+
+    let err = db
+        .execute("DELETE FROM accounts USING transfers WHERE id = 1")
+        .await
+        .expect_err("both tables have an id");
+    assert!(
+        format!("{err:#}").contains("ambiguous column 'id'"),
+        "{err:#}"
+    );
+    let err = db
+        .execute("UPDATE accounts SET balance = 0 FROM accounts WHERE balance > 0")
+        .await
+        .expect_err("the table is named twice");
+    assert!(
+        format!("{err:#}").contains("specified more than once"),
+        "{err:#}"
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT balance FROM accounts ORDER BY id").await?,
+        vec![
+            vec![Value::Int(100)],
+            vec![Value::Int(50)],
+            vec![Value::Int(0)]
+        ]
+    );
+    Ok(())
+}
@@ -0,0 +1,113 @@
+//! Integration tests for the data directory integrity manifest.
+
+use anyhow::Result;
+use database::{Database, Manifest};
+use std::fs::OpenOptions;
+use std::path::Path;
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+async fn populate(dir: &Path) -> Result<Database> {
+    let db = create_db(dir).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT)")
+        .await?;
+    db.execute("CREATE INDEX idx_users_name ON users (name)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (1, 'alice'), (2, 'bob')")
+        .await?;
+    Ok(db)
+}
+
+fn truncate(path: &Path, len: u64) -> Result<()> {
+    OpenOptions::new().write(true).open(path)?.set_len(len)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn manifest_lists_table_files_and_clean_reopen_succeeds() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    drop(populate(temp_dir.path()).await?);
+
+    let manifest = Manifest::load(&temp_dir.path().join("manifest.json"), None)?.expect("manifest");
+    assert!(manifest.clean);
+    let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+    for expected in ["catalog.json", "test.wal", "users.heap", "users.pk_idx"] {
+        assert!(paths.contains(&expected), "{expected} not in {paths:?}");
+    }
+    assert!(manifest.files.iter().all(|f| f.checksum.is_some()));
+
+    let db = create_db(temp_dir.path()).await?;
+    assert_eq!(select_rows(&db, "SELECT id FROM users").await?.len(), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn missing_and_truncated_files_fail_open_with_their_names() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    drop(populate(temp_dir.path()).await?);
+
+    std::fs::remove_file(temp_dir.path().join("users.pk_idx"))?;
+    truncate(&temp_dir.path().join("users.heap"), 10)?;
+
+    let err = create_db(temp_dir.path())
+        .await
+        .err()
+        .expect("open must fail");
+    let message = err.to_string();
+    assert!(message.contains("integrity check"), "{message}");
+    assert!(message.contains("users.pk_idx: missing"), "{message}");
+    assert!(message.contains("users.heap"), "{message}");
+
+    // Removing the manifest opts out of the check
+    std::fs::remove_file(temp_dir.path().join("manifest.json"))?;
+    create_db(temp_dir.path()).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn changed_bytes_are_caught_after_a_clean_close() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    drop(populate(temp_dir.path()).await?);
+
+    let heap = temp_dir.path().join("users.heap");
+    let mut bytes = std::fs::read(&heap)?;
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    std::fs::write(&heap, bytes)?;
+
+    let err = create_db(temp_dir.path())
+        .await
+        .err()
+        .expect("open must fail");
+    assert!(
+        err.to_string().contains("users.heap: checksum mismatch"),
+        "{err}"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn after_a_crash_only_shrinking_files_are_reported() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = populate(temp_dir.path()).await?;
+    db.execute("INSERT INTO users VALUES (3, 'carol')").await?;
+    // Simulate a crash: the manifest from the last schema change stays dirty
+    std::mem::forget(db);
+
+    let manifest = Manifest::load(&temp_dir.path().join("manifest.json"), None)?.expect("manifest");
+    assert!(!manifest.clean);
+
+    // Rows written since the manifest was recorded are fine
+    let db = create_db(temp_dir.path()).await?;
+    assert_eq!(select_rows(&db, "SELECT id FROM users").await?.len(), 3);
+    std::mem::forget(db);
+
+    truncate(&temp_dir.path().join("users.heap"), 0)?;
+    let err = create_db(temp_dir.path())
+        .await
+        .err()
+        .expect("open must fail");
+    assert!(err.to_string().contains("users.heap: truncated"), "{err}");
+    Ok(())
+}
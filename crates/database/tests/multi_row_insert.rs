@@ -0,0 +1,77 @@
+//! Integration tests for multi-row INSERT.
+
+use anyhow::Result;
+use database::{Database, QueryResult, RaftConfig};
+use types::Value;
+
+async fn row_count(db: &Database, sql: &str) -> Result<usize> {
+    match db.execute(sql).await? {
+        QueryResult::Rows { rows, .. } => Ok(rows.len()),
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn insert_multiple_rows_in_one_statement() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT)")
+        .await?;
+
+    match db
+        .execute("INSERT INTO users VALUES (1, 'alice'), (2, 'bob'), (3, 'carol')")
+        .await?
+    {
+        QueryResult::Count { affected, .. } => assert_eq!(affected, 3),
+        other => panic!("expected count, got {:?}", other),
+    }
+
+    match db.execute("SELECT name FROM users WHERE id = 3").await? {
+        QueryResult::Rows { rows, .. } => {
+            assert_eq!(rows[0].values, vec![Value::Text("carol".into())]);
+        }
+        other => panic!("expected rows, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn duplicate_key_rejects_whole_batch() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT)")
+        .await?;
+
+    let err = db
+        .execute("INSERT INTO users VALUES (1, 'alice'), (1, 'bob')")
+        .await
+        .expect_err("duplicate key within the batch should fail");
+    assert!(err.to_string().contains("duplicate primary key"), "{err}");
+    assert_eq!(row_count(&db, "SELECT * FROM users").await?, 0);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn multi_row_insert_through_raft() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::with_raft_config(
+        temp_dir.path(),
+        "catalog.json",
+        "test.wal",
+        10,
+        Some(RaftConfig::single_node(1)),
+    )
+    .await?;
+    db.execute("CREATE TABLE items (id INT, label TEXT)")
+        .await?;
+
+    match db
+        .execute("INSERT INTO items VALUES (1, 'a'), (2, 'b')")
+        .await?
+    {
+        QueryResult::Count { affected, .. } => assert_eq!(affected, 2),
+        other => panic!("expected count, got {:?}", other),
+    }
+    assert_eq!(row_count(&db, "SELECT * FROM items").await?, 2);
+    Ok(())
+}
@@ -0,0 +1,125 @@
+//! Integration tests for executing several statements in one call.
+
+use anyhow::Result;
+use database::{Database, QueryResult};
+use types::Value;
+
+mod helpers;
+use helpers::create_db;
+
+async fn count_rows(db: &Database, table: &str) -> Result<usize> {
+    match db.execute(&format!("SELECT * FROM {table}")).await? {
+        QueryResult::Rows { rows, .. } => Ok(rows.len()),
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn script_returns_each_statements_result() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    let results = db
+        .execute_script(
+            "CREATE TABLE users (id INT PRIMARY KEY, name TEXT);
+             -- seed data
+             INSERT INTO users VALUES (1, 'a;b'), (2, 'Bob');
+             SELECT name FROM users WHERE id = 1;",
+        )
+        .await?;
+    assert_eq!(results.len(), 3);
+    assert!(matches!(results[0], QueryResult::Empty));
+    assert!(matches!(results[1], QueryResult::Count { affected: 2, .. }));
+    match &results[2] {
+        QueryResult::Rows { rows, .. } => {
+            assert_eq!(rows[0].values, vec![Value::Text("a;b".into())])
+        }
+        other => panic!("expected rows, got {:?}", other),
+    }
+
+    // execute runs every statement and returns the last result
+    match db
+        .execute("INSERT INTO users VALUES (3, 'Carol'); SELECT * FROM users")
+        .await?
+    {
+        QueryResult::Rows { rows, .. } => assert_eq!(rows.len(), 3),
+        other => panic!("expected rows, got {:?}", other),
+    }
+    assert!(matches!(
+        db.execute(" ; -- nothing").await?,
+        QueryResult::Empty
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn syntax_error_runs_nothing() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE t (id INT)").await?;
+
+    let err = db
+        .execute("INSERT INTO t VALUES (1); INSERT INTO t VALUES (2); SELEC 3")
+        .await
+        .expect_err("third statement does not parse");
+    assert!(
+        err.to_string().starts_with("statement 3 of 3 failed"),
+        "{err}"
+    );
+    assert_eq!(count_rows(&db, "t").await?, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn execution_stops_at_the_failing_statement() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    let err = db
+        .execute_script(
+            "CREATE TABLE t (id INT PRIMARY KEY);
+             INSERT INTO t VALUES (1);
+             INSERT INTO t VALUES (1);
+             INSERT INTO t VALUES (2);",
+        )
+        .await
+        .expect_err("duplicate key");
+    let message = err.to_string();
+    assert!(message.starts_with("statement 3 of 4 failed"), "{message}");
+    assert!(message.contains("duplicate"), "{message}");
+
+    // The statements before the failure keep their effects
+    assert_eq!(count_rows(&db, "t").await?, 1);
+
+    // A single statement's error is reported as before
+    let err = db.execute("INSERT INTO t VALUES (1)").await.unwrap_err();
+    assert!(!err.to_string().contains("statement 1"), "{err}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn each_statement_is_audited_with_its_own_text() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute_as(
+        "alice",
+        "CREATE TABLE t (id INT) WITH (audit = true); INSERT INTO t VALUES (1); SELECT * FROM t",
+    )
+    .await?;
+    let statements: Vec<String> = db
+        .audit_log()
+        .read()?
+        .into_iter()
+        .map(|record| record.statement)
+        .collect();
+    assert_eq!(
+        statements,
+        vec![
+            "CREATE TABLE t (id INT) WITH (audit = true)",
+            "INSERT INTO t VALUES (1)",
+            "SELECT * FROM t",
+        ]
+    );
+    Ok(())
+}
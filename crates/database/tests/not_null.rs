@@ -0,0 +1,115 @@
+//! Integration tests for NOT NULL constraints.
+
+use anyhow::Result;
+use database::{Database, RaftConfig};
+use types::Value;
+
+mod helpers;
+use helpers::select_rows;
+
+async fn expect_not_null_violation(db: &Database, sql: &str, column: &str) {
+    let err = db.execute(sql).await.expect_err(sql);
+    let msg = err.to_string();
+    assert!(
+        msg.contains("NOT NULL") && msg.contains(&format!("'{column}'")),
+        "{sql}: {msg}"
+    );
+}
+
+async fn check_not_null_enforced(db: &Database) -> Result<()> {
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT NOT NULL, email TEXT)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (1, 'alice', NULL)")
+        .await?;
+
+    expect_not_null_violation(db, "INSERT INTO users VALUES (2, NULL, 'b@x')", "name").await;
+    expect_not_null_violation(
+        db,
+        "INSERT INTO users (id, email) VALUES (2, 'b@x')",
+        "name",
+    )
+    .await;
+    expect_not_null_violation(db, "INSERT INTO users VALUES (NULL, 'bob', NULL)", "id").await;
+    expect_not_null_violation(db, "UPDATE users SET name = NULL WHERE id = 1", "name").await;
+
+    // A rejected multi-row INSERT writes nothing
+    expect_not_null_violation(
+        db,
+        "INSERT INTO users VALUES (3, 'carol', NULL), (4, NULL, NULL)",
+        "name",
+    )
+    .await;
+
+    let rows = select_rows(db, "SELECT id, name FROM users").await?;
+    assert_eq!(rows, vec![vec![Value::Int(1), Value::Text("alice".into())]]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn not_null_enforced_locally() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    check_not_null_enforced(&db).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn not_null_enforced_through_raft() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::with_raft_config(
+        temp_dir.path(),
+        "catalog.json",
+        "test.wal",
+        10,
+        Some(RaftConfig::single_node(1)),
+    )
+    .await?;
+    check_not_null_enforced(&db).await
+}
+
+#[tokio::test]
+async fn update_is_all_or_nothing() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY, a INT, b INT NOT NULL)")
+        .await?;
+    db.execute("INSERT INTO t VALUES (1, 1, 1), (2, 2, 2)")
+        .await?;
+
+    expect_not_null_violation(&db, "UPDATE t SET a = 9, b = NULL", "b").await;
+    let rows = select_rows(&db, "SELECT id, a FROM t ORDER BY id").await?;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(1), Value::Int(1)],
+            vec![Value::Int(2), Value::Int(2)]
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn not_null_survives_restart_and_alter() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+        db.execute("CREATE TABLE t (id INT, label TEXT NOT NULL)")
+            .await?;
+        db.execute("INSERT INTO t VALUES (1, 'a')").await?;
+    }
+
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    expect_not_null_violation(&db, "INSERT INTO t VALUES (2, NULL)", "label").await;
+
+    let err = db
+        .execute("ALTER TABLE t ADD COLUMN qty INT NOT NULL")
+        .await
+        .expect_err("NOT NULL without default");
+    assert!(err.to_string().contains("without a DEFAULT"), "{err}");
+
+    db.execute("ALTER TABLE t ADD COLUMN qty INT NOT NULL DEFAULT 0")
+        .await?;
+    expect_not_null_violation(&db, "UPDATE t SET qty = NULL", "qty").await;
+    let rows = select_rows(&db, "SELECT qty FROM t").await?;
+    assert_eq!(rows, vec![vec![Value::Int(0)]]);
+    Ok(())
+}
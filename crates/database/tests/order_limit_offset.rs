@@ -490,3 +490,126 @@ async fn empty_table_with_order_and_limit() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn order_by_nulls_first_and_last() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+
+    db.execute("CREATE TABLE scores (id INT PRIMARY KEY, team TEXT, points INT)")
+        .await?;
+    db.execute(
+        "INSERT INTO scores VALUES (1, 'red', 10), (2, NULL, 5), (3, 'blue', NULL), \
+         (4, 'red', NULL), (5, 'blue', 7)",
+    )
+    .await?;
+
+    let ids = |result: QueryResult| match result {
+        QueryResult::Rows { rows, .. } => rows
+            .into_iter()
+            .map(|row| row.values[0].clone())
+            .collect::<Vec<_>>(),
+        _ => panic!("Expected Rows result"),
+    };
+
+    // Defaults: NULLs are the lowest value
+    let result = db
+        .execute("SELECT id, team, points FROM scores ORDER BY team, points DESC")
+        .await?;
+    assert_eq!(
+        ids(result),
+        vec![
+            Value::Int(2),
+            Value::Int(5),
+            Value::Int(3),
+            Value::Int(1),
+            Value::Int(4)
+        ]
+    );
+
+    let result = db
+        .execute(
+            "SELECT id, team, points FROM scores \
+             ORDER BY team DESC NULLS LAST, points NULLS FIRST",
+        )
+        .await?;
+    assert_eq!(
+        ids(result),
+        vec![
+            Value::Int(4),
+            Value::Int(1),
+            Value::Int(3),
+            Value::Int(5),
+            Value::Int(2)
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn order_by_expressions_aliases_and_unselected_columns() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, name TEXT, qty INT)")
+        .await?;
+    db.execute("INSERT INTO items VALUES (1, 'pear', 3), (2, 'fig', 9), (3, 'banana', 1)")
+        .await?;
+
+    let rows = |result: QueryResult| match result {
+        QueryResult::Rows { schema, rows, .. } => (
+            schema,
+            rows.into_iter().map(|row| row.values).collect::<Vec<_>>(),
+        ),
+        _ => panic!("Expected Rows result"),
+    };
+
+    // Sort keys outside the select list are computed and then dropped
+    let (schema, result) = rows(
+        db.execute("SELECT id FROM items ORDER BY LENGTH(name) DESC")
+            .await?,
+    );
+    assert_eq!(schema, vec!["id".to_string()]);
+    assert_eq!(
+        result,
+        vec![
+            vec![Value::Int(3)],
+            vec![Value::Int(1)],
+            vec![Value::Int(2)]
+        ]
+    );
+
+    let (_, result) = rows(db.execute("SELECT name FROM items ORDER BY qty").await?);
+    assert_eq!(
+        result,
+        vec![
+            vec![Value::Text("banana".into())],
+            vec![Value::Text("pear".into())],
+            vec![Value::Text("fig".into())]
+        ]
+    );
+
+    // Aliases refer to the select-list output
+    let (_, result) = rows(
+        db.execute("SELECT id, UPPER(name) AS label FROM items ORDER BY label LIMIT 2")
+            .await?,
+    );
+    assert_eq!(
+        result,
+        vec![
+            vec![Value::Int(3), Value::Text("BANANA".into())],
+            vec![Value::Int(2), Value::Text("FIG".into())]
+        ]
+    );
+
+    let err = db
+        .execute("SELECT id FROM items ORDER BY missing")
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("unknown column 'missing'"),
+        "{err}"
+    );
+    Ok(())
+}
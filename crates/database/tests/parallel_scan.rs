@@ -0,0 +1,92 @@
+//! Integration tests for sequential scans split across parallel workers.
+
+use anyhow::Result;
+use database::{Database, QueryResult};
+use types::Value;
+
+mod helpers;
+use helpers::select_rows;
+
+/// Rows in the test table; with their padding they fill well over one
+/// morsel of pages.
+const ROWS: i64 = 1200;
+
+async fn open(dir: &std::path::Path, workers: usize) -> Result<Database> {
+    Ok(Database::new(dir, "catalog.json", "test.wal", 10)
+        .await?
+        .with_max_parallel_workers(workers))
+}
+
+async fn create_items(db: &Database) -> Result<()> {
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, pad TEXT)")
+        .await?;
+    let pad = "x".repeat(150);
+    for chunk in (1..=ROWS).collect::<Vec<_>>().chunks(200) {
+        let values: Vec<String> = chunk.iter().map(|id| format!("({id}, '{pad}')")).collect();
+        db.execute(&format!("INSERT INTO items VALUES {}", values.join(", ")))
+            .await?;
+    }
+    Ok(())
+}
+
+fn ids(rows: &[Vec<Value>]) -> Vec<i64> {
+    let mut ids: Vec<i64> = rows
+        .iter()
+        .map(|row| match row[0] {
+            Value::Int(id) => id,
+            ref other => panic!("expected an id, got {other:?}"),
+        })
+        .collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[tokio::test]
+async fn parallel_scan_returns_every_row_once() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path(), 4).await?;
+    create_items(&db).await?;
+
+    let rows = select_rows(&db, "SELECT id FROM items").await?;
+    assert_eq!(ids(&rows), (1..=ROWS).collect::<Vec<_>>());
+
+    let rows = select_rows(&db, "SELECT id FROM items WHERE id > 1000").await?;
+    assert_eq!(ids(&rows), (1001..=ROWS).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[tokio::test]
+async fn parallel_scan_feeds_order_by_and_limit() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path(), 3).await?;
+    create_items(&db).await?;
+
+    let rows = select_rows(&db, "SELECT id FROM items ORDER BY id DESC LIMIT 3").await?;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(ROWS)],
+            vec![Value::Int(ROWS - 1)],
+            vec![Value::Int(ROWS - 2)],
+        ]
+    );
+
+    // Stopping early leaves the workers' remaining morsels unread
+    let rows = select_rows(&db, "SELECT id FROM items LIMIT 5").await?;
+    assert_eq!(rows.len(), 5);
+    Ok(())
+}
+
+#[tokio::test]
+async fn writes_scan_their_table_serially() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path(), 4).await?;
+    create_items(&db).await?;
+
+    let result = db.execute("DELETE FROM items WHERE id > 100").await?;
+    assert!(matches!(result, QueryResult::Count { affected, .. } if affected == ROWS as u64 - 100));
+
+    let rows = select_rows(&db, "SELECT id FROM items").await?;
+    assert_eq!(ids(&rows), (1..=100).collect::<Vec<_>>());
+    Ok(())
+}
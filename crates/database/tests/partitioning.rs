@@ -0,0 +1,203 @@
+//! Integration tests for range and list partitioned tables.
+
+use anyhow::Result;
+use database::Database;
+use types::Value;
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+async fn explain(db: &Database, sql: &str) -> Result<String> {
+    let rows = select_rows(db, &format!("EXPLAIN {sql}")).await?;
+    Ok(rows
+        .into_iter()
+        .flatten()
+        .map(|value| match value {
+            Value::Text(line) => line,
+            other => format!("{other:?}"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn ids(rows: Vec<Vec<Value>>) -> Vec<i64> {
+    let mut ids: Vec<i64> = rows
+        .into_iter()
+        .map(|row| match row[0] {
+            Value::Int(id) => id,
+            ref other => panic!("expected an id, got {other:?}"),
+        })
+        .collect();
+    ids.sort();
+    ids
+}
+
+async fn create_events(db: &Database) -> Result<()> {
+    db.execute(
+        "CREATE TABLE events (id INT PRIMARY KEY, day INT, kind TEXT) \
+         PARTITION BY RANGE (day) ( \
+             PARTITION early VALUES LESS THAN (10), \
+             PARTITION mid VALUES LESS THAN (20), \
+             PARTITION late VALUES LESS THAN (MAXVALUE))",
+    )
+    .await?;
+    db.execute(
+        "INSERT INTO events VALUES (1, 1, 'a'), (2, 9, 'b'), (3, 10, 'c'), \
+         (4, 19, 'd'), (5, 20, 'e'), (6, 500, 'f')",
+    )
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn range_partitions_store_rows_in_their_own_files() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    create_events(&db).await?;
+
+    for partition in ["early", "mid", "late"] {
+        assert!(temp_dir
+            .path()
+            .join(format!("events.{partition}.heap"))
+            .exists());
+    }
+    assert!(!temp_dir.path().join("events.heap").exists());
+
+    assert_eq!(
+        ids(select_rows(&db, "SELECT id FROM events").await?),
+        vec![1, 2, 3, 4, 5, 6]
+    );
+    assert_eq!(
+        ids(select_rows(&db, "SELECT id FROM events WHERE day >= 10 AND day < 20").await?),
+        vec![3, 4]
+    );
+
+    // The primary key spans every partition
+    let err = db
+        .execute("INSERT INTO events VALUES (3, 600, 'dup')")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("duplicate primary key"), "{err}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn predicates_on_the_key_prune_partitions() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    create_events(&db).await?;
+
+    let plan = explain(&db, "SELECT id FROM events WHERE day = 15").await?;
+    assert!(plan.contains("PartitionScan"), "{plan}");
+    assert!(plan.contains("partitions=[1]"), "{plan}");
+
+    let plan = explain(&db, "SELECT id FROM events WHERE day < 10 OR day >= 20").await?;
+    assert!(plan.contains("partitions=[0, 2]"), "{plan}");
+
+    // Predicates on other columns scan everything
+    let plan = explain(&db, "SELECT id FROM events WHERE kind = 'a'").await?;
+    assert!(plan.contains("SeqScan"), "{plan}");
+
+    assert_eq!(
+        ids(select_rows(&db, "SELECT id FROM events WHERE day = 15").await?),
+        Vec::<i64>::new()
+    );
+    assert_eq!(
+        ids(select_rows(&db, "SELECT id FROM events WHERE day < 10 OR day >= 20").await?),
+        vec![1, 2, 5, 6]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn updates_move_rows_between_partitions() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = create_db(temp_dir.path()).await?;
+        create_events(&db).await?;
+        db.execute("CREATE INDEX idx_events_kind ON events (kind)")
+            .await?;
+        db.execute("UPDATE events SET day = 25 WHERE id = 1")
+            .await?;
+        db.execute("DELETE FROM events WHERE id = 4").await?;
+    }
+
+    let db = create_db(temp_dir.path()).await?;
+    assert_eq!(
+        ids(select_rows(&db, "SELECT id FROM events WHERE day >= 20").await?),
+        vec![1, 5, 6]
+    );
+    assert_eq!(
+        ids(select_rows(&db, "SELECT id FROM events WHERE day < 20").await?),
+        vec![2, 3]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT day FROM events WHERE kind = 'a'").await?,
+        vec![vec![Value::Int(25)]]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT kind FROM events WHERE id = 1").await?,
+        vec![vec![Value::Text("a".into())]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_partitions_reject_unlisted_keys() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute(
+        "CREATE TABLE stores (id INT PRIMARY KEY, region TEXT) \
+         PARTITION BY LIST (region) ( \
+             PARTITION east VALUES IN ('ny', 'bos'), \
+             PARTITION west VALUES IN ('sf', 'la'))",
+    )
+    .await?;
+    db.execute("INSERT INTO stores VALUES (1, 'ny'), (2, 'sf'), (3, 'bos')")
+        .await?;
+
+    let err = db
+        .execute("INSERT INTO stores VALUES (4, 'chi')")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("no partition"), "{err}");
+
+    let plan = explain(&db, "SELECT id FROM stores WHERE region = 'bos'").await?;
+    assert!(plan.contains("partitions=[0]"), "{plan}");
+    assert_eq!(
+        ids(select_rows(&db, "SELECT id FROM stores WHERE region = 'bos'").await?),
+        vec![3]
+    );
+
+    db.execute("DROP TABLE stores").await?;
+    assert!(!temp_dir.path().join("stores.east.heap").exists());
+    assert!(!temp_dir.path().join("stores.west.heap").exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalid_partitioning_creates_no_table() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    let err = db
+        .execute(
+            "CREATE TABLE t (id INT, day INT) PARTITION BY RANGE (day) ( \
+             PARTITION a VALUES LESS THAN (20), PARTITION b VALUES LESS THAN (10))",
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("must increase"), "{err}");
+
+    let err = db
+        .execute(
+            "CREATE TABLE t (id INT, day INT) PARTITION BY RANGE (day) ( \
+             PARTITION a VALUES LESS THAN ('x'))",
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("key type"), "{err}");
+
+    db.execute("CREATE TABLE t (id INT, day INT)").await?;
+    Ok(())
+}
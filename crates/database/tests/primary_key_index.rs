@@ -0,0 +1,162 @@
+//! Integration tests for the persistent primary key index.
+
+use anyhow::Result;
+use types::Value;
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+#[tokio::test]
+async fn pk_index_is_created_with_table_and_survives_reopen() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = create_db(temp_dir.path()).await?;
+        db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT)")
+            .await?;
+        assert!(temp_dir.path().join("users.pk_idx").exists());
+
+        // Enough rows to split the index's root page
+        for chunk in (0..300).collect::<Vec<i64>>().chunks(50) {
+            let values: Vec<String> = chunk.iter().map(|id| format!("({id}, 'u{id}')")).collect();
+            db.execute(&format!("INSERT INTO users VALUES {}", values.join(", ")))
+                .await?;
+        }
+        db.execute("DELETE FROM users WHERE id = 42").await?;
+    }
+
+    let db = create_db(temp_dir.path()).await?;
+    for id in [0, 150, 299] {
+        let err = db
+            .execute(&format!("INSERT INTO users VALUES ({id}, 'dup')"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("duplicate primary key"), "{err}");
+    }
+    db.execute("INSERT INTO users VALUES (42, 'again')").await?;
+    assert_eq!(
+        select_rows(&db, "SELECT name FROM users WHERE id = 42").await?,
+        vec![vec![Value::Text("again".into())]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn dropped_table_keys_are_free_for_a_new_table() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY)").await?;
+    db.execute("INSERT INTO t VALUES (1), (2)").await?;
+    db.execute("DROP TABLE t").await?;
+    assert!(!temp_dir.path().join("t.pk_idx").exists());
+
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY)").await?;
+    db.execute("INSERT INTO t VALUES (1)").await?;
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM t").await?,
+        vec![vec![Value::Int(1)]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn lookups_on_the_key_scan_the_pk_index() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    // Statistics would show that scanning this small table costs less
+    let db = create_db(temp_dir.path()).await?.with_auto_analyze(None);
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT)")
+        .await?;
+    let values: Vec<String> = (0..300).map(|id| format!("({id}, 'u{id}')")).collect();
+    db.execute(&format!("INSERT INTO users VALUES {}", values.join(", ")))
+        .await?;
+    db.execute("UPDATE users SET name = 'moved' WHERE id = 7")
+        .await?;
+
+    let cases = [
+        (
+            "SELECT name FROM users WHERE id = 7",
+            vec!["moved".to_string()],
+        ),
+        (
+            "SELECT name FROM users WHERE id = 7.0",
+            vec!["moved".to_string()],
+        ),
+        (
+            "SELECT name FROM users WHERE id >= 297 ORDER BY id",
+            vec!["u297".into(), "u298".into(), "u299".into()],
+        ),
+        (
+            "SELECT name FROM users WHERE id < 2 ORDER BY id",
+            vec!["u0".into(), "u1".into()],
+        ),
+        ("SELECT name FROM users WHERE id = 1000", vec![]),
+    ];
+    for (sql, names) in cases {
+        let plan = select_rows(&db, &format!("EXPLAIN {sql}")).await?;
+        assert!(
+            format!("{plan:?}").contains("IndexScan table_id=1 index=PRIMARY KEY"),
+            "{sql}: {plan:?}"
+        );
+        let expected: Vec<Vec<Value>> = names.into_iter().map(|n| vec![Value::Text(n)]).collect();
+        assert_eq!(select_rows(&db, sql).await?, expected, "{sql}");
+    }
+
+    // Each branch of an OR probes the key
+    let sql = "SELECT id FROM users WHERE id = 250 OR id = 3 ORDER BY id";
+    let plan = select_rows(&db, &format!("EXPLAIN {sql}")).await?;
+    assert!(format!("{plan:?}").contains("IndexUnion"), "{plan:?}");
+    assert_eq!(
+        select_rows(&db, sql).await?,
+        vec![vec![Value::Int(3)], vec![Value::Int(250)]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn updates_and_deletes_on_the_key_scan_the_pk_index() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?.with_auto_analyze(None);
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT)")
+        .await?;
+    let values: Vec<String> = (0..300).map(|id| format!("({id}, 'u{id}')")).collect();
+    db.execute(&format!("INSERT INTO users VALUES {}", values.join(", ")))
+        .await?;
+
+    let cases = [
+        ("UPDATE users SET name = 'moved' WHERE id = 7", 1),
+        ("DELETE FROM users WHERE id = 8", 1),
+        ("DELETE FROM users WHERE id >= 298", 2),
+        ("UPDATE users SET name = 'none' WHERE id = 1000", 0),
+    ];
+    for (sql, affected) in cases {
+        let plan = select_rows(&db, &format!("EXPLAIN {sql}")).await?;
+        assert!(
+            format!("{plan:?}").contains("IndexScan table_id=1 index=PRIMARY KEY"),
+            "{sql}: {plan:?}"
+        );
+        let result = db.execute(sql).await?;
+        let info = result.info().expect("DML reports execution info");
+        assert_eq!(
+            (info.actual_rows, info.rows_scanned),
+            (affected, affected),
+            "{sql}"
+        );
+    }
+
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT id, name FROM users WHERE id >= 6 AND id <= 9 ORDER BY id"
+        )
+        .await?,
+        vec![
+            vec![Value::Int(6), Value::Text("u6".into())],
+            vec![Value::Int(7), Value::Text("moved".into())],
+            vec![Value::Int(9), Value::Text("u9".into())],
+        ]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT COUNT(*) FROM users").await?,
+        vec![vec![Value::Int(297)]]
+    );
+    Ok(())
+}
@@ -0,0 +1,86 @@
+//! Integration tests for `PROFILE`.
+
+use anyhow::Result;
+use database::{Database, QueryResult};
+use types::Value;
+
+#[global_allocator]
+static ALLOCATOR: common::alloc::CountingAllocator = common::alloc::CountingAllocator;
+
+async fn profile(db: &Database, sql: &str) -> Result<String> {
+    match db.execute(sql).await? {
+        QueryResult::Rows { schema, rows, .. } => {
+            assert_eq!(schema, vec!["Profile".to_string()]);
+            match rows[0].values.as_slice() {
+                [Value::Text(report)] => Ok(report.clone()),
+                other => panic!("expected a text report, got {:?}", other),
+            }
+        }
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn profile_reports_every_operator() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, grp INT, tag TEXT)")
+        .await?;
+    let values: Vec<String> = (0..30)
+        .map(|id| format!("({id}, {}, 'tag{id}')", id % 3))
+        .collect();
+    db.execute(&format!("INSERT INTO items VALUES {}", values.join(", ")))
+        .await?;
+
+    let report = profile(
+        &db,
+        "PROFILE SELECT tag FROM items WHERE grp = 1 ORDER BY id DESC LIMIT 4",
+    )
+    .await?;
+    let lines: Vec<&str> = report.lines().collect();
+    assert!(lines[0].starts_with("PROFILE: total="), "{report}");
+    assert!(lines[0].ends_with("rows=4"), "{report}");
+    // Two lines per operator, each input indented below its parent
+    let operators: Vec<&str> = lines[1..].iter().step_by(2).copied().collect();
+    let expected = [
+        "Limit",
+        "  Project",
+        "    Sort",
+        "      Project",
+        "        Filter",
+        "          SeqScan",
+    ];
+    assert_eq!(operators.len(), expected.len(), "{report}");
+    for (line, prefix) in operators.iter().zip(expected) {
+        assert!(line.starts_with(prefix), "{report}");
+    }
+    assert!(operators[0].contains("100.0%"), "{report}");
+    assert!(report.contains("rows=30 next_calls=31"), "{report}");
+    assert!(report.contains("rows=10 next_calls=11"), "{report}");
+    assert!(report.contains("allocs="), "{report}");
+    assert!(report.contains("storage_wait="), "{report}");
+
+    // Writes are profiled too, and run
+    let report = profile(&db, "PROFILE DELETE FROM items WHERE grp = 2").await?;
+    assert!(
+        report.lines().nth(1).unwrap().starts_with("Delete"),
+        "{report}"
+    );
+    let QueryResult::Rows { rows, .. } = db.execute("SELECT id FROM items").await? else {
+        panic!("expected rows");
+    };
+    assert_eq!(rows.len(), 20);
+
+    // and count towards the table's activity
+    let QueryResult::Rows { rows, .. } = db
+        .execute(
+            "SELECT inserts, deletes FROM information_schema.table_activity \
+             WHERE table_name = 'items'",
+        )
+        .await?
+    else {
+        panic!("expected rows");
+    };
+    assert_eq!(rows[0].values, vec![Value::Int(30), Value::Int(10)]);
+    Ok(())
+}
@@ -1,6 +1,6 @@
 //! Integration tests for Raft consensus mode.
 
-use database::{activity_channel, Database, QueryResult, RaftConfig};
+use database::{activity_channel, ActivityReceiver, Database, QueryResult, RaftConfig};
 use std::time::Duration;
 use tempfile::TempDir;
 use tokio::time::timeout;
@@ -21,7 +21,7 @@ async fn database_without_raft() {
     db.execute("CREATE TABLE test (id INT)").await.unwrap();
 
     let result = db.execute("INSERT INTO test VALUES (1)").await.unwrap();
-    if let QueryResult::Count { affected } = result {
+    if let QueryResult::Count { affected, .. } = result {
         assert_eq!(affected, 1);
     } else {
         panic!("Expected count result");
@@ -47,7 +47,7 @@ async fn database_with_raft_enabled() {
     db.execute("CREATE TABLE test (id INT)").await.unwrap();
 
     let result = db.execute("INSERT INTO test VALUES (42)").await.unwrap();
-    if let QueryResult::Count { affected } = result {
+    if let QueryResult::Count { affected, .. } = result {
         assert_eq!(affected, 1);
     } else {
         panic!("Expected count result");
@@ -123,7 +123,7 @@ async fn raft_multiple_inserts() {
 
     // Verify all rows are present
     let result = db.execute("SELECT * FROM users").await.unwrap();
-    if let QueryResult::Rows { rows, schema } = result {
+    if let QueryResult::Rows { rows, schema, .. } = result {
         assert_eq!(schema, vec!["id", "name", "active"]);
         assert_eq!(rows.len(), 3);
     } else {
@@ -265,7 +265,7 @@ async fn raft_update_rows() {
         .execute("UPDATE employees SET salary = 70000")
         .await
         .unwrap();
-    if let QueryResult::Count { affected } = result {
+    if let QueryResult::Count { affected, .. } = result {
         assert_eq!(affected, 3);
     } else {
         panic!("Expected count result");
@@ -313,7 +313,7 @@ async fn raft_update_with_filter() {
         .execute("UPDATE items SET status = 'processed' WHERE status = 'pending'")
         .await
         .unwrap();
-    if let QueryResult::Count { affected } = result {
+    if let QueryResult::Count { affected, .. } = result {
         assert_eq!(affected, 2);
     } else {
         panic!("Expected count result");
@@ -374,7 +374,7 @@ async fn raft_delete_rows() {
         .execute("DELETE FROM logs WHERE level = 'info'")
         .await
         .unwrap();
-    if let QueryResult::Count { affected } = result {
+    if let QueryResult::Count { affected, .. } = result {
         assert_eq!(affected, 2);
     } else {
         panic!("Expected count result");
@@ -408,7 +408,7 @@ async fn raft_delete_all_rows() {
 
     // Delete all rows (no WHERE clause)
     let result = db.execute("DELETE FROM temp").await.unwrap();
-    if let QueryResult::Count { affected } = result {
+    if let QueryResult::Count { affected, .. } = result {
         assert_eq!(affected, 3);
     } else {
         panic!("Expected count result");
@@ -454,7 +454,7 @@ async fn raft_full_dml_workflow() {
         .execute("UPDATE orders SET total = 50 WHERE customer = 'alice'")
         .await
         .unwrap();
-    if let QueryResult::Count { affected } = result {
+    if let QueryResult::Count { affected, .. } = result {
         assert_eq!(affected, 2);
     } else {
         panic!("Expected count result");
@@ -465,7 +465,7 @@ async fn raft_full_dml_workflow() {
         .execute("DELETE FROM orders WHERE customer = 'bob'")
         .await
         .unwrap();
-    if let QueryResult::Count { affected } = result {
+    if let QueryResult::Count { affected, .. } = result {
         assert_eq!(affected, 1);
     } else {
         panic!("Expected count result");
@@ -545,7 +545,7 @@ async fn raft_first_node_starts_as_cluster() {
         .execute("INSERT INTO test VALUES (1, 'hello')")
         .await
         .unwrap();
-    if let QueryResult::Count { affected } = result {
+    if let QueryResult::Count { affected, .. } = result {
         assert_eq!(affected, 1);
     } else {
         panic!("Expected count result");
@@ -553,7 +553,7 @@ async fn raft_first_node_starts_as_cluster() {
 
     // Verify data
     let result = db.execute("SELECT * FROM test").await.unwrap();
-    if let QueryResult::Rows { rows, schema } = result {
+    if let QueryResult::Rows { rows, schema, .. } = result {
         assert_eq!(schema, vec!["id", "name"]);
         assert_eq!(rows.len(), 1);
     } else {
@@ -600,7 +600,7 @@ async fn raft_with_persistent_storage() {
         .execute("INSERT INTO test VALUES (1, 'alice')")
         .await
         .unwrap();
-    if let QueryResult::Count { affected } = result {
+    if let QueryResult::Count { affected, .. } = result {
         assert_eq!(affected, 1);
     } else {
         panic!("Expected count result");
@@ -680,7 +680,7 @@ async fn raft_data_survives_restart() {
 
         // Table should exist and have data
         let result = db.execute("SELECT * FROM users").await.unwrap();
-        if let QueryResult::Rows { rows, schema } = result {
+        if let QueryResult::Rows { rows, schema, .. } = result {
             assert_eq!(schema, vec!["id", "name"]);
             assert_eq!(rows.len(), 2, "Expected 2 rows to survive restart");
         } else {
@@ -693,8 +693,56 @@ async fn raft_data_survives_restart() {
 // Activity Events Tests
 // =============================================================================
 
+/// Receive the catalog epoch a DDL statement replicated, and return it.
+async fn expect_catalog_epoch(rx: &mut ActivityReceiver) -> u64 {
+    let event = timeout(Duration::from_secs(1), rx.recv())
+        .await
+        .expect("Should receive event")
+        .expect("Channel not closed");
+    event
+        .description
+        .strip_prefix("CATALOG EPOCH ")
+        .unwrap_or_else(|| panic!("Got: {}", event.description))
+        .parse()
+        .unwrap()
+}
+
+/// Test that each DDL statement replicates the catalog epoch it advanced to,
+/// once, so followers invalidate their cached plans.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn activity_events_ddl_replicates_catalog_epoch() {
+    let tmp = TempDir::new().unwrap();
+    let (tx, mut rx) = activity_channel();
+
+    let raft_config = RaftConfig::single_node(1).with_activity_sender(tx);
+
+    let db =
+        Database::with_raft_config(tmp.path(), "catalog.json", "wal.log", 32, Some(raft_config))
+            .await
+            .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    while timeout(Duration::from_millis(50), rx.recv()).await.is_ok() {}
+
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY, name TEXT)")
+        .await
+        .unwrap();
+    let created = expect_catalog_epoch(&mut rx).await;
+    assert_eq!(created, db.catalog_epoch());
+
+    db.execute("ALTER TABLE t ADD COLUMN age INT")
+        .await
+        .unwrap();
+    assert_eq!(expect_catalog_epoch(&mut rx).await, created + 1);
+    assert_eq!(db.catalog_epoch(), created + 1);
+    assert!(timeout(Duration::from_millis(100), rx.recv())
+        .await
+        .is_err());
+}
+
 /// Test that activity events are received for INSERT operations.
-/// Note: DDL (CREATE TABLE) doesn't go through Raft, only DML does.
+/// Note: DDL (CREATE TABLE) only replicates the new catalog epoch; rows go
+/// through Raft as DML.
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn activity_events_insert() {
     let tmp = TempDir::new().unwrap();
@@ -713,10 +761,11 @@ async fn activity_events_insert() {
     // Drain initial events (membership, blanks, etc.)
     while timeout(Duration::from_millis(50), rx.recv()).await.is_ok() {}
 
-    // Create table (DDL doesn't go through Raft)
+    // DDL replicates only the new catalog epoch
     db.execute("CREATE TABLE test (id INT, name TEXT)")
         .await
         .unwrap();
+    expect_catalog_epoch(&mut rx).await;
 
     // Insert a row (DML goes through Raft)
     db.execute("INSERT INTO test VALUES (1, 'alice')")
@@ -753,10 +802,11 @@ async fn activity_events_update() {
     tokio::time::sleep(Duration::from_millis(200)).await;
     while timeout(Duration::from_millis(50), rx.recv()).await.is_ok() {}
 
-    // DDL doesn't go through Raft
+    // DDL replicates only the new catalog epoch
     db.execute("CREATE TABLE items (id INT, price INT)")
         .await
         .unwrap();
+    expect_catalog_epoch(&mut rx).await;
 
     db.execute("INSERT INTO items VALUES (1, 100)")
         .await
@@ -773,7 +823,7 @@ async fn activity_events_update() {
         .execute("UPDATE items SET price = 50 WHERE id = 1")
         .await
         .unwrap();
-    if let QueryResult::Count { affected } = result {
+    if let QueryResult::Count { affected, .. } = result {
         assert_eq!(affected, 1);
     }
 
@@ -805,8 +855,9 @@ async fn activity_events_delete() {
     tokio::time::sleep(Duration::from_millis(200)).await;
     while timeout(Duration::from_millis(50), rx.recv()).await.is_ok() {}
 
-    // DDL doesn't go through Raft
+    // DDL replicates only the new catalog epoch
     db.execute("CREATE TABLE items (id INT)").await.unwrap();
+    expect_catalog_epoch(&mut rx).await;
 
     db.execute("INSERT INTO items VALUES (1)").await.unwrap();
     db.execute("INSERT INTO items VALUES (2)").await.unwrap();
@@ -816,7 +867,7 @@ async fn activity_events_delete() {
 
     // Delete a row
     let result = db.execute("DELETE FROM items WHERE id = 1").await.unwrap();
-    if let QueryResult::Count { affected } = result {
+    if let QueryResult::Count { affected, .. } = result {
         assert_eq!(affected, 1);
     }
 
@@ -848,8 +899,9 @@ async fn activity_events_increasing_indices() {
     tokio::time::sleep(Duration::from_millis(200)).await;
     while timeout(Duration::from_millis(50), rx.recv()).await.is_ok() {}
 
-    // DDL doesn't go through Raft
+    // DDL replicates only the new catalog epoch
     db.execute("CREATE TABLE seq (id INT)").await.unwrap();
+    expect_catalog_epoch(&mut rx).await;
 
     // Insert multiple rows and verify indices increase
     let mut last_index = 0u64;
@@ -889,10 +941,11 @@ async fn activity_events_persistent_storage() {
     tokio::time::sleep(Duration::from_millis(200)).await;
     while timeout(Duration::from_millis(50), rx.recv()).await.is_ok() {}
 
-    // DDL doesn't go through Raft
+    // DDL replicates only the new catalog epoch
     db.execute("CREATE TABLE persist_test (id INT)")
         .await
         .unwrap();
+    expect_catalog_epoch(&mut rx).await;
 
     // DML goes through Raft
     db.execute("INSERT INTO persist_test VALUES (42)")
@@ -953,10 +1006,11 @@ async fn activity_events_full_workflow() {
     tokio::time::sleep(Duration::from_millis(200)).await;
     while timeout(Duration::from_millis(50), rx.recv()).await.is_ok() {}
 
-    // DDL doesn't go through Raft
+    // DDL replicates only the new catalog epoch
     db.execute("CREATE TABLE workflow (id INT, val INT)")
         .await
         .unwrap();
+    expect_catalog_epoch(&mut rx).await;
 
     // INSERT
     db.execute("INSERT INTO workflow VALUES (1, 100)")
@@ -0,0 +1,173 @@
+//! Integration tests for RANDOM() and SET random_seed.
+
+use anyhow::Result;
+use database::{Database, QueryResult, RaftConfig, LOCAL_PRINCIPAL};
+use types::Value;
+
+async fn random_values(db: &Database, table: &str) -> Result<Vec<f64>> {
+    match db
+        .execute(&format!("SELECT r FROM {table} ORDER BY id"))
+        .await?
+    {
+        QueryResult::Rows { rows, .. } => Ok(rows
+            .iter()
+            .map(|row| match row.values[0] {
+                Value::Float(f) => f,
+                ref other => panic!("expected a float, got {other:?}"),
+            })
+            .collect()),
+        other => panic!("expected rows, got {other:?}"),
+    }
+}
+
+/// Fill a new table with random values the way a data generation script
+/// would, and read them back.
+async fn generate(db: &Database, table: &str) -> Result<Vec<f64>> {
+    db.execute(&format!(
+        "CREATE TABLE {table} (id INT PRIMARY KEY, r FLOAT)"
+    ))
+    .await?;
+    db.execute(&format!(
+        "INSERT INTO {table} VALUES (1, RANDOM()), (2, RANDOM())"
+    ))
+    .await?;
+    db.execute(&format!("INSERT INTO {table} VALUES (3, RANDOM())"))
+        .await?;
+    db.execute(&format!("UPDATE {table} SET r = RANDOM() WHERE id = 2"))
+        .await?;
+    random_values(db, table).await
+}
+
+#[tokio::test]
+async fn a_fixed_seed_repeats_the_generated_data() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+
+    let result = db.execute("SET random_seed = 42").await?;
+    assert!(matches!(result, QueryResult::Empty));
+    let first = generate(&db, "samples").await?;
+    assert!(first.iter().all(|r| (0.0..1.0).contains(r)));
+    assert_ne!(first[0], first[1]);
+
+    // The sequence continues across statements until the seed is set again
+    let continued = generate(&db, "continued").await?;
+    assert_ne!(first, continued);
+
+    db.execute("SET random_seed = 42").await?;
+    assert_eq!(generate(&db, "repeated").await?, first);
+
+    // Another session with the same seed generates the same data
+    let other_dir = tempfile::tempdir()?;
+    let other = Database::new(other_dir.path(), "catalog.json", "test.wal", 10).await?;
+    other.set_random_seed(LOCAL_PRINCIPAL, Some(42));
+    assert_eq!(generate(&other, "samples").await?, first);
+    Ok(())
+}
+
+#[tokio::test]
+async fn default_seed_makes_the_values_unpredictable_again() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+
+    db.execute("SET random_seed = 7").await?;
+    let seeded = generate(&db, "seeded").await?;
+
+    db.execute("SET random_seed = DEFAULT").await?;
+    let unseeded = generate(&db, "unseeded").await?;
+    assert!(unseeded.iter().all(|r| (0.0..1.0).contains(r)));
+    assert_ne!(unseeded, seeded);
+    Ok(())
+}
+
+/// Generate rows with `INSERT ... SELECT` over a series under `seed`.
+async fn generate_series_rows(db: &Database, table: &str, seed: i64) -> Result<Vec<f64>> {
+    db.execute(&format!("SET random_seed = {seed}")).await?;
+    db.execute(&format!(
+        "CREATE TABLE {table} (id INT PRIMARY KEY, r FLOAT)"
+    ))
+    .await?;
+    let result = db
+        .execute(&format!(
+            "INSERT INTO {table} SELECT generate_series, RANDOM() FROM generate_series(1, 20)"
+        ))
+        .await?;
+    assert!(matches!(result, QueryResult::Count { affected: 20, .. }));
+    random_values(db, table).await
+}
+
+#[tokio::test]
+async fn insert_select_generates_reproducible_rows() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+
+    let first = generate_series_rows(&db, "samples", 42).await?;
+    assert_eq!(first.len(), 20);
+    assert!(first.iter().all(|r| (0.0..1.0).contains(r)));
+    assert_eq!(generate_series_rows(&db, "repeated", 42).await?, first);
+    assert_ne!(generate_series_rows(&db, "other", 7).await?, first);
+
+    // Rows can come from another table, into listed columns
+    db.execute("INSERT INTO repeated (id) SELECT id + 100 FROM samples WHERE r < 0.5")
+        .await?;
+    let copied = first.iter().filter(|r| **r < 0.5).count() as i64;
+    match db.execute("SELECT COUNT(*) FROM repeated").await? {
+        QueryResult::Rows { rows, .. } => {
+            assert_eq!(rows[0].values[0], Value::Int(20 + copied))
+        }
+        other => panic!("expected rows, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn insert_select_replicates_the_generated_rows() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::with_raft_config(
+        temp_dir.path(),
+        "catalog.json",
+        "test.wal",
+        10,
+        Some(RaftConfig::single_node(1)),
+    )
+    .await?;
+    let replicated = generate_series_rows(&db, "samples", 42).await?;
+
+    let local_dir = tempfile::tempdir()?;
+    let local = Database::new(local_dir.path(), "catalog.json", "test.wal", 10).await?;
+    assert_eq!(
+        generate_series_rows(&local, "samples", 42).await?,
+        replicated
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn insert_select_reads_and_inserts_without_interleaving() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE counters (id INT PRIMARY KEY)")
+        .await?;
+    db.execute("INSERT INTO counters VALUES (0)").await?;
+    let db = std::sync::Arc::new(db);
+
+    // Each statement takes the next id; a write landing between its read
+    // and its insert would make two of them take the same one
+    let mut statements = Vec::new();
+    for _ in 0..32 {
+        let db = db.clone();
+        statements.push(tokio::spawn(async move {
+            db.execute("INSERT INTO counters SELECT MAX(id) + 1 FROM counters")
+                .await
+        }));
+    }
+    for statement in statements {
+        statement.await??;
+    }
+    match db.execute("SELECT COUNT(*), MAX(id) FROM counters").await? {
+        QueryResult::Rows { rows, .. } => {
+            assert_eq!(rows[0].values, vec![Value::Int(33), Value::Int(32)])
+        }
+        other => panic!("expected rows, got {other:?}"),
+    }
+    Ok(())
+}
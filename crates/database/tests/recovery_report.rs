@@ -0,0 +1,143 @@
+//! Integration tests for the self-check run when a database is opened.
+
+use anyhow::Result;
+use database::recovery::{Component, Outcome};
+use database::RecoveryReport;
+use std::path::Path;
+use types::Value;
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+/// Create a table with an index and some rows, then simulate a crash so the
+/// next open does not require the files to match a clean manifest.
+async fn populate(dir: &Path) -> Result<()> {
+    let db = create_db(dir).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT)")
+        .await?;
+    db.execute("CREATE INDEX idx_users_name ON users (name)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (1, 'alice'), (2, 'bob')")
+        .await?;
+    std::mem::forget(db);
+    Ok(())
+}
+
+fn outcome<'a>(report: &'a RecoveryReport, component: Component, target: &str) -> &'a Outcome {
+    &report
+        .checks
+        .iter()
+        .find(|check| check.component == component && check.target == target)
+        .unwrap_or_else(|| panic!("no check of {component} {target} in\n{report}"))
+        .outcome
+}
+
+#[tokio::test]
+async fn a_healthy_database_reports_every_check_ok() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    populate(temp_dir.path()).await?;
+
+    let db = create_db(temp_dir.path()).await?;
+    let report = db.recovery_report();
+    assert!(report.is_healthy(), "{report}");
+    assert_eq!(report.repaired().count(), 0, "{report}");
+    for (component, target) in [
+        (Component::Catalog, "catalog.json"),
+        (Component::Wal, "test.wal"),
+        (Component::Table, "users"),
+        (Component::Index, "users primary key"),
+        (Component::Index, "idx_users_name"),
+    ] {
+        assert_eq!(outcome(report, component, target), &Outcome::Ok, "{report}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn a_torn_wal_record_is_cut_off() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    populate(temp_dir.path()).await?;
+    let wal = temp_dir.path().join("test.wal");
+    let len = std::fs::metadata(&wal)?.len();
+    // A length prefix promising more bytes than were written
+    let mut bytes = std::fs::read(&wal)?;
+    bytes.extend_from_slice(&[100, 0, 0, 0, 1, 2]);
+    std::fs::write(&wal, bytes)?;
+
+    let db = create_db(temp_dir.path()).await?;
+    let report = db.recovery_report();
+    assert!(report.is_healthy(), "{report}");
+    assert!(
+        matches!(outcome(report, Component::Wal, "test.wal"), Outcome::Repaired(what) if what.contains("6 bytes")),
+        "{report}"
+    );
+    assert_eq!(std::fs::metadata(&wal)?.len(), len);
+
+    // The log takes new records after the cut
+    db.execute("INSERT INTO users VALUES (3, 'carol')").await?;
+    assert_eq!(select_rows(&db, "SELECT id FROM users").await?.len(), 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn an_unreadable_index_is_rebuilt_from_its_table() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    populate(temp_dir.path()).await?;
+    let index = std::fs::read_dir(temp_dir.path())?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?
+        .into_iter()
+        .find(|path| path.extension().is_some_and(|ext| ext == "idx"))
+        .expect("index file");
+    let len = std::fs::metadata(&index)?.len() as usize;
+    std::fs::write(&index, vec![0xFF; len])?;
+
+    let db = create_db(temp_dir.path()).await?;
+    let report = db.recovery_report();
+    assert!(report.is_healthy(), "{report}");
+    assert!(
+        matches!(
+            outcome(report, Component::Index, "idx_users_name"),
+            Outcome::Repaired(_)
+        ),
+        "{report}"
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM users WHERE name = 'bob'").await?,
+        vec![vec![Value::Int(2)]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn a_damaged_table_is_reported_and_the_rest_still_opens() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    populate(temp_dir.path()).await?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE orders (id INT)").await?;
+    db.execute("INSERT INTO orders VALUES (7)").await?;
+    std::mem::forget(db);
+
+    let heap = temp_dir.path().join("users.heap");
+    let mut bytes = std::fs::read(&heap)?;
+    let at = bytes
+        .windows(5)
+        .position(|w| w == b"alice")
+        .expect("row bytes");
+    bytes[at] ^= 1;
+    std::fs::write(&heap, bytes)?;
+
+    let db = create_db(temp_dir.path()).await?;
+    let report = db.recovery_report();
+    assert!(!report.is_healthy(), "{report}");
+    assert!(
+        matches!(outcome(report, Component::Table, "users"), Outcome::Damaged(what) if what.contains("page 0")),
+        "{report}"
+    );
+    assert_eq!(outcome(report, Component::Table, "orders"), &Outcome::Ok);
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM orders").await?,
+        vec![vec![Value::Int(7)]]
+    );
+    Ok(())
+}
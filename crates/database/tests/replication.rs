@@ -0,0 +1,223 @@
+//! Integration tests for logical replication between databases.
+
+use anyhow::Result;
+use database::{ChangeBatch, Database, Publication, QueryResult, Subscription};
+use types::Value;
+
+async fn create_db(dir: &std::path::Path) -> Result<Database> {
+    let db = Database::new(dir, "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, color TEXT)")
+        .await?;
+    Ok(db)
+}
+
+async fn items(db: &Database) -> Result<Vec<Vec<Value>>> {
+    let QueryResult::Rows { rows, .. } = db.execute("SELECT * FROM items ORDER BY id").await?
+    else {
+        panic!("expected rows");
+    };
+    Ok(rows.into_iter().map(|row| row.values).collect())
+}
+
+fn item(id: i64, color: &str) -> Vec<Value> {
+    vec![Value::Int(id), Value::Text(color.into())]
+}
+
+/// Apply the changes published since the subscription's position.
+async fn sync(
+    publisher: &Database,
+    publication: &Publication,
+    subscriber: &Database,
+    subscription: &mut Subscription,
+) -> Result<u64> {
+    let batch = publisher
+        .read_changes(publication, subscription.position())
+        .await?;
+    subscriber.apply_changes(subscription, batch).await
+}
+
+#[tokio::test]
+async fn subscriber_applies_published_changes() -> Result<()> {
+    let publisher_dir = tempfile::tempdir()?;
+    let subscriber_dir = tempfile::tempdir()?;
+    let publisher = create_db(publisher_dir.path()).await?;
+    let subscriber = create_db(subscriber_dir.path()).await?;
+    publisher
+        .execute("CREATE TABLE notes (id INT PRIMARY KEY, body TEXT)")
+        .await?;
+    let publication = Publication::new("shop", ["items"]);
+    let mut subscription = Subscription::open(subscriber_dir.path(), "shop")?;
+
+    publisher
+        .execute("INSERT INTO items VALUES (1, 'red'), (2, 'blue'), (3, 'green')")
+        .await?;
+    publisher
+        .execute("INSERT INTO notes VALUES (1, 'not published')")
+        .await?;
+    publisher
+        .execute("UPDATE items SET color = 'teal' WHERE id = 2")
+        .await?;
+    publisher.execute("DELETE FROM items WHERE id = 3").await?;
+
+    let batch = publisher
+        .read_changes(&publication, subscription.position())
+        .await?;
+    assert_eq!(batch.changes.len(), 5);
+    assert!(batch
+        .changes
+        .iter()
+        .all(|(_, change)| change.table() == "items"));
+    assert_eq!(
+        subscriber
+            .apply_changes(&mut subscription, batch.clone())
+            .await?,
+        5
+    );
+    assert_eq!(subscription.position(), batch.end);
+    assert_eq!(
+        items(&subscriber).await?,
+        vec![item(1, "red"), item(2, "teal")]
+    );
+
+    // A batch applies only at the position it was read from
+    let err = subscriber
+        .apply_changes(&mut subscription, batch)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("batch starts at"), "{err}");
+
+    // The subscription resumes from where it was saved
+    publisher
+        .execute("UPDATE items SET color = 'pink' WHERE id = 1")
+        .await?;
+    publisher
+        .execute("INSERT INTO items VALUES (4, 'gold')")
+        .await?;
+    let mut subscription = Subscription::open(subscriber_dir.path(), "shop")?;
+    assert_eq!(
+        sync(&publisher, &publication, &subscriber, &mut subscription).await?,
+        2
+    );
+    assert_eq!(
+        items(&subscriber).await?,
+        vec![item(1, "pink"), item(2, "teal"), item(4, "gold")]
+    );
+    assert_eq!(
+        sync(&publisher, &publication, &subscriber, &mut subscription).await?,
+        0
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn failed_changes_are_retried_from_the_subscription() -> Result<()> {
+    let publisher_dir = tempfile::tempdir()?;
+    let subscriber_dir = tempfile::tempdir()?;
+    let publisher = create_db(publisher_dir.path()).await?;
+    let subscriber = create_db(subscriber_dir.path()).await?;
+    let publication = Publication::new("shop", ["items"]);
+    let mut subscription = Subscription::open(subscriber_dir.path(), "shop")?;
+
+    subscriber
+        .execute("INSERT INTO items VALUES (2, 'stale')")
+        .await?;
+    publisher
+        .execute("INSERT INTO items VALUES (1, 'red')")
+        .await?;
+    publisher
+        .execute("INSERT INTO items VALUES (2, 'blue')")
+        .await?;
+
+    // The second insert collides with the subscriber's own row
+    assert!(
+        sync(&publisher, &publication, &subscriber, &mut subscription)
+            .await
+            .is_err()
+    );
+    assert_eq!(
+        items(&subscriber).await?,
+        vec![item(1, "red"), item(2, "stale")]
+    );
+
+    // Retried changes of an interrupted batch replace the rows they insert
+    let mut subscription = Subscription::open(subscriber_dir.path(), "shop")?;
+    assert_eq!(
+        sync(&publisher, &publication, &subscriber, &mut subscription).await?,
+        1
+    );
+    assert_eq!(
+        items(&subscriber).await?,
+        vec![item(1, "red"), item(2, "blue")]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn publications_need_logged_tables_with_primary_keys() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE events (id INT, body TEXT)")
+        .await?;
+
+    let err = db
+        .read_changes(&Publication::new("log", ["events"]), 0)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("no primary key"), "{err}");
+
+    let err = db
+        .read_changes(&Publication::new("log", ["missing"]), 0)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("missing"), "{err}");
+
+    let err = db
+        .read_changes(&Publication::new("shop", ["items"]), 1 << 20)
+        .await
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("past the end"), "{err:#}");
+
+    assert!(Subscription::open(temp_dir.path(), "../shop").is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn subscription_starts_from_a_table_snapshot() -> Result<()> {
+    let publisher_dir = tempfile::tempdir()?;
+    let subscriber_dir = tempfile::tempdir()?;
+    let publisher = create_db(publisher_dir.path()).await?;
+    let subscriber = create_db(subscriber_dir.path()).await?;
+    let publication = Publication::new("shop", ["items"]);
+
+    publisher
+        .execute("INSERT INTO items VALUES (1, 'red'), (2, 'blue')")
+        .await?;
+    publisher
+        .execute("INSERT INTO items VALUES (3, 'green')")
+        .await?;
+    let snapshot = publisher.snapshot_table("items").await?;
+    publisher
+        .execute("UPDATE items SET color = 'teal' WHERE id = 2")
+        .await?;
+    publisher.execute("DELETE FROM items WHERE id = 1").await?;
+
+    let mut subscription = Subscription::open(subscriber_dir.path(), "shop")?;
+    let batch = ChangeBatch::from_snapshot(&publication, subscription.position(), snapshot)?;
+    assert_eq!(subscriber.apply_changes(&mut subscription, batch).await?, 3);
+    assert_eq!(
+        items(&subscriber).await?,
+        vec![item(1, "red"), item(2, "blue"), item(3, "green")]
+    );
+
+    // Later changes apply to the rows copied from the snapshot
+    assert_eq!(
+        sync(&publisher, &publication, &subscriber, &mut subscription).await?,
+        2
+    );
+    assert_eq!(items(&subscriber).await?, items(&publisher).await?);
+
+    let snapshot = publisher.snapshot_table("items").await?;
+    let other = Publication::new("other", ["notes"]);
+    assert!(ChangeBatch::from_snapshot(&other, 0, snapshot).is_err());
+    Ok(())
+}
@@ -0,0 +1,276 @@
+//! Integration tests for per-session resource limits.
+
+use anyhow::Result;
+use common::DbError;
+use database::sessions::SessionRegistry;
+use database::{Database, ResourceLimits};
+use types::Value;
+
+mod helpers;
+use helpers::select_rows;
+
+async fn create_db(dir: &std::path::Path, limits: ResourceLimits) -> Result<Database> {
+    let db = Database::new(dir, "catalog.json", "test.wal", 10).await?;
+    let db = db.with_resource_limits(limits);
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, label TEXT)")
+        .await?;
+    db.execute("INSERT INTO items VALUES (1, 'a'), (2, 'b'), (3, 'c'), (4, 'd'), (5, 'e')")
+        .await?;
+    Ok(db)
+}
+
+fn assert_exhausted(err: anyhow::Error, resource: &str) {
+    match err.downcast_ref::<DbError>() {
+        Some(DbError::ResourceExhausted(msg)) => assert!(msg.contains(resource), "{msg}"),
+        _ => panic!("expected ResourceExhausted, got {err}"),
+    }
+}
+
+#[tokio::test]
+async fn rows_scanned_limit_stops_large_scans() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let limits = ResourceLimits::builder().max_rows_scanned(3).build();
+    let db = create_db(temp_dir.path(), limits).await?;
+
+    let err = db.execute("SELECT * FROM items").await.unwrap_err();
+    assert_exhausted(err, "rows scanned");
+
+    // A LIMIT stops pulling rows before the cap is hit
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM items LIMIT 2")
+            .await?
+            .len(),
+        2
+    );
+
+    // UPDATE computes every new row before writing, so nothing changes
+    let err = db
+        .execute("UPDATE items SET label = 'z'")
+        .await
+        .unwrap_err();
+    assert_exhausted(err, "rows scanned");
+    let db = db.with_resource_limits(ResourceLimits::default());
+    let labels = select_rows(&db, "SELECT label FROM items WHERE label = 'z'").await?;
+    assert!(labels.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn memory_limit_applies_to_materializing_operators() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let limits = ResourceLimits::builder().max_memory_bytes(64).build();
+    let db = create_db(temp_dir.path(), limits).await?;
+
+    // Streaming scans buffer nothing
+    assert_eq!(select_rows(&db, "SELECT id FROM items").await?.len(), 5);
+
+    let err = db
+        .execute("SELECT id FROM items ORDER BY id")
+        .await
+        .unwrap_err();
+    assert_exhausted(err, "memory bytes");
+    Ok(())
+}
+
+#[tokio::test]
+async fn work_memory_spills_sorts_and_joins_instead_of_failing() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let limits = ResourceLimits::builder().max_memory_bytes(16_000).build();
+    let db = create_db(temp_dir.path(), limits).await?;
+    let values: Vec<String> = (6..=300).map(|id| format!("({id}, 'row {id}')")).collect();
+    db.execute(&format!("INSERT INTO items VALUES {}", values.join(", ")))
+        .await?;
+
+    let sort = "SELECT id FROM items ORDER BY id DESC";
+    let err = db.execute(sort).await.unwrap_err();
+    assert_exhausted(err, "memory bytes");
+
+    let db = db.with_resource_limits(
+        ResourceLimits::builder()
+            .max_memory_bytes(16_000)
+            .work_memory_bytes(4_000)
+            .build(),
+    );
+    let ids: Vec<Vec<Value>> = (1..=300).rev().map(|id| vec![Value::Int(id)]).collect();
+    assert_eq!(select_rows(&db, sort).await?, ids);
+
+    let joined = select_rows(
+        &db,
+        "SELECT a.id, b.label FROM items a JOIN items b ON a.id = b.id WHERE a.id > 298",
+    )
+    .await?;
+    assert_eq!(
+        joined,
+        vec![
+            vec![Value::Int(299), Value::Text("row 299".into())],
+            vec![Value::Int(300), Value::Text("row 300".into())],
+        ]
+    );
+
+    // Spill files are gone once the statements finish
+    let spilled = std::fs::read_dir(temp_dir.path())?
+        .filter(|entry| {
+            entry
+                .as_ref()
+                .is_ok_and(|e| e.file_name().to_string_lossy().starts_with("spill-"))
+        })
+        .count();
+    assert_eq!(spilled, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn temp_disk_limit_fails_a_statement_that_spills_too_much() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let limits = ResourceLimits::builder()
+        .work_memory_bytes(4_000)
+        .max_temp_bytes(1_000)
+        .build();
+    let db = create_db(temp_dir.path(), limits).await?;
+    let values: Vec<String> = (6..=300).map(|id| format!("({id}, 'row {id}')")).collect();
+    db.execute(&format!("INSERT INTO items VALUES {}", values.join(", ")))
+        .await?;
+
+    let sort = "SELECT id, label FROM items ORDER BY label";
+    let err = db.execute(sort).await.unwrap_err();
+    assert_exhausted(err, "temp bytes");
+
+    let db = db.with_resource_limits(
+        ResourceLimits::builder()
+            .work_memory_bytes(4_000)
+            .max_temp_bytes(1_000_000)
+            .build(),
+    );
+    assert_eq!(select_rows(&db, sort).await?.len(), 300);
+    Ok(())
+}
+
+#[tokio::test]
+async fn temp_disk_limit_covers_joins_aggregates_and_distinct() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let limits = ResourceLimits::builder()
+        .work_memory_bytes(4_000)
+        .max_temp_bytes(1_000)
+        .build();
+    let db = create_db(temp_dir.path(), limits).await?;
+    let values: Vec<String> = (6..=600)
+        .map(|id| format!("({id}, 'label {}')", id % 300))
+        .collect();
+    db.execute(&format!("INSERT INTO items VALUES {}", values.join(", ")))
+        .await?;
+
+    for sql in [
+        "SELECT a.id, b.label FROM items a JOIN items b ON a.id = b.id",
+        "SELECT label, COUNT(*) FROM items GROUP BY label",
+        "SELECT DISTINCT label FROM items",
+    ] {
+        let err = db.execute(sql).await.unwrap_err();
+        assert_exhausted(err, "temp bytes");
+    }
+
+    // The failed statements removed their spill files
+    let spilled = std::fs::read_dir(temp_dir.path())?
+        .filter(|entry| {
+            entry
+                .as_ref()
+                .is_ok_and(|e| e.file_name().to_string_lossy().starts_with("spill-"))
+        })
+        .count();
+    assert_eq!(spilled, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn work_memory_spills_aggregates_and_distinct_instead_of_failing() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let limits = ResourceLimits::builder().max_memory_bytes(16_000).build();
+    let db = create_db(temp_dir.path(), limits).await?;
+    let values: Vec<String> = (6..=600)
+        .map(|id| format!("({id}, 'label {}')", id % 300))
+        .collect();
+    db.execute(&format!("INSERT INTO items VALUES {}", values.join(", ")))
+        .await?;
+
+    let grouped = "SELECT label, COUNT(*) FROM items WHERE id > 5 GROUP BY label";
+    let distinct = "SELECT DISTINCT label FROM items WHERE id > 5";
+    for sql in [grouped, distinct] {
+        let err = db.execute(sql).await.unwrap_err();
+        assert_exhausted(err, "memory bytes");
+    }
+
+    let db = db.with_resource_limits(
+        ResourceLimits::builder()
+            .max_memory_bytes(16_000)
+            .work_memory_bytes(4_000)
+            .build(),
+    );
+    // Spilled groups come back partition by partition, so compare sorted
+    let labels: Vec<String> = (0..300).map(|n| format!("label {n}")).collect();
+    let mut rows = select_rows(&db, grouped).await?;
+    rows.sort();
+    let mut expected: Vec<Vec<Value>> = labels
+        .iter()
+        .map(|label| {
+            // Ids 6 to 600 give each remainder two rows, except 1 to 5
+            let count = if ["label 1", "label 2", "label 3", "label 4", "label 5"]
+                .contains(&label.as_str())
+            {
+                1
+            } else {
+                2
+            };
+            vec![Value::Text(label.clone()), Value::Int(count)]
+        })
+        .collect();
+    expected.sort();
+    assert_eq!(rows, expected);
+
+    let mut rows = select_rows(&db, distinct).await?;
+    rows.sort();
+    let mut expected: Vec<Vec<Value>> = labels
+        .into_iter()
+        .map(|label| vec![Value::Text(label)])
+        .collect();
+    expected.sort();
+    assert_eq!(rows, expected);
+
+    let spilled = std::fs::read_dir(temp_dir.path())?
+        .filter(|entry| {
+            entry
+                .as_ref()
+                .is_ok_and(|e| e.file_name().to_string_lossy().starts_with("spill-"))
+        })
+        .count();
+    assert_eq!(spilled, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn concurrent_statement_limit_is_per_session() -> Result<()> {
+    let registry = SessionRegistry::default();
+    let limits = ResourceLimits::builder()
+        .max_concurrent_statements(1)
+        .build();
+
+    let slot = registry.enter("alice", &limits)?;
+    let err = registry.enter("alice", &limits).unwrap_err();
+    assert!(matches!(err, DbError::ResourceExhausted(_)));
+    // Other sessions are unaffected
+    let _bob = registry.enter("bob", &limits)?;
+    assert_eq!(registry.active("alice"), 1);
+
+    drop(slot);
+    assert_eq!(registry.active("alice"), 0);
+    let _again = registry.enter("alice", &limits)?;
+
+    // Statements release their slot when they finish, including on error
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path(), limits).await?;
+    db.execute_as("alice", "SELECT * FROM items").await?;
+    db.execute_as("alice", "SELECT nope FROM items")
+        .await
+        .unwrap_err();
+    db.execute_as("alice", "SELECT * FROM items").await?;
+    assert_eq!(db.active_statements("alice"), 0);
+    Ok(())
+}
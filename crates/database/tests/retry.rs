@@ -0,0 +1,107 @@
+//! Tests for the opt-in retry policy.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use anyhow::Result;
+use database::{
+    is_retryable, Database, NotLeaderError, QueryResult, RetryPolicy, StatementClass,
+    VersionConflict,
+};
+
+fn not_leader() -> anyhow::Error {
+    anyhow::Error::new(NotLeaderError {
+        node_id: 2,
+        class: StatementClass::Write,
+        leader: None,
+        leader_addr: None,
+    })
+}
+
+fn fast_policy(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy::new(max_attempts).with_backoff(Duration::from_millis(1), Duration::from_millis(2))
+}
+
+#[test]
+fn only_unapplied_failures_are_retryable() {
+    assert!(is_retryable(&not_leader()));
+    assert!(is_retryable(&not_leader().context("election in progress")));
+    assert!(!is_retryable(&anyhow::Error::new(VersionConflict {
+        table: "t".into()
+    })));
+    assert!(!is_retryable(&anyhow::anyhow!("Raft write failed")));
+}
+
+#[test]
+fn backoff_grows_and_is_capped() {
+    let policy =
+        RetryPolicy::new(10).with_backoff(Duration::from_millis(10), Duration::from_millis(50));
+    assert_eq!(policy.backoff_ceiling(1), Duration::from_millis(10));
+    assert_eq!(policy.backoff_ceiling(2), Duration::from_millis(20));
+    assert_eq!(policy.backoff_ceiling(3), Duration::from_millis(40));
+    assert_eq!(policy.backoff_ceiling(4), Duration::from_millis(50));
+    assert_eq!(policy.backoff_ceiling(40), Duration::from_millis(50));
+    for attempt in 1..6 {
+        assert!(policy.backoff(attempt) <= policy.backoff_ceiling(attempt));
+    }
+}
+
+#[tokio::test]
+async fn run_retries_transient_errors_until_success() -> Result<()> {
+    let calls = AtomicU32::new(0);
+    let value = fast_policy(5)
+        .run(|| async {
+            if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(not_leader())
+            } else {
+                Ok(7)
+            }
+        })
+        .await?;
+    assert_eq!(value, 7);
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_gives_up_after_max_attempts() {
+    let calls = AtomicU32::new(0);
+    let err = fast_policy(3)
+        .run(|| async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(not_leader())
+        })
+        .await
+        .expect_err("should give up");
+    assert!(is_retryable(&err));
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn run_does_not_retry_permanent_errors() {
+    let calls = AtomicU32::new(0);
+    fast_policy(5)
+        .run(|| async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(anyhow::anyhow!("duplicate primary key"))
+        })
+        .await
+        .expect_err("should fail");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn database_with_retry_policy_executes_normally() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10)
+        .await?
+        .with_retry_policy(fast_policy(3));
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY)").await?;
+    db.execute("INSERT INTO t VALUES (1)").await?;
+    assert!(db.execute("INSERT INTO t VALUES (1)").await.is_err());
+    match db.execute("SELECT * FROM t").await? {
+        QueryResult::Rows { rows, .. } => assert_eq!(rows.len(), 1),
+        other => panic!("expected rows, got {:?}", other),
+    }
+    Ok(())
+}
@@ -0,0 +1,217 @@
+//! Tests for statement routing in Raft mode.
+
+use database::{
+    route, ClusterView, Database, NotLeaderError, QueryResult, RaftConfig, ReadConsistency, Route,
+    StatementClass,
+};
+use parser::parse_sql;
+use tempfile::TempDir;
+
+fn class_of(sql: &str) -> StatementClass {
+    let stmt = parse_sql(sql).unwrap().into_iter().next().unwrap();
+    StatementClass::of(&stmt)
+}
+
+fn leader_view() -> ClusterView {
+    ClusterView {
+        raft_enabled: true,
+        node_id: 1,
+        leader: Some(1),
+    }
+}
+
+fn follower_view() -> ClusterView {
+    ClusterView {
+        raft_enabled: true,
+        node_id: 2,
+        leader: Some(1),
+    }
+}
+
+#[test]
+fn classify_statements() {
+    assert_eq!(class_of("SELECT * FROM t"), StatementClass::Read);
+    assert_eq!(
+        class_of("EXPLAIN ANALYZE SELECT * FROM t"),
+        StatementClass::Read
+    );
+    assert_eq!(
+        class_of("SELECT * FROM t FOR UPDATE"),
+        StatementClass::LockingRead
+    );
+    assert_eq!(class_of("INSERT INTO t VALUES (1)"), StatementClass::Write);
+    assert_eq!(class_of("UPDATE t SET a = 1"), StatementClass::Write);
+    assert_eq!(class_of("DELETE FROM t"), StatementClass::Write);
+    assert_eq!(class_of("EXPLAIN DELETE FROM t"), StatementClass::Read);
+    assert_eq!(
+        class_of("EXPLAIN ANALYZE DELETE FROM t"),
+        StatementClass::Write
+    );
+    assert_eq!(class_of("PROFILE SELECT * FROM t"), StatementClass::Read);
+    assert_eq!(
+        class_of("PROFILE INSERT INTO t VALUES (1)"),
+        StatementClass::Write
+    );
+    assert_eq!(class_of("CREATE TABLE t (id INT)"), StatementClass::Ddl);
+    assert_eq!(class_of("DROP TABLE t"), StatementClass::Ddl);
+}
+
+#[test]
+fn standalone_runs_everything_locally() {
+    let view = ClusterView::standalone(1);
+    for class in [
+        StatementClass::Read,
+        StatementClass::Write,
+        StatementClass::Ddl,
+    ] {
+        assert_eq!(
+            route(class, ReadConsistency::Linearizable, &view),
+            Route::Local
+        );
+    }
+}
+
+#[test]
+fn writes_replicate_on_leader_and_redirect_on_follower() {
+    let consistency = ReadConsistency::Local;
+    assert_eq!(
+        route(StatementClass::Write, consistency, &leader_view()),
+        Route::Replicate
+    );
+    assert_eq!(
+        route(StatementClass::Write, consistency, &follower_view()),
+        Route::Redirect { leader: Some(1) }
+    );
+
+    let no_leader = ClusterView {
+        leader: None,
+        ..follower_view()
+    };
+    assert_eq!(
+        route(StatementClass::Write, consistency, &no_leader),
+        Route::Redirect { leader: None }
+    );
+}
+
+#[test]
+fn locking_reads_require_the_leader() {
+    let class = StatementClass::LockingRead;
+    assert_eq!(
+        route(class, ReadConsistency::Local, &leader_view()),
+        Route::Local
+    );
+    assert_eq!(
+        route(class, ReadConsistency::Local, &follower_view()),
+        Route::Redirect { leader: Some(1) }
+    );
+}
+
+#[test]
+fn ddl_is_applied_locally() {
+    assert_eq!(
+        route(
+            StatementClass::Ddl,
+            ReadConsistency::Linearizable,
+            &follower_view()
+        ),
+        Route::Local
+    );
+}
+
+#[test]
+fn reads_follow_consistency_level() {
+    let read = StatementClass::Read;
+    assert_eq!(
+        route(read, ReadConsistency::Local, &follower_view()),
+        Route::Local
+    );
+    assert_eq!(
+        route(read, ReadConsistency::Leader, &follower_view()),
+        Route::Redirect { leader: Some(1) }
+    );
+    assert_eq!(
+        route(read, ReadConsistency::Leader, &leader_view()),
+        Route::Local
+    );
+    assert_eq!(
+        route(read, ReadConsistency::Linearizable, &leader_view()),
+        Route::LinearizableRead
+    );
+    assert_eq!(
+        route(read, ReadConsistency::Linearizable, &follower_view()),
+        Route::Redirect { leader: Some(1) }
+    );
+}
+
+#[test]
+fn read_consistency_parses_from_str() {
+    assert_eq!("local".parse(), Ok(ReadConsistency::Local));
+    assert_eq!("Leader".parse(), Ok(ReadConsistency::Leader));
+    assert_eq!("linearizable".parse(), Ok(ReadConsistency::Linearizable));
+    assert!("strong".parse::<ReadConsistency>().is_err());
+}
+
+#[test]
+fn not_leader_error_includes_leader_hint() {
+    let err = NotLeaderError {
+        node_id: 2,
+        class: StatementClass::Write,
+        leader: Some(1),
+        leader_addr: Some("127.0.0.1:6001".into()),
+    };
+    assert_eq!(
+        err.to_string(),
+        "not the leader: this node is 2, cannot accept writes \
+         (current leader: node 1 at 127.0.0.1:6001)"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn linearizable_reads_on_single_node_leader() {
+    let tmp = TempDir::new().unwrap();
+    let config = RaftConfig::single_node(1).with_read_consistency(ReadConsistency::Linearizable);
+    let db = Database::with_raft_config(tmp.path(), "catalog.json", "wal.log", 32, Some(config))
+        .await
+        .unwrap();
+
+    db.execute("CREATE TABLE t (id INT)").await.unwrap();
+    db.execute("INSERT INTO t VALUES (1)").await.unwrap();
+
+    let stmt = parse_sql("SELECT * FROM t").unwrap().remove(0);
+    assert_eq!(db.route_statement(&stmt), Route::LinearizableRead);
+
+    match db.execute("SELECT * FROM t").await.unwrap() {
+        QueryResult::Rows { rows, .. } => assert_eq!(rows.len(), 1),
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn locking_read_runs_on_leader() {
+    let tmp = TempDir::new().unwrap();
+    let db = Database::with_raft_config(
+        tmp.path(),
+        "catalog.json",
+        "wal.log",
+        32,
+        Some(RaftConfig::single_node(1)),
+    )
+    .await
+    .unwrap();
+
+    db.execute("CREATE TABLE t (id INT, qty INT)")
+        .await
+        .unwrap();
+    db.execute("INSERT INTO t VALUES (1, 5), (2, 7)")
+        .await
+        .unwrap();
+
+    match db
+        .execute("SELECT qty FROM t WHERE id = 2 FOR UPDATE")
+        .await
+        .unwrap()
+    {
+        QueryResult::Rows { rows, .. } => assert_eq!(rows.len(), 1),
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
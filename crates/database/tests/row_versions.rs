@@ -0,0 +1,118 @@
+//! Integration tests for implicit row versions and optimistic updates.
+
+use anyhow::Result;
+use database::{Database, QueryResult, RaftConfig, VersionConflict};
+use types::Value;
+
+async fn setup(raft: Option<RaftConfig>) -> Result<(Database, tempfile::TempDir)> {
+    let temp_dir = tempfile::tempdir()?;
+    let db =
+        Database::with_raft_config(temp_dir.path(), "catalog.json", "test.wal", 10, raft).await?;
+    db.execute("CREATE TABLE accounts (id INT PRIMARY KEY, balance INT) WITH (row_version = true)")
+        .await?;
+    db.execute("INSERT INTO accounts VALUES (1, 100), (2, 50)")
+        .await?;
+    Ok((db, temp_dir))
+}
+
+async fn version_of(db: &Database, id: i64) -> Result<Value> {
+    match db
+        .execute(&format!("SELECT _version FROM accounts WHERE id = {id}"))
+        .await?
+    {
+        QueryResult::Rows { rows, .. } => Ok(rows[0].values[0].clone()),
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn inserts_start_at_version_one_and_updates_advance_it() -> Result<()> {
+    let (db, _temp) = setup(None).await?;
+    assert_eq!(version_of(&db, 1).await?, Value::Int(1));
+
+    db.execute("UPDATE accounts SET balance = 90 WHERE id = 1")
+        .await?;
+    assert_eq!(version_of(&db, 1).await?, Value::Int(2));
+    assert_eq!(version_of(&db, 2).await?, Value::Int(1));
+    Ok(())
+}
+
+#[tokio::test]
+async fn stale_version_reports_conflict() -> Result<()> {
+    let (db, _temp) = setup(None).await?;
+
+    let updated = db
+        .execute_versioned_update("UPDATE accounts SET balance = 80 WHERE id = 1 AND _version = 1")
+        .await?;
+    assert_eq!(updated, 1);
+
+    // A second writer still holding version 1 loses the race
+    let err = db
+        .execute_versioned_update("UPDATE accounts SET balance = 70 WHERE id = 1 AND _version = 1")
+        .await
+        .expect_err("stale version should conflict");
+    assert_eq!(
+        err.downcast_ref::<VersionConflict>(),
+        Some(&VersionConflict {
+            table: "accounts".into()
+        })
+    );
+
+    match db
+        .execute("SELECT balance, _version FROM accounts WHERE id = 1")
+        .await?
+    {
+        QueryResult::Rows { rows, .. } => {
+            assert_eq!(rows[0].values, vec![Value::Int(80), Value::Int(2)]);
+        }
+        other => panic!("expected rows, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn versioned_update_requires_version_predicate() -> Result<()> {
+    let (db, _temp) = setup(None).await?;
+    let err = db
+        .execute_versioned_update("UPDATE accounts SET balance = 0 WHERE id = 1")
+        .await
+        .expect_err("missing version predicate should fail");
+    assert!(err.to_string().contains("_version"), "{err}");
+
+    db.execute("CREATE TABLE plain (id INT)").await?;
+    let err = db
+        .execute_versioned_update("UPDATE plain SET id = 2 WHERE _version = 1")
+        .await
+        .expect_err("table without row version should fail");
+    assert!(err.to_string().contains("no row version"), "{err}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn version_column_name_is_reserved() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    let err = db
+        .execute("CREATE TABLE t (id INT, _version INT)")
+        .await
+        .expect_err("reserved column name should fail");
+    assert!(err.to_string().contains("reserved"), "{err}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn row_versions_through_raft() -> Result<()> {
+    let (db, _temp) = setup(Some(RaftConfig::single_node(1))).await?;
+    assert_eq!(version_of(&db, 2).await?, Value::Int(1));
+
+    db.execute_versioned_update("UPDATE accounts SET balance = 10 WHERE id = 2 AND _version = 1")
+        .await?;
+    assert_eq!(version_of(&db, 2).await?, Value::Int(2));
+
+    let err = db
+        .execute_versioned_update("UPDATE accounts SET balance = 20 WHERE id = 2 AND _version = 1")
+        .await
+        .expect_err("stale version should conflict");
+    assert!(err.downcast_ref::<VersionConflict>().is_some());
+    Ok(())
+}
@@ -0,0 +1,85 @@
+//! Integration tests for built-in scalar string functions.
+
+use anyhow::Result;
+use database::{Database, QueryResult};
+use types::Value;
+
+async fn setup() -> Result<(Database, tempfile::TempDir)> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (1, '  Alice ')")
+        .await?;
+    db.execute("INSERT INTO users VALUES (2, 'Bob')").await?;
+    Ok((db, temp_dir))
+}
+
+#[tokio::test]
+async fn functions_in_projection() -> Result<()> {
+    let (db, _temp) = setup().await?;
+
+    let result = db
+        .execute(
+            "SELECT id, UPPER(TRIM(name)), LENGTH(name), CONCAT(name, '!') FROM users WHERE id = 2",
+        )
+        .await?;
+
+    match result {
+        QueryResult::Rows { schema, rows, .. } => {
+            assert_eq!(
+                schema,
+                vec![
+                    "id",
+                    "upper(trim(name))",
+                    "length(name)",
+                    "concat(name, '!')"
+                ]
+            );
+            assert_eq!(
+                rows[0].values,
+                vec![
+                    Value::Int(2),
+                    Value::Text("BOB".into()),
+                    Value::Int(3),
+                    Value::Text("Bob!".into()),
+                ]
+            );
+        }
+        other => panic!("expected rows, got {other:?}"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn functions_in_predicate() -> Result<()> {
+    let (db, _temp) = setup().await?;
+
+    let result = db
+        .execute("SELECT id FROM users WHERE LOWER(TRIM(name)) = 'alice'")
+        .await?;
+
+    match result {
+        QueryResult::Rows { rows, .. } => {
+            assert_eq!(rows.len(), 1);
+            assert_eq!(rows[0].values, vec![Value::Int(1)]);
+        }
+        other => panic!("expected rows, got {other:?}"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn unknown_function_is_an_error() -> Result<()> {
+    let (db, _temp) = setup().await?;
+
+    let err = db
+        .execute("SELECT REVERSE(name) FROM users")
+        .await
+        .expect_err("unknown function should fail");
+    assert!(err.to_string().contains("unknown function"), "{err}");
+
+    Ok(())
+}
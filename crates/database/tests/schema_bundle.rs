@@ -0,0 +1,149 @@
+//! Tests for exporting and importing schema bundles.
+
+use anyhow::Result;
+use database::{schema::BUNDLE_VERSION, Database, QueryResult, SchemaBundle, SchemaChange};
+
+async fn open(dir: &std::path::Path) -> Result<Database> {
+    Database::new(dir, "catalog.json", "test.wal", 10).await
+}
+
+async fn rows(db: &Database, sql: &str) -> Result<usize> {
+    match db.execute(sql).await? {
+        QueryResult::Rows { rows, .. } => Ok(rows.len()),
+        other => panic!("expected rows, got {other:?}"),
+    }
+}
+
+const SCHEMA: &str = "\
+    CREATE TABLE customers (id INT AUTO_INCREMENT, name VARCHAR(40) NOT NULL, \
+        note TEXT DEFAULT 'it''s new', balance DECIMAL(10,2) DEFAULT 0, \
+        PRIMARY KEY (id)) WITH (row_version = true, audit = true, fillfactor = 80); \
+    CREATE UNIQUE INDEX customers_name ON customers USING HASH (name); \
+    CREATE TABLE events (id INT PRIMARY KEY, day INT) PARTITION BY RANGE (day) ( \
+        PARTITION early VALUES LESS THAN (10), PARTITION late VALUES LESS THAN (MAXVALUE)); \
+    CREATE TABLE scratch (k TEXT) ENGINE = memory; \
+    CREATE VIEW rich AS SELECT name FROM customers WHERE balance > 100";
+
+#[tokio::test]
+async fn exported_bundle_recreates_the_schema_elsewhere() -> Result<()> {
+    let source_dir = tempfile::tempdir()?;
+    let source = open(source_dir.path()).await?;
+    source.execute(SCHEMA).await?;
+    source.execute("INSERT INTO events VALUES (1, 5)").await?;
+    let json = source.export_schema().await.to_json();
+
+    let target_dir = tempfile::tempdir()?;
+    let target = open(target_dir.path()).await?;
+    let bundle = SchemaBundle::from_json(&json)?;
+    let changes = target.import_schema(&bundle).await?;
+    assert_eq!(changes.len(), 5);
+    assert_eq!(target.export_schema().await, bundle);
+
+    // Only the schema travels
+    assert_eq!(rows(&target, "SELECT id FROM events").await?, 0);
+    target
+        .execute("INSERT INTO customers (name) VALUES ('ada')")
+        .await?;
+    assert_eq!(
+        rows(&target, "SELECT note FROM customers WHERE id = 1").await?,
+        1
+    );
+
+    // Importing the same bundle again changes nothing
+    assert!(target.import_schema(&bundle).await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn sql_script_creates_the_same_schema() -> Result<()> {
+    let source_dir = tempfile::tempdir()?;
+    let source = open(source_dir.path()).await?;
+    source.execute(SCHEMA).await?;
+    let bundle = source.export_schema().await;
+
+    let target_dir = tempfile::tempdir()?;
+    let target = open(target_dir.path()).await?;
+    target.execute_script(&bundle.to_sql()?).await?;
+    assert_eq!(target.export_schema().await, bundle);
+    Ok(())
+}
+
+#[tokio::test]
+async fn import_alters_the_live_schema_and_keeps_data() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = open(dir.path()).await?;
+    db.execute(
+        "CREATE TABLE t (id INT PRIMARY KEY, old TEXT, v INT); \
+         CREATE INDEX t_v ON t (v); \
+         CREATE TABLE gone (id INT); \
+         CREATE VIEW big AS SELECT id FROM t WHERE v > 10; \
+         INSERT INTO t VALUES (1, 'a', 20), (2, 'b', 5)",
+    )
+    .await?;
+
+    let target_dir = tempfile::tempdir()?;
+    let target = open(target_dir.path()).await?;
+    target
+        .execute(
+            "CREATE TABLE t (id INT PRIMARY KEY, v INT, added INT DEFAULT 7); \
+             CREATE INDEX t_added ON t USING HASH (added); \
+             CREATE VIEW big AS SELECT id FROM t WHERE v > 1",
+        )
+        .await?;
+    let bundle = target.export_schema().await;
+
+    let planned = db.plan_schema_import(&bundle).await?;
+    let changes = db.import_schema(&bundle).await?;
+    assert_eq!(planned, changes);
+    let sql: Vec<String> = changes.iter().map(SchemaChange::to_sql).collect();
+    assert_eq!(
+        sql,
+        [
+            "DROP VIEW big",
+            "DROP INDEX t_v",
+            "DROP TABLE gone",
+            "ALTER TABLE t DROP COLUMN old",
+            "ALTER TABLE t ADD COLUMN added INT DEFAULT 7",
+            "CREATE INDEX t_added ON t USING HASH (added)",
+            "CREATE VIEW big AS SELECT id FROM t WHERE v > 1",
+        ]
+    );
+    assert_eq!(db.export_schema().await, bundle);
+    assert_eq!(rows(&db, "SELECT id FROM t WHERE added = 7").await?, 2);
+    assert_eq!(rows(&db, "SELECT id FROM big").await?, 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn changes_ddl_cannot_make_are_refused_up_front() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = open(dir.path()).await?;
+    db.execute("CREATE TABLE a (id INT, v INT); CREATE TABLE b (id INT)")
+        .await?;
+    let mut bundle = db.export_schema().await;
+    bundle.tables[0].columns[1].ty = "TEXT".into();
+    bundle.tables.pop();
+
+    let err = db.import_schema(&bundle).await.unwrap_err();
+    assert!(err.to_string().contains("column 'v' changed"), "{err}");
+    // Table b was not dropped
+    assert_eq!(rows(&db, "SELECT id FROM b").await?, 0);
+
+    let mut reordered = db.export_schema().await;
+    reordered.tables[0].columns.reverse();
+    let err = db.plan_schema_import(&reordered).await.unwrap_err();
+    assert!(err.to_string().contains("column order"), "{err}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn bundles_from_a_newer_version_are_refused() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = open(dir.path()).await?;
+    let mut bundle = db.export_schema().await;
+    assert_eq!(bundle.version, BUNDLE_VERSION);
+    bundle.version += 1;
+    let err = SchemaBundle::from_json(&bundle.to_json()).unwrap_err();
+    assert!(err.to_string().contains("newer"), "{err}");
+    Ok(())
+}
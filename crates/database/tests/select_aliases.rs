@@ -0,0 +1,69 @@
+//! Integration tests for SELECT column and expression aliases.
+
+use anyhow::Result;
+use database::{Database, QueryResult};
+use types::Value;
+
+#[tokio::test]
+async fn aliases_name_result_columns() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (1, 'alice')").await?;
+    db.execute("INSERT INTO users VALUES (2, 'bob')").await?;
+
+    let result = db
+        .execute("SELECT id AS user_id, UPPER(name) AS display FROM users ORDER BY display DESC")
+        .await?;
+
+    match result {
+        QueryResult::Rows { schema, rows, .. } => {
+            assert_eq!(schema, vec!["user_id", "display"]);
+            assert_eq!(
+                rows[0].values,
+                vec![Value::Int(2), Value::Text("BOB".into())]
+            );
+            assert_eq!(
+                rows[1].values,
+                vec![Value::Int(1), Value::Text("ALICE".into())]
+            );
+        }
+        other => panic!("expected rows, got {other:?}"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn alias_on_joined_qualified_column() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT)")
+        .await?;
+    db.execute("CREATE TABLE posts (id INT PRIMARY KEY, user_id INT)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (1, 'alice')").await?;
+    db.execute("INSERT INTO posts VALUES (10, 1)").await?;
+
+    let result = db
+        .execute(
+            "SELECT u.name AS author, p.id AS post FROM users u JOIN posts p ON u.id = p.user_id",
+        )
+        .await?;
+
+    match result {
+        QueryResult::Rows { schema, rows, .. } => {
+            assert_eq!(schema, vec!["author", "post"]);
+            assert_eq!(
+                rows[0].values,
+                vec![Value::Text("alice".into()), Value::Int(10)]
+            );
+        }
+        other => panic!("expected rows, got {other:?}"),
+    }
+
+    Ok(())
+}
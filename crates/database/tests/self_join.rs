@@ -0,0 +1,121 @@
+//! Integration tests for joining a table with itself.
+
+use anyhow::Result;
+use database::{Database, QueryResult};
+use types::Value;
+
+mod helpers;
+use helpers::select_rows;
+
+fn text(s: &str) -> Value {
+    Value::Text(s.into())
+}
+
+async fn employees(dir: &std::path::Path) -> Result<Database> {
+    let db = Database::new(dir, "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE employees (id INT PRIMARY KEY, name TEXT, manager_id INT)")
+        .await?;
+    db.execute(
+        "INSERT INTO employees VALUES (1, 'ada', NULL), (2, 'bob', 1), (3, 'cy', 2), (4, 'di', 1)",
+    )
+    .await?;
+    Ok(db)
+}
+
+#[tokio::test]
+async fn employees_join_their_managers() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = employees(temp_dir.path()).await?;
+
+    let result = db
+        .execute(
+            "SELECT e.name, m.name AS manager FROM employees e \
+             JOIN employees m ON e.manager_id = m.id ORDER BY e.name",
+        )
+        .await?;
+    match result {
+        QueryResult::Rows { schema, rows, .. } => {
+            assert_eq!(schema, vec!["e.name", "manager"]);
+            let rows: Vec<_> = rows.into_iter().map(|row| row.values).collect();
+            assert_eq!(
+                rows,
+                vec![
+                    vec![text("bob"), text("ada")],
+                    vec![text("cy"), text("bob")],
+                    vec![text("di"), text("ada")],
+                ]
+            );
+        }
+        other => panic!("expected rows, got {other:?}"),
+    }
+
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT e.name FROM employees e JOIN employees m ON e.manager_id = m.id \
+             WHERE m.name = 'ada' ORDER BY e.id"
+        )
+        .await?,
+        vec![vec![text("bob")], vec![text("di")]]
+    );
+    // Three copies: each employee's manager's manager
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT e.name, g.name FROM employees e \
+             JOIN employees m ON e.manager_id = m.id \
+             JOIN employees g ON m.manager_id = g.id"
+        )
+        .await?,
+        vec![vec![text("cy"), text("ada")]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn aliases_qualify_columns_of_a_single_table() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = employees(temp_dir.path()).await?;
+
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT e.name FROM employees e WHERE e.manager_id = 1 ORDER BY e.id DESC"
+        )
+        .await?,
+        vec![vec![text("di")], vec![text("bob")]]
+    );
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT employees.name FROM employees WHERE employees.id = 3"
+        )
+        .await?,
+        vec![vec![text("cy")]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn colliding_names_are_rejected() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = employees(temp_dir.path()).await?;
+
+    let err = db
+        .execute("SELECT * FROM employees JOIN employees ON employees.manager_id = employees.id")
+        .await
+        .expect_err("both sides are named employees");
+    assert!(
+        format!("{err:#}").contains("specified more than once"),
+        "{err:#}"
+    );
+    let err = db
+        .execute("SELECT name FROM employees e JOIN employees m ON e.manager_id = m.id")
+        .await
+        .expect_err("name is on both sides");
+    assert!(
+        format!("{err:#}").contains("ambiguous column 'name'"),
+        "{err:#}"
+    );
+    Ok(())
+}
@@ -0,0 +1,102 @@
+//! Integration tests for `SET GLOBAL` and runtime settings.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use database::{Database, Durability, QueryResult, LOCAL_PRINCIPAL};
+
+async fn open(dir: &std::path::Path) -> Result<Database> {
+    Database::new(dir, "catalog.json", "test.wal", 10).await
+}
+
+#[tokio::test]
+async fn set_global_changes_and_restores_settings() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path()).await?;
+    let initial = db.settings();
+    assert_eq!(initial.durability, Durability::Full);
+    assert_eq!(initial.slow_query_threshold, None);
+    assert_eq!(initial.buffer_pages, 10);
+
+    assert!(matches!(
+        db.execute("SET GLOBAL durability = relaxed").await?,
+        QueryResult::Empty
+    ));
+    db.execute("SET GLOBAL buffer_pages TO 3").await?;
+    db.execute("SET GLOBAL slow_query_threshold_ms = 250")
+        .await?;
+    let changed = db.settings();
+    assert_eq!(changed.durability, Durability::Relaxed);
+    assert_eq!(changed.buffer_pages, 3);
+    assert_eq!(
+        changed.slow_query_threshold,
+        Some(Duration::from_millis(250))
+    );
+    assert_eq!(changed.to_map()["slow_query_threshold_ms"], "250");
+
+    for name in ["durability", "buffer_pages", "slow_query_threshold_ms"] {
+        db.execute(&format!("SET GLOBAL {name} = DEFAULT")).await?;
+    }
+    assert_eq!(db.settings(), initial);
+
+    let err = db.execute("SET GLOBAL buffer_pages = 0").await.unwrap_err();
+    assert!(err.to_string().contains("positive integer"), "{err}");
+    let err = db.execute("SET GLOBAL fsync = off").await.unwrap_err();
+    assert!(err.to_string().contains("unknown setting 'fsync'"), "{err}");
+    assert_eq!(db.settings(), initial);
+    Ok(())
+}
+
+#[tokio::test]
+async fn rows_survive_a_smaller_buffer_pool_and_relaxed_durability() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = open(temp_dir.path()).await?;
+        db.execute("CREATE TABLE t (id INT PRIMARY KEY, v TEXT)")
+            .await?;
+        db.set_global("durability", Some("relaxed")).await?;
+        let values: Vec<String> = (1..=300).map(|i| format!("({i}, 'row {i}')")).collect();
+        db.execute(&format!("INSERT INTO t VALUES {}", values.join(", ")))
+            .await?;
+        db.set_global("buffer_pages", Some("1")).await?;
+        db.execute("UPDATE t SET v = 'changed' WHERE id = 7")
+            .await?;
+    }
+
+    let db = open(temp_dir.path()).await?;
+    match db.execute("SELECT v FROM t WHERE id = 7").await? {
+        QueryResult::Rows { rows, .. } => {
+            assert_eq!(rows[0].values, vec![types::Value::Text("changed".into())])
+        }
+        other => panic!("expected rows, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn statements_past_the_threshold_are_logged() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path()).await?;
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY)").await?;
+    assert!(db.slow_queries().is_empty());
+
+    db.execute("SET GLOBAL slow_query_threshold_ms = 0").await?;
+    db.execute_as("alice", "SELECT id FROM t").await?;
+    db.execute("SET GLOBAL slow_query_threshold_ms = off")
+        .await?;
+    db.execute("SELECT id FROM t").await?;
+
+    let slow = db.slow_queries();
+    let statements: Vec<(&str, &str)> = slow
+        .iter()
+        .map(|query| (query.principal.as_str(), query.sql.as_str()))
+        .collect();
+    assert_eq!(
+        statements,
+        vec![
+            (LOCAL_PRINCIPAL, "SET GLOBAL slow_query_threshold_ms = 0"),
+            ("alice", "SELECT id FROM t"),
+        ]
+    );
+    Ok(())
+}
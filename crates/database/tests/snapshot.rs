@@ -0,0 +1,74 @@
+//! Integration tests for consistent table snapshots.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use database::Database;
+use types::Value;
+
+#[tokio::test]
+async fn snapshot_holds_rows_as_of_one_point() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE Pairs (id INT PRIMARY KEY, pair INT)")
+        .await?;
+    db.execute("INSERT INTO pairs VALUES (1, 0), (2, 0)")
+        .await?;
+
+    let snapshot = db.snapshot_table("PAIRS").await?;
+    assert_eq!(snapshot.table(), "pairs");
+    assert_eq!(snapshot.columns(), ["id", "pair"]);
+    let mut rows: Vec<Vec<Value>> = snapshot.rows().map(<[Value]>::to_vec).collect();
+    rows.sort();
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(1), Value::Int(0)],
+            vec![Value::Int(2), Value::Int(0)],
+        ]
+    );
+
+    // Rows written after the snapshot are in the WAL past its position
+    db.execute("INSERT INTO pairs VALUES (3, 1), (4, 1)")
+        .await?;
+    assert!(db.snapshot_table("pairs").await?.position() > snapshot.position());
+    assert_eq!(snapshot.len(), 2);
+
+    assert!(db.snapshot_table("missing").await.is_err());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn snapshots_never_hold_part_of_a_write() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Arc::new(Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?);
+    db.execute("CREATE TABLE pairs (id INT PRIMARY KEY, pair INT)")
+        .await?;
+
+    // Every statement inserts both rows of a pair
+    let writer = {
+        let db = db.clone();
+        tokio::spawn(async move {
+            for pair in 0..100 {
+                db.execute(&format!(
+                    "INSERT INTO pairs VALUES ({}, {pair}), ({}, {pair})",
+                    2 * pair,
+                    2 * pair + 1
+                ))
+                .await?;
+            }
+            anyhow::Ok(())
+        })
+    };
+
+    let mut last = 0;
+    while !writer.is_finished() {
+        let snapshot = db.snapshot_table("pairs").await?;
+        assert_eq!(snapshot.len() % 2, 0, "snapshot holds half a pair");
+        assert!(snapshot.len() >= last);
+        last = snapshot.len();
+    }
+    writer.await??;
+    assert_eq!(db.snapshot_table("pairs").await?.len(), 200);
+    Ok(())
+}
@@ -0,0 +1,83 @@
+//! Integration tests for `SET statement_priority`.
+
+use anyhow::Result;
+use database::{Database, Priority, QueryResult};
+
+async fn open(dir: &std::path::Path) -> Result<Database> {
+    Database::new(dir, "catalog.json", "test.wal", 10).await
+}
+
+#[tokio::test]
+async fn priority_is_set_per_session() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path()).await?;
+
+    assert!(matches!(
+        db.execute_as("oltp", "SET statement_priority = high")
+            .await?,
+        QueryResult::Empty
+    ));
+    db.execute_as("reports", "SET statement_priority TO 'background'")
+        .await?;
+    assert_eq!(db.priority("oltp"), Priority::High);
+    assert_eq!(db.priority("reports"), Priority::Background);
+    assert_eq!(db.priority("other"), Priority::Normal);
+
+    db.execute_as("oltp", "SET statement_priority = DEFAULT")
+        .await?;
+    assert_eq!(db.priority("oltp"), Priority::Normal);
+
+    db.end_session("reports");
+    assert_eq!(db.priority("reports"), Priority::Normal);
+    Ok(())
+}
+
+#[tokio::test]
+async fn background_statements_return_the_same_rows() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path())
+        .await?
+        .with_max_parallel_workers(4)
+        .with_max_executing_statements(1);
+    db.execute("CREATE TABLE t (id INT PRIMARY KEY, v TEXT)")
+        .await?;
+    let values: Vec<String> = (1..=500).map(|i| format!("({i}, 'row {i}')")).collect();
+    db.execute(&format!("INSERT INTO t VALUES {}", values.join(", ")))
+        .await?;
+
+    db.set_priority("reports", Priority::Background);
+    let background = db
+        .execute_as("reports", "SELECT id, v FROM t ORDER BY id")
+        .await?;
+    let normal = db.execute("SELECT id, v FROM t ORDER BY id").await?;
+    match (background, normal) {
+        (QueryResult::Rows { rows: a, .. }, QueryResult::Rows { rows: b, .. }) => {
+            assert_eq!(a.len(), 500);
+            let values =
+                |rows: Vec<common::Row>| -> Vec<_> { rows.into_iter().map(|r| r.values).collect() };
+            assert_eq!(values(a), values(b));
+        }
+        other => panic!("expected rows, got {other:?}"),
+    }
+
+    db.execute_as("reports", "DELETE FROM t WHERE id > 250")
+        .await?;
+    match db.execute("SELECT id FROM t").await? {
+        QueryResult::Rows { rows, .. } => assert_eq!(rows.len(), 250),
+        other => panic!("expected rows, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn unknown_priorities_are_rejected() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = open(temp_dir.path()).await?;
+    let err = db
+        .execute_as("oltp", "SET statement_priority = urgent")
+        .await
+        .expect_err("unknown priority");
+    assert!(err.to_string().contains("statement_priority must be"));
+    assert_eq!(db.priority("oltp"), Priority::Normal);
+    Ok(())
+}
@@ -0,0 +1,297 @@
+//! Integration tests for ANALYZE, automatic statistics refresh and table
+//! activity counters.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use catalog::TableStatistics;
+use database::{AutoAnalyze, Database, QueryResult};
+use types::Value;
+
+async fn create_db(dir: &std::path::Path, policy: Option<AutoAnalyze>) -> Result<Database> {
+    let db = Database::new(dir, "catalog.json", "test.wal", 10).await?;
+    let db = db.with_auto_analyze(policy);
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, color TEXT)")
+        .await?;
+    Ok(db)
+}
+
+async fn statistics(db: &Database, table: &str) -> Option<TableStatistics> {
+    let catalog = db.catalog();
+    let catalog = catalog.read().await;
+    catalog.table(table).unwrap().statistics.clone()
+}
+
+async fn modifications(db: &Database, table: &str) -> u64 {
+    let catalog = db.catalog();
+    let catalog = catalog.read().await;
+    catalog.table(table).unwrap().modifications_since_analyze()
+}
+
+#[tokio::test]
+async fn analyze_gathers_row_and_column_counts() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path(), None).await?;
+    db.execute("INSERT INTO items VALUES (1, 'red'), (2, 'red'), (3, NULL)")
+        .await?;
+    db.execute("DELETE FROM items WHERE id = 3").await?;
+    assert_eq!(modifications(&db, "items").await, 4);
+    assert!(statistics(&db, "items").await.is_none());
+
+    db.execute("ANALYZE TABLE items").await?;
+    let stats = statistics(&db, "items").await.expect("statistics");
+    assert_eq!(stats.row_count, 2);
+    assert_eq!(stats.columns[0].distinct_count, 2);
+    assert_eq!(stats.columns[1].distinct_count, 1);
+    assert_eq!(stats.columns[1].null_count, 0);
+    assert_eq!(stats.page_count, 1);
+    assert_eq!(stats.columns[0].min, Some(Value::Int(1)));
+    assert_eq!(stats.columns[0].max, Some(Value::Int(2)));
+    assert_eq!(
+        stats.columns[0].histogram,
+        vec![Value::Int(1), Value::Int(2)]
+    );
+    assert_eq!(modifications(&db, "items").await, 0);
+
+    let err = db.execute("ANALYZE TABLE missing").await.unwrap_err();
+    assert!(err.to_string().contains("unknown table"), "{err}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn churn_past_the_threshold_reanalyzes_in_background() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let policy = AutoAnalyze {
+        min_modifications: 3,
+        scale_factor: 0.5,
+    };
+    let db = create_db(temp_dir.path(), Some(policy)).await?;
+
+    // Three modified rows do not pass the threshold
+    db.execute("INSERT INTO items VALUES (1, 'a'), (2, 'b'), (3, 'c')")
+        .await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(statistics(&db, "items").await.is_none());
+
+    db.execute("INSERT INTO items VALUES (4, 'd')").await?;
+    let stats = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let Some(stats) = statistics(&db, "items").await {
+                return stats;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await?;
+    assert_eq!(stats.row_count, 4);
+    assert_eq!(modifications(&db, "items").await, 0);
+
+    // With 4 analyzed rows the threshold is now 3 + 0.5 * 4 = 5
+    db.execute("UPDATE items SET color = 'z'").await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(modifications(&db, "items").await, 4);
+    Ok(())
+}
+
+#[tokio::test]
+async fn statistics_survive_restart() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = create_db(temp_dir.path(), None).await?;
+        db.execute("INSERT INTO items VALUES (1, 'a')").await?;
+        db.execute("ANALYZE TABLE items").await?;
+    }
+
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    let stats = statistics(&db, "items").await.expect("statistics");
+    assert_eq!(stats.row_count, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn reordered_joins_return_the_written_columns() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path(), None).await?;
+    db.execute("CREATE TABLE orders (id INT PRIMARY KEY, item INT)")
+        .await?;
+    db.execute("CREATE TABLE lines (id INT PRIMARY KEY, order_id INT)")
+        .await?;
+    db.execute("INSERT INTO items VALUES (1, 'red'), (2, 'blue'), (3, 'green')")
+        .await?;
+    db.execute("INSERT INTO orders VALUES (10, 1), (11, 2)")
+        .await?;
+    let lines: Vec<String> = (0..20).map(|i| format!("({i}, {})", 10 + i % 2)).collect();
+    db.execute(&format!("INSERT INTO lines VALUES {}", lines.join(", ")))
+        .await?;
+    for table in ["items", "orders", "lines"] {
+        db.execute(&format!("ANALYZE TABLE {table}")).await?;
+    }
+
+    let sql = "SELECT * FROM orders o JOIN lines l ON l.order_id = o.id \
+               JOIN items i ON o.item = i.id WHERE l.id < 3 ORDER BY l.id";
+    let QueryResult::Rows { schema, rows, .. } = db.execute(sql).await? else {
+        panic!("expected rows");
+    };
+    assert_eq!(
+        schema,
+        vec!["o.id", "o.item", "l.id", "l.order_id", "i.id", "i.color"]
+    );
+    let rows: Vec<Vec<Value>> = rows.into_iter().map(|row| row.values).collect();
+    assert_eq!(
+        rows,
+        (0..3)
+            .map(|i| {
+                let (order, item, color) = if i % 2 == 0 {
+                    (10, 1, "red")
+                } else {
+                    (11, 2, "blue")
+                };
+                vec![
+                    Value::Int(order),
+                    Value::Int(item),
+                    Value::Int(i),
+                    Value::Int(order),
+                    Value::Int(item),
+                    Value::Text(color.into()),
+                ]
+            })
+            .collect::<Vec<_>>()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn show_table_stats_lists_row_sizes_and_page_fill() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path(), None).await?;
+    db.execute("INSERT INTO items VALUES (1, 'red'), (2, 'green'), (3, NULL)")
+        .await?;
+
+    let err = db.execute("SHOW TABLE STATS items").await.unwrap_err();
+    assert!(err.to_string().contains("not been analyzed"), "{err}");
+
+    db.execute("ANALYZE TABLE items").await?;
+    let storage = statistics(&db, "items")
+        .await
+        .and_then(|stats| stats.storage)
+        .expect("storage statistics");
+    assert_eq!(storage.pages, 1);
+    assert_eq!(storage.rows, 3);
+    assert!(storage.min_row_bytes > 0 && storage.min_row_bytes <= storage.max_row_bytes);
+    assert!(storage.fill_factor() > 0.0 && storage.fill_factor() < 1.0);
+
+    let QueryResult::Rows { schema, rows, .. } = db.execute("SHOW TABLE STATS items").await? else {
+        panic!("expected rows");
+    };
+    assert_eq!(schema, vec!["statistic", "value"]);
+    let stat = |name: &str| {
+        rows.iter()
+            .find(|row| row.values[0] == Value::Text(name.into()))
+            .map(|row| row.values[1].clone())
+    };
+    assert_eq!(stat("row_count"), Some(Value::Int(3)));
+    assert_eq!(stat("page_count"), Some(Value::Int(1)));
+    assert_eq!(
+        stat("max_row_bytes"),
+        Some(Value::Int(storage.max_row_bytes as i64))
+    );
+    assert_eq!(
+        stat("fill_factor"),
+        Some(Value::Float(storage.fill_factor()))
+    );
+    assert!(stat("row_size_histogram").is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn fillfactor_keeps_pages_partly_empty() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path(), None).await?;
+    db.execute("CREATE TABLE sparse (id INT PRIMARY KEY, color TEXT) WITH (fillfactor = 50)")
+        .await?;
+    let fillfactor = {
+        let catalog = db.catalog();
+        let catalog = catalog.read().await;
+        catalog.table("sparse")?.fillfactor
+    };
+    assert_eq!(fillfactor, Some(50));
+
+    for table in ["items", "sparse"] {
+        for id in 0..200 {
+            db.execute(&format!("INSERT INTO {table} VALUES ({id}, 'color {id}')"))
+                .await?;
+        }
+        db.execute(&format!("ANALYZE TABLE {table}")).await?;
+    }
+    let storage = |stats: Option<TableStatistics>| stats.and_then(|stats| stats.storage).unwrap();
+    let full = storage(statistics(&db, "items").await);
+    let sparse = storage(statistics(&db, "sparse").await);
+    assert!(sparse.fill_factor() <= 0.5, "{sparse:?}");
+    assert!(full.fill_factor() > 0.5, "{full:?}");
+    assert!(sparse.pages > full.pages);
+    Ok(())
+}
+
+#[tokio::test]
+async fn table_activity_counts_writes_by_kind() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let sql = "SELECT table_name, inserts, updates, deletes, live_rows \
+               FROM information_schema.table_activity ORDER BY table_name";
+    {
+        let db = create_db(temp_dir.path(), None).await?;
+        db.execute("CREATE TABLE tags (id INT PRIMARY KEY)").await?;
+        db.execute("INSERT INTO items VALUES (1, 'red'), (2, 'blue'), (3, NULL)")
+            .await?;
+        db.execute("UPDATE items SET color = 'green' WHERE id < 3")
+            .await?;
+        db.execute("DELETE FROM items WHERE id = 3").await?;
+
+        let QueryResult::Rows { schema, rows, .. } = db.execute(sql).await? else {
+            panic!("expected rows");
+        };
+        assert_eq!(
+            schema,
+            vec!["table_name", "inserts", "updates", "deletes", "live_rows"]
+        );
+        let rows: Vec<Vec<Value>> = rows.into_iter().map(|row| row.values).collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    Value::Text("items".into()),
+                    Value::Int(3),
+                    Value::Int(2),
+                    Value::Int(1),
+                    Value::Int(2),
+                ],
+                vec![
+                    Value::Text("tags".into()),
+                    Value::Int(0),
+                    Value::Int(0),
+                    Value::Int(0),
+                    Value::Int(0),
+                ],
+            ]
+        );
+
+        let err = db
+            .execute("DELETE FROM information_schema.table_activity")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("table_activity"), "{err}");
+    }
+
+    // Counters are saved when the database is dropped
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    let QueryResult::Rows { rows, .. } = db
+        .execute(
+            "SELECT inserts, deletes FROM information_schema.table_activity \
+             WHERE table_name = 'items'",
+        )
+        .await?
+    else {
+        panic!("expected rows");
+    };
+    assert_eq!(rows[0].values, vec![Value::Int(3), Value::Int(1)]);
+    Ok(())
+}
@@ -0,0 +1,177 @@
+//! Integration tests for per-table storage engines.
+
+use anyhow::Result;
+use types::Value;
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+#[tokio::test]
+async fn memory_table_supports_dml_without_heap_file() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE cache (id INT PRIMARY KEY, v TEXT) ENGINE = memory")
+        .await?;
+    db.execute("CREATE INDEX idx_cache_v ON cache (v)").await?;
+
+    db.execute("INSERT INTO cache VALUES (1, 'a'), (2, 'b'), (3, 'c')")
+        .await?;
+    db.execute("UPDATE cache SET v = 'z' WHERE id = 2").await?;
+    db.execute("DELETE FROM cache WHERE id = 3").await?;
+    let err = db
+        .execute("INSERT INTO cache VALUES (1, 'dup')")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("duplicate primary key"), "{err}");
+
+    let rows = select_rows(&db, "SELECT id, v FROM cache ORDER BY id").await?;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(1), Value::Text("a".into())],
+            vec![Value::Int(2), Value::Text("z".into())]
+        ]
+    );
+    let rows = select_rows(&db, "SELECT id FROM cache WHERE v = 'z'").await?;
+    assert_eq!(rows, vec![vec![Value::Int(2)]]);
+    assert!(!temp_dir.path().join("cache.heap").exists());
+    assert!(!temp_dir.path().join("cache.pk_idx").exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn memory_tables_start_empty_after_reopen() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = create_db(temp_dir.path()).await?;
+        db.execute("CREATE TABLE cache (id INT PRIMARY KEY, v TEXT) ENGINE = MEMORY")
+            .await?;
+        db.execute("CREATE INDEX idx_cache_v ON cache (v)").await?;
+        db.execute("CREATE TABLE kept (id INT PRIMARY KEY)").await?;
+        db.execute("INSERT INTO cache VALUES (1, 'a')").await?;
+        db.execute("INSERT INTO kept VALUES (1)").await?;
+    }
+
+    let db = create_db(temp_dir.path()).await?;
+    assert!(select_rows(&db, "SELECT id FROM cache").await?.is_empty());
+    assert!(select_rows(&db, "SELECT id FROM cache WHERE v = 'a'")
+        .await?
+        .is_empty());
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM kept").await?,
+        vec![vec![Value::Int(1)]]
+    );
+
+    // Keys of the lost rows are free again
+    db.execute("INSERT INTO cache VALUES (1, 'b')").await?;
+    assert_eq!(
+        select_rows(&db, "SELECT v FROM cache").await?,
+        vec![vec![Value::Text("b".into())]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn dropping_memory_table_discards_rows_and_unknown_engines_fail() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE t (id INT) ENGINE = memory")
+        .await?;
+    db.execute("INSERT INTO t VALUES (1)").await?;
+    db.execute("DROP TABLE t").await?;
+    db.execute("CREATE TABLE t (id INT) ENGINE = memory")
+        .await?;
+    assert!(select_rows(&db, "SELECT id FROM t").await?.is_empty());
+
+    let err = db
+        .execute("CREATE TABLE c (id INT) ENGINE = rocksdb")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("unknown storage engine"), "{err}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn lsm_table_keeps_rows_across_reopen() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = create_db(temp_dir.path()).await?;
+        db.execute("CREATE TABLE events (id INT PRIMARY KEY, kind TEXT) ENGINE = lsm")
+            .await?;
+        db.execute("CREATE INDEX idx_events_kind ON events (kind)")
+            .await?;
+        db.execute("INSERT INTO events VALUES (1, 'a'), (2, 'b'), (3, 'c')")
+            .await?;
+        db.execute("UPDATE events SET kind = 'z' WHERE id = 1")
+            .await?;
+        db.execute("DELETE FROM events WHERE id = 2").await?;
+    }
+    assert!(temp_dir.path().join("events.lsm").is_dir());
+    assert!(!temp_dir.path().join("events.heap").exists());
+
+    let db = create_db(temp_dir.path()).await?;
+    let rows = select_rows(&db, "SELECT id, kind FROM events ORDER BY id").await?;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(1), Value::Text("z".into())],
+            vec![Value::Int(3), Value::Text("c".into())]
+        ]
+    );
+    let rows = select_rows(&db, "SELECT id FROM events WHERE kind = 'z'").await?;
+    assert_eq!(rows, vec![vec![Value::Int(1)]]);
+    let err = db
+        .execute("INSERT INTO events VALUES (3, 'dup')")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("duplicate primary key"), "{err}");
+
+    db.execute("DROP TABLE events").await?;
+    assert!(!temp_dir.path().join("events.lsm").exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn columnar_table_keeps_rows_across_reopen() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = create_db(temp_dir.path()).await?;
+        db.execute(
+            "CREATE TABLE sales (id INT PRIMARY KEY, region TEXT, amount INT) USING COLUMNAR",
+        )
+        .await?;
+        // Enough rows for the delta to be sealed into a stripe
+        for batch in 0..15 {
+            let values: Vec<String> = (batch * 100..(batch + 1) * 100)
+                .map(|id| format!("({id}, 'r{}', {})", id % 3, id % 10))
+                .collect();
+            db.execute(&format!("INSERT INTO sales VALUES {}", values.join(", ")))
+                .await?;
+        }
+        db.execute("UPDATE sales SET amount = 100 WHERE id = 7")
+            .await?;
+        db.execute("DELETE FROM sales WHERE id = 8").await?;
+        let schema = db.export_schema().await.to_sql()?;
+        assert!(schema.contains("ENGINE = columnar"), "{schema}");
+    }
+    let table_dir = temp_dir.path().join("sales.columnar");
+    assert!(table_dir.is_dir());
+    assert!(table_dir.join("00000000.rows").exists());
+    assert!(!temp_dir.path().join("sales.heap").exists());
+
+    let db = create_db(temp_dir.path()).await?;
+    let rows = select_rows(&db, "SELECT id, region, amount FROM sales WHERE id < 10").await?;
+    assert_eq!(rows.len(), 9);
+    assert!(rows.contains(&vec![
+        Value::Int(7),
+        Value::Text("r1".into()),
+        Value::Int(100)
+    ]));
+    let rows = select_rows(&db, "SELECT region FROM sales WHERE amount = 100").await?;
+    assert_eq!(rows, vec![vec![Value::Text("r1".into())]]);
+    assert_eq!(select_rows(&db, "SELECT id FROM sales").await?.len(), 1499);
+
+    db.execute("DROP TABLE sales").await?;
+    assert!(!table_dir.exists());
+    Ok(())
+}
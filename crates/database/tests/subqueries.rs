@@ -0,0 +1,100 @@
+//! Integration tests for `EXISTS`, `NOT EXISTS` and `IN` subqueries.
+
+use anyhow::Result;
+use database::{Database, QueryResult};
+use types::Value;
+
+async fn setup() -> Result<(tempfile::TempDir, Database)> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute_script(
+        "CREATE TABLE users (id INT PRIMARY KEY, name TEXT); \
+         CREATE TABLE orders (id INT PRIMARY KEY, user_id INT, total INT); \
+         INSERT INTO users VALUES (1, 'alice'), (2, 'bob'), (3, 'carol'), (4, NULL); \
+         INSERT INTO orders VALUES (10, 1, 50), (11, 1, 70), (12, 3, 20), (13, NULL, 90)",
+    )
+    .await?;
+    Ok((temp_dir, db))
+}
+
+async fn ids(db: &Database, sql: &str) -> Result<Vec<i64>> {
+    match db.execute(sql).await? {
+        QueryResult::Rows { rows, .. } => Ok(rows
+            .into_iter()
+            .map(|row| match row.values[0] {
+                Value::Int(id) => id,
+                ref other => panic!("expected an id, got {other:?}"),
+            })
+            .collect()),
+        other => panic!("expected rows, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn exists_returns_each_matching_row_once() -> Result<()> {
+    let (_dir, db) = setup().await?;
+
+    // alice has two orders but is returned once
+    let sql = "SELECT id FROM users u \
+               WHERE EXISTS (SELECT 1 FROM orders o WHERE o.user_id = u.id) ORDER BY id";
+    assert_eq!(ids(&db, sql).await?, vec![1, 3]);
+
+    let sql = "SELECT id FROM users u \
+               WHERE EXISTS (SELECT 1 FROM orders o WHERE o.user_id = u.id AND total > 60)";
+    assert_eq!(ids(&db, sql).await?, vec![1]);
+
+    // An uncorrelated subquery keeps every row or none
+    let sql = "SELECT id FROM users WHERE EXISTS (SELECT 1 FROM orders o WHERE total > 1000)";
+    assert!(ids(&db, sql).await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn not_exists_returns_rows_without_a_match() -> Result<()> {
+    let (_dir, db) = setup().await?;
+
+    let sql = "SELECT id FROM users u \
+               WHERE NOT EXISTS (SELECT 1 FROM orders o WHERE o.user_id = u.id) ORDER BY id";
+    assert_eq!(ids(&db, sql).await?, vec![2, 4]);
+
+    // Combined with the rest of the WHERE clause
+    let sql = "SELECT id FROM users u WHERE u.id > 2 \
+               AND NOT EXISTS (SELECT 1 FROM orders o WHERE o.user_id = u.id)";
+    assert_eq!(ids(&db, sql).await?, vec![4]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn in_subquery_matches_the_selected_column() -> Result<()> {
+    let (_dir, db) = setup().await?;
+
+    // The NULL user_id never matches
+    let sql = "SELECT id FROM users WHERE id IN (SELECT user_id FROM orders o) ORDER BY id";
+    assert_eq!(ids(&db, sql).await?, vec![1, 3]);
+
+    let sql = "SELECT id FROM users WHERE id IN (SELECT user_id FROM orders o WHERE total < 60) \
+               ORDER BY id";
+    assert_eq!(ids(&db, sql).await?, vec![1, 3]);
+
+    // Subqueries also filter joined rows
+    let sql = "SELECT o.id FROM users u JOIN orders o ON o.user_id = u.id \
+               WHERE u.id IN (SELECT user_id FROM orders p WHERE p.total = 20)";
+    assert_eq!(ids(&db, sql).await?, vec![12]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn subquery_names_resolve_to_its_own_table_first() -> Result<()> {
+    let (_dir, db) = setup().await?;
+
+    // `id` is the order's, so no order's id equals its user_id
+    let sql = "SELECT u.id FROM users u WHERE EXISTS (SELECT 1 FROM orders o WHERE id = u.id)";
+    assert!(ids(&db, sql).await?.is_empty());
+
+    let err = db
+        .execute("SELECT id FROM users WHERE id NOT IN (SELECT user_id FROM orders o)")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("NOT EXISTS"), "{err}");
+    Ok(())
+}
@@ -0,0 +1,135 @@
+//! Integration tests for TABLESAMPLE.
+
+use anyhow::Result;
+use database::{Database, QueryResult};
+use types::Value;
+
+const ROWS: i64 = 1000;
+
+async fn setup() -> Result<(tempfile::TempDir, Database)> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 64).await?;
+    db.execute("CREATE TABLE events (id INT PRIMARY KEY, payload TEXT)")
+        .await?;
+    for chunk in (0..ROWS).collect::<Vec<_>>().chunks(200) {
+        let values: Vec<String> = chunk
+            .iter()
+            .map(|id| format!("({id}, 'event number {id}')"))
+            .collect();
+        db.execute(&format!("INSERT INTO events VALUES {}", values.join(", ")))
+            .await?;
+    }
+    Ok((temp_dir, db))
+}
+
+async fn ids(db: &Database, sql: &str) -> Result<Vec<i64>> {
+    match db.execute(sql).await? {
+        QueryResult::Rows { rows, .. } => Ok(rows
+            .iter()
+            .map(|row| match row.values[0] {
+                Value::Int(n) => n,
+                ref other => panic!("expected an integer, got {other:?}"),
+            })
+            .collect()),
+        other => panic!("expected rows, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn bernoulli_keeps_about_the_requested_share_of_rows() -> Result<()> {
+    let (_dir, db) = setup().await?;
+
+    let sample = ids(&db, "SELECT id FROM events TABLESAMPLE BERNOULLI (10)").await?;
+    assert!((50..150).contains(&sample.len()), "{} rows", sample.len());
+    assert!(sample.iter().all(|id| (0..ROWS).contains(id)));
+
+    assert!(ids(&db, "SELECT id FROM events TABLESAMPLE BERNOULLI (0)")
+        .await?
+        .is_empty());
+    assert_eq!(
+        ids(&db, "SELECT id FROM events TABLESAMPLE BERNOULLI (100)")
+            .await?
+            .len() as i64,
+        ROWS
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn repeatable_samples_are_the_same_every_time() -> Result<()> {
+    let (_dir, db) = setup().await?;
+
+    for method in ["BERNOULLI", "SYSTEM"] {
+        let sql = format!("SELECT id FROM events TABLESAMPLE {method} (30) REPEATABLE (7)");
+        let first = ids(&db, &sql).await?;
+        assert!(!first.is_empty() && (first.len() as i64) < ROWS, "{method}");
+        assert_eq!(ids(&db, &sql).await?, first, "{method}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn system_samples_keep_whole_pages() -> Result<()> {
+    let (_dir, db) = setup().await?;
+
+    // Rows are stored in id order, so each kept page is a run of ids
+    let sample = ids(
+        &db,
+        "SELECT id FROM events TABLESAMPLE SYSTEM (50) REPEATABLE (11)",
+    )
+    .await?;
+    let runs = sample.windows(2).filter(|w| w[1] != w[0] + 1).count() + 1;
+    assert!(
+        sample.len() > runs * 2,
+        "{} rows in {runs} runs",
+        sample.len()
+    );
+
+    // Filters apply to the sampled rows
+    let filtered = ids(
+        &db,
+        "SELECT id FROM events e TABLESAMPLE SYSTEM (50) REPEATABLE (11) WHERE e.id < 500",
+    )
+    .await?;
+    let expected: Vec<i64> = sample.into_iter().filter(|id| *id < 500).collect();
+    assert_eq!(filtered, expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn samples_apply_to_the_table_they_follow_in_subqueries() -> Result<()> {
+    let (_dir, db) = setup().await?;
+
+    // The subquery's table is sampled down to nothing, so no row has a match
+    let count = ids(
+        &db,
+        "SELECT COUNT(*) FROM events WHERE EXISTS \
+         (SELECT id FROM events TABLESAMPLE BERNOULLI (0))",
+    )
+    .await?;
+    assert_eq!(count, vec![0]);
+    let count = ids(
+        &db,
+        "SELECT COUNT(*) FROM events WHERE id IN \
+         (SELECT id FROM events TABLESAMPLE BERNOULLI (100))",
+    )
+    .await?;
+    assert_eq!(count, vec![ROWS]);
+
+    // Only the outer table is sampled
+    let count = ids(
+        &db,
+        "SELECT COUNT(*) FROM events TABLESAMPLE BERNOULLI (0) WHERE EXISTS \
+         (SELECT id FROM events)",
+    )
+    .await?;
+    assert_eq!(count, vec![0]);
+    let count = ids(
+        &db,
+        "SELECT COUNT(*) FROM events TABLESAMPLE BERNOULLI (100) WHERE NOT EXISTS \
+         (SELECT id FROM events WHERE id > 5000)",
+    )
+    .await?;
+    assert_eq!(count, vec![ROWS]);
+    Ok(())
+}
@@ -0,0 +1,210 @@
+//! Integration tests for DATE / TIMESTAMP columns, CAST and date functions.
+
+use anyhow::Result;
+use types::{temporal, Value};
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+fn date(s: &str) -> Value {
+    Value::Date(temporal::parse_date(s).unwrap())
+}
+
+fn timestamp(s: &str) -> Value {
+    Value::Timestamp(temporal::parse_timestamp(s).unwrap())
+}
+
+#[tokio::test]
+async fn temporal_columns_store_compare_and_sort() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute("CREATE TABLE events (id INT PRIMARY KEY, day DATE, at TIMESTAMP)")
+        .await?;
+    // Text literals are read as dates and timestamps when stored
+    db.execute(
+        "INSERT INTO events VALUES \
+         (1, '2024-05-02', '2024-05-02 09:15:00'), \
+         (2, DATE '2024-04-30', TIMESTAMP '2024-04-30 23:59:59.5'), \
+         (3, '2024-05-01', DATE '2024-05-01')",
+    )
+    .await?;
+
+    assert_eq!(
+        select_rows(&db, "SELECT day, at FROM events WHERE id = 3").await?,
+        vec![vec![date("2024-05-01"), timestamp("2024-05-01 00:00:00")]]
+    );
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT id FROM events WHERE day >= '2024-05-01' ORDER BY id"
+        )
+        .await?,
+        vec![vec![Value::Int(1)], vec![Value::Int(3)]]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM events WHERE at < DATE '2024-05-01'").await?,
+        vec![vec![Value::Int(2)]]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM events ORDER BY at DESC").await?,
+        vec![
+            vec![Value::Int(1)],
+            vec![Value::Int(3)],
+            vec![Value::Int(2)]
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn btree_index_finds_temporal_keys() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute("CREATE TABLE events (id INT PRIMARY KEY, day DATE)")
+        .await?;
+    db.execute("CREATE INDEX idx_day ON events (day)").await?;
+    db.execute("INSERT INTO events VALUES (1, '2024-01-15'), (2, '2024-02-15'), (3, '2024-03-15')")
+        .await?;
+
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM events WHERE day = '2024-02-15'").await?,
+        vec![vec![Value::Int(2)]]
+    );
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT id FROM events WHERE day > DATE '2024-02-01' ORDER BY id"
+        )
+        .await?,
+        vec![vec![Value::Int(2)], vec![Value::Int(3)]]
+    );
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT id FROM events WHERE day <= '2024-02-15' ORDER BY id"
+        )
+        .await?,
+        vec![vec![Value::Int(1)], vec![Value::Int(2)]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn cast_now_and_date_trunc() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute("CREATE TABLE events (id INT PRIMARY KEY, at TIMESTAMP, note TEXT)")
+        .await?;
+    db.execute("INSERT INTO events VALUES (1, '2024-08-14 13:47:21', '42')")
+        .await?;
+
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT CAST(at AS DATE), CAST(note AS INT), CAST(at AS TEXT) FROM events"
+        )
+        .await?,
+        vec![vec![
+            date("2024-08-14"),
+            Value::Int(42),
+            Value::Text("2024-08-14 13:47:21".into())
+        ]]
+    );
+    assert_eq!(
+        select_rows(
+            &db,
+            "SELECT DATE_TRUNC('month', at), DATE_TRUNC('hour', at) FROM events"
+        )
+        .await?,
+        vec![vec![
+            timestamp("2024-08-01 00:00:00"),
+            timestamp("2024-08-14 13:00:00")
+        ]]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM events WHERE at < NOW()").await?,
+        vec![vec![Value::Int(1)]]
+    );
+
+    let err = db
+        .execute("SELECT DATE_TRUNC('fortnight', at) FROM events")
+        .await
+        .expect_err("unknown unit");
+    assert!(format!("{err:#}").contains("DATE_TRUNC unit"), "{err:#}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalid_dates_are_rejected() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute("CREATE TABLE events (id INT PRIMARY KEY, day DATE)")
+        .await?;
+    let err = db
+        .execute("INSERT INTO events VALUES (1, '2023-02-29')")
+        .await
+        .expect_err("2023 is not a leap year");
+    assert!(format!("{err:#}").contains("cannot hold"), "{err:#}");
+    assert!(db
+        .execute("INSERT INTO events VALUES (1, 20240101)")
+        .await
+        .is_err());
+    assert!(db
+        .execute("SELECT id FROM events WHERE day = DATE 'soon'")
+        .await
+        .is_err());
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM events").await?,
+        Vec::<Vec<Value>>::new()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn temporal_values_survive_reopen() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = create_db(temp_dir.path()).await?;
+        db.execute("CREATE TABLE t (id INT PRIMARY KEY, day DATE, at TIMESTAMP)")
+            .await?;
+        db.execute("INSERT INTO t VALUES (1, '1969-07-20', '1969-07-20 20:17:40')")
+            .await?;
+    }
+
+    let db = create_db(temp_dir.path()).await?;
+    assert_eq!(
+        select_rows(&db, "SELECT day, at FROM t").await?,
+        vec![vec![date("1969-07-20"), timestamp("1969-07-20 20:17:40")]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn tables_partition_by_date() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute(
+        "CREATE TABLE events (id INT PRIMARY KEY, day DATE) \
+         PARTITION BY RANGE (day) ( \
+             PARTITION y2023 VALUES LESS THAN ('2024-01-01'), \
+             PARTITION y2024 VALUES LESS THAN (MAXVALUE))",
+    )
+    .await?;
+    db.execute("INSERT INTO events VALUES (1, '2023-12-31'), (2, '2024-01-01')")
+        .await?;
+
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM events WHERE day < DATE '2024-01-01'").await?,
+        vec![vec![Value::Int(1)]]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM events WHERE day >= '2024-01-01'").await?,
+        vec![vec![Value::Int(2)]]
+    );
+    Ok(())
+}
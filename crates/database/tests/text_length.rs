@@ -0,0 +1,90 @@
+//! Integration tests for VARCHAR(n) and CHAR(n) columns.
+
+use anyhow::Result;
+use types::Value;
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+fn text(s: &str) -> Value {
+    Value::Text(s.into())
+}
+
+#[tokio::test]
+async fn varchar_columns_reject_longer_text() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+
+    db.execute(
+        "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(5), bio CHARACTER VARYING(255))",
+    )
+    .await?;
+    db.execute("INSERT INTO users VALUES (1, 'alice', 'hi'), (2, 'bob     ', NULL)")
+        .await?;
+
+    let err = db
+        .execute("INSERT INTO users VALUES (3, 'mallory', NULL)")
+        .await
+        .expect_err("seven characters");
+    assert!(
+        format!("{err:#}").contains(
+            "value too long for column 'name' of table 'users' of type VARCHAR(5): \
+             \"mallory\" has 7 characters"
+        ),
+        "{err:#}"
+    );
+    let err = db
+        .execute("UPDATE users SET name = CONCAT(name, '!') WHERE id = 1")
+        .await
+        .expect_err("six characters");
+    assert!(format!("{err:#}").contains("value too long"), "{err:#}");
+
+    // Spaces past the limit are dropped
+    assert_eq!(
+        select_rows(&db, "SELECT name FROM users ORDER BY id").await?,
+        vec![vec![text("alice")], vec![text("bob  ")]]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn char_columns_pad_with_spaces() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = create_db(temp_dir.path()).await?;
+        db.execute("CREATE TABLE codes (id INT PRIMARY KEY, code CHAR(3), flag CHAR)")
+            .await?;
+        db.execute("INSERT INTO codes VALUES (1, 'ab', 'y'), (2, 'xyz', NULL)")
+            .await?;
+        db.execute("UPDATE codes SET code = 'q' WHERE id = 2")
+            .await?;
+        assert!(db
+            .execute("INSERT INTO codes VALUES (3, 'abc', 'no')")
+            .await
+            .is_err());
+        db.execute("ALTER TABLE codes ADD COLUMN region CHAR(4) DEFAULT 'eu'")
+            .await?;
+    }
+
+    let db = create_db(temp_dir.path()).await?;
+    assert_eq!(
+        select_rows(&db, "SELECT code, flag, region FROM codes ORDER BY id").await?,
+        vec![
+            vec![text("ab "), text("y"), text("eu  ")],
+            vec![text("q  "), Value::Null, text("eu  ")],
+        ]
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT id FROM codes WHERE code = 'ab '").await?,
+        vec![vec![Value::Int(1)]]
+    );
+    assert!(db
+        .execute("ALTER TABLE codes ADD COLUMN zone CHAR(2) DEFAULT 'north'")
+        .await
+        .is_err());
+    assert!(db
+        .execute("CREATE TABLE bad (id INT PRIMARY KEY, name VARCHAR(0))")
+        .await
+        .is_err());
+    Ok(())
+}
@@ -0,0 +1,477 @@
+//! Integration tests for multi-statement transactions and their
+//! serializability checks.
+
+use anyhow::Result;
+use common::hooks::{FaultPlan, IoOp};
+use database::journal::JOURNAL_FILE;
+use database::{ConflictTracker, Database, IsolationLevel, QueryResult, SerializationFailure};
+use std::sync::Arc;
+use types::Value;
+
+/// A database with `checking` and `savings` tables holding one account each,
+/// 100 in both.
+async fn bank(dir: &tempfile::TempDir) -> Result<Database> {
+    let db = Database::new(dir.path(), "catalog.json", "test.wal", 10).await?;
+    db.execute("CREATE TABLE checking (id INT PRIMARY KEY, balance INT)")
+        .await?;
+    db.execute("CREATE TABLE savings (id INT PRIMARY KEY, balance INT)")
+        .await?;
+    db.execute("INSERT INTO checking VALUES (1, 100)").await?;
+    db.execute("INSERT INTO savings VALUES (1, 100)").await?;
+    Ok(db)
+}
+
+fn scalar(result: QueryResult) -> Value {
+    match result {
+        QueryResult::Rows { mut rows, .. } => rows.remove(0).values.remove(0),
+        other => panic!("expected rows, got {other:?}"),
+    }
+}
+
+fn failure(err: &anyhow::Error) -> &SerializationFailure {
+    err.downcast_ref()
+        .unwrap_or_else(|| panic!("expected a serialization failure, got {err:#}"))
+}
+
+async fn balance(db: &Database, table: &str) -> Result<Value> {
+    Ok(scalar(
+        db.execute(&format!("SELECT balance FROM {table} WHERE id = 1"))
+            .await?,
+    ))
+}
+
+/// Two withdrawals that each check the combined balance, then take 150
+/// from a different account. Either alone keeps the total non-negative;
+/// both together are write skew.
+async fn withdraw_both(db: &Database) -> Result<(Result<()>, Result<()>)> {
+    let mut first = db.begin();
+    let mut second = db.begin();
+    for txn in [&mut first, &mut second] {
+        let checking = scalar(txn.execute("SELECT balance FROM checking").await?);
+        let savings = scalar(txn.execute("SELECT balance FROM savings").await?);
+        assert_eq!((checking, savings), (Value::Int(100), Value::Int(100)));
+    }
+    first
+        .execute("UPDATE checking SET balance = balance - 150")
+        .await?;
+    second
+        .execute("UPDATE savings SET balance = balance - 150")
+        .await?;
+    let first = first.commit().await.map(drop);
+    let second = second.commit().await.map(drop);
+    Ok((first, second))
+}
+
+#[tokio::test]
+async fn serializable_aborts_write_skew() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = bank(&dir).await?;
+
+    let (first, second) = withdraw_both(&db).await?;
+    first?;
+    let err = second.unwrap_err();
+    assert_eq!(failure(&err), &SerializationFailure::DangerousStructure);
+
+    assert_eq!(balance(&db, "checking").await?, Value::Int(-50));
+    assert_eq!(balance(&db, "savings").await?, Value::Int(100));
+    Ok(())
+}
+
+#[tokio::test]
+async fn repeatable_read_allows_write_skew() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = bank(&dir).await?;
+    db.execute("SET SESSION CHARACTERISTICS AS TRANSACTION ISOLATION LEVEL REPEATABLE READ")
+        .await?;
+
+    let (first, second) = withdraw_both(&db).await?;
+    first?;
+    second?;
+    assert_eq!(balance(&db, "checking").await?, Value::Int(-50));
+    assert_eq!(balance(&db, "savings").await?, Value::Int(-50));
+    Ok(())
+}
+
+#[tokio::test]
+async fn the_second_writer_of_a_table_fails() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = bank(&dir).await?;
+
+    let mut first = db.begin();
+    let mut second = db.begin();
+    first
+        .execute("UPDATE checking SET balance = balance + 1")
+        .await?;
+    second
+        .execute("UPDATE checking SET balance = balance + 2")
+        .await?;
+    first.commit().await?;
+    let err = second.commit().await.unwrap_err();
+    assert_eq!(
+        failure(&err),
+        &SerializationFailure::ConcurrentWrite {
+            table: "checking".into()
+        }
+    );
+    assert_eq!(balance(&db, "checking").await?, Value::Int(101));
+    Ok(())
+}
+
+#[tokio::test]
+async fn a_locking_read_conflicts_like_a_write() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = bank(&dir).await?;
+
+    let mut first = db.begin();
+    let mut second = db.begin();
+    for txn in [&mut first, &mut second] {
+        let result = txn
+            .execute("SELECT balance FROM checking WHERE id = 1 FOR UPDATE")
+            .await?;
+        assert_eq!(scalar(result), Value::Int(100));
+    }
+    first.execute("UPDATE savings SET balance = 0").await?;
+    first.commit().await?;
+    let err = second.commit().await.unwrap_err();
+    assert_eq!(
+        failure(&err),
+        &SerializationFailure::ConcurrentWrite {
+            table: "checking".into()
+        }
+    );
+
+    // A plain read of the locked table does not conflict
+    let mut locking = db.begin();
+    let mut reading = db.begin();
+    locking
+        .execute("SELECT balance FROM savings FOR SHARE")
+        .await?;
+    reading.execute("SELECT balance FROM savings").await?;
+    locking.commit().await?;
+    reading.commit().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn a_crash_during_commit_applies_none_of_the_writes() -> Result<()> {
+    for n in 1.. {
+        let dir = tempfile::tempdir()?;
+        drop(bank(&dir).await?);
+        let plan = Arc::new(FaultPlan::new().crash_at(IoOp::PageWrite, n));
+        let db =
+            Database::with_faults(dir.path(), "catalog.json", "test.wal", 10, plan.clone()).await?;
+        let mut txn = db.begin();
+        txn.execute("UPDATE checking SET balance = 50").await?;
+        txn.execute("UPDATE savings SET balance = 150").await?;
+        let committed = txn.commit().await.is_ok();
+        drop(db);
+
+        let db = Database::new(dir.path(), "catalog.json", "test.wal", 10).await?;
+        let report = db.recovery_report();
+        assert!(report.is_healthy(), "{report}");
+        assert!(!dir.path().join(JOURNAL_FILE).exists());
+        let balances = (
+            balance(&db, "checking").await?,
+            balance(&db, "savings").await?,
+        );
+        if !plan.crashed() {
+            assert!(committed);
+            assert_eq!(balances, (Value::Int(50), Value::Int(150)));
+            break;
+        }
+        assert!(!committed);
+        assert_eq!(
+            balances,
+            (Value::Int(100), Value::Int(100)),
+            "crash at page write {n}"
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn shared_locks_do_not_conflict_with_each_other() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = bank(&dir).await?;
+
+    let mut first = db.begin();
+    let mut second = db.begin();
+    for txn in [&mut first, &mut second] {
+        let result = txn
+            .execute("SELECT balance FROM checking WHERE id = 1 FOR SHARE")
+            .await?;
+        assert_eq!(scalar(result), Value::Int(100));
+    }
+    first.commit().await?;
+    second.commit().await?;
+
+    // A shared lock still conflicts with a concurrent write or FOR UPDATE
+    let mut sharing = db.begin();
+    let mut writing = db.begin();
+    sharing
+        .execute("SELECT balance FROM checking FOR SHARE")
+        .await?;
+    writing.execute("UPDATE checking SET balance = 0").await?;
+    sharing.commit().await?;
+    let err = writing.commit().await.unwrap_err();
+    assert_eq!(
+        failure(&err),
+        &SerializationFailure::ConcurrentShare {
+            table: "checking".into()
+        }
+    );
+
+    let mut updating = db.begin();
+    let mut sharing = db.begin();
+    updating
+        .execute("SELECT balance FROM savings FOR UPDATE")
+        .await?;
+    sharing
+        .execute("SELECT balance FROM savings FOR SHARE")
+        .await?;
+    updating.commit().await?;
+    let err = sharing.commit().await.unwrap_err();
+    assert_eq!(
+        failure(&err),
+        &SerializationFailure::ConcurrentWrite {
+            table: "savings".into()
+        }
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn a_read_after_a_concurrent_commit_fails() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = bank(&dir).await?;
+    db.execute("SET SESSION CHARACTERISTICS AS TRANSACTION ISOLATION LEVEL REPEATABLE READ")
+        .await?;
+
+    let mut txn = db.begin();
+    assert_eq!(txn.isolation_level(), IsolationLevel::RepeatableRead);
+    txn.execute("SELECT balance FROM savings").await?;
+    db.execute("UPDATE checking SET balance = 0").await?;
+
+    // The snapshot would show 100, but the table holds only the new row
+    let err = txn
+        .execute("SELECT balance FROM checking")
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        failure(&err),
+        SerializationFailure::ConcurrentWrite { table } if table == "checking"
+    ));
+    let err = txn
+        .execute("SELECT balance FROM savings")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("aborted"), "{err}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn a_profiled_write_conflicts_like_the_write() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = bank(&dir).await?;
+
+    let mut txn = db.begin();
+    txn.execute("SELECT balance FROM checking").await?;
+    db.execute("PROFILE INSERT INTO checking VALUES (2, 50)")
+        .await?;
+
+    let err = txn
+        .execute("SELECT balance FROM checking")
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        failure(&err),
+        SerializationFailure::ConcurrentWrite { table } if table == "checking"
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn an_explain_analyze_write_conflicts_like_the_write() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = bank(&dir).await?;
+
+    let mut txn = db.begin();
+    txn.execute("SELECT balance FROM savings").await?;
+    db.execute("EXPLAIN ANALYZE DELETE FROM savings WHERE id = 1")
+        .await?;
+
+    let err = txn
+        .execute("SELECT balance FROM savings")
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        failure(&err),
+        SerializationFailure::ConcurrentWrite { table } if table == "savings"
+    ));
+
+    // Only ANALYZE runs the write
+    let mut txn = db.begin();
+    txn.execute("SELECT balance FROM checking").await?;
+    db.execute("EXPLAIN DELETE FROM checking").await?;
+    txn.execute("SELECT balance FROM checking").await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn read_committed_reads_the_latest_rows() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = bank(&dir).await?;
+    db.execute("SET TRANSACTION ISOLATION LEVEL READ COMMITTED")
+        .await?;
+
+    let mut txn = db.begin();
+    txn.execute("SELECT balance FROM checking").await?;
+    db.execute("UPDATE checking SET balance = 0").await?;
+    let result = txn.execute("SELECT balance FROM checking").await?;
+    assert_eq!(scalar(result), Value::Int(0));
+    txn.commit().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn transactions_on_different_tables_both_commit() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = bank(&dir).await?;
+
+    let mut first = db.begin();
+    let mut second = db.begin();
+    first.execute("SELECT balance FROM checking").await?;
+    first
+        .execute("UPDATE checking SET balance = balance + 1")
+        .await?;
+    second.execute("SELECT balance FROM savings").await?;
+    second
+        .execute("UPDATE savings SET balance = balance + 1")
+        .await?;
+    second.commit().await?;
+    let results = first.commit().await?;
+    assert!(matches!(
+        results[..],
+        [QueryResult::Count { affected: 1, .. }]
+    ));
+
+    assert_eq!(balance(&db, "checking").await?, Value::Int(101));
+    assert_eq!(balance(&db, "savings").await?, Value::Int(101));
+    Ok(())
+}
+
+#[tokio::test]
+async fn writes_apply_only_on_commit() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = bank(&dir).await?;
+
+    let mut txn = db.begin();
+    txn.execute("INSERT INTO checking VALUES (2, 5)").await?;
+    let err = txn
+        .execute("SELECT COUNT(*) FROM checking")
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("cannot be read after it is written"));
+    assert!(txn.execute("DROP TABLE savings").await.is_err());
+    drop(txn);
+    assert_eq!(
+        scalar(db.execute("SELECT COUNT(*) FROM checking").await?),
+        Value::Int(1)
+    );
+
+    let mut txn = db.begin();
+    txn.execute("INSERT INTO checking VALUES (2, 5)").await?;
+    txn.rollback();
+    assert_eq!(
+        scalar(db.execute("SELECT COUNT(*) FROM checking").await?),
+        Value::Int(1)
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn a_failing_write_rolls_back_the_whole_commit() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = bank(&dir).await?;
+    db.execute("INSERT INTO checking VALUES (3, 30)").await?;
+
+    let mut txn = db.begin();
+    txn.execute("UPDATE checking SET balance = balance + 50 WHERE id = 1")
+        .await?;
+    txn.execute("DELETE FROM checking WHERE id = 3").await?;
+    txn.execute("INSERT INTO savings VALUES (2, 5)").await?;
+    // Duplicates the row the previous write inserts
+    txn.execute("INSERT INTO savings VALUES (2, 7)").await?;
+    let err = txn.commit().await.unwrap_err();
+    assert!(err.to_string().contains("duplicate primary key"), "{err:#}");
+
+    assert_eq!(balance(&db, "checking").await?, Value::Int(100));
+    assert_eq!(
+        scalar(
+            db.execute("SELECT balance FROM checking WHERE id = 3")
+                .await?
+        ),
+        Value::Int(30)
+    );
+    assert_eq!(
+        scalar(db.execute("SELECT COUNT(*) FROM savings").await?),
+        Value::Int(1)
+    );
+    // The primary key index was rolled back with the rows
+    db.execute("INSERT INTO savings VALUES (2, 9)").await?;
+    db.execute("INSERT INTO checking VALUES (3, 31)")
+        .await
+        .unwrap_err();
+    Ok(())
+}
+
+#[tokio::test]
+async fn reads_through_a_view_conflict_with_its_tables() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = bank(&dir).await?;
+    db.execute("CREATE VIEW rich AS SELECT id FROM checking WHERE balance > 50")
+        .await?;
+
+    let mut first = db.begin();
+    let mut second = db.begin();
+    first.execute("SELECT id FROM rich").await?;
+    first
+        .execute("UPDATE savings SET balance = balance + 1")
+        .await?;
+    second.execute("SELECT balance FROM savings").await?;
+    second
+        .execute("UPDATE checking SET balance = balance - 100")
+        .await?;
+    first.commit().await?;
+    let err = second.commit().await.unwrap_err();
+    assert_eq!(failure(&err), &SerializationFailure::DangerousStructure);
+    Ok(())
+}
+
+#[test]
+fn the_tracker_forgets_transactions_nothing_overlaps() {
+    let tracker = ConflictTracker::default();
+    let old = tracker.begin(IsolationLevel::Serializable);
+    let young = tracker.begin(IsolationLevel::Serializable);
+    tracker.write(old, "t").unwrap();
+    tracker.commit(old, ["t"]).unwrap();
+    // Kept while `young`, which began first, still runs
+    assert_eq!(tracker.tracked(), 2);
+    assert_eq!(
+        tracker.read(young, ["t"]),
+        Err(SerializationFailure::ConcurrentWrite { table: "t".into() })
+    );
+    assert_eq!(tracker.tracked(), 0);
+
+    // Writes outside a transaction are only kept while one runs
+    tracker.record_write("t");
+    assert_eq!(tracker.tracked(), 0);
+    let running = tracker.begin(IsolationLevel::RepeatableRead);
+    tracker.record_write("t");
+    assert_eq!(tracker.tracked(), 2);
+    tracker.abort(running);
+    assert_eq!(tracker.tracked(), 0);
+}
@@ -0,0 +1,103 @@
+//! Integration tests for CREATE UNIQUE INDEX enforcement.
+
+use anyhow::Result;
+use types::Value;
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+#[tokio::test]
+async fn unique_index_rejects_duplicate_inserts() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, email TEXT)")
+        .await?;
+    db.execute("CREATE UNIQUE INDEX idx_users_email ON users (email)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (1, 'a@example.com')")
+        .await?;
+
+    let err = db
+        .execute("INSERT INTO users VALUES (2, 'a@example.com')")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("idx_users_email"), "{err}");
+
+    // Duplicates within one statement are rejected as a whole
+    let err = db
+        .execute("INSERT INTO users VALUES (3, 'b@example.com'), (4, 'b@example.com')")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("duplicate key"), "{err}");
+
+    // NULL keys never conflict
+    db.execute("INSERT INTO users VALUES (5, NULL), (6, NULL)")
+        .await?;
+
+    let rows = select_rows(&db, "SELECT id FROM users ORDER BY id").await?;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(1)],
+            vec![Value::Int(5)],
+            vec![Value::Int(6)]
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn unique_index_rejects_duplicate_updates() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, email TEXT)")
+        .await?;
+    db.execute("CREATE UNIQUE INDEX idx_users_email ON users USING HASH (email)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (1, 'a'), (2, 'b')")
+        .await?;
+
+    let err = db
+        .execute("UPDATE users SET email = 'a' WHERE id = 2")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("idx_users_email"), "{err}");
+
+    // Rewriting a row's own key is not a conflict
+    db.execute("UPDATE users SET email = 'a' WHERE id = 1")
+        .await?;
+    db.execute("UPDATE users SET email = 'c' WHERE id = 2")
+        .await?;
+
+    let rows = select_rows(&db, "SELECT id, email FROM users ORDER BY id").await?;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(1), Value::Text("a".into())],
+            vec![Value::Int(2), Value::Text("c".into())]
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn unique_index_cannot_be_built_over_duplicates() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, email TEXT)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (1, 'a'), (2, 'a')")
+        .await?;
+
+    let err = db
+        .execute("CREATE UNIQUE INDEX idx_users_email ON users (email)")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("duplicate key"), "{err}");
+
+    // The failed index leaves nothing behind; a non-unique one can still be built
+    db.execute("CREATE INDEX idx_users_email ON users (email)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (3, 'a')").await?;
+    Ok(())
+}
@@ -0,0 +1,219 @@
+//! Integration tests for VACUUM and automatic vacuuming.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use database::{AutoVacuum, Database};
+use types::Value;
+
+mod helpers;
+use helpers::select_rows;
+
+async fn create_db(dir: &std::path::Path) -> Result<Database> {
+    let db = Database::new(dir, "catalog.json", "test.wal", 10).await?;
+    Ok(db.with_auto_analyze(None))
+}
+
+/// A table of `rows` rows with a B+Tree index on `grp`, a hash index on
+/// `tag` and enough padding that the rows span several pages.
+async fn create_items(db: &Database, rows: i64) -> Result<()> {
+    db.execute("CREATE TABLE items (id INT PRIMARY KEY, grp INT, tag TEXT, pad TEXT)")
+        .await?;
+    db.execute("CREATE INDEX idx_grp ON items (grp)").await?;
+    db.execute("CREATE INDEX idx_tag ON items USING HASH (tag)")
+        .await?;
+    let values: Vec<String> = (1..=rows)
+        .map(|id| format!("({id}, {}, 't{}', '{}')", id % 10, id % 7, "x".repeat(200)))
+        .collect();
+    db.execute(&format!("INSERT INTO items VALUES {}", values.join(", ")))
+        .await?;
+    Ok(())
+}
+
+async fn ids(db: &Database, sql: &str) -> Result<Vec<i64>> {
+    let mut ids: Vec<i64> = select_rows(db, sql)
+        .await?
+        .into_iter()
+        .map(|row| match row[0] {
+            Value::Int(id) => id,
+            ref other => panic!("expected an id, got {other:?}"),
+        })
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
+async fn dead_rows(db: &Database, table: &str) -> u64 {
+    let catalog = db.catalog();
+    let catalog = catalog.read().await;
+    catalog.table(table).unwrap().activity().dead_rows
+}
+
+fn int(value: &Value) -> i64 {
+    match value {
+        Value::Int(n) => *n,
+        other => panic!("expected an integer, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn vacuum_reclaims_deleted_rows_and_rebuilds_indexes() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    create_items(&db, 200).await?;
+    db.execute("DELETE FROM items WHERE id <= 150").await?;
+    db.execute("UPDATE items SET pad = 'short' WHERE id = 200")
+        .await?;
+    assert_eq!(dead_rows(&db, "items").await, 151);
+    let heap = temp_dir.path().join("items.heap");
+    let size_before = std::fs::metadata(&heap)?.len();
+
+    let result = select_rows(&db, "VACUUM items").await?;
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0][0], Value::Text("items".into()));
+    assert_eq!(int(&result[0][1]), 50);
+    let (pages_before, pages_after) = (int(&result[0][2]), int(&result[0][3]));
+    assert!(
+        pages_after * 3 < pages_before,
+        "{pages_before} -> {pages_after}"
+    );
+    assert!(std::fs::metadata(&heap)?.len() * 3 < size_before);
+    assert_eq!(dead_rows(&db, "items").await, 0);
+
+    // Every index finds the rows at their new record IDs
+    let remaining: Vec<i64> = (151..=200).collect();
+    assert_eq!(ids(&db, "SELECT id FROM items").await?, remaining);
+    assert_eq!(
+        ids(&db, "SELECT id FROM items WHERE id = 175").await?,
+        vec![175]
+    );
+    let grp_3: Vec<i64> = remaining
+        .iter()
+        .copied()
+        .filter(|id| id % 10 == 3)
+        .collect();
+    for sql in [
+        "SELECT id FROM items WHERE grp = 3",
+        "SELECT id FROM items WHERE tag = 't2'",
+    ] {
+        let plan = select_rows(&db, &format!("EXPLAIN {sql}")).await?;
+        assert!(format!("{plan:?}").contains("IndexScan"), "{plan:?}");
+    }
+    assert_eq!(ids(&db, "SELECT id FROM items WHERE grp = 3").await?, grp_3);
+    let tag_2: Vec<i64> = remaining.iter().copied().filter(|id| id % 7 == 2).collect();
+    assert_eq!(
+        ids(&db, "SELECT id FROM items WHERE tag = 't2'").await?,
+        tag_2
+    );
+    assert_eq!(
+        select_rows(&db, "SELECT pad FROM items WHERE id = 200").await?,
+        vec![vec![Value::Text("short".into())]]
+    );
+
+    // The primary key index still rejects duplicates and accepts new keys
+    let err = db
+        .execute("INSERT INTO items VALUES (160, 0, 't0', '')")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("duplicate"), "{err}");
+    db.execute("INSERT INTO items VALUES (1, 1, 't1', '')")
+        .await?;
+    db.execute("DELETE FROM items WHERE id = 170").await?;
+    let grp_0: Vec<i64> = remaining
+        .iter()
+        .copied()
+        .filter(|id| id % 10 == 0 && *id != 170)
+        .collect();
+    assert_eq!(ids(&db, "SELECT id FROM items WHERE grp = 0").await?, grp_0);
+
+    // The compacted table and its indexes survive a restart
+    drop(db);
+    let db = create_db(temp_dir.path()).await?;
+    assert_eq!(ids(&db, "SELECT id FROM items WHERE grp = 3").await?, grp_3);
+    assert_eq!(
+        ids(&db, "SELECT id FROM items WHERE id = 1").await?,
+        vec![1]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn vacuum_without_a_table_covers_every_table() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    create_items(&db, 30).await?;
+    db.execute(
+        "CREATE TABLE events (id INT PRIMARY KEY, day INT) \
+         PARTITION BY RANGE (day) ( \
+             PARTITION early VALUES LESS THAN (10), \
+             PARTITION late VALUES LESS THAN (MAXVALUE))",
+    )
+    .await?;
+    let values: Vec<String> = (1..=40).map(|id| format!("({id}, {})", id % 20)).collect();
+    db.execute(&format!("INSERT INTO events VALUES {}", values.join(", ")))
+        .await?;
+    db.execute("DELETE FROM events WHERE id <= 20").await?;
+
+    let result = select_rows(&db, "VACUUM").await?;
+    let tables: Vec<_> = result.iter().map(|row| row[0].clone()).collect();
+    assert_eq!(
+        tables,
+        vec![Value::Text("events".into()), Value::Text("items".into())]
+    );
+    // Nothing was deleted from items, so none of its rows moved
+    assert_eq!(int(&result[1][1]), 0);
+    assert!(int(&result[0][1]) > 0);
+
+    assert_eq!(
+        ids(&db, "SELECT id FROM events").await?,
+        (21..=40).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        ids(&db, "SELECT id FROM events WHERE id = 35").await?,
+        vec![35]
+    );
+    assert_eq!(
+        ids(&db, "SELECT id FROM events WHERE day >= 10").await?,
+        (21..=40).filter(|id| id % 20 >= 10).collect::<Vec<_>>()
+    );
+
+    let err = db.execute("VACUUM missing").await.unwrap_err();
+    assert!(err.to_string().contains("unknown table"), "{err}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn dead_rows_past_the_threshold_vacuum_in_background() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let policy = AutoVacuum {
+        min_dead_rows: 20,
+        scale_factor: 0.5,
+    };
+    let db = create_db(temp_dir.path())
+        .await?
+        .with_auto_vacuum(Some(policy));
+    create_items(&db, 60).await?;
+
+    // 20 dead rows do not pass the threshold of 20 + 0.5 * 40 live rows
+    db.execute("DELETE FROM items WHERE id <= 20").await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(dead_rows(&db, "items").await, 20);
+
+    db.execute("DELETE FROM items WHERE id <= 40").await?;
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while dead_rows(&db, "items").await > 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await?;
+
+    assert_eq!(
+        ids(&db, "SELECT id FROM items WHERE grp = 5").await?,
+        vec![45, 55]
+    );
+    assert_eq!(
+        ids(&db, "SELECT id FROM items WHERE id = 60").await?,
+        vec![60]
+    );
+    Ok(())
+}
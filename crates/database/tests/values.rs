@@ -0,0 +1,59 @@
+//! Integration tests for `VALUES` lists and `SELECT` without `FROM`.
+
+use anyhow::Result;
+use database::{Database, QueryResult};
+use types::Value;
+
+async fn select(db: &Database, sql: &str) -> Result<(Vec<String>, Vec<Vec<Value>>)> {
+    match db.execute(sql).await? {
+        QueryResult::Rows { schema, rows, .. } => {
+            Ok((schema, rows.into_iter().map(|r| r.values).collect()))
+        }
+        other => panic!("expected rows, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn select_without_from_returns_one_row() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+
+    let (schema, rows) = select(&db, "SELECT 1 + 1 AS two, 'x'").await?;
+    assert_eq!(schema.len(), 2);
+    assert_eq!(schema[0], "two");
+    assert_eq!(rows, vec![vec![Value::Int(2), Value::Text("x".into())]]);
+
+    let (_, rows) = select(&db, "SELECT NOW()").await?;
+    assert_eq!(rows.len(), 1);
+    assert!(!matches!(rows[0][0], Value::Null));
+
+    // The single row can still be filtered out
+    let (_, rows) = select(&db, "SELECT 1 WHERE 1 > 2").await?;
+    assert!(rows.is_empty());
+
+    assert!(db.execute("SELECT *").await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn values_lists_return_their_rows() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", 10).await?;
+
+    let (schema, rows) = select(&db, "VALUES (1, 'a'), (2, 'b')").await?;
+    assert_eq!(schema, vec!["column1", "column2"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(1), Value::Text("a".into())],
+            vec![Value::Int(2), Value::Text("b".into())],
+        ]
+    );
+
+    let (_, rows) = select(&db, "VALUES (1, 'a'), (2, 'b') ORDER BY column1 DESC").await?;
+    assert_eq!(rows[0], vec![Value::Int(2), Value::Text("b".into())]);
+
+    assert!(db.execute("VALUES (1, 'a'), ('b', 2)").await.is_err());
+    assert!(db.execute("VALUES (1), (2, 3)").await.is_err());
+    Ok(())
+}
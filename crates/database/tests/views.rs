@@ -0,0 +1,139 @@
+//! Integration tests for CREATE VIEW / DROP VIEW and view expansion.
+
+use anyhow::Result;
+use database::Database;
+use types::Value;
+
+mod helpers;
+use helpers::{create_db, select_rows};
+
+fn text(s: &str) -> Value {
+    Value::Text(s.into())
+}
+
+async fn create_users(db: &Database) -> Result<()> {
+    db.execute("CREATE TABLE users (id INT PRIMARY KEY, name TEXT, age INT)")
+        .await?;
+    db.execute("INSERT INTO users VALUES (1, 'Alice', 34), (2, 'Bob', 12), (3, 'Carol', 27)")
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn select_from_view_runs_its_query() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    create_users(&db).await?;
+    db.execute("CREATE VIEW adults AS SELECT id, name AS who FROM users WHERE age >= 18")
+        .await?;
+
+    let rows = select_rows(&db, "SELECT * FROM adults ORDER BY id").await?;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(1), text("Alice")],
+            vec![Value::Int(3), text("Carol")],
+        ]
+    );
+
+    let rows = select_rows(&db, "SELECT UPPER(who) FROM adults WHERE id = 3").await?;
+    assert_eq!(rows, vec![vec![text("CAROL")]]);
+
+    // Views read the table's current rows
+    db.execute("INSERT INTO users VALUES (4, 'Dave', 40)")
+        .await?;
+    db.execute("UPDATE users SET age = 10 WHERE id = 1").await?;
+    let rows = select_rows(&db, "SELECT who FROM adults ORDER BY who").await?;
+    assert_eq!(rows, vec![vec![text("Carol")], vec![text("Dave")]]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn views_nest_and_join_with_tables() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    create_users(&db).await?;
+    db.execute("CREATE TABLE pets (owner INT, pet TEXT)")
+        .await?;
+    db.execute("INSERT INTO pets VALUES (1, 'cat'), (3, 'dog'), (2, 'fish')")
+        .await?;
+    db.execute("CREATE VIEW adults AS SELECT id, name FROM users WHERE age >= 18")
+        .await?;
+    db.execute("CREATE VIEW first_adult AS SELECT name FROM adults ORDER BY id LIMIT 1")
+        .await?;
+
+    let rows = select_rows(&db, "SELECT * FROM first_adult").await?;
+    assert_eq!(rows, vec![vec![text("Alice")]]);
+
+    let rows = select_rows(
+        &db,
+        "SELECT a.name, p.pet FROM adults a JOIN pets p ON a.id = p.owner ORDER BY p.pet",
+    )
+    .await?;
+    assert_eq!(
+        rows,
+        vec![
+            vec![text("Alice"), text("cat")],
+            vec![text("Carol"), text("dog")]
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn views_persist_until_dropped() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    {
+        let db = create_db(temp_dir.path()).await?;
+        create_users(&db).await?;
+        db.execute("CREATE VIEW minors AS SELECT name FROM users WHERE age < 18")
+            .await?;
+    }
+
+    let db = create_db(temp_dir.path()).await?;
+    let rows = select_rows(&db, "SELECT * FROM minors").await?;
+    assert_eq!(rows, vec![vec![text("Bob")]]);
+
+    db.execute("DROP VIEW minors").await?;
+    assert!(db.execute("SELECT * FROM minors").await.is_err());
+    let err = db.execute("DROP VIEW minors").await.unwrap_err();
+    assert!(err.to_string().contains("unknown view 'minors'"), "{err}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn create_view_validates_its_query_and_name() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let db = create_db(temp_dir.path()).await?;
+    create_users(&db).await?;
+
+    let err = db
+        .execute("CREATE VIEW v AS SELECT id FROM missing")
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("invalid query for view 'v'"),
+        "{err}"
+    );
+    let err = db
+        .execute("CREATE VIEW v AS SELECT nope FROM users")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("unknown column 'nope'"), "{err}");
+
+    let err = db
+        .execute("CREATE VIEW users AS SELECT id FROM users")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("already exists"), "{err}");
+
+    db.execute("CREATE VIEW v AS SELECT id FROM users").await?;
+    let err = db.execute("CREATE TABLE v (id INT)").await.unwrap_err();
+    assert!(err.to_string().contains("view 'v' already exists"), "{err}");
+
+    // A view whose table is gone fails when it is used
+    db.execute("DROP TABLE users").await?;
+    let err = db.execute("SELECT * FROM v").await.unwrap_err();
+    assert!(err.to_string().contains("unknown table 'users'"), "{err}");
+    Ok(())
+}
@@ -0,0 +1,453 @@
+//! Aggregate operator: groups rows and computes aggregate functions.
+
+use crate::filter::eval_resolved_expr;
+use crate::spill::{SpillPartitions, SPILL_PARTITIONS};
+use crate::{ExecutionContext, Executor};
+use common::{DbResult, ExecutionStats, Row};
+use expr::aggregate::Accumulator;
+use planner::{ResolvedAggregate, ResolvedExpr};
+use std::collections::HashMap;
+use std::time::Instant;
+use types::Value;
+
+/// Hash aggregate operator - groups input rows by key values and returns one
+/// row per group: its key values followed by its aggregates.
+///
+/// This is a blocking operator: the first call to `next()` reads every input
+/// row into the running state of its group's aggregates (see
+/// [`Accumulator`] for how NULLs are treated). Groups are returned in the
+/// order their first row was read. NULL keys are equal to each other, so
+/// all rows with a NULL key form one group.
+///
+/// Once the groups outgrow work memory, rows of groups not yet seen are
+/// split by key into spill partitions, as a hash join splits its inputs.
+/// The groups held in memory are returned first, then each partition is
+/// aggregated and returned in turn, so groups are no longer in order of
+/// first appearance.
+///
+/// Without keys every row belongs to one group, which is returned even when
+/// there are no rows, so `SELECT COUNT(*)` of an empty table is 0. With keys
+/// an empty input has no groups.
+pub struct HashAggregateExec {
+    input: Box<dyn Executor>,
+    group_by: Vec<ResolvedExpr>,
+    aggregates: Vec<ResolvedAggregate>,
+    schema: Vec<String>,
+    groups: Option<std::vec::IntoIter<Row>>,
+    partitions: Option<Partitions>,
+    stats: ExecutionStats,
+}
+
+impl HashAggregateExec {
+    /// Create a new aggregate operator.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Rows to group
+    /// * `group_by` - Key expressions over input rows; empty for one group
+    /// * `aggregates` - Aggregate calls over input rows
+    /// * `schema` - Output schema: key names, then aggregate names
+    pub fn new(
+        input: Box<dyn Executor>,
+        group_by: Vec<ResolvedExpr>,
+        aggregates: Vec<ResolvedAggregate>,
+        schema: Vec<String>,
+    ) -> Self {
+        Self {
+            input,
+            group_by,
+            aggregates,
+            schema,
+            groups: None,
+            partitions: None,
+            stats: ExecutionStats::default(),
+        }
+    }
+
+    /// Read every input row into the state of its group, returning the
+    /// output rows of the groups held in memory and spilling the rest.
+    fn aggregate(&mut self, ctx: &mut ExecutionContext) -> DbResult<Vec<Row>> {
+        let mut groups = Groups::default();
+        if self.group_by.is_empty() {
+            groups.insert(ctx, Vec::new(), &self.aggregates)?;
+        }
+
+        while let Some(row) = self.input.next(ctx)? {
+            let key = eval_key(&self.group_by, &row)?;
+            let position = match groups.position(&key) {
+                Some(position) => position,
+                None if self.partitions.is_some() => {
+                    self.spill(ctx, &key, &row)?;
+                    continue;
+                }
+                None => {
+                    let position = groups.insert(ctx, key.clone(), &self.aggregates)?;
+                    if !ctx.over_work_memory() {
+                        position
+                    } else {
+                        groups.remove_last(ctx);
+                        self.partitions = Some(Partitions {
+                            files: SpillPartitions::create(ctx)?,
+                            next: 0,
+                        });
+                        self.spill(ctx, &key, &row)?;
+                        continue;
+                    }
+                }
+            };
+            groups.update(position, &self.aggregates, &row)?;
+        }
+
+        if self.partitions.is_some() {
+            groups.release(ctx);
+        }
+        groups.finish()
+    }
+
+    /// Write `row`, of a group not held in memory, to its partition.
+    fn spill(&mut self, ctx: &mut ExecutionContext, key: &[Value], row: &Row) -> DbResult<()> {
+        let partitions = self.partitions.as_mut().expect("spilling");
+        partitions.files.write(ctx, key, row)
+    }
+
+    /// Aggregate the next spilled partition, or return `None` once every
+    /// partition has been.
+    fn next_partition(&mut self, ctx: &mut ExecutionContext) -> DbResult<Option<Vec<Row>>> {
+        let Some(partitions) = &mut self.partitions else {
+            return Ok(None);
+        };
+        if partitions.next == SPILL_PARTITIONS {
+            return Ok(None);
+        }
+        let partition = partitions.next;
+        partitions.next += 1;
+
+        let mut groups = Groups::default();
+        let mut rows = partitions.files.read(partition)?;
+        while let Some(row) = rows.next()? {
+            let key = eval_key(&self.group_by, &row)?;
+            let position = match groups.position(&key) {
+                Some(position) => position,
+                None => groups.insert(ctx, key, &self.aggregates)?,
+            };
+            groups.update(position, &self.aggregates, &row)?;
+        }
+        groups.release(ctx);
+        groups.finish().map(Some)
+    }
+}
+
+/// The spilled rows of a hash aggregate's groups.
+struct Partitions {
+    files: SpillPartitions,
+    /// The next partition to aggregate
+    next: usize,
+}
+
+/// Groups being aggregated, in order of first appearance, with the running
+/// state of their aggregates.
+#[derive(Default)]
+struct Groups {
+    keys: Vec<Vec<Value>>,
+    states: Vec<Vec<Accumulator>>,
+    positions: HashMap<Vec<Value>, usize>,
+}
+
+impl Groups {
+    fn position(&self, key: &[Value]) -> Option<usize> {
+        self.positions.get(key).copied()
+    }
+
+    /// Add a group, charging its key to the statement's memory.
+    fn insert(
+        &mut self,
+        ctx: &mut ExecutionContext,
+        key: Vec<Value>,
+        aggregates: &[ResolvedAggregate],
+    ) -> DbResult<usize> {
+        ctx.charge_memory(&Row::new(key.clone()))?;
+        let position = self.keys.len();
+        self.positions.insert(key.clone(), position);
+        self.keys.push(key);
+        self.states.push(
+            aggregates
+                .iter()
+                .map(|aggregate| Accumulator::new(aggregate.func))
+                .collect(),
+        );
+        Ok(position)
+    }
+
+    /// Remove the group added last, crediting back its memory.
+    fn remove_last(&mut self, ctx: &mut ExecutionContext) {
+        if let Some(key) = self.keys.pop() {
+            self.states.pop();
+            self.positions.remove(&key);
+            ctx.release_memory(&Row::new(key));
+        }
+    }
+
+    fn update(
+        &mut self,
+        position: usize,
+        aggregates: &[ResolvedAggregate],
+        row: &Row,
+    ) -> DbResult<()> {
+        for (aggregate, state) in aggregates.iter().zip(&mut self.states[position]) {
+            match &aggregate.arg {
+                Some(arg) => state.update(Some(&eval_resolved_expr(arg, row)?))?,
+                None => state.update(None)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Credit back the memory of every group's key.
+    fn release(&self, ctx: &mut ExecutionContext) {
+        for key in &self.keys {
+            ctx.release_memory(&Row::new(key.clone()));
+        }
+    }
+
+    /// Each group's output row.
+    fn finish(self) -> DbResult<Vec<Row>> {
+        self.keys
+            .into_iter()
+            .zip(self.states)
+            .map(|(mut values, state)| {
+                for aggregate in &state {
+                    values.push(aggregate.finish()?);
+                }
+                Ok(Row::new(values))
+            })
+            .collect()
+    }
+}
+
+fn eval_key(group_by: &[ResolvedExpr], row: &Row) -> DbResult<Vec<Value>> {
+    group_by
+        .iter()
+        .map(|expr| eval_resolved_expr(expr, row))
+        .collect()
+}
+
+impl Executor for HashAggregateExec {
+    fn open(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
+        let start = Instant::now();
+        self.stats = ExecutionStats::default();
+        self.groups = None;
+        self.partitions = None;
+        self.input.open(ctx)?;
+        self.stats.open_time = start.elapsed();
+        Ok(())
+    }
+
+    fn next(&mut self, ctx: &mut ExecutionContext) -> DbResult<Option<Row>> {
+        let start = Instant::now();
+
+        // Aggregate the whole input on the first call to next()
+        if self.groups.is_none() {
+            // Spilled groups are removed straight away if aggregating fails
+            let groups = match self.aggregate(ctx) {
+                Ok(groups) => groups,
+                Err(e) => {
+                    self.partitions = None;
+                    return Err(e);
+                }
+            };
+            self.groups = Some(groups.into_iter());
+        }
+
+        let mut result = self.groups.as_mut().and_then(Iterator::next);
+        while result.is_none() {
+            let Some(groups) = self.next_partition(ctx)? else {
+                break;
+            };
+            let mut groups = groups.into_iter();
+            result = groups.next();
+            self.groups = Some(groups);
+        }
+        if result.is_some() {
+            self.stats.rows_produced += 1;
+        }
+
+        self.stats.total_next_time += start.elapsed();
+        Ok(result)
+    }
+
+    fn close(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
+        let start = Instant::now();
+        self.groups = None;
+        self.partitions = None;
+        self.input.close(ctx)?;
+        self.stats.close_time = start.elapsed();
+        Ok(())
+    }
+
+    fn schema(&self) -> &[String] {
+        &self.schema
+    }
+
+    fn stats(&self) -> Option<&ExecutionStats> {
+        Some(&self.stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helpers::{
+        assert_exhausted, assert_next_row, setup_test_context, MockExecutor,
+    };
+    use expr::aggregate::AggregateFunc;
+
+    fn input(rows: Vec<Vec<Value>>) -> Box<dyn Executor> {
+        Box::new(MockExecutor::new(
+            rows.into_iter().map(Row::new).collect(),
+            vec!["dept".into(), "salary".into()],
+        ))
+    }
+
+    fn call(func: AggregateFunc, arg: Option<ResolvedExpr>) -> ResolvedAggregate {
+        ResolvedAggregate { func, arg }
+    }
+
+    #[test]
+    fn groups_in_order_of_first_row_and_skips_nulls() {
+        let (mut ctx, _temp) = setup_test_context();
+        let rows = vec![
+            vec![Value::Text("eng".into()), Value::Int(10)],
+            vec![Value::Text("ops".into()), Value::Null],
+            vec![Value::Null, Value::Int(5)],
+            vec![Value::Text("eng".into()), Value::Int(20)],
+            vec![Value::Null, Value::Int(7)],
+        ];
+        let salary = Some(ResolvedExpr::Column(1));
+        let mut exec = HashAggregateExec::new(
+            input(rows),
+            vec![ResolvedExpr::Column(0)],
+            vec![
+                call(AggregateFunc::Count, None),
+                call(AggregateFunc::Count, salary.clone()),
+                call(AggregateFunc::Sum, salary),
+            ],
+            vec![
+                "dept".into(),
+                "count(*)".into(),
+                "count(salary)".into(),
+                "sum(salary)".into(),
+            ],
+        );
+
+        exec.open(&mut ctx).unwrap();
+        let group = |dept: Value, rows, values, sum| {
+            Row::new(vec![dept, Value::Int(rows), Value::Int(values), sum])
+        };
+        assert_next_row(
+            &mut exec,
+            &mut ctx,
+            group(Value::Text("eng".into()), 2, 2, Value::Int(30)),
+        );
+        assert_next_row(
+            &mut exec,
+            &mut ctx,
+            group(Value::Text("ops".into()), 1, 0, Value::Null),
+        );
+        assert_next_row(
+            &mut exec,
+            &mut ctx,
+            group(Value::Null, 2, 2, Value::Int(12)),
+        );
+        assert_exhausted(&mut exec, &mut ctx);
+        exec.close(&mut ctx).unwrap();
+    }
+
+    #[test]
+    fn empty_input_has_one_row_without_keys_and_none_with_them() {
+        let (mut ctx, _temp) = setup_test_context();
+        let aggregates = vec![
+            call(AggregateFunc::Count, None),
+            call(AggregateFunc::Max, Some(ResolvedExpr::Column(1))),
+        ];
+
+        let mut exec = HashAggregateExec::new(
+            input(vec![]),
+            vec![],
+            aggregates.clone(),
+            vec!["count(*)".into(), "max(salary)".into()],
+        );
+        exec.open(&mut ctx).unwrap();
+        assert_next_row(
+            &mut exec,
+            &mut ctx,
+            Row::new(vec![Value::Int(0), Value::Null]),
+        );
+        assert_exhausted(&mut exec, &mut ctx);
+        exec.close(&mut ctx).unwrap();
+
+        let mut exec = HashAggregateExec::new(
+            input(vec![]),
+            vec![ResolvedExpr::Column(0)],
+            aggregates,
+            vec!["dept".into(), "count(*)".into(), "max(salary)".into()],
+        );
+        exec.open(&mut ctx).unwrap();
+        assert_exhausted(&mut exec, &mut ctx);
+        exec.close(&mut ctx).unwrap();
+    }
+
+    /// Sum salaries by department, under `work_memory_bytes` of work memory.
+    fn sum_by_dept(rows: Vec<Vec<Value>>, work_memory_bytes: u64) -> Vec<Vec<Value>> {
+        let (ctx, temp_dir) = setup_test_context();
+        let limits = common::ResourceLimits::builder()
+            .work_memory_bytes(work_memory_bytes)
+            .build();
+        let mut ctx = ctx.with_resource_limits(limits);
+        let mut exec = HashAggregateExec::new(
+            input(rows),
+            vec![ResolvedExpr::Column(0)],
+            vec![call(AggregateFunc::Sum, Some(ResolvedExpr::Column(1)))],
+            vec!["dept".into(), "sum(salary)".into()],
+        );
+
+        exec.open(&mut ctx).unwrap();
+        let mut groups = Vec::new();
+        while let Some(row) = exec.next(&mut ctx).unwrap() {
+            groups.push(row.values);
+        }
+        let usage = ctx.resource_usage();
+        if work_memory_bytes < u64::MAX {
+            assert_eq!(usage.memory_bytes, 0);
+            assert!(usage.spilled_bytes > 0);
+        } else {
+            assert_eq!(usage.spilled_bytes, 0);
+        }
+
+        exec.close(&mut ctx).unwrap();
+        let spill_files = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_string_lossy().starts_with("spill-")
+            })
+            .count();
+        assert_eq!(spill_files, 0);
+        groups.sort();
+        groups
+    }
+
+    #[test]
+    fn spills_groups_by_partition_once_over_work_memory() {
+        let rows: Vec<Vec<Value>> = (0..200)
+            .map(|i| vec![Value::Int(i % 50), Value::Int(i)])
+            .collect();
+
+        let spilled = sum_by_dept(rows.clone(), 1);
+        assert_eq!(spilled, sum_by_dept(rows, u64::MAX));
+        assert_eq!(spilled.len(), 50);
+        assert_eq!(
+            spilled[7],
+            vec![Value::Int(7), Value::Int(7 + 57 + 107 + 157)]
+        );
+    }
+}
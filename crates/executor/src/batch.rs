@@ -0,0 +1,134 @@
+//! Batches of rows passed between operators.
+//!
+//! Pulling one row per [`Executor::next`] call costs a virtual call through
+//! every operator of the tree for each row. [`Executor::next_batch`] pulls
+//! up to [`BATCH_SIZE`] rows at a time instead; scans, filters and
+//! projections handle a whole batch per call, and every other operator
+//! fills its batches from `next` (the trait's default).
+//!
+//! [`Executor::next`]: crate::Executor::next
+//! [`Executor::next_batch`]: crate::Executor::next_batch
+
+use common::Row;
+
+/// Rows a batch holds at most when it is pulled by [`execute_query`](crate::execute_query).
+pub const BATCH_SIZE: usize = 1024;
+
+/// Rows produced together by one call to
+/// [`Executor::next_batch`](crate::Executor::next_batch).
+#[derive(Clone, Debug, Default)]
+pub struct RowBatch {
+    rows: Vec<Row>,
+}
+
+impl RowBatch {
+    /// An empty batch with room for `capacity` rows.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            rows: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, row: Row) {
+        self.rows.push(row);
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    /// Keep only the rows for which `keep` returns true, stopping at the
+    /// first error.
+    pub fn try_retain(
+        &mut self,
+        mut keep: impl FnMut(&Row) -> common::DbResult<bool>,
+    ) -> common::DbResult<()> {
+        let mut result = Ok(());
+        self.rows.retain(|row| {
+            result.is_ok()
+                && match keep(row) {
+                    Ok(keep) => keep,
+                    Err(e) => {
+                        result = Err(e);
+                        false
+                    }
+                }
+        });
+        result
+    }
+
+    /// Replace every row with the result of `f` on it, stopping at the first
+    /// error.
+    pub fn try_map(self, f: impl FnMut(Row) -> common::DbResult<Row>) -> common::DbResult<Self> {
+        Ok(Self {
+            rows: self
+                .rows
+                .into_iter()
+                .map(f)
+                .collect::<common::DbResult<_>>()?,
+        })
+    }
+
+    pub fn into_rows(self) -> Vec<Row> {
+        self.rows
+    }
+}
+
+impl From<Vec<Row>> for RowBatch {
+    fn from(rows: Vec<Row>) -> Self {
+        Self { rows }
+    }
+}
+
+impl IntoIterator for RowBatch {
+    type Item = Row;
+    type IntoIter = std::vec::IntoIter<Row>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::DbError;
+    use types::Value;
+
+    fn batch(values: &[i64]) -> RowBatch {
+        values
+            .iter()
+            .map(|&v| Row::new(vec![Value::Int(v)]))
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    #[test]
+    fn retain_stops_at_the_first_error() {
+        let mut rows = batch(&[1, 2, 3, 4]);
+        rows.try_retain(|row| Ok(row.values[0] != Value::Int(2)))
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+
+        let mut seen = 0;
+        let err = rows
+            .try_retain(|row| {
+                seen += 1;
+                match row.values[0] {
+                    Value::Int(3) => Err(DbError::Executor("boom".into())),
+                    _ => Ok(true),
+                }
+            })
+            .unwrap_err();
+        assert!(matches!(err, DbError::Executor(_)));
+        assert_eq!(seen, 2);
+    }
+}
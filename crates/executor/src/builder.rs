@@ -1,16 +1,18 @@
 //! Builder: constructs executor trees from physical plans.
 
 use crate::{
+    aggregate::HashAggregateExec,
     dml::{DeleteExec, InsertExec, UpdateExec},
     filter::FilterExec,
-    join::NestedLoopJoinExec,
+    join::{HashJoinExec, NestedLoopJoinExec, SemiJoinExec},
     limit::LimitExec,
+    profile::{Profile, Profiler},
     project::ProjectExec,
-    scan::{IndexScanExec, SeqScanExec},
+    scan::{IndexScanExec, SeqScanExec, SeriesScanExec, SystemScanExec, ValuesScanExec},
     sort::{SortExec, SortKey},
     Executor,
 };
-use common::DbResult;
+use common::{DbError, DbResult};
 use planner::PhysicalPlan;
 
 /// Build an executor tree from a physical plan.
@@ -21,49 +23,145 @@ use planner::PhysicalPlan;
 ///
 /// Returns `DbError::Executor` if the plan contains unsupported operators.
 pub fn build_executor(plan: PhysicalPlan) -> DbResult<Box<dyn Executor>> {
+    build(plan, None)
+}
+
+/// Build an executor tree from a physical plan with every operator
+/// profiled, along with the profile it fills in as it runs.
+///
+/// # Errors
+///
+/// Returns `DbError::Executor` if the plan contains unsupported operators.
+pub fn build_profiled_executor(plan: PhysicalPlan) -> DbResult<(Box<dyn Executor>, Profile)> {
+    let mut profiler = Profiler::default();
+    let executor = build(plan, Some(&mut profiler))?;
+    Ok((executor, profiler.finish()))
+}
+
+/// Build the operator for the root of `plan` and its inputs, profiling each
+/// with `profiler` if given.
+fn build(plan: PhysicalPlan, profiler: Option<&mut Profiler>) -> DbResult<Box<dyn Executor>> {
+    let Some(profiler) = profiler else {
+        return build_operator(plan, None);
+    };
+    let label = planner::explain_physical(&plan);
+    let mark = profiler.begin();
+    let executor = build_operator(plan, Some(&mut *profiler))?;
+    Ok(profiler.wrap(mark, &label, executor))
+}
+
+fn build_operator(
+    plan: PhysicalPlan,
+    mut profiler: Option<&mut Profiler>,
+) -> DbResult<Box<dyn Executor>> {
     match plan {
-        PhysicalPlan::SeqScan { table_id, schema } => {
-            Ok(Box::new(SeqScanExec::new(table_id, schema)))
-        }
+        PhysicalPlan::SeqScan {
+            table_id,
+            schema,
+            projection,
+        } => Ok(Box::new(
+            SeqScanExec::new(table_id, schema).with_projection(projection),
+        )),
+
+        PhysicalPlan::SampleScan {
+            table_id,
+            sample,
+            schema,
+            projection,
+        } => Ok(Box::new(
+            SeqScanExec::new(table_id, schema)
+                .with_projection(projection)
+                .with_sample(sample),
+        )),
+
+        PhysicalPlan::PartitionScan {
+            table_id,
+            partitions,
+            schema,
+            projection,
+        } => Ok(Box::new(
+            SeqScanExec::new(table_id, schema)
+                .with_partitions(partitions)
+                .with_projection(projection),
+        )),
 
         PhysicalPlan::IndexScan {
             table_id,
             index_name,
             predicate,
             schema,
+            projection,
         } => Ok(Box::new(
             IndexScanExec::builder()
                 .table_id(table_id)
                 .index_name(index_name)
                 .predicate(predicate)
                 .schema(schema)
+                .maybe_projection(projection)
                 .build(),
         )),
 
+        PhysicalPlan::SystemScan { view, schema } => {
+            Ok(Box::new(SystemScanExec::new(view, schema)))
+        }
+
+        PhysicalPlan::SeriesScan {
+            start,
+            stop,
+            step,
+            schema,
+        } => Ok(Box::new(SeriesScanExec::new(start, stop, step, schema))),
+
+        PhysicalPlan::ValuesScan { rows, schema } => {
+            Ok(Box::new(ValuesScanExec::new(rows, schema)))
+        }
+
+        PhysicalPlan::IndexUnion {
+            table_id,
+            probes,
+            schema,
+            projection,
+        } => {
+            let mut probes = probes.into_iter();
+            let (index_name, predicate) = probes
+                .next()
+                .ok_or_else(|| DbError::Executor("index union without probes".into()))?;
+            Ok(Box::new(
+                IndexScanExec::builder()
+                    .table_id(table_id)
+                    .index_name(index_name)
+                    .predicate(predicate)
+                    .union(probes.collect())
+                    .schema(schema)
+                    .maybe_projection(projection)
+                    .build(),
+            ))
+        }
+
         PhysicalPlan::Filter { input, predicate } => {
-            let child = build_executor(*input)?;
+            let child = build(*input, profiler.as_deref_mut())?;
             Ok(Box::new(FilterExec::new(child, predicate)))
         }
 
         PhysicalPlan::Project { input, columns } => {
-            let child = build_executor(*input)?;
+            let child = build(*input, profiler.as_deref_mut())?;
             Ok(Box::new(ProjectExec::new(child, columns)))
         }
 
-        PhysicalPlan::Insert { table_id, values } => {
+        PhysicalPlan::Insert { table_id, rows } => {
             // No input operator for INSERT
             let schema = vec![]; // INSERT doesn't produce a schema
-            Ok(Box::new(InsertExec::new(table_id, schema, values)))
+            Ok(Box::new(InsertExec::new(table_id, schema, rows)))
         }
 
         PhysicalPlan::Update {
             table_id,
             assignments,
             predicate,
+            source,
         } => {
-            // Build scan + optional filter as input
-            let table_meta = get_table_schema_for_dml_scan(table_id);
-            let mut input: Box<dyn Executor> = Box::new(SeqScanExec::new(table_id, table_meta));
+            // Build scan (or the joined source) + optional filter as input
+            let mut input = build_dml_source(table_id, source, profiler.as_deref_mut())?;
 
             if let Some(pred) = predicate {
                 input = Box::new(FilterExec::new(input, pred));
@@ -83,10 +181,10 @@ pub fn build_executor(plan: PhysicalPlan) -> DbResult<Box<dyn Executor>> {
         PhysicalPlan::Delete {
             table_id,
             predicate,
+            source,
         } => {
-            // Build scan + optional filter as input
-            let table_meta = get_table_schema_for_dml_scan(table_id);
-            let mut input: Box<dyn Executor> = Box::new(SeqScanExec::new(table_id, table_meta));
+            // Build scan (or the joined source) + optional filter as input
+            let mut input = build_dml_source(table_id, source, profiler.as_deref_mut())?;
 
             if let Some(pred) = predicate {
                 input = Box::new(FilterExec::new(input, pred));
@@ -97,23 +195,36 @@ pub fn build_executor(plan: PhysicalPlan) -> DbResult<Box<dyn Executor>> {
         }
 
         PhysicalPlan::Sort { input, order_by } => {
-            let child = build_executor(*input)?;
+            let child = build(*input, profiler.as_deref_mut())?;
             let sort_keys = order_by
                 .into_iter()
                 .map(|o| SortKey {
                     column_id: o.column_id,
                     direction: o.direction,
+                    nulls: o.nulls,
                 })
                 .collect();
             Ok(Box::new(SortExec::new(child, sort_keys)))
         }
 
+        PhysicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+            schema,
+        } => {
+            let child = build(*input, profiler.as_deref_mut())?;
+            Ok(Box::new(HashAggregateExec::new(
+                child, group_by, aggregates, schema,
+            )))
+        }
+
         PhysicalPlan::Limit {
             input,
             limit,
             offset,
         } => {
-            let child = build_executor(*input)?;
+            let child = build(*input, profiler.as_deref_mut())?;
             Ok(Box::new(LimitExec::new(child, limit, offset)))
         }
 
@@ -123,8 +234,8 @@ pub fn build_executor(plan: PhysicalPlan) -> DbResult<Box<dyn Executor>> {
             condition,
             schema,
         } => {
-            let left_child = build_executor(*left)?;
-            let right_child = build_executor(*right)?;
+            let left_child = build(*left, profiler.as_deref_mut())?;
+            let right_child = build(*right, profiler)?;
             Ok(Box::new(NestedLoopJoinExec::new(
                 left_child,
                 right_child,
@@ -132,6 +243,70 @@ pub fn build_executor(plan: PhysicalPlan) -> DbResult<Box<dyn Executor>> {
                 schema,
             )))
         }
+
+        PhysicalPlan::HashJoin {
+            left,
+            right,
+            keys,
+            schema,
+        } => {
+            let left_child = build(*left, profiler.as_deref_mut())?;
+            let right_child = build(*right, profiler)?;
+            Ok(Box::new(HashJoinExec::new(
+                left_child,
+                right_child,
+                keys,
+                schema,
+            )))
+        }
+
+        PhysicalPlan::SemiJoin {
+            left,
+            right,
+            condition,
+            schema,
+        } => {
+            let left_child = build(*left, profiler.as_deref_mut())?;
+            let right_child = build(*right, profiler)?;
+            Ok(Box::new(SemiJoinExec::semi(
+                left_child,
+                right_child,
+                condition,
+                schema,
+            )))
+        }
+
+        PhysicalPlan::AntiJoin {
+            left,
+            right,
+            condition,
+            schema,
+        } => {
+            let left_child = build(*left, profiler.as_deref_mut())?;
+            let right_child = build(*right, profiler)?;
+            Ok(Box::new(SemiJoinExec::anti(
+                left_child,
+                right_child,
+                condition,
+                schema,
+            )))
+        }
+    }
+}
+
+/// The rows an UPDATE or DELETE reads: those of its table, or those of the
+/// table joined to the other tables the statement names.
+fn build_dml_source(
+    table_id: common::TableId,
+    source: Option<Box<PhysicalPlan>>,
+    profiler: Option<&mut Profiler>,
+) -> DbResult<Box<dyn Executor>> {
+    match source {
+        Some(source) => build(*source, profiler),
+        None => {
+            let table_meta = get_table_schema_for_dml_scan(table_id);
+            Ok(Box::new(SeqScanExec::new(table_id, table_meta)))
+        }
     }
 }
 
@@ -164,6 +339,7 @@ mod tests {
         let plan = PhysicalPlan::SeqScan {
             table_id: TableId(1),
             schema: vec!["id".into(), "name".into()],
+            projection: None,
         };
 
         let executor = build_executor(plan);
@@ -178,6 +354,7 @@ mod tests {
         let plan = PhysicalPlan::SeqScan {
             table_id: TableId(1),
             schema: vec![],
+            projection: None,
         };
 
         let executor = build_executor(plan);
@@ -197,6 +374,7 @@ mod tests {
                 value: ResolvedExpr::Literal(Value::Int(42)),
             },
             schema: vec!["id".into()],
+            projection: None,
         };
 
         let executor = build_executor(plan);
@@ -208,6 +386,7 @@ mod tests {
         let input = PhysicalPlan::SeqScan {
             table_id: TableId(1),
             schema: vec!["id".into()],
+            projection: None,
         };
 
         let plan = PhysicalPlan::Filter {
@@ -224,6 +403,7 @@ mod tests {
         let input = PhysicalPlan::SeqScan {
             table_id: TableId(1),
             schema: vec!["id".into(), "age".into()],
+            projection: None,
         };
 
         let predicate = ResolvedExpr::Binary {
@@ -246,11 +426,12 @@ mod tests {
         let input = PhysicalPlan::SeqScan {
             table_id: TableId(1),
             schema: vec!["id".into(), "name".into()],
+            projection: None,
         };
 
         let plan = PhysicalPlan::Project {
             input: Box::new(input),
-            columns: vec![("id".to_string(), 0)],
+            columns: vec![("id".to_string(), ResolvedExpr::Column(0))],
         };
 
         let executor = build_executor(plan);
@@ -262,11 +443,15 @@ mod tests {
         let input = PhysicalPlan::SeqScan {
             table_id: TableId(1),
             schema: vec!["id".into(), "name".into(), "age".into()],
+            projection: None,
         };
 
         let plan = PhysicalPlan::Project {
             input: Box::new(input),
-            columns: vec![("name".to_string(), 1), ("id".to_string(), 0)],
+            columns: vec![
+                ("name".to_string(), ResolvedExpr::Column(1)),
+                ("id".to_string(), ResolvedExpr::Column(0)),
+            ],
         };
 
         let executor = build_executor(plan);
@@ -277,10 +462,10 @@ mod tests {
     fn build_insert() {
         let plan = PhysicalPlan::Insert {
             table_id: TableId(1),
-            values: vec![
+            rows: vec![vec![
                 ResolvedExpr::Literal(Value::Int(1)),
                 ResolvedExpr::Literal(Value::Text("alice".into())),
-            ],
+            ]],
         };
 
         let executor = build_executor(plan);
@@ -294,6 +479,7 @@ mod tests {
             table_id: TableId(1),
             assignments: vec![(0, ResolvedExpr::Literal(Value::Int(100)))],
             predicate: None,
+            source: None,
         };
 
         let executor = build_executor(plan);
@@ -312,6 +498,7 @@ mod tests {
             table_id: TableId(1),
             assignments: vec![(1, ResolvedExpr::Literal(Value::Text("updated".into())))],
             predicate: Some(predicate),
+            source: None,
         };
 
         let executor = build_executor(plan);
@@ -323,6 +510,7 @@ mod tests {
         let plan = PhysicalPlan::Delete {
             table_id: TableId(1),
             predicate: None,
+            source: None,
         };
 
         let executor = build_executor(plan);
@@ -340,6 +528,7 @@ mod tests {
         let plan = PhysicalPlan::Delete {
             table_id: TableId(1),
             predicate: Some(predicate),
+            source: None,
         };
 
         let executor = build_executor(plan);
@@ -351,6 +540,7 @@ mod tests {
         let scan = PhysicalPlan::SeqScan {
             table_id: TableId(1),
             schema: vec!["id".into(), "active".into()],
+            projection: None,
         };
 
         let filter = PhysicalPlan::Filter {
@@ -367,6 +557,7 @@ mod tests {
         let scan = PhysicalPlan::SeqScan {
             table_id: TableId(1),
             schema: vec!["id".into(), "name".into(), "active".into()],
+            projection: None,
         };
 
         let filter = PhysicalPlan::Filter {
@@ -376,7 +567,7 @@ mod tests {
 
         let project = PhysicalPlan::Project {
             input: Box::new(filter),
-            columns: vec![("name".to_string(), 1)],
+            columns: vec![("name".to_string(), ResolvedExpr::Column(1))],
         };
 
         let executor = build_executor(project);
@@ -1,45 +1,61 @@
 //! DML operators: Insert, Update, Delete.
 
-use crate::{filter::eval_resolved_expr, ExecutionContext, Executor};
+use crate::{filter::eval_resolved_expr, ExecutionContext, Executor, UndoRecord};
 use btree::BTreeIndex;
 use catalog::IndexKind;
 use common::{ColumnId, DbResult, ExecutionStats, RecordId, Row, TableId};
 use hash::HashIndex;
 use planner::ResolvedExpr;
-use std::time::Instant;
+use std::{collections::HashSet, time::Instant};
 use storage::HeapTable;
 use types::Value;
 use wal::WalRecord;
 
 /// Update all secondary indexes (BTree and Hash) for a table after an INSERT.
-fn update_indexes_after_insert(
+///
+/// Each index is opened and flushed once for all of `rows`.
+pub(crate) fn update_indexes_after_insert(
     ctx: &ExecutionContext,
     table_id: TableId,
-    row: &Row,
-    rid: RecordId,
+    rows: &[(Row, RecordId)],
 ) -> DbResult<()> {
     let table_meta = ctx.catalog.table_by_id(table_id)?;
 
     for index_meta in &table_meta.indexes {
-        // Extract key columns from the row
-        let key: Vec<Value> = index_meta
-            .columns
-            .iter()
-            .filter_map(|&col_id| row.values.get(col_id as usize).cloned())
-            .collect();
+        // Extract key columns from a row
+        let key = |row: &Row| -> Vec<Value> {
+            index_meta
+                .columns
+                .iter()
+                .filter_map(|&col_id| row.values.get(col_id as usize).cloned())
+                .collect()
+        };
 
         // Open and update the index based on kind
         let index_path = ctx.data_dir.join(format!("index_{}.idx", index_meta.id.0));
         if index_path.exists() {
             match index_meta.kind {
                 IndexKind::BTree => {
-                    let mut btree = BTreeIndex::open(&index_path, index_meta.id)?;
-                    btree.insert(key, rid)?;
+                    let mut btree = BTreeIndex::open_with_key(
+                        &index_path,
+                        index_meta.id,
+                        ctx.catalog.encryption_key(),
+                    )?
+                    .with_compression(table_meta.compression);
+                    for (row, rid) in rows {
+                        btree.insert(key(row), *rid)?;
+                    }
                     btree.flush()?;
                 }
                 IndexKind::Hash => {
-                    let mut hash = HashIndex::open(&index_path, index_meta.id)?;
-                    hash.insert(key, rid)?;
+                    let mut hash = HashIndex::open_with_key(
+                        &index_path,
+                        index_meta.id,
+                        ctx.catalog.encryption_key(),
+                    )?;
+                    for (row, rid) in rows {
+                        hash.insert(key(row), *rid)?;
+                    }
                     hash.flush()?;
                 }
                 // Bitmap and Trie indexes not yet implemented
@@ -52,7 +68,7 @@ fn update_indexes_after_insert(
 }
 
 /// Update all secondary indexes (BTree and Hash) for a table after a DELETE.
-fn update_indexes_after_delete(
+pub(crate) fn update_indexes_after_delete(
     ctx: &ExecutionContext,
     table_id: TableId,
     row: &Row,
@@ -73,12 +89,21 @@ fn update_indexes_after_delete(
         if index_path.exists() {
             match index_meta.kind {
                 IndexKind::BTree => {
-                    let mut btree = BTreeIndex::open(&index_path, index_meta.id)?;
+                    let mut btree = BTreeIndex::open_with_key(
+                        &index_path,
+                        index_meta.id,
+                        ctx.catalog.encryption_key(),
+                    )?
+                    .with_compression(table_meta.compression);
                     btree.delete(&key, rid)?;
                     btree.flush()?;
                 }
                 IndexKind::Hash => {
-                    let mut hash = HashIndex::open(&index_path, index_meta.id)?;
+                    let mut hash = HashIndex::open_with_key(
+                        &index_path,
+                        index_meta.id,
+                        ctx.catalog.encryption_key(),
+                    )?;
                     hash.delete(&key, rid)?;
                     hash.flush()?;
                 }
@@ -93,7 +118,7 @@ fn update_indexes_after_delete(
 
 /// Update all secondary indexes (BTree and Hash) for a table after an UPDATE.
 /// This removes the old entry and inserts the new one.
-fn update_indexes_after_update(
+pub(crate) fn update_indexes_after_update(
     ctx: &ExecutionContext,
     table_id: TableId,
     old_row: &Row,
@@ -122,13 +147,22 @@ fn update_indexes_after_update(
         if index_path.exists() {
             match index_meta.kind {
                 IndexKind::BTree => {
-                    let mut btree = BTreeIndex::open(&index_path, index_meta.id)?;
+                    let mut btree = BTreeIndex::open_with_key(
+                        &index_path,
+                        index_meta.id,
+                        ctx.catalog.encryption_key(),
+                    )?
+                    .with_compression(table_meta.compression);
                     btree.delete(&old_key, old_rid)?;
                     btree.insert(new_key, new_rid)?;
                     btree.flush()?;
                 }
                 IndexKind::Hash => {
-                    let mut hash = HashIndex::open(&index_path, index_meta.id)?;
+                    let mut hash = HashIndex::open_with_key(
+                        &index_path,
+                        index_meta.id,
+                        ctx.catalog.encryption_key(),
+                    )?;
                     hash.delete(&old_key, old_rid)?;
                     hash.insert(new_key, new_rid)?;
                     hash.flush()?;
@@ -142,25 +176,160 @@ fn update_indexes_after_update(
     Ok(())
 }
 
+/// Reject rows that would give a unique secondary index a duplicate key.
+///
+/// `rows` are the rows about to be written and `replaced` are the record ids
+/// they overwrite (empty for INSERT), whose current index entries no longer
+/// count as conflicts. Keys containing NULL never conflict.
+fn check_unique_indexes(
+    ctx: &ExecutionContext,
+    table_id: TableId,
+    rows: &[&Row],
+    replaced: &HashSet<RecordId>,
+) -> DbResult<()> {
+    let table_meta = ctx.catalog.table_by_id(table_id)?;
+
+    for index_meta in table_meta.indexes.iter().filter(|index| index.unique) {
+        let index_path = ctx.data_dir.join(format!("index_{}.idx", index_meta.id.0));
+        if !index_path.exists() {
+            continue;
+        }
+        enum Lookup {
+            BTree(BTreeIndex),
+            Hash(HashIndex),
+        }
+        let mut index = match index_meta.kind {
+            IndexKind::BTree => Lookup::BTree(BTreeIndex::open_with_key(
+                &index_path,
+                index_meta.id,
+                ctx.catalog.encryption_key(),
+            )?),
+            IndexKind::Hash => Lookup::Hash(HashIndex::open_with_key(
+                &index_path,
+                index_meta.id,
+                ctx.catalog.encryption_key(),
+            )?),
+            // Bitmap and Trie indexes not yet implemented
+            IndexKind::Bitmap | IndexKind::Trie => continue,
+        };
+
+        let mut batch_keys = HashSet::with_capacity(rows.len());
+        for row in rows {
+            let key: Vec<Value> = index_meta
+                .columns
+                .iter()
+                .filter_map(|&col_id| row.values.get(col_id as usize).cloned())
+                .collect();
+            if key.iter().any(|value| matches!(value, Value::Null)) {
+                continue;
+            }
+            let existing = match &mut index {
+                Lookup::BTree(btree) => btree.search(&key)?,
+                Lookup::Hash(hash) => hash.search(&key)?,
+            };
+            let taken = existing.iter().any(|rid| !replaced.contains(rid));
+            if taken || !batch_keys.insert(key.clone()) {
+                return Err(common::DbError::Constraint(format!(
+                    "duplicate key value {:?} violates unique index '{}'",
+                    key, index_meta.name
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Fill NULL values in auto-increment columns from their sequences and
+/// record the generated values on the context.
+///
+/// When the column is the table's sole primary key, values already used as
+/// keys by stored rows or by other rows of the batch are skipped: a sequence
+/// can lag behind explicitly supplied keys, or behind values it handed out
+/// before a crash but never saved.
+fn fill_generated_values(
+    ctx: &mut ExecutionContext,
+    table_id: TableId,
+    rows: &mut [Row],
+) -> DbResult<()> {
+    let catalog = ctx.catalog;
+    let table_meta = catalog.table_by_id(table_id)?;
+    let sequences = table_meta
+        .columns()
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, column)| Some((idx, column.sequence.as_deref()?)))
+        .map(|(idx, name)| Ok((idx, catalog.sequence(name)?)))
+        .collect::<DbResult<Vec<_>>>()?;
+    if sequences.is_empty() {
+        return Ok(());
+    }
+
+    let key_column = match table_meta.primary_key.as_deref() {
+        Some(&[col]) => Some(col as usize),
+        _ => None,
+    };
+    let mut batch_keys: HashSet<i64> = rows
+        .iter()
+        .filter_map(|row| match key_column.and_then(|col| row.values.get(col)) {
+            Some(Value::Int(key)) => Some(*key),
+            _ => None,
+        })
+        .collect();
+    let mut pk_index = ctx.pk_index(table_id)?;
+
+    let mut generated = Vec::new();
+    for row in rows.iter_mut() {
+        for &(idx, sequence) in &sequences {
+            if !matches!(row.values.get(idx), Some(Value::Null)) {
+                continue;
+            }
+            let value = loop {
+                let value = sequence.next_value();
+                let in_use = key_column == Some(idx)
+                    && (batch_keys.contains(&value)
+                        || match pk_index.as_deref_mut() {
+                            Some(index) => index.contains(&[Value::Int(value)])?,
+                            None => false,
+                        });
+                if !in_use {
+                    break value;
+                }
+            };
+            if key_column == Some(idx) {
+                batch_keys.insert(value);
+            }
+            row.values[idx] = Value::Int(value);
+            generated.push(value);
+        }
+    }
+    ctx.generated_ids.extend(generated);
+    Ok(())
+}
+
 /// Insert operator - inserts rows into a table with WAL logging.
 ///
-/// Evaluates value expressions and writes to both WAL and storage.
-/// Returns a single row containing the number of inserted rows.
+/// Evaluates value expressions for every VALUES row and writes them to
+/// storage, then updates each secondary index, logs all rows to the WAL with
+/// a single sync and saves the primary key index once. NULL values in
+/// auto-increment columns are taken from the column's sequence and recorded in
+/// [`ExecutionContext::generated_ids`]. Returns a single row containing the
+/// number of inserted rows.
 pub struct InsertExec {
     table_id: TableId,
     schema: Vec<String>,
-    values: Vec<ResolvedExpr>,
+    rows: Vec<Vec<ResolvedExpr>>,
     executed: bool,
     stats: ExecutionStats,
 }
 
 impl InsertExec {
     /// Create a new insert operator.
-    pub fn new(table_id: TableId, schema: Vec<String>, values: Vec<ResolvedExpr>) -> Self {
+    pub fn new(table_id: TableId, schema: Vec<String>, rows: Vec<Vec<ResolvedExpr>>) -> Self {
         Self {
             table_id,
             schema,
-            values,
+            rows,
             executed: false,
             stats: ExecutionStats::default(),
         }
@@ -187,48 +356,77 @@ impl Executor for InsertExec {
 
         // Evaluate value expressions (no row context for INSERT literals)
         let empty_row = Row::new(vec![]);
-        let mut row_values = Vec::with_capacity(self.values.len());
+        let mut rows = self
+            .rows
+            .iter()
+            .map(|exprs| {
+                exprs
+                    .iter()
+                    .map(|expr| eval_resolved_expr(expr, &empty_row))
+                    .collect::<DbResult<Vec<_>>>()
+                    .map(Row::new)
+            })
+            .collect::<DbResult<Vec<_>>>()?;
+        fill_generated_values(ctx, self.table_id, &mut rows)?;
 
-        for expr in &self.values {
-            let value = eval_resolved_expr(expr, &empty_row)?;
-            row_values.push(value);
+        let table_meta = ctx.catalog.table_by_id(self.table_id)?;
+        for row in &mut rows {
+            table_meta.coerce_types(&mut row.values)?;
+            table_meta.check_not_null(&row.values)?;
         }
 
-        let row = Row::new(row_values.clone());
-
-        // 1. Check primary key uniqueness if table has PK
+        // 1. Check primary key uniqueness against the table and within the batch
         if let Some(pk_index) = ctx.pk_index(self.table_id)? {
-            let key = pk_index.extract_key(&row)?;
-            if pk_index.contains(&key) {
-                return Err(common::DbError::Constraint(format!(
-                    "duplicate primary key value: {:?}",
-                    key
-                )));
+            let mut batch_keys = HashSet::with_capacity(rows.len());
+            for row in &rows {
+                let key = pk_index.extract_key(row)?;
+                if pk_index.contains(&key)? || !batch_keys.insert(key.clone()) {
+                    return Err(common::DbError::Constraint(format!(
+                        "duplicate primary key value: {:?}",
+                        key
+                    )));
+                }
             }
         }
+        let new_rows: Vec<&Row> = rows.iter().collect();
+        check_unique_indexes(ctx, self.table_id, &new_rows, &HashSet::new())?;
 
-        // 2. Insert into storage to get RID
-        let rid = {
-            let mut heap_table = ctx.heap_table(self.table_id)?;
-            heap_table.insert(&row)?
-        };
+        let mut inserted = Vec::with_capacity(rows.len());
+        for row in rows {
+            // 2. Insert into storage to get RID
+            let rid = {
+                let mut heap_table = ctx.heap_table(self.table_id)?;
+                heap_table.insert(&row)?
+            };
 
-        // 3. Update PK index with new entry
-        if let Some(pk_index) = ctx.pk_index(self.table_id)? {
-            let key = pk_index.extract_key(&row)?;
-            pk_index.insert(key, rid)?;
+            ctx.record_undo(UndoRecord::Insert {
+                table: self.table_id,
+                rid,
+            });
+
+            // 3. Update PK index with new entry
+            if let Some(pk_index) = ctx.pk_index(self.table_id)? {
+                let key = pk_index.extract_key(&row)?;
+                pk_index.insert(key, rid)?;
+            }
+            inserted.push((row, rid));
         }
 
         // 4. Update secondary indexes
-        update_indexes_after_insert(ctx, self.table_id, &row, rid)?;
+        update_indexes_after_insert(ctx, self.table_id, &inserted)?;
 
-        // 5. Log to WAL after successful insert
-        let wal_record = WalRecord::Insert {
-            table: self.table_id,
-            row: row_values,
-            rid,
-        };
-        ctx.log_dml(wal_record)?;
+        let wal_records: Vec<WalRecord> = inserted
+            .into_iter()
+            .map(|(row, rid)| WalRecord::Insert {
+                table: self.table_id,
+                row: row.values,
+                rid,
+            })
+            .collect();
+
+        // 5. Log to WAL after successful insert (one sync for the whole batch)
+        let inserted = wal_records.len();
+        ctx.log_dml_batch(wal_records)?;
 
         // 6. Save PK index to disk
         ctx.save_pk_index(self.table_id)?;
@@ -236,7 +434,7 @@ impl Executor for InsertExec {
         // Return single row with affected count
         self.stats.rows_produced += 1;
         self.stats.total_next_time += start.elapsed();
-        Ok(Some(Row::new(vec![Value::Int(1)])))
+        Ok(Some(Row::new(vec![Value::Int(inserted as i64)])))
     }
 
     fn close(&mut self, _ctx: &mut ExecutionContext) -> DbResult<()> {
@@ -299,7 +497,10 @@ impl UpdateExec {
     }
 
     /// Apply assignments to a row to produce the updated row.
-    fn apply_assignments(&self, old_row: &Row) -> DbResult<Row> {
+    ///
+    /// If the table has a row version column that the assignments don't set
+    /// explicitly, the version is advanced.
+    fn apply_assignments(&self, old_row: &Row, version_col: Option<ColumnId>) -> DbResult<Row> {
         let mut new_values = old_row.values.clone();
 
         for (col_id, expr) in &self.assignments {
@@ -316,6 +517,12 @@ impl UpdateExec {
             new_values[idx] = value;
         }
 
+        if let Some(col) = version_col {
+            if !self.assignments.iter().any(|(c, _)| *c == col) {
+                catalog::bump_row_version(&mut new_values, col);
+            }
+        }
+
         Ok(Row::new(new_values))
     }
 }
@@ -360,10 +567,32 @@ impl Executor for UpdateExec {
             buffered_rows.push(row);
         }
 
-        // For each buffered row, apply updates
-        for old_row in buffered_rows {
-            let mut new_row = self.apply_assignments(&old_row)?;
+        let table_meta = ctx.catalog.table_by_id(self.table_id)?;
+        let version_col = table_meta.row_version_column();
+        let width = table_meta.columns().len();
+
+        // Compute and validate every new row before writing any of them.
+        // Rows joined to other tables' rows (`UPDATE ... FROM`) carry their
+        // columns after the table's own; a row that matched several of them
+        // is updated once, from the first.
+        let mut matched = HashSet::new();
+        let mut updates = Vec::with_capacity(buffered_rows.len());
+        for mut old_row in buffered_rows {
+            if old_row.rid().is_some_and(|rid| !matched.insert(rid)) {
+                continue;
+            }
+            let mut new_row = self.apply_assignments(&old_row, version_col)?;
+            new_row.values.truncate(width);
+            old_row.values.truncate(width);
+            table_meta.coerce_types(&mut new_row.values)?;
+            table_meta.check_not_null(&new_row.values)?;
+            updates.push((old_row, new_row));
+        }
+        let replaced: HashSet<RecordId> = updates.iter().filter_map(|(old, _)| old.rid()).collect();
+        let new_rows: Vec<&Row> = updates.iter().map(|(_, new)| new).collect();
+        check_unique_indexes(ctx, self.table_id, &new_rows, &replaced)?;
 
+        for (old_row, mut new_row) in updates {
             let Some(rid) = old_row.rid() else {
                 // Mock executors in unit tests don't populate RIDs; just count matches
                 count += 1;
@@ -374,6 +603,21 @@ impl Executor for UpdateExec {
                 heap_table.update(rid, &new_row)?
             };
             new_row.set_rid(Some(new_rid));
+            ctx.record_undo(UndoRecord::Update {
+                table: self.table_id,
+                rid: new_rid,
+                old_rid: rid,
+                old_row: old_row.values.clone(),
+            });
+
+            // Point the PK index at the row if the update moved it
+            if new_rid != rid {
+                if let Some(pk_index) = ctx.pk_index(self.table_id)? {
+                    let key = pk_index.extract_key(&new_row)?;
+                    pk_index.remove(&key)?;
+                    pk_index.insert(key, new_rid)?;
+                }
+            }
 
             // Update secondary indexes
             update_indexes_after_update(ctx, self.table_id, &old_row, &new_row, rid, new_rid)?;
@@ -388,6 +632,7 @@ impl Executor for UpdateExec {
         }
 
         self.executed = true;
+        ctx.save_pk_index(self.table_id)?;
 
         // Return count of matched rows
         self.stats.rows_produced += 1;
@@ -456,6 +701,9 @@ impl Executor for DeleteExec {
         }
 
         let mut count = 0;
+        // A row joined to several rows of the tables in `DELETE ... USING`
+        // comes once for each of them
+        let mut deleted = HashSet::new();
 
         // For each matching row, delete it
         while let Some(row) = self.input.next(ctx)? {
@@ -463,20 +711,31 @@ impl Executor for DeleteExec {
                 count += 1;
                 continue;
             };
+            if !deleted.insert(rid) {
+                continue;
+            }
+
+            // Delete the row before its index entries, so that a failed
+            // write never leaves a visible row the indexes don't know about
+            {
+                let mut heap_table = ctx.heap_table(self.table_id)?;
+                heap_table.delete(rid)?;
+            }
+            ctx.record_undo(UndoRecord::Delete {
+                table: self.table_id,
+                rid,
+                row: row.values.clone(),
+            });
 
             // Remove from PK index if table has primary key
             if let Some(pk_index) = ctx.pk_index(self.table_id)? {
                 let key = pk_index.extract_key(&row)?;
-                pk_index.remove(&key);
+                pk_index.remove(&key)?;
             }
 
             // Remove from secondary indexes
             update_indexes_after_delete(ctx, self.table_id, &row, rid)?;
 
-            {
-                let mut heap_table = ctx.heap_table(self.table_id)?;
-                heap_table.delete(rid)?;
-            }
             ctx.log_dml(WalRecord::Delete {
                 table: self.table_id,
                 rid,
@@ -536,7 +795,7 @@ mod tests {
             lit!(text: "alice"),
             ResolvedExpr::Literal(Value::Bool(true)),
         ];
-        let mut insert = InsertExec::new(table_id, vec![], values);
+        let mut insert = InsertExec::new(table_id, vec![], vec![values]);
 
         insert.open(&mut ctx).unwrap();
 
@@ -553,7 +812,7 @@ mod tests {
         let table_id = TableId(1);
 
         let values = vec![lit!(int: 42)];
-        let mut insert = InsertExec::new(table_id, vec![], values);
+        let mut insert = InsertExec::new(table_id, vec![], vec![values]);
 
         insert.open(&mut ctx).unwrap();
 
@@ -573,7 +832,7 @@ mod tests {
         let table_id = TableId(1);
 
         let values = vec![lit!(int: 1)];
-        let mut insert = InsertExec::new(table_id, vec![], values);
+        let mut insert = InsertExec::new(table_id, vec![], vec![values]);
 
         insert.open(&mut ctx).unwrap();
         assert_next_row(&mut insert, &mut ctx, Row::new(vec![Value::Int(1)]));
@@ -595,7 +854,7 @@ mod tests {
             ResolvedExpr::Literal(Value::Int(100)),
             ResolvedExpr::Literal(Value::Text("test".into())),
         ];
-        let mut insert = InsertExec::new(table_id, vec![], values);
+        let mut insert = InsertExec::new(table_id, vec![], vec![values]);
 
         insert.open(&mut ctx).unwrap();
         assert_next_row(&mut insert, &mut ctx, Row::new(vec![Value::Int(1)]));
@@ -606,7 +865,7 @@ mod tests {
     #[test]
     fn insert_schema_empty() {
         let table_id = TableId(1);
-        let insert = InsertExec::new(table_id, vec![], vec![lit!(int: 1)]);
+        let insert = InsertExec::new(table_id, vec![], vec![vec![lit!(int: 1)]]);
 
         assert_eq!(insert.schema().len(), 0);
     }
@@ -616,7 +875,7 @@ mod tests {
         let (mut ctx, _temp) = setup_test_context();
         let table_id = TableId(1);
 
-        let mut insert = InsertExec::new(table_id, vec![], vec![lit_int(1)]);
+        let mut insert = InsertExec::new(table_id, vec![], vec![vec![lit_int(1)]]);
 
         insert.open(&mut ctx).unwrap();
         assert!(insert.close(&mut ctx).is_ok());
@@ -632,7 +891,7 @@ mod tests {
             lit!(text: "alice"),
             ResolvedExpr::Literal(Value::Bool(true)),
         ];
-        let mut insert = InsertExec::new(table_id, vec![], values);
+        let mut insert = InsertExec::new(table_id, vec![], vec![values]);
 
         insert.open(&mut ctx).unwrap();
         assert_next_row(&mut insert, &mut ctx, Row::new(vec![Value::Int(1)]));
@@ -649,7 +908,7 @@ mod tests {
         let expr = binary!(lit!(int: 10), BinaryOp::Eq, lit!(int: 10));
 
         let values = vec![expr];
-        let mut insert = InsertExec::new(table_id, vec![], values);
+        let mut insert = InsertExec::new(table_id, vec![], vec![values]);
 
         insert.open(&mut ctx).unwrap();
         assert_next_row(&mut insert, &mut ctx, Row::new(vec![Value::Int(1)]));
@@ -657,6 +916,41 @@ mod tests {
         insert.close(&mut ctx).unwrap();
     }
 
+    #[test]
+    fn insert_fills_auto_increment_key_from_sequence() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut catalog = catalog::Catalog::new();
+        let table_id = catalog
+            .create_table(
+                "items",
+                vec![
+                    catalog::Column::new("id", types::SqlType::Int).with_sequence("items_id_seq"),
+                    catalog::Column::new("name", types::SqlType::Text),
+                ],
+                Some(vec![0]),
+            )
+            .unwrap();
+        let catalog = Box::leak(Box::new(catalog));
+        let mut ctx = crate::tests::helpers::create_context_from_catalog(catalog, &temp);
+
+        // The explicit key 1 is skipped by the generated one
+        let rows = vec![
+            vec![ResolvedExpr::Literal(Value::Null), lit!(text: "a")],
+            vec![lit!(int: 1), lit!(text: "b")],
+        ];
+        let mut insert = InsertExec::new(table_id, vec![], rows);
+        insert.open(&mut ctx).unwrap();
+        assert_next_row(&mut insert, &mut ctx, Row::new(vec![Value::Int(2)]));
+
+        let rows = vec![vec![ResolvedExpr::Literal(Value::Null), lit!(text: "c")]];
+        let mut insert = InsertExec::new(table_id, vec![], rows);
+        insert.open(&mut ctx).unwrap();
+        assert_next_row(&mut insert, &mut ctx, Row::new(vec![Value::Int(1)]));
+
+        assert_eq!(ctx.generated_ids(), &[2, 3]);
+        assert_eq!(ctx.catalog.sequence("items_id_seq").unwrap().peek(), 4);
+    }
+
     // UpdateExec tests
 
     #[test]
@@ -1033,11 +1327,11 @@ mod tests {
         for (id, name) in &[(1, "Ada"), (2, "Bob")] {
             let plan = PhysicalPlan::Insert {
                 table_id,
-                values: vec![
+                rows: vec![vec![
                     lit!(int: *id),
                     lit!(text: name),
                     ResolvedExpr::Literal(Value::Bool(true)),
-                ],
+                ]],
             };
             execute_dml(plan, &mut ctx).unwrap();
         }
@@ -1046,6 +1340,7 @@ mod tests {
             table_id,
             assignments: vec![(1, lit!(text: "Ada Lovelace"))],
             predicate: Some(ResolvedExpr::Literal(Value::Bool(true))),
+            source: None,
         };
 
         let count = execute_dml(plan, &mut ctx).unwrap();
@@ -1054,6 +1349,7 @@ mod tests {
         let scan_plan = PhysicalPlan::SeqScan {
             table_id,
             schema: vec!["id".into(), "name".into(), "active".into()],
+            projection: None,
         };
         let rows = execute_query(scan_plan, &mut ctx).unwrap();
         assert_eq!(rows.len(), 2);
@@ -1076,11 +1372,11 @@ mod tests {
         for (id, name, active) in &[(1, "Ada", true), (2, "Bob", false)] {
             let plan = PhysicalPlan::Insert {
                 table_id,
-                values: vec![
+                rows: vec![vec![
                     lit!(int: *id),
                     lit!(text: name),
                     ResolvedExpr::Literal(Value::Bool(*active)),
-                ],
+                ]],
             };
             execute_dml(plan, &mut ctx).unwrap();
         }
@@ -1088,6 +1384,7 @@ mod tests {
         let plan = PhysicalPlan::Delete {
             table_id,
             predicate: Some(ResolvedExpr::Column(2)),
+            source: None,
         };
 
         let count = execute_dml(plan, &mut ctx).unwrap();
@@ -1096,6 +1393,7 @@ mod tests {
         let scan_plan = PhysicalPlan::SeqScan {
             table_id,
             schema: vec!["id".into(), "name".into(), "active".into()],
+            projection: None,
         };
         let rows = execute_query(scan_plan, &mut ctx).unwrap();
         assert_eq!(rows.len(), 1);
@@ -1107,4 +1405,50 @@ mod tests {
             .iter()
             .any(|rec| matches!(rec, WalRecord::Delete { table, .. } if *table == table_id)));
     }
+
+    #[test]
+    fn update_from_joined_rows_updates_each_row_once() {
+        let (mut ctx, _temp) = setup_test_context();
+        let table_id = TableId(1);
+
+        for (id, name) in &[(1, "Ada"), (2, "Bob")] {
+            let plan = PhysicalPlan::Insert {
+                table_id,
+                rows: vec![vec![
+                    lit!(int: *id),
+                    lit!(text: name),
+                    ResolvedExpr::Literal(Value::Bool(true)),
+                ]],
+            };
+            execute_dml(plan, &mut ctx).unwrap();
+        }
+
+        // Every row joins both rows; the first sets its name
+        let schema: Vec<String> = vec!["id".into(), "name".into(), "active".into()];
+        let scan = || PhysicalPlan::SeqScan {
+            table_id,
+            schema: schema.clone(),
+            projection: None,
+        };
+        let plan = PhysicalPlan::Update {
+            table_id,
+            assignments: vec![(1, ResolvedExpr::Column(4))],
+            predicate: None,
+            source: Some(Box::new(PhysicalPlan::NestedLoopJoin {
+                left: Box::new(scan()),
+                right: Box::new(scan()),
+                condition: ResolvedExpr::Literal(Value::Bool(true)),
+                schema: vec![],
+            })),
+        };
+
+        let count = execute_dml(plan, &mut ctx).unwrap();
+        assert_eq!(count, 2);
+
+        let rows = execute_query(scan(), &mut ctx).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert!(rows
+            .iter()
+            .all(|row| row.values.len() == 3 && row.values[1] == Value::Text("Ada".into())));
+    }
 }
@@ -0,0 +1,104 @@
+//! Storage engines available to queries.
+//!
+//! Each table records the engine holding its rows in the catalog (see
+//! [`catalog::EngineKind`]); an [`EngineRegistry`] maps those kinds to
+//! [`TableEngine`] implementations. The slotted heap is always registered.
+//! Partitioned tables are opened as a [`PartitionedTable`] over the storage of
+//! each partition.
+
+use catalog::{EngineKind, TableMeta};
+use common::crypto::EncryptionKey;
+use common::{DbError, DbResult};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use storage::row_format::RowLayout;
+use storage::{HeapEngine, HeapTable, TableEngine};
+
+use crate::partitions::PartitionedTable;
+
+/// Storage engines by kind.
+#[derive(Clone)]
+pub struct EngineRegistry {
+    engines: HashMap<EngineKind, Arc<dyn TableEngine>>,
+}
+
+impl Default for EngineRegistry {
+    fn default() -> Self {
+        let mut engines: HashMap<EngineKind, Arc<dyn TableEngine>> = HashMap::new();
+        engines.insert(EngineKind::Heap, Arc::new(HeapEngine::default()));
+        Self { engines }
+    }
+}
+
+impl std::fmt::Debug for EngineRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.engines.keys()).finish()
+    }
+}
+
+impl EngineRegistry {
+    /// Make `engine` serve tables created on `kind`, replacing any engine
+    /// already registered for it.
+    pub fn with_engine(mut self, kind: EngineKind, engine: Arc<dyn TableEngine>) -> Self {
+        self.engines.insert(kind, engine);
+        self
+    }
+
+    /// The engine serving `kind`.
+    pub fn engine(&self, kind: EngineKind) -> DbResult<&dyn TableEngine> {
+        self.shared(kind).map(|engine| engine.as_ref())
+    }
+
+    fn shared(&self, kind: EngineKind) -> DbResult<&Arc<dyn TableEngine>> {
+        self.engines
+            .get(&kind)
+            .ok_or_else(|| DbError::Storage(format!("storage engine {kind:?} is not available")))
+    }
+
+    /// Open the rows of `table` through its engine.
+    pub fn open(
+        &self,
+        data_dir: &Path,
+        table: &TableMeta,
+        key: Option<&EncryptionKey>,
+    ) -> DbResult<Box<dyn HeapTable>> {
+        let mut heap: Box<dyn HeapTable> = match &table.partitioning {
+            Some(partitioning) => Box::new(PartitionedTable::new(
+                self.shared(table.engine)?.clone(),
+                data_dir.to_path_buf(),
+                table,
+                partitioning,
+                key,
+            )),
+            None => self
+                .engine(table.engine)?
+                .open(data_dir, &table.name, table.id.0, key)?,
+        };
+        if let Some(fillfactor) = table.fillfactor {
+            heap.set_fillfactor(fillfactor);
+        }
+        heap.set_compression(table.compression);
+        heap.set_row_layout(RowLayout::new(
+            table.schema.columns.iter().map(|column| &column.ty),
+        ));
+        Ok(heap)
+    }
+
+    /// Remove the storage of `table`, including every partition's.
+    pub fn drop_table(&self, data_dir: &Path, table: &TableMeta) -> DbResult<()> {
+        let engine = self.engine(table.engine)?;
+        for (name, id) in table.storage_units() {
+            engine.drop_table(data_dir, &name, id.0)?;
+        }
+        Ok(())
+    }
+
+    /// Whether writes to `table` must be logged to survive a restart.
+    ///
+    /// Tables on unregistered engines count as durable.
+    pub fn is_durable(&self, table: &TableMeta) -> bool {
+        self.engine(table.engine)
+            .map_or(true, |engine| engine.durable())
+    }
+}
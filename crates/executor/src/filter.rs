@@ -1,6 +1,6 @@
 //! Filter operator: applies WHERE predicates.
 
-use crate::{ExecutionContext, Executor};
+use crate::{ExecutionContext, Executor, RowBatch};
 use common::{DbResult, ExecutionStats, Row};
 use planner::ResolvedExpr;
 use std::time::Instant;
@@ -73,6 +73,25 @@ impl Executor for FilterExec {
         }
     }
 
+    fn next_batch(&mut self, ctx: &mut ExecutionContext, max: usize) -> DbResult<Option<RowBatch>> {
+        let start = Instant::now();
+
+        // Pull batches until one has a row left after filtering
+        while let Some(mut batch) = self.input.next_batch(ctx, max)? {
+            let pulled = batch.len();
+            batch.try_retain(|row| self.eval_predicate(row))?;
+            self.stats.rows_filtered += (pulled - batch.len()) as u64;
+            if !batch.is_empty() {
+                self.stats.rows_produced += batch.len() as u64;
+                self.stats.total_next_time += start.elapsed();
+                return Ok(Some(batch));
+            }
+        }
+
+        self.stats.total_next_time += start.elapsed();
+        Ok(None)
+    }
+
     fn close(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
         let start = Instant::now();
         self.input.close(ctx)?;
@@ -118,6 +137,45 @@ pub fn eval_resolved_expr(expr: &ResolvedExpr, row: &Row) -> DbResult<Value> {
             let right_val = eval_resolved_expr(right, row)?;
             eval_binary_op(left_val, *op, right_val)
         }
+        ResolvedExpr::Function { name, args } => {
+            let func = expr::functions::lookup(name)
+                .ok_or_else(|| common::DbError::Executor(format!("unknown function '{}'", name)))?;
+            let values = args
+                .iter()
+                .map(|arg| eval_resolved_expr(arg, row))
+                .collect::<DbResult<Vec<_>>>()?;
+            func.invoke(&values)
+        }
+        ResolvedExpr::Cast { expr, ty } => eval_resolved_expr(expr, row)?
+            .cast(ty)
+            .map_err(common::DbError::Executor),
+        ResolvedExpr::IsNull { expr, negated } => Ok(Value::Bool(
+            (eval_resolved_expr(expr, row)? == Value::Null) != *negated,
+        )),
+        ResolvedExpr::Case {
+            operand,
+            branches,
+            else_result,
+        } => {
+            let operand = operand
+                .as_deref()
+                .map(|o| eval_resolved_expr(o, row))
+                .transpose()?;
+            for (when, then) in branches {
+                let when = eval_resolved_expr(when, row)?;
+                let condition = match &operand {
+                    Some(operand) => eval_binary_op(operand.clone(), expr::BinaryOp::Eq, when)?,
+                    None => when,
+                };
+                if expr::case_branch_taken(condition)? {
+                    return eval_resolved_expr(then, row);
+                }
+            }
+            match else_result {
+                Some(else_result) => eval_resolved_expr(else_result, row),
+                None => Ok(Value::Null),
+            }
+        }
     }
 }
 
@@ -153,6 +211,44 @@ fn eval_binary_op(left: Value, op: expr::BinaryOp, right: Value) -> DbResult<Val
         (Value::Int(a), BinaryOp::Gt, Value::Int(b)) => Ok(Value::Bool(a > b)),
         (Value::Int(a), BinaryOp::Ge, Value::Int(b)) => Ok(Value::Bool(a >= b)),
 
+        // Floats and decimals compare with numbers by numeric value, and dates and
+        // timestamps with each other by instant and with text that parses
+        // as one, and byte strings byte by byte
+        (
+            left,
+            op @ (BinaryOp::Eq
+            | BinaryOp::Ne
+            | BinaryOp::Lt
+            | BinaryOp::Le
+            | BinaryOp::Gt
+            | BinaryOp::Ge),
+            right,
+        ) if matches!(
+            (&left, &right),
+            (
+                Value::Int(_) | Value::Float(_) | Value::Decimal(_),
+                Value::Int(_) | Value::Float(_) | Value::Decimal(_)
+            ) | (Value::Date(_) | Value::Timestamp(_), _)
+                | (_, Value::Date(_) | Value::Timestamp(_))
+                | (Value::Bytes(_), Value::Bytes(_))
+        ) =>
+        {
+            let Some(ord) = left.cmp_same_type(&right) else {
+                return Err(common::DbError::Executor(format!(
+                    "invalid binary operation: {:?} {:?} {:?}",
+                    left, op, right
+                )));
+            };
+            Ok(Value::Bool(match op {
+                BinaryOp::Eq => ord.is_eq(),
+                BinaryOp::Ne => ord.is_ne(),
+                BinaryOp::Lt => ord.is_lt(),
+                BinaryOp::Le => ord.is_le(),
+                BinaryOp::Gt => ord.is_gt(),
+                _ => ord.is_ge(),
+            }))
+        }
+
         (Value::Text(a), BinaryOp::Eq, Value::Text(b)) => Ok(Value::Bool(a == b)),
         (Value::Text(a), BinaryOp::Ne, Value::Text(b)) => Ok(Value::Bool(a != b)),
 
@@ -163,6 +259,8 @@ fn eval_binary_op(left: Value, op: expr::BinaryOp, right: Value) -> DbResult<Val
         (Value::Bool(a), BinaryOp::And, Value::Bool(b)) => Ok(Value::Bool(a && b)),
         (Value::Bool(a), BinaryOp::Or, Value::Bool(b)) => Ok(Value::Bool(a || b)),
 
+        (left, op, right) if op.is_arithmetic() => expr::arithmetic::apply(&left, op, &right),
+
         (left, op, right) => Err(common::DbError::Executor(format!(
             "invalid binary operation: {:?} {:?} {:?}",
             left, op, right
@@ -178,6 +276,7 @@ mod tests {
     };
     use expr::{BinaryOp, UnaryOp};
     use testsupport::prelude::*;
+    use types::SqlType;
 
     // ===== FilterExec Tests =====
 
@@ -206,6 +305,34 @@ mod tests {
         filter.close(&mut ctx).unwrap();
     }
 
+    #[test]
+    fn filter_batches_skip_batches_with_no_match() {
+        // Only the third batch of two has a row with value > 35
+        let rows = (1..=5).map(|i| int_row(&[i, i * 10])).collect();
+        let input = Box::new(MockExecutor::new(rows, vec!["id".into(), "value".into()]));
+        let predicate = binary(col(1), BinaryOp::Gt, lit!(int: 35));
+        let mut filter = FilterExec::new(input, predicate);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let catalog = create_test_catalog();
+        let mut pager = buffer::FilePager::new(temp_dir.path(), 10);
+        let mut wal = wal::Wal::open(temp_dir.path().join("test.wal")).unwrap();
+        let mut ctx = ExecutionContext::new(&catalog, &mut pager, &mut wal, temp_dir.path().into());
+
+        filter.open(&mut ctx).unwrap();
+        let batch = filter.next_batch(&mut ctx, 2).unwrap().unwrap();
+        let values: Vec<_> = batch.rows().iter().map(|row| row.values.clone()).collect();
+        assert_eq!(values, vec![int_row(&[4, 40]).values]);
+        let batch = filter.next_batch(&mut ctx, 2).unwrap().unwrap();
+        assert_eq!(batch.rows()[0].values, int_row(&[5, 50]).values);
+        assert!(filter.next_batch(&mut ctx, 2).unwrap().is_none());
+
+        let stats = filter.stats().unwrap();
+        assert_eq!(stats.rows_produced, 2);
+        assert_eq!(stats.rows_filtered, 3);
+        filter.close(&mut ctx).unwrap();
+    }
+
     #[test]
     fn filter_blocks_non_matching_rows() {
         let rows = vec![int_row(&[1, 10]), int_row(&[2, 20])];
@@ -444,6 +571,176 @@ mod tests {
         assert_eq!(eval_resolved_expr(&expr, &row).unwrap(), Value::Bool(false));
     }
 
+    // ===== Binary Operations - Float Comparison =====
+
+    #[test]
+    fn eval_float_comparisons() {
+        let row = Row::new(vec![]);
+        let expr = binary(
+            lit!(Value::Float(1.5)),
+            BinaryOp::Lt,
+            lit!(Value::Float(2.5)),
+        );
+        assert_eq!(eval_resolved_expr(&expr, &row).unwrap(), Value::Bool(true));
+
+        let expr = binary(
+            lit!(Value::Float(-0.0)),
+            BinaryOp::Eq,
+            lit!(Value::Float(0.0)),
+        );
+        assert_eq!(eval_resolved_expr(&expr, &row).unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn eval_int_float_compare_by_value() {
+        let row = Row::new(vec![]);
+        let expr = binary(lit!(int: 2), BinaryOp::Eq, lit!(Value::Float(2.0)));
+        assert_eq!(eval_resolved_expr(&expr, &row).unwrap(), Value::Bool(true));
+
+        let expr = binary(lit!(Value::Float(2.5)), BinaryOp::Gt, lit!(int: 2));
+        assert_eq!(eval_resolved_expr(&expr, &row).unwrap(), Value::Bool(true));
+
+        let expr = binary(lit!(int: 3), BinaryOp::Le, lit!(Value::Float(2.5)));
+        assert_eq!(eval_resolved_expr(&expr, &row).unwrap(), Value::Bool(false));
+    }
+
+    #[test]
+    fn eval_temporal_comparisons() {
+        let row = Row::new(vec![]);
+        let expr = binary(
+            lit!(Value::Date(19844)),
+            BinaryOp::Lt,
+            lit!(Value::Date(19845)),
+        );
+        assert_eq!(eval_resolved_expr(&expr, &row).unwrap(), Value::Bool(true));
+
+        // A date equals midnight of the same day
+        let expr = binary(
+            lit!(Value::Date(1)),
+            BinaryOp::Eq,
+            lit!(Value::Timestamp(86_400_000_000)),
+        );
+        assert_eq!(eval_resolved_expr(&expr, &row).unwrap(), Value::Bool(true));
+
+        // Text is read as a timestamp when compared with one
+        let expr = binary(
+            lit!(Value::Timestamp(0)),
+            BinaryOp::Ge,
+            lit!(text: "1970-01-01 00:00:00"),
+        );
+        assert_eq!(eval_resolved_expr(&expr, &row).unwrap(), Value::Bool(true));
+
+        let expr = binary(lit!(Value::Date(0)), BinaryOp::Lt, lit!(int: 1));
+        assert!(eval_resolved_expr(&expr, &row).is_err());
+    }
+
+    #[test]
+    fn eval_bytes_comparisons() {
+        let row = Row::new(vec![]);
+        let expr = binary(
+            lit!(Value::Bytes(vec![0xbe, 0xef])),
+            BinaryOp::Gt,
+            lit!(Value::Bytes(vec![0xbe])),
+        );
+        assert_eq!(eval_resolved_expr(&expr, &row).unwrap(), Value::Bool(true));
+
+        let expr = binary(
+            lit!(Value::Bytes(b"hi".to_vec())),
+            BinaryOp::Eq,
+            lit!(text: "hi"),
+        );
+        assert!(eval_resolved_expr(&expr, &row).is_err());
+    }
+
+    #[test]
+    fn eval_arithmetic() {
+        let row = Row::new(vec![
+            Value::Decimal(types::Decimal::new(1999, 2)),
+            Value::Int(3),
+        ]);
+        let expr = binary(col(0), BinaryOp::Mul, col(1));
+        assert_eq!(
+            eval_resolved_expr(&expr, &row).unwrap(),
+            Value::Decimal(types::Decimal::new(5997, 2))
+        );
+
+        // Arithmetic nests inside comparisons
+        let expr = binary(
+            binary(col(1), BinaryOp::Add, lit!(int: 1)),
+            BinaryOp::Eq,
+            lit!(int: 4),
+        );
+        assert_eq!(eval_resolved_expr(&expr, &row).unwrap(), Value::Bool(true));
+
+        let expr = binary(lit!(int: 1), BinaryOp::Div, lit!(int: 0));
+        assert!(eval_resolved_expr(&expr, &row).is_err());
+        let expr = binary(lit!(Value::Null), BinaryOp::Sub, lit!(int: 1));
+        assert_eq!(eval_resolved_expr(&expr, &row).unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn eval_cast() {
+        let row = Row::new(vec![]);
+        let expr = ResolvedExpr::Cast {
+            expr: Box::new(lit!(text: "2024-05-01")),
+            ty: SqlType::Date,
+        };
+        assert_eq!(eval_resolved_expr(&expr, &row).unwrap(), Value::Date(19844));
+
+        let expr = ResolvedExpr::Cast {
+            expr: Box::new(lit!(text: "tomorrow")),
+            ty: SqlType::Date,
+        };
+        assert!(eval_resolved_expr(&expr, &row).is_err());
+    }
+
+    #[test]
+    fn eval_case_evaluates_only_the_taken_branch() {
+        let row = Row::new(vec![Value::Int(2), Value::Null]);
+        // The ELSE branch would fail if it were evaluated
+        let expr = ResolvedExpr::Case {
+            operand: Some(Box::new(ResolvedExpr::Column(0))),
+            branches: vec![
+                (lit!(int: 1), lit!(text: "one")),
+                (lit!(int: 2), lit!(text: "two")),
+            ],
+            else_result: Some(Box::new(binary(lit!(int: 1), BinaryOp::Div, lit!(int: 0)))),
+        };
+        assert_eq!(
+            eval_resolved_expr(&expr, &row).unwrap(),
+            Value::Text("two".into())
+        );
+
+        let expr = ResolvedExpr::Case {
+            operand: None,
+            branches: vec![(ResolvedExpr::Column(1), lit!(text: "null"))],
+            else_result: None,
+        };
+        assert_eq!(eval_resolved_expr(&expr, &row).unwrap(), Value::Null);
+
+        let expr = ResolvedExpr::Case {
+            operand: None,
+            branches: vec![(ResolvedExpr::Column(0), lit!(text: "two"))],
+            else_result: None,
+        };
+        assert!(eval_resolved_expr(&expr, &row).is_err());
+    }
+
+    #[test]
+    fn eval_is_null() {
+        let row = Row::new(vec![Value::Int(2), Value::Null]);
+        for (column, negated, expected) in [(0, false, false), (1, false, true), (1, true, false)] {
+            let expr = ResolvedExpr::IsNull {
+                expr: Box::new(ResolvedExpr::Column(column)),
+                negated,
+            };
+            assert_eq!(
+                eval_resolved_expr(&expr, &row).unwrap(),
+                Value::Bool(expected)
+            );
+        }
+    }
+
     // ===== Binary Operations - Text Comparison =====
 
     #[test]
@@ -1,9 +1,12 @@
 //! Join operators: combines rows from multiple tables.
 
 use crate::filter::eval_resolved_expr;
+use crate::spill::{hash_key, SpillFile, SpillPartitions, SpillReader, SPILL_PARTITIONS};
 use crate::{ExecutionContext, Executor};
 use common::{DbResult, ExecutionStats, Row};
 use planner::ResolvedExpr;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::time::Instant;
 use types::Value;
 
@@ -24,6 +27,10 @@ use types::Value;
 /// - Time: O(n * m) where n = left rows, m = right rows
 /// - Space: O(m) to materialize right side
 ///
+/// Once the statement's buffered rows exceed its work memory, the right side
+/// is spilled to a temporary file instead, which is read through once per
+/// left row.
+///
 /// This is the simplest join algorithm, suitable for small tables or when no
 /// better access method is available. More sophisticated algorithms (HashJoin,
 /// MergeJoin) would be used for larger datasets.
@@ -35,8 +42,7 @@ pub struct NestedLoopJoinExec {
 
     // State
     current_left_row: Option<Row>,
-    right_materialized: Vec<Row>,
-    right_cursor: usize,
+    right: MaterializedRows,
     stats: ExecutionStats,
 }
 
@@ -61,37 +67,10 @@ impl NestedLoopJoinExec {
             condition,
             schema,
             current_left_row: None,
-            right_materialized: Vec::new(),
-            right_cursor: 0,
+            right: MaterializedRows::default(),
             stats: ExecutionStats::default(),
         }
     }
-
-    /// Combine a left and right row into a single row.
-    ///
-    /// The combined row has all columns from the left row first,
-    /// followed by all columns from the right row.
-    fn combine_rows(&self, left: &Row, right: &Row) -> Row {
-        let mut combined_values = left.values.clone();
-        combined_values.extend(right.values.clone());
-        Row::new(combined_values)
-    }
-
-    /// Evaluate the join condition against a combined row.
-    ///
-    /// Returns true if the rows should be joined, false otherwise.
-    /// NULL condition results are treated as false (SQL semantics).
-    fn eval_condition(&self, row: &Row) -> DbResult<bool> {
-        let result = eval_resolved_expr(&self.condition, row)?;
-        match result {
-            Value::Bool(b) => Ok(b),
-            Value::Null => Ok(false),
-            other => Err(common::DbError::Executor(format!(
-                "join condition must evaluate to boolean, got {:?}",
-                other
-            ))),
-        }
-    }
 }
 
 impl Executor for NestedLoopJoinExec {
@@ -104,14 +83,11 @@ impl Executor for NestedLoopJoinExec {
         self.right_input.open(ctx)?;
 
         // Materialize right side for repeated iteration
-        self.right_materialized.clear();
-        while let Some(row) = self.right_input.next(ctx)? {
-            self.right_materialized.push(row);
-        }
+        self.right.fill(self.right_input.as_mut(), ctx)?;
 
         // Get first left row
         self.current_left_row = self.left_input.next(ctx)?;
-        self.right_cursor = 0;
+        self.right.rewind()?;
 
         self.stats.open_time = start.elapsed();
         Ok(())
@@ -131,14 +107,8 @@ impl Executor for NestedLoopJoinExec {
             };
 
             // Try to find matching right row
-            while self.right_cursor < self.right_materialized.len() {
-                let right_row = &self.right_materialized[self.right_cursor];
-                self.right_cursor += 1;
-
-                // Combine rows and evaluate join condition
-                let combined = self.combine_rows(&left_row, right_row);
-
-                if self.eval_condition(&combined)? {
+            while let Some(combined) = self.right.next_combined(&left_row)? {
+                if condition_holds(&self.condition, &combined)? {
                     self.stats.rows_produced += 1;
                     self.stats.total_next_time += start.elapsed();
                     return Ok(Some(combined));
@@ -147,14 +117,16 @@ impl Executor for NestedLoopJoinExec {
 
             // Exhausted right side for current left row, advance left
             self.current_left_row = self.left_input.next(ctx)?;
-            self.right_cursor = 0;
+            if self.current_left_row.is_some() {
+                self.right.rewind()?;
+            }
         }
     }
 
     fn close(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
         let start = Instant::now();
 
-        self.right_materialized.clear();
+        self.right = MaterializedRows::default();
         self.current_left_row = None;
         self.left_input.close(ctx)?;
         self.right_input.close(ctx)?;
@@ -172,6 +144,512 @@ impl Executor for NestedLoopJoinExec {
     }
 }
 
+/// Semi or anti join: each left row once, on its own, if some right row
+/// satisfies the condition with it (semi) or if none does (anti).
+///
+/// Used for `EXISTS`, `IN` and `NOT EXISTS` subqueries, which ask whether a
+/// match exists rather than for the matches. Unlike an inner join, a left row
+/// matching many right rows is returned once, and scanning the right side for
+/// it stops at the first match.
+///
+/// The right side is materialized, and spilled past work memory, as in
+/// [`NestedLoopJoinExec`].
+pub struct SemiJoinExec {
+    left_input: Box<dyn Executor>,
+    right_input: Box<dyn Executor>,
+    condition: ResolvedExpr,
+    anti: bool,
+    schema: Vec<String>,
+
+    // State
+    right: MaterializedRows,
+    stats: ExecutionStats,
+}
+
+impl SemiJoinExec {
+    /// Create a semi join, returning the left rows with a match.
+    ///
+    /// `condition` is evaluated against a left row followed by a right row;
+    /// `schema` is the left input's.
+    pub fn semi(
+        left: Box<dyn Executor>,
+        right: Box<dyn Executor>,
+        condition: ResolvedExpr,
+        schema: Vec<String>,
+    ) -> Self {
+        Self::new(left, right, condition, false, schema)
+    }
+
+    /// Create an anti join, returning the left rows without a match.
+    pub fn anti(
+        left: Box<dyn Executor>,
+        right: Box<dyn Executor>,
+        condition: ResolvedExpr,
+        schema: Vec<String>,
+    ) -> Self {
+        Self::new(left, right, condition, true, schema)
+    }
+
+    fn new(
+        left: Box<dyn Executor>,
+        right: Box<dyn Executor>,
+        condition: ResolvedExpr,
+        anti: bool,
+        schema: Vec<String>,
+    ) -> Self {
+        Self {
+            left_input: left,
+            right_input: right,
+            condition,
+            anti,
+            schema,
+            right: MaterializedRows::default(),
+            stats: ExecutionStats::default(),
+        }
+    }
+
+    /// Whether some right row satisfies the condition with `left`.
+    fn has_match(&mut self, left: &Row) -> DbResult<bool> {
+        self.right.rewind()?;
+        while let Some(combined) = self.right.next_combined(left)? {
+            if condition_holds(&self.condition, &combined)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl Executor for SemiJoinExec {
+    fn open(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
+        let start = Instant::now();
+        self.stats = ExecutionStats::default();
+
+        self.left_input.open(ctx)?;
+        self.right_input.open(ctx)?;
+        self.right.fill(self.right_input.as_mut(), ctx)?;
+
+        self.stats.open_time = start.elapsed();
+        Ok(())
+    }
+
+    fn next(&mut self, ctx: &mut ExecutionContext) -> DbResult<Option<Row>> {
+        let start = Instant::now();
+
+        while let Some(left_row) = self.left_input.next(ctx)? {
+            if self.has_match(&left_row)? != self.anti {
+                self.stats.rows_produced += 1;
+                self.stats.total_next_time += start.elapsed();
+                return Ok(Some(left_row));
+            }
+        }
+        self.stats.total_next_time += start.elapsed();
+        Ok(None)
+    }
+
+    fn close(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
+        let start = Instant::now();
+
+        self.right = MaterializedRows::default();
+        self.left_input.close(ctx)?;
+        self.right_input.close(ctx)?;
+
+        self.stats.close_time = start.elapsed();
+        Ok(())
+    }
+
+    fn schema(&self) -> &[String] {
+        &self.schema
+    }
+
+    fn stats(&self) -> Option<&ExecutionStats> {
+        Some(&self.stats)
+    }
+}
+
+/// Hash join operator - joins the rows of the two inputs with equal keys.
+///
+/// # Algorithm
+///
+/// 1. `open()`: Read all right rows into a hash table on their key values.
+/// 2. `next()`: For each left row, look up the right rows with its key
+///    values and return each of them combined with it.
+///
+/// A row with a NULL key matches nothing, as `NULL = NULL` is not true.
+/// Keys hash by value across types, so an INT finds an equal FLOAT or
+/// DECIMAL and a DATE an equal TIMESTAMP; rows that hash alike are compared
+/// exactly before they are joined.
+///
+/// # Performance
+///
+/// - Time: O(n + m) where n = left rows, m = right rows, plus the matches
+/// - Space: O(m) for the hash table
+///
+/// Once the statement's buffered rows exceed its work memory while the
+/// table is built, both inputs are split by key hash into partitions
+/// spilled to temporary files, and the partitions are joined one at a time,
+/// so only one partition's right rows are held in memory.
+pub struct HashJoinExec {
+    left_input: Box<dyn Executor>,
+    right_input: Box<dyn Executor>,
+    left_keys: Vec<ResolvedExpr>,
+    right_keys: Vec<ResolvedExpr>,
+    schema: Vec<String>,
+
+    // State
+    table: HashTable,
+    partitions: Option<Partitions>,
+    /// The left row being joined, its keys, and the right rows it may match
+    current: Option<(Row, Vec<Value>, std::vec::IntoIter<usize>)>,
+    stats: ExecutionStats,
+}
+
+impl HashJoinExec {
+    /// Create a new hash join operator.
+    ///
+    /// # Arguments
+    ///
+    /// * `left` - Left (probe) input executor
+    /// * `right` - Right (build) input executor, will be hashed
+    /// * `keys` - Pairs of keys that must be equal: one over left rows, one
+    ///   over right rows
+    /// * `schema` - Combined output schema (left columns followed by right columns)
+    pub fn new(
+        left: Box<dyn Executor>,
+        right: Box<dyn Executor>,
+        keys: Vec<(ResolvedExpr, ResolvedExpr)>,
+        schema: Vec<String>,
+    ) -> Self {
+        let (left_keys, right_keys) = keys.into_iter().unzip();
+        Self {
+            left_input: left,
+            right_input: right,
+            left_keys,
+            right_keys,
+            schema,
+            table: HashTable::default(),
+            partitions: None,
+            current: None,
+            stats: ExecutionStats::default(),
+        }
+    }
+
+    /// Hash the right input, partitioning both inputs if it outgrows work
+    /// memory.
+    fn build(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
+        while let Some(row) = self.right_input.next(ctx)? {
+            let Some(key) = eval_keys(&self.right_keys, &row)? else {
+                continue;
+            };
+            match &mut self.partitions {
+                Some(partitions) => partitions.right.write(ctx, &key, &row)?,
+                None => {
+                    ctx.charge_memory(&row)?;
+                    self.table.insert(row, key);
+                    if ctx.over_work_memory() {
+                        self.spill(ctx)?;
+                    }
+                }
+            }
+        }
+
+        if let Some(partitions) = &mut self.partitions {
+            while let Some(row) = self.left_input.next(ctx)? {
+                if let Some(key) = eval_keys(&self.left_keys, &row)? {
+                    partitions.left.write(ctx, &key, &row)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Move the hashed right rows to spill partitions, crediting back their
+    /// memory.
+    fn spill(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
+        let mut partitions = Partitions {
+            left: SpillPartitions::create(ctx)?,
+            right: SpillPartitions::create(ctx)?,
+            next: 0,
+            reader: None,
+        };
+        for (row, key) in self.table.take(ctx) {
+            partitions.right.write(ctx, &key, &row)?;
+        }
+        self.partitions = Some(partitions);
+        Ok(())
+    }
+
+    /// The next left row to join: from the left input, or once spilled from
+    /// the left side of each partition in turn, with the partition's right
+    /// rows hashed first.
+    fn next_left(&mut self, ctx: &mut ExecutionContext) -> DbResult<Option<Row>> {
+        let Some(partitions) = &mut self.partitions else {
+            return self.left_input.next(ctx);
+        };
+        loop {
+            if let Some(reader) = &mut partitions.reader {
+                if let Some(row) = reader.next()? {
+                    return Ok(Some(row));
+                }
+            }
+            self.table.take(ctx);
+            if partitions.next == SPILL_PARTITIONS {
+                return Ok(None);
+            }
+            let partition = partitions.next;
+            partitions.next += 1;
+            let mut right = partitions.right.read(partition)?;
+            while let Some(row) = right.next()? {
+                if let Some(key) = eval_keys(&self.right_keys, &row)? {
+                    ctx.charge_memory(&row)?;
+                    self.table.insert(row, key);
+                }
+            }
+            partitions.reader = Some(partitions.left.read(partition)?);
+        }
+    }
+}
+
+impl Executor for HashJoinExec {
+    fn open(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
+        let start = Instant::now();
+        self.stats = ExecutionStats::default();
+        self.table = HashTable::default();
+        self.partitions = None;
+        self.current = None;
+
+        self.left_input.open(ctx)?;
+        self.right_input.open(ctx)?;
+        // A build that fails, say past the temporary disk limit, removes
+        // its spill files straight away
+        if let Err(e) = self.build(ctx) {
+            self.partitions = None;
+            return Err(e);
+        }
+
+        self.stats.open_time = start.elapsed();
+        Ok(())
+    }
+
+    fn next(&mut self, ctx: &mut ExecutionContext) -> DbResult<Option<Row>> {
+        let start = Instant::now();
+
+        loop {
+            // Try the remaining right rows that may match the current left row
+            if let Some((left_row, key, candidates)) = &mut self.current {
+                for position in candidates.by_ref() {
+                    let (right_row, right_key) = &self.table.rows[position];
+                    if keys_equal(key, right_key) {
+                        self.stats.rows_produced += 1;
+                        self.stats.total_next_time += start.elapsed();
+                        return Ok(Some(combine_rows(left_row, right_row)));
+                    }
+                }
+            }
+
+            // Advance to the next left row with non-NULL keys
+            let Some(left_row) = self.next_left(ctx)? else {
+                self.current = None;
+                self.stats.total_next_time += start.elapsed();
+                return Ok(None);
+            };
+            let Some(key) = eval_keys(&self.left_keys, &left_row)? else {
+                continue;
+            };
+            let candidates = self.table.candidates(&key).into_iter();
+            self.current = Some((left_row, key, candidates));
+        }
+    }
+
+    fn close(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
+        let start = Instant::now();
+
+        self.table = HashTable::default();
+        self.partitions = None;
+        self.current = None;
+        self.left_input.close(ctx)?;
+        self.right_input.close(ctx)?;
+
+        self.stats.close_time = start.elapsed();
+        Ok(())
+    }
+
+    fn schema(&self) -> &[String] {
+        &self.schema
+    }
+
+    fn stats(&self) -> Option<&ExecutionStats> {
+        Some(&self.stats)
+    }
+}
+
+/// Right rows of a hash join with their keys, indexed by hashed key.
+#[derive(Default)]
+struct HashTable {
+    rows: Vec<(Row, Vec<Value>)>,
+    buckets: HashMap<Vec<Value>, Vec<usize>>,
+}
+
+impl HashTable {
+    fn insert(&mut self, row: Row, key: Vec<Value>) {
+        self.buckets
+            .entry(hash_key(&key))
+            .or_default()
+            .push(self.rows.len());
+        self.rows.push((row, key));
+    }
+
+    /// Positions of the rows whose keys hash as `key` does.
+    fn candidates(&self, key: &[Value]) -> Vec<usize> {
+        self.buckets
+            .get(&hash_key(key))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Remove every row, crediting back their memory.
+    fn take(&mut self, ctx: &mut ExecutionContext) -> Vec<(Row, Vec<Value>)> {
+        self.buckets.clear();
+        let rows = std::mem::take(&mut self.rows);
+        for (row, _) in &rows {
+            ctx.release_memory(row);
+        }
+        rows
+    }
+}
+
+/// The spilled partitions of a hash join's inputs. Partition `i` of each
+/// side holds the rows whose keys hash to `i`.
+struct Partitions {
+    left: SpillPartitions,
+    right: SpillPartitions,
+    /// The next partition to join.
+    next: usize,
+    /// Left rows of the partition being joined.
+    reader: Option<SpillReader>,
+}
+
+/// The values of `keys` for `row`, or `None` if one is NULL.
+fn eval_keys(keys: &[ResolvedExpr], row: &Row) -> DbResult<Option<Vec<Value>>> {
+    let mut values = Vec::with_capacity(keys.len());
+    for key in keys {
+        match eval_resolved_expr(key, row)? {
+            Value::Null => return Ok(None),
+            value => values.push(value),
+        }
+    }
+    Ok(Some(values))
+}
+
+/// Whether `=` holds between each pair of key values.
+fn keys_equal(left: &[Value], right: &[Value]) -> bool {
+    left.iter()
+        .zip(right)
+        .all(|(l, r)| l.cmp_same_type(r) == Some(Ordering::Equal))
+}
+
+/// The right input of a nested loop, read through once per left row.
+///
+/// Rows are kept in memory until the statement's buffered rows exceed its
+/// work memory, then moved to a spill file.
+#[derive(Default)]
+struct MaterializedRows {
+    rows: Vec<Row>,
+    cursor: usize,
+    spilled: Option<SpillFile>,
+    reader: Option<SpillReader>,
+}
+
+impl MaterializedRows {
+    /// Read all of `input`, replacing any rows already held. If that fails
+    /// the spill file is removed straight away.
+    fn fill(&mut self, input: &mut dyn Executor, ctx: &mut ExecutionContext) -> DbResult<()> {
+        self.rows.clear();
+        self.spilled = None;
+        self.reader = None;
+        let filled = self.read_all(input, ctx);
+        if filled.is_err() {
+            self.spilled = None;
+        }
+        filled
+    }
+
+    fn read_all(&mut self, input: &mut dyn Executor, ctx: &mut ExecutionContext) -> DbResult<()> {
+        while let Some(row) = input.next(ctx)? {
+            ctx.charge_memory(&row)?;
+            self.rows.push(row);
+            if self.spilled.is_some() || ctx.over_work_memory() {
+                self.spill(ctx)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Move the buffered rows to the spill file, crediting back their memory.
+    fn spill(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
+        let file = match &mut self.spilled {
+            Some(file) => file,
+            None => self.spilled.insert(ctx.spill_file()?),
+        };
+        for row in self.rows.drain(..) {
+            ctx.write_spilled(file, &row)?;
+            ctx.release_memory(&row);
+        }
+        Ok(())
+    }
+
+    /// Start again from the first row.
+    fn rewind(&mut self) -> DbResult<()> {
+        self.cursor = 0;
+        if let Some(file) = &mut self.spilled {
+            self.reader = Some(file.read()?);
+        }
+        Ok(())
+    }
+
+    /// `left` combined with the next row, or `None` once all have been read.
+    fn next_combined(&mut self, left: &Row) -> DbResult<Option<Row>> {
+        if let Some(reader) = &mut self.reader {
+            return Ok(reader.next()?.map(|right| combine_rows(left, &right)));
+        }
+        let Some(right) = self.rows.get(self.cursor) else {
+            return Ok(None);
+        };
+        self.cursor += 1;
+        Ok(Some(combine_rows(left, right)))
+    }
+}
+
+/// Combine a left and right row into a single row.
+///
+/// The combined row has all columns from the left row first,
+/// followed by all columns from the right row. It keeps the left row's
+/// RID, so that an UPDATE or DELETE joined to other tables can find the
+/// rows it matched.
+fn combine_rows(left: &Row, right: &Row) -> Row {
+    let mut combined_values = left.values.clone();
+    combined_values.extend(right.values.clone());
+    let mut combined = Row::new(combined_values);
+    combined.set_rid(left.rid());
+    combined
+}
+
+/// Evaluate a join condition against a combined row.
+///
+/// Returns true if the rows should be joined, false otherwise.
+/// NULL condition results are treated as false (SQL semantics).
+fn condition_holds(condition: &ResolvedExpr, row: &Row) -> DbResult<bool> {
+    let result = eval_resolved_expr(condition, row)?;
+    match result {
+        Value::Bool(b) => Ok(b),
+        Value::Null => Ok(false),
+        other => Err(common::DbError::Executor(format!(
+            "join condition must evaluate to boolean, got {:?}",
+            other
+        ))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -299,6 +777,53 @@ mod tests {
         join.close(&mut ctx).unwrap();
     }
 
+    #[test]
+    fn join_spills_right_side_past_work_memory() {
+        let left = Box::new(MockExecutor::new(
+            vec![int_row(&[1]), int_row(&[2])],
+            vec!["id".into()],
+        ));
+        let right = Box::new(MockExecutor::new(
+            vec![int_row(&[2, 10]), int_row(&[1, 20]), int_row(&[2, 30])],
+            vec!["user_id".into(), "total".into()],
+        ));
+        let condition = binary(col(0), BinaryOp::Eq, col(1));
+        let schema = vec!["u.id".into(), "o.user_id".into(), "o.total".into()];
+
+        let mut join = NestedLoopJoinExec::new(left, right, condition, schema);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let catalog = create_test_catalog();
+        let mut pager = buffer::FilePager::new(temp_dir.path(), 10);
+        let mut wal = wal::Wal::open(temp_dir.path().join("test.wal")).unwrap();
+        let limits = common::ResourceLimits::builder()
+            .work_memory_bytes(1)
+            .build();
+        let mut ctx = ExecutionContext::new(&catalog, &mut pager, &mut wal, temp_dir.path().into())
+            .with_resource_limits(limits);
+
+        join.open(&mut ctx).unwrap();
+
+        // The spilled right side is read again for each left row
+        assert_next_row(&mut join, &mut ctx, int_row(&[1, 1, 20]));
+        assert_next_row(&mut join, &mut ctx, int_row(&[2, 2, 10]));
+        assert_next_row(&mut join, &mut ctx, int_row(&[2, 2, 30]));
+        assert_exhausted(&mut join, &mut ctx);
+        let usage = ctx.resource_usage();
+        assert_eq!(usage.memory_bytes, 0);
+        assert!(usage.spilled_bytes > 0);
+
+        join.close(&mut ctx).unwrap();
+        let spill_files = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_string_lossy().starts_with("spill-")
+            })
+            .count();
+        assert_eq!(spill_files, 0);
+    }
+
     #[test]
     fn join_no_matches_returns_none() {
         let left = Box::new(MockExecutor::new(vec![int_row(&[1])], vec!["a".into()]));
@@ -335,4 +860,219 @@ mod tests {
             &["l.a".to_string(), "l.b".to_string(), "r.c".to_string(), "r.d".to_string()]
         );
     }
+
+    /// The values of the rows `join` returns.
+    fn semi_join_rows(mut join: SemiJoinExec, work_memory_bytes: u64) -> Vec<Vec<Value>> {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let catalog = create_test_catalog();
+        let mut pager = buffer::FilePager::new(temp_dir.path(), 10);
+        let mut wal = wal::Wal::open(temp_dir.path().join("test.wal")).unwrap();
+        let limits = common::ResourceLimits::builder()
+            .work_memory_bytes(work_memory_bytes)
+            .build();
+        let mut ctx = ExecutionContext::new(&catalog, &mut pager, &mut wal, temp_dir.path().into())
+            .with_resource_limits(limits);
+
+        join.open(&mut ctx).unwrap();
+        let mut rows = Vec::new();
+        while let Some(row) = join.next(&mut ctx).unwrap() {
+            rows.push(row.values);
+        }
+        join.close(&mut ctx).unwrap();
+        rows
+    }
+
+    fn users_and_orders() -> (Box<MockExecutor>, Box<MockExecutor>) {
+        let users = Box::new(MockExecutor::new(
+            vec![int_row(&[1]), int_row(&[2]), int_row(&[3])],
+            vec!["id".into()],
+        ));
+        let orders = Box::new(MockExecutor::new(
+            vec![int_row(&[1, 10]), int_row(&[3, 20]), int_row(&[1, 30])],
+            vec!["user_id".into(), "total".into()],
+        ));
+        (users, orders)
+    }
+
+    #[test]
+    fn semi_join_returns_each_matching_left_row_once() {
+        let (users, orders) = users_and_orders();
+        let condition = binary(col(0), BinaryOp::Eq, col(1));
+        let join = SemiJoinExec::semi(users, orders, condition, vec!["id".into()]);
+
+        // User 1 has two orders but is returned once, without order columns
+        let rows = semi_join_rows(join, u64::MAX);
+        assert_eq!(rows, vec![vec![Value::Int(1)], vec![Value::Int(3)]]);
+    }
+
+    #[test]
+    fn anti_join_returns_left_rows_without_a_match() {
+        let (users, orders) = users_and_orders();
+        let condition = binary(col(0), BinaryOp::Eq, col(1));
+        let join = SemiJoinExec::anti(users, orders, condition, vec!["id".into()]);
+
+        assert_eq!(semi_join_rows(join, u64::MAX), vec![vec![Value::Int(2)]]);
+    }
+
+    #[test]
+    fn null_condition_is_not_a_match() {
+        let (users, orders) = users_and_orders();
+        let semi = SemiJoinExec::semi(
+            users,
+            orders,
+            ResolvedExpr::Literal(Value::Null),
+            vec!["id".into()],
+        );
+        assert!(semi_join_rows(semi, u64::MAX).is_empty());
+
+        let (users, orders) = users_and_orders();
+        let anti = SemiJoinExec::anti(
+            users,
+            orders,
+            ResolvedExpr::Literal(Value::Null),
+            vec!["id".into()],
+        );
+        assert_eq!(semi_join_rows(anti, u64::MAX).len(), 3);
+    }
+
+    #[test]
+    fn semi_join_spills_right_side_past_work_memory() {
+        let (users, orders) = users_and_orders();
+        let condition = binary(col(0), BinaryOp::Eq, col(1));
+        let join = SemiJoinExec::semi(users, orders, condition, vec!["id".into()]);
+
+        let rows = semi_join_rows(join, 1);
+        assert_eq!(rows, vec![vec![Value::Int(1)], vec![Value::Int(3)]]);
+    }
+
+    /// The values of the rows a hash join of `left` and `right` on column 0
+    /// of each returns, and the temporary directory it ran in.
+    fn hash_join_rows(
+        left: Box<MockExecutor>,
+        right: Box<MockExecutor>,
+        work_memory_bytes: u64,
+    ) -> (Vec<Vec<Value>>, tempfile::TempDir) {
+        let schema = left
+            .schema()
+            .iter()
+            .chain(right.schema())
+            .cloned()
+            .collect();
+        let mut join = HashJoinExec::new(left, right, vec![(col(0), col(0))], schema);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let catalog = create_test_catalog();
+        let mut pager = buffer::FilePager::new(temp_dir.path(), 10);
+        let mut wal = wal::Wal::open(temp_dir.path().join("test.wal")).unwrap();
+        let limits = common::ResourceLimits::builder()
+            .work_memory_bytes(work_memory_bytes)
+            .build();
+        let mut ctx = ExecutionContext::new(&catalog, &mut pager, &mut wal, temp_dir.path().into())
+            .with_resource_limits(limits);
+
+        join.open(&mut ctx).unwrap();
+        let mut rows = Vec::new();
+        while let Some(row) = join.next(&mut ctx).unwrap() {
+            rows.push(row.values);
+        }
+        if work_memory_bytes < u64::MAX {
+            let usage = ctx.resource_usage();
+            assert_eq!(usage.memory_bytes, 0);
+            assert!(usage.spilled_bytes > 0);
+        }
+        join.close(&mut ctx).unwrap();
+        (rows, temp_dir)
+    }
+
+    #[test]
+    fn hash_join_matches_keys_equal_across_types() {
+        let left = Box::new(MockExecutor::new(
+            vec![
+                Row::new(vec![Value::Int(1)]),
+                Row::new(vec![Value::Int(2)]),
+                Row::new(vec![Value::Null]),
+            ],
+            vec!["id".into()],
+        ));
+        let right = Box::new(MockExecutor::new(
+            vec![
+                Row::new(vec![Value::Float(2.0), Value::Int(10)]),
+                Row::new(vec![Value::Null, Value::Int(20)]),
+                Row::new(vec![Value::Int(1), Value::Int(30)]),
+                Row::new(vec![Value::Float(1.5), Value::Int(40)]),
+            ],
+            vec!["user_id".into(), "total".into()],
+        ));
+
+        // NULL keys match nothing, not even each other
+        let (rows, _dir) = hash_join_rows(left, right, u64::MAX);
+        assert_eq!(
+            rows,
+            vec![
+                vec![Value::Int(1), Value::Int(1), Value::Int(30)],
+                vec![Value::Int(2), Value::Float(2.0), Value::Int(10)],
+            ]
+        );
+    }
+
+    #[test]
+    fn hash_join_partitions_both_sides_past_work_memory() {
+        let (users, orders) = users_and_orders();
+        let (mut spilled, temp_dir) = hash_join_rows(users, orders, 1);
+
+        let (users, orders) = users_and_orders();
+        let (mut in_memory, _dir) = hash_join_rows(users, orders, u64::MAX);
+
+        // Partitions are joined one at a time, so rows may come in another order
+        spilled.sort_by_key(|values| format!("{:?}", values));
+        in_memory.sort_by_key(|values| format!("{:?}", values));
+        assert_eq!(spilled, in_memory);
+        assert_eq!(in_memory.len(), 3);
+
+        let spill_files = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_string_lossy().starts_with("spill-")
+            })
+            .count();
+        assert_eq!(spill_files, 0);
+    }
+
+    #[test]
+    fn hash_join_past_temp_limit_fails_and_removes_its_spill_files() {
+        let (users, orders) = users_and_orders();
+        let schema = users
+            .schema()
+            .iter()
+            .chain(orders.schema())
+            .cloned()
+            .collect();
+        let mut join = HashJoinExec::new(users, orders, vec![(col(0), col(0))], schema);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let catalog = create_test_catalog();
+        let mut pager = buffer::FilePager::new(temp_dir.path(), 10);
+        let mut wal = wal::Wal::open(temp_dir.path().join("test.wal")).unwrap();
+        let limits = common::ResourceLimits::builder()
+            .work_memory_bytes(1)
+            .max_temp_bytes(4)
+            .build();
+        let mut ctx = ExecutionContext::new(&catalog, &mut pager, &mut wal, temp_dir.path().into())
+            .with_resource_limits(limits);
+
+        let err = join.open(&mut ctx).unwrap_err();
+        assert!(err.to_string().contains("temp bytes"), "{err}");
+
+        // Gone while the join itself is still around
+        let spill_files = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_string_lossy().starts_with("spill-")
+            })
+            .count();
+        assert_eq!(spill_files, 0);
+        drop(join);
+    }
 }
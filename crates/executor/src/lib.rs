@@ -13,7 +13,7 @@
 //!     ↓
 //! open() → Initialize resources
 //!     ↓
-//! next() → Pull rows iteratively
+//! next() / next_batch() → Pull rows iteratively, one or a batch at a time
 //!     ↓
 //! close() → Clean up resources
 //! ```
@@ -37,6 +37,7 @@
 //! let plan = PhysicalPlan::SeqScan {
 //!     table_id: TableId(1),
 //!     schema: vec!["id".into(), "name".into()],
+//!     projection: None,
 //! };
 //! let results = execute_query(plan, &mut ctx).unwrap();
 //! ```
@@ -76,6 +77,7 @@ mod tests {
         let plan = PhysicalPlan::SeqScan {
             table_id: TableId(1),
             schema: vec!["id".into(), "name".into()],
+            projection: None,
         };
 
         let results = execute_query(plan, &mut ctx).unwrap();
@@ -105,6 +107,7 @@ mod tests {
         let plan = PhysicalPlan::SeqScan {
             table_id,
             schema: vec!["id".into(), "name".into(), "active".into()],
+            projection: None,
         };
 
         let results = execute_query(plan, &mut ctx).unwrap();
@@ -151,6 +154,7 @@ mod tests {
         let scan = PhysicalPlan::SeqScan {
             table_id,
             schema: vec!["id".into(), "name".into(), "active".into()],
+            projection: None,
         };
 
         let plan = PhysicalPlan::Filter {
@@ -162,6 +166,57 @@ mod tests {
         assert_eq!(results.len(), 2); // alice and carol
     }
 
+    #[test]
+    fn profiled_executor_reports_each_operator() {
+        let (ctx, _temp) = setup_test_context();
+        let mut ctx = ctx.with_storage_timing();
+        let table_id = TableId(1);
+        let rows = (1..=3)
+            .map(|id| {
+                Row::new(vec![
+                    Value::Int(id),
+                    Value::Text(format!("user{id}")),
+                    Value::Bool(id != 2),
+                ])
+            })
+            .collect();
+        insert_test_rows(&mut ctx, table_id, rows).unwrap();
+
+        let plan = PhysicalPlan::Filter {
+            input: Box::new(PhysicalPlan::SeqScan {
+                table_id,
+                schema: vec!["id".into(), "name".into(), "active".into()],
+                projection: None,
+            }),
+            predicate: ResolvedExpr::Column(2),
+        };
+        let (mut executor, profile) = build_profiled_executor(plan).unwrap();
+        executor.open(&mut ctx).unwrap();
+        while executor.next(&mut ctx).unwrap().is_some() {}
+        executor.close(&mut ctx).unwrap();
+
+        let filter = profile.report().unwrap();
+        assert!(filter.label.starts_with("Filter"), "{}", filter.label);
+        assert_eq!(filter.counters.rows, 2);
+        assert_eq!(filter.counters.next_calls, 3);
+        let [scan] = filter.inputs.as_slice() else {
+            panic!("expected one input: {filter:?}");
+        };
+        assert!(scan.label.starts_with("SeqScan"), "{}", scan.label);
+        assert_eq!(scan.counters.rows, 3);
+        assert!(scan.counters.total_time() <= filter.counters.total_time());
+        assert_eq!(
+            filter.self_time() + scan.counters.total_time(),
+            filter.counters.total_time()
+        );
+        assert!(scan.counters.storage_wait.unwrap() > std::time::Duration::ZERO);
+
+        let report = filter.render(filter.counters.total_time());
+        assert_eq!(report.lines().count(), 4, "{report}");
+        assert!(report.contains("[####################] 100.0%"), "{report}");
+        assert!(report.contains("\n  SeqScan"), "{report}");
+    }
+
     #[test]
     fn execute_query_with_project() {
         let (mut ctx, _temp) = setup_test_context();
@@ -178,11 +233,12 @@ mod tests {
         let scan = PhysicalPlan::SeqScan {
             table_id,
             schema: vec!["id".into(), "name".into(), "active".into()],
+            projection: None,
         };
 
         let plan = PhysicalPlan::Project {
             input: Box::new(scan),
-            columns: vec![("name".to_string(), 1)],
+            columns: vec![("name".to_string(), ResolvedExpr::Column(1))],
         };
 
         let results = execute_query(plan, &mut ctx).unwrap();
@@ -218,6 +274,7 @@ mod tests {
         let scan = PhysicalPlan::SeqScan {
             table_id,
             schema: vec!["id".into(), "name".into(), "active".into()],
+            projection: None,
         };
 
         let filter = PhysicalPlan::Filter {
@@ -227,7 +284,10 @@ mod tests {
 
         let plan = PhysicalPlan::Project {
             input: Box::new(filter),
-            columns: vec![("id".to_string(), 0), ("name".to_string(), 1)],
+            columns: vec![
+                ("id".to_string(), ResolvedExpr::Column(0)),
+                ("name".to_string(), ResolvedExpr::Column(1)),
+            ],
         };
 
         let results = execute_query(plan, &mut ctx).unwrap();
@@ -250,17 +310,50 @@ mod tests {
 
         let plan = PhysicalPlan::Insert {
             table_id: TableId(1),
-            values: vec![
+            rows: vec![vec![
                 lit!(int: 1),
                 lit!(text: "alice"),
                 ResolvedExpr::Literal(Value::Bool(true)),
-            ],
+            ]],
         };
 
         let count = execute_dml(plan, &mut ctx).unwrap();
         assert_eq!(count, 1);
     }
 
+    #[test]
+    fn execute_dml_insert_multiple_rows() {
+        let (mut ctx, _temp) = setup_test_context();
+
+        let plan = PhysicalPlan::Insert {
+            table_id: TableId(1),
+            rows: vec![
+                vec![lit!(int: 1), lit!(text: "alice"), lit!(bool: true)],
+                vec![lit!(int: 2), lit!(text: "bob"), lit!(bool: false)],
+                vec![lit!(int: 3), lit!(text: "carol"), lit!(bool: true)],
+            ],
+        };
+
+        let count = execute_dml(plan, &mut ctx).unwrap();
+        assert_eq!(count, 3);
+
+        let scan = PhysicalPlan::SeqScan {
+            table_id: TableId(1),
+            schema: vec!["id".into(), "name".into(), "active".into()],
+            projection: None,
+        };
+        let results = execute_query(scan, &mut ctx).unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[2].values,
+            vec![
+                Value::Int(3),
+                Value::Text("carol".into()),
+                Value::Bool(true)
+            ]
+        );
+    }
+
     #[test]
     fn execute_dml_update_returns_count() {
         let (mut ctx, _temp) = setup_test_context();
@@ -285,6 +378,7 @@ mod tests {
             table_id,
             assignments: vec![(1, lit!(text: "updated"))],
             predicate: None,
+            source: None,
         };
 
         let count = execute_dml(plan, &mut ctx).unwrap();
@@ -319,6 +413,7 @@ mod tests {
         let plan = PhysicalPlan::Delete {
             table_id,
             predicate: None,
+            source: None,
         };
 
         let count = execute_dml(plan, &mut ctx).unwrap();
@@ -334,6 +429,7 @@ mod tests {
         let _scan = PhysicalPlan::SeqScan {
             table_id: TableId(1),
             schema: vec![],
+            projection: None,
         };
 
         // This would fail because SeqScan doesn't return a DML count
@@ -341,7 +437,7 @@ mod tests {
         // So we'll test the Insert success path instead
         let plan = PhysicalPlan::Insert {
             table_id: TableId(1),
-            values: vec![lit!(int: 1)],
+            rows: vec![vec![lit!(int: 1)]],
         };
 
         let result = execute_dml(plan, &mut ctx);
@@ -381,6 +477,7 @@ mod tests {
         let plan = PhysicalPlan::SeqScan {
             table_id: TableId(999),
             schema: vec!["id".into()],
+            projection: None,
         };
 
         let result = execute_query(plan, &mut ctx);
@@ -393,7 +490,7 @@ mod tests {
 
         let plan = PhysicalPlan::Insert {
             table_id: TableId(999),
-            values: vec![lit!(int: 1)],
+            rows: vec![vec![lit!(int: 1)]],
         };
 
         let result = execute_dml(plan, &mut ctx);
@@ -522,586 +619,185 @@ mod tests {
 
     // Primary key uniqueness enforcement tests
 
-    #[test]
-    fn insert_duplicate_single_column_primary_key_rejected() {
-        use catalog::Column;
-        use types::SqlType;
-
-        let temp_dir = tempfile::tempdir().unwrap();
-        let mut catalog = Catalog::new();
-        catalog
-            .create_table(
-                "users",
-                vec![
-                    Column::new("id", SqlType::Int),
-                    Column::new("name", SqlType::Text),
-                    Column::new("active", SqlType::Bool),
-                ],
-                Some(vec![0]), // PRIMARY KEY (id)
-            )
-            .unwrap();
-
-        let catalog = Box::leak(Box::new(catalog));
-        let pager = Box::leak(Box::new(buffer::FilePager::new(temp_dir.path(), 10)));
-        let wal = Box::leak(Box::new(
-            wal::Wal::open(temp_dir.path().join("test.wal")).unwrap(),
-        ));
-        let mut ctx = ExecutionContext::new(catalog, pager, wal, temp_dir.path().into());
-
-        let table_id = TableId(1);
-
-        // Insert first row with id=1
-        let plan1 = PhysicalPlan::Insert {
-            table_id,
-            values: vec![
-                lit!(int: 1),
-                lit!(text: "alice"),
-                ResolvedExpr::Literal(Value::Bool(true)),
-            ],
-        };
-        assert!(execute_dml(plan1, &mut ctx).is_ok());
-
-        // Insert second row with id=1 should fail
-        let plan2 = PhysicalPlan::Insert {
-            table_id,
-            values: vec![
-                lit!(int: 1),
-                lit!(text: "bob"),
-                ResolvedExpr::Literal(Value::Bool(false)),
-            ],
-        };
-        let result = execute_dml(plan2, &mut ctx);
+    /// A database with a `users (id, name, active)` table keyed by
+    /// `primary_key`.
+    async fn users_db(primary_key: &str) -> TempDatabase {
+        TempDatabase::new()
+            .await
+            .unwrap()
+            .with_schema(&format!(
+                "CREATE TABLE users (id INT, name TEXT, active BOOL, PRIMARY KEY ({primary_key}))"
+            ))
+            .await
+            .unwrap()
+    }
 
-        assert!(result.is_err());
-        assert!(format!("{:?}", result).contains("duplicate primary key"));
+    async fn assert_rejected(db: &TempDatabase, sql: &str, message: &str) {
+        let err = db.execute(sql).await.expect_err(sql);
+        assert!(format!("{err:#}").contains(message), "{sql}: {err:#}");
     }
 
-    #[test]
-    fn insert_duplicate_composite_primary_key_rejected() {
-        use catalog::Column;
-        use types::SqlType;
+    async fn count_users(db: &TempDatabase) -> usize {
+        db.query("SELECT * FROM users").await.unwrap().len()
+    }
 
-        let temp_dir = tempfile::tempdir().unwrap();
-        let mut catalog = Catalog::new();
-        catalog
-            .create_table(
-                "users",
-                vec![
-                    Column::new("id", SqlType::Int),
-                    Column::new("name", SqlType::Text),
-                    Column::new("active", SqlType::Bool),
-                ],
-                Some(vec![0, 1]), // PRIMARY KEY (id, name)
-            )
+    #[tokio::test]
+    async fn insert_duplicate_single_column_primary_key_rejected() {
+        let db = users_db("id").await;
+        db.execute("INSERT INTO users VALUES (1, 'alice', TRUE)")
+            .await
             .unwrap();
 
-        let catalog = Box::leak(Box::new(catalog));
-        let pager = Box::leak(Box::new(buffer::FilePager::new(temp_dir.path(), 10)));
-        let wal = Box::leak(Box::new(
-            wal::Wal::open(temp_dir.path().join("test.wal")).unwrap(),
-        ));
-        let mut ctx = ExecutionContext::new(catalog, pager, wal, temp_dir.path().into());
-
-        let table_id = TableId(1);
-
-        // Insert first row with (id=1, name="alice")
-        let plan1 = PhysicalPlan::Insert {
-            table_id,
-            values: vec![
-                lit!(int: 1),
-                lit!(text: "alice"),
-                ResolvedExpr::Literal(Value::Bool(true)),
-            ],
-        };
-        assert!(execute_dml(plan1, &mut ctx).is_ok());
-
-        // Insert second row with (id=1, name="alice") should fail
-        let plan2 = PhysicalPlan::Insert {
-            table_id,
-            values: vec![
-                lit!(int: 1),
-                lit!(text: "alice"),
-                ResolvedExpr::Literal(Value::Bool(false)),
-            ],
-        };
-        let result = execute_dml(plan2, &mut ctx);
-
-        assert!(result.is_err());
-        assert!(format!("{:?}", result).contains("duplicate primary key"));
+        assert_rejected(
+            &db,
+            "INSERT INTO users VALUES (1, 'bob', FALSE)",
+            "duplicate primary key",
+        )
+        .await;
     }
 
-    #[test]
-    fn insert_different_composite_primary_key_allowed() {
-        use catalog::Column;
-        use types::SqlType;
+    #[tokio::test]
+    async fn insert_duplicate_primary_key_within_batch_rejected() {
+        let db = users_db("id").await;
+
+        assert_rejected(
+            &db,
+            "INSERT INTO users VALUES (1, 'alice', TRUE), (1, 'bob', TRUE)",
+            "duplicate primary key",
+        )
+        .await;
+        // The batch is validated before any row is written
+        assert_eq!(count_users(&db).await, 0);
+    }
 
-        let temp_dir = tempfile::tempdir().unwrap();
-        let mut catalog = Catalog::new();
-        catalog
-            .create_table(
-                "users",
-                vec![
-                    Column::new("id", SqlType::Int),
-                    Column::new("name", SqlType::Text),
-                    Column::new("active", SqlType::Bool),
-                ],
-                Some(vec![0, 1]), // PRIMARY KEY (id, name)
-            )
+    #[tokio::test]
+    async fn insert_duplicate_composite_primary_key_rejected() {
+        let db = users_db("id, name").await;
+        db.execute("INSERT INTO users VALUES (1, 'alice', TRUE)")
+            .await
             .unwrap();
 
-        let catalog = Box::leak(Box::new(catalog));
-        let pager = Box::leak(Box::new(buffer::FilePager::new(temp_dir.path(), 10)));
-        let wal = Box::leak(Box::new(
-            wal::Wal::open(temp_dir.path().join("test.wal")).unwrap(),
-        ));
-        let mut ctx = ExecutionContext::new(catalog, pager, wal, temp_dir.path().into());
-
-        let table_id = TableId(1);
-
-        // Insert rows with different composite PK values
-        let plan1 = PhysicalPlan::Insert {
-            table_id,
-            values: vec![
-                lit!(int: 1),
-                lit!(text: "alice"),
-                ResolvedExpr::Literal(Value::Bool(true)),
-            ],
-        };
-        assert!(execute_dml(plan1, &mut ctx).is_ok());
-
-        let plan2 = PhysicalPlan::Insert {
-            table_id,
-            values: vec![
-                lit!(int: 1),
-                lit!(text: "bob"),
-                ResolvedExpr::Literal(Value::Bool(false)),
-            ],
-        };
-        assert!(execute_dml(plan2, &mut ctx).is_ok());
+        assert_rejected(
+            &db,
+            "INSERT INTO users VALUES (1, 'alice', FALSE)",
+            "duplicate primary key",
+        )
+        .await;
+    }
 
-        let plan3 = PhysicalPlan::Insert {
-            table_id,
-            values: vec![
-                lit!(int: 2),
-                lit!(text: "alice"),
-                ResolvedExpr::Literal(Value::Bool(true)),
-            ],
-        };
-        assert!(execute_dml(plan3, &mut ctx).is_ok());
+    #[tokio::test]
+    async fn insert_different_composite_primary_key_allowed() {
+        let db = users_db("id, name").await;
+        for sql in [
+            "INSERT INTO users VALUES (1, 'alice', TRUE)",
+            "INSERT INTO users VALUES (1, 'bob', FALSE)",
+            "INSERT INTO users VALUES (2, 'alice', TRUE)",
+        ] {
+            db.execute(sql).await.unwrap();
+        }
 
-        // Verify all three rows exist
-        let scan_plan = PhysicalPlan::SeqScan {
-            table_id,
-            schema: vec!["id".into(), "name".into(), "active".into()],
-        };
-        let rows = execute_query(scan_plan, &mut ctx).unwrap();
-        assert_eq!(rows.len(), 3);
+        assert_eq!(count_users(&db).await, 3);
     }
 
-    #[test]
-    fn insert_builds_pk_index_on_first_access() {
-        use catalog::Column;
-        use types::SqlType;
-
-        let temp_dir = tempfile::tempdir().unwrap();
-        let mut catalog = Catalog::new();
-        catalog
-            .create_table(
-                "users",
-                vec![
-                    Column::new("id", SqlType::Int),
-                    Column::new("name", SqlType::Text),
-                    Column::new("active", SqlType::Bool),
-                ],
-                Some(vec![0]), // PRIMARY KEY (id)
-            )
+    #[tokio::test]
+    async fn insert_builds_pk_index_on_first_access() {
+        let db = users_db("id").await;
+        db.execute("INSERT INTO users VALUES (1, 'alice', TRUE)")
+            .await
+            .unwrap();
+        db.execute("INSERT INTO users VALUES (2, 'bob', FALSE)")
+            .await
             .unwrap();
 
-        let catalog = Box::leak(Box::new(catalog));
-        let pager = Box::leak(Box::new(buffer::FilePager::new(temp_dir.path(), 10)));
-        let wal = Box::leak(Box::new(
-            wal::Wal::open(temp_dir.path().join("test.wal")).unwrap(),
-        ));
-        let mut ctx = ExecutionContext::new(catalog, pager, wal, temp_dir.path().into());
-
-        let table_id = TableId(1);
-
-        // Insert first row
-        let plan1 = PhysicalPlan::Insert {
-            table_id,
-            values: vec![
-                lit!(int: 1),
-                lit!(text: "alice"),
-                ResolvedExpr::Literal(Value::Bool(true)),
-            ],
-        };
-        assert!(execute_dml(plan1, &mut ctx).is_ok());
-
-        // Insert second row with different PK
-        let plan2 = PhysicalPlan::Insert {
-            table_id,
-            values: vec![
-                lit!(int: 2),
-                lit!(text: "bob"),
-                ResolvedExpr::Literal(Value::Bool(false)),
-            ],
-        };
-        assert!(execute_dml(plan2, &mut ctx).is_ok());
-
-        // Verify both rows can be scanned
-        let scan_plan = PhysicalPlan::SeqScan {
-            table_id,
-            schema: vec!["id".into(), "name".into(), "active".into()],
-        };
-        let rows = execute_query(scan_plan, &mut ctx).unwrap();
-        assert_eq!(rows.len(), 2);
+        assert_eq!(count_users(&db).await, 2);
     }
 
-    #[test]
-    fn update_single_column_primary_key_rejected() {
-        use catalog::Column;
-        use types::SqlType;
-
-        let temp_dir = tempfile::tempdir().unwrap();
-        let mut catalog = Catalog::new();
-        catalog
-            .create_table(
-                "users",
-                vec![
-                    Column::new("id", SqlType::Int),
-                    Column::new("name", SqlType::Text),
-                    Column::new("active", SqlType::Bool),
-                ],
-                Some(vec![0]), // PRIMARY KEY (id)
-            )
+    #[tokio::test]
+    async fn update_single_column_primary_key_rejected() {
+        let db = users_db("id").await;
+        db.execute("INSERT INTO users VALUES (1, 'alice', TRUE)")
+            .await
             .unwrap();
 
-        let catalog = Box::leak(Box::new(catalog));
-        let pager = Box::leak(Box::new(buffer::FilePager::new(temp_dir.path(), 10)));
-        let wal = Box::leak(Box::new(
-            wal::Wal::open(temp_dir.path().join("test.wal")).unwrap(),
-        ));
-        let mut ctx = ExecutionContext::new(catalog, pager, wal, temp_dir.path().into());
-
-        let table_id = TableId(1);
-
-        // Insert a row
-        let insert_plan = PhysicalPlan::Insert {
-            table_id,
-            values: vec![
-                lit!(int: 1),
-                lit!(text: "alice"),
-                ResolvedExpr::Literal(Value::Bool(true)),
-            ],
-        };
-        assert!(execute_dml(insert_plan, &mut ctx).is_ok());
-
-        // Try to update the PK column (should fail)
-        let update_plan = PhysicalPlan::Update {
-            table_id,
-            assignments: vec![(0, lit!(int: 2))], // Update id column
-            predicate: Some(ResolvedExpr::Literal(Value::Bool(true))),
-        };
-        let result = execute_dml(update_plan, &mut ctx);
-
-        assert!(result.is_err());
-        assert!(format!("{:?}", result).contains("primary key"));
+        assert_rejected(&db, "UPDATE users SET id = 2", "primary key").await;
     }
 
-    #[test]
-    fn update_composite_primary_key_column_rejected() {
-        use catalog::Column;
-        use types::SqlType;
-
-        let temp_dir = tempfile::tempdir().unwrap();
-        let mut catalog = Catalog::new();
-        catalog
-            .create_table(
-                "users",
-                vec![
-                    Column::new("id", SqlType::Int),
-                    Column::new("name", SqlType::Text),
-                    Column::new("active", SqlType::Bool),
-                ],
-                Some(vec![0, 1]), // PRIMARY KEY (id, name)
-            )
+    #[tokio::test]
+    async fn update_composite_primary_key_column_rejected() {
+        let db = users_db("id, name").await;
+        db.execute("INSERT INTO users VALUES (1, 'alice', TRUE)")
+            .await
             .unwrap();
 
-        let catalog = Box::leak(Box::new(catalog));
-        let pager = Box::leak(Box::new(buffer::FilePager::new(temp_dir.path(), 10)));
-        let wal = Box::leak(Box::new(
-            wal::Wal::open(temp_dir.path().join("test.wal")).unwrap(),
-        ));
-        let mut ctx = ExecutionContext::new(catalog, pager, wal, temp_dir.path().into());
-
-        let table_id = TableId(1);
-
-        // Insert a row
-        let insert_plan = PhysicalPlan::Insert {
-            table_id,
-            values: vec![
-                lit!(int: 1),
-                lit!(text: "alice"),
-                ResolvedExpr::Literal(Value::Bool(true)),
-            ],
-        };
-        assert!(execute_dml(insert_plan, &mut ctx).is_ok());
-
-        // Try to update one PK column (should fail)
-        let update_plan = PhysicalPlan::Update {
-            table_id,
-            assignments: vec![(1, lit!(text: "bob"))], // Update name column (part of PK)
-            predicate: Some(ResolvedExpr::Literal(Value::Bool(true))),
-        };
-        let result = execute_dml(update_plan, &mut ctx);
-
-        assert!(result.is_err());
-        assert!(format!("{:?}", result).contains("primary key"));
+        assert_rejected(&db, "UPDATE users SET name = 'bob'", "primary key").await;
     }
 
-    #[test]
-    fn update_non_pk_column_allowed() {
-        use catalog::Column;
-        use types::SqlType;
-
-        let temp_dir = tempfile::tempdir().unwrap();
-        let mut catalog = Catalog::new();
-        catalog
-            .create_table(
-                "users",
-                vec![
-                    Column::new("id", SqlType::Int),
-                    Column::new("name", SqlType::Text),
-                    Column::new("active", SqlType::Bool),
-                ],
-                Some(vec![0]), // PRIMARY KEY (id)
-            )
+    #[tokio::test]
+    async fn update_non_pk_column_allowed() {
+        let db = users_db("id").await;
+        db.execute("INSERT INTO users VALUES (1, 'alice', TRUE)")
+            .await
             .unwrap();
 
-        let catalog = Box::leak(Box::new(catalog));
-        let pager = Box::leak(Box::new(buffer::FilePager::new(temp_dir.path(), 10)));
-        let wal = Box::leak(Box::new(
-            wal::Wal::open(temp_dir.path().join("test.wal")).unwrap(),
-        ));
-        let mut ctx = ExecutionContext::new(catalog, pager, wal, temp_dir.path().into());
-
-        let table_id = TableId(1);
-
-        // Insert a row
-        let insert_plan = PhysicalPlan::Insert {
-            table_id,
-            values: vec![
-                lit!(int: 1),
-                lit!(text: "alice"),
-                ResolvedExpr::Literal(Value::Bool(true)),
-            ],
-        };
-        assert!(execute_dml(insert_plan, &mut ctx).is_ok());
-
-        // Update non-PK columns (should succeed)
-        let update_plan = PhysicalPlan::Update {
-            table_id,
-            assignments: vec![
-                (1, lit!(text: "bob")),
-                (2, ResolvedExpr::Literal(Value::Bool(false))),
-            ],
-            predicate: Some(ResolvedExpr::Literal(Value::Bool(true))),
-        };
-        let result = execute_dml(update_plan, &mut ctx);
-
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), 1);
+        db.execute("UPDATE users SET name = 'bob', active = FALSE")
+            .await
+            .unwrap();
+        let rows = db.query("SELECT name, active FROM users").await.unwrap();
+        assert_eq!(
+            rows[0].values,
+            vec![Value::Text("bob".into()), Value::Bool(false)]
+        );
     }
 
-    #[test]
-    fn delete_removes_pk_entry_allowing_reinsertion() {
-        use catalog::Column;
-        use types::SqlType;
-
-        let temp_dir = tempfile::tempdir().unwrap();
-        let mut catalog = Catalog::new();
-        catalog
-            .create_table(
-                "users",
-                vec![
-                    Column::new("id", SqlType::Int),
-                    Column::new("name", SqlType::Text),
-                    Column::new("active", SqlType::Bool),
-                ],
-                Some(vec![0]), // PRIMARY KEY (id)
-            )
+    #[tokio::test]
+    async fn delete_removes_pk_entry_allowing_reinsertion() {
+        let db = users_db("id").await;
+        db.execute("INSERT INTO users VALUES (1, 'alice', TRUE)")
+            .await
             .unwrap();
+        db.execute("DELETE FROM users").await.unwrap();
 
-        let catalog = Box::leak(Box::new(catalog));
-        let pager = Box::leak(Box::new(buffer::FilePager::new(temp_dir.path(), 10)));
-        let wal = Box::leak(Box::new(
-            wal::Wal::open(temp_dir.path().join("test.wal")).unwrap(),
-        ));
-        let mut ctx = ExecutionContext::new(catalog, pager, wal, temp_dir.path().into());
-
-        let table_id = TableId(1);
-
-        // Insert a row with id=1
-        let insert_plan1 = PhysicalPlan::Insert {
-            table_id,
-            values: vec![
-                lit!(int: 1),
-                lit!(text: "alice"),
-                ResolvedExpr::Literal(Value::Bool(true)),
-            ],
-        };
-        assert!(execute_dml(insert_plan1, &mut ctx).is_ok());
-
-        // Delete the row
-        let delete_plan = PhysicalPlan::Delete {
-            table_id,
-            predicate: Some(ResolvedExpr::Literal(Value::Bool(true))),
-        };
-        let count = execute_dml(delete_plan, &mut ctx).unwrap();
-        assert_eq!(count, 1);
-
-        // Reinsert with same id=1 should now succeed
-        let insert_plan2 = PhysicalPlan::Insert {
-            table_id,
-            values: vec![
-                lit!(int: 1),
-                lit!(text: "bob"),
-                ResolvedExpr::Literal(Value::Bool(false)),
-            ],
-        };
-        let result = execute_dml(insert_plan2, &mut ctx);
-        assert!(result.is_ok());
+        db.execute("INSERT INTO users VALUES (1, 'bob', FALSE)")
+            .await
+            .unwrap();
+        assert_eq!(count_users(&db).await, 1);
     }
 
-    #[test]
-    fn delete_removes_composite_pk_entry() {
-        use catalog::Column;
-        use types::SqlType;
-
-        let temp_dir = tempfile::tempdir().unwrap();
-        let mut catalog = Catalog::new();
-        catalog
-            .create_table(
-                "users",
-                vec![
-                    Column::new("id", SqlType::Int),
-                    Column::new("name", SqlType::Text),
-                    Column::new("active", SqlType::Bool),
-                ],
-                Some(vec![0, 1]), // PRIMARY KEY (id, name)
-            )
+    #[tokio::test]
+    async fn delete_removes_composite_pk_entry() {
+        let db = users_db("id, name").await;
+        db.execute("INSERT INTO users VALUES (1, 'alice', TRUE)")
+            .await
             .unwrap();
+        db.execute("DELETE FROM users").await.unwrap();
 
-        let catalog = Box::leak(Box::new(catalog));
-        let pager = Box::leak(Box::new(buffer::FilePager::new(temp_dir.path(), 10)));
-        let wal = Box::leak(Box::new(
-            wal::Wal::open(temp_dir.path().join("test.wal")).unwrap(),
-        ));
-        let mut ctx = ExecutionContext::new(catalog, pager, wal, temp_dir.path().into());
-
-        let table_id = TableId(1);
-
-        // Insert row with (id=1, name="alice")
-        let insert_plan1 = PhysicalPlan::Insert {
-            table_id,
-            values: vec![
-                lit!(int: 1),
-                lit!(text: "alice"),
-                ResolvedExpr::Literal(Value::Bool(true)),
-            ],
-        };
-        assert!(execute_dml(insert_plan1, &mut ctx).is_ok());
-
-        // Delete the row
-        let delete_plan = PhysicalPlan::Delete {
-            table_id,
-            predicate: Some(ResolvedExpr::Literal(Value::Bool(true))),
-        };
-        let count = execute_dml(delete_plan, &mut ctx).unwrap();
-        assert_eq!(count, 1);
-
-        // Reinsert with same composite PK (1, "alice") should succeed
-        let insert_plan2 = PhysicalPlan::Insert {
-            table_id,
-            values: vec![
-                lit!(int: 1),
-                lit!(text: "alice"),
-                ResolvedExpr::Literal(Value::Bool(false)),
-            ],
-        };
-        let result = execute_dml(insert_plan2, &mut ctx);
-        assert!(result.is_ok());
+        db.execute("INSERT INTO users VALUES (1, 'alice', FALSE)")
+            .await
+            .unwrap();
+        assert_eq!(count_users(&db).await, 1);
     }
 
-    #[test]
-    fn delete_selective_removal_from_pk_index() {
-        use catalog::Column;
-        use types::SqlType;
-
-        let temp_dir = tempfile::tempdir().unwrap();
-        let mut catalog = Catalog::new();
-        catalog
-            .create_table(
-                "users",
-                vec![
-                    Column::new("id", SqlType::Int),
-                    Column::new("name", SqlType::Text),
-                    Column::new("active", SqlType::Bool),
-                ],
-                Some(vec![0]), // PRIMARY KEY (id)
-            )
+    #[tokio::test]
+    async fn delete_selective_removal_from_pk_index() {
+        let db = users_db("id").await;
+        db.execute(
+            "INSERT INTO users VALUES (1, 'alice', TRUE), (2, 'bob', TRUE), (3, 'carol', FALSE)",
+        )
+        .await
+        .unwrap();
+        db.execute("DELETE FROM users WHERE active").await.unwrap();
+
+        // Only the deleted keys are free again
+        db.execute("INSERT INTO users VALUES (1, 'new_alice', FALSE), (2, 'new_bob', FALSE)")
+            .await
             .unwrap();
-
-        let catalog = Box::leak(Box::new(catalog));
-        let pager = Box::leak(Box::new(buffer::FilePager::new(temp_dir.path(), 10)));
-        let wal = Box::leak(Box::new(
-            wal::Wal::open(temp_dir.path().join("test.wal")).unwrap(),
-        ));
-        let mut ctx = ExecutionContext::new(catalog, pager, wal, temp_dir.path().into());
-
-        let table_id = TableId(1);
-
-        // Insert three rows
-        for (id, name) in &[(1, "alice"), (2, "bob"), (3, "carol")] {
-            let plan = PhysicalPlan::Insert {
-                table_id,
-                values: vec![
-                    lit!(int: *id),
-                    lit!(text: name),
-                    ResolvedExpr::Literal(Value::Bool(true)),
-                ],
-            };
-            assert!(execute_dml(plan, &mut ctx).is_ok());
-        }
-
-        // Delete all rows where active=true (all three have active=true)
-        let delete_plan = PhysicalPlan::Delete {
-            table_id,
-            predicate: Some(ResolvedExpr::Column(2)), // WHERE active
-        };
-        let count = execute_dml(delete_plan, &mut ctx).unwrap();
-        assert_eq!(count, 3); // All three rows deleted
-
-        // All three PKs should now be available for reinsertion
-        for (id, name) in &[(1, "new_alice"), (2, "new_bob"), (3, "new_carol")] {
-            let plan = PhysicalPlan::Insert {
-                table_id,
-                values: vec![
-                    lit!(int: *id),
-                    lit!(text: name),
-                    ResolvedExpr::Literal(Value::Bool(false)),
-                ],
-            };
-            assert!(execute_dml(plan, &mut ctx).is_ok());
-        }
-
-        // Verify all three rows exist
-        let scan_plan = PhysicalPlan::SeqScan {
-            table_id,
-            schema: vec!["id".into(), "name".into(), "active".into()],
-        };
-        let rows = execute_query(scan_plan, &mut ctx).unwrap();
-        assert_eq!(rows.len(), 3);
+        assert_rejected(
+            &db,
+            "INSERT INTO users VALUES (3, 'new_carol', FALSE)",
+            "duplicate primary key",
+        )
+        .await;
+        assert_eq!(count_users(&db).await, 3);
     }
 
     #[test]
@@ -1126,25 +822,23 @@ mod tests {
                 .unwrap();
             catalog.save(&temp_dir.path().join("catalog.json")).unwrap();
 
-            let catalog = Box::leak(Box::new(catalog));
-            let pager = Box::leak(Box::new(buffer::FilePager::new(temp_dir.path(), 10)));
-            let wal = Box::leak(Box::new(
-                wal::Wal::open(temp_dir.path().join("test.wal")).unwrap(),
-            ));
-            let mut ctx = ExecutionContext::new(catalog, pager, wal, temp_dir.path().into());
+            let mut pager = buffer::FilePager::new(temp_dir.path(), 10);
+            let mut wal = wal::Wal::open(temp_dir.path().join("test.wal")).unwrap();
+            let mut ctx =
+                ExecutionContext::new(&catalog, &mut pager, &mut wal, temp_dir.path().into());
 
             let table_id = TableId(1);
 
             // Insert rows
             let insert1 = PhysicalPlan::Insert {
                 table_id,
-                values: vec![lit!(int: 1), lit!(text: "alice")],
+                rows: vec![vec![lit!(int: 1), lit!(text: "alice")]],
             };
             execute_dml(insert1, &mut ctx).unwrap();
 
             let insert2 = PhysicalPlan::Insert {
                 table_id,
-                values: vec![lit!(int: 2), lit!(text: "bob")],
+                rows: vec![vec![lit!(int: 2), lit!(text: "bob")]],
             };
             execute_dml(insert2, &mut ctx).unwrap();
 
@@ -1156,19 +850,17 @@ mod tests {
         // Session 2: Create new context, verify PK enforcement works (index loaded from file)
         {
             let catalog = Catalog::load(&temp_dir.path().join("catalog.json")).unwrap();
-            let catalog = Box::leak(Box::new(catalog));
-            let pager = Box::leak(Box::new(buffer::FilePager::new(temp_dir.path(), 10)));
-            let wal = Box::leak(Box::new(
-                wal::Wal::open(temp_dir.path().join("test.wal")).unwrap(),
-            ));
-            let mut ctx = ExecutionContext::new(catalog, pager, wal, temp_dir.path().into());
+            let mut pager = buffer::FilePager::new(temp_dir.path(), 10);
+            let mut wal = wal::Wal::open(temp_dir.path().join("test.wal")).unwrap();
+            let mut ctx =
+                ExecutionContext::new(&catalog, &mut pager, &mut wal, temp_dir.path().into());
 
             let table_id = TableId(1);
 
             // Try to insert duplicate PK (should fail)
             let insert_duplicate = PhysicalPlan::Insert {
                 table_id,
-                values: vec![lit!(int: 1), lit!(text: "charlie")],
+                rows: vec![vec![lit!(int: 1), lit!(text: "charlie")]],
             };
             let result = execute_dml(insert_duplicate, &mut ctx);
             assert!(result.is_err());
@@ -1177,7 +869,7 @@ mod tests {
             // Insert new unique PK (should succeed)
             let insert_new = PhysicalPlan::Insert {
                 table_id,
-                values: vec![lit!(int: 3), lit!(text: "charlie")],
+                rows: vec![vec![lit!(int: 3), lit!(text: "charlie")]],
             };
             assert!(execute_dml(insert_new, &mut ctx).is_ok());
         }
@@ -1201,19 +893,16 @@ mod tests {
             )
             .unwrap();
 
-        let catalog = Box::leak(Box::new(catalog));
-        let pager = Box::leak(Box::new(buffer::FilePager::new(temp_dir.path(), 10)));
-        let wal = Box::leak(Box::new(
-            wal::Wal::open(temp_dir.path().join("test.wal")).unwrap(),
-        ));
-        let mut ctx = ExecutionContext::new(catalog, pager, wal, temp_dir.path().into());
+        let mut pager = buffer::FilePager::new(temp_dir.path(), 10);
+        let mut wal = wal::Wal::open(temp_dir.path().join("test.wal")).unwrap();
+        let mut ctx = ExecutionContext::new(&catalog, &mut pager, &mut wal, temp_dir.path().into());
 
         let table_id = TableId(1);
 
         // Insert rows (creates .pk_idx)
         let insert = PhysicalPlan::Insert {
             table_id,
-            values: vec![lit!(int: 1), lit!(text: "alice")],
+            rows: vec![vec![lit!(int: 1), lit!(text: "alice")]],
         };
         execute_dml(insert, &mut ctx).unwrap();
 
@@ -1228,7 +917,7 @@ mod tests {
         // Try to insert duplicate (should still fail - rebuilt from heap)
         let insert_dup = PhysicalPlan::Insert {
             table_id,
-            values: vec![lit!(int: 1), lit!(text: "bob")],
+            rows: vec![vec![lit!(int: 1), lit!(text: "bob")]],
         };
         let result = execute_dml(insert_dup, &mut ctx);
         assert!(result.is_err());
@@ -1257,25 +946,24 @@ mod tests {
                 .unwrap();
             catalog.save(&temp_dir.path().join("catalog.json")).unwrap();
 
-            let catalog = Box::leak(Box::new(catalog));
-            let pager = Box::leak(Box::new(buffer::FilePager::new(temp_dir.path(), 10)));
-            let wal = Box::leak(Box::new(
-                wal::Wal::open(temp_dir.path().join("test.wal")).unwrap(),
-            ));
-            let mut ctx = ExecutionContext::new(catalog, pager, wal, temp_dir.path().into());
+            let mut pager = buffer::FilePager::new(temp_dir.path(), 10);
+            let mut wal = wal::Wal::open(temp_dir.path().join("test.wal")).unwrap();
+            let mut ctx =
+                ExecutionContext::new(&catalog, &mut pager, &mut wal, temp_dir.path().into());
 
             let table_id = TableId(1);
 
             // Insert then delete
             let insert = PhysicalPlan::Insert {
                 table_id,
-                values: vec![lit!(int: 1), lit!(text: "alice")],
+                rows: vec![vec![lit!(int: 1), lit!(text: "alice")]],
             };
             execute_dml(insert, &mut ctx).unwrap();
 
             let delete = PhysicalPlan::Delete {
                 table_id,
                 predicate: Some(ResolvedExpr::Literal(Value::Bool(true))),
+                source: None,
             };
             execute_dml(delete, &mut ctx).unwrap();
         }
@@ -1283,43 +971,60 @@ mod tests {
         // Session 2: Verify deleted key can be reinserted
         {
             let catalog = Catalog::load(&temp_dir.path().join("catalog.json")).unwrap();
-            let catalog = Box::leak(Box::new(catalog));
-            let pager = Box::leak(Box::new(buffer::FilePager::new(temp_dir.path(), 10)));
-            let wal = Box::leak(Box::new(
-                wal::Wal::open(temp_dir.path().join("test.wal")).unwrap(),
-            ));
-            let mut ctx = ExecutionContext::new(catalog, pager, wal, temp_dir.path().into());
+            let mut pager = buffer::FilePager::new(temp_dir.path(), 10);
+            let mut wal = wal::Wal::open(temp_dir.path().join("test.wal")).unwrap();
+            let mut ctx =
+                ExecutionContext::new(&catalog, &mut pager, &mut wal, temp_dir.path().into());
 
             let table_id = TableId(1);
 
             // Reinsert the deleted key (should succeed)
             let insert = PhysicalPlan::Insert {
                 table_id,
-                values: vec![lit!(int: 1), lit!(text: "alice")],
+                rows: vec![vec![lit!(int: 1), lit!(text: "alice")]],
             };
             assert!(execute_dml(insert, &mut ctx).is_ok());
         }
     }
 }
 
+mod aggregate;
+mod batch;
 mod builder;
 mod dml;
+mod engines;
 mod filter;
 mod join;
 mod limit;
+mod parallel;
+mod partitions;
 mod pk_index;
+pub mod profile;
 mod project;
+mod resources;
 mod scan;
 mod sort;
+mod spill;
+mod undo;
 
-pub use builder::build_executor;
-pub use join::NestedLoopJoinExec;
+pub use batch::{RowBatch, BATCH_SIZE};
+pub use builder::{build_executor, build_profiled_executor};
+pub use engines::EngineRegistry;
+pub use join::{NestedLoopJoinExec, SemiJoinExec};
 pub use pk_index::PrimaryKeyIndex;
+pub use resources::ResourceUsage;
+pub use undo::{roll_back, UndoRecord};
 
-use catalog::Catalog;
-use common::{DbError, DbResult, ExecutionStats, Row, TableId};
+use catalog::{Catalog, TableSchema};
+use common::compression::Compression;
+use common::{DbError, DbResult, ExecutionStats, Priority, RecordId, ResourceLimits, Row, TableId};
 use planner::PhysicalPlan;
+use resources::ResourceBudget;
+use spill::SpillFile;
+use std::cell::Cell;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use storage::HeapTable;
 use wal::{Wal, WalRecord};
 
@@ -1335,6 +1040,22 @@ pub trait Executor {
     /// Fetch the next row, or None if exhausted.
     fn next(&mut self, ctx: &mut ExecutionContext) -> DbResult<Option<Row>>;
 
+    /// Fetch the next rows, at most `max` of them, or None if exhausted.
+    /// A returned batch is never empty.
+    ///
+    /// Calls may be mixed with calls to [`next`](Executor::next); the
+    /// default fills the batch from `next`.
+    fn next_batch(&mut self, ctx: &mut ExecutionContext, max: usize) -> DbResult<Option<RowBatch>> {
+        let mut batch = RowBatch::with_capacity(max.min(BATCH_SIZE));
+        while batch.len() < max {
+            match self.next(ctx)? {
+                Some(row) => batch.push(row),
+                None => break,
+            }
+        }
+        Ok((!batch.is_empty()).then_some(batch))
+    }
+
     /// Release resources (close files, flush buffers, etc.).
     fn close(&mut self, ctx: &mut ExecutionContext) -> DbResult<()>;
 
@@ -1359,10 +1080,120 @@ pub struct ExecutionContext<'a> {
     pub data_dir: PathBuf,
     /// Primary key indexes, lazily built on first table access
     pk_indexes: std::collections::HashMap<TableId, pk_index::PrimaryKeyIndex>,
+    /// Resources used by the statement, checked against its limits
+    budget: ResourceBudget,
+    /// Values taken from sequences for auto-increment columns
+    generated_ids: Vec<i64>,
+    /// Storage engines that tables' rows are read from and written to
+    engines: Arc<EngineRegistry>,
+    /// Time spent in table storage, if the statement is profiled
+    storage_wait: Option<Cell<Duration>>,
+    /// Worker threads a sequential scan may split its pages across
+    max_parallel_workers: usize,
+    /// How urgently the statement runs
+    priority: Priority,
+    /// Row changes to reverse on rollback, if they are being kept
+    undo_log: Option<Vec<UndoRecord>>,
+}
+
+/// Table storage that upgrades rows written before `ALTER TABLE ... ADD COLUMN`.
+///
+/// Adding a column does not rewrite existing rows, so older rows are shorter
+/// than the schema; `get` fills the missing trailing columns with their
+/// defaults.
+///
+/// When the statement is profiled, the time each call spends in storage is
+/// added to `storage_wait`.
+struct SchemaHeap<'a> {
+    file: Box<dyn HeapTable>,
+    schema: &'a TableSchema,
+    storage_wait: Option<&'a Cell<Duration>>,
+}
+
+impl SchemaHeap<'_> {
+    /// Run `op` on the table's storage, timing it if profiled.
+    fn wait<T>(&mut self, op: impl FnOnce(&mut dyn HeapTable) -> T) -> T {
+        let Some(storage_wait) = self.storage_wait else {
+            return op(self.file.as_mut());
+        };
+        let start = Instant::now();
+        let result = op(self.file.as_mut());
+        storage_wait.set(storage_wait.get() + start.elapsed());
+        result
+    }
+}
+
+impl HeapTable for SchemaHeap<'_> {
+    fn insert(&mut self, row: &Row) -> DbResult<RecordId> {
+        self.wait(|file| file.insert(row))
+    }
+
+    fn get(&mut self, rid: RecordId) -> DbResult<Row> {
+        let mut row = self.wait(|file| file.get(rid))?;
+        self.schema.fill_missing_columns(&mut row.values);
+        Ok(row)
+    }
+
+    fn get_columns(&mut self, rid: RecordId, columns: &[common::ColumnId]) -> DbResult<Row> {
+        let mut row = self.wait(|file| file.get_columns(rid, columns))?;
+        self.schema.fill_missing_columns(&mut row.values);
+        Ok(row)
+    }
+
+    fn update(&mut self, rid: RecordId, row: &Row) -> DbResult<RecordId> {
+        self.wait(|file| file.update(rid, row))
+    }
+
+    fn delete(&mut self, rid: RecordId) -> DbResult<()> {
+        self.wait(|file| file.delete(rid))
+    }
+
+    fn page_runs(&self) -> Vec<common::PageId> {
+        self.file.page_runs()
+    }
+
+    fn run_pages(&mut self, start: common::PageId) -> DbResult<u64> {
+        self.wait(|file| file.run_pages(start))
+    }
+
+    fn scan_page(
+        &mut self,
+        page: common::PageId,
+        columns: Option<&[common::ColumnId]>,
+    ) -> DbResult<Option<Vec<Row>>> {
+        let mut rows = self.wait(|file| file.scan_page(page, columns))?;
+        for row in rows.iter_mut().flatten() {
+            self.schema.fill_missing_columns(&mut row.values);
+        }
+        Ok(rows)
+    }
+
+    fn page_usage(&mut self) -> DbResult<Option<storage::PageUsage>> {
+        self.wait(|file| file.page_usage())
+    }
+
+    fn set_fillfactor(&mut self, fillfactor: u8) {
+        self.file.set_fillfactor(fillfactor);
+    }
+
+    fn set_compression(&mut self, compression: Compression) {
+        self.file.set_compression(compression);
+    }
+
+    fn set_row_layout(&mut self, layout: storage::row_format::RowLayout) {
+        self.file.set_row_layout(layout);
+    }
+
+    fn vacuum(&mut self) -> DbResult<Vec<(RecordId, RecordId)>> {
+        self.wait(|file| file.vacuum())
+    }
 }
 
 impl<'a> ExecutionContext<'a> {
     /// Create a new execution context.
+    ///
+    /// The pager serves the statement at normal priority unless
+    /// [`ExecutionContext::with_priority`] says otherwise.
     pub fn new(
         catalog: &'a Catalog,
         pager: &'a mut dyn buffer::Pager,
@@ -1375,28 +1206,214 @@ impl<'a> ExecutionContext<'a> {
             wal,
             data_dir,
             pk_indexes: std::collections::HashMap::new(),
+            budget: ResourceBudget::default(),
+            generated_ids: Vec::new(),
+            engines: Arc::default(),
+            storage_wait: None,
+            max_parallel_workers: 0,
+            priority: Priority::default(),
+            undo_log: None,
+        }
+        .with_priority(Priority::default())
+    }
+
+    /// Use `engines` to open table storage.
+    ///
+    /// Without this only the default heap engine is available, and tables on
+    /// any other engine fail to open.
+    pub fn with_engines(mut self, engines: Arc<EngineRegistry>) -> Self {
+        self.engines = engines;
+        self
+    }
+
+    /// Cap the resources the statement run with this context may use.
+    ///
+    /// Exceeding a limit fails the statement with `DbError::ResourceExhausted`.
+    pub fn with_resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.budget = ResourceBudget::new(limits);
+        self
+    }
+
+    /// Let sequential scans read their pages on up to `workers` threads.
+    ///
+    /// Zero, the default, scans serially. Parallel scans return rows in no
+    /// particular order.
+    pub fn with_max_parallel_workers(mut self, workers: usize) -> Self {
+        self.max_parallel_workers = workers;
+        self
+    }
+
+    /// Worker threads a sequential scan may split its pages across; none
+    /// for background statements.
+    pub fn max_parallel_workers(&self) -> usize {
+        match self.priority {
+            Priority::Background => 0,
+            _ => self.max_parallel_workers,
+        }
+    }
+
+    /// Run the statement at `priority`.
+    ///
+    /// Background statements scan serially, and the pager keeps the pages
+    /// they fetch from pushing out other statements' pages (see
+    /// [`buffer::Pager::set_priority`]). The priority applies to fetches
+    /// made on the thread that builds the context, until the context is
+    /// dropped, so statements on other threads keep their own.
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self.pager.set_priority(priority);
+        self
+    }
+
+    /// How urgently the statement runs.
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Time how long table storage takes to answer, for `PROFILE`.
+    pub fn with_storage_timing(mut self) -> Self {
+        self.storage_wait = Some(Cell::default());
+        self
+    }
+
+    /// Time spent opening, reading and writing table storage so far, if it
+    /// is timed (see [`ExecutionContext::with_storage_timing`]).
+    pub fn storage_wait(&self) -> Option<Duration> {
+        self.storage_wait.as_ref().map(Cell::get)
+    }
+
+    /// Resources used so far by the statement run with this context.
+    pub fn resource_usage(&self) -> ResourceUsage {
+        self.budget.usage()
+    }
+
+    /// Account for rows read from a table.
+    pub fn charge_rows_scanned(&mut self, rows: u64) -> DbResult<()> {
+        self.budget.charge_rows_scanned(rows)
+    }
+
+    /// Account for a row an operator buffers in memory.
+    pub fn charge_memory(&mut self, row: &Row) -> DbResult<()> {
+        self.budget.charge_memory(row)
+    }
+
+    /// Account for a buffered row that an operator no longer holds.
+    pub fn release_memory(&mut self, row: &Row) {
+        self.budget.release_memory(row)
+    }
+
+    /// Append `row` to `file`, charging the bytes written to the statement's
+    /// temporary disk usage.
+    pub(crate) fn write_spilled(&mut self, file: &mut SpillFile, row: &Row) -> DbResult<()> {
+        let bytes = file.write(row)?;
+        self.budget.charge_temp_bytes(bytes)
+    }
+
+    /// Whether the statement's operators buffer more than its work memory,
+    /// so the one buffering should spill.
+    pub fn over_work_memory(&self) -> bool {
+        self.budget.over_work_memory()
+    }
+
+    /// Create a temporary file to spill rows to, removed when dropped.
+    pub(crate) fn spill_file(&self) -> DbResult<SpillFile> {
+        SpillFile::create(&self.data_dir, self.catalog.encryption_key())
+    }
+
+    /// Keep an [`UndoRecord`] for every row the statement writes, so the
+    /// writes can be reversed with [`roll_back`].
+    pub fn with_undo_log(mut self) -> Self {
+        self.undo_log = Some(Vec::new());
+        self
+    }
+
+    /// Take the undo records kept so far, oldest first; empty unless the
+    /// context was built [`with_undo_log`](Self::with_undo_log).
+    pub fn take_undo_log(&mut self) -> Vec<UndoRecord> {
+        self.undo_log
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Keep `record` if the context keeps an undo log.
+    pub(crate) fn record_undo(&mut self, record: UndoRecord) {
+        if let Some(undo_log) = &mut self.undo_log {
+            undo_log.push(record);
         }
     }
 
-    /// Open a heap table for the given table ID.
+    /// Values generated for auto-increment columns by the statement run with
+    /// this context, in insertion order.
+    pub fn generated_ids(&self) -> &[i64] {
+        &self.generated_ids
+    }
+
+    /// Open the storage of the given table ID through its engine.
+    ///
+    /// Rows read through the table are padded to the current schema width.
     pub fn heap_table(&mut self, table_id: TableId) -> DbResult<impl HeapTable + '_> {
         let table_meta = self.catalog.table_by_id(table_id)?;
+        let start = Instant::now();
+        let file = self
+            .engines
+            .open(&self.data_dir, table_meta, self.catalog.encryption_key())?;
+        let storage_wait = self.storage_wait.as_ref();
+        if let Some(storage_wait) = storage_wait {
+            storage_wait.set(storage_wait.get() + start.elapsed());
+        }
+        Ok(SchemaHeap {
+            file,
+            schema: &table_meta.schema,
+            storage_wait,
+        })
+    }
 
-        let file_path = self.data_dir.join(format!("{}.heap", table_meta.name));
-        storage::HeapFile::open(&file_path, table_id.0)
+    /// Whether writes to the table are logged and its primary key index
+    /// saved, i.e. whether its engine keeps rows across restarts.
+    fn is_durable(&self, table_id: TableId) -> bool {
+        self.catalog
+            .table_by_id(table_id)
+            .map_or(true, |table_meta| self.engines.is_durable(table_meta))
     }
 
     /// Log a DML operation to the WAL.
+    ///
+    /// Writes to tables on non-durable engines are not logged.
     pub fn log_dml(&mut self, record: WalRecord) -> DbResult<()> {
-        self.wal.append(&record)?;
-        self.wal.sync()
+        self.log_dml_batch(vec![record])
+    }
+
+    /// Log several DML operations to the WAL with a single commit, which
+    /// syncs the log unless its durability is relaxed.
+    pub fn log_dml_batch(&mut self, records: Vec<WalRecord>) -> DbResult<()> {
+        let mut logged = false;
+        for record in &records {
+            let table = match record {
+                WalRecord::Insert { table, .. }
+                | WalRecord::Update { table, .. }
+                | WalRecord::Delete { table, .. }
+                | WalRecord::CreateTable { table, .. }
+                | WalRecord::DropTable { table } => *table,
+            };
+            if self.is_durable(table) {
+                self.wal.append(record)?;
+                logged = true;
+            }
+        }
+        if logged {
+            self.wal.commit()?;
+        }
+        Ok(())
     }
 
-    /// Get or build the primary key index for a table.
+    /// Get or open the primary key index for a table.
     ///
     /// If the table has no primary key, returns None.
-    /// On first access, tries to load the index from `.pk_idx` file.
-    /// If the file is missing or corrupt, falls back to scanning existing rows.
+    /// On first access, opens the index from the table's `.pk_idx` file.
+    /// If the file is missing or unreadable, it is recreated by scanning
+    /// existing rows. Tables on non-durable engines have no index file and
+    /// are always scanned.
     pub fn pk_index(
         &mut self,
         table_id: TableId,
@@ -1408,92 +1425,76 @@ impl<'a> ExecutionContext<'a> {
             return Ok(None);
         };
 
-        // Index already built
+        // Index already opened
         if self.pk_indexes.contains_key(&table_id) {
             return Ok(Some(self.pk_indexes.get_mut(&table_id).unwrap()));
         }
 
-        // Try to load index from file first
-        let index_path = self.data_dir.join(format!("{}.pk_idx", table_meta.name));
-        let index = if index_path.exists() {
-            match pk_index::PrimaryKeyIndex::load_from_file(&index_path) {
-                Ok(idx) => {
-                    // Validate pk_columns match catalog
-                    if idx.pk_columns() != pk_columns.as_slice() {
-                        return Err(DbError::Executor(
-                            "PK index column mismatch with catalog".into(),
-                        ));
-                    }
-                    idx
-                }
+        let index = if self.is_durable(table_id) {
+            let index_path = self.data_dir.join(format!("{}.pk_idx", table_meta.name));
+            let key = self.catalog.encryption_key();
+            let compression = table_meta.compression;
+            match pk_index::PrimaryKeyIndex::open(&index_path, pk_columns.clone(), key) {
+                Ok(index) => index.with_compression(compression),
                 Err(_) => {
-                    // Corrupt file, fall back to heap scan
-                    self.build_pk_index_from_heap(table_id, pk_columns)?
+                    // Missing or corrupt file, rebuild it from the table
+                    let index =
+                        pk_index::PrimaryKeyIndex::create(&index_path, pk_columns.clone(), key)?
+                            .with_compression(compression);
+                    self.build_pk_index_from_heap(table_id, index)?
                 }
             }
         } else {
-            // No file, build from heap
-            self.build_pk_index_from_heap(table_id, pk_columns)?
+            let index = pk_index::PrimaryKeyIndex::new(pk_columns.clone());
+            self.build_pk_index_from_heap(table_id, index)?
         };
 
         self.pk_indexes.insert(table_id, index);
         Ok(Some(self.pk_indexes.get_mut(&table_id).unwrap()))
     }
 
-    /// Build primary key index by scanning all existing rows from the heap file.
+    /// Fill an empty primary key index by scanning all existing rows from the table.
     fn build_pk_index_from_heap(
         &mut self,
         table_id: TableId,
-        pk_columns: &[common::ColumnId],
+        mut index: pk_index::PrimaryKeyIndex,
     ) -> DbResult<pk_index::PrimaryKeyIndex> {
         let table_meta = self.catalog.table_by_id(table_id)?;
-        let mut index = pk_index::PrimaryKeyIndex::new(pk_columns.to_vec());
-
-        let file_path = self.data_dir.join(format!("{}.heap", table_meta.name));
-        let mut heap_file = storage::HeapFile::open(&file_path, table_id.0)?;
-
-        // Scan all pages and slots to find existing rows
-        let mut page_id = common::PageId(0);
-        loop {
-            let mut found_row_in_page = false;
-
-            for slot in 0..100 {
-                let rid = common::RecordId { page_id, slot };
-                // get() returns Ok(row) or Err if slot is empty/invalid
-                if let Ok(row) = heap_file.get(rid) {
-                    found_row_in_page = true;
-                    let key = index.extract_key(&row)?;
-                    // Silently ignore duplicates during index build (existing data may be inconsistent)
-                    let _ = index.insert(key, rid);
-                }
-            }
 
-            // Move to next page if we found any rows, otherwise we've scanned all data
-            if found_row_in_page {
-                page_id = common::PageId(page_id.0 + 1);
-            } else {
-                break;
-            }
-        }
+        let mut heap_file =
+            self.engines
+                .open(&self.data_dir, table_meta, self.catalog.encryption_key())?;
 
+        index.fill(heap_file.as_mut())?;
         Ok(index)
     }
 
-    /// Save the primary key index for a table to disk.
+    /// Make changes to the primary key index for a table durable.
+    ///
+    /// B+Tree index files are flushed. Indexes of tables on non-durable
+    /// engines are kept in memory only: their rows do not outlive the
+    /// process, so a saved index would go stale.
     ///
     /// # Errors
     ///
     /// Returns `DbError::Storage` if the index file cannot be written.
     pub fn save_pk_index(&mut self, table_id: TableId) -> DbResult<()> {
-        if let Some(index) = self.pk_indexes.get(&table_id) {
-            let table_meta = self.catalog.table_by_id(table_id)?;
-            let path = self.data_dir.join(format!("{}.pk_idx", table_meta.name));
-            index.save_to_file(&path)?;
+        if let Some(index) = self.pk_indexes.get_mut(&table_id) {
+            index.persist()?;
         }
         Ok(())
     }
 }
 
+impl Drop for ExecutionContext<'_> {
+    fn drop(&mut self) {
+        // Later work on this thread fetches at normal priority again
+        if self.priority != Priority::default() {
+            self.pager.set_priority(Priority::default());
+        }
+    }
+}
+
 /// Format execution statistics from an executor for EXPLAIN ANALYZE output.
 ///
 /// Displays operator name, timing, row counts, and operator-specific metrics
@@ -1541,8 +1542,8 @@ pub fn execute_query(plan: PhysicalPlan, ctx: &mut ExecutionContext) -> DbResult
     executor.open(ctx)?;
 
     let mut results = Vec::new();
-    while let Some(row) = executor.next(ctx)? {
-        results.push(row);
+    while let Some(batch) = executor.next_batch(ctx, BATCH_SIZE)? {
+        results.extend(batch);
     }
 
     executor.close(ctx)?;
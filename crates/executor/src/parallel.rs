@@ -0,0 +1,219 @@
+//! Parallel sequential scans.
+//!
+//! A sequential scan run with more than zero parallel workers (see
+//! [`ExecutionContext::with_max_parallel_workers`]) splits the pages it reads
+//! into morsels of [`MORSEL_PAGES`] pages. Worker threads each open their own
+//! handle on the table's storage and claim morsels one at a time until none
+//! are left, sending the rows of each to a [`Gather`] that the scan returns
+//! them from. Rows arrive in whatever order the workers finish their morsels.
+//!
+//! [`ExecutionContext::with_max_parallel_workers`]: crate::ExecutionContext::with_max_parallel_workers
+
+use crate::{EngineRegistry, SchemaHeap};
+use catalog::TableMeta;
+use common::crypto::EncryptionKey;
+use common::{ColumnId, DbResult, PageId, Row};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use storage::HeapTable;
+
+/// Pages each worker claims at a time.
+pub(crate) const MORSEL_PAGES: u64 = 16;
+
+/// Morsels read ahead of the scan's consumer, per worker.
+const QUEUED_MORSELS: usize = 2;
+
+/// A run of consecutive pages that one worker reads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Morsel {
+    pub start: PageId,
+    pub pages: u64,
+}
+
+/// Split runs of pages, given as their first page and length, into morsels.
+pub(crate) fn morsels(runs: &[(PageId, u64)]) -> Vec<Morsel> {
+    let mut morsels = Vec::new();
+    for &(start, pages) in runs {
+        let mut offset = 0;
+        while offset < pages {
+            morsels.push(Morsel {
+                start: PageId(start.0 + offset),
+                pages: MORSEL_PAGES.min(pages - offset),
+            });
+            offset += MORSEL_PAGES;
+        }
+    }
+    morsels
+}
+
+/// What a worker needs to open the table on its own thread.
+pub(crate) struct ScanSource {
+    pub data_dir: PathBuf,
+    pub table: TableMeta,
+    pub key: Option<EncryptionKey>,
+    pub engines: Arc<EngineRegistry>,
+    /// Columns to decode; all of them if `None`
+    pub projection: Option<Vec<ColumnId>>,
+}
+
+/// Collects the rows that parallel workers read from their morsels.
+///
+/// Dropping it stops the workers after the morsels they are reading and
+/// waits for them to finish.
+pub(crate) struct Gather {
+    receiver: Option<Receiver<DbResult<Vec<Row>>>>,
+    stop: Arc<AtomicBool>,
+    workers: Vec<JoinHandle<()>>,
+    pending: std::vec::IntoIter<Row>,
+}
+
+impl Gather {
+    /// Start up to `workers` threads reading `morsels` from `source`.
+    pub(crate) fn start(source: ScanSource, morsels: Vec<Morsel>, workers: usize) -> Self {
+        let workers = workers.clamp(1, morsels.len().max(1));
+        let (sender, receiver) = mpsc::sync_channel(workers * QUEUED_MORSELS);
+        let source = Arc::new(source);
+        let morsels = Arc::new(morsels);
+        let claimed = Arc::new(AtomicUsize::new(0));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let workers = (0..workers)
+            .map(|_| {
+                let source = Arc::clone(&source);
+                let morsels = Arc::clone(&morsels);
+                let claimed = Arc::clone(&claimed);
+                let stop = Arc::clone(&stop);
+                let sender = sender.clone();
+                std::thread::spawn(move || {
+                    run_worker(&source, &morsels, &claimed, &stop, &sender);
+                })
+            })
+            .collect();
+
+        Self {
+            receiver: Some(receiver),
+            stop,
+            workers,
+            pending: Vec::new().into_iter(),
+        }
+    }
+
+    /// Next row read by any worker, or `None` once every morsel is read.
+    ///
+    /// The first error a worker hits is returned, ending the scan.
+    pub(crate) fn next(&mut self) -> DbResult<Option<Row>> {
+        loop {
+            if let Some(row) = self.pending.next() {
+                return Ok(Some(row));
+            }
+            let Some(receiver) = &self.receiver else {
+                return Ok(None);
+            };
+            match receiver.recv() {
+                Ok(Ok(rows)) => self.pending = rows.into_iter(),
+                Ok(Err(e)) => {
+                    self.finish();
+                    return Err(e);
+                }
+                // Every worker has finished and hung up
+                Err(_) => {
+                    self.finish();
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    /// Stop the workers and wait for them.
+    fn finish(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // Dropping the receiver unblocks workers waiting to send
+        self.receiver = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for Gather {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Claim and read morsels until none are left, the gather stops, or reading
+/// one fails.
+fn run_worker(
+    source: &ScanSource,
+    morsels: &[Morsel],
+    claimed: &AtomicUsize,
+    stop: &AtomicBool,
+    sender: &SyncSender<DbResult<Vec<Row>>>,
+) {
+    let file = match source
+        .engines
+        .open(&source.data_dir, &source.table, source.key.as_ref())
+    {
+        Ok(file) => file,
+        Err(e) => {
+            let _ = sender.send(Err(e));
+            return;
+        }
+    };
+    let mut heap_table = SchemaHeap {
+        file,
+        schema: &source.table.schema,
+        storage_wait: None,
+    };
+
+    while !stop.load(Ordering::Relaxed) {
+        let Some(morsel) = morsels.get(claimed.fetch_add(1, Ordering::Relaxed)) else {
+            return;
+        };
+        let mut rows = Vec::new();
+        let read = (0..morsel.pages).try_for_each(|page| {
+            let page = PageId(morsel.start.0 + page);
+            let found = heap_table.scan_page(page, source.projection.as_deref())?;
+            rows.extend(found.unwrap_or_default());
+            Ok(())
+        });
+        let failed = read.is_err();
+        if sender.send(read.map(|()| rows)).is_err() || failed {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn morsels_split_each_run_separately() {
+        let runs = [(PageId(0), 40), (PageId(1000), 10)];
+        assert_eq!(
+            morsels(&runs),
+            vec![
+                Morsel {
+                    start: PageId(0),
+                    pages: 16
+                },
+                Morsel {
+                    start: PageId(16),
+                    pages: 16
+                },
+                Morsel {
+                    start: PageId(32),
+                    pages: 8
+                },
+                Morsel {
+                    start: PageId(1000),
+                    pages: 10
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Storage of partitioned tables.
+//!
+//! Each partition of a partitioned table keeps its rows in storage of its own,
+//! opened through the table's engine under the partition's storage name (see
+//! [`catalog::TableMeta::storage_units`]). A [`PartitionedTable`] presents the
+//! partitions as a single [`HeapTable`]: the high bits of a page number hold
+//! the partition's position, so record IDs stay unique across the table and
+//! the primary key and secondary indexes can point into any partition.
+
+use catalog::{Partitioning, TableMeta};
+use common::compression::Compression;
+use common::crypto::EncryptionKey;
+use common::{ColumnId, DbError, DbResult, PageId, RecordId, Row};
+use std::path::PathBuf;
+use std::sync::Arc;
+use storage::row_format::RowLayout;
+use storage::{HeapTable, PageUsage, TableEngine};
+use types::Value;
+
+/// Bits of a page number addressing the page within its partition.
+const PARTITION_PAGE_BITS: u32 = 32;
+const LOCAL_PAGE_MASK: u64 = (1 << PARTITION_PAGE_BITS) - 1;
+
+/// The partitions of one table, opened on first use.
+pub(crate) struct PartitionedTable {
+    engine: Arc<dyn TableEngine>,
+    data_dir: PathBuf,
+    key: Option<EncryptionKey>,
+    table_name: String,
+    partitioning: Partitioning,
+    storage: Vec<(String, u64)>,
+    open: Vec<Option<Box<dyn HeapTable>>>,
+    /// Applied to each partition as it is opened.
+    fillfactor: Option<u8>,
+    compression: Compression,
+    layout: Option<RowLayout>,
+}
+
+impl PartitionedTable {
+    pub(crate) fn new(
+        engine: Arc<dyn TableEngine>,
+        data_dir: PathBuf,
+        table: &TableMeta,
+        partitioning: &Partitioning,
+        key: Option<&EncryptionKey>,
+    ) -> Self {
+        let storage: Vec<(String, u64)> = table
+            .storage_units()
+            .into_iter()
+            .map(|(name, id)| (name, id.0))
+            .collect();
+        Self {
+            engine,
+            data_dir,
+            key: key.cloned(),
+            table_name: table.name.clone(),
+            partitioning: partitioning.clone(),
+            open: storage.iter().map(|_| None).collect(),
+            storage,
+            fillfactor: None,
+            compression: Compression::None,
+            layout: None,
+        }
+    }
+
+    /// The storage of partition `position`, opening it if needed.
+    fn partition(&mut self, position: usize) -> DbResult<&mut Box<dyn HeapTable>> {
+        let Some(slot) = self.open.get_mut(position) else {
+            return Err(DbError::Storage(format!(
+                "page beyond the last partition of table '{}'",
+                self.table_name
+            )));
+        };
+        if slot.is_none() {
+            let (name, id) = &self.storage[position];
+            let mut partition = self
+                .engine
+                .open(&self.data_dir, name, *id, self.key.as_ref())?;
+            if let Some(fillfactor) = self.fillfactor {
+                partition.set_fillfactor(fillfactor);
+            }
+            partition.set_compression(self.compression);
+            if let Some(layout) = &self.layout {
+                partition.set_row_layout(layout.clone());
+            }
+            *slot = Some(partition);
+        }
+        Ok(slot.as_mut().expect("partition opened above"))
+    }
+
+    /// Position of the partition that must hold `row`.
+    fn route(&self, row: &Row) -> DbResult<usize> {
+        let key = row
+            .values
+            .get(self.partitioning.column as usize)
+            .unwrap_or(&Value::Null);
+        self.partitioning.partition_for(key).ok_or_else(|| {
+            DbError::Constraint(format!(
+                "no partition of table '{}' accepts key {:?}",
+                self.table_name, key
+            ))
+        })
+    }
+
+    /// Split a table-wide record ID into a partition and its local record ID.
+    fn split(rid: RecordId) -> (usize, RecordId) {
+        let position = (rid.page_id.0 >> PARTITION_PAGE_BITS) as usize;
+        let local = RecordId {
+            page_id: PageId(rid.page_id.0 & LOCAL_PAGE_MASK),
+            slot: rid.slot,
+        };
+        (position, local)
+    }
+
+    /// The table-wide record ID of `local` in partition `position`.
+    fn join(position: usize, local: RecordId) -> RecordId {
+        RecordId {
+            page_id: PageId(((position as u64) << PARTITION_PAGE_BITS) | local.page_id.0),
+            slot: local.slot,
+        }
+    }
+}
+
+impl HeapTable for PartitionedTable {
+    fn insert(&mut self, row: &Row) -> DbResult<RecordId> {
+        let position = self.route(row)?;
+        let local = self.partition(position)?.insert(row)?;
+        Ok(Self::join(position, local))
+    }
+
+    fn get(&mut self, rid: RecordId) -> DbResult<Row> {
+        let (position, local) = Self::split(rid);
+        let mut row = self.partition(position)?.get(local)?;
+        if row.rid().is_some() {
+            row.set_rid(Some(rid));
+        }
+        Ok(row)
+    }
+
+    fn get_columns(&mut self, rid: RecordId, columns: &[ColumnId]) -> DbResult<Row> {
+        let (position, local) = Self::split(rid);
+        let mut row = self.partition(position)?.get_columns(local, columns)?;
+        if row.rid().is_some() {
+            row.set_rid(Some(rid));
+        }
+        Ok(row)
+    }
+
+    /// Update a row in place, or move it if its new key belongs to another
+    /// partition.
+    fn update(&mut self, rid: RecordId, row: &Row) -> DbResult<RecordId> {
+        let (position, local) = Self::split(rid);
+        let target = self.route(row)?;
+        if target == position {
+            let local = self.partition(position)?.update(local, row)?;
+            return Ok(Self::join(position, local));
+        }
+        let moved = self.partition(target)?.insert(row)?;
+        self.partition(position)?.delete(local)?;
+        Ok(Self::join(target, moved))
+    }
+
+    fn delete(&mut self, rid: RecordId) -> DbResult<()> {
+        let (position, local) = Self::split(rid);
+        self.partition(position)?.delete(local)
+    }
+
+    fn page_runs(&self) -> Vec<PageId> {
+        (0..self.storage.len())
+            .map(|position| PageId((position as u64) << PARTITION_PAGE_BITS))
+            .collect()
+    }
+
+    fn run_pages(&mut self, start: PageId) -> DbResult<u64> {
+        let (position, local) = Self::split(RecordId {
+            page_id: start,
+            slot: 0,
+        });
+        self.partition(position)?.run_pages(local.page_id)
+    }
+
+    fn scan_page(
+        &mut self,
+        page: PageId,
+        columns: Option<&[ColumnId]>,
+    ) -> DbResult<Option<Vec<Row>>> {
+        let (position, local) = Self::split(RecordId {
+            page_id: page,
+            slot: 0,
+        });
+        let Some(mut rows) = self
+            .partition(position)?
+            .scan_page(local.page_id, columns)?
+        else {
+            return Ok(None);
+        };
+        for row in &mut rows {
+            if let Some(rid) = row.rid() {
+                row.set_rid(Some(Self::join(position, rid)));
+            }
+        }
+        Ok(Some(rows))
+    }
+
+    /// The partitions' usage added up, if every partition keeps its rows in
+    /// pages.
+    fn page_usage(&mut self) -> DbResult<Option<PageUsage>> {
+        let mut total = PageUsage::default();
+        for position in 0..self.storage.len() {
+            let Some(usage) = self.partition(position)?.page_usage()? else {
+                return Ok(None);
+            };
+            total.pages += usage.pages;
+            total.used_bytes += usage.used_bytes;
+            total.row_sizes.extend(usage.row_sizes);
+        }
+        Ok(Some(total))
+    }
+
+    fn set_fillfactor(&mut self, fillfactor: u8) {
+        self.fillfactor = Some(fillfactor);
+        for partition in self.open.iter_mut().flatten() {
+            partition.set_fillfactor(fillfactor);
+        }
+    }
+
+    fn set_compression(&mut self, compression: Compression) {
+        self.compression = compression;
+        for partition in self.open.iter_mut().flatten() {
+            partition.set_compression(compression);
+        }
+    }
+
+    fn set_row_layout(&mut self, layout: RowLayout) {
+        for partition in self.open.iter_mut().flatten() {
+            partition.set_row_layout(layout.clone());
+        }
+        self.layout = Some(layout);
+    }
+
+    /// Vacuum each partition in turn.
+    fn vacuum(&mut self) -> DbResult<Vec<(RecordId, RecordId)>> {
+        let mut moved = Vec::new();
+        for position in 0..self.storage.len() {
+            moved.extend(
+                self.partition(position)?
+                    .vacuum()?
+                    .into_iter()
+                    .map(|(old, new)| (Self::join(position, old), Self::join(position, new))),
+            );
+        }
+        Ok(moved)
+    }
+}
@@ -1,50 +1,119 @@
-//! In-memory primary key index for enforcing uniqueness constraints.
+//! Primary key index for enforcing uniqueness constraints.
 //!
-//! The `PrimaryKeyIndex` maintains a BTreeMap from primary key values to RecordIds,
-//! enabling efficient duplicate detection during INSERT operations. The index is built
-//! lazily when a table is first accessed by scanning existing rows from storage.
-
+//! The `PrimaryKeyIndex` maps primary key values to RecordIds, enabling
+//! efficient duplicate detection during INSERT operations and index scans for
+//! point and range lookups on the key. Tables on durable engines keep it in a
+//! B+Tree file (`<table>.pk_idx`) that is created with the table and updated
+//! in place by every INSERT and DELETE, so opening the index and checking a
+//! key cost O(log n) instead of a scan of the table. In an encrypted
+//! database every node of the file is sealed with the database key (see
+//! [`BTreeIndex::open_with_key`]). Tables on non-durable engines keep theirs
+//! in memory, built from their rows.
+
+use btree::BTreeIndex;
+use catalog::IndexId;
+use common::compression::Compression;
+use common::crypto::EncryptionKey;
 use common::{ColumnId, DbError, DbResult, RecordId, Row};
-use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
-use std::fs;
 use std::path::Path;
+use storage::HeapTable;
 use types::Value;
 
-/// In-memory index tracking primary key → RecordId mappings for uniqueness enforcement.
+/// Primary key indexes are not catalog indexes, so their B+Tree files all
+/// carry this ID.
+const PK_INDEX_ID: IndexId = IndexId(0);
+
+/// Index tracking primary key → RecordId mappings for uniqueness enforcement.
 ///
 /// # Design
 ///
-/// - Single-column PK: BTreeMap key is `vec![Value::Int(42)]`
-/// - Composite PK: BTreeMap key is `vec![Value::Int(1), Value::Text("foo")]`
-/// - Built on first table access by scanning heap file or loading from `.pk_idx` file
-/// - Updated on every INSERT/DELETE and persisted to disk
+/// - Single-column PK: key is `vec![Value::Int(42)]`
+/// - Composite PK: key is `vec![Value::Int(1), Value::Text("foo")]`
+/// - Opened from its `.pk_idx` file on first table access, or rebuilt by
+///   scanning the table if the file is missing or unreadable
+/// - Updated on every INSERT/DELETE: a B+Tree file is written through and
+///   flushed by [`PrimaryKeyIndex::persist`]
 ///
 /// # Example
 ///
 /// ```ignore
-/// let mut index = PrimaryKeyIndex::new(vec![0]); // PRIMARY KEY (id)
+/// let mut index = PrimaryKeyIndex::create(Path::new("table.pk_idx"), vec![0], None)?; // PRIMARY KEY (id)
 /// index.insert(vec![Value::Int(1)], RecordId { page_id: PageId(0), slot: 0 })?;
-/// assert!(index.contains(&vec![Value::Int(1)]));
-/// index.save_to_file(Path::new("table.pk_idx"))?;
+/// assert!(index.contains(&[Value::Int(1)])?);
 /// ```
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug)]
 pub struct PrimaryKeyIndex {
     /// Column ordinals that comprise the primary key (in order)
     pk_columns: Vec<ColumnId>,
     /// Map from PK value tuple to RecordId
-    index: BTreeMap<Vec<Value>, RecordId>,
+    entries: Entries,
+}
+
+/// Where the entries of a [`PrimaryKeyIndex`] are kept.
+#[derive(Debug)]
+enum Entries {
+    /// A B+Tree file, written through on every change
+    File(BTreeIndex),
+    /// A map in memory, for tables whose rows do not outlive the process
+    Memory(BTreeMap<Vec<Value>, RecordId>),
 }
 
 impl PrimaryKeyIndex {
-    /// Create a new primary key index for the given column ordinals.
+    /// Create a new in-memory primary key index for the given column ordinals.
     pub fn new(pk_columns: Vec<ColumnId>) -> Self {
         Self {
             pk_columns,
-            index: BTreeMap::new(),
+            entries: Entries::Memory(BTreeMap::new()),
         }
     }
 
+    /// Create an empty B+Tree index at `path`, replacing any existing file,
+    /// whose nodes are encrypted with `key` if one is given.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DbError::Storage` if the file cannot be written.
+    pub fn create(
+        path: &Path,
+        pk_columns: Vec<ColumnId>,
+        key: Option<&EncryptionKey>,
+    ) -> DbResult<Self> {
+        Ok(Self {
+            pk_columns,
+            entries: Entries::File(BTreeIndex::create_with_key(path, PK_INDEX_ID, key)?),
+        })
+    }
+
+    /// Open an index created by [`PrimaryKeyIndex::create`] with the same key.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DbError::Storage` if the file is missing, is not a B+Tree, or
+    /// was encrypted with a different key.
+    pub fn open(
+        path: &Path,
+        pk_columns: Vec<ColumnId>,
+        key: Option<&EncryptionKey>,
+    ) -> DbResult<Self> {
+        let mut btree = BTreeIndex::open_with_key(path, PK_INDEX_ID, key)?;
+        // Walk to the first leaf so a file that is not a B+Tree fails here
+        btree.search(&[])?;
+        Ok(Self {
+            pk_columns,
+            entries: Entries::File(btree),
+        })
+    }
+
+    /// Compress the B+Tree's nodes with `compression` as they are written,
+    /// unless they are encrypted. An index kept in memory ignores it.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        if let Entries::File(btree) = self.entries {
+            self.entries = Entries::File(btree.with_compression(compression));
+        }
+        self
+    }
+
     /// Extract primary key values from a row based on configured PK columns.
     ///
     /// # Errors
@@ -70,9 +139,30 @@ impl PrimaryKeyIndex {
         Ok(key)
     }
 
+    /// Look up the row with the given primary key value.
+    pub fn get(&mut self, key: &[Value]) -> DbResult<Option<RecordId>> {
+        match &mut self.entries {
+            Entries::File(btree) => Ok(btree.search(key)?.first().copied()),
+            Entries::Memory(index) => Ok(index.get(key).copied()),
+        }
+    }
+
+    /// The rows whose keys lie between `low` and `high`, both included, in
+    /// key order.
+    pub fn range(&mut self, low: &[Value], high: &[Value]) -> DbResult<Vec<RecordId>> {
+        match &mut self.entries {
+            Entries::File(btree) => btree.range_scan(Some(low), Some(high)),
+            Entries::Memory(_) if low > high => Ok(Vec::new()),
+            Entries::Memory(index) => Ok(index
+                .range(low.to_vec()..=high.to_vec())
+                .map(|(_, rid)| *rid)
+                .collect()),
+        }
+    }
+
     /// Check if a primary key value already exists in the index.
-    pub fn contains(&self, key: &[Value]) -> bool {
-        self.index.contains_key(key)
+    pub fn contains(&mut self, key: &[Value]) -> DbResult<bool> {
+        Ok(self.get(key)?.is_some())
     }
 
     /// Insert a new primary key → RecordId mapping.
@@ -81,31 +171,68 @@ impl PrimaryKeyIndex {
     ///
     /// Returns `DbError::Constraint` if the key already exists.
     pub fn insert(&mut self, key: Vec<Value>, rid: RecordId) -> DbResult<()> {
-        if self.index.contains_key(&key) {
+        if self.contains(&key)? {
             return Err(DbError::Constraint(format!(
                 "duplicate primary key value: {:?}",
                 key
             )));
         }
-        self.index.insert(key, rid);
+        match &mut self.entries {
+            Entries::File(btree) => btree.insert(key, rid)?,
+            Entries::Memory(index) => {
+                index.insert(key, rid);
+            }
+        }
         Ok(())
     }
 
     /// Remove a primary key mapping.
     ///
     /// Returns `true` if the key was present, `false` otherwise.
-    pub fn remove(&mut self, key: &[Value]) -> bool {
-        self.index.remove(key).is_some()
+    pub fn remove(&mut self, key: &[Value]) -> DbResult<bool> {
+        let Some(rid) = self.get(key)? else {
+            return Ok(false);
+        };
+        match &mut self.entries {
+            Entries::File(btree) => btree.delete(key, rid),
+            Entries::Memory(index) => Ok(index.remove(key).is_some()),
+        }
+    }
+
+    /// Add the key of every row in `table`.
+    ///
+    /// Keys the index already holds are skipped, since existing data may be
+    /// inconsistent.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the table cannot be read or the index cannot be written.
+    pub fn fill(&mut self, table: &mut dyn HeapTable) -> DbResult<()> {
+        for entry in storage::Scan::new(table) {
+            let (rid, row) = entry?;
+            let key = self.extract_key(&row)?;
+            if let Err(e) = self.insert(key, rid) {
+                if !matches!(e, DbError::Constraint(_)) {
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
     }
 
     /// Get the number of entries in the index.
-    pub fn len(&self) -> usize {
-        self.index.len()
+    ///
+    /// Counting a B+Tree index reads all of its leaves.
+    pub fn len(&mut self) -> DbResult<usize> {
+        match &mut self.entries {
+            Entries::File(btree) => Ok(btree.scan_all()?.len()),
+            Entries::Memory(index) => Ok(index.len()),
+        }
     }
 
     /// Check if the index is empty.
-    pub fn is_empty(&self) -> bool {
-        self.index.is_empty()
+    pub fn is_empty(&mut self) -> DbResult<bool> {
+        Ok(self.len()? == 0)
     }
 
     /// Get the primary key column ordinals.
@@ -113,36 +240,19 @@ impl PrimaryKeyIndex {
         &self.pk_columns
     }
 
-    /// Save the index to a file using bincode serialization.
-    ///
-    /// # Errors
+    /// Make the index's changes durable.
     ///
-    /// Returns `DbError::Storage` if serialization or file I/O fails.
-    pub fn save_to_file(&self, path: &Path) -> DbResult<()> {
-        let config = bincode::config::legacy();
-        let bytes = bincode::serde::encode_to_vec(self, config)
-            .map_err(|e| DbError::Storage(format!("Failed to serialize PK index: {}", e)))?;
-
-        fs::write(path, bytes)
-            .map_err(|e| DbError::Storage(format!("Failed to write PK index file: {}", e)))?;
-
-        Ok(())
-    }
-
-    /// Load the index from a file using bincode deserialization.
+    /// A B+Tree index flushes its file; an in-memory index has nothing to
+    /// save.
     ///
     /// # Errors
     ///
-    /// Returns `DbError::Storage` if file I/O or deserialization fails.
-    pub fn load_from_file(path: &Path) -> DbResult<Self> {
-        let bytes = fs::read(path)
-            .map_err(|e| DbError::Storage(format!("Failed to read PK index file: {}", e)))?;
-
-        let config = bincode::config::legacy();
-        let (index, _len) = bincode::serde::decode_from_slice(&bytes, config)
-            .map_err(|e| DbError::Storage(format!("Failed to deserialize PK index: {}", e)))?;
-
-        Ok(index)
+    /// Returns `DbError::Storage` if the index file cannot be written.
+    pub fn persist(&mut self) -> DbResult<()> {
+        match &mut self.entries {
+            Entries::File(btree) => btree.flush(),
+            Entries::Memory(_) => Ok(()),
+        }
     }
 }
 
@@ -154,9 +264,9 @@ mod tests {
 
     #[test]
     fn new_index_is_empty() {
-        let index = PrimaryKeyIndex::new(vec![0]);
-        assert!(index.is_empty());
-        assert_eq!(index.len(), 0);
+        let mut index = PrimaryKeyIndex::new(vec![0]);
+        assert!(index.is_empty().unwrap());
+        assert_eq!(index.len().unwrap(), 0);
     }
 
     #[test]
@@ -197,8 +307,8 @@ mod tests {
         };
 
         index.insert(key.clone(), rid).unwrap();
-        assert!(index.contains(&key));
-        assert_eq!(index.len(), 1);
+        assert!(index.contains(&key).unwrap());
+        assert_eq!(index.len().unwrap(), 1);
     }
 
     #[test]
@@ -231,9 +341,9 @@ mod tests {
         };
 
         index.insert(key.clone(), rid).unwrap();
-        assert!(index.remove(&key));
-        assert!(!index.contains(&key));
-        assert_eq!(index.len(), 0);
+        assert!(index.remove(&key).unwrap());
+        assert!(!index.contains(&key).unwrap());
+        assert_eq!(index.len().unwrap(), 0);
     }
 
     #[test]
@@ -241,7 +351,7 @@ mod tests {
         let mut index = PrimaryKeyIndex::new(vec![0]);
         let key = vec![Value::Int(999)];
 
-        assert!(!index.remove(&key));
+        assert!(!index.remove(&key).unwrap());
     }
 
     #[test]
@@ -259,7 +369,7 @@ mod tests {
         index.insert(key1.clone(), rid).unwrap();
         index.insert(key2.clone(), rid).unwrap();
         index.insert(key3.clone(), rid).unwrap();
-        assert_eq!(index.len(), 3);
+        assert_eq!(index.len().unwrap(), 3);
 
         // But duplicate of key1 should fail
         let result = index.insert(key1, rid);
@@ -267,124 +377,85 @@ mod tests {
     }
 
     #[test]
-    fn serialize_empty_index() {
-        use tempfile::NamedTempFile;
-
-        let index = PrimaryKeyIndex::new(vec![0]);
-        let temp_file = NamedTempFile::new().unwrap();
-
-        // Save empty index
-        index.save_to_file(temp_file.path()).unwrap();
-
-        // Load and verify
-        let loaded = PrimaryKeyIndex::load_from_file(temp_file.path()).unwrap();
-        assert!(loaded.is_empty());
-        assert_eq!(loaded.pk_columns(), &[0]);
-    }
-
-    #[test]
-    fn serialize_single_key() {
-        use tempfile::NamedTempFile;
-
-        let mut index = PrimaryKeyIndex::new(vec![0]);
-        let key = vec![Value::Int(42)];
-        let rid = RecordId {
-            page_id: PageId(5),
-            slot: 3,
-        };
-
-        index.insert(key.clone(), rid).unwrap();
-
-        let temp_file = NamedTempFile::new().unwrap();
-        index.save_to_file(temp_file.path()).unwrap();
-
-        // Load and verify
-        let loaded = PrimaryKeyIndex::load_from_file(temp_file.path()).unwrap();
-        assert_eq!(loaded.len(), 1);
-        assert!(loaded.contains(&key));
-        assert_eq!(loaded.pk_columns(), &[0]);
-    }
-
-    #[test]
-    fn serialize_composite_keys() {
-        use tempfile::NamedTempFile;
-
-        let mut index = PrimaryKeyIndex::new(vec![1, 0]);
-        let key1 = vec![Value::Text("alice".into()), Value::Int(1)];
-        let key2 = vec![Value::Text("bob".into()), Value::Int(2)];
-        let rid1 = RecordId {
-            page_id: PageId(0),
-            slot: 0,
-        };
-        let rid2 = RecordId {
-            page_id: PageId(0),
-            slot: 1,
-        };
-
-        index.insert(key1.clone(), rid1).unwrap();
-        index.insert(key2.clone(), rid2).unwrap();
-
-        let temp_file = NamedTempFile::new().unwrap();
-        index.save_to_file(temp_file.path()).unwrap();
-
-        // Load and verify
-        let loaded = PrimaryKeyIndex::load_from_file(temp_file.path()).unwrap();
-        assert_eq!(loaded.len(), 2);
-        assert!(loaded.contains(&key1));
-        assert!(loaded.contains(&key2));
-        assert_eq!(loaded.pk_columns(), &[1, 0]);
+    fn open_missing_file_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = PrimaryKeyIndex::open(&dir.path().join("users.pk_idx"), vec![0], None);
+        assert!(result.is_err());
     }
 
     #[test]
-    fn load_from_missing_file_errors() {
-        use std::path::Path;
+    fn btree_index_persists_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("users.pk_idx");
+
+        {
+            let mut index = PrimaryKeyIndex::create(&path, vec![0], None).unwrap();
+            for i in 0..300 {
+                let rid = RecordId {
+                    page_id: PageId((i / 100) as u64),
+                    slot: (i % 100) as u16,
+                };
+                index.insert(vec![Value::Int(i)], rid).unwrap();
+            }
+            assert!(index.remove(&[Value::Int(7)]).unwrap());
+            index.persist().unwrap();
+        }
 
-        let result = PrimaryKeyIndex::load_from_file(Path::new("/nonexistent/path.pk_idx"));
-        assert!(result.is_err());
-        assert!(format!("{:?}", result).contains("Failed to read PK index file"));
+        let mut index = PrimaryKeyIndex::open(&path, vec![0], None).unwrap();
+        assert_eq!(index.len().unwrap(), 299);
+        assert!(!index.contains(&[Value::Int(7)]).unwrap());
+        assert_eq!(
+            index.get(&[Value::Int(150)]).unwrap(),
+            Some(RecordId {
+                page_id: PageId(1),
+                slot: 50,
+            })
+        );
+        assert!(index
+            .insert(
+                vec![Value::Int(1)],
+                RecordId {
+                    page_id: PageId(9),
+                    slot: 0,
+                }
+            )
+            .is_err());
     }
 
     #[test]
-    fn load_from_corrupt_file_errors() {
-        use std::io::Write;
-        use tempfile::NamedTempFile;
-
-        let mut temp_file = NamedTempFile::new().unwrap();
-        // Write garbage data
-        temp_file.write_all(b"invalid bincode data").unwrap();
+    fn open_rejects_a_file_that_is_not_a_btree() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("users.pk_idx");
+        std::fs::write(&path, vec![0xAB; storage::PAGE_SIZE]).unwrap();
 
-        let result = PrimaryKeyIndex::load_from_file(temp_file.path());
-        assert!(result.is_err());
-        assert!(format!("{:?}", result).contains("Failed to deserialize PK index"));
+        assert!(PrimaryKeyIndex::open(&path, vec![0], None).is_err());
     }
 
     #[test]
-    fn round_trip_preserves_all_entries() {
-        use tempfile::NamedTempFile;
+    fn encrypted_index_persists_in_place() {
+        use common::crypto::{self, EncryptionKey};
 
-        let mut index = PrimaryKeyIndex::new(vec![0]);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("users.pk_idx");
+        let key = EncryptionKey::from_bytes(&[7u8; crypto::KEY_LEN]).unwrap();
+        let secret = Value::Text("top-secret-key".into());
+        let rid = RecordId {
+            page_id: PageId(2),
+            slot: 4,
+        };
 
-        // Insert 100 entries
-        for i in 0..100 {
-            let key = vec![Value::Int(i)];
-            let rid = RecordId {
-                page_id: PageId((i / 10) as u64),
-                slot: (i % 10) as u16,
-            };
-            index.insert(key, rid).unwrap();
-        }
-        assert_eq!(index.len(), 100);
-
-        // Save and load
-        let temp_file = NamedTempFile::new().unwrap();
-        index.save_to_file(temp_file.path()).unwrap();
-        let loaded = PrimaryKeyIndex::load_from_file(temp_file.path()).unwrap();
-
-        // Verify all entries preserved
-        assert_eq!(loaded.len(), 100);
-        for i in 0..100 {
-            let key = vec![Value::Int(i)];
-            assert!(loaded.contains(&key));
+        {
+            let mut index = PrimaryKeyIndex::create(&path, vec![0], Some(&key)).unwrap();
+            index.insert(vec![secret.clone()], rid).unwrap();
+            index.persist().unwrap();
         }
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(!bytes
+            .windows(b"top-secret-key".len())
+            .any(|w| w == b"top-secret-key"));
+
+        let mut index = PrimaryKeyIndex::open(&path, vec![0], Some(&key)).unwrap();
+        assert_eq!(index.get(&[secret]).unwrap(), Some(rid));
+        assert!(PrimaryKeyIndex::open(&path, vec![0], None).is_err());
     }
 }
@@ -0,0 +1,331 @@
+//! Per-operator profiles for `PROFILE`.
+//!
+//! [`build_profiled_executor`](crate::build_profiled_executor) wraps every
+//! operator of the tree in a [`ProfiledExec`] that times each call into it
+//! and reads, before and after the call, the rows it returns, the heap
+//! allocations made on the thread (see [`common::alloc`]) and the time spent
+//! waiting on table storage (see
+//! [`ExecutionContext::with_storage_timing`]). A call's measurements include
+//! those of the calls it makes to its inputs, so each operator's own share
+//! is what remains after subtracting its inputs'.
+//!
+//! Operators that are not nodes of the plan, such as the scan and filter an
+//! UPDATE or DELETE reads its own table with, are counted as part of the
+//! node that built them.
+
+use std::{cell::RefCell, fmt::Write, rc::Rc, time::Duration, time::Instant};
+
+use common::{
+    alloc::{thread_allocations, Allocations},
+    DbResult, ExecutionStats, Row,
+};
+
+use crate::{ExecutionContext, Executor, RowBatch};
+
+/// Width of the bar showing each operator's share of the statement's time.
+const BAR_WIDTH: usize = 20;
+
+/// Longest operator description shown.
+const MAX_LABEL_CHARS: usize = 80;
+
+/// Measurements of one operator, including the calls it made to its inputs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OperatorCounters {
+    pub open_time: Duration,
+    pub next_time: Duration,
+    pub close_time: Duration,
+    /// Calls to `next` or `next_batch`, including the one that found no
+    /// more rows.
+    pub next_calls: u64,
+    pub rows: u64,
+    /// Heap allocations, or `None` if they are not counted.
+    pub allocations: Option<Allocations>,
+    /// Time spent in table storage, or `None` if it is not timed.
+    pub storage_wait: Option<Duration>,
+}
+
+impl OperatorCounters {
+    /// Time spent in the operator and its inputs.
+    pub fn total_time(&self) -> Duration {
+        self.open_time + self.next_time + self.close_time
+    }
+}
+
+/// The profile of an operator and, below it, of its inputs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OperatorProfile {
+    /// The operator as EXPLAIN describes it, on one line.
+    pub label: String,
+    pub counters: OperatorCounters,
+    pub inputs: Vec<OperatorProfile>,
+}
+
+impl OperatorProfile {
+    /// Time spent in the operator itself, not waiting on its inputs.
+    pub fn self_time(&self) -> Duration {
+        let inputs: Duration = self.inputs.iter().map(|i| i.counters.total_time()).sum();
+        self.counters.total_time().saturating_sub(inputs)
+    }
+
+    /// Allocations made by the operator itself.
+    pub fn self_allocations(&self) -> Option<Allocations> {
+        let mut own = self.counters.allocations?;
+        for input in &self.inputs {
+            own = own.since(input.counters.allocations.unwrap_or_default());
+        }
+        Some(own)
+    }
+
+    /// Storage wait of the operator itself.
+    pub fn self_storage_wait(&self) -> Option<Duration> {
+        let mut own = self.counters.storage_wait?;
+        for input in &self.inputs {
+            own = own.saturating_sub(input.counters.storage_wait.unwrap_or_default());
+        }
+        Some(own)
+    }
+
+    /// Render the profile as an indented tree, one operator per line, with
+    /// each operator's share of `total` drawn as a bar.
+    pub fn render(&self, total: Duration) -> String {
+        let mut out = String::new();
+        self.render_into(&mut out, 0, total);
+        out
+    }
+
+    fn render_into(&self, out: &mut String, depth: usize, total: Duration) {
+        let share = if total.is_zero() {
+            0.0
+        } else {
+            self.counters.total_time().as_secs_f64() / total.as_secs_f64()
+        };
+        let filled = ((share * BAR_WIDTH as f64).round() as usize).min(BAR_WIDTH);
+        let c = &self.counters;
+        let _ = write!(
+            out,
+            "{:indent$}{} [{}{}] {:.1}%\n{:indent$}  total={} self={} open={} next={} close={} rows={} next_calls={}",
+            "",
+            self.label,
+            "#".repeat(filled),
+            ".".repeat(BAR_WIDTH - filled),
+            share * 100.0,
+            "",
+            format_time(c.total_time()),
+            format_time(self.self_time()),
+            format_time(c.open_time),
+            format_time(c.next_time),
+            format_time(c.close_time),
+            c.rows,
+            c.next_calls,
+            indent = depth * 2,
+        );
+        if let (Some(all), Some(own)) = (c.allocations, self.self_allocations()) {
+            let _ = write!(
+                out,
+                " allocs={} ({} bytes) self_allocs={} ({} bytes)",
+                all.count, all.bytes, own.count, own.bytes
+            );
+        }
+        if let (Some(all), Some(own)) = (c.storage_wait, self.self_storage_wait()) {
+            let _ = write!(
+                out,
+                " storage_wait={} self_storage_wait={}",
+                format_time(all),
+                format_time(own)
+            );
+        }
+        out.push('\n');
+        for input in &self.inputs {
+            input.render_into(out, depth + 1, total);
+        }
+    }
+}
+
+/// Format `d` to the nanosecond below a microsecond, then to three
+/// significant decimals.
+pub fn format_time(d: Duration) -> String {
+    let nanos = d.as_nanos();
+    if nanos < 1_000 {
+        format!("{nanos}ns")
+    } else if nanos < 1_000_000 {
+        format!("{:.3}µs", nanos as f64 / 1e3)
+    } else if nanos < 1_000_000_000 {
+        format!("{:.3}ms", nanos as f64 / 1e6)
+    } else {
+        format!("{:.3}s", nanos as f64 / 1e9)
+    }
+}
+
+/// An operator's label, the counters its [`ProfiledExec`] updates and the
+/// nodes of its inputs.
+struct Node {
+    label: String,
+    counters: Rc<RefCell<OperatorCounters>>,
+    inputs: Vec<Node>,
+}
+
+impl Node {
+    fn profile(&self) -> OperatorProfile {
+        OperatorProfile {
+            label: self.label.clone(),
+            counters: self.counters.borrow().clone(),
+            inputs: self.inputs.iter().map(Node::profile).collect(),
+        }
+    }
+}
+
+/// Collects the nodes of an executor tree as it is built, children first.
+#[derive(Default)]
+pub(crate) struct Profiler {
+    built: Vec<Node>,
+}
+
+impl Profiler {
+    /// Mark the start of an operator's construction; the nodes built after
+    /// the mark are its inputs.
+    pub(crate) fn begin(&self) -> usize {
+        self.built.len()
+    }
+
+    /// Wrap `executor`, begun at `mark` and described by `label`, so that its
+    /// calls are profiled.
+    pub(crate) fn wrap(
+        &mut self,
+        mark: usize,
+        label: &str,
+        executor: Box<dyn Executor>,
+    ) -> Box<dyn Executor> {
+        let mut label = label.lines().next().unwrap_or_default().to_string();
+        if let Some((cut, _)) = label.char_indices().nth(MAX_LABEL_CHARS) {
+            label.truncate(cut);
+            label.push_str("...");
+        }
+        let counters = Rc::new(RefCell::new(OperatorCounters::default()));
+        let inputs = self.built.split_off(mark);
+        self.built.push(Node {
+            label,
+            counters: counters.clone(),
+            inputs,
+        });
+        Box::new(ProfiledExec {
+            inner: executor,
+            counters,
+        })
+    }
+
+    /// The profile of the root operator, which fills in as it runs.
+    pub(crate) fn finish(mut self) -> Profile {
+        Profile {
+            root: self.built.pop(),
+        }
+    }
+}
+
+/// The profile of an executor tree built with
+/// [`build_profiled_executor`](crate::build_profiled_executor). Its counters
+/// keep filling in while the tree runs; [`Profile::report`] reads them.
+pub struct Profile {
+    root: Option<Node>,
+}
+
+impl Profile {
+    /// The operators' measurements so far, from the root down.
+    pub fn report(&self) -> Option<OperatorProfile> {
+        self.root.as_ref().map(Node::profile)
+    }
+}
+
+/// Measures the calls into the operator it wraps.
+struct ProfiledExec {
+    inner: Box<dyn Executor>,
+    counters: Rc<RefCell<OperatorCounters>>,
+}
+
+/// Readings taken before a call, to measure the call by.
+struct Reading {
+    start: Instant,
+    allocations: Option<Allocations>,
+    storage_wait: Option<Duration>,
+}
+
+impl ProfiledExec {
+    fn read(ctx: &ExecutionContext) -> Reading {
+        Reading {
+            allocations: thread_allocations(),
+            storage_wait: ctx.storage_wait(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Add the call that began at `before` to the counters, returning how
+    /// long it took.
+    fn record(&self, before: Reading, ctx: &ExecutionContext) -> Duration {
+        let elapsed = before.start.elapsed();
+        let mut counters = self.counters.borrow_mut();
+        if let (Some(now), Some(then)) = (thread_allocations(), before.allocations) {
+            let made = now.since(then);
+            let total = counters
+                .allocations
+                .get_or_insert_with(Allocations::default);
+            total.count += made.count;
+            total.bytes += made.bytes;
+        }
+        if let (Some(now), Some(then)) = (ctx.storage_wait(), before.storage_wait) {
+            *counters.storage_wait.get_or_insert_with(Duration::default) +=
+                now.saturating_sub(then);
+        }
+        elapsed
+    }
+}
+
+impl Executor for ProfiledExec {
+    fn open(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
+        let before = Self::read(ctx);
+        let result = self.inner.open(ctx);
+        let elapsed = self.record(before, ctx);
+        self.counters.borrow_mut().open_time += elapsed;
+        result
+    }
+
+    fn next(&mut self, ctx: &mut ExecutionContext) -> DbResult<Option<Row>> {
+        let before = Self::read(ctx);
+        let result = self.inner.next(ctx);
+        let elapsed = self.record(before, ctx);
+        let mut counters = self.counters.borrow_mut();
+        counters.next_time += elapsed;
+        counters.next_calls += 1;
+        if matches!(result, Ok(Some(_))) {
+            counters.rows += 1;
+        }
+        result
+    }
+
+    fn next_batch(&mut self, ctx: &mut ExecutionContext, max: usize) -> DbResult<Option<RowBatch>> {
+        let before = Self::read(ctx);
+        let result = self.inner.next_batch(ctx, max);
+        let elapsed = self.record(before, ctx);
+        let mut counters = self.counters.borrow_mut();
+        counters.next_time += elapsed;
+        counters.next_calls += 1;
+        if let Ok(Some(batch)) = &result {
+            counters.rows += batch.len() as u64;
+        }
+        result
+    }
+
+    fn close(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
+        let before = Self::read(ctx);
+        let result = self.inner.close(ctx);
+        let elapsed = self.record(before, ctx);
+        self.counters.borrow_mut().close_time += elapsed;
+        result
+    }
+
+    fn schema(&self) -> &[String] {
+        self.inner.schema()
+    }
+
+    fn stats(&self) -> Option<&ExecutionStats> {
+        self.inner.stats()
+    }
+}
@@ -1,28 +1,44 @@
-//! Project operator: selects and reorders columns.
+//! Project operator: selects, reorders, and computes columns.
 
-use crate::{ExecutionContext, Executor};
-use common::{ColumnId, DbResult, ExecutionStats, Row};
+use crate::filter::eval_resolved_expr;
+use crate::{ExecutionContext, Executor, RowBatch};
+use common::{DbResult, ExecutionStats, Row};
+use planner::ResolvedExpr;
 use std::time::Instant;
 
-/// Project operator - selects/reorders columns from input rows.
+/// Project operator - evaluates output columns from input rows.
 ///
-/// Produces rows with a subset of columns in a specified order.
-/// Each projection is a (output_name, column_id) pair.
+/// Produces rows with a subset of columns in a specified order, or values
+/// computed from them (e.g. scalar function calls).
+/// Each projection is an (output_name, expression) pair.
 pub struct ProjectExec {
     input: Box<dyn Executor>,
-    projections: Vec<(String, ColumnId)>,
+    projections: Vec<(String, ResolvedExpr)>,
     stats: ExecutionStats,
 }
 
 impl ProjectExec {
     /// Create a new project operator.
-    pub fn new(input: Box<dyn Executor>, projections: Vec<(String, ColumnId)>) -> Self {
+    pub fn new(input: Box<dyn Executor>, projections: Vec<(String, ResolvedExpr)>) -> Self {
         Self {
             input,
             projections,
             stats: ExecutionStats::default(),
         }
     }
+
+    /// Evaluate each projection against an input row.
+    fn project(&self, row: &Row) -> DbResult<Row> {
+        let projected_values = self
+            .projections
+            .iter()
+            .map(|(_name, expr)| eval_resolved_expr(expr, row))
+            .collect::<DbResult<Vec<_>>>()?;
+
+        let mut projected = Row::new(projected_values);
+        projected.set_rid(row.rid());
+        Ok(projected)
+    }
 }
 
 impl Executor for ProjectExec {
@@ -45,35 +61,28 @@ impl Executor for ProjectExec {
             }
         };
 
-        let rid = row.rid();
-
-        // Project columns by evaluating each column reference
-        let mut projected_values = Vec::with_capacity(self.projections.len());
-
-        for (_name, col_id) in &self.projections {
-            let idx = *col_id as usize;
-            let value = row
-                .values
-                .get(idx)
-                .ok_or_else(|| {
-                    common::DbError::Executor(format!(
-                        "column index {} out of bounds (row has {} columns)",
-                        idx,
-                        row.values.len()
-                    ))
-                })?
-                .clone();
-            projected_values.push(value);
-        }
-
-        let mut projected = Row::new(projected_values);
-        projected.set_rid(rid);
+        let projected = self.project(&row)?;
 
         self.stats.rows_produced += 1;
         self.stats.total_next_time += start.elapsed();
         Ok(Some(projected))
     }
 
+    fn next_batch(&mut self, ctx: &mut ExecutionContext, max: usize) -> DbResult<Option<RowBatch>> {
+        let start = Instant::now();
+
+        let batch = match self.input.next_batch(ctx, max)? {
+            Some(batch) => Some(batch.try_map(|row| self.project(&row))?),
+            None => None,
+        };
+
+        if let Some(batch) = &batch {
+            self.stats.rows_produced += batch.len() as u64;
+        }
+        self.stats.total_next_time += start.elapsed();
+        Ok(batch)
+    }
+
     fn close(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
         let start = Instant::now();
         self.input.close(ctx)?;
@@ -120,7 +129,7 @@ mod tests {
         ));
 
         // Project just the name column (index 1)
-        let projections = vec![("name".to_string(), 1)];
+        let projections = vec![("name".to_string(), ResolvedExpr::Column(1))];
         let mut project = ProjectExec::new(input, projections);
 
         let (mut ctx, _temp) = setup_test_context();
@@ -156,7 +165,10 @@ mod tests {
         ));
 
         // Project id and active (columns 0 and 2)
-        let projections = vec![("id".to_string(), 0), ("active".to_string(), 2)];
+        let projections = vec![
+            ("id".to_string(), ResolvedExpr::Column(0)),
+            ("active".to_string(), ResolvedExpr::Column(2)),
+        ];
         let mut project = ProjectExec::new(input, projections);
 
         let (mut ctx, _temp) = setup_test_context();
@@ -186,9 +198,9 @@ mod tests {
 
         // Project in reverse order: active, name, id
         let projections = vec![
-            ("active".to_string(), 2),
-            ("name".to_string(), 1),
-            ("id".to_string(), 0),
+            ("active".to_string(), ResolvedExpr::Column(2)),
+            ("name".to_string(), ResolvedExpr::Column(1)),
+            ("id".to_string(), ResolvedExpr::Column(0)),
         ];
         let mut project = ProjectExec::new(input, projections);
 
@@ -215,7 +227,10 @@ mod tests {
         let input = Box::new(MockExecutor::new(rows, vec!["id".into(), "name".into()]));
 
         // Project same column twice
-        let projections = vec![("id1".to_string(), 0), ("id2".to_string(), 0)];
+        let projections = vec![
+            ("id1".to_string(), ResolvedExpr::Column(0)),
+            ("id2".to_string(), ResolvedExpr::Column(0)),
+        ];
         let mut project = ProjectExec::new(input, projections);
 
         let (mut ctx, _temp) = setup_test_context();
@@ -234,7 +249,7 @@ mod tests {
     #[test]
     fn project_empty_input_returns_none() {
         let input = Box::new(MockExecutor::new(vec![], vec![]));
-        let projections = vec![("id".to_string(), 0)];
+        let projections = vec![("id".to_string(), ResolvedExpr::Column(0))];
         let mut project = ProjectExec::new(input, projections);
 
         let (mut ctx, _temp) = setup_test_context();
@@ -250,7 +265,7 @@ mod tests {
         let input = Box::new(MockExecutor::new(rows, vec!["id".into(), "name".into()]));
 
         // Try to project column 5 which doesn't exist
-        let projections = vec![("nonexistent".to_string(), 5)];
+        let projections = vec![("nonexistent".to_string(), ResolvedExpr::Column(5))];
         let mut project = ProjectExec::new(input, projections);
 
         let (mut ctx, _temp) = setup_test_context();
@@ -264,7 +279,7 @@ mod tests {
         let rows = vec![Row::new(vec![Value::Int(100), Value::Text("data".into())])];
         let input = Box::new(MockExecutor::new(rows, vec!["id".into(), "name".into()]));
 
-        let projections = vec![("id".to_string(), 0)];
+        let projections = vec![("id".to_string(), ResolvedExpr::Column(0))];
         let mut project = ProjectExec::new(input, projections);
 
         let (mut ctx, _temp) = setup_test_context();
@@ -288,7 +303,7 @@ mod tests {
             vec!["id".into(), "name".into(), "active".into()],
         ));
 
-        let projections = vec![("active".to_string(), 2)];
+        let projections = vec![("active".to_string(), ResolvedExpr::Column(2))];
         let mut project = ProjectExec::new(input, projections);
 
         let (mut ctx, _temp) = setup_test_context();
@@ -305,7 +320,7 @@ mod tests {
         let rows = vec![Row::new(vec![Value::Int(1)])];
         let input = Box::new(MockExecutor::new(rows, vec!["id".into()]));
 
-        let projections = vec![("id".to_string(), 0)];
+        let projections = vec![("id".to_string(), ResolvedExpr::Column(0))];
         let mut project = ProjectExec::new(input, projections);
 
         let (mut ctx, _temp) = setup_test_context();
@@ -319,7 +334,7 @@ mod tests {
         let rows = vec![Row::new(vec![Value::Int(1)])];
         let input = Box::new(MockExecutor::new(rows, vec!["id".into()]));
 
-        let projections = vec![("id".to_string(), 0)];
+        let projections = vec![("id".to_string(), ResolvedExpr::Column(0))];
         let mut project = ProjectExec::new(input, projections);
 
         let (mut ctx, _temp) = setup_test_context();
@@ -334,7 +349,7 @@ mod tests {
             "test error".into(),
         )));
 
-        let projections = vec![("id".to_string(), 0)];
+        let projections = vec![("id".to_string(), ResolvedExpr::Column(0))];
         let mut project = ProjectExec::new(input, projections);
 
         let (mut ctx, _temp) = setup_test_context();
@@ -342,4 +357,58 @@ mod tests {
         project.open(&mut ctx).unwrap();
         assert_error_contains(project.next(&mut ctx), "test error");
     }
+
+    #[test]
+    fn project_evaluates_function_calls() {
+        let rows = vec![Row::new(vec![Value::Int(1), Value::Text("alice".into())])];
+        let input = Box::new(MockExecutor::new(rows, vec!["id".into(), "name".into()]));
+
+        let projections = vec![
+            ("id".to_string(), ResolvedExpr::Column(0)),
+            (
+                "upper(name)".to_string(),
+                ResolvedExpr::Function {
+                    name: "upper".into(),
+                    args: vec![ResolvedExpr::Column(1)],
+                },
+            ),
+        ];
+        let mut project = ProjectExec::new(input, projections);
+
+        let (mut ctx, _temp) = setup_test_context();
+
+        project.open(&mut ctx).unwrap();
+        assert_next_row(
+            &mut project,
+            &mut ctx,
+            Row::new(vec![Value::Int(1), Value::Text("ALICE".into())]),
+        );
+        assert_exhausted(&mut project, &mut ctx);
+    }
+
+    #[test]
+    fn project_batches_evaluate_every_row() {
+        let rows = (1..=3).map(|i| Row::new(vec![Value::Int(i)])).collect();
+        let input = Box::new(MockExecutor::new(rows, vec!["n".into()]));
+        let projections = vec![(
+            "double".to_string(),
+            ResolvedExpr::Binary {
+                left: Box::new(ResolvedExpr::Column(0)),
+                op: expr::BinaryOp::Mul,
+                right: Box::new(ResolvedExpr::Literal(Value::Int(2))),
+            },
+        )];
+        let mut project = ProjectExec::new(input, projections);
+        let (mut ctx, _temp) = setup_test_context();
+
+        project.open(&mut ctx).unwrap();
+        let batch = project.next_batch(&mut ctx, 2).unwrap().unwrap();
+        let values: Vec<_> = batch.into_iter().map(|row| row.values).collect();
+        assert_eq!(values, vec![vec![Value::Int(2)], vec![Value::Int(4)]]);
+        // Batch and row calls can be mixed
+        assert_next_row(&mut project, &mut ctx, Row::new(vec![Value::Int(6)]));
+        assert!(project.next_batch(&mut ctx, 2).unwrap().is_none());
+        assert_eq!(project.stats().unwrap().rows_produced, 3);
+        project.close(&mut ctx).unwrap();
+    }
 }
@@ -0,0 +1,164 @@
+//! Per-statement resource accounting.
+//!
+//! Scans charge every row they read and materializing operators (sort, the
+//! inner side of a nested loop join) charge every row they buffer. Once a
+//! charge pushes usage past the statement's [`ResourceLimits`], execution
+//! stops with [`DbError::ResourceExhausted`].
+//!
+//! Memory is an estimate of buffered row sizes, not an allocator measurement.
+//! All of a statement's materializing operators draw on the same budget: once
+//! their buffered rows together exceed the work memory limit, the operator
+//! that is buffering writes rows out to a [`SpillFile`](crate::spill::SpillFile)
+//! and credits them back. Rows that are still buffered when the statement
+//! ends are never credited back, so the figure is a conservative upper bound.
+//! Every byte written to a spill file counts against the temporary disk
+//! limit.
+
+use common::{DbError, DbResult, ResourceLimits, Row};
+use types::Value;
+
+/// Resources consumed by the current statement so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// Rows read from heap files.
+    pub rows_scanned: u64,
+    /// Estimated bytes buffered by materializing operators.
+    pub memory_bytes: u64,
+    /// Bytes written to temporary files by operators that ran out of work
+    /// memory.
+    pub spilled_bytes: u64,
+}
+
+/// Usage tracked against limits for one statement.
+#[derive(Debug, Default)]
+pub struct ResourceBudget {
+    limits: ResourceLimits,
+    usage: ResourceUsage,
+}
+
+impl ResourceBudget {
+    pub fn new(limits: ResourceLimits) -> Self {
+        Self {
+            limits,
+            usage: ResourceUsage::default(),
+        }
+    }
+
+    pub fn usage(&self) -> ResourceUsage {
+        self.usage
+    }
+
+    pub fn charge_rows_scanned(&mut self, rows: u64) -> DbResult<()> {
+        self.usage.rows_scanned += rows;
+        check(
+            "rows scanned",
+            self.usage.rows_scanned,
+            self.limits.max_rows_scanned,
+        )
+    }
+
+    pub fn charge_memory(&mut self, row: &Row) -> DbResult<()> {
+        self.usage.memory_bytes += estimated_row_bytes(row);
+        check(
+            "memory bytes",
+            self.usage.memory_bytes,
+            self.limits.max_memory_bytes,
+        )
+    }
+
+    /// Credit back a row charged with [`charge_memory`](Self::charge_memory)
+    /// that is no longer buffered.
+    pub fn release_memory(&mut self, row: &Row) {
+        let bytes = estimated_row_bytes(row);
+        self.usage.memory_bytes = self.usage.memory_bytes.saturating_sub(bytes);
+    }
+
+    pub fn charge_temp_bytes(&mut self, bytes: u64) -> DbResult<()> {
+        self.usage.spilled_bytes += bytes;
+        check(
+            "temp bytes",
+            self.usage.spilled_bytes,
+            self.limits.max_temp_bytes,
+        )
+    }
+
+    /// Whether buffered rows exceed the work memory limit, so the operator
+    /// buffering should spill.
+    pub fn over_work_memory(&self) -> bool {
+        self.limits
+            .work_memory_bytes
+            .is_some_and(|limit| self.usage.memory_bytes > limit)
+    }
+}
+
+fn check(resource: &str, used: u64, limit: Option<u64>) -> DbResult<()> {
+    match limit {
+        Some(limit) if used > limit => Err(DbError::ResourceExhausted(format!(
+            "statement exceeded {limit} {resource}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Approximate heap footprint of a buffered row.
+fn estimated_row_bytes(row: &Row) -> u64 {
+    let inline = std::mem::size_of::<Row>() + row.values.len() * std::mem::size_of::<Value>();
+    let text: usize = row
+        .values
+        .iter()
+        .map(|value| match value {
+            Value::Text(s) => s.len(),
+            _ => 0,
+        })
+        .sum();
+    (inline + text) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_rejects_usage_past_the_limit() {
+        let limits = ResourceLimits::builder().max_rows_scanned(2).build();
+        let mut budget = ResourceBudget::new(limits);
+        budget.charge_rows_scanned(2).unwrap();
+        let err = budget.charge_rows_scanned(1).unwrap_err();
+        assert!(matches!(err, DbError::ResourceExhausted(_)));
+        assert_eq!(budget.usage().rows_scanned, 3);
+    }
+
+    #[test]
+    fn memory_estimate_includes_text_payload() {
+        let short = Row::new(vec![Value::Text("a".into())]);
+        let long = Row::new(vec![Value::Text("a".repeat(1000))]);
+        assert!(estimated_row_bytes(&long) >= estimated_row_bytes(&short) + 999);
+
+        let mut budget = ResourceBudget::new(ResourceLimits::default());
+        budget.charge_memory(&long).unwrap();
+        assert!(budget.usage().memory_bytes >= 1000);
+    }
+
+    #[test]
+    fn spilled_rows_are_credited_back() {
+        let limits = ResourceLimits::builder().work_memory_bytes(500).build();
+        let mut budget = ResourceBudget::new(limits);
+        let row = Row::new(vec![Value::Text("a".repeat(1000))]);
+        budget.charge_memory(&row).unwrap();
+        assert!(budget.over_work_memory());
+
+        budget.release_memory(&row);
+        assert!(!budget.over_work_memory());
+        assert_eq!(budget.usage().memory_bytes, 0);
+    }
+
+    #[test]
+    fn temp_bytes_past_the_limit_fail() {
+        let limits = ResourceLimits::builder().max_temp_bytes(100).build();
+        let mut budget = ResourceBudget::new(limits);
+        budget.charge_temp_bytes(100).unwrap();
+        let err = budget.charge_temp_bytes(1).unwrap_err();
+        assert!(err.to_string().contains("100 temp bytes"), "{err}");
+        assert_eq!(budget.usage().spilled_bytes, 101);
+    }
+}
@@ -1,12 +1,16 @@
-//! Scan operators: SeqScan and IndexScan.
+//! Scan operators: SeqScan, IndexScan, SystemScan, SeriesScan and ValuesScan.
 
 use crate::filter::eval_resolved_expr;
-use crate::{ExecutionContext, Executor};
+use crate::parallel::{self, Gather, ScanSource};
+use crate::{ExecutionContext, Executor, PrimaryKeyIndex, RowBatch, BATCH_SIZE};
 use btree::BTreeIndex;
-use catalog::{IndexId, IndexKind};
-use common::{DbResult, ExecutionStats, PageId, RecordId, Row, TableId};
+use catalog::{IndexId, IndexKind, SystemView};
+use common::{ColumnId, DbResult, ExecutionStats, PageId, RecordId, Row, TableId};
+use expr::random::Generator;
 use hash::HashIndex;
-use planner::IndexPredicate;
+use planner::{IndexPredicate, ResolvedExpr, SampleMethod, TableSample, PRIMARY_KEY_INDEX};
+use std::collections::HashSet;
+use std::sync::Arc;
 use std::time::Instant;
 use storage::HeapTable;
 use types::Value;
@@ -14,13 +18,39 @@ use types::Value;
 /// Sequential scan operator - iterates all rows in a table.
 ///
 /// Scans pages sequentially from beginning to end, fetching each page
-/// via the buffer pool and deserializing rows.
+/// via the buffer pool and deserializing rows. Partitioned tables are scanned
+/// one partition after another, optionally only some of them. A sampling
+/// scan skips rows, or whole pages without reading them, at random. With a
+/// projection, only the projected columns are decoded; on a columnar table
+/// (see [`storage::columnar`]) the other columns are not read at all.
+///
+/// With parallel workers allowed, a scan of more than one morsel of pages
+/// hands its pages to worker threads (see [`crate::parallel`]) and returns
+/// rows in the order they finish reading them. Sampling scans are never
+/// parallel.
 pub struct SeqScanExec {
     table_id: TableId,
     schema: Vec<String>,
+    /// Positions of the partitions to scan; all of them if `None`
+    partitions: Option<Vec<usize>>,
+    /// Columns to decode; all of them if `None`
+    projection: Option<Vec<ColumnId>>,
+    /// Sample to return instead of every row
+    sample: Option<TableSample>,
+    /// Draws which rows or pages the sample keeps, seeded on open
+    sampler: Generator,
+    /// Page the sample was last decided for, with `SampleMethod::System`
+    sampled_page: Option<PageId>,
+    /// First page of each run of pages to scan, chosen on the first fetch
+    runs: Option<Vec<PageId>>,
+    current_run: usize,
     current_page: PageId,
-    current_slot: u16,
+    /// Rows read from the last page and not yet returned
+    page_rows: std::vec::IntoIter<Row>,
+    /// Pages in the current run
     num_pages: Option<u64>,
+    /// Rows read by parallel workers, if the scan is parallel
+    gather: Option<Gather>,
     stats: ExecutionStats,
 }
 
@@ -30,69 +60,160 @@ impl SeqScanExec {
         Self {
             table_id,
             schema,
+            partitions: None,
+            projection: None,
+            sample: None,
+            sampler: Generator::seeded(0),
+            sampled_page: None,
+            runs: None,
+            current_run: 0,
             current_page: PageId(0),
-            current_slot: 0,
+            page_rows: Vec::new().into_iter(),
             num_pages: None,
+            gather: None,
             stats: ExecutionStats::default(),
         }
     }
 
-    /// Try to fetch the next row from storage.
-    fn fetch_next_row(&mut self, ctx: &mut ExecutionContext) -> DbResult<Option<Row>> {
+    /// Scan only the partitions at the given positions of a partitioned table.
+    pub fn with_partitions(mut self, partitions: Vec<usize>) -> Self {
+        self.partitions = Some(partitions);
+        self
+    }
+
+    /// Decode only the given columns, returning the others as NULL (see
+    /// [`HeapTable::get_columns`]). `None` decodes every column.
+    pub fn with_projection(mut self, projection: Option<Vec<ColumnId>>) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    /// Return only a random sample of the rows.
+    pub fn with_sample(mut self, sample: TableSample) -> Self {
+        self.sample = Some(sample);
+        self
+    }
+
+    /// Whether the sample keeps the next row or page.
+    fn keep(&mut self) -> bool {
+        match self.sample {
+            Some(sample) => self.sampler.next_f64() * 100.0 < sample.percent,
+            None => true,
+        }
+    }
+
+    /// First page of each run of pages the scan reads.
+    fn chosen_runs(&self, heap_table: &impl HeapTable) -> Vec<PageId> {
+        let all = heap_table.page_runs();
+        match &self.partitions {
+            Some(partitions) => partitions
+                .iter()
+                .filter_map(|&position| all.get(position).copied())
+                .collect(),
+            None => all,
+        }
+    }
+
+    /// Hand the scan to parallel workers if they are allowed and the table
+    /// has more than one morsel of pages to share out.
+    fn start_gather(&mut self, ctx: &mut ExecutionContext) -> DbResult<Option<Gather>> {
+        let workers = ctx.max_parallel_workers();
+        if workers == 0 || self.sample.is_some() {
+            return Ok(None);
+        }
+
         let mut heap_table = ctx.heap_table(self.table_id)?;
+        let runs = self
+            .chosen_runs(&heap_table)
+            .into_iter()
+            .map(|start| Ok((start, heap_table.run_pages(start)?)))
+            .collect::<DbResult<Vec<_>>>()?;
+        drop(heap_table);
+        let morsels = parallel::morsels(&runs);
+        if morsels.len() < 2 {
+            return Ok(None);
+        }
 
-        // Check if we've exhausted all pages
-        let num_pages = match self.num_pages {
-            Some(n) => n,
-            None => {
-                // Compute number of pages on first call
-                let n = compute_num_pages(&mut heap_table)?;
-                self.num_pages = Some(n);
-                n
-            }
+        self.stats.pages_scanned += runs.iter().map(|&(_, pages)| pages).sum::<u64>();
+        self.runs = Some(Vec::new());
+        let source = ScanSource {
+            data_dir: ctx.data_dir.clone(),
+            table: ctx.catalog.table_by_id(self.table_id)?.clone(),
+            key: ctx.catalog.encryption_key().cloned(),
+            engines: Arc::clone(&ctx.engines),
+            projection: self.projection.clone(),
         };
+        Ok(Some(Gather::start(source, morsels, workers)))
+    }
 
-        if self.current_page.0 >= num_pages {
-            return Ok(None);
+    /// Try to fetch the next row from storage.
+    fn fetch_next_row(&mut self, ctx: &mut ExecutionContext) -> DbResult<Option<Row>> {
+        if self.runs.is_none() {
+            self.gather = self.start_gather(ctx)?;
+        }
+        if let Some(gather) = &mut self.gather {
+            return gather.next();
+        }
+
+        let mut heap_table = ctx.heap_table(self.table_id)?;
+
+        if self.runs.is_none() {
+            self.runs = Some(self.chosen_runs(&heap_table));
         }
 
-        // Try to fetch current slot
         loop {
-            let rid = RecordId {
-                page_id: self.current_page,
-                slot: self.current_slot,
+            if let Some(row) = self.page_rows.next() {
+                // Return the row unless a row-level sample leaves it out
+                if matches!(self.sample, Some(s) if s.method == SampleMethod::Bernoulli)
+                    && !self.keep()
+                {
+                    continue;
+                }
+                return Ok(Some(row));
+            }
+
+            // Check if we've exhausted all runs of pages
+            let Some(start) = self
+                .runs
+                .as_ref()
+                .and_then(|runs| runs.get(self.current_run).copied())
+            else {
+                return Ok(None);
             };
 
-            match heap_table.get(rid) {
-                Ok(row) => {
-                    // Found a row, advance slot and return
-                    self.current_slot += 1;
-                    return Ok(Some(row));
+            let num_pages = match self.num_pages {
+                Some(n) => n,
+                None => {
+                    // Compute number of pages on entering each run
+                    let n = heap_table.run_pages(start)?;
+                    self.num_pages = Some(n);
+                    self.stats.pages_scanned += n;
+                    self.current_page = start;
+                    n
                 }
-                Err(e) => {
-                    // Check if this is just an empty/deleted slot
-                    let is_empty_slot = matches!(e, common::DbError::Storage(ref msg) if msg.contains("slot") || msg.contains("empty"));
-
-                    if is_empty_slot {
-                        // Try next slot in same page
-                        self.current_slot += 1;
-
-                        // If we've tried enough slots, move to next page
-                        // (heuristic: assume max ~100 slots per page)
-                        if self.current_slot > 100 {
-                            self.current_page = PageId(self.current_page.0 + 1);
-                            self.current_slot = 0;
-
-                            if self.current_page.0 >= num_pages {
-                                return Ok(None);
-                            }
-                        }
-                    } else {
-                        // Real error, propagate
-                        return Err(e);
-                    }
+            };
+
+            if self.current_page.0 - start.0 >= num_pages {
+                self.current_run += 1;
+                self.num_pages = None;
+                continue;
+            }
+
+            let page = self.current_page;
+            self.current_page = PageId(page.0 + 1);
+
+            // Skip the pages a page-level sample leaves out unread
+            if matches!(self.sample, Some(s) if s.method == SampleMethod::System)
+                && self.sampled_page != Some(page)
+            {
+                self.sampled_page = Some(page);
+                if !self.keep() {
+                    continue;
                 }
             }
+
+            let rows = heap_table.scan_page(page, self.projection.as_deref())?;
+            self.page_rows = rows.unwrap_or_default().into_iter();
         }
     }
 }
@@ -102,10 +223,20 @@ impl Executor for SeqScanExec {
         let start = Instant::now();
 
         // Reset state
+        self.runs = None;
+        self.gather = None;
+        self.current_run = 0;
         self.current_page = PageId(0);
-        self.current_slot = 0;
+        self.page_rows = Vec::new().into_iter();
         self.num_pages = None;
         self.stats = ExecutionStats::default();
+        self.sampled_page = None;
+        if let Some(sample) = self.sample {
+            self.sampler = match sample.seed {
+                Some(seed) => Generator::seeded(seed as u64),
+                None => Generator::unseeded(),
+            };
+        }
 
         self.stats.open_time = start.elapsed();
         Ok(())
@@ -118,19 +249,32 @@ impl Executor for SeqScanExec {
 
         if row.is_some() {
             self.stats.rows_produced += 1;
+            ctx.charge_rows_scanned(1)?;
         }
 
-        // Track pages scanned (only when we have the num_pages computed)
-        if let Some(num_pages) = self.num_pages {
-            self.stats.pages_scanned = num_pages;
+        Ok(row)
+    }
+
+    fn next_batch(&mut self, ctx: &mut ExecutionContext, max: usize) -> DbResult<Option<RowBatch>> {
+        let start = Instant::now();
+        let mut batch = RowBatch::with_capacity(max.min(BATCH_SIZE));
+        while batch.len() < max {
+            let Some(row) = self.fetch_next_row(ctx)? else {
+                break;
+            };
+            ctx.charge_rows_scanned(1)?;
+            batch.push(row);
         }
+        self.stats.total_next_time += start.elapsed();
+        self.stats.rows_produced += batch.len() as u64;
 
-        Ok(row)
+        Ok((!batch.is_empty()).then_some(batch))
     }
 
     fn close(&mut self, _ctx: &mut ExecutionContext) -> DbResult<()> {
         let start = Instant::now();
-        // Nothing to clean up for seq scan
+        // Stop any parallel workers still reading
+        self.gather = None;
         self.stats.close_time = start.elapsed();
         Ok(())
     }
@@ -147,12 +291,18 @@ impl Executor for SeqScanExec {
 /// Index scan operator - uses B+Tree index to find rows efficiently.
 ///
 /// Uses a B+Tree index to find matching RecordIds, then fetches the
-/// actual rows from the heap table.
+/// actual rows from the heap table. With further probes, as for
+/// [`planner::PhysicalPlan::IndexUnion`], the RecordIds each finds are added
+/// to the first's, and a row found more than once is fetched once.
 pub struct IndexScanExec {
     table_id: TableId,
     index_name: String,
     predicate: IndexPredicate,
+    /// Further probes, each an index name and predicate
+    union: Vec<(String, IndexPredicate)>,
     schema: Vec<String>,
+    /// Columns to decode; all of them if `None`
+    projection: Option<Vec<ColumnId>>,
     /// RecordIds matching the predicate (populated on open)
     matching_rids: Vec<RecordId>,
     /// Current position in the matching_rids vector
@@ -179,13 +329,17 @@ impl IndexScanExec {
         table_id: TableId,
         index_name: String,
         predicate: IndexPredicate,
+        #[builder(default)] union: Vec<(String, IndexPredicate)>,
         schema: Vec<String>,
+        projection: Option<Vec<ColumnId>>,
     ) -> Self {
         Self {
             table_id,
             index_name,
             predicate,
+            union,
             schema,
+            projection,
             matching_rids: Vec::new(),
             cursor: 0,
             stats: ExecutionStats::default(),
@@ -200,11 +354,34 @@ impl IndexScanExec {
         eval_resolved_expr(pred, &empty_row)
     }
 
-    /// Query the index for matching RecordIds.
-    /// Supports both BTree and Hash indexes, and composite keys.
-    fn query_index(&self, ctx: &ExecutionContext) -> DbResult<Vec<RecordId>> {
+    /// Query the indexes of every probe for matching RecordIds, each once.
+    fn query_indexes(&self, ctx: &mut ExecutionContext) -> DbResult<Vec<RecordId>> {
+        let mut rids = self.query_index(ctx, &self.index_name, &self.predicate)?;
+        if self.union.is_empty() {
+            return Ok(rids);
+        }
+        for (index_name, predicate) in &self.union {
+            rids.extend(self.query_index(ctx, index_name, predicate)?);
+        }
+        let mut seen = HashSet::with_capacity(rids.len());
+        rids.retain(|rid| seen.insert(*rid));
+        Ok(rids)
+    }
+
+    /// Query an index for matching RecordIds.
+    /// Supports both BTree and Hash indexes, the primary key index, and
+    /// composite keys.
+    fn query_index(
+        &self,
+        ctx: &mut ExecutionContext,
+        index_name: &str,
+        predicate: &IndexPredicate,
+    ) -> DbResult<Vec<RecordId>> {
+        if index_name == PRIMARY_KEY_INDEX {
+            return self.query_primary_key(ctx, predicate);
+        }
         let table_meta = ctx.catalog.table_by_id(self.table_id)?;
-        let index_meta = table_meta.index(&self.index_name)?;
+        let index_meta = table_meta.index(index_name)?;
         let index_id = index_meta.id;
         let index_kind = index_meta.kind.clone();
         let index_path = ctx.data_dir.join(format!("index_{}.idx", index_id.0));
@@ -217,11 +394,11 @@ impl IndexScanExec {
             )));
         }
 
-        match &self.predicate {
+        match predicate {
             IndexPredicate::Eq { value, .. } => {
                 let key_value = self.eval_predicate_value(value)?;
                 let key = vec![key_value];
-                self.search_index(&index_path, index_id, &index_kind, &key)
+                self.search_index(ctx, &index_path, index_id, &index_kind, &key)
             }
             IndexPredicate::CompositeEq { values, .. } => {
                 // Evaluate all values in the composite key
@@ -229,13 +406,14 @@ impl IndexScanExec {
                     .iter()
                     .map(|v| self.eval_predicate_value(v))
                     .collect::<DbResult<Vec<_>>>()?;
-                self.search_index(&index_path, index_id, &index_kind, &key)
+                self.search_index(ctx, &index_path, index_id, &index_kind, &key)
             }
             IndexPredicate::Range { low, high, .. } => {
                 // Range predicates only work with BTree indexes
                 match index_kind {
                     IndexKind::BTree => {
-                        let mut btree = BTreeIndex::open(&index_path, index_id)?;
+                        let key = ctx.catalog.encryption_key();
+                        let mut btree = BTreeIndex::open_with_key(&index_path, index_id, key)?;
                         let low_key = self.eval_predicate_value(low)?;
                         let high_key = self.eval_predicate_value(high)?;
                         btree.range_scan(Some(&[low_key]), Some(&[high_key]))
@@ -252,9 +430,42 @@ impl IndexScanExec {
         }
     }
 
+    /// Query the table's primary key index for matching RecordIds.
+    fn query_primary_key(
+        &self,
+        ctx: &mut ExecutionContext,
+        predicate: &IndexPredicate,
+    ) -> DbResult<Vec<RecordId>> {
+        let key = match predicate {
+            IndexPredicate::Eq { value, .. } => vec![self.eval_predicate_value(value)?],
+            IndexPredicate::CompositeEq { values, .. } => values
+                .iter()
+                .map(|v| self.eval_predicate_value(v))
+                .collect::<DbResult<Vec<_>>>()?,
+            IndexPredicate::Range { low, high, .. } => {
+                let low_key = self.eval_predicate_value(low)?;
+                let high_key = self.eval_predicate_value(high)?;
+                let pk_index = self.primary_key_index(ctx)?;
+                return pk_index.range(&[low_key], &[high_key]);
+            }
+        };
+        let pk_index = self.primary_key_index(ctx)?;
+        Ok(pk_index.get(&key)?.into_iter().collect())
+    }
+
+    fn primary_key_index<'c>(
+        &self,
+        ctx: &'c mut ExecutionContext,
+    ) -> DbResult<&'c mut PrimaryKeyIndex> {
+        ctx.pk_index(self.table_id)?.ok_or_else(|| {
+            common::DbError::Executor(format!("table {} has no primary key", self.table_id.0))
+        })
+    }
+
     /// Search an index for matching RecordIds.
     fn search_index(
         &self,
+        ctx: &ExecutionContext,
         index_path: &std::path::Path,
         index_id: IndexId,
         index_kind: &IndexKind,
@@ -262,11 +473,13 @@ impl IndexScanExec {
     ) -> DbResult<Vec<RecordId>> {
         match index_kind {
             IndexKind::BTree => {
-                let mut btree = BTreeIndex::open(index_path, index_id)?;
+                let mut btree =
+                    BTreeIndex::open_with_key(index_path, index_id, ctx.catalog.encryption_key())?;
                 btree.search(key)
             }
             IndexKind::Hash => {
-                let mut hash = HashIndex::open(index_path, index_id)?;
+                let mut hash =
+                    HashIndex::open_with_key(index_path, index_id, ctx.catalog.encryption_key())?;
                 hash.search(key)
             }
             // Bitmap and Trie indexes not yet implemented
@@ -286,7 +499,7 @@ impl Executor for IndexScanExec {
         self.stats = ExecutionStats::default();
 
         // Query the index for matching RecordIds
-        self.matching_rids = self.query_index(ctx)?;
+        self.matching_rids = self.query_indexes(ctx)?;
 
         self.stats.open_time = start.elapsed();
         Ok(())
@@ -304,8 +517,12 @@ impl Executor for IndexScanExec {
         self.cursor += 1;
 
         // Fetch the actual row from the heap table
-        let mut heap_table = ctx.heap_table(self.table_id)?;
-        let row = heap_table.get(rid)?;
+        let row = fetch_row(
+            &mut ctx.heap_table(self.table_id)?,
+            rid,
+            self.projection.as_deref(),
+        )?;
+        ctx.charge_rows_scanned(1)?;
 
         self.stats.rows_produced += 1;
         self.stats.total_next_time += start.elapsed();
@@ -329,42 +546,220 @@ impl Executor for IndexScanExec {
     }
 }
 
-/// Helper: compute number of pages in a heap file.
-fn compute_num_pages(heap_table: &mut impl HeapTable) -> DbResult<u64> {
-    // Try to probe increasing page IDs until we get an error
-    // This is a simple heuristic; ideally HeapTable would expose num_pages()
-    let mut page_id = 0;
-    loop {
-        let rid = RecordId {
-            page_id: PageId(page_id),
-            slot: 0,
-        };
+/// Scan of a view of catalog state, such as
+/// `information_schema.table_activity`.
+///
+/// The view's rows are built from the catalog when the scan is opened.
+pub struct SystemScanExec {
+    view: SystemView,
+    schema: Vec<String>,
+    rows: std::vec::IntoIter<Vec<Value>>,
+    stats: ExecutionStats,
+}
 
-        match heap_table.get(rid) {
-            Ok(_) => page_id += 1,
-            Err(e) => {
-                // Check if this is a "page not found" error
-                if matches!(e, common::DbError::Storage(ref msg) if msg.contains("page") || msg.contains("beyond"))
-                {
-                    return Ok(page_id);
-                }
-                // For empty slots, the page exists
-                if matches!(e, common::DbError::Storage(ref msg) if msg.contains("slot") || msg.contains("empty"))
-                {
-                    page_id += 1;
-                } else {
-                    return Err(e);
-                }
-            }
+impl SystemScanExec {
+    /// Create a scan of `view`, whose columns are named `schema`.
+    pub fn new(view: SystemView, schema: Vec<String>) -> Self {
+        Self {
+            view,
+            schema,
+            rows: Vec::new().into_iter(),
+            stats: ExecutionStats::default(),
+        }
+    }
+}
+
+impl Executor for SystemScanExec {
+    fn open(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
+        let start = Instant::now();
+        self.rows = self.view.rows(ctx.catalog).into_iter();
+        self.stats = ExecutionStats::default();
+        self.stats.open_time = start.elapsed();
+        Ok(())
+    }
+
+    fn next(&mut self, _ctx: &mut ExecutionContext) -> DbResult<Option<Row>> {
+        let row = self.rows.next().map(Row::new);
+        if row.is_some() {
+            self.stats.rows_produced += 1;
+        }
+        Ok(row)
+    }
+
+    fn close(&mut self, _ctx: &mut ExecutionContext) -> DbResult<()> {
+        self.rows = Vec::new().into_iter();
+        Ok(())
+    }
+
+    fn schema(&self) -> &[String] {
+        &self.schema
+    }
+
+    fn stats(&self) -> Option<&ExecutionStats> {
+        Some(&self.stats)
+    }
+}
+
+/// Scan of the integers `generate_series(start, stop, step)` returns.
+///
+/// The bounds are evaluated when the scan is opened and the values are
+/// produced one at a time, so a long series takes no memory. A NULL bound
+/// gives no rows.
+pub struct SeriesScanExec {
+    start: ResolvedExpr,
+    stop: ResolvedExpr,
+    step: ResolvedExpr,
+    schema: Vec<String>,
+    /// The next value and the bounds, or `None` once the series is done
+    state: Option<(i64, i64, i64)>,
+    stats: ExecutionStats,
+}
+
+impl SeriesScanExec {
+    /// Create a scan of the series, whose column is named by `schema`.
+    pub fn new(
+        start: ResolvedExpr,
+        stop: ResolvedExpr,
+        step: ResolvedExpr,
+        schema: Vec<String>,
+    ) -> Self {
+        Self {
+            start,
+            stop,
+            step,
+            schema,
+            state: None,
+            stats: ExecutionStats::default(),
         }
+    }
 
-        // Safety limit
-        if page_id > 100_000 {
-            return Ok(page_id);
+    fn bound(expr: &ResolvedExpr, what: &str) -> DbResult<Option<i64>> {
+        match eval_resolved_expr(expr, &Row::new(Vec::new()))? {
+            Value::Null => Ok(None),
+            Value::Int(n) => Ok(Some(n)),
+            other => Err(common::DbError::Executor(format!(
+                "generate_series {what} must be an integer, got {other:?}"
+            ))),
         }
     }
 }
 
+impl Executor for SeriesScanExec {
+    fn open(&mut self, _ctx: &mut ExecutionContext) -> DbResult<()> {
+        let start_time = Instant::now();
+        let start = Self::bound(&self.start, "start")?;
+        let stop = Self::bound(&self.stop, "stop")?;
+        let step = Self::bound(&self.step, "step")?;
+        if step == Some(0) {
+            return Err(common::DbError::Executor(
+                "generate_series step cannot be zero".into(),
+            ));
+        }
+        self.state = start.zip(stop).zip(step).map(|((a, b), c)| (a, b, c));
+        self.stats = ExecutionStats::default();
+        self.stats.open_time = start_time.elapsed();
+        Ok(())
+    }
+
+    fn next(&mut self, _ctx: &mut ExecutionContext) -> DbResult<Option<Row>> {
+        let Some((value, stop, step)) = self.state else {
+            return Ok(None);
+        };
+        if (step > 0 && value > stop) || (step < 0 && value < stop) {
+            self.state = None;
+            return Ok(None);
+        }
+        // A series that reaches the end of the integers stops there
+        self.state = value.checked_add(step).map(|next| (next, stop, step));
+        self.stats.rows_produced += 1;
+        Ok(Some(Row::new(vec![Value::Int(value)])))
+    }
+
+    fn close(&mut self, _ctx: &mut ExecutionContext) -> DbResult<()> {
+        self.state = None;
+        Ok(())
+    }
+
+    fn schema(&self) -> &[String] {
+        &self.schema
+    }
+
+    fn stats(&self) -> Option<&ExecutionStats> {
+        Some(&self.stats)
+    }
+}
+
+/// Scan of literal rows, from a `VALUES` list or a `SELECT` without `FROM`.
+///
+/// Each row's expressions are evaluated as the row is produced, so a
+/// function like `NOW()` is read when the row is.
+pub struct ValuesScanExec {
+    rows: Vec<Vec<ResolvedExpr>>,
+    schema: Vec<String>,
+    /// Position of the next row to produce
+    next: usize,
+    stats: ExecutionStats,
+}
+
+impl ValuesScanExec {
+    /// Create a scan of `rows`, whose columns are named by `schema`.
+    pub fn new(rows: Vec<Vec<ResolvedExpr>>, schema: Vec<String>) -> Self {
+        Self {
+            rows,
+            schema,
+            next: 0,
+            stats: ExecutionStats::default(),
+        }
+    }
+}
+
+impl Executor for ValuesScanExec {
+    fn open(&mut self, _ctx: &mut ExecutionContext) -> DbResult<()> {
+        self.next = 0;
+        self.stats = ExecutionStats::default();
+        Ok(())
+    }
+
+    fn next(&mut self, _ctx: &mut ExecutionContext) -> DbResult<Option<Row>> {
+        let Some(exprs) = self.rows.get(self.next) else {
+            return Ok(None);
+        };
+        let empty = Row::new(Vec::new());
+        let values = exprs
+            .iter()
+            .map(|expr| eval_resolved_expr(expr, &empty))
+            .collect::<DbResult<Vec<_>>>()?;
+        self.next += 1;
+        self.stats.rows_produced += 1;
+        Ok(Some(Row::new(values)))
+    }
+
+    fn close(&mut self, _ctx: &mut ExecutionContext) -> DbResult<()> {
+        self.next = self.rows.len();
+        Ok(())
+    }
+
+    fn schema(&self) -> &[String] {
+        &self.schema
+    }
+
+    fn stats(&self) -> Option<&ExecutionStats> {
+        Some(&self.stats)
+    }
+}
+
+/// Read the row at `rid`, decoding only `projection` if given.
+fn fetch_row(
+    heap_table: &mut impl HeapTable,
+    rid: RecordId,
+    projection: Option<&[ColumnId]>,
+) -> DbResult<Row> {
+    match projection {
+        Some(columns) => heap_table.get_columns(rid, columns),
+        None => heap_table.get(rid),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -672,17 +1067,10 @@ mod tests {
         // Scan heap and add entries to index
         let heap_path = temp.path().join("users.heap");
         let mut heap = storage::HeapFile::open(&heap_path, table_id.0).unwrap();
-        for page_id in 0..10u64 {
-            for slot in 0..100u16 {
-                let rid = common::RecordId {
-                    page_id: common::PageId(page_id),
-                    slot,
-                };
-                if let Ok(row) = heap.get(rid) {
-                    let key = vec![row.values[0].clone()]; // "id" column
-                    btree.insert(key, rid).unwrap();
-                }
-            }
+        for entry in heap.scan() {
+            let (rid, row) = entry.unwrap();
+            let key = vec![row.values[0].clone()]; // "id" column
+            btree.insert(key, rid).unwrap();
         }
         btree.flush().unwrap();
 
@@ -768,17 +1156,10 @@ mod tests {
         // Scan heap and add entries to index
         let heap_path = temp.path().join("users.heap");
         let mut heap = storage::HeapFile::open(&heap_path, table_id.0).unwrap();
-        for page_id in 0..10u64 {
-            for slot in 0..100u16 {
-                let rid = common::RecordId {
-                    page_id: common::PageId(page_id),
-                    slot,
-                };
-                if let Ok(row) = heap.get(rid) {
-                    let key = vec![row.values[0].clone()];
-                    btree.insert(key, rid).unwrap();
-                }
-            }
+        for entry in heap.scan() {
+            let (rid, row) = entry.unwrap();
+            let key = vec![row.values[0].clone()];
+            btree.insert(key, rid).unwrap();
         }
         btree.flush().unwrap();
 
@@ -927,17 +1308,10 @@ mod tests {
         let mut btree = btree::BTreeIndex::create(&index_path, index_id).unwrap();
         let heap_path = temp.path().join("users.heap");
         let mut heap = storage::HeapFile::open(&heap_path, table_id.0).unwrap();
-        for page_id in 0..10u64 {
-            for slot in 0..100u16 {
-                let rid = common::RecordId {
-                    page_id: common::PageId(page_id),
-                    slot,
-                };
-                if let Ok(row) = heap.get(rid) {
-                    let key = vec![row.values[0].clone()];
-                    btree.insert(key, rid).unwrap();
-                }
-            }
+        for entry in heap.scan() {
+            let (rid, row) = entry.unwrap();
+            let key = vec![row.values[0].clone()];
+            btree.insert(key, rid).unwrap();
         }
         btree.flush().unwrap();
 
@@ -1016,4 +1390,42 @@ mod tests {
         assert_exhausted(&mut scan, &mut ctx);
         scan.close(&mut ctx).unwrap();
     }
+
+    #[test]
+    fn values_scan_evaluates_each_row() {
+        let (mut ctx, _temp) = setup_test_context();
+        let rows = vec![
+            vec![
+                ResolvedExpr::Binary {
+                    left: Box::new(ResolvedExpr::Literal(Value::Int(1))),
+                    op: expr::BinaryOp::Add,
+                    right: Box::new(ResolvedExpr::Literal(Value::Int(1))),
+                },
+                ResolvedExpr::Literal(Value::Text("a".into())),
+            ],
+            vec![
+                ResolvedExpr::Literal(Value::Int(3)),
+                ResolvedExpr::Literal(Value::Null),
+            ],
+        ];
+        let mut scan = ValuesScanExec::new(rows, vec!["column1".into(), "column2".into()]);
+
+        // Reopening starts over from the first row
+        for _ in 0..2 {
+            scan.open(&mut ctx).unwrap();
+            assert_next_row(
+                &mut scan,
+                &mut ctx,
+                Row::new(vec![Value::Int(2), Value::Text("a".into())]),
+            );
+            assert_next_row(
+                &mut scan,
+                &mut ctx,
+                Row::new(vec![Value::Int(3), Value::Null]),
+            );
+            assert_exhausted(&mut scan, &mut ctx);
+            scan.close(&mut ctx).unwrap();
+        }
+        assert_eq!(scan.stats().unwrap().rows_produced, 2);
+    }
 }
@@ -1,17 +1,26 @@
 //! Sort operator: orders rows based on specified columns.
 
+use crate::spill::{SpillFile, SpillReader};
 use crate::{ExecutionContext, Executor};
 use common::{ColumnId, DbResult, ExecutionStats, Row};
-use planner::SortDirection;
+use planner::{NullsOrder, SortDirection};
 use std::cmp::Ordering;
 use std::time::Instant;
 use types::Value;
 
-/// Resolved ORDER BY clause with column ID and direction.
+/// Fewest rows written to a run, so that a work memory mostly held by other
+/// operators does not turn every row into a run of its own.
+const MIN_RUN_ROWS: usize = 64;
+
+/// Most runs merged at once, to bound the files open at a time.
+const MERGE_FAN_IN: usize = 64;
+
+/// Resolved ORDER BY clause with column ID, direction and NULL placement.
 #[derive(Clone, Debug)]
 pub struct SortKey {
     pub column_id: ColumnId,
     pub direction: SortDirection,
+    pub nulls: NullsOrder,
 }
 
 /// Sort operator - materializes input and returns rows in sorted order.
@@ -19,42 +28,95 @@ pub struct SortKey {
 /// This is a blocking operator that must consume all input rows before
 /// returning the first sorted row. Uses stable sort to preserve insertion
 /// order for equal keys.
+///
+/// Once the statement's buffered rows exceed its work memory, the rows
+/// buffered so far are sorted and spilled to a temporary file as a run, and
+/// the sorted output is a merge of the runs.
 pub struct SortExec {
     input: Box<dyn Executor>,
     sort_keys: Vec<SortKey>,
-    sorted_rows: Option<Vec<Row>>,
-    current_index: usize,
+    sorted: Option<Sorted>,
     stats: ExecutionStats,
 }
 
+/// Where the sorted rows are returned from.
+enum Sorted {
+    /// Every row was buffered in memory.
+    Memory(std::vec::IntoIter<Row>),
+    /// Runs spilled to disk, merged as they are read.
+    Merge(RunMerge),
+}
+
 impl SortExec {
     /// Create a new sort operator.
     pub fn new(input: Box<dyn Executor>, sort_keys: Vec<SortKey>) -> Self {
         Self {
             input,
             sort_keys,
-            sorted_rows: None,
-            current_index: 0,
+            sorted: None,
             stats: ExecutionStats::default(),
         }
     }
 
     /// Materialize and sort all rows from input.
-    fn materialize_and_sort(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
+    fn materialize_and_sort(&mut self, ctx: &mut ExecutionContext) -> DbResult<Sorted> {
         let mut rows = Vec::new();
+        let mut runs = Vec::new();
 
-        // Collect all rows from input
+        // Collect all rows from input, spilling runs past the work memory
         while let Some(row) = self.input.next(ctx)? {
+            ctx.charge_memory(&row)?;
             rows.push(row);
+            if rows.len() >= MIN_RUN_ROWS && ctx.over_work_memory() {
+                runs.push(self.spill_run(&mut rows, ctx)?);
+            }
+        }
+
+        if runs.is_empty() {
+            // Sort rows using stable sort
+            let sort_keys = &self.sort_keys;
+            rows.sort_by(|a, b| compare_rows(a, b, sort_keys));
+            return Ok(Sorted::Memory(rows.into_iter()));
+        }
+        if !rows.is_empty() {
+            runs.push(self.spill_run(&mut rows, ctx)?);
         }
 
-        // Sort rows using stable sort
+        // Merge neighbouring runs until few enough are left to merge at once,
+        // keeping them in input order so that equal keys stay stable
+        while runs.len() > MERGE_FAN_IN {
+            let mut merged = Vec::new();
+            let mut remaining = runs.into_iter();
+            loop {
+                let group: Vec<SpillFile> = remaining.by_ref().take(MERGE_FAN_IN).collect();
+                match group.len() {
+                    0 => break,
+                    1 => merged.extend(group),
+                    _ => {
+                        let mut merge = RunMerge::new(group)?;
+                        let mut run = ctx.spill_file()?;
+                        while let Some(row) = merge.next(&self.sort_keys)? {
+                            ctx.write_spilled(&mut run, &row)?;
+                        }
+                        merged.push(run);
+                    }
+                }
+            }
+            runs = merged;
+        }
+        Ok(Sorted::Merge(RunMerge::new(runs)?))
+    }
+
+    /// Sort `rows` and write them to a new run, crediting back their memory.
+    fn spill_run(&self, rows: &mut Vec<Row>, ctx: &mut ExecutionContext) -> DbResult<SpillFile> {
         let sort_keys = &self.sort_keys;
         rows.sort_by(|a, b| compare_rows(a, b, sort_keys));
-
-        self.sorted_rows = Some(rows);
-        self.current_index = 0;
-        Ok(())
+        let mut run = ctx.spill_file()?;
+        for row in rows.drain(..) {
+            ctx.write_spilled(&mut run, &row)?;
+            ctx.release_memory(&row);
+        }
+        Ok(run)
     }
 }
 
@@ -62,8 +124,7 @@ impl Executor for SortExec {
     fn open(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
         let start = Instant::now();
         self.stats = ExecutionStats::default();
-        self.sorted_rows = None;
-        self.current_index = 0;
+        self.sorted = None;
         self.input.open(ctx)?;
         self.stats.open_time = start.elapsed();
         Ok(())
@@ -73,32 +134,27 @@ impl Executor for SortExec {
         let start = Instant::now();
 
         // Materialize and sort on first call to next()
-        if self.sorted_rows.is_none() {
-            self.materialize_and_sort(ctx)?;
+        if self.sorted.is_none() {
+            self.sorted = Some(self.materialize_and_sort(ctx)?);
         }
 
         // Return next sorted row
-        let result = match &self.sorted_rows {
-            Some(rows) => {
-                if self.current_index < rows.len() {
-                    let row = rows[self.current_index].clone();
-                    self.current_index += 1;
-                    self.stats.rows_produced += 1;
-                    Ok(Some(row))
-                } else {
-                    Ok(None)
-                }
-            }
-            None => Ok(None),
+        let result = match &mut self.sorted {
+            Some(Sorted::Memory(rows)) => rows.next(),
+            Some(Sorted::Merge(merge)) => merge.next(&self.sort_keys)?,
+            None => None,
         };
+        if result.is_some() {
+            self.stats.rows_produced += 1;
+        }
 
         self.stats.total_next_time += start.elapsed();
-        result
+        Ok(result)
     }
 
     fn close(&mut self, ctx: &mut ExecutionContext) -> DbResult<()> {
         let start = Instant::now();
-        self.sorted_rows = None;
+        self.sorted = None;
         self.input.close(ctx)?;
         self.stats.close_time = start.elapsed();
         Ok(())
@@ -113,31 +169,82 @@ impl Executor for SortExec {
     }
 }
 
-/// Compare two rows based on sort keys.
+/// Merges sorted runs into one sorted sequence.
+struct RunMerge {
+    /// The runs, kept until the merge is dropped so their files are too.
+    _runs: Vec<SpillFile>,
+    readers: Vec<SpillReader>,
+    /// The smallest unread row of each run.
+    heads: Vec<Option<Row>>,
+}
+
+impl RunMerge {
+    fn new(mut runs: Vec<SpillFile>) -> DbResult<Self> {
+        let mut readers = Vec::with_capacity(runs.len());
+        let mut heads = Vec::with_capacity(runs.len());
+        for run in &mut runs {
+            let mut reader = run.read()?;
+            heads.push(reader.next()?);
+            readers.push(reader);
+        }
+        Ok(Self {
+            _runs: runs,
+            readers,
+            heads,
+        })
+    }
+
+    /// The smallest row left in any run. Of equal rows the one from the
+    /// earliest run comes first, which keeps the sort stable.
+    fn next(&mut self, sort_keys: &[SortKey]) -> DbResult<Option<Row>> {
+        let mut smallest: Option<usize> = None;
+        for (i, head) in self.heads.iter().enumerate() {
+            let Some(row) = head else { continue };
+            let smaller = match smallest.and_then(|s| self.heads[s].as_ref()) {
+                Some(best) => compare_rows(row, best, sort_keys) == Ordering::Less,
+                None => true,
+            };
+            if smaller {
+                smallest = Some(i);
+            }
+        }
+        let Some(i) = smallest else {
+            return Ok(None);
+        };
+        let next = self.readers[i].next()?;
+        Ok(std::mem::replace(&mut self.heads[i], next))
+    }
+}
+
+/// Compare two rows based on sort keys, lexicographically across the keys.
 fn compare_rows(a: &Row, b: &Row, sort_keys: &[SortKey]) -> Ordering {
     for key in sort_keys {
         let col_idx = key.column_id as usize;
 
         // Get values, treating out-of-bounds as NULL
-        let val_a = a.values.get(col_idx);
-        let val_b = b.values.get(col_idx);
+        let val_a = a.values.get(col_idx).filter(|v| !matches!(v, Value::Null));
+        let val_b = b.values.get(col_idx).filter(|v| !matches!(v, Value::Null));
 
         let ordering = match (val_a, val_b) {
             (None, None) => Ordering::Equal,
-            (None, Some(_)) => Ordering::Less, // NULL sorts before non-NULL
-            (Some(_), None) => Ordering::Greater, // non-NULL sorts after NULL
-            (Some(a_val), Some(b_val)) => compare_values(a_val, b_val),
-        };
-
-        // Apply sort direction
-        let directed_ordering = match key.direction {
-            SortDirection::Asc => ordering,
-            SortDirection::Desc => ordering.reverse(),
+            // NULL placement is independent of the sort direction
+            (None, Some(_)) => match key.nulls {
+                NullsOrder::First => Ordering::Less,
+                NullsOrder::Last => Ordering::Greater,
+            },
+            (Some(_), None) => match key.nulls {
+                NullsOrder::First => Ordering::Greater,
+                NullsOrder::Last => Ordering::Less,
+            },
+            (Some(a_val), Some(b_val)) => match key.direction {
+                SortDirection::Asc => compare_values(a_val, b_val),
+                SortDirection::Desc => compare_values(a_val, b_val).reverse(),
+            },
         };
 
         // If not equal, we have our answer
-        if directed_ordering != Ordering::Equal {
-            return directed_ordering;
+        if ordering != Ordering::Equal {
+            return ordering;
         }
         // Otherwise, continue to next sort key
     }
@@ -162,13 +269,30 @@ fn compare_values(a: &Value, b: &Value) -> Ordering {
         // Bool comparison (false < true)
         (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
 
-        // Cross-type comparisons: order by type (Bool < Int < Text)
-        (Value::Bool(_), Value::Int(_)) => Ordering::Less,
+        // Float, decimal and mixed numeric comparison by value (NaN sorts
+        // last)
+        (
+            Value::Float(_) | Value::Decimal(_),
+            Value::Int(_) | Value::Float(_) | Value::Decimal(_),
+        )
+        | (Value::Int(_), Value::Float(_) | Value::Decimal(_)) => a.cmp(b),
+
+        // Dates and timestamps by instant, after numbers and before text
+        (Value::Date(_) | Value::Timestamp(_), _) | (_, Value::Date(_) | Value::Timestamp(_)) => {
+            a.cmp(b)
+        }
+
+        // Bytes byte by byte, after everything else
+        (Value::Bytes(_), _) | (_, Value::Bytes(_)) => a.cmp(b),
+
+        // Cross-type comparisons: order by type (Bool < Int, Decimal, Float
+        // < Text)
+        (Value::Bool(_), Value::Int(_) | Value::Float(_) | Value::Decimal(_)) => Ordering::Less,
         (Value::Bool(_), Value::Text(_)) => Ordering::Less,
-        (Value::Int(_), Value::Bool(_)) => Ordering::Greater,
-        (Value::Int(_), Value::Text(_)) => Ordering::Less,
+        (Value::Int(_) | Value::Float(_) | Value::Decimal(_), Value::Bool(_)) => Ordering::Greater,
+        (Value::Int(_) | Value::Float(_) | Value::Decimal(_), Value::Text(_)) => Ordering::Less,
         (Value::Text(_), Value::Bool(_)) => Ordering::Greater,
-        (Value::Text(_), Value::Int(_)) => Ordering::Greater,
+        (Value::Text(_), Value::Int(_) | Value::Float(_) | Value::Decimal(_)) => Ordering::Greater,
     }
 }
 
@@ -178,7 +302,6 @@ mod tests {
     use crate::tests::helpers::{
         assert_exhausted, assert_next_row, setup_test_context, MockExecutor,
     };
-    use planner::SortDirection;
 
     #[test]
     fn sort_single_column_ascending() {
@@ -195,6 +318,7 @@ mod tests {
         let sort_keys = vec![SortKey {
             column_id: 0,
             direction: SortDirection::Asc,
+            nulls: NullsOrder::First,
         }];
         let mut sort_exec = SortExec::new(input, sort_keys);
 
@@ -223,6 +347,7 @@ mod tests {
         let sort_keys = vec![SortKey {
             column_id: 0,
             direction: SortDirection::Desc,
+            nulls: NullsOrder::Last,
         }];
         let mut sort_exec = SortExec::new(input, sort_keys);
 
@@ -256,10 +381,12 @@ mod tests {
             SortKey {
                 column_id: 0,
                 direction: SortDirection::Asc,
+                nulls: NullsOrder::First,
             },
             SortKey {
                 column_id: 1,
                 direction: SortDirection::Desc,
+                nulls: NullsOrder::Last,
             },
         ];
         let mut sort_exec = SortExec::new(input, sort_keys);
@@ -309,6 +436,7 @@ mod tests {
         let sort_keys = vec![SortKey {
             column_id: 0,
             direction: SortDirection::Asc,
+            nulls: NullsOrder::First,
         }];
         let mut sort_exec = SortExec::new(input, sort_keys);
 
@@ -324,6 +452,54 @@ mod tests {
         sort_exec.close(&mut ctx).unwrap();
     }
 
+    #[test]
+    fn sort_nulls_placement_is_independent_of_direction() {
+        let (mut ctx, _temp) = setup_test_context();
+
+        let rows = vec![
+            Row::new(vec![Value::Int(1), Value::Int(10)]),
+            Row::new(vec![Value::Null, Value::Int(20)]),
+            Row::new(vec![Value::Int(2), Value::Null]),
+            Row::new(vec![Value::Int(1), Value::Null]),
+            Row::new(vec![Value::Int(2), Value::Int(30)]),
+        ];
+        let input = Box::new(MockExecutor::new(
+            rows,
+            vec!["a".to_string(), "b".to_string()],
+        ));
+
+        // ORDER BY a DESC NULLS FIRST, b ASC NULLS LAST
+        let sort_keys = vec![
+            SortKey {
+                column_id: 0,
+                direction: SortDirection::Desc,
+                nulls: NullsOrder::First,
+            },
+            SortKey {
+                column_id: 1,
+                direction: SortDirection::Asc,
+                nulls: NullsOrder::Last,
+            },
+        ];
+        let mut sort_exec = SortExec::new(input, sort_keys);
+
+        sort_exec.open(&mut ctx).unwrap();
+
+        let expected = [
+            (Value::Null, Value::Int(20)),
+            (Value::Int(2), Value::Int(30)),
+            (Value::Int(2), Value::Null),
+            (Value::Int(1), Value::Int(10)),
+            (Value::Int(1), Value::Null),
+        ];
+        for (a, b) in expected {
+            assert_next_row(&mut sort_exec, &mut ctx, Row::new(vec![a, b]));
+        }
+        assert_exhausted(&mut sort_exec, &mut ctx);
+
+        sort_exec.close(&mut ctx).unwrap();
+    }
+
     #[test]
     fn sort_text_lexicographic() {
         let (mut ctx, _temp) = setup_test_context();
@@ -338,6 +514,7 @@ mod tests {
         let sort_keys = vec![SortKey {
             column_id: 0,
             direction: SortDirection::Asc,
+            nulls: NullsOrder::First,
         }];
         let mut sort_exec = SortExec::new(input, sort_keys);
 
@@ -363,6 +540,41 @@ mod tests {
         sort_exec.close(&mut ctx).unwrap();
     }
 
+    #[test]
+    fn sort_mixes_ints_and_floats_by_value() {
+        let (mut ctx, _temp) = setup_test_context();
+
+        let rows = vec![
+            Row::new(vec![Value::Float(f64::NAN)]),
+            Row::new(vec![Value::Int(2)]),
+            Row::new(vec![Value::Float(-1.5)]),
+            Row::new(vec![Value::Float(2.5)]),
+            Row::new(vec![Value::Int(-3)]),
+        ];
+        let input = Box::new(MockExecutor::new(rows, vec!["n".to_string()]));
+
+        let sort_keys = vec![SortKey {
+            column_id: 0,
+            direction: SortDirection::Asc,
+            nulls: NullsOrder::First,
+        }];
+        let mut sort_exec = SortExec::new(input, sort_keys);
+
+        sort_exec.open(&mut ctx).unwrap();
+        for expected in [
+            Value::Int(-3),
+            Value::Float(-1.5),
+            Value::Int(2),
+            Value::Float(2.5),
+            Value::Float(f64::NAN),
+        ] {
+            assert_next_row(&mut sort_exec, &mut ctx, Row::new(vec![expected]));
+        }
+        assert_exhausted(&mut sort_exec, &mut ctx);
+
+        sort_exec.close(&mut ctx).unwrap();
+    }
+
     #[test]
     fn sort_empty_input() {
         let (mut ctx, _temp) = setup_test_context();
@@ -371,6 +583,7 @@ mod tests {
         let sort_keys = vec![SortKey {
             column_id: 0,
             direction: SortDirection::Asc,
+            nulls: NullsOrder::First,
         }];
         let mut sort_exec = SortExec::new(input, sort_keys);
 
@@ -388,6 +601,7 @@ mod tests {
         let sort_keys = vec![SortKey {
             column_id: 0,
             direction: SortDirection::Asc,
+            nulls: NullsOrder::First,
         }];
         let mut sort_exec = SortExec::new(input, sort_keys);
 
@@ -408,6 +622,7 @@ mod tests {
         let sort_keys = vec![SortKey {
             column_id: 0,
             direction: SortDirection::Asc,
+            nulls: NullsOrder::First,
         }];
         let sort_exec = SortExec::new(input, sort_keys);
 
@@ -433,6 +648,7 @@ mod tests {
         let sort_keys = vec![SortKey {
             column_id: 0,
             direction: SortDirection::Asc,
+            nulls: NullsOrder::First,
         }];
         let mut sort_exec = SortExec::new(input, sort_keys);
 
@@ -472,6 +688,7 @@ mod tests {
         let sort_keys = vec![SortKey {
             column_id: 0,
             direction: SortDirection::Asc,
+            nulls: NullsOrder::First,
         }];
         let mut sort_exec = SortExec::new(input, sort_keys);
 
@@ -498,6 +715,7 @@ mod tests {
         let sort_keys = vec![SortKey {
             column_id: 0,
             direction: SortDirection::Asc,
+            nulls: NullsOrder::First,
         }];
         let mut sort_exec = SortExec::new(input, sort_keys);
 
@@ -512,4 +730,56 @@ mod tests {
         assert_exhausted(&mut sort_exec, &mut ctx);
         sort_exec.close(&mut ctx).unwrap();
     }
+
+    #[test]
+    fn sort_spills_runs_past_work_memory_and_stays_stable() {
+        let (ctx, temp) = setup_test_context();
+        let limits = common::ResourceLimits::builder()
+            .work_memory_bytes(1)
+            .build();
+        let mut ctx = ctx.with_resource_limits(limits);
+
+        // Enough rows for more runs than are merged at once
+        let count = (MIN_RUN_ROWS * (MERGE_FAN_IN + 2)) as i64;
+        let rows = (0..count)
+            .map(|seq| Row::new(vec![Value::Int(seq * 7 % 5), Value::Int(seq)]))
+            .collect();
+        let input = Box::new(MockExecutor::new(
+            rows,
+            vec!["key".to_string(), "seq".to_string()],
+        ));
+        let sort_keys = vec![SortKey {
+            column_id: 0,
+            direction: SortDirection::Asc,
+            nulls: NullsOrder::First,
+        }];
+        let mut sort_exec = SortExec::new(input, sort_keys);
+
+        sort_exec.open(&mut ctx).unwrap();
+        let mut previous: Option<(i64, i64)> = None;
+        let mut produced = 0;
+        while let Some(row) = sort_exec.next(&mut ctx).unwrap() {
+            let (Value::Int(key), Value::Int(seq)) = (&row.values[0], &row.values[1]) else {
+                panic!("unexpected row {row:?}");
+            };
+            // Equal keys keep their input order
+            if let Some(previous) = previous {
+                assert!(previous < (*key, *seq), "{previous:?} before {key}, {seq}");
+            }
+            previous = Some((*key, *seq));
+            produced += 1;
+        }
+        assert_eq!(produced, count);
+        assert!(ctx.resource_usage().spilled_bytes > 0);
+
+        sort_exec.close(&mut ctx).unwrap();
+        let spill_files = std::fs::read_dir(temp.path())
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_string_lossy().starts_with("spill-")
+            })
+            .count();
+        assert_eq!(spill_files, 0);
+    }
 }
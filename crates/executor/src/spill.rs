@@ -0,0 +1,277 @@
+//! Temporary files that operators spill rows to once their statement runs
+//! out of work memory (see [`ResourceLimits::work_memory_bytes`]).
+//!
+//! A spill file lives in the data directory under a name no other file
+//! uses, holds rows encoded with bincode one after another, record ID
+//! included, and is removed when dropped, so a statement that fails part way
+//! leaves nothing behind. In an encrypted database each row is sealed with
+//! the database key and bound to its position in the file (see
+//! [`common::crypto`]), and written after its sealed length.
+//!
+//! Hashing operators spill into [`SpillPartitions`], splitting rows by key
+//! so that each partition can be processed on its own afterwards.
+//!
+//! [`ResourceLimits::work_memory_bytes`]: common::ResourceLimits::work_memory_bytes
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use common::{crypto::EncryptionKey, DbError, DbResult, RecordId, Row};
+use types::{temporal, Value};
+
+use crate::ExecutionContext;
+
+/// Partitions a hashing operator splits its rows into once they outgrow
+/// work memory.
+pub(crate) const SPILL_PARTITIONS: usize = 8;
+
+/// Distinguishes the spill files of one process.
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+fn config() -> impl bincode::config::Config {
+    bincode::config::standard()
+}
+
+/// Associated data for the `index`th row of an encrypted spill file.
+fn row_aad(index: u64) -> Vec<u8> {
+    let mut aad = b"spill row ".to_vec();
+    aad.extend_from_slice(&index.to_le_bytes());
+    aad
+}
+
+/// Rows written to a temporary file, to be read back in the same order.
+pub(crate) struct SpillFile {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+    rows: u64,
+    /// Key rows are sealed with, in an encrypted database
+    key: Option<EncryptionKey>,
+}
+
+impl SpillFile {
+    /// Create an empty spill file in `dir`, sealing its rows with `key` if
+    /// given.
+    pub(crate) fn create(dir: &Path, key: Option<&EncryptionKey>) -> DbResult<Self> {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("spill-{}-{id}.tmp", std::process::id()));
+        let file = File::create(&path)?;
+        Ok(Self {
+            path,
+            writer: Some(BufWriter::new(file)),
+            rows: 0,
+            key: key.cloned(),
+        })
+    }
+
+    /// Append `row` to the file, returning the bytes it took.
+    pub(crate) fn write(&mut self, row: &Row) -> DbResult<u64> {
+        let writer = self
+            .writer
+            .as_mut()
+            .ok_or_else(|| DbError::Executor("spill file is already being read".into()))?;
+        let record = (row.rid(), &row.values);
+        let bytes = match &self.key {
+            None => bincode::serde::encode_into_std_write(record, writer, config())
+                .map_err(|e| DbError::Executor(format!("failed to spill row: {e}")))?,
+            Some(key) => {
+                let plaintext = bincode::serde::encode_to_vec(record, config())
+                    .map_err(|e| DbError::Executor(format!("failed to spill row: {e}")))?;
+                let sealed = key.seal(&row_aad(self.rows), &plaintext)?;
+                writer.write_all(&(sealed.len() as u32).to_le_bytes())?;
+                writer.write_all(&sealed)?;
+                4 + sealed.len()
+            }
+        };
+        self.rows += 1;
+        Ok(bytes as u64)
+    }
+
+    /// Read the rows back from the start. The file takes no more writes once
+    /// it has been read; it may be read any number of times.
+    pub(crate) fn read(&mut self) -> DbResult<SpillReader> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush()?;
+        }
+        Ok(SpillReader {
+            reader: BufReader::new(File::open(&self.path)?),
+            read: 0,
+            rows: self.rows,
+            key: self.key.clone(),
+        })
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        self.writer = None;
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Reads the rows of a [`SpillFile`] in the order they were written.
+pub(crate) struct SpillReader {
+    reader: BufReader<File>,
+    read: u64,
+    rows: u64,
+    key: Option<EncryptionKey>,
+}
+
+impl SpillReader {
+    /// The next row, or `None` once every row has been read.
+    pub(crate) fn next(&mut self) -> DbResult<Option<Row>> {
+        if self.read == self.rows {
+            return Ok(None);
+        }
+        let decode_error = |e| DbError::Executor(format!("failed to read spilled row: {e}"));
+        let (rid, values): (Option<RecordId>, Vec<Value>) = match &self.key {
+            None => bincode::serde::decode_from_std_read(&mut self.reader, config())
+                .map_err(decode_error)?,
+            Some(key) => {
+                let mut len = [0u8; 4];
+                self.reader.read_exact(&mut len)?;
+                let mut sealed = vec![0u8; u32::from_le_bytes(len) as usize];
+                self.reader.read_exact(&mut sealed)?;
+                let plaintext = key.open(&row_aad(self.read), &sealed)?;
+                bincode::serde::decode_from_slice(&plaintext, config())
+                    .map_err(decode_error)?
+                    .0
+            }
+        };
+        self.read += 1;
+        let mut row = Row::new(values);
+        row.set_rid(rid);
+        Ok(Some(row))
+    }
+}
+
+/// Rows split by key into [`SPILL_PARTITIONS`] spill files. Rows whose
+/// keys hash alike (see [`hash_key`]) land in the same partition.
+pub(crate) struct SpillPartitions {
+    files: Vec<SpillFile>,
+}
+
+impl SpillPartitions {
+    /// Create an empty spill file for each partition.
+    pub(crate) fn create(ctx: &ExecutionContext) -> DbResult<Self> {
+        let files = (0..SPILL_PARTITIONS)
+            .map(|_| ctx.spill_file())
+            .collect::<DbResult<Vec<_>>>()?;
+        Ok(Self { files })
+    }
+
+    /// Append `row`, whose key is `key`, to its partition, charging the
+    /// bytes written to the statement's temporary disk usage.
+    pub(crate) fn write(
+        &mut self,
+        ctx: &mut ExecutionContext,
+        key: &[Value],
+        row: &Row,
+    ) -> DbResult<()> {
+        ctx.write_spilled(&mut self.files[partition_of(key)], row)
+    }
+
+    /// Read back the rows of `partition`.
+    pub(crate) fn read(&mut self, partition: usize) -> DbResult<SpillReader> {
+        self.files[partition].read()
+    }
+}
+
+/// Keys as hashed: numbers as floats and dates as timestamps, so that
+/// values `=` finds equal hash alike.
+pub(crate) fn hash_key(key: &[Value]) -> Vec<Value> {
+    key.iter()
+        .map(|value| match value {
+            Value::Int(i) => Value::Float(*i as f64),
+            Value::Decimal(d) => Value::Float(d.to_f64()),
+            Value::Date(days) => Value::Timestamp(temporal::date_to_timestamp(*days)),
+            other => other.clone(),
+        })
+        .collect()
+}
+
+/// The spill partition of rows with keys `key`.
+fn partition_of(key: &[Value]) -> usize {
+    let mut hasher = DefaultHasher::new();
+    hash_key(key).hash(&mut hasher);
+    (hasher.finish() % SPILL_PARTITIONS as u64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::PageId;
+    use types::Value;
+
+    #[test]
+    fn rows_read_back_in_order_and_file_is_removed_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = SpillFile::create(dir.path(), None).unwrap();
+        let rows = [
+            Row::new(vec![Value::Int(1), Value::Text("a".into())]).with_rid(RecordId {
+                page_id: PageId(3),
+                slot: 7,
+            }),
+            Row::new(vec![Value::Null, Value::Bool(true)]),
+        ];
+        for row in &rows {
+            file.write(row).unwrap();
+        }
+
+        // Each read starts again from the first row
+        for _ in 0..2 {
+            let mut reader = file.read().unwrap();
+            for row in &rows {
+                let read = reader.next().unwrap().unwrap();
+                assert_eq!(read.values, row.values);
+                assert_eq!(read.rid(), row.rid());
+            }
+            assert!(reader.next().unwrap().is_none());
+        }
+
+        drop(file);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn spill_files_of_an_encrypted_database_hold_no_plaintext() {
+        use catalog::Catalog;
+        use common::crypto::{self, EncryptionKey};
+
+        let dir = tempfile::tempdir().unwrap();
+        let key = EncryptionKey::from_bytes(&[9u8; crypto::KEY_LEN]).unwrap();
+        let catalog = Catalog::load_with_key(&dir.path().join("catalog.json"), Some(key)).unwrap();
+        let mut pager = buffer::FilePager::new(dir.path(), 10);
+        let mut wal = wal::Wal::open(dir.path().join("test.wal")).unwrap();
+        let ctx = ExecutionContext::new(&catalog, &mut pager, &mut wal, dir.path().into());
+
+        let mut file = ctx.spill_file().unwrap();
+        let rows: Vec<Row> = (0..3)
+            .map(|i| {
+                Row::new(vec![
+                    Value::Int(i),
+                    Value::Text(format!("spilled-secret-{i}")),
+                ])
+            })
+            .collect();
+        for row in &rows {
+            file.write(row).unwrap();
+        }
+        let mut reader = file.read().unwrap();
+
+        let bytes = fs::read(&file.path).unwrap();
+        assert!(!bytes
+            .windows(b"spilled-secret".len())
+            .any(|w| w == b"spilled-secret"));
+
+        for row in &rows {
+            assert_eq!(reader.next().unwrap().unwrap().values, row.values);
+        }
+        assert!(reader.next().unwrap().is_none());
+    }
+}
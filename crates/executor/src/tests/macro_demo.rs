@@ -41,22 +41,22 @@ mod tests {
         // Insert first row with id=1
         let plan1 = PhysicalPlan::Insert {
             table_id,
-            values: vec![
+            rows: vec![vec![
                 lit!(int: 1),
                 lit!(text: "alice"),
                 ResolvedExpr::Literal(Value::Bool(true)),
-            ],
+            ]],
         };
         assert!(execute_dml(plan1, &mut ctx).is_ok());
 
         // Insert second row with id=1 should fail
         let plan2 = PhysicalPlan::Insert {
             table_id,
-            values: vec![
+            rows: vec![vec![
                 lit!(int: 1),
                 lit!(text: "bob"),
                 ResolvedExpr::Literal(Value::Bool(false)),
-            ],
+            ]],
         };
         let result = execute_dml(plan2, &mut ctx);
 
@@ -77,22 +77,22 @@ mod tests {
         // Insert first row with id=1
         let plan1 = PhysicalPlan::Insert {
             table_id,
-            values: vec![
+            rows: vec![vec![
                 lit!(int: 1),
                 lit!(text: "alice"),
                 ResolvedExpr::Literal(Value::Bool(true)),
-            ],
+            ]],
         };
         assert!(execute_dml(plan1, &mut ctx).is_ok());
 
         // Insert second row with id=1 should fail
         let plan2 = PhysicalPlan::Insert {
             table_id,
-            values: vec![
+            rows: vec![vec![
                 lit!(int: 1),
                 lit!(text: "bob"),
                 ResolvedExpr::Literal(Value::Bool(false)),
-            ],
+            ]],
         };
         let result = execute_dml(plan2, &mut ctx);
 
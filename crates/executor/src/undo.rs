@@ -0,0 +1,207 @@
+//! Undo records for rolling back applied row changes.
+//!
+//! The WAL is redo-only: it records the rows a write left behind, not the
+//! ones it replaced. A context built with
+//! [`ExecutionContext::with_undo_log`] also keeps, for every row the DML
+//! operators write, what it takes to reverse it. [`roll_back`] applies those
+//! records newest first as ordinary writes, maintaining the indexes and
+//! logging each one, so recovery replays the rollback like any other change.
+
+use std::collections::HashMap;
+
+use common::{DbResult, RecordId, Row, TableId};
+use serde::{Deserialize, Serialize};
+use storage::HeapTable;
+use types::Value;
+use wal::WalRecord;
+
+use crate::dml::{
+    update_indexes_after_delete, update_indexes_after_insert, update_indexes_after_update,
+};
+use crate::ExecutionContext;
+
+/// One row change made by a DML operator, with what reversing it needs.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum UndoRecord {
+    /// A row was inserted at `rid`.
+    Insert { table: TableId, rid: RecordId },
+    /// The row that held `old_row` at `old_rid` was updated and is now at
+    /// `rid`.
+    Update {
+        table: TableId,
+        rid: RecordId,
+        old_rid: RecordId,
+        old_row: Vec<Value>,
+    },
+    /// `row` was deleted from `rid`.
+    Delete {
+        table: TableId,
+        rid: RecordId,
+        row: Vec<Value>,
+    },
+}
+
+/// Reverse `records`, newest first.
+///
+/// A row an update moves, or a deleted row that is inserted again, can land
+/// at a new record id; older records that name the row are followed there.
+///
+/// # Errors
+///
+/// Fails if a row cannot be written back, for example because a statement
+/// outside the rolled back writes has since taken its primary key. The
+/// records before the failing one are left unapplied.
+pub fn roll_back(ctx: &mut ExecutionContext, records: Vec<UndoRecord>) -> DbResult<()> {
+    let mut moved: HashMap<(TableId, RecordId), RecordId> = HashMap::new();
+    let current = |moved: &HashMap<_, _>, table, rid| *moved.get(&(table, rid)).unwrap_or(&rid);
+
+    let mut tables = Vec::new();
+    for record in records.into_iter().rev() {
+        match record {
+            UndoRecord::Insert { table, rid } => {
+                let rid = current(&moved, table, rid);
+                let row = {
+                    let mut heap_table = ctx.heap_table(table)?;
+                    let row = heap_table.get(rid)?;
+                    heap_table.delete(rid)?;
+                    row
+                };
+                if let Some(pk_index) = ctx.pk_index(table)? {
+                    let key = pk_index.extract_key(&row)?;
+                    pk_index.remove(&key)?;
+                }
+                update_indexes_after_delete(ctx, table, &row, rid)?;
+                ctx.log_dml(WalRecord::Delete { table, rid })?;
+                tables.push(table);
+            }
+            UndoRecord::Update {
+                table,
+                rid,
+                old_rid,
+                old_row,
+            } => {
+                let rid = current(&moved, table, rid);
+                let old_row = Row::new(old_row);
+                let (row, restored_rid) = {
+                    let mut heap_table = ctx.heap_table(table)?;
+                    let row = heap_table.get(rid)?;
+                    let restored_rid = heap_table.update(rid, &old_row)?;
+                    (row, restored_rid)
+                };
+                if restored_rid != rid {
+                    if let Some(pk_index) = ctx.pk_index(table)? {
+                        let key = pk_index.extract_key(&old_row)?;
+                        pk_index.remove(&key)?;
+                        pk_index.insert(key, restored_rid)?;
+                    }
+                }
+                update_indexes_after_update(ctx, table, &row, &old_row, rid, restored_rid)?;
+                ctx.log_dml(WalRecord::Update {
+                    table,
+                    rid: restored_rid,
+                    new_row: old_row.values,
+                })?;
+                moved.insert((table, old_rid), restored_rid);
+                tables.push(table);
+            }
+            UndoRecord::Delete {
+                table,
+                rid: old_rid,
+                row,
+            } => {
+                let row = Row::new(row);
+                let rid = {
+                    let mut heap_table = ctx.heap_table(table)?;
+                    heap_table.insert(&row)?
+                };
+                if let Some(pk_index) = ctx.pk_index(table)? {
+                    let key = pk_index.extract_key(&row)?;
+                    pk_index.insert(key, rid)?;
+                }
+                update_indexes_after_insert(ctx, table, &[(row.clone(), rid)])?;
+                ctx.log_dml(WalRecord::Insert {
+                    table,
+                    row: row.values,
+                    rid,
+                })?;
+                moved.insert((table, old_rid), rid);
+                tables.push(table);
+            }
+        }
+    }
+
+    tables.sort_by_key(|table| table.0);
+    tables.dedup();
+    for table in tables {
+        ctx.save_pk_index(table)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helpers::setup_test_context;
+    use crate::{execute_dml, execute_query};
+    use planner::{PhysicalPlan, ResolvedExpr};
+    use testsupport::prelude::*;
+
+    fn scan(ctx: &mut ExecutionContext) -> Vec<Vec<Value>> {
+        let plan = PhysicalPlan::SeqScan {
+            table_id: TableId(1),
+            schema: vec!["id".into(), "name".into(), "active".into()],
+            projection: None,
+        };
+        let mut rows: Vec<_> = execute_query(plan, ctx)
+            .unwrap()
+            .into_iter()
+            .map(|row| row.values)
+            .collect();
+        rows.sort_by_key(|values| format!("{values:?}"));
+        rows
+    }
+
+    #[test]
+    fn roll_back_restores_inserted_updated_and_deleted_rows() {
+        let (ctx, _temp) = setup_test_context();
+        let mut ctx = ctx.with_undo_log();
+        let table_id = TableId(1);
+        for (id, name) in [(1, "Ada"), (2, "Bob")] {
+            let plan = PhysicalPlan::Insert {
+                table_id,
+                rows: vec![vec![lit!(int: id), lit!(text: name), lit!(bool: true)]],
+            };
+            execute_dml(plan, &mut ctx).unwrap();
+        }
+        ctx.take_undo_log();
+        let before = scan(&mut ctx);
+
+        let statements = [
+            PhysicalPlan::Insert {
+                table_id,
+                rows: vec![vec![lit!(int: 3), lit!(text: "Cy"), lit!(bool: false)]],
+            },
+            PhysicalPlan::Update {
+                table_id,
+                assignments: vec![(1, lit!(text: "Ada Lovelace"))],
+                predicate: Some(ResolvedExpr::Literal(Value::Bool(true))),
+                source: None,
+            },
+            PhysicalPlan::Delete {
+                table_id,
+                predicate: Some(ResolvedExpr::Literal(Value::Bool(true))),
+                source: None,
+            },
+        ];
+        for plan in statements {
+            execute_dml(plan, &mut ctx).unwrap();
+        }
+        assert!(scan(&mut ctx).is_empty());
+
+        let undo = ctx.take_undo_log();
+        assert_eq!(undo.len(), 7);
+        roll_back(&mut ctx, undo).unwrap();
+        assert_eq!(scan(&mut ctx), before);
+        assert!(ctx.take_undo_log().is_empty());
+    }
+}
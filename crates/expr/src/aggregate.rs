@@ -0,0 +1,146 @@
+//! Aggregate functions: `COUNT`, `SUM`, `AVG`, `MIN` and `MAX`.
+//!
+//! An [`Accumulator`] holds the running state of one aggregate over one
+//! group of rows. It follows SQL's NULL rules: every aggregate skips NULL
+//! arguments, so `COUNT(col)` counts the rows where `col` is not NULL while
+//! `COUNT(*)` counts every row, and `SUM`, `AVG`, `MIN` and `MAX` of a group
+//! with no non-NULL values are NULL. `COUNT` of such a group is 0.
+
+use crate::{BinaryOp, arithmetic};
+use common::{DbError, DbResult};
+use std::cmp::Ordering;
+use std::fmt;
+use types::Value;
+
+/// An aggregate function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum AggregateFunc {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateFunc {
+    /// The aggregate function called `name` (case-insensitive), if any.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "count" => Some(AggregateFunc::Count),
+            "sum" => Some(AggregateFunc::Sum),
+            "avg" => Some(AggregateFunc::Avg),
+            "min" => Some(AggregateFunc::Min),
+            "max" => Some(AggregateFunc::Max),
+            _ => None,
+        }
+    }
+
+    /// Canonical (lowercase) name.
+    pub fn name(self) -> &'static str {
+        match self {
+            AggregateFunc::Count => "count",
+            AggregateFunc::Sum => "sum",
+            AggregateFunc::Avg => "avg",
+            AggregateFunc::Min => "min",
+            AggregateFunc::Max => "max",
+        }
+    }
+}
+
+impl fmt::Display for AggregateFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Running state of one aggregate over one group of rows.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Accumulator {
+    func: AggregateFunc,
+    /// Arguments counted, NULLs excepted.
+    count: i64,
+    /// Sum of the arguments for `SUM` and `AVG`, or the smallest or largest
+    /// for `MIN` and `MAX`; `None` until a non-NULL argument is seen.
+    value: Option<Value>,
+}
+
+impl Accumulator {
+    /// The state of `func` over no rows.
+    pub fn new(func: AggregateFunc) -> Self {
+        Self {
+            func,
+            count: 0,
+            value: None,
+        }
+    }
+
+    /// Add a row's argument to the aggregate. `None` stands for the row
+    /// itself, which `COUNT(*)` counts whatever its values; a NULL argument
+    /// is skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DbError::Executor` if `SUM` or `AVG` is given a value that
+    /// is not a number, `MIN` or `MAX` values that cannot be compared, or a
+    /// sum overflows.
+    pub fn update(&mut self, arg: Option<&Value>) -> DbResult<()> {
+        let Some(value) = arg else {
+            self.count += 1;
+            return Ok(());
+        };
+        if matches!(value, Value::Null) {
+            return Ok(());
+        }
+        self.count += 1;
+        self.value = match (self.func, self.value.take()) {
+            (AggregateFunc::Count, _) => None,
+            (AggregateFunc::Sum | AggregateFunc::Avg, sum) => {
+                if !matches!(value, Value::Int(_) | Value::Float(_) | Value::Decimal(_)) {
+                    return Err(DbError::Executor(format!(
+                        "{} expects numbers, got {}",
+                        self.func.name().to_uppercase(),
+                        value.describe()
+                    )));
+                }
+                match sum {
+                    Some(sum) => Some(arithmetic::apply(&sum, BinaryOp::Add, value)?),
+                    None => Some(value.clone()),
+                }
+            }
+            (AggregateFunc::Min | AggregateFunc::Max, None) => Some(value.clone()),
+            (AggregateFunc::Min | AggregateFunc::Max, Some(current)) => {
+                let ord = value.cmp_same_type(&current).ok_or_else(|| {
+                    DbError::Executor(format!(
+                        "{} cannot compare {} with {}",
+                        self.func.name().to_uppercase(),
+                        value.describe(),
+                        current.describe()
+                    ))
+                })?;
+                let replace = match self.func {
+                    AggregateFunc::Min => ord == Ordering::Less,
+                    _ => ord == Ordering::Greater,
+                };
+                Some(if replace { value.clone() } else { current })
+            }
+        };
+        Ok(())
+    }
+
+    /// The aggregate of the values added so far.
+    ///
+    /// `AVG` of integers or floats is a float, and of decimals a decimal.
+    pub fn finish(&self) -> DbResult<Value> {
+        match (self.func, &self.value) {
+            (AggregateFunc::Count, _) => Ok(Value::Int(self.count)),
+            (_, None) => Ok(Value::Null),
+            (AggregateFunc::Avg, Some(sum @ Value::Decimal(_))) => {
+                arithmetic::apply(sum, BinaryOp::Div, &Value::Int(self.count))
+            }
+            (AggregateFunc::Avg, Some(sum)) => {
+                arithmetic::apply(sum, BinaryOp::Div, &Value::Float(self.count as f64))
+            }
+            (_, Some(value)) => Ok(value.clone()),
+        }
+    }
+}
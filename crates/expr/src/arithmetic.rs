@@ -0,0 +1,79 @@
+//! `+`, `-`, `*` and `/` on numbers.
+//!
+//! Ints combine to ints and fail on overflow, with `/` truncating toward
+//! zero as in PostgreSQL. Decimals combine with ints and decimals exactly
+//! (see [`Decimal`]), and a float on either side makes the result a float.
+//! NULL on either side gives NULL.
+
+use crate::BinaryOp;
+use common::{DbError, DbResult};
+use types::{Decimal, Value};
+
+/// Apply the arithmetic operator `op` to two values.
+pub fn apply(left: &Value, op: BinaryOp, right: &Value) -> DbResult<Value> {
+    let invalid = || DbError::Executor(format!("cannot apply {op} to {left:?} and {right:?}"));
+    let division_by_zero = || DbError::Executor("division by zero".into());
+
+    match (left, right) {
+        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+        (Value::Int(a), Value::Int(b)) => {
+            let result = match op {
+                BinaryOp::Add => a.checked_add(*b),
+                BinaryOp::Sub => a.checked_sub(*b),
+                BinaryOp::Mul => a.checked_mul(*b),
+                BinaryOp::Div if *b == 0 => return Err(division_by_zero()),
+                BinaryOp::Div => a.checked_div(*b),
+                _ => return Err(invalid()),
+            };
+            result.map(Value::Int).ok_or_else(|| {
+                DbError::Executor(format!("integer out of range: {left:?} {op} {right:?}"))
+            })
+        }
+        (Value::Float(_), _) | (_, Value::Float(_)) => {
+            let (Some(a), Some(b)) = (as_f64(left), as_f64(right)) else {
+                return Err(invalid());
+            };
+            Ok(Value::Float(match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Sub => a - b,
+                BinaryOp::Mul => a * b,
+                BinaryOp::Div if b == 0.0 => return Err(division_by_zero()),
+                BinaryOp::Div => a / b,
+                _ => return Err(invalid()),
+            }))
+        }
+        _ => {
+            let (Some(a), Some(b)) = (as_decimal(left), as_decimal(right)) else {
+                return Err(invalid());
+            };
+            let result = match op {
+                BinaryOp::Add => a.checked_add(&b),
+                BinaryOp::Sub => a.checked_sub(&b),
+                BinaryOp::Mul => a.checked_mul(&b),
+                BinaryOp::Div if b.units() == 0 => return Err(division_by_zero()),
+                BinaryOp::Div => a.checked_div(&b),
+                _ => return Err(invalid()),
+            };
+            result.map(Value::Decimal).ok_or_else(|| {
+                DbError::Executor(format!("numeric value out of range: {a} {op} {b}"))
+            })
+        }
+    }
+}
+
+fn as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(i) => Some(*i as f64),
+        Value::Float(f) => Some(*f),
+        Value::Decimal(d) => Some(d.to_f64()),
+        _ => None,
+    }
+}
+
+fn as_decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::Int(i) => Some(Decimal::from_int(*i)),
+        Value::Decimal(d) => Some(*d),
+        _ => None,
+    }
+}
@@ -0,0 +1,330 @@
+//! Built-in scalar functions.
+//!
+//! Functions are looked up by name in a static registry. The planner uses the
+//! registry to validate calls at bind time and the evaluators use it to invoke
+//! them, so adding a function only requires a new entry in [`REGISTRY`].
+//!
+//! All functions return NULL when any argument is NULL, except `CONCAT`,
+//! which skips NULL arguments, and `COALESCE` and `NULLIF`, which exist to
+//! handle them.
+
+use common::{DbError, DbResult};
+use types::{Value, binary, temporal};
+
+/// A scalar function: maps a list of argument values to a single value.
+#[derive(Debug)]
+pub struct ScalarFunction {
+    /// Canonical (lowercase) function name.
+    pub name: &'static str,
+    /// Minimum number of arguments.
+    pub min_args: usize,
+    /// Maximum number of arguments (`None` for variadic functions).
+    pub max_args: Option<usize>,
+    eval: fn(&[Value]) -> DbResult<Value>,
+}
+
+impl ScalarFunction {
+    /// Whether the function can be called with `n` arguments.
+    pub fn accepts(&self, n: usize) -> bool {
+        n >= self.min_args && self.max_args.is_none_or(|max| n <= max)
+    }
+
+    /// Invoke the function on already-evaluated arguments.
+    pub fn invoke(&self, args: &[Value]) -> DbResult<Value> {
+        if !self.accepts(args.len()) {
+            return Err(DbError::Executor(format!(
+                "{} called with {} arguments",
+                self.name.to_uppercase(),
+                args.len()
+            )));
+        }
+        (self.eval)(args)
+    }
+}
+
+/// Registry of all built-in scalar functions.
+static REGISTRY: &[ScalarFunction] = &[
+    ScalarFunction {
+        name: "upper",
+        min_args: 1,
+        max_args: Some(1),
+        eval: upper,
+    },
+    ScalarFunction {
+        name: "lower",
+        min_args: 1,
+        max_args: Some(1),
+        eval: lower,
+    },
+    ScalarFunction {
+        name: "length",
+        min_args: 1,
+        max_args: Some(1),
+        eval: length,
+    },
+    ScalarFunction {
+        name: "substr",
+        min_args: 2,
+        max_args: Some(3),
+        eval: substr,
+    },
+    ScalarFunction {
+        name: "concat",
+        min_args: 1,
+        max_args: None,
+        eval: concat,
+    },
+    ScalarFunction {
+        name: "coalesce",
+        min_args: 1,
+        max_args: None,
+        eval: coalesce,
+    },
+    ScalarFunction {
+        name: "nullif",
+        min_args: 2,
+        max_args: Some(2),
+        eval: nullif,
+    },
+    ScalarFunction {
+        name: "trim",
+        min_args: 1,
+        max_args: Some(1),
+        eval: trim,
+    },
+    ScalarFunction {
+        name: "now",
+        min_args: 0,
+        max_args: Some(0),
+        eval: now,
+    },
+    ScalarFunction {
+        name: "random",
+        min_args: 0,
+        max_args: Some(0),
+        eval: random,
+    },
+    ScalarFunction {
+        name: "date_trunc",
+        min_args: 2,
+        max_args: Some(2),
+        eval: date_trunc,
+    },
+    ScalarFunction {
+        name: "encode",
+        min_args: 2,
+        max_args: Some(2),
+        eval: encode,
+    },
+    ScalarFunction {
+        name: "decode",
+        min_args: 2,
+        max_args: Some(2),
+        eval: decode,
+    },
+];
+
+/// Look up a scalar function by name (case-insensitive).
+pub fn lookup(name: &str) -> Option<&'static ScalarFunction> {
+    REGISTRY.iter().find(|f| f.name.eq_ignore_ascii_case(name))
+}
+
+/// All registered scalar functions.
+pub fn registry() -> &'static [ScalarFunction] {
+    REGISTRY
+}
+
+fn upper(args: &[Value]) -> DbResult<Value> {
+    map_text("UPPER", &args[0], |s| Value::Text(s.to_uppercase()))
+}
+
+fn lower(args: &[Value]) -> DbResult<Value> {
+    map_text("LOWER", &args[0], |s| Value::Text(s.to_lowercase()))
+}
+
+/// `LENGTH(text)` in characters, or `LENGTH(bytes)` in bytes.
+fn length(args: &[Value]) -> DbResult<Value> {
+    match &args[0] {
+        Value::Bytes(b) => Ok(Value::Int(b.len() as i64)),
+        other => map_text("LENGTH", other, |s| Value::Int(s.chars().count() as i64)),
+    }
+}
+
+fn trim(args: &[Value]) -> DbResult<Value> {
+    map_text("TRIM", &args[0], |s| Value::Text(s.trim().to_string()))
+}
+
+/// `SUBSTR(text, start [, len])` with 1-based character positions.
+fn substr(args: &[Value]) -> DbResult<Value> {
+    if args.iter().any(|v| matches!(v, Value::Null)) {
+        return Ok(Value::Null);
+    }
+    let s = expect_text("SUBSTR", &args[0])?;
+    let start = expect_int("SUBSTR", &args[1])?;
+    let end = match args.get(2) {
+        Some(v) => {
+            let len = expect_int("SUBSTR", v)?;
+            if len < 0 {
+                return Err(DbError::Executor(
+                    "SUBSTR length must not be negative".into(),
+                ));
+            }
+            start.saturating_add(len)
+        }
+        None => i64::MAX,
+    };
+    let begin = start.max(1);
+    if end <= begin {
+        return Ok(Value::Text(String::new()));
+    }
+    let out = s
+        .chars()
+        .skip((begin - 1) as usize)
+        .take((end - begin) as usize)
+        .collect();
+    Ok(Value::Text(out))
+}
+
+fn concat(args: &[Value]) -> DbResult<Value> {
+    let mut out = String::new();
+    for arg in args {
+        match arg {
+            Value::Text(s) => out.push_str(s),
+            Value::Int(i) => out.push_str(&i.to_string()),
+            Value::Bool(b) => out.push_str(&b.to_string()),
+            Value::Float(f) => out.push_str(&format!("{f:?}")),
+            Value::Date(d) => out.push_str(&temporal::format_date(*d)),
+            Value::Timestamp(t) => out.push_str(&temporal::format_timestamp(*t)),
+            Value::Decimal(d) => out.push_str(&d.to_string()),
+            Value::Bytes(b) => out.push_str(&binary::format_hex(b)),
+            Value::Null => {}
+        }
+    }
+    Ok(Value::Text(out))
+}
+
+/// `COALESCE(a, b, ...)`: the first argument that is not NULL, or NULL.
+fn coalesce(args: &[Value]) -> DbResult<Value> {
+    Ok(args
+        .iter()
+        .find(|v| !matches!(v, Value::Null))
+        .cloned()
+        .unwrap_or(Value::Null))
+}
+
+/// `NULLIF(a, b)`: NULL if `a` equals `b`, otherwise `a`.
+fn nullif(args: &[Value]) -> DbResult<Value> {
+    match args[0].eq_same_type(&args[1]) {
+        Some(true) => Ok(Value::Null),
+        _ => Ok(args[0].clone()),
+    }
+}
+
+/// `NOW()`: the current UTC time.
+fn now(_args: &[Value]) -> DbResult<Value> {
+    Ok(Value::Timestamp(temporal::now()))
+}
+
+/// `RANDOM()`: a float in `[0, 1)`, drawn from the thread's generator (see
+/// [`crate::random`]).
+fn random(_args: &[Value]) -> DbResult<Value> {
+    Ok(Value::Float(crate::random::next_random()))
+}
+
+/// `DATE_TRUNC(unit, t)`: `t` rounded down to the start of its `unit`, e.g.
+/// `'month'`. A date stays a date.
+fn date_trunc(args: &[Value]) -> DbResult<Value> {
+    if args.iter().any(|v| matches!(v, Value::Null)) {
+        return Ok(Value::Null);
+    }
+    let unit = expect_text("DATE_TRUNC", &args[0])?;
+    let micros = match &args[1] {
+        Value::Timestamp(t) => *t,
+        Value::Date(d) => temporal::date_to_timestamp(*d),
+        other => {
+            return Err(DbError::Executor(format!(
+                "DATE_TRUNC expects a date or timestamp, got {other:?}"
+            )));
+        }
+    };
+    let truncated = temporal::truncate_timestamp(micros, unit).ok_or_else(|| {
+        DbError::Executor(format!(
+            "DATE_TRUNC unit must be one of {}, got '{unit}'",
+            temporal::TRUNCATE_UNITS.join(", ")
+        ))
+    })?;
+    Ok(match args[1] {
+        Value::Date(_) => Value::Date(temporal::timestamp_to_date(truncated)),
+        _ => Value::Timestamp(truncated),
+    })
+}
+
+/// `ENCODE(bytes, format)`: the bytes as `'hex'` or `'base64'` text.
+fn encode(args: &[Value]) -> DbResult<Value> {
+    if args.iter().any(|v| matches!(v, Value::Null)) {
+        return Ok(Value::Null);
+    }
+    let bytes = match &args[0] {
+        Value::Bytes(b) => b,
+        other => {
+            return Err(DbError::Executor(format!(
+                "ENCODE expects bytes, got {other:?}"
+            )));
+        }
+    };
+    let format = expect_text("ENCODE", &args[1])?;
+    let text = match format.to_ascii_lowercase().as_str() {
+        "hex" => binary::encode_hex(bytes),
+        "base64" => binary::encode_base64(bytes),
+        _ => return Err(unknown_encoding("ENCODE", format)),
+    };
+    Ok(Value::Text(text))
+}
+
+/// `DECODE(text, format)`: the bytes written as `'hex'` or `'base64'` text.
+fn decode(args: &[Value]) -> DbResult<Value> {
+    if args.iter().any(|v| matches!(v, Value::Null)) {
+        return Ok(Value::Null);
+    }
+    let text = expect_text("DECODE", &args[0])?;
+    let format = expect_text("DECODE", &args[1])?;
+    let bytes = match format.to_ascii_lowercase().as_str() {
+        "hex" => binary::decode_hex(text),
+        "base64" => binary::decode_base64(text),
+        _ => return Err(unknown_encoding("DECODE", format)),
+    };
+    bytes
+        .map(Value::Bytes)
+        .ok_or_else(|| DbError::Executor(format!("DECODE found invalid {format} text '{text}'")))
+}
+
+fn unknown_encoding(func: &str, format: &str) -> DbError {
+    DbError::Executor(format!(
+        "{func} format must be 'hex' or 'base64', got '{format}'"
+    ))
+}
+
+fn map_text(func: &str, arg: &Value, f: impl FnOnce(&str) -> Value) -> DbResult<Value> {
+    match arg {
+        Value::Null => Ok(Value::Null),
+        other => Ok(f(expect_text(func, other)?)),
+    }
+}
+
+fn expect_text<'a>(func: &str, v: &'a Value) -> DbResult<&'a str> {
+    match v {
+        Value::Text(s) => Ok(s),
+        other => Err(DbError::Executor(format!(
+            "{func} expects text, got {other:?}"
+        ))),
+    }
+}
+
+fn expect_int(func: &str, v: &Value) -> DbResult<i64> {
+    match v {
+        Value::Int(i) => Ok(*i),
+        other => Err(DbError::Executor(format!(
+            "{func} expects int, got {other:?}"
+        ))),
+    }
+}
@@ -1,12 +1,18 @@
 #[cfg(test)]
 mod tests;
 
+pub mod aggregate;
+pub mod arithmetic;
+pub mod functions;
+pub mod random;
+
+use aggregate::AggregateFunc;
 use common::{DbError, DbResult, Row};
 use std::cmp::Ordering;
-#[allow(unused_imports)]
-use types::{SqlType, Value};
+use std::fmt;
+use types::{SqlType, Value, binary, temporal};
 
-/// Binary comparison and logical operators.
+/// Binary comparison, logical and arithmetic operators.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum BinaryOp {
     Eq,
@@ -17,6 +23,20 @@ pub enum BinaryOp {
     Ge,
     And,
     Or,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl BinaryOp {
+    /// Whether the operator is `+`, `-`, `*` or `/` (see [`arithmetic`]).
+    pub fn is_arithmetic(self) -> bool {
+        matches!(
+            self,
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div
+        )
+    }
 }
 
 /// Unary operators (currently just logical NOT).
@@ -50,6 +70,147 @@ pub enum Expr {
         op: BinaryOp,
         right: Box<Expr>,
     },
+    /// Scalar function call, e.g. `UPPER(name)`.
+    ///
+    /// The name is normalized to lowercase and resolved against
+    /// [`functions::lookup`] during planning.
+    Function {
+        name: String,
+        args: Vec<Expr>,
+    },
+    /// `CAST(expr AS ty)`; see [`Value::cast`].
+    Cast {
+        expr: Box<Expr>,
+        ty: SqlType,
+    },
+    /// `expr IS NULL`, or `expr IS NOT NULL` if `negated`. Never NULL itself.
+    IsNull {
+        expr: Box<Expr>,
+        negated: bool,
+    },
+    /// `CASE [operand] WHEN .. THEN .. [ELSE ..] END`.
+    ///
+    /// Without an operand, the first branch whose `WHEN` condition is true
+    /// is taken; with one, the first whose `WHEN` value equals the operand.
+    /// If no branch is taken the result is the `ELSE` result, or NULL.
+    Case {
+        operand: Option<Box<Expr>>,
+        /// `(WHEN, THEN)` pairs, in order.
+        branches: Vec<(Expr, Expr)>,
+        else_result: Option<Box<Expr>>,
+    },
+    /// Aggregate function call, e.g. `COUNT(*)` or `SUM(amount)`.
+    ///
+    /// Aggregates are computed over groups of rows by the planner's
+    /// aggregation, which replaces each call with a column of its output;
+    /// they cannot be evaluated against a single row.
+    Aggregate {
+        func: AggregateFunc,
+        /// The argument; `None` for `COUNT(*)`.
+        arg: Option<Box<Expr>>,
+    },
+}
+
+/// Whether a `CASE` branch is taken, given the value of its `WHEN` condition
+/// (of `operand = value` for a CASE with an operand). NULL counts as false.
+pub fn case_branch_taken(condition: Value) -> DbResult<bool> {
+    match condition {
+        Value::Bool(b) => Ok(b),
+        Value::Null => Ok(false),
+        other => Err(DbError::Executor(format!(
+            "CASE WHEN condition must be boolean, got {other:?}"
+        ))),
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            BinaryOp::Eq => "=",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::And => "AND",
+            BinaryOp::Or => "OR",
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Renders the expression as SQL-like text; used to name computed columns.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Literal(Value::Int(i)) => write!(f, "{i}"),
+            Expr::Literal(Value::Text(s)) => write!(f, "'{s}'"),
+            Expr::Literal(Value::Bool(b)) => write!(f, "{b}"),
+            Expr::Literal(Value::Float(x)) => write!(f, "{x:?}"),
+            Expr::Literal(Value::Decimal(d)) => write!(f, "{d}"),
+            Expr::Literal(Value::Bytes(b)) => write!(f, "X'{}'", binary::encode_hex(b)),
+            Expr::Literal(Value::Date(d)) => write!(f, "DATE '{}'", temporal::format_date(*d)),
+            Expr::Literal(Value::Timestamp(t)) => {
+                write!(f, "TIMESTAMP '{}'", temporal::format_timestamp(*t))
+            }
+            Expr::Literal(Value::Null) => f.write_str("NULL"),
+            Expr::Column {
+                table: Some(table),
+                name,
+            } => write!(f, "{table}.{name}"),
+            Expr::Column { table: None, name } => f.write_str(name),
+            Expr::Unary {
+                op: UnaryOp::Not,
+                expr,
+            } => write!(f, "NOT {expr}"),
+            Expr::Binary { left, op, right } => write!(f, "{left} {op} {right}"),
+            Expr::Function { name, args } => {
+                write!(f, "{name}(")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{arg}")?;
+                }
+                f.write_str(")")
+            }
+            Expr::Cast { expr, ty } => write!(f, "CAST({expr} AS {ty})"),
+            Expr::IsNull {
+                expr,
+                negated: false,
+            } => write!(f, "{expr} IS NULL"),
+            Expr::IsNull {
+                expr,
+                negated: true,
+            } => write!(f, "{expr} IS NOT NULL"),
+            Expr::Case {
+                operand,
+                branches,
+                else_result,
+            } => {
+                f.write_str("CASE")?;
+                if let Some(operand) = operand {
+                    write!(f, " {operand}")?;
+                }
+                for (when, then) in branches {
+                    write!(f, " WHEN {when} THEN {then}")?;
+                }
+                if let Some(else_result) = else_result {
+                    write!(f, " ELSE {else_result}")?;
+                }
+                f.write_str(" END")
+            }
+            Expr::Aggregate { func, arg: None } => write!(f, "{func}(*)"),
+            Expr::Aggregate {
+                func,
+                arg: Some(arg),
+            } => write!(f, "{func}({arg})"),
+        }
+    }
 }
 
 /// Evaluation context consisting of the row schema (column names in order).
@@ -82,6 +243,45 @@ impl<'a> EvalContext<'a> {
                 let rv = self.eval(right, row)?;
                 self.eval_binary(&lv, *op, &rv)
             }
+            Expr::Function { name, args } => {
+                let func = functions::lookup(name)
+                    .ok_or_else(|| DbError::Executor(format!("unknown function '{name}'")))?;
+                let values = args
+                    .iter()
+                    .map(|arg| self.eval(arg, row))
+                    .collect::<DbResult<Vec<_>>>()?;
+                func.invoke(&values)
+            }
+            Expr::Cast { expr, ty } => self.eval(expr, row)?.cast(ty).map_err(DbError::Executor),
+            Expr::IsNull { expr, negated } => Ok(Value::Bool(
+                (self.eval(expr, row)? == Value::Null) != *negated,
+            )),
+            Expr::Case {
+                operand,
+                branches,
+                else_result,
+            } => {
+                let operand = operand.as_ref().map(|o| self.eval(o, row)).transpose()?;
+                for (when, then) in branches {
+                    let when = self.eval(when, row)?;
+                    let condition = match &operand {
+                        Some(Value::Null) => Value::Null,
+                        Some(_) if when == Value::Null => Value::Null,
+                        Some(operand) => self.eval_binary(operand, BinaryOp::Eq, &when)?,
+                        None => when,
+                    };
+                    if case_branch_taken(condition)? {
+                        return self.eval(then, row);
+                    }
+                }
+                match else_result {
+                    Some(else_result) => self.eval(else_result, row),
+                    None => Ok(Value::Null),
+                }
+            }
+            Expr::Aggregate { .. } => Err(DbError::Executor(format!(
+                "aggregate {expr} cannot be evaluated against a single row"
+            ))),
         }
     }
 
@@ -102,6 +302,7 @@ impl<'a> EvalContext<'a> {
                     _ => unreachable!(),
                 }));
             }
+            op if op.is_arithmetic() => return arithmetic::apply(l, op, r),
             _ => {}
         }
 
@@ -138,14 +339,17 @@ impl<'a> EvalContext<'a> {
             self.schema
                 .iter()
                 .position(|c| c.eq_ignore_ascii_case(&full_name))
-                .ok_or_else(|| DbError::Executor(format!("unknown column '{}.{}'", qualifier, name)))
+                .ok_or_else(|| {
+                    DbError::Executor(format!("unknown column '{}.{}'", qualifier, name))
+                })
         } else {
             // Unqualified: try exact match first, then suffix match
             self.schema
                 .iter()
                 .position(|c| {
                     c.eq_ignore_ascii_case(name)
-                        || c.to_lowercase().ends_with(&format!(".{}", name.to_lowercase()))
+                        || c.to_lowercase()
+                            .ends_with(&format!(".{}", name.to_lowercase()))
                 })
                 .ok_or_else(|| DbError::Executor(format!("unknown column '{}'", name)))
         }
@@ -0,0 +1,84 @@
+//! The generator `RANDOM()` draws from.
+//!
+//! Each thread has a generator, seeded unpredictably on first use. Code that
+//! needs a reproducible sequence (the database, for a session with a fixed
+//! seed) runs the evaluation inside [`with_generator`], which makes `RANDOM()`
+//! draw from the given generator and leaves it where the sequence stopped, so
+//! the next statement continues it.
+//!
+//! The generator is SplitMix64: fast and statistically sound for test data,
+//! but not suitable for anything that must be unguessable.
+
+use std::{
+    cell::RefCell,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// A seedable source of pseudo-random numbers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Generator {
+    state: u64,
+}
+
+impl Generator {
+    /// A generator that always produces the same sequence for `seed`.
+    pub fn seeded(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// A generator seeded from the hash keys the standard library randomizes
+    /// per process.
+    pub fn unseeded() -> Self {
+        Self::seeded(RandomState::new().build_hasher().finish())
+    }
+
+    /// The next number of the sequence.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// The next number of the sequence as a float in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Generator>> = const { RefCell::new(None) };
+}
+
+/// Draw the next float in `[0, 1)` from the current thread's generator.
+pub fn next_random() -> f64 {
+    CURRENT.with_borrow_mut(|current| current.get_or_insert_with(Generator::unseeded).next_f64())
+}
+
+/// Run `f` with `RANDOM()` on this thread drawing from `generator`, which is
+/// left at the point in its sequence `f` stopped at.
+pub fn with_generator<T>(generator: &mut Generator, f: impl FnOnce() -> T) -> T {
+    /// Puts the thread's own generator back, even if `f` panics.
+    struct Restore<'a> {
+        generator: &'a mut Generator,
+        previous: Option<Generator>,
+    }
+
+    impl Drop for Restore<'_> {
+        fn drop(&mut self) {
+            let previous = self.previous.take();
+            if let Some(used) = CURRENT.replace(previous) {
+                *self.generator = used;
+            }
+        }
+    }
+
+    let previous = CURRENT.replace(Some(generator.clone()));
+    let _restore = Restore {
+        generator,
+        previous,
+    };
+    f()
+}
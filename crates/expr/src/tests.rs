@@ -277,3 +277,416 @@ fn eval_join_condition_no_match() {
 
     assert_eq!(ctx.eval(&condition, &row).unwrap(), Bool(false));
 }
+
+fn call(name: &str, args: Vec<Expr>) -> Expr {
+    Expr::Function {
+        name: name.to_string(),
+        args,
+    }
+}
+
+fn text(s: &str) -> Expr {
+    Expr::Literal(Text(s.into()))
+}
+
+#[test]
+fn eval_string_functions() {
+    let row = Row::new(vec![Text("  Will  ".into()), Text("Smith".into())]);
+    let schema = schema(&["first", "last"]);
+    let ctx = EvalContext { schema: &schema };
+
+    let cases = [
+        (call("upper", vec![col("last")]), Text("SMITH".into())),
+        (call("lower", vec![col("last")]), Text("smith".into())),
+        (call("length", vec![col("last")]), Int(5)),
+        (call("trim", vec![col("first")]), Text("Will".into())),
+        (
+            call(
+                "substr",
+                vec![col("last"), Expr::Literal(Int(2)), Expr::Literal(Int(3))],
+            ),
+            Text("mit".into()),
+        ),
+        (
+            call("substr", vec![col("last"), Expr::Literal(Int(3))]),
+            Text("ith".into()),
+        ),
+        (
+            call(
+                "concat",
+                vec![col("last"), text(", "), Expr::Literal(Int(7))],
+            ),
+            Text("Smith, 7".into()),
+        ),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(ctx.eval(&expr, &row).unwrap(), expected, "{expr}");
+    }
+}
+
+#[test]
+fn functions_propagate_null() {
+    let row = Row::new(vec![Null]);
+    let schema = schema(&["name"]);
+    let ctx = EvalContext { schema: &schema };
+
+    assert_eq!(
+        ctx.eval(&call("upper", vec![col("name")]), &row).unwrap(),
+        Null
+    );
+    assert_eq!(
+        ctx.eval(
+            &call("concat", vec![text("a"), col("name"), text("b")]),
+            &row
+        )
+        .unwrap(),
+        Text("ab".into())
+    );
+}
+
+#[test]
+fn coalesce_and_nullif_handle_null() {
+    let row = Row::new(vec![Null, Int(3)]);
+    let schema = schema(&["nick", "n"]);
+    let ctx = EvalContext { schema: &schema };
+
+    let cases = [
+        (
+            call("coalesce", vec![col("nick"), text("anon")]),
+            Text("anon".into()),
+        ),
+        (
+            call("coalesce", vec![col("n"), Expr::Literal(Int(0))]),
+            Int(3),
+        ),
+        (call("coalesce", vec![col("nick")]), Null),
+        (call("nullif", vec![col("n"), Expr::Literal(Int(3))]), Null),
+        (
+            call("nullif", vec![col("n"), Expr::Literal(Int(0))]),
+            Int(3),
+        ),
+        (
+            call("nullif", vec![col("nick"), Expr::Literal(Int(0))]),
+            Null,
+        ),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(ctx.eval(&expr, &row).unwrap(), expected, "{expr}");
+    }
+}
+
+fn case(operand: Option<Expr>, branches: Vec<(Expr, Expr)>, else_result: Option<Expr>) -> Expr {
+    Expr::Case {
+        operand: operand.map(Box::new),
+        branches,
+        else_result: else_result.map(Box::new),
+    }
+}
+
+#[test]
+fn eval_case_takes_the_first_matching_branch() {
+    let row = Row::new(vec![Int(15), Null]);
+    let schema = schema(&["age", "nick"]);
+    let ctx = EvalContext { schema: &schema };
+    let lt = |n| Expr::Binary {
+        left: Box::new(col("age")),
+        op: BinaryOp::Lt,
+        right: Box::new(Expr::Literal(Int(n))),
+    };
+
+    let searched = case(
+        None,
+        vec![
+            (lt(13), text("child")),
+            (lt(18), text("teen")),
+            (lt(99), text("adult")),
+        ],
+        Some(text("old")),
+    );
+    assert_eq!(ctx.eval(&searched, &row).unwrap(), Text("teen".into()));
+
+    let simple = case(
+        Some(col("age")),
+        vec![
+            (Expr::Literal(Int(14)), text("a")),
+            (Expr::Literal(Int(15)), text("b")),
+        ],
+        None,
+    );
+    assert_eq!(ctx.eval(&simple, &row).unwrap(), Text("b".into()));
+    assert_eq!(
+        simple.to_string(),
+        "CASE age WHEN 14 THEN 'a' WHEN 15 THEN 'b' END"
+    );
+
+    // NULL matches nothing, and without ELSE the result is NULL
+    let null_operand = case(
+        Some(col("nick")),
+        vec![(Expr::Literal(Null), text("x"))],
+        None,
+    );
+    assert_eq!(ctx.eval(&null_operand, &row).unwrap(), Null);
+    let null_condition = case(None, vec![(col("nick"), text("x"))], Some(text("y")));
+    assert_eq!(ctx.eval(&null_condition, &row).unwrap(), Text("y".into()));
+
+    let not_bool = case(None, vec![(col("age"), text("x"))], None);
+    assert!(ctx.eval(&not_bool, &row).is_err());
+}
+
+#[test]
+fn eval_is_null_is_never_null() {
+    let row = Row::new(vec![Int(15), Null]);
+    let schema = schema(&["age", "nick"]);
+    let ctx = EvalContext { schema: &schema };
+    let is_null = |name, negated| Expr::IsNull {
+        expr: Box::new(col(name)),
+        negated,
+    };
+
+    assert_eq!(ctx.eval(&is_null("age", false), &row).unwrap(), Bool(false));
+    assert_eq!(ctx.eval(&is_null("age", true), &row).unwrap(), Bool(true));
+    assert_eq!(ctx.eval(&is_null("nick", false), &row).unwrap(), Bool(true));
+    assert_eq!(ctx.eval(&is_null("nick", true), &row).unwrap(), Bool(false));
+    assert_eq!(is_null("nick", false).to_string(), "nick IS NULL");
+    assert_eq!(is_null("nick", true).to_string(), "nick IS NOT NULL");
+}
+
+#[test]
+fn function_errors() {
+    let row = Row::new(vec![Int(1)]);
+    let schema = schema(&["id"]);
+    let ctx = EvalContext { schema: &schema };
+
+    assert!(ctx.eval(&call("upper", vec![col("id")]), &row).is_err());
+    assert!(ctx.eval(&call("nope", vec![col("id")]), &row).is_err());
+    assert!(ctx.eval(&call("upper", vec![]), &row).is_err());
+}
+
+#[test]
+fn substr_handles_out_of_range_positions() {
+    let f = functions::lookup("SUBSTR").unwrap();
+    let s = Text("hello".into());
+    assert_eq!(
+        f.invoke(&[s.clone(), Int(0), Int(3)]).unwrap(),
+        Text("he".into())
+    );
+    assert_eq!(f.invoke(&[s.clone(), Int(10)]).unwrap(), Text("".into()));
+    assert!(f.invoke(&[s, Int(1), Int(-1)]).is_err());
+}
+
+#[test]
+fn random_repeats_its_sequence_for_a_seed() {
+    let f = functions::lookup("RANDOM").unwrap();
+    let draw = |generator: &mut random::Generator| {
+        random::with_generator(generator, || {
+            (0..3)
+                .map(|_| match f.invoke(&[]).unwrap() {
+                    Float(x) => x,
+                    other => panic!("expected a float, got {other:?}"),
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    let mut first = random::Generator::seeded(42);
+    let values = draw(&mut first);
+    assert!(values.iter().all(|x| (0.0..1.0).contains(x)), "{values:?}");
+    assert_ne!(values[0], values[1]);
+    assert_eq!(draw(&mut random::Generator::seeded(42)), values);
+    assert_ne!(draw(&mut random::Generator::seeded(43)), values);
+
+    // The generator continues where the last call left it
+    let mut second = random::Generator::seeded(42);
+    draw(&mut second);
+    assert_eq!(second, first);
+    assert_ne!(draw(&mut second), values);
+    assert!(f.invoke(&[Int(1)]).is_err());
+}
+
+#[test]
+fn date_trunc_rounds_down_and_keeps_the_input_type() {
+    let f = functions::lookup("date_trunc").unwrap();
+    let noon = Timestamp(types::temporal::parse_timestamp("2024-08-14 12:30:00").unwrap());
+    assert_eq!(
+        f.invoke(&[Text("month".into()), noon]).unwrap(),
+        Timestamp(types::temporal::parse_timestamp("2024-08-01").unwrap())
+    );
+    assert_eq!(
+        f.invoke(&[Text("year".into()), Date(19949)]).unwrap(),
+        Date(19723)
+    );
+    assert_eq!(f.invoke(&[Text("day".into()), Null]).unwrap(), Null);
+
+    let err = f
+        .invoke(&[Text("fortnight".into()), Date(0)])
+        .expect_err("unknown unit");
+    assert!(format!("{err:?}").contains("DATE_TRUNC unit"), "{err:?}");
+    assert!(f.invoke(&[Text("day".into()), Int(0)]).is_err());
+}
+
+#[test]
+fn now_returns_a_timestamp() {
+    let f = functions::lookup("NOW").unwrap();
+    assert!(matches!(f.invoke(&[]).unwrap(), Timestamp(t) if t > 0));
+    assert!(f.invoke(&[Int(1)]).is_err());
+}
+
+#[test]
+fn eval_cast() {
+    let row = Row::new(vec![Text("2024-05-01".into())]);
+    let schema = schema(&["day"]);
+    let ctx = EvalContext { schema: &schema };
+
+    let cast = Expr::Cast {
+        expr: Box::new(col("day")),
+        ty: SqlType::Date,
+    };
+    assert_eq!(ctx.eval(&cast, &row).unwrap(), Date(19844));
+    assert_eq!(cast.to_string(), "CAST(day AS DATE)");
+
+    let cast = Expr::Cast {
+        expr: Box::new(text("12x")),
+        ty: SqlType::Int,
+    };
+    assert!(ctx.eval(&cast, &row).is_err());
+}
+
+fn dec(s: &str) -> Value {
+    Decimal(types::Decimal::parse(s).unwrap())
+}
+
+#[test]
+fn arithmetic_keeps_ints_and_decimals_exact() {
+    let row = Row::new(vec![dec("19.99"), Int(3), Float(0.5)]);
+    let schema = schema(&["price", "qty", "rate"]);
+    let ctx = EvalContext { schema: &schema };
+    let binary = |left: Expr, op, right: Expr| Expr::Binary {
+        left: Box::new(left),
+        op,
+        right: Box::new(right),
+    };
+
+    let cases = [
+        (
+            binary(col("qty"), BinaryOp::Add, Expr::Literal(Int(4))),
+            Int(7),
+        ),
+        (
+            binary(Expr::Literal(Int(7)), BinaryOp::Div, col("qty")),
+            Int(2),
+        ),
+        (
+            binary(col("price"), BinaryOp::Mul, col("qty")),
+            dec("59.97"),
+        ),
+        (
+            binary(col("price"), BinaryOp::Sub, Expr::Literal(dec("0.99"))),
+            dec("19.00"),
+        ),
+        (
+            binary(col("price"), BinaryOp::Mul, col("rate")),
+            Float(9.995),
+        ),
+        (binary(col("qty"), BinaryOp::Sub, col("rate")), Float(2.5)),
+        (binary(col("qty"), BinaryOp::Add, Expr::Literal(Null)), Null),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(ctx.eval(&expr, &row).unwrap(), expected, "{expr}");
+    }
+    assert_eq!(
+        binary(col("price"), BinaryOp::Mul, col("qty")).to_string(),
+        "price * qty"
+    );
+}
+
+#[test]
+fn arithmetic_errors() {
+    let apply = arithmetic::apply;
+    let err = apply(&Int(1), BinaryOp::Div, &Int(0)).unwrap_err();
+    assert!(format!("{err:?}").contains("division by zero"), "{err:?}");
+    assert!(apply(&dec("1"), BinaryOp::Div, &dec("0.00")).is_err());
+    assert!(apply(&Float(1.0), BinaryOp::Div, &Int(0)).is_err());
+    assert!(apply(&Int(i64::MAX), BinaryOp::Add, &Int(1)).is_err());
+    assert!(apply(&Text("1".into()), BinaryOp::Add, &Int(1)).is_err());
+    assert_eq!(
+        apply(&dec("10.00"), BinaryOp::Div, &Int(4)).unwrap(),
+        dec("2.5")
+    );
+}
+
+#[test]
+fn display_renders_function_calls() {
+    let expr = call("concat", vec![qual_col("u", "name"), text("!")]);
+    assert_eq!(expr.to_string(), "concat(u.name, '!')");
+}
+
+/// The value of `func` over `args`, each `None` standing for a row counted
+/// by `COUNT(*)`.
+fn aggregate(func: aggregate::AggregateFunc, args: &[Option<Value>]) -> DbResult<Value> {
+    let mut acc = aggregate::Accumulator::new(func);
+    for arg in args {
+        acc.update(arg.as_ref())?;
+    }
+    acc.finish()
+}
+
+#[test]
+fn aggregates_skip_nulls() {
+    use aggregate::AggregateFunc::*;
+    let args = [Some(Int(4)), Some(Null), Some(Int(1)), Some(Int(7))];
+    assert_eq!(aggregate(Count, &args).unwrap(), Int(3));
+    assert_eq!(aggregate(Sum, &args).unwrap(), Int(12));
+    assert_eq!(aggregate(Avg, &args).unwrap(), Float(4.0));
+    assert_eq!(aggregate(Min, &args).unwrap(), Int(1));
+    assert_eq!(aggregate(Max, &args).unwrap(), Int(7));
+    // COUNT(*) counts rows, not values
+    assert_eq!(aggregate(Count, &[None, None, None]).unwrap(), Int(3));
+}
+
+#[test]
+fn aggregates_of_only_nulls() {
+    use aggregate::AggregateFunc::*;
+    for args in [&[][..], &[Some(Null), Some(Null)][..]] {
+        assert_eq!(aggregate(Count, args).unwrap(), Int(0));
+        for func in [Sum, Avg, Min, Max] {
+            assert_eq!(aggregate(func, args).unwrap(), Null, "{func}");
+        }
+    }
+}
+
+#[test]
+fn aggregate_types_and_errors() {
+    use aggregate::AggregateFunc::*;
+    assert_eq!(
+        aggregate(Avg, &[Some(dec("1.50")), Some(dec("2.00"))]).unwrap(),
+        dec("1.75")
+    );
+    assert_eq!(
+        aggregate(Sum, &[Some(Int(1)), Some(Float(0.5))]).unwrap(),
+        Float(1.5)
+    );
+    assert_eq!(
+        aggregate(Max, &[Some(Text("b".into())), Some(Text("a".into()))]).unwrap(),
+        Text("b".into())
+    );
+    assert!(aggregate(Sum, &[Some(Text("a".into()))]).is_err());
+    assert!(aggregate(Sum, &[Some(Int(i64::MAX)), Some(Int(1))]).is_err());
+    assert!(aggregate(Min, &[Some(Int(1)), Some(Text("a".into()))]).is_err());
+}
+
+#[test]
+fn display_renders_aggregate_calls() {
+    let count = Expr::Aggregate {
+        func: aggregate::AggregateFunc::Count,
+        arg: None,
+    };
+    let sum = Expr::Aggregate {
+        func: aggregate::AggregateFunc::Sum,
+        arg: Some(Box::new(qual_col("o", "total"))),
+    };
+    assert_eq!(count.to_string(), "count(*)");
+    assert_eq!(sum.to_string(), "sum(o.total)");
+    let schema = schema(&["total"]);
+    let ctx = EvalContext { schema: &schema };
+    assert!(ctx.eval(&count, &Row::new(vec![Int(1)])).is_err());
+}
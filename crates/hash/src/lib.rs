@@ -2,8 +2,14 @@
 //!
 //! Provides O(1) average-case lookups for equality predicates.
 //! Only supports exact key matches (no range queries).
+//!
+//! The index of an encrypted database (see [`HashIndex::open_with_key`])
+//! seals every page with the database key, bound to the index and page, and
+//! stores it as `ENCRYPTED_MAGIC || sealed length || sealed page` the way
+//! B+Tree nodes are.
 
 use catalog::IndexId;
+use common::crypto::{EncryptionKey, SEAL_OVERHEAD};
 use common::{DbError, DbResult, PageId, RecordId};
 use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
@@ -22,6 +28,13 @@ const NUM_BUCKETS: usize = 256;
 /// Maximum entries per bucket before using overflow.
 const MAX_BUCKET_ENTRIES: usize = 40;
 
+/// Marks the start of an encrypted page. Read as a header or bucket it
+/// would claim an impossible length.
+const ENCRYPTED_MAGIC: [u8; 4] = [0xFF, 0xFF, b'E', b'N'];
+
+/// Bytes of an encrypted page before the sealed contents: magic and length.
+const ENCRYPTED_HEADER: usize = ENCRYPTED_MAGIC.len() + 4;
+
 /// Hash index using static hashing with overflow chains.
 ///
 /// Layout:
@@ -35,6 +48,8 @@ pub struct HashIndex {
     file: File,
     /// Total number of pages allocated.
     num_pages: u64,
+    /// Key sealing every page, if the database is encrypted
+    key: Option<EncryptionKey>,
 }
 
 /// A bucket page containing key-value entries.
@@ -56,6 +71,16 @@ struct HashHeader {
 impl HashIndex {
     /// Create a new hash index file.
     pub fn create(path: &Path, index_id: IndexId) -> DbResult<Self> {
+        Self::create_with_key(path, index_id, None)
+    }
+
+    /// Create a new hash index file whose pages are encrypted with `key`, if
+    /// given.
+    pub fn create_with_key(
+        path: &Path,
+        index_id: IndexId,
+        key: Option<&EncryptionKey>,
+    ) -> DbResult<Self> {
         let file = OpenOptions::new()
             .read(true)
             .write(true)
@@ -71,6 +96,7 @@ impl HashIndex {
             index_id,
             file,
             num_pages,
+            key: key.cloned(),
         };
 
         // Write header
@@ -87,28 +113,38 @@ impl HashIndex {
 
     /// Open an existing hash index file.
     pub fn open(path: &Path, index_id: IndexId) -> DbResult<Self> {
-        let mut file = OpenOptions::new()
+        Self::open_with_key(path, index_id, None)
+    }
+
+    /// Open an existing hash index file of a database encrypted with `key`,
+    /// if given. Fails if the file's encryption does not match `key`.
+    pub fn open_with_key(
+        path: &Path,
+        index_id: IndexId,
+        key: Option<&EncryptionKey>,
+    ) -> DbResult<Self> {
+        let file = OpenOptions::new()
             .read(true)
             .write(true)
             .open(path)
             .map_err(|e| DbError::Storage(format!("failed to open hash index: {}", e)))?;
 
-        // Read header from page 0
-        let mut buf = vec![0u8; PAGE_SIZE];
-        file.seek(SeekFrom::Start(0))
-            .map_err(|e| DbError::Storage(format!("seek error: {}", e)))?;
-        file.read_exact(&mut buf)
-            .map_err(|e| DbError::Storage(format!("read error: {}", e)))?;
-
-        let header: HashHeader = bincode::serde::decode_from_slice(&buf, bincode::config::legacy())
-            .map_err(|e| DbError::Storage(format!("failed to decode header: {}", e)))?
-            .0;
-
-        Ok(Self {
+        let mut index = Self {
             index_id,
             file,
-            num_pages: header.num_pages,
-        })
+            num_pages: 0,
+            key: key.cloned(),
+        };
+        // Read header from page 0
+        let header: HashHeader = bincode::serde::decode_from_slice(
+            &index.read_page(PageId(0))?,
+            bincode::config::legacy(),
+        )
+        .map_err(|e| DbError::Storage(format!("failed to decode header: {}", e)))?
+        .0;
+        index.num_pages = header.num_pages;
+
+        Ok(index)
     }
 
     /// Search for all RecordIds matching the given key.
@@ -207,7 +243,7 @@ impl HashIndex {
         self.write_header()?;
         self.file
             .sync_all()
-            .map_err(|e| DbError::Storage(format!("sync error: {}", e)))?;
+            .map_err(|e| DbError::from_write(e, DbError::Storage, "sync error"))?;
         Ok(())
     }
 
@@ -219,16 +255,7 @@ impl HashIndex {
 
     /// Read a bucket from disk.
     fn read_bucket(&mut self, page_id: PageId) -> DbResult<HashBucket> {
-        let offset = page_id.0 * PAGE_SIZE as u64;
-        self.file
-            .seek(SeekFrom::Start(offset))
-            .map_err(|e| DbError::Storage(format!("seek error: {}", e)))?;
-
-        let mut buf = vec![0u8; PAGE_SIZE];
-        self.file
-            .read_exact(&mut buf)
-            .map_err(|e| DbError::Storage(format!("read error: {}", e)))?;
-
+        let buf = self.read_page(page_id)?;
         let bucket: HashBucket = bincode::serde::decode_from_slice(&buf, bincode::config::legacy())
             .map_err(|e| DbError::Storage(format!("failed to decode bucket: {}", e)))?
             .0;
@@ -238,22 +265,9 @@ impl HashIndex {
 
     /// Write a bucket to disk.
     fn write_bucket(&mut self, page_id: PageId, bucket: &HashBucket) -> DbResult<()> {
-        let offset = page_id.0 * PAGE_SIZE as u64;
-        self.file
-            .seek(SeekFrom::Start(offset))
-            .map_err(|e| DbError::Storage(format!("seek error: {}", e)))?;
-
         let encoded = bincode::serde::encode_to_vec(bucket, bincode::config::legacy())
             .map_err(|e| DbError::Storage(format!("failed to encode bucket: {}", e)))?;
-
-        let mut buf = vec![0u8; PAGE_SIZE];
-        buf[..encoded.len()].copy_from_slice(&encoded);
-
-        self.file
-            .write_all(&buf)
-            .map_err(|e| DbError::Storage(format!("write error: {}", e)))?;
-
-        Ok(())
+        self.write_page(page_id, &encoded)
     }
 
     /// Write header to page 0.
@@ -264,16 +278,88 @@ impl HashIndex {
 
         let encoded = bincode::serde::encode_to_vec(&header, bincode::config::legacy())
             .map_err(|e| DbError::Storage(format!("failed to encode header: {}", e)))?;
+        self.write_page(PageId(0), &encoded)
+    }
+
+    /// Associated data binding a sealed page to its index and page.
+    fn aad(&self, page_id: PageId) -> [u8; 16] {
+        let mut aad = [0u8; 16];
+        aad[..8].copy_from_slice(&self.index_id.0.to_le_bytes());
+        aad[8..].copy_from_slice(&page_id.0.to_le_bytes());
+        aad
+    }
+
+    /// Read the contents of a page, opening it if the index is encrypted.
+    fn read_page(&mut self, page_id: PageId) -> DbResult<Vec<u8>> {
+        let offset = page_id.0 * PAGE_SIZE as u64;
+        self.file
+            .seek(SeekFrom::Start(offset))
+            .map_err(|e| DbError::Storage(format!("seek error: {}", e)))?;
 
         let mut buf = vec![0u8; PAGE_SIZE];
-        buf[..encoded.len()].copy_from_slice(&encoded);
+        self.file
+            .read_exact(&mut buf)
+            .map_err(|e| DbError::Storage(format!("read error: {}", e)))?;
 
+        let encrypted = buf.starts_with(&ENCRYPTED_MAGIC);
+        match &self.key {
+            Some(key) if encrypted => {
+                let len = u32::from_le_bytes(
+                    buf[ENCRYPTED_MAGIC.len()..ENCRYPTED_HEADER]
+                        .try_into()
+                        .expect("four bytes"),
+                ) as usize;
+                let Some(sealed) = buf.get(ENCRYPTED_HEADER..ENCRYPTED_HEADER + len) else {
+                    return Err(DbError::Corruption(format!(
+                        "encrypted hash page {} claims {len} bytes, more than its page holds",
+                        page_id.0
+                    )));
+                };
+                key.open(&self.aad(page_id), sealed)
+            }
+            Some(_) => Err(DbError::Storage(
+                "hash index is not encrypted but an encryption key was given".into(),
+            )),
+            None if encrypted => Err(DbError::Storage(
+                "hash index is encrypted but no encryption key was given".into(),
+            )),
+            None => Ok(buf),
+        }
+    }
+
+    /// Write `contents` to a page, sealing it if the index is encrypted.
+    fn write_page(&mut self, page_id: PageId, contents: &[u8]) -> DbResult<()> {
+        let capacity = match self.key {
+            Some(_) => PAGE_SIZE - ENCRYPTED_HEADER - SEAL_OVERHEAD,
+            None => PAGE_SIZE,
+        };
+        if contents.len() > capacity {
+            return Err(DbError::Storage(format!(
+                "hash page too large: {} bytes (max {})",
+                contents.len(),
+                capacity
+            )));
+        }
+
+        let mut buf = vec![0u8; PAGE_SIZE];
+        match &self.key {
+            Some(key) => {
+                let sealed = key.seal(&self.aad(page_id), contents)?;
+                buf[..ENCRYPTED_MAGIC.len()].copy_from_slice(&ENCRYPTED_MAGIC);
+                buf[ENCRYPTED_MAGIC.len()..ENCRYPTED_HEADER]
+                    .copy_from_slice(&(sealed.len() as u32).to_le_bytes());
+                buf[ENCRYPTED_HEADER..ENCRYPTED_HEADER + sealed.len()].copy_from_slice(&sealed);
+            }
+            None => buf[..contents.len()].copy_from_slice(contents),
+        }
+
+        let offset = page_id.0 * PAGE_SIZE as u64;
         self.file
-            .seek(SeekFrom::Start(0))
+            .seek(SeekFrom::Start(offset))
             .map_err(|e| DbError::Storage(format!("seek error: {}", e)))?;
         self.file
             .write_all(&buf)
-            .map_err(|e| DbError::Storage(format!("write error: {}", e)))?;
+            .map_err(|e| DbError::from_write(e, DbError::Storage, "write error"))?;
 
         Ok(())
     }
@@ -299,6 +385,28 @@ pub fn hash_key(key: &[Value]) -> u64 {
             Value::Null => {
                 3u8.hash(&mut hasher);
             }
+            Value::Float(_) => {
+                4u8.hash(&mut hasher);
+                // Value's Hash agrees with its Eq: -0.0 and 0.0 hash alike
+                val.hash(&mut hasher);
+            }
+            Value::Date(d) => {
+                5u8.hash(&mut hasher);
+                d.hash(&mut hasher);
+            }
+            Value::Timestamp(t) => {
+                6u8.hash(&mut hasher);
+                t.hash(&mut hasher);
+            }
+            Value::Decimal(d) => {
+                7u8.hash(&mut hasher);
+                // 1.5 and 1.50 hash alike
+                d.hash(&mut hasher);
+            }
+            Value::Bytes(b) => {
+                8u8.hash(&mut hasher);
+                b.hash(&mut hasher);
+            }
         }
     }
     hasher.finish()
@@ -463,6 +571,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn encrypted_pages_round_trip_under_their_key() {
+        use common::crypto::{self, EncryptionKey};
+
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("test.idx");
+        let key = EncryptionKey::from_bytes(&[3u8; crypto::KEY_LEN]).unwrap();
+        let secret = vec![Value::Text("plaintext-secret".into())];
+        let rid = RecordId {
+            page_id: PageId(1),
+            slot: 2,
+        };
+
+        {
+            let mut index = HashIndex::create_with_key(&path, IndexId(1), Some(&key)).unwrap();
+            // Enough entries in one chain to spill into overflow pages
+            for _ in 0..MAX_BUCKET_ENTRIES * 2 {
+                index.insert(secret.clone(), rid).unwrap();
+            }
+            index.flush().unwrap();
+        }
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(!bytes
+            .windows(b"plaintext-secret".len())
+            .any(|w| w == b"plaintext-secret"));
+
+        let mut index = HashIndex::open_with_key(&path, IndexId(1), Some(&key)).unwrap();
+        assert_eq!(index.search(&secret).unwrap().len(), MAX_BUCKET_ENTRIES * 2);
+        assert!(HashIndex::open(&path, IndexId(1)).is_err());
+        // Pages are bound to their index
+        assert!(HashIndex::open_with_key(&path, IndexId(2), Some(&key)).is_err());
+    }
+
     #[test]
     fn overflow_bucket_handling() {
         let (mut index, _temp) = temp_index();
@@ -557,6 +698,54 @@ mod tests {
         assert_ne!(h1, h3);
     }
 
+    #[test]
+    fn hash_key_floats() {
+        // Equal floats hash alike; an equal int is a different key
+        assert_eq!(
+            hash_key(&[Value::Float(0.0)]),
+            hash_key(&[Value::Float(-0.0)])
+        );
+        assert_ne!(hash_key(&[Value::Float(1.0)]), hash_key(&[Value::Int(1)]));
+        assert_ne!(
+            hash_key(&[Value::Float(1.0)]),
+            hash_key(&[Value::Float(1.5)])
+        );
+    }
+
+    #[test]
+    fn hash_key_temporals() {
+        // A date is a different key from the same number of days as an int
+        assert_ne!(hash_key(&[Value::Date(1)]), hash_key(&[Value::Int(1)]));
+        assert_ne!(
+            hash_key(&[Value::Date(1)]),
+            hash_key(&[Value::Timestamp(1)])
+        );
+        assert_eq!(
+            hash_key(&[Value::Timestamp(86_400_000_000)]),
+            hash_key(&[Value::Timestamp(86_400_000_000)])
+        );
+    }
+
+    #[test]
+    fn hash_key_bytes_differ_from_text() {
+        assert_ne!(
+            hash_key(&[Value::Bytes(b"ab".to_vec())]),
+            hash_key(&[Value::Text("ab".into())])
+        );
+        assert_eq!(
+            hash_key(&[Value::Bytes(vec![0, 1])]),
+            hash_key(&[Value::Bytes(vec![0, 1])])
+        );
+    }
+
+    #[test]
+    fn hash_key_decimals_ignore_scale() {
+        let dec = |units, scale| Value::Decimal(types::Decimal::new(units, scale));
+        assert_eq!(hash_key(&[dec(15, 1)]), hash_key(&[dec(150, 2)]));
+        assert_ne!(hash_key(&[dec(15, 1)]), hash_key(&[dec(16, 1)]));
+        assert_ne!(hash_key(&[dec(1, 0)]), hash_key(&[Value::Int(1)]));
+    }
+
     #[test]
     fn hash_key_composite_order_matters() {
         let h1 = hash_key(&[Value::Int(1), Value::Int(2)]);
@@ -1,4 +1,7 @@
+use common::compression::Compression;
+use common::Priority;
 use expr::Expr;
+use types::Value;
 
 /// Index type (algorithm) for CREATE INDEX.
 #[derive(Clone, Debug, PartialEq, Default)]
@@ -8,6 +11,56 @@ pub enum IndexType {
     Hash,
 }
 
+/// Row lock requested by `SELECT ... FOR SHARE` / `FOR UPDATE`.
+///
+/// Locking reads are routed to the leader. Inside a transaction `FOR UPDATE`
+/// claims the tables the query reads as if the transaction wrote them, so a
+/// concurrent transaction that locks or writes one of them fails when it
+/// commits second. `FOR SHARE` claims them shared: it conflicts only with
+/// transactions that write them or lock them `FOR UPDATE`, so concurrent
+/// `FOR SHARE` readers all commit. Conflicts are checked per table. Outside
+/// a transaction the lock lasts only as long as the statement, and
+/// statements already execute one at a time against local storage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowLock {
+    Share,
+    Update,
+}
+
+/// How `TABLESAMPLE` picks the rows of a table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleMethod {
+    /// `BERNOULLI`: each row is kept with the sample's probability.
+    Bernoulli,
+    /// `SYSTEM`: each page is kept with the sample's probability, with all
+    /// of its rows, and the pages left out are not read.
+    System,
+}
+
+/// `TABLESAMPLE <method> (<percent>) [REPEATABLE (<seed>)]` after a table
+/// in FROM.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TableSample {
+    pub method: SampleMethod,
+    /// Share of the rows or pages kept, from 0 to 100.
+    pub percent: f64,
+    /// Seed that makes every scan pick the same sample; `None` picks a new
+    /// one each time.
+    pub seed: Option<i64>,
+}
+
+/// Transaction isolation level selected with `SET TRANSACTION ISOLATION LEVEL`.
+///
+/// `READ UNCOMMITTED` is accepted and mapped to [`IsolationLevel::ReadCommitted`],
+/// since dirty reads are never exposed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IsolationLevel {
+    ReadCommitted,
+    RepeatableRead,
+    #[default]
+    Serializable,
+}
+
 /// Sort direction for ORDER BY clauses.
 #[derive(Clone, Debug, PartialEq)]
 pub enum SortDirection {
@@ -15,30 +68,100 @@ pub enum SortDirection {
     Desc,
 }
 
-/// ORDER BY expression specifying column and sort direction.
+/// Where NULLs go in an ORDER BY column.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
+impl NullsOrder {
+    /// Placement when the query doesn't say: NULLs compare lower than every
+    /// value, so they come first ascending and last descending.
+    pub fn default_for(direction: &SortDirection) -> Self {
+        match direction {
+            SortDirection::Asc => NullsOrder::First,
+            SortDirection::Desc => NullsOrder::Last,
+        }
+    }
+}
+
+/// ORDER BY item: the sort key, its direction and NULL placement.
+///
+/// The key is any expression over the query's columns; a bare name may also
+/// refer to a select-list alias.
 #[derive(Clone, Debug, PartialEq)]
 pub struct OrderByExpr {
-    pub column: String,
+    pub expr: Expr,
     pub direction: SortDirection,
+    pub nulls: NullsOrder,
+}
+
+/// How `PARTITION BY` assigns rows to partitions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PartitionMethod {
+    Range,
+    List,
+}
+
+/// The keys one partition holds.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PartitionValues {
+    /// `VALUES LESS THAN (v)`; `None` for `MAXVALUE`.
+    LessThan(Option<Value>),
+    /// `VALUES IN (v, ...)`.
+    In(Vec<Value>),
+}
+
+/// `PARTITION name VALUES ...` in a `PARTITION BY` clause.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PartitionDef {
+    pub name: String,
+    pub values: PartitionValues,
+}
+
+/// `PARTITION BY RANGE|LIST (column) (PARTITION ..., ...)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PartitionBy {
+    pub method: PartitionMethod,
+    pub column: String,
+    pub partitions: Vec<PartitionDef>,
 }
 
 /// Join type for multi-table queries.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum JoinType {
     Inner,
+    /// Left rows with a matching right row, each once: `EXISTS (...)` or
+    /// `x IN (SELECT ...)` in `WHERE`
+    Semi,
+    /// Left rows without a matching right row: `NOT EXISTS (...)` in `WHERE`
+    Anti,
 }
 
 /// Table reference with optional alias.
 ///
 /// Examples:
-/// - `TableRef { name: "users", alias: None }` - `users`
-/// - `TableRef { name: "users", alias: Some("u") }` - `users u` or `users AS u`
+/// - `TableRef { name: "users", alias: None, .. }` - `users`
+/// - `TableRef { name: "users", alias: Some("u"), .. }` - `users u` or `users AS u`
+/// - `TableRef { name: "generate_series", args: Some(..), .. }` -
+///   `generate_series(1, 10)`
+/// - `TableRef { name: "users", sample: Some(..), .. }` -
+///   `users TABLESAMPLE BERNOULLI (1)`
+/// - `TableRef { values: Some(..), .. }` - `VALUES (1, 'a'), (2, 'b')`, or
+///   the single empty row a `SELECT` without `FROM` reads
 #[derive(Clone, Debug, PartialEq)]
 pub struct TableRef {
-    /// Table name.
+    /// Table name, or the name of the table function called.
     pub name: String,
     /// Optional alias (e.g., `u` in `users u`).
     pub alias: Option<String>,
+    /// Arguments of a table function call; `None` for a table or view.
+    pub args: Option<Vec<Expr>>,
+    /// `TABLESAMPLE` clause, which only a table can have.
+    pub sample: Option<TableSample>,
+    /// Rows of a `VALUES` list; `None` for a table, view or table function.
+    pub values: Option<Vec<Vec<Expr>>>,
 }
 
 impl TableRef {
@@ -47,6 +170,29 @@ impl TableRef {
     pub fn effective_name(&self) -> &str {
         self.alias.as_deref().unwrap_or(&self.name)
     }
+
+    /// The table or view read, or `None` for a table function call or a
+    /// `VALUES` list.
+    pub fn table(&self) -> Option<&str> {
+        (self.args.is_none() && self.values.is_none()).then_some(self.name.as_str())
+    }
+
+    /// The rows of a `VALUES` list, whose columns are named `column1`,
+    /// `column2` and so on.
+    pub fn values(rows: Vec<Vec<Expr>>) -> Self {
+        Self {
+            name: "values".to_string(),
+            alias: None,
+            args: None,
+            sample: None,
+            values: Some(rows),
+        }
+    }
+
+    /// What a `SELECT` without `FROM` reads: one row with no columns.
+    pub fn single_row() -> Self {
+        Self::values(vec![Vec::new()])
+    }
 }
 
 /// Join clause for multi-table queries.
@@ -60,6 +206,9 @@ pub struct JoinClause {
     pub table: TableRef,
     /// Join condition (ON clause).
     pub condition: Expr,
+    /// For `x IN (SELECT y ...)`, `x` from the outer query and `y` from the
+    /// subquery, which match when equal.
+    pub key: Option<(Expr, Expr)>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -68,22 +217,69 @@ pub enum Statement {
         name: String,
         columns: Vec<ColumnDef>,
         primary_key: Option<Vec<String>>,
+        /// `WITH (row_version = true)`: maintain an implicit `_version` column.
+        row_version: bool,
+        /// `WITH (audit = true)`: record every statement touching the table
+        /// in the audit log.
+        audit: bool,
+        /// `WITH (fillfactor = <percent>)`: fill heap pages only up to this
+        /// percentage on insert, leaving the rest for updates.
+        fillfactor: Option<u8>,
+        /// `WITH (compression = 'lz4' | 'zstd')`: compress the table's heap
+        /// and B+Tree index pages as they are written.
+        compression: Compression,
+        /// `ENGINE = <name>` or `USING <name>`: storage engine for the
+        /// table's rows.
+        engine: Option<String>,
+        /// `PARTITION BY ...`: split the table's rows across partitions.
+        partition_by: Option<PartitionBy>,
+        /// `CREATE TEMPORARY TABLE`: keep the table only until the database
+        /// is closed.
+        temporary: bool,
+    },
+    /// `CREATE [TEMPORARY] TABLE <name> AS <select>`: create a table with
+    /// the query's columns, holding its rows.
+    CreateTableAs {
+        name: String,
+        temporary: bool,
+        query: Box<Statement>,
     },
     DropTable {
         name: String,
     },
+    AlterTable {
+        name: String,
+        action: AlterTableAction,
+    },
     CreateIndex {
         name: String,
         table: String,
         column: String,
         index_type: IndexType,
+        unique: bool,
     },
     DropIndex {
         name: String,
     },
+    /// `CREATE VIEW <name> AS <select>`.
+    CreateView {
+        name: String,
+        /// The view's SELECT as SQL text, which is stored in the catalog and
+        /// planned wherever the view is referenced.
+        query: String,
+    },
+    DropView {
+        name: String,
+    },
     Insert {
         table: String,
-        values: Vec<Expr>,
+        /// Explicit target column list; empty means every column in table order.
+        columns: Vec<String>,
+        /// One entry per VALUES row.
+        rows: Vec<Vec<Expr>>,
+        /// `INSERT ... SELECT`: the query whose rows are inserted, in place
+        /// of `rows`, which is then empty.
+        query: Option<Box<Statement>>,
     },
     Select {
         columns: Vec<SelectItem>,
@@ -92,33 +288,177 @@ pub enum Statement {
         /// JOIN clauses (may be empty for single-table queries).
         joins: Vec<JoinClause>,
         selection: Option<Expr>,
+        /// `GROUP BY` expressions; empty if the query has none.
+        group_by: Vec<Expr>,
+        /// `HAVING` condition over each group.
+        having: Option<Box<Expr>>,
         order_by: Vec<OrderByExpr>,
         limit: Option<u64>,
         offset: Option<u64>,
+        /// `FOR SHARE` / `FOR UPDATE` clause, if any.
+        lock: Option<RowLock>,
     },
     Update {
         table: String,
+        /// Alias of the updated table (e.g., `a` in `UPDATE accounts a`).
+        alias: Option<String>,
         assignments: Vec<(String, Expr)>,
+        /// `FROM` tables whose rows the updated rows are matched against.
+        from: Vec<TableRef>,
         selection: Option<Expr>,
     },
     Delete {
         table: String,
+        /// Alias of the table rows are deleted from.
+        alias: Option<String>,
+        /// `USING` tables whose rows the deleted rows are matched against.
+        using: Vec<TableRef>,
         selection: Option<Expr>,
     },
     Explain {
         query: Box<Statement>,
         analyze: bool,
     },
+    /// `PROFILE <query>`: run the query and report per-operator timings.
+    Profile {
+        query: Box<Statement>,
+    },
+    SetTransaction {
+        isolation: IsolationLevel,
+        /// `SET SESSION CHARACTERISTICS AS TRANSACTION ...` rather than
+        /// `SET TRANSACTION ...`.
+        session: bool,
+    },
+    /// `SET random_seed = <int>` fixes the sequence `RANDOM()` draws from for
+    /// the session; `SET random_seed = DEFAULT` makes it unpredictable again.
+    SetRandomSeed {
+        seed: Option<i64>,
+    },
+    /// `SET statement_priority = high | normal | background` sets how urgently
+    /// the session's statements run; `DEFAULT` resets it to normal.
+    SetPriority {
+        priority: Priority,
+    },
+    /// `SET GLOBAL <name> = <value>` changes a setting of the running node
+    /// for every session; `DEFAULT` restores the value it started with.
+    /// The name is lowercase and the value as written, without quotes.
+    SetGlobal {
+        name: String,
+        value: Option<String>,
+    },
+    /// `ANALYZE TABLE <table>`: recompute the table's planner statistics.
+    Analyze {
+        table: String,
+    },
+    /// `ADMIN GC`: clean up data directory files that no table refers to.
+    AdminGc,
+    /// `VACUUM [<table>]`: compact the table's storage, or every table's,
+    /// reclaiming the space of deleted rows.
+    Vacuum {
+        table: Option<String>,
+    },
+    /// `SHOW TABLE STATS <table>`: list the statistics the last `ANALYZE` of
+    /// the table gathered.
+    ShowTableStats {
+        table: String,
+    },
+    /// `COPY (<select>) TO '<path>' [FORMAT CSV|JSON]`: write a query's rows
+    /// to a new file in the export directory. `COPY <table> TO ...` is
+    /// accepted as `SELECT * FROM <table>`.
+    CopyTo {
+        query: Box<Statement>,
+        path: String,
+        format: CopyFormat,
+    },
+}
+
+impl Statement {
+    /// Names of the tables the statement reads or writes, in order of
+    /// appearance. `DROP INDEX` does not name its table, so it has none,
+    /// and neither does a `VACUUM` of every table.
+    pub fn tables(&self) -> Vec<&str> {
+        match self {
+            Statement::CreateTable { name, .. }
+            | Statement::DropTable { name }
+            | Statement::CreateView { name, .. }
+            | Statement::DropView { name }
+            | Statement::AlterTable { name, .. } => vec![name],
+            Statement::Analyze { table } | Statement::ShowTableStats { table } => vec![table],
+            Statement::Vacuum { table } => table.iter().map(String::as_str).collect(),
+            Statement::CreateIndex { table, .. } => vec![table],
+            Statement::Insert { table, query, .. } => std::iter::once(table.as_str())
+                .chain(query.iter().flat_map(|query| query.tables()))
+                .collect(),
+            Statement::Update {
+                table,
+                from: others,
+                ..
+            }
+            | Statement::Delete {
+                table,
+                using: others,
+                ..
+            } => std::iter::once(table.as_str())
+                .chain(others.iter().filter_map(TableRef::table))
+                .collect(),
+            Statement::Select { from, joins, .. } => std::iter::once(from)
+                .chain(joins.iter().map(|join| &join.table))
+                .filter_map(TableRef::table)
+                .collect(),
+            Statement::CreateTableAs { name, query, .. } => std::iter::once(name.as_str())
+                .chain(query.tables())
+                .collect(),
+            Statement::Explain { query, .. }
+            | Statement::Profile { query }
+            | Statement::CopyTo { query, .. } => query.tables(),
+            Statement::DropIndex { .. }
+            | Statement::SetTransaction { .. }
+            | Statement::SetRandomSeed { .. }
+            | Statement::SetPriority { .. }
+            | Statement::SetGlobal { .. }
+            | Statement::AdminGc => Vec::new(),
+        }
+    }
+}
+
+/// File format written by `COPY ... TO`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CopyFormat {
+    /// Comma-separated values with a header line of column names.
+    #[default]
+    Csv,
+    /// A JSON array with one object per row, keyed by column name.
+    Json,
+}
+
+/// The change made by an `ALTER TABLE` statement.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AlterTableAction {
+    AddColumn(ColumnDef),
+    DropColumn { name: String },
+    RenameColumn { old: String, new: String },
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct ColumnDef {
     pub name: String,
     pub ty: String,
+    /// `DEFAULT <expr>` clause, if any.
+    pub default: Option<Expr>,
+    /// `NOT NULL` was specified.
+    pub not_null: bool,
+    /// Values are generated from a sequence: the column was declared
+    /// `SERIAL`, `AUTO_INCREMENT` or `AUTOINCREMENT`.
+    pub auto_increment: bool,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum SelectItem {
     Wildcard,
     Column(String),
+    /// Computed or aliased expression, e.g. `UPPER(name)` or `id AS user_id`.
+    Expr {
+        expr: Expr,
+        alias: Option<String>,
+    },
 }
@@ -4,15 +4,41 @@ mod tests;
 
 pub use ast::*;
 
-use common::{DbError, DbResult};
+use std::borrow::Cow;
+
+use common::compression::Compression;
+use common::{DbError, DbResult, Priority};
+use expr::aggregate::AggregateFunc;
 use expr::{BinaryOp, Expr, UnaryOp};
 use sqlparser::ast as sqlast;
 use sqlparser::dialect::GenericDialect;
-use sqlparser::parser::Parser as SqlParser;
-use types::Value;
+use sqlparser::keywords::{
+    Keyword, ALL_KEYWORDS, ALL_KEYWORDS_INDEX, RESERVED_FOR_COLUMN_ALIAS, RESERVED_FOR_TABLE_ALIAS,
+};
+use sqlparser::parser::{Parser as SqlParser, ParserError};
+use sqlparser::tokenizer::{Token, Tokenizer};
+use types::{binary, SqlType, Value};
 
 /// Parse SQL text into the internal AST statements.
 pub fn parse_sql(sql: &str) -> DbResult<Vec<Statement>> {
+    if let Some(stmt) = parse_admin(sql) {
+        return Ok(vec![stmt]);
+    }
+    if let Some(stmt) = parse_partitioned_create_table(sql)? {
+        return Ok(vec![stmt]);
+    }
+    if let Some(stmt) = parse_create_table_using(sql)? {
+        return Ok(vec![stmt]);
+    }
+    if let Some(stmt) = parse_copy_with_format(sql)? {
+        return Ok(vec![stmt]);
+    }
+    if let Some(stmt) = parse_profile(sql)? {
+        return Ok(vec![stmt]);
+    }
+    if let Some(stmts) = parse_table_samples(sql)? {
+        return Ok(stmts);
+    }
     let dialect = GenericDialect {};
     let stmts = SqlParser::parse_sql(&dialect, sql)
         .map_err(|e| DbError::Parser(format!("SQL parse error: {e}")))?;
@@ -20,66 +46,1090 @@ pub fn parse_sql(sql: &str) -> DbResult<Vec<Statement>> {
     stmts.into_iter().map(map_statement).collect()
 }
 
+/// Split SQL text into the text of each statement.
+///
+/// Statements end at semicolons outside string literals, quoted identifiers
+/// and comments. Statements that are empty or only comments are dropped.
+/// Text that does not tokenize is returned whole, for [`parse_sql`] to
+/// report the error.
+pub fn split_statements(sql: &str) -> Vec<&str> {
+    let dialect = GenericDialect {};
+    let Ok(tokens) = Tokenizer::new(&dialect, sql).tokenize_with_location() else {
+        return Some(sql.trim())
+            .filter(|s| !s.is_empty())
+            .into_iter()
+            .collect();
+    };
+
+    // Which statements have more than whitespace and comments
+    let mut has_content = vec![false];
+    for token in &tokens {
+        match token.token {
+            Token::SemiColon => has_content.push(false),
+            Token::Whitespace(_) => {}
+            _ => *has_content.last_mut().unwrap() = true,
+        }
+    }
+
+    // Tokens carry line and column, so walk the text to find the byte
+    // offset of each semicolon
+    let mut separators = tokens
+        .iter()
+        .filter(|token| token.token == Token::SemiColon)
+        .map(|token| (token.location.line, token.location.column))
+        .peekable();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let (mut line, mut column) = (1, 1);
+    for (offset, ch) in sql.char_indices() {
+        if separators.peek() == Some(&(line, column)) {
+            separators.next();
+            statements.push(&sql[start..offset]);
+            start = offset + 1;
+        }
+        if ch == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+    statements.push(&sql[start..]);
+
+    statements
+        .into_iter()
+        .zip(has_content)
+        .filter(|(_, has_content)| *has_content)
+        .map(|(statement, _)| statement.trim())
+        .collect()
+}
+
+/// Keywords that sqlparser reads as something other than a table or column
+/// name in some position, besides those it reserves from aliases: function
+/// names it expects arguments after, literals, and words it takes to start a
+/// clause or constraint.
+const RESERVED_KEYWORDS: &[Keyword] = &[
+    Keyword::ALL,
+    Keyword::ARRAY_AGG,
+    Keyword::CASE,
+    Keyword::CAST,
+    Keyword::CEIL,
+    Keyword::CHECK,
+    Keyword::CONCURRENTLY,
+    Keyword::CONSTRAINT,
+    Keyword::CONVERT,
+    Keyword::CURRENT_CATALOG,
+    Keyword::CURRENT_DATE,
+    Keyword::CURRENT_TIME,
+    Keyword::CURRENT_TIMESTAMP,
+    Keyword::CURRENT_USER,
+    Keyword::DIRECTORY,
+    Keyword::DISTINCT,
+    Keyword::EXISTS,
+    Keyword::EXTRACT,
+    Keyword::FALSE,
+    Keyword::FLOOR,
+    Keyword::FOREIGN,
+    Keyword::FULLTEXT,
+    Keyword::INDEX,
+    Keyword::INTERVAL,
+    Keyword::JSON_TABLE,
+    Keyword::KEY,
+    Keyword::LISTAGG,
+    Keyword::LOCAL,
+    Keyword::LOCALTIME,
+    Keyword::LOCALTIMESTAMP,
+    Keyword::MATCH,
+    Keyword::NOT,
+    Keyword::NULL,
+    Keyword::OVERLAY,
+    Keyword::PRIMARY,
+    Keyword::SAFE_CAST,
+    Keyword::SESSION_USER,
+    Keyword::SPATIAL,
+    Keyword::STRUCT,
+    Keyword::SUBSTRING,
+    Keyword::TABLE,
+    Keyword::TABLESAMPLE,
+    Keyword::TRIM,
+    Keyword::TRUE,
+    Keyword::TRY_CAST,
+    Keyword::UNIQUE,
+    Keyword::UNNEST,
+    Keyword::USER,
+];
+
+/// Write `name` as an identifier that [`parse_sql`] reads back as `name`, for
+/// SQL and plan descriptions generated from the catalog.
+///
+/// Names are stored in lowercase. One made of lowercase letters, digits and
+/// underscores that does not start with a digit is written as it is, unless
+/// it is a reserved keyword; any other name is put in double quotes, with
+/// double quotes in it doubled.
+pub fn quote_ident(name: &str) -> Cow<'_, str> {
+    let plain = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && !is_reserved(name);
+    match plain {
+        true => Cow::Borrowed(name),
+        false => Cow::Owned(format!("\"{}\"", name.replace('"', "\"\""))),
+    }
+}
+
+/// Whether `word` cannot be used as a bare table or column name.
+fn is_reserved(word: &str) -> bool {
+    let upper = word.to_ascii_uppercase();
+    ALL_KEYWORDS
+        .binary_search(&upper.as_str())
+        .is_ok_and(|position| {
+            let keyword = ALL_KEYWORDS_INDEX[position];
+            RESERVED_FOR_TABLE_ALIAS.contains(&keyword)
+                || RESERVED_FOR_COLUMN_ALIAS.contains(&keyword)
+                || RESERVED_KEYWORDS.contains(&keyword)
+        })
+}
+
+/// Recognize `ADMIN` commands, `SHOW TABLE STATS`, `VACUUM` and `SET GLOBAL`,
+/// which are not SQL or which sqlparser rejects.
+fn parse_admin(sql: &str) -> Option<Statement> {
+    let dialect = GenericDialect {};
+    let mut tokens = Tokenizer::new(&dialect, sql).tokenize().ok()?;
+    tokens.retain(|token| !matches!(token, Token::Whitespace(_)));
+    while matches!(tokens.last(), Some(Token::SemiColon)) {
+        tokens.pop();
+    }
+    match tokens.as_slice() {
+        [admin, gc] if is_word(admin, "ADMIN") && is_word(gc, "GC") => Some(Statement::AdminGc),
+        [show, table, stats, Token::Word(name)]
+            if is_word(show, "SHOW") && is_word(table, "TABLE") && is_word(stats, "STATS") =>
+        {
+            Some(Statement::ShowTableStats {
+                table: normalize_ident(&name.to_ident()),
+            })
+        }
+        [vacuum] if is_word(vacuum, "VACUUM") => Some(Statement::Vacuum { table: None }),
+        [vacuum, Token::Word(name)] if is_word(vacuum, "VACUUM") => Some(Statement::Vacuum {
+            table: Some(normalize_ident(&name.to_ident())),
+        }),
+        [set, global, Token::Word(name), to, value]
+            if is_word(set, "SET")
+                && is_word(global, "GLOBAL")
+                && (matches!(to, Token::Eq) || is_word(to, "TO")) =>
+        {
+            let value = match value {
+                word if is_word(word, "DEFAULT") => None,
+                Token::Word(word) => Some(word.value.clone()),
+                Token::Number(n, _) => Some(n.clone()),
+                Token::SingleQuotedString(s) => Some(s.clone()),
+                _ => return None,
+            };
+            Some(Statement::SetGlobal {
+                name: name.value.to_lowercase(),
+                value,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Parse `CREATE TABLE ... PARTITION BY RANGE|LIST (column) (...)`, or return
+/// `None` for any other SQL.
+///
+/// sqlparser has no syntax for declaring partitions, so the clause is cut off
+/// the statement's tokens and parsed here; the rest goes through sqlparser.
+fn parse_partitioned_create_table(sql: &str) -> DbResult<Option<Statement>> {
+    let dialect = GenericDialect {};
+    // Malformed SQL is reported by the regular parse
+    let Ok(tokens) = Tokenizer::new(&dialect, sql).tokenize() else {
+        return Ok(None);
+    };
+    let significant: Vec<(usize, &Token)> = tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| !matches!(token, Token::Whitespace(_)))
+        .collect();
+    let is_keyword = |token: &Token, keyword: Keyword| matches!(token, Token::Word(word) if word.keyword == keyword);
+    if !matches!(
+        significant.as_slice(),
+        [(_, create), (_, table), ..]
+            if is_keyword(create, Keyword::CREATE) && is_keyword(table, Keyword::TABLE)
+    ) {
+        return Ok(None);
+    }
+
+    // Find PARTITION BY outside the column list
+    let mut depth = 0usize;
+    let mut clause_start = None;
+    for (pair, &(position, token)) in significant.iter().enumerate() {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen => depth = depth.saturating_sub(1),
+            _ if depth == 0
+                && is_keyword(token, Keyword::PARTITION)
+                && significant
+                    .get(pair + 1)
+                    .is_some_and(|(_, next)| is_keyword(next, Keyword::BY)) =>
+            {
+                clause_start = Some(position);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let Some(clause_start) = clause_start else {
+        return Ok(None);
+    };
+
+    let syntax_error = |e: ParserError| DbError::Parser(format!("SQL parse error: {e}"));
+    let mut table_tokens = tokens;
+    let clause_tokens = table_tokens.split_off(clause_start);
+    let mut stmts = SqlParser::new(&dialect)
+        .with_tokens(table_tokens)
+        .parse_statements()
+        .map_err(syntax_error)?;
+    let (Some(stmt), None) = (stmts.pop(), stmts.pop()) else {
+        return Err(DbError::Parser(
+            "PARTITION BY must end a single CREATE TABLE statement".into(),
+        ));
+    };
+    let mut stmt = map_statement(stmt)?;
+
+    let clause = parse_partition_clause(&mut SqlParser::new(&dialect).with_tokens(clause_tokens))
+        .map_err(syntax_error)?;
+    let partitions = clause
+        .partitions
+        .into_iter()
+        .map(|(name, values)| {
+            let values = match values {
+                RawPartitionValues::LessThan(None) => PartitionValues::LessThan(None),
+                RawPartitionValues::LessThan(Some(bound)) => {
+                    PartitionValues::LessThan(Some(map_partition_value(bound)?))
+                }
+                RawPartitionValues::In(values) => PartitionValues::In(
+                    values
+                        .into_iter()
+                        .map(map_partition_value)
+                        .collect::<DbResult<_>>()?,
+                ),
+            };
+            Ok(PartitionDef { name, values })
+        })
+        .collect::<DbResult<_>>()?;
+    if let Statement::CreateTable { partition_by, .. } = &mut stmt {
+        *partition_by = Some(PartitionBy {
+            method: clause.method,
+            column: clause.column,
+            partitions,
+        });
+    }
+    Ok(Some(stmt))
+}
+
+/// Parse `CREATE TABLE ... USING <engine>`, or return `None` for any other
+/// SQL.
+///
+/// sqlparser only accepts the storage engine as `ENGINE = <name>`, after
+/// every other clause, so `USING <engine>` is cut out of the statement's
+/// tokens wherever it follows the column list and becomes its engine.
+fn parse_create_table_using(sql: &str) -> DbResult<Option<Statement>> {
+    let dialect = GenericDialect {};
+    // Malformed SQL is reported by the regular parse
+    let Ok(mut tokens) = Tokenizer::new(&dialect, sql).tokenize() else {
+        return Ok(None);
+    };
+    let significant: Vec<(usize, &Token)> = tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| !matches!(token, Token::Whitespace(_)))
+        .collect();
+    let is_keyword = |token: &Token, keyword: Keyword| matches!(token, Token::Word(word) if word.keyword == keyword);
+    if !matches!(
+        significant.as_slice(),
+        [(_, create), (_, table), ..]
+            if is_keyword(create, Keyword::CREATE) && is_keyword(table, Keyword::TABLE)
+    ) {
+        return Ok(None);
+    }
+
+    // Find USING <engine> after the column list
+    let mut depth = 0usize;
+    let mut seen_columns = false;
+    let mut clause = None;
+    for (pair, &(position, token)) in significant.iter().enumerate() {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen => {
+                depth = depth.saturating_sub(1);
+                seen_columns = true;
+            }
+            _ if depth == 0 && seen_columns && is_keyword(token, Keyword::USING) => {
+                match significant.get(pair + 1) {
+                    Some(&(name_position, Token::Word(name))) => {
+                        clause = Some((position, name_position, name.value.clone()));
+                        break;
+                    }
+                    _ => {
+                        return Err(DbError::Parser(
+                            "USING in CREATE TABLE takes a storage engine name".into(),
+                        ))
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    let Some((start, end, name)) = clause else {
+        return Ok(None);
+    };
+
+    tokens.drain(start..=end);
+    let mut stmts = SqlParser::new(&dialect)
+        .with_tokens(tokens)
+        .parse_statements()
+        .map_err(|e| DbError::Parser(format!("SQL parse error: {e}")))?;
+    let (Some(stmt), None) = (stmts.pop(), stmts.pop()) else {
+        return Err(DbError::Parser(
+            "USING <engine> must be part of a single CREATE TABLE statement".into(),
+        ));
+    };
+    let mut stmt = map_statement(stmt)?;
+    match &mut stmt {
+        Statement::CreateTable {
+            engine: engine @ None,
+            ..
+        } => *engine = Some(name),
+        Statement::CreateTable { .. } => {
+            return Err(DbError::Parser(
+                "a table has one storage engine; give either USING or ENGINE".into(),
+            ))
+        }
+        _ => {
+            return Err(DbError::Parser(
+                "USING <engine> is not supported in CREATE TABLE ... AS".into(),
+            ))
+        }
+    }
+    Ok(Some(stmt))
+}
+
+/// Parse `COPY ... TO '<path>' FORMAT <name>`, or return `None` for any
+/// other SQL.
+///
+/// sqlparser only accepts the format as `WITH (FORMAT <name>)`, so a trailing
+/// bare `FORMAT <name>` is cut off and passed on as that option.
+fn parse_copy_with_format(sql: &str) -> DbResult<Option<Statement>> {
+    let dialect = GenericDialect {};
+    // Malformed SQL is reported by the regular parse
+    let Ok(mut tokens) = Tokenizer::new(&dialect, sql).tokenize() else {
+        return Ok(None);
+    };
+    let mut significant: Vec<(usize, &Token)> = tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| !matches!(token, Token::Whitespace(_)))
+        .collect();
+    if matches!(significant.last(), Some((_, Token::SemiColon))) {
+        significant.pop();
+    }
+    let (format_start, name) = match significant.as_slice() {
+        [(_, Token::Word(copy)), .., (position, Token::Word(format)), (_, Token::Word(name))]
+            if copy.keyword == Keyword::COPY && format.keyword == Keyword::FORMAT =>
+        {
+            (*position, sqlast::Ident::new(name.value.clone()))
+        }
+        _ => return Ok(None),
+    };
+
+    tokens.truncate(format_start);
+    let mut stmts = SqlParser::new(&dialect)
+        .with_tokens(tokens)
+        .parse_statements()
+        .map_err(|e| DbError::Parser(format!("SQL parse error: {e}")))?;
+    let (Some(mut stmt), None) = (stmts.pop(), stmts.pop()) else {
+        return Err(DbError::Parser(
+            "FORMAT must end a single COPY statement".into(),
+        ));
+    };
+    if let sqlast::Statement::Copy { options, .. } = &mut stmt {
+        options.push(sqlast::CopyOption::Format(name));
+    }
+    map_statement(stmt).map(Some)
+}
+
+/// Parse `PROFILE <statement>`, or return `None` for any other SQL.
+///
+/// sqlparser has no PROFILE statement, so the keyword is cut off and the
+/// statement after it parsed on its own. Like EXPLAIN ANALYZE, it profiles
+/// SELECT, INSERT, UPDATE and DELETE.
+fn parse_profile(sql: &str) -> DbResult<Option<Statement>> {
+    let dialect = GenericDialect {};
+    // Malformed SQL is reported by the regular parse
+    let Ok(tokens) = Tokenizer::new(&dialect, sql).tokenize() else {
+        return Ok(None);
+    };
+    let Some(first) = tokens
+        .iter()
+        .position(|token| !matches!(token, Token::Whitespace(_)))
+    else {
+        return Ok(None);
+    };
+    if !matches!(&tokens[first], Token::Word(word) if word.value.eq_ignore_ascii_case("PROFILE")) {
+        return Ok(None);
+    }
+
+    let mut stmts = parse_sql_tokens(tokens[first + 1..].to_vec())?;
+    let (Some(query), None) = (stmts.pop(), stmts.pop()) else {
+        return Err(DbError::Parser("PROFILE takes a single statement".into()));
+    };
+    match query {
+        Statement::Select { .. }
+        | Statement::Insert { .. }
+        | Statement::Update { .. }
+        | Statement::Delete { .. } => Ok(Some(Statement::Profile {
+            query: Box::new(query),
+        })),
+        _ => Err(DbError::Parser(
+            "PROFILE supports SELECT, INSERT, UPDATE and DELETE".into(),
+        )),
+    }
+}
+
+/// Parse statements from already tokenized SQL.
+fn parse_sql_tokens(mut tokens: Vec<Token>) -> DbResult<Vec<Statement>> {
+    let dialect = GenericDialect {};
+    let samples = cut_table_samples(&mut tokens)?;
+    let mut stmts = SqlParser::new(&dialect)
+        .with_tokens(tokens)
+        .parse_statements()
+        .map_err(|e| DbError::Parser(format!("SQL parse error: {e}")))?
+        .into_iter()
+        .map(map_statement)
+        .collect::<DbResult<Vec<_>>>()?;
+    // A sample left in the hints of a table nothing samples is lost
+    let sampled = stmts
+        .iter_mut()
+        .flat_map(sampled_tables)
+        .filter(|table| table.sample.is_some())
+        .count();
+    if sampled < samples {
+        return Err(DbError::Parser(
+            "TABLESAMPLE must follow a table read by a SELECT".into(),
+        ));
+    }
+    Ok(stmts)
+}
+
+/// Parse SQL containing `TABLESAMPLE`, or return `None` for any other SQL.
+///
+/// sqlparser has no syntax for sampling, so each clause is replaced by a
+/// table hint that sqlparser keeps with the table before it, and read back
+/// from there as the table is mapped (see [`cut_table_samples`]).
+fn parse_table_samples(sql: &str) -> DbResult<Option<Vec<Statement>>> {
+    let dialect = GenericDialect {};
+    // Malformed SQL is reported by the regular parse
+    let Ok(tokens) = Tokenizer::new(&dialect, sql).tokenize() else {
+        return Ok(None);
+    };
+    if !tokens.iter().any(|token| is_word(token, "TABLESAMPLE")) {
+        return Ok(None);
+    }
+    parse_sql_tokens(tokens).map(Some)
+}
+
+fn is_word(token: &Token, word: &str) -> bool {
+    matches!(token, Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case(word))
+}
+
+/// Prefix of the table hint a `TABLESAMPLE` clause is replaced with.
+const SAMPLE_HINT: &str = "tablesample";
+
+/// Replace each `TABLESAMPLE <method> (<percent>) [REPEATABLE (<seed>)]` in
+/// `tokens` by the hint `WITH ('tablesample <method> <percent> [<seed>]')`,
+/// returning how many there were.
+///
+/// sqlparser reads hints as part of the table they follow, so each sample
+/// stays with its own table wherever it is, subqueries included.
+fn cut_table_samples(tokens: &mut Vec<Token>) -> DbResult<usize> {
+    let mut kept: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut samples = 0;
+    let mut rest = std::mem::take(tokens)
+        .into_iter()
+        .filter(|token| !matches!(token, Token::Whitespace(_)))
+        .peekable();
+    while let Some(token) = rest.next() {
+        if !is_word(&token, "TABLESAMPLE") {
+            kept.push(token);
+            continue;
+        }
+        if !matches!(kept.last(), Some(Token::Word(_))) {
+            return Err(DbError::Parser("TABLESAMPLE must follow a table".into()));
+        }
+        let method = match rest.next() {
+            Some(token) if is_word(&token, "BERNOULLI") => SampleMethod::Bernoulli,
+            Some(token) if is_word(&token, "SYSTEM") => SampleMethod::System,
+            _ => {
+                return Err(DbError::Parser(
+                    "TABLESAMPLE method must be BERNOULLI or SYSTEM".into(),
+                ))
+            }
+        };
+        let percent = match sample_argument(&mut rest, "TABLESAMPLE")? {
+            (false, number) => number.parse::<f64>().ok(),
+            (true, _) => None,
+        }
+        .filter(|percent| (0.0..=100.0).contains(percent))
+        .ok_or_else(|| {
+            DbError::Parser("TABLESAMPLE percentage must be between 0 and 100".into())
+        })?;
+        let seed = if rest.next_if(|token| is_word(token, "REPEATABLE")).is_some() {
+            let (negative, number) = sample_argument(&mut rest, "REPEATABLE")?;
+            let seed = number
+                .parse::<i64>()
+                .map_err(|_| DbError::Parser("REPEATABLE seed must be an integer".into()))?;
+            Some(if negative { -seed } else { seed })
+        } else {
+            None
+        };
+        let method = match method {
+            SampleMethod::Bernoulli => "bernoulli",
+            SampleMethod::System => "system",
+        };
+        let mut hint = format!("{SAMPLE_HINT} {method} {percent}");
+        if let Some(seed) = seed {
+            hint.push_str(&format!(" {seed}"));
+        }
+        kept.extend([
+            Token::make_keyword("WITH"),
+            Token::LParen,
+            Token::SingleQuotedString(hint),
+            Token::RParen,
+        ]);
+        samples += 1;
+    }
+    *tokens = kept;
+    Ok(samples)
+}
+
+/// The sample a table's hints carry, put there by [`cut_table_samples`].
+fn hinted_sample(hints: &[sqlast::Expr]) -> Option<TableSample> {
+    hints.iter().find_map(|hint| {
+        let sqlast::Expr::Value(sqlast::Value::SingleQuotedString(hint)) = hint else {
+            return None;
+        };
+        let mut words = hint.split(' ');
+        if words.next() != Some(SAMPLE_HINT) {
+            return None;
+        }
+        let method = match words.next()? {
+            "bernoulli" => SampleMethod::Bernoulli,
+            "system" => SampleMethod::System,
+            _ => return None,
+        };
+        let percent = words.next()?.parse().ok()?;
+        let seed = match words.next() {
+            Some(seed) => Some(seed.parse().ok()?),
+            None => None,
+        };
+        Some(TableSample {
+            method,
+            percent,
+            seed,
+        })
+    })
+}
+
+/// Read `(<number>)` after `clause`, returning whether the number is
+/// negated and its digits.
+fn sample_argument(
+    tokens: &mut impl Iterator<Item = Token>,
+    clause: &str,
+) -> DbResult<(bool, String)> {
+    let error = || DbError::Parser(format!("{clause} takes a number in parentheses"));
+    if tokens.next() != Some(Token::LParen) {
+        return Err(error());
+    }
+    let mut token = tokens.next();
+    let negative = token == Some(Token::Minus);
+    if negative {
+        token = tokens.next();
+    }
+    let Some(Token::Number(number, _)) = token else {
+        return Err(error());
+    };
+    if tokens.next() != Some(Token::RParen) {
+        return Err(error());
+    }
+    Ok((negative, number))
+}
+
+/// The tables a statement reads rows from that may be sampled: those in the
+/// FROM clause of a query and its joins, subqueries in WHERE included, and
+/// those of a query being explained, profiled, copied out or inserted.
+fn sampled_tables(stmt: &mut Statement) -> Vec<&mut TableRef> {
+    match stmt {
+        Statement::Select { from, joins, .. } => std::iter::once(from)
+            .chain(joins.iter_mut().map(|join| &mut join.table))
+            .collect(),
+        Statement::Explain { query, .. }
+        | Statement::Profile { query }
+        | Statement::CopyTo { query, .. }
+        | Statement::CreateTableAs { query, .. } => sampled_tables(query),
+        Statement::Insert {
+            query: Some(query), ..
+        } => sampled_tables(query),
+        _ => Vec::new(),
+    }
+}
+
+/// A `PARTITION BY` clause whose bounds are still SQL expressions.
+struct RawPartitionBy {
+    method: PartitionMethod,
+    column: String,
+    partitions: Vec<(String, RawPartitionValues)>,
+}
+
+enum RawPartitionValues {
+    LessThan(Option<sqlast::Expr>),
+    In(Vec<sqlast::Expr>),
+}
+
+fn parse_partition_clause(parser: &mut SqlParser) -> Result<RawPartitionBy, ParserError> {
+    // LIST, LESS and THAN are not sqlparser keywords
+    fn parse_word(parser: &mut SqlParser, word: &str) -> bool {
+        match parser.peek_token().token {
+            Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case(word) => {
+                parser.next_token();
+                true
+            }
+            _ => false,
+        }
+    }
+    fn expect_word(parser: &mut SqlParser, word: &str) -> Result<(), ParserError> {
+        if parse_word(parser, word) {
+            Ok(())
+        } else {
+            parser.expected(word, parser.peek_token())
+        }
+    }
+
+    parser.expect_keywords(&[Keyword::PARTITION, Keyword::BY])?;
+    let method = if parser.parse_keyword(Keyword::RANGE) {
+        PartitionMethod::Range
+    } else if parse_word(parser, "LIST") {
+        PartitionMethod::List
+    } else {
+        return parser.expected("RANGE or LIST", parser.peek_token());
+    };
+    parser.expect_token(&Token::LParen)?;
+    let column = normalize_ident_owned(parser.parse_identifier(false)?);
+    parser.expect_token(&Token::RParen)?;
+
+    parser.expect_token(&Token::LParen)?;
+    let partitions = parser.parse_comma_separated(|parser| {
+        parser.expect_keyword(Keyword::PARTITION)?;
+        let name = normalize_ident_owned(parser.parse_identifier(false)?);
+        parser.expect_keyword(Keyword::VALUES)?;
+        let values = match method {
+            PartitionMethod::Range => {
+                expect_word(parser, "LESS")?;
+                expect_word(parser, "THAN")?;
+                parser.expect_token(&Token::LParen)?;
+                let bound = if parser.parse_keyword(Keyword::MAXVALUE) {
+                    None
+                } else {
+                    Some(parser.parse_expr()?)
+                };
+                parser.expect_token(&Token::RParen)?;
+                RawPartitionValues::LessThan(bound)
+            }
+            PartitionMethod::List => {
+                parser.expect_keyword(Keyword::IN)?;
+                parser.expect_token(&Token::LParen)?;
+                let values = parser.parse_comma_separated(SqlParser::parse_expr)?;
+                parser.expect_token(&Token::RParen)?;
+                RawPartitionValues::In(values)
+            }
+        };
+        Ok((name, values))
+    })?;
+    parser.expect_token(&Token::RParen)?;
+
+    let _ = parser.consume_token(&Token::SemiColon);
+    if parser.peek_token().token != Token::EOF {
+        return parser.expected("end of statement", parser.peek_token());
+    }
+    Ok(RawPartitionBy {
+        method,
+        column,
+        partitions,
+    })
+}
+
+/// A partition bound: a literal, possibly a negative number.
+fn map_partition_value(expr: sqlast::Expr) -> DbResult<Value> {
+    let (negate, expr) = match expr {
+        sqlast::Expr::UnaryOp {
+            op: sqlast::UnaryOperator::Minus,
+            expr,
+        } => (true, *expr),
+        other => (false, other),
+    };
+    match (negate, map_expr(expr)?) {
+        (false, Expr::Literal(value)) => Ok(value),
+        (true, Expr::Literal(Value::Int(n))) => Ok(Value::Int(-n)),
+        (true, Expr::Literal(Value::Float(f))) => Ok(Value::Float(-f)),
+        _ => Err(DbError::Parser("partition bounds must be literals".into())),
+    }
+}
+
 fn map_statement(stmt: sqlast::Statement) -> DbResult<Statement> {
     use sqlast::Statement as SqlStatement;
 
     match stmt {
         SqlStatement::CreateTable {
             name,
+            temporary,
+            query: Some(query),
+            columns,
+            constraints,
+            with_options,
+            engine,
+            ..
+        } => map_create_table_as(
+            name,
+            temporary,
+            columns,
+            constraints,
+            with_options,
+            engine,
+            *query,
+        ),
+        SqlStatement::CreateTable {
+            name,
+            temporary,
             columns,
             constraints,
+            with_options,
+            engine,
             ..
-        } => map_create_table(name, columns, constraints),
+        } => map_create_table(name, temporary, columns, constraints, with_options, engine),
         SqlStatement::Drop {
             object_type, names, ..
         } => map_drop(object_type, names),
+        SqlStatement::AlterTable {
+            name, operations, ..
+        } => map_alter_table(name, operations),
         SqlStatement::CreateIndex {
             name,
             table_name,
             columns,
             using,
+            unique,
             ..
-        } => map_create_index(name, table_name, columns, using),
+        } => map_create_index(name, table_name, columns, using, unique),
+        SqlStatement::CreateView {
+            or_replace: false,
+            materialized: false,
+            name,
+            columns,
+            query,
+            with_no_schema_binding: false,
+            if_not_exists: false,
+            temporary: false,
+            ..
+        } => map_create_view(name, columns, *query),
+        SqlStatement::CreateView { .. } => Err(DbError::Parser(
+            "only plain CREATE VIEW <name> AS <select> is supported".into(),
+        )),
         SqlStatement::Insert {
-            table_name, source, ..
-        } => map_insert(table_name, source),
+            table_name,
+            columns,
+            source,
+            ..
+        } => map_insert(table_name, columns, source),
         SqlStatement::Query(query) => map_select(*query),
         SqlStatement::Update {
             table,
             assignments,
+            from,
             selection,
             ..
-        } => map_update(table, assignments, selection),
+        } => map_update(table, assignments, from, selection),
         SqlStatement::Delete {
-            from, selection, ..
-        } => map_delete(from, selection),
+            from,
+            using,
+            selection,
+            ..
+        } => map_delete(from, using, selection),
         SqlStatement::Explain {
             statement, analyze, ..
         } => map_explain(*statement, analyze),
+        SqlStatement::SetTransaction {
+            modes,
+            snapshot,
+            session,
+        } => map_set_transaction(modes, snapshot, session),
+        SqlStatement::SetVariable {
+            local: false,
+            hivevar: false,
+            variable,
+            value,
+        } if variable.to_string().eq_ignore_ascii_case("random_seed") => map_set_random_seed(value),
+        SqlStatement::SetVariable {
+            local: false,
+            hivevar: false,
+            variable,
+            value,
+        } if variable
+            .to_string()
+            .eq_ignore_ascii_case("statement_priority") =>
+        {
+            map_set_priority(value)
+        }
+        SqlStatement::Analyze { table_name, .. } => Ok(Statement::Analyze {
+            table: normalize_object_name(&table_name)?,
+        }),
+        SqlStatement::Copy {
+            source,
+            to,
+            target,
+            options,
+            legacy_options,
+            ..
+        } => map_copy(source, to, target, options, legacy_options),
         _ => Err(DbError::Parser("unsupported statement".into())),
     }
 }
 
 fn map_create_table(
     name: sqlast::ObjectName,
+    temporary: bool,
     columns: Vec<sqlast::ColumnDef>,
     constraints: Vec<sqlast::TableConstraint>,
+    with_options: Vec<sqlast::SqlOption>,
+    engine: Option<String>,
 ) -> DbResult<Statement> {
     let table = normalize_object_name(&name)?;
     let primary_key = resolve_primary_key(&columns, &constraints)?;
+    let options = resolve_table_options(&with_options)?;
 
     let mapped_columns = columns
         .into_iter()
-        .map(|col| ColumnDef {
-            name: normalize_ident_owned(col.name),
-            ty: col.data_type.to_string().to_uppercase(),
-        })
-        .collect();
+        .map(map_column_def)
+        .collect::<DbResult<Vec<_>>>()?;
 
     Ok(Statement::CreateTable {
         name: table,
         columns: mapped_columns,
         primary_key,
+        row_version: options.row_version,
+        audit: options.audit,
+        fillfactor: options.fillfactor,
+        compression: options.compression,
+        engine,
+        partition_by: None,
+        temporary,
+    })
+}
+
+fn map_create_table_as(
+    name: sqlast::ObjectName,
+    temporary: bool,
+    columns: Vec<sqlast::ColumnDef>,
+    constraints: Vec<sqlast::TableConstraint>,
+    with_options: Vec<sqlast::SqlOption>,
+    engine: Option<String>,
+    query: sqlast::Query,
+) -> DbResult<Statement> {
+    if !columns.is_empty() || !constraints.is_empty() || !with_options.is_empty() {
+        return Err(DbError::Parser(
+            "CREATE TABLE ... AS takes its columns from the query; column lists, constraints and WITH options are not supported".into(),
+        ));
+    }
+    if engine.is_some() {
+        return Err(DbError::Parser(
+            "ENGINE is not supported in CREATE TABLE ... AS".into(),
+        ));
+    }
+    let query = match map_select(query)? {
+        Statement::Select { lock: Some(_), .. } => {
+            return Err(DbError::Parser(
+                "FOR SHARE / FOR UPDATE not allowed in CREATE TABLE ... AS".into(),
+            ))
+        }
+        query => query,
+    };
+    Ok(Statement::CreateTableAs {
+        name: normalize_object_name(&name)?,
+        temporary,
+        query: Box::new(query),
+    })
+}
+
+fn map_column_def(col: sqlast::ColumnDef) -> DbResult<ColumnDef> {
+    let mut default = None;
+    let mut not_null = false;
+    let mut ty = col.data_type.to_string().to_uppercase();
+    // SERIAL is shorthand for an auto-increment INT
+    let mut auto_increment = ty == "SERIAL";
+    if auto_increment {
+        ty = "INT".into();
+    }
+    for opt in col.options {
+        match opt.option {
+            sqlast::ColumnOption::Default(expr) => default = Some(map_expr(expr)?),
+            sqlast::ColumnOption::NotNull => not_null = true,
+            sqlast::ColumnOption::Null => not_null = false,
+            sqlast::ColumnOption::DialectSpecific(tokens) => {
+                auto_increment |= tokens.iter().any(|token| {
+                    let token = token.to_string().to_uppercase();
+                    token == "AUTO_INCREMENT" || token == "AUTOINCREMENT"
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(ColumnDef {
+        name: normalize_ident_owned(col.name),
+        ty,
+        default,
+        not_null,
+        auto_increment,
+    })
+}
+
+fn map_alter_table(
+    name: sqlast::ObjectName,
+    operations: Vec<sqlast::AlterTableOperation>,
+) -> DbResult<Statement> {
+    use sqlast::AlterTableOperation;
+
+    let name = normalize_object_name(&name)?;
+    let mut operations = operations.into_iter();
+    let (Some(operation), None) = (operations.next(), operations.next()) else {
+        return Err(DbError::Parser(
+            "ALTER TABLE supports exactly one action per statement".into(),
+        ));
+    };
+
+    let action = match operation {
+        AlterTableOperation::AddColumn {
+            if_not_exists: false,
+            column_def,
+            ..
+        } => AlterTableAction::AddColumn(map_column_def(column_def)?),
+        AlterTableOperation::DropColumn {
+            column_name,
+            if_exists: false,
+            cascade: false,
+        } => AlterTableAction::DropColumn {
+            name: normalize_ident_owned(column_name),
+        },
+        AlterTableOperation::RenameColumn {
+            old_column_name,
+            new_column_name,
+        } => AlterTableAction::RenameColumn {
+            old: normalize_ident_owned(old_column_name),
+            new: normalize_ident_owned(new_column_name),
+        },
+        AlterTableOperation::AddColumn { .. } | AlterTableOperation::DropColumn { .. } => {
+            return Err(DbError::Parser(
+                "IF [NOT] EXISTS and CASCADE not supported in ALTER TABLE".into(),
+            ))
+        }
+        other => {
+            return Err(DbError::Parser(format!(
+                "unsupported ALTER TABLE action: {other}"
+            )))
+        }
+    };
+    Ok(Statement::AlterTable { name, action })
+}
+
+/// Options accepted in `CREATE TABLE ... WITH (...)`.
+#[derive(Default)]
+struct TableOptions {
+    row_version: bool,
+    audit: bool,
+    fillfactor: Option<u8>,
+    compression: Compression,
+}
+
+/// Lowest `fillfactor` accepted, as in PostgreSQL.
+const MIN_FILLFACTOR: u8 = 10;
+
+/// Read the `row_version`, `audit`, `fillfactor` and `compression` table
+/// options; no other options are supported.
+fn resolve_table_options(options: &[sqlast::SqlOption]) -> DbResult<TableOptions> {
+    let mut resolved = TableOptions::default();
+    for option in options {
+        let name = option.name.value.to_ascii_lowercase();
+        let flag = match name.as_str() {
+            "row_version" => &mut resolved.row_version,
+            "audit" => &mut resolved.audit,
+            "fillfactor" => {
+                resolved.fillfactor = Some(resolve_fillfactor(&option.value)?);
+                continue;
+            }
+            "compression" => {
+                resolved.compression = resolve_compression(&option.value)?;
+                continue;
+            }
+            _ => {
+                return Err(DbError::Parser(format!(
+                    "unsupported table option: {}",
+                    option.name.value
+                )))
+            }
+        };
+        *flag = match &option.value {
+            sqlast::Expr::Value(sqlast::Value::Boolean(b)) => *b,
+            other => {
+                return Err(DbError::Parser(format!(
+                    "{name} expects true or false, got {other}"
+                )))
+            }
+        };
+    }
+    Ok(resolved)
+}
+
+fn resolve_fillfactor(value: &sqlast::Expr) -> DbResult<u8> {
+    match value {
+        sqlast::Expr::Value(sqlast::Value::Number(n, _)) => n
+            .parse::<u8>()
+            .ok()
+            .filter(|percent| (MIN_FILLFACTOR..=100).contains(percent))
+            .ok_or_else(|| {
+                DbError::Parser(format!(
+                    "fillfactor must be between {MIN_FILLFACTOR} and 100, got {n}"
+                ))
+            }),
+        other => Err(DbError::Parser(format!(
+            "fillfactor expects a percentage, got {other}"
+        ))),
+    }
+}
+
+fn resolve_compression(value: &sqlast::Expr) -> DbResult<Compression> {
+    let name = match value {
+        sqlast::Expr::Value(sqlast::Value::SingleQuotedString(name)) => name,
+        sqlast::Expr::Identifier(ident) => &ident.value,
+        other => {
+            return Err(DbError::Parser(format!(
+                "compression expects a codec name, got {other}"
+            )))
+        }
+    };
+    Compression::from_name(name).map_err(|_| {
+        DbError::Parser(format!(
+            "unknown compression '{name}'; supported: none, lz4, zstd"
+        ))
     })
 }
 
@@ -94,6 +1144,9 @@ fn map_drop(
         sqlast::ObjectType::Index => Ok(Statement::DropIndex {
             name: first_name(names)?,
         }),
+        sqlast::ObjectType::View => Ok(Statement::DropView {
+            name: first_name(names)?,
+        }),
         _ => Err(DbError::Parser(format!(
             "unsupported DROP type: {object_type:?}"
         ))),
@@ -105,6 +1158,7 @@ fn map_create_index(
     table_name: sqlast::ObjectName,
     columns: Vec<sqlast::OrderByExpr>,
     using: Option<sqlast::Ident>,
+    unique: bool,
 ) -> DbResult<Statement> {
     let index_name = name
         .ok_or_else(|| DbError::Parser("index name required".into()))
@@ -135,26 +1189,82 @@ fn map_create_index(
         table,
         column,
         index_type,
+        unique,
     })
 }
 
+fn map_create_view(
+    name: sqlast::ObjectName,
+    columns: Vec<sqlast::ViewColumnDef>,
+    query: sqlast::Query,
+) -> DbResult<Statement> {
+    let name = normalize_object_name(&name)?;
+    if !columns.is_empty() {
+        return Err(DbError::Parser(
+            "column lists not supported in CREATE VIEW; alias the select items instead".into(),
+        ));
+    }
+    // Stored as text, so check now that it will parse as a plain query
+    let sql = query.to_string();
+    if let Statement::Select { lock: Some(_), .. } = map_select(query)? {
+        return Err(DbError::Parser(
+            "FOR SHARE / FOR UPDATE not allowed in a view".into(),
+        ));
+    }
+    Ok(Statement::CreateView { name, query: sql })
+}
+
 fn map_insert(
     table_name: sqlast::ObjectName,
+    columns: Vec<sqlast::Ident>,
     source: Option<Box<sqlast::Query>>,
 ) -> DbResult<Statement> {
     let table = normalize_object_name(&table_name)?;
     let source = source.ok_or_else(|| DbError::Parser("INSERT source missing".into()))?;
-    let values = extract_values(*source)?;
+    let (rows, query) = if matches!(*source.body, sqlast::SetExpr::Values(_)) {
+        (extract_values(*source)?, None)
+    } else {
+        let query = match map_select(*source)? {
+            Statement::Select { lock: Some(_), .. } => {
+                return Err(DbError::Parser(
+                    "FOR SHARE / FOR UPDATE not allowed in INSERT ... SELECT".into(),
+                ))
+            }
+            query => query,
+        };
+        (Vec::new(), Some(Box::new(query)))
+    };
+
+    let columns: Vec<String> = columns.into_iter().map(normalize_ident_owned).collect();
+    for (i, column) in columns.iter().enumerate() {
+        if columns[..i].contains(column) {
+            return Err(DbError::Parser(format!(
+                "column '{column}' specified more than once in INSERT"
+            )));
+        }
+    }
+    if !columns.is_empty() && rows.iter().any(|row| row.len() != columns.len()) {
+        return Err(DbError::Parser(format!(
+            "INSERT has {} target columns but a VALUES row has a different number of values",
+            columns.len()
+        )));
+    }
 
-    Ok(Statement::Insert { table, values })
+    Ok(Statement::Insert {
+        table,
+        columns,
+        rows,
+        query,
+    })
 }
 
 fn map_update(
     table: sqlast::TableWithJoins,
     assignments: Vec<sqlast::Assignment>,
+    from: Option<sqlast::TableWithJoins>,
     selection: Option<sqlast::Expr>,
 ) -> DbResult<Statement> {
-    let table = table_name_from_with_joins(&table)?;
+    let target = table_ref_without_joins(&table)?;
     let assignments = assignments
         .into_iter()
         .map(|assign| {
@@ -165,78 +1275,259 @@ fn map_update(
             Ok((normalize_ident(ident), map_expr(assign.value)?))
         })
         .collect::<DbResult<Vec<_>>>()?;
+    let from = from
+        .iter()
+        .map(table_ref_without_joins)
+        .collect::<DbResult<Vec<_>>>()?;
     let selection = selection.map(map_expr).transpose()?;
 
     Ok(Statement::Update {
-        table,
+        table: target.name,
+        alias: target.alias,
         assignments,
+        from,
         selection,
     })
 }
 
 fn map_delete(
     from: Vec<sqlast::TableWithJoins>,
+    using: Option<Vec<sqlast::TableWithJoins>>,
     selection: Option<sqlast::Expr>,
 ) -> DbResult<Statement> {
     if from.is_empty() {
         return Err(DbError::Parser("DELETE requires FROM source".into()));
     }
-    let table = table_name_from_with_joins(&from[0])?;
+    let target = table_ref_without_joins(&from[0])?;
     if from.len() > 1 {
-        return Err(DbError::Parser("multi-table DELETE not supported".into()));
+        return Err(DbError::Parser(
+            "multi-table DELETE not supported; name the other tables in USING".into(),
+        ));
     }
+    let using = using
+        .unwrap_or_default()
+        .iter()
+        .map(table_ref_without_joins)
+        .collect::<DbResult<Vec<_>>>()?;
     let selection = selection.map(map_expr).transpose()?;
 
-    Ok(Statement::Delete { table, selection })
+    Ok(Statement::Delete {
+        table: target.name,
+        alias: target.alias,
+        using,
+        selection,
+    })
 }
 
-fn map_explain(statement: sqlast::Statement, analyze: bool) -> DbResult<Statement> {
-    let query = Box::new(map_statement(statement)?);
-    Ok(Statement::Explain { query, analyze })
-}
+fn map_copy(
+    source: sqlast::CopySource,
+    to: bool,
+    target: sqlast::CopyTarget,
+    options: Vec<sqlast::CopyOption>,
+    legacy_options: Vec<sqlast::CopyLegacyOption>,
+) -> DbResult<Statement> {
+    if !to {
+        return Err(DbError::Parser("COPY ... FROM not supported".into()));
+    }
+    let sqlast::CopyTarget::File { filename: path } = target else {
+        return Err(DbError::Parser("COPY ... TO only writes to a file".into()));
+    };
 
-fn map_select(query: sqlast::Query) -> DbResult<Statement> {
-    use sqlast::SetExpr;
+    let query = match source {
+        sqlast::CopySource::Query(query) => map_select(*query)?,
+        sqlast::CopySource::Table {
+            table_name,
+            columns,
+        } => Statement::Select {
+            columns: if columns.is_empty() {
+                vec![SelectItem::Wildcard]
+            } else {
+                columns
+                    .into_iter()
+                    .map(|column| SelectItem::Column(normalize_ident_owned(column)))
+                    .collect()
+            },
+            from: TableRef {
+                name: normalize_object_name(&table_name)?,
+                alias: None,
+                args: None,
+                sample: None,
+                values: None,
+            },
+            joins: Vec::new(),
+            selection: None,
+            group_by: Vec::new(),
+            having: None,
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+            lock: None,
+        },
+    };
+    if let Statement::Select { lock: Some(_), .. } = query {
+        return Err(DbError::Parser(
+            "FOR SHARE / FOR UPDATE not allowed in COPY".into(),
+        ));
+    }
 
-    let select = match *query.body {
-        SetExpr::Select(select) => select,
-        SetExpr::Values(_) => {
-            return Err(DbError::Parser("standalone VALUES not supported".into()))
+    let mut format = None;
+    let mut set_format = |value: CopyFormat| {
+        if format.replace(value).is_some() {
+            return Err(DbError::Parser(
+                "COPY format specified more than once".into(),
+            ));
         }
-        _ => return Err(DbError::Parser("SET operations not supported".into())),
+        Ok(())
     };
+    for option in options {
+        match option {
+            sqlast::CopyOption::Format(name) => set_format(map_copy_format(&name)?)?,
+            other => return Err(DbError::Parser(format!("unsupported COPY option: {other}"))),
+        }
+    }
+    for option in legacy_options {
+        match option {
+            sqlast::CopyLegacyOption::Csv(csv_options) if csv_options.is_empty() => {
+                set_format(CopyFormat::Csv)?
+            }
+            other => return Err(DbError::Parser(format!("unsupported COPY option: {other}"))),
+        }
+    }
 
-    let sqlast::Select {
-        projection,
-        from,
-        selection,
-        ..
-    } = *select;
+    Ok(Statement::CopyTo {
+        query: Box::new(query),
+        path,
+        format: format.unwrap_or_default(),
+    })
+}
 
-    if from.is_empty() {
-        return Err(DbError::Parser("SELECT requires FROM clause".into()));
+fn map_copy_format(name: &sqlast::Ident) -> DbResult<CopyFormat> {
+    match name.value.to_ascii_lowercase().as_str() {
+        "csv" => Ok(CopyFormat::Csv),
+        "json" => Ok(CopyFormat::Json),
+        _ => Err(DbError::Parser(format!(
+            "unsupported COPY format '{}', expected CSV or JSON",
+            name.value
+        ))),
     }
-    if from.len() > 1 {
+}
+
+fn map_explain(statement: sqlast::Statement, analyze: bool) -> DbResult<Statement> {
+    let query = Box::new(map_statement(statement)?);
+    Ok(Statement::Explain { query, analyze })
+}
+
+fn map_set_transaction(
+    modes: Vec<sqlast::TransactionMode>,
+    snapshot: Option<sqlast::Value>,
+    session: bool,
+) -> DbResult<Statement> {
+    use sqlast::{TransactionIsolationLevel as Level, TransactionMode};
+
+    if snapshot.is_some() {
         return Err(DbError::Parser(
-            "comma-separated table lists not supported; use JOIN instead".into(),
+            "SET TRANSACTION SNAPSHOT not supported".into(),
         ));
     }
 
-    // Parse primary FROM table with optional alias
-    let from_table = map_table_ref(&from[0])?;
+    let mut isolation = None;
+    for mode in modes {
+        match mode {
+            TransactionMode::IsolationLevel(level) => {
+                isolation = Some(match level {
+                    Level::ReadUncommitted | Level::ReadCommitted => IsolationLevel::ReadCommitted,
+                    Level::RepeatableRead => IsolationLevel::RepeatableRead,
+                    Level::Serializable => IsolationLevel::Serializable,
+                });
+            }
+            TransactionMode::AccessMode(mode) => {
+                return Err(DbError::Parser(format!(
+                    "transaction access mode {mode} not supported"
+                )));
+            }
+        }
+    }
 
-    // Parse JOIN clauses
-    let joins = from[0]
-        .joins
-        .iter()
-        .map(map_join_clause)
-        .collect::<DbResult<Vec<_>>>()?;
+    let isolation = isolation
+        .ok_or_else(|| DbError::Parser("SET TRANSACTION requires an ISOLATION LEVEL".into()))?;
+    Ok(Statement::SetTransaction { isolation, session })
+}
 
-    let columns = projection
-        .into_iter()
-        .map(map_select_item)
-        .collect::<DbResult<Vec<_>>>()?;
-    let selection = selection.map(map_expr).transpose()?;
+/// `SET random_seed = <int> | DEFAULT`.
+fn map_set_random_seed(value: Vec<sqlast::Expr>) -> DbResult<Statement> {
+    use sqlast::{Expr as SqlExpr, UnaryOperator, Value as SqlValue};
+
+    let seed = match value.as_slice() {
+        [SqlExpr::Identifier(ident)] if ident.value.eq_ignore_ascii_case("DEFAULT") => {
+            return Ok(Statement::SetRandomSeed { seed: None });
+        }
+        [SqlExpr::Value(SqlValue::Number(n, _))] => n.clone(),
+        [SqlExpr::UnaryOp {
+            op: UnaryOperator::Minus,
+            expr,
+        }] => match &**expr {
+            SqlExpr::Value(SqlValue::Number(n, _)) => format!("-{n}"),
+            _ => String::new(),
+        },
+        _ => String::new(),
+    };
+    let seed = seed
+        .parse::<i64>()
+        .map_err(|_| DbError::Parser("random_seed must be an integer or DEFAULT".into()))?;
+    Ok(Statement::SetRandomSeed { seed: Some(seed) })
+}
+
+/// `SET statement_priority = high | normal | background | DEFAULT`.
+fn map_set_priority(value: Vec<sqlast::Expr>) -> DbResult<Statement> {
+    use sqlast::{Expr as SqlExpr, Value as SqlValue};
+
+    let name = match value.as_slice() {
+        [SqlExpr::Identifier(ident)] => ident.value.as_str(),
+        [SqlExpr::Value(SqlValue::SingleQuotedString(name))] => name.as_str(),
+        _ => "",
+    };
+    let priority = if name.eq_ignore_ascii_case("DEFAULT") {
+        Priority::default()
+    } else {
+        Priority::from_name(name).ok_or_else(|| {
+            DbError::Parser("statement_priority must be HIGH, NORMAL, BACKGROUND or DEFAULT".into())
+        })?
+    };
+    Ok(Statement::SetPriority { priority })
+}
+
+fn map_select(query: sqlast::Query) -> DbResult<Statement> {
+    use sqlast::SetExpr;
+
+    let (columns, from_table, joins, selection, group_by, having) = match *query.body {
+        SetExpr::Select(select) => match select.distinct.clone() {
+            None => map_select_body(*select)?,
+            Some(sqlast::Distinct::Distinct) => {
+                let (columns, from_table, joins, selection, group_by, having) =
+                    map_select_body(*select)?;
+                if !group_by.is_empty() || having.is_some() {
+                    return Err(DbError::Parser(
+                        "SELECT DISTINCT with GROUP BY or HAVING not supported".into(),
+                    ));
+                }
+                let group_by = group_by_select_list(&columns)?;
+                (columns, from_table, joins, selection, group_by, having)
+            }
+            Some(sqlast::Distinct::On(_)) => {
+                return Err(DbError::Parser("DISTINCT ON not supported".into()))
+            }
+        },
+        // A standalone VALUES list reads as `SELECT * FROM <values>`
+        SetExpr::Values(values) => (
+            vec![ast::SelectItem::Wildcard],
+            map_values(values)?,
+            Vec::new(),
+            None,
+            Vec::new(),
+            None,
+        ),
+        _ => return Err(DbError::Parser("SET operations not supported".into())),
+    };
 
     // Extract ORDER BY clauses
     let order_by = query
@@ -271,26 +1562,349 @@ fn map_select(query: sqlast::Query) -> DbResult<Statement> {
         })
         .transpose()?;
 
+    let lock = map_lock_clauses(query.locks)?;
+
     Ok(Statement::Select {
         columns,
         from: from_table,
         joins,
         selection,
+        group_by,
+        having: having.map(Box::new),
         order_by,
         limit,
         offset,
+        lock,
+    })
+}
+
+/// `SELECT DISTINCT` as a `GROUP BY` of its whole select list, so that it
+/// runs, and spills, as a hash aggregation without aggregates.
+fn group_by_select_list(columns: &[ast::SelectItem]) -> DbResult<Vec<Expr>> {
+    columns
+        .iter()
+        .map(|item| match item {
+            ast::SelectItem::Wildcard => Err(DbError::Parser(
+                "SELECT DISTINCT * not supported; list the columns".into(),
+            )),
+            ast::SelectItem::Column(name) => Ok(match name.split_once('.') {
+                Some((table, name)) => Expr::Column {
+                    table: Some(table.to_string()),
+                    name: name.to_string(),
+                },
+                None => Expr::Column {
+                    table: None,
+                    name: name.clone(),
+                },
+            }),
+            ast::SelectItem::Expr { expr, .. } if calls_aggregate(expr) => Err(DbError::Parser(
+                "SELECT DISTINCT with aggregate functions not supported".into(),
+            )),
+            ast::SelectItem::Expr { expr, .. } => Ok(expr.clone()),
+        })
+        .collect()
+}
+
+/// Whether `e` calls an aggregate function.
+fn calls_aggregate(e: &Expr) -> bool {
+    match e {
+        Expr::Aggregate { .. } => true,
+        Expr::Literal(_) | Expr::Column { .. } => false,
+        Expr::Unary { expr, .. } | Expr::Cast { expr, .. } | Expr::IsNull { expr, .. } => {
+            calls_aggregate(expr)
+        }
+        Expr::Binary { left, right, .. } => calls_aggregate(left) || calls_aggregate(right),
+        Expr::Function { args, .. } => args.iter().any(calls_aggregate),
+        Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => {
+            operand
+                .iter()
+                .chain(else_result)
+                .any(|e| calls_aggregate(e))
+                || branches
+                    .iter()
+                    .any(|(when, then)| calls_aggregate(when) || calls_aggregate(then))
+        }
+    }
+}
+
+/// The select list, source, joins, WHERE clause, GROUP BY expressions and
+/// HAVING clause of a SELECT. Without a FROM clause it reads a single row
+/// with no columns.
+type SelectBody = (
+    Vec<ast::SelectItem>,
+    ast::TableRef,
+    Vec<ast::JoinClause>,
+    Option<Expr>,
+    Vec<Expr>,
+    Option<Expr>,
+);
+
+fn map_select_body(select: sqlast::Select) -> DbResult<SelectBody> {
+    let sqlast::Select {
+        projection,
+        from,
+        selection,
+        group_by,
+        having,
+        ..
+    } = select;
+
+    if from.len() > 1 {
+        return Err(DbError::Parser(
+            "comma-separated table lists not supported; use JOIN instead".into(),
+        ));
+    }
+
+    let (from_table, mut joins) = match from.first() {
+        // Parse primary FROM table with optional alias, then its JOIN clauses
+        Some(from) => (
+            map_table_ref(from)?,
+            from.joins
+                .iter()
+                .map(map_join_clause)
+                .collect::<DbResult<Vec<_>>>()?,
+        ),
+        None => (ast::TableRef::single_row(), Vec::new()),
+    };
+
+    let columns = projection
+        .into_iter()
+        .map(map_select_item)
+        .collect::<DbResult<Vec<_>>>()?;
+    if columns.contains(&ast::SelectItem::Wildcard) && from.is_empty() {
+        return Err(DbError::Parser("SELECT * requires a FROM clause".into()));
+    }
+    let selection = match selection {
+        Some(selection) => map_where(selection, &mut joins)?,
+        None => None,
+    };
+    let group_by = match group_by {
+        sqlast::GroupByExpr::Expressions(exprs) => exprs
+            .into_iter()
+            .map(map_expr)
+            .collect::<DbResult<Vec<_>>>()?,
+        sqlast::GroupByExpr::All => {
+            return Err(DbError::Parser("GROUP BY ALL not supported".into()))
+        }
+    };
+    let having = having.map(map_expr).transpose()?;
+    Ok((columns, from_table, joins, selection, group_by, having))
+}
+
+/// Map a WHERE clause, moving each `EXISTS`, `NOT EXISTS` and
+/// `IN (SELECT ...)` condition AND-ed into it to a semi or anti join pushed
+/// onto `joins`. The conditions left, if any, are returned.
+fn map_where(selection: sqlast::Expr, joins: &mut Vec<ast::JoinClause>) -> DbResult<Option<Expr>> {
+    use sqlast::Expr as SqlExpr;
+
+    let mut conjuncts = Vec::new();
+    split_conjuncts(selection.clone(), &mut conjuncts);
+    let is_subquery =
+        |expr: &SqlExpr| matches!(expr, SqlExpr::Exists { .. } | SqlExpr::InSubquery { .. });
+    if !conjuncts.iter().any(is_subquery) {
+        return map_expr(selection).map(Some);
+    }
+
+    let mut remaining = Vec::new();
+    for conjunct in conjuncts {
+        match conjunct {
+            SqlExpr::Exists { subquery, negated } => {
+                let join_type = if negated {
+                    ast::JoinType::Anti
+                } else {
+                    ast::JoinType::Semi
+                };
+                joins.push(map_subquery_join(*subquery, join_type, None)?);
+            }
+            SqlExpr::InSubquery {
+                expr,
+                subquery,
+                negated: false,
+            } => joins.push(map_subquery_join(
+                *subquery,
+                ast::JoinType::Semi,
+                Some(*expr),
+            )?),
+            SqlExpr::InSubquery { negated: true, .. } => {
+                return Err(DbError::Parser(
+                    "NOT IN (subquery) not supported; use NOT EXISTS instead".into(),
+                ))
+            }
+            other => remaining.push(map_expr(other)?),
+        }
+    }
+    Ok(remaining.into_iter().reduce(|left, right| Expr::Binary {
+        left: Box::new(left),
+        op: BinaryOp::And,
+        right: Box::new(right),
+    }))
+}
+
+/// The conditions AND-ed together in `expr`, without their parentheses.
+fn split_conjuncts(expr: sqlast::Expr, out: &mut Vec<sqlast::Expr>) {
+    match expr {
+        sqlast::Expr::BinaryOp {
+            left,
+            op: sqlast::BinaryOperator::And,
+            right,
+        } => {
+            split_conjuncts(*left, out);
+            split_conjuncts(*right, out);
+        }
+        sqlast::Expr::Nested(expr) => split_conjuncts(*expr, out),
+        other => out.push(other),
+    }
+}
+
+/// Map a subquery in WHERE to a join against the table it reads, on its
+/// WHERE clause. For `IN`, `outer` is the expression tested against the
+/// subquery's single column.
+///
+/// The subquery may refer to the outer query's tables, so a correlated
+/// subquery needs no special handling; names it does not qualify are looked
+/// up in its own table first.
+fn map_subquery_join(
+    query: sqlast::Query,
+    join_type: ast::JoinType,
+    outer: Option<sqlast::Expr>,
+) -> DbResult<ast::JoinClause> {
+    if query.with.is_some()
+        || !query.order_by.is_empty()
+        || query.limit.is_some()
+        || query.offset.is_some()
+        || query.fetch.is_some()
+        || !query.locks.is_empty()
+    {
+        return Err(DbError::Parser(
+            "WITH, ORDER BY, LIMIT, OFFSET and locking clauses not supported in subqueries".into(),
+        ));
+    }
+    let sqlast::SetExpr::Select(select) = *query.body else {
+        return Err(DbError::Parser("subquery must be a SELECT".into()));
+    };
+    let (columns, table, joins, selection, group_by, having) = map_select_body(*select)?;
+    if !joins.is_empty() {
+        return Err(DbError::Parser(
+            "joins and nested subqueries not supported in subqueries".into(),
+        ));
+    }
+    if !group_by.is_empty() || having.is_some() {
+        return Err(DbError::Parser(
+            "GROUP BY and HAVING not supported in subqueries".into(),
+        ));
+    }
+
+    let key = match outer {
+        Some(outer) => {
+            let inner = match <[_; 1]>::try_from(columns) {
+                Ok([ast::SelectItem::Column(name)]) => match name.split_once('.') {
+                    Some((table, name)) => Expr::Column {
+                        table: Some(table.to_string()),
+                        name: name.to_string(),
+                    },
+                    None => Expr::Column { table: None, name },
+                },
+                Ok([ast::SelectItem::Expr { expr, .. }]) => expr,
+                _ => {
+                    return Err(DbError::Parser(
+                        "IN subquery must select exactly one column".into(),
+                    ))
+                }
+            };
+            Some((map_expr(outer)?, inner))
+        }
+        None => None,
+    };
+    Ok(ast::JoinClause {
+        join_type,
+        table,
+        condition: selection.unwrap_or(Expr::Literal(Value::Bool(true))),
+        key,
     })
 }
 
+/// Map the rows of a `VALUES` list, which must all have the same number of
+/// columns.
+fn map_values(values: sqlast::Values) -> DbResult<ast::TableRef> {
+    let rows = values
+        .rows
+        .into_iter()
+        .map(|row| row.into_iter().map(map_expr).collect::<DbResult<Vec<_>>>())
+        .collect::<DbResult<Vec<_>>>()?;
+    let width = rows.first().map_or(0, Vec::len);
+    if width == 0 || rows.iter().any(|row| row.len() != width) {
+        return Err(DbError::Parser(
+            "VALUES lists must all have the same, non-zero number of columns".into(),
+        ));
+    }
+    Ok(ast::TableRef::values(rows))
+}
+
+/// Map `FOR SHARE` / `FOR UPDATE` to a single row lock mode.
+///
+/// NOWAIT and SKIP LOCKED are accepted: locks never conflict between
+/// statements, so neither can change the result.
+fn map_lock_clauses(locks: Vec<sqlast::LockClause>) -> DbResult<Option<ast::RowLock>> {
+    let mut mode = None;
+    for lock in locks {
+        if lock.of.is_some() {
+            return Err(DbError::Parser(
+                "FOR UPDATE OF <table> not supported".into(),
+            ));
+        }
+        let requested = match lock.lock_type {
+            sqlast::LockType::Share => ast::RowLock::Share,
+            sqlast::LockType::Update => ast::RowLock::Update,
+        };
+        // The strongest requested lock wins
+        if mode != Some(ast::RowLock::Update) {
+            mode = Some(requested);
+        }
+    }
+    Ok(mode)
+}
+
 /// Extract table reference with optional alias from a TableWithJoins.
 fn map_table_ref(table: &sqlast::TableWithJoins) -> DbResult<ast::TableRef> {
-    match &table.relation {
-        sqlast::TableFactor::Table { name, alias, .. } => Ok(ast::TableRef {
-            name: normalize_object_name(name)?,
-            alias: alias.as_ref().map(|a| normalize_ident(&a.name)),
-        }),
-        _ => Err(DbError::Parser("unsupported table factor".into())),
-    }
+    map_table_factor(&table.relation)?
+        .ok_or_else(|| DbError::Parser("unsupported table factor".into()))
+}
+
+/// Map a table, or a call of a table function such as
+/// `generate_series(1, 10)`, with its optional alias. `None` for any other
+/// table factor.
+fn map_table_factor(factor: &sqlast::TableFactor) -> DbResult<Option<ast::TableRef>> {
+    let sqlast::TableFactor::Table {
+        name,
+        alias,
+        args,
+        with_hints,
+        ..
+    } = factor
+    else {
+        return Ok(None);
+    };
+    let args = match args {
+        Some(args) => Some(
+            args.iter()
+                .cloned()
+                .map(map_function_arg)
+                .collect::<DbResult<Vec<_>>>()?,
+        ),
+        None => None,
+    };
+    Ok(Some(ast::TableRef {
+        name: normalize_object_name(name)?,
+        alias: alias.as_ref().map(|a| normalize_ident(&a.name)),
+        // Only a table can be sampled, not a table function
+        sample: hinted_sample(with_hints).filter(|_| args.is_none()),
+        args,
+        values: None,
+    }))
 }
 
 /// Map a single JOIN clause to our AST.
@@ -326,13 +1940,8 @@ fn map_join_clause(join: &sqlast::Join) -> DbResult<ast::JoinClause> {
     };
 
     // Extract table reference from join
-    let table = match &join.relation {
-        sqlast::TableFactor::Table { name, alias, .. } => ast::TableRef {
-            name: normalize_object_name(name)?,
-            alias: alias.as_ref().map(|a| normalize_ident(&a.name)),
-        },
-        _ => return Err(DbError::Parser("unsupported join table factor".into())),
-    };
+    let table = map_table_factor(&join.relation)?
+        .ok_or_else(|| DbError::Parser("unsupported join table factor".into()))?;
 
     // Parse the ON condition
     let condition = map_expr(condition_expr)?;
@@ -341,28 +1950,12 @@ fn map_join_clause(join: &sqlast::Join) -> DbResult<ast::JoinClause> {
         join_type,
         table,
         condition,
+        key: None,
     })
 }
 
 fn map_order_by_expr(expr: sqlast::OrderByExpr) -> DbResult<ast::OrderByExpr> {
-    // Extract column name from expression
-    let column = match expr.expr {
-        sqlast::Expr::Identifier(ident) => normalize_ident(&ident),
-        sqlast::Expr::CompoundIdentifier(parts) => {
-            if parts.len() == 1 {
-                normalize_ident(&parts[0])
-            } else {
-                return Err(DbError::Parser(
-                    "qualified column names not supported in ORDER BY".into(),
-                ));
-            }
-        }
-        _ => {
-            return Err(DbError::Parser(
-                "ORDER BY supports column names only".into(),
-            ))
-        }
-    };
+    let key = map_expr(expr.expr)?;
 
     // Extract sort direction (default is ASC)
     let direction = if let Some(asc) = expr.asc {
@@ -376,20 +1969,37 @@ fn map_order_by_expr(expr: sqlast::OrderByExpr) -> DbResult<ast::OrderByExpr> {
         ast::SortDirection::Asc
     };
 
-    Ok(ast::OrderByExpr { column, direction })
+    // NULLS FIRST / NULLS LAST, defaulting to NULLs as the lowest value
+    let nulls = match expr.nulls_first {
+        Some(true) => ast::NullsOrder::First,
+        Some(false) => ast::NullsOrder::Last,
+        None => ast::NullsOrder::default_for(&direction),
+    };
+
+    Ok(ast::OrderByExpr {
+        expr: key,
+        direction,
+        nulls,
+    })
 }
 
-fn extract_values(query: sqlast::Query) -> DbResult<Vec<Expr>> {
+fn extract_values(query: sqlast::Query) -> DbResult<Vec<Vec<Expr>>> {
     match *query.body {
         sqlast::SetExpr::Values(values) => {
-            let mut rows = values.rows.into_iter();
-            let row = rows
-                .next()
-                .ok_or_else(|| DbError::Parser("INSERT requires at least one row".into()))?;
-            if rows.next().is_some() {
-                return Err(DbError::Parser("multi-row INSERT not supported".into()));
+            if values.rows.is_empty() {
+                return Err(DbError::Parser("INSERT requires at least one row".into()));
+            }
+            let width = values.rows[0].len();
+            if values.rows.iter().any(|row| row.len() != width) {
+                return Err(DbError::Parser(
+                    "all VALUES rows must have the same number of values".into(),
+                ));
             }
-            row.into_iter().map(map_expr).collect()
+            values
+                .rows
+                .into_iter()
+                .map(|row| row.into_iter().map(map_expr).collect())
+                .collect()
         }
         _ => Err(DbError::Parser("INSERT expects VALUES list".into())),
     }
@@ -415,13 +2025,18 @@ fn map_select_item(item: sqlast::SelectItem) -> DbResult<SelectItem> {
                     .join(".");
                 Ok(SelectItem::Column(qualified_name))
             }
-            other => Err(DbError::Parser(format!(
-                "unsupported select item: {other:?}"
-            ))),
+            other => match map_expr(other) {
+                Ok(expr) => Ok(SelectItem::Expr { expr, alias: None }),
+                Err(DbError::Parser(msg)) => {
+                    Err(DbError::Parser(format!("unsupported select item: {msg}")))
+                }
+                Err(e) => Err(e),
+            },
         },
-        sqlast::SelectItem::ExprWithAlias { .. } => {
-            Err(DbError::Parser("select aliases not supported".into()))
-        }
+        sqlast::SelectItem::ExprWithAlias { expr, alias } => Ok(SelectItem::Expr {
+            expr: map_expr(expr)?,
+            alias: Some(normalize_ident_owned(alias)),
+        }),
     }
 }
 
@@ -456,19 +2071,176 @@ fn map_expr(expr: sqlast::Expr) -> DbResult<Expr> {
             op: map_binary_op(op)?,
             right: Box::new(map_expr(*right)?),
         }),
+        // A minus sign in front of a number is part of the literal
+        SqlExpr::UnaryOp {
+            op: sqlast::UnaryOperator::Minus,
+            expr,
+        } if matches!(*expr, SqlExpr::Value(sqlast::Value::Number(..))) => {
+            let SqlExpr::Value(sqlast::Value::Number(num, long)) = *expr else {
+                unreachable!("checked by the guard")
+            };
+            Ok(Expr::Literal(map_value(sqlast::Value::Number(
+                format!("-{num}"),
+                long,
+            ))?))
+        }
         SqlExpr::UnaryOp { op, expr } => Ok(Expr::Unary {
             op: map_unary_op(op)?,
             expr: Box::new(map_expr(*expr)?),
         }),
         SqlExpr::Nested(expr) => map_expr(*expr),
+        SqlExpr::Function(func) => map_function(func),
+        // DATE '2024-01-31', TIMESTAMP '2024-01-31 12:00:00'
+        SqlExpr::TypedString { data_type, value } => {
+            let ty = map_data_type(&data_type)?;
+            Value::Text(value)
+                .cast(&ty)
+                .map(Expr::Literal)
+                .map_err(DbError::Parser)
+        }
+        SqlExpr::Cast {
+            expr,
+            data_type,
+            format: None,
+        } => Ok(Expr::Cast {
+            expr: Box::new(map_expr(*expr)?),
+            ty: map_data_type(&data_type)?,
+        }),
+        SqlExpr::IsNull(expr) => Ok(Expr::IsNull {
+            expr: Box::new(map_expr(*expr)?),
+            negated: false,
+        }),
+        SqlExpr::IsNotNull(expr) => Ok(Expr::IsNull {
+            expr: Box::new(map_expr(*expr)?),
+            negated: true,
+        }),
+        SqlExpr::Case {
+            operand,
+            conditions,
+            results,
+            else_result,
+        } => Ok(Expr::Case {
+            operand: operand.map(|o| map_expr(*o).map(Box::new)).transpose()?,
+            branches: conditions
+                .into_iter()
+                .zip(results)
+                .map(|(when, then)| Ok((map_expr(when)?, map_expr(then)?)))
+                .collect::<DbResult<Vec<_>>>()?,
+            else_result: else_result
+                .map(|e| map_expr(*e).map(Box::new))
+                .transpose()?,
+        }),
+        SqlExpr::Substring {
+            expr,
+            substring_from,
+            substring_for,
+            ..
+        } => {
+            let mut args = vec![map_expr(*expr)?];
+            args.push(match substring_from {
+                Some(from) => map_expr(*from)?,
+                None => Expr::Literal(Value::Int(1)),
+            });
+            if let Some(len) = substring_for {
+                args.push(map_expr(*len)?);
+            }
+            Ok(Expr::Function {
+                name: "substr".into(),
+                args,
+            })
+        }
+        SqlExpr::Trim {
+            expr,
+            trim_where: None,
+            trim_what: None,
+            trim_characters: None,
+        } => Ok(Expr::Function {
+            name: "trim".into(),
+            args: vec![map_expr(*expr)?],
+        }),
+        SqlExpr::Trim { .. } => Err(DbError::Parser(
+            "TRIM with LEADING/TRAILING/characters not supported".into(),
+        )),
         _ => Err(DbError::Parser("unsupported expr".into())),
     }
 }
 
+fn map_function(func: sqlast::Function) -> DbResult<Expr> {
+    if func.over.is_some()
+        || func.filter.is_some()
+        || func.distinct
+        || func.null_treatment.is_some()
+        || !func.order_by.is_empty()
+    {
+        return Err(DbError::Parser(format!(
+            "unsupported function call: {func}"
+        )));
+    }
+    if func.name.0.len() != 1 {
+        return Err(DbError::Parser(format!(
+            "qualified function names not supported: {}",
+            func.name
+        )));
+    }
+    let name = normalize_object_name(&func.name)?;
+
+    if let Some(aggregate) = AggregateFunc::from_name(&name) {
+        return map_aggregate(aggregate, func.args);
+    }
+
+    let args = func
+        .args
+        .into_iter()
+        .map(map_function_arg)
+        .collect::<DbResult<Vec<_>>>()?;
+    Ok(Expr::Function { name, args })
+}
+
+/// Map a call of an aggregate function, which takes one argument, or `*`
+/// for `COUNT(*)`.
+fn map_aggregate(func: AggregateFunc, args: Vec<sqlast::FunctionArg>) -> DbResult<Expr> {
+    let [arg] = <[_; 1]>::try_from(args).map_err(|args| {
+        DbError::Parser(format!(
+            "{} expects one argument, got {}",
+            func.name().to_uppercase(),
+            args.len()
+        ))
+    })?;
+    let arg = match arg {
+        sqlast::FunctionArg::Unnamed(sqlast::FunctionArgExpr::Wildcard)
+            if func == AggregateFunc::Count =>
+        {
+            None
+        }
+        arg => Some(Box::new(map_function_arg(arg)?)),
+    };
+    Ok(Expr::Aggregate { func, arg })
+}
+
+fn map_function_arg(arg: sqlast::FunctionArg) -> DbResult<Expr> {
+    match arg {
+        sqlast::FunctionArg::Unnamed(sqlast::FunctionArgExpr::Expr(e)) => map_expr(e),
+        other => Err(DbError::Parser(format!(
+            "unsupported function argument: {other}"
+        ))),
+    }
+}
+
+fn map_data_type(data_type: &sqlast::DataType) -> DbResult<SqlType> {
+    SqlType::from_name(&data_type.to_string())
+        .ok_or_else(|| DbError::Parser(format!("unsupported type: {data_type}")))
+}
+
 fn map_value(value: sqlast::Value) -> DbResult<Value> {
     use sqlast::Value as SqlValue;
 
     match value {
+        SqlValue::Number(num, _) if num.contains(['.', 'e', 'E']) => {
+            let parsed = num
+                .parse::<f64>()
+                .map_err(|_| DbError::Parser(format!("invalid float literal: {num}")))?;
+            Ok(Value::Float(parsed))
+        }
         SqlValue::Number(num, _) => {
             let parsed = num
                 .parse::<i64>()
@@ -476,6 +2248,10 @@ fn map_value(value: sqlast::Value) -> DbResult<Value> {
             Ok(Value::Int(parsed))
         }
         SqlValue::SingleQuotedString(s) => Ok(Value::Text(s)),
+        // X'DEADBEEF'
+        SqlValue::HexStringLiteral(hex) => binary::decode_hex(&hex)
+            .map(Value::Bytes)
+            .ok_or_else(|| DbError::Parser(format!("invalid hex literal: X'{hex}'"))),
         SqlValue::Boolean(b) => Ok(Value::Bool(b)),
         SqlValue::Null => Ok(Value::Null),
         other => Err(DbError::Parser(format!("unsupported literal: {other:?}"))),
@@ -494,6 +2270,10 @@ fn map_binary_op(op: sqlast::BinaryOperator) -> DbResult<BinaryOp> {
         SqlBinary::GtEq => BinaryOp::Ge,
         SqlBinary::And => BinaryOp::And,
         SqlBinary::Or => BinaryOp::Or,
+        SqlBinary::Plus => BinaryOp::Add,
+        SqlBinary::Minus => BinaryOp::Sub,
+        SqlBinary::Multiply => BinaryOp::Mul,
+        SqlBinary::Divide => BinaryOp::Div,
         other => return Err(DbError::Parser(format!("unsupported operator: {other:?}"))),
     })
 }
@@ -519,11 +2299,19 @@ fn normalize_ident_owned(ident: sqlast::Ident) -> String {
     ident.value.to_lowercase()
 }
 
+/// The name of a table or other object. Names in `information_schema` keep
+/// their schema; other qualified names resolve to their first part.
 fn normalize_object_name(name: &sqlast::ObjectName) -> DbResult<String> {
-    name.0
-        .first()
-        .map(|ident| ident.value.to_lowercase())
-        .ok_or_else(|| DbError::Parser("invalid object name".into()))
+    match name.0.as_slice() {
+        [schema, object] if schema.value.eq_ignore_ascii_case("information_schema") => Ok(format!(
+            "information_schema.{}",
+            object.value.to_lowercase()
+        )),
+        parts => parts
+            .first()
+            .map(|ident| ident.value.to_lowercase())
+            .ok_or_else(|| DbError::Parser("invalid object name".into())),
+    }
 }
 
 fn first_name(mut names: Vec<sqlast::ObjectName>) -> DbResult<String> {
@@ -534,16 +2322,13 @@ fn first_name(mut names: Vec<sqlast::ObjectName>) -> DbResult<String> {
 }
 
 /// Extract simple table name for UPDATE/DELETE (no joins allowed).
-fn table_name_from_with_joins(table: &sqlast::TableWithJoins) -> DbResult<String> {
+fn table_ref_without_joins(table: &sqlast::TableWithJoins) -> DbResult<ast::TableRef> {
     if !table.joins.is_empty() {
         return Err(DbError::Parser(
-            "joins not supported in UPDATE/DELETE statements".into(),
+            "joins not supported in UPDATE/DELETE statements; use FROM or USING".into(),
         ));
     }
-    match &table.relation {
-        sqlast::TableFactor::Table { name, .. } => normalize_object_name(name),
-        _ => Err(DbError::Parser("unsupported table factor".into())),
-    }
+    map_table_ref(table)
 }
 
 fn map_index_column(column: Option<&sqlast::OrderByExpr>) -> DbResult<String> {
@@ -8,6 +8,13 @@ fn stmt(sql: &str) -> Statement {
     stmts.remove(0)
 }
 
+fn column(name: &str) -> Expr {
+    Expr::Column {
+        table: None,
+        name: name.into(),
+    }
+}
+
 fn stmts(sql: &str) -> Vec<Statement> {
     parse_sql(sql).expect("parser should succeed")
 }
@@ -42,11 +49,13 @@ fn parse_create_and_drop_statements() {
             table,
             column,
             index_type,
+            unique,
         } => {
             assert_eq!(name, "idx_users_name");
             assert_eq!(table, "users");
             assert_eq!(column, "name");
             assert_eq!(*index_type, IndexType::BTree); // Default type
+            assert!(!unique);
         }
         other => panic!("expected CreateIndex, got {other:?}"),
     }
@@ -65,7 +74,9 @@ fn parse_create_and_drop_statements() {
 fn parse_dml_statements() {
     let insert = stmt("INSERT INTO posts VALUES (42, 'Hello', true)");
     match insert {
-        Statement::Insert { table, values } => {
+        Statement::Insert { table, rows, .. } => {
+            assert_eq!(rows.len(), 1);
+            let values = &rows[0];
             assert_eq!(table, "posts");
             assert_eq!(values.len(), 3);
             assert!(matches!(values[0], Expr::Literal(Value::Int(42))));
@@ -99,6 +110,7 @@ fn parse_dml_statements() {
             table,
             assignments,
             selection,
+            ..
         } => {
             assert_eq!(table, "posts");
             assert_eq!(assignments.len(), 1);
@@ -111,7 +123,9 @@ fn parse_dml_statements() {
 
     let delete = stmt("DELETE FROM posts WHERE title = 'old'");
     match delete {
-        Statement::Delete { table, selection } => {
+        Statement::Delete {
+            table, selection, ..
+        } => {
             assert_eq!(table, "posts");
             assert!(selection.is_some());
         }
@@ -120,20 +134,539 @@ fn parse_dml_statements() {
 }
 
 #[test]
-fn reject_multi_row_insert() {
-    let result = parse_sql("INSERT INTO users VALUES (1), (2)");
-    let err = result.expect_err("multi-row insert should fail");
-    assert!(format!("{err:?}").contains("multi-row"));
+fn parse_row_lock_clauses() {
+    let lock_of = |sql: &str| match stmt(sql) {
+        Statement::Select { lock, .. } => lock,
+        other => panic!("expected Select, got {other:?}"),
+    };
+    assert_eq!(lock_of("SELECT * FROM users"), None);
+    assert_eq!(
+        lock_of("SELECT * FROM users WHERE id = 1 FOR UPDATE"),
+        Some(RowLock::Update)
+    );
+    assert_eq!(
+        lock_of("SELECT id FROM users FOR SHARE NOWAIT"),
+        Some(RowLock::Share)
+    );
+    assert_eq!(
+        lock_of("SELECT id FROM users FOR UPDATE FOR SHARE"),
+        Some(RowLock::Update)
+    );
+
+    let err = parse_sql("SELECT * FROM users FOR UPDATE OF users")
+        .expect_err("FOR UPDATE OF should fail");
+    assert!(format!("{err:?}").contains("FOR UPDATE OF"));
 }
 
 #[test]
-fn reject_aliases_and_accept_joins() {
-    // SELECT aliases not supported.
-    let err = parse_sql("SELECT name AS n FROM users").expect_err("aliases should be rejected");
-    assert!(
-        format!("{err:?}").contains("select aliases not supported"),
-        "{err:?}"
+fn parse_not_null_columns() {
+    match stmt("CREATE TABLE t (id INT NOT NULL, name TEXT NULL, qty INT DEFAULT 1 NOT NULL)") {
+        Statement::CreateTable { columns, .. } => {
+            let not_null: Vec<bool> = columns.iter().map(|c| c.not_null).collect();
+            assert_eq!(not_null, vec![true, false, true]);
+            assert_eq!(columns[2].default, Some(Expr::Literal(Value::Int(1))));
+        }
+        other => panic!("expected CreateTable, got {other:?}"),
+    }
+}
+
+#[test]
+fn parse_auto_increment_columns() {
+    for sql in [
+        "CREATE TABLE t (id SERIAL PRIMARY KEY, name TEXT)",
+        "CREATE TABLE t (id INT AUTO_INCREMENT PRIMARY KEY, name TEXT)",
+        "CREATE TABLE t (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT)",
+    ] {
+        match stmt(sql) {
+            Statement::CreateTable { columns, .. } => {
+                assert!(columns[0].auto_increment, "{sql}");
+                assert!(matches!(columns[0].ty.as_str(), "INT" | "INTEGER"), "{sql}");
+                assert!(!columns[1].auto_increment, "{sql}");
+            }
+            other => panic!("expected CreateTable, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn parse_analyze_table() {
+    assert_eq!(
+        stmt("ANALYZE TABLE Users"),
+        Statement::Analyze {
+            table: "users".into()
+        }
     );
+    assert_eq!(stmt("ANALYZE TABLE users").tables(), vec!["users"]);
+}
+
+#[test]
+fn parse_admin_gc() {
+    assert_eq!(stmt("ADMIN GC"), Statement::AdminGc);
+    assert_eq!(stmt("  admin   gc; "), Statement::AdminGc);
+    assert!(parse_sql("ADMIN REBOOT").is_err());
+}
+
+#[test]
+fn parse_vacuum() {
+    assert_eq!(stmt("VACUUM"), Statement::Vacuum { table: None });
+    assert_eq!(
+        stmt("vacuum Users;"),
+        Statement::Vacuum {
+            table: Some("users".into())
+        }
+    );
+    assert_eq!(stmt("VACUUM users").tables(), vec!["users"]);
+    assert!(stmt("VACUUM").tables().is_empty());
+    assert!(parse_sql("VACUUM users orders").is_err());
+}
+
+#[test]
+fn information_schema_names_keep_their_schema() {
+    assert_eq!(
+        stmt("SELECT * FROM INFORMATION_SCHEMA.Table_Activity").tables(),
+        vec!["information_schema.table_activity"]
+    );
+}
+
+#[test]
+fn table_functions_keep_their_arguments() {
+    let select = stmt("SELECT * FROM GENERATE_SERIES(1, 10, 2) AS g JOIN users u ON u.id = g.n");
+    match &select {
+        Statement::Select { from, .. } => {
+            assert_eq!(from.name, "generate_series");
+            assert_eq!(from.effective_name(), "g");
+            assert_eq!(
+                from.args,
+                Some(vec![
+                    Expr::Literal(Value::Int(1)),
+                    Expr::Literal(Value::Int(10)),
+                    Expr::Literal(Value::Int(2)),
+                ])
+            );
+        }
+        other => panic!("expected Select, got {other:?}"),
+    }
+    assert_eq!(select.tables(), vec!["users"]);
+}
+
+#[test]
+fn where_subqueries_become_semi_and_anti_joins() {
+    let select = stmt(
+        "SELECT name FROM users u WHERE u.age > 30 \
+         AND (EXISTS (SELECT 1 FROM orders o WHERE o.user_id = u.id) \
+         AND NOT EXISTS (SELECT 1 FROM bans)) \
+         AND u.id IN (SELECT p.user_id FROM payments p)",
+    );
+    let Statement::Select {
+        joins, selection, ..
+    } = &select
+    else {
+        panic!("expected Select, got {select:?}");
+    };
+    let types: Vec<_> = joins.iter().map(|join| join.join_type).collect();
+    assert_eq!(types, vec![JoinType::Semi, JoinType::Anti, JoinType::Semi]);
+    assert_eq!(joins[0].table.effective_name(), "o");
+    assert_eq!(joins[0].key, None);
+    assert_eq!(joins[1].condition, Expr::Literal(Value::Bool(true)));
+    assert_eq!(
+        joins[2].key,
+        Some((
+            Expr::Column {
+                table: Some("u".into()),
+                name: "id".into()
+            },
+            Expr::Column {
+                table: Some("p".into()),
+                name: "user_id".into()
+            },
+        ))
+    );
+    // Only the conditions that are not subqueries are left to filter by
+    assert_eq!(
+        selection,
+        &Some(Expr::Binary {
+            left: Box::new(Expr::Column {
+                table: Some("u".into()),
+                name: "age".into()
+            }),
+            op: BinaryOp::Gt,
+            right: Box::new(Expr::Literal(Value::Int(30))),
+        })
+    );
+    assert_eq!(select.tables(), vec!["users", "orders", "bans", "payments"]);
+}
+
+#[test]
+fn unsupported_subqueries_are_rejected() {
+    for (sql, message) in [
+        (
+            "SELECT * FROM t WHERE id NOT IN (SELECT id FROM u)",
+            "use NOT EXISTS",
+        ),
+        (
+            "SELECT * FROM t WHERE id IN (SELECT id, v FROM u)",
+            "exactly one column",
+        ),
+        (
+            "SELECT * FROM t WHERE EXISTS (SELECT 1 FROM u WHERE EXISTS (SELECT 1 FROM v))",
+            "nested subqueries",
+        ),
+        (
+            "SELECT * FROM t WHERE EXISTS (SELECT 1 FROM u LIMIT 1)",
+            "not supported in subqueries",
+        ),
+    ] {
+        let err = parse_sql(sql).unwrap_err();
+        assert!(err.to_string().contains(message), "{sql}: {err}");
+    }
+}
+
+#[test]
+fn select_distinct_groups_by_the_select_list() {
+    let select = stmt("SELECT DISTINCT u.name, age + 1 AS next FROM users u ORDER BY u.name");
+    let Statement::Select { group_by, .. } = &select else {
+        panic!("expected Select, got {select:?}");
+    };
+    assert_eq!(
+        group_by,
+        &vec![
+            Expr::Column {
+                table: Some("u".into()),
+                name: "name".into(),
+            },
+            Expr::Binary {
+                left: Box::new(Expr::Column {
+                    table: None,
+                    name: "age".into(),
+                }),
+                op: BinaryOp::Add,
+                right: Box::new(Expr::Literal(Value::Int(1))),
+            },
+        ]
+    );
+
+    for (sql, message) in [
+        ("SELECT DISTINCT * FROM users", "list the columns"),
+        ("SELECT DISTINCT COUNT(*) FROM users", "aggregate"),
+        ("SELECT DISTINCT age FROM users GROUP BY age", "GROUP BY"),
+        ("SELECT DISTINCT ON (age) name FROM users", "DISTINCT ON"),
+    ] {
+        let err = parse_sql(sql).expect_err(sql);
+        assert!(format!("{err:?}").contains(message), "{sql}: {err:?}");
+    }
+}
+
+#[test]
+fn table_samples_attach_to_the_table_they_follow() {
+    let select = stmt(
+        "SELECT * FROM users u TABLESAMPLE SYSTEM (2.5) REPEATABLE (-3) \
+         JOIN orders TABLESAMPLE bernoulli(10) ON orders.user_id = u.id",
+    );
+    let Statement::Select { from, joins, .. } = &select else {
+        panic!("expected Select, got {select:?}");
+    };
+    assert_eq!(
+        from.sample,
+        Some(TableSample {
+            method: SampleMethod::System,
+            percent: 2.5,
+            seed: Some(-3),
+        })
+    );
+    assert_eq!(
+        joins[0].table.sample,
+        Some(TableSample {
+            method: SampleMethod::Bernoulli,
+            percent: 10.0,
+            seed: None,
+        })
+    );
+
+    // A subquery's table of the same name is a different table
+    let bernoulli = Some(TableSample {
+        method: SampleMethod::Bernoulli,
+        percent: 0.0,
+        seed: None,
+    });
+    let select = stmt(
+        "SELECT COUNT(*) FROM users WHERE EXISTS (SELECT id FROM users TABLESAMPLE BERNOULLI (0))",
+    );
+    let Statement::Select { from, joins, .. } = &select else {
+        panic!("expected Select, got {select:?}");
+    };
+    assert_eq!((from.sample, joins[0].table.sample), (None, bernoulli));
+    let select = stmt(
+        "SELECT COUNT(*) FROM users TABLESAMPLE BERNOULLI (0) WHERE id IN (SELECT id FROM users)",
+    );
+    let Statement::Select { from, joins, .. } = &select else {
+        panic!("expected Select, got {select:?}");
+    };
+    assert_eq!((from.sample, joins[0].table.sample), (bernoulli, None));
+
+    match stmt("EXPLAIN SELECT id FROM users TABLESAMPLE BERNOULLI (1)") {
+        Statement::Explain { query, .. } => {
+            assert!(matches!(*query, Statement::Select { ref from, .. } if from.sample.is_some()))
+        }
+        other => panic!("expected Explain, got {other:?}"),
+    }
+
+    for (sql, message) in [
+        (
+            "SELECT * FROM users TABLESAMPLE RANDOM (1)",
+            "BERNOULLI or SYSTEM",
+        ),
+        (
+            "SELECT * FROM users TABLESAMPLE BERNOULLI (101)",
+            "between 0 and 100",
+        ),
+        (
+            "SELECT * FROM users TABLESAMPLE BERNOULLI 5",
+            "number in parentheses",
+        ),
+        (
+            "SELECT * FROM users TABLESAMPLE SYSTEM (5) REPEATABLE (1.5)",
+            "integer",
+        ),
+        (
+            "DELETE FROM users TABLESAMPLE SYSTEM (5)",
+            "table read by a SELECT",
+        ),
+    ] {
+        let err = parse_sql(sql).expect_err(sql);
+        assert!(format!("{err:?}").contains(message), "{sql}: {err:?}");
+    }
+}
+
+#[test]
+fn parse_show_table_stats() {
+    assert_eq!(
+        stmt("SHOW TABLE STATS Users;"),
+        Statement::ShowTableStats {
+            table: "users".into()
+        }
+    );
+    assert_eq!(stmt("show table stats users").tables(), vec!["users"]);
+    assert!(parse_sql("SHOW TABLE STATS").is_err());
+}
+
+#[test]
+fn parse_alter_table_actions() {
+    assert_eq!(
+        stmt("ALTER TABLE Users ADD COLUMN Score INT DEFAULT 0"),
+        Statement::AlterTable {
+            name: "users".into(),
+            action: AlterTableAction::AddColumn(ColumnDef {
+                name: "score".into(),
+                ty: "INT".into(),
+                default: Some(Expr::Literal(Value::Int(0))),
+                not_null: false,
+                auto_increment: false,
+            }),
+        }
+    );
+    assert_eq!(
+        stmt("ALTER TABLE users DROP COLUMN score"),
+        Statement::AlterTable {
+            name: "users".into(),
+            action: AlterTableAction::DropColumn {
+                name: "score".into()
+            },
+        }
+    );
+    assert_eq!(
+        stmt("ALTER TABLE users RENAME COLUMN name TO full_name"),
+        Statement::AlterTable {
+            name: "users".into(),
+            action: AlterTableAction::RenameColumn {
+                old: "name".into(),
+                new: "full_name".into(),
+            },
+        }
+    );
+
+    let err = parse_sql("ALTER TABLE users ADD COLUMN a INT, DROP COLUMN b")
+        .expect_err("multiple actions should fail");
+    assert!(format!("{err:?}").contains("exactly one action"));
+    let err = parse_sql("ALTER TABLE users RENAME TO people").expect_err("RENAME TO should fail");
+    assert!(format!("{err:?}").contains("unsupported ALTER TABLE action"));
+}
+
+#[test]
+fn parse_set_transaction_isolation_level() {
+    assert_eq!(
+        stmt("SET TRANSACTION ISOLATION LEVEL READ COMMITTED"),
+        Statement::SetTransaction {
+            isolation: IsolationLevel::ReadCommitted,
+            session: false,
+        }
+    );
+    assert_eq!(
+        stmt("SET TRANSACTION ISOLATION LEVEL READ UNCOMMITTED"),
+        Statement::SetTransaction {
+            isolation: IsolationLevel::ReadCommitted,
+            session: false,
+        }
+    );
+    assert_eq!(
+        stmt("SET SESSION CHARACTERISTICS AS TRANSACTION ISOLATION LEVEL REPEATABLE READ"),
+        Statement::SetTransaction {
+            isolation: IsolationLevel::RepeatableRead,
+            session: true,
+        }
+    );
+    assert_eq!(
+        stmt("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE"),
+        Statement::SetTransaction {
+            isolation: IsolationLevel::Serializable,
+            session: false,
+        }
+    );
+
+    let err = parse_sql("SET TRANSACTION READ ONLY").expect_err("access mode should fail");
+    assert!(format!("{err:?}").contains("access mode"));
+}
+
+#[test]
+fn parse_set_random_seed() {
+    assert_eq!(
+        stmt("SET random_seed = 42"),
+        Statement::SetRandomSeed { seed: Some(42) }
+    );
+    assert_eq!(
+        stmt("SET RANDOM_SEED TO -7"),
+        Statement::SetRandomSeed { seed: Some(-7) }
+    );
+    assert_eq!(
+        stmt("SET random_seed = DEFAULT"),
+        Statement::SetRandomSeed { seed: None }
+    );
+
+    let err = parse_sql("SET random_seed = 'abc'").expect_err("text seed should fail");
+    assert!(format!("{err:?}").contains("random_seed must be an integer"));
+}
+
+#[test]
+fn parse_set_priority() {
+    assert_eq!(
+        stmt("SET statement_priority = high"),
+        Statement::SetPriority {
+            priority: Priority::High
+        }
+    );
+    assert_eq!(
+        stmt("SET STATEMENT_PRIORITY TO 'Background'"),
+        Statement::SetPriority {
+            priority: Priority::Background
+        }
+    );
+    assert_eq!(
+        stmt("SET statement_priority = DEFAULT"),
+        Statement::SetPriority {
+            priority: Priority::Normal
+        }
+    );
+
+    let err =
+        parse_sql("SET statement_priority = urgent").expect_err("unknown priority should fail");
+    assert!(format!("{err:?}").contains("statement_priority must be"));
+}
+
+#[test]
+fn parse_set_global() {
+    assert_eq!(
+        stmt("SET GLOBAL Durability = relaxed"),
+        Statement::SetGlobal {
+            name: "durability".into(),
+            value: Some("relaxed".into()),
+        }
+    );
+    assert_eq!(
+        stmt("set global buffer_pages to 512;"),
+        Statement::SetGlobal {
+            name: "buffer_pages".into(),
+            value: Some("512".into()),
+        }
+    );
+    assert_eq!(
+        stmt("SET GLOBAL slow_query_threshold_ms = DEFAULT"),
+        Statement::SetGlobal {
+            name: "slow_query_threshold_ms".into(),
+            value: None,
+        }
+    );
+    assert!(parse_sql("SET GLOBAL durability = (1)").is_err());
+}
+
+#[test]
+fn parse_insert_column_list_and_defaults() {
+    match stmt("INSERT INTO users (Name, id) VALUES ('a', 1)") {
+        Statement::Insert { columns, rows, .. } => {
+            assert_eq!(columns, vec!["name".to_string(), "id".to_string()]);
+            assert_eq!(rows[0].len(), 2);
+        }
+        other => panic!("expected Insert, got {other:?}"),
+    }
+
+    match stmt("CREATE TABLE t (id INT, qty INT DEFAULT 0, note TEXT)") {
+        Statement::CreateTable { columns, .. } => {
+            assert_eq!(columns[0].default, None);
+            assert_eq!(columns[1].default, Some(Expr::Literal(Value::Int(0))));
+        }
+        other => panic!("expected CreateTable, got {other:?}"),
+    }
+
+    let err = parse_sql("INSERT INTO users (id, id) VALUES (1, 2)")
+        .expect_err("duplicate target column should fail");
+    assert!(format!("{err:?}").contains("more than once"));
+
+    let err = parse_sql("INSERT INTO users (id, name) VALUES (1)")
+        .expect_err("row width must match column list");
+    assert!(format!("{err:?}").contains("target columns"));
+}
+
+#[test]
+fn parse_multi_row_insert() {
+    match stmt("INSERT INTO users VALUES (1, 'a'), (2, 'b'), (3, NULL)") {
+        Statement::Insert { table, rows, .. } => {
+            assert_eq!(table, "users");
+            assert_eq!(rows.len(), 3);
+            assert!(rows.iter().all(|row| row.len() == 2));
+            assert!(matches!(rows[2][0], Expr::Literal(Value::Int(3))));
+            assert!(matches!(rows[2][1], Expr::Literal(Value::Null)));
+        }
+        other => panic!("expected Insert, got {other:?}"),
+    }
+}
+
+#[test]
+fn reject_multi_row_insert_with_mismatched_widths() {
+    let result = parse_sql("INSERT INTO users VALUES (1, 'a'), (2)");
+    let err = result.expect_err("ragged VALUES rows should fail");
+    assert!(format!("{err:?}").contains("same number of values"));
+}
+
+#[test]
+fn accept_aliases_and_joins() {
+    // SELECT aliases are carried on the select item.
+    match stmt("SELECT name AS n, UPPER(name) AS loud FROM users") {
+        Statement::Select { columns, .. } => {
+            assert_eq!(
+                columns[0],
+                SelectItem::Expr {
+                    expr: Expr::Column {
+                        table: None,
+                        name: "name".into()
+                    },
+                    alias: Some("n".into()),
+                }
+            );
+            assert!(
+                matches!(&columns[1], SelectItem::Expr { alias: Some(a), .. } if a == "loud"),
+                "{columns:?}"
+            );
+        }
+        other => panic!("expected Select, got {other:?}"),
+    }
 
     // JOINs are now supported.
     let result = parse_sql("SELECT * FROM users u JOIN posts p ON u.id = p.user_id");
@@ -160,8 +693,49 @@ fn reject_multi_table_delete() {
 }
 
 #[test]
-fn drop_rejects_non_table_objects() {
-    let err = parse_sql("DROP VIEW users").expect_err("DROP VIEW should fail");
+fn update_from_and_delete_using_name_their_tables() {
+    match stmt("UPDATE accounts a SET balance = t.amount FROM transfers t WHERE a.id = t.account") {
+        Statement::Update {
+            table, alias, from, ..
+        } => {
+            assert_eq!(table, "accounts");
+            assert_eq!(alias.as_deref(), Some("a"));
+            assert_eq!(
+                from,
+                vec![TableRef {
+                    name: "transfers".into(),
+                    alias: Some("t".into()),
+                    args: None,
+                    sample: None,
+                    values: None,
+                }]
+            );
+        }
+        other => panic!("expected Update, got {other:?}"),
+    }
+
+    let delete = stmt("DELETE FROM orders USING customers c, regions WHERE orders.customer = c.id");
+    assert_eq!(delete.tables(), vec!["orders", "customers", "regions"]);
+    match delete {
+        Statement::Delete { alias, using, .. } => {
+            assert_eq!(alias, None);
+            assert_eq!(using.len(), 2);
+            assert_eq!(using[0].alias.as_deref(), Some("c"));
+        }
+        other => panic!("expected Delete, got {other:?}"),
+    }
+
+    let err = parse_sql("UPDATE a SET x = 1 FROM b JOIN c ON b.id = c.id")
+        .expect_err("joins in FROM are not supported");
+    assert!(
+        format!("{err:?}").contains("joins not supported"),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn drop_rejects_unsupported_objects() {
+    let err = parse_sql("DROP SCHEMA users").expect_err("DROP SCHEMA should fail");
     assert!(format!("{err:?}").contains("unsupported DROP type"));
 }
 
@@ -176,10 +750,56 @@ fn create_index_validates_inputs() {
 }
 
 #[test]
-fn select_requires_from_clause_and_single_table() {
-    let err = parse_sql("SELECT 1").expect_err("FROM clause should be required");
-    assert!(format!("{err:?}").contains("SELECT requires FROM clause"));
+fn select_without_from_reads_a_single_row() {
+    match stmt("SELECT 1 + 1 AS two") {
+        Statement::Select { from, joins, .. } => {
+            assert_eq!(from, TableRef::single_row());
+            assert_eq!(from.table(), None);
+            assert!(joins.is_empty());
+        }
+        other => panic!("expected SELECT, got {other:?}"),
+    }
+
+    let err = parse_sql("SELECT *").expect_err("nothing to expand");
+    assert!(format!("{err:?}").contains("SELECT * requires a FROM clause"));
+}
+
+#[test]
+fn standalone_values_select_every_column() {
+    match stmt("VALUES (1, 'a'), (2, 'b') ORDER BY column1 DESC LIMIT 1") {
+        Statement::Select {
+            columns,
+            from,
+            order_by,
+            limit,
+            ..
+        } => {
+            assert_eq!(columns, vec![SelectItem::Wildcard]);
+            assert_eq!(
+                from.values,
+                Some(vec![
+                    vec![
+                        Expr::Literal(Value::Int(1)),
+                        Expr::Literal(Value::Text("a".into()))
+                    ],
+                    vec![
+                        Expr::Literal(Value::Int(2)),
+                        Expr::Literal(Value::Text("b".into()))
+                    ],
+                ])
+            );
+            assert_eq!(order_by.len(), 1);
+            assert_eq!(limit, Some(1));
+        }
+        other => panic!("expected SELECT, got {other:?}"),
+    }
+
+    let err = parse_sql("VALUES (1, 'a'), (2)").expect_err("ragged rows");
+    assert!(format!("{err:?}").contains("VALUES lists must all have the same"));
+}
 
+#[test]
+fn select_requires_a_single_table() {
     // Implicit cross joins (comma-separated tables) are rejected - use explicit JOIN syntax
     let err = parse_sql("SELECT * FROM users, posts").expect_err("implicit join should fail");
     assert!(
@@ -189,22 +809,56 @@ fn select_requires_from_clause_and_single_table() {
 }
 
 #[test]
-fn insert_requires_values_clause() {
-    let err = parse_sql("INSERT INTO users SELECT 1").expect_err("VALUES clause required");
-    assert!(format!("{err:?}").contains("INSERT expects VALUES list"));
+fn insert_select_takes_its_rows_from_the_query() {
+    match stmt("INSERT INTO users (id) SELECT generate_series FROM generate_series(1, 3)") {
+        Statement::Insert {
+            table,
+            columns,
+            rows,
+            query: Some(query),
+        } => {
+            assert_eq!(table, "users");
+            assert_eq!(columns, vec!["id".to_string()]);
+            assert!(rows.is_empty());
+            assert!(matches!(*query, Statement::Select { .. }));
+        }
+        other => panic!("expected INSERT ... SELECT, got {other:?}"),
+    }
+    assert_eq!(
+        stmt("INSERT INTO archive SELECT * FROM users").tables(),
+        vec!["archive", "users"]
+    );
+
+    let err = parse_sql("INSERT INTO archive SELECT * FROM users FOR UPDATE")
+        .expect_err("locking query should fail");
+    assert!(format!("{err:?}").contains("not allowed in INSERT ... SELECT"));
 }
 
 #[test]
-fn literal_parsing_requires_ints() {
-    let err =
-        parse_sql("INSERT INTO users VALUES (1.5)").expect_err("non-integer literal should fail");
+fn parse_numeric_literals() {
+    match stmt("INSERT INTO prices VALUES (1.5, -2, -0.25, 1e3, .5)") {
+        Statement::Insert { rows, .. } => assert_eq!(
+            rows[0],
+            vec![
+                Expr::Literal(Value::Float(1.5)),
+                Expr::Literal(Value::Int(-2)),
+                Expr::Literal(Value::Float(-0.25)),
+                Expr::Literal(Value::Float(1000.0)),
+                Expr::Literal(Value::Float(0.5)),
+            ]
+        ),
+        other => panic!("expected Insert, got {other:?}"),
+    }
+
+    let err = parse_sql("INSERT INTO users VALUES (99999999999999999999)")
+        .expect_err("out of range int literal should fail");
     assert!(format!("{err:?}").contains("invalid int literal"));
 }
 
 #[test]
 fn unsupported_binary_and_unary_ops_report_errors() {
-    let err = parse_sql("SELECT * FROM users WHERE (id + 1) > 2")
-        .expect_err("arithmetic ops are not supported");
+    let err =
+        parse_sql("SELECT * FROM users WHERE (id % 2) > 0").expect_err("modulo is not supported");
     let msg = format!("{err:?}");
     assert!(msg.contains("unsupported operator"), "{msg}");
 
@@ -319,7 +973,9 @@ fn sql_subset_v1_example_script() {
     }
 
     match &stmts[3] {
-        Statement::Insert { table, values } => {
+        Statement::Insert { table, rows, .. } => {
+            assert_eq!(rows.len(), 1);
+            let values = &rows[0];
             assert_eq!(table, "users");
             assert!(matches!(
                 values.as_slice(),
@@ -334,7 +990,9 @@ fn sql_subset_v1_example_script() {
     }
 
     match &stmts[4] {
-        Statement::Insert { values, .. } => {
+        Statement::Insert { rows, .. } => {
+            assert_eq!(rows.len(), 1);
+            let values = &rows[0];
             assert!(matches!(
                 values.as_slice(),
                 [
@@ -402,20 +1060,183 @@ fn sql_subset_v1_example_script() {
 }
 
 #[test]
-fn reject_create_view_statement() {
-    let err = parse_sql("CREATE VIEW v AS SELECT * FROM users")
-        .expect_err("CREATE VIEW should be rejected");
-    assert!(format!("{err:?}").contains("unsupported statement"));
+fn create_and_drop_view_statements() {
+    assert_eq!(
+        stmt("CREATE VIEW Adults AS SELECT id, name FROM users WHERE age >= 18"),
+        Statement::CreateView {
+            name: "adults".into(),
+            query: "SELECT id, name FROM users WHERE age >= 18".into(),
+        }
+    );
+    assert_eq!(
+        stmt("DROP VIEW adults"),
+        Statement::DropView {
+            name: "adults".into()
+        }
+    );
+
+    for (sql, message) in [
+        ("CREATE VIEW v (a) AS SELECT id FROM users", "column lists"),
+        (
+            "CREATE MATERIALIZED VIEW v AS SELECT id FROM users",
+            "plain CREATE VIEW",
+        ),
+        (
+            "CREATE VIEW v AS SELECT id FROM users FOR UPDATE",
+            "not allowed in a view",
+        ),
+        ("CREATE VIEW v AS SELECT 1 UNION SELECT 2", "SET operations"),
+    ] {
+        let err = parse_sql(sql).expect_err(sql);
+        assert!(format!("{err:?}").contains(message), "{sql}: {err:?}");
+    }
 }
 
 #[test]
-fn select_rejects_values_and_set_operations() {
-    let err = parse_sql("VALUES (1)").expect_err("standalone VALUES should fail");
-    assert!(
-        format!("{err:?}").contains("standalone VALUES not supported"),
-        "{err:?}"
+fn create_table_as_and_temporary_tables() {
+    match stmt("CREATE TEMP TABLE Staged AS SELECT id FROM users WHERE age >= 18") {
+        Statement::CreateTableAs {
+            name,
+            temporary,
+            query,
+        } => {
+            assert_eq!(name, "staged");
+            assert!(temporary);
+            assert_eq!(query.tables(), vec!["users"]);
+        }
+        other => panic!("expected CREATE TABLE AS, got {other:?}"),
+    }
+    assert!(matches!(
+        stmt("CREATE TABLE copy AS SELECT * FROM users"),
+        Statement::CreateTableAs {
+            temporary: false,
+            ..
+        }
+    ));
+    assert!(matches!(
+        stmt("CREATE TEMPORARY TABLE notes (id INT)"),
+        Statement::CreateTable {
+            temporary: true,
+            ..
+        }
+    ));
+
+    for (sql, message) in [
+        (
+            "CREATE TABLE t (id INT) AS SELECT id FROM users",
+            "column lists",
+        ),
+        (
+            "CREATE TABLE t AS SELECT id FROM users FOR UPDATE",
+            "not allowed in CREATE TABLE",
+        ),
+    ] {
+        let err = parse_sql(sql).expect_err(sql);
+        assert!(format!("{err:?}").contains(message), "{sql}: {err:?}");
+    }
+}
+
+#[test]
+fn split_statements_at_top_level_semicolons() {
+    let sql = "CREATE TABLE t (id INT, note TEXT);\n\
+               INSERT INTO t VALUES (1, 'a;b'), (2, 'it''s');\n\
+               -- trailing comment; not a statement\n\
+               SELECT \"odd;name\" FROM t; ;\n\
+               /* only a comment */ ;";
+    assert_eq!(
+        split_statements(sql),
+        vec![
+            "CREATE TABLE t (id INT, note TEXT)",
+            "INSERT INTO t VALUES (1, 'a;b'), (2, 'it''s')",
+            "-- trailing comment; not a statement\nSELECT \"odd;name\" FROM t",
+        ]
     );
+    assert_eq!(split_statements("SELECT 1"), vec!["SELECT 1"]);
+    assert!(split_statements("  ; -- nothing\n").is_empty());
+    // Left whole for the parser to reject
+    assert_eq!(split_statements("SELECT 'open; x"), vec!["SELECT 'open; x"]);
+
+    // Each piece parses on its own, including the non-sqlparser forms
+    let pieces = split_statements("ADMIN GC; COPY t TO 't.json' FORMAT JSON;");
+    assert_eq!(stmt(pieces[0]), Statement::AdminGc);
+    assert!(matches!(
+        stmt(pieces[1]),
+        Statement::CopyTo {
+            format: CopyFormat::Json,
+            ..
+        }
+    ));
+}
 
+#[test]
+fn copy_to_file_in_csv_and_json() {
+    let select = |sql: &str| Box::new(stmt(sql));
+    assert_eq!(
+        stmt("COPY (SELECT id FROM users WHERE id > 1) TO 'out.csv' FORMAT CSV"),
+        Statement::CopyTo {
+            query: select("SELECT id FROM users WHERE id > 1"),
+            path: "out.csv".into(),
+            format: CopyFormat::Csv,
+        }
+    );
+    assert_eq!(
+        stmt("COPY (SELECT * FROM users) TO '/tmp/users.json' format json;"),
+        Statement::CopyTo {
+            query: select("SELECT * FROM users"),
+            path: "/tmp/users.json".into(),
+            format: CopyFormat::Json,
+        }
+    );
+    assert_eq!(
+        stmt("COPY users (id, name) TO 'users.json' WITH (FORMAT json)"),
+        Statement::CopyTo {
+            query: select("SELECT id, name FROM users"),
+            path: "users.json".into(),
+            format: CopyFormat::Json,
+        }
+    );
+    assert_eq!(
+        stmt("COPY Users TO 'users.csv'"),
+        Statement::CopyTo {
+            query: select("SELECT * FROM users"),
+            path: "users.csv".into(),
+            format: CopyFormat::Csv,
+        }
+    );
+    assert_eq!(
+        stmt("COPY (SELECT * FROM users) TO 'users.csv' CSV")
+            .tables()
+            .as_slice(),
+        ["users"]
+    );
+
+    for (sql, message) in [
+        ("COPY users FROM 'users.csv'", "FROM not supported"),
+        ("COPY users TO STDOUT", "only writes to a file"),
+        (
+            "COPY users TO 'users.xml' FORMAT xml",
+            "unsupported COPY format",
+        ),
+        (
+            "COPY users TO 'users.csv' WITH (FORMAT csv) FORMAT json",
+            "more than once",
+        ),
+        (
+            "COPY users TO 'users.csv' WITH (HEADER)",
+            "unsupported COPY option",
+        ),
+        (
+            "COPY (SELECT * FROM users FOR UPDATE) TO 'users.csv'",
+            "not allowed in COPY",
+        ),
+    ] {
+        let err = parse_sql(sql).expect_err(sql);
+        assert!(format!("{err:?}").contains(message), "{sql}: {err:?}");
+    }
+}
+
+#[test]
+fn select_rejects_set_operations() {
     let err = parse_sql("SELECT 1 UNION SELECT 2").expect_err("SET ops should fail");
     assert!(
         format!("{err:?}").contains("SET operations not supported"),
@@ -431,7 +1252,7 @@ fn select_item_limitations() {
         "{err:?}"
     );
 
-    let err = parse_sql("SELECT id + 1 FROM users")
+    let err = parse_sql("SELECT id % 2 FROM users")
         .expect_err("complex projection expressions should fail");
     assert!(
         format!("{err:?}").contains("unsupported select item"),
@@ -481,8 +1302,9 @@ fn compound_identifiers_and_nested_exprs_parse() {
 
 #[test]
 fn unsupported_exists_expressions_report_errors() {
-    let err = parse_sql("SELECT * FROM users WHERE EXISTS (SELECT 1 FROM users)")
-        .expect_err("EXISTS expressions should fail");
+    // Only a subquery AND-ed into WHERE becomes a join
+    let err = parse_sql("SELECT * FROM users WHERE id = 1 OR EXISTS (SELECT 1 FROM users)")
+        .expect_err("EXISTS under OR should fail");
     assert!(format!("{err:?}").contains("unsupported expr"), "{err:?}");
 }
 
@@ -490,7 +1312,9 @@ fn unsupported_exists_expressions_report_errors() {
 fn boolean_and_null_literals_are_supported() {
     let stmt = stmt("INSERT INTO flags VALUES (TRUE, NULL)");
     match stmt {
-        Statement::Insert { values, .. } => {
+        Statement::Insert { rows, .. } => {
+            assert_eq!(rows.len(), 1);
+            let values = &rows[0];
             assert!(matches!(values[0], Expr::Literal(Value::Bool(true))));
             assert!(matches!(values[1], Expr::Literal(Value::Null)));
         }
@@ -593,78 +1417,290 @@ fn create_index_default_is_btree() {
 }
 
 #[test]
-fn not_equal_operator_is_supported() {
-    let sql = "SELECT * FROM users WHERE id != 5";
-    let stmt = stmt(sql);
-    match stmt {
-        Statement::Select { selection, .. } => {
-            let selection = selection.expect("WHERE clause required");
-            match selection {
-                Expr::Binary { op, .. } => {
-                    assert_eq!(op, expr::BinaryOp::Ne, "expected NotEq operator");
-                }
-                other => panic!("expected binary comparison, got {other:?}"),
-            }
+fn create_unique_index() {
+    let stmt = stmt("CREATE UNIQUE INDEX idx_users_email ON users USING HASH (email)");
+    match stmt {
+        Statement::CreateIndex {
+            name,
+            index_type,
+            unique,
+            ..
+        } => {
+            assert_eq!(name, "idx_users_email");
+            assert_eq!(index_type, IndexType::Hash);
+            assert!(unique);
+        }
+        other => panic!("expected CreateIndex, got {other:?}"),
+    }
+}
+
+#[test]
+fn not_equal_operator_is_supported() {
+    let sql = "SELECT * FROM users WHERE id != 5";
+    let stmt = stmt(sql);
+    match stmt {
+        Statement::Select { selection, .. } => {
+            let selection = selection.expect("WHERE clause required");
+            match selection {
+                Expr::Binary { op, .. } => {
+                    assert_eq!(op, expr::BinaryOp::Ne, "expected NotEq operator");
+                }
+                other => panic!("expected binary comparison, got {other:?}"),
+            }
+        }
+        other => panic!("expected Select, got {other:?}"),
+    }
+}
+
+#[test]
+fn not_operator_is_supported() {
+    let sql = "SELECT * FROM users WHERE NOT active";
+    let stmt = stmt(sql);
+    match stmt {
+        Statement::Select { selection, .. } => {
+            let selection = selection.expect("WHERE clause required");
+            match selection {
+                Expr::Unary { op, .. } => {
+                    assert_eq!(op, expr::UnaryOp::Not, "expected NOT operator");
+                }
+                other => panic!("expected unary NOT, got {other:?}"),
+            }
+        }
+        other => panic!("expected Select, got {other:?}"),
+    }
+}
+
+#[test]
+fn or_operator_is_supported() {
+    let sql = "SELECT * FROM users WHERE id = 1 OR id = 2";
+    let stmt = stmt(sql);
+    match stmt {
+        Statement::Select { selection, .. } => {
+            let selection = selection.expect("WHERE clause required");
+            match selection {
+                Expr::Binary { op, .. } => {
+                    assert_eq!(op, expr::BinaryOp::Or, "expected OR operator");
+                }
+                other => panic!("expected binary OR, got {other:?}"),
+            }
+        }
+        other => panic!("expected Select, got {other:?}"),
+    }
+}
+
+#[test]
+fn update_assignment_requires_valid_expressions() {
+    // Test UPDATE with complex unsupported expression in assignment
+    let err = parse_sql("UPDATE users SET score = score % 10 WHERE id = 1")
+        .expect_err("modulo in assignment should fail");
+    assert!(
+        format!("{err:?}").contains("unsupported operator"),
+        "expected unsupported operator error, got: {err:?}"
+    );
+}
+
+#[test]
+fn invalid_sql_syntax_produces_parse_error() {
+    // Test completely invalid SQL to trigger sqlparser error
+    let err = parse_sql("SELECTTTT FROMM users WHEREE").expect_err("invalid SQL should fail");
+    assert!(
+        format!("{err:?}").contains("SQL parse error"),
+        "expected SQL parse error, got: {err:?}"
+    );
+}
+
+#[test]
+fn create_table_with_row_version_option() {
+    match stmt("CREATE TABLE accounts (id INT, balance INT) WITH (row_version = true)") {
+        Statement::CreateTable { row_version, .. } => assert!(row_version),
+        other => panic!("expected CreateTable, got {other:?}"),
+    }
+    match stmt("CREATE TABLE accounts (id INT)") {
+        Statement::CreateTable { row_version, .. } => assert!(!row_version),
+        other => panic!("expected CreateTable, got {other:?}"),
+    }
+
+    let err = parse_sql("CREATE TABLE t (id INT) WITH (autovacuum_enabled = false)")
+        .expect_err("unknown table option should fail");
+    assert!(format!("{err:?}").contains("unsupported table option"));
+}
+
+#[test]
+fn create_table_with_audit_option() {
+    match stmt("CREATE TABLE ledger (id INT) WITH (audit = true, row_version = true)") {
+        Statement::CreateTable {
+            audit, row_version, ..
+        } => assert!(audit && row_version),
+        other => panic!("expected CreateTable, got {other:?}"),
+    }
+    match stmt("CREATE TABLE ledger (id INT)") {
+        Statement::CreateTable { audit, .. } => assert!(!audit),
+        other => panic!("expected CreateTable, got {other:?}"),
+    }
+
+    let err = parse_sql("CREATE TABLE t (id INT) WITH (audit = 1)")
+        .expect_err("non-boolean audit option should fail");
+    assert!(format!("{err:?}").contains("audit expects true or false"));
+}
+
+#[test]
+fn create_table_with_fillfactor_option() {
+    match stmt("CREATE TABLE hot (id INT) WITH (fillfactor = 70)") {
+        Statement::CreateTable { fillfactor, .. } => assert_eq!(fillfactor, Some(70)),
+        other => panic!("expected CreateTable, got {other:?}"),
+    }
+    match stmt("CREATE TABLE cold (id INT)") {
+        Statement::CreateTable { fillfactor, .. } => assert_eq!(fillfactor, None),
+        other => panic!("expected CreateTable, got {other:?}"),
+    }
+
+    for bad in ["5", "101", "true"] {
+        let sql = format!("CREATE TABLE t (id INT) WITH (fillfactor = {bad})");
+        let err = parse_sql(&sql).expect_err("invalid fillfactor should fail");
+        assert!(format!("{err:?}").contains("fillfactor"), "{err:?}");
+    }
+}
+
+#[test]
+fn create_table_with_compression_option() {
+    match stmt("CREATE TABLE notes (id INT, body TEXT) WITH (compression = 'zstd')") {
+        Statement::CreateTable { compression, .. } => assert_eq!(compression, Compression::Zstd),
+        other => panic!("expected CreateTable, got {other:?}"),
+    }
+    match stmt("CREATE TABLE notes (id INT) WITH (compression = lz4, fillfactor = 90)") {
+        Statement::CreateTable { compression, .. } => assert_eq!(compression, Compression::Lz4),
+        other => panic!("expected CreateTable, got {other:?}"),
+    }
+    match stmt("CREATE TABLE plain (id INT)") {
+        Statement::CreateTable { compression, .. } => assert_eq!(compression, Compression::None),
+        other => panic!("expected CreateTable, got {other:?}"),
+    }
+
+    let err = parse_sql("CREATE TABLE t (id INT) WITH (compression = 'gzip')")
+        .expect_err("unknown codec should fail");
+    assert!(
+        format!("{err:?}").contains("unknown compression 'gzip'"),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn create_table_with_engine() {
+    match stmt("CREATE TABLE cache (id INT) ENGINE = memory") {
+        Statement::CreateTable { engine, .. } => assert_eq!(engine.as_deref(), Some("memory")),
+        other => panic!("expected CreateTable, got {other:?}"),
+    }
+    match stmt("CREATE TABLE t (id INT)") {
+        Statement::CreateTable { engine, .. } => assert_eq!(engine, None),
+        other => panic!("expected CreateTable, got {other:?}"),
+    }
+}
+
+#[test]
+fn create_table_using_engine() {
+    match stmt("CREATE TABLE facts (id INT, region TEXT) USING COLUMNAR") {
+        Statement::CreateTable { name, engine, .. } => {
+            assert_eq!(name, "facts");
+            assert_eq!(engine.as_deref(), Some("COLUMNAR"));
         }
-        other => panic!("expected Select, got {other:?}"),
+        other => panic!("expected CreateTable, got {other:?}"),
     }
+    match stmt("CREATE TABLE facts (id INT) USING columnar WITH (fillfactor = 90);") {
+        Statement::CreateTable {
+            engine, fillfactor, ..
+        } => {
+            assert_eq!(engine.as_deref(), Some("columnar"));
+            assert_eq!(fillfactor, Some(90));
+        }
+        other => panic!("expected CreateTable, got {other:?}"),
+    }
+
+    let err = parse_sql("CREATE TABLE t (id INT) USING columnar ENGINE = lsm")
+        .expect_err("two engines should fail");
+    assert!(format!("{err:?}").contains("one storage engine"), "{err:?}");
 }
 
 #[test]
-fn not_operator_is_supported() {
-    let sql = "SELECT * FROM users WHERE NOT active";
-    let stmt = stmt(sql);
-    match stmt {
-        Statement::Select { selection, .. } => {
-            let selection = selection.expect("WHERE clause required");
-            match selection {
-                Expr::Unary { op, .. } => {
-                    assert_eq!(op, expr::UnaryOp::Not, "expected NOT operator");
-                }
-                other => panic!("expected unary NOT, got {other:?}"),
-            }
+fn create_table_partition_by_range_and_list() {
+    match stmt(
+        "CREATE TABLE events (id INT PRIMARY KEY, day INT) PARTITION BY RANGE (day) ( \
+         PARTITION p1 VALUES LESS THAN (-5), PARTITION pmax VALUES LESS THAN (MAXVALUE));",
+    ) {
+        Statement::CreateTable {
+            name,
+            primary_key,
+            partition_by,
+            ..
+        } => {
+            assert_eq!(name, "events");
+            assert_eq!(primary_key, Some(vec!["id".to_string()]));
+            assert_eq!(
+                partition_by,
+                Some(PartitionBy {
+                    method: PartitionMethod::Range,
+                    column: "day".into(),
+                    partitions: vec![
+                        PartitionDef {
+                            name: "p1".into(),
+                            values: PartitionValues::LessThan(Some(Value::Int(-5))),
+                        },
+                        PartitionDef {
+                            name: "pmax".into(),
+                            values: PartitionValues::LessThan(None),
+                        },
+                    ],
+                })
+            );
         }
-        other => panic!("expected Select, got {other:?}"),
+        other => panic!("expected CreateTable, got {other:?}"),
     }
-}
 
-#[test]
-fn or_operator_is_supported() {
-    let sql = "SELECT * FROM users WHERE id = 1 OR id = 2";
-    let stmt = stmt(sql);
-    match stmt {
-        Statement::Select { selection, .. } => {
-            let selection = selection.expect("WHERE clause required");
-            match selection {
-                Expr::Binary { op, .. } => {
-                    assert_eq!(op, expr::BinaryOp::Or, "expected OR operator");
-                }
-                other => panic!("expected binary OR, got {other:?}"),
-            }
+    match stmt(
+        "CREATE TABLE stores (id INT, region TEXT) PARTITION BY LIST (region) ( \
+         PARTITION east VALUES IN ('ny', 'bos'), PARTITION other VALUES IN (NULL))",
+    ) {
+        Statement::CreateTable { partition_by, .. } => {
+            let partition_by = partition_by.expect("partitioned");
+            assert_eq!(partition_by.method, PartitionMethod::List);
+            assert_eq!(
+                partition_by.partitions[0].values,
+                PartitionValues::In(vec![Value::Text("ny".into()), Value::Text("bos".into())])
+            );
+            assert_eq!(
+                partition_by.partitions[1].values,
+                PartitionValues::In(vec![Value::Null])
+            );
         }
-        other => panic!("expected Select, got {other:?}"),
+        other => panic!("expected CreateTable, got {other:?}"),
     }
-}
 
-#[test]
-fn update_assignment_requires_valid_expressions() {
-    // Test UPDATE with complex unsupported expression in assignment
-    let err = parse_sql("UPDATE users SET score = score + 10 WHERE id = 1")
-        .expect_err("arithmetic in assignment should fail");
-    assert!(
-        format!("{err:?}").contains("unsupported operator"),
-        "expected unsupported operator error, got: {err:?}"
-    );
+    let err = parse_sql(
+        "CREATE TABLE t (id INT) PARTITION BY RANGE (id) (PARTITION p VALUES LESS THAN (id))",
+    )
+    .expect_err("non-literal bound should fail");
+    assert!(format!("{err:?}").contains("partition bounds must be literals"));
+    let err = parse_sql("CREATE TABLE t (id INT) PARTITION BY HASH (id) (PARTITION p)")
+        .expect_err("hash partitioning is not supported");
+    assert!(format!("{err:?}").contains("RANGE or LIST"), "{err:?}");
 }
 
 #[test]
-fn invalid_sql_syntax_produces_parse_error() {
-    // Test completely invalid SQL to trigger sqlparser error
-    let err = parse_sql("SELECTTTT FROMM users WHEREE").expect_err("invalid SQL should fail");
-    assert!(
-        format!("{err:?}").contains("SQL parse error"),
-        "expected SQL parse error, got: {err:?}"
+fn statement_tables_lists_every_referenced_table() {
+    let tables = |sql: &str| {
+        stmt(sql)
+            .tables()
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        tables("SELECT * FROM users u JOIN orders o ON u.id = o.user_id"),
+        vec!["users", "orders"]
     );
+    assert_eq!(tables("EXPLAIN SELECT * FROM users"), vec!["users"]);
+    assert_eq!(tables("DELETE FROM users WHERE id = 1"), vec!["users"]);
+    assert_eq!(tables("CREATE INDEX idx ON users (name)"), vec!["users"]);
+    assert!(tables("DROP INDEX idx").is_empty());
 }
 
 #[test]
@@ -677,6 +1713,7 @@ fn create_table_with_single_column_primary_key() {
             name,
             columns,
             primary_key,
+            ..
         } => {
             assert_eq!(name, "users");
             assert_eq!(columns.len(), 2);
@@ -800,6 +1837,31 @@ fn explain_analyze_select() {
     }
 }
 
+#[test]
+fn profile_wraps_one_query_or_write() {
+    let stmts = parse_sql("  profile SELECT * FROM users WHERE id > 10;").unwrap();
+    match stmts.as_slice() {
+        [Statement::Profile { query }] => {
+            assert!(matches!(&**query, Statement::Select { from, .. } if from.name == "users"));
+        }
+        other => panic!("expected PROFILE statement, got {other:?}"),
+    }
+    assert!(matches!(
+        parse_sql("PROFILE DELETE FROM users").unwrap().as_slice(),
+        [Statement::Profile { query }] if matches!(**query, Statement::Delete { .. })
+    ));
+
+    let err = parse_sql("PROFILE CREATE TABLE t (id INT)").unwrap_err();
+    assert!(err.to_string().contains("PROFILE supports"), "{err}");
+    let err = parse_sql("PROFILE SELECT 1 FROM a; SELECT 2 FROM b").unwrap_err();
+    assert!(err.to_string().contains("single statement"), "{err}");
+    // A column named profile is not the statement
+    assert!(matches!(
+        parse_sql("SELECT profile FROM users").unwrap().as_slice(),
+        [Statement::Select { .. }]
+    ));
+}
+
 #[test]
 fn create_table_primary_key_case_insensitive() {
     let stmts = parse_sql("CREATE TABLE users (ID INT, NAME TEXT, PRIMARY KEY (ID))").unwrap();
@@ -831,7 +1893,7 @@ fn select_with_order_by_asc() {
             assert_eq!(columns, vec![SelectItem::Wildcard]);
             assert!(selection.is_none());
             assert_eq!(order_by.len(), 1);
-            assert_eq!(order_by[0].column, "name");
+            assert_eq!(order_by[0].expr, column("name"));
             assert_eq!(order_by[0].direction, SortDirection::Asc);
             assert!(limit.is_none());
             assert!(offset.is_none());
@@ -846,7 +1908,7 @@ fn select_with_order_by_desc() {
     match stmt {
         Statement::Select { order_by, .. } => {
             assert_eq!(order_by.len(), 1);
-            assert_eq!(order_by[0].column, "age");
+            assert_eq!(order_by[0].expr, column("age"));
             assert_eq!(order_by[0].direction, SortDirection::Desc);
         }
         other => panic!("expected Select, got {other:?}"),
@@ -859,9 +1921,25 @@ fn select_with_multiple_order_by() {
     match stmt {
         Statement::Select { order_by, .. } => {
             assert_eq!(order_by.len(), 2);
-            assert_eq!(order_by[0].column, "age");
+            assert_eq!(order_by[0].expr, column("age"));
             assert_eq!(order_by[0].direction, SortDirection::Desc);
-            assert_eq!(order_by[1].column, "name");
+            assert_eq!(order_by[1].expr, column("name"));
+            assert_eq!(order_by[1].direction, SortDirection::Asc);
+        }
+        other => panic!("expected Select, got {other:?}"),
+    }
+}
+
+#[test]
+fn select_with_order_by_nulls_placement() {
+    let stmt = stmt("SELECT * FROM users ORDER BY age DESC NULLS FIRST, name NULLS LAST, id DESC");
+    match stmt {
+        Statement::Select { order_by, .. } => {
+            let placements: Vec<_> = order_by.iter().map(|o| o.nulls).collect();
+            assert_eq!(
+                placements,
+                vec![NullsOrder::First, NullsOrder::Last, NullsOrder::Last]
+            );
             assert_eq!(order_by[1].direction, SortDirection::Asc);
         }
         other => panic!("expected Select, got {other:?}"),
@@ -874,7 +1952,7 @@ fn select_with_order_by_default_direction() {
     match stmt {
         Statement::Select { order_by, .. } => {
             assert_eq!(order_by.len(), 1);
-            assert_eq!(order_by[0].column, "name");
+            assert_eq!(order_by[0].expr, column("name"));
             assert_eq!(order_by[0].direction, SortDirection::Asc);
         }
         other => panic!("expected Select, got {other:?}"),
@@ -937,7 +2015,7 @@ fn select_with_order_by_and_limit() {
             order_by, limit, ..
         } => {
             assert_eq!(order_by.len(), 1);
-            assert_eq!(order_by[0].column, "age");
+            assert_eq!(order_by[0].expr, column("age"));
             assert_eq!(order_by[0].direction, SortDirection::Desc);
             assert_eq!(limit, Some(5));
         }
@@ -953,7 +2031,7 @@ fn select_with_order_by_and_offset() {
             order_by, offset, ..
         } => {
             assert_eq!(order_by.len(), 1);
-            assert_eq!(order_by[0].column, "name");
+            assert_eq!(order_by[0].expr, column("name"));
             assert_eq!(offset, Some(100));
         }
         other => panic!("expected Select, got {other:?}"),
@@ -979,9 +2057,9 @@ fn select_with_order_by_limit_and_offset() {
                 ]
             );
             assert_eq!(order_by.len(), 2);
-            assert_eq!(order_by[0].column, "age");
+            assert_eq!(order_by[0].expr, column("age"));
             assert_eq!(order_by[0].direction, SortDirection::Desc);
-            assert_eq!(order_by[1].column, "name");
+            assert_eq!(order_by[1].expr, column("name"));
             assert_eq!(order_by[1].direction, SortDirection::Asc);
             assert_eq!(limit, Some(10));
             assert_eq!(offset, Some(20));
@@ -991,23 +2069,28 @@ fn select_with_order_by_limit_and_offset() {
 }
 
 #[test]
-fn select_order_by_rejects_qualified_columns() {
-    let err = parse_sql("SELECT * FROM users ORDER BY users.name")
-        .expect_err("qualified column names in ORDER BY should fail");
-    assert!(
-        format!("{err:?}").contains("qualified column names not supported in ORDER BY"),
-        "{err:?}"
-    );
-}
-
-#[test]
-fn select_order_by_rejects_expressions() {
-    let err = parse_sql("SELECT * FROM users ORDER BY age + 1")
-        .expect_err("expressions in ORDER BY should fail");
-    assert!(
-        format!("{err:?}").contains("ORDER BY supports column names only"),
-        "{err:?}"
-    );
+fn select_order_by_accepts_expressions_and_qualified_columns() {
+    match stmt("SELECT UPPER(name) AS shout FROM users u ORDER BY u.id, LENGTH(name) DESC, shout") {
+        Statement::Select { order_by, .. } => {
+            assert_eq!(
+                order_by[0].expr,
+                Expr::Column {
+                    table: Some("u".into()),
+                    name: "id".into(),
+                }
+            );
+            assert_eq!(
+                order_by[1].expr,
+                Expr::Function {
+                    name: "length".into(),
+                    args: vec![column("name")],
+                }
+            );
+            assert_eq!(order_by[1].direction, SortDirection::Desc);
+            assert_eq!(order_by[2].expr, column("shout"));
+        }
+        other => panic!("expected Select, got {other:?}"),
+    }
 }
 
 #[test]
@@ -1047,3 +2130,378 @@ fn select_offset_rejects_non_numeric_values() {
         "{err:?}"
     );
 }
+
+#[test]
+fn scalar_function_calls_parse_in_projections_and_predicates() {
+    let stmt = stmt("SELECT UPPER(name), id FROM users WHERE LENGTH(TRIM(name)) > 3");
+    match stmt {
+        Statement::Select {
+            columns, selection, ..
+        } => {
+            assert_eq!(
+                columns[0],
+                SelectItem::Expr {
+                    expr: Expr::Function {
+                        name: "upper".into(),
+                        args: vec![Expr::Column {
+                            table: None,
+                            name: "name".into()
+                        }],
+                    },
+                    alias: None,
+                }
+            );
+            assert_eq!(columns[1], SelectItem::Column("id".into()));
+            match selection.expect("WHERE clause required") {
+                Expr::Binary { left, .. } => match *left {
+                    Expr::Function { name, args } => {
+                        assert_eq!(name, "length");
+                        assert!(
+                            matches!(&args[0], Expr::Function { name, .. } if name == "trim"),
+                            "TRIM should map to a function call: {args:?}"
+                        );
+                    }
+                    other => panic!("expected function call, got {other:?}"),
+                },
+                other => panic!("expected binary comparison, got {other:?}"),
+            }
+        }
+        other => panic!("expected Select, got {other:?}"),
+    }
+}
+
+#[test]
+fn substring_syntax_maps_to_substr() {
+    let stmt = stmt("SELECT SUBSTRING(name FROM 2 FOR 3) FROM users");
+    match stmt {
+        Statement::Select { columns, .. } => match &columns[0] {
+            SelectItem::Expr {
+                expr: Expr::Function { name, args },
+                ..
+            } => {
+                assert_eq!(name, "substr");
+                assert_eq!(args.len(), 3);
+            }
+            other => panic!("expected substr call, got {other:?}"),
+        },
+        other => panic!("expected Select, got {other:?}"),
+    }
+}
+
+#[test]
+fn typed_string_literals_parse_to_temporal_values() {
+    let sql = "SELECT id FROM events \
+               WHERE day = DATE '2024-05-01' AND at < TIMESTAMP '2024-05-01 08:30:00'";
+    match stmt(sql) {
+        Statement::Select {
+            selection: Some(Expr::Binary { left, right, .. }),
+            ..
+        } => {
+            assert_eq!(
+                *left,
+                Expr::Binary {
+                    left: Box::new(column("day")),
+                    op: BinaryOp::Eq,
+                    right: Box::new(Expr::Literal(Value::Date(19844))),
+                }
+            );
+            assert_eq!(
+                *right,
+                Expr::Binary {
+                    left: Box::new(column("at")),
+                    op: BinaryOp::Lt,
+                    right: Box::new(Expr::Literal(Value::Timestamp(
+                        19844 * 86_400_000_000 + 30_600_000_000
+                    ))),
+                }
+            );
+        }
+        other => panic!("expected Select with WHERE, got {other:?}"),
+    }
+
+    let err = parse_sql("SELECT id FROM events WHERE day = DATE '2024-02-30'")
+        .expect_err("invalid date should fail");
+    assert!(format!("{err:?}").contains("cannot cast"), "{err:?}");
+}
+
+#[test]
+fn cast_syntax_maps_to_cast_expression() {
+    match stmt("SELECT CAST(at AS DATE), id::TEXT FROM events") {
+        Statement::Select { columns, .. } => {
+            assert_eq!(
+                columns[0],
+                SelectItem::Expr {
+                    expr: Expr::Cast {
+                        expr: Box::new(column("at")),
+                        ty: SqlType::Date,
+                    },
+                    alias: None,
+                }
+            );
+            assert!(
+                matches!(
+                    &columns[1],
+                    SelectItem::Expr {
+                        expr: Expr::Cast {
+                            ty: SqlType::Text,
+                            ..
+                        },
+                        ..
+                    }
+                ),
+                "{columns:?}"
+            );
+        }
+        other => panic!("expected Select, got {other:?}"),
+    }
+
+    let err = parse_sql("SELECT CAST(id AS INTERVAL) FROM events")
+        .expect_err("unknown cast target should fail");
+    assert!(format!("{err:?}").contains("unsupported type"), "{err:?}");
+}
+
+#[test]
+fn case_expressions_keep_their_branches_in_order() {
+    match stmt(
+        "SELECT CASE WHEN age < 18 THEN 'minor' ELSE 'adult' END, \
+         CASE kind WHEN 1 THEN 'a' WHEN 2 THEN 'b' END FROM users",
+    ) {
+        Statement::Select { columns, .. } => {
+            let SelectItem::Expr { expr: searched, .. } = &columns[0] else {
+                panic!("expected an expression, got {:?}", columns[0]);
+            };
+            assert_eq!(
+                *searched,
+                Expr::Case {
+                    operand: None,
+                    branches: vec![(
+                        Expr::Binary {
+                            left: Box::new(column("age")),
+                            op: BinaryOp::Lt,
+                            right: Box::new(Expr::Literal(Value::Int(18))),
+                        },
+                        Expr::Literal(Value::Text("minor".into())),
+                    )],
+                    else_result: Some(Box::new(Expr::Literal(Value::Text("adult".into())))),
+                }
+            );
+            let SelectItem::Expr { expr: simple, .. } = &columns[1] else {
+                panic!("expected an expression, got {:?}", columns[1]);
+            };
+            assert_eq!(
+                simple.to_string(),
+                "CASE kind WHEN 1 THEN 'a' WHEN 2 THEN 'b' END"
+            );
+        }
+        other => panic!("expected Select, got {other:?}"),
+    }
+}
+
+#[test]
+fn is_null_maps_to_is_null_expression() {
+    match stmt("SELECT * FROM emp WHERE mgr IS NULL OR name IS NOT NULL") {
+        Statement::Select { selection, .. } => assert_eq!(
+            selection,
+            Some(Expr::Binary {
+                left: Box::new(Expr::IsNull {
+                    expr: Box::new(column("mgr")),
+                    negated: false,
+                }),
+                op: BinaryOp::Or,
+                right: Box::new(Expr::IsNull {
+                    expr: Box::new(column("name")),
+                    negated: true,
+                }),
+            })
+        ),
+        other => panic!("expected Select, got {other:?}"),
+    }
+
+    let Statement::Select { columns, .. } =
+        stmt("SELECT CASE WHEN mgr IS NULL THEN 'top' END FROM emp")
+    else {
+        panic!("expected Select");
+    };
+    let SelectItem::Expr { expr, .. } = &columns[0] else {
+        panic!("expected an expression, got {:?}", columns[0]);
+    };
+    assert_eq!(expr.to_string(), "CASE WHEN mgr IS NULL THEN 'top' END");
+}
+
+#[test]
+fn arithmetic_operators_parse_with_sql_precedence() {
+    match stmt("SELECT price * qty + 1 FROM items WHERE price / 2 - 1 > 0") {
+        Statement::Select {
+            columns, selection, ..
+        } => {
+            let binary = |left, op, right| Expr::Binary {
+                left: Box::new(left),
+                op,
+                right: Box::new(right),
+            };
+            assert_eq!(
+                columns[0],
+                SelectItem::Expr {
+                    expr: binary(
+                        binary(column("price"), BinaryOp::Mul, column("qty")),
+                        BinaryOp::Add,
+                        Expr::Literal(Value::Int(1)),
+                    ),
+                    alias: None,
+                }
+            );
+            assert_eq!(
+                selection,
+                Some(binary(
+                    binary(
+                        binary(column("price"), BinaryOp::Div, Expr::Literal(Value::Int(2))),
+                        BinaryOp::Sub,
+                        Expr::Literal(Value::Int(1)),
+                    ),
+                    BinaryOp::Gt,
+                    Expr::Literal(Value::Int(0)),
+                ))
+            );
+        }
+        other => panic!("expected Select, got {other:?}"),
+    }
+}
+
+#[test]
+fn decimal_casts_keep_precision_and_scale() {
+    match stmt("SELECT CAST(price AS NUMERIC(10, 2)) FROM items") {
+        Statement::Select { columns, .. } => assert_eq!(
+            columns[0],
+            SelectItem::Expr {
+                expr: Expr::Cast {
+                    expr: Box::new(column("price")),
+                    ty: SqlType::Decimal {
+                        precision: 10,
+                        scale: 2
+                    },
+                },
+                alias: None,
+            }
+        ),
+        other => panic!("expected Select, got {other:?}"),
+    }
+}
+
+#[test]
+fn hex_literals_parse_to_bytes() {
+    match stmt("INSERT INTO files VALUES (1, X'CAFE', '\\xbeef')") {
+        Statement::Insert { rows, .. } => {
+            assert_eq!(rows[0][1], Expr::Literal(Value::Bytes(vec![0xca, 0xfe])));
+            // Read as bytes once stored in a BYTEA column
+            assert_eq!(rows[0][2], Expr::Literal(Value::Text("\\xbeef".into())));
+        }
+        other => panic!("expected Insert, got {other:?}"),
+    }
+
+    let err = parse_sql("INSERT INTO files VALUES (X'ABC')").expect_err("odd number of digits");
+    assert!(
+        format!("{err:?}").contains("invalid hex literal"),
+        "{err:?}"
+    );
+
+    match stmt("SELECT CAST(body AS BYTEA), CAST(body AS BLOB) FROM files") {
+        Statement::Select { columns, .. } => {
+            for item in &columns {
+                assert!(
+                    matches!(
+                        item,
+                        SelectItem::Expr {
+                            expr: Expr::Cast {
+                                ty: SqlType::Bytes,
+                                ..
+                            },
+                            ..
+                        }
+                    ),
+                    "{item:?}"
+                );
+            }
+        }
+        other => panic!("expected Select, got {other:?}"),
+    }
+}
+
+#[test]
+fn now_and_date_trunc_parse_as_function_calls() {
+    match stmt("SELECT DATE_TRUNC('month', at), NOW() FROM events") {
+        Statement::Select { columns, .. } => {
+            let calls: Vec<_> = columns
+                .iter()
+                .map(|item| match item {
+                    SelectItem::Expr {
+                        expr: Expr::Function { name, args },
+                        ..
+                    } => (name.as_str(), args.len()),
+                    other => panic!("expected function call, got {other:?}"),
+                })
+                .collect();
+            assert_eq!(calls, vec![("date_trunc", 2), ("now", 0)]);
+        }
+        other => panic!("expected Select, got {other:?}"),
+    }
+}
+
+#[test]
+fn unsupported_function_forms_rejected() {
+    let err = parse_sql("SELECT COUNT(DISTINCT id) FROM users")
+        .expect_err("DISTINCT arguments should fail");
+    assert!(
+        format!("{err:?}").contains("unsupported function call"),
+        "{err:?}"
+    );
+
+    let err = parse_sql("SELECT TRIM(LEADING 'x' FROM name) FROM users")
+        .expect_err("TRIM options should fail");
+    assert!(format!("{err:?}").contains("TRIM"), "{err:?}");
+}
+
+#[test]
+fn quote_ident_only_quotes_names_that_need_it() {
+    assert_eq!(quote_ident("users"), "users");
+    assert_eq!(quote_ident("_tmp2"), "_tmp2");
+    assert_eq!(quote_ident("name"), "name");
+    assert_eq!(quote_ident("order"), "\"order\"");
+    assert_eq!(quote_ident("primary"), "\"primary\"");
+    assert_eq!(quote_ident("my table"), "\"my table\"");
+    assert_eq!(quote_ident("2nd"), "\"2nd\"");
+    assert_eq!(quote_ident("say \"hi\""), "\"say \"\"hi\"\"\"");
+}
+
+#[test]
+fn quoted_names_round_trip_through_parse_sql() {
+    let keywords = sqlparser::keywords::ALL_KEYWORDS
+        .iter()
+        .map(|keyword| keyword.to_lowercase());
+    let awkward = ["my table", "2nd", "say \"hi\"", "a.b", "dash-ed", "ünï"].map(String::from);
+    for name in keywords.chain(awkward) {
+        let q = quote_ident(&name);
+        let statements = [
+            format!("CREATE TABLE {q} ({q} INT PRIMARY KEY, b INT)"),
+            format!("SELECT {q}, b FROM {q} WHERE {q} = 1 ORDER BY {q}"),
+            format!("SELECT {q}.{q} FROM {q} JOIN t ON {q}.{q} = t.a"),
+            format!("INSERT INTO {q} ({q}, b) VALUES (1, 2)"),
+            format!("UPDATE {q} SET {q} = 1 WHERE {q} = 2"),
+            format!("DELETE FROM {q} WHERE {q} = 1"),
+            format!("CREATE INDEX {q} ON {q} ({q})"),
+            format!("VACUUM {q}"),
+            format!("SHOW TABLE STATS {q};"),
+        ];
+        for sql in statements {
+            let parsed = match parse_sql(&sql) {
+                Ok(parsed) => parsed,
+                Err(err) => panic!("{sql}: {err}"),
+            };
+            assert_eq!(parsed.len(), 1, "{sql}");
+            assert!(
+                format!("{:?}", parsed[0]).contains(&format!("{name:?}")),
+                "{sql}: {:?}",
+                parsed[0]
+            );
+        }
+    }
+}
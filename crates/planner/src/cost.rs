@@ -0,0 +1,923 @@
+//! Cost estimates from table statistics.
+//!
+//! Tables analyzed with `ANALYZE` carry row and page counts and per-column
+//! distinct counts, bounds and histograms (see [`catalog::TableStatistics`]).
+//! From them the planner estimates how many rows each plan node produces
+//! and what reading them costs, in the units of PostgreSQL's cost model: one
+//! sequential page read costs 1.
+//!
+//! Estimates guide two decisions. A filtered scan uses the cheapest usable
+//! index, or none if reading the whole table is cheaper; and the tables of
+//! a chain of joins are joined in the order estimated to be cheapest, which
+//! keeps intermediate results small and materializes small inputs on the
+//! right of each nested loop join. Without statistics the planner keeps its
+//! rules: the index matching the most predicate columns is used, and joins
+//! keep their written order.
+
+use crate::{IndexPredicate, PhysicalPlan, ResolvedExpr};
+use catalog::{Catalog, ColumnStatistics, TableStatistics};
+use common::ColumnId;
+use expr::{BinaryOp, UnaryOp};
+use std::cmp::Ordering;
+use types::Value;
+
+/// Cost of reading a page as part of a sequential scan.
+const SEQ_PAGE_COST: f64 = 1.0;
+/// Cost of reading a page out of order, as an index scan does.
+const RANDOM_PAGE_COST: f64 = 4.0;
+/// Cost of processing a row.
+const CPU_TUPLE_COST: f64 = 0.01;
+/// Cost of processing an index entry.
+const CPU_INDEX_TUPLE_COST: f64 = 0.005;
+/// Cost of evaluating an operator, such as a join condition.
+const CPU_OPERATOR_COST: f64 = 0.0025;
+/// Fraction of rows assumed to pass a predicate the statistics say nothing
+/// about.
+const DEFAULT_SELECTIVITY: f64 = 1.0 / 3.0;
+
+/// Estimated output of a plan.
+pub(crate) struct Estimate<'a> {
+    /// Rows the plan produces.
+    pub rows: f64,
+    /// Statistics of the table column each output column reads, if it
+    /// reads one unchanged.
+    columns: Vec<Option<ColumnEstimate<'a>>>,
+}
+
+/// Statistics of a table column.
+#[derive(Clone, Copy)]
+struct ColumnEstimate<'a> {
+    stats: &'a ColumnStatistics,
+    table_rows: u64,
+}
+
+impl ColumnEstimate<'_> {
+    /// Fraction of the table's rows where the column is not NULL.
+    fn non_null(&self) -> f64 {
+        if self.table_rows == 0 {
+            return 0.0;
+        }
+        1.0 - self.stats.null_count as f64 / self.table_rows as f64
+    }
+
+    /// Fraction of rows where the column equals `value`, or an unknown
+    /// value if `None`.
+    fn eq_selectivity(&self, value: Option<&Value>) -> f64 {
+        if let Some(value) = value {
+            let below_min = self
+                .stats
+                .min
+                .as_ref()
+                .and_then(|min| value.cmp_same_type(min))
+                == Some(Ordering::Less);
+            let above_max = self
+                .stats
+                .max
+                .as_ref()
+                .and_then(|max| value.cmp_same_type(max))
+                == Some(Ordering::Greater);
+            if matches!(value, Value::Null) || below_min || above_max {
+                return 0.0;
+            }
+        }
+        self.non_null() / self.stats.distinct_count.max(1) as f64
+    }
+
+    /// Fraction of rows where the column is below `value`, or `None` if
+    /// `value` cannot be compared with the column's values.
+    fn fraction_below(&self, value: &Value) -> Option<f64> {
+        let bounds = &self.stats.histogram;
+        let (Some(first), Some(last)) = (bounds.first(), bounds.last()) else {
+            return Some(0.0);
+        };
+        if value.cmp_same_type(first)? != Ordering::Greater {
+            return Some(0.0);
+        }
+        if value.cmp_same_type(last)? == Ordering::Greater {
+            return Some(self.non_null());
+        }
+        // The bucket holding the value, and how far into it the value lies
+        let buckets = bounds.len() - 1;
+        let bucket = bounds[1..]
+            .iter()
+            .position(|bound| value.cmp_same_type(bound) != Some(Ordering::Greater))
+            .unwrap_or(buckets - 1);
+        let (low, high) = (&bounds[bucket], &bounds[bucket + 1]);
+        let within = match (as_number(low), as_number(high), as_number(value)) {
+            (Some(low), Some(high), Some(value)) if high > low => (value - low) / (high - low),
+            _ => 0.5,
+        };
+        Some((bucket as f64 + within) / buckets as f64 * self.non_null())
+    }
+
+    /// Fraction of rows where the column is between `low` and `high`.
+    /// Bounds that cannot be compared with the column's values, such as the
+    /// open ends of an index range, are unbounded.
+    fn range_selectivity(&self, low: Option<&Value>, high: Option<&Value>) -> f64 {
+        let below_high = high
+            .and_then(|high| self.fraction_below(high))
+            .unwrap_or(self.non_null());
+        let below_low = low.and_then(|low| self.fraction_below(low)).unwrap_or(0.0);
+        (below_high - below_low).max(0.0)
+    }
+}
+
+/// Numeric position of a value, for interpolating within a histogram
+/// bucket.
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(i) => Some(*i as f64),
+        Value::Float(f) if f.is_finite() => Some(*f),
+        Value::Decimal(d) => Some(d.to_f64()),
+        Value::Date(d) => Some(*d as f64),
+        Value::Timestamp(t) => Some(*t as f64),
+        _ => None,
+    }
+}
+
+fn literal(expr: &ResolvedExpr) -> Option<&Value> {
+    match expr {
+        ResolvedExpr::Literal(value) => Some(value),
+        _ => None,
+    }
+}
+
+/// Cost of reading every row of a table.
+pub(crate) fn seq_scan_cost(stats: &TableStatistics) -> f64 {
+    stats.page_count.max(1) as f64 * SEQ_PAGE_COST + stats.row_count as f64 * CPU_TUPLE_COST
+}
+
+/// Cost of finding `rows` rows of a table through an index and reading
+/// them. Each row costs a random page read, up to the table's size.
+pub(crate) fn index_scan_cost(stats: &TableStatistics, rows: f64) -> f64 {
+    let pages = rows.min(stats.page_count.max(1) as f64);
+    (1.0 + pages) * RANDOM_PAGE_COST + rows * (CPU_INDEX_TUPLE_COST + CPU_TUPLE_COST)
+}
+
+/// Rows of a table an index scan with `predicate` finds.
+pub(crate) fn index_rows(stats: &TableStatistics, predicate: &IndexPredicate) -> f64 {
+    let column = |col: ColumnId| {
+        stats
+            .columns
+            .get(col as usize)
+            .map(|column| ColumnEstimate {
+                stats: column,
+                table_rows: stats.row_count,
+            })
+    };
+    let eq = |col: ColumnId, value: &ResolvedExpr| {
+        column(col).map_or(DEFAULT_SELECTIVITY, |c| c.eq_selectivity(literal(value)))
+    };
+    let selectivity = match predicate {
+        IndexPredicate::Eq { col, value } => eq(*col, value),
+        IndexPredicate::CompositeEq { columns, values } => columns
+            .iter()
+            .zip(values)
+            .map(|(col, value)| eq(*col, value))
+            .product(),
+        IndexPredicate::Range { col, low, high } => column(*col).map_or(DEFAULT_SELECTIVITY, |c| {
+            c.range_selectivity(literal(low), literal(high))
+        }),
+    };
+    stats.row_count as f64 * selectivity
+}
+
+/// Estimate the output of a query plan, if every table it reads has been
+/// analyzed.
+pub(crate) fn estimate<'a>(plan: &PhysicalPlan, catalog: &'a Catalog) -> Option<Estimate<'a>> {
+    match plan {
+        PhysicalPlan::SeqScan { table_id, .. } | PhysicalPlan::PartitionScan { table_id, .. } => {
+            table_estimate(catalog, *table_id)
+        }
+        PhysicalPlan::SampleScan {
+            table_id, sample, ..
+        } => {
+            let mut estimate = table_estimate(catalog, *table_id)?;
+            estimate.rows *= sample.percent / 100.0;
+            Some(estimate)
+        }
+        PhysicalPlan::IndexScan {
+            table_id,
+            predicate,
+            ..
+        } => {
+            let stats = catalog.table_by_id(*table_id).ok()?.statistics.as_ref()?;
+            let mut estimate = table_estimate(catalog, *table_id)?;
+            estimate.rows = index_rows(stats, predicate);
+            Some(estimate)
+        }
+        PhysicalPlan::IndexUnion {
+            table_id, probes, ..
+        } => {
+            let stats = catalog.table_by_id(*table_id).ok()?.statistics.as_ref()?;
+            let mut estimate = table_estimate(catalog, *table_id)?;
+            let rows: f64 = probes
+                .iter()
+                .map(|(_, predicate)| index_rows(stats, predicate))
+                .sum();
+            estimate.rows = rows.min(stats.row_count as f64);
+            Some(estimate)
+        }
+        PhysicalPlan::Filter { input, predicate } => {
+            let mut estimate = estimate(input, catalog)?;
+            // An index scan below already applied the filter's predicate
+            if !matches!(
+                **input,
+                PhysicalPlan::IndexScan { .. } | PhysicalPlan::IndexUnion { .. }
+            ) {
+                estimate.rows *= selectivity(predicate, &estimate);
+            }
+            Some(estimate)
+        }
+        PhysicalPlan::Project { input, columns } => {
+            let estimate = estimate(input, catalog)?;
+            let columns = columns
+                .iter()
+                .map(|(_, expr)| match expr {
+                    ResolvedExpr::Column(id) => estimate.columns.get(*id as usize).copied()?,
+                    _ => None,
+                })
+                .collect();
+            Some(Estimate {
+                rows: estimate.rows,
+                columns,
+            })
+        }
+        PhysicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+            ..
+        } => {
+            let input = estimate(input, catalog)?;
+            // One row per combination of the grouped columns' values, at
+            // most one per input row; without keys, a single row
+            let mut groups = 1.0;
+            let mut columns = Vec::with_capacity(group_by.len() + aggregates.len());
+            for key in group_by {
+                let column = match key {
+                    ResolvedExpr::Column(id) => input.columns.get(*id as usize).copied().flatten(),
+                    _ => None,
+                };
+                groups *= match column {
+                    Some(column) => column.stats.distinct_count.max(1) as f64,
+                    None => input.rows,
+                };
+                columns.push(column);
+            }
+            columns.extend(aggregates.iter().map(|_| None));
+            Some(Estimate {
+                rows: if group_by.is_empty() {
+                    1.0
+                } else {
+                    groups.min(input.rows)
+                },
+                columns,
+            })
+        }
+        PhysicalPlan::Sort { input, .. } => estimate(input, catalog),
+        PhysicalPlan::Limit {
+            input,
+            limit,
+            offset,
+        } => {
+            let mut estimate = estimate(input, catalog)?;
+            estimate.rows = (estimate.rows - offset.unwrap_or(0) as f64).max(0.0);
+            if let Some(limit) = limit {
+                estimate.rows = estimate.rows.min(*limit as f64);
+            }
+            Some(estimate)
+        }
+        PhysicalPlan::NestedLoopJoin {
+            left,
+            right,
+            condition,
+            ..
+        } => {
+            let left = estimate(left, catalog)?;
+            let right = estimate(right, catalog)?;
+            let mut joined = Estimate {
+                rows: left.rows * right.rows,
+                columns: left.columns.into_iter().chain(right.columns).collect(),
+            };
+            joined.rows *= selectivity(condition, &joined);
+            Some(joined)
+        }
+        PhysicalPlan::HashJoin {
+            left, right, keys, ..
+        } => {
+            let left_width = crate::Planner::output_schema(left).len() as ColumnId;
+            let left = estimate(left, catalog)?;
+            let right = estimate(right, catalog)?;
+            let mut joined = Estimate {
+                rows: left.rows * right.rows,
+                columns: left.columns.into_iter().chain(right.columns).collect(),
+            };
+            let condition = crate::hash_join::key_condition(keys, left_width);
+            joined.rows *= selectivity(&condition, &joined);
+            Some(joined)
+        }
+        PhysicalPlan::SemiJoin {
+            left,
+            right,
+            condition,
+            ..
+        }
+        | PhysicalPlan::AntiJoin {
+            left,
+            right,
+            condition,
+            ..
+        } => {
+            let left = estimate(left, catalog)?;
+            let right = estimate(right, catalog)?;
+            let joined = Estimate {
+                rows: left.rows * right.rows,
+                columns: left.columns.iter().cloned().chain(right.columns).collect(),
+            };
+            // A left row is kept at most once, however many rows it matches
+            let matched = (joined.rows * selectivity(condition, &joined)).min(left.rows);
+            let rows = match plan {
+                PhysicalPlan::AntiJoin { .. } => left.rows - matched,
+                _ => matched,
+            };
+            Some(Estimate {
+                rows,
+                columns: left.columns,
+            })
+        }
+        PhysicalPlan::SeriesScan {
+            start, stop, step, ..
+        } => Some(Estimate {
+            rows: series_rows(start, stop, step)?,
+            columns: vec![None],
+        }),
+        PhysicalPlan::ValuesScan { rows, schema } => Some(Estimate {
+            rows: rows.len() as f64,
+            columns: vec![None; schema.len()],
+        }),
+        PhysicalPlan::SystemScan { .. }
+        | PhysicalPlan::Insert { .. }
+        | PhysicalPlan::Update { .. }
+        | PhysicalPlan::Delete { .. } => None,
+    }
+}
+
+/// Length of a series whose bounds are literals.
+fn series_rows(start: &ResolvedExpr, stop: &ResolvedExpr, step: &ResolvedExpr) -> Option<f64> {
+    let int = |e: &ResolvedExpr| match e {
+        ResolvedExpr::Literal(Value::Int(n)) => Some(*n as f64),
+        _ => None,
+    };
+    let (start, stop, step) = (int(start)?, int(stop)?, int(step)?);
+    if step == 0.0 {
+        return None;
+    }
+    Some(((stop - start) / step).floor().max(-1.0) + 1.0)
+}
+
+fn table_estimate(catalog: &Catalog, table_id: common::TableId) -> Option<Estimate<'_>> {
+    let stats = catalog.table_by_id(table_id).ok()?.statistics.as_ref()?;
+    Some(Estimate {
+        rows: stats.row_count as f64,
+        columns: stats
+            .columns
+            .iter()
+            .map(|column| {
+                Some(ColumnEstimate {
+                    stats: column,
+                    table_rows: stats.row_count,
+                })
+            })
+            .collect(),
+    })
+}
+
+/// Fraction of the rows of `input` that pass `predicate`.
+fn selectivity(predicate: &ResolvedExpr, input: &Estimate) -> f64 {
+    let column = |expr: &ResolvedExpr| match expr {
+        ResolvedExpr::Column(id) => input.columns.get(*id as usize).copied().flatten(),
+        _ => None,
+    };
+    match predicate {
+        ResolvedExpr::Literal(Value::Bool(b)) => f64::from(u8::from(*b)),
+        ResolvedExpr::Unary {
+            op: UnaryOp::Not,
+            expr,
+        } => 1.0 - selectivity(expr, input),
+        ResolvedExpr::Binary {
+            left,
+            op: BinaryOp::And,
+            right,
+        } => selectivity(left, input) * selectivity(right, input),
+        ResolvedExpr::Binary {
+            left,
+            op: BinaryOp::Or,
+            right,
+        } => {
+            let (l, r) = (selectivity(left, input), selectivity(right, input));
+            l + r - l * r
+        }
+        ResolvedExpr::Binary { left, op, right } => {
+            // Put the column, if there is one, on the left
+            let (left, op, right) = match (column(left), column(right)) {
+                (None, Some(_)) => match crate::Planner::flip_comparison(*op) {
+                    Some(flipped) => (&**right, flipped, &**left),
+                    None => return DEFAULT_SELECTIVITY,
+                },
+                _ => (&**left, *op, &**right),
+            };
+            let Some(col) = column(left) else {
+                return DEFAULT_SELECTIVITY;
+            };
+            if let (BinaryOp::Eq, Some(other)) = (op, column(right)) {
+                let distinct = col.stats.distinct_count.max(other.stats.distinct_count);
+                return col.non_null() * other.non_null() / distinct.max(1) as f64;
+            }
+            let Some(value) = literal(right) else {
+                return match op {
+                    BinaryOp::Eq => col.eq_selectivity(None),
+                    _ => DEFAULT_SELECTIVITY,
+                };
+            };
+            match op {
+                BinaryOp::Eq => col.eq_selectivity(Some(value)),
+                BinaryOp::Ne => (col.non_null() - col.eq_selectivity(Some(value))).max(0.0),
+                BinaryOp::Lt | BinaryOp::Le => col.range_selectivity(None, Some(value)),
+                BinaryOp::Gt | BinaryOp::Ge => col.range_selectivity(Some(value), None),
+                _ => DEFAULT_SELECTIVITY,
+            }
+        }
+        _ => DEFAULT_SELECTIVITY,
+    }
+}
+
+/// Reorder the tables of each chain of joins in a query by estimated cost,
+/// when every table in the chain has an estimate. A projection above a
+/// reordered chain restores its column order.
+///
+/// Chains of up to [`EXHAUSTIVE_JOIN_LIMIT`] tables try every order, and
+/// longer ones are built greedily. The written order is kept unless another
+/// is estimated to be cheaper.
+///
+/// UPDATE and DELETE sources are left alone: the target table must stay
+/// leftmost so its rows keep their record IDs.
+pub(crate) fn order_joins(plan: PhysicalPlan, catalog: &Catalog) -> PhysicalPlan {
+    let order = |input: Box<PhysicalPlan>| Box::new(order_joins(*input, catalog));
+    match plan {
+        PhysicalPlan::Filter { input, predicate } => PhysicalPlan::Filter {
+            input: order(input),
+            predicate,
+        },
+        PhysicalPlan::Project { input, columns } => {
+            let joined = matches!(*input, PhysicalPlan::NestedLoopJoin { .. });
+            match order_joins(*input, catalog) {
+                // Read through the projection restoring a reordered join's
+                // columns
+                PhysicalPlan::Project {
+                    input,
+                    columns: restored,
+                } if joined => {
+                    let position = |id: ColumnId| match restored[id as usize].1 {
+                        ResolvedExpr::Column(position) => position,
+                        _ => unreachable!("a reordered join's projection only moves columns"),
+                    };
+                    PhysicalPlan::Project {
+                        input,
+                        columns: columns
+                            .into_iter()
+                            .map(|(name, expr)| (name, map_columns(expr, &position)))
+                            .collect(),
+                    }
+                }
+                input => PhysicalPlan::Project {
+                    input: Box::new(input),
+                    columns,
+                },
+            }
+        }
+        PhysicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+            schema,
+        } => PhysicalPlan::Aggregate {
+            input: order(input),
+            group_by,
+            aggregates,
+            schema,
+        },
+        PhysicalPlan::Sort { input, order_by } => PhysicalPlan::Sort {
+            input: order(input),
+            order_by,
+        },
+        PhysicalPlan::Limit {
+            input,
+            limit,
+            offset,
+        } => PhysicalPlan::Limit {
+            input: order(input),
+            limit,
+            offset,
+        },
+        // The right side of a semi or anti join is a subquery's own table,
+        // so only the joins within each side are reordered
+        PhysicalPlan::SemiJoin {
+            left,
+            right,
+            condition,
+            schema,
+        } => PhysicalPlan::SemiJoin {
+            left: order(left),
+            right: order(right),
+            condition,
+            schema,
+        },
+        PhysicalPlan::AntiJoin {
+            left,
+            right,
+            condition,
+            schema,
+        } => PhysicalPlan::AntiJoin {
+            left: order(left),
+            right: order(right),
+            condition,
+            schema,
+        },
+        join @ PhysicalPlan::NestedLoopJoin { .. } => {
+            let chain = JoinChain::flatten(join, catalog);
+            match chain.cheaper_order(catalog) {
+                Some(order) => chain.reorder(&order),
+                None => chain.rebuild(),
+            }
+        }
+        other => other,
+    }
+}
+
+/// Most tables in a join chain whose order is chosen by trying every order.
+const EXHAUSTIVE_JOIN_LIMIT: usize = 8;
+
+/// A left-deep chain of joins, taken apart.
+///
+/// Join `k` of the chain joins the first `k + 1` relations with relation
+/// `k + 1`, so the chain's column ordinals number the columns of every
+/// relation in order.
+struct JoinChain {
+    relations: Vec<PhysicalPlan>,
+    /// The condition of each join.
+    conditions: Vec<ResolvedExpr>,
+    /// Ordinal of each relation's first column, and the chain's width.
+    offsets: Vec<usize>,
+    schema: Vec<String>,
+}
+
+impl JoinChain {
+    /// Take apart the chain of joins rooted at `plan`, ordering the joins
+    /// within each of its relations.
+    fn flatten(plan: PhysicalPlan, catalog: &Catalog) -> Self {
+        let schema = crate::Planner::output_schema(&plan);
+        let mut relations = Vec::new();
+        let mut conditions = Vec::new();
+        let mut plan = plan;
+        while let PhysicalPlan::NestedLoopJoin {
+            left,
+            right,
+            condition,
+            ..
+        } = plan
+        {
+            relations.push(order_joins(*right, catalog));
+            conditions.push(condition);
+            plan = *left;
+        }
+        relations.push(order_joins(plan, catalog));
+        relations.reverse();
+        conditions.reverse();
+
+        let mut offsets = vec![0];
+        for relation in &relations {
+            offsets.push(offsets.last().unwrap() + crate::Planner::output_schema(relation).len());
+        }
+        JoinChain {
+            relations,
+            conditions,
+            offsets,
+            schema,
+        }
+    }
+
+    /// The relation a column of the chain belongs to.
+    fn relation_of(&self, id: ColumnId) -> usize {
+        self.offsets
+            .partition_point(|&offset| offset <= id as usize)
+            - 1
+    }
+
+    /// The relations an expression reads, as a bit set.
+    fn relations_read(&self, expr: &ResolvedExpr) -> u64 {
+        let mut read = 0;
+        for_each_column(expr, &mut |id| read |= 1 << self.relation_of(id));
+        read
+    }
+
+    /// An order of the relations estimated to be cheaper to join than the
+    /// written one, if every relation has an estimate and there is one.
+    fn cheaper_order(&self, catalog: &Catalog) -> Option<Vec<usize>> {
+        let count = self.relations.len();
+        if count > u64::BITS as usize {
+            return None;
+        }
+        let estimates = self
+            .relations
+            .iter()
+            .map(|relation| estimate(relation, catalog))
+            .collect::<Option<Vec<_>>>()?;
+        let rows: Vec<f64> = estimates.iter().map(|estimate| estimate.rows).collect();
+        // Selectivities only depend on the columns a condition reads
+        let all = Estimate {
+            rows: 1.0,
+            columns: estimates
+                .into_iter()
+                .flat_map(|estimate| estimate.columns)
+                .collect(),
+        };
+        let conjuncts: Vec<(u64, f64)> = self
+            .conditions
+            .iter()
+            .flat_map(conjuncts)
+            .map(|conjunct| (self.relations_read(conjunct), selectivity(conjunct, &all)))
+            .collect();
+
+        // Rows of the join of a set of relations
+        let cardinality = |set: u64| {
+            let rows: f64 = (0..count)
+                .filter(|i| set & 1 << i != 0)
+                .map(|i| rows[i])
+                .product();
+            conjuncts
+                .iter()
+                .filter(|(read, _)| read & !set == 0)
+                .fold(rows, |rows, (_, selectivity)| rows * selectivity)
+        };
+        let cost = |order: &[usize]| {
+            let mut set = 1 << order[0];
+            let mut total = 0.0;
+            for &next in &order[1..] {
+                total += join_cost(cardinality(set), rows[next]);
+                set |= 1 << next;
+            }
+            total
+        };
+
+        let best = if count <= EXHAUSTIVE_JOIN_LIMIT {
+            // The cheapest order of each set of relations, from the
+            // cheapest orders of its subsets
+            let mut best: Vec<Option<(f64, Vec<usize>)>> = vec![None; 1 << count];
+            for i in 0..count {
+                best[1 << i] = Some((0.0, vec![i]));
+            }
+            for set in 1..1usize << count {
+                if set.count_ones() < 2 {
+                    continue;
+                }
+                for last in (0..count).filter(|i| set & 1 << i != 0) {
+                    let rest = set & !(1 << last);
+                    let Some((rest_cost, rest_order)) = &best[rest] else {
+                        continue;
+                    };
+                    let total = rest_cost + join_cost(cardinality(rest as u64), rows[last]);
+                    if best[set].as_ref().is_none_or(|(cost, _)| total < *cost) {
+                        let mut order = rest_order.clone();
+                        order.push(last);
+                        best[set] = Some((total, order));
+                    }
+                }
+            }
+            best.pop().flatten()?.1
+        } else {
+            // From each starting relation, join whichever relation is
+            // cheapest to join next
+            (0..count)
+                .map(|first| {
+                    let mut order = vec![first];
+                    let mut set = 1 << first;
+                    while order.len() < count {
+                        let next = (0..count)
+                            .filter(|i| set & 1 << i == 0)
+                            .min_by(|&a, &b| {
+                                let outer = cardinality(set);
+                                join_cost(outer, rows[a]).total_cmp(&join_cost(outer, rows[b]))
+                            })
+                            .unwrap();
+                        order.push(next);
+                        set |= 1 << next;
+                    }
+                    order
+                })
+                .min_by(|a, b| cost(a).total_cmp(&cost(b)))
+                .unwrap()
+        };
+        let written: Vec<usize> = (0..count).collect();
+        (cost(&best) < cost(&written)).then_some(best)
+    }
+
+    /// Join the relations in `order`, evaluating each conjunct of the
+    /// conditions at the first join that has every column it reads.
+    fn reorder(mut self, order: &[usize]) -> PhysicalPlan {
+        let mut relations: Vec<Option<PhysicalPlan>> = std::mem::take(&mut self.relations)
+            .into_iter()
+            .map(Some)
+            .collect();
+        let mut offsets = vec![0; relations.len()];
+        let mut offset = 0;
+        for &relation in order {
+            offsets[relation] = offset;
+            offset += self.offsets[relation + 1] - self.offsets[relation];
+        }
+        // Old position of each column -> its position after reordering
+        let moved = |id: ColumnId| {
+            let relation = self.relation_of(id);
+            (offsets[relation] + id as usize - self.offsets[relation]) as ColumnId
+        };
+
+        let mut pending: Vec<(u64, ResolvedExpr)> = self
+            .conditions
+            .iter()
+            .flat_map(conjuncts)
+            .map(|conjunct| {
+                (
+                    self.relations_read(conjunct),
+                    map_columns(conjunct.clone(), &moved),
+                )
+            })
+            .collect();
+        let columns =
+            |relation: usize| &self.schema[self.offsets[relation]..self.offsets[relation + 1]];
+
+        let mut plan = relations[order[0]].take().unwrap();
+        let mut schema = columns(order[0]).to_vec();
+        let mut set = 1 << order[0];
+        for &relation in &order[1..] {
+            set |= 1 << relation;
+            schema.extend_from_slice(columns(relation));
+            let (ready, rest) = pending.into_iter().partition(|(read, _)| read & !set == 0);
+            pending = rest;
+            let condition = ready
+                .into_iter()
+                .map(|(_, conjunct)| conjunct)
+                .reduce(|left, right| ResolvedExpr::Binary {
+                    left: Box::new(left),
+                    op: BinaryOp::And,
+                    right: Box::new(right),
+                })
+                .unwrap_or(ResolvedExpr::Literal(Value::Bool(true)));
+            plan = PhysicalPlan::NestedLoopJoin {
+                left: Box::new(plan),
+                right: Box::new(relations[relation].take().unwrap()),
+                condition,
+                schema: schema.clone(),
+            };
+        }
+
+        let columns = self
+            .schema
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (name.clone(), ResolvedExpr::Column(moved(id as ColumnId))))
+            .collect();
+        PhysicalPlan::Project {
+            input: Box::new(plan),
+            columns,
+        }
+    }
+
+    /// Join the relations as written.
+    fn rebuild(self) -> PhysicalPlan {
+        let mut relations = self.relations.into_iter();
+        let mut plan = relations.next().unwrap();
+        for (k, (right, condition)) in relations.zip(self.conditions).enumerate() {
+            plan = PhysicalPlan::NestedLoopJoin {
+                left: Box::new(plan),
+                right: Box::new(right),
+                condition,
+                schema: self.schema[..self.offsets[k + 2]].to_vec(),
+            };
+        }
+        plan
+    }
+}
+
+/// Cost of a nested loop join that materializes the `inner_rows` rows of
+/// its right input and evaluates the condition on every pair of rows.
+fn join_cost(outer_rows: f64, inner_rows: f64) -> f64 {
+    inner_rows * CPU_TUPLE_COST + outer_rows * inner_rows * CPU_OPERATOR_COST
+}
+
+/// The conjuncts of a condition, leaving out `TRUE`.
+pub(crate) fn conjuncts(expr: &ResolvedExpr) -> Vec<&ResolvedExpr> {
+    match expr {
+        ResolvedExpr::Binary {
+            left,
+            op: BinaryOp::And,
+            right,
+        } => {
+            let mut both = conjuncts(left);
+            both.extend(conjuncts(right));
+            both
+        }
+        ResolvedExpr::Literal(Value::Bool(true)) => Vec::new(),
+        other => vec![other],
+    }
+}
+
+/// The branches of a disjunction; a single branch for any other condition.
+pub(crate) fn disjuncts(expr: &ResolvedExpr) -> Vec<&ResolvedExpr> {
+    match expr {
+        ResolvedExpr::Binary {
+            left,
+            op: BinaryOp::Or,
+            right,
+        } => {
+            let mut either = disjuncts(left);
+            either.extend(disjuncts(right));
+            either
+        }
+        other => vec![other],
+    }
+}
+
+/// Call `f` with each column an expression reads.
+pub(crate) fn for_each_column(expr: &ResolvedExpr, f: &mut impl FnMut(ColumnId)) {
+    match expr {
+        ResolvedExpr::Column(id) => f(*id),
+        ResolvedExpr::Literal(_) => {}
+        ResolvedExpr::Unary { expr, .. }
+        | ResolvedExpr::Cast { expr, .. }
+        | ResolvedExpr::IsNull { expr, .. } => for_each_column(expr, f),
+        ResolvedExpr::Binary { left, right, .. } => {
+            for_each_column(left, f);
+            for_each_column(right, f);
+        }
+        ResolvedExpr::Function { args, .. } => {
+            for arg in args {
+                for_each_column(arg, f);
+            }
+        }
+        ResolvedExpr::Case {
+            operand,
+            branches,
+            else_result,
+        } => {
+            for expr in operand.iter().chain(else_result) {
+                for_each_column(expr, f);
+            }
+            for (when, then) in branches {
+                for_each_column(when, f);
+                for_each_column(then, f);
+            }
+        }
+    }
+}
+
+/// Rewrite the column references of an expression with `map`.
+pub(crate) fn map_columns(expr: ResolvedExpr, map: &impl Fn(ColumnId) -> ColumnId) -> ResolvedExpr {
+    let boxed = |expr: Box<ResolvedExpr>| Box::new(map_columns(*expr, map));
+    match expr {
+        ResolvedExpr::Column(id) => ResolvedExpr::Column(map(id)),
+        ResolvedExpr::Literal(_) => expr,
+        ResolvedExpr::Unary { op, expr } => ResolvedExpr::Unary {
+            op,
+            expr: boxed(expr),
+        },
+        ResolvedExpr::Binary { left, op, right } => ResolvedExpr::Binary {
+            left: boxed(left),
+            op,
+            right: boxed(right),
+        },
+        ResolvedExpr::Function { name, args } => ResolvedExpr::Function {
+            name,
+            args: args.into_iter().map(|arg| map_columns(arg, map)).collect(),
+        },
+        ResolvedExpr::Cast { expr, ty } => ResolvedExpr::Cast {
+            expr: boxed(expr),
+            ty,
+        },
+        ResolvedExpr::IsNull { expr, negated } => ResolvedExpr::IsNull {
+            expr: boxed(expr),
+            negated,
+        },
+        ResolvedExpr::Case {
+            operand,
+            branches,
+            else_result,
+        } => ResolvedExpr::Case {
+            operand: operand.map(boxed),
+            branches: branches
+                .into_iter()
+                .map(|(when, then)| (map_columns(when, map), map_columns(then, map)))
+                .collect(),
+            else_result: else_result.map(boxed),
+        },
+    }
+}
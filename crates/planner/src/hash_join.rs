@@ -0,0 +1,225 @@
+//! Hash joins for joins on equal values.
+//!
+//! A nested loop join evaluates its condition on every pair of rows. When
+//! the condition is an AND of terms and some of them equate an expression
+//! of the left side's columns with one of the right side's, such as
+//! `o.user_id = u.id`, only pairs with equal values of those expressions
+//! can match, and a hash join finds them directly: the right rows are
+//! hashed on their key values and each left row looks up its own. The
+//! other terms, such as `o.total > u.credit` or an `OR`, are kept as a
+//! filter above the join. A condition with no such term stays a nested
+//! loop join.
+//!
+//! A term is a key only if hashing agrees with its `=`; see
+//! [`hashable_equality`].
+
+use crate::cost::{conjuncts, for_each_column, map_columns};
+use crate::typecheck::hashable_equality;
+use crate::{PhysicalPlan, Planner, PlanningContext, ResolvedExpr};
+use common::ColumnId;
+use expr::BinaryOp;
+
+/// Replace each nested loop join in `plan` whose condition equates the
+/// two sides with a hash join.
+pub(crate) fn use_hash_joins(plan: PhysicalPlan, ctx: &PlanningContext) -> PhysicalPlan {
+    let hash = |input: Box<PhysicalPlan>| Box::new(use_hash_joins(*input, ctx));
+    match plan {
+        PhysicalPlan::Filter { input, predicate } => PhysicalPlan::Filter {
+            input: hash(input),
+            predicate,
+        },
+        PhysicalPlan::Project { input, columns } => PhysicalPlan::Project {
+            input: hash(input),
+            columns,
+        },
+        PhysicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+            schema,
+        } => PhysicalPlan::Aggregate {
+            input: hash(input),
+            group_by,
+            aggregates,
+            schema,
+        },
+        PhysicalPlan::Sort { input, order_by } => PhysicalPlan::Sort {
+            input: hash(input),
+            order_by,
+        },
+        PhysicalPlan::Limit {
+            input,
+            limit,
+            offset,
+        } => PhysicalPlan::Limit {
+            input: hash(input),
+            limit,
+            offset,
+        },
+        PhysicalPlan::Update {
+            table_id,
+            assignments,
+            predicate,
+            source,
+        } => PhysicalPlan::Update {
+            table_id,
+            assignments,
+            predicate,
+            source: source.map(hash),
+        },
+        PhysicalPlan::Delete {
+            table_id,
+            predicate,
+            source,
+        } => PhysicalPlan::Delete {
+            table_id,
+            predicate,
+            source: source.map(hash),
+        },
+        PhysicalPlan::SemiJoin {
+            left,
+            right,
+            condition,
+            schema,
+        } => PhysicalPlan::SemiJoin {
+            left: hash(left),
+            right: hash(right),
+            condition,
+            schema,
+        },
+        PhysicalPlan::AntiJoin {
+            left,
+            right,
+            condition,
+            schema,
+        } => PhysicalPlan::AntiJoin {
+            left: hash(left),
+            right: hash(right),
+            condition,
+            schema,
+        },
+        PhysicalPlan::NestedLoopJoin {
+            left,
+            right,
+            condition,
+            schema,
+        } => hash_join(hash(left), hash(right), condition, schema, ctx),
+        other => other,
+    }
+}
+
+/// A join of `left` and `right` on `condition`: a hash join on the terms
+/// of the condition that equate the two sides, under a filter with the
+/// other terms, or a nested loop join if there are none.
+fn hash_join(
+    left: Box<PhysicalPlan>,
+    right: Box<PhysicalPlan>,
+    condition: ResolvedExpr,
+    schema: Vec<String>,
+    ctx: &PlanningContext,
+) -> PhysicalPlan {
+    let left_width = Planner::output_schema(&left).len() as ColumnId;
+    let mut keys = Vec::new();
+    let mut residual = Vec::new();
+    for term in conjuncts(&condition) {
+        let key = equi_key(term, left_width).map(|(l, r)| {
+            let r = map_columns(r.clone(), &|id| id - left_width);
+            (l.clone(), r)
+        });
+        match key {
+            Some((l, r)) if hashable_equality(&left, &right, ctx, &l, &r) => keys.push((l, r)),
+            _ => residual.push(term.clone()),
+        }
+    }
+    if keys.is_empty() {
+        return PhysicalPlan::NestedLoopJoin {
+            left,
+            right,
+            condition,
+            schema,
+        };
+    }
+
+    let join = PhysicalPlan::HashJoin {
+        left,
+        right,
+        keys,
+        schema,
+    };
+    match residual
+        .into_iter()
+        .reduce(|left, right| ResolvedExpr::Binary {
+            left: Box::new(left),
+            op: BinaryOp::And,
+            right: Box::new(right),
+        }) {
+        Some(predicate) => PhysicalPlan::Filter {
+            input: Box::new(join),
+            predicate,
+        },
+        None => join,
+    }
+}
+
+/// The left and right operands of `term` if it is an equality between an
+/// expression reading only left columns, those before `left_width`, and
+/// one reading only right columns, in either order.
+fn equi_key(term: &ResolvedExpr, left_width: ColumnId) -> Option<(&ResolvedExpr, &ResolvedExpr)> {
+    let ResolvedExpr::Binary {
+        left,
+        op: BinaryOp::Eq,
+        right,
+    } = term
+    else {
+        return None;
+    };
+    match (side(left, left_width)?, side(right, left_width)?) {
+        (Side::Left, Side::Right) => Some((left, right)),
+        (Side::Right, Side::Left) => Some((right, left)),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+}
+
+/// The side of the join whose columns `expr` reads, if it reads any and
+/// only those of one side.
+fn side(expr: &ResolvedExpr, left_width: ColumnId) -> Option<Side> {
+    let (mut left, mut right) = (false, false);
+    for_each_column(expr, &mut |id| {
+        if id < left_width {
+            left = true;
+        } else {
+            right = true;
+        }
+    });
+    match (left, right) {
+        (true, false) => Some(Side::Left),
+        (false, true) => Some(Side::Right),
+        _ => None,
+    }
+}
+
+/// The condition a hash join's keys stand for, over its combined rows: an
+/// AND of the equalities of each pair.
+pub(crate) fn key_condition(
+    keys: &[(ResolvedExpr, ResolvedExpr)],
+    left_width: ColumnId,
+) -> ResolvedExpr {
+    keys.iter()
+        .map(|(left, right)| ResolvedExpr::Binary {
+            left: Box::new(left.clone()),
+            op: BinaryOp::Eq,
+            right: Box::new(map_columns(right.clone(), &|id| id + left_width)),
+        })
+        .reduce(|left, right| ResolvedExpr::Binary {
+            left: Box::new(left),
+            op: BinaryOp::And,
+            right: Box::new(right),
+        })
+        .unwrap_or(ResolvedExpr::Literal(types::Value::Bool(true)))
+}
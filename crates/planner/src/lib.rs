@@ -1,11 +1,13 @@
 //! Query planner: converts SQL AST to optimized physical execution plans.
 //!
 //! The planner bridges between the parser's abstract syntax tree and the executor's
-//! runtime operators. It performs three main tasks:
+//! runtime operators. It performs four main tasks:
 //!
 //! 1. **Name Binding** - Resolves column names to ordinals using catalog schemas
 //! 2. **Optimization** - Applies simple rules like predicate pushdown and projection pruning
-//! 3. **Access Method Selection** - Chooses between sequential and index scans
+//! 3. **Access Method Selection** - Chooses between sequential and index scans,
+//!    and orders joined tables, by estimated cost once tables are analyzed
+//! 4. **Type Checking** - Rejects operands and assigned values of the wrong type
 //!
 //! # Architecture
 //!
@@ -18,6 +20,10 @@
 //!     ↓
 //! Bind (names → IDs)
 //!     ↓
+//! Type check (operand and column types)
+//!     ↓
+//! Order joins (estimated costs)
+//!     ↓
 //! Physical Plan (table IDs, column ordinals, access methods)
 //!     ↓
 //! Executor
@@ -36,17 +42,26 @@
 //! let plan = Planner::plan(stmt, &mut ctx).unwrap();
 //! ```
 
+mod cost;
+mod hash_join;
+mod prune;
+mod shape;
 #[cfg(test)]
 mod tests;
+mod typecheck;
 
-use catalog::{Catalog, IndexKind, TableMeta};
+use catalog::{Catalog, EngineKind, IndexKind, Partitioning, SystemView, TableMeta};
 use common::{ColumnId, DbError, DbResult, TableId};
+use expr::aggregate::AggregateFunc;
 use expr::{BinaryOp, Expr, UnaryOp};
-use parser::{JoinType, SelectItem, Statement};
-use types::Value;
+use parser::{JoinClause, JoinType, SelectItem, Statement, TableRef, quote_ident};
+use std::collections::BTreeSet;
+use std::ops::Bound;
+use types::{Decimal, SqlType, Value};
 
 // Re-export for use by executor and internal use
-pub use parser::{JoinType as PlanJoinType, SortDirection};
+pub use parser::{JoinType as PlanJoinType, NullsOrder, SampleMethod, SortDirection, TableSample};
+pub use shape::PlanShape;
 
 /// Logical plan node - optimizer-friendly representation with string names.
 ///
@@ -57,13 +72,38 @@ pub enum LogicalPlan {
     TableScan {
         table: String,
     },
+    /// Call of a table function in FROM, such as `generate_series(1, 10)`.
+    TableFunction {
+        name: String,
+        args: Vec<Expr>,
+    },
+    /// Rows of a `VALUES` list, or the single empty row a `SELECT` without
+    /// `FROM` reads.
+    Values {
+        rows: Vec<Vec<Expr>>,
+    },
+    /// Some of the rows of a table, from `TABLESAMPLE`.
+    Sample {
+        input: Box<LogicalPlan>,
+        sample: TableSample,
+    },
     Filter {
         input: Box<LogicalPlan>,
         predicate: Expr,
     },
+    /// Output columns; a lone `SelectItem::Wildcard` keeps every input column.
     Project {
         input: Box<LogicalPlan>,
-        columns: Vec<String>,
+        columns: Vec<SelectItem>,
+    },
+    /// Groups of rows with equal `group_by` values, each reduced to one row
+    /// of its key values followed by the value of each aggregate call. With
+    /// no keys, every row forms a single group, even if there are none.
+    Aggregate {
+        input: Box<LogicalPlan>,
+        group_by: Vec<Expr>,
+        /// Distinct `Expr::Aggregate` calls the query makes.
+        aggregates: Vec<Expr>,
     },
     Sort {
         input: Box<LogicalPlan>,
@@ -76,16 +116,24 @@ pub enum LogicalPlan {
     },
     Insert {
         table: String,
-        values: Vec<Expr>,
+        /// Explicit target columns; empty means positional.
+        columns: Vec<String>,
+        rows: Vec<Vec<Expr>>,
     },
     Update {
         table: String,
         assignments: Vec<(String, Expr)>,
         predicate: Option<Expr>,
+        /// The table's rows joined to those of the tables in `FROM`, if
+        /// the statement names any.
+        source: Option<Box<LogicalPlan>>,
     },
     Delete {
         table: String,
         predicate: Option<Expr>,
+        /// The table's rows joined to those of the tables in `USING`, if
+        /// the statement names any.
+        source: Option<Box<LogicalPlan>>,
     },
     /// Join two plans together.
     Join {
@@ -94,6 +142,9 @@ pub enum LogicalPlan {
         join_type: JoinType,
         /// Join condition (ON clause).
         condition: Expr,
+        /// For a semi join from `x IN (SELECT y ...)`, `x` over the left
+        /// side and `y` over the right, which must be equal to match.
+        key: Option<(Expr, Expr)>,
         /// Effective name (alias or table name) for the left side.
         left_name: String,
         /// Effective name (alias or table name) for the right side.
@@ -101,11 +152,13 @@ pub enum LogicalPlan {
     },
 }
 
-/// Logical ORDER BY expression with column name.
+/// Logical ORDER BY expression: a select-list alias or an expression over
+/// the query's columns.
 #[derive(Clone, Debug, PartialEq)]
 pub struct OrderByExpr {
-    pub column: String,
+    pub expr: Expr,
     pub direction: SortDirection,
+    pub nulls: NullsOrder,
 }
 
 /// Physical plan node - executor-ready with resolved IDs and access methods.
@@ -117,20 +170,67 @@ pub enum PhysicalPlan {
     SeqScan {
         table_id: TableId,
         schema: Vec<String>,
+        /// Columns the operators above read, in ascending order. The others
+        /// are returned as NULL without being decoded. `None` reads every
+        /// column.
+        projection: Option<Vec<ColumnId>>,
+    },
+    /// Sequential scan that returns a random sample of the table's rows or
+    /// pages, from `TABLESAMPLE`.
+    SampleScan {
+        table_id: TableId,
+        sample: TableSample,
+        schema: Vec<String>,
+        /// As for [`PhysicalPlan::SeqScan`].
+        projection: Option<Vec<ColumnId>>,
+    },
+    /// Sequential scan of some partitions of a partitioned table, by their
+    /// positions in its partitioning; the others cannot hold matching rows.
+    PartitionScan {
+        table_id: TableId,
+        partitions: Vec<usize>,
+        schema: Vec<String>,
+        /// As for [`PhysicalPlan::SeqScan`].
+        projection: Option<Vec<ColumnId>>,
     },
     IndexScan {
         table_id: TableId,
         index_name: String,
         predicate: IndexPredicate,
         schema: Vec<String>,
+        /// As for [`PhysicalPlan::SeqScan`].
+        projection: Option<Vec<ColumnId>>,
+    },
+    /// Index scans for each branch of an OR, such as `id = 1 OR id = 7`,
+    /// whose matches are combined. A row matched by several probes is
+    /// returned once.
+    IndexUnion {
+        table_id: TableId,
+        /// Index name and predicate of each probe.
+        probes: Vec<(String, IndexPredicate)>,
+        schema: Vec<String>,
+        /// As for [`PhysicalPlan::SeqScan`].
+        projection: Option<Vec<ColumnId>>,
     },
     Filter {
         input: Box<PhysicalPlan>,
         predicate: ResolvedExpr,
     },
+    /// Each output column is an (output_name, expression) pair.
     Project {
         input: Box<PhysicalPlan>,
-        columns: Vec<(String, ColumnId)>,
+        columns: Vec<(String, ResolvedExpr)>,
+    },
+    /// Hash aggregation: the input rows grouped by the values of
+    /// `group_by`, each group returned as its key values followed by its
+    /// aggregates. Without keys, a single row over every input row.
+    Aggregate {
+        input: Box<PhysicalPlan>,
+        group_by: Vec<ResolvedExpr>,
+        aggregates: Vec<ResolvedAggregate>,
+        /// Names of the keys, such as `users.dept`, followed by the
+        /// aggregates', such as `count(*)`.
+        schema: Vec<String>,
     },
     Sort {
         input: Box<PhysicalPlan>,
@@ -143,16 +243,48 @@ pub enum PhysicalPlan {
     },
     Insert {
         table_id: TableId,
-        values: Vec<ResolvedExpr>,
+        rows: Vec<Vec<ResolvedExpr>>,
     },
+    /// With a `source`, the assignments and predicate are bound against
+    /// its rows, which start with the table's columns; otherwise against the
+    /// table's rows.
     Update {
         table_id: TableId,
         assignments: Vec<(ColumnId, ResolvedExpr)>,
         predicate: Option<ResolvedExpr>,
+        /// The table's rows joined to other tables' rows, from
+        /// `UPDATE ... FROM`, or an index scan of the table's rows that may
+        /// match `predicate`. `None` scans the whole table.
+        source: Option<Box<PhysicalPlan>>,
     },
     Delete {
         table_id: TableId,
         predicate: Option<ResolvedExpr>,
+        /// The table's rows joined to other tables' rows, from
+        /// `DELETE ... USING`, or an index scan as for
+        /// [`PhysicalPlan::Update`].
+        source: Option<Box<PhysicalPlan>>,
+    },
+    /// Scan of a view of catalog state, such as
+    /// `information_schema.table_activity`.
+    SystemScan {
+        view: SystemView,
+        schema: Vec<String>,
+    },
+    /// The integers from `start` to `stop` in increments of `step`, from
+    /// `generate_series(start, stop[, step])`. The bounds are evaluated once,
+    /// when the scan is opened.
+    SeriesScan {
+        start: ResolvedExpr,
+        stop: ResolvedExpr,
+        step: ResolvedExpr,
+        schema: Vec<String>,
+    },
+    /// Rows of a `VALUES` list, each expression evaluated as its row is
+    /// returned. Columns are named `column1`, `column2` and so on.
+    ValuesScan {
+        rows: Vec<Vec<ResolvedExpr>>,
+        schema: Vec<String>,
     },
     /// Nested loop join - for each row from left, scan all right rows.
     NestedLoopJoin {
@@ -164,6 +296,39 @@ pub enum PhysicalPlan {
         /// Column names are prefixed with table/alias name (e.g., "users.id").
         schema: Vec<String>,
     },
+    /// Hash join - the right rows are hashed on their key values, and each
+    /// left row is matched with the right rows of equal keys. Chosen for a
+    /// join whose condition equates columns or expressions of the two
+    /// sides; the rest of the condition is a filter above the join.
+    HashJoin {
+        left: Box<PhysicalPlan>,
+        right: Box<PhysicalPlan>,
+        /// Pairs of keys that must be equal, each an expression over the
+        /// left input's columns and one over the right input's.
+        keys: Vec<(ResolvedExpr, ResolvedExpr)>,
+        /// Combined schema, as for a nested loop join.
+        schema: Vec<String>,
+    },
+    /// Each left row once if some right row satisfies the condition with
+    /// it: an `EXISTS` or `IN` subquery.
+    SemiJoin {
+        left: Box<PhysicalPlan>,
+        right: Box<PhysicalPlan>,
+        /// Condition over a left row followed by a right row.
+        condition: ResolvedExpr,
+        /// The left side's schema; no right columns are returned.
+        schema: Vec<String>,
+    },
+    /// Each left row no right row satisfies the condition with: a
+    /// `NOT EXISTS` subquery.
+    AntiJoin {
+        left: Box<PhysicalPlan>,
+        right: Box<PhysicalPlan>,
+        /// Condition over a left row followed by a right row.
+        condition: ResolvedExpr,
+        /// The left side's schema; no right columns are returned.
+        schema: Vec<String>,
+    },
 }
 
 /// Physical ORDER BY expression with resolved column ID.
@@ -171,8 +336,21 @@ pub enum PhysicalPlan {
 pub struct ResolvedOrderByExpr {
     pub column_id: ColumnId,
     pub direction: SortDirection,
+    pub nulls: NullsOrder,
+}
+
+/// Aggregate call with its argument bound to the input's columns.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvedAggregate {
+    pub func: AggregateFunc,
+    /// The argument; `None` for `COUNT(*)`.
+    pub arg: Option<ResolvedExpr>,
 }
 
+/// Index name under which an index scan reads a table's primary key index,
+/// which is not a catalog index.
+pub const PRIMARY_KEY_INDEX: &str = "PRIMARY KEY";
+
 /// Index predicate for index scans.
 #[derive(Clone, Debug, PartialEq)]
 pub enum IndexPredicate {
@@ -208,6 +386,27 @@ pub enum ResolvedExpr {
         op: BinaryOp,
         right: Box<ResolvedExpr>,
     },
+    /// Scalar function call; `name` is a key in `expr::functions`.
+    Function {
+        name: String,
+        args: Vec<ResolvedExpr>,
+    },
+    /// `CAST(expr AS ty)`; see [`Value::cast`].
+    Cast {
+        expr: Box<ResolvedExpr>,
+        ty: SqlType,
+    },
+    /// `expr IS [NOT] NULL`; see [`Expr::IsNull`].
+    IsNull {
+        expr: Box<ResolvedExpr>,
+        negated: bool,
+    },
+    /// `CASE [operand] WHEN .. THEN .. [ELSE ..] END`; see [`Expr::Case`].
+    Case {
+        operand: Option<Box<ResolvedExpr>>,
+        branches: Vec<(ResolvedExpr, ResolvedExpr)>,
+        else_result: Option<Box<ResolvedExpr>>,
+    },
 }
 
 /// Planning context - holds catalog for schema lookups.
@@ -237,85 +436,329 @@ impl Planner {
     ///
     /// # Steps
     ///
-    /// 1. Lower AST to logical plan
+    /// 1. Lower AST to logical plan, expanding views
     /// 2. Apply optimization rules
     /// 3. Bind names to IDs and select access methods
+    /// 4. Check the types of the bound expressions
     ///
     /// # Errors
     ///
     /// Returns `DbError::Planner` if:
     /// - Table or column names don't exist
     /// - Statement type is unsupported (DDL in v1)
+    /// - An operator is applied to operands of types it cannot take, a
+    ///   condition is not boolean, or a column is given a value of a type it
+    ///   cannot hold
     pub fn plan(stmt: Statement, ctx: &mut PlanningContext) -> DbResult<PhysicalPlan> {
-        let logical = Self::lower_to_logical(stmt)?;
+        let logical = Self::expand_views(Self::lower_to_logical(stmt)?, ctx, &mut Vec::new())?;
         let optimized = Self::optimize(logical, ctx)?;
-        Self::bind(optimized, ctx)
+        let plan = Self::bind(optimized, ctx)?;
+        typecheck::check_plan(&plan, ctx)?;
+        let plan = cost::order_joins(plan, ctx.catalog);
+        let plan = hash_join::use_hash_joins(plan, ctx);
+        Ok(prune::prune_columns(plan))
+    }
+
+    /// Arrange INSERT rows in table column order.
+    ///
+    /// With an explicit column list, each value goes to its listed column.
+    /// Columns that are not supplied, including trailing columns of a short
+    /// positional row, get the column's DEFAULT (NULL if it has none); the
+    /// implicit row version starts at 1.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DbError::Planner` if a column doesn't exist, or a row has
+    /// more values than the table has columns or a different number of
+    /// values than the column list names.
+    pub fn expand_insert_rows(
+        table: &TableMeta,
+        columns: &[String],
+        rows: Vec<Vec<Expr>>,
+    ) -> DbResult<Vec<Vec<Expr>>> {
+        let schema = table.columns();
+        let targets: Vec<usize> = if columns.is_empty() {
+            (0..schema.len()).collect()
+        } else {
+            columns
+                .iter()
+                .map(|name| {
+                    table
+                        .schema
+                        .column_index(name)
+                        .map(|id| id as usize)
+                        .ok_or_else(|| {
+                            DbError::Planner(format!(
+                                "unknown column '{}' in INSERT into '{}'",
+                                name, table.name
+                            ))
+                        })
+                })
+                .collect::<DbResult<_>>()?
+        };
+        let version_col = table.row_version_column().map(|id| id as usize);
+
+        for (number, row) in rows.iter().enumerate().map(|(i, row)| (i + 1, row)) {
+            if columns.is_empty() && row.len() > schema.len() {
+                return Err(DbError::Planner(format!(
+                    "INSERT into '{}' has {} values in row {number}, but the table has {} columns",
+                    table.name,
+                    row.len(),
+                    schema.len()
+                )));
+            }
+            if !columns.is_empty() && row.len() != columns.len() {
+                return Err(DbError::Planner(format!(
+                    "INSERT into '{}' has {} values in row {number} for {} target columns",
+                    table.name,
+                    row.len(),
+                    columns.len()
+                )));
+            }
+        }
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                // A full positional row is already in column order
+                if columns.is_empty() && row.len() == schema.len() {
+                    return row;
+                }
+                let mut slots: Vec<Option<Expr>> = vec![None; schema.len()];
+                for (value, &target) in row.into_iter().zip(&targets) {
+                    slots[target] = Some(value);
+                }
+                slots
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, slot)| {
+                        slot.unwrap_or_else(|| {
+                            if Some(idx) == version_col {
+                                Expr::Literal(Value::Int(1))
+                            } else {
+                                Expr::Literal(schema[idx].default_value())
+                            }
+                        })
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Check that each literal in an INSERT row has its column's type, or
+    /// one that it is converted from on storage (see [`Value::coerce_to`]).
+    /// Other expressions are checked as their rows are inserted.
+    fn check_insert_literals(table: &TableMeta, row: &[ResolvedExpr]) -> DbResult<()> {
+        for (column, expr) in table.columns().iter().zip(row) {
+            let ResolvedExpr::Literal(value) = expr else {
+                continue;
+            };
+            let fits = matches!(
+                (value.clone().coerce_to(&column.ty), &column.ty),
+                (Value::Null, _)
+                    | (Value::Int(_), SqlType::Int)
+                    | (Value::Text(_), SqlType::Text)
+                    | (Value::Bool(_), SqlType::Bool)
+                    | (Value::Float(_), SqlType::Float)
+                    | (Value::Date(_), SqlType::Date)
+                    | (Value::Timestamp(_), SqlType::Timestamp)
+                    | (Value::Decimal(_), SqlType::Decimal { .. })
+                    | (Value::Bytes(_), SqlType::Bytes)
+            );
+            if !fits {
+                return Err(DbError::Planner(format!(
+                    "column '{}' of table '{}' has type {} and cannot hold {}",
+                    column.name,
+                    table.name,
+                    column.ty,
+                    value.describe()
+                )));
+            }
+        }
+        Ok(())
     }
 
     /// Lower parser AST to logical plan.
     fn lower_to_logical(stmt: Statement) -> DbResult<LogicalPlan> {
         match stmt {
             Statement::CreateTable { .. }
+            | Statement::CreateTableAs { .. }
             | Statement::DropTable { .. }
+            | Statement::AlterTable { .. }
             | Statement::CreateIndex { .. }
-            | Statement::DropIndex { .. } => {
+            | Statement::DropIndex { .. }
+            | Statement::CreateView { .. }
+            | Statement::DropView { .. }
+            | Statement::Analyze { .. }
+            | Statement::Vacuum { .. }
+            | Statement::AdminGc
+            | Statement::ShowTableStats { .. } => {
                 Err(DbError::Planner("DDL handled elsewhere in v1".into()))
             }
-            Statement::Explain { query, .. } => {
+            Statement::SetTransaction { .. } => Err(DbError::Planner(
+                "SET TRANSACTION is a session setting, not a plannable statement".into(),
+            )),
+            Statement::SetRandomSeed { .. } => Err(DbError::Planner(
+                "SET random_seed is a session setting, not a plannable statement".into(),
+            )),
+            Statement::SetPriority { .. } => Err(DbError::Planner(
+                "SET statement_priority is a session setting, not a plannable statement".into(),
+            )),
+            Statement::SetGlobal { .. } => Err(DbError::Planner(
+                "SET GLOBAL changes a node setting, not a plannable statement".into(),
+            )),
+            Statement::Explain { query, .. } | Statement::Profile { query } => {
                 // For EXPLAIN, just plan the inner query
                 // The analyze flag will be handled by the REPL/executor
                 Self::lower_to_logical(*query)
             }
-            Statement::Insert { table, values } => Ok(LogicalPlan::Insert { table, values }),
+            // The rows are planned here; the database writes them to the file
+            Statement::CopyTo { query, .. } => Self::lower_to_logical(*query),
+            // The database runs the query and inserts its rows as VALUES
+            Statement::Insert { query: Some(_), .. } => Err(DbError::Planner(
+                "INSERT ... SELECT runs its query before the insert is planned".into(),
+            )),
+            Statement::Insert {
+                table,
+                columns,
+                rows,
+                query: None,
+            } => Ok(LogicalPlan::Insert {
+                table,
+                columns,
+                rows,
+            }),
             Statement::Update {
                 table,
+                alias,
                 assignments,
+                from,
                 selection,
-            } => Ok(LogicalPlan::Update {
-                table,
-                assignments,
-                predicate: selection,
-            }),
-            Statement::Delete { table, selection } => Ok(LogicalPlan::Delete {
+            } => {
+                let target = TableRef {
+                    name: table,
+                    alias,
+                    args: None,
+                    sample: None,
+                    values: None,
+                };
+                // Without other tables the rows are the table's own, whose
+                // columns are unqualified
+                let (assignments, selection) = if from.is_empty() {
+                    let qualifier = target.effective_name();
+                    (
+                        assignments
+                            .into_iter()
+                            .map(|(column, e)| (column, unqualify(e, qualifier)))
+                            .collect(),
+                        selection.map(|e| unqualify(e, qualifier)),
+                    )
+                } else {
+                    (assignments, selection)
+                };
+                Ok(LogicalPlan::Update {
+                    source: Self::dml_source(&target, from)?.map(Box::new),
+                    table: target.name,
+                    assignments,
+                    predicate: selection,
+                })
+            }
+            Statement::Delete {
                 table,
-                predicate: selection,
-            }),
+                alias,
+                using,
+                selection,
+            } => {
+                let target = TableRef {
+                    name: table,
+                    alias,
+                    args: None,
+                    sample: None,
+                    values: None,
+                };
+                let selection = if using.is_empty() {
+                    selection.map(|e| unqualify(e, target.effective_name()))
+                } else {
+                    selection
+                };
+                Ok(LogicalPlan::Delete {
+                    source: Self::dml_source(&target, using)?.map(Box::new),
+                    table: target.name,
+                    predicate: selection,
+                })
+            }
             Statement::Select {
                 columns,
                 from,
                 joins,
                 selection,
+                group_by,
+                having,
                 order_by,
                 limit,
                 offset,
+                lock: _,
             } => {
+                let joins = rename_shadowing_subqueries(&from, joins);
+                ensure_distinct_names(
+                    std::iter::once(&from).chain(joins.iter().map(|join| &join.table)),
+                )?;
+
+                // A lone table's columns are unqualified, so references
+                // qualified by its name or alias drop the qualifier. Semi and
+                // anti joins from subqueries add no columns, so do not count.
+                let (subqueries, joins): (Vec<_>, Vec<_>) = joins
+                    .into_iter()
+                    .partition(|join| join.join_type != JoinType::Inner);
+                let (columns, selection, group_by, having, order_by) = if joins.is_empty() {
+                    let qualifier = from.effective_name();
+                    (
+                        columns
+                            .into_iter()
+                            .map(|item| unqualify_item(item, qualifier))
+                            .collect(),
+                        selection.map(|e| unqualify(e, qualifier)),
+                        group_by
+                            .into_iter()
+                            .map(|e| unqualify(e, qualifier))
+                            .collect(),
+                        having.map(|e| unqualify(*e, qualifier)),
+                        order_by
+                            .into_iter()
+                            .map(|o| parser::OrderByExpr {
+                                expr: unqualify(o.expr, qualifier),
+                                ..o
+                            })
+                            .collect(),
+                    )
+                } else {
+                    (columns, selection, group_by, having.map(|e| *e), order_by)
+                };
+
                 // Build initial scan from primary FROM table
                 let from_name = from.effective_name().to_string();
-                let mut plan = LogicalPlan::TableScan {
-                    table: from.name.clone(),
-                };
+                let mut plan = scan(&from);
 
                 // Add JOINs left-to-right
                 let mut current_left_name = from_name;
                 for join_clause in joins {
                     let right_name = join_clause.table.effective_name().to_string();
-                    let right_scan = LogicalPlan::TableScan {
-                        table: join_clause.table.name.clone(),
-                    };
+                    let right_scan = scan(&join_clause.table);
                     plan = LogicalPlan::Join {
                         left: Box::new(plan),
                         right: Box::new(right_scan),
                         join_type: join_clause.join_type,
                         condition: join_clause.condition,
+                        key: None,
                         left_name: current_left_name.clone(),
                         right_name: right_name.clone(),
                     };
-                    // For chained joins, the effective name becomes complex
-                    // but we don't support chained joins in v1, so this is fine
+                    // Once joined, every column is qualified by its table, so
+                    // later joins never qualify columns with this name
                     current_left_name = format!("{}_{}", current_left_name, right_name);
                 }
 
-                let with_filter = if let Some(pred) = selection {
+                let mut with_filter = if let Some(pred) = selection {
                     LogicalPlan::Filter {
                         input: Box::new(plan),
                         predicate: pred,
@@ -323,22 +766,83 @@ impl Planner {
                 } else {
                     plan
                 };
+
+                // Subqueries keep or drop the rows left after the WHERE
+                // clause's other conditions
+                for subquery in subqueries {
+                    with_filter = LogicalPlan::Join {
+                        left: Box::new(with_filter),
+                        right: Box::new(scan(&subquery.table)),
+                        join_type: subquery.join_type,
+                        condition: subquery.condition,
+                        key: subquery.key,
+                        left_name: current_left_name.clone(),
+                        right_name: subquery.table.effective_name().to_string(),
+                    };
+                }
+
+                // With GROUP BY, HAVING or an aggregate call, the select list,
+                // HAVING and ORDER BY read the aggregation's output, so their
+                // aggregate calls and grouped expressions become references
+                // to its columns
+                let aggregated = !group_by.is_empty()
+                    || having.is_some()
+                    || columns.iter().any(|item| match item {
+                        SelectItem::Expr { expr, .. } => has_aggregate(expr),
+                        _ => false,
+                    })
+                    || order_by.iter().any(|o| has_aggregate(&o.expr));
+                let (columns, order_by) = if aggregated {
+                    if is_wildcard(&columns) {
+                        return Err(DbError::Planner(
+                            "SELECT * cannot be used with GROUP BY or aggregate functions".into(),
+                        ));
+                    }
+                    let mut calls = Vec::new();
+                    let mut replace = |e| replace_aggregates(e, &group_by, &mut calls);
+                    let columns: Vec<_> = columns
+                        .into_iter()
+                        .map(|item| match item {
+                            SelectItem::Expr { expr, alias } => SelectItem::Expr {
+                                expr: replace(expr),
+                                alias,
+                            },
+                            other => other,
+                        })
+                        .collect();
+                    let having = having.map(&mut replace);
+                    let order_by: Vec<_> = order_by
+                        .into_iter()
+                        .map(|o| parser::OrderByExpr {
+                            expr: replace(o.expr),
+                            ..o
+                        })
+                        .collect();
+                    with_filter = LogicalPlan::Aggregate {
+                        input: Box::new(with_filter),
+                        group_by,
+                        aggregates: calls,
+                    };
+                    if let Some(having) = having {
+                        with_filter = LogicalPlan::Filter {
+                            input: Box::new(with_filter),
+                            predicate: having,
+                        };
+                    }
+                    (columns, order_by)
+                } else {
+                    (columns, order_by)
+                };
+
                 let with_project = if columns.iter().any(|c| matches!(c, SelectItem::Wildcard)) {
                     LogicalPlan::Project {
                         input: Box::new(with_filter),
-                        columns: vec!["*".into()],
+                        columns: vec![SelectItem::Wildcard],
                     }
                 } else {
-                    let names = columns
-                        .into_iter()
-                        .map(|c| match c {
-                            SelectItem::Column(name) => name,
-                            SelectItem::Wildcard => unreachable!(),
-                        })
-                        .collect();
                     LogicalPlan::Project {
                         input: Box::new(with_filter),
-                        columns: names,
+                        columns,
                     }
                 };
 
@@ -347,8 +851,9 @@ impl Planner {
                     let order_exprs = order_by
                         .into_iter()
                         .map(|o| OrderByExpr {
-                            column: o.column,
+                            expr: o.expr,
                             direction: o.direction,
+                            nulls: o.nulls,
                         })
                         .collect();
                     LogicalPlan::Sort {
@@ -375,6 +880,150 @@ impl Planner {
         }
     }
 
+    /// The rows an `UPDATE ... FROM` or `DELETE ... USING` matches: every
+    /// row of `target` joined to every row of each of `others`, for the
+    /// statement's predicate to filter. `None` if there are no others.
+    fn dml_source(target: &TableRef, others: Vec<TableRef>) -> DbResult<Option<LogicalPlan>> {
+        if others.is_empty() {
+            return Ok(None);
+        }
+        ensure_distinct_names(std::iter::once(target).chain(&others))?;
+
+        let mut plan = LogicalPlan::TableScan {
+            table: target.name.clone(),
+        };
+        let mut left_name = target.effective_name().to_string();
+        for other in others {
+            let right_name = other.effective_name().to_string();
+            plan = LogicalPlan::Join {
+                left: Box::new(plan),
+                right: Box::new(scan(&other)),
+                join_type: JoinType::Inner,
+                condition: Expr::Literal(Value::Bool(true)),
+                key: None,
+                left_name: left_name.clone(),
+                right_name: right_name.clone(),
+            };
+            left_name = format!("{left_name}_{right_name}");
+        }
+        Ok(Some(plan))
+    }
+
+    /// Replace scans of views with the plans of their queries.
+    ///
+    /// `expanding` holds the views whose queries are being expanded, so a view
+    /// that refers back to itself is an error rather than endless recursion.
+    fn expand_views(
+        plan: LogicalPlan,
+        ctx: &PlanningContext,
+        expanding: &mut Vec<String>,
+    ) -> DbResult<LogicalPlan> {
+        use LogicalPlan::*;
+        Ok(match plan {
+            TableScan { table } => {
+                let Ok(view) = ctx.catalog.view(&table) else {
+                    return Ok(TableScan { table });
+                };
+                if expanding.contains(&view.name) {
+                    return Err(DbError::Planner(format!(
+                        "view '{}' refers to itself",
+                        view.name
+                    )));
+                }
+                let mut stmts = parser::parse_sql(&view.query)?;
+                let query = match (stmts.pop(), stmts.is_empty()) {
+                    (Some(query @ Statement::Select { .. }), true) => query,
+                    _ => {
+                        return Err(DbError::Planner(format!(
+                            "view '{}' is not defined by a single SELECT",
+                            view.name
+                        )));
+                    }
+                };
+                expanding.push(view.name.clone());
+                let expanded = Self::expand_views(Self::lower_to_logical(query)?, ctx, expanding);
+                expanding.pop();
+                expanded?
+            }
+            Filter { input, predicate } => Filter {
+                input: Box::new(Self::expand_views(*input, ctx, expanding)?),
+                predicate,
+            },
+            Project { input, columns } => Project {
+                input: Box::new(Self::expand_views(*input, ctx, expanding)?),
+                columns,
+            },
+            Aggregate {
+                input,
+                group_by,
+                aggregates,
+            } => Aggregate {
+                input: Box::new(Self::expand_views(*input, ctx, expanding)?),
+                group_by,
+                aggregates,
+            },
+            Sort { input, order_by } => Sort {
+                input: Box::new(Self::expand_views(*input, ctx, expanding)?),
+                order_by,
+            },
+            Limit {
+                input,
+                limit,
+                offset,
+            } => Limit {
+                input: Box::new(Self::expand_views(*input, ctx, expanding)?),
+                limit,
+                offset,
+            },
+            Join {
+                left,
+                right,
+                join_type,
+                condition,
+                key,
+                left_name,
+                right_name,
+            } => Join {
+                left: Box::new(Self::expand_views(*left, ctx, expanding)?),
+                right: Box::new(Self::expand_views(*right, ctx, expanding)?),
+                join_type,
+                condition,
+                key,
+                left_name,
+                right_name,
+            },
+            Update {
+                table,
+                assignments,
+                predicate,
+                source,
+            } => Update {
+                table,
+                assignments,
+                predicate,
+                source: source
+                    .map(|source| Self::expand_views(*source, ctx, expanding).map(Box::new))
+                    .transpose()?,
+            },
+            Delete {
+                table,
+                predicate,
+                source,
+            } => Delete {
+                table,
+                predicate,
+                source: source
+                    .map(|source| Self::expand_views(*source, ctx, expanding).map(Box::new))
+                    .transpose()?,
+            },
+            Sample { input, sample } => Sample {
+                input: Box::new(Self::expand_views(*input, ctx, expanding)?),
+                sample,
+            },
+            Insert { .. } | TableFunction { .. } | Values { .. } => plan,
+        })
+    }
+
     /// Apply optimization rules.
     fn optimize(plan: LogicalPlan, _ctx: &mut PlanningContext) -> DbResult<LogicalPlan> {
         let p1 = Self::pushdown(plan);
@@ -392,7 +1041,7 @@ impl Planner {
                     columns,
                 } => {
                     // Only push down if projection is wildcard
-                    if columns.len() == 1 && columns[0] == "*" {
+                    if is_wildcard(&columns) {
                         Filter {
                             input: inner,
                             predicate,
@@ -416,6 +1065,15 @@ impl Planner {
                 input: Box::new(Self::pushdown(*input)),
                 columns,
             },
+            Aggregate {
+                input,
+                group_by,
+                aggregates,
+            } => Aggregate {
+                input: Box::new(Self::pushdown(*input)),
+                group_by,
+                aggregates,
+            },
             Sort { input, order_by } => Sort {
                 input: Box::new(Self::pushdown(*input)),
                 order_by,
@@ -429,13 +1087,23 @@ impl Planner {
                 limit,
                 offset,
             },
-            Insert { .. } | Update { .. } | Delete { .. } | TableScan { .. } => plan,
+            Sample { input, sample } => Sample {
+                input: Box::new(Self::pushdown(*input)),
+                sample,
+            },
+            Insert { .. }
+            | Update { .. }
+            | Delete { .. }
+            | TableScan { .. }
+            | TableFunction { .. }
+            | Values { .. } => plan,
             // For joins, recurse into both sides but don't try to push filters through yet
             Join {
                 left,
                 right,
                 join_type,
                 condition,
+                key,
                 left_name,
                 right_name,
             } => Join {
@@ -443,6 +1111,7 @@ impl Planner {
                 right: Box::new(Self::pushdown(*right)),
                 join_type,
                 condition,
+                key,
                 left_name,
                 right_name,
             },
@@ -459,7 +1128,7 @@ impl Planner {
                     columns: inner_cols,
                 } => {
                     // Remove double project when top is wildcard
-                    if columns.len() == 1 && columns[0] == "*" {
+                    if is_wildcard(&columns) {
                         Project {
                             input: inner,
                             columns: inner_cols,
@@ -504,18 +1173,45 @@ impl Planner {
     fn bind(plan: LogicalPlan, ctx: &mut PlanningContext) -> DbResult<PhysicalPlan> {
         match plan {
             LogicalPlan::TableScan { table } => {
+                if let Some(view) = SystemView::from_name(&table) {
+                    return Ok(PhysicalPlan::SystemScan {
+                        view,
+                        schema: view.columns().into_iter().map(|c| c.name).collect(),
+                    });
+                }
                 let t = ctx.table(&table)?;
                 Ok(PhysicalPlan::SeqScan {
                     table_id: t.id,
                     schema: t.schema.columns().iter().map(|c| c.name.clone()).collect(),
+                    projection: None,
                 })
             }
+            LogicalPlan::TableFunction { name, args } => Self::bind_table_function(&name, args),
+            LogicalPlan::Values { rows } => Self::bind_values(rows),
+            LogicalPlan::Sample { input, sample } => match Self::bind(*input, ctx)? {
+                PhysicalPlan::SeqScan {
+                    table_id,
+                    schema,
+                    projection,
+                } => Ok(PhysicalPlan::SampleScan {
+                    table_id,
+                    sample,
+                    schema,
+                    projection,
+                }),
+                _ => Err(DbError::Planner(
+                    "TABLESAMPLE can only sample a table".into(),
+                )),
+            },
             LogicalPlan::Filter { input, predicate } => {
                 let input_physical = Self::bind(*input, ctx)?;
-                let resolved = Self::bind_expr(&input_physical, predicate, ctx)?;
+                let resolved = Self::bind_expr(&input_physical, predicate, ctx)
+                    .map_err(|e| Self::grouping_error(&input_physical, e))?;
 
                 // Try index scan optimization using composite key selection
-                if let PhysicalPlan::SeqScan { table_id, schema } = &input_physical
+                if let PhysicalPlan::SeqScan {
+                    table_id, schema, ..
+                } = &input_physical
                     && let Some((index_name, idx_pred)) =
                         Self::find_best_index(ctx, table_id, &resolved)
                 {
@@ -524,6 +1220,7 @@ impl Planner {
                         index_name,
                         predicate: idx_pred,
                         schema: schema.clone(),
+                        projection: None,
                     };
                     return Ok(PhysicalPlan::Filter {
                         input: Box::new(idx_scan),
@@ -531,6 +1228,40 @@ impl Planner {
                     });
                 }
 
+                if let PhysicalPlan::SeqScan {
+                    table_id, schema, ..
+                } = &input_physical
+                    && let Some(probes) = Self::find_index_union(ctx, table_id, &resolved)
+                {
+                    let union = PhysicalPlan::IndexUnion {
+                        table_id: *table_id,
+                        probes,
+                        schema: schema.clone(),
+                        projection: None,
+                    };
+                    return Ok(PhysicalPlan::Filter {
+                        input: Box::new(union),
+                        predicate: resolved,
+                    });
+                }
+
+                if let PhysicalPlan::SeqScan {
+                    table_id, schema, ..
+                } = &input_physical
+                    && let Some(partitions) = Self::prune_partitions(ctx, table_id, &resolved)
+                {
+                    let partition_scan = PhysicalPlan::PartitionScan {
+                        table_id: *table_id,
+                        partitions,
+                        schema: schema.clone(),
+                        projection: None,
+                    };
+                    return Ok(PhysicalPlan::Filter {
+                        input: Box::new(partition_scan),
+                        predicate: resolved,
+                    });
+                }
+
                 Ok(PhysicalPlan::Filter {
                     input: Box::new(input_physical),
                     predicate: resolved,
@@ -540,27 +1271,34 @@ impl Planner {
                 let input_physical = Self::bind(*input, ctx)?;
                 let schema = Self::output_schema(&input_physical);
 
-                if columns.len() == 1 && columns[0] == "*" {
-                    let cols: Vec<(String, ColumnId)> = schema
-                        .iter()
-                        .enumerate()
-                        .map(|(i, name)| (name.clone(), i as ColumnId))
-                        .collect();
+                if is_wildcard(&columns) {
                     return Ok(PhysicalPlan::Project {
                         input: Box::new(input_physical),
-                        columns: cols,
+                        columns: Self::identity_columns(&schema),
                     });
                 }
 
                 let cols = columns
                     .into_iter()
-                    .map(|name| {
-                        let idx = schema
-                            .iter()
-                            .position(|c| c.eq_ignore_ascii_case(&name))
-                            .ok_or_else(|| DbError::Planner(format!("unknown column '{name}'")))?
-                            as ColumnId;
-                        Ok((name, idx))
+                    .map(|item| match item {
+                        SelectItem::Column(name) => {
+                            let (table, column) = match name.split_once('.') {
+                                Some((table, column)) => (Some(table), column),
+                                None => (None, name.as_str()),
+                            };
+                            let idx = Self::find_column_in_schema(&schema, table, column)
+                                .map_err(|e| Self::grouping_error(&input_physical, e))?;
+                            Ok((name, ResolvedExpr::Column(idx as ColumnId)))
+                        }
+                        SelectItem::Expr { expr, alias } => {
+                            let name = alias.unwrap_or_else(|| expr.to_string());
+                            let expr = Self::bind_expr_with_schema(&schema, expr)
+                                .map_err(|e| Self::grouping_error(&input_physical, e))?;
+                            Ok((name, expr))
+                        }
+                        SelectItem::Wildcard => Err(DbError::Planner(
+                            "wildcard must be the only select item".into(),
+                        )),
                     })
                     .collect::<DbResult<Vec<_>>>()?;
 
@@ -569,26 +1307,37 @@ impl Planner {
                     columns: cols,
                 })
             }
-            LogicalPlan::Insert { table, values } => {
+            LogicalPlan::Insert {
+                table,
+                columns,
+                rows,
+            } => {
                 let t = ctx.table(&table)?;
-                let vals = values
+                let rows = Self::expand_insert_rows(t, &columns, rows)?
                     .into_iter()
-                    .map(Self::bind_expr_seq)
+                    .map(|row| {
+                        let row = row
+                            .into_iter()
+                            .map(Self::bind_expr_seq)
+                            .collect::<DbResult<Vec<_>>>()?;
+                        Self::check_insert_literals(t, &row)?;
+                        Ok(row)
+                    })
                     .collect::<DbResult<Vec<_>>>()?;
                 Ok(PhysicalPlan::Insert {
                     table_id: t.id,
-                    values: vals,
+                    rows,
                 })
             }
             LogicalPlan::Update {
                 table,
                 assignments,
                 predicate,
+                source,
             } => {
+                let (schema_names, source) = Self::bind_dml_source(&table, source, ctx)?;
                 let t = ctx.table(&table)?;
                 let schema = &t.schema;
-                let schema_names: Vec<String> =
-                    schema.columns().iter().map(|c| c.name.clone()).collect();
                 let assigns = assignments
                     .into_iter()
                     .map(|(name, e)| {
@@ -602,53 +1351,83 @@ impl Planner {
                 let pred = predicate
                     .map(|p| Self::bind_expr_with_schema(&schema_names, p))
                     .transpose()?;
+                let table_id = t.id;
+                let source =
+                    source.or_else(|| Self::dml_index_scan(ctx, table_id, schema_names, &pred));
                 Ok(PhysicalPlan::Update {
-                    table_id: t.id,
+                    table_id,
                     assignments: assigns,
                     predicate: pred,
+                    source,
                 })
             }
-            LogicalPlan::Delete { table, predicate } => {
+            LogicalPlan::Delete {
+                table,
+                predicate,
+                source,
+            } => {
+                let (schema_names, source) = Self::bind_dml_source(&table, source, ctx)?;
                 let t = ctx.table(&table)?;
-                let schema_names: Vec<String> =
-                    t.schema.columns().iter().map(|c| c.name.clone()).collect();
                 let pred = predicate
                     .map(|p| Self::bind_expr_with_schema(&schema_names, p))
                     .transpose()?;
+                let table_id = t.id;
+                let source =
+                    source.or_else(|| Self::dml_index_scan(ctx, table_id, schema_names, &pred));
                 Ok(PhysicalPlan::Delete {
-                    table_id: t.id,
+                    table_id,
                     predicate: pred,
+                    source,
                 })
             }
-            LogicalPlan::Sort { input, order_by } => {
+            LogicalPlan::Aggregate {
+                input,
+                group_by,
+                aggregates,
+            } => {
                 let input_physical = Self::bind(*input, ctx)?;
-                let schema = Self::output_schema(&input_physical);
-
-                // Resolve column names to column IDs
-                let resolved_order_by = order_by
+                let input_schema = Self::output_schema(&input_physical);
+                let mut schema = Vec::with_capacity(group_by.len() + aggregates.len());
+                let group_by = group_by
                     .into_iter()
-                    .map(|order_expr| {
-                        let col_id = schema
-                            .iter()
-                            .position(|c| c.eq_ignore_ascii_case(&order_expr.column))
-                            .ok_or_else(|| {
-                                DbError::Planner(format!(
-                                    "unknown column '{}' in ORDER BY",
-                                    order_expr.column
-                                ))
-                            })? as ColumnId;
-                        Ok(ResolvedOrderByExpr {
-                            column_id: col_id,
-                            direction: order_expr.direction,
-                        })
+                    .map(|key| {
+                        let name = key.to_string();
+                        let key = Self::bind_expr_with_schema(&input_schema, key)?;
+                        // A grouped column keeps its name, qualifier included,
+                        // so references to it bind as they would below
+                        schema.push(match key {
+                            ResolvedExpr::Column(id) => input_schema[id as usize].clone(),
+                            _ => name,
+                        });
+                        Ok(key)
                     })
                     .collect::<DbResult<Vec<_>>>()?;
-
-                Ok(PhysicalPlan::Sort {
+                let aggregates = aggregates
+                    .into_iter()
+                    .map(|call| {
+                        schema.push(call.to_string());
+                        let Expr::Aggregate { func, arg } = call else {
+                            return Err(DbError::Planner(format!(
+                                "{call} is not an aggregate function call"
+                            )));
+                        };
+                        let arg = arg
+                            .map(|arg| Self::bind_expr_with_schema(&input_schema, *arg))
+                            .transpose()?;
+                        Ok(ResolvedAggregate { func, arg })
+                    })
+                    .collect::<DbResult<Vec<_>>>()?;
+                Ok(PhysicalPlan::Aggregate {
                     input: Box::new(input_physical),
-                    order_by: resolved_order_by,
+                    group_by,
+                    aggregates,
+                    schema,
                 })
             }
+            LogicalPlan::Sort { input, order_by } => {
+                let input_physical = Self::bind(*input, ctx)?;
+                Self::bind_sort(input_physical, order_by)
+            }
             LogicalPlan::Limit {
                 input,
                 limit,
@@ -664,8 +1443,9 @@ impl Planner {
             LogicalPlan::Join {
                 left,
                 right,
-                join_type: _,
+                join_type,
                 condition,
+                key,
                 left_name,
                 right_name,
             } => {
@@ -697,28 +1477,147 @@ impl Planner {
                     }))
                     .collect();
 
-                // Bind condition expression with combined schema
-                let resolved_condition =
-                    Self::bind_expr_with_schema(&combined_schema, condition)?;
+                if join_type == JoinType::Inner {
+                    // Bind condition expression with combined schema
+                    let resolved_condition =
+                        Self::bind_expr_with_schema(&combined_schema, condition)?;
+
+                    return Ok(PhysicalPlan::NestedLoopJoin {
+                        left: Box::new(left_physical),
+                        right: Box::new(right_physical),
+                        condition: resolved_condition,
+                        schema: combined_schema,
+                    });
+                }
+
+                // A subquery's own columns hide the outer query's of the same
+                // name, so its unqualified names are looked up there first
+                let scope = |e: Expr| {
+                    map_columns(e, &|table, name| match table {
+                        None if Self::find_column_in_schema(&right_schema, None, &name).is_ok() => {
+                            Expr::Column {
+                                table: Some(right_name.clone()),
+                                name,
+                            }
+                        }
+                        table => Expr::Column { table, name },
+                    })
+                };
+                let mut resolved_condition =
+                    Self::bind_expr_with_schema(&combined_schema, scope(condition))?;
+                if let Some((outer, inner)) = key {
+                    let equal = ResolvedExpr::Binary {
+                        left: Box::new(Self::bind_expr_with_schema(
+                            &combined_schema[..left_schema.len()],
+                            outer,
+                        )?),
+                        op: BinaryOp::Eq,
+                        right: Box::new(Self::bind_expr_with_schema(
+                            &combined_schema,
+                            scope(inner),
+                        )?),
+                    };
+                    resolved_condition = match resolved_condition {
+                        ResolvedExpr::Literal(Value::Bool(true)) => equal,
+                        condition => ResolvedExpr::Binary {
+                            left: Box::new(equal),
+                            op: BinaryOp::And,
+                            right: Box::new(condition),
+                        },
+                    };
+                }
 
-                Ok(PhysicalPlan::NestedLoopJoin {
-                    left: Box::new(left_physical),
-                    right: Box::new(right_physical),
-                    condition: resolved_condition,
-                    schema: combined_schema,
+                let (left, right) = (Box::new(left_physical), Box::new(right_physical));
+                Ok(match join_type {
+                    JoinType::Anti => PhysicalPlan::AntiJoin {
+                        left,
+                        right,
+                        condition: resolved_condition,
+                        schema: left_schema,
+                    },
+                    _ => PhysicalPlan::SemiJoin {
+                        left,
+                        right,
+                        condition: resolved_condition,
+                        schema: left_schema,
+                    },
                 })
             }
         }
     }
 
+    /// Bind the rows an UPDATE or DELETE of `table` reads, returning the
+    /// names of their columns and the bound source, if there is one.
+    fn bind_dml_source(
+        table: &str,
+        source: Option<Box<LogicalPlan>>,
+        ctx: &mut PlanningContext,
+    ) -> DbResult<(Vec<String>, Option<Box<PhysicalPlan>>)> {
+        match source {
+            Some(source) => {
+                let source = Self::bind(*source, ctx)?;
+                Ok((Self::output_schema(&source), Some(Box::new(source))))
+            }
+            None => {
+                let t = ctx.table(table)?;
+                let schema = t.schema.columns().iter().map(|c| c.name.clone()).collect();
+                Ok((schema, None))
+            }
+        }
+    }
+
+    /// An index scan for the rows an UPDATE or DELETE that joins no other
+    /// tables changes, if an index can serve its predicate, chosen as for a
+    /// filtered `SELECT`. The predicate is still applied to the rows it
+    /// returns.
+    fn dml_index_scan(
+        ctx: &PlanningContext,
+        table_id: TableId,
+        schema: Vec<String>,
+        predicate: &Option<ResolvedExpr>,
+    ) -> Option<Box<PhysicalPlan>> {
+        let predicate = predicate.as_ref()?;
+        let scan = if let Some((index_name, predicate)) =
+            Self::find_best_index(ctx, &table_id, predicate)
+        {
+            PhysicalPlan::IndexScan {
+                table_id,
+                index_name,
+                predicate,
+                schema,
+                projection: None,
+            }
+        } else {
+            PhysicalPlan::IndexUnion {
+                table_id,
+                probes: Self::find_index_union(ctx, &table_id, predicate)?,
+                schema,
+                projection: None,
+            }
+        };
+        Some(Box::new(scan))
+    }
+
     /// Get the output schema (column names) from a physical plan.
     fn output_schema(plan: &PhysicalPlan) -> Vec<String> {
         match plan {
             PhysicalPlan::SeqScan { schema, .. }
+            | PhysicalPlan::PartitionScan { schema, .. }
+            | PhysicalPlan::SampleScan { schema, .. }
             | PhysicalPlan::IndexScan { schema, .. }
-            | PhysicalPlan::NestedLoopJoin { schema, .. } => schema.clone(),
+            | PhysicalPlan::IndexUnion { schema, .. }
+            | PhysicalPlan::SystemScan { schema, .. }
+            | PhysicalPlan::SeriesScan { schema, .. }
+            | PhysicalPlan::ValuesScan { schema, .. }
+            | PhysicalPlan::NestedLoopJoin { schema, .. }
+            | PhysicalPlan::HashJoin { schema, .. }
+            | PhysicalPlan::SemiJoin { schema, .. }
+            | PhysicalPlan::AntiJoin { schema, .. }
+            | PhysicalPlan::Aggregate { schema, .. } => schema.clone(),
+            PhysicalPlan::Project { columns, .. } => {
+                columns.iter().map(|(name, _)| name.clone()).collect()
+            }
             PhysicalPlan::Filter { input, .. }
-            | PhysicalPlan::Project { input, .. }
             | PhysicalPlan::Sort { input, .. }
             | PhysicalPlan::Limit { input, .. } => Self::output_schema(input),
             PhysicalPlan::Insert { .. }
@@ -727,6 +1626,111 @@ impl Planner {
         }
     }
 
+    /// Sort `input` by `order_by`.
+    ///
+    /// A key naming an output column sorts by that column. Any other key is
+    /// bound against the projection's input and, unless the projection
+    /// already computes it, appended to the projection as a hidden column
+    /// that a final projection drops after sorting.
+    fn bind_sort(input: PhysicalPlan, order_by: Vec<OrderByExpr>) -> DbResult<PhysicalPlan> {
+        let output = Self::output_schema(&input);
+        let projected = matches!(input, PhysicalPlan::Project { .. });
+        let (source, mut columns) = match input {
+            PhysicalPlan::Project { input, columns } => (*input, columns),
+            other => (other, Self::identity_columns(&output)),
+        };
+        let source_schema = Self::output_schema(&source);
+
+        let mut resolved_order_by = Vec::with_capacity(order_by.len());
+        for order_expr in order_by {
+            let output_column = match &order_expr.expr {
+                Expr::Column { table: None, name } => {
+                    output.iter().position(|c| c.eq_ignore_ascii_case(name))
+                }
+                _ => None,
+            };
+            let column_id = match output_column {
+                Some(position) => position,
+                None => {
+                    let key = Self::bind_expr_with_schema(&source_schema, order_expr.expr.clone())
+                        .map_err(|e| match (&order_expr.expr, e) {
+                            // Name the column as written, qualifier included
+                            (column @ Expr::Column { .. }, DbError::Planner(msg))
+                                if msg.starts_with("unknown column ") =>
+                            {
+                                DbError::Planner(format!("unknown column '{column}' in ORDER BY"))
+                            }
+                            (_, e) => e,
+                        })?;
+                    match columns.iter().position(|(_, expr)| *expr == key) {
+                        Some(position) => position,
+                        None => {
+                            columns.push((order_expr.expr.to_string(), key));
+                            columns.len() - 1
+                        }
+                    }
+                }
+            };
+            resolved_order_by.push(ResolvedOrderByExpr {
+                column_id: column_id as ColumnId,
+                direction: order_expr.direction,
+                nulls: order_expr.nulls,
+            });
+        }
+
+        let hidden = columns.len() > output.len();
+        let sort_input = if projected || hidden {
+            PhysicalPlan::Project {
+                input: Box::new(source),
+                columns,
+            }
+        } else {
+            source
+        };
+        let sort = PhysicalPlan::Sort {
+            input: Box::new(sort_input),
+            order_by: resolved_order_by,
+        };
+        if !hidden {
+            return Ok(sort);
+        }
+        Ok(PhysicalPlan::Project {
+            input: Box::new(sort),
+            columns: Self::identity_columns(&output),
+        })
+    }
+
+    /// Explain a failure to bind an expression over `input`: above an
+    /// aggregation, a column is unknown because it is neither grouped on
+    /// nor aggregated.
+    fn grouping_error(input: &PhysicalPlan, e: DbError) -> DbError {
+        let grouped = match input {
+            PhysicalPlan::Filter { input, .. } => &**input,
+            other => other,
+        };
+        match e {
+            DbError::Planner(msg)
+                if matches!(grouped, PhysicalPlan::Aggregate { .. })
+                    && msg.starts_with("unknown column ") =>
+            {
+                DbError::Planner(format!(
+                    "{} must appear in the GROUP BY clause or be used in an aggregate function",
+                    &msg["unknown ".len()..]
+                ))
+            }
+            e => e,
+        }
+    }
+
+    /// Projection passing every column of `schema` through unchanged.
+    fn identity_columns(schema: &[String]) -> Vec<(String, ResolvedExpr)> {
+        schema
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.clone(), ResolvedExpr::Column(i as ColumnId)))
+            .collect()
+    }
+
     /// Bind expression with input schema context.
     fn bind_expr(
         input: &PhysicalPlan,
@@ -737,6 +1741,50 @@ impl Planner {
         Self::bind_expr_with_schema(&schema, e)
     }
 
+    /// Bind a call of a table function. Its arguments are evaluated before
+    /// any row is read, so they cannot refer to columns.
+    fn bind_table_function(name: &str, args: Vec<Expr>) -> DbResult<PhysicalPlan> {
+        let mut args = args
+            .into_iter()
+            .map(Self::bind_expr_seq)
+            .collect::<DbResult<Vec<_>>>()?;
+        match (name, args.len()) {
+            ("generate_series", 2 | 3) => {
+                let step = if args.len() == 3 {
+                    args.remove(2)
+                } else {
+                    ResolvedExpr::Literal(Value::Int(1))
+                };
+                let stop = args.remove(1);
+                let start = args.remove(0);
+                Ok(PhysicalPlan::SeriesScan {
+                    start,
+                    stop,
+                    step,
+                    schema: vec![name.to_string()],
+                })
+            }
+            ("generate_series", _) => Err(DbError::Planner(
+                "generate_series expects (start, stop) or (start, stop, step)".into(),
+            )),
+            _ => Err(DbError::Planner(format!("unknown table function '{name}'"))),
+        }
+    }
+
+    /// Bind the rows of a `VALUES` list. Like a table function's arguments,
+    /// their expressions cannot refer to columns.
+    fn bind_values(rows: Vec<Vec<Expr>>) -> DbResult<PhysicalPlan> {
+        let width = rows.first().map_or(0, Vec::len);
+        let rows = rows
+            .into_iter()
+            .map(|row| row.into_iter().map(Self::bind_expr_seq).collect())
+            .collect::<DbResult<Vec<_>>>()?;
+        Ok(PhysicalPlan::ValuesScan {
+            rows,
+            schema: (1..=width).map(|i| format!("column{i}")).collect(),
+        })
+    }
+
     /// Bind standalone expression (no column context).
     fn bind_expr_seq(e: Expr) -> DbResult<ResolvedExpr> {
         Self::bind_expr_with_schema(&[], e)
@@ -759,6 +1807,60 @@ impl Planner {
                 op,
                 right: Box::new(Self::bind_expr_with_schema(schema, *right)?),
             }),
+            Expr::Function { name, args } => {
+                let func = expr::functions::lookup(&name)
+                    .ok_or_else(|| DbError::Planner(format!("unknown function '{name}'")))?;
+                if !func.accepts(args.len()) {
+                    return Err(DbError::Planner(format!(
+                        "function '{}' does not accept {} argument(s)",
+                        func.name,
+                        args.len()
+                    )));
+                }
+                let args = args
+                    .into_iter()
+                    .map(|arg| Self::bind_expr_with_schema(schema, arg))
+                    .collect::<DbResult<Vec<_>>>()?;
+                Ok(ResolvedExpr::Function {
+                    name: func.name.to_string(),
+                    args,
+                })
+            }
+            // Casts of literals are folded, so that `CAST('2024-01-01' AS
+            // DATE)` can be an index key
+            Expr::Cast { expr, ty } => match Self::bind_expr_with_schema(schema, *expr)? {
+                ResolvedExpr::Literal(value) => value
+                    .cast(&ty)
+                    .map(ResolvedExpr::Literal)
+                    .map_err(DbError::Planner),
+                expr => Ok(ResolvedExpr::Cast {
+                    expr: Box::new(expr),
+                    ty,
+                }),
+            },
+            Expr::IsNull { expr, negated } => Ok(ResolvedExpr::IsNull {
+                expr: Box::new(Self::bind_expr_with_schema(schema, *expr)?),
+                negated,
+            }),
+            Expr::Case {
+                operand,
+                branches,
+                else_result,
+            } => {
+                let bind = |e: Expr| Self::bind_expr_with_schema(schema, e);
+                Ok(ResolvedExpr::Case {
+                    operand: operand.map(|o| bind(*o).map(Box::new)).transpose()?,
+                    branches: branches
+                        .into_iter()
+                        .map(|(when, then)| Ok((bind(when)?, bind(then)?)))
+                        .collect::<DbResult<Vec<_>>>()?,
+                    else_result: else_result.map(|e| bind(*e).map(Box::new)).transpose()?,
+                })
+            }
+            // The aggregation replaces the calls it computes with its columns
+            Expr::Aggregate { .. } => Err(DbError::Planner(format!(
+                "aggregate function {e} not allowed here"
+            ))),
         }
     }
 
@@ -778,9 +1880,7 @@ impl Planner {
             schema
                 .iter()
                 .position(|c| c.eq_ignore_ascii_case(&full_name))
-                .ok_or_else(|| {
-                    DbError::Planner(format!("unknown column '{}.{}'", qualifier, name))
-                })
+                .ok_or_else(|| DbError::Planner(format!("unknown column '{}.{}'", qualifier, name)))
         } else {
             // Unqualified: search for simple match or suffix match
             // First try exact match
@@ -897,40 +1997,280 @@ impl Planner {
         }
     }
 
+    /// Positions of the partitions of a partitioned table that may hold rows
+    /// matching `pred`, or `None` if the table is not partitioned or every
+    /// partition may.
+    ///
+    /// Comparisons of the partition key with a literal narrow the partitions
+    /// down; AND intersects the partitions of its sides and OR unites them.
+    fn prune_partitions(
+        ctx: &PlanningContext,
+        table_id: &TableId,
+        pred: &ResolvedExpr,
+    ) -> Option<Vec<usize>> {
+        let table_meta = ctx.catalog.table_by_id(*table_id).ok()?;
+        let partitioning = table_meta.partitioning.as_ref()?;
+        let key_type = table_meta.schema.column_type(partitioning.column)?;
+        let matching = Self::matching_partitions(partitioning, key_type, pred)?;
+        (matching.len() < partitioning.partitions.len()).then(|| matching.into_iter().collect())
+    }
+
+    fn matching_partitions(
+        partitioning: &Partitioning,
+        key_type: &SqlType,
+        pred: &ResolvedExpr,
+    ) -> Option<BTreeSet<usize>> {
+        let ResolvedExpr::Binary { left, op, right } = pred else {
+            return None;
+        };
+        match op {
+            BinaryOp::And => {
+                let left = Self::matching_partitions(partitioning, key_type, left);
+                let right = Self::matching_partitions(partitioning, key_type, right);
+                match (left, right) {
+                    (Some(left), Some(right)) => Some(&left & &right),
+                    (side, None) | (None, side) => side,
+                }
+            }
+            BinaryOp::Or => {
+                let left = Self::matching_partitions(partitioning, key_type, left)?;
+                let right = Self::matching_partitions(partitioning, key_type, right)?;
+                Some(&left | &right)
+            }
+            _ => {
+                // Normalize to `key <op> value`
+                let (op, value) = match (left.as_ref(), right.as_ref()) {
+                    (ResolvedExpr::Column(col), ResolvedExpr::Literal(value))
+                        if *col == partitioning.column =>
+                    {
+                        (*op, value)
+                    }
+                    (ResolvedExpr::Literal(value), ResolvedExpr::Column(col))
+                        if *col == partitioning.column =>
+                    {
+                        (Self::flip_comparison(*op)?, value)
+                    }
+                    _ => return None,
+                };
+                let value = &value.clone().coerce_to(key_type);
+                let fits_key = matches!(
+                    (key_type, value),
+                    (SqlType::Int, Value::Int(_))
+                        | (SqlType::Text, Value::Text(_))
+                        | (SqlType::Bool, Value::Bool(_))
+                        | (SqlType::Float, Value::Float(_))
+                        | (SqlType::Date, Value::Date(_))
+                        | (SqlType::Timestamp, Value::Timestamp(_))
+                ) || matches!(
+                    (key_type, value),
+                    (SqlType::Decimal { scale, .. }, Value::Decimal(d)) if d.scale() == *scale
+                );
+                if !fits_key {
+                    return None;
+                }
+                let (low, high) = match op {
+                    BinaryOp::Eq => (Bound::Included(value), Bound::Included(value)),
+                    BinaryOp::Lt => (Bound::Unbounded, Bound::Excluded(value)),
+                    BinaryOp::Le => (Bound::Unbounded, Bound::Included(value)),
+                    BinaryOp::Gt => (Bound::Excluded(value), Bound::Unbounded),
+                    BinaryOp::Ge => (Bound::Included(value), Bound::Unbounded),
+                    _ => return None,
+                };
+                Some(partitioning.overlapping(low, high).into_iter().collect())
+            }
+        }
+    }
+
+    /// The comparison `b <op'> a` equivalent to `a <op> b`.
+    fn flip_comparison(op: BinaryOp) -> Option<BinaryOp> {
+        match op {
+            BinaryOp::Eq => Some(BinaryOp::Eq),
+            BinaryOp::Lt => Some(BinaryOp::Gt),
+            BinaryOp::Le => Some(BinaryOp::Ge),
+            BinaryOp::Gt => Some(BinaryOp::Lt),
+            BinaryOp::Ge => Some(BinaryOp::Le),
+            _ => None,
+        }
+    }
+
     /// Find the best index for a predicate, supporting composite keys.
     ///
     /// Ranking:
     /// 1. Full composite match > prefix match > single column
     /// 2. For equality: prefer Hash > BTree
     /// 3. For range: require BTree
+    /// 4. The primary key index, for equality on every key column or a
+    ///    range over a single key column, after any catalog index
+    ///
+    /// Once the table has been analyzed, the usable index with the lowest
+    /// estimated cost is chosen instead, or none if reading the whole table
+    /// costs less (see [`cost`]).
     fn find_best_index(
         ctx: &PlanningContext,
         table_id: &TableId,
         pred: &ResolvedExpr,
     ) -> Option<(String, IndexPredicate)> {
         let table_meta = ctx.catalog.table_by_id(*table_id).ok()?;
-        let indexes: Vec<_> = table_meta.indexes().to_vec();
+        let candidates = Self::index_candidates(table_meta, pred);
+        let Some(stats) = &table_meta.statistics else {
+            return candidates.into_iter().next();
+        };
 
-        if indexes.is_empty() {
+        let seq_cost = cost::seq_scan_cost(stats);
+        candidates
+            .into_iter()
+            .map(|(name, predicate)| {
+                let rows = cost::index_rows(stats, &predicate);
+                (cost::index_scan_cost(stats, rows), name, predicate)
+            })
+            .filter(|(cost, ..)| *cost < seq_cost)
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, name, predicate)| (name, predicate))
+    }
+
+    /// Choose index probes for a predicate that is, or is ANDed with, a
+    /// disjunction whose every branch some index can serve, such as
+    /// `id = 1 OR id = 7`. Each branch is probed with its best index by the
+    /// ranking of [`Planner::find_best_index`].
+    ///
+    /// Once the table has been analyzed, none are chosen if the probes
+    /// together cost more than reading the whole table.
+    fn find_index_union(
+        ctx: &PlanningContext,
+        table_id: &TableId,
+        pred: &ResolvedExpr,
+    ) -> Option<Vec<(String, IndexPredicate)>> {
+        let table_meta = ctx.catalog.table_by_id(*table_id).ok()?;
+        let probe_cost = |stats, predicate: &IndexPredicate| {
+            cost::index_scan_cost(stats, cost::index_rows(stats, predicate))
+        };
+        let best_probe = |branch: &ResolvedExpr| {
+            let mut candidates = Self::index_candidates(table_meta, branch).into_iter();
+            match &table_meta.statistics {
+                Some(stats) => candidates
+                    .min_by(|a, b| probe_cost(stats, &a.1).total_cmp(&probe_cost(stats, &b.1))),
+                None => candidates.next(),
+            }
+        };
+
+        cost::conjuncts(pred).into_iter().find_map(|conjunct| {
+            let branches = cost::disjuncts(conjunct);
+            if branches.len() < 2 {
+                return None;
+            }
+            let probes: Vec<_> = branches
+                .into_iter()
+                .map(best_probe)
+                .collect::<Option<_>>()?;
+            if let Some(stats) = &table_meta.statistics {
+                let union_cost: f64 = probes.iter().map(|(_, p)| probe_cost(stats, p)).sum();
+                if union_cost >= cost::seq_scan_cost(stats) {
+                    return None;
+                }
+            }
+            Some(probes)
+        })
+    }
+
+    /// The indexes that can serve a predicate, each with the predicate it
+    /// would scan for, best first by the ranking of
+    /// [`Planner::find_best_index`].
+    fn index_candidates(
+        table_meta: &TableMeta,
+        pred: &ResolvedExpr,
+    ) -> Vec<(String, IndexPredicate)> {
+        let mut candidates = Self::catalog_index_candidates(table_meta, pred);
+        candidates.extend(Self::primary_key_candidate(table_meta, pred));
+        candidates
+    }
+
+    /// The primary key index as a candidate for a predicate with equality
+    /// on every key column, or a range over a single numeric or temporal
+    /// key column.
+    ///
+    /// Only heap tables keep the index in a B+Tree file; other engines
+    /// rebuild it from the whole table.
+    fn primary_key_candidate(
+        table_meta: &TableMeta,
+        pred: &ResolvedExpr,
+    ) -> Option<(String, IndexPredicate)> {
+        let pk_columns = table_meta.primary_key.as_ref()?;
+        if table_meta.engine != EngineKind::Heap {
             return None;
         }
 
+        let eq_preds: std::collections::HashMap<ColumnId, ResolvedExpr> =
+            Self::extract_equality_predicates(pred)
+                .into_iter()
+                .collect();
+        let predicate = if let [col] = pk_columns[..]
+            && !eq_preds.contains_key(&col)
+        {
+            let (range_col, range_pred) = Self::try_extract_index_predicate(&[], pred)?;
+            let ranged = matches!(
+                table_meta.schema.column_type(col),
+                Some(
+                    SqlType::Int
+                        | SqlType::Float
+                        | SqlType::Decimal { .. }
+                        | SqlType::Date
+                        | SqlType::Timestamp
+                )
+            );
+            if range_col != col || !ranged {
+                return None;
+            }
+            range_pred
+        } else {
+            let values = pk_columns
+                .iter()
+                .map(|col| eq_preds.get(col).cloned())
+                .collect::<Option<Vec<_>>>()?;
+            match pk_columns[..] {
+                [col] => IndexPredicate::Eq {
+                    col,
+                    value: values.into_iter().next()?,
+                },
+                _ => IndexPredicate::CompositeEq {
+                    columns: pk_columns.clone(),
+                    values,
+                },
+            }
+        };
+        Some((
+            PRIMARY_KEY_INDEX.to_string(),
+            Self::coerce_index_predicate(predicate, table_meta),
+        ))
+    }
+
+    /// The catalog indexes that can serve a predicate, best first.
+    fn catalog_index_candidates(
+        table_meta: &TableMeta,
+        pred: &ResolvedExpr,
+    ) -> Vec<(String, IndexPredicate)> {
+        let indexes = table_meta.indexes();
+        if indexes.is_empty() {
+            return Vec::new();
+        }
+
         let is_equality_only = Self::is_pure_equality_predicate(pred);
         let eq_preds = Self::extract_equality_predicates(pred);
 
         if eq_preds.is_empty() {
             // No equality predicates - try range predicates with single-column extraction
-            if let Some((col, range_pred)) = Self::try_extract_index_predicate(&[], pred) {
-                for idx in &indexes {
-                    if idx.columns.len() == 1
+            let Some((col, range_pred)) = Self::try_extract_index_predicate(&[], pred) else {
+                return Vec::new();
+            };
+            let range_pred = Self::coerce_index_predicate(range_pred, table_meta);
+            return indexes
+                .iter()
+                .filter(|idx| {
+                    idx.columns.len() == 1
                         && idx.columns[0] == col
                         && matches!(idx.kind, IndexKind::BTree)
-                    {
-                        return Some((idx.name.clone(), range_pred));
-                    }
-                }
-            }
-            return None;
+                })
+                .map(|idx| (idx.name.clone(), range_pred.clone()))
+                .collect();
         }
 
         // Build map of column -> value for quick lookup
@@ -938,9 +2278,9 @@ impl Planner {
             eq_preds.into_iter().collect();
 
         // Score each index by prefix column coverage
-        let mut best_match: Option<(&catalog::IndexMeta, usize)> = None;
+        let mut matches: Vec<(&catalog::IndexMeta, usize)> = Vec::new();
 
-        for idx in &indexes {
+        for idx in indexes {
             // Filter by index kind based on predicate type
             if !is_equality_only && !matches!(idx.kind, IndexKind::BTree) {
                 continue; // Range requires BTree
@@ -950,55 +2290,120 @@ impl Planner {
             }
 
             // Check prefix match: index columns must match predicate columns in order
-            let mut matched_count = 0;
-            for &col in &idx.columns {
-                if pred_map.contains_key(&col) {
-                    matched_count += 1;
-                } else {
-                    break; // Prefix match broken
-                }
-            }
-
+            let matched_count = idx
+                .columns
+                .iter()
+                .take_while(|col| pred_map.contains_key(col))
+                .count();
             if matched_count > 0 {
-                let is_better = match &best_match {
-                    None => true,
-                    Some((_, best_count)) => {
-                        // Prefer more columns matched
-                        matched_count > *best_count
-                    }
-                };
-                if is_better {
-                    best_match = Some((idx, matched_count));
-                }
+                matches.push((idx, matched_count));
             }
         }
 
-        let (best_idx, matched_count) = best_match?;
+        // Prefer more columns matched; the sort is stable, so ties keep
+        // catalog order
+        matches.sort_by_key(|(_, matched_count)| std::cmp::Reverse(*matched_count));
+        matches
+            .into_iter()
+            .map(|(idx, matched_count)| {
+                // Build the predicate
+                let columns: Vec<ColumnId> = idx.columns[..matched_count].to_vec();
+                let values: Vec<ResolvedExpr> = columns
+                    .iter()
+                    .map(|col| pred_map.get(col).cloned().unwrap())
+                    .collect();
 
-        // Build the predicate
-        let columns: Vec<ColumnId> = best_idx.columns[..matched_count].to_vec();
-        let values: Vec<ResolvedExpr> = columns
-            .iter()
-            .map(|col| pred_map.get(col).cloned().unwrap())
-            .collect();
+                let predicate = if matched_count == 1 {
+                    IndexPredicate::Eq {
+                        col: columns[0],
+                        value: values.into_iter().next().unwrap(),
+                    }
+                } else {
+                    IndexPredicate::CompositeEq { columns, values }
+                };
+                (
+                    idx.name.clone(),
+                    Self::coerce_index_predicate(predicate, table_meta),
+                )
+            })
+            .collect()
+    }
 
-        let predicate = if matched_count == 1 {
-            IndexPredicate::Eq {
-                col: columns[0],
-                value: values.into_iter().next().unwrap(),
-            }
-        } else {
-            IndexPredicate::CompositeEq { columns, values }
+    /// Bring literal keys to the indexed columns' types, so that `price = 3`
+    /// finds the key `3.0` of a FLOAT column and `id = 3.0` finds the key `3`
+    /// of an INT column.
+    ///
+    /// Open range bounds become -infinity and NaN (the largest float) on
+    /// FLOAT columns, and the smallest and largest values on DECIMAL, DATE
+    /// and TIMESTAMP columns. The filter above the index scan still checks
+    /// every row.
+    fn coerce_index_predicate(predicate: IndexPredicate, table: &TableMeta) -> IndexPredicate {
+        let coerce = |col: ColumnId, key: ResolvedExpr| {
+            let (ResolvedExpr::Literal(value), Some(ty)) = (&key, table.schema.column_type(col))
+            else {
+                return key;
+            };
+            let value = match (value, ty) {
+                (Value::Int(i64::MIN), SqlType::Float) => Value::Float(f64::NEG_INFINITY),
+                (Value::Int(i64::MAX), SqlType::Float) => Value::Float(f64::NAN),
+                (
+                    Value::Int(bound @ (i64::MIN | i64::MAX)),
+                    SqlType::Decimal { precision, scale },
+                ) => match Decimal::largest(*precision, *scale) {
+                    Some(largest) if *bound == i64::MAX => Value::Decimal(largest),
+                    Some(largest) => Value::Decimal(Decimal::new(-largest.units(), *scale)),
+                    None => value.clone(),
+                },
+                (Value::Int(i64::MIN), SqlType::Date) => Value::Date(i32::MIN),
+                (Value::Int(i64::MAX), SqlType::Date) => Value::Date(i32::MAX),
+                (Value::Int(i64::MIN), SqlType::Timestamp) => Value::Timestamp(i64::MIN),
+                (Value::Int(i64::MAX), SqlType::Timestamp) => Value::Timestamp(i64::MAX),
+                (Value::Float(f), SqlType::Int)
+                    if f.fract() == 0.0 && (i64::MIN as f64..i64::MAX as f64).contains(f) =>
+                {
+                    Value::Int(*f as i64)
+                }
+                (Value::Decimal(d), SqlType::Int) => match d.to_i64() {
+                    Some(i) if Decimal::from_int(i) == *d => Value::Int(i),
+                    _ => value.clone(),
+                },
+                (value, ty) => value.clone().coerce_to(ty),
+            };
+            ResolvedExpr::Literal(value)
         };
-
-        Some((best_idx.name.clone(), predicate))
+        match predicate {
+            IndexPredicate::Eq { col, value } => IndexPredicate::Eq {
+                col,
+                value: coerce(col, value),
+            },
+            IndexPredicate::CompositeEq { columns, values } => IndexPredicate::CompositeEq {
+                values: columns
+                    .iter()
+                    .zip(values)
+                    .map(|(&col, value)| coerce(col, value))
+                    .collect(),
+                columns,
+            },
+            IndexPredicate::Range { col, low, high } => IndexPredicate::Range {
+                col,
+                low: coerce(col, low),
+                high: coerce(col, high),
+            },
+        }
     }
 }
 
 /// Pretty-print a logical plan for debugging.
 pub fn explain_logical(p: &LogicalPlan) -> String {
     match p {
-        LogicalPlan::TableScan { table } => format!("TableScan table={}", table),
+        LogicalPlan::TableScan { table } => format!("TableScan table={}", quote_ident(table)),
+        LogicalPlan::TableFunction { name, args } => {
+            format!("TableFunction {}({:?})", name, args)
+        }
+        LogicalPlan::Values { rows } => format!("Values rows={rows:?}"),
+        LogicalPlan::Sample { input, sample } => {
+            format!("Sample {:?}\n  {}", sample, indent(&explain_logical(input)))
+        }
         LogicalPlan::Filter { input, predicate } => format!(
             "Filter [{predicate:?}]\n  {}",
             indent(&explain_logical(input))
@@ -1008,20 +2413,50 @@ pub fn explain_logical(p: &LogicalPlan) -> String {
             columns,
             indent(&explain_logical(input))
         ),
-        LogicalPlan::Insert { table, values } => {
-            format!("Insert table={} values={:?}", table, values)
+        LogicalPlan::Insert {
+            table,
+            columns,
+            rows,
+        } => {
+            format!(
+                "Insert table={} columns={:?} rows={:?}",
+                quote_ident(table),
+                columns,
+                rows
+            )
         }
         LogicalPlan::Update {
             table,
             assignments,
             predicate,
+            source,
         } => format!(
-            "Update table={} assigns={:?} pred={:?}",
-            table, assignments, predicate
+            "Update table={} assigns={:?} pred={:?}{}",
+            quote_ident(table),
+            assignments,
+            predicate,
+            explain_source(source.as_deref().map(explain_logical))
+        ),
+        LogicalPlan::Delete {
+            table,
+            predicate,
+            source,
+        } => format!(
+            "Delete table={} pred={:?}{}",
+            quote_ident(table),
+            predicate,
+            explain_source(source.as_deref().map(explain_logical))
+        ),
+        LogicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+        } => format!(
+            "Aggregate keys={:?} aggregates={:?}\n  {}",
+            group_by,
+            aggregates,
+            indent(&explain_logical(input))
         ),
-        LogicalPlan::Delete { table, predicate } => {
-            format!("Delete table={} pred={:?}", table, predicate)
-        }
         LogicalPlan::Sort { input, order_by } => {
             format!("Sort {:?}\n  {}", order_by, indent(&explain_logical(input)))
         }
@@ -1040,12 +2475,16 @@ pub fn explain_logical(p: &LogicalPlan) -> String {
             right,
             join_type,
             condition,
+            key,
             left_name,
             right_name,
         } => format!(
-            "Join type={:?} on={:?} ({} x {})\n  left: {}\n  right: {}",
+            "Join type={:?} on={:?}{} ({} x {})\n  left: {}\n  right: {}",
             join_type,
             condition,
+            key.as_ref()
+                .map(|(left, right)| format!(" key={left:?}={right:?}"))
+                .unwrap_or_default(),
             left_name,
             right_name,
             indent(&explain_logical(left)),
@@ -1054,48 +2493,171 @@ pub fn explain_logical(p: &LogicalPlan) -> String {
     }
 }
 
+/// Rows `plan` is estimated to produce, from the statistics of the tables it
+/// reads. `None` unless every one of them has been analyzed.
+pub fn estimated_rows(plan: &PhysicalPlan, catalog: &Catalog) -> Option<u64> {
+    cost::estimate(plan, catalog).map(|estimate| estimate.rows.round() as u64)
+}
+
 /// Pretty-print a physical plan for debugging.
+///
+/// Expressions are written as SQL-like text, naming the columns they read
+/// from their operator's input, and every name is quoted where SQL needs it
+/// (see [`quote_ident`]).
 pub fn explain_physical(p: &PhysicalPlan) -> String {
     match p {
-        PhysicalPlan::SeqScan { table_id, .. } => format!("SeqScan table_id={}", table_id.0),
+        PhysicalPlan::SeqScan {
+            table_id,
+            schema,
+            projection,
+        } => format!(
+            "SeqScan table_id={}{}",
+            table_id.0,
+            explain_projection(schema, projection)
+        ),
+        PhysicalPlan::SampleScan {
+            table_id,
+            sample,
+            schema,
+            projection,
+        } => format!(
+            "SampleScan table_id={} sample={sample:?}{}",
+            table_id.0,
+            explain_projection(schema, projection)
+        ),
+        PhysicalPlan::PartitionScan {
+            table_id,
+            partitions,
+            schema,
+            projection,
+        } => format!(
+            "PartitionScan table_id={} partitions={partitions:?}{}",
+            table_id.0,
+            explain_projection(schema, projection)
+        ),
         PhysicalPlan::IndexScan {
             table_id,
             index_name,
             predicate,
-            ..
+            schema,
+            projection,
         } => format!(
-            "IndexScan table_id={} index={} pred={predicate:?}",
-            table_id.0, index_name
+            "IndexScan table_id={} index={} pred={}{}",
+            table_id.0,
+            explain_index(index_name),
+            explain_index_predicate(predicate, schema),
+            explain_projection(schema, projection)
         ),
-        PhysicalPlan::Filter { input, predicate } => format!(
-            "Filter [{predicate:?}]\n  {}",
-            indent(&explain_physical(input))
+        PhysicalPlan::IndexUnion {
+            table_id,
+            probes,
+            schema,
+            projection,
+        } => format!(
+            "IndexUnion table_id={} probes={}{}",
+            table_id.0,
+            explain_list(probes.iter().map(|(index, predicate)| {
+                format!(
+                    "{}: {}",
+                    explain_index(index),
+                    explain_index_predicate(predicate, schema)
+                )
+            })),
+            explain_projection(schema, projection)
         ),
-        PhysicalPlan::Project { input, columns } => format!(
-            "Project {:?}\n  {}",
-            columns,
+        PhysicalPlan::SystemScan { view, .. } => format!("SystemScan view={}", view.name()),
+        PhysicalPlan::SeriesScan {
+            start, stop, step, ..
+        } => format!("SeriesScan start={start:?} stop={stop:?} step={step:?}"),
+        PhysicalPlan::ValuesScan { rows, schema } => {
+            format!("ValuesScan rows={} columns={}", rows.len(), schema.len())
+        }
+        PhysicalPlan::Filter { input, predicate } => format!(
+            "Filter [{}]\n  {}",
+            explain_expr(predicate, &Planner::output_schema(input)),
             indent(&explain_physical(input))
         ),
-        PhysicalPlan::Insert { table_id, values } => {
-            format!("Insert table_id={} values={:?}", table_id.0, values)
+        PhysicalPlan::Project { input, columns } => {
+            let schema = Planner::output_schema(input);
+            let columns = columns.iter().map(|(name, expr)| {
+                let expr = explain_expr(expr, &schema);
+                let name = quote_ident(name);
+                // A column passed through under its own name is named once
+                match expr.rsplit('.').next() == Some(&*name) {
+                    true => expr,
+                    false => format!("{expr} AS {name}"),
+                }
+            });
+            format!(
+                "Project {}\n  {}",
+                explain_list(columns),
+                indent(&explain_physical(input))
+            )
+        }
+        PhysicalPlan::Insert { table_id, rows } => {
+            format!("Insert table_id={} rows={:?}", table_id.0, rows)
         }
         PhysicalPlan::Update {
             table_id,
             assignments,
             predicate,
+            source,
         } => format!(
-            "Update table_id={} assigns={:?} pred={:?}",
-            table_id.0, assignments, predicate
+            "Update table_id={} assigns={:?} pred={:?}{}",
+            table_id.0,
+            assignments,
+            predicate,
+            explain_source(source.as_deref().map(explain_physical))
         ),
         PhysicalPlan::Delete {
             table_id,
             predicate,
-        } => format!("Delete table_id={} pred={:?}", table_id.0, predicate),
-        PhysicalPlan::Sort { input, order_by } => format!(
-            "Sort {:?}\n  {}",
-            order_by,
-            indent(&explain_physical(input))
+            source,
+        } => format!(
+            "Delete table_id={} pred={:?}{}",
+            table_id.0,
+            predicate,
+            explain_source(source.as_deref().map(explain_physical))
         ),
+        PhysicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+            ..
+        } => {
+            let schema = Planner::output_schema(input);
+            let aggregates = aggregates.iter().map(|aggregate| match &aggregate.arg {
+                Some(arg) => format!("{}({})", aggregate.func, explain_expr(arg, &schema)),
+                None => format!("{}(*)", aggregate.func),
+            });
+            format!(
+                "Aggregate keys={} aggregates={}\n  {}",
+                explain_list(group_by.iter().map(|key| explain_expr(key, &schema))),
+                explain_list(aggregates),
+                indent(&explain_physical(input))
+            )
+        }
+        PhysicalPlan::Sort { input, order_by } => {
+            let schema = Planner::output_schema(input);
+            let keys = order_by.iter().map(|key| {
+                let mut text = explain_column(&schema, key.column_id);
+                if key.direction == SortDirection::Desc {
+                    text.push_str(" DESC");
+                }
+                if key.nulls != NullsOrder::default_for(&key.direction) {
+                    text.push_str(match key.nulls {
+                        NullsOrder::First => " NULLS FIRST",
+                        NullsOrder::Last => " NULLS LAST",
+                    });
+                }
+                text
+            });
+            format!(
+                "Sort {}\n  {}",
+                explain_list(keys),
+                indent(&explain_physical(input))
+            )
+        }
         PhysicalPlan::Limit {
             input,
             limit,
@@ -1112,15 +2674,620 @@ pub fn explain_physical(p: &PhysicalPlan) -> String {
             condition,
             schema,
         } => format!(
-            "NestedLoopJoin on={:?} schema={:?}\n  left: {}\n  right: {}",
-            condition,
+            "NestedLoopJoin on={} schema={}\n  left: {}\n  right: {}",
+            explain_expr(condition, schema),
+            explain_list(schema.iter().map(|name| explain_name(name))),
+            indent(&explain_physical(left)),
+            indent(&explain_physical(right))
+        ),
+        PhysicalPlan::HashJoin {
+            left,
+            right,
+            keys,
             schema,
+        } => {
+            let (left_schema, right_schema) =
+                (Planner::output_schema(left), Planner::output_schema(right));
+            let keys = keys.iter().map(|(left_key, right_key)| {
+                format!(
+                    "{} = {}",
+                    explain_expr(left_key, &left_schema),
+                    explain_expr(right_key, &right_schema)
+                )
+            });
+            format!(
+                "HashJoin keys={} schema={}\n  left: {}\n  right: {}",
+                explain_list(keys),
+                explain_list(schema.iter().map(|name| explain_name(name))),
+                indent(&explain_physical(left)),
+                indent(&explain_physical(right))
+            )
+        }
+        PhysicalPlan::SemiJoin {
+            left,
+            right,
+            condition,
+            ..
+        } => format!(
+            "SemiJoin on={}\n  left: {}\n  right: {}",
+            explain_expr(condition, &join_schema(left, right)),
             indent(&explain_physical(left)),
             indent(&explain_physical(right))
         ),
+        PhysicalPlan::AntiJoin {
+            left,
+            right,
+            condition,
+            ..
+        } => format!(
+            "AntiJoin on={}\n  left: {}\n  right: {}",
+            explain_expr(condition, &join_schema(left, right)),
+            indent(&explain_physical(left)),
+            indent(&explain_physical(right))
+        ),
+    }
+}
+
+/// The columns a semi or anti join condition reads: a left row followed by
+/// a right row.
+fn join_schema(left: &PhysicalPlan, right: &PhysicalPlan) -> Vec<String> {
+    let mut schema = Planner::output_schema(left);
+    schema.extend(Planner::output_schema(right));
+    schema
+}
+
+/// `expr` as SQL-like text, naming the columns it reads from `schema`, the
+/// columns of its input. Operations nested in others are parenthesized.
+fn explain_expr(expr: &ResolvedExpr, schema: &[String]) -> String {
+    let operand = |expr: &ResolvedExpr| match expr {
+        ResolvedExpr::Binary { .. } => format!("({})", explain_expr(expr, schema)),
+        _ => explain_expr(expr, schema),
+    };
+    match expr {
+        ResolvedExpr::Literal(value) => Expr::Literal(value.clone()).to_string(),
+        ResolvedExpr::Column(id) => explain_column(schema, *id),
+        ResolvedExpr::Unary {
+            op: UnaryOp::Not,
+            expr,
+        } => format!("NOT {}", operand(expr)),
+        ResolvedExpr::Binary { left, op, right } => {
+            format!("{} {op} {}", operand(left), operand(right))
+        }
+        ResolvedExpr::Function { name, args } => {
+            let args: Vec<_> = args.iter().map(|arg| explain_expr(arg, schema)).collect();
+            format!("{name}({})", args.join(", "))
+        }
+        ResolvedExpr::Cast { expr, ty } => format!("CAST({} AS {ty})", explain_expr(expr, schema)),
+        ResolvedExpr::IsNull { expr, negated } => {
+            let not = if *negated { " NOT" } else { "" };
+            format!("{} IS{not} NULL", operand(expr))
+        }
+        ResolvedExpr::Case {
+            operand: case_operand,
+            branches,
+            else_result,
+        } => {
+            let mut text = "CASE".to_string();
+            if let Some(case_operand) = case_operand {
+                text.push_str(&format!(" {}", operand(case_operand)));
+            }
+            for (when, then) in branches {
+                text.push_str(&format!(" WHEN {} THEN {}", operand(when), operand(then)));
+            }
+            if let Some(else_result) = else_result {
+                text.push_str(&format!(" ELSE {}", operand(else_result)));
+            }
+            text + " END"
+        }
+    }
+}
+
+/// An index probe's predicate, naming its columns from the scanned table's
+/// `schema`.
+fn explain_index_predicate(predicate: &IndexPredicate, schema: &[String]) -> String {
+    match predicate {
+        IndexPredicate::Eq { col, value } => format!(
+            "{} = {}",
+            explain_column(schema, *col),
+            explain_expr(value, schema)
+        ),
+        IndexPredicate::CompositeEq { columns, values } => {
+            let columns: Vec<_> = columns
+                .iter()
+                .map(|&col| explain_column(schema, col))
+                .collect();
+            let values: Vec<_> = values
+                .iter()
+                .map(|value| explain_expr(value, schema))
+                .collect();
+            format!("({}) = ({})", columns.join(", "), values.join(", "))
+        }
+        IndexPredicate::Range { col, low, high } => format!(
+            "{} BETWEEN {} AND {}",
+            explain_column(schema, *col),
+            explain_expr(low, schema),
+            explain_expr(high, schema)
+        ),
     }
 }
 
+/// Column `id` of `schema` as EXPLAIN shows it, or `#id` if there is none.
+fn explain_column(schema: &[String], id: ColumnId) -> String {
+    schema
+        .get(id as usize)
+        .map_or_else(|| format!("#{id}"), |name| explain_name(name))
+}
+
+/// A column name as EXPLAIN shows it. A name qualified by its table, such as
+/// `users.id` in a join's schema, has each part quoted on its own.
+fn explain_name(name: &str) -> String {
+    match name.split_once('.') {
+        Some((table, column))
+            if !table.is_empty() && table.chars().all(|c| c.is_alphanumeric() || c == '_') =>
+        {
+            format!("{}.{}", quote_ident(table), quote_ident(column))
+        }
+        _ => quote_ident(name).into_owned(),
+    }
+}
+
+/// `items` in brackets, separated by commas.
+fn explain_list(items: impl IntoIterator<Item = String>) -> String {
+    format!("[{}]", items.into_iter().collect::<Vec<_>>().join(", "))
+}
+
+/// An index name as EXPLAIN shows it: quoted like any identifier, except
+/// for the primary key index, which is named by a keyword.
+fn explain_index(name: &str) -> std::borrow::Cow<'_, str> {
+    if name == PRIMARY_KEY_INDEX {
+        name.into()
+    } else {
+        quote_ident(name)
+    }
+}
+
+/// The columns a scan decodes, if not all of them, named from the table's
+/// `schema`.
+fn explain_projection(schema: &[String], projection: &Option<Vec<ColumnId>>) -> String {
+    match projection {
+        Some(columns) => format!(
+            " columns={}",
+            explain_list(columns.iter().map(|&id| explain_column(schema, id)))
+        ),
+        None => String::new(),
+    }
+}
+
+/// Describe how an INSERT, UPDATE or DELETE plan will run against `table`:
+/// where its rows come from, which indexes it keeps up to date and which
+/// constraints it checks.
+///
+/// Returns `None` for any other plan.
+pub fn explain_dml(p: &PhysicalPlan, table: &TableMeta) -> Option<String> {
+    let primary_key = table
+        .primary_key
+        .as_ref()
+        .map(|pk| format!("primary key ({})", column_list(table, pk.iter().copied())));
+    let indexes = table.indexes.iter().map(|index| {
+        let kind = match index.kind {
+            IndexKind::BTree => "btree",
+            IndexKind::Hash => "hash",
+            IndexKind::Bitmap => "bitmap",
+            IndexKind::Trie => "trie",
+        };
+        let unique = if index.unique { "unique " } else { "" };
+        format!(
+            "{unique}{kind} {} ({})",
+            quote_ident(&index.name),
+            column_list(table, index.columns.iter().copied())
+        )
+    });
+
+    let (verb, lines) = match p {
+        PhysicalPlan::Insert { rows, .. } => {
+            let maintained: Vec<String> = primary_key.iter().cloned().chain(indexes).collect();
+            let mut checks: Vec<String> =
+                not_null_check(table, 0..table.columns().len() as ColumnId)
+                    .into_iter()
+                    .collect();
+            checks.extend(primary_key.iter().map(|pk| format!("{pk} unique")));
+            checks.extend(unique_index_checks(table, |_| true));
+            let mut lines = vec![
+                format!("Source: {} literal row(s)", rows.len()),
+                format!("Index maintenance: {}", or_none(maintained)),
+                format!("Constraint checks: {}", or_none(checks)),
+            ];
+            let generated: Vec<_> = table
+                .columns()
+                .iter()
+                .filter(|c| c.sequence.is_some())
+                .map(|c| quote_ident(&c.name))
+                .collect();
+            if !generated.is_empty() {
+                lines.push(format!("Generated values: {}", generated.join(", ")));
+            }
+            ("Insert", lines)
+        }
+        PhysicalPlan::Update {
+            assignments,
+            predicate,
+            source,
+            ..
+        } => {
+            let assigned: BTreeSet<ColumnId> = assignments.iter().map(|(id, _)| *id).collect();
+            let maintained: Vec<String> = indexes
+                .chain(primary_key.iter().map(|pk| format!("{pk} for moved rows")))
+                .collect();
+            let mut checks: Vec<String> = primary_key
+                .iter()
+                .map(|pk| format!("{pk} not assigned"))
+                .collect();
+            checks.extend(not_null_check(table, assigned.iter().copied()));
+            checks.extend(unique_index_checks(table, |index| {
+                index.columns.iter().any(|c| assigned.contains(c))
+            }));
+            let mut lines = vec![
+                format!("Set: {}", column_list(table, assigned.iter().copied())),
+                dml_scan(table, source.as_deref(), predicate),
+                format!("Index maintenance: {}", or_none(maintained)),
+                format!("Constraint checks: {}", or_none(checks)),
+            ];
+            if let Some(version) = table.row_version_column()
+                && !assigned.contains(&version)
+            {
+                lines.push(format!(
+                    "Generated values: {} incremented",
+                    column_list(table, [version])
+                ));
+            }
+            ("Update", lines)
+        }
+        PhysicalPlan::Delete {
+            predicate, source, ..
+        } => {
+            let maintained: Vec<String> = primary_key.iter().cloned().chain(indexes).collect();
+            let lines = vec![
+                dml_scan(table, source.as_deref(), predicate),
+                format!("Index maintenance: {}", or_none(maintained)),
+                "Constraint checks: none".to_string(),
+            ];
+            ("Delete", lines)
+        }
+        _ => return None,
+    };
+
+    let mut text = format!(
+        "{verb} table={} table_id={}",
+        quote_ident(&table.name),
+        table.id.0
+    );
+    for line in lines {
+        text.push('\n');
+        text.push_str(&indent(&line));
+    }
+    Some(text)
+}
+
+/// The scan an UPDATE or DELETE uses to find its rows.
+fn dml_scan(
+    table: &TableMeta,
+    source: Option<&PhysicalPlan>,
+    predicate: &Option<ResolvedExpr>,
+) -> String {
+    format!(
+        "Scan: {}",
+        explain_physical(&dml_input(table, source, predicate))
+    )
+}
+
+/// The rows an UPDATE or DELETE of `table` reads: its `source`, or else the
+/// whole table, filtered by `predicate`.
+fn dml_input(
+    table: &TableMeta,
+    source: Option<&PhysicalPlan>,
+    predicate: &Option<ResolvedExpr>,
+) -> PhysicalPlan {
+    let scan = source.cloned().unwrap_or_else(|| PhysicalPlan::SeqScan {
+        table_id: table.id,
+        schema: table.columns().iter().map(|c| c.name.clone()).collect(),
+        projection: None,
+    });
+    match predicate {
+        Some(predicate) => PhysicalPlan::Filter {
+            input: Box::new(scan),
+            predicate: predicate.clone(),
+        },
+        None => scan,
+    }
+}
+
+/// Comma-separated names of the given columns, quoted where SQL needs it.
+fn column_list(table: &TableMeta, ids: impl IntoIterator<Item = ColumnId>) -> String {
+    ids.into_iter()
+        .map(|id| {
+            table
+                .columns()
+                .get(id as usize)
+                .map_or("?".into(), |c| quote_ident(&c.name))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The NOT NULL check covering whichever of `ids` reject NULL, if any do.
+fn not_null_check(table: &TableMeta, ids: impl IntoIterator<Item = ColumnId>) -> Option<String> {
+    let ids: Vec<ColumnId> = ids
+        .into_iter()
+        .filter(|&id| table.columns().get(id as usize).is_some_and(|c| c.not_null))
+        .collect();
+    (!ids.is_empty()).then(|| format!("NOT NULL ({})", column_list(table, ids)))
+}
+
+fn unique_index_checks<'a>(
+    table: &'a TableMeta,
+    touched: impl Fn(&catalog::IndexMeta) -> bool + 'a,
+) -> impl Iterator<Item = String> + 'a {
+    table
+        .indexes
+        .iter()
+        .filter(move |index| index.unique && touched(index))
+        .map(|index| format!("unique index {}", quote_ident(&index.name)))
+}
+
+/// The source of an UPDATE or DELETE, on the lines after the statement.
+fn explain_source(source: Option<String>) -> String {
+    source
+        .map(|source| format!("\n  source: {}", indent(&source)))
+        .unwrap_or_default()
+}
+
+fn or_none(items: Vec<String>) -> String {
+    if items.is_empty() {
+        "none".to_string()
+    } else {
+        items.join(", ")
+    }
+}
+
+/// Scan of the rows `table` names: those of a table or view, those a table
+/// function returns, or those of a `VALUES` list.
+fn scan(table: &TableRef) -> LogicalPlan {
+    let plan = match (&table.values, &table.args) {
+        (Some(rows), _) => LogicalPlan::Values { rows: rows.clone() },
+        (None, Some(args)) => LogicalPlan::TableFunction {
+            name: table.name.clone(),
+            args: args.clone(),
+        },
+        (None, None) => LogicalPlan::TableScan {
+            table: table.name.clone(),
+        },
+    };
+    match table.sample {
+        Some(sample) => LogicalPlan::Sample {
+            input: Box::new(plan),
+            sample,
+        },
+        None => plan,
+    }
+}
+
+/// Columns are bound by table name or alias, so each table a statement
+/// reads needs its own.
+fn ensure_distinct_names<'a>(tables: impl IntoIterator<Item = &'a TableRef>) -> DbResult<()> {
+    let mut names: Vec<&str> = Vec::new();
+    for table in tables {
+        let name = table.effective_name();
+        if names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+            return Err(DbError::Planner(format!(
+                "table name '{name}' specified more than once; give each an alias"
+            )));
+        }
+        names.push(name);
+    }
+    Ok(())
+}
+
+/// `joins` with the table of each subquery that has the name of a table
+/// outside it renamed, and the references to it in the subquery with it:
+/// inside the subquery the name means the subquery's own table.
+fn rename_shadowing_subqueries(from: &TableRef, mut joins: Vec<JoinClause>) -> Vec<JoinClause> {
+    let outer: Vec<String> = std::iter::once(from)
+        .chain(
+            joins
+                .iter()
+                .filter(|join| join.join_type == JoinType::Inner)
+                .map(|join| &join.table),
+        )
+        .map(|table| table.effective_name().to_string())
+        .collect();
+    for (position, join) in joins.iter_mut().enumerate() {
+        let name = join.table.effective_name().to_string();
+        if join.join_type == JoinType::Inner || !outer.iter().any(|n| n.eq_ignore_ascii_case(&name))
+        {
+            continue;
+        }
+        // No unquoted name contains '#', so the alias is not taken
+        let alias = format!("{name}#{position}");
+        let rename = |e: Expr| {
+            map_columns(e, &|qualifier, column| Expr::Column {
+                table: match qualifier {
+                    Some(qualifier) if qualifier.eq_ignore_ascii_case(&name) => Some(alias.clone()),
+                    qualifier => qualifier,
+                },
+                name: column,
+            })
+        };
+        join.condition = rename(std::mem::replace(
+            &mut join.condition,
+            Expr::Literal(Value::Bool(true)),
+        ));
+        join.key = join.key.take().map(|(outer, inner)| (outer, rename(inner)));
+        join.table.alias = Some(alias);
+    }
+    joins
+}
+
+/// `item` with the qualifier `table` removed from its column references.
+fn unqualify_item(item: SelectItem, table: &str) -> SelectItem {
+    match item {
+        SelectItem::Column(name) => match name.split_once('.') {
+            Some((qualifier, column)) if qualifier.eq_ignore_ascii_case(table) => {
+                SelectItem::Column(column.to_string())
+            }
+            _ => SelectItem::Column(name),
+        },
+        SelectItem::Expr { expr, alias } => SelectItem::Expr {
+            expr: unqualify(expr, table),
+            alias,
+        },
+        SelectItem::Wildcard => SelectItem::Wildcard,
+    }
+}
+
+/// `e` with the qualifier `table` removed from its column references.
+fn unqualify(e: Expr, table: &str) -> Expr {
+    map_columns(e, &|qualifier, name| match qualifier {
+        Some(qualifier) if qualifier.eq_ignore_ascii_case(table) => {
+            Expr::Column { table: None, name }
+        }
+        qualifier => Expr::Column {
+            table: qualifier,
+            name,
+        },
+    })
+}
+
+/// `e` with each column reference replaced by `f` of its qualifier and name.
+fn map_columns(e: Expr, f: &impl Fn(Option<String>, String) -> Expr) -> Expr {
+    match e {
+        Expr::Column { table, name } => f(table, name),
+        Expr::Literal(_) => e,
+        Expr::Unary { op, expr } => Expr::Unary {
+            op,
+            expr: Box::new(map_columns(*expr, f)),
+        },
+        Expr::Binary { left, op, right } => Expr::Binary {
+            left: Box::new(map_columns(*left, f)),
+            op,
+            right: Box::new(map_columns(*right, f)),
+        },
+        Expr::Function { name, args } => Expr::Function {
+            name,
+            args: args.into_iter().map(|arg| map_columns(arg, f)).collect(),
+        },
+        Expr::Cast { expr, ty } => Expr::Cast {
+            expr: Box::new(map_columns(*expr, f)),
+            ty,
+        },
+        Expr::IsNull { expr, negated } => Expr::IsNull {
+            expr: Box::new(map_columns(*expr, f)),
+            negated,
+        },
+        Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => Expr::Case {
+            operand: operand.map(|o| Box::new(map_columns(*o, f))),
+            branches: branches
+                .into_iter()
+                .map(|(when, then)| (map_columns(when, f), map_columns(then, f)))
+                .collect(),
+            else_result: else_result.map(|e| Box::new(map_columns(*e, f))),
+        },
+        Expr::Aggregate { func, arg } => Expr::Aggregate {
+            func,
+            arg: arg.map(|arg| Box::new(map_columns(*arg, f))),
+        },
+    }
+}
+
+/// Whether `e` calls an aggregate function.
+fn has_aggregate(e: &Expr) -> bool {
+    match e {
+        Expr::Aggregate { .. } => true,
+        Expr::Literal(_) | Expr::Column { .. } => false,
+        Expr::Unary { expr, .. } | Expr::Cast { expr, .. } | Expr::IsNull { expr, .. } => {
+            has_aggregate(expr)
+        }
+        Expr::Binary { left, right, .. } => has_aggregate(left) || has_aggregate(right),
+        Expr::Function { args, .. } => args.iter().any(has_aggregate),
+        Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => {
+            operand.iter().chain(else_result).any(|e| has_aggregate(e))
+                || branches
+                    .iter()
+                    .any(|(when, then)| has_aggregate(when) || has_aggregate(then))
+        }
+    }
+}
+
+/// `e` read from the output of an aggregation by `group_by`: each aggregate
+/// call, added to `calls` unless already there, and each grouped expression
+/// other than a plain column become references to the aggregation's column
+/// of that name. Grouped columns keep binding by name.
+fn replace_aggregates(e: Expr, group_by: &[Expr], calls: &mut Vec<Expr>) -> Expr {
+    let output = |e: &Expr| Expr::Column {
+        table: None,
+        name: e.to_string(),
+    };
+    if !matches!(e, Expr::Column { .. }) && group_by.contains(&e) {
+        return output(&e);
+    }
+    let mut replace = |e: Expr| replace_aggregates(e, group_by, calls);
+    match e {
+        Expr::Aggregate { .. } => {
+            let column = output(&e);
+            if !calls.contains(&e) {
+                calls.push(e);
+            }
+            column
+        }
+        Expr::Literal(_) | Expr::Column { .. } => e,
+        Expr::Unary { op, expr } => Expr::Unary {
+            op,
+            expr: Box::new(replace(*expr)),
+        },
+        Expr::Binary { left, op, right } => Expr::Binary {
+            left: Box::new(replace(*left)),
+            op,
+            right: Box::new(replace(*right)),
+        },
+        Expr::Function { name, args } => Expr::Function {
+            name,
+            args: args.into_iter().map(replace).collect(),
+        },
+        Expr::Cast { expr, ty } => Expr::Cast {
+            expr: Box::new(replace(*expr)),
+            ty,
+        },
+        Expr::IsNull { expr, negated } => Expr::IsNull {
+            expr: Box::new(replace(*expr)),
+            negated,
+        },
+        Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => Expr::Case {
+            operand: operand.map(|o| Box::new(replace(*o))),
+            branches: branches
+                .into_iter()
+                .map(|(when, then)| (replace(when), replace(then)))
+                .collect(),
+            else_result: else_result.map(|e| Box::new(replace(*e))),
+        },
+    }
+}
+
+/// Whether a projection list is the lone `*` wildcard.
+fn is_wildcard(columns: &[SelectItem]) -> bool {
+    matches!(columns, [SelectItem::Wildcard])
+}
+
 fn indent(s: &str) -> String {
     s.lines()
         .map(|l| format!("  {l}"))
@@ -0,0 +1,259 @@
+//! Column pruning: scans decode only the columns a query reads.
+//!
+//! Rows keep their full width so that column ordinals bound elsewhere in the
+//! plan stay valid; a scan instead returns the columns nothing above it reads
+//! as NULL, without decoding them or reading their overflow pages. Which
+//! columns a node needs from its input follows from what its parent needs
+//! plus what its own expressions read, so one pass from the root down sets
+//! every scan's projection.
+//!
+//! Projections evaluate every expression, so they need every column their
+//! expressions read even if the parent uses only some of the results. The
+//! sources of UPDATE and DELETE are left alone: the rows they produce are
+//! written back.
+
+use crate::cost::for_each_column;
+use crate::hash_join::key_condition;
+use crate::{PhysicalPlan, Planner, ResolvedExpr};
+use common::ColumnId;
+use std::collections::BTreeSet;
+
+/// Set the projection of each scan in `plan` to the columns read above it.
+pub(crate) fn prune_columns(plan: PhysicalPlan) -> PhysicalPlan {
+    match plan {
+        PhysicalPlan::Insert { .. } | PhysicalPlan::Update { .. } | PhysicalPlan::Delete { .. } => {
+            plan
+        }
+        other => {
+            let all = all_columns(&other);
+            prune(other, all)
+        }
+    }
+}
+
+/// Every column `plan` outputs.
+fn all_columns(plan: &PhysicalPlan) -> BTreeSet<ColumnId> {
+    (0..Planner::output_schema(plan).len() as ColumnId).collect()
+}
+
+/// Prune `plan`, whose parent reads the columns in `needed`.
+fn prune(plan: PhysicalPlan, mut needed: BTreeSet<ColumnId>) -> PhysicalPlan {
+    match plan {
+        PhysicalPlan::SeqScan {
+            table_id, schema, ..
+        } => PhysicalPlan::SeqScan {
+            projection: projection(needed, schema.len()),
+            table_id,
+            schema,
+        },
+        PhysicalPlan::SampleScan {
+            table_id,
+            sample,
+            schema,
+            ..
+        } => PhysicalPlan::SampleScan {
+            projection: projection(needed, schema.len()),
+            table_id,
+            sample,
+            schema,
+        },
+        PhysicalPlan::PartitionScan {
+            table_id,
+            partitions,
+            schema,
+            ..
+        } => PhysicalPlan::PartitionScan {
+            projection: projection(needed, schema.len()),
+            table_id,
+            partitions,
+            schema,
+        },
+        PhysicalPlan::IndexScan {
+            table_id,
+            index_name,
+            predicate,
+            schema,
+            ..
+        } => PhysicalPlan::IndexScan {
+            projection: projection(needed, schema.len()),
+            table_id,
+            index_name,
+            predicate,
+            schema,
+        },
+        PhysicalPlan::IndexUnion {
+            table_id,
+            probes,
+            schema,
+            ..
+        } => PhysicalPlan::IndexUnion {
+            projection: projection(needed, schema.len()),
+            table_id,
+            probes,
+            schema,
+        },
+        PhysicalPlan::Filter { input, predicate } => {
+            for_each_column(&predicate, &mut |column| {
+                needed.insert(column);
+            });
+            PhysicalPlan::Filter {
+                input: Box::new(prune(*input, needed)),
+                predicate,
+            }
+        }
+        PhysicalPlan::Project { input, columns } => {
+            let mut read = BTreeSet::new();
+            for (_, expr) in &columns {
+                for_each_column(expr, &mut |column| {
+                    read.insert(column);
+                });
+            }
+            PhysicalPlan::Project {
+                input: Box::new(prune(*input, read)),
+                columns,
+            }
+        }
+        // Keys and arguments are evaluated for every row, whatever the
+        // parent reads
+        PhysicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+            schema,
+        } => {
+            let mut read = BTreeSet::new();
+            for expr in group_by.iter().chain(
+                aggregates
+                    .iter()
+                    .filter_map(|aggregate| aggregate.arg.as_ref()),
+            ) {
+                for_each_column(expr, &mut |column| {
+                    read.insert(column);
+                });
+            }
+            PhysicalPlan::Aggregate {
+                input: Box::new(prune(*input, read)),
+                group_by,
+                aggregates,
+                schema,
+            }
+        }
+        PhysicalPlan::Sort { input, order_by } => {
+            needed.extend(order_by.iter().map(|key| key.column_id));
+            PhysicalPlan::Sort {
+                input: Box::new(prune(*input, needed)),
+                order_by,
+            }
+        }
+        PhysicalPlan::Limit {
+            input,
+            limit,
+            offset,
+        } => PhysicalPlan::Limit {
+            input: Box::new(prune(*input, needed)),
+            limit,
+            offset,
+        },
+        PhysicalPlan::NestedLoopJoin {
+            left,
+            right,
+            condition,
+            schema,
+        } => {
+            let (left, right) = prune_join(left, right, &condition, needed);
+            PhysicalPlan::NestedLoopJoin {
+                left,
+                right,
+                condition,
+                schema,
+            }
+        }
+        PhysicalPlan::HashJoin {
+            left,
+            right,
+            keys,
+            schema,
+        } => {
+            let left_width = Planner::output_schema(&left).len() as ColumnId;
+            let condition = key_condition(&keys, left_width);
+            let (left, right) = prune_join(left, right, &condition, needed);
+            PhysicalPlan::HashJoin {
+                left,
+                right,
+                keys,
+                schema,
+            }
+        }
+        PhysicalPlan::SemiJoin {
+            left,
+            right,
+            condition,
+            schema,
+        } => {
+            let (left, right) = prune_join(left, right, &condition, needed);
+            PhysicalPlan::SemiJoin {
+                left,
+                right,
+                condition,
+                schema,
+            }
+        }
+        PhysicalPlan::AntiJoin {
+            left,
+            right,
+            condition,
+            schema,
+        } => {
+            let (left, right) = prune_join(left, right, &condition, needed);
+            PhysicalPlan::AntiJoin {
+                left,
+                right,
+                condition,
+                schema,
+            }
+        }
+        // Views are built whole from the catalog, a series has one column
+        // and VALUES rows are written out in full
+        system @ (PhysicalPlan::SystemScan { .. }
+        | PhysicalPlan::SeriesScan { .. }
+        | PhysicalPlan::ValuesScan { .. }) => system,
+        dml @ (PhysicalPlan::Insert { .. }
+        | PhysicalPlan::Update { .. }
+        | PhysicalPlan::Delete { .. }) => dml,
+    }
+}
+
+/// Prune both sides of a join, which read `needed` of a left row followed
+/// by a right row and the columns of `condition`.
+fn prune_join(
+    left: Box<PhysicalPlan>,
+    right: Box<PhysicalPlan>,
+    condition: &ResolvedExpr,
+    mut needed: BTreeSet<ColumnId>,
+) -> (Box<PhysicalPlan>, Box<PhysicalPlan>) {
+    for_each_column(condition, &mut |column| {
+        needed.insert(column);
+    });
+    let left_width = Planner::output_schema(&left).len() as ColumnId;
+    let right_needed = needed.split_off(&left_width);
+    (
+        Box::new(prune(*left, needed)),
+        Box::new(prune(
+            *right,
+            right_needed
+                .into_iter()
+                .map(|column| column - left_width)
+                .collect(),
+        )),
+    )
+}
+
+/// The projection of a scan of `width` columns that must read `needed`, or
+/// `None` if that is all of them.
+fn projection(needed: BTreeSet<ColumnId>, width: usize) -> Option<Vec<ColumnId>> {
+    let needed: Vec<ColumnId> = needed
+        .into_iter()
+        .filter(|&column| usize::from(column) < width)
+        .collect();
+    (needed.len() < width).then_some(needed)
+}
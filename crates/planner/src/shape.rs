@@ -0,0 +1,323 @@
+//! Stable descriptions of physical plans, for catching plan changes.
+//!
+//! [`explain_physical`](crate::explain_physical) prints every detail of a
+//! plan, including table IDs and the full text of each expression, so it
+//! changes whenever those do. A [`PlanShape`] keeps only what decides how a
+//! query runs: each operator, the tables, indexes and partitions it reads by
+//! name, and the columns it produces, sorts or writes. Two plans with the
+//! same shape run the same way, so comparing a query's shape before and
+//! after an optimizer change shows whether its access path moved.
+//!
+//! Shapes serialize with serde and print as an indented tree:
+//!
+//! ```text
+//! Limit limit=10
+//!   Sort keys=[age DESC]
+//!     Project columns=[name, age]
+//!       SeqScan decodes=[name, age] table=users
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use catalog::{Catalog, TableMeta};
+use common::{ColumnId, DbResult, TableId};
+use serde::{Deserialize, Serialize};
+
+use crate::{IndexPredicate, PhysicalPlan, Planner, ResolvedExpr, SortDirection, dml_input};
+
+/// One operator of a plan, and the operators it reads from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanShape {
+    /// Operator name, as in [`explain_physical`](crate::explain_physical)
+    pub operator: String,
+    /// What the operator reads and how, such as `table` and `index`
+    pub properties: BTreeMap<String, String>,
+    /// Inputs, left before right for joins
+    pub children: Vec<PlanShape>,
+}
+
+impl PlanShape {
+    /// Describe `plan`, naming the tables and columns it uses from `catalog`.
+    pub fn of(plan: &PhysicalPlan, catalog: &Catalog) -> DbResult<Self> {
+        let shape = Self::new(operator_name(plan));
+        Ok(match plan {
+            PhysicalPlan::SeqScan {
+                table_id,
+                projection,
+                ..
+            } => shape.scan(catalog, *table_id, projection)?,
+            PhysicalPlan::SampleScan {
+                table_id,
+                sample,
+                projection,
+                ..
+            } => shape.scan(catalog, *table_id, projection)?.with(
+                "sample",
+                format!("{:?} {}%", sample.method, sample.percent).to_lowercase(),
+            ),
+            PhysicalPlan::PartitionScan {
+                table_id,
+                partitions,
+                projection,
+                ..
+            } => {
+                let table = catalog.table_by_id(*table_id)?;
+                let names = table.partitioning.as_ref().map(|partitioning| {
+                    partitions
+                        .iter()
+                        .filter_map(|&i| partitioning.partitions.get(i))
+                        .map(|partition| partition.name.clone())
+                        .collect::<Vec<_>>()
+                });
+                shape
+                    .scan(catalog, *table_id, projection)?
+                    .with("partitions", list(names.unwrap_or_default()))
+            }
+            PhysicalPlan::IndexScan {
+                table_id,
+                index_name,
+                predicate,
+                projection,
+                ..
+            } => {
+                let table = catalog.table_by_id(*table_id)?;
+                shape
+                    .scan(catalog, *table_id, projection)?
+                    .with("index", index_name.clone())
+                    .with("predicate", describe_predicate(table, predicate))
+            }
+            PhysicalPlan::IndexUnion {
+                table_id,
+                probes,
+                projection,
+                ..
+            } => {
+                let table = catalog.table_by_id(*table_id)?;
+                let probes = probes.iter().map(|(index, predicate)| {
+                    format!("{index}: {}", describe_predicate(table, predicate))
+                });
+                shape
+                    .scan(catalog, *table_id, projection)?
+                    .with("probes", list(probes))
+            }
+            PhysicalPlan::SystemScan { view, .. } => shape.with("view", view.name().to_string()),
+            PhysicalPlan::SeriesScan { .. } => shape,
+            PhysicalPlan::ValuesScan { rows, .. } => shape.with("rows", rows.len().to_string()),
+            PhysicalPlan::Filter { input, .. } => shape.child(input, catalog)?,
+            PhysicalPlan::Project { input, columns } => shape
+                .with(
+                    "columns",
+                    list(columns.iter().map(|(name, _)| name.clone())),
+                )
+                .child(input, catalog)?,
+            PhysicalPlan::Aggregate {
+                input,
+                group_by,
+                schema,
+                ..
+            } => {
+                let (keys, aggregates) = schema.split_at(group_by.len().min(schema.len()));
+                shape
+                    .with("keys", list(keys.iter().cloned()))
+                    .with("aggregates", list(aggregates.iter().cloned()))
+                    .child(input, catalog)?
+            }
+            PhysicalPlan::Sort { input, order_by } => {
+                let schema = Planner::output_schema(input);
+                let keys = order_by.iter().map(|key| {
+                    let name = schema
+                        .get(key.column_id as usize)
+                        .cloned()
+                        .unwrap_or_else(|| format!("#{}", key.column_id));
+                    match key.direction {
+                        SortDirection::Asc => name,
+                        SortDirection::Desc => format!("{name} DESC"),
+                    }
+                });
+                shape.with("keys", list(keys)).child(input, catalog)?
+            }
+            PhysicalPlan::Limit {
+                input,
+                limit,
+                offset,
+            } => {
+                let mut shape = shape;
+                if let Some(limit) = limit {
+                    shape = shape.with("limit", limit.to_string());
+                }
+                if let Some(offset) = offset {
+                    shape = shape.with("offset", offset.to_string());
+                }
+                shape.child(input, catalog)?
+            }
+            PhysicalPlan::Insert { table_id, rows } => shape
+                .with("table", catalog.table_by_id(*table_id)?.name.clone())
+                .with("rows", rows.len().to_string()),
+            PhysicalPlan::Update {
+                table_id,
+                assignments,
+                predicate,
+                source,
+            } => {
+                let table = catalog.table_by_id(*table_id)?;
+                let assigned = assignments.iter().map(|(id, _)| column_name(table, *id));
+                shape
+                    .with("table", table.name.clone())
+                    .with("set", list(assigned))
+                    .child(&dml_input(table, source.as_deref(), predicate), catalog)?
+            }
+            PhysicalPlan::Delete {
+                table_id,
+                predicate,
+                source,
+            } => {
+                let table = catalog.table_by_id(*table_id)?;
+                shape
+                    .with("table", table.name.clone())
+                    .child(&dml_input(table, source.as_deref(), predicate), catalog)?
+            }
+            PhysicalPlan::HashJoin {
+                left, right, keys, ..
+            } => {
+                let (left_schema, right_schema) =
+                    (Planner::output_schema(left), Planner::output_schema(right));
+                let keys = keys.iter().map(|(left_key, right_key)| {
+                    format!(
+                        "{} = {}",
+                        describe_key(left_key, &left_schema),
+                        describe_key(right_key, &right_schema)
+                    )
+                });
+                shape
+                    .with("keys", list(keys))
+                    .child(left, catalog)?
+                    .child(right, catalog)?
+            }
+            PhysicalPlan::NestedLoopJoin { left, right, .. }
+            | PhysicalPlan::SemiJoin { left, right, .. }
+            | PhysicalPlan::AntiJoin { left, right, .. } => {
+                shape.child(left, catalog)?.child(right, catalog)?
+            }
+        })
+    }
+
+    fn new(operator: &str) -> Self {
+        Self {
+            operator: operator.to_string(),
+            properties: BTreeMap::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn with(mut self, key: &str, value: String) -> Self {
+        self.properties.insert(key.to_string(), value);
+        self
+    }
+
+    fn child(mut self, plan: &PhysicalPlan, catalog: &Catalog) -> DbResult<Self> {
+        self.children.push(Self::of(plan, catalog)?);
+        Ok(self)
+    }
+
+    /// Name the scanned table and, if not all of them, the columns decoded.
+    fn scan(
+        self,
+        catalog: &Catalog,
+        table_id: TableId,
+        projection: &Option<Vec<ColumnId>>,
+    ) -> DbResult<Self> {
+        let table = catalog.table_by_id(table_id)?;
+        let shape = self.with("table", table.name.clone());
+        Ok(match projection {
+            Some(columns) => shape.with(
+                "decodes",
+                list(columns.iter().map(|&id| column_name(table, id))),
+            ),
+            None => shape,
+        })
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        write!(f, "{:width$}{}", "", self.operator, width = depth * 2)?;
+        for (key, value) in &self.properties {
+            write!(f, " {key}={value}")?;
+        }
+        writeln!(f)?;
+        for child in &self.children {
+            child.write(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// One operator per line, each input indented under the operator reading it.
+impl fmt::Display for PlanShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+fn operator_name(plan: &PhysicalPlan) -> &'static str {
+    match plan {
+        PhysicalPlan::SeqScan { .. } => "SeqScan",
+        PhysicalPlan::SampleScan { .. } => "SampleScan",
+        PhysicalPlan::PartitionScan { .. } => "PartitionScan",
+        PhysicalPlan::IndexScan { .. } => "IndexScan",
+        PhysicalPlan::IndexUnion { .. } => "IndexUnion",
+        PhysicalPlan::Filter { .. } => "Filter",
+        PhysicalPlan::Project { .. } => "Project",
+        PhysicalPlan::Aggregate { .. } => "Aggregate",
+        PhysicalPlan::Sort { .. } => "Sort",
+        PhysicalPlan::Limit { .. } => "Limit",
+        PhysicalPlan::Insert { .. } => "Insert",
+        PhysicalPlan::Update { .. } => "Update",
+        PhysicalPlan::Delete { .. } => "Delete",
+        PhysicalPlan::SystemScan { .. } => "SystemScan",
+        PhysicalPlan::SeriesScan { .. } => "SeriesScan",
+        PhysicalPlan::ValuesScan { .. } => "ValuesScan",
+        PhysicalPlan::NestedLoopJoin { .. } => "NestedLoopJoin",
+        PhysicalPlan::HashJoin { .. } => "HashJoin",
+        PhysicalPlan::SemiJoin { .. } => "SemiJoin",
+        PhysicalPlan::AntiJoin { .. } => "AntiJoin",
+    }
+}
+
+/// The columns an index probe matches on, without the values it looks up.
+fn describe_predicate(table: &TableMeta, predicate: &IndexPredicate) -> String {
+    match predicate {
+        IndexPredicate::Eq { col, .. } => format!("{} = ?", column_name(table, *col)),
+        IndexPredicate::CompositeEq { columns, .. } => format!(
+            "({}) = ?",
+            columns
+                .iter()
+                .map(|&id| column_name(table, id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        IndexPredicate::Range { col, .. } => format!("{} in range", column_name(table, *col)),
+    }
+}
+
+fn column_name(table: &TableMeta, id: ColumnId) -> String {
+    table
+        .columns()
+        .get(id as usize)
+        .map(|column| column.name.clone())
+        .unwrap_or_else(|| format!("#{id}"))
+}
+
+/// A join key: the column it is, by name, or `expr` for a computed key.
+fn describe_key(key: &ResolvedExpr, schema: &[String]) -> String {
+    match key {
+        ResolvedExpr::Column(id) => schema
+            .get(*id as usize)
+            .cloned()
+            .unwrap_or_else(|| format!("#{id}")),
+        _ => "expr".to_string(),
+    }
+}
+
+fn list(items: impl IntoIterator<Item = String>) -> String {
+    format!("[{}]", items.into_iter().collect::<Vec<_>>().join(", "))
+}
@@ -1,5 +1,5 @@
 use super::*;
-use catalog::{Column, IndexKind};
+use catalog::{Column, IndexKind, TableStatistics};
 use parser::parse_sql;
 use pretty_assertions::assert_eq;
 use types::SqlType;
@@ -12,6 +12,17 @@ fn col(name: &str) -> Expr {
     }
 }
 
+/// Helper to build a projection list; `"*"` becomes the wildcard.
+fn items(names: &[&str]) -> Vec<SelectItem> {
+    names
+        .iter()
+        .map(|n| match *n {
+            "*" => SelectItem::Wildcard,
+            name => SelectItem::Column(name.to_string()),
+        })
+        .collect()
+}
+
 /// Create a sample catalog with a users table.
 fn sample_catalog() -> Catalog {
     let mut catalog = Catalog::new();
@@ -72,7 +83,13 @@ fn select_specific_columns_resolves_ordinals() {
 
     match plan {
         PhysicalPlan::Project { columns, .. } => {
-            assert_eq!(columns, vec![("name".into(), 1), ("age".into(), 2)]);
+            assert_eq!(
+                columns,
+                vec![
+                    ("name".into(), ResolvedExpr::Column(1)),
+                    ("age".into(), ResolvedExpr::Column(2))
+                ]
+            );
         }
         _ => panic!("expected Project"),
     }
@@ -138,6 +155,33 @@ fn predicate_on_non_indexed_column_uses_seqscan() {
     assert!(!text.contains("IndexScan"));
 }
 
+#[test]
+fn disjunction_of_indexed_predicates_uses_index_union() {
+    let catalog = sample_catalog();
+    let plan = |sql: &str| {
+        let mut ctx = PlanningContext::new(&catalog);
+        Planner::plan(parse_sql(sql).unwrap().remove(0), &mut ctx).unwrap()
+    };
+
+    let text = explain_physical(&plan(
+        "SELECT name FROM users WHERE id = 1 OR id = 7 OR age > 60",
+    ));
+    assert!(text.contains("IndexUnion"), "{text}");
+    assert_eq!(text.matches("idx_users_id").count(), 2, "{text}");
+    assert!(text.contains("idx_users_age"), "{text}");
+
+    // The disjunction can be one conjunct of the predicate
+    let text = explain_physical(&plan(
+        "SELECT * FROM users WHERE (id = 1 OR age = 30) AND name = 'x'",
+    ));
+    assert!(text.contains("IndexUnion"), "{text}");
+
+    // Every branch needs an index
+    let text = explain_physical(&plan("SELECT * FROM users WHERE id = 1 OR name = 'x'"));
+    assert!(text.contains("SeqScan"), "{text}");
+    assert!(!text.contains("IndexUnion"), "{text}");
+}
+
 #[test]
 fn insert_plan_includes_values() {
     let catalog = sample_catalog();
@@ -149,7 +193,9 @@ fn insert_plan_includes_values() {
     let plan = Planner::plan(stmt, &mut ctx).unwrap();
 
     match plan {
-        PhysicalPlan::Insert { table_id, values } => {
+        PhysicalPlan::Insert { table_id, rows } => {
+            assert_eq!(rows.len(), 1);
+            let values = &rows[0];
             assert_eq!(table_id.0, 1);
             assert_eq!(values.len(), 3);
         }
@@ -172,11 +218,17 @@ fn update_plan_resolves_assignments() {
             table_id,
             assignments,
             predicate,
+            source,
         } => {
             assert_eq!(table_id.0, 1);
             assert_eq!(assignments.len(), 1);
             assert_eq!(assignments[0].0, 2); // age column
             assert!(predicate.is_some());
+            // The key lookup reads the rows through an index
+            assert!(matches!(
+                source.as_deref(),
+                Some(PhysicalPlan::IndexScan { .. })
+            ));
         }
         _ => panic!("expected Update"),
     }
@@ -196,9 +248,14 @@ fn delete_plan_with_predicate() {
         PhysicalPlan::Delete {
             table_id,
             predicate,
+            source,
         } => {
             assert_eq!(table_id.0, 1);
             assert!(predicate.is_some());
+            assert!(matches!(
+                source.as_deref(),
+                Some(PhysicalPlan::IndexScan { .. })
+            ));
         }
         _ => panic!("expected Delete"),
     }
@@ -216,6 +273,7 @@ fn delete_without_predicate() {
         PhysicalPlan::Delete {
             table_id,
             predicate,
+            ..
         } => {
             assert_eq!(table_id.0, 1);
             assert!(predicate.is_none());
@@ -295,9 +353,9 @@ fn projection_pruning_removes_redundant_wildcard() {
             input: Box::new(LogicalPlan::TableScan {
                 table: "users".into(),
             }),
-            columns: vec!["id".into(), "name".into()],
+            columns: items(&["id", "name"]),
         }),
-        columns: vec!["*".into()],
+        columns: items(&["*"]),
     };
 
     let catalog = sample_catalog();
@@ -307,7 +365,7 @@ fn projection_pruning_removes_redundant_wildcard() {
     // Outer wildcard project should be replaced by inner specific columns
     match pruned {
         LogicalPlan::Project { columns, .. } => {
-            assert_eq!(columns, vec!["id".to_string(), "name".to_string()]);
+            assert_eq!(columns, items(&["id", "name"]));
         }
         _ => panic!("expected Project"),
     }
@@ -418,6 +476,7 @@ fn explain_physical_formats_correctly() {
         input: Box::new(PhysicalPlan::SeqScan {
             table_id: TableId(1),
             schema: vec!["id".into(), "name".into()],
+            projection: None,
         }),
         predicate: ResolvedExpr::Binary {
             left: Box::new(ResolvedExpr::Column(0)),
@@ -445,7 +504,13 @@ fn end_to_end_query_with_index() {
     // Should produce: Project([name, age]) → Filter(id = 42) → IndexScan(idx_users_id)
     match plan {
         PhysicalPlan::Project { input, columns } => {
-            assert_eq!(columns, vec![("name".into(), 1), ("age".into(), 2)]);
+            assert_eq!(
+                columns,
+                vec![
+                    ("name".into(), ResolvedExpr::Column(1)),
+                    ("age".into(), ResolvedExpr::Column(2))
+                ]
+            );
             match *input {
                 PhysicalPlan::Filter { input, .. } => match *input {
                     PhysicalPlan::IndexScan { index_name, .. } => {
@@ -539,7 +604,7 @@ fn nested_filters_optimize_recursively() {
     };
     let project = LogicalPlan::Project {
         input: Box::new(filter1),
-        columns: vec!["*".into()],
+        columns: items(&["*"]),
     };
     let filter2 = LogicalPlan::Filter {
         input: Box::new(project),
@@ -568,6 +633,56 @@ fn nested_filters_optimize_recursively() {
     }
 }
 
+#[test]
+fn insert_column_list_fills_missing_columns() {
+    let mut catalog = Catalog::new();
+    catalog
+        .create_table(
+            "items",
+            vec![
+                Column::new("id", SqlType::Int),
+                Column::new("label", SqlType::Text),
+                Column::new("qty", SqlType::Int).with_default(Value::Int(0)),
+            ],
+            None,
+        )
+        .unwrap();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("INSERT INTO items (qty, id) VALUES (5, 1), (6, 2)")
+        .unwrap()
+        .remove(0);
+
+    match Planner::plan(stmt, &mut ctx).unwrap() {
+        PhysicalPlan::Insert { rows, .. } => {
+            assert_eq!(
+                rows[1],
+                vec![
+                    ResolvedExpr::Literal(Value::Int(2)),
+                    ResolvedExpr::Literal(Value::Null),
+                    ResolvedExpr::Literal(Value::Int(6)),
+                ]
+            );
+        }
+        other => panic!("expected Insert, got {other:?}"),
+    }
+
+    let stmt = parse_sql("INSERT INTO items (id) VALUES (3)")
+        .unwrap()
+        .remove(0);
+    match Planner::plan(stmt, &mut ctx).unwrap() {
+        PhysicalPlan::Insert { rows, .. } => {
+            assert_eq!(rows[0][2], ResolvedExpr::Literal(Value::Int(0)));
+        }
+        other => panic!("expected Insert, got {other:?}"),
+    }
+
+    let stmt = parse_sql("INSERT INTO items (nope) VALUES (3)")
+        .unwrap()
+        .remove(0);
+    let err = Planner::plan(stmt, &mut ctx).unwrap_err();
+    assert!(err.to_string().contains("unknown column 'nope'"), "{err}");
+}
+
 #[test]
 fn bind_expr_in_insert_values() {
     let catalog = sample_catalog();
@@ -581,7 +696,9 @@ fn bind_expr_in_insert_values() {
     let plan = Planner::plan(stmt, &mut ctx).unwrap();
 
     match plan {
-        PhysicalPlan::Insert { values, .. } => {
+        PhysicalPlan::Insert { rows, .. } => {
+            assert_eq!(rows.len(), 1);
+            let values = &rows[0];
             assert_eq!(values.len(), 3);
             assert!(matches!(values[0], ResolvedExpr::Literal(Value::Int(1))));
             assert!(matches!(values[1], ResolvedExpr::Literal(Value::Text(_))));
@@ -591,6 +708,105 @@ fn bind_expr_in_insert_values() {
     }
 }
 
+#[test]
+fn insert_values_must_fit_the_table() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let plan_err = |ctx: &mut PlanningContext, sql: &str| {
+        let stmt = parse_sql(sql).unwrap().remove(0);
+        Planner::plan(stmt, ctx).unwrap_err().to_string()
+    };
+
+    let err = plan_err(&mut ctx, "INSERT INTO users VALUES (1, 'a', 30, 40)");
+    assert!(
+        err.contains("has 4 values in row 1, but the table has 3 columns"),
+        "{err}"
+    );
+    let err = plan_err(&mut ctx, "INSERT INTO users VALUES (1, 'alice', 'thirty')");
+    assert!(
+        err.contains("column 'age' of table 'users' has type INT and cannot hold Text(\"thirty\")"),
+        "{err}"
+    );
+    let err = plan_err(&mut ctx, "INSERT INTO users (name) VALUES (7)");
+    assert!(
+        err.contains("column 'name' of table 'users' has type TEXT"),
+        "{err}"
+    );
+
+    // The parser checks VALUES rows against the column list too
+    let stmt = Statement::Insert {
+        table: "users".into(),
+        columns: vec!["id".into(), "age".into()],
+        rows: vec![vec![Expr::Literal(Value::Int(1))]],
+        query: None,
+    };
+    let err = Planner::plan(stmt, &mut ctx).unwrap_err().to_string();
+    assert!(
+        err.contains("has 1 values in row 1 for 2 target columns"),
+        "{err}"
+    );
+
+    // NULLs, and values converted on storage, fit
+    let stmt = parse_sql("INSERT INTO users VALUES (NULL, NULL, CAST(30 AS INT))")
+        .unwrap()
+        .remove(0);
+    assert!(Planner::plan(stmt, &mut ctx).is_ok());
+}
+
+#[test]
+fn operand_and_column_types_are_checked_when_planning() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let mut plan = |sql: &str| {
+        let stmt = parse_sql(sql).unwrap().remove(0);
+        Planner::plan(stmt, &mut ctx).map_err(|e| e.to_string())
+    };
+
+    for (sql, message) in [
+        (
+            "SELECT * FROM users WHERE name > 5",
+            "operator > cannot be applied to TEXT and INT",
+        ),
+        (
+            "SELECT age + name FROM users",
+            "operator + cannot be applied to INT and TEXT",
+        ),
+        (
+            "SELECT * FROM users WHERE age",
+            "WHERE clause must be BOOL, not INT",
+        ),
+        (
+            "DELETE FROM users WHERE NOT name",
+            "argument of NOT must be BOOL, not TEXT",
+        ),
+        (
+            "SELECT CASE WHEN age THEN 1 END FROM users",
+            "CASE WHEN condition must be BOOL, not INT",
+        ),
+        (
+            "UPDATE users SET age = name",
+            "column 'age' of table 'users' has type INT and cannot hold TEXT values",
+        ),
+        (
+            "UPDATE users SET name = age * 2 WHERE id = 1",
+            "column 'name' of table 'users' has type TEXT and cannot hold INT values",
+        ),
+    ] {
+        let err = plan(sql).expect_err(sql);
+        assert!(err.contains(message), "{sql}: {err}");
+    }
+
+    // NULLs, functions and mixed numbers pass
+    for sql in [
+        "SELECT * FROM users WHERE age > 1.5 OR name = NULL",
+        "SELECT * FROM users WHERE UPPER(name) = 'A' AND age + NULL > 1",
+        "UPDATE users SET name = CONCAT(name, '!'), age = CASE WHEN age > 1 THEN age END",
+        "SELECT CAST(age AS TEXT) FROM users WHERE CAST(name AS INT) = 3",
+    ] {
+        plan(sql).unwrap_or_else(|e| panic!("{sql}: {e}"));
+    }
+}
+
 #[test]
 fn update_assignment_with_expression() {
     let catalog = sample_catalog();
@@ -798,7 +1014,7 @@ fn try_extract_index_predicate_column_on_right() {
 fn output_schema_for_modify_operations() {
     let schema = Planner::output_schema(&PhysicalPlan::Insert {
         table_id: TableId(1),
-        values: vec![],
+        rows: vec![],
     });
     assert_eq!(schema, Vec::<String>::new());
 
@@ -806,12 +1022,14 @@ fn output_schema_for_modify_operations() {
         table_id: TableId(1),
         assignments: vec![],
         predicate: None,
+        source: None,
     });
     assert_eq!(schema, Vec::<String>::new());
 
     let schema = Planner::output_schema(&PhysicalPlan::Delete {
         table_id: TableId(1),
         predicate: None,
+        source: None,
     });
     assert_eq!(schema, Vec::<String>::new());
 }
@@ -823,9 +1041,9 @@ fn projection_with_specific_columns_not_pruned() {
             input: Box::new(LogicalPlan::TableScan {
                 table: "users".into(),
             }),
-            columns: vec!["id".into(), "name".into()],
+            columns: items(&["id", "name"]),
         }),
-        columns: vec!["id".into()], // Different from inner
+        columns: items(&["id"]), // Different from inner
     };
 
     let _catalog = sample_catalog();
@@ -835,7 +1053,7 @@ fn projection_with_specific_columns_not_pruned() {
     // Should NOT prune because outer projection is different
     match pruned {
         LogicalPlan::Project { input, columns } => {
-            assert_eq!(columns, vec!["id".to_string()]);
+            assert_eq!(columns, items(&["id"]));
             assert!(matches!(*input, LogicalPlan::Project { .. }));
         }
         _ => panic!("expected Project"),
@@ -978,6 +1196,91 @@ fn unknown_column_in_order_by_returns_error() {
     );
 }
 
+#[test]
+fn order_by_errors_keep_the_qualifier_and_ambiguity() {
+    let catalog = analyzed_catalog();
+    let plan = |sql: &str| {
+        let mut ctx = PlanningContext::new(&catalog);
+        Planner::plan(parse_sql(sql).unwrap().remove(0), &mut ctx)
+    };
+    let join = "SELECT u.name FROM users u JOIN orders o ON o.user_id = u.id";
+
+    assert!(plan(&format!("{join} ORDER BY o.id DESC, u.age")).is_ok());
+    let err = plan(&format!("{join} ORDER BY o.missing")).unwrap_err();
+    assert!(
+        err.to_string().contains("unknown column 'o.missing'"),
+        "{err}"
+    );
+    let err = plan(&format!("{join} ORDER BY id")).unwrap_err();
+    assert!(err.to_string().contains("ambiguous column 'id'"), "{err}");
+}
+
+#[test]
+fn order_by_alias_sorts_by_projected_column() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("SELECT id, UPPER(name) AS shout FROM users ORDER BY shout DESC")
+        .unwrap()
+        .remove(0);
+
+    let plan = Planner::plan(stmt, &mut ctx).unwrap();
+
+    match plan {
+        PhysicalPlan::Sort { input, order_by } => {
+            assert_eq!(order_by[0].column_id, 1);
+            assert_eq!(order_by[0].direction, SortDirection::Desc);
+            assert!(
+                matches!(*input, PhysicalPlan::Project { ref columns, .. } if columns.len() == 2)
+            );
+        }
+        _ => panic!("expected Sort, got {:?}", plan),
+    }
+}
+
+#[test]
+fn order_by_unselected_expression_adds_hidden_column() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("SELECT id FROM users ORDER BY LENGTH(name), age, id")
+        .unwrap()
+        .remove(0);
+
+    let plan = Planner::plan(stmt, &mut ctx).unwrap();
+
+    // Project(id) -> Sort -> Project(id, LENGTH(name), age) -> SeqScan
+    let PhysicalPlan::Project { input, columns } = plan else {
+        panic!("expected Project dropping the sort keys, got {plan:?}");
+    };
+    assert_eq!(columns, vec![("id".to_string(), ResolvedExpr::Column(0))]);
+    let PhysicalPlan::Sort { input, order_by } = *input else {
+        panic!("expected Sort");
+    };
+    let keys: Vec<ColumnId> = order_by.iter().map(|o| o.column_id).collect();
+    assert_eq!(keys, vec![1, 2, 0]);
+    let PhysicalPlan::Project { columns, .. } = *input else {
+        panic!("expected Project computing the sort keys");
+    };
+    let names: Vec<&str> = columns.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["id", "length(name)", "age"]);
+    assert_eq!(columns[2].1, ResolvedExpr::Column(2));
+}
+
+#[test]
+fn order_by_reuses_projected_expression() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("SELECT LOWER(name) FROM users ORDER BY LOWER(name)")
+        .unwrap()
+        .remove(0);
+
+    let plan = Planner::plan(stmt, &mut ctx).unwrap();
+
+    match plan {
+        PhysicalPlan::Sort { order_by, .. } => assert_eq!(order_by[0].column_id, 0),
+        _ => panic!("expected Sort without a hidden column, got {:?}", plan),
+    }
+}
+
 #[test]
 fn order_by_explain_formats_correctly() {
     let catalog = sample_catalog();
@@ -993,3 +1296,970 @@ fn order_by_explain_formats_correctly() {
     assert!(text.contains("limit=Some(10)"));
     assert!(text.contains("Sort"));
 }
+
+#[test]
+fn function_projection_binds_arguments() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("SELECT id, UPPER(name) FROM users")
+        .unwrap()
+        .remove(0);
+
+    let plan = Planner::plan(stmt, &mut ctx).unwrap();
+
+    match plan {
+        PhysicalPlan::Project { columns, .. } => {
+            assert_eq!(
+                columns,
+                vec![
+                    ("id".into(), ResolvedExpr::Column(0)),
+                    (
+                        "upper(name)".into(),
+                        ResolvedExpr::Function {
+                            name: "upper".into(),
+                            args: vec![ResolvedExpr::Column(1)],
+                        }
+                    ),
+                ]
+            );
+        }
+        other => panic!("expected Project, got {other:?}"),
+    }
+}
+
+#[test]
+fn function_in_predicate_binds() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("SELECT * FROM users WHERE LENGTH(name) > 3")
+        .unwrap()
+        .remove(0);
+
+    let plan = Planner::plan(stmt, &mut ctx).unwrap();
+
+    match plan {
+        PhysicalPlan::Project { input, .. } => match *input {
+            PhysicalPlan::Filter { predicate, .. } => {
+                assert!(
+                    matches!(
+                        predicate,
+                        ResolvedExpr::Binary { ref left, .. }
+                            if matches!(**left, ResolvedExpr::Function { ref name, .. } if name == "length")
+                    ),
+                    "{predicate:?}"
+                );
+            }
+            other => panic!("expected Filter, got {other:?}"),
+        },
+        other => panic!("expected Project, got {other:?}"),
+    }
+}
+
+#[test]
+fn unknown_function_or_bad_arity_is_planner_error() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+
+    let stmt = parse_sql("SELECT FROBNICATE(name) FROM users")
+        .unwrap()
+        .remove(0);
+    let err = Planner::plan(stmt, &mut ctx).unwrap_err();
+    assert!(err.to_string().contains("unknown function"), "{err}");
+
+    let stmt = parse_sql("SELECT UPPER(name, id) FROM users")
+        .unwrap()
+        .remove(0);
+    let err = Planner::plan(stmt, &mut ctx).unwrap_err();
+    assert!(err.to_string().contains("does not accept 2"), "{err}");
+}
+
+#[test]
+fn select_aliases_name_projected_columns() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("SELECT id AS user_id, UPPER(name) AS shout FROM users")
+        .unwrap()
+        .remove(0);
+
+    let plan = Planner::plan(stmt, &mut ctx).unwrap();
+
+    match plan {
+        PhysicalPlan::Project { columns, .. } => {
+            let names: Vec<_> = columns.iter().map(|(n, _)| n.as_str()).collect();
+            assert_eq!(names, vec!["user_id", "shout"]);
+            assert_eq!(columns[0].1, ResolvedExpr::Column(0));
+        }
+        other => panic!("expected Project, got {other:?}"),
+    }
+}
+
+#[test]
+fn order_by_resolves_against_projected_columns() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("SELECT name, age AS years FROM users ORDER BY years DESC")
+        .unwrap()
+        .remove(0);
+
+    let plan = Planner::plan(stmt, &mut ctx).unwrap();
+
+    match plan {
+        PhysicalPlan::Sort { order_by, .. } => {
+            // `years` is the second projected column, not the table's `age` ordinal
+            assert_eq!(order_by[0].column_id, 1);
+        }
+        other => panic!("expected Sort, got {other:?}"),
+    }
+}
+
+#[test]
+fn view_reference_expands_to_its_query() {
+    let mut catalog = sample_catalog();
+    catalog
+        .create_view("adults", "SELECT id, name FROM users WHERE age >= 18")
+        .unwrap();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("SELECT name FROM adults WHERE id = 42")
+        .unwrap()
+        .remove(0);
+
+    let plan = Planner::plan(stmt, &mut ctx).unwrap();
+
+    let PhysicalPlan::Project { input, columns } = plan else {
+        panic!("expected Project, got {:?}", plan);
+    };
+    assert_eq!(columns, vec![("name".into(), ResolvedExpr::Column(1))]);
+    let PhysicalPlan::Filter { input, .. } = *input else {
+        panic!("expected Filter over the view");
+    };
+    let text = explain_physical(&input);
+    assert!(text.starts_with("Project"), "{text}");
+    assert!(text.contains("Filter"), "{text}");
+}
+
+#[test]
+fn self_referential_view_is_rejected() {
+    let mut catalog = sample_catalog();
+    catalog.create_view("a", "SELECT * FROM b").unwrap();
+    catalog.create_view("b", "SELECT * FROM a").unwrap();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("SELECT * FROM a").unwrap().remove(0);
+
+    let err = Planner::plan(stmt, &mut ctx).unwrap_err();
+    assert!(
+        format!("{err}").contains("view 'a' refers to itself"),
+        "{err}"
+    );
+}
+
+#[test]
+fn self_join_binds_each_side_by_alias() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("SELECT e.name, m.name FROM users e JOIN users m ON e.age = m.id")
+        .unwrap()
+        .remove(0);
+
+    let plan = Planner::plan(stmt, &mut ctx).unwrap();
+
+    let PhysicalPlan::Project { input, columns } = plan else {
+        panic!("expected Project, got {:?}", plan);
+    };
+    assert_eq!(
+        columns,
+        vec![
+            ("e.name".into(), ResolvedExpr::Column(1)),
+            ("m.name".into(), ResolvedExpr::Column(4)),
+        ]
+    );
+    // e.age hashed against m.id, each over its own side's columns
+    let PhysicalPlan::HashJoin { keys, .. } = *input else {
+        panic!("expected HashJoin");
+    };
+    assert_eq!(
+        keys,
+        vec![(ResolvedExpr::Column(2), ResolvedExpr::Column(0))]
+    );
+}
+
+#[test]
+fn join_equalities_become_hash_keys_and_the_rest_a_filter() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt =
+        parse_sql("SELECT e.name FROM users e JOIN users m ON e.id + 1 = m.age AND e.age > m.age")
+            .unwrap()
+            .remove(0);
+
+    let plan = Planner::plan(stmt, &mut ctx).unwrap();
+
+    let PhysicalPlan::Project { input, .. } = plan else {
+        panic!("expected Project, got {:?}", plan);
+    };
+    let PhysicalPlan::Filter { input, predicate } = *input else {
+        panic!("expected Filter, got {:?}", input);
+    };
+    assert_eq!(
+        predicate,
+        ResolvedExpr::Binary {
+            left: Box::new(ResolvedExpr::Column(2)),
+            op: BinaryOp::Gt,
+            right: Box::new(ResolvedExpr::Column(5)),
+        }
+    );
+    let PhysicalPlan::HashJoin { keys, .. } = *input else {
+        panic!("expected HashJoin, got {:?}", input);
+    };
+    assert_eq!(
+        keys,
+        vec![(
+            ResolvedExpr::Binary {
+                left: Box::new(ResolvedExpr::Column(0)),
+                op: BinaryOp::Add,
+                right: Box::new(ResolvedExpr::Literal(Value::Int(1))),
+            },
+            ResolvedExpr::Column(2),
+        )]
+    );
+
+    // An OR has no term every match satisfies, so it stays a nested loop
+    let stmt = parse_sql("SELECT e.name FROM users e JOIN users m ON e.id = m.age OR e.age = m.id")
+        .unwrap()
+        .remove(0);
+    let PhysicalPlan::Project { input, .. } = Planner::plan(stmt, &mut ctx).unwrap() else {
+        panic!("expected Project");
+    };
+    assert!(
+        matches!(*input, PhysicalPlan::NestedLoopJoin { .. }),
+        "{input:?}"
+    );
+}
+
+#[test]
+fn table_named_twice_without_aliases_is_rejected() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("SELECT users.name FROM users JOIN users ON users.age = users.id")
+        .unwrap()
+        .remove(0);
+
+    let err = Planner::plan(stmt, &mut ctx).unwrap_err();
+    assert!(
+        format!("{err}").contains("table name 'users' specified more than once"),
+        "{err}"
+    );
+}
+
+#[test]
+fn subquery_tables_hide_outer_tables_of_the_same_name() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let sql = "SELECT name FROM users WHERE EXISTS (SELECT id FROM users WHERE users.age > 30)";
+    let plan = Planner::plan(parse_sql(sql).unwrap().remove(0), &mut ctx).unwrap();
+    let explain = explain_physical(&plan);
+    // `users.age` is the subquery's column, which only the right side reads
+    assert!(explain.contains("SemiJoin on=age > 30"), "{explain}");
+    assert!(
+        explain.contains("left:   SeqScan table_id=1 columns=[name]"),
+        "{explain}"
+    );
+}
+
+#[test]
+fn unqualified_join_columns_must_be_unambiguous() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("SELECT name FROM users e JOIN users m ON e.age = m.id")
+        .unwrap()
+        .remove(0);
+
+    let err = Planner::plan(stmt, &mut ctx).unwrap_err();
+    assert!(
+        format!("{err}").contains("ambiguous column 'name'"),
+        "{err}"
+    );
+}
+
+#[test]
+fn exists_subquery_is_planned_as_a_semi_join() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let sql = "SELECT name FROM users u \
+               WHERE u.age > 30 AND EXISTS (SELECT 1 FROM users m WHERE m.id = u.age)";
+    let plan = Planner::plan(parse_sql(sql).unwrap().remove(0), &mut ctx).unwrap();
+
+    let PhysicalPlan::Project { input, columns } = plan else {
+        panic!("expected Project, got {:?}", plan);
+    };
+    assert_eq!(columns, vec![("name".into(), ResolvedExpr::Column(1))]);
+    let PhysicalPlan::SemiJoin {
+        left,
+        condition,
+        schema,
+        ..
+    } = *input
+    else {
+        panic!("expected SemiJoin, got {:?}", input);
+    };
+    // The rest of the WHERE clause still filters the outer table first
+    assert!(
+        explain_physical(&left).contains("IndexScan"),
+        "{}",
+        explain_physical(&left)
+    );
+    assert_eq!(schema, vec!["id", "name", "age"]);
+    assert_eq!(
+        condition,
+        ResolvedExpr::Binary {
+            left: Box::new(ResolvedExpr::Column(3)),
+            op: BinaryOp::Eq,
+            right: Box::new(ResolvedExpr::Column(2)),
+        }
+    );
+}
+
+#[test]
+fn subquery_columns_hide_outer_columns_of_the_same_name() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let sql = "SELECT name FROM users u WHERE NOT EXISTS (SELECT 1 FROM users m WHERE id = u.age)";
+    let plan = Planner::plan(parse_sql(sql).unwrap().remove(0), &mut ctx).unwrap();
+
+    let PhysicalPlan::Project { input, .. } = plan else {
+        panic!("expected Project, got {:?}", plan);
+    };
+    let PhysicalPlan::AntiJoin { condition, .. } = *input else {
+        panic!("expected AntiJoin, got {:?}", input);
+    };
+    // `id` is the subquery's, not the outer query's
+    assert_eq!(
+        condition,
+        ResolvedExpr::Binary {
+            left: Box::new(ResolvedExpr::Column(3)),
+            op: BinaryOp::Eq,
+            right: Box::new(ResolvedExpr::Column(2)),
+        }
+    );
+}
+
+#[test]
+fn in_subquery_matches_its_selected_column() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let sql = "SELECT name FROM users u WHERE age IN (SELECT id FROM users m WHERE name = 'x')";
+    let plan = Planner::plan(parse_sql(sql).unwrap().remove(0), &mut ctx).unwrap();
+
+    let PhysicalPlan::Project { input, .. } = plan else {
+        panic!("expected Project, got {:?}", plan);
+    };
+    let PhysicalPlan::SemiJoin { condition, .. } = *input else {
+        panic!("expected SemiJoin, got {:?}", input);
+    };
+    assert_eq!(
+        condition,
+        ResolvedExpr::Binary {
+            left: Box::new(ResolvedExpr::Binary {
+                left: Box::new(ResolvedExpr::Column(2)),
+                op: BinaryOp::Eq,
+                right: Box::new(ResolvedExpr::Column(3)),
+            }),
+            op: BinaryOp::And,
+            right: Box::new(ResolvedExpr::Binary {
+                left: Box::new(ResolvedExpr::Column(4)),
+                op: BinaryOp::Eq,
+                right: Box::new(ResolvedExpr::Literal(Value::Text("x".into()))),
+            }),
+        }
+    );
+}
+
+#[test]
+fn single_table_alias_qualifies_columns() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("SELECT u.name FROM users u WHERE u.id = 42 ORDER BY u.age")
+        .unwrap()
+        .remove(0);
+
+    let plan = Planner::plan(stmt, &mut ctx).unwrap();
+
+    let text = explain_physical(&plan);
+    assert!(text.contains("IndexScan"), "{text}");
+    assert_eq!(Planner::output_schema(&plan), vec!["name"]);
+
+    // The alias replaces the table name
+    let stmt = parse_sql("SELECT users.name FROM users u")
+        .unwrap()
+        .remove(0);
+    let err = Planner::plan(stmt, &mut ctx).unwrap_err();
+    assert!(
+        format!("{err}").contains("unknown column 'users.name'"),
+        "{err}"
+    );
+}
+
+fn accounts_catalog() -> Catalog {
+    let mut catalog = Catalog::new();
+    catalog
+        .create_table(
+            "accounts",
+            vec![
+                Column::new("id", SqlType::Int),
+                Column::new("email", SqlType::Text).with_not_null(),
+                Column::new("balance", SqlType::Int),
+            ],
+            Some(vec![0]),
+        )
+        .unwrap();
+    catalog
+        .create_index()
+        .table_name("accounts")
+        .index_name("idx_accounts_email")
+        .columns(&["email"])
+        .kind(IndexKind::Hash)
+        .unique(true)
+        .call()
+        .unwrap();
+    catalog
+}
+
+fn explain_dml_sql(catalog: &Catalog, sql: &str) -> String {
+    let mut ctx = PlanningContext::new(catalog);
+    let stmt = parse_sql(sql).unwrap().remove(0);
+    let plan = Planner::plan(stmt, &mut ctx).unwrap();
+    explain_dml(&plan, catalog.table("accounts").unwrap()).unwrap()
+}
+
+#[test]
+fn explain_dml_lists_indexes_and_constraints() {
+    let catalog = accounts_catalog();
+
+    let text = explain_dml_sql(&catalog, "INSERT INTO accounts VALUES (1, 'a@x', 5)");
+    assert_eq!(
+        text,
+        "Insert table=accounts table_id=1\n  \
+         Source: 1 literal row(s)\n  \
+         Index maintenance: primary key (id), unique hash idx_accounts_email (email)\n  \
+         Constraint checks: NOT NULL (email), primary key (id) unique, unique index idx_accounts_email"
+    );
+
+    let text = explain_dml_sql(&catalog, "UPDATE accounts SET balance = 0 WHERE id = 1");
+    assert!(text.starts_with("Update table=accounts"), "{text}");
+    assert!(text.contains("Set: balance"), "{text}");
+    assert!(text.contains("Scan: Filter"), "{text}");
+    assert!(
+        text.contains("IndexScan table_id=1 index=PRIMARY KEY"),
+        "{text}"
+    );
+    assert!(
+        text.ends_with("Constraint checks: primary key (id) not assigned"),
+        "{text}"
+    );
+
+    let text = explain_dml_sql(&catalog, "UPDATE accounts SET email = 'b@x'");
+    assert!(
+        text.contains(
+            "Constraint checks: primary key (id) not assigned, NOT NULL (email), \
+             unique index idx_accounts_email"
+        ),
+        "{text}"
+    );
+
+    let text = explain_dml_sql(&catalog, "DELETE FROM accounts");
+    assert!(text.contains("Scan: SeqScan table_id=1"), "{text}");
+    assert!(text.ends_with("Constraint checks: none"), "{text}");
+}
+
+#[test]
+fn explain_quotes_names_sql_would_not_read_back() {
+    let mut catalog = Catalog::new();
+    catalog
+        .create_table(
+            "order",
+            vec![
+                Column::new("id", SqlType::Int),
+                Column::new("unit price", SqlType::Int).with_not_null(),
+            ],
+            Some(vec![0]),
+        )
+        .unwrap();
+    catalog
+        .create_index()
+        .table_name("order")
+        .index_name("select")
+        .columns(&["unit price"])
+        .kind(IndexKind::BTree)
+        .call()
+        .unwrap();
+    let plan = |sql: &str| {
+        let mut ctx = PlanningContext::new(&catalog);
+        Planner::plan(parse_sql(sql).unwrap().remove(0), &mut ctx).unwrap()
+    };
+
+    let text = explain_dml(
+        &plan(r#"INSERT INTO "order" VALUES (1, 2)"#),
+        catalog.table("order").unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        text,
+        "Insert table=\"order\" table_id=1\n  \
+         Source: 1 literal row(s)\n  \
+         Index maintenance: primary key (id), btree \"select\" (\"unit price\")\n  \
+         Constraint checks: NOT NULL (\"unit price\"), primary key (id) unique"
+    );
+
+    let text = explain_physical(&plan(r#"SELECT id FROM "order" WHERE "unit price" = 3"#));
+    assert!(text.contains("index=\"select\""), "{text}");
+}
+
+#[test]
+fn explain_quotes_the_columns_a_query_reads() {
+    let mut catalog = Catalog::new();
+    catalog
+        .create_table(
+            "events",
+            vec![
+                Column::new("id", SqlType::Int),
+                Column::new("from", SqlType::Int),
+                Column::new("unit price", SqlType::Int),
+            ],
+            Some(vec![0]),
+        )
+        .unwrap();
+    let mut ctx = PlanningContext::new(&catalog);
+    let sql = r#"SELECT "from", "unit price" * 2 AS "select" FROM events
+                 WHERE "from" > 1 AND id = 3 ORDER BY "from" DESC"#;
+    let plan = Planner::plan(parse_sql(sql).unwrap().remove(0), &mut ctx).unwrap();
+
+    assert_eq!(
+        explain_physical(&plan),
+        "Sort [\"from\" DESC]\n    \
+         Project [\"from\", \"unit price\" * 2 AS \"select\"]\n      \
+         Filter [(\"from\" > 1) AND (id = 3)]\n        \
+         IndexScan table_id=1 index=PRIMARY KEY pred=id = 3"
+    );
+}
+
+#[test]
+fn explain_dml_ignores_queries() {
+    let catalog = accounts_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("SELECT * FROM accounts").unwrap().remove(0);
+    let plan = Planner::plan(stmt, &mut ctx).unwrap();
+    assert_eq!(explain_dml(&plan, catalog.table("accounts").unwrap()), None);
+}
+
+#[test]
+fn update_from_binds_against_the_joined_rows() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("UPDATE users SET age = m.age FROM users m WHERE users.id = m.id")
+        .unwrap()
+        .remove(0);
+
+    let plan = Planner::plan(stmt, &mut ctx).unwrap();
+
+    let PhysicalPlan::Update {
+        assignments,
+        predicate,
+        source: Some(source),
+        ..
+    } = plan
+    else {
+        panic!("expected Update with a source, got {plan:?}");
+    };
+    assert_eq!(assignments, vec![(2, ResolvedExpr::Column(5))]);
+    assert_eq!(
+        predicate,
+        Some(ResolvedExpr::Binary {
+            left: Box::new(ResolvedExpr::Column(0)),
+            op: BinaryOp::Eq,
+            right: Box::new(ResolvedExpr::Column(3)),
+        })
+    );
+    let PhysicalPlan::NestedLoopJoin { left, schema, .. } = *source else {
+        panic!("expected NestedLoopJoin");
+    };
+    assert!(matches!(*left, PhysicalPlan::SeqScan { .. }));
+    assert_eq!(schema[0], "users.id");
+    assert_eq!(schema[3], "m.id");
+}
+
+#[test]
+fn delete_using_needs_distinct_table_names() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("DELETE FROM users USING users WHERE users.id = 1")
+        .unwrap()
+        .remove(0);
+    let err = Planner::plan(stmt, &mut ctx).unwrap_err();
+    assert!(
+        format!("{err}").contains("specified more than once"),
+        "{err}"
+    );
+
+    // A single table's alias qualifies its columns
+    let stmt = parse_sql("DELETE FROM users u WHERE u.id = 1")
+        .unwrap()
+        .remove(0);
+    let PhysicalPlan::Delete {
+        predicate, source, ..
+    } = Planner::plan(stmt, &mut ctx).unwrap()
+    else {
+        panic!("expected Delete");
+    };
+    assert!(predicate.is_some());
+    assert!(matches!(
+        source.as_deref(),
+        Some(PhysicalPlan::IndexScan { .. })
+    ));
+}
+
+/// The sample catalog with an `orders` table, both tables analyzed: 1000
+/// users over 200 pages with ages 0 to 49, and 10 orders in one page.
+fn analyzed_catalog() -> Catalog {
+    let mut catalog = sample_catalog();
+    let orders = catalog
+        .create_table(
+            "orders",
+            vec![
+                Column::new("id", SqlType::Int),
+                Column::new("user_id", SqlType::Int),
+            ],
+            None,
+        )
+        .unwrap();
+    let users: Vec<Vec<Value>> = (0..1000)
+        .map(|i| {
+            vec![
+                Value::Int(i),
+                Value::Text(format!("user{i}")),
+                Value::Int(i % 50),
+            ]
+        })
+        .collect();
+    let stats = TableStatistics::from_rows(3, users.iter().map(Vec::as_slice)).with_page_count(200);
+    let users_id = catalog.table("users").unwrap().id;
+    catalog.set_table_statistics(users_id, stats, 0).unwrap();
+    let rows: Vec<Vec<Value>> = (0..10)
+        .map(|i| vec![Value::Int(i), Value::Int(i * 7)])
+        .collect();
+    let stats = TableStatistics::from_rows(2, rows.iter().map(Vec::as_slice)).with_page_count(1);
+    catalog.set_table_statistics(orders, stats, 0).unwrap();
+    catalog
+}
+
+#[test]
+fn analyzed_tables_use_an_index_only_when_it_is_cheaper() {
+    let catalog = analyzed_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let plan = |ctx: &mut PlanningContext, sql: &str| {
+        let stmt = parse_sql(sql).unwrap().remove(0);
+        explain_physical(&Planner::plan(stmt, ctx).unwrap())
+    };
+
+    // One user of a thousand
+    let text = plan(&mut ctx, "SELECT name FROM users WHERE id = 42");
+    assert!(text.contains("IndexScan"), "{text}");
+    assert!(text.contains("idx_users_id"), "{text}");
+    // Four in five users: reading the table is cheaper
+    let text = plan(&mut ctx, "SELECT name FROM users WHERE age >= 10");
+    assert!(text.contains("SeqScan"), "{text}");
+    assert!(!text.contains("IndexScan"), "{text}");
+    // Twenty users of a thousand
+    let text = plan(&mut ctx, "SELECT name FROM users WHERE age = 7");
+    assert!(text.contains("idx_users_age"), "{text}");
+    // Outside the analyzed values of the column
+    let text = plan(&mut ctx, "SELECT name FROM users WHERE age > 100");
+    assert!(text.contains("idx_users_age"), "{text}");
+}
+
+#[test]
+fn analyzed_joins_materialize_the_smaller_input() {
+    let catalog = analyzed_catalog();
+    let users_id = catalog.table("users").unwrap().id;
+    let mut ctx = PlanningContext::new(&catalog);
+    let stmt = parse_sql("SELECT * FROM orders o JOIN users u ON o.user_id = u.id")
+        .unwrap()
+        .remove(0);
+
+    let plan = Planner::plan(stmt, &mut ctx).unwrap();
+
+    // The columns keep the written order
+    assert_eq!(
+        Planner::output_schema(&plan),
+        vec!["o.id", "o.user_id", "u.id", "u.name", "u.age"]
+    );
+    let PhysicalPlan::Project { input, columns } = plan else {
+        panic!("expected Project, got {:?}", plan);
+    };
+    assert_eq!(columns[0].1, ResolvedExpr::Column(3));
+    assert_eq!(columns[2].1, ResolvedExpr::Column(0));
+    let PhysicalPlan::HashJoin { left, keys, .. } = *input else {
+        panic!("expected HashJoin, got {:?}", input);
+    };
+    assert!(
+        matches!(*left, PhysicalPlan::SeqScan { table_id, .. } if table_id == users_id),
+        "{left:?}"
+    );
+    assert_eq!(
+        keys,
+        vec![(ResolvedExpr::Column(0), ResolvedExpr::Column(1))]
+    );
+
+    // Written the other way round, the join is left alone
+    let stmt = parse_sql("SELECT * FROM users u JOIN orders o ON o.user_id = u.id")
+        .unwrap()
+        .remove(0);
+    let PhysicalPlan::Project { input, .. } = Planner::plan(stmt, &mut ctx).unwrap() else {
+        panic!("expected Project");
+    };
+    let PhysicalPlan::HashJoin { left, .. } = *input else {
+        panic!("expected HashJoin, got {:?}", input);
+    };
+    assert!(
+        matches!(*left, PhysicalPlan::SeqScan { table_id, .. } if table_id == users_id),
+        "{left:?}"
+    );
+}
+
+#[test]
+fn analyzed_join_chains_are_reordered_by_cost() {
+    let mut catalog = analyzed_catalog();
+    let items = catalog
+        .create_table(
+            "items",
+            vec![
+                Column::new("id", SqlType::Int),
+                Column::new("order_id", SqlType::Int),
+            ],
+            None,
+        )
+        .unwrap();
+    let rows: Vec<Vec<Value>> = (0..100)
+        .map(|i| vec![Value::Int(i), Value::Int(i % 10)])
+        .collect();
+    let stats = TableStatistics::from_rows(2, rows.iter().map(Vec::as_slice)).with_page_count(1);
+    catalog.set_table_statistics(items, stats, 0).unwrap();
+    let users_id = catalog.table("users").unwrap().id;
+    let orders_id = catalog.table("orders").unwrap().id;
+    let mut ctx = PlanningContext::new(&catalog);
+    // Joining items to orders first multiplies the rows joined to users
+    let stmt = parse_sql(
+        "SELECT * FROM orders o JOIN items i ON i.order_id = o.id JOIN users u ON o.user_id = u.id",
+    )
+    .unwrap()
+    .remove(0);
+
+    let plan = Planner::plan(stmt, &mut ctx).unwrap();
+
+    assert_eq!(
+        Planner::output_schema(&plan),
+        vec![
+            "o.id",
+            "o.user_id",
+            "i.id",
+            "i.order_id",
+            "u.id",
+            "u.name",
+            "u.age"
+        ]
+    );
+    let PhysicalPlan::Project { input, columns } = plan else {
+        panic!("expected Project, got {:?}", plan);
+    };
+    let positions: Vec<_> = columns.into_iter().map(|(_, expr)| expr).collect();
+    assert_eq!(
+        positions,
+        [3, 4, 5, 6, 0, 1, 2].map(ResolvedExpr::Column).to_vec()
+    );
+    let key = |left, right| vec![(ResolvedExpr::Column(left), ResolvedExpr::Column(right))];
+    // users, then orders, then items, each join hashing on the condition
+    // that reads the tables joined so far
+    let PhysicalPlan::HashJoin { left, keys, .. } = *input else {
+        panic!("expected HashJoin, got {:?}", input);
+    };
+    assert_eq!(keys, key(3, 1));
+    let PhysicalPlan::HashJoin {
+        left,
+        right,
+        keys,
+        schema,
+    } = *left
+    else {
+        panic!("expected HashJoin, got {:?}", left);
+    };
+    assert_eq!(keys, key(0, 1));
+    assert_eq!(schema, vec!["u.id", "u.name", "u.age", "o.id", "o.user_id"]);
+    assert!(matches!(*left, PhysicalPlan::SeqScan { table_id, .. } if table_id == users_id));
+    assert!(matches!(*right, PhysicalPlan::SeqScan { table_id, .. } if table_id == orders_id));
+}
+
+/// The projection of each scan in `plan`, left to right.
+fn scan_projections(plan: &PhysicalPlan) -> Vec<Option<Vec<ColumnId>>> {
+    match plan {
+        PhysicalPlan::SeqScan { projection, .. }
+        | PhysicalPlan::PartitionScan { projection, .. }
+        | PhysicalPlan::SampleScan { projection, .. }
+        | PhysicalPlan::IndexScan { projection, .. }
+        | PhysicalPlan::IndexUnion { projection, .. } => vec![projection.clone()],
+        PhysicalPlan::Filter { input, .. }
+        | PhysicalPlan::Project { input, .. }
+        | PhysicalPlan::Aggregate { input, .. }
+        | PhysicalPlan::Sort { input, .. }
+        | PhysicalPlan::Limit { input, .. } => scan_projections(input),
+        PhysicalPlan::NestedLoopJoin { left, right, .. }
+        | PhysicalPlan::HashJoin { left, right, .. }
+        | PhysicalPlan::SemiJoin { left, right, .. }
+        | PhysicalPlan::AntiJoin { left, right, .. } => {
+            let mut scans = scan_projections(left);
+            scans.extend(scan_projections(right));
+            scans
+        }
+        PhysicalPlan::Update { source, .. } | PhysicalPlan::Delete { source, .. } => {
+            source.as_deref().map(scan_projections).unwrap_or_default()
+        }
+        PhysicalPlan::SystemScan { .. }
+        | PhysicalPlan::SeriesScan { .. }
+        | PhysicalPlan::ValuesScan { .. }
+        | PhysicalPlan::Insert { .. } => Vec::new(),
+    }
+}
+
+#[test]
+fn scans_decode_only_the_columns_the_query_reads() {
+    let mut catalog = sample_catalog();
+    catalog
+        .create_table(
+            "orders",
+            vec![
+                Column::new("id", SqlType::Int),
+                Column::new("user_id", SqlType::Int),
+                Column::new("note", SqlType::Text),
+            ],
+            None,
+        )
+        .unwrap();
+    let plan = |sql: &str| {
+        let mut ctx = PlanningContext::new(&catalog);
+        Planner::plan(parse_sql(sql).unwrap().remove(0), &mut ctx).unwrap()
+    };
+
+    let filtered = plan("SELECT name FROM users WHERE age > 30");
+    assert_eq!(scan_projections(&filtered), vec![Some(vec![1, 2])]);
+    assert!(explain_physical(&filtered).contains("columns=[name, age]"));
+
+    let joined = plan("SELECT u.name FROM users u JOIN orders o ON o.user_id = u.id");
+    assert_eq!(
+        scan_projections(&joined),
+        vec![Some(vec![0, 1]), Some(vec![1])]
+    );
+
+    // Sort keys are read even when not selected
+    let sorted = plan("SELECT name FROM users ORDER BY age LIMIT 3");
+    assert_eq!(scan_projections(&sorted), vec![Some(vec![1, 2])]);
+
+    assert_eq!(scan_projections(&plan("SELECT * FROM users")), vec![None]);
+
+    // Rows an UPDATE reads are written back whole
+    let update = plan("UPDATE users SET age = 1 FROM orders o WHERE o.user_id = users.id");
+    assert_eq!(scan_projections(&update), vec![None, None]);
+}
+
+#[test]
+fn information_schema_views_scan_the_catalog() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let sql = "SELECT table_name, inserts FROM information_schema.table_activity WHERE deletes > 0";
+    let plan = Planner::plan(parse_sql(sql).unwrap().remove(0), &mut ctx).unwrap();
+
+    let explain = explain_physical(&plan);
+    assert!(
+        explain.contains("SystemScan view=information_schema.table_activity"),
+        "{explain}"
+    );
+    assert_eq!(Planner::output_schema(&plan), vec!["table_name", "inserts"]);
+
+    let mut ctx = PlanningContext::new(&catalog);
+    let write = "DELETE FROM information_schema.table_activity";
+    assert!(Planner::plan(parse_sql(write).unwrap().remove(0), &mut ctx).is_err());
+}
+
+#[test]
+fn values_and_select_without_from_are_planned_as_a_values_scan() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let plan = Planner::plan(
+        parse_sql("SELECT 1 + 1 AS two").unwrap().remove(0),
+        &mut ctx,
+    )
+    .unwrap();
+    let explain = explain_physical(&plan);
+    assert!(explain.contains("ValuesScan rows=1 columns=0"), "{explain}");
+    assert_eq!(Planner::output_schema(&plan), vec!["two"]);
+
+    let mut ctx = PlanningContext::new(&catalog);
+    let sql = "VALUES (1, 'a'), (2, 'b')";
+    let plan = Planner::plan(parse_sql(sql).unwrap().remove(0), &mut ctx).unwrap();
+    let explain = explain_physical(&plan);
+    assert!(explain.contains("ValuesScan rows=2 columns=2"), "{explain}");
+    assert_eq!(Planner::output_schema(&plan), vec!["column1", "column2"]);
+
+    let mut ctx = PlanningContext::new(&catalog);
+    let err =
+        Planner::plan(parse_sql("VALUES (1), ('a')").unwrap().remove(0), &mut ctx).unwrap_err();
+    assert!(err.to_string().contains("mixes"), "{err}");
+}
+
+#[test]
+fn generate_series_is_planned_as_a_series_scan() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let sql = "SELECT generate_series FROM generate_series(1, 100, 10) WHERE generate_series > 50";
+    let plan = Planner::plan(parse_sql(sql).unwrap().remove(0), &mut ctx).unwrap();
+    let explain = explain_physical(&plan);
+    assert!(
+        explain.contains("SeriesScan start=Literal(Int(1))"),
+        "{explain}"
+    );
+    assert_eq!(Planner::output_schema(&plan), vec!["generate_series"]);
+
+    // Joined, the series' column is qualified by its alias
+    let mut ctx = PlanningContext::new(&catalog);
+    let sql = "SELECT g.generate_series, u.name FROM generate_series(1, 3) g \
+               JOIN users u ON u.id = g.generate_series";
+    assert!(Planner::plan(parse_sql(sql).unwrap().remove(0), &mut ctx).is_ok());
+
+    for (sql, message) in [
+        ("SELECT * FROM generate_series(1)", "expects (start, stop)"),
+        ("SELECT * FROM generate_series('a', 3)", "start must be INT"),
+        ("SELECT * FROM generate_series(1, id)", "id"),
+        (
+            "SELECT * FROM numbers(1, 2)",
+            "unknown table function 'numbers'",
+        ),
+    ] {
+        let mut ctx = PlanningContext::new(&catalog);
+        let err = Planner::plan(parse_sql(sql).unwrap().remove(0), &mut ctx).unwrap_err();
+        assert!(err.to_string().contains(message), "{sql}: {err}");
+    }
+}
+
+#[test]
+fn table_samples_are_planned_as_sample_scans() {
+    let catalog = sample_catalog();
+    let mut ctx = PlanningContext::new(&catalog);
+    let sql = "SELECT name FROM users TABLESAMPLE BERNOULLI (5) REPEATABLE (1) WHERE id = 1";
+    let plan = Planner::plan(parse_sql(sql).unwrap().remove(0), &mut ctx).unwrap();
+    let explain = explain_physical(&plan);
+    // The sample is kept rather than replaced by an index lookup
+    assert!(explain.contains("SampleScan"), "{explain}");
+    assert!(!explain.contains("IndexScan"), "{explain}");
+    assert_eq!(scan_projections(&plan), vec![Some(vec![0, 1])]);
+
+    let mut ctx = PlanningContext::new(&catalog);
+    let sql = "SELECT * FROM information_schema.table_activity TABLESAMPLE SYSTEM (5)";
+    let err = Planner::plan(parse_sql(sql).unwrap().remove(0), &mut ctx).unwrap_err();
+    assert!(err.to_string().contains("can only sample a table"), "{err}");
+}
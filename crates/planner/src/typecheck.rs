@@ -0,0 +1,466 @@
+//! Type checking of bound plans.
+//!
+//! After binding, every expression in a plan is checked against the types
+//! of the columns it reads, so that comparing text with a number, adding a
+//! date to a number or storing text in an INT column fails when the
+//! statement is planned rather than partway through executing it.
+//!
+//! Checking is conservative: NULL literals and function calls have no known
+//! type and pass every check, leaving their values to the executor.
+
+use crate::{PhysicalPlan, PlanningContext, ResolvedAggregate, ResolvedExpr};
+use catalog::TableMeta;
+use common::{ColumnId, DbError, DbResult};
+use expr::aggregate::AggregateFunc;
+use expr::{BinaryOp, UnaryOp};
+use std::fmt;
+use types::{SqlType, Value};
+
+/// The type of an expression's values, ignoring decimal precision and text
+/// length.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Int,
+    Float,
+    Decimal,
+    Text,
+    Bool,
+    Date,
+    Timestamp,
+    Bytes,
+}
+
+impl Kind {
+    fn of_type(ty: &SqlType) -> Self {
+        match ty {
+            SqlType::Int => Kind::Int,
+            SqlType::Float => Kind::Float,
+            SqlType::Decimal { .. } => Kind::Decimal,
+            SqlType::Text => Kind::Text,
+            SqlType::Bool => Kind::Bool,
+            SqlType::Date => Kind::Date,
+            SqlType::Timestamp => Kind::Timestamp,
+            SqlType::Bytes => Kind::Bytes,
+        }
+    }
+
+    /// The kind of a literal; NULL has none.
+    fn of_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => None,
+            Value::Int(_) => Some(Kind::Int),
+            Value::Float(_) => Some(Kind::Float),
+            Value::Decimal(_) => Some(Kind::Decimal),
+            Value::Text(_) => Some(Kind::Text),
+            Value::Bool(_) => Some(Kind::Bool),
+            Value::Date(_) => Some(Kind::Date),
+            Value::Timestamp(_) => Some(Kind::Timestamp),
+            Value::Bytes(_) => Some(Kind::Bytes),
+        }
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, Kind::Int | Kind::Float | Kind::Decimal)
+    }
+
+    fn is_temporal(self) -> bool {
+        matches!(self, Kind::Date | Kind::Timestamp)
+    }
+
+    /// Whether values of the two kinds can be compared (see
+    /// [`Value::cmp_same_type`]). Text compares with dates and timestamps
+    /// by the instant it names.
+    fn comparable(self, other: Kind) -> bool {
+        self == other
+            || (self.is_numeric() && other.is_numeric())
+            || ((self.is_temporal() || self == Kind::Text)
+                && (other.is_temporal() || other == Kind::Text))
+    }
+
+    /// Whether a column of type `ty` can store values of this kind,
+    /// possibly after converting them (see [`Value::coerce_to`]).
+    fn storable_in(self, ty: &SqlType) -> bool {
+        match ty {
+            SqlType::Int => self == Kind::Int,
+            SqlType::Float => self.is_numeric(),
+            SqlType::Decimal { .. } => self.is_numeric() || self == Kind::Text,
+            SqlType::Text => self == Kind::Text,
+            SqlType::Bool => self == Kind::Bool,
+            SqlType::Date => matches!(self, Kind::Date | Kind::Text),
+            SqlType::Timestamp => self.is_temporal() || self == Kind::Text,
+            SqlType::Bytes => matches!(self, Kind::Bytes | Kind::Text),
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Kind::Int => "INT",
+            Kind::Float => "FLOAT",
+            Kind::Decimal => "DECIMAL",
+            Kind::Text => "TEXT",
+            Kind::Bool => "BOOL",
+            Kind::Date => "DATE",
+            Kind::Timestamp => "TIMESTAMP",
+            Kind::Bytes => "BYTEA",
+        })
+    }
+}
+
+/// Check the expressions of a bound plan and of its inputs.
+///
+/// # Errors
+///
+/// Returns `DbError::Planner` if an operator is applied to operands it
+/// cannot take, a predicate or `CASE WHEN` condition is not boolean, or an
+/// INSERT or UPDATE gives a column a value of a type it cannot hold.
+pub fn check_plan(plan: &PhysicalPlan, ctx: &PlanningContext) -> DbResult<()> {
+    output_kinds(plan, ctx).map(|_| ())
+}
+
+/// Whether `left_key = right_key`, over the rows of `left` and of `right`
+/// respectively, compares values of one kind, or numbers with numbers, or
+/// dates and timestamps with each other, so that equal values can be found
+/// by hashing (see [`PhysicalPlan::HashJoin`]). Text compared with a date,
+/// or an expression of unknown kind, can equal values that hash apart.
+pub(crate) fn hashable_equality(
+    left: &PhysicalPlan,
+    right: &PhysicalPlan,
+    ctx: &PlanningContext,
+    left_key: &ResolvedExpr,
+    right_key: &ResolvedExpr,
+) -> bool {
+    let kind = |plan, key| {
+        output_kinds(plan, ctx)
+            .and_then(|columns| expr_kind(key, &columns))
+            .ok()
+            .flatten()
+    };
+    match (kind(left, left_key), kind(right, right_key)) {
+        (Some(left), Some(right)) => {
+            left == right
+                || (left.is_numeric() && right.is_numeric())
+                || (left.is_temporal() && right.is_temporal())
+        }
+        _ => false,
+    }
+}
+
+/// Check a plan, returning the kinds of its output columns.
+fn output_kinds(plan: &PhysicalPlan, ctx: &PlanningContext) -> DbResult<Vec<Option<Kind>>> {
+    match plan {
+        PhysicalPlan::SeqScan { table_id, .. }
+        | PhysicalPlan::PartitionScan { table_id, .. }
+        | PhysicalPlan::SampleScan { table_id, .. }
+        | PhysicalPlan::IndexScan { table_id, .. }
+        | PhysicalPlan::IndexUnion { table_id, .. } => {
+            Ok(table_kinds(ctx.catalog.table_by_id(*table_id)?))
+        }
+        PhysicalPlan::SystemScan { view, .. } => Ok(view
+            .columns()
+            .iter()
+            .map(|column| Some(Kind::of_type(&column.ty)))
+            .collect()),
+        PhysicalPlan::SeriesScan {
+            start, stop, step, ..
+        } => {
+            for (bound, what) in [(start, "start"), (stop, "stop"), (step, "step")] {
+                match expr_kind(bound, &[])? {
+                    Some(kind) if kind != Kind::Int => {
+                        return Err(DbError::Planner(format!(
+                            "generate_series {what} must be INT, not {kind}"
+                        )));
+                    }
+                    _ => {}
+                }
+            }
+            Ok(vec![Some(Kind::Int)])
+        }
+        PhysicalPlan::ValuesScan { rows, schema } => {
+            let mut kinds = vec![None; schema.len()];
+            for row in rows {
+                for ((kind, expr), name) in kinds.iter_mut().zip(row).zip(schema) {
+                    *kind = match (*kind, expr_kind(expr, &[])?) {
+                        (known, None) | (None, known) => known,
+                        (Some(a), Some(b)) if a == b => Some(a),
+                        // Mixed numbers have no single kind
+                        (Some(a), Some(b)) if a.is_numeric() && b.is_numeric() => None,
+                        (Some(a), Some(b)) => {
+                            return Err(DbError::Planner(format!(
+                                "VALUES {name} mixes {a} and {b}"
+                            )));
+                        }
+                    };
+                }
+            }
+            Ok(kinds)
+        }
+        PhysicalPlan::Filter { input, predicate } => {
+            let kinds = output_kinds(input, ctx)?;
+            expect_bool(expr_kind(predicate, &kinds)?, "WHERE clause")?;
+            Ok(kinds)
+        }
+        PhysicalPlan::Project { input, columns } => {
+            let kinds = output_kinds(input, ctx)?;
+            columns
+                .iter()
+                .map(|(_, expr)| expr_kind(expr, &kinds))
+                .collect()
+        }
+        PhysicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+            ..
+        } => {
+            let kinds = output_kinds(input, ctx)?;
+            let mut output = group_by
+                .iter()
+                .map(|key| expr_kind(key, &kinds))
+                .collect::<DbResult<Vec<_>>>()?;
+            for aggregate in aggregates {
+                output.push(aggregate_kind(aggregate, &kinds)?);
+            }
+            Ok(output)
+        }
+        PhysicalPlan::Sort { input, .. } | PhysicalPlan::Limit { input, .. } => {
+            output_kinds(input, ctx)
+        }
+        PhysicalPlan::NestedLoopJoin {
+            left,
+            right,
+            condition,
+            ..
+        } => {
+            let mut kinds = output_kinds(left, ctx)?;
+            kinds.extend(output_kinds(right, ctx)?);
+            expect_bool(expr_kind(condition, &kinds)?, "join condition")?;
+            Ok(kinds)
+        }
+        PhysicalPlan::HashJoin {
+            left, right, keys, ..
+        } => {
+            let left = output_kinds(left, ctx)?;
+            let right = output_kinds(right, ctx)?;
+            for (left_key, right_key) in keys {
+                let left_key = expr_kind(left_key, &left)?;
+                binary_kind(left_key, BinaryOp::Eq, expr_kind(right_key, &right)?)?;
+            }
+            Ok(left.into_iter().chain(right).collect())
+        }
+        PhysicalPlan::SemiJoin {
+            left,
+            right,
+            condition,
+            ..
+        }
+        | PhysicalPlan::AntiJoin {
+            left,
+            right,
+            condition,
+            ..
+        } => {
+            let kinds = output_kinds(left, ctx)?;
+            let mut combined = kinds.clone();
+            combined.extend(output_kinds(right, ctx)?);
+            expect_bool(expr_kind(condition, &combined)?, "subquery condition")?;
+            Ok(kinds)
+        }
+        PhysicalPlan::Insert { table_id, rows } => {
+            let table = ctx.catalog.table_by_id(*table_id)?;
+            for row in rows {
+                for (id, expr) in row.iter().enumerate() {
+                    check_assignment(table, id as ColumnId, expr_kind(expr, &[])?)?;
+                }
+            }
+            Ok(vec![])
+        }
+        PhysicalPlan::Update {
+            table_id,
+            assignments,
+            predicate,
+            source,
+        } => {
+            let table = ctx.catalog.table_by_id(*table_id)?;
+            let kinds = match source {
+                Some(source) => output_kinds(source, ctx)?,
+                None => table_kinds(table),
+            };
+            for (id, expr) in assignments {
+                check_assignment(table, *id, expr_kind(expr, &kinds)?)?;
+            }
+            if let Some(predicate) = predicate {
+                expect_bool(expr_kind(predicate, &kinds)?, "WHERE clause")?;
+            }
+            Ok(vec![])
+        }
+        PhysicalPlan::Delete {
+            table_id,
+            predicate,
+            source,
+        } => {
+            let kinds = match source {
+                Some(source) => output_kinds(source, ctx)?,
+                None => table_kinds(ctx.catalog.table_by_id(*table_id)?),
+            };
+            if let Some(predicate) = predicate {
+                expect_bool(expr_kind(predicate, &kinds)?, "WHERE clause")?;
+            }
+            Ok(vec![])
+        }
+    }
+}
+
+fn table_kinds(table: &TableMeta) -> Vec<Option<Kind>> {
+    table
+        .columns()
+        .iter()
+        .map(|column| Some(Kind::of_type(&column.ty)))
+        .collect()
+}
+
+/// Check an expression over rows whose columns have `columns` kinds,
+/// returning the kind of its values if it is known.
+fn expr_kind(expr: &ResolvedExpr, columns: &[Option<Kind>]) -> DbResult<Option<Kind>> {
+    match expr {
+        ResolvedExpr::Literal(value) => Ok(Kind::of_value(value)),
+        ResolvedExpr::Column(id) => Ok(columns.get(*id as usize).copied().flatten()),
+        ResolvedExpr::Unary {
+            op: UnaryOp::Not,
+            expr,
+        } => {
+            expect_bool(expr_kind(expr, columns)?, "argument of NOT")?;
+            Ok(Some(Kind::Bool))
+        }
+        ResolvedExpr::Binary { left, op, right } => {
+            let left = expr_kind(left, columns)?;
+            let right = expr_kind(right, columns)?;
+            binary_kind(left, *op, right)
+        }
+        ResolvedExpr::Function { args, .. } => {
+            for arg in args {
+                expr_kind(arg, columns)?;
+            }
+            Ok(None)
+        }
+        ResolvedExpr::Cast { expr, ty } => {
+            expr_kind(expr, columns)?;
+            Ok(Some(Kind::of_type(ty)))
+        }
+        ResolvedExpr::IsNull { expr, .. } => {
+            expr_kind(expr, columns)?;
+            Ok(Some(Kind::Bool))
+        }
+        ResolvedExpr::Case {
+            operand,
+            branches,
+            else_result,
+        } => {
+            let operand = operand
+                .as_deref()
+                .map(|operand| expr_kind(operand, columns))
+                .transpose()?;
+            let mut result = None;
+            for (when, then) in branches {
+                let when = expr_kind(when, columns)?;
+                match operand {
+                    Some(operand) => {
+                        binary_kind(operand, BinaryOp::Eq, when)?;
+                    }
+                    None => expect_bool(when, "CASE WHEN condition")?,
+                }
+                result = result.or(expr_kind(then, columns)?);
+            }
+            if let Some(else_result) = else_result {
+                result = result.or(expr_kind(else_result, columns)?);
+            }
+            Ok(result)
+        }
+    }
+}
+
+/// Check an aggregate call over rows whose columns have `columns` kinds,
+/// returning the kind of its result if it is known. `SUM` and `AVG` take
+/// numbers; `AVG` of integers is a float.
+fn aggregate_kind(
+    aggregate: &ResolvedAggregate,
+    columns: &[Option<Kind>],
+) -> DbResult<Option<Kind>> {
+    let arg = match &aggregate.arg {
+        Some(arg) => expr_kind(arg, columns)?,
+        None => None,
+    };
+    match (aggregate.func, arg) {
+        (AggregateFunc::Count, _) => Ok(Some(Kind::Int)),
+        (AggregateFunc::Sum | AggregateFunc::Avg, Some(kind)) if !kind.is_numeric() => {
+            Err(DbError::Planner(format!(
+                "{} cannot be applied to {kind}",
+                aggregate.func.name().to_uppercase()
+            )))
+        }
+        (AggregateFunc::Avg, Some(Kind::Int)) => Ok(Some(Kind::Float)),
+        (_, arg) => Ok(arg),
+    }
+}
+
+/// The kind of `left op right`, if the operands' kinds allow it.
+fn binary_kind(left: Option<Kind>, op: BinaryOp, right: Option<Kind>) -> DbResult<Option<Kind>> {
+    match op {
+        BinaryOp::And | BinaryOp::Or => {
+            expect_bool(left, &format!("left operand of {op}"))?;
+            expect_bool(right, &format!("right operand of {op}"))?;
+            Ok(Some(Kind::Bool))
+        }
+        op if op.is_arithmetic() => {
+            let (Some(l), Some(r)) = (left, right) else {
+                // NULL arithmetic gives NULL; keep whichever kind is known
+                return Ok(left.or(right).filter(|kind| kind.is_numeric()));
+            };
+            if !l.is_numeric() || !r.is_numeric() {
+                return Err(mismatch(l, op, r));
+            }
+            Ok(Some(if l == Kind::Float || r == Kind::Float {
+                Kind::Float
+            } else if l == Kind::Decimal || r == Kind::Decimal {
+                Kind::Decimal
+            } else {
+                Kind::Int
+            }))
+        }
+        _ => {
+            if let (Some(l), Some(r)) = (left, right)
+                && !l.comparable(r)
+            {
+                return Err(mismatch(l, op, r));
+            }
+            Ok(Some(Kind::Bool))
+        }
+    }
+}
+
+fn mismatch(left: Kind, op: BinaryOp, right: Kind) -> DbError {
+    DbError::Planner(format!(
+        "operator {op} cannot be applied to {left} and {right}"
+    ))
+}
+
+fn expect_bool(kind: Option<Kind>, what: &str) -> DbResult<()> {
+    match kind {
+        Some(kind) if kind != Kind::Bool => {
+            Err(DbError::Planner(format!("{what} must be BOOL, not {kind}")))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_assignment(table: &TableMeta, id: ColumnId, kind: Option<Kind>) -> DbResult<()> {
+    let column = &table.columns()[id as usize];
+    match kind {
+        Some(kind) if !kind.storable_in(&column.ty) => Err(DbError::Planner(format!(
+            "column '{}' of table '{}' has type {} and cannot hold {kind} values",
+            column.name, table.name, column.ty
+        ))),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,224 @@
+//! Golden-file tests of the plans chosen for a corpus of queries.
+//!
+//! Each file in `tests/golden/` lists queries and the [`PlanShape`] planned
+//! for each against the catalog built by [`catalog`]. A case is the query,
+//! a `----` line and the expected shape, with cases separated by blank
+//! lines; `#` lines before a query describe it. A change to the planner that
+//! alters any shape fails this test, so access path changes show up in the
+//! diff of the golden files.
+//!
+//! After an intended change, run with `UPDATE_GOLDEN=1` to rewrite the
+//! files with the new shapes, then review the diff.
+
+use catalog::{Catalog, Column, IndexKind, PartitionBound, PartitionMethod, TableStatistics};
+use parser::parse_sql;
+use planner::{PlanShape, Planner, PlanningContext};
+use std::fs;
+use std::path::{Path, PathBuf};
+use types::{SqlType, Value};
+
+const SEPARATOR: &str = "----";
+
+/// Tables the corpus plans against.
+///
+/// `users` and `orders` are analyzed, so their plans are chosen by
+/// estimated cost; `events` is not, and is partitioned by `day`.
+fn catalog() -> Catalog {
+    let mut catalog = Catalog::new();
+    let users = catalog
+        .create_table(
+            "users",
+            vec![
+                Column::new("id", SqlType::Int),
+                Column::new("name", SqlType::Text),
+                Column::new("age", SqlType::Int),
+            ],
+            Some(vec![0]),
+        )
+        .unwrap();
+    let orders = catalog
+        .create_table(
+            "orders",
+            vec![
+                Column::new("id", SqlType::Int),
+                Column::new("user_id", SqlType::Int),
+                Column::new("total", SqlType::Int),
+            ],
+            Some(vec![0]),
+        )
+        .unwrap();
+    catalog
+        .create_table(
+            "events",
+            vec![
+                Column::new("id", SqlType::Int),
+                Column::new("day", SqlType::Int),
+                Column::new("kind", SqlType::Text),
+            ],
+            None,
+        )
+        .unwrap();
+    catalog
+        .partition_table(
+            "events",
+            PartitionMethod::Range,
+            "day",
+            vec![
+                (
+                    "early".into(),
+                    PartitionBound::LessThan(Some(Value::Int(10))),
+                ),
+                ("late".into(), PartitionBound::LessThan(None)),
+            ],
+        )
+        .unwrap();
+    for (table, name, column, kind) in [
+        ("users", "idx_users_id", "id", IndexKind::BTree),
+        ("users", "idx_users_age", "age", IndexKind::BTree),
+        ("orders", "idx_orders_user", "user_id", IndexKind::Hash),
+    ] {
+        catalog
+            .create_index()
+            .table_name(table)
+            .index_name(name)
+            .columns(&[column])
+            .kind(kind)
+            .call()
+            .unwrap();
+    }
+
+    let rows: Vec<Vec<Value>> = (0..1000)
+        .map(|i| {
+            vec![
+                Value::Int(i),
+                Value::Text(format!("user{i}")),
+                Value::Int(i % 50),
+            ]
+        })
+        .collect();
+    let stats = TableStatistics::from_rows(3, rows.iter().map(Vec::as_slice)).with_page_count(200);
+    catalog.set_table_statistics(users, stats, 0).unwrap();
+    let rows: Vec<Vec<Value>> = (0..50)
+        .map(|i| vec![Value::Int(i), Value::Int(i * 7), Value::Int(i * 100)])
+        .collect();
+    let stats = TableStatistics::from_rows(3, rows.iter().map(Vec::as_slice)).with_page_count(2);
+    catalog.set_table_statistics(orders, stats, 0).unwrap();
+    catalog
+}
+
+/// A query from a golden file, with the comment lines before it.
+struct Case {
+    comments: Vec<String>,
+    sql: String,
+    expected: String,
+}
+
+fn parse_cases(text: &str) -> Vec<Case> {
+    text.split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .map(|block| {
+            let (query, expected) = block.split_once(SEPARATOR).unwrap_or((block, ""));
+            let (comments, sql): (Vec<&str>, Vec<&str>) =
+                query.lines().partition(|line| line.starts_with('#'));
+            Case {
+                comments: comments.into_iter().map(str::to_string).collect(),
+                sql: sql.join("\n").trim().to_string(),
+                expected: expected.trim_matches('\n').to_string(),
+            }
+        })
+        .collect()
+}
+
+fn render_cases(cases: &[Case]) -> String {
+    cases
+        .iter()
+        .map(|case| {
+            let mut block = case.comments.join("\n");
+            if !block.is_empty() {
+                block.push('\n');
+            }
+            format!("{block}{}\n{SEPARATOR}\n{}\n", case.sql, case.expected)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn plan_shape(catalog: &Catalog, sql: &str) -> String {
+    let stmt = parse_sql(sql)
+        .unwrap_or_else(|e| panic!("cannot parse {sql:?}: {e}"))
+        .remove(0);
+    let mut ctx = PlanningContext::new(catalog);
+    let plan = Planner::plan(stmt, &mut ctx).unwrap_or_else(|e| panic!("cannot plan {sql:?}: {e}"));
+    let shape = PlanShape::of(&plan, catalog).unwrap();
+    shape.to_string().trim_end().to_string()
+}
+
+fn golden_files() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "plans"))
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn plans_match_golden_files() {
+    let catalog = catalog();
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let mut mismatches = Vec::new();
+
+    for path in golden_files() {
+        let mut cases = parse_cases(&fs::read_to_string(&path).unwrap());
+        for case in &mut cases {
+            let actual = plan_shape(&catalog, &case.sql);
+            if actual != case.expected {
+                mismatches.push(format!(
+                    "{}: {}\n--- expected\n{}\n+++ actual\n{actual}",
+                    path.display(),
+                    case.sql,
+                    case.expected
+                ));
+                case.expected = actual;
+            }
+        }
+        if update {
+            fs::write(&path, render_cases(&cases)).unwrap();
+        }
+    }
+
+    assert!(
+        update || mismatches.is_empty(),
+        "{} plan(s) changed; rerun with UPDATE_GOLDEN=1 to accept them\n\n{}",
+        mismatches.len(),
+        mismatches.join("\n\n")
+    );
+}
+
+#[test]
+fn golden_files_round_trip() {
+    for path in golden_files() {
+        let text = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            render_cases(&parse_cases(&text)),
+            text,
+            "{}",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn plan_shapes_serialize() {
+    let catalog = catalog();
+    let sql = "SELECT u.name, o.total FROM users u JOIN orders o ON o.user_id = u.id";
+    let stmt = parse_sql(sql).unwrap().remove(0);
+    let plan = Planner::plan(stmt, &mut PlanningContext::new(&catalog)).unwrap();
+    let shape = PlanShape::of(&plan, &catalog).unwrap();
+
+    let json = serde_json::to_string(&shape).unwrap();
+    assert_eq!(serde_json::from_str::<PlanShape>(&json).unwrap(), shape);
+    assert!(json.contains(r#""table":"orders""#), "{json}");
+}
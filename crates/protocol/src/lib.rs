@@ -49,6 +49,14 @@ pub enum ErrorCode {
     IoError,
     /// Unknown error
     Unknown,
+    /// A per-session resource limit was exceeded
+    ResourceExhausted,
+    /// Every buffer pool page stayed pinned for the whole wait
+    BufferPoolExhausted,
+    /// Data on disk failed an integrity check
+    Corruption,
+    /// The disk is full and the database is read-only until space is freed
+    DiskFull,
 }
 
 /// Frame format: [u32 length (little-endian)][bincode payload]
@@ -0,0 +1,850 @@
+//! Administrative HTTP endpoints.
+//!
+//! These endpoints let operators inspect and manage a node without a SQL client.
+//! They are mounted on the Raft HTTP server only when an admin token is configured,
+//! and every request must carry an `Authorization: Bearer <token>` header.
+//!
+//! - `GET /admin/status` - Raft role, term, log indexes, and membership
+//! - `GET /admin/tables` - Tables registered in the catalog
+//! - `POST /admin/checkpoint` - Flush buffered pages and truncate the WAL
+//! - `POST /admin/snapshot` - Ask Raft to build a snapshot now
+//! - `GET /admin/membership` - Current voters and learners
+//! - `POST /admin/membership` - Replace the voter set (`{"voters": [1, 2, 3]}`)
+//! - `GET /admin/checksums` - Per-table content checksums on this node
+//! - `GET /admin/consistency` - Compare checksums across every known node
+//! - `GET /admin/settings` - Settings that can change without a restart
+//! - `POST /admin/settings` - Change settings (`{"durability": "relaxed"}`,
+//!   `null` restores a setting's startup value)
+
+use crate::http_server::RaftHttpState;
+use crate::NodeId;
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use catalog::Catalog;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Handler invoked by `POST /admin/checkpoint`.
+///
+/// The database supplies this callback because the Raft crate does not own the
+/// pager or WAL. It runs on a blocking thread and returns a message on failure.
+pub type CheckpointHandler = Arc<dyn Fn() -> Result<(), String> + Send + Sync>;
+
+/// Handler invoked by `GET /admin/checksums`.
+///
+/// Like [`CheckpointHandler`], the database supplies this because it owns the
+/// table storage. It runs on a blocking thread.
+pub type ChecksumHandler = Arc<dyn Fn() -> Result<Vec<TableChecksum>, String> + Send + Sync>;
+
+/// Handler invoked by `GET` and `POST /admin/settings`.
+///
+/// Applies each `(name, value)` change in order, where `None` restores the
+/// setting's startup value, and returns every setting's value afterwards.
+/// `GET` passes no changes. The database supplies this because it owns the
+/// settings. It runs on a blocking thread and returns a message on failure.
+pub type SettingsHandler = Arc<
+    dyn Fn(&[(String, Option<String>)]) -> Result<BTreeMap<String, String>, String> + Send + Sync,
+>;
+
+/// Content checksum of one table on one node.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableChecksum {
+    pub table: String,
+    pub rows: u64,
+    /// CRC32 of the rows in primary key order.
+    pub checksum: u32,
+}
+
+/// A table whose checksum is not the same on every node that reported.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Divergence {
+    pub table: String,
+    /// Each reporting node's checksum, or `None` where the table is missing.
+    pub nodes: BTreeMap<NodeId, Option<TableChecksum>>,
+}
+
+/// Find the tables whose checksums differ between the given nodes.
+///
+/// A table that exists on some nodes but not others also diverges.
+pub fn compare_checksums(reports: &BTreeMap<NodeId, Vec<TableChecksum>>) -> Vec<Divergence> {
+    let tables: BTreeSet<&str> = reports
+        .values()
+        .flatten()
+        .map(|t| t.table.as_str())
+        .collect();
+    tables
+        .into_iter()
+        .filter_map(|table| {
+            let nodes: BTreeMap<NodeId, Option<TableChecksum>> = reports
+                .iter()
+                .map(|(node, checksums)| {
+                    let found = checksums.iter().find(|t| t.table == table).cloned();
+                    (*node, found)
+                })
+                .collect();
+            let mut values = nodes.values();
+            let first = values.next()?;
+            values.any(|v| v != first).then(|| Divergence {
+                table: table.to_string(),
+                nodes,
+            })
+        })
+        .collect()
+}
+
+/// Configuration for the admin endpoints.
+#[derive(Clone)]
+pub struct AdminConfig {
+    /// Bearer token required on every admin request.
+    pub token: String,
+    /// Catalog used to answer `/admin/tables`.
+    pub catalog: Arc<RwLock<Catalog>>,
+    /// Callback that performs a storage checkpoint.
+    pub checkpoint: CheckpointHandler,
+    /// Callback that checksums every table (None disables the checks).
+    pub checksums: Option<ChecksumHandler>,
+    /// Callback that reads and changes settings (None disables them).
+    pub settings: Option<SettingsHandler>,
+    /// Base URLs of the other nodes, queried by `/admin/consistency`.
+    pub peers: BTreeMap<NodeId, String>,
+}
+
+impl AdminConfig {
+    /// Create an admin configuration.
+    pub fn new(
+        token: impl Into<String>,
+        catalog: Arc<RwLock<Catalog>>,
+        checkpoint: CheckpointHandler,
+    ) -> Self {
+        Self {
+            token: token.into(),
+            catalog,
+            checkpoint,
+            checksums: None,
+            settings: None,
+            peers: BTreeMap::new(),
+        }
+    }
+
+    /// Serve `/admin/checksums` and `/admin/consistency` using `handler`.
+    pub fn with_checksums(mut self, handler: ChecksumHandler) -> Self {
+        self.checksums = Some(handler);
+        self
+    }
+
+    /// Serve `/admin/settings` using `handler`.
+    pub fn with_settings(mut self, handler: SettingsHandler) -> Self {
+        self.settings = Some(handler);
+        self
+    }
+
+    /// Set the base URLs (e.g. `http://127.0.0.1:5002`) of the other nodes.
+    pub fn with_peers(mut self, peers: BTreeMap<NodeId, String>) -> Self {
+        self.peers = peers;
+        self
+    }
+}
+
+/// Request body for `POST /admin/membership`.
+#[derive(Debug, Deserialize)]
+pub struct MembershipRequest {
+    /// The complete set of voters after the change.
+    pub voters: BTreeSet<NodeId>,
+}
+
+/// Create the router for the admin endpoints.
+pub fn admin_router() -> Router<RaftHttpState> {
+    Router::new()
+        .route("/admin/status", get(handle_status))
+        .route("/admin/tables", get(handle_tables))
+        .route("/admin/checkpoint", post(handle_checkpoint))
+        .route("/admin/snapshot", post(handle_snapshot))
+        .route(
+            "/admin/membership",
+            get(handle_membership).post(handle_change_membership),
+        )
+        .route("/admin/checksums", get(handle_checksums))
+        .route("/admin/consistency", get(handle_consistency))
+        .route(
+            "/admin/settings",
+            get(handle_settings).post(handle_change_settings),
+        )
+}
+
+/// Check the bearer token and return the admin configuration.
+// ring has deprecated its constant-time comparison without a replacement
+#[allow(deprecated)]
+fn authorize<'a>(
+    state: &'a RaftHttpState,
+    headers: &HeaderMap,
+) -> Result<&'a AdminConfig, (StatusCode, &'static str)> {
+    let admin = state
+        .admin
+        .as_ref()
+        .ok_or((StatusCode::NOT_FOUND, "admin endpoints are disabled"))?;
+
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match provided {
+        // Compare in constant time so the response does not leak how much of
+        // the token matched
+        Some(token) => {
+            ring::constant_time::verify_slices_are_equal(token.as_bytes(), admin.token.as_bytes())
+                .map(|()| admin)
+                .map_err(|_| (StatusCode::FORBIDDEN, "invalid admin token"))
+        }
+        None => Err((StatusCode::UNAUTHORIZED, "missing bearer token")),
+    }
+}
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    let body = serde_json::json!({ "error": message.into() });
+    (status, Json(body)).into_response()
+}
+
+fn membership_json(state: &RaftHttpState) -> serde_json::Value {
+    let metrics = state.raft.metrics().borrow().clone();
+    let membership = metrics.membership_config.membership();
+    serde_json::json!({
+        "log_index": metrics.membership_config.log_id().map(|l| l.index),
+        "voters": membership.voter_ids().collect::<Vec<_>>(),
+        "learners": membership.learner_ids().collect::<Vec<_>>(),
+    })
+}
+
+/// Report the node's Raft status.
+async fn handle_status(State(state): State<RaftHttpState>, headers: HeaderMap) -> Response {
+    if let Err((status, msg)) = authorize(&state, &headers) {
+        return error_response(status, msg);
+    }
+
+    let metrics = state.raft.metrics().borrow().clone();
+    let status = serde_json::json!({
+        "node_id": metrics.id,
+        "state": format!("{:?}", metrics.state),
+        "current_leader": metrics.current_leader,
+        "current_term": metrics.current_term,
+        "last_log_index": metrics.last_log_index,
+        "last_applied": metrics.last_applied.map(|l| l.index),
+        "snapshot_index": metrics.snapshot.map(|l| l.index),
+        "membership": membership_json(&state),
+    });
+    (StatusCode::OK, Json(status)).into_response()
+}
+
+/// List the tables in the catalog.
+async fn handle_tables(State(state): State<RaftHttpState>, headers: HeaderMap) -> Response {
+    let admin = match authorize(&state, &headers) {
+        Ok(admin) => admin,
+        Err((status, msg)) => return error_response(status, msg),
+    };
+
+    let catalog = admin.catalog.read().await;
+    let tables: Vec<_> = catalog
+        .table_summaries()
+        .into_iter()
+        .map(|t| {
+            serde_json::json!({
+                "id": t.id.0,
+                "name": t.name,
+                "columns": t.column_count,
+                "indexes": t.index_count,
+            })
+        })
+        .collect();
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "tables": tables })),
+    )
+        .into_response()
+}
+
+/// Run a storage checkpoint on this node.
+async fn handle_checkpoint(State(state): State<RaftHttpState>, headers: HeaderMap) -> Response {
+    let admin = match authorize(&state, &headers) {
+        Ok(admin) => admin,
+        Err((status, msg)) => return error_response(status, msg),
+    };
+
+    let checkpoint = admin.checkpoint.clone();
+    match tokio::task::spawn_blocking(move || checkpoint()).await {
+        Ok(Ok(())) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "status": "checkpointed" })),
+        )
+            .into_response(),
+        Ok(Err(e)) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("checkpoint failed: {}", e),
+        ),
+        Err(e) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("checkpoint task panicked: {}", e),
+        ),
+    }
+}
+
+/// Trigger a Raft snapshot on this node.
+async fn handle_snapshot(State(state): State<RaftHttpState>, headers: HeaderMap) -> Response {
+    if let Err((status, msg)) = authorize(&state, &headers) {
+        return error_response(status, msg);
+    }
+
+    match state.raft.trigger().snapshot().await {
+        Ok(()) => (
+            StatusCode::ACCEPTED,
+            Json(serde_json::json!({ "status": "snapshot triggered" })),
+        )
+            .into_response(),
+        Err(e) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("snapshot failed: {}", e),
+        ),
+    }
+}
+
+/// Report the current cluster membership.
+async fn handle_membership(State(state): State<RaftHttpState>, headers: HeaderMap) -> Response {
+    if let Err((status, msg)) = authorize(&state, &headers) {
+        return error_response(status, msg);
+    }
+    (StatusCode::OK, Json(membership_json(&state))).into_response()
+}
+
+/// Replace the voter set. Must be sent to the leader.
+async fn handle_change_membership(
+    State(state): State<RaftHttpState>,
+    headers: HeaderMap,
+    Json(req): Json<MembershipRequest>,
+) -> Response {
+    if let Err((status, msg)) = authorize(&state, &headers) {
+        return error_response(status, msg);
+    }
+    if req.voters.is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, "voters must not be empty");
+    }
+
+    match state.raft.change_membership(req.voters, false).await {
+        Ok(_) => (StatusCode::OK, Json(membership_json(&state))).into_response(),
+        Err(e) => error_response(
+            StatusCode::CONFLICT,
+            format!("membership change failed: {}", e),
+        ),
+    }
+}
+
+/// Checksum every table on this node.
+async fn local_checksums(admin: &AdminConfig) -> Result<Vec<TableChecksum>, String> {
+    let handler = admin
+        .checksums
+        .clone()
+        .ok_or_else(|| "checksums are not available on this node".to_string())?;
+    tokio::task::spawn_blocking(move || handler())
+        .await
+        .map_err(|e| format!("checksum task panicked: {}", e))?
+}
+
+/// Fetch a peer's table checksums and the log index they reflect.
+async fn peer_checksums(
+    client: &reqwest::Client,
+    base_url: &str,
+    token: &str,
+) -> Result<(Option<u64>, Vec<TableChecksum>), String> {
+    #[derive(Deserialize)]
+    struct Report {
+        last_applied: Option<u64>,
+        tables: Vec<TableChecksum>,
+    }
+
+    let resp = client
+        .get(format!("{}/admin/checksums", base_url))
+        .bearer_auth(token)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("HTTP {}: {}", status, body));
+    }
+    let report: Report = resp.json().await.map_err(|e| e.to_string())?;
+    Ok((report.last_applied, report.tables))
+}
+
+/// Report per-table checksums on this node.
+///
+/// `last_applied` is read before the tables are scanned, so the checksums
+/// cover at least every entry up to that index.
+async fn handle_checksums(State(state): State<RaftHttpState>, headers: HeaderMap) -> Response {
+    let admin = match authorize(&state, &headers) {
+        Ok(admin) => admin,
+        Err((status, msg)) => return error_response(status, msg),
+    };
+
+    let metrics = state.raft.metrics().borrow().clone();
+    match local_checksums(admin).await {
+        Ok(tables) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "node_id": metrics.id,
+                "last_applied": metrics.last_applied.map(|l| l.index),
+                "tables": tables,
+            })),
+        )
+            .into_response(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// Compare table checksums between this node and every peer.
+///
+/// Nodes that have applied different amounts of the log can differ without
+/// a bug, so each node's `last_applied` index is reported alongside the
+/// result; a divergence between nodes at the same index is a real one.
+/// Unreachable peers are listed with their error and left out of the
+/// comparison, and the cluster is then not reported consistent.
+async fn handle_consistency(State(state): State<RaftHttpState>, headers: HeaderMap) -> Response {
+    let admin = match authorize(&state, &headers) {
+        Ok(admin) => admin,
+        Err((status, msg)) => return error_response(status, msg),
+    };
+
+    let metrics = state.raft.metrics().borrow().clone();
+    let local = match local_checksums(admin).await {
+        Ok(tables) => tables,
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+
+    let mut reports = BTreeMap::new();
+    let mut nodes = vec![serde_json::json!({
+        "node_id": metrics.id,
+        "last_applied": metrics.last_applied.map(|l| l.index),
+    })];
+    reports.insert(metrics.id, local);
+
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    for (&node_id, base_url) in admin.peers.iter().filter(|(id, _)| **id != metrics.id) {
+        match peer_checksums(&client, base_url, &admin.token).await {
+            Ok((last_applied, tables)) => {
+                nodes.push(serde_json::json!({
+                    "node_id": node_id,
+                    "last_applied": last_applied,
+                }));
+                reports.insert(node_id, tables);
+            }
+            Err(e) => nodes.push(serde_json::json!({
+                "node_id": node_id,
+                "error": e,
+            })),
+        }
+    }
+
+    let divergent = compare_checksums(&reports);
+    let all_reported = reports.len() == nodes.len();
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "consistent": all_reported && divergent.is_empty(),
+            "nodes": nodes,
+            "divergent": divergent,
+        })),
+    )
+        .into_response()
+}
+
+/// Run the settings handler with `changes` and respond with the settings.
+async fn apply_settings(admin: &AdminConfig, changes: Vec<(String, Option<String>)>) -> Response {
+    let Some(handler) = admin.settings.clone() else {
+        return error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "settings are not available on this node",
+        );
+    };
+    match tokio::task::spawn_blocking(move || handler(&changes)).await {
+        Ok(Ok(settings)) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "settings": settings })),
+        )
+            .into_response(),
+        Ok(Err(e)) => error_response(StatusCode::BAD_REQUEST, e),
+        Err(e) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("settings task panicked: {}", e),
+        ),
+    }
+}
+
+/// Report the settings that can change without a restart.
+async fn handle_settings(State(state): State<RaftHttpState>, headers: HeaderMap) -> Response {
+    match authorize(&state, &headers) {
+        Ok(admin) => apply_settings(admin, Vec::new()).await,
+        Err((status, msg)) => error_response(status, msg),
+    }
+}
+
+/// Change settings on this node. Values may be strings or numbers; `null`
+/// restores the startup value. Changes before a rejected one stay applied.
+async fn handle_change_settings(
+    State(state): State<RaftHttpState>,
+    headers: HeaderMap,
+    Json(req): Json<BTreeMap<String, serde_json::Value>>,
+) -> Response {
+    let admin = match authorize(&state, &headers) {
+        Ok(admin) => admin,
+        Err((status, msg)) => return error_response(status, msg),
+    };
+
+    let mut changes = Vec::with_capacity(req.len());
+    for (name, value) in req {
+        let value = match value {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(value) => Some(value),
+            serde_json::Value::Number(value) => Some(value.to_string()),
+            _ => {
+                return error_response(
+                    StatusCode::BAD_REQUEST,
+                    format!("setting '{}' must be a string, number or null", name),
+                )
+            }
+        };
+        changes.push((name, value));
+    }
+    apply_settings(admin, changes).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_server::create_router;
+    use crate::{NetworkFactory, TypeConfig};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use openraft::storage::Adaptor;
+    use openraft::{BasicNode, Raft};
+    use std::collections::BTreeMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    const TOKEN: &str = "secret";
+
+    async fn single_node_raft() -> Arc<crate::RaftNode> {
+        let store = crate::create_mem_storage(None);
+        let (log_store, state_machine) =
+            Adaptor::<TypeConfig, Arc<crate::MemRaftStore>>::new(store);
+        let config = Arc::new(openraft::Config {
+            cluster_name: "test-admin".to_string(),
+            election_timeout_min: 150,
+            election_timeout_max: 300,
+            heartbeat_interval: 50,
+            ..Default::default()
+        });
+        let raft =
+            Raft::<TypeConfig>::new(1, config, NetworkFactory::new(1), log_store, state_machine)
+                .await
+                .unwrap();
+        let mut members = BTreeMap::new();
+        members.insert(1u64, BasicNode::default());
+        raft.initialize(members).await.unwrap();
+        raft.wait(Some(Duration::from_secs(2)))
+            .current_leader(1, "leader elected")
+            .await
+            .unwrap();
+        Arc::new(raft)
+    }
+
+    async fn admin_app(checkpoints: Arc<AtomicUsize>) -> Router {
+        create_router(admin_state(checkpoints).await)
+    }
+
+    async fn admin_state(checkpoints: Arc<AtomicUsize>) -> RaftHttpState {
+        let mut catalog = Catalog::new();
+        catalog
+            .create_table(
+                "users",
+                vec![catalog::Column::new("id", types::SqlType::Int)],
+                None,
+            )
+            .unwrap();
+        let checkpoint: CheckpointHandler = Arc::new(move || {
+            checkpoints.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        });
+        let admin = AdminConfig::new(TOKEN, Arc::new(RwLock::new(catalog)), checkpoint);
+        RaftHttpState::new(single_node_raft().await).with_admin(admin)
+    }
+
+    fn checksum(table: &str, rows: u64, checksum: u32) -> TableChecksum {
+        TableChecksum {
+            table: table.to_string(),
+            rows,
+            checksum,
+        }
+    }
+
+    /// Admin state whose checksum handler reports `tables`.
+    async fn checksum_state(tables: Vec<TableChecksum>) -> RaftHttpState {
+        let mut state = admin_state(Arc::new(AtomicUsize::new(0))).await;
+        let admin = state.admin.take().unwrap();
+        state.with_admin(admin.with_checksums(Arc::new(move || Ok(tables.clone()))))
+    }
+
+    fn request(method: &str, uri: &str, token: Option<&str>) -> Request<Body> {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    async fn json_body(resp: Response) -> serde_json::Value {
+        let bytes = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_and_invalid_tokens() {
+        let app = admin_app(Arc::new(AtomicUsize::new(0))).await;
+
+        let resp = app
+            .clone()
+            .oneshot(request("GET", "/admin/status", None))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let resp = app
+            .clone()
+            .oneshot(request("GET", "/admin/status", Some("wrong")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let resp = app
+            .oneshot(request("GET", "/admin/status", Some(&TOKEN[..3])))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn admin_routes_absent_without_config() {
+        let app = create_router(RaftHttpState::new(single_node_raft().await));
+        let resp = app
+            .oneshot(request("GET", "/admin/status", Some(TOKEN)))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn status_tables_and_membership() {
+        let app = admin_app(Arc::new(AtomicUsize::new(0))).await;
+
+        let resp = app
+            .clone()
+            .oneshot(request("GET", "/admin/status", Some(TOKEN)))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let status = json_body(resp).await;
+        assert_eq!(status["node_id"], 1);
+        assert_eq!(status["current_leader"], 1);
+
+        let resp = app
+            .clone()
+            .oneshot(request("GET", "/admin/tables", Some(TOKEN)))
+            .await
+            .unwrap();
+        let tables = json_body(resp).await;
+        assert_eq!(tables["tables"][0]["name"], "users");
+        assert_eq!(tables["tables"][0]["columns"], 1);
+
+        let resp = app
+            .oneshot(request("GET", "/admin/membership", Some(TOKEN)))
+            .await
+            .unwrap();
+        let membership = json_body(resp).await;
+        assert_eq!(membership["voters"], serde_json::json!([1]));
+    }
+
+    #[tokio::test]
+    async fn checkpoint_and_snapshot() {
+        let checkpoints = Arc::new(AtomicUsize::new(0));
+        let app = admin_app(checkpoints.clone()).await;
+
+        let resp = app
+            .clone()
+            .oneshot(request("POST", "/admin/checkpoint", Some(TOKEN)))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(checkpoints.load(Ordering::SeqCst), 1);
+
+        let resp = app
+            .oneshot(request("POST", "/admin/snapshot", Some(TOKEN)))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::ACCEPTED);
+    }
+
+    #[test]
+    fn compare_checksums_reports_differing_and_missing_tables() {
+        let mut reports = BTreeMap::new();
+        reports.insert(1, vec![checksum("a", 2, 10), checksum("b", 1, 20)]);
+        reports.insert(2, vec![checksum("a", 2, 10), checksum("b", 1, 21)]);
+        reports.insert(3, vec![checksum("a", 2, 10)]);
+
+        let divergent = compare_checksums(&reports);
+        assert_eq!(divergent.len(), 1);
+        assert_eq!(divergent[0].table, "b");
+        assert_eq!(divergent[0].nodes[&1], Some(checksum("b", 1, 20)));
+        assert_eq!(divergent[0].nodes[&2], Some(checksum("b", 1, 21)));
+        assert_eq!(divergent[0].nodes[&3], None);
+
+        reports.remove(&2);
+        reports.remove(&3);
+        assert!(compare_checksums(&reports).is_empty());
+    }
+
+    #[tokio::test]
+    async fn checksums_require_a_handler() {
+        let app = admin_app(Arc::new(AtomicUsize::new(0))).await;
+        let resp = app
+            .oneshot(request("GET", "/admin/checksums", Some(TOKEN)))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = json_body(resp).await;
+        assert_eq!(body["error"], "checksums are not available on this node");
+    }
+
+    #[tokio::test]
+    async fn settings_are_read_and_changed_through_the_handler() {
+        let mut state = admin_state(Arc::new(AtomicUsize::new(0))).await;
+        let admin = state.admin.take().unwrap();
+        let values = Arc::new(std::sync::Mutex::new(BTreeMap::from([(
+            "buffer_pages".to_string(),
+            "64".to_string(),
+        )])));
+        let handler: SettingsHandler = {
+            let values = values.clone();
+            Arc::new(move |changes| {
+                let mut values = values.lock().unwrap();
+                for (name, value) in changes {
+                    let value = value.clone().ok_or("no default")?;
+                    values.insert(name.clone(), value);
+                }
+                Ok(values.clone())
+            })
+        };
+        let app = create_router(state.with_admin(admin.with_settings(handler)));
+
+        let resp = app
+            .clone()
+            .oneshot(request("GET", "/admin/settings", Some(TOKEN)))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp).await["settings"]["buffer_pages"], "64");
+
+        let change = |body: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/admin/settings")
+                .header(header::AUTHORIZATION, format!("Bearer {}", TOKEN))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let resp = app
+            .clone()
+            .oneshot(change(r#"{"buffer_pages": 128}"#))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp).await["settings"]["buffer_pages"], "128");
+
+        let resp = app
+            .clone()
+            .oneshot(change(r#"{"buffer_pages": null}"#))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(json_body(resp).await["error"], "no default");
+
+        let resp = app.oneshot(change(r#"{"durability": [1]}"#)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(values.lock().unwrap()["buffer_pages"], "128");
+    }
+
+    #[tokio::test]
+    async fn consistency_compares_with_peers() {
+        // A peer serving the same admin endpoints on a real listener
+        let peer_state = checksum_state(vec![checksum("users", 3, 7)]).await;
+        let mut peer = crate::start_server("127.0.0.1:0".parse().unwrap(), peer_state)
+            .await
+            .unwrap();
+        let peer_url = format!("http://{}", peer.local_addr());
+
+        let consistency = |tables: Vec<TableChecksum>, peers: BTreeMap<NodeId, String>| async {
+            let mut state = checksum_state(tables).await;
+            let admin = state.admin.take().unwrap();
+            let app = create_router(state.with_admin(admin.with_peers(peers)));
+            let resp = app
+                .oneshot(request("GET", "/admin/consistency", Some(TOKEN)))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            json_body(resp).await
+        };
+
+        let report = consistency(
+            vec![checksum("users", 3, 7)],
+            BTreeMap::from([(2, peer_url.clone())]),
+        )
+        .await;
+        assert_eq!(report["consistent"], true);
+        assert_eq!(report["nodes"][1]["node_id"], 2);
+        assert_eq!(report["divergent"], serde_json::json!([]));
+
+        let report = consistency(
+            vec![checksum("users", 3, 8)],
+            BTreeMap::from([(2, peer_url.clone())]),
+        )
+        .await;
+        assert_eq!(report["consistent"], false);
+        assert_eq!(report["divergent"][0]["table"], "users");
+        assert_eq!(report["divergent"][0]["nodes"]["1"]["checksum"], 8);
+        assert_eq!(report["divergent"][0]["nodes"]["2"]["checksum"], 7);
+
+        // An unreachable peer is reported and makes the check inconclusive
+        let report = consistency(
+            vec![checksum("users", 3, 7)],
+            BTreeMap::from([(2, peer_url), (3, "http://127.0.0.1:1".to_string())]),
+        )
+        .await;
+        assert_eq!(report["consistent"], false);
+        assert_eq!(report["divergent"], serde_json::json!([]));
+        assert!(report["nodes"][2]["error"].is_string());
+
+        peer.shutdown();
+    }
+}
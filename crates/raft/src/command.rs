@@ -70,6 +70,9 @@ impl RaftActivityEvent {
             } => {
                 format!("DROP INDEX {} on table={}", index_name, table_id.0)
             }
+            Command::AdvanceCatalogEpoch { epoch } => {
+                format!("CATALOG EPOCH {}", epoch)
+            }
         };
         Self::new(log_index, term, description)
     }
@@ -140,6 +143,10 @@ pub enum Command {
         table_id: TableId,
         index_name: String,
     },
+
+    /// Advance the catalog epoch after a schema change, so every replica
+    /// drops what it cached against the old schema.
+    AdvanceCatalogEpoch { epoch: u64 },
 }
 
 /// Column definition for table creation.
@@ -287,6 +294,13 @@ mod tests {
         assert_eq!(event.description, "DROP INDEX idx_users_email on table=3");
     }
 
+    #[test]
+    fn activity_event_from_advance_catalog_epoch_command() {
+        let cmd = Command::AdvanceCatalogEpoch { epoch: 12 };
+        let event = RaftActivityEvent::from_command(27, 3, &cmd);
+        assert_eq!(event.description, "CATALOG EPOCH 12");
+    }
+
     #[test]
     fn activity_event_membership() {
         let event = RaftActivityEvent::membership(77, 9);
@@ -384,6 +398,7 @@ mod tests {
                 ],
                 primary_key: Some(vec!["id".to_string()]),
             },
+            Command::AdvanceCatalogEpoch { epoch: 7 },
         ];
 
         for cmd in commands {
@@ -2,7 +2,10 @@
 //!
 //! This module provides HTTP endpoints for inter-node Raft communication.
 //! Each node runs an HTTP server that handles AppendEntries, Vote, and InstallSnapshot RPCs.
+//! When an admin token is configured, the operator endpoints in [`crate::admin`] are
+//! served from the same listener.
 
+use crate::admin::{admin_router, AdminConfig};
 use crate::type_config::TypeConfig;
 use crate::{NodeId, RaftNode};
 use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::post, Json, Router};
@@ -16,23 +19,36 @@ use tokio::net::TcpListener;
 pub struct RaftHttpState {
     /// The Raft node instance.
     pub raft: Arc<RaftNode>,
+    /// Admin endpoint configuration (None disables `/admin/*`).
+    pub admin: Option<AdminConfig>,
 }
 
 impl RaftHttpState {
     /// Create new HTTP state with the given Raft node.
     pub fn new(raft: Arc<RaftNode>) -> Self {
-        Self { raft }
+        Self { raft, admin: None }
+    }
+
+    /// Enable the admin endpoints with the given configuration.
+    pub fn with_admin(mut self, admin: AdminConfig) -> Self {
+        self.admin = Some(admin);
+        self
     }
 }
 
 /// Create the Raft HTTP router with all RPC endpoints.
+///
+/// The admin endpoints are only mounted when the state carries an [`AdminConfig`].
 pub fn create_router(state: RaftHttpState) -> Router {
-    Router::new()
+    let mut router = Router::new()
         .route("/raft/append_entries", post(handle_append_entries))
         .route("/raft/vote", post(handle_vote))
         .route("/raft/install_snapshot", post(handle_install_snapshot))
-        .route("/health", post(handle_health).get(handle_health))
-        .with_state(state)
+        .route("/health", post(handle_health).get(handle_health));
+    if state.admin.is_some() {
+        router = router.merge(admin_router());
+    }
+    router.with_state(state)
 }
 
 /// Start the Raft HTTP server on the given address.
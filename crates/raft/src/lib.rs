@@ -77,8 +77,13 @@
 //! - `POST /raft/install_snapshot` - State transfer for new nodes
 //! - `GET /health` - Node health and Raft status
 //!
+//! With an admin token configured, operator endpoints are also served under
+//! `/admin/*` (status, tables, checkpoint, snapshot, membership, checksums and
+//! cross-node consistency). See [`admin`].
+//!
 //! # Modules
 //!
+//! - [`admin`]: Token-protected operator endpoints
 //! - [`command`]: Raft command types for DML/DDL operations
 //! - [`config`]: Node configuration (data directory, ports)
 //! - [`http_server`]: Axum HTTP endpoints for Raft RPCs
@@ -93,6 +98,7 @@
 //! - Dynamic membership changes
 //! - Read scaling via follower reads
 
+pub mod admin;
 pub mod command;
 pub mod config;
 pub mod http_server;
@@ -102,6 +108,10 @@ pub mod persistent_storage;
 pub mod state_machine;
 pub mod type_config;
 
+pub use admin::{
+    compare_checksums, AdminConfig, CheckpointHandler, ChecksumHandler, Divergence,
+    SettingsHandler, TableChecksum,
+};
 pub use command::{
     activity_channel, ActivityReceiver, ActivitySender, Command, CommandResponse, RaftActivityEvent,
 };
@@ -0,0 +1,270 @@
+//! Crash-recovery simulator.
+//!
+//! Runs a workload of single-row INSERT, UPDATE and DELETE statements against
+//! a database whose disk operations go through a [`FaultPlan`] that "kills the
+//! process" at a chosen WAL append, WAL sync, page read or page write: that
+//! operation and every one after it fail without touching the files (see
+//! [`common::hooks`]). The directory is then opened again and checked:
+//!
+//! - the database opens,
+//! - every statement acknowledged before the crash is visible,
+//! - the statement in flight at the crash is either fully applied or not
+//!   applied at all,
+//! - each primary key appears at most once and `name` is never NULL,
+//! - the primary key index agrees with the rows: inserting a visible key is
+//!   rejected and inserting a missing key succeeds.
+//!
+//! [`simulate`] runs one workload; the strategies in this crate generate
+//! workloads and crash points for property tests.
+//!
+//! # Example
+//! ```no_run
+//! use common::hooks::IoOp;
+//! use recovery_sim::{CrashPoint, Op, simulate};
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let dir = tempfile::tempdir()?;
+//! let workload = [
+//!     Op::Insert { id: 1, name: "a".into() },
+//!     Op::Update { id: 1, name: "b".into() },
+//! ];
+//! let report = simulate(dir.path(), &workload, CrashPoint::new(IoOp::WalSync, 2)).await?;
+//! assert!(report.crashed);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::BTreeMap, fmt, path::Path, sync::Arc};
+
+use anyhow::{Context, Result, bail};
+use common::hooks::{FaultPlan, IoOp};
+use database::{Database, QueryResult};
+use proptest::prelude::*;
+use types::Value;
+
+/// Table every workload runs against.
+pub const TABLE: &str = "kv";
+
+/// Workloads use primary keys `0..KEYS`, so statements often collide.
+pub const KEYS: i64 = 8;
+
+const CATALOG_FILE: &str = "catalog.json";
+const WAL_FILE: &str = "sim.wal";
+const BUFFER_PAGES: usize = 4;
+
+/// Rows of [`TABLE`] by primary key.
+pub type Model = BTreeMap<i64, String>;
+
+/// A single-row statement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Op {
+    Insert { id: i64, name: String },
+    Update { id: i64, name: String },
+    Delete { id: i64 },
+}
+
+impl Op {
+    /// The statement's SQL.
+    pub fn sql(&self) -> String {
+        match self {
+            Op::Insert { id, name } => format!("INSERT INTO {TABLE} VALUES ({id}, '{name}')"),
+            Op::Update { id, name } => {
+                format!("UPDATE {TABLE} SET name = '{name}' WHERE id = {id}")
+            }
+            Op::Delete { id } => format!("DELETE FROM {TABLE} WHERE id = {id}"),
+        }
+    }
+
+    /// Apply the statement to `model`, returning the number of rows it
+    /// should affect, or `None` if it should fail on a duplicate key.
+    pub fn apply(&self, model: &mut Model) -> Option<u64> {
+        match self {
+            Op::Insert { id, name } => {
+                if model.contains_key(id) {
+                    return None;
+                }
+                model.insert(*id, name.clone());
+                Some(1)
+            }
+            Op::Update { id, name } => Some(match model.get_mut(id) {
+                Some(row) => {
+                    *row = name.clone();
+                    1
+                }
+                None => 0,
+            }),
+            Op::Delete { id } => Some(model.remove(id).map_or(0, |_| 1)),
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.sql())
+    }
+}
+
+/// The operation at which the simulated process dies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CrashPoint {
+    pub op: IoOp,
+    /// Counted from 1 among operations of the same kind
+    pub n: u64,
+}
+
+impl CrashPoint {
+    pub fn new(op: IoOp, n: u64) -> Self {
+        Self { op, n }
+    }
+}
+
+/// What happened during a [`simulate`] run that passed every check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    /// Statements acknowledged before the crash
+    pub acknowledged: usize,
+    /// Whether the crash point was reached
+    pub crashed: bool,
+    /// Whether the statement in flight at the crash survived it
+    pub in_flight_applied: bool,
+    /// Rows visible after the restart
+    pub recovered: Model,
+}
+
+/// Strategy for one statement. Names vary in length so that updates both
+/// shrink and grow rows, and pages fill quickly.
+pub fn arb_op() -> impl Strategy<Value = Op> {
+    let id = 0..KEYS;
+    let name = "[a-z]{0,300}";
+    prop_oneof![
+        3 => (id.clone(), name).prop_map(|(id, name)| Op::Insert { id, name }),
+        2 => (id.clone(), name).prop_map(|(id, name)| Op::Update { id, name }),
+        1 => id.prop_map(|id| Op::Delete { id }),
+    ]
+}
+
+/// Strategy for a workload of 1 to 40 statements.
+pub fn arb_workload() -> impl Strategy<Value = Vec<Op>> {
+    prop::collection::vec(arb_op(), 1..40)
+}
+
+/// Strategy for a crash point. Some fall after the workload has finished,
+/// which checks a clean shutdown and restart.
+pub fn arb_crash_point() -> impl Strategy<Value = CrashPoint> {
+    prop_oneof![
+        (1..=80u64).prop_map(|n| CrashPoint::new(IoOp::WalAppend, n)),
+        (1..=80u64).prop_map(|n| CrashPoint::new(IoOp::WalSync, n)),
+        (1..=120u64).prop_map(|n| CrashPoint::new(IoOp::PageWrite, n)),
+        (1..=400u64).prop_map(|n| CrashPoint::new(IoOp::PageRead, n)),
+    ]
+}
+
+/// Run `workload` in a new database in `dir`, crash at `crash`, restart, and
+/// check the recovered rows.
+///
+/// # Errors
+///
+/// Returns an error describing the first broken invariant, or a statement
+/// that behaved differently from the model before the crash.
+pub async fn simulate(dir: &Path, workload: &[Op], crash: CrashPoint) -> Result<Report> {
+    let db = open(dir).await?;
+    db.execute(&format!(
+        "CREATE TABLE {TABLE} (id INT PRIMARY KEY, name TEXT NOT NULL)"
+    ))
+    .await?;
+    drop(db);
+
+    let plan = Arc::new(FaultPlan::new().crash_at(crash.op, crash.n));
+    let db = Database::with_faults(dir, CATALOG_FILE, WAL_FILE, BUFFER_PAGES, plan.clone())
+        .await?
+        .with_auto_analyze(None);
+
+    let mut committed = Model::new();
+    let mut in_flight = None;
+    let mut acknowledged = 0;
+    for op in workload {
+        let mut after = committed.clone();
+        let expected = op.apply(&mut after);
+        match (db.execute(&op.sql()).await, expected) {
+            (Ok(QueryResult::Count { affected, .. }), Some(expected)) if affected == expected => {}
+            (Ok(result), _) => {
+                bail!("`{op}` returned {result:?}, expected {expected:?} rows affected")
+            }
+            (Err(_), _) if plan.crashed() => {
+                in_flight = Some(after);
+                break;
+            }
+            (Err(e), None) if format!("{e:#}").contains("duplicate primary key") => {}
+            (Err(e), _) => bail!("`{op}` failed before the crash: {e:#}"),
+        }
+        committed = after;
+        acknowledged += 1;
+    }
+    let crashed = plan.crashed();
+    drop(db);
+
+    let db = open(dir)
+        .await
+        .context("database does not open after the crash")?;
+    let recovered = read_rows(&db).await?;
+    let in_flight_applied = recovered != committed;
+    if in_flight_applied && in_flight.as_ref() != Some(&recovered) {
+        bail!(
+            "recovered rows {recovered:?} are neither the acknowledged state {committed:?} \
+             nor that plus the statement in flight {in_flight:?}"
+        );
+    }
+    check_primary_key_index(&db, &recovered).await?;
+
+    Ok(Report {
+        acknowledged,
+        crashed,
+        in_flight_applied,
+        recovered,
+    })
+}
+
+async fn open(dir: &Path) -> Result<Database> {
+    Ok(Database::new(dir, CATALOG_FILE, WAL_FILE, BUFFER_PAGES)
+        .await?
+        .with_auto_analyze(None))
+}
+
+/// Visible rows, failing on a duplicate key or a NULL name.
+async fn read_rows(db: &Database) -> Result<Model> {
+    let QueryResult::Rows { rows, .. } =
+        db.execute(&format!("SELECT id, name FROM {TABLE}")).await?
+    else {
+        bail!("SELECT did not return rows");
+    };
+    let mut model = Model::new();
+    for row in rows {
+        let (id, name) = match row.values.as_slice() {
+            [Value::Int(id), Value::Text(name)] => (*id, name.clone()),
+            values => bail!("row {values:?} breaks the table's constraints"),
+        };
+        if model.insert(id, name).is_some() {
+            bail!("primary key {id} appears more than once");
+        }
+    }
+    Ok(model)
+}
+
+/// Probe every key: the index must reject visible keys and accept the rest.
+async fn check_primary_key_index(db: &Database, rows: &Model) -> Result<()> {
+    for id in 0..KEYS {
+        let probe = db
+            .execute(&format!("INSERT INTO {TABLE} VALUES ({id}, 'probe')"))
+            .await;
+        match (rows.contains_key(&id), probe) {
+            (true, Err(e)) if format!("{e:#}").contains("duplicate primary key") => {}
+            (true, probe) => bail!("key {id} is visible but the index allowed {probe:?}"),
+            (false, Ok(_)) => {
+                db.execute(&format!("DELETE FROM {TABLE} WHERE id = {id}"))
+                    .await?;
+            }
+            (false, Err(e)) => bail!("key {id} is missing but the index rejected it: {e:#}"),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,166 @@
+//! Property tests crashing random workloads at random disk operations.
+
+use common::hooks::IoOp;
+use proptest::prelude::*;
+use recovery_sim::{CrashPoint, Op, arb_crash_point, arb_workload, simulate};
+
+fn run(workload: &[Op], crash: CrashPoint) -> anyhow::Result<recovery_sim::Report> {
+    let dir = tempfile::tempdir()?;
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(simulate(dir.path(), workload, crash))
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn recovers_from_a_crash_at_any_io_boundary(
+        workload in arb_workload(),
+        crash in arb_crash_point(),
+    ) {
+        if let Err(e) = run(&workload, crash) {
+            return Err(TestCaseError::fail(format!("{e:#}")));
+        }
+    }
+}
+
+#[test]
+fn acknowledged_statements_survive_a_crash_on_the_next_one() -> anyhow::Result<()> {
+    let workload = [
+        Op::Insert {
+            id: 1,
+            name: "a".into(),
+        },
+        Op::Insert {
+            id: 2,
+            name: "b".into(),
+        },
+        Op::Delete { id: 1 },
+    ];
+    // The delete's WAL append is the third
+    let report = run(&workload, CrashPoint::new(IoOp::WalAppend, 3))?;
+    assert!(report.crashed);
+    assert_eq!(report.acknowledged, 2);
+    // The delete reached its page before the crash
+    assert!(report.in_flight_applied);
+    assert_eq!(
+        report.recovered.keys().copied().collect::<Vec<_>>(),
+        vec![2]
+    );
+    Ok(())
+}
+
+#[test]
+fn a_crash_before_the_first_page_write_loses_only_the_statement_in_flight() -> anyhow::Result<()> {
+    let workload = [Op::Insert {
+        id: 1,
+        name: "a".into(),
+    }];
+    let report = run(&workload, CrashPoint::new(IoOp::PageWrite, 1))?;
+    assert!(report.crashed);
+    assert_eq!(report.acknowledged, 0);
+    assert!(!report.in_flight_applied);
+    assert!(report.recovered.is_empty());
+    Ok(())
+}
+
+#[test]
+fn a_workload_that_finishes_before_the_crash_point_recovers_every_row() -> anyhow::Result<()> {
+    let workload = [
+        Op::Insert {
+            id: 1,
+            name: "a".into(),
+        },
+        Op::Update {
+            id: 1,
+            name: "b".repeat(200),
+        },
+        Op::Insert {
+            id: 1,
+            name: "dup".into(),
+        },
+    ];
+    let report = run(&workload, CrashPoint::new(IoOp::WalSync, 1_000))?;
+    assert!(!report.crashed);
+    assert_eq!(report.acknowledged, 3);
+    assert_eq!(report.recovered.get(&1), Some(&"b".repeat(200)));
+    Ok(())
+}
+
+#[test]
+fn a_growing_update_is_not_lost_by_a_crash() -> anyhow::Result<()> {
+    let workload = [
+        Op::Insert {
+            id: 1,
+            name: "a".into(),
+        },
+        Op::Update {
+            id: 1,
+            name: "b".repeat(200),
+        },
+    ];
+    for n in 1..=4 {
+        let report = run(&workload, CrashPoint::new(IoOp::PageWrite, n))?;
+        assert_eq!(
+            report.recovered.len(),
+            report.acknowledged.min(1),
+            "crash at {n}"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn a_crash_while_relocating_a_row_loses_no_row() -> anyhow::Result<()> {
+    // Eight rows fill the first page, so growing one moves it to another
+    // page behind a forwarding pointer, and shrinking it moves it back
+    let mut workload: Vec<Op> = ('a'..='h')
+        .zip(0..)
+        .map(|(c, id)| Op::Insert {
+            id,
+            name: c.to_string().repeat(450),
+        })
+        .collect();
+    workload.push(Op::Update {
+        id: 0,
+        name: "z".repeat(1000),
+    });
+    workload.push(Op::Update {
+        id: 0,
+        name: "y".into(),
+    });
+    let mut n = 1;
+    loop {
+        let report = run(&workload, CrashPoint::new(IoOp::PageWrite, n))
+            .map_err(|e| e.context(format!("crash at page write {n}")))?;
+        if !report.crashed {
+            assert_eq!(report.recovered.get(&0).map(String::as_str), Some("y"));
+            return Ok(());
+        }
+        assert!(report.acknowledged == 0 || report.recovered.contains_key(&0));
+        n += 1;
+    }
+}
+
+#[test]
+fn a_crashed_delete_keeps_its_row_indexed() -> anyhow::Result<()> {
+    let workload = [
+        Op::Insert {
+            id: 1,
+            name: "a".into(),
+        },
+        Op::Delete { id: 1 },
+    ];
+    for n in 1..=4 {
+        // simulate probes the index for every key after the restart
+        let report = run(&workload, CrashPoint::new(IoOp::PageWrite, n))?;
+        assert_eq!(
+            report.recovered.len(),
+            usize::from(report.acknowledged == 1),
+            "crash at {n}"
+        );
+    }
+    Ok(())
+}
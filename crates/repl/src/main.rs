@@ -5,6 +5,10 @@ use clap::Parser;
 use database::{Database, QueryResult};
 use std::path::PathBuf;
 
+/// Counts allocations for `PROFILE`.
+#[global_allocator]
+static ALLOCATOR: common::alloc::CountingAllocator = common::alloc::CountingAllocator;
+
 const DEFAULT_DATA_DIR: &str = "./db_data";
 const DEFAULT_CATALOG_FILE: &str = "catalog.json";
 const DEFAULT_WAL_FILE: &str = "toydb.wal";
@@ -60,18 +64,25 @@ async fn main() -> Result<()> {
 }
 
 async fn execute_and_exit(db: Database, input: &str) -> Result<()> {
-    use common::pretty::{self, TableStyleKind};
-
     // Check if this is a meta-command
     if input.trim().starts_with('.') {
         return execute_meta_command(db, input.trim()).await;
     }
 
-    // Otherwise execute as SQL
-    let result = db.execute(input).await?;
+    // Otherwise execute as SQL, printing each statement's result
+    for result in db.execute_script(input).await? {
+        print_result(result);
+    }
+
+    Ok(())
+}
 
+fn print_result(result: QueryResult) {
+    use common::pretty::{self, TableStyleKind};
+
+    let info = result.info().cloned();
     match result {
-        QueryResult::Rows { schema, rows } => {
+        QueryResult::Rows { schema, rows, .. } => {
             let batch = common::RecordBatch {
                 columns: schema,
                 rows,
@@ -79,15 +90,34 @@ async fn execute_and_exit(db: Database, input: &str) -> Result<()> {
             let rendered = pretty::render_record_batch(&batch, TableStyleKind::Modern);
             println!("{}", rendered);
         }
-        QueryResult::Count { affected } => {
+        QueryResult::Count { affected, .. } => {
             println!("{} row(s) affected.", affected);
         }
+        QueryResult::Inserted {
+            affected,
+            generated_ids,
+            ..
+        } => {
+            println!(
+                "{} row(s) affected. Generated ids: {}.",
+                affected,
+                format_ids(&generated_ids)
+            );
+        }
         QueryResult::Empty => {
             // For DDL operations, no output
         }
     }
+    if let Some(info) = info {
+        println!("({info})");
+    }
+}
 
-    Ok(())
+fn format_ids(ids: &[i64]) -> String {
+    ids.iter()
+        .map(i64::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 async fn execute_meta_command(db: Database, input: &str) -> Result<()> {
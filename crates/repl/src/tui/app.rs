@@ -122,20 +122,35 @@ impl<'a> App<'a> {
     }
 
     fn execute_sql_inner(&mut self, sql: &str) -> Result<()> {
-        let result = self.runtime_handle.block_on(self.db.execute(sql))?;
+        // Pasted scripts run statement by statement; show the last result
+        let mut results = self.runtime_handle.block_on(self.db.execute_script(sql))?;
+        let result = results.pop().unwrap_or(QueryResult::Empty);
 
         match result {
-            QueryResult::Rows { schema, rows } => {
+            QueryResult::Rows { schema, rows, .. } => {
                 self.results = Some(RecordBatch {
                     columns: schema,
                     rows,
                 });
                 self.status_message = None;
             }
-            QueryResult::Count { affected } => {
+            QueryResult::Count { affected, .. } => {
                 self.results = None;
                 self.status_message = Some(format!("{} row(s) affected", affected));
             }
+            QueryResult::Inserted {
+                affected,
+                generated_ids,
+                ..
+            } => {
+                let ids: Vec<String> = generated_ids.iter().map(i64::to_string).collect();
+                self.results = None;
+                self.status_message = Some(format!(
+                    "{} row(s) affected, generated ids: {}",
+                    affected,
+                    ids.join(", ")
+                ));
+            }
             QueryResult::Empty => {
                 self.results = None;
                 self.status_message = Some("Success".to_string());
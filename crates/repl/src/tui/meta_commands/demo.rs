@@ -91,7 +91,7 @@ impl DemoCommand {
         output_lines.push("".to_string());
 
         match Self::exec(db, runtime_handle, join_query1)? {
-            database::QueryResult::Rows { schema, rows } => {
+            database::QueryResult::Rows { schema, rows, .. } => {
                 // Format header
                 output_lines.push(format!("  | {} |", schema.join(" | ")));
                 output_lines.push(format!("  |{}|", "-".repeat(schema.len() * 12)));
@@ -117,7 +117,7 @@ impl DemoCommand {
         output_lines.push("".to_string());
 
         match Self::exec(db, runtime_handle, join_query2)? {
-            database::QueryResult::Rows { schema, rows } => {
+            database::QueryResult::Rows { schema, rows, .. } => {
                 // Format header
                 output_lines.push(format!("  | {} |", schema.join(" | ")));
                 output_lines.push(format!("  |{}|", "-".repeat(schema.len() * 15)));
@@ -28,7 +28,7 @@ impl MetaCommand for HelpCommand {
             "  Ctrl+Q             Quit application".to_string(),
             "".to_string(),
             "SQL Support:".to_string(),
-            "  DDL: CREATE TABLE, DROP TABLE, CREATE INDEX, DROP INDEX".to_string(),
+            "  DDL: CREATE TABLE, DROP TABLE, ALTER TABLE, CREATE INDEX, DROP INDEX".to_string(),
             "  DML: INSERT, SELECT, UPDATE, DELETE".to_string(),
             "  JOIN: SELECT ... FROM t1 JOIN t2 ON condition".to_string(),
             "  Types: INT, TEXT, BOOL".to_string(),
@@ -43,7 +43,7 @@ impl MetaCommand for SchemaCommand {
                     .map(|col| {
                         common::Row::new(vec![
                             types::Value::Text(col.name.clone()),
-                            types::Value::Text(format!("{:?}", col.ty)),
+                            types::Value::Text(col.type_name()),
                         ])
                     })
                     .collect();
@@ -6,7 +6,7 @@ use ratatui::{
     text::{Line, Span},
     widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
 };
-use types::Value;
+use types::{Value, binary, temporal};
 
 pub fn render(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
@@ -150,5 +150,10 @@ fn format_value(value: &Value) -> String {
         Value::Text(s) => format!("'{}'", s),
         Value::Bool(b) => b.to_string(),
         Value::Null => "NULL".to_string(),
+        Value::Float(f) => format!("{f:?}"),
+        Value::Date(d) => temporal::format_date(*d),
+        Value::Timestamp(t) => temporal::format_timestamp(*t),
+        Value::Decimal(d) => d.to_string(),
+        Value::Bytes(b) => binary::format_hex(b),
     }
 }
@@ -19,6 +19,10 @@ pub fn map_error_to_code(err: &anyhow::Error) -> ErrorCode {
             DbError::Storage(_) => ErrorCode::StorageError,
             DbError::Wal(_) => ErrorCode::WalError,
             DbError::Constraint(_) => ErrorCode::ConstraintViolation,
+            DbError::ResourceExhausted(_) => ErrorCode::ResourceExhausted,
+            DbError::BufferPoolExhausted(_) => ErrorCode::BufferPoolExhausted,
+            DbError::Corruption(_) => ErrorCode::Corruption,
+            DbError::DiskFull(_) => ErrorCode::DiskFull,
             DbError::Io(_) => ErrorCode::IoError,
         }
     } else {
@@ -76,6 +80,40 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_map_resource_exhausted_error() {
+        let err = anyhow!(DbError::ResourceExhausted(
+            "statement exceeded 10 rows scanned".into()
+        ));
+        assert!(matches!(
+            map_error_to_code(&err),
+            ErrorCode::ResourceExhausted
+        ));
+    }
+
+    #[test]
+    fn test_map_buffer_pool_exhausted_error() {
+        let err = anyhow!(DbError::BufferPoolExhausted(
+            "all 10 pages are pinned after waiting 500ms".into()
+        ));
+        assert!(matches!(
+            map_error_to_code(&err),
+            ErrorCode::BufferPoolExhausted
+        ));
+    }
+
+    #[test]
+    fn test_map_corruption_error() {
+        let err = anyhow!(DbError::Corruption("page 3 failed its checksum".into()));
+        assert!(matches!(map_error_to_code(&err), ErrorCode::Corruption));
+    }
+
+    #[test]
+    fn test_map_disk_full_error() {
+        let err = anyhow!(DbError::DiskFull("failed to write page".into()));
+        assert!(matches!(map_error_to_code(&err), ErrorCode::DiskFull));
+    }
+
     #[test]
     fn test_map_io_error() {
         let err = anyhow!(DbError::Io(std::io::Error::other("disk full")));
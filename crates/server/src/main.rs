@@ -23,19 +23,46 @@
 //!     --peer 1,127.0.0.1:6001 --peer 2,127.0.0.1:6002 \
 //!     --data-dir ./node3 --port 5003
 //! ```
+//!
+//! Passing `--admin-token <TOKEN>` also serves `/admin/*` endpoints (status, tables,
+//! checkpoint, snapshot, membership) on the Raft address, authenticated with
+//! `Authorization: Bearer <TOKEN>`.
+//!
+//! Writes are only accepted on the leader; other nodes reject them with the
+//! leader's ID and address. `--read-consistency leader|linearizable` applies
+//! the same rule to reads (the default, `local`, serves reads on any node).
+//!
+//! `--encryption-key-file <PATH>` encrypts heap pages, B-tree indexes, the
+//! catalog, and the WAL at rest with the hex-encoded key in `PATH`. To rotate
+//! keys, pass the new key there and each old one with
+//! `--previous-encryption-key-file <PATH>` until `VACUUM` has rewritten the
+//! tables.
+//!
+//! `--session-idle-timeout <SECS>` closes connections that send no request
+//! for that long, rolling back the transaction a client left open.
 
 mod error;
+mod session;
 mod tui;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
-use database::{ActivityReceiver, Database, QueryResult, RaftConfig, activity_channel};
+use database::{
+    ActivityReceiver, Database, EncryptionKey, QueryResult, RaftConfig, ReadConsistency,
+    ResourceLimits, RetryPolicy, activity_channel,
+};
 use protocol::{ClientRequest, ServerResponse, frame};
+use session::{Session, SessionEvent};
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::RwLock;
 
+/// Counts allocations for `PROFILE`.
+#[global_allocator]
+static ALLOCATOR: common::alloc::CountingAllocator = common::alloc::CountingAllocator;
+
 const DEFAULT_HOST: &str = "127.0.0.1";
 const DEFAULT_PORT: u16 = 5432;
 const DEFAULT_DATA_DIR: &str = "./db_data";
@@ -91,6 +118,70 @@ struct Args {
     #[arg(long)]
     persistent: bool,
 
+    /// Bearer token for the `/admin/*` endpoints on the Raft HTTP server.
+    /// Admin endpoints are disabled unless this is set.
+    #[arg(long)]
+    admin_token: Option<String>,
+
+    /// Read consistency in Raft mode: "local" (any node, may be stale),
+    /// "leader" (leader only) or "linearizable" (leader, confirmed by quorum).
+    #[arg(long, default_value_t = ReadConsistency::Local)]
+    read_consistency: ReadConsistency,
+
+    /// Retry statements rejected during a leader change, up to this many
+    /// attempts in total. Retries are disabled when unset.
+    #[arg(long)]
+    retry_attempts: Option<u32>,
+
+    /// Fail any statement that reads more than this many rows.
+    #[arg(long)]
+    max_rows_scanned: Option<u64>,
+
+    /// Fail any statement that buffers more than this many bytes for sorts
+    /// and joins.
+    #[arg(long)]
+    max_statement_memory: Option<u64>,
+
+    /// Spill sorts and joins to temporary files once a statement buffers
+    /// more than this many bytes for them.
+    #[arg(long)]
+    work_memory: Option<u64>,
+
+    /// Fail any statement that writes more than this many bytes to
+    /// temporary files.
+    #[arg(long)]
+    max_temp_bytes: Option<u64>,
+
+    /// Reject statements from a connection that already has this many in
+    /// flight.
+    #[arg(long)]
+    max_concurrent_statements: Option<usize>,
+
+    /// Let at most this many statements execute at once across all
+    /// connections; the rest wait their turn in arrival order.
+    #[arg(long)]
+    max_executing_statements: Option<usize>,
+
+    /// Split each sequential scan across up to this many worker threads.
+    /// Scans run serially when 0.
+    #[arg(long, default_value_t = 0)]
+    max_parallel_workers: usize,
+
+    /// Encrypt data files at rest with the key in this file (64 hex digits).
+    /// The same key must be given every time the data directory is opened.
+    #[arg(long)]
+    encryption_key_file: Option<PathBuf>,
+
+    /// Keys that data may still be sealed with after a rotation, one file
+    /// per flag. They are only used to read; new writes use the current key.
+    #[arg(long, requires = "encryption_key_file")]
+    previous_encryption_key_file: Vec<PathBuf>,
+
+    /// Close client connections that send no request for this many seconds.
+    /// Connections may stay idle indefinitely when unset.
+    #[arg(long, value_name = "SECS")]
+    session_idle_timeout: Option<u64>,
+
     /// Run in headless mode (static banner, no TUI).
     /// Useful for running in scripts or when stdout is not a TTY.
     #[arg(long)]
@@ -121,7 +212,7 @@ impl Args {
             .collect();
         let peers = peers?;
 
-        let config = if let Some(ref raft_addr) = self.raft_addr {
+        let mut config = if let Some(ref raft_addr) = self.raft_addr {
             // Multi-node cluster mode
             if self.persistent {
                 RaftConfig::cluster_persistent(node_id, raft_addr.clone(), peers)
@@ -139,8 +230,20 @@ impl Args {
             anyhow::bail!("--raft-addr is required when peers are specified");
         };
 
+        if let Some(ref token) = self.admin_token {
+            if token.is_empty() {
+                anyhow::bail!("--admin-token must not be empty");
+            }
+            config = config.with_admin_token(token.clone());
+        }
+        config = config.with_read_consistency(self.read_consistency);
+
         Ok(Some(config))
     }
+
+    fn session_idle_timeout(&self) -> Option<Duration> {
+        self.session_idle_timeout.map(Duration::from_secs)
+    }
 }
 
 #[tokio::main]
@@ -160,17 +263,47 @@ async fn main() -> Result<()> {
         None
     };
 
+    let read_key = |path: &PathBuf| -> Result<EncryptionKey> {
+        let hex = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read key file {}", path.display()))?;
+        Ok(EncryptionKey::from_hex(&hex)?)
+    };
+    let encryption = match &args.encryption_key_file {
+        Some(path) => {
+            let mut key = read_key(path)?;
+            for path in &args.previous_encryption_key_file {
+                key = key.with_previous(&read_key(path)?);
+            }
+            Some(key)
+        }
+        None => None,
+    };
+
     // Initialize database
-    let db = Arc::new(
-        Database::with_raft_config(
-            &args.data_dir,
-            &args.catalog_file,
-            &args.wal_file,
-            args.buffer_pages,
-            raft_config.clone(),
-        )
-        .await?,
-    );
+    let mut db = Database::with_encryption(
+        &args.data_dir,
+        &args.catalog_file,
+        &args.wal_file,
+        args.buffer_pages,
+        raft_config.clone(),
+        encryption,
+    )
+    .await?;
+    if let Some(attempts) = args.retry_attempts {
+        db = db.with_retry_policy(RetryPolicy::new(attempts));
+    }
+    db = db.with_resource_limits(ResourceLimits {
+        max_rows_scanned: args.max_rows_scanned,
+        max_memory_bytes: args.max_statement_memory,
+        work_memory_bytes: args.work_memory,
+        max_temp_bytes: args.max_temp_bytes,
+        max_concurrent_statements: args.max_concurrent_statements,
+    });
+    db = db.with_max_parallel_workers(args.max_parallel_workers);
+    if let Some(max) = args.max_executing_statements {
+        db = db.with_max_executing_statements(max);
+    }
+    let db = Arc::new(db);
 
     // Bind TCP listener
     let addr = format!("{}:{}", args.host, args.port);
@@ -181,7 +314,15 @@ async fn main() -> Result<()> {
         run_headless(db, listener, &addr, &args, raft_config.as_ref()).await
     } else {
         // TUI mode: real-time status display
-        run_tui_mode(db, listener, &addr, raft_config.as_ref(), activity_rx).await
+        run_tui_mode(
+            db,
+            listener,
+            &addr,
+            raft_config.as_ref(),
+            activity_rx,
+            args.session_idle_timeout(),
+        )
+        .await
     }
 }
 
@@ -257,7 +398,7 @@ async fn run_headless(
     println!();
 
     // Spawn server task
-    let server_task = tokio::spawn(run_server(listener, db));
+    let server_task = tokio::spawn(run_server(listener, db, args.session_idle_timeout()));
 
     // Wait for shutdown signal
     tokio::signal::ctrl_c().await?;
@@ -276,6 +417,7 @@ async fn run_tui_mode(
     addr: &str,
     raft_config: Option<&RaftConfig>,
     activity_rx: Option<ActivityReceiver>,
+    idle_timeout: Option<Duration>,
 ) -> Result<()> {
     let node_id = raft_config.map(|c| c.node_id).unwrap_or(1);
     let raft_addr = raft_config.and_then(|c| c.listen_addr.clone());
@@ -288,21 +430,26 @@ async fn run_tui_mode(
         node_id,
     )));
 
-    tui::run_tui(db, listener, state, activity_rx).await
+    tui::run_tui(db, listener, state, activity_rx, idle_timeout).await
 }
 
 /// Run the server loop, accepting connections and spawning handlers.
 /// Used only in headless mode.
-async fn run_server(listener: TcpListener, db: Arc<Database>) -> Result<()> {
+async fn run_server(
+    listener: TcpListener,
+    db: Arc<Database>,
+    idle_timeout: Option<Duration>,
+) -> Result<()> {
     loop {
         match listener.accept().await {
             Ok((socket, addr)) => {
                 println!("New connection from {}", addr);
                 let db_clone = db.clone();
                 tokio::spawn(async move {
-                    if let Err(e) = handle_client(socket, db_clone).await {
+                    if let Err(e) = handle_client(socket, db_clone.clone(), idle_timeout).await {
                         eprintln!("Error handling client {}: {}", addr, e);
                     }
+                    db_clone.end_session(&addr.to_string());
                     println!("Connection closed: {}", addr);
                 });
             }
@@ -313,15 +460,13 @@ async fn run_server(listener: TcpListener, db: Arc<Database>) -> Result<()> {
     }
 }
 
-/// Read a single request from the client.
-/// Returns Ok(None) if client disconnected gracefully.
-async fn read_client_request(socket: &mut TcpStream) -> Result<Option<ClientRequest>> {
-    match frame::read_message_async(socket).await {
-        Ok(request) => Ok(Some(request)),
-        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
-            // Client disconnected gracefully
-            Ok(None)
-        }
+/// Read a single request from the client, waiting at most `idle_timeout`.
+async fn read_client_request(
+    socket: &mut TcpStream,
+    idle_timeout: Option<Duration>,
+) -> Result<SessionEvent> {
+    match session::next_request(socket, idle_timeout).await {
+        Ok(event) => Ok(event),
         Err(e) => {
             // Send error response before closing
             let response = ServerResponse::Error {
@@ -334,21 +479,25 @@ async fn read_client_request(socket: &mut TcpStream) -> Result<Option<ClientRequ
     }
 }
 
-/// Execute SQL and convert the result to a server response.
-/// Handles logging and timing internally.
-async fn execute_sql_request(db: &Database, sql: &str, client_addr: &str) -> ServerResponse {
+/// Execute SQL in the client's session and convert the result to a server
+/// response. Handles logging and timing internally.
+async fn execute_sql_request(
+    session: &mut Session<'_>,
+    sql: &str,
+    client_addr: &str,
+) -> ServerResponse {
     log_request(client_addr, sql);
     let start = std::time::Instant::now();
 
-    let result = db.execute(sql).await;
+    let result = session.execute(sql).await;
 
     match result {
-        Ok(QueryResult::Rows { schema, rows }) => {
+        Ok(QueryResult::Rows { schema, rows, .. }) => {
             let row_count = rows.len();
             log_response(client_addr, start.elapsed(), &format!("{} rows", row_count));
             ServerResponse::Rows { schema, rows }
         }
-        Ok(QueryResult::Count { affected }) => {
+        Ok(QueryResult::Count { affected, .. }) => {
             log_response(
                 client_addr,
                 start.elapsed(),
@@ -356,6 +505,19 @@ async fn execute_sql_request(db: &Database, sql: &str, client_addr: &str) -> Ser
             );
             ServerResponse::Count { affected }
         }
+        // Generated ids are not part of the wire protocol yet
+        Ok(QueryResult::Inserted {
+            affected,
+            generated_ids,
+            ..
+        }) => {
+            log_response(
+                client_addr,
+                start.elapsed(),
+                &format!("{} affected, generated ids {:?}", affected, generated_ids),
+            );
+            ServerResponse::Count { affected }
+        }
         Ok(QueryResult::Empty) => {
             log_response(client_addr, start.elapsed(), "DDL success");
             ServerResponse::Empty
@@ -373,29 +535,46 @@ async fn execute_sql_request(db: &Database, sql: &str, client_addr: &str) -> Ser
 
 /// Handle a single client connection.
 /// Used only in headless mode.
-async fn handle_client(mut socket: TcpStream, db: Arc<Database>) -> Result<()> {
+async fn handle_client(
+    mut socket: TcpStream,
+    db: Arc<Database>,
+    idle_timeout: Option<Duration>,
+) -> Result<()> {
     let client_addr = socket
         .peer_addr()
         .map(|addr| addr.to_string())
         .unwrap_or_else(|_| "unknown".to_string());
+    let mut session = Session::new(&db, client_addr.clone());
 
     loop {
         // Read next request
-        let Some(request) = read_client_request(&mut socket).await? else {
-            // Client disconnected
-            break;
+        let request = match read_client_request(&mut socket, idle_timeout).await? {
+            SessionEvent::Request(request) => request,
+            SessionEvent::Closed => break,
+            SessionEvent::IdleTimeout => {
+                if session.end() {
+                    println!(
+                        "Closing idle connection {} and rolling back its transaction",
+                        client_addr
+                    );
+                } else {
+                    println!("Closing idle connection {}", client_addr);
+                }
+                break;
+            }
         };
 
         // Handle request
         match request {
             ClientRequest::Execute { sql } => {
-                let response = execute_sql_request(&db, &sql, &client_addr).await;
+                let response = execute_sql_request(&mut session, &sql, &client_addr).await;
                 frame::write_message_async(&mut socket, &response).await?;
             }
             ClientRequest::Close => break,
         }
     }
 
+    session.end();
     Ok(())
 }
 
@@ -0,0 +1,267 @@
+//! Client session lifetime.
+//!
+//! With `--session-idle-timeout`, a connection that sends no request for
+//! that long is closed, so abandoned or half-open clients do not keep a
+//! handler task (and their session in the database) around forever.
+//!
+//! A client runs one statement at a time, each committing before its
+//! response is sent, unless it opens a transaction with `BEGIN`. The
+//! statements up to `COMMIT` or `ROLLBACK` then run in a [`Transaction`],
+//! which holds no locks but stays in the database's conflict tracker, so
+//! transactions committing after it are checked against it. When the
+//! connection closes, idle or not, its open transaction is rolled back
+//! and leaves the tracker.
+
+use anyhow::{Result, bail};
+use database::{Database, QueryResult, Transaction};
+use protocol::{ClientRequest, frame};
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+/// A statement that opens or ends a session's transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Control {
+    Begin,
+    Commit,
+    Rollback,
+}
+
+impl Control {
+    /// Recognize `BEGIN` or `START TRANSACTION`, `COMMIT` and `ROLLBACK`,
+    /// each optionally followed by `WORK` or `TRANSACTION`, in any case.
+    fn parse(sql: &str) -> Option<Self> {
+        let words: Vec<String> = sql
+            .trim()
+            .trim_end_matches(';')
+            .split_whitespace()
+            .map(str::to_ascii_uppercase)
+            .collect();
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        match words[..] {
+            ["START", "TRANSACTION"] => Some(Self::Begin),
+            [first] | [first, "WORK" | "TRANSACTION"] => match first {
+                "BEGIN" => Some(Self::Begin),
+                "COMMIT" => Some(Self::Commit),
+                "ROLLBACK" => Some(Self::Rollback),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// A client's statements, run on behalf of its principal, and the
+/// transaction it has open.
+pub struct Session<'a> {
+    db: &'a Database,
+    principal: String,
+    transaction: Option<Transaction<'a>>,
+}
+
+impl<'a> Session<'a> {
+    pub fn new(db: &'a Database, principal: impl Into<String>) -> Self {
+        Self {
+            db,
+            principal: principal.into(),
+            transaction: None,
+        }
+    }
+
+    /// Run one request's SQL.
+    ///
+    /// Inside a transaction, statements run as [`Transaction::execute`]
+    /// runs them, and `COMMIT` returns the number of rows its writes
+    /// affected in all.
+    pub async fn execute(&mut self, sql: &str) -> Result<QueryResult> {
+        match Control::parse(sql) {
+            Some(Control::Begin) => {
+                if self.transaction.is_some() {
+                    bail!("a transaction is already open; COMMIT or ROLLBACK it first");
+                }
+                self.transaction = Some(self.db.begin_as(&self.principal));
+                Ok(QueryResult::Empty)
+            }
+            Some(Control::Commit) => {
+                let Some(transaction) = self.transaction.take() else {
+                    bail!("no transaction is open");
+                };
+                let affected = transaction
+                    .commit()
+                    .await?
+                    .iter()
+                    .map(|result| match result {
+                        QueryResult::Count { affected, .. }
+                        | QueryResult::Inserted { affected, .. } => *affected,
+                        _ => 0,
+                    })
+                    .sum();
+                Ok(QueryResult::Count {
+                    affected,
+                    info: None,
+                })
+            }
+            Some(Control::Rollback) => {
+                self.end();
+                Ok(QueryResult::Empty)
+            }
+            None => match &mut self.transaction {
+                Some(transaction) => transaction.execute(sql).await,
+                None => self.db.execute_as(&self.principal, sql).await,
+            },
+        }
+    }
+
+    /// Roll back the open transaction, if there is one, and return whether
+    /// there was.
+    pub fn end(&mut self) -> bool {
+        self.transaction.take().map(Transaction::rollback).is_some()
+    }
+}
+
+/// What happened while waiting for a client's next request.
+#[derive(Debug)]
+pub enum SessionEvent {
+    /// The client sent a request.
+    Request(ClientRequest),
+    /// The client disconnected.
+    Closed,
+    /// The client sent nothing within the idle timeout.
+    IdleTimeout,
+}
+
+/// Wait for the next request on `socket`, for at most `idle_timeout`.
+pub async fn next_request(
+    socket: &mut TcpStream,
+    idle_timeout: Option<Duration>,
+) -> std::io::Result<SessionEvent> {
+    let read = frame::read_message_async(socket);
+    let result = match idle_timeout {
+        Some(limit) => match tokio::time::timeout(limit, read).await {
+            Ok(result) => result,
+            Err(_) => return Ok(SessionEvent::IdleTimeout),
+        },
+        None => read.await,
+    };
+    match result {
+        Ok(request) => Ok(SessionEvent::Request(request)),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(SessionEvent::Closed),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    async fn connected_pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        (client, server)
+    }
+
+    #[tokio::test]
+    async fn transactions_span_requests_and_roll_back_when_the_session_ends() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path(), "catalog.json", "test.wal", 10)
+            .await
+            .unwrap();
+        db.execute("CREATE TABLE t (id INT PRIMARY KEY)")
+            .await
+            .unwrap();
+        let count = || async {
+            match db.execute("SELECT COUNT(*) FROM t").await.unwrap() {
+                QueryResult::Rows { rows, .. } => rows[0].values[0].clone(),
+                other => panic!("expected rows, got {other:?}"),
+            }
+        };
+        let mut session = Session::new(&db, "client");
+
+        session.execute("BEGIN").await.unwrap();
+        session.execute("INSERT INTO t VALUES (1)").await.unwrap();
+        session.execute("INSERT INTO t VALUES (2)").await.unwrap();
+        assert_eq!(count().await, types::Value::Int(0));
+        let result = session.execute("commit;").await.unwrap();
+        assert!(matches!(result, QueryResult::Count { affected: 2, .. }));
+        assert_eq!(count().await, types::Value::Int(2));
+        assert!(session.execute("COMMIT").await.is_err());
+
+        // A client that goes idle mid-transaction leaves nothing behind
+        session.execute("START TRANSACTION").await.unwrap();
+        session.execute("DELETE FROM t").await.unwrap();
+        assert!(session.execute("BEGIN").await.is_err());
+        assert_eq!(db.tracked_transactions(), 1);
+        assert!(session.end());
+        assert_eq!(db.tracked_transactions(), 0);
+        assert!(!session.end());
+        assert_eq!(count().await, types::Value::Int(2));
+    }
+
+    #[tokio::test]
+    async fn random_seeds_belong_to_the_session_that_sets_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path(), "catalog.json", "test.wal", 10)
+            .await
+            .unwrap();
+        async fn draw(session: &mut Session<'_>) -> types::Value {
+            match session.execute("SELECT RANDOM()").await.unwrap() {
+                QueryResult::Rows { rows, .. } => rows[0].values[0].clone(),
+                other => panic!("expected rows, got {other:?}"),
+            }
+        }
+        let mut alone = Session::new(&db, "alone");
+        alone.execute("SET random_seed = 42").await.unwrap();
+        let mut expected = Vec::new();
+        for _ in 0..3 {
+            expected.push(draw(&mut alone).await);
+        }
+
+        // Another client drawing and reseeding in between changes nothing
+        let mut first = Session::new(&db, "first");
+        let mut second = Session::new(&db, "second");
+        first.execute("SET random_seed = 42").await.unwrap();
+        let mut drawn = Vec::new();
+        let mut reseeded = Vec::new();
+        for _ in 0..3 {
+            second.execute("SET random_seed = 7").await.unwrap();
+            reseeded.push(draw(&mut second).await);
+            drawn.push(draw(&mut first).await);
+        }
+        assert_eq!(drawn, expected);
+        assert!(reseeded.iter().all(|value| *value == reseeded[0]));
+    }
+
+    #[tokio::test]
+    async fn idle_client_times_out() {
+        let (_client, mut server) = connected_pair().await;
+        let event = next_request(&mut server, Some(Duration::from_millis(20)))
+            .await
+            .unwrap();
+        assert!(matches!(event, SessionEvent::IdleTimeout));
+    }
+
+    #[tokio::test]
+    async fn requests_and_disconnects_arrive_before_the_timeout() {
+        let (mut client, mut server) = connected_pair().await;
+        let timeout = Some(Duration::from_secs(5));
+
+        let request = ClientRequest::Execute {
+            sql: "SELECT 1".into(),
+        };
+        frame::write_message_async(&mut client, &request)
+            .await
+            .unwrap();
+        let event = next_request(&mut server, timeout).await.unwrap();
+        assert!(matches!(
+            event,
+            SessionEvent::Request(ClientRequest::Execute { .. })
+        ));
+
+        drop(client);
+        let event = next_request(&mut server, timeout).await.unwrap();
+        assert!(matches!(event, SessionEvent::Closed));
+    }
+}
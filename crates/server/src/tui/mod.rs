@@ -55,6 +55,7 @@ pub async fn run_tui(
     listener: TcpListener,
     state: SharedTuiState,
     activity_rx: Option<ActivityReceiver>,
+    idle_timeout: Option<Duration>,
 ) -> Result<()> {
     // Set up panic hook to restore terminal
     let original_hook = std::panic::take_hook();
@@ -75,7 +76,7 @@ pub async fn run_tui(
     let mut shutdown_rx1 = shutdown_tx.subscribe();
     tokio::spawn(async move {
         tokio::select! {
-            _ = run_server_with_state(listener, db_clone, state_clone) => {}
+            _ = run_server_with_state(listener, db_clone, state_clone, idle_timeout) => {}
             _ = shutdown_rx1.recv() => {}
         }
     });
@@ -193,6 +194,7 @@ async fn run_server_with_state(
     listener: TcpListener,
     db: Arc<Database>,
     state: SharedTuiState,
+    idle_timeout: Option<Duration>,
 ) -> Result<()> {
     loop {
         match listener.accept().await {
@@ -215,11 +217,13 @@ async fn run_server_with_state(
                 tokio::spawn(async move {
                     let result = handle_client_with_state(
                         socket,
-                        db_clone,
+                        db_clone.clone(),
                         state_clone.clone(),
                         &addr_clone,
+                        idle_timeout,
                     )
                     .await;
+                    db_clone.end_session(&addr_clone);
 
                     // Log disconnection
                     {
@@ -254,21 +258,36 @@ async fn handle_client_with_state(
     db: Arc<Database>,
     state: SharedTuiState,
     client_addr: &str,
+    idle_timeout: Option<Duration>,
 ) -> Result<()> {
+    use crate::session::{self, Session, SessionEvent};
     use protocol::{ClientRequest, ServerResponse, frame};
 
+    let mut client_session = Session::new(&db, client_addr);
     loop {
         // Read request
-        let request: ClientRequest = match frame::read_message_async(&mut socket).await {
-            Ok(req) => req,
-            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
-            Err(e) => return Err(e.into()),
+        let request = match session::next_request(&mut socket, idle_timeout).await? {
+            SessionEvent::Request(req) => req,
+            SessionEvent::Closed => break,
+            SessionEvent::IdleTimeout => {
+                let message = if client_session.end() {
+                    format!(
+                        "{} idle, rolling back its transaction and closing connection",
+                        client_addr
+                    )
+                } else {
+                    format!("{} idle, closing connection", client_addr)
+                };
+                let mut state_lock = state.write().await;
+                state_lock.add_activity(message, ActivityKind::Connection);
+                break;
+            }
         };
 
         match request {
             ClientRequest::Execute { sql } => {
                 let start = std::time::Instant::now();
-                let result = db.execute(&sql).await;
+                let result = client_session.execute(&sql).await;
                 let duration_ms = start.elapsed().as_millis() as u64;
 
                 // Truncate SQL for display
@@ -279,14 +298,22 @@ async fn handle_client_with_state(
                 };
 
                 let (response, result_info) = match result {
-                    Ok(database::QueryResult::Rows { schema, rows }) => {
+                    Ok(database::QueryResult::Rows { schema, rows, .. }) => {
                         let info = format!("{} rows", rows.len());
                         (ServerResponse::Rows { schema, rows }, info)
                     }
-                    Ok(database::QueryResult::Count { affected }) => {
+                    Ok(database::QueryResult::Count { affected, .. }) => {
                         let info = format!("{} affected", affected);
                         (ServerResponse::Count { affected }, info)
                     }
+                    Ok(database::QueryResult::Inserted {
+                        affected,
+                        generated_ids,
+                        ..
+                    }) => {
+                        let info = format!("{} affected, ids {:?}", affected, generated_ids);
+                        (ServerResponse::Count { affected }, info)
+                    }
                     Ok(database::QueryResult::Empty) => (ServerResponse::Empty, "OK".to_string()),
                     Err(e) => {
                         let msg = e.to_string();
@@ -320,5 +347,6 @@ async fn handle_client_with_state(
         }
     }
 
+    client_session.end();
     Ok(())
 }
@@ -286,13 +286,18 @@ async fn e2e_activity_events_for_insert() {
     let rx = server.activity_receiver();
     while timeout(Duration::from_millis(50), rx.recv()).await.is_ok() {}
 
-    // Connect client and execute DDL (doesn't go through Raft)
+    // Connect client and execute DDL, which replicates only its catalog
+    // epoch through Raft
     let mut client = Client::connect(&addr).await.unwrap();
     client
         .execute("CREATE TABLE test (id INT, name TEXT)")
         .await
         .unwrap();
 
+    // Drain the catalog epoch the DDL replicated
+    let rx = server.activity_receiver();
+    while timeout(Duration::from_millis(50), rx.recv()).await.is_ok() {}
+
     // Execute DML (goes through Raft)
     expect_count(
         client
@@ -336,6 +341,10 @@ async fn e2e_activity_events_for_update_delete() {
         .await
         .unwrap();
 
+    // Drain the catalog epoch the DDL replicated
+    let rx = server.activity_receiver();
+    while timeout(Duration::from_millis(50), rx.recv()).await.is_ok() {}
+
     // INSERT
     expect_count(
         client
@@ -409,6 +418,10 @@ async fn e2e_activity_events_multiple_clients() {
     setup.execute("CREATE TABLE events (id INT)").await.unwrap();
     setup.close().await.unwrap();
 
+    // Drain the catalog epoch the DDL replicated
+    let rx = server.activity_receiver();
+    while timeout(Duration::from_millis(50), rx.recv()).await.is_ok() {}
+
     // Multiple clients write concurrently
     let mut handles = vec![];
     for i in 0..5 {
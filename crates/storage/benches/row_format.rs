@@ -0,0 +1,124 @@
+//! Decode speed of [`RowLayout`] against bincode on a wide table.
+//!
+//! Run with `cargo bench -p storage --bench row_format`. Prints the encoded
+//! size and the time to decode every row, and to read one column of every
+//! row, in each format.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bincode::serde::{decode_from_slice, encode_to_vec};
+use common::Row;
+use storage::row_format::RowLayout;
+use types::{Decimal, SqlType, Value};
+
+const ROWS: usize = 20_000;
+const ROUNDS: usize = 5;
+
+/// A 48-column table mixing every type, a sixth of its values NULL.
+fn wide_table() -> (Vec<SqlType>, Vec<Row>) {
+    let types = [
+        SqlType::Int,
+        SqlType::Text,
+        SqlType::Float,
+        SqlType::Bool,
+        SqlType::Timestamp,
+        SqlType::Decimal {
+            precision: 12,
+            scale: 2,
+        },
+        SqlType::Date,
+        SqlType::Bytes,
+    ];
+    let types: Vec<SqlType> = types.iter().cycle().take(48).cloned().collect();
+    let rows = (0..ROWS as i64)
+        .map(|i| {
+            let values = types
+                .iter()
+                .enumerate()
+                .map(|(column, ty)| match (ty, (i as usize + column) % 6) {
+                    (_, 0) => Value::Null,
+                    (SqlType::Int, _) => Value::Int(i * 31 + column as i64),
+                    (SqlType::Text, _) => Value::Text(format!("customer-{i}-{column}")),
+                    (SqlType::Float, _) => Value::Float(i as f64 / 7.0),
+                    (SqlType::Bool, _) => Value::Bool(i % 2 == 0),
+                    (SqlType::Timestamp, _) => Value::Timestamp(1_700_000_000_000_000 + i),
+                    (SqlType::Decimal { .. }, _) => Value::Decimal(Decimal::new(i.into(), 2)),
+                    (SqlType::Date, _) => Value::Date(19_000 + i as i32),
+                    (_, _) => Value::Bytes(i.to_le_bytes().to_vec()),
+                })
+                .collect();
+            Row::new(values)
+        })
+        .collect();
+    (types, rows)
+}
+
+/// Fastest of [`ROUNDS`] runs of `f`.
+fn fastest(mut f: impl FnMut()) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let started = Instant::now();
+            f();
+            started.elapsed()
+        })
+        .min()
+        .expect("at least one round")
+}
+
+fn main() {
+    let (types, rows) = wide_table();
+    let layout = RowLayout::new(&types);
+    let config = bincode::config::legacy();
+
+    let bincode_rows: Vec<Vec<u8>> = rows
+        .iter()
+        .map(|row| encode_to_vec(row, config).expect("encode with bincode"))
+        .collect();
+    let compact_rows: Vec<Vec<u8>> = rows
+        .iter()
+        .map(|row| layout.encode(row).expect("encode with layout"))
+        .collect();
+
+    let bincode_full = fastest(|| {
+        for tuple in &bincode_rows {
+            let (row, _): (Row, usize) = decode_from_slice(tuple, config).expect("decode");
+            black_box(row);
+        }
+    });
+    let compact_full = fastest(|| {
+        for tuple in &compact_rows {
+            black_box(layout.decode(tuple).expect("decode"));
+        }
+    });
+    // bincode must decode the whole row to reach its last column
+    let compact_column = fastest(|| {
+        for tuple in &compact_rows {
+            black_box(
+                layout
+                    .decode_column(tuple, types.len() - 1)
+                    .expect("decode"),
+            );
+        }
+    });
+
+    let size = |tuples: &[Vec<u8>]| tuples.iter().map(Vec::len).sum::<usize>() / ROWS;
+    println!("{ROWS} rows of {} columns", types.len());
+    println!(
+        "bytes per row:      bincode {:>6}  layout {:>6}",
+        size(&bincode_rows),
+        size(&compact_rows)
+    );
+    println!(
+        "decode every row:   bincode {:>6.1?}  layout {:>6.1?}  ({:.1}x)",
+        bincode_full,
+        compact_full,
+        bincode_full.as_secs_f64() / compact_full.as_secs_f64()
+    );
+    println!(
+        "decode last column: bincode {:>6.1?}  layout {:>6.1?}  ({:.1}x)",
+        bincode_full,
+        compact_column,
+        bincode_full.as_secs_f64() / compact_column.as_secs_f64()
+    );
+}
@@ -0,0 +1,73 @@
+//! Page checksums: detecting pages that changed on disk.
+//!
+//! A heap page created since checksums existed has [`CHECKSUMMED`] set in
+//! its header's slot count and a CRC32 of its contents in the four bytes
+//! after the header, ahead of its slots. The checksum is stored when the
+//! page is written and checked when it is read back, whether through a
+//! [`crate::PageIo`] or the buffer pool, so a page damaged on disk fails
+//! with [`DbError::Corruption`] instead of decoding as garbage rows. It
+//! covers the whole page with its own four bytes taken as zero.
+//!
+//! Pages written before checksums existed keep their layout: without the
+//! flag they have no checksum and are read unverified, as is a page whose
+//! stored checksum is zero. A page of zeros was never written (a file grows
+//! by whole pages, and a page past the last one written reads as zeros) and
+//! has no flag either.
+//!
+//! Pages in memory are not checksummed: the bytes hold whatever was last
+//! read or stored, and only the copy that reaches the disk is stamped.
+
+use std::ops::Range;
+use std::path::Path;
+
+use common::{DbError, DbResult};
+
+/// Bit of [`crate::PageHeader::num_slots`] set on disk when the page holds
+/// a checksum. A page has far fewer slots than it would take to reach it.
+pub const CHECKSUMMED: u16 = 0x8000;
+
+/// Bytes holding the checksum of a page with [`CHECKSUMMED`] set, right
+/// after `num_slots` and `free_offset`. Whatever such a page holds there is
+/// replaced when it is written.
+pub const CHECKSUM_BYTES: Range<usize> = 4..8;
+
+/// Whether the page in `data` has [`CHECKSUMMED`] set.
+pub fn is_checksummed(data: &[u8]) -> bool {
+    u16::from_le_bytes([data[0], data[1]]) & CHECKSUMMED != 0
+}
+
+/// The checksum of a page's bytes.
+fn compute(data: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&data[..CHECKSUM_BYTES.start]);
+    hasher.update(&[0; CHECKSUM_BYTES.end - CHECKSUM_BYTES.start]);
+    hasher.update(&data[CHECKSUM_BYTES.end..]);
+    hasher.finalize()
+}
+
+fn stored(data: &[u8]) -> u32 {
+    u32::from_le_bytes(data[CHECKSUM_BYTES].try_into().expect("four bytes"))
+}
+
+/// Store the checksum of a page about to be written, if it holds one.
+pub fn stamp(data: &mut [u8]) {
+    if is_checksummed(data) {
+        let checksum = compute(data);
+        data[CHECKSUM_BYTES].copy_from_slice(&checksum.to_le_bytes());
+    }
+}
+
+/// Check page `page_id` of the file at `path` as read from disk.
+pub fn verify(data: &[u8], page_id: u64, path: &Path) -> DbResult<()> {
+    if !is_checksummed(data) {
+        return Ok(());
+    }
+    let (stored, computed) = (stored(data), compute(data));
+    if stored == computed || stored == 0 {
+        return Ok(());
+    }
+    Err(DbError::Corruption(format!(
+        "page {page_id} of {} failed its checksum: stored {stored:08x}, computed {computed:08x}",
+        path.display()
+    )))
+}
@@ -0,0 +1,710 @@
+//! Column-oriented storage engine, for analytics tables.
+//!
+//! A table created `USING COLUMNAR` keeps each column's values apart from
+//! the others, so a scan that uses a few columns of a wide table reads only
+//! those columns from disk (see [`HeapTable::get_columns`]).
+//!
+//! Writes go to an in-memory delta backed by an append-only `delta.log`,
+//! framed like the LSM engine's memtable log. Once the delta holds
+//! [`ColumnarOptions::stripe_rows`] writes it is sealed into stripes and the
+//! log is truncated. A stripe covers a range of row positions and is stored
+//! as a `<seq>.rows` file listing the live positions in the range, plus one
+//! `<seq>.<column>.seg` segment file per column holding that column's values
+//! for those rows. Each segment is stored in whichever lightweight encoding
+//! is smallest: plain values, runs of equal values, or a dictionary of at
+//! most [`MAX_DICTIONARY`] distinct values with a one-byte code per row.
+//!
+//! Stripes are immutable. Sealing an update or deletion of a row in a stripe
+//! rewrites the stripe under a new sequence number; the `.rows` file is
+//! written last, so it marks the stripe complete, and on open a stripe
+//! hides an older one for the same positions that a crash left behind.
+//!
+//! Like the other engines, rows are keyed by their position in insertion
+//! order, exposed as page/slot [`RecordId`]s, and updates never move a row.
+//! Stripe files are sealed with the table's key when the database is
+//! encrypted.
+
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use bincode::serde::{decode_from_slice, encode_to_vec};
+use common::crypto::{self, EncryptionKey};
+use common::{ColumnId, DbError, DbResult, PageId, RecordId, Row};
+use serde::{Deserialize, Serialize};
+use types::Value;
+
+use crate::lsm::{FRAME_HEADER_BYTES, split_frame};
+use crate::{HeapTable, TableEngine, bincode_config, lock, project};
+
+/// Slots per page of a columnar table.
+const COLUMNAR_PAGE_SLOTS: u64 = 64;
+const LOG_FILE: &str = "delta.log";
+const ROWS_EXTENSION: &str = "rows";
+const SEGMENT_EXTENSION: &str = "seg";
+/// Most distinct values a dictionary-encoded segment may hold.
+pub const MAX_DICTIONARY: usize = 256;
+
+/// When a [`ColumnarEngine`] seals its delta into stripes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColumnarOptions {
+    /// Writes buffered in a table's delta before they are sealed.
+    pub stripe_rows: usize,
+}
+
+impl Default for ColumnarOptions {
+    fn default() -> Self {
+        Self { stripe_rows: 1024 }
+    }
+}
+
+/// Stores each table column by column in a `<table>.columnar` directory.
+#[derive(Debug, Default)]
+pub struct ColumnarEngine {
+    options: ColumnarOptions,
+    stores: Mutex<HashMap<u64, Arc<Mutex<ColumnStore>>>>,
+}
+
+impl ColumnarEngine {
+    pub fn new(options: ColumnarOptions) -> Self {
+        Self {
+            options,
+            stores: Mutex::default(),
+        }
+    }
+
+    fn dir(data_dir: &Path, table_name: &str) -> PathBuf {
+        data_dir.join(format!("{table_name}.columnar"))
+    }
+}
+
+impl TableEngine for ColumnarEngine {
+    fn open(
+        &self,
+        data_dir: &Path,
+        table_name: &str,
+        table_id: u64,
+        key: Option<&EncryptionKey>,
+    ) -> DbResult<Box<dyn HeapTable>> {
+        let mut stores = lock(&self.stores)?;
+        let store = match stores.get(&table_id) {
+            Some(store) => store.clone(),
+            None => {
+                let dir = Self::dir(data_dir, table_name);
+                let store = ColumnStore::open(dir, table_id, key.cloned(), self.options)?;
+                let store = Arc::new(Mutex::new(store));
+                stores.insert(table_id, store.clone());
+                store
+            }
+        };
+        Ok(Box::new(ColumnarTable { store }))
+    }
+
+    fn drop_table(&self, data_dir: &Path, table_name: &str, table_id: u64) -> DbResult<()> {
+        lock(&self.stores)?.remove(&table_id);
+        let dir = Self::dir(data_dir, table_name);
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        Ok(())
+    }
+
+    fn durable(&self) -> bool {
+        true
+    }
+}
+
+/// A sealed stripe, as stored in its `.rows` file.
+#[derive(Debug, Serialize, Deserialize)]
+struct Stripe {
+    #[serde(skip)]
+    seq: u64,
+    /// First position the stripe covers.
+    first: u64,
+    /// Position after the last one the stripe covers.
+    end: u64,
+    /// Number of columns, and so of segment files.
+    width: usize,
+    /// Live positions in ascending order; segments hold one value per entry.
+    positions: Vec<u64>,
+}
+
+/// One column's values for the live rows of a stripe.
+#[derive(Debug, Serialize, Deserialize)]
+enum Segment {
+    Plain(Vec<Value>),
+    /// Each value with the number of consecutive rows holding it.
+    RunLength(Vec<(Value, u32)>),
+    /// The distinct values, and each row's index into them.
+    Dictionary {
+        values: Vec<Value>,
+        codes: Vec<u8>,
+    },
+}
+
+impl Segment {
+    /// Encode `values` in whichever encoding takes the fewest bytes.
+    fn encode(values: Vec<Value>) -> DbResult<Vec<u8>> {
+        // Values are compared by their encoding, so that only identical
+        // values share a run or dictionary entry
+        let encoded = values.iter().map(serialize).collect::<DbResult<Vec<_>>>()?;
+
+        let mut runs: Vec<(Value, u32)> = Vec::new();
+        for (index, value) in values.iter().enumerate() {
+            match runs.last_mut() {
+                Some((_, count)) if encoded[index - 1] == encoded[index] => *count += 1,
+                _ => runs.push((value.clone(), 1)),
+            }
+        }
+
+        let mut lookup: HashMap<&[u8], u8> = HashMap::new();
+        let mut dictionary = Vec::new();
+        let mut codes = Vec::with_capacity(values.len());
+        for (value, bytes) in values.iter().zip(&encoded) {
+            let next = dictionary.len();
+            let code = *lookup.entry(bytes.as_slice()).or_insert_with(|| {
+                dictionary.push(value.clone());
+                next as u8
+            });
+            if dictionary.len() > MAX_DICTIONARY {
+                break;
+            }
+            codes.push(code);
+        }
+
+        let mut candidates = Vec::new();
+        if runs.len() < values.len() {
+            candidates.push(serialize(&Segment::RunLength(runs))?);
+        }
+        if dictionary.len() <= MAX_DICTIONARY && dictionary.len() < values.len() {
+            candidates.push(serialize(&Segment::Dictionary {
+                values: dictionary,
+                codes,
+            })?);
+        }
+        candidates.push(serialize(&Segment::Plain(values))?);
+        Ok(candidates
+            .into_iter()
+            .min_by_key(Vec::len)
+            .expect("plain encoding is always a candidate"))
+    }
+
+    fn decode(bytes: &[u8]) -> DbResult<Vec<Value>> {
+        let segment: Segment = deserialize(bytes)?;
+        Ok(match segment {
+            Segment::Plain(values) => values,
+            Segment::RunLength(runs) => runs
+                .into_iter()
+                .flat_map(|(value, count)| std::iter::repeat_n(value, count as usize))
+                .collect(),
+            Segment::Dictionary { values, codes } => codes
+                .into_iter()
+                .map(|code| {
+                    values.get(code as usize).cloned().ok_or_else(|| {
+                        DbError::Corruption(format!("columnar segment code {code} is undefined"))
+                    })
+                })
+                .collect::<DbResult<_>>()?,
+        })
+    }
+}
+
+fn serialize<T: Serialize>(value: &T) -> DbResult<Vec<u8>> {
+    encode_to_vec(value, bincode_config())
+        .map_err(|e| DbError::Storage(format!("serialize columnar data failed: {e}")))
+}
+
+fn deserialize<T: for<'de> Deserialize<'de>>(bytes: &[u8]) -> DbResult<T> {
+    let (value, _) = decode_from_slice(bytes, bincode_config())
+        .map_err(|e| DbError::Corruption(format!("deserialize columnar data failed: {e}")))?;
+    Ok(value)
+}
+
+/// Write `bytes` to a new file at `path` and sync it.
+fn write_file(path: &Path, bytes: &[u8]) -> DbResult<()> {
+    let mut file = File::create(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// One table's delta, log and stripes.
+#[derive(Debug)]
+struct ColumnStore {
+    dir: PathBuf,
+    table_id: u64,
+    key: Option<EncryptionKey>,
+    options: ColumnarOptions,
+    /// Writes since the delta was last sealed; `None` marks a deletion.
+    delta: BTreeMap<u64, Option<Row>>,
+    log: File,
+    /// Ordered by position.
+    stripes: Vec<Stripe>,
+    next_position: u64,
+    next_seq: u64,
+    /// Decoded columns of the stripe read last, by column. Scans read a
+    /// stripe page by page, so each segment is decoded once per scan.
+    cached: Option<(u64, HashMap<usize, Vec<Value>>)>,
+}
+
+impl ColumnStore {
+    fn open(
+        dir: PathBuf,
+        table_id: u64,
+        key: Option<EncryptionKey>,
+        options: ColumnarOptions,
+    ) -> DbResult<Self> {
+        fs::create_dir_all(&dir)?;
+
+        let mut rows_files = BTreeMap::new();
+        let mut segments = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let seq = name
+                .split('.')
+                .next()
+                .and_then(|seq| seq.parse::<u64>().ok());
+            match (seq, path.extension().and_then(|ext| ext.to_str())) {
+                (Some(seq), Some(ROWS_EXTENSION)) => {
+                    rows_files.insert(seq, path);
+                }
+                (Some(seq), Some(SEGMENT_EXTENSION)) => segments.push((seq, path)),
+                // Leftover from a stripe that was never completed
+                (_, Some("tmp")) => fs::remove_file(&path)?,
+                _ => {}
+            }
+        }
+        for (seq, path) in segments {
+            if !rows_files.contains_key(&seq) {
+                fs::remove_file(&path)?;
+            }
+        }
+
+        let log = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(dir.join(LOG_FILE))?;
+        let mut store = Self {
+            dir,
+            table_id,
+            key,
+            options,
+            delta: BTreeMap::new(),
+            log,
+            stripes: Vec::new(),
+            next_position: 0,
+            next_seq: rows_files.keys().next_back().map_or(0, |seq| seq + 1),
+            cached: None,
+        };
+
+        // Newest first, so a rewritten stripe hides the one it replaced
+        let mut by_first: BTreeMap<u64, Stripe> = BTreeMap::new();
+        for (seq, path) in rows_files.into_iter().rev() {
+            let bytes = crypto::open_file(
+                store.key.as_ref(),
+                &store.aad(seq, u64::MAX),
+                fs::read(&path)?,
+            )?;
+            let mut stripe: Stripe = deserialize(&bytes)?;
+            stripe.seq = seq;
+            match by_first.entry(stripe.first) {
+                Entry::Occupied(_) => store.remove_stripe(&stripe)?,
+                Entry::Vacant(slot) => {
+                    slot.insert(stripe);
+                }
+            }
+        }
+        store.stripes = by_first.into_values().collect();
+        store.next_position = store.stripes.last().map_or(0, |stripe| stripe.end);
+        store.replay_log()?;
+        Ok(store)
+    }
+
+    /// Load the delta from the log, dropping a partially written tail.
+    fn replay_log(&mut self) -> DbResult<()> {
+        let mut bytes = Vec::new();
+        self.log.seek(SeekFrom::Start(0))?;
+        self.log.read_to_end(&mut bytes)?;
+
+        let mut offset = 0usize;
+        while let Some((position, payload)) = split_frame(&bytes[offset..]) {
+            let value = self.decode_write(position, payload)?;
+            offset += FRAME_HEADER_BYTES as usize + payload.len();
+            self.next_position = self.next_position.max(position + 1);
+            self.delta.insert(position, value);
+        }
+        if offset < bytes.len() {
+            self.log.set_len(offset as u64)?;
+        }
+        self.log.seek(SeekFrom::End(0))?;
+        Ok(())
+    }
+
+    /// Associated data binding a stripe file to its table, stripe and
+    /// column; `u64::MAX` stands for the `.rows` file.
+    fn aad(&self, seq: u64, column: u64) -> [u8; 24] {
+        let mut aad = [0u8; 24];
+        aad[..8].copy_from_slice(&self.table_id.to_le_bytes());
+        aad[8..16].copy_from_slice(&seq.to_le_bytes());
+        aad[16..].copy_from_slice(&column.to_le_bytes());
+        aad
+    }
+
+    /// Associated data binding a logged write to its table and position.
+    fn log_aad(&self, position: u64) -> [u8; 16] {
+        let mut aad = [0u8; 16];
+        aad[..8].copy_from_slice(&self.table_id.to_le_bytes());
+        aad[8..].copy_from_slice(&position.to_le_bytes());
+        aad
+    }
+
+    fn rows_path(&self, seq: u64) -> PathBuf {
+        self.dir.join(format!("{seq:08}.{ROWS_EXTENSION}"))
+    }
+
+    fn segment_path(&self, seq: u64, column: usize) -> PathBuf {
+        self.dir
+            .join(format!("{seq:08}.{column}.{SEGMENT_EXTENSION}"))
+    }
+
+    fn encode_write(&self, position: u64, value: &Option<Row>) -> DbResult<Vec<u8>> {
+        let payload = serialize(value)?;
+        let payload = match &self.key {
+            Some(key) => key.seal(&self.log_aad(position), &payload)?,
+            None => payload,
+        };
+        let mut frame = Vec::with_capacity(FRAME_HEADER_BYTES as usize + payload.len());
+        frame.extend_from_slice(&position.to_le_bytes());
+        frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&payload);
+        Ok(frame)
+    }
+
+    fn decode_write(&self, position: u64, payload: &[u8]) -> DbResult<Option<Row>> {
+        match &self.key {
+            Some(key) => deserialize(&key.open(&self.log_aad(position), payload)?),
+            None => deserialize(payload),
+        }
+    }
+
+    /// Index of the stripe covering `position`, if it is sealed.
+    fn stripe_index(&self, position: u64) -> Option<usize> {
+        let index = self
+            .stripes
+            .partition_point(|stripe| stripe.end <= position);
+        self.stripes
+            .get(index)
+            .filter(|stripe| stripe.first <= position)
+            .map(|_| index)
+    }
+
+    fn read_segment(&self, stripe: &Stripe, column: usize) -> DbResult<Vec<Value>> {
+        let path = self.segment_path(stripe.seq, column);
+        let aad = self.aad(stripe.seq, column as u64);
+        let values = Segment::decode(&crypto::open_file(
+            self.key.as_ref(),
+            &aad,
+            fs::read(&path)?,
+        )?)?;
+        if values.len() != stripe.positions.len() {
+            return Err(DbError::Corruption(format!(
+                "columnar segment {} holds {} values for {} rows",
+                path.display(),
+                values.len(),
+                stripe.positions.len()
+            )));
+        }
+        Ok(values)
+    }
+
+    /// The values of `column` in the stripe at `index`, decoded once and
+    /// kept until another stripe is read.
+    fn column(&mut self, index: usize, column: usize) -> DbResult<&[Value]> {
+        let seq = self.stripes[index].seq;
+        let cached = matches!(
+            &self.cached,
+            Some((cached, columns)) if *cached == seq && columns.contains_key(&column)
+        );
+        if !cached {
+            let values = self.read_segment(&self.stripes[index], column)?;
+            match &mut self.cached {
+                Some((cached, columns)) if *cached == seq => {
+                    columns.insert(column, values);
+                }
+                other => *other = Some((seq, HashMap::from([(column, values)]))),
+            }
+        }
+        let (_, columns) = self.cached.as_ref().expect("stripe is cached");
+        Ok(&columns[&column])
+    }
+
+    /// The row at `position`, if it is live. With `columns`, which are in
+    /// ascending order, only those are read and the others are NULL.
+    fn lookup(&mut self, position: u64, columns: Option<&[ColumnId]>) -> DbResult<Option<Row>> {
+        if let Some(value) = self.delta.get(&position) {
+            let mut value = value.clone();
+            if let Some(row) = &mut value {
+                project(row, columns);
+            }
+            return Ok(value);
+        }
+        let Some(index) = self.stripe_index(position) else {
+            return Ok(None);
+        };
+        let stripe = &self.stripes[index];
+        let Ok(slot) = stripe.positions.binary_search(&position) else {
+            return Ok(None);
+        };
+        let width = stripe.width;
+        let wanted: Vec<usize> = match columns {
+            Some(columns) => columns
+                .iter()
+                .map(|&column| column as usize)
+                .filter(|&column| column < width)
+                .collect(),
+            None => (0..width).collect(),
+        };
+        let mut values = vec![Value::Null; width];
+        for column in wanted {
+            values[column] = self.column(index, column)?[slot].clone();
+        }
+        Ok(Some(Row::new(values)))
+    }
+
+    fn write(&mut self, position: u64, mut value: Option<Row>) -> DbResult<()> {
+        if let Some(row) = &mut value {
+            row.set_rid(None);
+        }
+        let frame = self.encode_write(position, &value)?;
+        self.log.write_all(&frame)?;
+        self.log.flush()?;
+        self.delta.insert(position, value);
+        if self.delta.len() >= self.options.stripe_rows {
+            self.seal()?;
+        }
+        Ok(())
+    }
+
+    /// Move the delta into stripes and empty the log.
+    fn seal(&mut self) -> DbResult<()> {
+        let sealed_end = self.stripes.last().map_or(0, |stripe| stripe.end);
+        let mut changed: BTreeMap<usize, Vec<(u64, Option<Row>)>> = BTreeMap::new();
+        let mut fresh = BTreeMap::new();
+        for (position, value) in std::mem::take(&mut self.delta) {
+            match self.stripe_index(position) {
+                Some(index) => changed.entry(index).or_default().push((position, value)),
+                None => {
+                    if let Some(row) = value {
+                        fresh.insert(position, row);
+                    }
+                }
+            }
+        }
+
+        // Writes to sealed rows rewrite their stripes
+        for (index, writes) in changed {
+            let mut rows = self.stripe_rows(index)?;
+            for (position, value) in writes {
+                match value {
+                    Some(row) => rows.insert(position, row),
+                    None => rows.remove(&position),
+                };
+            }
+            let (first, end) = (self.stripes[index].first, self.stripes[index].end);
+            let stripe = self.write_stripe(first, end, rows)?;
+            let old = std::mem::replace(&mut self.stripes[index], stripe);
+            self.remove_stripe(&old)?;
+        }
+        if sealed_end < self.next_position {
+            let stripe = self.write_stripe(sealed_end, self.next_position, fresh)?;
+            self.stripes.push(stripe);
+        }
+
+        self.cached = None;
+        self.log.set_len(0)?;
+        self.log.seek(SeekFrom::Start(0))?;
+        Ok(())
+    }
+
+    /// Every live row of the stripe at `index`, with all of its columns.
+    fn stripe_rows(&self, index: usize) -> DbResult<BTreeMap<u64, Row>> {
+        let stripe = &self.stripes[index];
+        let mut rows: Vec<Vec<Value>> =
+            vec![Vec::with_capacity(stripe.width); stripe.positions.len()];
+        for column in 0..stripe.width {
+            for (row, value) in rows.iter_mut().zip(self.read_segment(stripe, column)?) {
+                row.push(value);
+            }
+        }
+        Ok(stripe
+            .positions
+            .iter()
+            .copied()
+            .zip(rows.into_iter().map(Row::new))
+            .collect())
+    }
+
+    /// Write `rows` as a new stripe covering `first..end`.
+    fn write_stripe(&mut self, first: u64, end: u64, rows: BTreeMap<u64, Row>) -> DbResult<Stripe> {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let width = rows.values().map(|row| row.values.len()).max().unwrap_or(0);
+        let positions: Vec<u64> = rows.keys().copied().collect();
+
+        let mut columns = vec![Vec::with_capacity(rows.len()); width];
+        for row in rows.into_values() {
+            let mut values = row.values.into_iter();
+            for column in &mut columns {
+                column.push(values.next().unwrap_or(Value::Null));
+            }
+        }
+        for (column, values) in columns.into_iter().enumerate() {
+            let aad = self.aad(seq, column as u64);
+            let bytes = crypto::seal_file(self.key.as_ref(), &aad, Segment::encode(values)?)?;
+            write_file(&self.segment_path(seq, column), &bytes)?;
+        }
+
+        let stripe = Stripe {
+            seq,
+            first,
+            end,
+            width,
+            positions,
+        };
+        let aad = self.aad(seq, u64::MAX);
+        let bytes = crypto::seal_file(self.key.as_ref(), &aad, serialize(&stripe)?)?;
+        let path = self.rows_path(seq);
+        let tmp = path.with_extension("tmp");
+        write_file(&tmp, &bytes)?;
+        fs::rename(&tmp, &path)?;
+        Ok(stripe)
+    }
+
+    /// Delete a stripe's files, its `.rows` file first so that a crash
+    /// leaves only segments that the next open removes.
+    fn remove_stripe(&self, stripe: &Stripe) -> DbResult<()> {
+        fs::remove_file(self.rows_path(stripe.seq))?;
+        for column in 0..stripe.width {
+            let path = self.segment_path(stripe.seq, column);
+            if path.exists() {
+                fs::remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Handle to one [`ColumnarEngine`] table.
+struct ColumnarTable {
+    store: Arc<Mutex<ColumnStore>>,
+}
+
+impl ColumnarTable {
+    /// Position of `rid`, failing like a heap file for unallocated pages and
+    /// slots.
+    fn position(store: &ColumnStore, rid: RecordId) -> DbResult<u64> {
+        let pages = store.next_position.div_ceil(COLUMNAR_PAGE_SLOTS);
+        if rid.page_id.0 >= pages {
+            return Err(DbError::Storage(format!(
+                "page {} not allocated",
+                rid.page_id.0
+            )));
+        }
+        let position = rid.page_id.0 * COLUMNAR_PAGE_SLOTS + rid.slot as u64;
+        if rid.slot as u64 >= COLUMNAR_PAGE_SLOTS || position >= store.next_position {
+            return Err(DbError::Storage(format!("invalid slot {}", rid.slot)));
+        }
+        Ok(position)
+    }
+
+    /// The live row at `rid`, with only `columns` read if given.
+    fn live_row(
+        store: &mut ColumnStore,
+        rid: RecordId,
+        columns: Option<&[ColumnId]>,
+    ) -> DbResult<(u64, Row)> {
+        let position = Self::position(store, rid)?;
+        match store.lookup(position, columns)? {
+            Some(mut row) => {
+                row.set_rid(Some(rid));
+                Ok((position, row))
+            }
+            None => Err(DbError::Storage("slot empty".into())),
+        }
+    }
+}
+
+impl HeapTable for ColumnarTable {
+    fn insert(&mut self, row: &Row) -> DbResult<RecordId> {
+        let mut store = lock(&self.store)?;
+        let position = store.next_position;
+        store.next_position += 1;
+        store.write(position, Some(row.clone()))?;
+        Ok(RecordId {
+            page_id: PageId(position / COLUMNAR_PAGE_SLOTS),
+            slot: (position % COLUMNAR_PAGE_SLOTS) as u16,
+        })
+    }
+
+    fn get(&mut self, rid: RecordId) -> DbResult<Row> {
+        let mut store = lock(&self.store)?;
+        Ok(Self::live_row(&mut store, rid, None)?.1)
+    }
+
+    fn get_columns(&mut self, rid: RecordId, columns: &[ColumnId]) -> DbResult<Row> {
+        let mut store = lock(&self.store)?;
+        Ok(Self::live_row(&mut store, rid, Some(columns))?.1)
+    }
+
+    fn update(&mut self, rid: RecordId, row: &Row) -> DbResult<RecordId> {
+        let mut store = lock(&self.store)?;
+        let (position, _) = Self::live_row(&mut store, rid, Some(&[]))?;
+        store.write(position, Some(row.clone()))?;
+        Ok(rid)
+    }
+
+    fn delete(&mut self, rid: RecordId) -> DbResult<()> {
+        let mut store = lock(&self.store)?;
+        let (position, _) = Self::live_row(&mut store, rid, Some(&[]))?;
+        store.write(position, None)
+    }
+
+    fn run_pages(&mut self, _start: PageId) -> DbResult<u64> {
+        Ok(lock(&self.store)?
+            .next_position
+            .div_ceil(COLUMNAR_PAGE_SLOTS))
+    }
+
+    fn scan_page(
+        &mut self,
+        page: PageId,
+        columns: Option<&[ColumnId]>,
+    ) -> DbResult<Option<Vec<Row>>> {
+        let mut store = lock(&self.store)?;
+        let start = page.0 * COLUMNAR_PAGE_SLOTS;
+        if start >= store.next_position {
+            return Ok(None);
+        }
+        let end = store.next_position.min(start + COLUMNAR_PAGE_SLOTS);
+        let mut rows = Vec::new();
+        for position in start..end {
+            let Some(mut row) = store.lookup(position, columns)? else {
+                continue;
+            };
+            row.set_rid(Some(RecordId {
+                page_id: page,
+                slot: (position - start) as u16,
+            }));
+            rows.push(row);
+        }
+        Ok(Some(rows))
+    }
+}
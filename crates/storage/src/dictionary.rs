@@ -0,0 +1,151 @@
+//! Per-page string dictionaries.
+//!
+//! Every heap page allocated by [`crate::HeapFile`] keeps a dictionary of
+//! short strings in slot 0. Rows on such a page store each short `Text`
+//! value as an index into the dictionary, so a status or country code
+//! repeated on every row is stored once per page. Rows are also encoded with
+//! variable-length integers, which keeps small numbers and lengths to a byte
+//! or two.
+//!
+//! The dictionary and the rows that refer to it share a page, so a page write
+//! always leaves them consistent. Entries are never removed: a deleted row's
+//! strings stay in the dictionary until the page is rewritten.
+//!
+//! A field can also refer to a value stored in overflow pages (see
+//! [`crate::overflow`]).
+//!
+//! Pages written before dictionaries existed have a row, or an empty slot, in
+//! slot 0, and their rows keep the fixed-width encoding. A row in that
+//! encoding starts with its column count as a little-endian `u64`; a row that
+//! fits on a page has far fewer than 65536 columns, so its bytes 2 and 3 are
+//! zero and it can never start with [`DICTIONARY_MAGIC`].
+
+use bincode::config::{self, Config};
+use bincode::serde::{decode_from_slice, encode_to_vec};
+use common::{DbError, DbResult, Row};
+use serde::{Deserialize, Serialize};
+use types::Value;
+
+use crate::overflow::OverflowRef;
+
+/// Prefix of the tuple in slot 0 that holds a page's dictionary.
+pub(crate) const DICTIONARY_MAGIC: &[u8; 4] = b"DICT";
+
+/// Longest string stored in a dictionary. Longer strings are rarely
+/// repeated and are stored in the row.
+pub(crate) const MAX_ENTRY_LEN: usize = 64;
+
+fn varint_config() -> impl Config {
+    config::standard()
+}
+
+/// A row field as written to a page with a dictionary.
+#[derive(Serialize)]
+enum FieldRef<'a> {
+    Value(&'a Value),
+    Entry(u16),
+    Overflow(OverflowRef),
+}
+
+/// A row field as read from a page with a dictionary.
+#[derive(Deserialize)]
+enum Field {
+    Value(Value),
+    Entry(u16),
+    Overflow(OverflowRef),
+}
+
+/// The strings stored once for every row on a page.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct PageDictionary {
+    entries: Vec<String>,
+}
+
+impl PageDictionary {
+    /// Decode the tuple in slot 0, or `None` if it is not a dictionary.
+    pub(crate) fn decode(tuple: &[u8]) -> DbResult<Option<Self>> {
+        let Some(body) = tuple.strip_prefix(DICTIONARY_MAGIC.as_slice()) else {
+            return Ok(None);
+        };
+        let (entries, _) = decode_from_slice(body, varint_config())
+            .map_err(|e| DbError::Storage(format!("read page dictionary failed: {e}")))?;
+        Ok(Some(Self { entries }))
+    }
+
+    pub(crate) fn encode(&self) -> DbResult<Vec<u8>> {
+        let body = encode_to_vec(&self.entries, varint_config())
+            .map_err(|e| DbError::Storage(format!("write page dictionary failed: {e}")))?;
+        Ok([DICTIONARY_MAGIC.as_slice(), &body].concat())
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Encode `row`, adding its short strings that are not yet in the
+    /// dictionary. A value with an entry in `spilled` is written as its
+    /// overflow reference.
+    pub(crate) fn encode_row(
+        &mut self,
+        row: &Row,
+        spilled: &[Option<OverflowRef>],
+    ) -> DbResult<Vec<u8>> {
+        let fields: Vec<FieldRef<'_>> = row
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| match (value, spilled.get(i)) {
+                (_, Some(Some(overflow))) => FieldRef::Overflow(*overflow),
+                (Value::Text(text), _) if text.len() <= MAX_ENTRY_LEN => self
+                    .entry(text)
+                    .map_or(FieldRef::Value(value), FieldRef::Entry),
+                (value, _) => FieldRef::Value(value),
+            })
+            .collect();
+        encode_to_vec(&fields, varint_config())
+            .map_err(|e| DbError::Storage(format!("serialize row failed: {e}")))
+    }
+
+    /// Index of `text`, adding it if there is room.
+    fn entry(&mut self, text: &str) -> Option<u16> {
+        let position = match self.entries.iter().position(|entry| entry == text) {
+            Some(position) => position,
+            None if self.entries.len() <= usize::from(u16::MAX) => {
+                self.entries.push(text.to_owned());
+                self.entries.len() - 1
+            }
+            None => return None,
+        };
+        u16::try_from(position).ok()
+    }
+
+    /// Decode a row written by [`PageDictionary::encode_row`], reading values
+    /// in overflow pages with `read_overflow`. Columns for which `wanted` is
+    /// false are returned as NULL without being looked up or read.
+    pub(crate) fn decode_row(
+        &self,
+        tuple: &[u8],
+        wanted: impl Fn(usize) -> bool,
+        mut read_overflow: impl FnMut(OverflowRef) -> DbResult<Value>,
+    ) -> DbResult<Row> {
+        let (fields, _): (Vec<Field>, usize) = decode_from_slice(tuple, varint_config())
+            .map_err(|e| DbError::Storage(format!("deserialize row failed: {e}")))?;
+        let values = fields
+            .into_iter()
+            .enumerate()
+            .map(|(column, field)| match field {
+                _ if !wanted(column) => Ok(Value::Null),
+                Field::Value(value) => Ok(value),
+                Field::Entry(index) => self
+                    .entries
+                    .get(usize::from(index))
+                    .map(|text| Value::Text(text.clone()))
+                    .ok_or_else(|| {
+                        DbError::Storage(format!("row refers to missing dictionary entry {index}"))
+                    }),
+                Field::Overflow(overflow) => read_overflow(overflow),
+            })
+            .collect::<DbResult<Vec<_>>>()?;
+        Ok(Row::new(values))
+    }
+}
@@ -0,0 +1,257 @@
+//! Storage engines: where a table's rows live.
+//!
+//! A [`TableEngine`] opens the [`HeapTable`] holding one table's rows. The
+//! slotted-page [`HeapEngine`] is the default; [`MemoryEngine`] keeps rows in
+//! process memory, [`crate::LsmEngine`] suits write-heavy tables and
+//! [`crate::ColumnarEngine`] suits analytics tables. Engines address rows
+//! with the same page/slot [`RecordId`]s and report missing pages and empty
+//! slots with the same errors as [`HeapFile`], so scans and indexes work
+//! unchanged on any engine.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use common::crypto::EncryptionKey;
+use common::hooks::{FaultInjector, no_faults};
+use common::{ColumnId, DbError, DbResult, PageId, RecordId, Row};
+
+use crate::free_space::FreeSpaceMap;
+use crate::page_io::PageIoSource;
+use crate::{HeapFile, HeapTable, lock, project};
+
+/// Slots per page of a [`MemoryEngine`] table.
+const MEMORY_PAGE_SLOTS: usize = 64;
+
+/// A storage engine that tables can be created on.
+pub trait TableEngine: Send + Sync {
+    /// Open the rows of the table `table_id`, creating its storage if needed.
+    fn open(
+        &self,
+        data_dir: &Path,
+        table_name: &str,
+        table_id: u64,
+        key: Option<&EncryptionKey>,
+    ) -> DbResult<Box<dyn HeapTable>>;
+
+    /// Remove the table's storage and every row in it.
+    fn drop_table(&self, data_dir: &Path, table_name: &str, table_id: u64) -> DbResult<()>;
+
+    /// Whether rows survive the database being closed and reopened.
+    ///
+    /// Writes to tables on a non-durable engine are not logged to the WAL.
+    fn durable(&self) -> bool;
+}
+
+/// The default engine: one [`HeapFile`] named `<table>.heap` per table.
+///
+/// Handles to the same file share its free-space map (see
+/// [`crate::free_space`]), so the map is built once rather than per handle.
+#[derive(Debug)]
+pub struct HeapEngine {
+    faults: Arc<dyn FaultInjector>,
+    free_space: Mutex<HashMap<PathBuf, Arc<Mutex<FreeSpaceMap>>>>,
+    /// Opens each file's page I/O, or `None` to read and write files
+    /// directly
+    page_io: Option<Arc<dyn PageIoSource>>,
+}
+
+impl Default for HeapEngine {
+    fn default() -> Self {
+        Self::with_faults(no_faults())
+    }
+}
+
+impl HeapEngine {
+    /// Open every heap file with [`HeapFile::with_faults`].
+    pub fn with_faults(faults: Arc<dyn FaultInjector>) -> Self {
+        Self {
+            faults,
+            free_space: Mutex::default(),
+            page_io: None,
+        }
+    }
+
+    /// Read and write every heap file's pages through I/O opened by
+    /// `source`, such as a buffer pool's (see [`crate::page_io`]).
+    pub fn with_page_io(mut self, source: Arc<dyn PageIoSource>) -> Self {
+        self.page_io = Some(source);
+        self
+    }
+
+    fn path(data_dir: &Path, table_name: &str) -> PathBuf {
+        data_dir.join(format!("{table_name}.heap"))
+    }
+}
+
+impl TableEngine for HeapEngine {
+    fn open(
+        &self,
+        data_dir: &Path,
+        table_name: &str,
+        table_id: u64,
+        key: Option<&EncryptionKey>,
+    ) -> DbResult<Box<dyn HeapTable>> {
+        let path = Self::path(data_dir, table_name);
+        let free_space = lock(&self.free_space)?
+            .entry(path.clone())
+            .or_default()
+            .clone();
+        let heap = match &self.page_io {
+            Some(source) => {
+                HeapFile::with_io(&path, table_id, key, source.open(&path, table_id, key)?)
+            }
+            None => HeapFile::open_with_key(&path, table_id, key)?,
+        };
+        Ok(Box::new(
+            heap.with_faults(self.faults.clone())
+                .with_free_space(free_space),
+        ))
+    }
+
+    fn drop_table(&self, data_dir: &Path, table_name: &str, table_id: u64) -> DbResult<()> {
+        let path = Self::path(data_dir, table_name);
+        lock(&self.free_space)?.remove(&path);
+        if let Some(source) = &self.page_io {
+            source.forget(&path, table_id)?;
+        }
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        Ok(())
+    }
+
+    fn durable(&self) -> bool {
+        true
+    }
+}
+
+type MemoryRows = Arc<Mutex<Vec<Option<Row>>>>;
+
+/// Keeps each table's rows in memory, keyed by table ID.
+///
+/// Rows are shared by every handle the engine opens, so they persist across
+/// statements, but are gone once the engine is dropped.
+#[derive(Debug, Default)]
+pub struct MemoryEngine {
+    tables: Mutex<HashMap<u64, MemoryRows>>,
+}
+
+impl TableEngine for MemoryEngine {
+    fn open(
+        &self,
+        _data_dir: &Path,
+        _table_name: &str,
+        table_id: u64,
+        _key: Option<&EncryptionKey>,
+    ) -> DbResult<Box<dyn HeapTable>> {
+        let rows = lock(&self.tables)?.entry(table_id).or_default().clone();
+        Ok(Box::new(MemoryTable { rows }))
+    }
+
+    fn drop_table(&self, _data_dir: &Path, _table_name: &str, table_id: u64) -> DbResult<()> {
+        lock(&self.tables)?.remove(&table_id);
+        Ok(())
+    }
+
+    fn durable(&self) -> bool {
+        false
+    }
+}
+
+/// Handle to one [`MemoryEngine`] table.
+///
+/// Rows are stored in insertion order; the row at position `i` has page
+/// `i / MEMORY_PAGE_SLOTS` and slot `i % MEMORY_PAGE_SLOTS`. Deleted rows
+/// leave an empty slot behind, as in a heap file.
+struct MemoryTable {
+    rows: MemoryRows,
+}
+
+impl MemoryTable {
+    fn position(rows: &[Option<Row>], rid: RecordId) -> DbResult<usize> {
+        let page = rid.page_id.0 as usize;
+        let pages = rows.len().div_ceil(MEMORY_PAGE_SLOTS);
+        if page >= pages {
+            return Err(DbError::Storage(format!("page {page} not allocated")));
+        }
+        let position = page * MEMORY_PAGE_SLOTS + rid.slot as usize;
+        if rid.slot as usize >= MEMORY_PAGE_SLOTS || position >= rows.len() {
+            return Err(DbError::Storage(format!("invalid slot {}", rid.slot)));
+        }
+        if rows[position].is_none() {
+            return Err(DbError::Storage("slot empty".into()));
+        }
+        Ok(position)
+    }
+}
+
+impl HeapTable for MemoryTable {
+    fn insert(&mut self, row: &Row) -> DbResult<RecordId> {
+        let mut rows = lock(&self.rows)?;
+        let position = rows.len();
+        let mut stored = row.clone();
+        stored.set_rid(None);
+        rows.push(Some(stored));
+        Ok(RecordId {
+            page_id: PageId((position / MEMORY_PAGE_SLOTS) as u64),
+            slot: (position % MEMORY_PAGE_SLOTS) as u16,
+        })
+    }
+
+    fn get(&mut self, rid: RecordId) -> DbResult<Row> {
+        let rows = lock(&self.rows)?;
+        let position = Self::position(&rows, rid)?;
+        let mut row = rows[position].clone().expect("position checks the slot");
+        row.set_rid(Some(rid));
+        Ok(row)
+    }
+
+    fn update(&mut self, rid: RecordId, row: &Row) -> DbResult<RecordId> {
+        let mut rows = lock(&self.rows)?;
+        let position = Self::position(&rows, rid)?;
+        let mut stored = row.clone();
+        stored.set_rid(None);
+        rows[position] = Some(stored);
+        Ok(rid)
+    }
+
+    fn delete(&mut self, rid: RecordId) -> DbResult<()> {
+        let mut rows = lock(&self.rows)?;
+        let position = Self::position(&rows, rid)?;
+        rows[position] = None;
+        Ok(())
+    }
+
+    fn run_pages(&mut self, _start: PageId) -> DbResult<u64> {
+        Ok(lock(&self.rows)?.len().div_ceil(MEMORY_PAGE_SLOTS) as u64)
+    }
+
+    fn scan_page(
+        &mut self,
+        page: PageId,
+        columns: Option<&[ColumnId]>,
+    ) -> DbResult<Option<Vec<Row>>> {
+        let rows = lock(&self.rows)?;
+        let start = page.0 as usize * MEMORY_PAGE_SLOTS;
+        if start >= rows.len() {
+            return Ok(None);
+        }
+        let end = rows.len().min(start + MEMORY_PAGE_SLOTS);
+        let mut found = Vec::new();
+        for (slot, row) in rows[start..end].iter().enumerate() {
+            let Some(row) = row else {
+                continue;
+            };
+            let mut row = row.clone();
+            project(&mut row, columns);
+            row.set_rid(Some(RecordId {
+                page_id: page,
+                slot: slot as u16,
+            }));
+            found.push(row);
+        }
+        Ok(Some(found))
+    }
+}
@@ -0,0 +1,93 @@
+//! Forwarding pointers for rows that outgrow their page.
+//!
+//! Indexes refer to rows by record ID, so an update keeps a row's ID even
+//! when the new row no longer fits on its page. [`crate::HeapFile`] then
+//! stores the row on another page as a *relocated* tuple, which names the
+//! record ID it belongs to, and replaces the row in its own slot with a
+//! *forwarding pointer* to it. Reads follow the pointer; scans pass over
+//! relocated tuples, which are reached through their pointer. A row that
+//! moves again only has its pointer rewritten, so there is never more than
+//! one hop, and VACUUM rewrites moved rows in place of their pointers.
+//!
+//! Rows are padded to at least the length of a pointer, so a pointer always
+//! fits in a row's place, and it is written only once the relocated tuple
+//! is: a crash in between leaves a relocated tuple nothing points to, which
+//! reads and scans never see.
+//!
+//! Both tuples start with [`FORWARD_TAG`], which no row on a page with a
+//! dictionary starts with: those rows begin with their column count as a
+//! variable-length integer, whose first byte is never `0xFF`, or with
+//! [`crate::row_format::LAYOUT_TAG`]. Pages without a
+//! dictionary hold neither kind (see [`crate::dictionary`]). After the tag
+//! come a kind byte, the page ID as a little-endian `u64` and the slot as a
+//! little-endian `u16`; a relocated tuple continues with the row.
+
+use common::{DbError, DbResult, PageId, RecordId};
+
+/// First byte of a forwarding pointer or relocated tuple.
+const FORWARD_TAG: u8 = 0xFF;
+
+const POINTER_KIND: u8 = b'F';
+const RELOCATED_KIND: u8 = b'R';
+
+/// Bytes before the row in a relocated tuple; also the length of a pointer.
+pub(crate) const HEADER_LEN: usize = 2 + 8 + 2;
+
+/// What a tuple on a page with a dictionary holds.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Tuple<'a> {
+    /// A row stored under its own record ID.
+    Row(&'a [u8]),
+    /// A pointer to where the slot's row was relocated.
+    Pointer(RecordId),
+    /// A row relocated from `home`, reached through the pointer there.
+    Relocated { home: RecordId, row: &'a [u8] },
+}
+
+impl<'a> Tuple<'a> {
+    pub(crate) fn decode(tuple: &'a [u8]) -> DbResult<Self> {
+        if tuple.first() != Some(&FORWARD_TAG) {
+            return Ok(Tuple::Row(tuple));
+        }
+        let corrupt = || DbError::Storage("forwarded tuple is corrupt".into());
+        if tuple.len() < HEADER_LEN {
+            return Err(corrupt());
+        }
+        let page = u64::from_le_bytes(tuple[2..10].try_into().map_err(|_| corrupt())?);
+        let slot = u16::from_le_bytes(tuple[10..12].try_into().map_err(|_| corrupt())?);
+        let rid = RecordId {
+            page_id: PageId(page),
+            slot,
+        };
+        match tuple[1] {
+            POINTER_KIND if tuple.len() == HEADER_LEN => Ok(Tuple::Pointer(rid)),
+            RELOCATED_KIND => Ok(Tuple::Relocated {
+                home: rid,
+                row: &tuple[HEADER_LEN..],
+            }),
+            _ => Err(corrupt()),
+        }
+    }
+}
+
+/// A forwarding pointer to the relocated tuple at `to`.
+pub(crate) fn pointer(to: RecordId) -> Vec<u8> {
+    header(POINTER_KIND, to)
+}
+
+/// The tuple storing `row`, which was encoded for the page it is written to,
+/// away from `home`.
+pub(crate) fn relocated(home: RecordId, row: &[u8]) -> Vec<u8> {
+    let mut tuple = header(RELOCATED_KIND, home);
+    tuple.extend_from_slice(row);
+    tuple
+}
+
+fn header(kind: u8, rid: RecordId) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN);
+    bytes.push(FORWARD_TAG);
+    bytes.push(kind);
+    bytes.extend_from_slice(&rid.page_id.0.to_le_bytes());
+    bytes.extend_from_slice(&rid.slot.to_le_bytes());
+    bytes
+}
@@ -0,0 +1,82 @@
+//! Free-space map: how full each page of a heap file is.
+//!
+//! Deleting a row only empties its slot, so the space it took is only usable
+//! once the page is compacted, which an insert into the page does as needed
+//! and which reuses the empty slot too. To find such space without reading
+//! every page, [`crate::HeapFile`] keeps the bytes used on each page in a
+//! [`FreeSpaceMap`] and inserts a row into the fullest page that still has
+//! room for it, only growing the file when no page has.
+//!
+//! The map is kept in memory. It is built by reading every page on the first
+//! insert and updated on each page write; [`crate::HeapEngine`] shares one map
+//! between all handles to a table so that it is built once per process. It
+//! is only a hint: an insert checks that the row fits on the chosen page, so
+//! a map that missed writes through another handle costs a page read rather
+//! than a wrong result.
+
+use std::collections::BTreeSet;
+
+use crate::PAGE_SIZE;
+
+/// Bytes used on each page of a heap file (see the [module docs](self)).
+#[derive(Debug, Default)]
+pub(crate) struct FreeSpaceMap {
+    /// Bytes used by page ID, or `None` until the map is built.
+    used: Option<Vec<u16>>,
+    /// The same entries ordered by bytes used, for best-fit lookups.
+    by_used: BTreeSet<(u16, u64)>,
+}
+
+impl FreeSpaceMap {
+    pub(crate) fn is_built(&self) -> bool {
+        self.used.is_some()
+    }
+
+    /// Start from the bytes used on every page, in page order.
+    pub(crate) fn build(&mut self, used: Vec<u16>) {
+        self.by_used = used
+            .iter()
+            .enumerate()
+            .map(|(page, used)| (*used, page as u64))
+            .collect();
+        self.used = Some(used);
+    }
+
+    /// Forget every page, for example once the file was rewritten. The map is
+    /// built again on the next insert.
+    pub(crate) fn clear(&mut self) {
+        self.used = None;
+        self.by_used.clear();
+    }
+
+    /// Note that `page` now has `used` bytes in use. Pages past the end of
+    /// the map are taken to be full until written. Does nothing until the map
+    /// is built.
+    pub(crate) fn record(&mut self, page: u64, used: u16) {
+        let Some(pages) = &mut self.used else {
+            return;
+        };
+        let index = page as usize;
+        if index >= pages.len() {
+            let full = PAGE_SIZE as u16;
+            for missing in pages.len()..index {
+                self.by_used.insert((full, missing as u64));
+            }
+            pages.resize(index + 1, full);
+            self.by_used.insert((full, page));
+        }
+        self.by_used.remove(&(pages[index], page));
+        pages[index] = used;
+        self.by_used.insert((used, page));
+    }
+
+    /// The fullest page on which `needed` more bytes leave at most `limit`
+    /// bytes in use, if any.
+    pub(crate) fn best_fit(&self, needed: usize, limit: usize) -> Option<u64> {
+        let most = u16::try_from(limit.checked_sub(needed)?).unwrap_or(u16::MAX);
+        self.by_used
+            .range(..=(most, u64::MAX))
+            .next_back()
+            .map(|(_, page)| *page)
+    }
+}
@@ -1,11 +1,38 @@
-use std::fs::{File, OpenOptions};
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::fs;
 use std::mem::size_of;
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
 
 use bincode::config::{self, Config};
 use bincode::serde::{decode_from_slice, encode_into_slice, encode_to_vec};
-use common::{DbError, DbResult, PageId, RecordId, Row};
+use common::compression::Compression;
+use common::crypto::EncryptionKey;
+use common::hooks::{FaultInjector, no_faults};
+use common::{ColumnId, DbError, DbResult, PageId, RecordId, Row};
+
+pub mod checksum;
+pub mod columnar;
+mod dictionary;
+pub mod engine;
+mod forward;
+mod free_space;
+pub mod lsm;
+mod overflow;
+pub mod page_io;
+pub mod row_format;
+
+use dictionary::{DICTIONARY_MAGIC, PageDictionary};
+use forward::Tuple;
+use free_space::FreeSpaceMap;
+use overflow::OverflowRef;
+use row_format::{LAYOUT_TAG, RowLayout};
+use types::Value;
+
+pub use columnar::{ColumnarEngine, ColumnarOptions};
+pub use engine::{HeapEngine, MemoryEngine, TableEngine};
+pub use lsm::{LsmEngine, LsmOptions};
+pub use page_io::{FileIo, PageIo, PageIoSource};
 
 pub const PAGE_SIZE: usize = 4096;
 const HEADER_BYTES: usize = size_of::<PageHeader>();
@@ -15,6 +42,13 @@ fn bincode_config() -> impl Config {
     config::legacy()
 }
 
+/// Lock state shared by an engine's table handles.
+fn lock<T>(mutex: &Mutex<T>) -> DbResult<MutexGuard<'_, T>> {
+    mutex
+        .lock()
+        .map_err(|_| DbError::Storage("storage engine lock poisoned".into()))
+}
+
 #[derive(Debug, Clone)]
 pub struct Page {
     pub id: u64,
@@ -27,31 +61,48 @@ impl Page {
             id,
             data: vec![0u8; PAGE_SIZE],
         };
+        // Writing the header keeps the flag
+        page.data[..2].copy_from_slice(&checksum::CHECKSUMMED.to_le_bytes());
         page.write_header(&PageHeader::default())
             .expect("initialize page header");
         page
     }
 
     fn header(&self) -> DbResult<PageHeader> {
-        let (header, read) = decode_from_slice(&self.data[..HEADER_BYTES], bincode_config())
-            .map_err(|e| DbError::Storage(format!("read page header failed: {e}")))?;
+        let (mut header, read): (PageHeader, usize) =
+            decode_from_slice(&self.data[..HEADER_BYTES], bincode_config())
+                .map_err(|e| DbError::Storage(format!("read page header failed: {e}")))?;
         debug_assert_eq!(read, HEADER_BYTES);
+        header.num_slots &= !checksum::CHECKSUMMED;
         Ok(header)
     }
 
     fn write_header(&mut self, header: &PageHeader) -> DbResult<()> {
-        let written = encode_into_slice(header, &mut self.data[..HEADER_BYTES], bincode_config())
+        let mut header = header.clone();
+        if checksum::is_checksummed(&self.data) {
+            header.num_slots |= checksum::CHECKSUMMED;
+        }
+        let written = encode_into_slice(&header, &mut self.data[..HEADER_BYTES], bincode_config())
             .map_err(|e| DbError::Storage(format!("write page header failed: {e}")))?;
         debug_assert_eq!(written, HEADER_BYTES);
         Ok(())
     }
 
-    fn slot_offset(slot_idx: u16) -> usize {
-        HEADER_BYTES + slot_idx as usize * SLOT_BYTES
+    /// Where the slots start: after the header, and after the checksum on
+    /// a page that has one (see [`checksum`]).
+    fn slots_start(&self) -> usize {
+        match checksum::is_checksummed(&self.data) {
+            true => checksum::CHECKSUM_BYTES.end,
+            false => HEADER_BYTES,
+        }
+    }
+
+    fn slot_offset(&self, slot_idx: u16) -> usize {
+        self.slots_start() + slot_idx as usize * SLOT_BYTES
     }
 
     fn read_slot(&self, slot_idx: u16) -> DbResult<Slot> {
-        let start = Self::slot_offset(slot_idx);
+        let start = self.slot_offset(slot_idx);
         let end = start + SLOT_BYTES;
         if end > PAGE_SIZE {
             return Err(DbError::Storage(format!("slot {slot_idx} out of bounds")));
@@ -63,7 +114,7 @@ impl Page {
     }
 
     fn write_slot(&mut self, slot_idx: u16, slot: &Slot) -> DbResult<()> {
-        let start = Self::slot_offset(slot_idx);
+        let start = self.slot_offset(slot_idx);
         let end = start + SLOT_BYTES;
         if end > PAGE_SIZE {
             return Err(DbError::Storage(format!("slot {slot_idx} out of bounds")));
@@ -74,9 +125,142 @@ impl Page {
         Ok(())
     }
 
+    fn tuple(&self, slot: &Slot) -> &[u8] {
+        &self.data[slot.range()]
+    }
+
+    /// What the tuple in `slot` holds. Only pages with a dictionary hold
+    /// forwarding pointers and relocated rows (see [`forward`]).
+    fn stored(&self, slot: &Slot) -> DbResult<Tuple<'_>> {
+        match self.has_dictionary()? {
+            true => Tuple::decode(self.tuple(slot)),
+            false => Ok(Tuple::Row(self.tuple(slot))),
+        }
+    }
+
+    fn has_dictionary(&self) -> DbResult<bool> {
+        if self.header()?.num_slots == 0 {
+            return Ok(false);
+        }
+        Ok(self
+            .tuple(&self.read_slot(0)?)
+            .starts_with(DICTIONARY_MAGIC))
+    }
+
+    /// The page's string dictionary, or `None` for a page written before
+    /// dictionaries existed (see [`dictionary`]).
+    fn dictionary(&self) -> DbResult<Option<PageDictionary>> {
+        if self.header()?.num_slots == 0 {
+            return Ok(None);
+        }
+        PageDictionary::decode(self.tuple(&self.read_slot(0)?))
+    }
+
+    /// Whether `slot_idx` holds the page's dictionary rather than a row.
+    fn is_dictionary_slot(&self, slot_idx: u16, slot: &Slot) -> bool {
+        slot_idx == 0 && self.tuple(slot).starts_with(DICTIONARY_MAGIC)
+    }
+
+    /// Encode `row` for this page, with `layout` if the page has a
+    /// dictionary and the layout can hold the row (see [`row_format`]), and
+    /// otherwise storing any strings it adds to the page's dictionary. Values
+    /// with an entry in `spilled` are written as references to their
+    /// overflow pages.
+    ///
+    /// Rows shorter than a forwarding pointer are padded to its length, so
+    /// that the pointer can always take the row's place (see [`forward`]).
+    ///
+    /// Returns `None`, leaving the page unchanged, if the page has no room
+    /// for the new dictionary entries, or has no dictionary and so cannot
+    /// refer to overflow pages.
+    fn encode_row(
+        &mut self,
+        row: &Row,
+        spilled: &[Option<OverflowRef>],
+        layout: Option<&RowLayout>,
+    ) -> DbResult<Option<Vec<u8>>> {
+        let Some(mut dictionary) = self.dictionary()? else {
+            if spilled.iter().any(Option::is_some) {
+                return Ok(None);
+            }
+            return encode_to_vec(row, bincode_config())
+                .map(Some)
+                .map_err(|e| DbError::Storage(format!("serialize row failed: {e}")));
+        };
+        let mut bytes = match layout_encoded(row, spilled, layout) {
+            Some(bytes) => bytes,
+            None => {
+                let entries = dictionary.len();
+                let bytes = dictionary.encode_row(row, spilled)?;
+                if dictionary.len() > entries && !self.replace_tuple(0, &dictionary.encode()?)? {
+                    return Ok(None);
+                }
+                bytes
+            }
+        };
+        bytes.resize(bytes.len().max(forward::HEADER_LEN), 0);
+        Ok(Some(bytes))
+    }
+
+    /// Decode a row stored on this page, with `layout` if it was stored with
+    /// one, reading values stored in overflow pages with `read_overflow`.
+    /// Columns for which `wanted` is false are returned as NULL.
+    fn decode_row(
+        &self,
+        tuple: &[u8],
+        layout: Option<&RowLayout>,
+        wanted: impl Fn(usize) -> bool,
+        read_overflow: impl FnMut(OverflowRef) -> DbResult<Value>,
+    ) -> DbResult<Row> {
+        if tuple.first() == Some(&LAYOUT_TAG) && self.has_dictionary()? {
+            let layout = layout.ok_or_else(|| {
+                DbError::Storage("row is stored with a layout the table was not given".into())
+            })?;
+            return layout.decode_stored(tuple, wanted);
+        }
+        match self.dictionary()? {
+            Some(dictionary) => dictionary.decode_row(tuple, wanted, read_overflow),
+            None => {
+                // Pages written before dictionaries hold whole rows
+                let (mut row, _): (Row, usize) = decode_from_slice(tuple, bincode_config())
+                    .map_err(|e| DbError::Storage(format!("deserialize row failed: {e}")))?;
+                for (column, value) in row.values.iter_mut().enumerate() {
+                    if !wanted(column) {
+                        *value = Value::Null;
+                    }
+                }
+                Ok(row)
+            }
+        }
+    }
+
+    /// Check that the slot array ends before `free_offset` and that every
+    /// tuple lies between the slot array and the end of the page.
+    fn check(&self) -> DbResult<()> {
+        let header = self.header()?;
+        let slots_end = self.slot_offset(header.num_slots);
+        let free_offset = usize::from(header.free_offset);
+        if slots_end > free_offset || free_offset > PAGE_SIZE {
+            return Err(DbError::Corruption(format!(
+                "{} slots end at byte {slots_end}, past free space at {free_offset}",
+                header.num_slots
+            )));
+        }
+        for idx in 0..header.num_slots {
+            let slot = self.read_slot(idx)?;
+            let range = slot.range();
+            if !slot.is_empty() && (range.start < slots_end || range.end > PAGE_SIZE) {
+                return Err(DbError::Corruption(format!(
+                    "slot {idx} points at bytes {range:?}, outside {slots_end}..{PAGE_SIZE}"
+                )));
+            }
+        }
+        Ok(())
+    }
+
     fn free_space(&self) -> DbResult<usize> {
         let header = self.header()?;
-        let slots_start = HEADER_BYTES + header.num_slots as usize * SLOT_BYTES;
+        let slots_start = self.slot_offset(header.num_slots);
         let free_offset = usize::from(header.free_offset);
         Ok(free_offset.saturating_sub(slots_start))
     }
@@ -86,12 +270,79 @@ impl Page {
         Ok(self.free_space()? >= needed)
     }
 
+    /// Bytes a tuple added with [`Page::insert_tuple`] can take: the free
+    /// space once deleted tuples are compacted away, less a new slot unless
+    /// an empty one can be reused. Overflow pages have none.
+    fn room(&self) -> DbResult<usize> {
+        let header = self.header()?;
+        if header.num_slots == 0 {
+            return Ok(0);
+        }
+        let mut used = self.slot_offset(header.num_slots);
+        let mut reusable = false;
+        for idx in 0..header.num_slots {
+            let slot = self.read_slot(idx)?;
+            used += usize::from(slot.len);
+            reusable |= slot.is_empty();
+        }
+        let room = PAGE_SIZE.saturating_sub(used);
+        Ok(match reusable {
+            true => room,
+            false => room.saturating_sub(SLOT_BYTES),
+        })
+    }
+
+    /// Bytes in use for the free-space map: those a new tuple cannot take.
+    fn used_bytes(&self) -> DbResult<u16> {
+        Ok((PAGE_SIZE - self.room()?) as u16)
+    }
+
+    /// Whether a tuple of `payload_len` bytes fits without filling more than
+    /// `fillfactor` percent of the page.
+    fn can_fit_within(&self, payload_len: usize, fillfactor: u8) -> DbResult<bool> {
+        let room = self.room()?;
+        let used = PAGE_SIZE - room + payload_len;
+        Ok(payload_len <= room && used * 100 <= PAGE_SIZE * usize::from(fillfactor))
+    }
+
+    /// First slot whose row was deleted, if any.
+    fn empty_slot(&self) -> DbResult<Option<u16>> {
+        for idx in 0..self.header()?.num_slots {
+            if self.read_slot(idx)?.is_empty() {
+                return Ok(Some(idx));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Add a tuple in the first empty slot, or in a new slot if there is
+    /// none, first compacting away the bytes of deleted and replaced tuples
+    /// so that the page holds no more than its live tuples.
+    fn insert_tuple(&mut self, bytes: &[u8]) -> DbResult<u16> {
+        let header = self.header()?;
+        let mut live = 0;
+        for idx in 0..header.num_slots {
+            live += usize::from(self.read_slot(idx)?.len);
+        }
+        if usize::from(header.free_offset) + live < PAGE_SIZE {
+            self.compact(None)?;
+        }
+        match self.empty_slot()? {
+            Some(idx) => match self.replace_tuple(idx, bytes)? {
+                true => Ok(idx),
+                false => Err(DbError::Storage("page full".into())),
+            },
+            None => self.append_tuple(bytes),
+        }
+    }
+
     fn append_tuple(&mut self, bytes: &[u8]) -> DbResult<u16> {
         if bytes.len() > u16::MAX as usize {
             return Err(DbError::Storage("row exceeds maximum tuple size".into()));
         }
         let mut header = self.header()?;
-        if header.num_slots == u16::MAX {
+        // The top bit of the slot count is the checksum flag
+        if header.num_slots == !checksum::CHECKSUMMED {
             return Err(DbError::Storage("slot index overflow".into()));
         }
         if !self.can_fit(bytes.len())? {
@@ -113,6 +364,65 @@ impl Page {
         self.write_header(&header)?;
         Ok(slot_idx)
     }
+
+    /// Store `bytes` as the new contents of an existing slot, placing them in
+    /// the page's free space and compacting the page first if it has to.
+    ///
+    /// Returns `false`, leaving the page unchanged, if the page cannot hold
+    /// the other tuples and `bytes` together.
+    fn replace_tuple(&mut self, slot_idx: u16, bytes: &[u8]) -> DbResult<bool> {
+        let mut header = self.header()?;
+        let slots_end = self.slot_offset(header.num_slots);
+        if usize::from(header.free_offset) < slots_end + bytes.len() {
+            let mut used = 0;
+            for idx in (0..header.num_slots).filter(|&idx| idx != slot_idx) {
+                used += usize::from(self.read_slot(idx)?.len);
+            }
+            if slots_end + used + bytes.len() > PAGE_SIZE {
+                return Ok(false);
+            }
+            self.compact(Some(slot_idx))?;
+            header = self.header()?;
+        }
+
+        let offset = usize::from(header.free_offset) - bytes.len();
+        self.data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        let slot = Slot {
+            offset: offset as u16,
+            len: bytes.len() as u16,
+        };
+        self.write_slot(slot_idx, &slot)?;
+        header.free_offset = offset as u16;
+        self.write_header(&header)?;
+        Ok(true)
+    }
+
+    /// Move the tuples together at the end of the page, leaving out the
+    /// bytes of deleted tuples and of the tuple in `except`, which is about
+    /// to be replaced. Slots keep their numbers.
+    fn compact(&mut self, except: Option<u16>) -> DbResult<()> {
+        let mut header = self.header()?;
+        let mut live = Vec::new();
+        for idx in (0..header.num_slots).filter(|&idx| Some(idx) != except) {
+            let slot = self.read_slot(idx)?;
+            if !slot.is_empty() {
+                live.push((idx, self.tuple(&slot).to_vec()));
+            }
+        }
+
+        let mut offset = PAGE_SIZE;
+        for (idx, tuple) in live {
+            offset -= tuple.len();
+            self.data[offset..offset + tuple.len()].copy_from_slice(&tuple);
+            let slot = Slot {
+                offset: offset as u16,
+                len: tuple.len() as u16,
+            };
+            self.write_slot(idx, &slot)?;
+        }
+        header.free_offset = offset as u16;
+        self.write_header(&header)
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -140,38 +450,302 @@ impl Slot {
     pub fn is_empty(&self) -> bool {
         self.len == 0
     }
+
+    fn range(&self) -> Range<usize> {
+        let start = self.offset as usize;
+        start..start + self.len as usize
+    }
 }
 
 pub trait HeapTable {
     fn insert(&mut self, row: &Row) -> DbResult<RecordId>;
     fn get(&mut self, rid: RecordId) -> DbResult<Row>;
+
+    /// Read the row at `rid` for a scan that only uses `columns`, which are
+    /// in ascending order; the other values are returned as NULL.
+    ///
+    /// Storage that can skip decoding unused values overrides this.
+    fn get_columns(&mut self, rid: RecordId, columns: &[ColumnId]) -> DbResult<Row> {
+        let mut row = self.get(rid)?;
+        project(&mut row, Some(columns));
+        Ok(row)
+    }
+
+    /// Replace the row at `rid`, returning the record ID it is stored under
+    /// afterwards. Every engine keeps `rid` where it can, but callers that
+    /// index rows must still point the indexes at the returned ID.
     fn update(&mut self, rid: RecordId, row: &Row) -> DbResult<RecordId>;
     fn delete(&mut self, rid: RecordId) -> DbResult<()>;
+
+    /// First page of each run of consecutively numbered pages holding rows.
+    ///
+    /// Scans walk each run from its first page until a page is missing.
+    /// Storage kept in one file has a single run starting at page 0.
+    fn page_runs(&self) -> Vec<PageId> {
+        vec![PageId(0)]
+    }
+
+    /// Number of pages in the run starting at `start`, one of
+    /// [`HeapTable::page_runs`].
+    fn run_pages(&mut self, start: PageId) -> DbResult<u64>;
+
+    /// The rows on `page` in slot order, each carrying its record ID, or
+    /// `None` if the page is past the end of its run. A page can hold no
+    /// rows, for example once they are deleted or if it holds part of a
+    /// large value. With `columns`, which are in ascending order, only those
+    /// are read and the others are returned as NULL, as with
+    /// [`HeapTable::get_columns`].
+    fn scan_page(
+        &mut self,
+        page: PageId,
+        columns: Option<&[ColumnId]>,
+    ) -> DbResult<Option<Vec<Row>>>;
+
+    /// Every row of the table with its record ID, run by run and page by
+    /// page. Use [`Scan::new`] to scan a `dyn HeapTable`.
+    fn scan(&mut self) -> Scan<'_, Self>
+    where
+        Self: Sized,
+    {
+        Scan::new(self)
+    }
+
+    /// How full the table's pages are, or `None` for storage that does not
+    /// keep rows in pages.
+    fn page_usage(&mut self) -> DbResult<Option<PageUsage>> {
+        Ok(None)
+    }
+
+    /// Fill pages only up to `fillfactor` percent on insert, leaving the rest
+    /// for rows to grow into on update. Storage that does not keep rows in
+    /// pages ignores it.
+    fn set_fillfactor(&mut self, _fillfactor: u8) {}
+
+    /// Compress pages with `compression` as they are written (see
+    /// [`common::compression`]). Storage that does not keep rows in pages
+    /// ignores it.
+    fn set_compression(&mut self, _compression: Compression) {}
+
+    /// Store rows with `layout`, the layout of the table's columns (see
+    /// [`row_format`]). Storage that does not keep rows in pages ignores it.
+    fn set_row_layout(&mut self, _layout: RowLayout) {}
+
+    /// Reclaim the space of deleted rows, returning the old and new record
+    /// ID of each row that moved. Storage that reuses that space by itself
+    /// moves nothing.
+    fn vacuum(&mut self) -> DbResult<Vec<(RecordId, RecordId)>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Iterator over every row of a table, returned by [`HeapTable::scan`].
+///
+/// Pages are read one at a time with [`HeapTable::scan_page`]. Iteration
+/// ends after the first error.
+pub struct Scan<'a, T: HeapTable + ?Sized> {
+    table: &'a mut T,
+    runs: std::vec::IntoIter<PageId>,
+    /// Next page to read in the current run, or `None` between runs.
+    page: Option<PageId>,
+    rows: std::vec::IntoIter<Row>,
+}
+
+impl<'a, T: HeapTable + ?Sized> Scan<'a, T> {
+    pub fn new(table: &'a mut T) -> Self {
+        let runs = table.page_runs().into_iter();
+        Self {
+            table,
+            runs,
+            page: None,
+            rows: Vec::new().into_iter(),
+        }
+    }
+
+    fn fail(&mut self, error: DbError) -> Option<DbResult<(RecordId, Row)>> {
+        self.runs = Vec::new().into_iter();
+        self.page = None;
+        Some(Err(error))
+    }
+}
+
+impl<T: HeapTable + ?Sized> Iterator for Scan<'_, T> {
+    type Item = DbResult<(RecordId, Row)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.rows.next() {
+                return match row.rid() {
+                    Some(rid) => Some(Ok((rid, row))),
+                    None => self.fail(DbError::Storage("scanned row has no record ID".into())),
+                };
+            }
+            let page = match self.page {
+                Some(page) => page,
+                None => self.runs.next()?,
+            };
+            match self.table.scan_page(page, None) {
+                Ok(Some(rows)) => {
+                    self.rows = rows.into_iter();
+                    self.page = Some(PageId(page.0 + 1));
+                }
+                Ok(None) => self.page = None,
+                Err(e) => return self.fail(e),
+            }
+        }
+    }
+}
+
+/// Set the values of `row` outside `columns`, if given, to NULL.
+pub(crate) fn project(row: &mut Row, columns: Option<&[ColumnId]>) {
+    let Some(columns) = columns else {
+        return;
+    };
+    for (column, value) in row.values.iter_mut().enumerate() {
+        if !is_wanted(columns, column) {
+            *value = Value::Null;
+        }
+    }
+}
+
+/// `row` stored with `layout`, if it has one and the row has no values in
+/// overflow pages and matches its columns.
+fn layout_encoded(
+    row: &Row,
+    spilled: &[Option<OverflowRef>],
+    layout: Option<&RowLayout>,
+) -> Option<Vec<u8>> {
+    layout
+        .filter(|_| spilled.iter().all(Option::is_none))
+        .and_then(|layout| layout.encode_stored(row))
+}
+
+/// Bytes `row` takes on a page whose dictionary has none of its strings yet,
+/// counting the entries it adds: roughly the most it takes on any page.
+fn estimated_len(
+    row: &Row,
+    spilled: &[Option<OverflowRef>],
+    layout: Option<&RowLayout>,
+) -> DbResult<usize> {
+    if let Some(bytes) = layout_encoded(row, spilled, layout) {
+        return Ok(bytes.len().max(forward::HEADER_LEN));
+    }
+    let empty = PageDictionary::default();
+    let mut dictionary = empty.clone();
+    let row_len = dictionary.encode_row(row, spilled)?.len();
+    Ok(row_len.max(forward::HEADER_LEN) + dictionary.encode()?.len() - empty.encode()?.len())
+}
+
+/// Whether `column` is among `columns`, which are in ascending order.
+fn is_wanted(columns: &[ColumnId], column: usize) -> bool {
+    ColumnId::try_from(column).is_ok_and(|column| columns.binary_search(&column).is_ok())
+}
+
+/// Space taken by a table's rows in its pages (see
+/// [`HeapTable::page_usage`]).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageUsage {
+    /// Pages holding rows. Overflow pages are not counted.
+    pub pages: u64,
+    /// Bytes of those pages taken by headers, slots, dictionaries and rows,
+    /// including deleted rows whose space has not been reclaimed.
+    pub used_bytes: u64,
+    /// Stored size of each row in bytes. A value moved to overflow pages
+    /// counts as the reference to them.
+    pub row_sizes: Vec<u64>,
 }
 
 #[derive(Debug)]
 pub struct HeapFile {
-    file: File,
+    /// Where pages are read and written (see [`page_io`])
+    io: Box<dyn PageIo>,
+    path: PathBuf,
     pub table_id: u64,
+    key: Option<EncryptionKey>,
+    faults: Arc<dyn FaultInjector>,
+    /// Percentage of a page that inserts may fill.
+    fillfactor: u8,
+    compression: Compression,
+    /// Layout of the table's rows (see [`row_format`]).
+    layout: Option<Arc<RowLayout>>,
+    /// Bytes used on each page, shared by the table's handles.
+    free_space: Arc<Mutex<FreeSpaceMap>>,
 }
 
 impl HeapFile {
     pub fn open(path: &Path, table_id: u64) -> DbResult<Self> {
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(false)
-            .open(path)?;
-        Ok(Self { file, table_id })
+        Self::open_with_key(path, table_id, None)
+    }
+
+    /// Open a heap file whose pages are encrypted with `key`.
+    ///
+    /// Each encrypted page occupies `PAGE_SIZE + SEAL_OVERHEAD` bytes on disk
+    /// and is bound to its table and page ID. With `None` this is the same as
+    /// [`HeapFile::open`].
+    pub fn open_with_key(
+        path: &Path,
+        table_id: u64,
+        key: Option<&EncryptionKey>,
+    ) -> DbResult<Self> {
+        let io = FileIo::open(path, table_id, key)?;
+        Ok(Self::with_io(path, table_id, key, Box::new(io)))
+    }
+
+    /// A heap file at `path` whose pages are read and written through `io`.
+    pub(crate) fn with_io(
+        path: &Path,
+        table_id: u64,
+        key: Option<&EncryptionKey>,
+        io: Box<dyn PageIo>,
+    ) -> Self {
+        Self {
+            io,
+            path: path.to_path_buf(),
+            table_id,
+            key: key.cloned(),
+            faults: no_faults(),
+            fillfactor: 100,
+            compression: Compression::None,
+            layout: None,
+            free_space: Arc::default(),
+        }
+    }
+
+    /// Consult `faults` before each page read and write (see
+    /// [`common::hooks`]), unless the file's [`PageIo`] checks them
+    /// elsewhere.
+    pub fn with_faults(mut self, faults: Arc<dyn FaultInjector>) -> Self {
+        self.io.set_faults(faults.clone());
+        self.faults = faults;
+        self
+    }
+
+    /// Keep track of free space in `free_space`, shared with the table's
+    /// other handles, rather than in a map of this handle's own (see
+    /// [`free_space`]).
+    pub(crate) fn with_free_space(mut self, free_space: Arc<Mutex<FreeSpaceMap>>) -> Self {
+        self.free_space = free_space;
+        self
+    }
+
+    /// Stop inserting into a page once `fillfactor` percent of it is used.
+    ///
+    /// Updates may still use the rest, so a row that grows can stay on its
+    /// page. A row is always inserted into an empty page, even one it fills
+    /// past the limit.
+    pub fn with_fillfactor(mut self, fillfactor: u8) -> Self {
+        self.fillfactor = fillfactor;
+        self
     }
 
-    fn file_len(&self) -> DbResult<u64> {
-        Ok(self.file.metadata()?.len())
+    /// Compress pages with `compression` as they are written. Pages already
+    /// in the file are read whether or not they were compressed.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.set_compression(compression);
+        self
     }
 
     fn num_pages(&self) -> DbResult<u64> {
-        Ok(self.file_len()? / PAGE_SIZE as u64)
+        self.io.num_pages()
     }
 
     fn last_page_id(&self) -> DbResult<Option<u64>> {
@@ -183,31 +757,43 @@ impl HeapFile {
         }
     }
 
+    /// A new, empty page past the end of the file, with an empty dictionary
+    /// in slot 0.
     fn allocate_page(&self) -> DbResult<Page> {
-        let id = self.num_pages()?;
-        Ok(Page::new(id))
+        let mut page = Page::new(self.num_pages()?);
+        page.append_tuple(&PageDictionary::default().encode()?)?;
+        Ok(page)
     }
 
     fn read_page(&mut self, page_id: u64) -> DbResult<Page> {
-        let mut page = Page::new(page_id);
         if page_id >= self.num_pages()? {
-            return Ok(page);
+            return Ok(Page::new(page_id));
         }
-
-        self.file
-            .seek(SeekFrom::Start(page_id * PAGE_SIZE as u64))?;
-        self.file.read_exact(&mut page.data)?;
-        Ok(page)
+        self.io.read_page(page_id)
     }
 
     fn write_page(&mut self, page: &Page) -> DbResult<()> {
-        self.file
-            .seek(SeekFrom::Start(page.id * PAGE_SIZE as u64))?;
-        self.file.write_all(&page.data)?;
-        self.file.flush()?;
+        self.io.write_page(page)?;
+
+        let mut free_space = lock(&self.free_space)?;
+        if free_space.is_built() {
+            free_space.record(page.id, page.used_bytes()?);
+        }
         Ok(())
     }
 
+    /// Read every page back, which verifies its checksum, and check its
+    /// header and slots, returning the pages that failed and why.
+    pub fn check_pages(&mut self) -> DbResult<Vec<(u64, DbError)>> {
+        let mut damaged = Vec::new();
+        for id in 0..self.num_pages()? {
+            if let Err(e) = self.read_page(id).and_then(|page| page.check()) {
+                damaged.push((id, e));
+            }
+        }
+        Ok(damaged)
+    }
+
     fn ensure_page_exists(&self, page_id: u64) -> DbResult<()> {
         if page_id >= self.num_pages()? {
             return Err(DbError::Storage(format!("page {page_id} not allocated")));
@@ -215,6 +801,141 @@ impl HeapFile {
         Ok(())
     }
 
+    /// Store each value of `row` that is too large for a page in a new chain
+    /// of overflow pages (see [`overflow`]), returning where each is stored.
+    fn spill(&mut self, row: &Row) -> DbResult<Vec<Option<OverflowRef>>> {
+        row.values
+            .iter()
+            .map(|value| match overflow::spills(value) {
+                true => self.write_overflow(value).map(Some),
+                false => Ok(None),
+            })
+            .collect()
+    }
+
+    fn write_overflow(&mut self, value: &Value) -> DbResult<OverflowRef> {
+        let bytes = overflow::encode_value(value)?;
+        let len = u32::try_from(bytes.len())
+            .map_err(|_| DbError::Storage("value exceeds maximum stored size".into()))?;
+        let first = self.num_pages()?;
+        let chunks = bytes.len().div_ceil(overflow::CHUNK_BYTES);
+        for (i, chunk) in bytes.chunks(overflow::CHUNK_BYTES).enumerate() {
+            let id = first + i as u64;
+            let next = (i + 1 < chunks).then_some(id + 1);
+            self.write_page(&overflow::chain_page(id, chunk, next)?)?;
+        }
+        Ok(OverflowRef { page: first, len })
+    }
+
+    fn read_overflow(&mut self, overflow: OverflowRef) -> DbResult<Value> {
+        // Not a missing-page error: scans take those for the end of the table
+        let corrupt = || {
+            DbError::Storage(format!(
+                "overflow chain starting at {} is corrupt",
+                overflow.page
+            ))
+        };
+        let len = overflow.len as usize;
+        let mut bytes = Vec::with_capacity(len);
+        let mut next = Some(overflow.page);
+        while bytes.len() < len {
+            let id = next.ok_or_else(corrupt)?;
+            if id >= self.num_pages()? {
+                return Err(corrupt());
+            }
+            let page = self.read_page(id)?;
+            let (chunk, after) = overflow::read_chunk(&page).ok_or_else(corrupt)?;
+            if chunk.is_empty() {
+                return Err(corrupt());
+            }
+            bytes.extend_from_slice(chunk);
+            next = after;
+        }
+        if bytes.len() != len || next.is_some() {
+            return Err(corrupt());
+        }
+        overflow::decode_value(&bytes)
+    }
+
+    /// Add `row`, whose large values have been stored at `spilled`, to the
+    /// fullest page with room for it according to the free-space map, else to
+    /// `last_page`, or to a new page if it fits on neither.
+    ///
+    /// With a `home`, the row is stored as relocated from that record ID (see
+    /// [`forward`]), and only on a page with a dictionary.
+    fn insert_spilled(
+        &mut self,
+        row: &Row,
+        last_page: Option<u64>,
+        spilled: &[Option<OverflowRef>],
+        home: Option<RecordId>,
+    ) -> DbResult<RecordId> {
+        let wrap = |bytes: Vec<u8>| match home {
+            Some(home) => forward::relocated(home, &bytes),
+            None => bytes,
+        };
+        let header_len = home.map_or(0, |_| forward::HEADER_LEN);
+        let layout = self.layout.clone();
+        let layout = layout.as_deref();
+        let limit = PAGE_SIZE * usize::from(self.fillfactor) / 100;
+        let best_fit = self
+            .free_space_map()?
+            .best_fit(estimated_len(row, spilled, layout)? + header_len, limit);
+        let mut candidates = best_fit.into_iter().chain(last_page).collect::<Vec<_>>();
+        candidates.dedup();
+
+        let mut fits = None;
+        for id in candidates {
+            let mut page = self.read_page(id)?;
+            if home.is_some() && !page.has_dictionary()? {
+                continue;
+            }
+            let used = page.used_bytes()?;
+            if let Some(bytes) = page.encode_row(row, spilled, layout)?.map(wrap)
+                && page.can_fit_within(bytes.len(), self.fillfactor)?
+            {
+                fits = Some((page, bytes));
+                break;
+            }
+            // In case the map missed writes through another handle
+            lock(&self.free_space)?.record(id, used);
+        }
+        let (mut page, bytes) = match fits {
+            Some(fits) => fits,
+            None => {
+                let mut page = self.allocate_page()?;
+                let bytes = page
+                    .encode_row(row, spilled, layout)?
+                    .map(wrap)
+                    .ok_or_else(|| DbError::Storage("page full".into()))?;
+                (page, bytes)
+            }
+        };
+
+        let slot = page.insert_tuple(&bytes)?;
+        self.write_page(&page)?;
+
+        Ok(RecordId {
+            page_id: PageId(page.id),
+            slot,
+        })
+    }
+
+    /// The free-space map, built by reading every page if no handle has yet.
+    fn free_space_map(&mut self) -> DbResult<MutexGuard<'_, FreeSpaceMap>> {
+        if !lock(&self.free_space)?.is_built() {
+            let mut used = Vec::new();
+            for id in 0..self.num_pages()? {
+                used.push(self.read_page(id)?.used_bytes()?);
+            }
+            let mut map = lock(&self.free_space)?;
+            if !map.is_built() {
+                map.build(used);
+            }
+        }
+        lock(&self.free_space)
+    }
+
     /// Validate a RecordId and return the page and slot.
     /// Returns error if page doesn't exist, slot is out of bounds, or slot is empty.
     fn validate_and_read_slot(&mut self, rid: RecordId) -> DbResult<(Page, Slot)> {
@@ -225,77 +946,313 @@ impl HeapFile {
             return Err(DbError::Storage(format!("invalid slot {}", rid.slot)));
         }
         let slot = page.read_slot(rid.slot)?;
-        if slot.is_empty() {
+        if slot.is_empty() || page.is_dictionary_slot(rid.slot, &slot) {
             return Err(DbError::Storage("slot empty".into()));
         }
         Ok((page, slot))
     }
-}
-
-impl HeapTable for HeapFile {
-    fn insert(&mut self, row: &Row) -> DbResult<RecordId> {
-        let bytes = encode_to_vec(row, bincode_config())
-            .map_err(|e| DbError::Storage(format!("serialize row failed: {e}")))?;
 
-        let mut page = match self.last_page_id()? {
-            Some(id) => self.read_page(id)?,
-            None => self.allocate_page()?,
+    /// The page holding the row stored under `rid` and where the row's
+    /// bytes are on it, following the row's forwarding pointer if it was
+    /// relocated. A relocated tuple has no record ID of its own, so reading
+    /// it directly fails as for an empty slot.
+    fn locate(&mut self, rid: RecordId) -> DbResult<(Page, Range<usize>)> {
+        let (page, slot) = self.validate_and_read_slot(rid)?;
+        let to = match page.stored(&slot)? {
+            Tuple::Row(_) => return Ok((page, slot.range())),
+            Tuple::Pointer(to) => to,
+            Tuple::Relocated { .. } => return Err(DbError::Storage("slot empty".into())),
+        };
+        let (target, slot) = self.validate_and_read_slot(to)?;
+        let row_len = match target.stored(&slot)? {
+            Tuple::Relocated { home, row } if home == rid => row.len(),
+            _ => {
+                return Err(DbError::Storage(format!(
+                    "forwarding pointer of {rid:?} is corrupt"
+                )));
+            }
         };
+        let end = slot.range().end;
+        Ok((target, end - row_len..end))
+    }
 
-        if !page.can_fit(bytes.len())? {
-            page = self.allocate_page()?;
-        }
+    /// The page of `rid` and where its row was relocated to, if it was.
+    /// Fails as [`HeapFile::locate`] does for a relocated tuple's own
+    /// position.
+    fn relocation(&mut self, rid: RecordId) -> DbResult<(Page, Option<RecordId>)> {
+        let (page, slot) = self.validate_and_read_slot(rid)?;
+        let to = match page.stored(&slot)? {
+            Tuple::Row(_) => None,
+            Tuple::Pointer(to) => Some(to),
+            Tuple::Relocated { .. } => return Err(DbError::Storage("slot empty".into())),
+        };
+        Ok((page, to))
+    }
 
-        let slot = page.append_tuple(&bytes)?;
-        self.write_page(&page)?;
+    /// Empty the slot at `rid`, leaving the tuple's bytes to be compacted
+    /// away by the next insert into the page.
+    fn clear_slot(&mut self, rid: RecordId) -> DbResult<()> {
+        let mut page = self.read_page(rid.page_id.0)?;
+        let mut slot = page.read_slot(rid.slot)?;
+        slot.len = 0;
+        page.write_slot(rid.slot, &slot)?;
+        self.write_page(&page)
+    }
+}
 
-        Ok(RecordId {
-            page_id: PageId(page.id),
-            slot,
-        })
+impl HeapTable for HeapFile {
+    fn insert(&mut self, row: &Row) -> DbResult<RecordId> {
+        // The page to add the row to is the last one before its overflow
+        // pages, if it has any
+        let last_page = self.last_page_id()?;
+        let spilled = self.spill(row)?;
+        self.insert_spilled(row, last_page, &spilled, None)
     }
 
     fn get(&mut self, rid: RecordId) -> DbResult<Row> {
-        let (page, slot) = self.validate_and_read_slot(rid)?;
-        let start = slot.offset as usize;
-        let end = start + slot.len as usize;
-        let (mut row, _): (Row, usize) =
-            decode_from_slice(&page.data[start..end], bincode_config())
-                .map_err(|e| DbError::Storage(format!("deserialize row failed: {e}")))?;
+        let (page, tuple) = self.locate(rid)?;
+        let tuple = &page.data[tuple];
+        let layout = self.layout.clone();
+        let read_overflow = |overflow| self.read_overflow(overflow);
+        let mut row = page.decode_row(tuple, layout.as_deref(), |_| true, read_overflow)?;
         row.set_rid(Some(rid));
         Ok(row)
     }
 
+    /// Values in overflow pages and the page dictionary are only read for
+    /// the wanted columns.
+    fn get_columns(&mut self, rid: RecordId, columns: &[ColumnId]) -> DbResult<Row> {
+        let (page, tuple) = self.locate(rid)?;
+        let tuple = &page.data[tuple];
+        let wanted = |column: usize| is_wanted(columns, column);
+        let layout = self.layout.clone();
+        let read_overflow = |overflow| self.read_overflow(overflow);
+        let mut row = page.decode_row(tuple, layout.as_deref(), wanted, read_overflow)?;
+        row.set_rid(Some(rid));
+        Ok(row)
+    }
+
+    /// The row keeps `rid`: it is rewritten in its slot if it fits on its
+    /// page, and otherwise relocated to another page with a forwarding
+    /// pointer left in the slot (see [`forward`]). A relocated row moves
+    /// back once it fits on its page again. Each step writes the row's new
+    /// place before freeing its old one, so a crash leaves either the old
+    /// row or the new one, and at most a relocated tuple nothing points to.
+    ///
+    /// Only rows that cannot make way for a pointer, on pages written before
+    /// dictionaries existed or stored before rows were padded to a pointer's
+    /// length, are deleted and inserted again under a new record ID.
     fn update(&mut self, rid: RecordId, row: &Row) -> DbResult<RecordId> {
-        let (mut page, mut slot) = self.validate_and_read_slot(rid)?;
-
-        let bytes = encode_to_vec(row, bincode_config())
-            .map_err(|e| DbError::Storage(format!("serialize row failed: {e}")))?;
-
-        if bytes.len() <= slot.len as usize {
-            let start = slot.offset as usize;
-            let end = start + bytes.len();
-            page.data[start..end].copy_from_slice(&bytes);
-            if bytes.len() != slot.len as usize {
-                slot.len = bytes.len() as u16;
-                page.write_slot(rid.slot, &slot)?;
+        let (mut page, moved_to) = self.relocation(rid)?;
+        let spilled = self.spill(row)?;
+        let layout = self.layout.clone();
+
+        if let Some(bytes) = page.encode_row(row, &spilled, layout.as_deref())? {
+            // Read the slot after encoding: storing new dictionary entries
+            // may have moved the row
+            let mut slot = page.read_slot(rid.slot)?;
+            let stored = if bytes.len() <= slot.len as usize {
+                let start = slot.offset as usize;
+                let end = start + bytes.len();
+                page.data[start..end].copy_from_slice(&bytes);
+                if bytes.len() != slot.len as usize {
+                    slot.len = bytes.len() as u16;
+                    page.write_slot(rid.slot, &slot)?;
+                }
+                true
+            } else {
+                // A longer row stays on its page if it can, so that the
+                // update is a single page write and a crash cannot leave the
+                // row half-moved
+                page.replace_tuple(rid.slot, &bytes)?
+            };
+            if stored {
+                self.write_page(&page)?;
+                if let Some(to) = moved_to {
+                    self.clear_slot(to)?;
+                }
+                return Ok(rid);
             }
-            self.write_page(&page)?;
-            return Ok(rid);
         }
 
-        // If the new row doesn't fit, delete and reinsert to obtain a new RID
+        if let Some(to) = moved_to {
+            // A relocated row that does not fit back on its own page stays
+            // where it is if it still fits there
+            let mut target = self.read_page(to.page_id.0)?;
+            if let Some(bytes) = target.encode_row(row, &spilled, layout.as_deref())?
+                && target.replace_tuple(to.slot, &forward::relocated(rid, &bytes))?
+            {
+                self.write_page(&target)?;
+                return Ok(rid);
+            }
+        }
+
+        // Otherwise relocate the row, then point its slot at it. A crash
+        // between the writes leaves a relocated tuple that nothing points
+        // to, which reads and scans never see.
+        let last_page = self.last_page_id()?;
+        let room = self.allocate_page()?.room()?;
+        let needed = estimated_len(row, &spilled, layout.as_deref())? + forward::HEADER_LEN;
+        if page.has_dictionary()? && needed <= room {
+            let to = self.insert_spilled(row, last_page, &spilled, Some(rid))?;
+            // Read the page again: the insert may have written to it
+            let mut page = self.read_page(rid.page_id.0)?;
+            if page.replace_tuple(rid.slot, &forward::pointer(to))? {
+                self.write_page(&page)?;
+                if let Some(old) = moved_to {
+                    self.clear_slot(old)?;
+                }
+                return Ok(rid);
+            }
+            // A row stored unpadded, on a page that cannot spare the rest of
+            // the pointer's bytes
+            self.clear_slot(to)?;
+        }
+
+        // Delete and reinsert to obtain a new RID. The WAL is not replayed,
+        // so a crash between the two writes loses the row.
         self.delete(rid)?;
-        self.insert(row)
+        self.insert_spilled(row, last_page, &spilled, None)
     }
 
     fn delete(&mut self, rid: RecordId) -> DbResult<()> {
-        let (mut page, mut slot) = self.validate_and_read_slot(rid)?;
-        slot.len = 0;
-        page.write_slot(rid.slot, &slot)?;
-        self.write_page(&page)?;
+        let (_, moved_to) = self.relocation(rid)?;
+        self.clear_slot(rid)?;
+        // Clearing the pointer first means a crash in between leaves a
+        // relocated tuple nothing points to rather than a dangling pointer
+        if let Some(to) = moved_to {
+            self.clear_slot(to)?;
+        }
         Ok(())
     }
+
+    fn run_pages(&mut self, _start: PageId) -> DbResult<u64> {
+        self.num_pages()
+    }
+
+    /// Rows are found from the page's slot count. Relocated rows are read
+    /// through their forwarding pointer, so they appear under their own
+    /// record ID and only once (see [`forward`]).
+    fn scan_page(
+        &mut self,
+        page_id: PageId,
+        columns: Option<&[ColumnId]>,
+    ) -> DbResult<Option<Vec<Row>>> {
+        if page_id.0 >= self.num_pages()? {
+            return Ok(None);
+        }
+        let page = self.read_page(page_id.0)?;
+        let layout = self.layout.clone();
+        let layout = layout.as_deref();
+        let mut rows = Vec::new();
+        for idx in 0..page.header()?.num_slots {
+            let slot = page.read_slot(idx)?;
+            if slot.is_empty() || page.is_dictionary_slot(idx, &slot) {
+                continue;
+            }
+            let rid = RecordId { page_id, slot: idx };
+            let wanted = |column| columns.is_none_or(|columns| is_wanted(columns, column));
+            let mut row = match page.stored(&slot)? {
+                Tuple::Row(tuple) => {
+                    let read_overflow = |overflow| self.read_overflow(overflow);
+                    page.decode_row(tuple, layout, wanted, read_overflow)?
+                }
+                Tuple::Pointer(_) => {
+                    let (target, tuple) = self.locate(rid)?;
+                    let tuple = &target.data[tuple];
+                    let read_overflow = |overflow| self.read_overflow(overflow);
+                    target.decode_row(tuple, layout, wanted, read_overflow)?
+                }
+                Tuple::Relocated { .. } => continue,
+            };
+            row.set_rid(Some(rid));
+            rows.push(row);
+        }
+        Ok(Some(rows))
+    }
+
+    fn page_usage(&mut self) -> DbResult<Option<PageUsage>> {
+        let mut usage = PageUsage::default();
+        for id in 0..self.num_pages()? {
+            let page = self.read_page(id)?;
+            let num_slots = page.header()?.num_slots;
+            // Overflow pages have no slots
+            if num_slots == 0 {
+                continue;
+            }
+            usage.pages += 1;
+            usage.used_bytes += (PAGE_SIZE - page.free_space()?) as u64;
+            for idx in 0..num_slots {
+                let slot = page.read_slot(idx)?;
+                if slot.is_empty() || page.is_dictionary_slot(idx, &slot) {
+                    continue;
+                }
+                // A moved row counts where it was moved to
+                if !matches!(page.stored(&slot)?, Tuple::Pointer(_)) {
+                    usage.row_sizes.push(slot.len.into());
+                }
+            }
+        }
+        Ok(Some(usage))
+    }
+
+    fn set_fillfactor(&mut self, fillfactor: u8) {
+        self.fillfactor = fillfactor;
+    }
+
+    fn set_compression(&mut self, compression: Compression) {
+        self.io.set_compression(compression);
+        self.compression = compression;
+    }
+
+    fn set_row_layout(&mut self, layout: RowLayout) {
+        self.layout = Some(Arc::new(layout));
+    }
+
+    /// Copy the live rows into a new file at the current fillfactor and
+    /// swap it in, leaving out deleted rows, overflow chains no row refers
+    /// to and pages left empty. Rows are copied in record ID order, so the
+    /// file is at most as long as before.
+    ///
+    /// The new file is written beside the heap as `<name>.vacuum.tmp` and
+    /// renamed over it once synced, so a crash leaves either the old or the
+    /// compacted rows.
+    fn vacuum(&mut self) -> DbResult<Vec<(RecordId, RecordId)>> {
+        let mut name = self.path.clone().into_os_string();
+        name.push(".vacuum.tmp");
+        let tmp_path = PathBuf::from(name);
+        if tmp_path.exists() {
+            // Left by a vacuum that did not finish
+            fs::remove_file(&tmp_path)?;
+        }
+        let mut compacted = HeapFile::open_with_key(&tmp_path, self.table_id, self.key.as_ref())?
+            .with_faults(self.faults.clone())
+            .with_fillfactor(self.fillfactor)
+            .with_compression(self.compression);
+        compacted.layout = self.layout.clone();
+
+        let mut moved = Vec::new();
+        for id in 0..self.num_pages()? {
+            // Moved rows are read through their pointer, so they are stored
+            // under their own record ID again
+            for row in self.scan_page(PageId(id), None)?.unwrap_or_default() {
+                let old = row
+                    .rid()
+                    .ok_or_else(|| DbError::Storage("scanned row has no record ID".into()))?;
+                let new = compacted.insert(&row)?;
+                if new != old {
+                    moved.push((old, new));
+                }
+            }
+        }
+
+        compacted.io.sync()?;
+        drop(compacted);
+        fs::rename(&tmp_path, &self.path)?;
+        self.io.reopen()?;
+        lock(&self.free_space)?.clear();
+        Ok(moved)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,500 @@
+//! Log-structured merge tree storage engine.
+//!
+//! Every insert, update and delete is one sequential append to the table's
+//! memtable log plus an in-memory memtable update, instead of a page
+//! read-modify-write. Once the memtable holds
+//! [`LsmOptions::memtable_entries`] writes it is written out as an immutable
+//! sorted run and the log is truncated; once there are more than
+//! [`LsmOptions::max_runs`] runs they are merged into one, discarding
+//! overwritten rows and deletion tombstones.
+//!
+//! A table lives in a `<table>.lsm` directory holding `memtable.log` and
+//! numbered `<seq>.run` files. Rows are keyed by their position in insertion
+//! order, exposed as page/slot [`RecordId`]s like the other engines, and
+//! updates never move a row. Reads check the memtable, then runs from newest
+//! to oldest; a run keeps its keys and file offsets in memory and reads rows
+//! from disk.
+//!
+//! Log and run entries are framed as `[position u64][len u32][payload]`,
+//! where the payload is the bincode-encoded `Option<Row>` (`None` marks a
+//! deletion), sealed with the table's key when the database is encrypted.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use bincode::serde::{decode_from_slice, encode_to_vec};
+use common::crypto::EncryptionKey;
+use common::{ColumnId, DbError, DbResult, PageId, RecordId, Row};
+
+use crate::{HeapTable, TableEngine, bincode_config, lock, project};
+
+/// Slots per page of an LSM table.
+const LSM_PAGE_SLOTS: u64 = 64;
+const LOG_FILE: &str = "memtable.log";
+const RUN_EXTENSION: &str = "run";
+/// Bytes before a run's first entry: next position (u64) and compacted flag (u8).
+const RUN_HEADER_BYTES: u64 = 9;
+/// Bytes before an entry's payload: position (u64) and payload length (u32).
+pub(crate) const FRAME_HEADER_BYTES: u64 = 12;
+
+/// When an [`LsmEngine`] flushes memtables and compacts runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LsmOptions {
+    /// Writes buffered in a table's memtable before it becomes a sorted run.
+    pub memtable_entries: usize,
+    /// Sorted runs a table may have before they are merged into one.
+    pub max_runs: usize,
+}
+
+impl Default for LsmOptions {
+    fn default() -> Self {
+        Self {
+            memtable_entries: 1024,
+            max_runs: 4,
+        }
+    }
+}
+
+/// Stores each table as an LSM tree in a `<table>.lsm` directory.
+#[derive(Debug, Default)]
+pub struct LsmEngine {
+    options: LsmOptions,
+    trees: Mutex<HashMap<u64, Arc<Mutex<LsmTree>>>>,
+}
+
+impl LsmEngine {
+    pub fn new(options: LsmOptions) -> Self {
+        Self {
+            options,
+            trees: Mutex::default(),
+        }
+    }
+
+    fn dir(data_dir: &Path, table_name: &str) -> PathBuf {
+        data_dir.join(format!("{table_name}.lsm"))
+    }
+}
+
+impl TableEngine for LsmEngine {
+    fn open(
+        &self,
+        data_dir: &Path,
+        table_name: &str,
+        table_id: u64,
+        key: Option<&EncryptionKey>,
+    ) -> DbResult<Box<dyn HeapTable>> {
+        let mut trees = lock(&self.trees)?;
+        let tree = match trees.get(&table_id) {
+            Some(tree) => tree.clone(),
+            None => {
+                let dir = Self::dir(data_dir, table_name);
+                let tree = LsmTree::open(dir, table_id, key.cloned(), self.options)?;
+                let tree = Arc::new(Mutex::new(tree));
+                trees.insert(table_id, tree.clone());
+                tree
+            }
+        };
+        Ok(Box::new(LsmTable { tree }))
+    }
+
+    fn drop_table(&self, data_dir: &Path, table_name: &str, table_id: u64) -> DbResult<()> {
+        lock(&self.trees)?.remove(&table_id);
+        let dir = Self::dir(data_dir, table_name);
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        Ok(())
+    }
+
+    fn durable(&self) -> bool {
+        true
+    }
+}
+
+/// One table's memtable, log and sorted runs.
+#[derive(Debug)]
+struct LsmTree {
+    dir: PathBuf,
+    table_id: u64,
+    key: Option<EncryptionKey>,
+    options: LsmOptions,
+    memtable: BTreeMap<u64, Option<Row>>,
+    log: File,
+    /// Oldest first.
+    runs: Vec<Run>,
+    next_position: u64,
+    next_run: u64,
+}
+
+impl LsmTree {
+    fn open(
+        dir: PathBuf,
+        table_id: u64,
+        key: Option<EncryptionKey>,
+        options: LsmOptions,
+    ) -> DbResult<Self> {
+        fs::create_dir_all(&dir)?;
+
+        let mut runs = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let seq = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<u64>().ok());
+            match seq {
+                Some(seq) if path.extension().is_some_and(|ext| ext == RUN_EXTENSION) => {
+                    runs.push(Run::open(path, seq)?);
+                }
+                // Leftover from a run that was never completed
+                _ if path.extension().is_some_and(|ext| ext == "tmp") => fs::remove_file(&path)?,
+                _ => {}
+            }
+        }
+        runs.sort_by_key(|run| run.seq);
+
+        // A compacted run subsumes every older run; drop any a crash left behind
+        if let Some(newest) = runs.iter().rposition(|run| run.compacted) {
+            for run in runs.drain(..newest) {
+                fs::remove_file(&run.path)?;
+            }
+        }
+
+        let log = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(dir.join(LOG_FILE))?;
+        let mut tree = Self {
+            dir,
+            table_id,
+            key,
+            options,
+            memtable: BTreeMap::new(),
+            log,
+            next_position: runs.iter().map(|run| run.next_position).max().unwrap_or(0),
+            next_run: runs.last().map_or(0, |run| run.seq + 1),
+            runs,
+        };
+        tree.replay_log()?;
+        Ok(tree)
+    }
+
+    /// Load the memtable from the log, dropping a partially written tail.
+    fn replay_log(&mut self) -> DbResult<()> {
+        let mut bytes = Vec::new();
+        self.log.seek(SeekFrom::Start(0))?;
+        self.log.read_to_end(&mut bytes)?;
+
+        let mut offset = 0usize;
+        while let Some((position, payload)) = split_frame(&bytes[offset..]) {
+            let value = self.decode(position, payload)?;
+            offset += FRAME_HEADER_BYTES as usize + payload.len();
+            self.next_position = self.next_position.max(position + 1);
+            self.memtable.insert(position, value);
+        }
+        if offset < bytes.len() {
+            self.log.set_len(offset as u64)?;
+        }
+        self.log.seek(SeekFrom::End(0))?;
+        Ok(())
+    }
+
+    fn aad(&self, position: u64) -> [u8; 16] {
+        let mut aad = [0u8; 16];
+        aad[..8].copy_from_slice(&self.table_id.to_le_bytes());
+        aad[8..].copy_from_slice(&position.to_le_bytes());
+        aad
+    }
+
+    fn encode(&self, position: u64, value: &Option<Row>) -> DbResult<Vec<u8>> {
+        let payload = encode_to_vec(value, bincode_config())
+            .map_err(|e| DbError::Storage(format!("serialize row failed: {e}")))?;
+        let payload = match &self.key {
+            Some(key) => key.seal(&self.aad(position), &payload)?,
+            None => payload,
+        };
+        let mut frame = Vec::with_capacity(FRAME_HEADER_BYTES as usize + payload.len());
+        frame.extend_from_slice(&position.to_le_bytes());
+        frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&payload);
+        Ok(frame)
+    }
+
+    fn decode(&self, position: u64, payload: &[u8]) -> DbResult<Option<Row>> {
+        let opened;
+        let payload = match &self.key {
+            Some(key) => {
+                opened = key.open(&self.aad(position), payload)?;
+                opened.as_slice()
+            }
+            None => payload,
+        };
+        let (value, _): (Option<Row>, usize) = decode_from_slice(payload, bincode_config())
+            .map_err(|e| DbError::Storage(format!("deserialize row failed: {e}")))?;
+        Ok(value)
+    }
+
+    /// The latest value written at `position`, if any.
+    fn lookup(&mut self, position: u64) -> DbResult<Option<Row>> {
+        if let Some(value) = self.memtable.get(&position) {
+            return Ok(value.clone());
+        }
+        for index in (0..self.runs.len()).rev() {
+            if let Some(payload) = self.runs[index].read(position)? {
+                return self.decode(position, &payload);
+            }
+        }
+        Ok(None)
+    }
+
+    fn write(&mut self, position: u64, mut value: Option<Row>) -> DbResult<()> {
+        if let Some(row) = &mut value {
+            row.set_rid(None);
+        }
+        let frame = self.encode(position, &value)?;
+        self.log.write_all(&frame)?;
+        self.log.flush()?;
+        self.memtable.insert(position, value);
+        if self.memtable.len() >= self.options.memtable_entries {
+            self.flush_memtable()?;
+        }
+        Ok(())
+    }
+
+    /// Write the memtable out as a new run and empty the log.
+    fn flush_memtable(&mut self) -> DbResult<()> {
+        let memtable = std::mem::take(&mut self.memtable);
+        let run = self.write_run(&memtable, false)?;
+        self.runs.push(run);
+        self.log.set_len(0)?;
+        self.log.seek(SeekFrom::Start(0))?;
+        if self.runs.len() > self.options.max_runs {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Merge every run into one, keeping only the newest live row per position.
+    fn compact(&mut self) -> DbResult<()> {
+        let mut merged = BTreeMap::new();
+        for index in 0..self.runs.len() {
+            let positions: Vec<u64> = self.runs[index].positions().collect();
+            for position in positions {
+                let payload = self.runs[index]
+                    .read(position)?
+                    .expect("position is in run");
+                merged.insert(position, self.decode(position, &payload)?);
+            }
+        }
+        merged.retain(|_, value| value.is_some());
+
+        let run = self.write_run(&merged, true)?;
+        for old in std::mem::replace(&mut self.runs, vec![run]) {
+            fs::remove_file(&old.path)?;
+        }
+        Ok(())
+    }
+
+    fn write_run(
+        &mut self,
+        entries: &BTreeMap<u64, Option<Row>>,
+        compacted: bool,
+    ) -> DbResult<Run> {
+        let seq = self.next_run;
+        self.next_run += 1;
+        let path = self.dir.join(format!("{seq:08}.{RUN_EXTENSION}"));
+        let tmp = path.with_extension("tmp");
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.next_position.to_le_bytes());
+        bytes.push(compacted as u8);
+        for (&position, value) in entries {
+            bytes.extend_from_slice(&self.encode(position, value)?);
+        }
+        let mut file = File::create(&tmp)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&tmp, &path)?;
+        Run::open(path, seq)
+    }
+}
+
+/// Split the first complete frame off `bytes`.
+pub(crate) fn split_frame(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let header = FRAME_HEADER_BYTES as usize;
+    if bytes.len() < header {
+        return None;
+    }
+    let position = u64::from_le_bytes(bytes[..8].try_into().ok()?);
+    let len = u32::from_le_bytes(bytes[8..12].try_into().ok()?) as usize;
+    let payload = bytes.get(header..header + len)?;
+    Some((position, payload))
+}
+
+/// An immutable sorted run on disk.
+#[derive(Debug)]
+struct Run {
+    seq: u64,
+    path: PathBuf,
+    file: File,
+    /// Table's next position when the run was written.
+    next_position: u64,
+    /// Whether the run is the result of merging all earlier runs.
+    compacted: bool,
+    /// Payload offset and length by position.
+    index: BTreeMap<u64, (u64, u32)>,
+}
+
+impl Run {
+    fn open(path: PathBuf, seq: u64) -> DbResult<Self> {
+        let mut file = File::open(&path)?;
+        let mut header = [0u8; RUN_HEADER_BYTES as usize];
+        file.read_exact(&mut header)?;
+        let next_position = u64::from_le_bytes(header[..8].try_into().expect("8 bytes"));
+        let compacted = header[8] != 0;
+
+        let len = file.metadata()?.len();
+        let mut index = BTreeMap::new();
+        let mut offset = RUN_HEADER_BYTES;
+        while offset < len {
+            let mut frame = [0u8; FRAME_HEADER_BYTES as usize];
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut frame).map_err(|e| match e.kind() {
+                ErrorKind::UnexpectedEof => {
+                    DbError::Storage(format!("truncated LSM run {}", path.display()))
+                }
+                _ => e.into(),
+            })?;
+            let position = u64::from_le_bytes(frame[..8].try_into().expect("8 bytes"));
+            let payload_len = u32::from_le_bytes(frame[8..].try_into().expect("4 bytes"));
+            index.insert(position, (offset + FRAME_HEADER_BYTES, payload_len));
+            offset += FRAME_HEADER_BYTES + payload_len as u64;
+        }
+
+        Ok(Self {
+            seq,
+            path,
+            file,
+            next_position,
+            compacted,
+            index,
+        })
+    }
+
+    fn positions(&self) -> impl Iterator<Item = u64> + '_ {
+        self.index.keys().copied()
+    }
+
+    /// The payload stored for `position`, if the run has one.
+    fn read(&mut self, position: u64) -> DbResult<Option<Vec<u8>>> {
+        let Some(&(offset, len)) = self.index.get(&position) else {
+            return Ok(None);
+        };
+        let mut payload = vec![0u8; len as usize];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut payload)?;
+        Ok(Some(payload))
+    }
+}
+
+/// Handle to one [`LsmEngine`] table.
+struct LsmTable {
+    tree: Arc<Mutex<LsmTree>>,
+}
+
+impl LsmTable {
+    /// Position of `rid`, failing like a heap file for unallocated pages and
+    /// slots.
+    fn position(tree: &LsmTree, rid: RecordId) -> DbResult<u64> {
+        let pages = tree.next_position.div_ceil(LSM_PAGE_SLOTS);
+        if rid.page_id.0 >= pages {
+            return Err(DbError::Storage(format!(
+                "page {} not allocated",
+                rid.page_id.0
+            )));
+        }
+        let position = rid.page_id.0 * LSM_PAGE_SLOTS + rid.slot as u64;
+        if rid.slot as u64 >= LSM_PAGE_SLOTS || position >= tree.next_position {
+            return Err(DbError::Storage(format!("invalid slot {}", rid.slot)));
+        }
+        Ok(position)
+    }
+
+    /// Position of the live row at `rid`.
+    fn live_position(tree: &mut LsmTree, rid: RecordId) -> DbResult<(u64, Row)> {
+        let position = Self::position(tree, rid)?;
+        match tree.lookup(position)? {
+            Some(row) => Ok((position, row)),
+            None => Err(DbError::Storage("slot empty".into())),
+        }
+    }
+}
+
+impl HeapTable for LsmTable {
+    fn insert(&mut self, row: &Row) -> DbResult<RecordId> {
+        let mut tree = lock(&self.tree)?;
+        let position = tree.next_position;
+        tree.next_position += 1;
+        tree.write(position, Some(row.clone()))?;
+        Ok(RecordId {
+            page_id: PageId(position / LSM_PAGE_SLOTS),
+            slot: (position % LSM_PAGE_SLOTS) as u16,
+        })
+    }
+
+    fn get(&mut self, rid: RecordId) -> DbResult<Row> {
+        let mut tree = lock(&self.tree)?;
+        let (_, mut row) = Self::live_position(&mut tree, rid)?;
+        row.set_rid(Some(rid));
+        Ok(row)
+    }
+
+    fn update(&mut self, rid: RecordId, row: &Row) -> DbResult<RecordId> {
+        let mut tree = lock(&self.tree)?;
+        let (position, _) = Self::live_position(&mut tree, rid)?;
+        tree.write(position, Some(row.clone()))?;
+        Ok(rid)
+    }
+
+    fn delete(&mut self, rid: RecordId) -> DbResult<()> {
+        let mut tree = lock(&self.tree)?;
+        let (position, _) = Self::live_position(&mut tree, rid)?;
+        tree.write(position, None)
+    }
+
+    fn run_pages(&mut self, _start: PageId) -> DbResult<u64> {
+        Ok(lock(&self.tree)?.next_position.div_ceil(LSM_PAGE_SLOTS))
+    }
+
+    fn scan_page(
+        &mut self,
+        page: PageId,
+        columns: Option<&[ColumnId]>,
+    ) -> DbResult<Option<Vec<Row>>> {
+        let mut tree = lock(&self.tree)?;
+        let start = page.0 * LSM_PAGE_SLOTS;
+        if start >= tree.next_position {
+            return Ok(None);
+        }
+        let end = tree.next_position.min(start + LSM_PAGE_SLOTS);
+        let mut rows = Vec::new();
+        for position in start..end {
+            let Some(mut row) = tree.lookup(position)? else {
+                continue;
+            };
+            project(&mut row, columns);
+            row.set_rid(Some(RecordId {
+                page_id: page,
+                slot: (position - start) as u16,
+            }));
+            rows.push(row);
+        }
+        Ok(Some(rows))
+    }
+}
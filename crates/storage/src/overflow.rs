@@ -0,0 +1,109 @@
+//! Overflow pages for values too large to keep in their row.
+//!
+//! A row has to fit on one page, so [`crate::HeapFile`] moves each `Text` or
+//! `Bytes` value longer than [`MAX_INLINE_LEN`] bytes out of its row and into
+//! a chain of overflow pages, leaving an [`OverflowRef`] in the row. The chain
+//! is written before the row, so a crash between the writes leaves pages that
+//! nothing refers to rather than a row that refers to missing pages.
+//!
+//! An overflow page has no slots and no free space: it holds no rows, rows
+//! are never added to it, and scans pass over it as they do a page whose rows
+//! were all deleted. Where its slots would start come [`OVERFLOW_MAGIC`], the
+//! ID of the next page of the chain (`u64::MAX` on the last page), the length
+//! of the part of the value on this page as a `u16`, and that part. The value
+//! is stored encoded, so the chain's total length is that of the encoding.
+//!
+//! Overflow pages are only referred to from rows on pages with a dictionary
+//! (see [`crate::dictionary`]). The pages of a value that is deleted or
+//! replaced are not reused.
+
+use bincode::serde::{decode_from_slice, encode_to_vec};
+use common::{DbError, DbResult};
+use serde::{Deserialize, Serialize};
+use types::Value;
+
+use crate::{PAGE_SIZE, Page, PageHeader, checksum};
+
+/// Prefix of the contents of an overflow page.
+const OVERFLOW_MAGIC: &[u8; 4] = b"OVFL";
+
+/// Next-page ID of the last page of a chain.
+const END_OF_CHAIN: u64 = u64::MAX;
+
+/// Bytes before the chunk on an overflow page, after its slots start.
+const CHUNK_PREFIX: usize = OVERFLOW_MAGIC.len() + 8 + 2;
+
+/// Where the chunk starts on an overflow page with a checksum.
+const CHUNK_START: usize = checksum::CHECKSUM_BYTES.end + CHUNK_PREFIX;
+
+/// Bytes of a value stored on each overflow page.
+pub(crate) const CHUNK_BYTES: usize = PAGE_SIZE - CHUNK_START;
+
+/// Longest text or byte string kept in its row.
+pub(crate) const MAX_INLINE_LEN: usize = 1024;
+
+/// Where a value moved out of its row is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct OverflowRef {
+    /// First page of the chain.
+    pub(crate) page: u64,
+    /// Length of the encoded value.
+    pub(crate) len: u32,
+}
+
+/// Whether `value` is stored in overflow pages rather than in its row.
+pub(crate) fn spills(value: &Value) -> bool {
+    match value {
+        Value::Text(text) => text.len() > MAX_INLINE_LEN,
+        Value::Bytes(bytes) => bytes.len() > MAX_INLINE_LEN,
+        _ => false,
+    }
+}
+
+pub(crate) fn encode_value(value: &Value) -> DbResult<Vec<u8>> {
+    encode_to_vec(value, bincode::config::standard())
+        .map_err(|e| DbError::Storage(format!("serialize overflow value failed: {e}")))
+}
+
+pub(crate) fn decode_value(bytes: &[u8]) -> DbResult<Value> {
+    decode_from_slice(bytes, bincode::config::standard())
+        .map(|(value, _)| value)
+        .map_err(|e| DbError::Storage(format!("deserialize overflow value failed: {e}")))
+}
+
+/// An overflow page holding `chunk`, followed in its chain by `next`.
+pub(crate) fn chain_page(id: u64, chunk: &[u8], next: Option<u64>) -> DbResult<Page> {
+    debug_assert!(chunk.len() <= CHUNK_BYTES);
+    let mut page = Page::new(id);
+    let mut at = page.slots_start();
+    page.write_header(&PageHeader {
+        num_slots: 0,
+        free_offset: at as u16,
+    })?;
+    let next = next.unwrap_or(END_OF_CHAIN);
+    for part in [
+        OVERFLOW_MAGIC.as_slice(),
+        &next.to_le_bytes(),
+        &(chunk.len() as u16).to_le_bytes(),
+        chunk,
+    ] {
+        page.data[at..at + part.len()].copy_from_slice(part);
+        at += part.len();
+    }
+    Ok(page)
+}
+
+/// The part of a value on an overflow page, and the next page of its chain,
+/// or `None` if `page` is not an overflow page.
+pub(crate) fn read_chunk(page: &Page) -> Option<(&[u8], Option<u64>)> {
+    let start = page.slots_start();
+    let body = page.data[start..].strip_prefix(OVERFLOW_MAGIC.as_slice())?;
+    let next = u64::from_le_bytes(body[..8].try_into().ok()?);
+    let len = usize::from(u16::from_le_bytes(body[8..10].try_into().ok()?));
+    let chunk = start + CHUNK_PREFIX;
+    if chunk + len > PAGE_SIZE {
+        return None;
+    }
+    let next = (next != END_OF_CHAIN).then_some(next);
+    Some((&page.data[chunk..chunk + len], next))
+}
@@ -0,0 +1,196 @@
+//! Page I/O: where a heap file's pages are read from and written to.
+//!
+//! [`crate::HeapFile`] reads and writes whole pages through a [`PageIo`].
+//! [`FileIo`] reads and writes the file directly and is what
+//! [`crate::HeapFile::open`] uses. A [`PageIoSource`] given to
+//! [`crate::HeapEngine::with_page_io`] opens the I/O of every heap file the
+//! engine opens instead, for example through a buffer pool that keeps hot
+//! pages in memory.
+//!
+//! Pages are compressed as they are written if the table asks for it (see
+//! [`common::compression`]), and a compressed page is decompressed when read
+//! whatever the setting.
+//!
+//! Whatever the I/O, a page write reaches the file before `write_page`
+//! returns. The heap orders its writes so that a crash between two of them
+//! loses no row (see [`crate::forward`] and [`crate::overflow`]); writing
+//! pages back later, in another order, would undo that.
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use common::DbResult;
+use common::compression::{self, Compression};
+use common::crypto::{EncryptionKey, SEAL_OVERHEAD};
+use common::hooks::{FaultInjector, IoOp, no_faults};
+
+use crate::{PAGE_SIZE, Page, checksum};
+
+/// Reads and writes the pages of one heap file.
+pub trait PageIo: fmt::Debug + Send {
+    /// Number of pages in the file.
+    fn num_pages(&self) -> DbResult<u64>;
+
+    /// Read page `id`, which is below [`PageIo::num_pages`].
+    fn read_page(&mut self, id: u64) -> DbResult<Page>;
+
+    /// Write `page` to the file, growing it if the page is past the end.
+    fn write_page(&mut self, page: &Page) -> DbResult<()>;
+
+    /// Make the file's writes durable.
+    fn sync(&mut self) -> DbResult<()>;
+
+    /// Open the file again after it was replaced on disk, forgetting
+    /// anything read from the old one.
+    fn reopen(&mut self) -> DbResult<()>;
+
+    /// Consult `faults` before each page read and write (see
+    /// [`common::hooks`]). I/O whose reads and writes are checked elsewhere
+    /// ignores it.
+    fn set_faults(&mut self, _faults: Arc<dyn FaultInjector>) {}
+
+    /// Compress pages with `compression` as they are written. I/O that
+    /// cannot save space by compressing ignores it.
+    fn set_compression(&mut self, _compression: Compression) {}
+}
+
+/// Opens the [`PageIo`] of each heap file a [`crate::HeapEngine`] opens.
+pub trait PageIoSource: fmt::Debug + Send + Sync {
+    /// I/O for the heap file of table `table_id` at `path`, whose pages are
+    /// encrypted with `key` if given.
+    fn open(
+        &self,
+        path: &Path,
+        table_id: u64,
+        key: Option<&EncryptionKey>,
+    ) -> DbResult<Box<dyn PageIo>>;
+
+    /// Forget the pages of the heap file at `path`, which is about to be
+    /// removed.
+    fn forget(&self, path: &Path, table_id: u64) -> DbResult<()>;
+}
+
+/// Reads and writes a heap file's pages directly.
+///
+/// Each encrypted page occupies `PAGE_SIZE + SEAL_OVERHEAD` bytes on disk
+/// and is bound to its table and page ID. Encrypted pages are not
+/// compressed, since a sealed page fills its slot whatever it holds.
+#[derive(Debug)]
+pub struct FileIo {
+    file: File,
+    path: PathBuf,
+    table_id: u64,
+    key: Option<EncryptionKey>,
+    faults: Arc<dyn FaultInjector>,
+    compression: Compression,
+}
+
+impl FileIo {
+    /// Open the heap file at `path`, creating it if it does not exist.
+    pub fn open(path: &Path, table_id: u64, key: Option<&EncryptionKey>) -> DbResult<Self> {
+        Ok(Self {
+            file: Self::open_file(path)?,
+            path: path.to_path_buf(),
+            table_id,
+            key: key.cloned(),
+            faults: no_faults(),
+            compression: Compression::None,
+        })
+    }
+
+    fn open_file(path: &Path) -> DbResult<File> {
+        Ok(OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?)
+    }
+
+    /// Size of one page on disk.
+    fn stride(&self) -> u64 {
+        match self.key {
+            Some(_) => (PAGE_SIZE + SEAL_OVERHEAD) as u64,
+            None => PAGE_SIZE as u64,
+        }
+    }
+
+    fn page_aad(&self, page_id: u64) -> [u8; 16] {
+        let mut aad = [0u8; 16];
+        aad[..8].copy_from_slice(&self.table_id.to_le_bytes());
+        aad[8..].copy_from_slice(&page_id.to_le_bytes());
+        aad
+    }
+}
+
+impl PageIo for FileIo {
+    fn num_pages(&self) -> DbResult<u64> {
+        Ok(self.file.metadata()?.len() / self.stride())
+    }
+
+    fn read_page(&mut self, id: u64) -> DbResult<Page> {
+        self.faults.check(IoOp::PageRead)?;
+        let mut page = Page::new(id);
+        self.file.seek(SeekFrom::Start(id * self.stride()))?;
+        match &self.key {
+            Some(key) => {
+                let mut sealed = vec![0u8; PAGE_SIZE + SEAL_OVERHEAD];
+                self.file.read_exact(&mut sealed)?;
+                page.data = key.open(&self.page_aad(id), &sealed)?;
+            }
+            None => {
+                self.file.read_exact(&mut page.data)?;
+                page.data = compression::decompress_page(page.data)?;
+            }
+        }
+        checksum::verify(&page.data, id, &self.path)?;
+        Ok(page)
+    }
+
+    fn write_page(&mut self, page: &Page) -> DbResult<()> {
+        self.faults.check(IoOp::PageWrite)?;
+        let mut data = page.data.clone();
+        checksum::stamp(&mut data);
+        self.file.seek(SeekFrom::Start(page.id * self.stride()))?;
+        match &self.key {
+            Some(key) => {
+                let sealed = key.seal(&self.page_aad(page.id), &data)?;
+                self.file.write_all(&sealed)?;
+            }
+            None => match compression::compress_page(self.compression, &data)? {
+                Some(frame) => {
+                    self.file.write_all(&frame)?;
+                    // Keep the file a whole number of slots long when the
+                    // last page is compressed
+                    let end = (page.id + 1) * self.stride();
+                    if self.file.metadata()?.len() < end {
+                        self.file.set_len(end)?;
+                    }
+                }
+                None => self.file.write_all(&data)?,
+            },
+        }
+        self.file.flush()?;
+        Ok(())
+    }
+
+    fn sync(&mut self) -> DbResult<()> {
+        Ok(self.file.sync_all()?)
+    }
+
+    fn reopen(&mut self) -> DbResult<()> {
+        self.file = Self::open_file(&self.path)?;
+        Ok(())
+    }
+
+    fn set_faults(&mut self, faults: Arc<dyn FaultInjector>) {
+        self.faults = faults;
+    }
+
+    fn set_compression(&mut self, compression: Compression) {
+        self.compression = compression;
+    }
+}
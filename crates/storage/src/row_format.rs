@@ -0,0 +1,386 @@
+//! Compact, schema-aware row encoding.
+//!
+//! Rows encoded with bincode carry an enum tag per value and a length per
+//! string, and must be decoded front to back to reach any one column. A
+//! [`RowLayout`] is built from a table's column types and lays a row out
+//! the way its schema allows:
+//!
+//! ```text
+//! [column count: u16][null bitmap][fixed-width slots][variable-length tail]
+//! ```
+//!
+//! - The null bitmap has a bit per column, set when the column is NULL.
+//! - Every column has a slot at an offset fixed by the layout: 8 bytes for
+//!   `INT`, `FLOAT` and `TIMESTAMP`, 4 for `DATE`, 1 for `BOOL` and 17 for
+//!   `DECIMAL` (its units and scale). A NULL column's slot is zero.
+//! - `TEXT` and `BYTES` columns have a 4-byte slot holding where their bytes
+//!   end in the tail; they start where the previous variable-length column
+//!   ends.
+//!
+//! All numbers are little-endian. Types are not stored, so a tuple only
+//! decodes with the layout that encoded it; the column count is stored to
+//! catch a tuple decoded with the layout of a table that has since gained
+//! or lost columns. Any one column can be read with
+//! [`RowLayout::decode_column`] without decoding the others.
+//!
+//! A [`crate::HeapFile`] given its table's layout with
+//! [`crate::HeapTable::set_row_layout`] stores rows this way on pages with a
+//! dictionary, after [`LAYOUT_TAG`]. Rows the layout cannot hold, such as
+//! those with values in overflow pages, are stored as before, and rows of
+//! either kind are read back. A row stored before the table gained columns
+//! decodes with the layout of its first columns.
+//!
+//! `cargo bench -p storage --bench row_format` compares decoding a wide
+//! table's rows with bincode.
+
+use std::borrow::Cow;
+
+use common::{DbError, DbResult, Row};
+use types::decimal::MAX_SCALE;
+use types::{Decimal, SqlType, Value};
+
+/// Bytes of the column count that starts every tuple.
+const COUNT_BYTES: usize = 2;
+
+/// First byte of a row stored with a [`RowLayout`] on a heap page. Other rows
+/// on pages with a dictionary begin with their column count as a
+/// variable-length integer, whose first byte is at most `0xFD`.
+pub(crate) const LAYOUT_TAG: u8 = 0xFE;
+
+/// How a column's value is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Int,
+    Bool,
+    Float,
+    Date,
+    Timestamp,
+    Decimal,
+    Text,
+    Bytes,
+}
+
+impl Kind {
+    fn of(ty: &SqlType) -> Self {
+        match ty {
+            SqlType::Int => Kind::Int,
+            SqlType::Bool => Kind::Bool,
+            SqlType::Float => Kind::Float,
+            SqlType::Date => Kind::Date,
+            SqlType::Timestamp => Kind::Timestamp,
+            SqlType::Decimal { .. } => Kind::Decimal,
+            SqlType::Text => Kind::Text,
+            SqlType::Bytes => Kind::Bytes,
+        }
+    }
+
+    /// Bytes of the column's slot.
+    fn width(self) -> usize {
+        match self {
+            Kind::Int | Kind::Float | Kind::Timestamp => 8,
+            Kind::Date | Kind::Text | Kind::Bytes => 4,
+            Kind::Bool => 1,
+            Kind::Decimal => 17,
+        }
+    }
+
+    fn is_variable(self) -> bool {
+        matches!(self, Kind::Text | Kind::Bytes)
+    }
+}
+
+/// Where a column is stored in a tuple.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ColumnSlot {
+    kind: Kind,
+    /// Offset of the column's slot from the start of the tuple.
+    offset: usize,
+    /// For a variable-length column, the slot offset of the previous one,
+    /// whose end is where this column's bytes start.
+    previous: Option<usize>,
+}
+
+/// Encodes and decodes the rows of a table with known column types (see the
+/// [module docs](self)).
+///
+/// # Example
+/// ```
+/// use common::Row;
+/// use storage::row_format::RowLayout;
+/// use types::{SqlType, Value};
+///
+/// let layout = RowLayout::new(&[SqlType::Int, SqlType::Text, SqlType::Bool]);
+/// let row = Row::new(vec![Value::Int(7), Value::Text("alice".into()), Value::Null]);
+/// let tuple = layout.encode(&row).unwrap();
+/// assert_eq!(layout.decode(&tuple).unwrap().values, row.values);
+/// assert_eq!(layout.decode_column(&tuple, 1).unwrap(), Value::Text("alice".into()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowLayout {
+    columns: Vec<ColumnSlot>,
+    /// Offset of the variable-length tail: the bytes every tuple has.
+    fixed_bytes: usize,
+}
+
+impl RowLayout {
+    /// The layout for columns of the given types, e.g. those of a
+    /// `TableSchema`'s columns in order.
+    pub fn new<'a>(types: impl IntoIterator<Item = &'a SqlType>) -> Self {
+        Self::of_kinds(types.into_iter().map(Kind::of).collect())
+    }
+
+    fn of_kinds(kinds: Vec<Kind>) -> Self {
+        let mut offset = COUNT_BYTES + kinds.len().div_ceil(8);
+        let mut previous = None;
+        let columns = kinds
+            .into_iter()
+            .map(|kind| {
+                let slot = ColumnSlot {
+                    kind,
+                    offset,
+                    previous: previous.filter(|_| kind.is_variable()),
+                };
+                if kind.is_variable() {
+                    previous = Some(offset);
+                }
+                offset += kind.width();
+                slot
+            })
+            .collect();
+        Self {
+            columns,
+            fixed_bytes: offset,
+        }
+    }
+
+    /// Number of columns in the layout.
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// The layout `tuple` was encoded with: this one, or that of its first
+    /// columns for a tuple stored before the table gained the others.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DbError::Storage` if the tuple has more columns than the
+    /// layout.
+    pub(crate) fn of_tuple(&self, tuple: &[u8]) -> DbResult<Cow<'_, Self>> {
+        let count = match tuple {
+            [low, high, ..] => usize::from(u16::from_le_bytes([*low, *high])),
+            _ => return Err(DbError::Storage("tuple has no column count".into())),
+        };
+        match count {
+            count if count == self.columns.len() => Ok(Cow::Borrowed(self)),
+            count if count < self.columns.len() => Ok(Cow::Owned(Self::of_kinds(
+                self.columns[..count].iter().map(|slot| slot.kind).collect(),
+            ))),
+            count => Err(DbError::Storage(format!(
+                "tuple has {count} columns but its layout has {}",
+                self.columns.len()
+            ))),
+        }
+    }
+
+    /// Encode `row`, whose values must match the layout's column types.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DbError::Storage` if the row has the wrong number of values,
+    /// or a value that is neither NULL nor of its column's type.
+    pub fn encode(&self, row: &Row) -> DbResult<Vec<u8>> {
+        if row.values.len() != self.columns.len() {
+            return Err(DbError::Storage(format!(
+                "row has {} values but its layout has {} columns",
+                row.values.len(),
+                self.columns.len()
+            )));
+        }
+        let mut tuple = vec![0; self.fixed_bytes];
+        tuple[..COUNT_BYTES].copy_from_slice(&(self.columns.len() as u16).to_le_bytes());
+        for (column, (slot, value)) in self.columns.iter().zip(&row.values).enumerate() {
+            let bytes = match (slot.kind, value) {
+                (_, Value::Null) => {
+                    tuple[COUNT_BYTES + column / 8] |= 1 << (column % 8);
+                    match slot.kind.is_variable() {
+                        true => Vec::new(),
+                        false => continue,
+                    }
+                }
+                (Kind::Int, Value::Int(v)) | (Kind::Timestamp, Value::Timestamp(v)) => {
+                    v.to_le_bytes().to_vec()
+                }
+                (Kind::Bool, Value::Bool(b)) => vec![u8::from(*b)],
+                (Kind::Float, Value::Float(f)) => f.to_le_bytes().to_vec(),
+                (Kind::Date, Value::Date(days)) => days.to_le_bytes().to_vec(),
+                (Kind::Decimal, Value::Decimal(d)) => {
+                    let mut bytes = d.units().to_le_bytes().to_vec();
+                    bytes.push(d.scale());
+                    bytes
+                }
+                (Kind::Text, Value::Text(text)) => text.as_bytes().to_vec(),
+                (Kind::Bytes, Value::Bytes(bytes)) => bytes.clone(),
+                (kind, value) => {
+                    return Err(DbError::Storage(format!(
+                        "column {column} is stored as {kind:?} but holds {value:?}"
+                    )));
+                }
+            };
+            if slot.kind.is_variable() {
+                tuple.extend_from_slice(&bytes);
+                let end = u32::try_from(tuple.len() - self.fixed_bytes)
+                    .map_err(|_| DbError::Storage("row too large to encode".into()))?;
+                put(&mut tuple, slot.offset, &end.to_le_bytes());
+            } else {
+                put(&mut tuple, slot.offset, &bytes);
+            }
+        }
+        Ok(tuple)
+    }
+
+    /// Decode a tuple written by [`RowLayout::encode`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DbError::Storage` if the tuple was not written with a layout
+    /// of this many columns, or is truncated or damaged.
+    pub fn decode(&self, tuple: &[u8]) -> DbResult<Row> {
+        self.check(tuple)?;
+        let mut values = Vec::with_capacity(self.columns.len());
+        for column in 0..self.columns.len() {
+            values.push(self.read(tuple, column)?);
+        }
+        Ok(Row::new(values))
+    }
+
+    /// Decode one column of a tuple written by [`RowLayout::encode`], without
+    /// decoding the others.
+    ///
+    /// # Errors
+    ///
+    /// As for [`RowLayout::decode`], and if `column` is out of range.
+    pub fn decode_column(&self, tuple: &[u8], column: usize) -> DbResult<Value> {
+        self.check(tuple)?;
+        if column >= self.columns.len() {
+            return Err(DbError::Storage(format!(
+                "column {column} out of range for a layout of {} columns",
+                self.columns.len()
+            )));
+        }
+        self.read(tuple, column)
+    }
+
+    /// `row` as stored on a heap page, after [`LAYOUT_TAG`], or `None` if
+    /// it does not match the layout's columns.
+    pub(crate) fn encode_stored(&self, row: &Row) -> Option<Vec<u8>> {
+        let mut stored = vec![LAYOUT_TAG];
+        stored.extend(self.encode(row).ok()?);
+        Some(stored)
+    }
+
+    /// Decode a row stored on a heap page by [`RowLayout::encode_stored`],
+    /// possibly before the table gained columns. Columns for which `wanted`
+    /// is false are returned as NULL without being read.
+    pub(crate) fn decode_stored(
+        &self,
+        stored: &[u8],
+        wanted: impl Fn(usize) -> bool,
+    ) -> DbResult<Row> {
+        let tuple = stored.strip_prefix(&[LAYOUT_TAG]).unwrap_or(stored);
+        let layout = self.of_tuple(tuple)?;
+        layout.check(tuple)?;
+        let values = (0..layout.len())
+            .map(|column| match wanted(column) {
+                true => layout.read(tuple, column),
+                false => Ok(Value::Null),
+            })
+            .collect::<DbResult<_>>()?;
+        Ok(Row::new(values))
+    }
+
+    /// Check a tuple's column count and length before reading its slots.
+    fn check(&self, tuple: &[u8]) -> DbResult<()> {
+        if tuple.len() < self.fixed_bytes {
+            return Err(DbError::Storage(format!(
+                "tuple of {} bytes is shorter than its layout's {}",
+                tuple.len(),
+                self.fixed_bytes
+            )));
+        }
+        let count = u16::from_le_bytes([tuple[0], tuple[1]]);
+        if usize::from(count) != self.columns.len() {
+            return Err(DbError::Storage(format!(
+                "tuple has {count} columns but its layout has {}",
+                self.columns.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Read a column of a tuple that passed [`RowLayout::check`].
+    ///
+    /// Inlined into [`RowLayout::decode`]'s loop, which is several times
+    /// slower when it calls this.
+    #[inline(always)]
+    fn read(&self, tuple: &[u8], column: usize) -> DbResult<Value> {
+        if tuple[COUNT_BYTES + column / 8] & (1 << (column % 8)) != 0 {
+            return Ok(Value::Null);
+        }
+        let slot = self.columns[column];
+        let at = |width: usize| &tuple[slot.offset..slot.offset + width];
+        Ok(match slot.kind {
+            Kind::Int => Value::Int(i64::from_le_bytes(array(at(8)))),
+            Kind::Timestamp => Value::Timestamp(i64::from_le_bytes(array(at(8)))),
+            Kind::Float => Value::Float(f64::from_le_bytes(array(at(8)))),
+            Kind::Date => Value::Date(i32::from_le_bytes(array(at(4)))),
+            Kind::Bool => Value::Bool(at(1)[0] != 0),
+            Kind::Decimal => {
+                let bytes = at(17);
+                let scale = bytes[16];
+                if scale > MAX_SCALE {
+                    return Err(DbError::Storage(format!(
+                        "column {column} has decimal scale {scale}"
+                    )));
+                }
+                Value::Decimal(Decimal::new(
+                    i128::from_le_bytes(array(&bytes[..16])),
+                    scale,
+                ))
+            }
+            Kind::Text => {
+                let bytes = self.variable(tuple, slot)?;
+                let text = std::str::from_utf8(bytes).map_err(|e| {
+                    DbError::Storage(format!("column {column} is not valid UTF-8: {e}"))
+                })?;
+                Value::Text(text.to_owned())
+            }
+            Kind::Bytes => Value::Bytes(self.variable(tuple, slot)?.to_vec()),
+        })
+    }
+
+    /// The tail bytes of a variable-length column.
+    fn variable<'t>(&self, tuple: &'t [u8], slot: ColumnSlot) -> DbResult<&'t [u8]> {
+        let end_at = |offset: usize| u32::from_le_bytes(array(&tuple[offset..offset + 4])) as usize;
+        let start = slot.previous.map_or(0, end_at);
+        let end = end_at(slot.offset);
+        let tail = &tuple[self.fixed_bytes..];
+        tail.get(start..end).ok_or_else(|| {
+            DbError::Storage(format!(
+                "value at {start}..{end} is outside the tuple's {} tail bytes",
+                tail.len()
+            ))
+        })
+    }
+}
+
+fn put(tuple: &mut [u8], offset: usize, bytes: &[u8]) {
+    tuple[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+fn array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    bytes.try_into().expect("slot width matches its kind")
+}
@@ -1,7 +1,19 @@
 use super::*;
+use common::crypto::SEAL_OVERHEAD;
 use tempfile::tempdir;
 use types::Value;
 
+/// A row of `len` copies of `c`, split into values short enough to be kept
+/// in the row rather than in overflow pages.
+fn inline_row(c: char, len: usize) -> Row {
+    let values = (0..len)
+        .step_by(overflow::MAX_INLINE_LEN)
+        .map(|start| (len - start).min(overflow::MAX_INLINE_LEN))
+        .map(|n| Value::Text(c.to_string().repeat(n)))
+        .collect();
+    Row::new(values)
+}
+
 #[test]
 fn insert_and_get_round_trip() {
     let dir = tempdir().unwrap();
@@ -40,8 +52,7 @@ fn large_rows_allocate_new_pages() {
     let path = dir.path().join("heap.tbl");
     let mut table = HeapFile::open(&path, 1).unwrap();
 
-    let big_payload = "x".repeat(PAGE_SIZE - 256);
-    let row = Row::new(vec![Value::Text(big_payload.clone())]);
+    let row = inline_row('x', PAGE_SIZE - 256);
 
     let rid_a = table.insert(&row).unwrap();
     let rid_b = table.insert(&row).unwrap();
@@ -49,7 +60,7 @@ fn large_rows_allocate_new_pages() {
     assert!(rid_b.page_id.0 > rid_a.page_id.0);
 
     let fetched = table.get(rid_b).unwrap();
-    assert_eq!(fetched.values, vec![Value::Text(big_payload)]);
+    assert_eq!(fetched.values, row.values);
 }
 
 #[test]
@@ -112,7 +123,7 @@ fn append_tuple_respects_size_and_capacity_limits() {
 fn append_tuple_rejects_slot_overflow() {
     let mut page = Page::new(0);
     let mut header = page.header().unwrap();
-    header.num_slots = u16::MAX;
+    header.num_slots = !checksum::CHECKSUMMED;
     page.write_header(&header).unwrap();
 
     let err = page.append_tuple(&[1u8]).unwrap_err();
@@ -137,25 +148,423 @@ fn heapfile_update_rewrites_rows() {
 }
 
 #[test]
-fn heapfile_update_relocates_when_needed() {
+fn heapfile_update_grows_rows_in_place_when_the_page_has_room() {
     let dir = tempdir().unwrap();
     let path = dir.path().join("heap.tbl");
     let mut table = HeapFile::open(&path, 1).unwrap();
 
     let short = Row::new(vec![Value::Text("a".into())]);
     let rid = table.insert(&short).unwrap();
+    let neighbour = table
+        .insert(&Row::new(vec![Value::Text("b".repeat(1000))]))
+        .unwrap();
 
-    let long = Row::new(vec![Value::Text(
-        "a very long string that exceeds the slot".into(),
-    )]);
-    let new_rid = table.update(rid, &long).unwrap();
-    assert_ne!(new_rid, rid);
+    // Each update needs more room than the last one freed, so the page is
+    // compacted before the final one fits
+    for len in [500, 1000, 1500, 2000] {
+        let long = inline_row('a', len);
+        assert_eq!(table.update(rid, &long).unwrap(), rid);
+        assert_eq!(table.get(rid).unwrap().values, long.values);
+    }
+    assert_eq!(table.num_pages().unwrap(), 1);
+    assert_eq!(
+        table.get(neighbour).unwrap().values,
+        vec![Value::Text("b".repeat(1000))]
+    );
+}
 
-    let fetched = table.get(new_rid).unwrap();
-    assert_eq!(fetched.values, long.values);
+#[test]
+fn heapfile_update_relocates_rows_that_outgrow_their_page_under_the_same_rid() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let mut table = HeapFile::open(&path, 1).unwrap();
+
+    let short = Row::new(vec![Value::Text("a".into())]);
+    let rid = table.insert(&short).unwrap();
+    let neighbour = table.insert(&inline_row('b', 3000)).unwrap();
+
+    let long = inline_row('a', 1500);
+    assert_eq!(table.update(rid, &long).unwrap(), rid);
+    assert_eq!(table.get(rid).unwrap().values, long.values);
+    assert_eq!(table.num_pages().unwrap(), 2);
+
+    // The relocated tuple is only reachable through the pointer
+    let to = RecordId {
+        page_id: PageId(1),
+        slot: 1,
+    };
+    assert!(table.get(to).is_err());
+    assert!(table.update(to, &short).is_err());
+    assert!(table.delete(to).is_err());
+
+    // Growing past the room on its new page moves the row again, without
+    // chaining pointers, and frees the tuple it left
+    table.insert(&inline_row('d', 2000)).unwrap();
+    let longer = inline_row('c', 3500);
+    assert_eq!(table.update(rid, &longer).unwrap(), rid);
+    assert_eq!(table.get(rid).unwrap().values, longer.values);
+    let (_, moved_to) = table.relocation(rid).unwrap();
+    let target = table.read_page(moved_to.unwrap().page_id.0).unwrap();
+    assert!(matches!(
+        target.stored(&target.read_slot(moved_to.unwrap().slot).unwrap()).unwrap(),
+        Tuple::Relocated { home, .. } if home == rid
+    ));
+    assert!(table.read_page(1).unwrap().read_slot(1).unwrap().is_empty());
+
+    // A row that fits on its page again moves back
+    assert_eq!(table.update(rid, &short).unwrap(), rid);
+    assert_eq!(table.relocation(rid).unwrap().1, None);
+    assert_eq!(table.get(rid).unwrap().values, short.values);
+    assert_eq!(
+        table.get(neighbour).unwrap().values,
+        inline_row('b', 3000).values
+    );
 
-    // Old rid should now be empty
+    // Deleting a relocated row frees both of its tuples
+    table.update(rid, &long).unwrap();
+    let (_, moved_to) = table.relocation(rid).unwrap();
+    table.delete(rid).unwrap();
     assert!(table.get(rid).is_err());
+    let to = moved_to.unwrap();
+    assert!(
+        table
+            .read_page(to.page_id.0)
+            .unwrap()
+            .read_slot(to.slot)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn rows_shorter_than_a_pointer_relocate_from_a_full_page() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let mut table = HeapFile::open(&path, 1).unwrap();
+
+    let mut rids = Vec::new();
+    while table.num_pages().unwrap() < 2 {
+        rids.push(table.insert(&Row::new(vec![Value::Int(1)])).unwrap());
+    }
+    rids.pop();
+    let long = inline_row('a', 1500);
+    for &rid in rids.iter().rev().take(8) {
+        assert_eq!(table.update(rid, &long).unwrap(), rid);
+        assert_eq!(table.get(rid).unwrap().values, long.values);
+    }
+}
+
+#[test]
+fn relocated_rows_are_counted_once_and_moved_home_by_vacuum() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let mut table = HeapFile::open(&path, 1).unwrap();
+
+    let rid = table.insert(&Row::new(vec![Value::Int(1)])).unwrap();
+    table.insert(&inline_row('b', 3000)).unwrap();
+    let long = inline_row('a', 1500);
+    table.update(rid, &long).unwrap();
+    assert!(table.relocation(rid).unwrap().1.is_some());
+
+    let usage = table.page_usage().unwrap().unwrap();
+    assert_eq!(usage.row_sizes.len(), 2);
+
+    let moved = table.vacuum().unwrap();
+    let new_rid = moved
+        .iter()
+        .find(|(from, _)| *from == rid)
+        .map_or(rid, |(_, to)| *to);
+    assert_eq!(table.get(new_rid).unwrap().values, long.values);
+    assert_eq!(table.relocation(new_rid).unwrap().1, None);
+    assert_eq!(table.page_usage().unwrap().unwrap().row_sizes.len(), 2);
+}
+
+#[test]
+fn scan_finds_every_slot_of_every_page() {
+    let dir = tempdir().unwrap();
+    let mut table = HeapFile::open(&dir.path().join("heap.tbl"), 1).unwrap();
+
+    let mut rids = Vec::new();
+    for i in 0..200 {
+        rids.push(table.insert(&Row::new(vec![Value::Int(i)])).unwrap());
+    }
+    table.insert(&inline_row('x', 3000)).unwrap();
+    assert!(rids[0].page_id == rids[199].page_id && rids[199].slot > 100);
+    table.delete(rids[7]).unwrap();
+
+    let scanned: Vec<(RecordId, Row)> = table.scan().map(Result::unwrap).collect();
+    assert_eq!(scanned.len(), 200);
+    assert!(scanned.iter().all(|(rid, row)| row.rid() == Some(*rid)));
+    let ids: Vec<RecordId> = scanned.iter().map(|(rid, _)| *rid).take(199).collect();
+    let mut expected = rids.clone();
+    expected.remove(7);
+    assert_eq!(ids, expected);
+}
+
+#[test]
+fn scan_returns_relocated_rows_once_under_their_record_id() {
+    let dir = tempdir().unwrap();
+    let mut table = HeapFile::open(&dir.path().join("heap.tbl"), 1).unwrap();
+
+    let rid = table.insert(&Row::new(vec![Value::Int(1)])).unwrap();
+    table.insert(&inline_row('b', 3000)).unwrap();
+    let long = inline_row('a', 1500);
+    table.update(rid, &long).unwrap();
+    assert!(table.relocation(rid).unwrap().1.is_some());
+
+    let scanned: Vec<(RecordId, Row)> = table.scan().map(Result::unwrap).collect();
+    assert_eq!(scanned.len(), 2);
+    assert_eq!(scanned[0].0, rid);
+    assert_eq!(scanned[0].1.values, long.values);
+}
+
+#[test]
+fn scan_page_reads_only_wanted_columns() {
+    let dir = tempdir().unwrap();
+    let mut table = HeapFile::open(&dir.path().join("heap.tbl"), 1).unwrap();
+    let row = Row::new(vec![Value::Int(7), Value::Text("red".into())]);
+    table.insert(&row).unwrap();
+
+    let rows = table.scan_page(PageId(0), Some(&[1])).unwrap().unwrap();
+    assert_eq!(rows[0].values, vec![Value::Null, Value::Text("red".into())]);
+    assert_eq!(table.run_pages(PageId(0)).unwrap(), 1);
+    assert!(table.scan_page(PageId(1), None).unwrap().is_none());
+}
+
+#[test]
+fn repeated_short_strings_are_stored_once_per_page() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let mut table = HeapFile::open(&path, 1).unwrap();
+
+    let statuses = ["active", "suspended", "closed"];
+    let mut rids = Vec::new();
+    for i in 0..200 {
+        let row = Row::new(vec![
+            Value::Int(i),
+            Value::Text(statuses[i as usize % 3].into()),
+            Value::Text("CA".into()),
+        ]);
+        rids.push(table.insert(&row).unwrap());
+    }
+
+    // Inline, each row would take over 50 bytes and need three pages
+    assert_eq!(table.num_pages().unwrap(), 1);
+    let dictionary = table.read_page(0).unwrap().dictionary().unwrap().unwrap();
+    assert_eq!(dictionary.len(), 4);
+    for (i, rid) in rids.into_iter().enumerate() {
+        assert_eq!(
+            table.get(rid).unwrap().values,
+            vec![
+                Value::Int(i as i64),
+                Value::Text(statuses[i % 3].into()),
+                Value::Text("CA".into()),
+            ]
+        );
+    }
+}
+
+#[test]
+fn long_strings_stay_in_their_rows() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let mut table = HeapFile::open(&path, 1).unwrap();
+
+    let long = "x".repeat(dictionary::MAX_ENTRY_LEN + 1);
+    let rid = table
+        .insert(&Row::new(vec![Value::Text(long.clone()), Value::Null]))
+        .unwrap();
+
+    let dictionary = table.read_page(0).unwrap().dictionary().unwrap().unwrap();
+    assert_eq!(dictionary.len(), 0);
+    assert_eq!(
+        table.get(rid).unwrap().values,
+        vec![Value::Text(long), Value::Null]
+    );
+}
+
+#[test]
+fn updates_add_dictionary_entries() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let mut table = HeapFile::open(&path, 1).unwrap();
+
+    let rid = table
+        .insert(&Row::new(vec![Value::Text("new".into())]))
+        .unwrap();
+    let full = inline_row('b', 4000);
+    let neighbour = table.insert(&full).unwrap();
+
+    let shipped = Row::new(vec![Value::Text("shipped".into())]);
+    assert_eq!(table.update(rid, &shipped).unwrap(), rid);
+    assert_eq!(table.get(rid).unwrap().values, shipped.values);
+    assert_eq!(table.get(neighbour).unwrap().values, full.values);
+
+    // No room on the page for another entry: the row moves to a new page,
+    // keeping its record ID
+    let cancelled = Row::new(vec![Value::Text("cancelled".repeat(7))]);
+    assert_eq!(table.update(rid, &cancelled).unwrap(), rid);
+    let (_, moved_to) = table.relocation(rid).unwrap();
+    assert_ne!(moved_to.unwrap().page_id, rid.page_id);
+    assert_eq!(table.get(rid).unwrap().values, cancelled.values);
+}
+
+#[test]
+fn the_dictionary_slot_is_not_a_row() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let mut table = HeapFile::open(&path, 1).unwrap();
+
+    let rid = table.insert(&Row::new(vec![Value::Int(1)])).unwrap();
+    assert_eq!(rid.slot, 1);
+
+    let dictionary = RecordId {
+        page_id: PageId(0),
+        slot: 0,
+    };
+    for err in [
+        table.get(dictionary).unwrap_err(),
+        table.delete(dictionary).unwrap_err(),
+        table
+            .update(dictionary, &Row::new(vec![Value::Int(2)]))
+            .unwrap_err(),
+    ] {
+        assert!(
+            matches!(err, DbError::Storage(ref msg) if msg == "slot empty"),
+            "{err}"
+        );
+    }
+}
+
+#[test]
+fn pages_without_a_dictionary_stay_readable_and_writable() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let mut table = HeapFile::open(&path, 1).unwrap();
+
+    // A page as written before dictionaries existed
+    let old = Row::new(vec![Value::Int(1), Value::Text("active".into())]);
+    let mut page = Page::new(0);
+    page.append_tuple(&encode_to_vec(&old, bincode_config()).unwrap())
+        .unwrap();
+    table.write_page(&page).unwrap();
+
+    let first = RecordId {
+        page_id: PageId(0),
+        slot: 0,
+    };
+    assert_eq!(table.get(first).unwrap().values, old.values);
+
+    // New rows on the page keep its encoding
+    let new = Row::new(vec![Value::Int(2), Value::Text("active".into())]);
+    let second = table.insert(&new).unwrap();
+    assert_eq!(second.page_id, PageId(0));
+    assert!(table.read_page(0).unwrap().dictionary().unwrap().is_none());
+
+    let updated = Row::new(vec![Value::Int(1), Value::Text("closed".into())]);
+    assert_eq!(table.update(first, &updated).unwrap(), first);
+    assert_eq!(table.get(first).unwrap().values, updated.values);
+    assert_eq!(table.get(second).unwrap().values, new.values);
+}
+
+#[test]
+fn large_values_are_stored_in_overflow_pages() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let mut table = HeapFile::open(&path, 1).unwrap();
+
+    let blob: Vec<u8> = (0..3 * PAGE_SIZE).map(|i| (i % 251) as u8).collect();
+    let row = Row::new(vec![
+        Value::Int(1),
+        Value::Bytes(blob),
+        Value::Text("z".repeat(5000)),
+    ]);
+    let rid = table.insert(&row).unwrap();
+    assert_eq!(table.get(rid).unwrap().values, row.values);
+
+    // Four pages for the bytes, two for the text and one for the row
+    assert_eq!(table.num_pages().unwrap(), 7);
+    assert_eq!(rid.page_id, PageId(6));
+
+    // Rows are added to the row's page rather than to an overflow page, and
+    // overflow pages read as pages without rows
+    let small = Row::new(vec![
+        Value::Int(2),
+        Value::Bytes(vec![1; overflow::MAX_INLINE_LEN]),
+    ]);
+    assert_eq!(table.insert(&small).unwrap().page_id, rid.page_id);
+    let overflow_slot = RecordId {
+        page_id: PageId(0),
+        slot: 0,
+    };
+    assert!(
+        matches!(table.get(overflow_slot), Err(DbError::Storage(ref msg)) if msg == "invalid slot 0")
+    );
+
+    let reopened = table.insert(&row).unwrap();
+    let mut table = HeapFile::open(&path, 1).unwrap();
+    assert_eq!(table.get(reopened).unwrap().values, row.values);
+}
+
+#[test]
+fn updates_move_values_into_and_out_of_overflow_pages() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let mut table = HeapFile::open(&path, 1).unwrap();
+
+    let rid = table.insert(&Row::new(vec![Value::Int(1)])).unwrap();
+    let neighbour = table.insert(&inline_row('n', 3000)).unwrap();
+
+    // Only the reference is stored on the full page
+    let large = Row::new(vec![Value::Text("l".repeat(10_000))]);
+    assert_eq!(table.update(rid, &large).unwrap(), rid);
+    assert_eq!(table.get(rid).unwrap().values, large.values);
+
+    let small = Row::new(vec![Value::Text("s".into())]);
+    assert_eq!(table.update(rid, &small).unwrap(), rid);
+    assert_eq!(table.get(rid).unwrap().values, small.values);
+    assert_eq!(
+        table.get(neighbour).unwrap().values,
+        inline_row('n', 3000).values
+    );
+}
+
+#[test]
+fn overflow_pages_are_encrypted() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let key = EncryptionKey::from_bytes(&[9u8; common::crypto::KEY_LEN]).unwrap();
+
+    let row = Row::new(vec![Value::Text("top secret ".repeat(1000))]);
+    let rid = {
+        let mut table = HeapFile::open_with_key(&path, 1, Some(&key)).unwrap();
+        table.insert(&row).unwrap()
+    };
+    let bytes = std::fs::read(&path).unwrap();
+    assert!(!bytes.windows(10).any(|w| w == b"top secret"));
+
+    let mut table = HeapFile::open_with_key(&path, 1, Some(&key)).unwrap();
+    assert_eq!(table.get(rid).unwrap().values, row.values);
+}
+
+#[test]
+fn broken_overflow_chains_are_reported() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let mut table = HeapFile::open(&path, 1).unwrap();
+
+    let rid = table
+        .insert(&Row::new(vec![Value::Bytes(vec![7; 2 * PAGE_SIZE])]))
+        .unwrap();
+    table.write_page(&Page::new(1)).unwrap();
+
+    // Not a missing-page error, which scans would take for the end of the
+    // table
+    let err = table.get(rid).unwrap_err();
+    assert!(
+        matches!(err, DbError::Storage(ref msg) if msg == "overflow chain starting at 0 is corrupt"),
+        "{err}"
+    );
 }
 
 #[test]
@@ -261,3 +670,886 @@ fn validate_and_read_slot_returns_page_and_slot() {
     assert_eq!(page.id, rid.page_id.0);
     assert!(!slot.is_empty());
 }
+
+#[test]
+fn damaged_pages_fail_their_checksum() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let row = Row::new(vec![Value::Int(1), Value::Text("intact".into())]);
+    let rid = HeapFile::open(&path, 1).unwrap().insert(&row).unwrap();
+    let mut table = HeapFile::open(&path, 1).unwrap();
+    assert_eq!(table.get(rid).unwrap().values, row.values);
+
+    let mut bytes = std::fs::read(&path).unwrap();
+    let at = bytes.windows(6).position(|w| w == b"intact").unwrap();
+    bytes[at] ^= 1;
+    std::fs::write(&path, &bytes).unwrap();
+    let err = HeapFile::open(&path, 1).unwrap().get(rid).unwrap_err();
+    assert!(matches!(err, DbError::Corruption(_)), "{err}");
+    assert!(err.to_string().contains("page 0"), "{err}");
+}
+
+#[test]
+fn heap_files_written_before_checksums_open_unverified() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+
+    // A page as written before checksums and dictionaries existed: the slots
+    // follow the four-byte header, and rows are stored whole
+    let old = Row::new(vec![Value::Int(1), Value::Text("active".into())]);
+    let tuple = encode_to_vec(&old, bincode_config()).unwrap();
+    let offset = (PAGE_SIZE - tuple.len()) as u16;
+    let mut bytes = vec![0u8; PAGE_SIZE];
+    for (at, field) in [1, offset, offset, tuple.len() as u16]
+        .into_iter()
+        .enumerate()
+    {
+        bytes[2 * at..2 * at + 2].copy_from_slice(&field.to_le_bytes());
+    }
+    bytes[usize::from(offset)..].copy_from_slice(&tuple);
+    std::fs::write(&path, &bytes).unwrap();
+
+    let mut table = HeapFile::open(&path, 1).unwrap();
+    let first = RecordId {
+        page_id: PageId(0),
+        slot: 0,
+    };
+    assert_eq!(table.get(first).unwrap().values, old.values);
+    let new = Row::new(vec![Value::Int(2), Value::Text("closed".into())]);
+    let second = table.insert(&new).unwrap();
+    assert_eq!(second.page_id, PageId(0));
+    assert!(table.check_pages().unwrap().is_empty());
+
+    // The page keeps its layout, so a damaged row reads as it is
+    let mut bytes = std::fs::read(&path).unwrap();
+    assert!(!checksum::is_checksummed(&bytes));
+    let at = bytes.windows(6).position(|w| w == b"closed").unwrap();
+    bytes[at] = b'C';
+    std::fs::write(&path, &bytes).unwrap();
+    let mut table = HeapFile::open(&path, 1).unwrap();
+    assert_eq!(
+        table.get(second).unwrap().values,
+        vec![Value::Int(2), Value::Text("Closed".into())]
+    );
+
+    // New pages are checksummed, unless the checksum is zero. A value
+    // stored in overflow pages keeps the row off the old page.
+    let third = table
+        .insert(&Row::new(vec![Value::Text("x".repeat(2000))]))
+        .unwrap();
+    let start = third.page_id.0 as usize * PAGE_SIZE;
+    let mut bytes = std::fs::read(&path).unwrap();
+    let page = &mut bytes[start..start + PAGE_SIZE];
+    assert!(checksum::is_checksummed(page));
+    page[PAGE_SIZE / 2] ^= 1;
+    std::fs::write(&path, &bytes).unwrap();
+    let err = HeapFile::open(&path, 1).unwrap().get(third).unwrap_err();
+    assert!(matches!(err, DbError::Corruption(_)), "{err}");
+    bytes[start..][checksum::CHECKSUM_BYTES].fill(0);
+    std::fs::write(&path, &bytes).unwrap();
+    assert!(HeapFile::open(&path, 1).unwrap().get(third).is_ok());
+}
+
+#[test]
+fn check_pages_reports_damaged_pages() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let mut table = HeapFile::open(&path, 1).unwrap();
+    for i in 0..3 {
+        table.insert(&inline_row('a', 3000 + i)).unwrap();
+    }
+    assert_eq!(table.num_pages().unwrap(), 3);
+    assert!(table.check_pages().unwrap().is_empty());
+
+    let mut bytes = std::fs::read(&path).unwrap();
+    // Page 1 fails its checksum
+    bytes[PAGE_SIZE + 100] ^= 1;
+    // Page 2 is checksummed, but its free space starts inside its slots
+    let page = &mut bytes[2 * PAGE_SIZE..];
+    page[2..4].copy_from_slice(&2u16.to_le_bytes());
+    checksum::stamp(&mut page[..PAGE_SIZE]);
+    std::fs::write(&path, &bytes).unwrap();
+
+    let damaged = HeapFile::open(&path, 1).unwrap().check_pages().unwrap();
+    let pages: Vec<u64> = damaged.iter().map(|(page, _)| *page).collect();
+    assert_eq!(pages, vec![1, 2]);
+    assert!(
+        damaged
+            .iter()
+            .all(|(_, err)| matches!(err, DbError::Corruption(_)))
+    );
+    let problem = damaged[1].1.to_string();
+    assert!(problem.contains("free space"), "{problem}");
+}
+
+#[test]
+fn encrypted_heap_round_trips_and_hides_plaintext() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let key = EncryptionKey::from_bytes(&[9u8; common::crypto::KEY_LEN]).unwrap();
+
+    let row = Row::new(vec![Value::Int(1), Value::Text("top secret".into())]);
+    let rid = {
+        let mut table = HeapFile::open_with_key(&path, 1, Some(&key)).unwrap();
+        table.insert(&row).unwrap()
+    };
+
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(bytes.len(), PAGE_SIZE + SEAL_OVERHEAD);
+    assert!(!bytes.windows(10).any(|w| w == b"top secret"));
+
+    let mut table = HeapFile::open_with_key(&path, 1, Some(&key)).unwrap();
+    assert_eq!(table.get(rid).unwrap().values, row.values);
+
+    // Pages are bound to their table, so a different table ID cannot read them.
+    let mut other = HeapFile::open_with_key(&path, 2, Some(&key)).unwrap();
+    assert!(other.get(rid).is_err());
+}
+
+#[test]
+fn memory_engine_shares_rows_between_handles_until_dropped() {
+    let dir = tempdir().unwrap();
+    let engine = MemoryEngine::default();
+    assert!(!engine.durable());
+
+    let mut first = engine.open(dir.path(), "t", 1, None).unwrap();
+    let rids: Vec<RecordId> = (0..70)
+        .map(|i| first.insert(&Row::new(vec![Value::Int(i)])).unwrap())
+        .collect();
+    assert_eq!(rids[65].page_id, PageId(1));
+    first.delete(rids[1]).unwrap();
+
+    let mut second = engine.open(dir.path(), "t", 1, None).unwrap();
+    assert_eq!(second.get(rids[65]).unwrap().values, vec![Value::Int(65)]);
+    assert_eq!(
+        second
+            .update(rids[0], &Row::new(vec![Value::Int(-1)]))
+            .unwrap(),
+        rids[0]
+    );
+    assert_eq!(first.get(rids[0]).unwrap().values, vec![Value::Int(-1)]);
+
+    // Missing rows fail like they do in a heap file
+    let err = second.get(rids[1]).unwrap_err();
+    assert!(err.to_string().contains("slot empty"), "{err}");
+    let past_end = RecordId {
+        page_id: PageId(2),
+        slot: 0,
+    };
+    let err = second.get(past_end).unwrap_err();
+    assert!(err.to_string().contains("page 2"), "{err}");
+    assert!(!dir.path().join("t.heap").exists());
+
+    engine.drop_table(dir.path(), "t", 1).unwrap();
+    let mut reopened = engine.open(dir.path(), "t", 1, None).unwrap();
+    assert!(reopened.get(rids[0]).is_err());
+}
+
+#[test]
+fn memory_and_lsm_tables_scan_their_live_rows() {
+    let dir = tempdir().unwrap();
+    let memory = MemoryEngine::default();
+    let lsm = LsmEngine::new(lsm_options());
+    for engine in [&memory as &dyn TableEngine, &lsm] {
+        let mut table = engine.open(dir.path(), "t", 1, None).unwrap();
+        let rids: Vec<RecordId> = (0..150)
+            .map(|i| table.insert(&Row::new(vec![Value::Int(i)])).unwrap())
+            .collect();
+        table.delete(rids[70]).unwrap();
+
+        assert_eq!(table.run_pages(PageId(0)).unwrap(), 3);
+        let scanned: Vec<RecordId> = Scan::new(table.as_mut())
+            .map(|entry| entry.unwrap().0)
+            .collect();
+        let mut expected = rids;
+        expected.remove(70);
+        assert_eq!(scanned, expected);
+    }
+}
+
+#[test]
+fn heap_engine_stores_rows_in_table_heap_file() {
+    let dir = tempdir().unwrap();
+    let engine = HeapEngine::default();
+    let rid = {
+        let mut table = engine.open(dir.path(), "users", 1, None).unwrap();
+        table.insert(&Row::new(vec![Value::Int(7)])).unwrap()
+    };
+    let path = dir.path().join("users.heap");
+    assert_eq!(
+        HeapFile::open(&path, 1).unwrap().get(rid).unwrap().values,
+        vec![Value::Int(7)]
+    );
+
+    engine.drop_table(dir.path(), "users", 1).unwrap();
+    assert!(!path.exists());
+}
+
+fn lsm_options() -> LsmOptions {
+    LsmOptions {
+        memtable_entries: 4,
+        max_runs: 2,
+    }
+}
+
+#[test]
+fn lsm_engine_flushes_compacts_and_recovers() {
+    let dir = tempdir().unwrap();
+    let lsm_dir = dir.path().join("events.lsm");
+    let rids: Vec<RecordId> = {
+        let engine = LsmEngine::new(lsm_options());
+        let mut table = engine.open(dir.path(), "events", 1, None).unwrap();
+        let rids: Vec<RecordId> = (0..20)
+            .map(|i| table.insert(&Row::new(vec![Value::Int(i)])).unwrap())
+            .collect();
+        table
+            .update(rids[3], &Row::new(vec![Value::Int(-3)]))
+            .unwrap();
+        table.delete(rids[5]).unwrap();
+        // Compaction keeps the run count bounded
+        let runs = std::fs::read_dir(&lsm_dir)
+            .unwrap()
+            .filter(|entry| entry.as_ref().unwrap().path().extension().unwrap() == "run")
+            .count();
+        assert!(runs <= 2, "{runs} runs");
+        rids
+    };
+    assert_eq!(rids[19].page_id, PageId(0));
+    assert_eq!(rids[19].slot, 19);
+
+    // A new engine rebuilds the table from its runs and memtable log
+    let engine = LsmEngine::new(lsm_options());
+    let mut table = engine.open(dir.path(), "events", 1, None).unwrap();
+    assert_eq!(table.get(rids[3]).unwrap().values, vec![Value::Int(-3)]);
+    assert_eq!(table.get(rids[19]).unwrap().values, vec![Value::Int(19)]);
+    let err = table.get(rids[5]).unwrap_err();
+    assert!(err.to_string().contains("slot empty"), "{err}");
+    // Deleted positions are not reused
+    let rid = table.insert(&Row::new(vec![Value::Int(20)])).unwrap();
+    assert_eq!(rid.slot, 20);
+
+    engine.drop_table(dir.path(), "events", 1).unwrap();
+    assert!(!lsm_dir.exists());
+}
+
+#[test]
+fn lsm_engine_ignores_torn_log_tail_and_encrypts_rows() {
+    let dir = tempdir().unwrap();
+    let key = EncryptionKey::from_bytes(&[3u8; common::crypto::KEY_LEN]).unwrap();
+    let rid = {
+        let engine = LsmEngine::new(lsm_options());
+        let mut table = engine.open(dir.path(), "t", 1, Some(&key)).unwrap();
+        table
+            .insert(&Row::new(vec![Value::Text("secret-value".into())]))
+            .unwrap()
+    };
+    let log = dir.path().join("t.lsm").join("memtable.log");
+    let bytes = std::fs::read(&log).unwrap();
+    assert!(!bytes.windows(12).any(|w| w == b"secret-value"));
+    // A write cut short by a crash
+    std::fs::write(&log, [bytes.as_slice(), &[9, 9, 9]].concat()).unwrap();
+
+    let engine = LsmEngine::new(lsm_options());
+    let mut table = engine.open(dir.path(), "t", 1, Some(&key)).unwrap();
+    assert_eq!(
+        table.get(rid).unwrap().values,
+        vec![Value::Text("secret-value".into())]
+    );
+    assert_eq!(std::fs::read(&log).unwrap(), bytes);
+}
+
+fn columnar_row(i: i64) -> Row {
+    Row::new(vec![
+        Value::Int(i),
+        Value::Text(format!("region-{}", i % 3)),
+        Value::Bool(i % 2 == 0),
+    ])
+}
+
+/// Paths in `dir` with the given extension.
+fn files_with_extension(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .collect()
+}
+
+#[test]
+fn columnar_engine_seals_stripes_and_recovers() {
+    let dir = tempdir().unwrap();
+    let table_dir = dir.path().join("facts.columnar");
+    let options = ColumnarOptions { stripe_rows: 8 };
+    let rids: Vec<RecordId> = {
+        let engine = ColumnarEngine::new(options);
+        let mut table = engine.open(dir.path(), "facts", 1, None).unwrap();
+        let rids: Vec<RecordId> = (0..20)
+            .map(|i| table.insert(&columnar_row(i)).unwrap())
+            .collect();
+        // Both rows are in a sealed stripe, which is rewritten
+        table.update(rids[3], &columnar_row(-3)).unwrap();
+        table.delete(rids[5]).unwrap();
+        for i in 20..26 {
+            table.insert(&columnar_row(i)).unwrap();
+        }
+        assert_eq!(
+            table.get_columns(rids[3], &[1]).unwrap().values,
+            vec![Value::Null, Value::Text("region-0".into()), Value::Null]
+        );
+        rids
+    };
+    // One segment per column of each stripe; the rewritten stripe replaced
+    // the one it was rewritten from
+    let stripes = files_with_extension(&table_dir, "rows").len();
+    assert_eq!(stripes, 3);
+    assert_eq!(files_with_extension(&table_dir, "seg").len(), 3 * stripes);
+
+    // A new engine rebuilds the table from its stripes and delta log
+    let engine = ColumnarEngine::new(options);
+    let mut table = engine.open(dir.path(), "facts", 1, None).unwrap();
+    assert_eq!(table.get(rids[3]).unwrap().values, columnar_row(-3).values);
+    assert_eq!(table.get(rids[19]).unwrap().values, columnar_row(19).values);
+    let err = table.get(rids[5]).unwrap_err();
+    assert!(err.to_string().contains("slot empty"), "{err}");
+    let scanned: Vec<RecordId> = Scan::new(table.as_mut())
+        .map(|entry| entry.unwrap().0)
+        .collect();
+    assert_eq!(scanned.len(), 25);
+    // Deleted positions are not reused
+    let rid = table.insert(&columnar_row(26)).unwrap();
+    assert_eq!(rid.slot, 26);
+
+    engine.drop_table(dir.path(), "facts", 1).unwrap();
+    assert!(!table_dir.exists());
+}
+
+#[test]
+fn columnar_scans_read_only_projected_columns() {
+    let dir = tempdir().unwrap();
+    let table_dir = dir.path().join("facts.columnar");
+    let options = ColumnarOptions { stripe_rows: 4 };
+    {
+        let engine = ColumnarEngine::new(options);
+        let mut table = engine.open(dir.path(), "facts", 1, None).unwrap();
+        for i in 0..8 {
+            table.insert(&columnar_row(i)).unwrap();
+        }
+    }
+    // Without the third column's segments, only scans that skip it succeed
+    for path in files_with_extension(&table_dir, "seg") {
+        if path.to_string_lossy().ends_with(".2.seg") {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+    let engine = ColumnarEngine::new(options);
+    let mut table = engine.open(dir.path(), "facts", 1, None).unwrap();
+    let rows = table.scan_page(PageId(0), Some(&[0, 1])).unwrap().unwrap();
+    assert_eq!(rows.len(), 8);
+    assert_eq!(
+        rows[4].values,
+        vec![Value::Int(4), Value::Text("region-1".into()), Value::Null]
+    );
+    assert!(table.scan_page(PageId(0), None).is_err());
+}
+
+#[test]
+fn columnar_segments_pick_the_smallest_encoding() {
+    let dir = tempdir().unwrap();
+    let table_dir = dir.path().join("t.columnar");
+    let engine = ColumnarEngine::new(ColumnarOptions { stripe_rows: 200 });
+    let mut table = engine.open(dir.path(), "t", 1, None).unwrap();
+    let label = "a label long enough to dominate the segment size";
+    for i in 0..200 {
+        table
+            .insert(&Row::new(vec![
+                Value::Int(i),
+                Value::Text(label.into()),
+                Value::Text(format!("{label} {}", i % 4)),
+            ]))
+            .unwrap();
+    }
+    let size = |column: usize| {
+        std::fs::metadata(table_dir.join(format!("00000000.{column}.seg")))
+            .unwrap()
+            .len() as usize
+    };
+    // Distinct values are stored plainly, one repeated value as a single
+    // run, and a few distinct values once each in a dictionary
+    assert!(size(0) >= 200 * 8, "{}", size(0));
+    assert!(size(1) < 2 * label.len(), "{}", size(1));
+    assert!(size(2) < 4 * label.len() + 2 * 200, "{}", size(2));
+    let rid = RecordId {
+        page_id: PageId(3),
+        slot: 2,
+    };
+    assert_eq!(
+        table.get(rid).unwrap().values[2],
+        Value::Text(format!("{label} 2"))
+    );
+}
+
+#[test]
+fn heap_engine_passes_faults_to_its_files() {
+    use common::hooks::{FaultPlan, IoOp};
+
+    let dir = tempdir().unwrap();
+    let faults = Arc::new(FaultPlan::new().fail_nth(IoOp::PageWrite, 2));
+    let engine = HeapEngine::with_faults(faults.clone());
+    let mut table = engine.open(dir.path(), "users", 1, None).unwrap();
+
+    let rid = table.insert(&Row::new(vec![Value::Int(1)])).unwrap();
+    let err = table.insert(&Row::new(vec![Value::Int(2)])).unwrap_err();
+    assert!(err.to_string().contains("injected fault"), "{err}");
+    assert_eq!(faults.count(IoOp::PageWrite), 2);
+
+    // The failed insert left the page as it was
+    let reopened = HeapFile::open(&dir.path().join("users.heap"), 1).unwrap();
+    assert_eq!(reopened.num_pages().unwrap(), 1);
+    let mut table = engine.open(dir.path(), "users", 1, None).unwrap();
+    assert_eq!(table.get(rid).unwrap().values, vec![Value::Int(1)]);
+    assert!(
+        table
+            .get(RecordId {
+                page_id: rid.page_id,
+                slot: rid.slot + 1,
+            })
+            .is_err()
+    );
+}
+
+#[test]
+fn page_usage_counts_row_pages_and_live_rows() {
+    let dir = tempdir().unwrap();
+    let mut table = HeapFile::open(&dir.path().join("heap.tbl"), 1).unwrap();
+    assert_eq!(table.page_usage().unwrap(), Some(PageUsage::default()));
+
+    let small = table.insert(&Row::new(vec![Value::Int(1)])).unwrap();
+    table.insert(&Row::new(vec![Value::Int(2)])).unwrap();
+    table.delete(small).unwrap();
+    // A value stored in overflow pages, whose pages hold no rows
+    let big = Value::Text("y".repeat(3 * PAGE_SIZE));
+    let big_rid = table.insert(&Row::new(vec![big])).unwrap();
+    assert_eq!(big_rid.page_id, small.page_id);
+
+    let usage = table.page_usage().unwrap().unwrap();
+    assert_eq!(usage.pages, 1);
+    assert_eq!(usage.row_sizes.len(), 2);
+    let page = table.read_page(small.page_id.0).unwrap();
+    assert_eq!(
+        usage.used_bytes,
+        (PAGE_SIZE - page.free_space().unwrap()) as u64
+    );
+    assert!(usage.used_bytes > usage.row_sizes.iter().sum::<u64>());
+}
+
+#[test]
+fn fillfactor_leaves_room_for_updates() {
+    let dir = tempdir().unwrap();
+    let mut table = HeapFile::open(&dir.path().join("heap.tbl"), 1)
+        .unwrap()
+        .with_fillfactor(50);
+    let row = |i: i64| Row::new(vec![Value::Int(i), Value::Bytes(vec![0; 100])]);
+
+    let mut rids = Vec::new();
+    for i in 0..60 {
+        rids.push(table.insert(&row(i)).unwrap());
+    }
+    assert!(rids.iter().any(|rid| rid.page_id != rids[0].page_id));
+    for page_id in 0..2 {
+        let page = table.read_page(page_id).unwrap();
+        assert!(PAGE_SIZE - page.free_space().unwrap() <= PAGE_SIZE / 2);
+    }
+
+    // A row that grows stays on its page
+    let grown = Row::new(vec![Value::Int(0), Value::Bytes(vec![1; 1000])]);
+    assert_eq!(table.update(rids[0], &grown).unwrap(), rids[0]);
+    assert_eq!(table.get(rids[0]).unwrap().values, grown.values);
+}
+
+#[test]
+fn compressed_pages_read_back_whatever_the_setting() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let row = |i: i64| {
+        Row::new(vec![
+            Value::Int(i),
+            Value::Text(format!("order {i} shipped to warehouse {}", i % 7)),
+        ])
+    };
+    let mut rids = Vec::new();
+    {
+        let mut table = HeapFile::open(&path, 1)
+            .unwrap()
+            .with_compression(Compression::Lz4);
+        for i in 0..300 {
+            rids.push(table.insert(&row(i)).unwrap());
+        }
+    }
+    let bytes = fs::read(&path).unwrap();
+    assert_eq!(bytes.len() % PAGE_SIZE, 0);
+    assert!(bytes.len() / PAGE_SIZE > 1);
+    for slot in bytes.chunks(PAGE_SIZE) {
+        assert!(common::compression::is_compressed(slot));
+    }
+
+    // Opened without compression, the old pages still read and new writes
+    // are stored as they are
+    let mut table = HeapFile::open(&path, 1).unwrap();
+    assert!(table.check_pages().unwrap().is_empty());
+    let grown = Row::new(vec![Value::Int(0), Value::Text("x".repeat(500))]);
+    table.update(rids[0], &grown).unwrap();
+    let bytes = fs::read(&path).unwrap();
+    assert!(!common::compression::is_compressed(&bytes[..PAGE_SIZE]));
+    assert_eq!(table.get(rids[0]).unwrap().values, grown.values);
+    for (i, rid) in rids.iter().enumerate().skip(1) {
+        assert_eq!(table.get(*rid).unwrap().values, row(i as i64).values);
+    }
+}
+
+#[test]
+fn get_columns_returns_unread_columns_as_null() {
+    let dir = tempdir().unwrap();
+    let mut table = HeapFile::open(&dir.path().join("heap.tbl"), 1).unwrap();
+    let big = Value::Text("z".repeat(2 * PAGE_SIZE));
+    let row = Row::new(vec![Value::Int(7), Value::Text("red".into()), big.clone()]);
+    let rid = table.insert(&row).unwrap();
+
+    let read = table.get_columns(rid, &[0, 1]).unwrap();
+    assert_eq!(
+        read.values,
+        vec![Value::Int(7), Value::Text("red".into()), Value::Null]
+    );
+    assert_eq!(read.rid(), Some(rid));
+    assert_eq!(
+        table.get_columns(rid, &[2]).unwrap().values,
+        vec![Value::Null, Value::Null, big]
+    );
+    assert_eq!(
+        table.get_columns(rid, &[0, 1, 2]).unwrap().values,
+        row.values
+    );
+}
+
+#[test]
+fn vacuum_compacts_live_rows_and_truncates_the_file() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let mut table = HeapFile::open(&path, 1).unwrap();
+    let row = |i: i64| Row::new(vec![Value::Int(i), Value::Bytes(vec![0; 500])]);
+
+    let mut rids = Vec::new();
+    for i in 0..40 {
+        rids.push(table.insert(&row(i)).unwrap());
+    }
+    // A value in overflow pages whose row is then deleted
+    let big = table
+        .insert(&Row::new(vec![Value::Text("b".repeat(3 * PAGE_SIZE))]))
+        .unwrap();
+    table.delete(big).unwrap();
+    for rid in rids.iter().filter(|rid| rid.slot % 2 == 0) {
+        table.delete(*rid).unwrap();
+    }
+    let pages_before = table.num_pages().unwrap();
+    let live: Vec<_> = rids.iter().filter(|rid| rid.slot % 2 == 1).collect();
+
+    let moved = table.vacuum().unwrap();
+    assert!(table.num_pages().unwrap() < pages_before / 2);
+    assert!(!dir.path().join("heap.tbl.vacuum.tmp").exists());
+    assert!(!moved.is_empty());
+
+    // Every live row is found at its new record ID, in the old order
+    let new_rids: Vec<_> = live
+        .iter()
+        .map(|old| {
+            moved
+                .iter()
+                .find(|(from, _)| from == *old)
+                .map_or(**old, |(_, to)| *to)
+        })
+        .collect();
+    let order = |rid: &RecordId| (rid.page_id.0, rid.slot);
+    assert!(
+        new_rids
+            .windows(2)
+            .all(|pair| order(&pair[0]) < order(&pair[1]))
+    );
+    let mut table = HeapFile::open(&path, 1).unwrap();
+    for (old, new) in live.iter().zip(&new_rids) {
+        let original = rids.iter().position(|rid| rid == *old).unwrap();
+        assert_eq!(table.get(*new).unwrap().values, row(original as i64).values);
+    }
+    let usage = table.page_usage().unwrap().unwrap();
+    assert_eq!(usage.row_sizes.len(), live.len());
+}
+
+#[test]
+fn vacuum_keeps_overflow_values_and_encryption() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let key = EncryptionKey::from_bytes(&[3u8; common::crypto::KEY_LEN]).unwrap();
+    let mut table = HeapFile::open_with_key(&path, 1, Some(&key)).unwrap();
+
+    let gone = table.insert(&Row::new(vec![Value::Int(0)])).unwrap();
+    let row = Row::new(vec![Value::Int(1), Value::Text("k".repeat(2 * PAGE_SIZE))]);
+    let kept = table.insert(&row).unwrap();
+    table.delete(gone).unwrap();
+
+    let moved = table.vacuum().unwrap();
+    let new = moved
+        .iter()
+        .find(|(old, _)| *old == kept)
+        .map(|(_, new)| *new);
+    let new = new.unwrap_or(kept);
+    assert_eq!(table.get(new).unwrap().values, row.values);
+
+    let mut reopened = HeapFile::open_with_key(&path, 1, Some(&key)).unwrap();
+    assert_eq!(reopened.get(new).unwrap().values, row.values);
+    let bytes = std::fs::read(&path).unwrap();
+    assert!(!bytes.windows(16).any(|w| w == "k".repeat(16).as_bytes()));
+}
+
+#[test]
+fn inserts_reuse_the_space_and_slots_of_deleted_rows() {
+    let dir = tempdir().unwrap();
+    let mut table = HeapFile::open(&dir.path().join("heap.tbl"), 1).unwrap();
+    let row = |i: i64| Row::new(vec![Value::Int(i), Value::Bytes(vec![0; 500])]);
+
+    let rids: Vec<RecordId> = (0..30).map(|i| table.insert(&row(i)).unwrap()).collect();
+    let pages = table.num_pages().unwrap();
+    let first_page: Vec<RecordId> = rids
+        .iter()
+        .copied()
+        .filter(|rid| rid.page_id == PageId(0))
+        .collect();
+    for rid in &first_page {
+        table.delete(*rid).unwrap();
+    }
+
+    // Once the last page is full, the new rows take the deleted rows' slots
+    // rather than new pages
+    let mut reused = Vec::new();
+    for i in 100..200 {
+        let rid = table.insert(&row(i)).unwrap();
+        assert_eq!(table.get(rid).unwrap().values, row(i).values);
+        if rid.page_id == PageId(0) {
+            reused.push(rid);
+            if reused.len() == first_page.len() {
+                break;
+            }
+        }
+    }
+    assert_eq!(reused, first_page);
+    assert_eq!(table.num_pages().unwrap(), pages);
+    for rid in rids.iter().filter(|rid| rid.page_id != PageId(0)) {
+        assert!(table.get(*rid).is_ok());
+    }
+}
+
+#[test]
+fn inserts_choose_the_fullest_page_with_room() {
+    let dir = tempdir().unwrap();
+    let mut table = HeapFile::open(&dir.path().join("heap.tbl"), 1).unwrap();
+    let row = |len: usize| Row::new(vec![Value::Bytes(vec![1; len])]);
+
+    // Three full pages, then free a little space on page 1 and a lot on page 0
+    let mut rids = Vec::new();
+    while table.num_pages().unwrap() < 4 {
+        rids.push(table.insert(&row(900)).unwrap());
+    }
+    let on_page = |page: u64| {
+        rids.iter()
+            .copied()
+            .filter(move |rid| rid.page_id == PageId(page))
+    };
+    for rid in on_page(0).take(3) {
+        table.delete(rid).unwrap();
+    }
+    table.delete(on_page(1).next().unwrap()).unwrap();
+
+    let rid = table.insert(&row(800)).unwrap();
+    assert_eq!(rid.page_id, PageId(1));
+    let rid = table.insert(&row(800)).unwrap();
+    assert_eq!(rid.page_id, PageId(0));
+}
+
+#[test]
+fn heap_engine_handles_share_free_space() {
+    use common::hooks::{FaultPlan, IoOp};
+
+    let dir = tempdir().unwrap();
+    let faults = Arc::new(FaultPlan::new());
+    let engine = HeapEngine::with_faults(faults.clone());
+    let row = |i: i64| Row::new(vec![Value::Int(i), Value::Bytes(vec![0; 500])]);
+    {
+        let mut table = engine.open(dir.path(), "t", 1, None).unwrap();
+        for i in 0..20 {
+            table.insert(&row(i)).unwrap();
+        }
+    }
+
+    // A new handle does not read every page to find room for a row
+    let reads = faults.count(IoOp::PageRead);
+    let mut table = engine.open(dir.path(), "t", 1, None).unwrap();
+    table.insert(&row(20)).unwrap();
+    assert_eq!(faults.count(IoOp::PageRead) - reads, 1);
+
+    // A new engine builds its map from the file
+    let pages = HeapFile::open(&dir.path().join("t.heap"), 1)
+        .unwrap()
+        .num_pages()
+        .unwrap();
+    let engine = HeapEngine::with_faults(faults.clone());
+    let reads = faults.count(IoOp::PageRead);
+    let mut table = engine.open(dir.path(), "t", 1, None).unwrap();
+    table.insert(&row(21)).unwrap();
+    assert!(faults.count(IoOp::PageRead) - reads > pages);
+}
+
+#[test]
+fn row_layout_round_trips_every_type() {
+    use row_format::RowLayout;
+    use types::{Decimal, SqlType};
+
+    let types = [
+        SqlType::Int,
+        SqlType::Text,
+        SqlType::Bool,
+        SqlType::Float,
+        SqlType::Date,
+        SqlType::Timestamp,
+        SqlType::Decimal {
+            precision: 10,
+            scale: 2,
+        },
+        SqlType::Bytes,
+        SqlType::Text,
+    ];
+    let layout = RowLayout::new(&types);
+    let row = Row::new(vec![
+        Value::Int(-42),
+        Value::Text("héllo".into()),
+        Value::Bool(true),
+        Value::Float(2.5),
+        Value::Date(19_000),
+        Value::Timestamp(1_700_000_000_000_000),
+        Value::Decimal(Decimal::new(-12345, 2)),
+        Value::Bytes(vec![0, 1, 2]),
+        Value::Text(String::new()),
+    ]);
+    let tuple = layout.encode(&row).unwrap();
+    assert_eq!(layout.decode(&tuple).unwrap().values, row.values);
+    for (column, value) in row.values.iter().enumerate() {
+        assert_eq!(&layout.decode_column(&tuple, column).unwrap(), value);
+    }
+
+    // NULLs take no tail bytes, and the columns after them still decode
+    let mut nulls = row.clone();
+    nulls.values[1] = Value::Null;
+    nulls.values[3] = Value::Null;
+    let tuple = layout.encode(&nulls).unwrap();
+    assert_eq!(layout.decode(&tuple).unwrap().values, nulls.values);
+    assert_eq!(
+        layout.decode_column(&tuple, 7).unwrap(),
+        Value::Bytes(vec![0, 1, 2])
+    );
+}
+
+#[test]
+fn row_layout_is_smaller_than_bincode_for_wide_rows() {
+    use row_format::RowLayout;
+    use types::SqlType;
+
+    let types = vec![SqlType::Int; 32];
+    let layout = RowLayout::new(&types);
+    let row = Row::new((0..32).map(|i| Value::Int(i * 1_000_000)).collect());
+    let compact = layout.encode(&row).unwrap();
+    let bincode = encode_to_vec(&row, bincode_config()).unwrap();
+    assert!(
+        compact.len() < bincode.len(),
+        "{} >= {}",
+        compact.len(),
+        bincode.len()
+    );
+}
+
+#[test]
+fn row_layout_rejects_rows_that_do_not_match() {
+    use row_format::RowLayout;
+    use types::SqlType;
+
+    let layout = RowLayout::new(&[SqlType::Int, SqlType::Text]);
+    assert!(layout.encode(&Row::new(vec![Value::Int(1)])).is_err());
+    assert!(
+        layout
+            .encode(&Row::new(vec![Value::Text("1".into()), Value::Null]))
+            .is_err()
+    );
+
+    let tuple = layout
+        .encode(&Row::new(vec![Value::Int(1), Value::Text("abc".into())]))
+        .unwrap();
+    // A tuple decoded with another table's layout, or cut short
+    assert!(RowLayout::new(&[SqlType::Int]).decode(&tuple).is_err());
+    assert!(layout.decode(&tuple[..tuple.len() - 1]).is_err());
+    assert!(layout.decode(&tuple[..4]).is_err());
+    assert!(layout.decode_column(&tuple, 2).is_err());
+}
+
+#[test]
+fn heap_files_store_rows_with_their_table_layout() {
+    use row_format::{LAYOUT_TAG, RowLayout};
+    use types::SqlType;
+
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("heap.tbl");
+    let before = Row::new(vec![Value::Int(1), Value::Text("red".into())]);
+    let before_rid = HeapFile::open(&path, 1).unwrap().insert(&before).unwrap();
+
+    let mut table = HeapFile::open(&path, 1).unwrap();
+    table.set_row_layout(RowLayout::new(&[SqlType::Int, SqlType::Text]));
+    let row = Row::new(vec![Value::Int(2), Value::Text("blue".into())]);
+    let rid = table.insert(&row).unwrap();
+    let page = table.read_page(rid.page_id.0).unwrap();
+    let slot = page.read_slot(rid.slot).unwrap();
+    assert_eq!(page.data[slot.offset as usize], LAYOUT_TAG);
+
+    // Rows the layout cannot hold are stored with the page dictionary
+    let spilled = Row::new(vec![Value::Int(3), Value::Text("z".repeat(5000))]);
+    let mistyped = Row::new(vec![Value::Text("4".into()), Value::Null]);
+    let spilled_rid = table.insert(&spilled).unwrap();
+    let mistyped_rid = table.insert(&mistyped).unwrap();
+    for (rid, row) in [
+        (before_rid, &before),
+        (rid, &row),
+        (spilled_rid, &spilled),
+        (mistyped_rid, &mistyped),
+    ] {
+        assert_eq!(table.get(rid).unwrap().values, row.values);
+    }
+    assert_eq!(
+        table.get_columns(rid, &[1]).unwrap().values,
+        vec![Value::Null, Value::Text("blue".into())]
+    );
+    let scanned: Vec<Row> = Scan::new(&mut table).map(|row| row.unwrap().1).collect();
+    assert_eq!(scanned.len(), 4);
+    assert!(scanned.iter().any(|scanned| scanned.values == row.values));
+
+    // Updates switch between the encodings
+    table.update(rid, &spilled).unwrap();
+    assert_eq!(table.get(rid).unwrap().values, spilled.values);
+    table.update(rid, &row).unwrap();
+    assert_eq!(table.get(rid).unwrap().values, row.values);
+
+    // Rows stored before the table gained a column keep their own columns
+    let columns = [SqlType::Int, SqlType::Text, SqlType::Bool];
+    table.set_row_layout(RowLayout::new(&columns));
+    assert_eq!(table.get(rid).unwrap().values, row.values);
+    let wider = Row::new(vec![Value::Int(5), Value::Null, Value::Bool(true)]);
+    let wider_rid = table.insert(&wider).unwrap();
+    assert_eq!(table.get(wider_rid).unwrap().values, wider.values);
+
+    // Opened without its layout, the table cannot read them
+    let mut without = HeapFile::open(&path, 1).unwrap();
+    assert!(without.get(rid).is_err());
+    assert_eq!(without.get(before_rid).unwrap().values, before.values);
+}
@@ -2,6 +2,7 @@
 //!
 //! This crate provides comprehensive testing infrastructure including:
 //! - Isolated test execution contexts with temporary storage
+//! - Embedded databases in temporary directories ([`testkit::TempDatabase`])
 //! - SQL script execution with pretty-printed output for snapshot testing
 //! - Common test fixtures and data generators
 //! - Property-based test generators for core types
@@ -68,6 +69,7 @@ pub mod macros;
 pub mod proptest_generators;
 pub mod runner;
 pub mod server;
+pub mod testkit;
 
 /// Convenient re-exports for common testing patterns.
 pub mod prelude {
@@ -76,6 +78,7 @@ pub mod prelude {
     pub use crate::fixtures::*;
     pub use crate::runner::*;
     pub use crate::server::{TestServer, TestServerWithRaft};
+    pub use crate::testkit::TempDatabase;
 
     // Re-export test setup macros
     pub use crate::row;
@@ -149,13 +149,13 @@ macro_rules! test_db {
 macro_rules! test_pager {
     ($pager:ident, $table:ident) => {
         let _dir = ::tempfile::tempdir().unwrap();
-        let mut $pager = ::buffer::FilePager::new(_dir.path(), 10);
+        let $pager = ::buffer::FilePager::new(_dir.path(), 10);
         let $table = ::common::TableId(1);
     };
 
     ($pager:ident, $table:ident, capacity: $cap:expr) => {
         let _dir = ::tempfile::tempdir().unwrap();
-        let mut $pager = ::buffer::FilePager::new(_dir.path(), $cap);
+        let $pager = ::buffer::FilePager::new(_dir.path(), $cap);
         let $table = ::common::TableId(1);
     };
 }
@@ -5,17 +5,24 @@
 
 use common::Row;
 use proptest::prelude::*;
-use types::{SqlType, Value};
+use types::{Decimal, SqlType, Value};
 use wal::WalRecord;
 
 /// Strategy for generating random `Value` instances.
 ///
-/// Generates a mix of Int, Text, Bool, and Null values.
+/// Generates a mix of Int, Text, Bool, Float, Date, Timestamp, Decimal,
+/// Bytes and Null values.
 pub fn arb_value() -> impl Strategy<Value = Value> {
     prop_oneof![
         any::<i64>().prop_map(Value::Int),
         "[a-z]{1,20}".prop_map(Value::Text),
         any::<bool>().prop_map(Value::Bool),
+        any::<f64>().prop_map(Value::Float),
+        any::<i32>().prop_map(Value::Date),
+        any::<i64>().prop_map(Value::Timestamp),
+        (any::<i64>(), 0..=10u8)
+            .prop_map(|(units, scale)| Value::Decimal(Decimal::new(units.into(), scale))),
+        prop::collection::vec(any::<u8>(), 0..32).prop_map(Value::Bytes),
         Just(Value::Null),
     ]
 }
@@ -38,6 +45,8 @@ pub fn arb_value() -> impl Strategy<Value = Value> {
 ///     }
 /// }
 /// ```
+// The example shows the strategy inside a test module
+#[allow(clippy::test_attr_in_doctest)]
 pub fn arb_row() -> impl Strategy<Value = Row> {
     prop::collection::vec(arb_value(), 1..10).prop_map(Row::new)
 }
@@ -57,13 +66,26 @@ pub fn arb_row() -> impl Strategy<Value = Row> {
 ///     }
 /// }
 /// ```
+// The example shows the strategy inside a test module
+#[allow(clippy::test_attr_in_doctest)]
 pub fn arb_row_with_len(len: usize) -> impl Strategy<Value = Row> {
     prop::collection::vec(arb_value(), len).prop_map(Row::new)
 }
 
 /// Strategy for generating random `SqlType` instances.
 pub fn arb_sql_type() -> impl Strategy<Value = SqlType> {
-    prop_oneof![Just(SqlType::Int), Just(SqlType::Text), Just(SqlType::Bool),]
+    prop_oneof![
+        Just(SqlType::Int),
+        Just(SqlType::Text),
+        Just(SqlType::Bool),
+        Just(SqlType::Float),
+        Just(SqlType::Date),
+        Just(SqlType::Timestamp),
+        (1..=38u8)
+            .prop_flat_map(|precision| (Just(precision), 0..=precision))
+            .prop_map(|(precision, scale)| SqlType::Decimal { precision, scale }),
+        Just(SqlType::Bytes),
+    ]
 }
 
 /// Strategy for generating WAL records for testing.
@@ -117,9 +139,17 @@ mod tests {
 
         #[test]
         fn prop_arb_value_always_valid(value in arb_value()) {
-            // Every generated value should be one of the four variants
+            // Every generated value should be one of the nine variants
             match value {
-                Value::Int(_) | Value::Text(_) | Value::Bool(_) | Value::Null => {}
+                Value::Int(_)
+                | Value::Text(_)
+                | Value::Bool(_)
+                | Value::Float(_)
+                | Value::Date(_)
+                | Value::Timestamp(_)
+                | Value::Decimal(_)
+                | Value::Bytes(_)
+                | Value::Null => {}
             }
         }
 
@@ -141,16 +141,26 @@ pub async fn run_sql_script_with_db(sql: &str, db: &Database) -> DbResult<String
 /// Format the query result for display.
 fn format_result(result: QueryResult, stmt: &str) -> String {
     match result {
-        QueryResult::Rows { schema, rows } => {
+        QueryResult::Rows { schema, rows, .. } => {
             let batch = RecordBatch {
                 columns: schema,
                 rows,
             };
             pretty::render_record_batch(&batch, TableStyleKind::Modern)
         }
-        QueryResult::Count { affected } => {
+        QueryResult::Count { affected, .. } => {
             format!("{} row(s) affected", affected)
         }
+        QueryResult::Inserted {
+            affected,
+            generated_ids,
+            ..
+        } => {
+            format!(
+                "{} row(s) affected, generated ids {:?}",
+                affected, generated_ids
+            )
+        }
         QueryResult::Empty => {
             // For DDL, extract names from SQL statements
             let stmt_trimmed = stmt.trim();
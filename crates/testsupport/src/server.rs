@@ -143,8 +143,13 @@ async fn handle_client(mut socket: TcpStream, db: Arc<Database>) -> Result<()> {
             ClientRequest::Execute { sql } => {
                 let result = db.execute(&sql).await;
                 let response = match result {
-                    Ok(QueryResult::Rows { schema, rows }) => ServerResponse::Rows { schema, rows },
-                    Ok(QueryResult::Count { affected }) => ServerResponse::Count { affected },
+                    Ok(QueryResult::Rows { schema, rows, .. }) => {
+                        ServerResponse::Rows { schema, rows }
+                    }
+                    Ok(
+                        QueryResult::Count { affected, .. }
+                        | QueryResult::Inserted { affected, .. },
+                    ) => ServerResponse::Count { affected },
                     Ok(QueryResult::Empty) => ServerResponse::Empty,
                     Err(err) => {
                         let code = map_error_to_code(&err);
@@ -173,6 +178,10 @@ fn map_error_to_code(err: &anyhow::Error) -> ErrorCode {
             DbError::Storage(_) => ErrorCode::StorageError,
             DbError::Wal(_) => ErrorCode::WalError,
             DbError::Constraint(_) => ErrorCode::ConstraintViolation,
+            DbError::ResourceExhausted(_) => ErrorCode::ResourceExhausted,
+            DbError::BufferPoolExhausted(_) => ErrorCode::BufferPoolExhausted,
+            DbError::Corruption(_) => ErrorCode::Corruption,
+            DbError::DiskFull(_) => ErrorCode::DiskFull,
             DbError::Io(_) => ErrorCode::IoError,
         }
     } else {
@@ -0,0 +1,233 @@
+//! Embedded databases for integration tests.
+//!
+//! [`TempDatabase`] opens a full [`Database`] in its own temporary directory,
+//! so a test can run SQL against it without a server, a shared data
+//! directory, or the `Box::leak` setup that [`TestContext`] needs to build an
+//! [`ExecutionContext`] by hand. The directory is removed when the database
+//! is dropped.
+//!
+//! [`TestContext`]: crate::context::TestContext
+//! [`ExecutionContext`]: executor::ExecutionContext
+
+use anyhow::{bail, Result};
+use common::Row;
+use database::{Database, EngineKind, QueryResult};
+use std::ops::Deref;
+use std::path::Path;
+use tempfile::TempDir;
+use types::decimal::MAX_PRECISION;
+use types::{binary, temporal, Value};
+
+/// Buffer pool pages each test database gets.
+const BUFFER_PAGES: usize = 64;
+
+/// A [`Database`] in a temporary directory, removed when dropped.
+///
+/// Derefs to the [`Database`], so everything it offers is available.
+///
+/// # Example
+///
+/// ```
+/// use testsupport::testkit::TempDatabase;
+/// use testsupport::fixtures::mixed_row;
+/// use types::Value;
+///
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let db = TempDatabase::new()
+///     .await?
+///     .with_schema("CREATE TABLE users (id INT PRIMARY KEY, name TEXT)")
+///     .await?;
+/// db.seed("users", [mixed_row(vec![Value::Int(1), Value::Text("Alice".into())])])
+///     .await?;
+///
+/// let rows = db.query("SELECT name FROM users").await?;
+/// assert_eq!(rows[0].values, vec![Value::Text("Alice".into())]);
+/// # anyhow::Ok(())
+/// # }).unwrap();
+/// ```
+pub struct TempDatabase {
+    db: Database,
+    temp_dir: TempDir,
+}
+
+impl TempDatabase {
+    /// Open an empty database whose tables are stored on disk, as they
+    /// would be in production.
+    pub async fn new() -> Result<Self> {
+        Self::open(EngineKind::Heap).await
+    }
+
+    /// Open an empty database whose tables keep their rows in memory.
+    ///
+    /// Tables created without `ENGINE = <name>` use the memory engine, so
+    /// tests that do not care about storage skip writing heap files. The
+    /// catalog and WAL are still written to the temporary directory.
+    pub async fn in_memory() -> Result<Self> {
+        Self::open(EngineKind::Memory).await
+    }
+
+    async fn open(engine: EngineKind) -> Result<Self> {
+        let temp_dir = TempDir::new()?;
+        let db = Database::new(temp_dir.path(), "catalog.json", "test.wal", BUFFER_PAGES)
+            .await?
+            .with_default_engine(engine);
+        Ok(Self { db, temp_dir })
+    }
+
+    /// Run `;`-separated DDL, such as `CREATE TABLE` and `CREATE INDEX`
+    /// statements, before the database is used.
+    pub async fn with_schema(self, sql: &str) -> Result<Self> {
+        self.db.execute_script(sql).await?;
+        Ok(self)
+    }
+
+    /// Insert `rows` into `table` with a single `INSERT`, returning how many
+    /// were inserted.
+    ///
+    /// Each row gives a value for every column, in the table's column order.
+    pub async fn seed(&self, table: &str, rows: impl IntoIterator<Item = Row>) -> Result<u64> {
+        let tuples: Vec<String> = rows
+            .into_iter()
+            .map(|row| {
+                let values: Vec<String> = row.values.iter().map(sql_literal).collect();
+                format!("({})", values.join(", "))
+            })
+            .collect();
+        if tuples.is_empty() {
+            return Ok(0);
+        }
+        let sql = format!("INSERT INTO {table} VALUES {}", tuples.join(", "));
+        match self.db.execute(&sql).await? {
+            QueryResult::Count { affected, .. } | QueryResult::Inserted { affected, .. } => {
+                Ok(affected)
+            }
+            other => bail!("expected a row count from INSERT, got {other:?}"),
+        }
+    }
+
+    /// Run a query and return its rows.
+    pub async fn query(&self, sql: &str) -> Result<Vec<Row>> {
+        match self.db.execute(sql).await? {
+            QueryResult::Rows { rows, .. } => Ok(rows),
+            other => bail!("expected rows from {sql:?}, got {other:?}"),
+        }
+    }
+
+    /// The temporary directory holding the database's files.
+    pub fn path(&self) -> &Path {
+        self.temp_dir.path()
+    }
+
+    /// The database, for APIs that take one by reference.
+    pub fn database(&self) -> &Database {
+        &self.db
+    }
+}
+
+impl Deref for TempDatabase {
+    type Target = Database;
+
+    fn deref(&self) -> &Database {
+        &self.db
+    }
+}
+
+/// `value` written as a SQL literal of the same type.
+fn sql_literal(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Int(n) => n.to_string(),
+        Value::Bool(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+        Value::Text(s) => format!("'{}'", s.replace('\'', "''")),
+        // Debug keeps the decimal point, so `1.0` is not read as an integer
+        Value::Float(f) if f.is_finite() => format!("{f:?}"),
+        Value::Float(f) => format!("CAST('{f}' AS DOUBLE)"),
+        Value::Date(days) => format!("DATE '{}'", temporal::format_date(*days)),
+        Value::Timestamp(micros) => {
+            format!("TIMESTAMP '{}'", temporal::format_timestamp(*micros))
+        }
+        Value::Decimal(d) => format!("CAST('{d}' AS DECIMAL({MAX_PRECISION}, {}))", d.scale()),
+        Value::Bytes(bytes) => format!("X'{}'", binary::encode_hex(bytes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{int_row, mixed_row};
+    use types::Decimal;
+
+    const SCHEMA: &str = "CREATE TABLE t (id INT PRIMARY KEY, v INT); \
+                          CREATE INDEX t_v ON t (v)";
+
+    #[tokio::test]
+    async fn seeded_rows_can_be_queried() {
+        let dbs = [
+            TempDatabase::new().await.unwrap(),
+            TempDatabase::in_memory().await.unwrap(),
+        ];
+        for db in dbs {
+            let db = db.with_schema(SCHEMA).await.unwrap();
+            let seeded = db
+                .seed("t", (1..=3).map(|i| int_row(&[i, i * 10])))
+                .await
+                .unwrap();
+            assert_eq!(seeded, 3);
+
+            let rows = db.query("SELECT v FROM t WHERE id = 2").await.unwrap();
+            assert_eq!(rows[0].values, vec![Value::Int(20)]);
+            assert_eq!(db.seed("t", Vec::new()).await.unwrap(), 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn in_memory_tables_use_the_memory_engine() {
+        let db = TempDatabase::in_memory()
+            .await
+            .unwrap()
+            .with_schema("CREATE TABLE t (id INT); CREATE TABLE h (id INT) ENGINE = heap")
+            .await
+            .unwrap();
+        let catalog = db.catalog();
+        let catalog = catalog.read().await;
+        assert_eq!(catalog.table("t").unwrap().engine, EngineKind::Memory);
+        assert_eq!(catalog.table("h").unwrap().engine, EngineKind::Heap);
+    }
+
+    #[tokio::test]
+    async fn every_value_type_round_trips_through_seed() {
+        let db = TempDatabase::new()
+            .await
+            .unwrap()
+            .with_schema(
+                "CREATE TABLE v (i INT, t TEXT, b BOOL, f DOUBLE, d DATE, \
+                 ts TIMESTAMP, n DECIMAL(10, 2), x BYTEA, z INT)",
+            )
+            .await
+            .unwrap();
+        let values = vec![
+            Value::Int(-7),
+            Value::Text("it's".into()),
+            Value::Bool(true),
+            Value::Float(1.0),
+            Value::Date(19_000),
+            Value::Timestamp(1_700_000_000_000_000),
+            Value::Decimal(Decimal::new(150, 2)),
+            Value::Bytes(vec![0xde, 0xad]),
+            Value::Null,
+        ];
+        db.seed("v", [mixed_row(values.clone())]).await.unwrap();
+
+        let rows = db.query("SELECT * FROM v").await.unwrap();
+        assert_eq!(rows[0].values, values);
+    }
+
+    #[tokio::test]
+    async fn directory_is_removed_on_drop() {
+        let db = TempDatabase::new().await.unwrap();
+        let path = db.path().to_path_buf();
+        assert!(path.exists());
+        drop(db);
+        assert!(!path.exists());
+    }
+}
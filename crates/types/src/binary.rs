@@ -0,0 +1,95 @@
+//! Text forms of `BYTEA` values.
+//!
+//! Bytes are written as hex with a `\x` prefix, as PostgreSQL does, and can
+//! also be read from and written as base64 with `ENCODE` and `DECODE`.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+/// Prefix of the hex form of a byte string.
+pub const HEX_PREFIX: &str = "\\x";
+
+/// Render bytes as `\x` followed by two lowercase hex digits per byte.
+pub fn format_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(HEX_PREFIX.len() + bytes.len() * 2);
+    out.push_str(HEX_PREFIX);
+    out.push_str(&encode_hex(bytes));
+    out
+}
+
+/// Two lowercase hex digits per byte, without a prefix.
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Parse hex digits, two per byte, in either case. Whitespace between
+/// bytes is ignored.
+pub fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = s
+        .bytes()
+        .filter(|b| !b.is_ascii_whitespace())
+        .map(|b| (b as char).to_digit(16).map(|d| d as u8))
+        .collect::<Option<_>>()?;
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    Some(
+        digits
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair[1])
+            .collect(),
+    )
+}
+
+/// Read text as bytes the way a `BYTEA` column does: `\x` followed by hex
+/// digits, or otherwise the text's own UTF-8 bytes.
+pub fn parse_bytes(s: &str) -> Option<Vec<u8>> {
+    match s.strip_prefix(HEX_PREFIX) {
+        Some(hex) => decode_hex(hex),
+        None => Some(s.as_bytes().to_vec()),
+    }
+}
+
+/// Standard base64 with padding.
+pub fn encode_base64(bytes: &[u8]) -> String {
+    STANDARD.encode(bytes)
+}
+
+/// Parse standard base64 with padding. Whitespace, such as line breaks, is
+/// ignored.
+pub fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    let compact: String = s.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    STANDARD.decode(compact).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_round_trips() {
+        assert_eq!(format_hex(&[0x00, 0xab, 0x10]), "\\x00ab10");
+        assert_eq!(format_hex(&[]), "\\x");
+        assert_eq!(decode_hex("00AB10"), Some(vec![0x00, 0xab, 0x10]));
+        assert_eq!(decode_hex("00 ab"), Some(vec![0x00, 0xab]));
+        assert_eq!(decode_hex("abc"), None);
+        assert_eq!(decode_hex("zz"), None);
+    }
+
+    #[test]
+    fn text_reads_as_hex_or_utf8() {
+        assert_eq!(
+            parse_bytes("\\xdeadbeef"),
+            Some(vec![0xde, 0xad, 0xbe, 0xef])
+        );
+        assert_eq!(parse_bytes("hi"), Some(b"hi".to_vec()));
+        assert_eq!(parse_bytes("\\xnope"), None);
+    }
+
+    #[test]
+    fn base64_round_trips() {
+        assert_eq!(encode_base64(b"hello"), "aGVsbG8=");
+        assert_eq!(decode_base64("aGVs\nbG8="), Some(b"hello".to_vec()));
+        assert_eq!(decode_base64("not base64!"), None);
+    }
+}
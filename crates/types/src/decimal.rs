@@ -0,0 +1,378 @@
+//! Exact fixed-point numbers for `DECIMAL(p, s)` / `NUMERIC(p, s)` values.
+//!
+//! A decimal is a count of units of `10^-scale`, held in an `i128`, so
+//! `19.99` is 1999 units at scale 2. Up to 38 digits fit. Arithmetic is exact
+//! except where a result has more fractional digits than [`MAX_SCALE`], or a
+//! quotient, which is rounded half away from zero.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Most digits a decimal can hold, and the largest precision a `DECIMAL`
+/// column can declare.
+pub const MAX_PRECISION: u8 = 38;
+
+/// Most digits after the point.
+pub const MAX_SCALE: u8 = MAX_PRECISION;
+
+/// Extra fractional digits kept by division beyond those of its operands.
+const DIVISION_EXTRA_SCALE: u8 = 6;
+
+fn pow10(exp: u8) -> Option<i128> {
+    10i128.checked_pow(u32::from(exp))
+}
+
+/// `n / d` rounded half away from zero.
+fn div_round(n: i128, d: i128) -> Option<i128> {
+    let quotient = n.checked_div(d)?;
+    let remainder = n % d;
+    if remainder.unsigned_abs() * 2 >= d.unsigned_abs() {
+        let away = if (n < 0) == (d < 0) { 1 } else { -1 };
+        quotient.checked_add(away)
+    } else {
+        Some(quotient)
+    }
+}
+
+/// An exact decimal number. Equal numbers with different scales, such as
+/// `1.5` and `1.50`, compare and hash alike.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct Decimal {
+    units: i128,
+    scale: u8,
+}
+
+impl Decimal {
+    /// `units * 10^-scale`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `scale` exceeds [`MAX_SCALE`].
+    pub fn new(units: i128, scale: u8) -> Self {
+        assert!(
+            scale <= MAX_SCALE,
+            "decimal scale {scale} exceeds {MAX_SCALE}"
+        );
+        Self { units, scale }
+    }
+
+    /// `units * 10^-scale` if it has at most [`MAX_PRECISION`] digits.
+    fn bounded(units: i128, scale: u8) -> Option<Self> {
+        Some(Self::new(units, scale)).filter(|d| d.precision() <= MAX_PRECISION)
+    }
+
+    /// The largest number a `DECIMAL(precision, scale)` column holds, e.g.
+    /// `999.99` for `DECIMAL(5, 2)`.
+    pub fn largest(precision: u8, scale: u8) -> Option<Self> {
+        Self::bounded(pow10(precision)? - 1, scale).filter(|_| scale <= precision)
+    }
+
+    pub fn from_int(i: i64) -> Self {
+        Self::new(i128::from(i), 0)
+    }
+
+    pub fn units(&self) -> i128 {
+        self.units
+    }
+
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    /// Number of significant digits, counting at least one.
+    pub fn precision(&self) -> u8 {
+        let mut digits = 1;
+        let mut rest = self.units.unsigned_abs() / 10;
+        while rest > 0 {
+            digits += 1;
+            rest /= 10;
+        }
+        digits
+    }
+
+    /// Parse `[+-]digits[.digits]`, keeping every digit written.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let (negative, digits) = match s.as_bytes().first()? {
+            b'-' => (true, &s[1..]),
+            b'+' => (false, &s[1..]),
+            _ => (false, s),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if whole.is_empty() && fraction.is_empty()
+            || !whole
+                .bytes()
+                .chain(fraction.bytes())
+                .all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+        let scale = u8::try_from(fraction.len())
+            .ok()
+            .filter(|&s| s <= MAX_SCALE)?;
+        let mut units: i128 = 0;
+        for digit in whole.bytes().chain(fraction.bytes()) {
+            units = units
+                .checked_mul(10)?
+                .checked_add(i128::from(digit - b'0'))?;
+        }
+        Self::bounded(if negative { -units } else { units }, scale)
+    }
+
+    /// The float's shortest decimal form, so `0.1` rather than the binary
+    /// fraction nearest it, rounded half away from zero to `scale` digits
+    /// after the point. `None` if the float is not finite or too large.
+    pub fn from_f64(f: f64, scale: u8) -> Option<Self> {
+        if !f.is_finite() {
+            return None;
+        }
+        Self::parse(&f.to_string())
+            .or_else(|| Self::parse(&format!("{:.*}", usize::from(MAX_SCALE), f)))?
+            .rescale(scale)
+    }
+
+    /// The nearest float.
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    /// The nearest integer, rounding half away from zero.
+    pub fn to_i64(&self) -> Option<i64> {
+        i64::try_from(self.rescale(0)?.units).ok()
+    }
+
+    /// The same number with `scale` digits after the point, rounding half
+    /// away from zero if that drops digits. `None` if it no longer fits.
+    pub fn rescale(&self, scale: u8) -> Option<Self> {
+        if scale > MAX_SCALE {
+            return None;
+        }
+        let units = match scale.cmp(&self.scale) {
+            Ordering::Equal => self.units,
+            Ordering::Greater => self.units.checked_mul(pow10(scale - self.scale)?)?,
+            Ordering::Less => div_round(self.units, pow10(self.scale - scale)?)?,
+        };
+        Self::bounded(units, scale)
+    }
+
+    /// Round to `scale` digits after the point and check that the result has
+    /// at most `precision` digits, as a `DECIMAL(precision, scale)` column
+    /// requires.
+    pub fn fit(&self, precision: u8, scale: u8) -> Option<Self> {
+        self.rescale(scale).filter(|d| d.precision() <= precision)
+    }
+
+    /// The same number without trailing fractional zeros.
+    fn normalized(&self) -> Self {
+        let mut d = *self;
+        while d.scale > 0 && d.units % 10 == 0 {
+            d = Self::new(d.units / 10, d.scale - 1);
+        }
+        d
+    }
+
+    /// Both operands at the larger of their scales.
+    fn aligned(&self, other: &Self) -> Option<(i128, i128, u8)> {
+        let scale = self.scale.max(other.scale);
+        Some((
+            self.rescale(scale)?.units,
+            other.rescale(scale)?.units,
+            scale,
+        ))
+    }
+
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let (a, b, scale) = self.aligned(other)?;
+        Self::bounded(a.checked_add(b)?, scale)
+    }
+
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        let (a, b, scale) = self.aligned(other)?;
+        Self::bounded(a.checked_sub(b)?, scale)
+    }
+
+    pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+        let units = self.units.checked_mul(other.units)?;
+        let scale = self.scale + other.scale;
+        if scale <= MAX_SCALE {
+            Self::bounded(units, scale)
+        } else {
+            let units = div_round(units, pow10(scale - MAX_SCALE)?)?;
+            Self::bounded(units, MAX_SCALE)
+        }
+    }
+
+    /// The quotient with [`DIVISION_EXTRA_SCALE`] more digits after the point
+    /// than the more precise operand. `None` when dividing by zero.
+    pub fn checked_div(&self, other: &Self) -> Option<Self> {
+        if other.units == 0 {
+            return None;
+        }
+        let scale = (self.scale.max(other.scale) + DIVISION_EXTRA_SCALE).min(MAX_SCALE);
+        // units / 10^scale = (a / 10^sa) / (b / 10^sb)
+        let numerator = self
+            .units
+            .checked_mul(pow10(scale + other.scale - self.scale)?)?;
+        Self::bounded(div_round(numerator, other.units)?, scale)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Whole parts first, so that no operand has to grow to the other's
+        // scale; the fractions then fit at the larger scale.
+        let whole = |d: &Self| d.units / pow10(d.scale).expect("scale is at most 38");
+        let fraction = |d: &Self| {
+            let fraction = Self::new(
+                d.units % pow10(d.scale).expect("scale is at most 38"),
+                d.scale,
+            );
+            fraction
+                .rescale(self.scale.max(other.scale))
+                .expect("a fraction fits at any larger scale")
+                .units
+        };
+        whole(self)
+            .cmp(&whole(other))
+            .then_with(|| fraction(self).cmp(&fraction(other)))
+    }
+}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let d = self.normalized();
+        d.units.hash(state);
+        d.scale.hash(state);
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.units.unsigned_abs().to_string();
+        let scale = usize::from(self.scale);
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        if self.units < 0 {
+            f.write_str("-")?;
+        }
+        f.write_str(whole)?;
+        if scale > 0 {
+            write!(f, ".{fraction}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::parse(s).unwrap()
+    }
+
+    #[test]
+    fn parse_and_display_round_trip() {
+        for s in [
+            "0",
+            "19.99",
+            "-0.05",
+            "100",
+            "0.000",
+            "-12345678901234567890.123456",
+        ] {
+            assert_eq!(dec(s).to_string(), s);
+        }
+        assert_eq!(dec("+.5").to_string(), "0.5");
+        assert_eq!(dec("7.").to_string(), "7");
+        assert_eq!(Decimal::parse("1.2.3"), None);
+        assert_eq!(Decimal::parse("1e5"), None);
+        assert_eq!(Decimal::parse("."), None);
+        assert_eq!(Decimal::parse(&"9".repeat(39)), None);
+    }
+
+    #[test]
+    fn equal_values_ignore_scale() {
+        use std::collections::hash_map::DefaultHasher;
+        let hash = |d: Decimal| {
+            let mut hasher = DefaultHasher::new();
+            d.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(dec("1.5"), dec("1.50"));
+        assert_eq!(hash(dec("1.5")), hash(dec("1.50")));
+        assert!(dec("-0.5") < dec("0.3"));
+        assert!(dec("2") > dec("1.999"));
+        assert!(dec("-1.25") < dec("-1.2"));
+        // Operands too far apart to share a scale still compare
+        assert!(dec(&"9".repeat(38)) > dec("0.00000000000000000000000000000000000001"));
+    }
+
+    #[test]
+    fn rescale_rounds_half_away_from_zero() {
+        assert_eq!(dec("2.345").rescale(2), Some(dec("2.35")));
+        assert_eq!(dec("-2.345").rescale(2), Some(dec("-2.35")));
+        assert_eq!(dec("2.344").rescale(2), Some(dec("2.34")));
+        assert_eq!(dec("2.5").rescale(4).unwrap().to_string(), "2.5000");
+        assert_eq!(dec("123.456").fit(5, 2), Some(dec("123.46")));
+        assert_eq!(dec("1234.5").fit(5, 2), None);
+        assert_eq!(dec("-2.5").to_i64(), Some(-3));
+        assert_eq!(Decimal::largest(5, 2), Some(dec("999.99")));
+        assert_eq!(Decimal::largest(38, 0), Some(dec(&"9".repeat(38))));
+    }
+
+    #[test]
+    fn arithmetic_is_exact() {
+        assert_eq!(dec("0.1").checked_add(&dec("0.2")), Some(dec("0.3")));
+        assert_eq!(
+            dec("10.00").checked_sub(&dec("0.01")).unwrap().to_string(),
+            "9.99"
+        );
+        assert_eq!(
+            dec("1.10").checked_mul(&dec("3")).unwrap().to_string(),
+            "3.30"
+        );
+        assert_eq!(
+            dec("10.00").checked_div(&dec("3")).unwrap().to_string(),
+            "3.33333333"
+        );
+        assert_eq!(
+            dec("2").checked_div(&dec("-3")).unwrap().to_string(),
+            "-0.666667"
+        );
+        assert_eq!(dec("1").checked_div(&dec("0")), None);
+        assert_eq!(dec(&"9".repeat(38)).checked_add(&dec("1")), None);
+    }
+
+    #[test]
+    fn converts_to_and_from_floats() {
+        assert_eq!(Decimal::from_f64(0.1, 2), Some(dec("0.10")));
+        assert_eq!(Decimal::from_f64(-2.675, 1), Some(dec("-2.7")));
+        assert_eq!(Decimal::from_f64(0.125, 2), Some(dec("0.13")));
+        assert_eq!(Decimal::from_f64(1e-300, 2), Some(dec("0.00")));
+        assert_eq!(Decimal::from_f64(f64::NAN, 2), None);
+        assert_eq!(Decimal::from_f64(1e300, 0), None);
+        assert_eq!(dec("19.99").to_f64(), 19.99);
+    }
+}
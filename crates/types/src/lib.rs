@@ -1,18 +1,241 @@
 use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+pub mod binary;
+pub mod decimal;
+pub mod temporal;
+
+pub use decimal::Decimal;
 
 #[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum SqlType {
     Int,
     Text,
     Bool,
+    Float,
+    Date,
+    Timestamp,
+    /// Exact numbers with `precision` digits, `scale` of them after the
+    /// point.
+    Decimal {
+        precision: u8,
+        scale: u8,
+    },
+    /// Byte strings of any length.
+    Bytes,
+}
+
+impl SqlType {
+    /// Resolve a type name as written in SQL, e.g. `INTEGER` or
+    /// `DOUBLE PRECISION`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_uppercase().as_str() {
+            "INT" | "INTEGER" => Some(SqlType::Int),
+            "TEXT" | "STRING" | "VARCHAR" => Some(SqlType::Text),
+            "BOOL" | "BOOLEAN" => Some(SqlType::Bool),
+            "FLOAT" | "FLOAT4" | "FLOAT8" | "REAL" | "DOUBLE" | "DOUBLE PRECISION" => {
+                Some(SqlType::Float)
+            }
+            "DATE" => Some(SqlType::Date),
+            "TIMESTAMP" | "TIMESTAMP WITHOUT TIME ZONE" | "DATETIME" => Some(SqlType::Timestamp),
+            "BYTEA" | "BLOB" | "BYTES" => Some(SqlType::Bytes),
+            name if TextLength::from_type_name(name).is_some() => Some(SqlType::Text),
+            name => Self::decimal_from_name(name),
+        }
+    }
+
+    /// `DECIMAL`, `DECIMAL(p)` or `DECIMAL(p, s)`, or the same with `NUMERIC`
+    /// or `DEC`. The precision defaults to the largest and the scale to 0.
+    fn decimal_from_name(name: &str) -> Option<Self> {
+        let (base, args) = match name.split_once('(') {
+            Some((base, args)) => (base.trim_end(), Some(args.strip_suffix(')')?)),
+            None => (name, None),
+        };
+        if !matches!(base, "DECIMAL" | "NUMERIC" | "DEC") {
+            return None;
+        }
+        let args: Vec<Option<u8>> = match args {
+            Some(args) => args.split(',').map(|arg| arg.trim().parse().ok()).collect(),
+            None => Vec::new(),
+        };
+        let (precision, scale) = match args.as_slice() {
+            [] => (decimal::MAX_PRECISION, 0),
+            [Some(precision)] => (*precision, 0),
+            [Some(precision), Some(scale)] => (*precision, *scale),
+            _ => return None,
+        };
+        ((1..=decimal::MAX_PRECISION).contains(&precision) && scale <= precision)
+            .then_some(SqlType::Decimal { precision, scale })
+    }
+}
+
+impl fmt::Display for SqlType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SqlType::Int => "INT",
+            SqlType::Text => "TEXT",
+            SqlType::Bool => "BOOL",
+            SqlType::Float => "FLOAT",
+            SqlType::Date => "DATE",
+            SqlType::Timestamp => "TIMESTAMP",
+            SqlType::Bytes => "BYTEA",
+            SqlType::Decimal { precision, scale } => {
+                return write!(f, "DECIMAL({precision},{scale})");
+            }
+        })
+    }
+}
+
+/// Declared length of a `VARCHAR(n)` or `CHAR(n)` column, which holds text
+/// of at most, or exactly, `n` characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum TextLength {
+    /// `VARCHAR(n)`: at most `n` characters.
+    Varying(u32),
+    /// `CHAR(n)`: shorter text is padded with spaces to `n` characters.
+    Fixed(u32),
+}
+
+impl TextLength {
+    /// The length declared by a type name such as `VARCHAR(255)`,
+    /// `CHARACTER VARYING(8)` or `CHAR(2)`. A `CHAR` without a length has
+    /// length 1, as in PostgreSQL. `None` for other names, including a
+    /// `VARCHAR` without a length.
+    pub fn from_type_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_uppercase();
+        let (base, len) = match name.split_once('(') {
+            Some((base, len)) => (base.trim_end(), Some(len.strip_suffix(')')?)),
+            None => (name.as_str(), None),
+        };
+        let len = match len {
+            Some(len) => Some(len.trim().parse().ok().filter(|&n| n > 0)?),
+            None => None,
+        };
+        match (base, len) {
+            ("VARCHAR" | "CHARACTER VARYING" | "CHAR VARYING", Some(n)) => {
+                Some(TextLength::Varying(n))
+            }
+            ("CHAR" | "CHARACTER" | "BPCHAR", n) => Some(TextLength::Fixed(n.unwrap_or(1))),
+            _ => None,
+        }
+    }
+
+    /// `text` as stored under this length, or `None` if it is too long.
+    ///
+    /// As in PostgreSQL, spaces past the end are dropped rather than
+    /// rejected, and `CHAR` pads text with spaces.
+    pub fn fit(self, text: &str) -> Option<String> {
+        let (TextLength::Varying(n) | TextLength::Fixed(n)) = self;
+        let n = n as usize;
+        let len = text.chars().count();
+        if len > n {
+            let (end, _) = text.char_indices().nth(n)?;
+            return text[end..]
+                .chars()
+                .all(|c| c == ' ')
+                .then(|| text[..end].to_string());
+        }
+        match self {
+            TextLength::Varying(_) => Some(text.to_string()),
+            TextLength::Fixed(_) => Some(format!("{text}{}", " ".repeat(n - len))),
+        }
+    }
+}
+
+impl fmt::Display for TextLength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextLength::Varying(n) => write!(f, "VARCHAR({n})"),
+            TextLength::Fixed(n) => write!(f, "CHAR({n})"),
+        }
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+// New variants go at the end: rows are stored with bincode, which encodes
+// the variant index.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub enum Value {
     Int(i64),
     Text(String),
     Bool(bool),
     Null,
+    /// Double precision. `-0.0` equals `0.0`, and NaN equals itself and sorts
+    /// above every other number, as in PostgreSQL.
+    Float(f64),
+    /// Days since 1970-01-01 (see [`temporal`]).
+    Date(i32),
+    /// Microseconds since 1970-01-01 00:00:00, without a time zone.
+    Timestamp(i64),
+    /// Exact fixed-point number; `1.5` equals `1.50`.
+    Decimal(Decimal),
+    /// Byte string, ordered byte by byte (see [`binary`]).
+    Bytes(Vec<u8>),
+}
+
+/// The representative of `f`'s equivalence class: one zero and one NaN.
+fn canonical(f: f64) -> f64 {
+    if f.is_nan() {
+        f64::NAN
+    } else if f == 0.0 {
+        0.0
+    } else {
+        f
+    }
+}
+
+fn cmp_floats(a: f64, b: f64) -> Ordering {
+    canonical(a).total_cmp(&canonical(b))
+}
+
+/// Compare a decimal with a float by the float nearest the decimal.
+fn cmp_decimal_float(d: &Decimal, f: f64) -> Ordering {
+    cmp_floats(d.to_f64(), f)
+}
+
+/// Compare a date with a timestamp as the instant at the start of the date.
+fn cmp_date_timestamp(days: i32, micros: i64) -> Ordering {
+    temporal::date_to_timestamp(days).cmp(&micros)
+}
+
+/// Compare an integer with a float exactly, without rounding the integer.
+fn cmp_int_float(i: i64, f: f64) -> Ordering {
+    // 2^63 is exactly representable; i64 covers [-2^63, 2^63)
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+    if f.is_nan() || f >= LIMIT {
+        return Ordering::Less;
+    }
+    if f < -LIMIT {
+        return Ordering::Greater;
+    }
+    let whole = f.trunc();
+    i.cmp(&(whole as i64))
+        .then_with(|| 0.0.partial_cmp(&(f - whole)).unwrap_or(Ordering::Equal))
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Value {}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Value::Int(i) => i.hash(state),
+            Value::Text(s) => s.hash(state),
+            Value::Bool(b) => b.hash(state),
+            Value::Null => {}
+            Value::Float(f) => canonical(*f).to_bits().hash(state),
+            Value::Date(d) => d.hash(state),
+            Value::Timestamp(t) => t.hash(state),
+            Value::Decimal(d) => d.hash(state),
+            Value::Bytes(b) => b.hash(state),
+        }
+    }
 }
 
 impl PartialOrd for Value {
@@ -24,22 +247,55 @@ impl PartialOrd for Value {
 impl Ord for Value {
     fn cmp(&self, other: &Self) -> Ordering {
         // Define a total ordering for all values:
-        // Null < Bool < Int < Text
-        // Within each type, use natural ordering
+        // Null < Bool < Int, Decimal and Float < Date and Timestamp < Text
+        //   < Bytes
+        // Within each type, use natural ordering. Numbers are ordered by
+        // value, with an int before an equal decimal before an equal float,
+        // and dates and timestamps by instant, with a date before its
+        // midnight.
         match (self, other) {
             (Value::Null, Value::Null) => Ordering::Equal,
             (Value::Null, _) => Ordering::Less,
             (_, Value::Null) => Ordering::Greater,
 
             (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
-            (Value::Bool(_), Value::Int(_) | Value::Text(_)) => Ordering::Less,
-            (Value::Int(_) | Value::Text(_), Value::Bool(_)) => Ordering::Greater,
+            (Value::Bool(_), _) => Ordering::Less,
+            (_, Value::Bool(_)) => Ordering::Greater,
 
-            (Value::Int(a), Value::Int(b)) => a.cmp(b),
-            (Value::Int(_), Value::Text(_)) => Ordering::Less,
-            (Value::Text(_), Value::Int(_)) => Ordering::Greater,
+            (Value::Bytes(a), Value::Bytes(b)) => a.cmp(b),
+            (Value::Bytes(_), _) => Ordering::Greater,
+            (_, Value::Bytes(_)) => Ordering::Less,
 
             (Value::Text(a), Value::Text(b)) => a.cmp(b),
+            (Value::Text(_), _) => Ordering::Greater,
+            (_, Value::Text(_)) => Ordering::Less,
+
+            (Value::Date(a), Value::Date(b)) => a.cmp(b),
+            (Value::Timestamp(a), Value::Timestamp(b)) => a.cmp(b),
+            (Value::Date(a), Value::Timestamp(b)) => {
+                cmp_date_timestamp(*a, *b).then(Ordering::Less)
+            }
+            (Value::Timestamp(a), Value::Date(b)) => {
+                cmp_date_timestamp(*b, *a).reverse().then(Ordering::Greater)
+            }
+            (Value::Date(_) | Value::Timestamp(_), _) => Ordering::Greater,
+            (_, Value::Date(_) | Value::Timestamp(_)) => Ordering::Less,
+
+            (Value::Int(a), Value::Int(b)) => a.cmp(b),
+            (Value::Float(a), Value::Float(b)) => cmp_floats(*a, *b),
+            (Value::Int(a), Value::Float(b)) => cmp_int_float(*a, *b).then(Ordering::Less),
+            (Value::Float(a), Value::Int(b)) => {
+                cmp_int_float(*b, *a).reverse().then(Ordering::Greater)
+            }
+            (Value::Decimal(a), Value::Decimal(b)) => a.cmp(b),
+            (Value::Int(a), Value::Decimal(b)) => Decimal::from_int(*a).cmp(b).then(Ordering::Less),
+            (Value::Decimal(a), Value::Int(b)) => {
+                a.cmp(&Decimal::from_int(*b)).then(Ordering::Greater)
+            }
+            (Value::Decimal(a), Value::Float(b)) => cmp_decimal_float(a, *b).then(Ordering::Less),
+            (Value::Float(a), Value::Decimal(b)) => {
+                cmp_decimal_float(b, *a).reverse().then(Ordering::Greater)
+            }
         }
     }
 }
@@ -52,23 +308,156 @@ impl Value {
         }
     }
 
+    /// Compare values of the same type, treating ints, decimals and floats as
+    /// one numeric type and dates and timestamps as one temporal type. Text is
+    /// compared with a date or timestamp by parsing it, so that
+    /// `created = '2024-01-01'` works.
     pub fn cmp_same_type(&self, other: &Value) -> Option<Ordering> {
         match (self, other) {
             (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
             (Value::Text(a), Value::Text(b)) => Some(a.cmp(b)),
             (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            (Value::Bytes(a), Value::Bytes(b)) => Some(a.cmp(b)),
+            (Value::Float(a), Value::Float(b)) => Some(cmp_floats(*a, *b)),
+            (Value::Int(a), Value::Float(b)) => Some(cmp_int_float(*a, *b)),
+            (Value::Float(a), Value::Int(b)) => Some(cmp_int_float(*b, *a).reverse()),
+            (Value::Decimal(a), Value::Decimal(b)) => Some(a.cmp(b)),
+            (Value::Int(a), Value::Decimal(b)) => Some(Decimal::from_int(*a).cmp(b)),
+            (Value::Decimal(a), Value::Int(b)) => Some(a.cmp(&Decimal::from_int(*b))),
+            (Value::Decimal(a), Value::Float(b)) => Some(cmp_decimal_float(a, *b)),
+            (Value::Float(a), Value::Decimal(b)) => Some(cmp_decimal_float(b, *a).reverse()),
+            (Value::Date(a), Value::Date(b)) => Some(a.cmp(b)),
+            (Value::Timestamp(a), Value::Timestamp(b)) => Some(a.cmp(b)),
+            (Value::Date(a), Value::Timestamp(b)) => Some(cmp_date_timestamp(*a, *b)),
+            (Value::Timestamp(a), Value::Date(b)) => Some(cmp_date_timestamp(*b, *a).reverse()),
+            (temporal @ (Value::Date(_) | Value::Timestamp(_)), Value::Text(text)) => {
+                temporal.cmp_same_type(&Value::Text(text.clone()).cast(&SqlType::Timestamp).ok()?)
+            }
+            (Value::Text(_), Value::Date(_) | Value::Timestamp(_)) => {
+                other.cmp_same_type(self).map(Ordering::reverse)
+            }
             _ => None,
         }
     }
 
     pub fn eq_same_type(&self, other: &Value) -> Option<bool> {
-        match (self, other) {
-            (Value::Int(a), Value::Int(b)) => Some(a.eq(b)),
-            (Value::Text(a), Value::Text(b)) => Some(a.eq(b)),
-            (Value::Bool(a), Value::Bool(b)) => Some(a.eq(b)),
-            _ => None,
+        self.cmp_same_type(other)
+            .map(|ordering| ordering == Ordering::Equal)
+    }
+
+    /// The value as named in error messages, e.g. `the float 2.5` or
+    /// `the date 2024-01-01`.
+    pub fn describe(&self) -> String {
+        match self {
+            Value::Float(f) => format!("the float {f:?}"),
+            Value::Decimal(d) => format!("the decimal {d}"),
+            Value::Date(d) => format!("the date {}", temporal::format_date(*d)),
+            Value::Timestamp(t) => format!("the timestamp {}", temporal::format_timestamp(*t)),
+            Value::Bytes(b) => format!("the bytes {}", binary::format_hex(b)),
+            other => format!("{other:?}"),
         }
     }
+
+    /// Convert the value for storage in, or comparison with, a column of type
+    /// `ty`: integers and decimals become floats in FLOAT columns, dates
+    /// become timestamps in TIMESTAMP columns, and text that parses as a date
+    /// or timestamp becomes one in DATE and TIMESTAMP columns. Numbers, and
+    /// text that parses as one, are rounded to the scale of a DECIMAL column
+    /// if they fit its precision. Text becomes bytes in BYTEA columns (see
+    /// [`binary::parse_bytes`]). Other values are returned unchanged.
+    pub fn coerce_to(self, ty: &SqlType) -> Value {
+        match (self, ty) {
+            (Value::Int(i), SqlType::Float) => Value::Float(i as f64),
+            (Value::Decimal(d), SqlType::Float) => Value::Float(d.to_f64()),
+            (
+                value @ (Value::Int(_) | Value::Float(_) | Value::Decimal(_) | Value::Text(_)),
+                SqlType::Decimal { .. },
+            ) => value.clone().cast(ty).unwrap_or(value),
+            (value @ (Value::Date(_) | Value::Text(_)), SqlType::Timestamp)
+            | (value @ Value::Text(_), SqlType::Date | SqlType::Bytes) => {
+                value.clone().cast(ty).unwrap_or(value)
+            }
+            (value, _) => value,
+        }
+    }
+
+    /// `CAST(value AS ty)`. NULL casts to NULL.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message naming the value if it has no `ty` equivalent, for
+    /// example text that is not a number or a float too large for an INT.
+    pub fn cast(self, ty: &SqlType) -> Result<Value, String> {
+        let cast = match (&self, ty) {
+            (Value::Null, _) => Some(Value::Null),
+            (Value::Int(_), SqlType::Int)
+            | (Value::Text(_), SqlType::Text)
+            | (Value::Bool(_), SqlType::Bool)
+            | (Value::Float(_), SqlType::Float)
+            | (Value::Date(_), SqlType::Date)
+            | (Value::Timestamp(_), SqlType::Timestamp)
+            | (Value::Bytes(_), SqlType::Bytes) => Some(self.clone()),
+
+            (Value::Int(i), SqlType::Decimal { precision, scale }) => Decimal::from_int(*i)
+                .fit(*precision, *scale)
+                .map(Value::Decimal),
+            (Value::Float(f), SqlType::Decimal { precision, scale }) => {
+                Decimal::from_f64(*f, *scale)
+                    .and_then(|d| d.fit(*precision, *scale))
+                    .map(Value::Decimal)
+            }
+            (Value::Decimal(d), SqlType::Decimal { precision, scale }) => {
+                d.fit(*precision, *scale).map(Value::Decimal)
+            }
+            (Value::Decimal(d), SqlType::Int) => d.to_i64().map(Value::Int),
+            (Value::Decimal(d), SqlType::Float) => Some(Value::Float(d.to_f64())),
+
+            (Value::Int(i), SqlType::Float) => Some(Value::Float(*i as f64)),
+            (Value::Int(i), SqlType::Bool) => Some(Value::Bool(*i != 0)),
+            (Value::Bool(b), SqlType::Int) => Some(Value::Int(i64::from(*b))),
+            // Rounds half away from zero, as PostgreSQL does
+            (Value::Float(f), SqlType::Int) => {
+                let rounded = f.round();
+                (rounded >= i64::MIN as f64 && rounded < i64::MAX as f64)
+                    .then_some(Value::Int(rounded as i64))
+            }
+            (Value::Date(d), SqlType::Timestamp) => {
+                Some(Value::Timestamp(temporal::date_to_timestamp(*d)))
+            }
+            (Value::Timestamp(t), SqlType::Date) => {
+                Some(Value::Date(temporal::timestamp_to_date(*t)))
+            }
+
+            (Value::Int(i), SqlType::Text) => Some(Value::Text(i.to_string())),
+            (Value::Bool(b), SqlType::Text) => Some(Value::Text(b.to_string())),
+            (Value::Float(f), SqlType::Text) => Some(Value::Text(format!("{f:?}"))),
+            (Value::Decimal(d), SqlType::Text) => Some(Value::Text(d.to_string())),
+            (Value::Date(d), SqlType::Text) => Some(Value::Text(temporal::format_date(*d))),
+            (Value::Timestamp(t), SqlType::Text) => {
+                Some(Value::Text(temporal::format_timestamp(*t)))
+            }
+            (Value::Bytes(b), SqlType::Text) => Some(Value::Text(binary::format_hex(b))),
+
+            (Value::Text(s), SqlType::Int) => s.trim().parse().ok().map(Value::Int),
+            (Value::Text(s), SqlType::Float) => s.trim().parse().ok().map(Value::Float),
+            (Value::Text(s), SqlType::Decimal { precision, scale }) => Decimal::parse(s)
+                .and_then(|d| d.fit(*precision, *scale))
+                .map(Value::Decimal),
+            (Value::Text(s), SqlType::Bool) => match s.trim().to_ascii_lowercase().as_str() {
+                "true" | "t" | "yes" | "1" => Some(Value::Bool(true)),
+                "false" | "f" | "no" | "0" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            (Value::Text(s), SqlType::Date) => temporal::parse_date(s).map(Value::Date),
+            (Value::Text(s), SqlType::Timestamp) => {
+                temporal::parse_timestamp(s).map(Value::Timestamp)
+            }
+            (Value::Text(s), SqlType::Bytes) => binary::parse_bytes(s).map(Value::Bytes),
+
+            _ => None,
+        };
+        cast.ok_or_else(|| format!("cannot cast {self:?} to {ty}"))
+    }
 }
 
 #[cfg(test)]
@@ -131,6 +520,11 @@ mod tests {
             Value::Text("Ada".into()),
             Value::Bool(true),
             Value::Null,
+            Value::Float(-2.5),
+            Value::Date(-365),
+            Value::Timestamp(1_700_000_000_000_000),
+            Value::Decimal(Decimal::new(-1999, 2)),
+            Value::Bytes(vec![0, 255, 7]),
         ];
 
         let json = serde_json::to_string(&vals).unwrap();
@@ -171,7 +565,290 @@ mod tests {
         assert_eq!(Value::Int(42).cmp(&Value::Int(42)), Equal);
     }
 
+    #[test]
+    fn floats_compare_by_value() {
+        assert_eq!(
+            Value::Float(1.5).cmp_same_type(&Value::Float(2.5)),
+            Some(Less)
+        );
+        assert_eq!(Value::Float(-0.0), Value::Float(0.0));
+        assert_eq!(Value::Float(f64::NAN), Value::Float(f64::NAN));
+        // NaN sorts above every number, infinity included
+        assert!(Value::Float(f64::INFINITY) < Value::Float(f64::NAN));
+        assert!(Value::Float(f64::NEG_INFINITY) < Value::Int(i64::MIN));
+
+        // Ints and floats compare as numbers
+        assert_eq!(Value::Int(1).eq_same_type(&Value::Float(1.0)), Some(true));
+        assert_eq!(
+            Value::Float(2.5).cmp_same_type(&Value::Int(2)),
+            Some(Greater)
+        );
+        assert_eq!(
+            Value::Int(-2).cmp_same_type(&Value::Float(-1.5)),
+            Some(Less)
+        );
+        // i64::MAX rounds up to 2^63 as a float but is smaller
+        assert_eq!(
+            Value::Int(i64::MAX).cmp_same_type(&Value::Float(i64::MAX as f64)),
+            Some(Less)
+        );
+        assert_eq!(
+            Value::Float(1.0).cmp_same_type(&Value::Text("1".into())),
+            None
+        );
+
+        // The total order keeps an int before an equal float
+        assert!(Value::Int(1) < Value::Float(1.0));
+        assert!(Value::Float(1.0) < Value::Int(2));
+        assert!(Value::Bool(true) < Value::Float(f64::NEG_INFINITY));
+        assert!(Value::Float(f64::NAN) < Value::Text(String::new()));
+    }
+
+    #[test]
+    fn equal_floats_hash_alike() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let hash = |value: &Value| {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&Value::Float(-0.0)), hash(&Value::Float(0.0)));
+        assert_eq!(
+            hash(&Value::Float(f64::NAN)),
+            hash(&Value::Float(-f64::NAN))
+        );
+    }
+
+    #[test]
+    fn ints_widen_to_float_columns() {
+        assert_eq!(Value::Int(3).coerce_to(&SqlType::Float), Value::Float(3.0));
+        assert_eq!(Value::Int(3).coerce_to(&SqlType::Int), Value::Int(3));
+        assert_eq!(Value::Null.coerce_to(&SqlType::Float), Value::Null);
+    }
+
+    #[test]
+    fn dates_and_timestamps_compare_by_instant() {
+        let day = Value::Date(temporal::parse_date("2024-05-01").unwrap());
+        let midnight = Value::Timestamp(temporal::parse_timestamp("2024-05-01").unwrap());
+        let noon = Value::Timestamp(temporal::parse_timestamp("2024-05-01 12:00:00").unwrap());
+
+        assert_eq!(day.cmp_same_type(&midnight), Some(Equal));
+        assert_eq!(day.cmp_same_type(&noon), Some(Less));
+        assert_eq!(noon.cmp_same_type(&day), Some(Greater));
+        assert_eq!(day.cmp_same_type(&Value::Int(0)), None);
+
+        // Text is parsed for the comparison
+        assert_eq!(
+            day.cmp_same_type(&Value::Text("2024-05-01".into())),
+            Some(Equal)
+        );
+        assert_eq!(
+            Value::Text("2024-05-01 13:00:00".into()).cmp_same_type(&noon),
+            Some(Greater)
+        );
+        assert_eq!(day.cmp_same_type(&Value::Text("May 1st".into())), None);
+
+        // The total order keeps a date before its midnight, and temporal
+        // values between numbers and text
+        assert!(day < midnight);
+        assert!(midnight < noon);
+        assert!(Value::Float(f64::NAN) < Value::Date(i32::MIN));
+        assert!(Value::Timestamp(i64::MAX) < Value::Text(String::new()));
+    }
+
+    #[test]
+    fn text_widens_to_temporal_columns() {
+        assert_eq!(
+            Value::Text("2024-05-01".into()).coerce_to(&SqlType::Date),
+            Value::Date(19844)
+        );
+        assert_eq!(
+            Value::Date(1).coerce_to(&SqlType::Timestamp),
+            Value::Timestamp(temporal::MICROS_PER_DAY)
+        );
+        // Text that is not a date is left for the caller to reject
+        assert_eq!(
+            Value::Text("soon".into()).coerce_to(&SqlType::Date),
+            Value::Text("soon".into())
+        );
+    }
+
+    #[test]
+    fn casts_between_types() {
+        let cast = |value: Value, ty| value.cast(&ty);
+        assert_eq!(
+            cast(Value::Text(" 42 ".into()), SqlType::Int),
+            Ok(Value::Int(42))
+        );
+        assert_eq!(cast(Value::Float(2.5), SqlType::Int), Ok(Value::Int(3)));
+        assert_eq!(cast(Value::Float(-2.5), SqlType::Int), Ok(Value::Int(-3)));
+        assert_eq!(cast(Value::Int(7), SqlType::Float), Ok(Value::Float(7.0)));
+        assert_eq!(
+            cast(Value::Text("yes".into()), SqlType::Bool),
+            Ok(Value::Bool(true))
+        );
+        assert_eq!(cast(Value::Null, SqlType::Date), Ok(Value::Null));
+        assert_eq!(
+            cast(Value::Text("2024-05-01 08:30:00".into()), SqlType::Date),
+            Err("cannot cast Text(\"2024-05-01 08:30:00\") to DATE".into())
+        );
+        let noon = cast(
+            Value::Text("2024-05-01 12:00:00".into()),
+            SqlType::Timestamp,
+        )
+        .unwrap();
+        assert_eq!(cast(noon.clone(), SqlType::Date), Ok(Value::Date(19844)));
+        assert_eq!(
+            cast(noon, SqlType::Text),
+            Ok(Value::Text("2024-05-01 12:00:00".into()))
+        );
+        assert!(cast(Value::Float(f64::NAN), SqlType::Int).is_err());
+        assert!(cast(Value::Float(1e19), SqlType::Int).is_err());
+        assert!(cast(Value::Bool(true), SqlType::Date).is_err());
+    }
+
+    #[test]
+    fn decimals_compare_with_other_numbers_by_value() {
+        let dec = |s: &str| Value::Decimal(Decimal::parse(s).unwrap());
+
+        assert_eq!(dec("1.50"), dec("1.5"));
+        assert_eq!(dec("2.00").eq_same_type(&Value::Int(2)), Some(true));
+        assert_eq!(dec("2.5").cmp_same_type(&Value::Float(2.25)), Some(Greater));
+        assert_eq!(Value::Int(3).cmp_same_type(&dec("2.99")), Some(Greater));
+        assert_eq!(dec("1").cmp_same_type(&Value::Text("1".into())), None);
+
+        // The total order keeps an int before an equal decimal before an
+        // equal float
+        assert!(Value::Int(2) < dec("2.0"));
+        assert!(dec("2.0") < Value::Float(2.0));
+        assert!(dec("1.99") < Value::Int(2));
+        assert!(Value::Bool(true) < dec("-1000"));
+        assert!(dec("1000") < Value::Date(i32::MIN));
+    }
+
+    #[test]
+    fn numbers_round_to_decimal_columns() {
+        let money = SqlType::Decimal {
+            precision: 5,
+            scale: 2,
+        };
+        let dec = |s: &str| Value::Decimal(Decimal::parse(s).unwrap());
+
+        assert_eq!(Value::Int(3).coerce_to(&money), dec("3.00"));
+        assert_eq!(Value::Float(0.125).coerce_to(&money), dec("0.13"));
+        assert_eq!(Value::Text("19.999".into()).coerce_to(&money), dec("20.00"));
+        // Values too large for the column are left for the caller to reject
+        assert_eq!(Value::Int(1000).coerce_to(&money), Value::Int(1000));
+        assert_eq!(dec("1.5").coerce_to(&SqlType::Float), Value::Float(1.5));
+
+        assert_eq!(dec("2.5").cast(&SqlType::Int), Ok(Value::Int(3)));
+        assert_eq!(
+            dec("-0.05").cast(&SqlType::Text),
+            Ok(Value::Text("-0.05".into()))
+        );
+        assert_eq!(
+            dec("1234.5").cast(&money),
+            Err("cannot cast Decimal(1234.5) to DECIMAL(5,2)".into())
+        );
+        assert!(Value::Text("12x".into()).cast(&money).is_err());
+    }
+
+    #[test]
+    fn bytes_sort_after_text_and_read_hex() {
+        let bytes = |b: &[u8]| Value::Bytes(b.to_vec());
+
+        assert!(bytes(&[1]) < bytes(&[1, 0]));
+        assert!(bytes(&[0x7f]) < bytes(&[0x80]));
+        assert!(Value::Text("zzz".into()) < bytes(&[]));
+        assert_eq!(bytes(b"a").cmp_same_type(&Value::Text("a".into())), None);
+
+        assert_eq!(
+            Value::Text("\\x00ff".into()).coerce_to(&SqlType::Bytes),
+            bytes(&[0x00, 0xff])
+        );
+        assert_eq!(
+            Value::Text("ab".into()).coerce_to(&SqlType::Bytes),
+            bytes(b"ab")
+        );
+        assert_eq!(
+            bytes(&[0xca, 0xfe]).cast(&SqlType::Text),
+            Ok(Value::Text("\\xcafe".into()))
+        );
+        assert!(Value::Text("\\xf".into()).cast(&SqlType::Bytes).is_err());
+        assert!(Value::Int(1).cast(&SqlType::Bytes).is_err());
+    }
+
+    #[test]
+    fn sql_type_names() {
+        assert_eq!(SqlType::from_name("integer"), Some(SqlType::Int));
+        assert_eq!(SqlType::from_name("double precision"), Some(SqlType::Float));
+        assert_eq!(SqlType::from_name("Timestamp"), Some(SqlType::Timestamp));
+        assert_eq!(SqlType::from_name("DATE"), Some(SqlType::Date));
+        assert_eq!(SqlType::from_name("interval"), None);
+        assert_eq!(SqlType::Timestamp.to_string(), "TIMESTAMP");
+
+        let decimal = |precision, scale| Some(SqlType::Decimal { precision, scale });
+        assert_eq!(SqlType::from_name("DECIMAL(10,2)"), decimal(10, 2));
+        assert_eq!(SqlType::from_name("numeric (12, 4)"), decimal(12, 4));
+        assert_eq!(SqlType::from_name("DEC(7)"), decimal(7, 0));
+        assert_eq!(SqlType::from_name("NUMERIC"), decimal(38, 0));
+        assert_eq!(SqlType::from_name("DECIMAL(39,2)"), None);
+        assert_eq!(SqlType::from_name("DECIMAL(4,5)"), None);
+        assert_eq!(SqlType::from_name("DECIMAL(0)"), None);
+        assert_eq!(decimal(10, 2).unwrap().to_string(), "DECIMAL(10,2)");
+        assert_eq!(SqlType::from_name("blob"), Some(SqlType::Bytes));
+        assert_eq!(SqlType::Bytes.to_string(), "BYTEA");
+    }
+
+    #[test]
+    fn text_lengths_check_and_pad() {
+        assert_eq!(SqlType::from_name("VARCHAR(255)"), Some(SqlType::Text));
+        assert_eq!(SqlType::from_name("character(2)"), Some(SqlType::Text));
+        assert_eq!(SqlType::from_name("VARCHAR(0)"), None);
+        assert_eq!(
+            TextLength::from_type_name("character varying(8)"),
+            Some(TextLength::Varying(8))
+        );
+        assert_eq!(
+            TextLength::from_type_name("CHAR"),
+            Some(TextLength::Fixed(1))
+        );
+        assert_eq!(TextLength::from_type_name("VARCHAR"), None);
+        assert_eq!(TextLength::from_type_name("TEXT"), None);
+        assert_eq!(TextLength::Fixed(3).to_string(), "CHAR(3)");
+
+        let varchar = TextLength::Varying(3);
+        assert_eq!(varchar.fit("ab").as_deref(), Some("ab"));
+        assert_eq!(varchar.fit("héé").as_deref(), Some("héé"));
+        assert_eq!(varchar.fit("abc  ").as_deref(), Some("abc"));
+        assert_eq!(varchar.fit("abcd"), None);
+        let char = TextLength::Fixed(3);
+        assert_eq!(char.fit("a").as_deref(), Some("a  "));
+        assert_eq!(char.fit("abcd"), None);
+    }
+
     proptest! {
+        // Float comparisons agree with f64 apart from NaN
+        #[test]
+        fn float_cmp_matches_f64(a in -1e6..1e6f64, b in -1e6..1e6f64) {
+            prop_assert_eq!(
+                Value::Float(a).cmp_same_type(&Value::Float(b)),
+                a.partial_cmp(&b)
+            );
+        }
+
+        // Int-float comparisons agree with exact arithmetic on small values
+        #[test]
+        fn int_float_cmp_is_exact(i in -1000i64..1000, f in -1000.0..1000.0f64) {
+            let expected = (i as f64).partial_cmp(&f);
+            prop_assert_eq!(Value::Int(i).cmp_same_type(&Value::Float(f)), expected);
+            prop_assert_eq!(
+                Value::Float(f).cmp_same_type(&Value::Int(i)),
+                expected.map(Ordering::reverse)
+            );
+        }
+
         // Order symmetry: if a < b, then b > a
         #[test]
         fn order_is_antisymmetric(i in any::<i64>(), j in any::<i64>()) {
@@ -0,0 +1,185 @@
+//! Calendar conversions for `DATE` and `TIMESTAMP` values.
+//!
+//! A date is stored as days since 1970-01-01 and a timestamp as microseconds
+//! since 1970-01-01 00:00:00. Neither has a time zone; `NOW()` reads UTC.
+
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveDateTime, Timelike, Utc};
+
+pub const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+fn epoch() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1970, 1, 1).expect("1970-01-01 is a date")
+}
+
+fn to_days(date: NaiveDate) -> Option<i32> {
+    i32::try_from(date.signed_duration_since(epoch()).num_days()).ok()
+}
+
+fn to_date(days: i32) -> Option<NaiveDate> {
+    match days {
+        0.. => epoch().checked_add_days(Days::new(days as u64)),
+        _ => epoch().checked_sub_days(Days::new(u64::from(days.unsigned_abs()))),
+    }
+}
+
+fn to_datetime(micros: i64) -> Option<NaiveDateTime> {
+    DateTime::from_timestamp_micros(micros).map(|t| t.naive_utc())
+}
+
+fn to_micros(datetime: NaiveDateTime) -> i64 {
+    datetime.and_utc().timestamp_micros()
+}
+
+/// Parse `YYYY-MM-DD`.
+pub fn parse_date(s: &str) -> Option<i32> {
+    NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+        .ok()
+        .and_then(to_days)
+}
+
+/// Parse `YYYY-MM-DD HH:MM:SS[.ffffff]`, with a space or `T` between the
+/// date and the time, or a bare date for midnight.
+pub fn parse_timestamp(s: &str) -> Option<i64> {
+    let s = s.trim();
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .map(to_micros)
+        .or_else(|| parse_date(s).map(date_to_timestamp))
+}
+
+/// Render a date as `YYYY-MM-DD`.
+pub fn format_date(days: i32) -> String {
+    match to_date(days) {
+        Some(date) => date.format("%Y-%m-%d").to_string(),
+        None => format!("{days} days after 1970-01-01"),
+    }
+}
+
+/// Render a timestamp as `YYYY-MM-DD HH:MM:SS`, followed by the fraction of
+/// a second if there is one.
+pub fn format_timestamp(micros: i64) -> String {
+    match to_datetime(micros) {
+        Some(datetime) if datetime.nanosecond() == 0 => {
+            datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+        }
+        Some(datetime) => datetime.format("%Y-%m-%d %H:%M:%S%.6f").to_string(),
+        None => format!("{micros} microseconds after 1970-01-01 00:00:00"),
+    }
+}
+
+/// Midnight at the start of the date.
+pub fn date_to_timestamp(days: i32) -> i64 {
+    i64::from(days) * MICROS_PER_DAY
+}
+
+/// The date a timestamp falls on.
+pub fn timestamp_to_date(micros: i64) -> i32 {
+    micros.div_euclid(MICROS_PER_DAY) as i32
+}
+
+/// The current time in UTC.
+pub fn now() -> i64 {
+    Utc::now().timestamp_micros()
+}
+
+/// Units accepted by [`truncate_timestamp`].
+pub const TRUNCATE_UNITS: &[&str] = &[
+    "microseconds",
+    "second",
+    "minute",
+    "hour",
+    "day",
+    "week",
+    "month",
+    "quarter",
+    "year",
+];
+
+/// Round a timestamp down to the start of its `unit` (one of
+/// [`TRUNCATE_UNITS`], case-insensitive). Weeks start on Monday.
+///
+/// Returns `None` for an unknown unit.
+pub fn truncate_timestamp(micros: i64, unit: &str) -> Option<i64> {
+    let within = |step: i64| micros - micros.rem_euclid(step);
+    let truncated = match unit.to_ascii_lowercase().as_str() {
+        "microseconds" => micros,
+        "second" => within(1_000_000),
+        "minute" => within(60_000_000),
+        "hour" => within(3_600_000_000),
+        "day" => within(MICROS_PER_DAY),
+        unit @ ("week" | "month" | "quarter" | "year") => {
+            let date = to_date(timestamp_to_date(micros))?;
+            let start = match unit {
+                "week" => date.checked_sub_days(Days::new(u64::from(
+                    date.weekday().num_days_from_monday(),
+                )))?,
+                "month" => date.with_day(1)?,
+                "quarter" => date
+                    .with_day(1)?
+                    .checked_sub_months(Months::new(date.month0() % 3))?,
+                _ => date.with_day(1)?.with_month(1)?,
+            };
+            date_to_timestamp(to_days(start)?)
+        }
+        _ => return None,
+    };
+    Some(truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_round_trip() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("1969-12-31"), Some(-1));
+        assert_eq!(parse_date("2024-02-29"), Some(19782));
+        assert_eq!(format_date(19782), "2024-02-29");
+        assert_eq!(format_date(-1), "1969-12-31");
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("yesterday"), None);
+    }
+
+    #[test]
+    fn timestamps_round_trip() {
+        let t = parse_timestamp("2024-03-10 14:05:09").unwrap();
+        assert_eq!(format_timestamp(t), "2024-03-10 14:05:09");
+        assert_eq!(parse_timestamp("2024-03-10T14:05:09"), Some(t));
+        assert_eq!(
+            format_timestamp(parse_timestamp("2024-03-10 14:05:09.25").unwrap()),
+            "2024-03-10 14:05:09.250000"
+        );
+        assert_eq!(
+            parse_timestamp("2024-03-10"),
+            Some(date_to_timestamp(parse_date("2024-03-10").unwrap()))
+        );
+        assert_eq!(timestamp_to_date(t), parse_date("2024-03-10").unwrap());
+        assert_eq!(timestamp_to_date(-1), -1);
+        assert_eq!(parse_timestamp("2024-03-10 25:00:00"), None);
+    }
+
+    #[test]
+    fn truncation_rounds_down_to_each_unit() {
+        let t = parse_timestamp("2024-08-14 13:47:21.5").unwrap();
+        let truncate = |unit| format_timestamp(truncate_timestamp(t, unit).unwrap());
+        assert_eq!(truncate("second"), "2024-08-14 13:47:21");
+        assert_eq!(truncate("minute"), "2024-08-14 13:47:00");
+        assert_eq!(truncate("HOUR"), "2024-08-14 13:00:00");
+        assert_eq!(truncate("day"), "2024-08-14 00:00:00");
+        // 2024-08-14 is a Wednesday
+        assert_eq!(truncate("week"), "2024-08-12 00:00:00");
+        assert_eq!(truncate("month"), "2024-08-01 00:00:00");
+        assert_eq!(truncate("quarter"), "2024-07-01 00:00:00");
+        assert_eq!(truncate("year"), "2024-01-01 00:00:00");
+        assert_eq!(truncate_timestamp(t, "fortnight"), None);
+
+        // Before the epoch, days still start at midnight
+        let before = parse_timestamp("1969-12-31 23:00:00").unwrap();
+        assert_eq!(
+            format_timestamp(truncate_timestamp(before, "day").unwrap()),
+            "1969-12-31 00:00:00"
+        );
+    }
+}
@@ -42,15 +42,21 @@ mod tests;
 
 use bincode::config::{self, Config};
 use bincode::serde::{decode_from_slice, encode_to_vec};
+use common::crypto::EncryptionKey;
+use common::hooks::{FaultInjector, IoOp, no_faults};
 use common::{DbError, DbResult, RecordId, TableId};
 use serde::{Deserialize, Serialize};
 use std::{
     fs::{File, OpenOptions},
-    io::{Read, Write},
+    io::{Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
+    sync::Arc,
 };
 use types::Value;
 
+/// Associated data for encrypted WAL records.
+const WAL_AAD: &[u8] = b"wal";
+
 /// A logical change to the database that can be written to the WAL and replayed.
 ///
 /// Each variant represents a different type of database operation:
@@ -78,14 +84,59 @@ pub enum WalRecord {
     DropTable { table: TableId },
 }
 
+/// How [`Wal::commit`] makes a statement's records durable.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Durability {
+    /// Fsync the log before the statement returns.
+    #[default]
+    Full,
+    /// Leave the records in the OS page cache. They survive the process
+    /// crashing, but the last statements can be lost if the machine does.
+    Relaxed,
+}
+
+impl Durability {
+    /// Every mode, most durable first.
+    pub const ALL: [Durability; 2] = [Durability::Full, Durability::Relaxed];
+
+    /// The mode named `name`, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|durability| durability.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Name of the mode as written in `SET GLOBAL durability`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Durability::Full => "full",
+            Durability::Relaxed => "relaxed",
+        }
+    }
+}
+
 /// Write-Ahead Log manager.
 ///
+/// What [`Wal::repair_tail`] found in a WAL file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WalTail {
+    /// Complete records in the file.
+    pub records: usize,
+    /// Bytes of a torn last record cut off the end of the file.
+    pub trimmed: u64,
+}
+
 /// Manages a single WAL file with append-only writes and sequential replay.
 /// Records are length-prefixed (4-byte LE) for safe iteration.
+/// When opened with an encryption key, each record body is sealed
+/// individually; the length prefix stays in the clear.
 #[derive(Debug)]
 pub struct Wal {
     path: PathBuf,
     file: File,
+    key: Option<EncryptionKey>,
+    faults: Arc<dyn FaultInjector>,
+    durability: Durability,
 }
 
 impl Wal {
@@ -97,6 +148,13 @@ impl Wal {
     ///
     /// Returns `DbError::Wal` if the file cannot be opened.
     pub fn open(path: impl AsRef<Path>) -> DbResult<Self> {
+        Self::open_with_key(path, None)
+    }
+
+    /// Open or create a WAL file whose records are encrypted with `key`.
+    ///
+    /// With `None` this is the same as [`Wal::open`].
+    pub fn open_with_key(path: impl AsRef<Path>, key: Option<&EncryptionKey>) -> DbResult<Self> {
         let path = path.as_ref().to_path_buf();
         let file = OpenOptions::new()
             .create(true)
@@ -106,7 +164,24 @@ impl Wal {
             .open(&path)
             .map_err(|e| DbError::Wal(format!("Failed to open WAL file: {}", e)))?;
 
-        Ok(Self { path, file })
+        Ok(Self {
+            path,
+            file,
+            key: key.cloned(),
+            faults: no_faults(),
+            durability: Durability::default(),
+        })
+    }
+
+    /// Consult `faults` before each append and sync (see [`common::hooks`]).
+    pub fn with_faults(mut self, faults: Arc<dyn FaultInjector>) -> Self {
+        self.faults = faults;
+        self
+    }
+
+    /// The key records are encrypted with, if any.
+    pub fn encryption_key(&self) -> Option<&EncryptionKey> {
+        self.key.as_ref()
     }
 
     /// Append a record to the WAL.
@@ -116,23 +191,31 @@ impl Wal {
     ///
     /// # Errors
     ///
-    /// Returns `DbError::Wal` if serialization or writing fails.
+    /// Returns `DbError::Wal` if serialization or writing fails, or
+    /// `DbError::DiskFull` if the disk is out of space.
     pub fn append(&mut self, rec: &WalRecord) -> DbResult<()> {
         let bytes = encode_to_vec(rec, bincode_config())
             .map_err(|e| DbError::Wal(format!("Failed to serialize record: {}", e)))?;
+        let bytes = match &self.key {
+            Some(key) => key.seal(WAL_AAD, &bytes)?,
+            None => bytes,
+        };
 
+        self.faults
+            .check(IoOp::WalAppend)
+            .map_err(|e| DbError::from_write(e, DbError::Wal, "Failed to write record"))?;
         let len = bytes.len() as u32;
         self.file
             .write_all(&len.to_le_bytes())
-            .map_err(|e| DbError::Wal(format!("Failed to write length prefix: {}", e)))?;
+            .map_err(|e| DbError::from_write(e, DbError::Wal, "Failed to write length prefix"))?;
 
         self.file
             .write_all(&bytes)
-            .map_err(|e| DbError::Wal(format!("Failed to write record: {}", e)))?;
+            .map_err(|e| DbError::from_write(e, DbError::Wal, "Failed to write record"))?;
 
         self.file
             .flush()
-            .map_err(|e| DbError::Wal(format!("Failed to flush WAL: {}", e)))?;
+            .map_err(|e| DbError::from_write(e, DbError::Wal, "Failed to flush WAL"))?;
 
         Ok(())
     }
@@ -144,11 +227,34 @@ impl Wal {
     ///
     /// # Errors
     ///
-    /// Returns `DbError::Wal` if fsync fails.
+    /// Returns `DbError::Wal` if fsync fails, or `DbError::DiskFull` if the
+    /// disk is out of space.
     pub fn sync(&mut self) -> DbResult<()> {
+        self.faults
+            .check(IoOp::WalSync)
+            .map_err(|e| DbError::from_write(e, DbError::Wal, "Failed to sync WAL"))?;
         self.file
             .sync_all()
-            .map_err(|e| DbError::Wal(format!("Failed to sync WAL: {}", e)))
+            .map_err(|e| DbError::from_write(e, DbError::Wal, "Failed to sync WAL"))
+    }
+
+    /// Make a statement's appended records durable as the log's
+    /// [`Durability`] requires: fsync them under `Full`, leave them as
+    /// [`Wal::append`] wrote them under `Relaxed`.
+    pub fn commit(&mut self) -> DbResult<()> {
+        match self.durability {
+            Durability::Full => self.sync(),
+            Durability::Relaxed => Ok(()),
+        }
+    }
+
+    /// How [`Wal::commit`] treats records from now on.
+    pub fn set_durability(&mut self, durability: Durability) {
+        self.durability = durability;
+    }
+
+    pub fn durability(&self) -> Durability {
+        self.durability
     }
 
     /// Replay all records from the WAL file.
@@ -160,6 +266,19 @@ impl Wal {
     ///
     /// Returns `DbError::Wal` if the file cannot be opened or deserialization fails.
     pub fn replay(path: impl AsRef<Path>) -> DbResult<Vec<WalRecord>> {
+        Self::replay_with_key(path, None)
+    }
+
+    /// Replay a WAL file written with [`Wal::open_with_key`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DbError::Wal` under the same conditions as [`Wal::replay`],
+    /// and `DbError::Storage` if a record fails to decrypt.
+    pub fn replay_with_key(
+        path: impl AsRef<Path>,
+        key: Option<&EncryptionKey>,
+    ) -> DbResult<Vec<WalRecord>> {
         let mut file = OpenOptions::new()
             .read(true)
             .open(path.as_ref())
@@ -171,37 +290,120 @@ impl Wal {
                 DbError::Wal(format!("Failed to open WAL for replay: {}", e))
             })?;
 
-        let mut records = Vec::new();
+        Ok(read_records(&mut file, key, 0)?
+            .into_iter()
+            .map(|(_, record)| record)
+            .collect())
+    }
 
-        loop {
-            // Read length prefix
-            let mut len_buf = [0u8; 4];
-            match file.read_exact(&mut len_buf) {
-                Ok(()) => {}
-                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
-                    // Normal EOF
-                    break;
-                }
-                Err(e) => {
-                    return Err(DbError::Wal(format!("Failed to read length prefix: {}", e)));
-                }
+    /// Check that every record of the WAL file at `path` can be read, and
+    /// cut off a record left half written by a crash.
+    ///
+    /// A record is torn when its length prefix or its bytes run past the end
+    /// of the file; it can only be the last one, and is removed by
+    /// truncating the file to the end of the record before it. A missing
+    /// file has no records. Run this before opening the log for appending.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DbError::Wal` if the file cannot be read or truncated, or a
+    /// complete record fails to deserialize, and `DbError::Storage` if one
+    /// fails to decrypt. Nothing is truncated then.
+    pub fn repair_tail(path: impl AsRef<Path>, key: Option<&EncryptionKey>) -> DbResult<WalTail> {
+        let mut file = match OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path.as_ref())
+        {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(WalTail::default()),
+            Err(e) => {
+                return Err(DbError::Wal(format!(
+                    "Failed to open WAL for repair: {}",
+                    e
+                )));
             }
+        };
+        let len = file
+            .metadata()
+            .map_err(|e| DbError::Wal(format!("Failed to read WAL metadata: {}", e)))?
+            .len();
 
-            let len = u32::from_le_bytes(len_buf);
-
-            // Read record data
-            let mut buf = vec![0u8; len as usize];
+        let mut tail = WalTail::default();
+        let mut position = 0;
+        while position < len {
+            if position + 4 > len {
+                break;
+            }
+            let mut len_buf = [0u8; 4];
+            file.read_exact(&mut len_buf)
+                .map_err(|e| DbError::Wal(format!("Failed to read length prefix: {}", e)))?;
+            let record_len = u64::from(u32::from_le_bytes(len_buf));
+            if position + 4 + record_len > len {
+                break;
+            }
+            let mut buf = vec![0u8; record_len as usize];
             file.read_exact(&mut buf)
                 .map_err(|e| DbError::Wal(format!("Failed to read record data: {}", e)))?;
+            decode_record(buf, key).map_err(|e| match e {
+                DbError::Wal(message) => {
+                    DbError::Wal(format!("record at byte {position}: {message}"))
+                }
+                e => e,
+            })?;
+            position += 4 + record_len;
+            tail.records += 1;
+        }
 
-            // Deserialize
-            let (rec, _bytes_read) = decode_from_slice(&buf, bincode_config())
-                .map_err(|e| DbError::Wal(format!("Failed to deserialize record: {}", e)))?;
+        if position < len {
+            file.set_len(position)
+                .and_then(|()| file.sync_all())
+                .map_err(|e| DbError::Wal(format!("Failed to truncate torn record: {}", e)))?;
+            tail.trimmed = len - position;
+        }
+        Ok(tail)
+    }
 
-            records.push(rec);
+    /// Read the records after byte position `offset` of the log, each with
+    /// the position just past it, from which a later read can continue.
+    ///
+    /// Positions stay valid until the log is truncated. The caller must keep
+    /// records from being appended meanwhile, so that none is read half
+    /// written.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DbError::Wal` if `offset` is past the end of the log (for
+    /// example because it has since been truncated) or a record cannot be
+    /// read, and `DbError::Storage` if a record fails to decrypt.
+    pub fn read_from(&self, offset: u64) -> DbResult<Vec<(u64, WalRecord)>> {
+        let mut file = File::open(&self.path)
+            .map_err(|e| DbError::Wal(format!("Failed to open WAL for reading: {}", e)))?;
+        let len = file
+            .metadata()
+            .map_err(|e| DbError::Wal(format!("Failed to read WAL metadata: {}", e)))?
+            .len();
+        if offset > len {
+            return Err(DbError::Wal(format!(
+                "position {offset} is past the end of the WAL ({len} bytes)"
+            )));
         }
+        file.seek(SeekFrom::Start(offset))
+            .map_err(|e| DbError::Wal(format!("Failed to seek in WAL: {}", e)))?;
+        read_records(&mut file, self.key.as_ref(), offset)
+    }
 
-        Ok(records)
+    /// Position just past the last record appended, where the next one will
+    /// be written.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DbError::Wal` if the file's length cannot be read.
+    pub fn end(&self) -> DbResult<u64> {
+        self.file
+            .metadata()
+            .map(|metadata| metadata.len())
+            .map_err(|e| DbError::Wal(format!("Failed to read WAL metadata: {}", e)))
     }
 
     /// Truncate the WAL file, removing all records.
@@ -235,6 +437,54 @@ impl Wal {
     }
 }
 
+/// Read records from `file` up to EOF, each with the position just past it,
+/// counting from `position`, the offset `file` is at.
+fn read_records(
+    file: &mut File,
+    key: Option<&EncryptionKey>,
+    mut position: u64,
+) -> DbResult<Vec<(u64, WalRecord)>> {
+    let mut records = Vec::new();
+
+    loop {
+        // Read length prefix
+        let mut len_buf = [0u8; 4];
+        match file.read_exact(&mut len_buf) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                // Normal EOF
+                break;
+            }
+            Err(e) => {
+                return Err(DbError::Wal(format!("Failed to read length prefix: {}", e)));
+            }
+        }
+
+        let len = u32::from_le_bytes(len_buf);
+
+        // Read record data
+        let mut buf = vec![0u8; len as usize];
+        file.read_exact(&mut buf)
+            .map_err(|e| DbError::Wal(format!("Failed to read record data: {}", e)))?;
+        let rec = decode_record(buf, key)?;
+
+        position += 4 + u64::from(len);
+        records.push((position, rec));
+    }
+
+    Ok(records)
+}
+
+/// Decrypt and deserialize the bytes of a record, without its length prefix.
+fn decode_record(mut buf: Vec<u8>, key: Option<&EncryptionKey>) -> DbResult<WalRecord> {
+    if let Some(key) = key {
+        buf = key.open(WAL_AAD, &buf)?;
+    }
+    let (rec, _bytes_read) = decode_from_slice(&buf, bincode_config())
+        .map_err(|e| DbError::Wal(format!("Failed to deserialize record: {}", e)))?;
+    Ok(rec)
+}
+
 /// Get the bincode configuration for WAL serialization.
 ///
 /// Uses little-endian, fixed-width integers for cross-platform compatibility.
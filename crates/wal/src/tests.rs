@@ -1088,6 +1088,44 @@ fn checkpoint_workflow() {
     assert_eq!(new_records.len(), 50);
 }
 
+#[test]
+fn read_from_continues_where_the_last_read_ended() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("positions.wal");
+    let mut wal = Wal::open(&file).unwrap();
+    let insert = |i: i64| WalRecord::Insert {
+        table: TableId(1),
+        row: vec![Int(i)],
+        rid: RecordId {
+            page_id: PageId(0),
+            slot: i as u16,
+        },
+    };
+
+    wal.append(&insert(0)).unwrap();
+    wal.append(&insert(1)).unwrap();
+    wal.sync().unwrap();
+    let first = wal.read_from(0).unwrap();
+    assert_eq!(first.len(), 2);
+    assert_eq!(first[1].1, insert(1));
+    let end = first[1].0;
+    assert_eq!(end, std::fs::metadata(&file).unwrap().len());
+    assert_eq!(end, wal.end().unwrap());
+
+    wal.append(&insert(2)).unwrap();
+    wal.sync().unwrap();
+    let second = wal.read_from(end).unwrap();
+    assert_eq!(second.len(), 1);
+    assert_eq!(second[0].1, insert(2));
+    assert_eq!(wal.read_from(first[0].0).unwrap().len(), 2);
+
+    // Positions do not survive truncation
+    wal.truncate().unwrap();
+    assert!(wal.read_from(end).is_err());
+    assert!(wal.read_from(0).unwrap().is_empty());
+    assert_eq!(wal.end().unwrap(), 0);
+}
+
 #[test]
 fn max_slot_value() {
     let dir = tempdir().unwrap();
@@ -1114,3 +1152,148 @@ fn max_slot_value() {
         _ => panic!("wrong record type"),
     }
 }
+
+#[test]
+fn encrypted_wal_requires_key_to_replay() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("toydb.wal");
+    let key = EncryptionKey::from_bytes(&[3u8; common::crypto::KEY_LEN]).unwrap();
+
+    let rec = WalRecord::Insert {
+        table: TableId(1),
+        row: vec![Int(1), Text("classified".into())],
+        rid: RecordId {
+            page_id: PageId(0),
+            slot: 0,
+        },
+    };
+    let mut wal = Wal::open_with_key(&file, Some(&key)).unwrap();
+    wal.append(&rec).unwrap();
+    wal.sync().unwrap();
+
+    let bytes = std::fs::read(&file).unwrap();
+    assert!(!bytes.windows(10).any(|w| w == b"classified"));
+
+    assert_eq!(Wal::replay_with_key(&file, Some(&key)).unwrap(), vec![rec]);
+    assert!(Wal::replay(&file).is_err());
+}
+
+#[test]
+fn failed_append_writes_nothing() {
+    use common::hooks::{FaultPlan, IoOp};
+    use std::sync::Arc;
+
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("toydb.wal");
+    let faults = Arc::new(FaultPlan::new().fail_nth(IoOp::WalAppend, 2));
+    let mut wal = Wal::open(&file).unwrap().with_faults(faults.clone());
+    let record = |table| WalRecord::DropTable {
+        table: TableId(table),
+    };
+
+    wal.append(&record(1)).unwrap();
+    let err = wal.append(&record(2)).unwrap_err();
+    assert!(err.to_string().contains("injected fault"), "{err}");
+    wal.append(&record(3)).unwrap();
+    wal.sync().unwrap();
+
+    assert_eq!(Wal::replay(&file).unwrap(), vec![record(1), record(3)]);
+    assert_eq!(faults.count(IoOp::WalAppend), 3);
+}
+
+#[test]
+fn crash_stops_every_later_write() {
+    use common::hooks::{FaultPlan, IoOp};
+    use std::sync::Arc;
+
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("toydb.wal");
+    let faults = Arc::new(FaultPlan::new().crash_at(IoOp::WalSync, 1));
+    let mut wal = Wal::open(&file).unwrap().with_faults(faults.clone());
+    let record = WalRecord::DropTable { table: TableId(1) };
+
+    wal.append(&record).unwrap();
+    assert!(wal.sync().is_err());
+    assert!(faults.crashed());
+    assert!(wal.append(&record).is_err());
+
+    // The record appended before the crash reached the file
+    assert_eq!(Wal::replay(&file).unwrap(), vec![record]);
+}
+
+#[test]
+fn relaxed_commit_skips_the_sync() {
+    use common::hooks::{FaultPlan, IoOp};
+    use std::sync::Arc;
+
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("toydb.wal");
+    let faults = Arc::new(FaultPlan::new());
+    let mut wal = Wal::open(&file).unwrap().with_faults(faults.clone());
+    let record = WalRecord::DropTable { table: TableId(1) };
+
+    wal.append(&record).unwrap();
+    wal.commit().unwrap();
+    assert_eq!(faults.count(IoOp::WalSync), 1);
+
+    wal.set_durability(Durability::Relaxed);
+    wal.append(&record).unwrap();
+    wal.commit().unwrap();
+    assert_eq!(faults.count(IoOp::WalSync), 1);
+    assert_eq!(Wal::replay(&file).unwrap(), vec![record.clone(), record]);
+
+    assert_eq!(Durability::from_name("RELAXED"), Some(Durability::Relaxed));
+    assert_eq!(Durability::from_name("sometimes"), None);
+}
+
+#[test]
+fn repair_tail_cuts_off_a_torn_record() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("toydb.wal");
+    let record = |table| WalRecord::DropTable {
+        table: TableId(table),
+    };
+
+    // A missing file has nothing to repair
+    assert_eq!(Wal::repair_tail(&file, None).unwrap(), WalTail::default());
+
+    let mut wal = Wal::open(&file).unwrap();
+    wal.append(&record(1)).unwrap();
+    wal.append(&record(2)).unwrap();
+    let end = wal.end().unwrap();
+    drop(wal);
+    let clean = Wal::repair_tail(&file, None).unwrap();
+    assert_eq!((clean.records, clean.trimmed), (2, 0));
+
+    // Half of a third record, as a crash mid-append leaves it
+    let bytes = std::fs::read(&file).unwrap();
+    let first_len = 4 + u32::from_le_bytes(bytes[..4].try_into().unwrap()) as usize;
+    std::fs::write(&file, [bytes.as_slice(), &bytes[..first_len - 3]].concat()).unwrap();
+    let torn = Wal::repair_tail(&file, None).unwrap();
+    assert_eq!(torn.records, 2);
+    assert_eq!(torn.trimmed, first_len as u64 - 3);
+    assert_eq!(std::fs::metadata(&file).unwrap().len(), end);
+    assert_eq!(Wal::replay(&file).unwrap(), vec![record(1), record(2)]);
+
+    // A length prefix cut short
+    std::fs::write(&file, [bytes.as_slice(), &[7, 0]].concat()).unwrap();
+    assert_eq!(Wal::repair_tail(&file, None).unwrap().trimmed, 2);
+}
+
+#[test]
+fn repair_tail_leaves_damaged_records_alone() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("toydb.wal");
+    let mut wal = Wal::open(&file).unwrap();
+    wal.append(&WalRecord::DropTable { table: TableId(1) })
+        .unwrap();
+    drop(wal);
+
+    // A complete record whose bytes are not a record
+    let mut bytes = std::fs::read(&file).unwrap();
+    bytes[4] = 0xFF;
+    std::fs::write(&file, &bytes).unwrap();
+    let err = Wal::repair_tail(&file, None).unwrap_err();
+    assert!(err.to_string().contains("record at byte 0"), "{err}");
+    assert_eq!(std::fs::read(&file).unwrap(), bytes);
+}